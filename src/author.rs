@@ -0,0 +1,31 @@
+//! Who is making a change, for `created_by` and `updated_by`.
+//!
+//! `MEDULLA_AUTHOR` wins when set, so agents and CI can name themselves;
+//! otherwise the git identity is used, name first and then email.
+
+use std::process::Command;
+
+/// Environment variable that overrides the git identity.
+pub const AUTHOR_ENV: &str = "MEDULLA_AUTHOR";
+
+/// Detect the current author, or None when no identity is configured.
+pub fn detect() -> Option<String> {
+    std::env::var(AUTHOR_ENV)
+        .ok()
+        .and_then(non_empty)
+        .or_else(|| git_config("user.name"))
+        .or_else(|| git_config("user.email"))
+}
+
+fn git_config(key: &str) -> Option<String> {
+    let output = Command::new("git").args(["config", key]).output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    String::from_utf8(output.stdout).ok().and_then(non_empty)
+}
+
+fn non_empty(value: String) -> Option<String> {
+    let value = value.trim();
+    (!value.is_empty()).then(|| value.to_string())
+}
@@ -1,9 +1,13 @@
 mod sqlite_cache;
+mod vector_index;
 
 pub use sqlite_cache::{
-    compute_text_hash, cosine_similarity, embeddable_text, BlockedTask, CacheStats, CachedRelation,
-    ComponentSearchResult, DecisionSearchResult, FilterMetadata, LinkSearchResult,
-    NoteSearchResult, PromptSearchResult, ReadyTask, SearchResult, SemanticSearchResult,
-    SqliteCache, TaskBlocker, TaskSearchResult, ENTITY_WARNING_THRESHOLD,
+    compute_text_hash, cosine_similarity, embeddable_text, title_similarity, AssignedTask,
+    AssigneeWip, AssigneeWorkload, BlockedTask, CacheStats, CachedRelation, ComponentSearchResult,
+    DecisionSearchResult, DueTask, DuplicateCandidate, DuplicateEntity, DuplicatePair,
+    EntityPageQuery, EntitySort, FilterMetadata, IndexEntry, LinkSearchResult,
+    MilestoneSearchResult, NoteSearchResult, PromptSearchResult, QuestionSearchResult, ReadyTask,
+    SearchResult, SemanticSearchResult, SqliteCache, TaskBlocker, TaskSearchResult,
+    DUPLICATE_EMBEDDING_THRESHOLD, DUPLICATE_TITLE_THRESHOLD, ENTITY_WARNING_THRESHOLD,
     LORO_SIZE_WARNING_THRESHOLD,
 };
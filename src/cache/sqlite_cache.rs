@@ -1,18 +1,48 @@
+use std::collections::{BTreeMap, HashSet};
 use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Mutex;
 
-use chrono::Utc;
-use rusqlite::{params, Connection, OptionalExtension};
+use chrono::{Days, NaiveDate, Utc};
+use rusqlite::types::Value;
+use rusqlite::{params, params_from_iter, Connection, OptionalExtension, ParamsFromIter};
 
-use crate::entity::{Component, Decision, Link, Note, Prompt, Relation, Task};
+use crate::config::{TaskScore, TaskScoring, WipLimits};
+use crate::entity::{Component, Decision, Link, Milestone, Note, Prompt, Question, Relation, Task};
 use crate::error::{MedullaError, Result};
+use crate::search::SearchFilter;
+
+use super::vector_index::{VectorIndex, VECTOR_INDEX_FILE};
 
 const CACHE_DB: &str = "cache.db";
 
+/// FTS5 virtual tables, one per entity type
+const FTS_TABLES: [&str; 8] = [
+    "decisions_fts",
+    "tasks_fts",
+    "notes_fts",
+    "prompts_fts",
+    "components_fts",
+    "links_fts",
+    "questions_fts",
+    "milestones_fts",
+];
+
+/// Suffix appended to a relation's composite key for its materialized inverse row
+const INVERSE_KEY_SUFFIX: &str = ":inverse";
+
 /// SQLite cache for full-text search and query acceleration
 pub struct SqliteCache {
     conn: Connection,
-    #[allow(dead_code)]
     path: PathBuf,
+    /// Vector index loaded by the last semantic search, if still current
+    vector_index: Mutex<Option<VectorIndex>>,
+    /// Whether the in-memory index has writes the persisted one lacks
+    vector_index_unsaved: AtomicBool,
+    /// Vector index lookups served from memory, and those that had to load
+    /// or rebuild it
+    vector_index_hits: AtomicU64,
+    vector_index_misses: AtomicU64,
 }
 
 impl SqliteCache {
@@ -21,7 +51,31 @@ impl SqliteCache {
         let path = medulla_dir.join(CACHE_DB);
         let conn = Connection::open(&path)?;
 
-        let cache = Self { conn, path };
+        let cache = Self {
+            conn,
+            path,
+            vector_index: Mutex::new(None),
+            vector_index_unsaved: AtomicBool::new(false),
+            vector_index_hits: AtomicU64::new(0),
+            vector_index_misses: AtomicU64::new(0),
+        };
+        cache.init_schema()?;
+        Ok(cache)
+    }
+
+    /// Open an empty in-memory cache, e.g. to go with a staging copy of the
+    /// store
+    pub fn open_in_memory() -> Result<Self> {
+        let conn = Connection::open_in_memory()?;
+
+        let cache = Self {
+            conn,
+            path: PathBuf::new(),
+            vector_index: Mutex::new(None),
+            vector_index_unsaved: AtomicBool::new(false),
+            vector_index_hits: AtomicU64::new(0),
+            vector_index_misses: AtomicU64::new(0),
+        };
         cache.init_schema()?;
         Ok(cache)
     }
@@ -49,10 +103,12 @@ impl SqliteCache {
                 tags TEXT,
                 created_at TEXT NOT NULL,
                 updated_at TEXT NOT NULL,
-                created_by TEXT
+                created_by TEXT,
+                priority TEXT
             )",
             [],
         )?;
+        self.add_column_if_missing("decisions", "priority", "TEXT")?;
 
         // FTS5 virtual table for full-text search on decisions
         self.conn.execute(
@@ -266,10 +322,12 @@ impl SqliteCache {
                 tags TEXT,
                 created_at TEXT NOT NULL,
                 updated_at TEXT NOT NULL,
-                created_by TEXT
+                created_by TEXT,
+                priority TEXT
             )",
             [],
         )?;
+        self.add_column_if_missing("components", "priority", "TEXT")?;
 
         // FTS5 virtual table for full-text search on components
         self.conn.execute(
@@ -363,6 +421,112 @@ impl SqliteCache {
             ",
         )?;
 
+        // Questions table
+        self.conn.execute(
+            "CREATE TABLE IF NOT EXISTS questions (
+                id TEXT PRIMARY KEY,
+                sequence_number INTEGER NOT NULL,
+                title TEXT NOT NULL,
+                content TEXT,
+                status TEXT NOT NULL,
+                answered_by TEXT,
+                tags TEXT,
+                created_at TEXT NOT NULL,
+                updated_at TEXT NOT NULL,
+                created_by TEXT
+            )",
+            [],
+        )?;
+
+        // FTS5 virtual table for full-text search on questions
+        self.conn.execute(
+            "CREATE VIRTUAL TABLE IF NOT EXISTS questions_fts USING fts5(
+                id,
+                title,
+                content,
+                status,
+                tags,
+                content='questions',
+                content_rowid='rowid'
+            )",
+            [],
+        )?;
+
+        // Triggers to keep FTS in sync with questions table
+        self.conn.execute_batch(
+            "
+            CREATE TRIGGER IF NOT EXISTS questions_ai AFTER INSERT ON questions BEGIN
+                INSERT INTO questions_fts(rowid, id, title, content, status, tags)
+                VALUES (new.rowid, new.id, new.title, new.content, new.status, new.tags);
+            END;
+
+            CREATE TRIGGER IF NOT EXISTS questions_ad AFTER DELETE ON questions BEGIN
+                INSERT INTO questions_fts(questions_fts, rowid, id, title, content, status, tags)
+                VALUES ('delete', old.rowid, old.id, old.title, old.content, old.status, old.tags);
+            END;
+
+            CREATE TRIGGER IF NOT EXISTS questions_au AFTER UPDATE ON questions BEGIN
+                INSERT INTO questions_fts(questions_fts, rowid, id, title, content, status, tags)
+                VALUES ('delete', old.rowid, old.id, old.title, old.content, old.status, old.tags);
+                INSERT INTO questions_fts(rowid, id, title, content, status, tags)
+                VALUES (new.rowid, new.id, new.title, new.content, new.status, new.tags);
+            END;
+            ",
+        )?;
+
+        // Milestones table
+        self.conn.execute(
+            "CREATE TABLE IF NOT EXISTS milestones (
+                id TEXT PRIMARY KEY,
+                sequence_number INTEGER NOT NULL,
+                title TEXT NOT NULL,
+                content TEXT,
+                status TEXT NOT NULL,
+                target_date TEXT,
+                tags TEXT,
+                created_at TEXT NOT NULL,
+                updated_at TEXT NOT NULL,
+                created_by TEXT
+            )",
+            [],
+        )?;
+
+        // FTS5 virtual table for full-text search on milestones
+        self.conn.execute(
+            "CREATE VIRTUAL TABLE IF NOT EXISTS milestones_fts USING fts5(
+                id,
+                title,
+                content,
+                status,
+                tags,
+                content='milestones',
+                content_rowid='rowid'
+            )",
+            [],
+        )?;
+
+        // Triggers to keep FTS in sync with milestones table
+        self.conn.execute_batch(
+            "
+            CREATE TRIGGER IF NOT EXISTS milestones_ai AFTER INSERT ON milestones BEGIN
+                INSERT INTO milestones_fts(rowid, id, title, content, status, tags)
+                VALUES (new.rowid, new.id, new.title, new.content, new.status, new.tags);
+            END;
+
+            CREATE TRIGGER IF NOT EXISTS milestones_ad AFTER DELETE ON milestones BEGIN
+                INSERT INTO milestones_fts(milestones_fts, rowid, id, title, content, status, tags)
+                VALUES ('delete', old.rowid, old.id, old.title, old.content, old.status, old.tags);
+            END;
+
+            CREATE TRIGGER IF NOT EXISTS milestones_au AFTER UPDATE ON milestones BEGIN
+                INSERT INTO milestones_fts(milestones_fts, rowid, id, title, content, status, tags)
+                VALUES ('delete', old.rowid, old.id, old.title, old.content, old.status, old.tags);
+                INSERT INTO milestones_fts(rowid, id, title, content, status, tags)
+                VALUES (new.rowid, new.id, new.title, new.content, new.status, new.tags);
+            END;
+            ",
+        )?;
+
         // Relations table with indexes for fast lookups
         self.conn.execute(
             "CREATE TABLE IF NOT EXISTS relations (
@@ -373,10 +537,14 @@ impl SqliteCache {
                 target_type TEXT NOT NULL,
                 relation_type TEXT NOT NULL,
                 created_at TEXT NOT NULL,
-                created_by TEXT
+                created_by TEXT,
+                resolved_at TEXT,
+                is_inverse INTEGER NOT NULL DEFAULT 0
             )",
             [],
         )?;
+        self.add_column_if_missing("relations", "resolved_at", "TEXT")?;
+        self.add_column_if_missing("relations", "is_inverse", "INTEGER NOT NULL DEFAULT 0")?;
 
         // Indexes for relation queries
         self.conn.execute(
@@ -388,6 +556,87 @@ impl SqliteCache {
             [],
         )?;
 
+        // Normalized tag table so tag lookups don't scan every entity.
+        // A cache created before this table existed has no tag rows, so
+        // drop the stored version to force a full resync.
+        let had_tag_table: bool = self.conn.query_row(
+            "SELECT COUNT(*) > 0 FROM sqlite_master WHERE type = 'table' AND name = 'entity_tags'",
+            [],
+            |row| row.get(0),
+        )?;
+        self.conn.execute(
+            "CREATE TABLE IF NOT EXISTS entity_tags (
+                entity_id TEXT NOT NULL,
+                entity_type TEXT NOT NULL,
+                tag TEXT NOT NULL,
+                PRIMARY KEY (entity_id, tag)
+            )",
+            [],
+        )?;
+        self.conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_entity_tags_tag ON entity_tags(tag)",
+            [],
+        )?;
+        if !had_tag_table {
+            self.conn
+                .execute("DELETE FROM meta WHERE key = 'loro_version'", [])?;
+        }
+
+        // Entity aliases, so index listings can show them. As with tags, a
+        // cache created before this table existed must be resynced.
+        let had_alias_table: bool = self.conn.query_row(
+            "SELECT COUNT(*) > 0 FROM sqlite_master WHERE type = 'table' AND name = 'entity_aliases'",
+            [],
+            |row| row.get(0),
+        )?;
+        self.conn.execute(
+            "CREATE TABLE IF NOT EXISTS entity_aliases (
+                alias TEXT PRIMARY KEY,
+                entity_id TEXT NOT NULL
+            )",
+            [],
+        )?;
+        self.conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_entity_aliases_entity ON entity_aliases(entity_id)",
+            [],
+        )?;
+        if !had_alias_table {
+            self.conn
+                .execute("DELETE FROM meta WHERE key = 'loro_version'", [])?;
+        }
+
+        // Collection memberships, for filtering listings by collection
+        let had_collection_table: bool = self.conn.query_row(
+            "SELECT COUNT(*) > 0 FROM sqlite_master WHERE type = 'table' AND name = 'entity_collections'",
+            [],
+            |row| row.get(0),
+        )?;
+        self.conn.execute(
+            "CREATE TABLE IF NOT EXISTS entity_collections (
+                entity_id TEXT PRIMARY KEY,
+                collection TEXT NOT NULL,
+                sequence INTEGER NOT NULL
+            )",
+            [],
+        )?;
+        self.conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_entity_collections_collection ON entity_collections(collection)",
+            [],
+        )?;
+        if !had_collection_table {
+            self.conn
+                .execute("DELETE FROM meta WHERE key = 'loro_version'", [])?;
+        }
+
+        // IDs of archived entities, excluded from ready tasks and (by the
+        // CLI) from default list and search results
+        self.conn.execute(
+            "CREATE TABLE IF NOT EXISTS archived_entities (
+                entity_id TEXT PRIMARY KEY
+            )",
+            [],
+        )?;
+
         // Embeddings table for semantic search
         self.conn.execute(
             "CREATE TABLE IF NOT EXISTS embeddings (
@@ -409,6 +658,26 @@ impl SqliteCache {
         Ok(())
     }
 
+    /// Add a column to an existing table if an older cache was created without it.
+    fn add_column_if_missing(&self, table: &str, column: &str, decl: &str) -> Result<()> {
+        let exists: bool = self.conn.query_row(
+            &format!(
+                "SELECT COUNT(*) > 0 FROM pragma_table_info('{}') WHERE name = ?1",
+                table
+            ),
+            [column],
+            |row| row.get(0),
+        )?;
+
+        if !exists {
+            self.conn.execute(
+                &format!("ALTER TABLE {} ADD COLUMN {} {}", table, column, decl),
+                [],
+            )?;
+        }
+        Ok(())
+    }
+
     /// Get the stored Loro version hash
     pub fn get_loro_version(&self) -> Result<Option<String>> {
         let result: Option<String> = self
@@ -437,8 +706,8 @@ impl SqliteCache {
 
         self.conn.execute(
             "INSERT OR REPLACE INTO decisions
-             (id, sequence_number, title, content, status, context, tags, created_at, updated_at, created_by)
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+             (id, sequence_number, title, content, status, context, tags, created_at, updated_at, created_by, priority)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
             params![
                 decision.base.id.to_string(),
                 decision.base.sequence_number,
@@ -450,9 +719,17 @@ impl SqliteCache {
                 decision.base.created_at.to_rfc3339(),
                 decision.base.updated_at.to_rfc3339(),
                 decision.base.created_by,
+                decision.priority.map(|p| p.to_string()),
             ],
         )?;
 
+        self.index_tags(
+            &decision.base.id.to_string(),
+            "decision",
+            &decision.base.tags,
+        )?;
+        self.index_archived(&decision.base.id.to_string(), decision.base.archived)?;
+
         Ok(())
     }
 
@@ -460,6 +737,8 @@ impl SqliteCache {
     pub fn remove_decision(&self, id: &str) -> Result<()> {
         self.conn
             .execute("DELETE FROM decisions WHERE id = ?1", [id])?;
+        self.remove_tags(id)?;
+        self.index_archived(id, false)?;
         Ok(())
     }
 
@@ -487,12 +766,17 @@ impl SqliteCache {
             ],
         )?;
 
+        self.index_tags(&task.base.id.to_string(), "task", &task.base.tags)?;
+        self.index_archived(&task.base.id.to_string(), task.base.archived)?;
+
         Ok(())
     }
 
     /// Remove a task from the cache
     pub fn remove_task(&self, id: &str) -> Result<()> {
         self.conn.execute("DELETE FROM tasks WHERE id = ?1", [id])?;
+        self.remove_tags(id)?;
+        self.index_archived(id, false)?;
         Ok(())
     }
 
@@ -517,12 +801,17 @@ impl SqliteCache {
             ],
         )?;
 
+        self.index_tags(&note.base.id.to_string(), "note", &note.base.tags)?;
+        self.index_archived(&note.base.id.to_string(), note.base.archived)?;
+
         Ok(())
     }
 
     /// Remove a note from the cache
     pub fn remove_note(&self, id: &str) -> Result<()> {
         self.conn.execute("DELETE FROM notes WHERE id = ?1", [id])?;
+        self.remove_tags(id)?;
+        self.index_archived(id, false)?;
         Ok(())
     }
 
@@ -550,6 +839,9 @@ impl SqliteCache {
             ],
         )?;
 
+        self.index_tags(&prompt.base.id.to_string(), "prompt", &prompt.base.tags)?;
+        self.index_archived(&prompt.base.id.to_string(), prompt.base.archived)?;
+
         Ok(())
     }
 
@@ -557,6 +849,8 @@ impl SqliteCache {
     pub fn remove_prompt(&self, id: &str) -> Result<()> {
         self.conn
             .execute("DELETE FROM prompts WHERE id = ?1", [id])?;
+        self.remove_tags(id)?;
+        self.index_archived(id, false)?;
         Ok(())
     }
 
@@ -566,8 +860,8 @@ impl SqliteCache {
 
         self.conn.execute(
             "INSERT OR REPLACE INTO components
-             (id, sequence_number, title, content, status, component_type, owner, tags, created_at, updated_at, created_by)
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+             (id, sequence_number, title, content, status, component_type, owner, tags, created_at, updated_at, created_by, priority)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)",
             params![
                 component.base.id.to_string(),
                 component.base.sequence_number,
@@ -580,9 +874,17 @@ impl SqliteCache {
                 component.base.created_at.to_rfc3339(),
                 component.base.updated_at.to_rfc3339(),
                 component.base.created_by,
+                component.priority.map(|p| p.to_string()),
             ],
         )?;
 
+        self.index_tags(
+            &component.base.id.to_string(),
+            "component",
+            &component.base.tags,
+        )?;
+        self.index_archived(&component.base.id.to_string(), component.base.archived)?;
+
         Ok(())
     }
 
@@ -590,6 +892,8 @@ impl SqliteCache {
     pub fn remove_component(&self, id: &str) -> Result<()> {
         self.conn
             .execute("DELETE FROM components WHERE id = ?1", [id])?;
+        self.remove_tags(id)?;
+        self.index_archived(id, false)?;
         Ok(())
     }
 
@@ -615,138 +919,842 @@ impl SqliteCache {
             ],
         )?;
 
+        self.index_tags(&link.base.id.to_string(), "link", &link.base.tags)?;
+        self.index_archived(&link.base.id.to_string(), link.base.archived)?;
+
         Ok(())
     }
 
     /// Remove a link from the cache
     pub fn remove_link(&self, id: &str) -> Result<()> {
         self.conn.execute("DELETE FROM links WHERE id = ?1", [id])?;
+        self.remove_tags(id)?;
+        self.index_archived(id, false)?;
         Ok(())
     }
 
-    /// Index a relation in the cache
-    pub fn index_relation(&self, relation: &Relation) -> Result<()> {
+    /// Index a question in the cache
+    pub fn index_question(&self, question: &Question) -> Result<()> {
+        let tags_str = question.base.tags.join(", ");
+
         self.conn.execute(
-            "INSERT OR REPLACE INTO relations
-             (composite_key, source_id, source_type, target_id, target_type, relation_type, created_at, created_by)
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+            "INSERT OR REPLACE INTO questions
+             (id, sequence_number, title, content, status, answered_by, tags, created_at, updated_at, created_by)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
             params![
-                relation.composite_key(),
-                relation.source_id.to_string(),
-                relation.source_type,
-                relation.target_id.to_string(),
-                relation.target_type,
-                relation.relation_type.to_string(),
-                relation.created_at.to_rfc3339(),
-                relation.created_by,
+                question.base.id.to_string(),
+                question.base.sequence_number,
+                question.base.title,
+                question.base.content,
+                question.status.to_string(),
+                question.answered_by,
+                tags_str,
+                question.base.created_at.to_rfc3339(),
+                question.base.updated_at.to_rfc3339(),
+                question.base.created_by,
             ],
         )?;
-        Ok(())
-    }
 
-    /// Remove a relation from the cache
-    pub fn remove_relation(&self, composite_key: &str) -> Result<()> {
-        self.conn.execute(
-            "DELETE FROM relations WHERE composite_key = ?1",
-            [composite_key],
+        self.index_tags(
+            &question.base.id.to_string(),
+            "question",
+            &question.base.tags,
         )?;
+        self.index_archived(&question.base.id.to_string(), question.base.archived)?;
+
         Ok(())
     }
 
-    /// Clear all cached data (for full rebuild)
-    pub fn clear(&self) -> Result<()> {
-        self.conn.execute("DELETE FROM decisions", [])?;
-        self.conn.execute("DELETE FROM tasks", [])?;
-        self.conn.execute("DELETE FROM notes", [])?;
-        self.conn.execute("DELETE FROM prompts", [])?;
-        self.conn.execute("DELETE FROM components", [])?;
-        self.conn.execute("DELETE FROM links", [])?;
-        self.conn.execute("DELETE FROM relations", [])?;
-        self.conn.execute("DELETE FROM embeddings", [])?;
-        self.conn.execute("DELETE FROM meta", [])?;
+    /// Remove a question from the cache
+    pub fn remove_question(&self, id: &str) -> Result<()> {
+        self.conn
+            .execute("DELETE FROM questions WHERE id = ?1", [id])?;
+        self.remove_tags(id)?;
+        self.index_archived(id, false)?;
         Ok(())
     }
 
-    // =========================================================================
-    // Embedding Storage Methods
-    // =========================================================================
-
-    /// Store an embedding for an entity.
-    /// The embedding is stored as a BLOB (f32 array serialized as bytes).
-    pub fn store_embedding(
-        &self,
-        entity_id: &str,
-        entity_type: &str,
-        embedding: &[f32],
-        text_hash: &str,
-    ) -> Result<()> {
-        // Convert f32 array to bytes
-        let embedding_bytes = embedding_to_bytes(embedding);
+    /// Index a milestone in the cache
+    pub fn index_milestone(&self, milestone: &Milestone) -> Result<()> {
+        let tags_str = milestone.base.tags.join(", ");
 
         self.conn.execute(
-            "INSERT OR REPLACE INTO embeddings
-             (entity_id, entity_type, embedding, text_hash, computed_at)
-             VALUES (?1, ?2, ?3, ?4, ?5)",
+            "INSERT OR REPLACE INTO milestones
+             (id, sequence_number, title, content, status, target_date, tags, created_at, updated_at, created_by)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
             params![
-                entity_id,
-                entity_type,
-                embedding_bytes,
-                text_hash,
-                Utc::now().to_rfc3339(),
+                milestone.base.id.to_string(),
+                milestone.base.sequence_number,
+                milestone.base.title,
+                milestone.base.content,
+                milestone.status.to_string(),
+                milestone.target_date.map(|d| d.to_string()),
+                tags_str,
+                milestone.base.created_at.to_rfc3339(),
+                milestone.base.updated_at.to_rfc3339(),
+                milestone.base.created_by,
             ],
         )?;
 
+        self.index_tags(
+            &milestone.base.id.to_string(),
+            "milestone",
+            &milestone.base.tags,
+        )?;
+        self.index_archived(&milestone.base.id.to_string(), milestone.base.archived)?;
+
         Ok(())
     }
 
-    /// Get an embedding for an entity.
-    /// Returns None if no embedding exists.
-    pub fn get_embedding(&self, entity_id: &str) -> Result<Option<Vec<f32>>> {
-        let result: Option<Vec<u8>> = self
-            .conn
-            .query_row(
-                "SELECT embedding FROM embeddings WHERE entity_id = ?1",
-                [entity_id],
-                |row| row.get(0),
-            )
-            .optional()?;
-
-        Ok(result.map(|bytes| bytes_to_embedding(&bytes)))
+    /// Remove a milestone from the cache
+    pub fn remove_milestone(&self, id: &str) -> Result<()> {
+        self.conn
+            .execute("DELETE FROM milestones WHERE id = ?1", [id])?;
+        self.remove_tags(id)?;
+        self.index_archived(id, false)?;
+        Ok(())
     }
 
-    /// Get the text hash for an entity's embedding.
-    /// Used to check if content has changed and embedding needs recomputing.
-    pub fn get_embedding_text_hash(&self, entity_id: &str) -> Result<Option<String>> {
-        let result: Option<String> = self
-            .conn
-            .query_row(
-                "SELECT text_hash FROM embeddings WHERE entity_id = ?1",
-                [entity_id],
-                |row| row.get(0),
-            )
-            .optional()?;
-        Ok(result)
+    /// Replace the tag rows for an entity
+    fn index_tags(&self, entity_id: &str, entity_type: &str, tags: &[String]) -> Result<()> {
+        self.remove_tags(entity_id)?;
+        let mut stmt = self.conn.prepare(
+            "INSERT OR IGNORE INTO entity_tags (entity_id, entity_type, tag) VALUES (?1, ?2, ?3)",
+        )?;
+        for tag in tags {
+            stmt.execute(params![entity_id, entity_type, tag])?;
+        }
+        Ok(())
     }
 
-    /// Delete an embedding for an entity.
-    pub fn delete_embedding(&self, entity_id: &str) -> Result<()> {
+    /// Remove all tag rows for an entity
+    fn remove_tags(&self, entity_id: &str) -> Result<()> {
         self.conn
-            .execute("DELETE FROM embeddings WHERE entity_id = ?1", [entity_id])?;
+            .execute("DELETE FROM entity_tags WHERE entity_id = ?1", [entity_id])?;
         Ok(())
     }
 
-    /// List all embeddings for a specific entity type.
-    /// Returns tuples of (entity_id, embedding).
-    pub fn list_embeddings_by_type(&self, entity_type: &str) -> Result<Vec<(String, Vec<f32>)>> {
+    /// Record whether an entity is archived
+    fn index_archived(&self, entity_id: &str, archived: bool) -> Result<()> {
+        if archived {
+            self.conn.execute(
+                "INSERT OR IGNORE INTO archived_entities (entity_id) VALUES (?1)",
+                [entity_id],
+            )?;
+        } else {
+            self.conn.execute(
+                "DELETE FROM archived_entities WHERE entity_id = ?1",
+                [entity_id],
+            )?;
+        }
+        Ok(())
+    }
+
+    /// IDs of all archived entities
+    pub fn get_archived_ids(&self) -> Result<HashSet<String>> {
         let mut stmt = self
             .conn
-            .prepare("SELECT entity_id, embedding FROM embeddings WHERE entity_type = ?1")?;
+            .prepare("SELECT entity_id FROM archived_entities")?;
+        let ids = stmt
+            .query_map([], |row| row.get(0))?
+            .collect::<std::result::Result<HashSet<String>, _>>()?;
+        Ok(ids)
+    }
+
+    /// Get the (entity_id, entity_type) pairs of all entities with a tag.
+    /// Uses the tag index rather than scanning entities.
+    pub fn get_entity_ids_by_tag(&self, tag: &str) -> Result<Vec<(String, String)>> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT entity_id, entity_type FROM entity_tags WHERE tag = ?1")?;
 
         let results = stmt
-            .query_map([entity_type], |row| {
-                let entity_id: String = row.get(0)?;
-                let embedding_bytes: Vec<u8> = row.get(1)?;
-                Ok((entity_id, bytes_to_embedding(&embedding_bytes)))
+            .query_map([tag], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+
+        Ok(results)
+    }
+
+    /// One page of entities matching `query`, ordered by sequence number, and
+    /// the number of entities matching the filters regardless of the cursor
+    /// and offset.
+    pub fn list_entity_page(
+        &self,
+        query: &EntityPageQuery<'_>,
+    ) -> Result<(Vec<EntityPageRow>, usize)> {
+        const SOURCES: [(&str, &str, &str, &str); 8] = [
+            ("decision", "decisions", "status", "priority"),
+            ("task", "tasks", "status", "priority"),
+            ("note", "notes", "NULL", "NULL"),
+            ("prompt", "prompts", "NULL", "NULL"),
+            ("component", "components", "status", "priority"),
+            ("link", "links", "NULL", "NULL"),
+            ("question", "questions", "status", "NULL"),
+            ("milestone", "milestones", "status", "NULL"),
+        ];
+        let union = SOURCES
+            .iter()
+            .filter(|(entity_type, ..)| query.entity_type.map_or(true, |t| t == *entity_type))
+            .map(|(entity_type, table, status, priority)| {
+                format!(
+                    "SELECT id, '{}' AS entity_type, sequence_number, title, {} AS status,
+                            {} AS priority, created_at, updated_at
+                     FROM {}",
+                    entity_type, status, priority, table
+                )
+            })
+            .collect::<Vec<_>>()
+            .join(" UNION ALL ");
+        if union.is_empty() {
+            return Ok((Vec::new(), 0));
+        }
+
+        let filters = "(?1 IS NULL OR e.status = ?1)
+             AND (?2 IS NULL OR e.id IN (SELECT entity_id FROM entity_tags WHERE tag = ?2))
+             AND (?3 IS NULL OR julianday(e.created_at) >= julianday(?3))
+             AND (?4 IS NULL OR julianday(e.updated_at) >= julianday(?4))
+             AND (?5 IS NULL OR e.id IN (SELECT entity_id FROM entity_collections WHERE collection = ?5))";
+        let created_after = query.created_after.map(|t| t.to_rfc3339());
+        let updated_after = query.updated_after.map(|t| t.to_rfc3339());
+
+        let total: i64 = self.conn.query_row(
+            &format!("SELECT COUNT(*) FROM ({}) e WHERE {}", union, filters),
+            params![
+                query.status,
+                query.tag,
+                created_after,
+                updated_after,
+                query.collection
+            ],
+            |row| row.get(0),
+        )?;
+
+        let direction = if query.descending { "DESC" } else { "ASC" };
+        let order = match query.sort {
+            EntitySort::Sequence => format!("e.sequence_number {}", direction),
+            EntitySort::Created => {
+                format!("julianday(e.created_at) {}, e.sequence_number", direction)
+            }
+            EntitySort::Updated => {
+                format!("julianday(e.updated_at) {}, e.sequence_number", direction)
+            }
+            EntitySort::Title => format!("e.title COLLATE NOCASE {}, e.sequence_number", direction),
+            EntitySort::Priority => format!(
+                "CASE e.priority
+                   WHEN 'urgent' THEN 4
+                   WHEN 'high' THEN 3
+                   WHEN 'normal' THEN 2
+                   WHEN 'low' THEN 1
+                   ELSE 0
+                 END {}, e.sequence_number",
+                direction
+            ),
+        };
+
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT e.id, e.entity_type, e.sequence_number FROM ({}) e
+             WHERE {} AND e.sequence_number > ?6
+             ORDER BY {}, e.id
+             LIMIT ?7 OFFSET ?8",
+            union, filters, order
+        ))?;
+        let rows = stmt
+            .query_map(
+                params![
+                    query.status,
+                    query.tag,
+                    created_after,
+                    updated_after,
+                    query.collection,
+                    query.after_sequence.map_or(-1, i64::from),
+                    query.limit as i64,
+                    query.offset as i64,
+                ],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+            )?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+
+        Ok((rows, total as usize))
+    }
+
+    /// Every unarchived entity matching the filters, and the full-text
+    /// `query` when it is not empty, as `(entity_id, entity_type)` in type
+    /// then sequence order.
+    pub fn find_entities(
+        &self,
+        query: &str,
+        filter: &SearchFilter,
+    ) -> Result<Vec<(String, String)>> {
+        const SOURCES: [(&str, &str, &str, &str); 8] = [
+            ("decision", "decisions", "status", "NULL"),
+            ("task", "tasks", "status", "assignee"),
+            ("note", "notes", "NULL", "NULL"),
+            ("prompt", "prompts", "NULL", "NULL"),
+            ("component", "components", "status", "NULL"),
+            ("link", "links", "NULL", "NULL"),
+            ("question", "questions", "status", "NULL"),
+            ("milestone", "milestones", "status", "NULL"),
+        ];
+        let sources: Vec<_> = SOURCES
+            .iter()
+            .filter(|(entity_type, ..)| {
+                filter
+                    .entity_type
+                    .as_deref()
+                    .map_or(true, |t| t == *entity_type)
+            })
+            .collect();
+        if sources.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let union = sources
+            .iter()
+            .enumerate()
+            .map(|(order, (entity_type, table, status, assignee))| {
+                format!(
+                    "SELECT id, '{}' AS entity_type, {} AS type_order, sequence_number,
+                            {} AS status, {} AS assignee, created_at, created_by
+                     FROM {}",
+                    entity_type, order, status, assignee, table
+                )
+            })
+            .collect::<Vec<_>>()
+            .join(" UNION ALL ");
+
+        let mut values = Vec::new();
+        let mut text_condition = String::new();
+        if !query.is_empty() {
+            values.push(Value::Text(query.to_string()));
+            let matches = sources
+                .iter()
+                .map(|(_, table, ..)| {
+                    format!("SELECT id FROM {0}_fts WHERE {0}_fts MATCH ?1", table)
+                })
+                .collect::<Vec<_>>()
+                .join(" UNION ");
+            text_condition = format!(" AND e.id IN ({})", matches);
+        }
+        let (conditions, filter_values) =
+            filter_conditions("e", filter, true, true, values.len() + 1);
+        values.extend(filter_values);
+
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT e.id, e.entity_type FROM ({}) e
+             WHERE e.id NOT IN (SELECT entity_id FROM archived_entities){}{}
+             ORDER BY e.type_order, e.sequence_number",
+            union, text_condition, conditions
+        ))?;
+        let rows = stmt
+            .query_map(params_from_iter(values), |row| {
+                Ok((row.get(0)?, row.get(1)?))
+            })?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+
+        Ok(rows)
+    }
+
+    /// Check whether an entity has a tag (case-insensitive).
+    pub fn entity_has_tag(&self, entity_id: &str, tag: &str) -> Result<bool> {
+        let found: bool = self.conn.query_row(
+            "SELECT COUNT(*) > 0 FROM entity_tags WHERE entity_id = ?1 AND tag = ?2 COLLATE NOCASE",
+            params![entity_id, tag],
+            |row| row.get(0),
+        )?;
+        Ok(found)
+    }
+
+    /// Index a relation in the cache.
+    /// With inverse relations enabled, a swapped row flagged `is_inverse` is
+    /// written alongside the canonical one.
+    pub fn index_relation(&self, relation: &Relation) -> Result<()> {
+        self.conn.execute(
+            "INSERT OR REPLACE INTO relations
+             (composite_key, source_id, source_type, target_id, target_type, relation_type, created_at, created_by, resolved_at, is_inverse)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, 0)",
+            params![
+                relation.composite_key(),
+                relation.source_id.to_string(),
+                relation.source_type,
+                relation.target_id.to_string(),
+                relation.target_type,
+                relation.relation_type.to_string(),
+                relation.created_at.to_rfc3339(),
+                relation.created_by,
+                relation.resolved_at.map(|dt| dt.to_rfc3339()),
+            ],
+        )?;
+
+        if self.inverse_relations_enabled()? {
+            self.conn.execute(
+                "INSERT OR REPLACE INTO relations
+                 (composite_key, source_id, source_type, target_id, target_type, relation_type, created_at, created_by, resolved_at, is_inverse)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, 1)",
+                params![
+                    format!("{}{}", relation.composite_key(), INVERSE_KEY_SUFFIX),
+                    relation.target_id.to_string(),
+                    relation.target_type,
+                    relation.source_id.to_string(),
+                    relation.source_type,
+                    relation.relation_type.to_string(),
+                    relation.created_at.to_rfc3339(),
+                    relation.created_by,
+                    relation.resolved_at.map(|dt| dt.to_rfc3339()),
+                ],
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Record an entity's alias, replacing any entity that had it before
+    pub fn index_alias(&self, alias: &str, entity_id: &str) -> Result<()> {
+        self.conn.execute(
+            "DELETE FROM entity_aliases WHERE entity_id = ?1",
+            [entity_id],
+        )?;
+        self.conn.execute(
+            "INSERT OR REPLACE INTO entity_aliases (alias, entity_id) VALUES (?1, ?2)",
+            [alias, entity_id],
+        )?;
+        Ok(())
+    }
+
+    /// Record the collection an entity belongs to and its number there
+    pub fn index_collection(&self, entity_id: &str, collection: &str, sequence: u32) -> Result<()> {
+        self.conn.execute(
+            "INSERT OR REPLACE INTO entity_collections (entity_id, collection, sequence)
+             VALUES (?1, ?2, ?3)",
+            params![entity_id, collection, sequence],
+        )?;
+        Ok(())
+    }
+
+    /// Forget the collection an entity belonged to
+    pub fn unindex_collection(&self, entity_id: &str) -> Result<()> {
+        self.conn.execute(
+            "DELETE FROM entity_collections WHERE entity_id = ?1",
+            [entity_id],
+        )?;
+        Ok(())
+    }
+
+    /// Entity ID an alias names, if any
+    pub fn resolve_alias(&self, alias: &str) -> Result<Option<String>> {
+        let id = self
+            .conn
+            .query_row(
+                "SELECT entity_id FROM entity_aliases WHERE alias = ?1",
+                [alias.trim().to_lowercase()],
+                |row| row.get(0),
+            )
+            .optional()?;
+        Ok(id)
+    }
+
+    /// Remove a relation (and its materialized inverse, if any) from the cache
+    pub fn remove_relation(&self, composite_key: &str) -> Result<()> {
+        self.conn.execute(
+            "DELETE FROM relations WHERE composite_key IN (?1, ?1 || ?2)",
+            [composite_key, INVERSE_KEY_SUFFIX],
+        )?;
+        Ok(())
+    }
+
+    /// Whether inverse relation rows are materialized in the cache
+    pub fn inverse_relations_enabled(&self) -> Result<bool> {
+        let value: Option<String> = self
+            .conn
+            .query_row(
+                "SELECT value FROM meta WHERE key = 'inverse_relations'",
+                [],
+                |row| row.get(0),
+            )
+            .optional()?;
+        Ok(value.as_deref() == Some("1"))
+    }
+
+    /// Weights used to order ready tasks, as last set by `set_task_scoring`
+    pub fn task_scoring(&self) -> Result<TaskScoring> {
+        let value: Option<String> = self
+            .conn
+            .query_row(
+                "SELECT value FROM meta WHERE key = 'task_scoring'",
+                [],
+                |row| row.get(0),
+            )
+            .optional()?;
+        Ok(value
+            .and_then(|v| serde_json::from_str(&v).ok())
+            .unwrap_or_default())
+    }
+
+    /// Set the weights used to order ready tasks. Like the inverse relation
+    /// setting, it is kept in the cache so every reader applies the project's
+    /// config without loading it.
+    pub fn set_task_scoring(&self, scoring: &TaskScoring) -> Result<()> {
+        self.conn.execute(
+            "INSERT OR REPLACE INTO meta (key, value) VALUES ('task_scoring', ?1)",
+            [serde_json::to_string(scoring)?],
+        )?;
+        Ok(())
+    }
+
+    /// Turn materialized inverse relations on or off.
+    ///
+    /// The setting is kept in the cache and existing relations are updated in
+    /// place, so no resync is needed. Only the canonical relation is stored in
+    /// Loro; inverse rows are derived.
+    pub fn set_inverse_relations(&self, enabled: bool) -> Result<()> {
+        if self.inverse_relations_enabled()? == enabled {
+            return Ok(());
+        }
+
+        if enabled {
+            self.conn.execute(
+                "INSERT OR REPLACE INTO relations
+                 (composite_key, source_id, source_type, target_id, target_type, relation_type, created_at, created_by, resolved_at, is_inverse)
+                 SELECT composite_key || ?1, target_id, target_type, source_id, source_type,
+                        relation_type, created_at, created_by, resolved_at, 1
+                 FROM relations WHERE is_inverse = 0",
+                [INVERSE_KEY_SUFFIX],
+            )?;
+            self.conn.execute(
+                "INSERT OR REPLACE INTO meta (key, value) VALUES ('inverse_relations', '1')",
+                [],
+            )?;
+        } else {
+            self.conn
+                .execute("DELETE FROM relations WHERE is_inverse = 1", [])?;
+            self.conn
+                .execute("DELETE FROM meta WHERE key = 'inverse_relations'", [])?;
+        }
+        Ok(())
+    }
+
+    /// Clear all cached data (for full rebuild)
+    pub fn clear(&self) -> Result<()> {
+        self.conn.execute("DELETE FROM decisions", [])?;
+        self.conn.execute("DELETE FROM tasks", [])?;
+        self.conn.execute("DELETE FROM notes", [])?;
+        self.conn.execute("DELETE FROM prompts", [])?;
+        self.conn.execute("DELETE FROM components", [])?;
+        self.conn.execute("DELETE FROM links", [])?;
+        self.conn.execute("DELETE FROM questions", [])?;
+        self.conn.execute("DELETE FROM milestones", [])?;
+        self.conn.execute("DELETE FROM relations", [])?;
+        self.conn.execute("DELETE FROM entity_tags", [])?;
+        self.conn.execute("DELETE FROM entity_aliases", [])?;
+        self.conn.execute("DELETE FROM entity_collections", [])?;
+        self.conn.execute("DELETE FROM archived_entities", [])?;
+        self.conn.execute("DELETE FROM embeddings", [])?;
+        // Keep settings; only the version marker must go
+        self.conn
+            .execute("DELETE FROM meta WHERE key != 'inverse_relations'", [])?;
+        Ok(())
+    }
+
+    /// Rebuild all FTS5 indexes from their content tables.
+    ///
+    /// Unlike `clear` + resync, this leaves content tables, relations and
+    /// embeddings untouched. Returns the number of FTS tables rebuilt.
+    pub fn rebuild_fts(&self) -> Result<usize> {
+        for table in FTS_TABLES {
+            self.conn.execute(
+                &format!("INSERT INTO {0}({0}) VALUES('rebuild')", table),
+                [],
+            )?;
+        }
+        Ok(FTS_TABLES.len())
+    }
+
+    /// Reclaim the space left by deleted rows and rewritten indexes.
+    pub fn vacuum(&self) -> Result<()> {
+        self.conn.execute_batch("VACUUM")?;
+        Ok(())
+    }
+
+    /// FTS tables whose index no longer matches their content table, e.g.
+    /// after an interrupted write. `cache rebuild-fts` repairs them.
+    pub fn check_fts_integrity(&self) -> Result<Vec<&'static str>> {
+        let mut broken = Vec::new();
+        for table in FTS_TABLES {
+            let check = self.conn.execute(
+                &format!(
+                    "INSERT INTO {0}({0}, rank) VALUES('integrity-check', 1)",
+                    table
+                ),
+                [],
+            );
+            if check.is_err() {
+                broken.push(table);
+            }
+        }
+        Ok(broken)
+    }
+
+    /// IDs of every entity in the cache
+    pub fn get_entity_ids(&self) -> Result<HashSet<String>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id FROM decisions UNION ALL SELECT id FROM tasks
+             UNION ALL SELECT id FROM notes UNION ALL SELECT id FROM prompts
+             UNION ALL SELECT id FROM components UNION ALL SELECT id FROM links
+             UNION ALL SELECT id FROM questions UNION ALL SELECT id FROM milestones",
+        )?;
+        let ids = stmt
+            .query_map([], |row| row.get(0))?
+            .collect::<std::result::Result<HashSet<String>, _>>()?;
+        Ok(ids)
+    }
+
+    /// Number of stored embeddings whose entity is no longer in the cache
+    pub fn count_orphaned_embeddings(&self) -> Result<usize> {
+        let count: i64 = self.conn.query_row(
+            "SELECT COUNT(*) FROM embeddings WHERE entity_id NOT IN (
+                SELECT id FROM decisions UNION ALL SELECT id FROM tasks
+                UNION ALL SELECT id FROM notes UNION ALL SELECT id FROM prompts
+                UNION ALL SELECT id FROM components UNION ALL SELECT id FROM links
+                UNION ALL SELECT id FROM questions UNION ALL SELECT id FROM milestones
+            )",
+            [],
+            |row| row.get(0),
+        )?;
+        Ok(count as usize)
+    }
+
+    // =========================================================================
+    // Vector Index
+    // =========================================================================
+
+    /// Token that changes whenever an embedding is stored or deleted, creating
+    /// one if the cache has none yet (new or cleared cache).
+    fn embedding_generation(&self) -> Result<String> {
+        let generation: Option<String> = self
+            .conn
+            .query_row(
+                "SELECT value FROM meta WHERE key = 'embedding_generation'",
+                [],
+                |row| row.get(0),
+            )
+            .optional()?;
+        match generation {
+            Some(generation) => Ok(generation),
+            None => self.bump_embedding_generation(),
+        }
+    }
+
+    fn bump_embedding_generation(&self) -> Result<String> {
+        let generation = uuid::Uuid::new_v4().to_string();
+        self.conn.execute(
+            "INSERT OR REPLACE INTO meta (key, value) VALUES ('embedding_generation', ?1)",
+            [&generation],
+        )?;
+        Ok(generation)
+    }
+
+    /// Path of the persisted vector index, None for an in-memory cache
+    fn vector_index_path(&self) -> Option<PathBuf> {
+        if self.path.as_os_str().is_empty() {
+            None
+        } else {
+            Some(self.path.with_file_name(VECTOR_INDEX_FILE))
+        }
+    }
+
+    /// Record an embedding write: bump the generation and, if the index in
+    /// memory was current, apply the write to it in place rather than leave
+    /// it to be rebuilt.
+    fn update_vector_index(&self, apply: impl FnOnce(&mut VectorIndex, String)) -> Result<()> {
+        let previous = self.embedding_generation()?;
+        let generation = uuid::Uuid::new_v4().to_string();
+        // Only move on from `previous` if no other connection has meanwhile,
+        // or the index would be tagged current while missing that write
+        let moved = self.conn.execute(
+            "UPDATE meta SET value = ?1 WHERE key = 'embedding_generation' AND value = ?2",
+            [&generation, &previous],
+        )?;
+        if moved == 0 {
+            self.bump_embedding_generation()?;
+            return Ok(());
+        }
+        let mut slot = self
+            .vector_index
+            .lock()
+            .map_err(|e| MedullaError::Storage(format!("vector index lock poisoned: {}", e)))?;
+        if let Some(index) = slot.as_mut().filter(|i| i.generation() == previous) {
+            apply(index, generation);
+            self.vector_index_unsaved.store(true, Ordering::Relaxed);
+        }
+        Ok(())
+    }
+
+    /// The vector index for the current embeddings: the one already in
+    /// memory, else the persisted one, else built from the embeddings table
+    /// (and persisted for the next process). An index updated in place is
+    /// persisted on its next use.
+    fn current_vector_index<'a>(
+        &self,
+        slot: &'a mut Option<VectorIndex>,
+    ) -> Result<&'a VectorIndex> {
+        let generation = self.embedding_generation()?;
+        if slot.as_ref().map_or(true, |i| i.generation() != generation) {
+            self.vector_index_misses.fetch_add(1, Ordering::Relaxed);
+            self.vector_index_unsaved.store(false, Ordering::Relaxed);
+            let path = self.vector_index_path();
+            let loaded = path
+                .as_deref()
+                .and_then(VectorIndex::load)
+                .filter(|i| i.generation() == generation);
+            *slot = Some(match loaded {
+                Some(index) => index,
+                None => {
+                    let index = VectorIndex::build(generation, self.list_all_embeddings(None)?);
+                    if let Some(path) = path {
+                        index.save(&path)?;
+                    }
+                    index
+                }
+            });
+        } else {
+            self.vector_index_hits.fetch_add(1, Ordering::Relaxed);
+            if self.vector_index_unsaved.swap(false, Ordering::Relaxed) {
+                if let (Some(index), Some(path)) = (slot.as_ref(), self.vector_index_path()) {
+                    index.save(&path)?;
+                }
+            }
+        }
+        Ok(slot.as_ref().expect("vector index was just set"))
+    }
+
+    /// Size of the cache database file in bytes (0 for an in-memory cache)
+    pub fn size_bytes(&self) -> u64 {
+        std::fs::metadata(&self.path).map(|m| m.len()).unwrap_or(0)
+    }
+
+    /// `(hits, misses)` of vector index lookups since the cache was opened:
+    /// hits found the index already in memory, misses loaded or rebuilt it
+    pub fn vector_index_lookups(&self) -> (u64, u64) {
+        (
+            self.vector_index_hits.load(Ordering::Relaxed),
+            self.vector_index_misses.load(Ordering::Relaxed),
+        )
+    }
+
+    /// Rebuild the persisted vector index from the embeddings table, e.g.
+    /// after `cache rebuild` or when upgrading a cache that predates it.
+    /// Returns the number of vectors indexed.
+    pub fn rebuild_vector_index(&self) -> Result<usize> {
+        let generation = self.bump_embedding_generation()?;
+        let index = VectorIndex::build(generation, self.list_all_embeddings(None)?);
+        if let Some(path) = self.vector_index_path() {
+            index.save(&path)?;
+        }
+        let len = index.len();
+        self.vector_index_unsaved.store(false, Ordering::Relaxed);
+        *self
+            .vector_index
+            .lock()
+            .map_err(|e| MedullaError::Storage(format!("vector index lock poisoned: {}", e)))? =
+            Some(index);
+        Ok(len)
+    }
+
+    // =========================================================================
+    // Embedding Storage Methods
+    // =========================================================================
+
+    /// Store an embedding for an entity.
+    /// The embedding is stored as a BLOB (f32 array serialized as bytes).
+    pub fn store_embedding(
+        &self,
+        entity_id: &str,
+        entity_type: &str,
+        embedding: &[f32],
+        text_hash: &str,
+    ) -> Result<()> {
+        // Convert f32 array to bytes
+        let embedding_bytes = embedding_to_bytes(embedding);
+
+        self.conn.execute(
+            "INSERT OR REPLACE INTO embeddings
+             (entity_id, entity_type, embedding, text_hash, computed_at)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![
+                entity_id,
+                entity_type,
+                embedding_bytes,
+                text_hash,
+                Utc::now().to_rfc3339(),
+            ],
+        )?;
+        self.update_vector_index(|index, generation| {
+            index.upsert(generation, entity_id, entity_type, embedding)
+        })?;
+
+        Ok(())
+    }
+
+    /// Get an embedding for an entity.
+    /// Returns None if no embedding exists.
+    pub fn get_embedding(&self, entity_id: &str) -> Result<Option<Vec<f32>>> {
+        let result: Option<Vec<u8>> = self
+            .conn
+            .query_row(
+                "SELECT embedding FROM embeddings WHERE entity_id = ?1",
+                [entity_id],
+                |row| row.get(0),
+            )
+            .optional()?;
+
+        Ok(result.map(|bytes| bytes_to_embedding(&bytes)))
+    }
+
+    /// Get the text hash for an entity's embedding.
+    /// Used to check if content has changed and embedding needs recomputing.
+    pub fn get_embedding_text_hash(&self, entity_id: &str) -> Result<Option<String>> {
+        let result: Option<String> = self
+            .conn
+            .query_row(
+                "SELECT text_hash FROM embeddings WHERE entity_id = ?1",
+                [entity_id],
+                |row| row.get(0),
+            )
+            .optional()?;
+        Ok(result)
+    }
+
+    /// Dimension of the stored embeddings, or None if none are stored.
+    pub fn embedding_dimension(&self) -> Result<Option<usize>> {
+        let bytes: Option<i64> = self
+            .conn
+            .query_row(
+                "SELECT length(embedding) FROM embeddings LIMIT 1",
+                [],
+                |row| row.get(0),
+            )
+            .optional()?;
+        Ok(bytes.map(|b| b as usize / std::mem::size_of::<f32>()))
+    }
+
+    /// Delete an embedding for an entity.
+    pub fn delete_embedding(&self, entity_id: &str) -> Result<()> {
+        self.conn
+            .execute("DELETE FROM embeddings WHERE entity_id = ?1", [entity_id])?;
+        self.update_vector_index(|index, generation| index.remove(generation, entity_id))?;
+        Ok(())
+    }
+
+    /// List all embeddings for a specific entity type.
+    /// Returns tuples of (entity_id, embedding).
+    pub fn list_embeddings_by_type(&self, entity_type: &str) -> Result<Vec<(String, Vec<f32>)>> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT entity_id, embedding FROM embeddings WHERE entity_type = ?1")?;
+
+        let results = stmt
+            .query_map([entity_type], |row| {
+                let entity_id: String = row.get(0)?;
+                let embedding_bytes: Vec<u8> = row.get(1)?;
+                Ok((entity_id, bytes_to_embedding(&embedding_bytes)))
             })?
             .collect::<std::result::Result<Vec<_>, _>>()?;
 
@@ -816,14 +1824,24 @@ impl SqliteCache {
         let links: i64 = self
             .conn
             .query_row("SELECT COUNT(*) FROM links", [], |row| row.get(0))?;
-        let relations: i64 = self
+        let questions: i64 = self
             .conn
-            .query_row("SELECT COUNT(*) FROM relations", [], |row| row.get(0))?;
+            .query_row("SELECT COUNT(*) FROM questions", [], |row| row.get(0))?;
+        let milestones: i64 =
+            self.conn
+                .query_row("SELECT COUNT(*) FROM milestones", [], |row| row.get(0))?;
+        let relations: i64 = self.conn.query_row(
+            "SELECT COUNT(*) FROM relations WHERE is_inverse = 0",
+            [],
+            |row| row.get(0),
+        )?;
         let embeddings: i64 =
             self.conn
                 .query_row("SELECT COUNT(*) FROM embeddings", [], |row| row.get(0))?;
 
-        let entity_count = (decisions + tasks + notes + prompts + components + links) as usize;
+        let entity_count =
+            (decisions + tasks + notes + prompts + components + links + questions + milestones)
+                as usize;
 
         Ok(CacheStats {
             entity_count,
@@ -834,6 +1852,8 @@ impl SqliteCache {
             prompts: prompts as usize,
             components: components as usize,
             links: links as usize,
+            questions: questions as usize,
+            milestones: milestones as usize,
             relations: relations as usize,
         })
     }
@@ -875,19 +1895,32 @@ impl SqliteCache {
 
     /// Full-text search for decisions
     pub fn search_decisions(&self, query: &str, limit: i64) -> Result<Vec<DecisionSearchResult>> {
-        let mut stmt = self.conn.prepare(
+        self.search_decisions_filtered(query, &SearchFilter::default(), limit)
+    }
+
+    /// Full-text search for decisions, narrowed by structured filters
+    pub fn search_decisions_filtered(
+        &self,
+        query: &str,
+        filter: &SearchFilter,
+        limit: i64,
+    ) -> Result<Vec<DecisionSearchResult>> {
+        let (conditions, values) = filter_conditions("d", filter, true, false, 3);
+        let mut stmt = self.conn.prepare(&format!(
             "SELECT d.id, d.sequence_number, d.title, d.status,
                     highlight(decisions_fts, 1, '<mark>', '</mark>') as title_highlight,
-                    snippet(decisions_fts, 2, '<mark>', '</mark>', '...', 32) as content_snippet
+                    snippet(decisions_fts, 2, '<mark>', '</mark>', '...', 32) as content_snippet,
+                    f.rank
              FROM decisions_fts f
              JOIN decisions d ON d.id = f.id
-             WHERE decisions_fts MATCH ?1
+             WHERE decisions_fts MATCH ?1{}
              ORDER BY rank
              LIMIT ?2",
-        )?;
+            conditions
+        ))?;
 
         let results = stmt
-            .query_map(params![query, limit], |row| {
+            .query_map(search_params(query, limit, values), |row| {
                 Ok(DecisionSearchResult {
                     id: row.get(0)?,
                     sequence_number: row.get(1)?,
@@ -895,6 +1928,7 @@ impl SqliteCache {
                     status: row.get(3)?,
                     title_highlight: row.get(4)?,
                     content_snippet: row.get(5)?,
+                    rank: row.get(6)?,
                 })
             })?
             .collect::<std::result::Result<Vec<_>, _>>()?;
@@ -904,19 +1938,32 @@ impl SqliteCache {
 
     /// Full-text search for tasks
     pub fn search_tasks(&self, query: &str, limit: i64) -> Result<Vec<TaskSearchResult>> {
-        let mut stmt = self.conn.prepare(
+        self.search_tasks_filtered(query, &SearchFilter::default(), limit)
+    }
+
+    /// Full-text search for tasks, narrowed by structured filters
+    pub fn search_tasks_filtered(
+        &self,
+        query: &str,
+        filter: &SearchFilter,
+        limit: i64,
+    ) -> Result<Vec<TaskSearchResult>> {
+        let (conditions, values) = filter_conditions("t", filter, true, true, 3);
+        let mut stmt = self.conn.prepare(&format!(
             "SELECT t.id, t.sequence_number, t.title, t.status, t.priority, t.assignee,
                     highlight(tasks_fts, 1, '<mark>', '</mark>') as title_highlight,
-                    snippet(tasks_fts, 2, '<mark>', '</mark>', '...', 32) as content_snippet
+                    snippet(tasks_fts, 2, '<mark>', '</mark>', '...', 32) as content_snippet,
+                    f.rank
              FROM tasks_fts f
              JOIN tasks t ON t.id = f.id
-             WHERE tasks_fts MATCH ?1
+             WHERE tasks_fts MATCH ?1{}
              ORDER BY rank
              LIMIT ?2",
-        )?;
+            conditions
+        ))?;
 
         let results = stmt
-            .query_map(params![query, limit], |row| {
+            .query_map(search_params(query, limit, values), |row| {
                 Ok(TaskSearchResult {
                     id: row.get(0)?,
                     sequence_number: row.get(1)?,
@@ -926,6 +1973,7 @@ impl SqliteCache {
                     assignee: row.get(5)?,
                     title_highlight: row.get(6)?,
                     content_snippet: row.get(7)?,
+                    rank: row.get(8)?,
                 })
             })?
             .collect::<std::result::Result<Vec<_>, _>>()?;
@@ -935,19 +1983,32 @@ impl SqliteCache {
 
     /// Full-text search for notes
     pub fn search_notes(&self, query: &str, limit: i64) -> Result<Vec<NoteSearchResult>> {
-        let mut stmt = self.conn.prepare(
+        self.search_notes_filtered(query, &SearchFilter::default(), limit)
+    }
+
+    /// Full-text search for notes, narrowed by structured filters
+    pub fn search_notes_filtered(
+        &self,
+        query: &str,
+        filter: &SearchFilter,
+        limit: i64,
+    ) -> Result<Vec<NoteSearchResult>> {
+        let (conditions, values) = filter_conditions("n", filter, false, false, 3);
+        let mut stmt = self.conn.prepare(&format!(
             "SELECT n.id, n.sequence_number, n.title, n.note_type,
                     highlight(notes_fts, 1, '<mark>', '</mark>') as title_highlight,
-                    snippet(notes_fts, 2, '<mark>', '</mark>', '...', 32) as content_snippet
+                    snippet(notes_fts, 2, '<mark>', '</mark>', '...', 32) as content_snippet,
+                    f.rank
              FROM notes_fts f
              JOIN notes n ON n.id = f.id
-             WHERE notes_fts MATCH ?1
+             WHERE notes_fts MATCH ?1{}
              ORDER BY rank
              LIMIT ?2",
-        )?;
+            conditions
+        ))?;
 
         let results = stmt
-            .query_map(params![query, limit], |row| {
+            .query_map(search_params(query, limit, values), |row| {
                 Ok(NoteSearchResult {
                     id: row.get(0)?,
                     sequence_number: row.get(1)?,
@@ -955,6 +2016,7 @@ impl SqliteCache {
                     note_type: row.get(3)?,
                     title_highlight: row.get(4)?,
                     content_snippet: row.get(5)?,
+                    rank: row.get(6)?,
                 })
             })?
             .collect::<std::result::Result<Vec<_>, _>>()?;
@@ -964,19 +2026,32 @@ impl SqliteCache {
 
     /// Full-text search for prompts
     pub fn search_prompts(&self, query: &str, limit: i64) -> Result<Vec<PromptSearchResult>> {
-        let mut stmt = self.conn.prepare(
+        self.search_prompts_filtered(query, &SearchFilter::default(), limit)
+    }
+
+    /// Full-text search for prompts, narrowed by structured filters
+    pub fn search_prompts_filtered(
+        &self,
+        query: &str,
+        filter: &SearchFilter,
+        limit: i64,
+    ) -> Result<Vec<PromptSearchResult>> {
+        let (conditions, values) = filter_conditions("p", filter, false, false, 3);
+        let mut stmt = self.conn.prepare(&format!(
             "SELECT p.id, p.sequence_number, p.title, p.variables,
                     highlight(prompts_fts, 1, '<mark>', '</mark>') as title_highlight,
-                    snippet(prompts_fts, 2, '<mark>', '</mark>', '...', 32) as content_snippet
+                    snippet(prompts_fts, 2, '<mark>', '</mark>', '...', 32) as content_snippet,
+                    f.rank
              FROM prompts_fts f
              JOIN prompts p ON p.id = f.id
-             WHERE prompts_fts MATCH ?1
+             WHERE prompts_fts MATCH ?1{}
              ORDER BY rank
              LIMIT ?2",
-        )?;
+            conditions
+        ))?;
 
         let results = stmt
-            .query_map(params![query, limit], |row| {
+            .query_map(search_params(query, limit, values), |row| {
                 let vars_str: String = row.get(3)?;
                 let variables = vars_str
                     .split(", ")
@@ -990,6 +2065,7 @@ impl SqliteCache {
                     variables,
                     title_highlight: row.get(4)?,
                     content_snippet: row.get(5)?,
+                    rank: row.get(6)?,
                 })
             })?
             .collect::<std::result::Result<Vec<_>, _>>()?;
@@ -999,19 +2075,32 @@ impl SqliteCache {
 
     /// Full-text search for components
     pub fn search_components(&self, query: &str, limit: i64) -> Result<Vec<ComponentSearchResult>> {
-        let mut stmt = self.conn.prepare(
+        self.search_components_filtered(query, &SearchFilter::default(), limit)
+    }
+
+    /// Full-text search for components, narrowed by structured filters
+    pub fn search_components_filtered(
+        &self,
+        query: &str,
+        filter: &SearchFilter,
+        limit: i64,
+    ) -> Result<Vec<ComponentSearchResult>> {
+        let (conditions, values) = filter_conditions("c", filter, true, false, 3);
+        let mut stmt = self.conn.prepare(&format!(
             "SELECT c.id, c.sequence_number, c.title, c.status, c.component_type, c.owner,
                     highlight(components_fts, 1, '<mark>', '</mark>') as title_highlight,
-                    snippet(components_fts, 2, '<mark>', '</mark>', '...', 32) as content_snippet
+                    snippet(components_fts, 2, '<mark>', '</mark>', '...', 32) as content_snippet,
+                    f.rank
              FROM components_fts f
              JOIN components c ON c.id = f.id
-             WHERE components_fts MATCH ?1
+             WHERE components_fts MATCH ?1{}
              ORDER BY rank
              LIMIT ?2",
-        )?;
+            conditions
+        ))?;
 
         let results = stmt
-            .query_map(params![query, limit], |row| {
+            .query_map(search_params(query, limit, values), |row| {
                 Ok(ComponentSearchResult {
                     id: row.get(0)?,
                     sequence_number: row.get(1)?,
@@ -1021,6 +2110,7 @@ impl SqliteCache {
                     owner: row.get(5)?,
                     title_highlight: row.get(6)?,
                     content_snippet: row.get(7)?,
+                    rank: row.get(8)?,
                 })
             })?
             .collect::<std::result::Result<Vec<_>, _>>()?;
@@ -1030,19 +2120,32 @@ impl SqliteCache {
 
     /// Full-text search for links
     pub fn search_links(&self, query: &str, limit: i64) -> Result<Vec<LinkSearchResult>> {
-        let mut stmt = self.conn.prepare(
+        self.search_links_filtered(query, &SearchFilter::default(), limit)
+    }
+
+    /// Full-text search for links, narrowed by structured filters
+    pub fn search_links_filtered(
+        &self,
+        query: &str,
+        filter: &SearchFilter,
+        limit: i64,
+    ) -> Result<Vec<LinkSearchResult>> {
+        let (conditions, values) = filter_conditions("l", filter, false, false, 3);
+        let mut stmt = self.conn.prepare(&format!(
             "SELECT l.id, l.sequence_number, l.title, l.url, l.link_type,
                     highlight(links_fts, 1, '<mark>', '</mark>') as title_highlight,
-                    snippet(links_fts, 2, '<mark>', '</mark>', '...', 32) as content_snippet
+                    snippet(links_fts, 2, '<mark>', '</mark>', '...', 32) as content_snippet,
+                    f.rank
              FROM links_fts f
              JOIN links l ON l.id = f.id
-             WHERE links_fts MATCH ?1
+             WHERE links_fts MATCH ?1{}
              ORDER BY rank
              LIMIT ?2",
-        )?;
+            conditions
+        ))?;
 
         let results = stmt
-            .query_map(params![query, limit], |row| {
+            .query_map(search_params(query, limit, values), |row| {
                 Ok(LinkSearchResult {
                     id: row.get(0)?,
                     sequence_number: row.get(1)?,
@@ -1051,6 +2154,95 @@ impl SqliteCache {
                     link_type: row.get(4)?,
                     title_highlight: row.get(5)?,
                     content_snippet: row.get(6)?,
+                    rank: row.get(7)?,
+                })
+            })?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+
+        Ok(results)
+    }
+
+    /// Full-text search for questions
+    pub fn search_questions(&self, query: &str, limit: i64) -> Result<Vec<QuestionSearchResult>> {
+        self.search_questions_filtered(query, &SearchFilter::default(), limit)
+    }
+
+    /// Full-text search for questions, narrowed by structured filters
+    pub fn search_questions_filtered(
+        &self,
+        query: &str,
+        filter: &SearchFilter,
+        limit: i64,
+    ) -> Result<Vec<QuestionSearchResult>> {
+        let (conditions, values) = filter_conditions("q", filter, true, false, 3);
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT q.id, q.sequence_number, q.title, q.status, q.answered_by,
+                    highlight(questions_fts, 1, '<mark>', '</mark>') as title_highlight,
+                    snippet(questions_fts, 2, '<mark>', '</mark>', '...', 32) as content_snippet,
+                    f.rank
+             FROM questions_fts f
+             JOIN questions q ON q.id = f.id
+             WHERE questions_fts MATCH ?1{}
+             ORDER BY rank
+             LIMIT ?2",
+            conditions
+        ))?;
+
+        let results = stmt
+            .query_map(search_params(query, limit, values), |row| {
+                Ok(QuestionSearchResult {
+                    id: row.get(0)?,
+                    sequence_number: row.get(1)?,
+                    title: row.get(2)?,
+                    status: row.get(3)?,
+                    answered_by: row.get(4)?,
+                    title_highlight: row.get(5)?,
+                    content_snippet: row.get(6)?,
+                    rank: row.get(7)?,
+                })
+            })?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+
+        Ok(results)
+    }
+
+    /// Full-text search for milestones
+    pub fn search_milestones(&self, query: &str, limit: i64) -> Result<Vec<MilestoneSearchResult>> {
+        self.search_milestones_filtered(query, &SearchFilter::default(), limit)
+    }
+
+    /// Full-text search for milestones, narrowed by structured filters
+    pub fn search_milestones_filtered(
+        &self,
+        query: &str,
+        filter: &SearchFilter,
+        limit: i64,
+    ) -> Result<Vec<MilestoneSearchResult>> {
+        let (conditions, values) = filter_conditions("m", filter, true, false, 3);
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT m.id, m.sequence_number, m.title, m.status, m.target_date,
+                    highlight(milestones_fts, 1, '<mark>', '</mark>') as title_highlight,
+                    snippet(milestones_fts, 2, '<mark>', '</mark>', '...', 32) as content_snippet,
+                    f.rank
+             FROM milestones_fts f
+             JOIN milestones m ON m.id = f.id
+             WHERE milestones_fts MATCH ?1{}
+             ORDER BY rank
+             LIMIT ?2",
+            conditions
+        ))?;
+
+        let results = stmt
+            .query_map(search_params(query, limit, values), |row| {
+                Ok(MilestoneSearchResult {
+                    id: row.get(0)?,
+                    sequence_number: row.get(1)?,
+                    title: row.get(2)?,
+                    status: row.get(3)?,
+                    target_date: row.get(4)?,
+                    title_highlight: row.get(5)?,
+                    content_snippet: row.get(6)?,
+                    rank: row.get(7)?,
                 })
             })?
             .collect::<std::result::Result<Vec<_>, _>>()?;
@@ -1091,64 +2283,166 @@ impl SqliteCache {
             for r in components {
                 all_results.push(SearchResult::Component(r));
             }
-        }
-
-        if let Ok(links) = self.search_links(query, limit) {
-            for r in links {
-                all_results.push(SearchResult::Link(r));
+        }
+
+        if let Ok(links) = self.search_links(query, limit) {
+            for r in links {
+                all_results.push(SearchResult::Link(r));
+            }
+        }
+
+        if let Ok(questions) = self.search_questions(query, limit) {
+            for r in questions {
+                all_results.push(SearchResult::Question(r));
+            }
+        }
+
+        if let Ok(milestones) = self.search_milestones(query, limit) {
+            for r in milestones {
+                all_results.push(SearchResult::Milestone(r));
+            }
+        }
+
+        // Merge the types by relevance, then limit total results
+        all_results.sort_by(|a, b| a.rank().total_cmp(&b.rank()));
+        all_results.truncate(limit as usize);
+
+        Ok(all_results)
+    }
+
+    /// Search a specific entity type with full-text search.
+    pub fn search_by_type(
+        &self,
+        entity_type: &str,
+        query: &str,
+        limit: i64,
+    ) -> Result<Vec<SearchResult>> {
+        match entity_type {
+            "decision" => {
+                let results = self.search_decisions(query, limit)?;
+                Ok(results.into_iter().map(SearchResult::Decision).collect())
+            }
+            "task" => {
+                let results = self.search_tasks(query, limit)?;
+                Ok(results.into_iter().map(SearchResult::Task).collect())
+            }
+            "note" => {
+                let results = self.search_notes(query, limit)?;
+                Ok(results.into_iter().map(SearchResult::Note).collect())
+            }
+            "prompt" => {
+                let results = self.search_prompts(query, limit)?;
+                Ok(results.into_iter().map(SearchResult::Prompt).collect())
+            }
+            "component" => {
+                let results = self.search_components(query, limit)?;
+                Ok(results.into_iter().map(SearchResult::Component).collect())
+            }
+            "link" => {
+                let results = self.search_links(query, limit)?;
+                Ok(results.into_iter().map(SearchResult::Link).collect())
+            }
+            "question" => {
+                let results = self.search_questions(query, limit)?;
+                Ok(results.into_iter().map(SearchResult::Question).collect())
+            }
+            "milestone" => {
+                let results = self.search_milestones(query, limit)?;
+                Ok(results.into_iter().map(SearchResult::Milestone).collect())
             }
+            _ => Ok(Vec::new()),
         }
-
-        // Limit total results
-        all_results.truncate(limit as usize);
-
-        Ok(all_results)
     }
 
-    /// Search a specific entity type with full-text search.
-    pub fn search_by_type(
+    /// Search a specific entity type, narrowed by structured filters.
+    pub fn search_by_type_filtered(
         &self,
         entity_type: &str,
         query: &str,
+        filter: &SearchFilter,
         limit: i64,
     ) -> Result<Vec<SearchResult>> {
         match entity_type {
             "decision" => {
-                let results = self.search_decisions(query, limit)?;
+                let results = self.search_decisions_filtered(query, filter, limit)?;
                 Ok(results.into_iter().map(SearchResult::Decision).collect())
             }
             "task" => {
-                let results = self.search_tasks(query, limit)?;
+                let results = self.search_tasks_filtered(query, filter, limit)?;
                 Ok(results.into_iter().map(SearchResult::Task).collect())
             }
             "note" => {
-                let results = self.search_notes(query, limit)?;
+                let results = self.search_notes_filtered(query, filter, limit)?;
                 Ok(results.into_iter().map(SearchResult::Note).collect())
             }
             "prompt" => {
-                let results = self.search_prompts(query, limit)?;
+                let results = self.search_prompts_filtered(query, filter, limit)?;
                 Ok(results.into_iter().map(SearchResult::Prompt).collect())
             }
             "component" => {
-                let results = self.search_components(query, limit)?;
+                let results = self.search_components_filtered(query, filter, limit)?;
                 Ok(results.into_iter().map(SearchResult::Component).collect())
             }
             "link" => {
-                let results = self.search_links(query, limit)?;
+                let results = self.search_links_filtered(query, filter, limit)?;
                 Ok(results.into_iter().map(SearchResult::Link).collect())
             }
+            "question" => {
+                let results = self.search_questions_filtered(query, filter, limit)?;
+                Ok(results.into_iter().map(SearchResult::Question).collect())
+            }
+            "milestone" => {
+                let results = self.search_milestones_filtered(query, filter, limit)?;
+                Ok(results.into_iter().map(SearchResult::Milestone).collect())
+            }
             _ => Ok(Vec::new()),
         }
     }
 
+    /// Count every entity of a type matching a full-text query and filters,
+    /// regardless of any limit.
+    pub fn count_search_filtered(
+        &self,
+        entity_type: &str,
+        query: &str,
+        filter: &SearchFilter,
+    ) -> Result<usize> {
+        let (table, alias, has_status, has_assignee) = match entity_type {
+            "decision" => ("decisions", "d", true, false),
+            "task" => ("tasks", "t", true, true),
+            "note" => ("notes", "n", false, false),
+            "prompt" => ("prompts", "p", false, false),
+            "component" => ("components", "c", true, false),
+            "link" => ("links", "l", false, false),
+            "question" => ("questions", "q", true, false),
+            "milestone" => ("milestones", "m", true, false),
+            _ => return Ok(0),
+        };
+        let (conditions, values) = filter_conditions(alias, filter, has_status, has_assignee, 2);
+        let mut params = vec![Value::Text(query.to_string())];
+        params.extend(values);
+        let count: i64 = self.conn.query_row(
+            &format!(
+                "SELECT COUNT(*) FROM {0}_fts f
+                 JOIN {0} {1} ON {1}.id = f.id
+                 WHERE {0}_fts MATCH ?1{2}",
+                table, alias, conditions
+            ),
+            params_from_iter(params),
+            |row| row.get(0),
+        )?;
+        Ok(count as usize)
+    }
+
     // =========================================================================
     // Semantic Search
     // =========================================================================
 
     /// Search entities by semantic similarity using vector embeddings.
     ///
-    /// Computes cosine similarity between the query embedding and all stored
-    /// embeddings, returning the top results above the threshold.
+    /// Scores the query against the vector index (see `VectorIndex`), which is
+    /// reloaded or rebuilt whenever the stored embeddings have changed, and
+    /// returns the top results above the threshold.
     ///
     /// # Arguments
     /// * `query_embedding` - The embedding vector for the query text
@@ -1162,45 +2456,221 @@ impl SqliteCache {
         limit: usize,
         threshold: f32,
     ) -> Result<Vec<SemanticSearchResult>> {
-        // Load all embeddings (optionally filtered by type)
-        let embeddings = self.list_all_embeddings(entity_type)?;
-
-        // Compute similarity and collect results
-        let mut results: Vec<SemanticSearchResult> = embeddings
-            .into_iter()
-            .map(|(entity_id, entity_type, embedding)| {
-                let score = cosine_similarity(query_embedding, &embedding);
-                (entity_id, entity_type, score)
-            })
-            .filter(|(_, _, score)| *score >= threshold)
-            .collect::<Vec<_>>()
-            .into_iter()
-            .filter_map(|(entity_id, entity_type, score)| {
-                // Look up metadata for this entity
-                let metadata = self.get_entity_metadata(&entity_id, &entity_type).ok()?;
-                metadata.map(|(seq, title)| SemanticSearchResult {
-                    entity_id,
-                    entity_type,
+        self.search_semantic_within(query_embedding, entity_type, None, limit, threshold)
+    }
+
+    /// Semantic search restricted to the entity IDs in `candidates`, such as
+    /// the hits of a full-text query, which are then ranked by similarity.
+    /// With `None` every entity is a candidate.
+    pub fn search_semantic_within(
+        &self,
+        query_embedding: &[f32],
+        entity_type: Option<&str>,
+        candidates: Option<&HashSet<String>>,
+        limit: usize,
+        threshold: f32,
+    ) -> Result<Vec<SemanticSearchResult>> {
+        let mut guard = self
+            .vector_index
+            .lock()
+            .map_err(|e| MedullaError::Storage(format!("vector index lock poisoned: {}", e)))?;
+        let index = self.current_vector_index(&mut guard)?;
+
+        // Metadata is only looked up for hits that can make the cut; entities
+        // missing from the cache are skipped without using up the limit
+        let mut results = Vec::new();
+        for (entity_id, entity_type, score) in
+            index.search(query_embedding, entity_type, candidates, threshold)
+        {
+            if results.len() >= limit {
+                break;
+            }
+            if let Some((seq, title)) = self.get_entity_metadata(entity_id, entity_type)? {
+                results.push(SemanticSearchResult {
+                    entity_id: entity_id.to_string(),
+                    entity_type: entity_type.to_string(),
                     sequence_number: seq,
                     title,
                     score,
-                })
-            })
-            .collect();
+                });
+            }
+        }
 
-        // Sort by score descending
-        results.sort_by(|a, b| {
-            b.score
-                .partial_cmp(&a.score)
-                .unwrap_or(std::cmp::Ordering::Equal)
-        });
+        Ok(results)
+    }
+
+    /// Entities whose embeddings are closest to that of `entity_id`, most
+    /// similar first, leaving out the entity itself and the IDs in
+    /// `exclude` (typically those it already has a relation with). Empty if
+    /// the entity has no embedding.
+    pub fn suggest_related(
+        &self,
+        entity_id: &str,
+        exclude: &HashSet<String>,
+        limit: usize,
+        threshold: f32,
+    ) -> Result<Vec<SemanticSearchResult>> {
+        let Some(embedding) = self.get_embedding(entity_id)? else {
+            return Ok(Vec::new());
+        };
 
-        // Limit results
+        // Over-fetch so excluded hits don't eat into the limit
+        let mut results =
+            self.search_semantic(&embedding, None, limit + exclude.len() + 1, threshold)?;
+        results.retain(|r| r.entity_id != entity_id && !exclude.contains(&r.entity_id));
         results.truncate(limit);
+        Ok(results)
+    }
+
+    /// Pairs of same-type entities whose embeddings have a cosine similarity
+    /// of at least `threshold`, most similar first. Used to find near-duplicate
+    /// entities; pairs involving entities missing from the cache are skipped.
+    pub fn find_duplicate_pairs(
+        &self,
+        entity_type: Option<&str>,
+        threshold: f32,
+        limit: usize,
+    ) -> Result<Vec<DuplicatePair>> {
+        let mut guard = self
+            .vector_index
+            .lock()
+            .map_err(|e| MedullaError::Storage(format!("vector index lock poisoned: {}", e)))?;
+        let index = self.current_vector_index(&mut guard)?;
+
+        let mut results = Vec::new();
+        for (id_a, id_b, entity_type, score) in index.similar_pairs(entity_type, threshold) {
+            if results.len() >= limit {
+                break;
+            }
+            let (Some(a), Some(b)) = (
+                self.get_entity_metadata(id_a, entity_type)?,
+                self.get_entity_metadata(id_b, entity_type)?,
+            ) else {
+                continue;
+            };
+            // Report the older entity first, as the natural one to keep
+            let (first, second) = if a.0 <= b.0 {
+                ((id_a, a), (id_b, b))
+            } else {
+                ((id_b, b), (id_a, a))
+            };
+            results.push(DuplicatePair {
+                entity_type: entity_type.to_string(),
+                first: DuplicateEntity {
+                    entity_id: first.0.to_string(),
+                    sequence_number: first.1 .0,
+                    title: first.1 .1,
+                },
+                second: DuplicateEntity {
+                    entity_id: second.0.to_string(),
+                    sequence_number: second.1 .0,
+                    title: second.1 .1,
+                },
+                score,
+            });
+        }
 
         Ok(results)
     }
 
+    /// Existing entities of `entity_type` that a new entity titled `title`
+    /// would likely duplicate: those with a near-identical title, found
+    /// through full-text search, and, when `embedding` is given, those with
+    /// a near-identical embedding. Most similar first.
+    pub fn find_duplicate_candidates(
+        &self,
+        entity_type: &str,
+        title: &str,
+        embedding: Option<&[f32]>,
+        limit: usize,
+    ) -> Result<Vec<DuplicateCandidate>> {
+        let table = match entity_type {
+            "decision" => "decisions",
+            "task" => "tasks",
+            "note" => "notes",
+            "prompt" => "prompts",
+            "component" => "components",
+            "link" => "links",
+            "question" => "questions",
+            "milestone" => "milestones",
+            _ => return Ok(Vec::new()),
+        };
+
+        let mut candidates: Vec<DuplicateCandidate> = Vec::new();
+
+        // Any entity sharing a word with the title, then compared in full
+        let words: Vec<String> = title
+            .split(|c: char| !c.is_alphanumeric())
+            .filter(|w| !w.is_empty())
+            .map(|w| format!("\"{}\"", w.to_lowercase()))
+            .collect();
+        if !words.is_empty() {
+            let mut stmt = self.conn.prepare(&format!(
+                "SELECT e.id, e.sequence_number, e.title FROM {0}_fts f
+                 JOIN {0} e ON e.id = f.id
+                 WHERE {0}_fts MATCH ?1
+                 ORDER BY f.rank
+                 LIMIT 50",
+                table
+            ))?;
+            let rows = stmt
+                .query_map([words.join(" OR ")], |row| {
+                    Ok((
+                        row.get::<_, String>(0)?,
+                        row.get::<_, u32>(1)?,
+                        row.get::<_, String>(2)?,
+                    ))
+                })?
+                .collect::<std::result::Result<Vec<_>, _>>()?;
+            for (entity_id, sequence_number, existing) in rows {
+                let score = title_similarity(title, &existing);
+                if score >= DUPLICATE_TITLE_THRESHOLD {
+                    candidates.push(DuplicateCandidate {
+                        entity_id,
+                        entity_type: entity_type.to_string(),
+                        sequence_number,
+                        title: existing,
+                        score,
+                        matched_on: "title".to_string(),
+                    });
+                }
+            }
+        }
+
+        if let Some(embedding) = embedding {
+            for hit in self.search_semantic(
+                embedding,
+                Some(entity_type),
+                limit,
+                DUPLICATE_EMBEDDING_THRESHOLD,
+            )? {
+                match candidates.iter_mut().find(|c| c.entity_id == hit.entity_id) {
+                    Some(existing) if existing.score >= hit.score => {}
+                    Some(existing) => {
+                        existing.score = hit.score;
+                        existing.matched_on = "embedding".to_string();
+                    }
+                    None => candidates.push(DuplicateCandidate {
+                        entity_id: hit.entity_id,
+                        entity_type: hit.entity_type,
+                        sequence_number: hit.sequence_number,
+                        title: hit.title,
+                        score: hit.score,
+                        matched_on: "embedding".to_string(),
+                    }),
+                }
+            }
+        }
+
+        candidates.sort_by(|a, b| {
+            b.score
+                .total_cmp(&a.score)
+                .then(a.sequence_number.cmp(&b.sequence_number))
+        });
+        candidates.truncate(limit);
+        Ok(candidates)
+    }
+
     /// Get basic metadata (sequence_number, title) for an entity.
     fn get_entity_metadata(
         &self,
@@ -1214,6 +2684,8 @@ impl SqliteCache {
             "prompt" => "prompts",
             "component" => "components",
             "link" => "links",
+            "question" => "questions",
+            "milestone" => "milestones",
             _ => return Ok(None),
         };
 
@@ -1227,6 +2699,42 @@ impl SqliteCache {
         Ok(result)
     }
 
+    /// Get a compact index of every entity of a type: sequence number, title
+    /// and status (for types that have one), ordered by sequence number.
+    pub fn get_entity_index(&self, entity_type: &str) -> Result<Vec<IndexEntry>> {
+        let (table, status) = match entity_type {
+            "decision" => ("decisions", "status"),
+            "task" => ("tasks", "status"),
+            "component" => ("components", "status"),
+            "note" => ("notes", "NULL"),
+            "prompt" => ("prompts", "NULL"),
+            "link" => ("links", "NULL"),
+            "question" => ("questions", "status"),
+            "milestone" => ("milestones", "status"),
+            _ => return Ok(Vec::new()),
+        };
+
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT e.sequence_number, e.title, {}, a.alias FROM {} e
+             LEFT JOIN entity_aliases a ON a.entity_id = e.id
+             ORDER BY e.sequence_number",
+            status, table
+        ))?;
+
+        let results = stmt
+            .query_map([], |row| {
+                Ok(IndexEntry {
+                    sequence_number: row.get(0)?,
+                    title: row.get(1)?,
+                    status: row.get(2)?,
+                    alias: row.get(3)?,
+                })
+            })?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+
+        Ok(results)
+    }
+
     /// Get filter-relevant metadata for an entity.
     /// Returns status (if applicable), tags, and created_at for filter matching.
     pub fn get_filter_metadata(
@@ -1237,40 +2745,63 @@ impl SqliteCache {
         // Build query based on entity type (different tables have different columns)
         let (query, has_status) = match entity_type {
             "decision" => (
-                "SELECT status, tags, created_at FROM decisions WHERE id = ?1",
+                "SELECT status, tags, created_at, created_by, NULL as assignee FROM decisions WHERE id = ?1",
                 true,
             ),
             "task" => (
-                "SELECT status, tags, created_at FROM tasks WHERE id = ?1",
+                "SELECT status, tags, created_at, created_by, assignee FROM tasks WHERE id = ?1",
                 true,
             ),
             "component" => (
-                "SELECT status, tags, created_at FROM components WHERE id = ?1",
+                "SELECT status, tags, created_at, created_by, NULL as assignee FROM components WHERE id = ?1",
                 true,
             ),
             "note" => (
-                "SELECT NULL as status, tags, created_at FROM notes WHERE id = ?1",
+                "SELECT NULL as status, tags, created_at, created_by, NULL as assignee FROM notes WHERE id = ?1",
                 false,
             ),
             "prompt" => (
-                "SELECT NULL as status, tags, created_at FROM prompts WHERE id = ?1",
+                "SELECT NULL as status, tags, created_at, created_by, NULL as assignee FROM prompts WHERE id = ?1",
                 false,
             ),
             "link" => (
-                "SELECT NULL as status, tags, created_at FROM links WHERE id = ?1",
+                "SELECT NULL as status, tags, created_at, created_by, NULL as assignee FROM links WHERE id = ?1",
                 false,
             ),
+            "question" => (
+                "SELECT status, tags, created_at, created_by, NULL as assignee FROM questions WHERE id = ?1",
+                true,
+            ),
+            "milestone" => (
+                "SELECT status, tags, created_at, created_by, NULL as assignee FROM milestones WHERE id = ?1",
+                true,
+            ),
             _ => return Ok(None),
         };
 
-        let result: Option<(Option<String>, Option<String>, String)> = self
+        #[allow(clippy::type_complexity)]
+        let result: Option<(
+            Option<String>,
+            Option<String>,
+            String,
+            Option<String>,
+            Option<String>,
+        )> = self
             .conn
             .query_row(query, [entity_id], |row| {
-                Ok((row.get(0)?, row.get(1)?, row.get(2)?))
+                Ok((
+                    row.get(0)?,
+                    row.get(1)?,
+                    row.get(2)?,
+                    row.get(3)?,
+                    row.get(4)?,
+                ))
             })
             .optional()?;
 
-        Ok(result.map(|(status_opt, tags_str, created_at_str)| {
+        Ok(result.map(|row| {
+            let (status_opt, tags_str, created_at_str, created_by, assignee) = row;
+
             // Parse tags from comma-separated string
             let tags = tags_str
                 .map(|s| {
@@ -1290,22 +2821,25 @@ impl SqliteCache {
                 status: if has_status { status_opt } else { None },
                 tags,
                 created_at,
+                created_by,
+                assignee,
             }
         }))
     }
 
-    /// Get relations from a source entity
+    /// Get relations from a source entity.
+    /// Includes materialized inverses when inverse relations are enabled.
     pub fn get_relations_from(&self, source_id: &str) -> Result<Vec<CachedRelation>> {
         let mut stmt = self.conn.prepare(
             "SELECT composite_key, source_id, source_type, target_id, target_type,
-                    relation_type, created_at, created_by
+                    relation_type, created_at, created_by, resolved_at, is_inverse
              FROM relations WHERE source_id = ?1",
         )?;
 
         let results = stmt
             .query_map([source_id], |row| {
                 Ok(CachedRelation {
-                    composite_key: row.get(0)?,
+                    composite_key: canonical_relation_key(row.get(0)?),
                     source_id: row.get(1)?,
                     source_type: row.get(2)?,
                     target_id: row.get(3)?,
@@ -1313,6 +2847,8 @@ impl SqliteCache {
                     relation_type: row.get(5)?,
                     created_at: row.get(6)?,
                     created_by: row.get(7)?,
+                    resolved_at: row.get(8)?,
+                    is_inverse: row.get(9)?,
                 })
             })?
             .collect::<std::result::Result<Vec<_>, _>>()?;
@@ -1320,18 +2856,19 @@ impl SqliteCache {
         Ok(results)
     }
 
-    /// Get relations to a target entity
+    /// Get relations to a target entity.
+    /// Includes materialized inverses when inverse relations are enabled.
     pub fn get_relations_to(&self, target_id: &str) -> Result<Vec<CachedRelation>> {
         let mut stmt = self.conn.prepare(
             "SELECT composite_key, source_id, source_type, target_id, target_type,
-                    relation_type, created_at, created_by
+                    relation_type, created_at, created_by, resolved_at, is_inverse
              FROM relations WHERE target_id = ?1",
         )?;
 
         let results = stmt
             .query_map([target_id], |row| {
                 Ok(CachedRelation {
-                    composite_key: row.get(0)?,
+                    composite_key: canonical_relation_key(row.get(0)?),
                     source_id: row.get(1)?,
                     source_type: row.get(2)?,
                     target_id: row.get(3)?,
@@ -1339,6 +2876,8 @@ impl SqliteCache {
                     relation_type: row.get(5)?,
                     created_at: row.get(6)?,
                     created_by: row.get(7)?,
+                    resolved_at: row.get(8)?,
+                    is_inverse: row.get(9)?,
                 })
             })?
             .collect::<std::result::Result<Vec<_>, _>>()?;
@@ -1389,7 +2928,11 @@ impl SqliteCache {
         prompts: &[Prompt],
         components: &[Component],
         links: &[Link],
+        questions: &[Question],
+        milestones: &[Milestone],
         relations: &[Relation],
+        aliases: &[(String, uuid::Uuid)],
+        collections: &[(String, u32, uuid::Uuid)],
         loro_version: &str,
     ) -> Result<bool> {
         let stored_version = self.get_loro_version()?;
@@ -1427,10 +2970,26 @@ impl SqliteCache {
             self.index_link(link)?;
         }
 
+        for question in questions {
+            self.index_question(question)?;
+        }
+
+        for milestone in milestones {
+            self.index_milestone(milestone)?;
+        }
+
         for relation in relations {
             self.index_relation(relation)?;
         }
 
+        for (alias, id) in aliases {
+            self.index_alias(alias, &id.to_string())?;
+        }
+
+        for (collection, sequence, id) in collections {
+            self.index_collection(&id.to_string(), collection, *sequence)?;
+        }
+
         self.set_loro_version(loro_version)?;
 
         Ok(true)
@@ -1444,61 +3003,121 @@ impl SqliteCache {
     ///
     /// A task is "ready" if:
     /// - Its status is not "done"
-    /// - It has no incoming "blocks" relations from tasks that are not "done"
+    /// - It has no unresolved incoming "blocks" relations from tasks that are not "done"
     ///
-    /// Results are sorted by:
-    /// 1. Priority (urgent > high > normal > low)
-    /// 2. Due date (earliest first, nulls last)
-    /// 3. Sequence number (oldest first)
+    /// Results are sorted by score (see `TaskScoring`: priority, due-date
+    /// proximity, age and how many tasks each unblocks), then by due date
+    /// (earliest first, nulls last) and sequence number.
     pub fn get_ready_tasks(&self, limit: Option<u32>) -> Result<Vec<ReadyTask>> {
-        let limit = limit.unwrap_or(50).min(100) as i64;
+        self.get_ready_tasks_with(limit, false, None)
+    }
+
+    /// Ready tasks as in `get_ready_tasks`, optionally including archived
+    /// ones, which are left out by default.
+    pub fn get_ready_tasks_with(
+        &self,
+        limit: Option<u32>,
+        include_archived: bool,
+        wip_limits: Option<&WipLimits>,
+    ) -> Result<Vec<ReadyTask>> {
+        let limit = limit.unwrap_or(50).min(100) as usize;
+        let scoring = self.task_scoring()?;
+        let now = Utc::now();
+        let today = now.date_naive();
 
         // Query for tasks that:
         // 1. Are not done
         // 2. Have no blocking relations from non-done tasks
         //
         // The subquery finds all task IDs that ARE blocked by non-done tasks,
-        // and we exclude those from our results.
+        // and we exclude those from our results. Ordering needs the score,
+        // which is computed here rather than in SQL.
         let mut stmt = self.conn.prepare(
-            "SELECT t.id, t.sequence_number, t.title, t.status, t.priority, t.due_date, t.assignee
+            "SELECT t.id, t.sequence_number, t.title, t.status, t.priority, t.due_date, t.assignee,
+                    t.created_at,
+                    (SELECT COUNT(DISTINCT r.target_id)
+                     FROM relations r
+                     JOIN tasks blocked ON blocked.id = r.target_id
+                     WHERE r.source_id = t.id
+                       AND r.relation_type = 'blocks'
+                       AND r.is_inverse = 0
+                       AND r.resolved_at IS NULL
+                       AND blocked.status != 'done')
              FROM tasks t
              WHERE t.status != 'done'
+               AND (?1 OR t.id NOT IN (SELECT entity_id FROM archived_entities))
                AND t.id NOT IN (
                    -- Tasks that have at least one non-done blocker
                    SELECT r.target_id
                    FROM relations r
                    JOIN tasks blocker ON blocker.id = r.source_id
                    WHERE r.relation_type = 'blocks'
+                     AND r.is_inverse = 0
+                     AND r.resolved_at IS NULL
                      AND blocker.status != 'done'
-               )
-             ORDER BY
-               CASE t.priority
-                 WHEN 'urgent' THEN 1
-                 WHEN 'high' THEN 2
-                 WHEN 'normal' THEN 3
-                 WHEN 'low' THEN 4
-                 ELSE 5
-               END,
-               CASE WHEN t.due_date IS NULL THEN 1 ELSE 0 END,
-               t.due_date,
-               t.sequence_number
-             LIMIT ?1",
+               )",
         )?;
 
-        let results = stmt
-            .query_map(params![limit], |row: &rusqlite::Row| {
+        let mut results = stmt
+            .query_map(params![include_archived], |row: &rusqlite::Row| {
+                let priority: String = row.get(4)?;
+                let due_date: Option<String> = row.get(5)?;
+                let created_at: String = row.get(7)?;
+                let unblocks: u32 = row.get(8)?;
+                let age_days = chrono::DateTime::parse_from_rfc3339(&created_at)
+                    .map_or(0, |created| (now - created.with_timezone(&Utc)).num_days());
+                let due = due_date
+                    .as_deref()
+                    .and_then(|d| NaiveDate::parse_from_str(d, "%Y-%m-%d").ok());
                 Ok(ReadyTask {
                     id: row.get(0)?,
                     sequence_number: row.get(1)?,
                     title: row.get(2)?,
                     status: row.get(3)?,
-                    priority: row.get(4)?,
-                    due_date: row.get(5)?,
+                    score: scoring.score(&priority, due, age_days, unblocks, today),
+                    priority,
+                    due_date,
                     assignee: row.get(6)?,
+                    unblocks,
                 })
             })?
             .collect::<std::result::Result<Vec<_>, _>>()?;
 
+        // Highest score first; ties go to the earliest due date, then the
+        // oldest task
+        results.sort_by(|a, b| {
+            b.score
+                .total
+                .partial_cmp(&a.score.total)
+                .unwrap_or(std::cmp::Ordering::Equal)
+                .then_with(|| match (&a.due_date, &b.due_date) {
+                    (Some(x), Some(y)) => x.cmp(y),
+                    (Some(_), None) => std::cmp::Ordering::Less,
+                    (None, Some(_)) => std::cmp::Ordering::Greater,
+                    (None, None) => std::cmp::Ordering::Equal,
+                })
+                .then_with(|| a.sequence_number.cmp(&b.sequence_number))
+        });
+
+        // Leave out tasks that would push their assignee past the WIP
+        // limit; tasks already in progress stay listed
+        if let Some(limits) = wip_limits.filter(|l| !l.is_empty()) {
+            let in_progress = self.in_progress_by_assignee()?;
+            results.retain(|task| {
+                let Some(assignee) = task.assignee.as_deref().map(str::trim) else {
+                    return true;
+                };
+                let held = in_progress
+                    .get(&assignee.to_lowercase())
+                    .map_or(0, |(_, tasks)| tasks.len());
+                task.status == "in_progress"
+                    || limits
+                        .limit_for(assignee)
+                        .map_or(true, |limit| held < limit as usize)
+            });
+        }
+        results.truncate(limit);
+
         Ok(results)
     }
 
@@ -1506,7 +3125,7 @@ impl SqliteCache {
     ///
     /// A task is "blocked" if:
     /// - Its status is not "done"
-    /// - It has at least one incoming "blocks" relation from a task that is not "done"
+    /// - It has at least one unresolved incoming "blocks" relation from a task that is not "done"
     ///
     /// Each blocked task includes a list of the tasks that are blocking it.
     pub fn get_blocked_tasks(&self, limit: Option<u32>) -> Result<Vec<BlockedTask>> {
@@ -1523,6 +3142,8 @@ impl SqliteCache {
                    FROM relations r
                    JOIN tasks blocker ON blocker.id = r.source_id
                    WHERE r.relation_type = 'blocks'
+                     AND r.is_inverse = 0
+                     AND r.resolved_at IS NULL
                      AND blocker.status != 'done'
                )
              ORDER BY
@@ -1566,7 +3187,9 @@ impl SqliteCache {
              FROM relations r
              JOIN tasks blocker ON blocker.id = r.source_id
              WHERE r.relation_type = 'blocks'
+               AND r.is_inverse = 0
                AND r.target_id = ?1
+               AND r.resolved_at IS NULL
                AND blocker.status != 'done'
              ORDER BY blocker.sequence_number",
         )?;
@@ -1609,32 +3232,283 @@ impl SqliteCache {
              FROM relations r
              JOIN tasks blocker ON blocker.id = r.source_id
              WHERE r.relation_type = 'blocks'
+               AND r.is_inverse = 0
                AND r.target_id = ?1
+               AND r.resolved_at IS NULL
                AND blocker.status != 'done'
              ORDER BY blocker.sequence_number",
         )?;
 
         let results = stmt
-            .query_map(params![task_id], |row: &rusqlite::Row| {
-                Ok(TaskBlocker {
-                    id: row.get(0)?,
-                    sequence_number: row.get(1)?,
-                    title: row.get(2)?,
-                    status: row.get(3)?,
-                })
-            })?
+            .query_map(params![task_id], |row: &rusqlite::Row| {
+                Ok(TaskBlocker {
+                    id: row.get(0)?,
+                    sequence_number: row.get(1)?,
+                    title: row.get(2)?,
+                    status: row.get(3)?,
+                })
+            })?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+
+        Ok(results)
+    }
+
+    /// Get the single highest-scoring ready task.
+    ///
+    /// Convenience method that returns the first task from `get_ready_tasks(limit=1)`.
+    pub fn get_next_task(&self) -> Result<Option<ReadyTask>> {
+        self.get_next_task_with(None)
+    }
+
+    /// The highest-scoring ready task, skipping tasks whose assignee is at
+    /// their WIP limit when limits are given.
+    pub fn get_next_task_with(&self, wip_limits: Option<&WipLimits>) -> Result<Option<ReadyTask>> {
+        let tasks = self.get_ready_tasks_with(Some(1), false, wip_limits)?;
+        Ok(tasks.into_iter().next())
+    }
+
+    /// Get tasks that are not done and whose due date is before `today`,
+    /// most overdue first.
+    pub fn get_overdue_tasks(&self, today: NaiveDate, limit: Option<u32>) -> Result<Vec<DueTask>> {
+        self.query_due_tasks("t.due_date < ?1", today, None, limit)
+    }
+
+    /// Get tasks that are not done and are due between `today` and `days`
+    /// days from now (inclusive), soonest first.
+    pub fn get_tasks_due_within(
+        &self,
+        today: NaiveDate,
+        days: u32,
+        limit: Option<u32>,
+    ) -> Result<Vec<DueTask>> {
+        let until = today
+            .checked_add_days(Days::new(u64::from(days)))
+            .unwrap_or(NaiveDate::MAX);
+        self.query_due_tasks(
+            "t.due_date >= ?1 AND t.due_date <= ?2",
+            today,
+            Some(until),
+            limit,
+        )
+    }
+
+    /// Open (not done, not archived) tasks grouped by assignee, for
+    /// workload views. Groups are ordered by assignee name with unassigned
+    /// tasks last; tasks within a group by priority, due date, then
+    /// sequence number. With `assignee`, only that person's group is
+    /// returned (matched case-insensitively), which may be empty.
+    pub fn get_assignee_workload(&self, assignee: Option<&str>) -> Result<Vec<AssigneeWorkload>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT t.id, t.sequence_number, t.title, t.status, t.priority, t.due_date,
+                    NULLIF(TRIM(t.assignee), ''),
+                    t.id IN (
+                        SELECT r.target_id
+                        FROM relations r
+                        JOIN tasks blocker ON blocker.id = r.source_id
+                        WHERE r.relation_type = 'blocks'
+                          AND r.is_inverse = 0
+                          AND r.resolved_at IS NULL
+                          AND blocker.status != 'done'
+                    )
+             FROM tasks t
+             WHERE t.status != 'done'
+               AND t.id NOT IN (SELECT entity_id FROM archived_entities)
+               AND (?1 IS NULL OR TRIM(t.assignee) = ?1 COLLATE NOCASE)
+             ORDER BY
+               CASE WHEN NULLIF(TRIM(t.assignee), '') IS NULL THEN 1 ELSE 0 END,
+               TRIM(t.assignee) COLLATE NOCASE,
+               CASE t.priority
+                 WHEN 'urgent' THEN 1
+                 WHEN 'high' THEN 2
+                 WHEN 'normal' THEN 3
+                 WHEN 'low' THEN 4
+                 ELSE 5
+               END,
+               CASE WHEN t.due_date IS NULL THEN 1 ELSE 0 END,
+               t.due_date,
+               t.sequence_number",
+        )?;
+
+        let rows = stmt
+            .query_map(params![assignee.map(str::trim)], |row: &rusqlite::Row| {
+                Ok((
+                    row.get::<_, Option<String>>(6)?,
+                    AssignedTask {
+                        id: row.get(0)?,
+                        sequence_number: row.get(1)?,
+                        title: row.get(2)?,
+                        status: row.get(3)?,
+                        priority: row.get(4)?,
+                        due_date: row.get(5)?,
+                        blocked: row.get(7)?,
+                    },
+                ))
+            })?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+
+        let mut groups: Vec<AssigneeWorkload> = Vec::new();
+        for (assignee, task) in rows {
+            let same_group = groups
+                .last()
+                .is_some_and(|g| match (&g.assignee, &assignee) {
+                    (Some(a), Some(b)) => a.eq_ignore_ascii_case(b),
+                    (None, None) => true,
+                    _ => false,
+                });
+            if !same_group {
+                groups.push(AssigneeWorkload {
+                    assignee,
+                    open: 0,
+                    blocked: 0,
+                    by_priority: BTreeMap::new(),
+                    tasks: Vec::new(),
+                });
+            }
+            let group = groups.last_mut().expect("group just pushed");
+            group.open += 1;
+            if task.blocked {
+                group.blocked += 1;
+            }
+            *group.by_priority.entry(task.priority.clone()).or_insert(0) += 1;
+            group.tasks.push(task);
+        }
+
+        Ok(groups)
+    }
+
+    /// Unarchived in-progress tasks by lowercase assignee, with the
+    /// assignee as first written, in sequence order
+    fn in_progress_by_assignee(&self) -> Result<BTreeMap<String, (String, Vec<AssignedTask>)>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT t.id, t.sequence_number, t.title, t.status, t.priority, t.due_date,
+                    TRIM(t.assignee),
+                    t.id IN (
+                        SELECT r.target_id
+                        FROM relations r
+                        JOIN tasks blocker ON blocker.id = r.source_id
+                        WHERE r.relation_type = 'blocks'
+                          AND r.is_inverse = 0
+                          AND r.resolved_at IS NULL
+                          AND blocker.status != 'done'
+                    )
+             FROM tasks t
+             WHERE t.status = 'in_progress'
+               AND NULLIF(TRIM(t.assignee), '') IS NOT NULL
+               AND t.id NOT IN (SELECT entity_id FROM archived_entities)
+             ORDER BY t.sequence_number",
+        )?;
+
+        let rows = stmt
+            .query_map([], |row: &rusqlite::Row| {
+                Ok((
+                    row.get::<_, String>(6)?,
+                    AssignedTask {
+                        id: row.get(0)?,
+                        sequence_number: row.get(1)?,
+                        title: row.get(2)?,
+                        status: row.get(3)?,
+                        priority: row.get(4)?,
+                        due_date: row.get(5)?,
+                        blocked: row.get(7)?,
+                    },
+                ))
+            })?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+
+        let mut by_assignee: BTreeMap<String, (String, Vec<AssignedTask>)> = BTreeMap::new();
+        for (assignee, task) in rows {
+            by_assignee
+                .entry(assignee.to_lowercase())
+                .or_insert_with(|| (assignee, Vec::new()))
+                .1
+                .push(task);
+        }
+        Ok(by_assignee)
+    }
+
+    /// In-progress work of every assignee holding tasks or given a limit,
+    /// those over their WIP limit first, then by name.
+    pub fn get_wip_report(&self, limits: &WipLimits) -> Result<Vec<AssigneeWip>> {
+        let mut by_assignee = self.in_progress_by_assignee()?;
+        for name in limits.assignees.keys() {
+            let name = name.trim();
+            by_assignee
+                .entry(name.to_lowercase())
+                .or_insert_with(|| (name.to_string(), Vec::new()));
+        }
+
+        let mut report: Vec<AssigneeWip> = by_assignee
+            .into_values()
+            .map(|(assignee, tasks)| {
+                let limit = limits.limit_for(&assignee);
+                AssigneeWip {
+                    in_progress: tasks.len(),
+                    over_limit: limit.is_some_and(|l| tasks.len() > l as usize),
+                    limit,
+                    assignee,
+                    tasks,
+                }
+            })
+            .collect();
+        report.sort_by_key(|w| !w.over_limit);
+        Ok(report)
+    }
+
+    fn query_due_tasks(
+        &self,
+        condition: &str,
+        today: NaiveDate,
+        until: Option<NaiveDate>,
+        limit: Option<u32>,
+    ) -> Result<Vec<DueTask>> {
+        let limit = limit.unwrap_or(50).min(100) as i64;
+
+        // Dates are stored as YYYY-MM-DD, so they compare correctly as text
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT t.id, t.sequence_number, t.title, t.status, t.priority, t.due_date, t.assignee
+             FROM tasks t
+             WHERE t.status != 'done'
+               AND t.due_date IS NOT NULL
+               AND {}
+             ORDER BY
+               t.due_date,
+               CASE t.priority
+                 WHEN 'urgent' THEN 1
+                 WHEN 'high' THEN 2
+                 WHEN 'normal' THEN 3
+                 WHEN 'low' THEN 4
+                 ELSE 5
+               END,
+               t.sequence_number
+             LIMIT ?3",
+            condition
+        ))?;
+
+        let until = until.map(|d| d.to_string());
+        let results = stmt
+            .query_map(
+                params![today.to_string(), until, limit],
+                |row: &rusqlite::Row| {
+                    let due_date: String = row.get(5)?;
+                    let days_until_due = NaiveDate::parse_from_str(&due_date, "%Y-%m-%d")
+                        .map(|d| (d - today).num_days())
+                        .unwrap_or(0);
+                    Ok(DueTask {
+                        id: row.get(0)?,
+                        sequence_number: row.get(1)?,
+                        title: row.get(2)?,
+                        status: row.get(3)?,
+                        priority: row.get(4)?,
+                        due_date,
+                        assignee: row.get(6)?,
+                        days_until_due,
+                    })
+                },
+            )?
             .collect::<std::result::Result<Vec<_>, _>>()?;
 
         Ok(results)
     }
-
-    /// Get the single highest-priority ready task.
-    ///
-    /// Convenience method that returns the first task from `get_ready_tasks(limit=1)`.
-    pub fn get_next_task(&self) -> Result<Option<ReadyTask>> {
-        let tasks = self.get_ready_tasks(Some(1))?;
-        Ok(tasks.into_iter().next())
-    }
 }
 
 /// Search result from full-text search for decisions
@@ -1646,6 +3520,8 @@ pub struct DecisionSearchResult {
     pub status: String,
     pub title_highlight: Option<String>,
     pub content_snippet: Option<String>,
+    /// FTS5 bm25 rank; lower is a better match
+    pub rank: f64,
 }
 
 /// Search result from full-text search for tasks
@@ -1659,6 +3535,8 @@ pub struct TaskSearchResult {
     pub assignee: Option<String>,
     pub title_highlight: Option<String>,
     pub content_snippet: Option<String>,
+    /// FTS5 bm25 rank; lower is a better match
+    pub rank: f64,
 }
 
 /// Search result from full-text search for notes
@@ -1670,6 +3548,8 @@ pub struct NoteSearchResult {
     pub note_type: Option<String>,
     pub title_highlight: Option<String>,
     pub content_snippet: Option<String>,
+    /// FTS5 bm25 rank; lower is a better match
+    pub rank: f64,
 }
 
 /// Search result from full-text search for prompts
@@ -1681,6 +3561,8 @@ pub struct PromptSearchResult {
     pub variables: Vec<String>,
     pub title_highlight: Option<String>,
     pub content_snippet: Option<String>,
+    /// FTS5 bm25 rank; lower is a better match
+    pub rank: f64,
 }
 
 /// Search result from full-text search for components
@@ -1694,6 +3576,8 @@ pub struct ComponentSearchResult {
     pub owner: Option<String>,
     pub title_highlight: Option<String>,
     pub content_snippet: Option<String>,
+    /// FTS5 bm25 rank; lower is a better match
+    pub rank: f64,
 }
 
 /// Search result from full-text search for links
@@ -1706,6 +3590,36 @@ pub struct LinkSearchResult {
     pub link_type: Option<String>,
     pub title_highlight: Option<String>,
     pub content_snippet: Option<String>,
+    /// FTS5 bm25 rank; lower is a better match
+    pub rank: f64,
+}
+
+/// Search result from full-text search for questions
+#[derive(Debug, Clone)]
+pub struct QuestionSearchResult {
+    pub id: String,
+    pub sequence_number: u32,
+    pub title: String,
+    pub status: String,
+    pub answered_by: Option<String>,
+    pub title_highlight: Option<String>,
+    pub content_snippet: Option<String>,
+    /// FTS5 bm25 rank; lower is a better match
+    pub rank: f64,
+}
+
+/// Search result from full-text search for milestones
+#[derive(Debug, Clone)]
+pub struct MilestoneSearchResult {
+    pub id: String,
+    pub sequence_number: u32,
+    pub title: String,
+    pub status: String,
+    pub target_date: Option<String>,
+    pub title_highlight: Option<String>,
+    pub content_snippet: Option<String>,
+    /// FTS5 bm25 rank; lower is a better match
+    pub rank: f64,
 }
 
 /// Combined search result for all entity types
@@ -1717,10 +3631,42 @@ pub enum SearchResult {
     Prompt(PromptSearchResult),
     Component(ComponentSearchResult),
     Link(LinkSearchResult),
+    Question(QuestionSearchResult),
+    Milestone(MilestoneSearchResult),
+}
+
+impl SearchResult {
+    /// ID of the matched entity
+    pub fn id(&self) -> &str {
+        match self {
+            SearchResult::Decision(d) => &d.id,
+            SearchResult::Task(t) => &t.id,
+            SearchResult::Note(n) => &n.id,
+            SearchResult::Prompt(p) => &p.id,
+            SearchResult::Component(c) => &c.id,
+            SearchResult::Link(l) => &l.id,
+            SearchResult::Question(q) => &q.id,
+            SearchResult::Milestone(m) => &m.id,
+        }
+    }
+
+    /// FTS5 bm25 rank of the match; lower is more relevant
+    pub fn rank(&self) -> f64 {
+        match self {
+            SearchResult::Decision(d) => d.rank,
+            SearchResult::Task(t) => t.rank,
+            SearchResult::Note(n) => n.rank,
+            SearchResult::Prompt(p) => p.rank,
+            SearchResult::Component(c) => c.rank,
+            SearchResult::Link(l) => l.rank,
+            SearchResult::Question(q) => q.rank,
+            SearchResult::Milestone(m) => m.rank,
+        }
+    }
 }
 
 /// Result from semantic similarity search
-#[derive(Debug, Clone, serde::Serialize)]
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize, schemars::JsonSchema)]
 pub struct SemanticSearchResult {
     pub entity_id: String,
     pub entity_type: String,
@@ -1729,17 +3675,136 @@ pub struct SemanticSearchResult {
     pub score: f32,
 }
 
+/// One side of a [`DuplicatePair`]
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct DuplicateEntity {
+    pub entity_id: String,
+    pub sequence_number: u32,
+    pub title: String,
+}
+
+/// An existing entity that a new one would likely duplicate
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize, schemars::JsonSchema)]
+pub struct DuplicateCandidate {
+    pub entity_id: String,
+    pub entity_type: String,
+    pub sequence_number: u32,
+    pub title: String,
+    /// Title or embedding similarity, from 0 to 1
+    pub score: f32,
+    /// What was compared: "title" or "embedding"
+    pub matched_on: String,
+}
+
+/// Two entities of the same type with near-identical embeddings
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct DuplicatePair {
+    pub entity_type: String,
+    /// The entity with the lower sequence number
+    pub first: DuplicateEntity,
+    pub second: DuplicateEntity,
+    pub score: f32,
+}
+
 /// Metadata for filter matching
 #[derive(Debug, Clone)]
 pub struct FilterMetadata {
     pub status: Option<String>,
     pub tags: Vec<String>,
     pub created_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub created_by: Option<String>,
+    pub assignee: Option<String>,
+}
+
+/// Extra WHERE conditions applying a search's structured filters to the
+/// entity table aliased `alias`, with the values they bind from `?{first}`
+/// on. Filters on a field the entity type lacks match nothing.
+fn filter_conditions(
+    alias: &str,
+    filter: &SearchFilter,
+    has_status: bool,
+    has_assignee: bool,
+    first: usize,
+) -> (String, Vec<Value>) {
+    let mut conditions = String::new();
+    let mut values = Vec::new();
+    let mut bind = |value: String| {
+        values.push(Value::Text(value));
+        format!("?{}", first + values.len() - 1)
+    };
+
+    match (&filter.status, has_status) {
+        (Some(status), true) => {
+            let param = bind(status.clone());
+            conditions.push_str(&format!(" AND {}.status = {}", alias, param));
+        }
+        (Some(_), false) => conditions.push_str(" AND 0"),
+        (None, _) => {}
+    }
+    for tag in &filter.tags {
+        let param = bind(tag.clone());
+        conditions.push_str(&format!(
+            " AND EXISTS (SELECT 1 FROM entity_tags et
+                          WHERE et.entity_id = {}.id AND et.tag = {} COLLATE NOCASE)",
+            alias, param
+        ));
+    }
+    if let Some(after) = filter.created_after {
+        let param = bind(after.to_rfc3339());
+        conditions.push_str(&format!(
+            " AND julianday({}.created_at) >= julianday({})",
+            alias, param
+        ));
+    }
+    if let Some(before) = filter.created_before {
+        let param = bind(before.to_rfc3339());
+        conditions.push_str(&format!(
+            " AND julianday({}.created_at) <= julianday({})",
+            alias, param
+        ));
+    }
+    if let Some(ref created_by) = filter.created_by {
+        let param = bind(created_by.clone());
+        conditions.push_str(&format!(
+            " AND {}.created_by = {} COLLATE NOCASE",
+            alias, param
+        ));
+    }
+    match (&filter.assignee, has_assignee) {
+        (Some(assignee), true) => {
+            let param = bind(assignee.clone());
+            conditions.push_str(&format!(
+                " AND {}.assignee = {} COLLATE NOCASE",
+                alias, param
+            ));
+        }
+        (Some(_), false) => conditions.push_str(" AND 0"),
+        (None, _) => {}
+    }
+
+    (conditions, values)
+}
+
+/// Parameters for a full-text search: the match query, the limit, then
+/// the values of its filter conditions.
+fn search_params(query: &str, limit: i64, values: Vec<Value>) -> ParamsFromIter<Vec<Value>> {
+    let mut params = vec![Value::Text(query.to_string()), Value::Integer(limit)];
+    params.extend(values);
+    params_from_iter(params)
+}
+
+/// Strip the inverse suffix so both rows of a relation report the same key
+fn canonical_relation_key(key: String) -> String {
+    match key.strip_suffix(INVERSE_KEY_SUFFIX) {
+        Some(canonical) => canonical.to_string(),
+        None => key,
+    }
 }
 
 /// Cached relation for fast queries
 #[derive(Debug, Clone)]
 pub struct CachedRelation {
+    /// Key of the canonical stored relation (shared by its inverse row)
     pub composite_key: String,
     pub source_id: String,
     pub source_type: String,
@@ -1748,6 +3813,73 @@ pub struct CachedRelation {
     pub relation_type: String,
     pub created_at: String,
     pub created_by: Option<String>,
+    pub resolved_at: Option<String>,
+    /// Derived row with source and target swapped
+    pub is_inverse: bool,
+}
+
+/// `(entity_id, entity_type, sequence_number)` of an entity listed by
+/// [`SqliteCache::list_entity_page`]
+pub type EntityPageRow = (String, String, u32);
+
+/// Filters, order and position of a [`SqliteCache::list_entity_page`] query
+#[derive(Debug, Clone, Default)]
+pub struct EntityPageQuery<'a> {
+    pub entity_type: Option<&'a str>,
+    pub status: Option<&'a str>,
+    pub tag: Option<&'a str>,
+    /// Only entities created at or after this time
+    pub created_after: Option<chrono::DateTime<chrono::Utc>>,
+    /// Only entities last updated at or after this time
+    pub updated_after: Option<chrono::DateTime<chrono::Utc>>,
+    /// Only entities in this collection (lowercase)
+    pub collection: Option<&'a str>,
+    pub sort: EntitySort,
+    pub descending: bool,
+    /// Only entities with a higher sequence number (a cursor position)
+    pub after_sequence: Option<u32>,
+    pub offset: usize,
+    pub limit: usize,
+}
+
+/// Field an entity listing is ordered by; ties go to the lower sequence
+/// number
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum EntitySort {
+    #[default]
+    Sequence,
+    Created,
+    Updated,
+    Title,
+    /// Urgent, high, normal, low, then entities without a priority
+    /// (descending)
+    Priority,
+}
+
+impl std::str::FromStr for EntitySort {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "sequence" => Ok(EntitySort::Sequence),
+            "created" => Ok(EntitySort::Created),
+            "updated" => Ok(EntitySort::Updated),
+            "title" => Ok(EntitySort::Title),
+            "priority" => Ok(EntitySort::Priority),
+            _ => Err(format!("Invalid sort field: {}", s)),
+        }
+    }
+}
+
+/// A minimal index entry for an entity
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct IndexEntry {
+    pub sequence_number: u32,
+    pub title: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub status: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub alias: Option<String>,
 }
 
 /// A task that is ready to work on (no unresolved blockers)
@@ -1760,6 +3892,61 @@ pub struct ReadyTask {
     pub priority: String,
     pub due_date: Option<String>,
     pub assignee: Option<String>,
+    /// Number of open tasks this task blocks
+    pub unblocks: u32,
+    /// Score the ready list is ordered by (see `TaskScoring`)
+    pub score: TaskScore,
+}
+
+/// A task that is not done and has a due date, for deadline reports
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct DueTask {
+    pub id: String,
+    pub sequence_number: u32,
+    pub title: String,
+    pub status: String,
+    pub priority: String,
+    pub due_date: String,
+    pub assignee: Option<String>,
+    /// Days from today until the due date (negative when overdue)
+    pub days_until_due: i64,
+}
+
+/// An open task in an assignee's queue
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct AssignedTask {
+    pub id: String,
+    pub sequence_number: u32,
+    pub title: String,
+    pub status: String,
+    pub priority: String,
+    pub due_date: Option<String>,
+    /// Whether an unfinished task blocks this one
+    pub blocked: bool,
+}
+
+/// One assignee's in-progress tasks against their WIP limit
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct AssigneeWip {
+    pub assignee: String,
+    pub in_progress: usize,
+    /// None when no limit applies to the assignee
+    pub limit: Option<u32>,
+    /// More tasks in progress than the limit allows
+    pub over_limit: bool,
+    pub tasks: Vec<AssignedTask>,
+}
+
+/// One assignee's open tasks, with counts
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct AssigneeWorkload {
+    /// None for the unassigned bucket
+    pub assignee: Option<String>,
+    pub open: usize,
+    pub blocked: usize,
+    /// Open task count per priority
+    pub by_priority: BTreeMap<String, usize>,
+    pub tasks: Vec<AssignedTask>,
 }
 
 /// A blocked task with information about what blocks it
@@ -1813,6 +4000,10 @@ pub struct CacheStats {
     pub components: usize,
     /// Count of links
     pub links: usize,
+    /// Count of questions
+    pub questions: usize,
+    /// Count of milestones
+    pub milestones: usize,
     /// Count of relations
     pub relations: usize,
 }
@@ -1821,6 +4012,52 @@ pub struct CacheStats {
 // Embedding Helper Functions
 // =========================================================================
 
+/// Title similarity at or above which a new entity is reported as a
+/// likely duplicate of an existing one.
+pub const DUPLICATE_TITLE_THRESHOLD: f32 = 0.8;
+
+/// Embedding similarity at or above which a new entity is reported as a
+/// likely duplicate of an existing one.
+pub const DUPLICATE_EMBEDDING_THRESHOLD: f32 = 0.92;
+
+/// Similarity of two titles from 0 to 1: the Dice coefficient of their
+/// character bigrams, ignoring case, punctuation and spacing. Tolerates
+/// small wording changes ("Use Postgres" / "Use PostgreSQL") that exact
+/// comparison misses. Titles with different numbers in them ("Sprint 12
+/// review" / "Sprint 13 review") score 0.
+pub fn title_similarity(a: &str, b: &str) -> f32 {
+    fn numbers(text: &str) -> Vec<&str> {
+        text.split(|c: char| !c.is_ascii_digit())
+            .filter(|n| !n.is_empty())
+            .collect()
+    }
+    fn bigrams(text: &str) -> Vec<(char, char)> {
+        let chars: Vec<char> = text
+            .chars()
+            .filter(|c| c.is_alphanumeric())
+            .flat_map(char::to_lowercase)
+            .collect();
+        chars.windows(2).map(|w| (w[0], w[1])).collect()
+    }
+
+    if numbers(a) != numbers(b) {
+        return 0.0;
+    }
+    let (a, mut b) = (bigrams(a), bigrams(b));
+    if a.is_empty() || b.is_empty() {
+        return if a == b { 1.0 } else { 0.0 };
+    }
+    let total = a.len() + b.len();
+    let mut shared = 0;
+    for pair in &a {
+        if let Some(i) = b.iter().position(|p| p == pair) {
+            b.swap_remove(i);
+            shared += 1;
+        }
+    }
+    (2 * shared) as f32 / total as f32
+}
+
 /// Compute cosine similarity between two vectors.
 /// Returns a value between -1 and 1, where 1 means identical direction.
 pub fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
@@ -1968,6 +4205,88 @@ mod tests {
         assert_eq!(to_results[0].relation_type, "supersedes");
     }
 
+    #[test]
+    fn test_inverse_relations_are_symmetric() {
+        let tmp = TempDir::new().unwrap();
+        let cache = SqliteCache::open(tmp.path()).unwrap();
+        cache.set_inverse_relations(true).unwrap();
+
+        let a = uuid::Uuid::new_v4().to_string();
+        let b = uuid::Uuid::new_v4().to_string();
+        let relation = crate::entity::Relation::new(
+            uuid::Uuid::parse_str(&a).unwrap(),
+            "task".to_string(),
+            uuid::Uuid::parse_str(&b).unwrap(),
+            "decision".to_string(),
+            crate::entity::RelationType::Implements,
+        );
+        cache.index_relation(&relation).unwrap();
+
+        // The canonical row is visible from A and to B
+        let from_a = cache.get_relations_from(&a).unwrap();
+        let to_b = cache.get_relations_to(&b).unwrap();
+        assert_eq!(from_a.len(), 1);
+        assert_eq!(to_b.len(), 1);
+        assert!(!from_a[0].is_inverse);
+        assert_eq!(from_a[0].composite_key, to_b[0].composite_key);
+
+        // The inverse is visible from B and to A, under the same key
+        let from_b = cache.get_relations_from(&b).unwrap();
+        let to_a = cache.get_relations_to(&a).unwrap();
+        assert_eq!(from_b.len(), 1);
+        assert_eq!(to_a.len(), 1);
+        assert!(from_b[0].is_inverse);
+        assert_eq!(from_b[0].target_id, a);
+        assert_eq!(from_b[0].composite_key, relation.composite_key());
+
+        // Only the canonical relation is counted, and removal takes both rows
+        assert_eq!(cache.get_stats().unwrap().relations, 1);
+        cache.remove_relation(&relation.composite_key()).unwrap();
+        assert!(cache.get_relations_from(&b).unwrap().is_empty());
+        assert!(cache.get_relations_to(&b).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_toggle_inverse_relations_updates_existing_rows() {
+        let tmp = TempDir::new().unwrap();
+        let cache = SqliteCache::open(tmp.path()).unwrap();
+
+        let source_id = uuid::Uuid::new_v4();
+        let target_id = uuid::Uuid::new_v4();
+        let relation = crate::entity::Relation::new(
+            source_id,
+            "task".to_string(),
+            target_id,
+            "task".to_string(),
+            crate::entity::RelationType::Blocks,
+        );
+        cache.index_relation(&relation).unwrap();
+        assert!(cache
+            .get_relations_from(&target_id.to_string())
+            .unwrap()
+            .is_empty());
+
+        cache.set_inverse_relations(true).unwrap();
+        assert_eq!(
+            cache
+                .get_relations_from(&target_id.to_string())
+                .unwrap()
+                .len(),
+            1
+        );
+
+        // The setting survives a clear
+        cache.clear().unwrap();
+        assert!(cache.inverse_relations_enabled().unwrap());
+
+        cache.set_inverse_relations(false).unwrap();
+        cache.index_relation(&relation).unwrap();
+        assert!(cache
+            .get_relations_from(&target_id.to_string())
+            .unwrap()
+            .is_empty());
+    }
+
     #[test]
     fn test_sync_from_loro() {
         let tmp = TempDir::new().unwrap();
@@ -2151,6 +4470,116 @@ mod tests {
         assert_eq!(ready[3].title, "Normal no date");
     }
 
+    #[test]
+    fn test_get_overdue_and_due_within() {
+        let tmp = TempDir::new().unwrap();
+        let cache = SqliteCache::open(tmp.path()).unwrap();
+        let today = NaiveDate::from_ymd_opt(2025, 6, 10).unwrap();
+
+        let due = |title: &str, seq: u32, status: TaskStatus, day: Option<u32>| {
+            let mut task = create_task(title, seq, status, TaskPriority::Normal);
+            task.due_date = day.map(|d| NaiveDate::from_ymd_opt(2025, 6, d).unwrap());
+            cache.index_task(&task).unwrap();
+        };
+        due("Long overdue", 1, TaskStatus::Todo, Some(1));
+        due("Yesterday", 2, TaskStatus::InProgress, Some(9));
+        due("Done late", 3, TaskStatus::Done, Some(2));
+        due("Today", 4, TaskStatus::Todo, Some(10));
+        due("Next week", 5, TaskStatus::Todo, Some(17));
+        due("Later", 6, TaskStatus::Todo, Some(30));
+        due("Someday", 7, TaskStatus::Todo, None);
+
+        let overdue = cache.get_overdue_tasks(today, None).unwrap();
+        let titles: Vec<_> = overdue.iter().map(|t| t.title.as_str()).collect();
+        assert_eq!(titles, ["Long overdue", "Yesterday"]);
+        assert_eq!(overdue[0].days_until_due, -9);
+
+        let upcoming = cache.get_tasks_due_within(today, 7, None).unwrap();
+        let titles: Vec<_> = upcoming.iter().map(|t| t.title.as_str()).collect();
+        assert_eq!(titles, ["Today", "Next week"]);
+        assert_eq!(upcoming[1].days_until_due, 7);
+
+        assert_eq!(cache.get_tasks_due_within(today, 0, None).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_get_assignee_workload() {
+        let tmp = TempDir::new().unwrap();
+        let cache = SqliteCache::open(tmp.path()).unwrap();
+
+        let assigned = |title: &str, seq: u32, status, priority, assignee: Option<&str>| {
+            let mut task = create_task(title, seq, status, priority);
+            task.assignee = assignee.map(String::from);
+            cache.index_task(&task).unwrap();
+            task
+        };
+        let blocker = assigned(
+            "Blocker",
+            1,
+            TaskStatus::Todo,
+            TaskPriority::Low,
+            Some("bob"),
+        );
+        let blocked = assigned(
+            "Blocked",
+            2,
+            TaskStatus::Todo,
+            TaskPriority::High,
+            Some("alice"),
+        );
+        assigned(
+            "Review",
+            3,
+            TaskStatus::InProgress,
+            TaskPriority::Urgent,
+            Some("Alice"),
+        );
+        assigned(
+            "Shipped",
+            4,
+            TaskStatus::Done,
+            TaskPriority::High,
+            Some("alice"),
+        );
+        assigned("Triage", 5, TaskStatus::Todo, TaskPriority::Normal, None);
+        assigned(
+            "Blank",
+            6,
+            TaskStatus::Todo,
+            TaskPriority::Normal,
+            Some(" "),
+        );
+        cache
+            .index_relation(&Relation::new(
+                blocker.base.id,
+                "task".to_string(),
+                blocked.base.id,
+                "task".to_string(),
+                RelationType::Blocks,
+            ))
+            .unwrap();
+
+        let workload = cache.get_assignee_workload(None).unwrap();
+        let names: Vec<_> = workload.iter().map(|g| g.assignee.as_deref()).collect();
+        assert_eq!(names, [Some("Alice"), Some("bob"), None]);
+
+        let alice = &workload[0];
+        assert_eq!(alice.open, 2);
+        assert_eq!(alice.blocked, 1);
+        assert_eq!(alice.by_priority.get("urgent"), Some(&1));
+        let titles: Vec<_> = alice.tasks.iter().map(|t| t.title.as_str()).collect();
+        assert_eq!(titles, ["Review", "Blocked"]);
+        assert_eq!(workload[2].open, 2);
+
+        let mine = cache.get_assignee_workload(Some("ALICE")).unwrap();
+        assert_eq!(mine.len(), 1);
+        assert_eq!(mine[0].open, 2);
+        assert!(cache
+            .get_assignee_workload(Some("carol"))
+            .unwrap()
+            .is_empty());
+    }
+
     #[test]
     fn test_get_blocked_tasks() {
         let tmp = TempDir::new().unwrap();
@@ -2269,6 +4698,52 @@ mod tests {
         assert!(next.is_none());
     }
 
+    #[test]
+    fn test_get_ready_tasks_scoring() {
+        let tmp = TempDir::new().unwrap();
+        let cache = SqliteCache::open(tmp.path()).unwrap();
+
+        let gate = create_task("Unblocks two", 1, TaskStatus::Todo, TaskPriority::Normal);
+        let plain = create_task("Plain", 2, TaskStatus::Todo, TaskPriority::Normal);
+        let mut old = create_task("Old", 3, TaskStatus::Todo, TaskPriority::Normal);
+        old.base.created_at = Utc::now() - chrono::Duration::days(60);
+        for task in [&gate, &plain, &old] {
+            cache.index_task(task).unwrap();
+        }
+        for seq in [4, 5] {
+            let blocked = create_task("Blocked", seq, TaskStatus::Todo, TaskPriority::Normal);
+            cache.index_task(&blocked).unwrap();
+            cache
+                .index_relation(&Relation::new(
+                    gate.base.id,
+                    "task".to_string(),
+                    blocked.base.id,
+                    "task".to_string(),
+                    RelationType::Blocks,
+                ))
+                .unwrap();
+        }
+
+        let ready = cache.get_ready_tasks(None).unwrap();
+        let titles: Vec<&str> = ready.iter().map(|t| t.title.as_str()).collect();
+        assert_eq!(titles, vec!["Old", "Unblocks two", "Plain"]);
+        assert_eq!(ready[0].score.age, 0.5);
+        assert_eq!(ready[1].unblocks, 2);
+        assert_eq!(ready[1].score.unblocks, 0.4);
+
+        // Weights come from the project config via the cache
+        cache
+            .set_task_scoring(&TaskScoring {
+                age: 0.0,
+                ..Default::default()
+            })
+            .unwrap();
+        assert_eq!(
+            cache.get_next_task().unwrap().unwrap().title,
+            "Unblocks two"
+        );
+    }
+
     #[test]
     fn test_get_ready_tasks_limit() {
         let tmp = TempDir::new().unwrap();
@@ -2285,9 +4760,114 @@ mod tests {
             cache.index_task(&task).unwrap();
         }
 
-        // Get with limit
-        let ready = cache.get_ready_tasks(Some(3)).unwrap();
-        assert_eq!(ready.len(), 3);
+        // Get with limit
+        let ready = cache.get_ready_tasks(Some(3)).unwrap();
+        assert_eq!(ready.len(), 3);
+    }
+
+    #[test]
+    fn test_wip_limits() {
+        let tmp = TempDir::new().unwrap();
+        let cache = SqliteCache::open(tmp.path()).unwrap();
+
+        let assigned = |title: &str, seq: u32, status, priority, assignee: Option<&str>| {
+            let mut task = create_task(title, seq, status, priority);
+            task.assignee = assignee.map(String::from);
+            cache.index_task(&task).unwrap();
+        };
+        assigned(
+            "Review",
+            1,
+            TaskStatus::InProgress,
+            TaskPriority::Normal,
+            Some("bot-1"),
+        );
+        assigned(
+            "Deploy",
+            2,
+            TaskStatus::Todo,
+            TaskPriority::Urgent,
+            Some("Bot-1"),
+        );
+        assigned(
+            "Docs",
+            3,
+            TaskStatus::Todo,
+            TaskPriority::High,
+            Some("bot-2"),
+        );
+        assigned("Triage", 4, TaskStatus::Todo, TaskPriority::Low, None);
+
+        let limits = WipLimits {
+            default: Some(1),
+            ..Default::default()
+        };
+        let titles = |limits: Option<&WipLimits>| -> Vec<String> {
+            cache
+                .get_ready_tasks_with(None, false, limits)
+                .unwrap()
+                .into_iter()
+                .map(|t| t.title)
+                .collect()
+        };
+        assert_eq!(titles(None), vec!["Deploy", "Docs", "Review", "Triage"]);
+        // bot-1 is at its limit, so Deploy is skipped but Review stays
+        assert_eq!(titles(Some(&limits)), vec!["Docs", "Review", "Triage"]);
+        assert_eq!(
+            cache
+                .get_next_task_with(Some(&limits))
+                .unwrap()
+                .unwrap()
+                .title,
+            "Docs"
+        );
+
+        assigned(
+            "Hotfix",
+            5,
+            TaskStatus::InProgress,
+            TaskPriority::Normal,
+            Some("bot-1"),
+        );
+        let mut limits = limits;
+        limits.assignees.insert("idle-bot".to_string(), 2);
+        let report = cache.get_wip_report(&limits).unwrap();
+        assert_eq!(report.len(), 2);
+        assert_eq!(report[0].assignee, "bot-1");
+        assert_eq!(report[0].in_progress, 2);
+        assert!(report[0].over_limit);
+        assert_eq!(report[1].assignee, "idle-bot");
+        assert_eq!(report[1].limit, Some(2));
+        assert!(!report[1].over_limit);
+    }
+
+    #[test]
+    fn test_get_ready_tasks_skips_archived() {
+        let tmp = TempDir::new().unwrap();
+        let cache = SqliteCache::open(tmp.path()).unwrap();
+
+        let live = create_task("Live", 1, TaskStatus::Todo, TaskPriority::Normal);
+        let mut archived = create_task("Stale", 2, TaskStatus::Todo, TaskPriority::Normal);
+        archived.base.archived = true;
+        cache.index_task(&live).unwrap();
+        cache.index_task(&archived).unwrap();
+
+        let ready = cache.get_ready_tasks(None).unwrap();
+        assert_eq!(ready.len(), 1);
+        assert_eq!(ready[0].title, "Live");
+        assert_eq!(
+            cache.get_ready_tasks_with(None, true, None).unwrap().len(),
+            2
+        );
+        assert!(cache
+            .get_archived_ids()
+            .unwrap()
+            .contains(&archived.base.id.to_string()));
+
+        // Unarchiving (reindexing) or removing clears the flag
+        archived.base.archived = false;
+        cache.index_task(&archived).unwrap();
+        assert!(cache.get_archived_ids().unwrap().is_empty());
     }
 
     // =========================================================================
@@ -2467,6 +5047,302 @@ mod tests {
         assert_eq!(cache.count_embeddings().unwrap(), 0);
     }
 
+    #[test]
+    fn test_health_queries() {
+        let tmp = TempDir::new().unwrap();
+        let cache = SqliteCache::open(tmp.path()).unwrap();
+
+        let note = crate::entity::Note::new("Kept".to_string(), 1);
+        cache.index_note(&note).unwrap();
+        cache
+            .store_embedding(&note.base.id.to_string(), "note", &[1.0], "h")
+            .unwrap();
+        cache.store_embedding("gone", "note", &[1.0], "h").unwrap();
+
+        assert_eq!(
+            cache.get_entity_ids().unwrap(),
+            [note.base.id.to_string()].into_iter().collect()
+        );
+        assert_eq!(cache.count_orphaned_embeddings().unwrap(), 1);
+        assert!(cache.check_fts_integrity().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_list_entity_page() {
+        let tmp = TempDir::new().unwrap();
+        let cache = SqliteCache::open(tmp.path()).unwrap();
+
+        let mut decision = crate::entity::Decision::new("Decision".to_string(), 1);
+        decision.base.tags = vec!["infra".to_string()];
+        cache.index_decision(&decision).unwrap();
+        let mut task = crate::entity::Task::new("Task".to_string(), 2);
+        task.base.tags = vec!["infra".to_string()];
+        cache.index_task(&task).unwrap();
+        cache
+            .index_note(&crate::entity::Note::new("Note".to_string(), 3))
+            .unwrap();
+
+        let query = EntityPageQuery {
+            limit: 2,
+            ..Default::default()
+        };
+        let (rows, total) = cache.list_entity_page(&query).unwrap();
+        assert_eq!(total, 3);
+        let seqs: Vec<u32> = rows.iter().map(|(_, _, seq)| *seq).collect();
+        assert_eq!(seqs, vec![1, 2]);
+        assert_eq!(rows[1].1, "task");
+
+        let (rows, total) = cache
+            .list_entity_page(&EntityPageQuery {
+                after_sequence: Some(2),
+                ..query.clone()
+            })
+            .unwrap();
+        assert_eq!(total, 3);
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0].1, "note");
+
+        let (rows, total) = cache
+            .list_entity_page(&EntityPageQuery {
+                tag: Some("infra"),
+                status: Some("todo"),
+                ..query.clone()
+            })
+            .unwrap();
+        assert_eq!(total, 1);
+        assert_eq!(rows[0].0, task.base.id.to_string());
+
+        cache
+            .index_collection(&task.base.id.to_string(), "backend", 1)
+            .unwrap();
+        let (rows, total) = cache
+            .list_entity_page(&EntityPageQuery {
+                collection: Some("backend"),
+                ..query.clone()
+            })
+            .unwrap();
+        assert_eq!(total, 1);
+        assert_eq!(rows[0].0, task.base.id.to_string());
+
+        let (rows, _) = cache
+            .list_entity_page(&EntityPageQuery {
+                entity_type: Some("note"),
+                offset: 1,
+                ..query
+            })
+            .unwrap();
+        assert!(rows.is_empty());
+    }
+
+    #[test]
+    fn test_list_entity_page_dates_and_sort() {
+        let tmp = TempDir::new().unwrap();
+        let cache = SqliteCache::open(tmp.path()).unwrap();
+
+        let day = |d: u32| {
+            chrono::NaiveDate::from_ymd_opt(2024, 6, d)
+                .unwrap()
+                .and_hms_opt(12, 0, 0)
+                .unwrap()
+                .and_utc()
+        };
+        let mut low = crate::entity::Task::new("b task".to_string(), 1);
+        low.priority = crate::entity::TaskPriority::Low;
+        low.base.created_at = day(1);
+        low.base.updated_at = day(20);
+        cache.index_task(&low).unwrap();
+        let mut urgent = crate::entity::Task::new("C task".to_string(), 2);
+        urgent.priority = crate::entity::TaskPriority::Urgent;
+        urgent.base.created_at = day(10);
+        urgent.base.updated_at = day(10);
+        cache.index_task(&urgent).unwrap();
+        let mut note = crate::entity::Note::new("a note".to_string(), 3);
+        note.base.created_at = day(5);
+        note.base.updated_at = day(5);
+        cache.index_note(&note).unwrap();
+
+        let seqs = |query: EntityPageQuery| -> (Vec<u32>, usize) {
+            let (rows, total) = cache
+                .list_entity_page(&EntityPageQuery { limit: 10, ..query })
+                .unwrap();
+            (rows.iter().map(|(_, _, seq)| *seq).collect(), total)
+        };
+
+        let since = chrono::NaiveDate::from_ymd_opt(2024, 6, 5)
+            .unwrap()
+            .and_hms_opt(0, 0, 0)
+            .unwrap()
+            .and_utc();
+        assert_eq!(
+            seqs(EntityPageQuery {
+                created_after: Some(since),
+                ..Default::default()
+            }),
+            (vec![2, 3], 2)
+        );
+        assert_eq!(
+            seqs(EntityPageQuery {
+                updated_after: Some(day(11)),
+                ..Default::default()
+            }),
+            (vec![1], 1)
+        );
+        assert_eq!(
+            seqs(EntityPageQuery {
+                sort: EntitySort::Priority,
+                descending: true,
+                ..Default::default()
+            })
+            .0,
+            vec![2, 1, 3]
+        );
+        assert_eq!(
+            seqs(EntityPageQuery {
+                sort: EntitySort::Title,
+                ..Default::default()
+            })
+            .0,
+            vec![3, 1, 2]
+        );
+        assert_eq!(
+            seqs(EntityPageQuery {
+                sort: EntitySort::Updated,
+                descending: true,
+                ..Default::default()
+            })
+            .0,
+            vec![1, 2, 3]
+        );
+        assert_eq!(
+            seqs(EntityPageQuery {
+                sort: EntitySort::Created,
+                ..Default::default()
+            })
+            .0,
+            vec![1, 3, 2]
+        );
+    }
+
+    #[test]
+    fn test_entity_tags_index() {
+        let tmp = TempDir::new().unwrap();
+        let cache = SqliteCache::open(tmp.path()).unwrap();
+
+        let mut decision = crate::entity::Decision::new("Tagged decision".to_string(), 1);
+        decision.base.tags = vec!["backend".to_string(), "db".to_string()];
+        cache.index_decision(&decision).unwrap();
+
+        let mut task = crate::entity::Task::new("Tagged task".to_string(), 2);
+        task.base.tags = vec!["backend".to_string()];
+        cache.index_task(&task).unwrap();
+
+        let decision_id = decision.base.id.to_string();
+        let task_id = task.base.id.to_string();
+
+        let mut backend = cache.get_entity_ids_by_tag("backend").unwrap();
+        backend.sort();
+        let mut expected = vec![
+            (decision_id.clone(), "decision".to_string()),
+            (task_id.clone(), "task".to_string()),
+        ];
+        expected.sort();
+        assert_eq!(backend, expected);
+        assert_eq!(cache.get_entity_ids_by_tag("db").unwrap().len(), 1);
+        assert!(cache.entity_has_tag(&decision_id, "DB").unwrap());
+
+        // Re-indexing with changed tags updates the join table
+        task.base.tags = vec!["frontend".to_string()];
+        cache.index_task(&task).unwrap();
+        assert_eq!(
+            cache.get_entity_ids_by_tag("backend").unwrap(),
+            vec![(decision_id.clone(), "decision".to_string())]
+        );
+        assert_eq!(
+            cache.get_entity_ids_by_tag("frontend").unwrap(),
+            vec![(task_id, "task".to_string())]
+        );
+
+        // Removing the entity drops its tags
+        cache.remove_decision(&decision_id).unwrap();
+        assert!(cache.get_entity_ids_by_tag("db").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_get_entity_index() {
+        let tmp = TempDir::new().unwrap();
+        let cache = SqliteCache::open(tmp.path()).unwrap();
+
+        cache
+            .index_decision(&crate::entity::Decision::new("Second".to_string(), 2))
+            .unwrap();
+        cache
+            .index_decision(&crate::entity::Decision::new("First".to_string(), 1))
+            .unwrap();
+        cache
+            .index_note(&crate::entity::Note::new("A note".to_string(), 3))
+            .unwrap();
+
+        let index = cache.get_entity_index("decision").unwrap();
+        assert_eq!(index.len(), 2);
+        assert_eq!(index[0].sequence_number, 1);
+        assert_eq!(index[0].title, "First");
+        assert_eq!(index[0].status.as_deref(), Some("proposed"));
+
+        let index = cache.get_entity_index("note").unwrap();
+        assert_eq!(index.len(), 1);
+        assert!(index[0].status.is_none());
+        assert!(index[0].alias.is_none());
+    }
+
+    #[test]
+    fn test_entity_aliases() {
+        let tmp = TempDir::new().unwrap();
+        let cache = SqliteCache::open(tmp.path()).unwrap();
+        let decision = crate::entity::Decision::new("Use OAuth".to_string(), 1);
+        let id = decision.base.id.to_string();
+        cache.index_decision(&decision).unwrap();
+
+        cache.index_alias("auth", &id).unwrap();
+        cache.index_alias("oauth", &id).unwrap();
+        assert_eq!(cache.resolve_alias("OAuth").unwrap(), Some(id));
+        assert_eq!(cache.resolve_alias("auth").unwrap(), None);
+
+        let index = cache.get_entity_index("decision").unwrap();
+        assert_eq!(index[0].alias.as_deref(), Some("oauth"));
+
+        cache.clear().unwrap();
+        assert_eq!(cache.resolve_alias("oauth").unwrap(), None);
+    }
+
+    #[test]
+    fn test_rebuild_fts_restores_search() {
+        let tmp = TempDir::new().unwrap();
+        let cache = SqliteCache::open(tmp.path()).unwrap();
+
+        let decision = crate::entity::Decision::new("Use PostgreSQL for database".to_string(), 1);
+        cache.index_decision(&decision).unwrap();
+        cache
+            .store_embedding(&decision.base.id.to_string(), "decision", &[1.0, 0.0], "h1")
+            .unwrap();
+
+        // Wipe the FTS index while leaving the content table intact
+        cache
+            .conn
+            .execute(
+                "INSERT INTO decisions_fts(decisions_fts) VALUES('delete-all')",
+                [],
+            )
+            .unwrap();
+        assert!(cache.search_decisions("PostgreSQL", 50).unwrap().is_empty());
+
+        assert_eq!(cache.rebuild_fts().unwrap(), 6);
+
+        let results = cache.search_decisions("PostgreSQL", 50).unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].title, "Use PostgreSQL for database");
+        assert_eq!(cache.count_embeddings().unwrap(), 1);
+    }
+
     #[test]
     fn test_embeddable_text_title_only() {
         let text = embeddable_text("My Title", None, &[]);
@@ -2531,6 +5407,85 @@ mod tests {
         assert_eq!(sim, 0.0);
     }
 
+    #[test]
+    fn test_search_filtered() {
+        let tmp = TempDir::new().unwrap();
+        let cache = SqliteCache::open(tmp.path()).unwrap();
+
+        let mut old = Task::new("Parser rewrite".to_string(), 1);
+        old.base.created_at = "2025-01-10T09:00:00Z".parse().unwrap();
+        old.base.tags = vec!["Backend".to_string()];
+        old.assignee = Some("ada".to_string());
+        let mut new = Task::new("Parser tests".to_string(), 2);
+        new.base.created_at = "2025-06-10T09:00:00.123456Z".parse().unwrap();
+        new.status = crate::entity::TaskStatus::Done;
+        new.base.created_by = Some("Grace".to_string());
+        cache.index_task(&old).unwrap();
+        cache.index_task(&new).unwrap();
+        let note = Note::new("Parser notes".to_string(), 3);
+        cache.index_note(&note).unwrap();
+
+        let (_, filter) = crate::search::parse_query("status:done");
+        let results = cache.search_tasks_filtered("parser", &filter, 10).unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].title, "Parser tests");
+        // Notes have no status, so a status filter excludes them
+        assert!(cache
+            .search_notes_filtered("parser", &filter, 10)
+            .unwrap()
+            .is_empty());
+
+        let (_, filter) = crate::search::parse_query("tag:backend assignee:ADA");
+        let results = cache.search_tasks_filtered("parser", &filter, 10).unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].title, "Parser rewrite");
+
+        let (_, filter) = crate::search::parse_query("created:>2025-06-10 created_by:grace");
+        let results = cache.search_tasks_filtered("parser", &filter, 10).unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].title, "Parser tests");
+
+        let (_, filter) = crate::search::parse_query("created:<2025-03-01");
+        let results = cache.search_tasks_filtered("parser", &filter, 10).unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].title, "Parser rewrite");
+
+        // The limit applies after filtering
+        let (_, filter) = crate::search::parse_query("status:done");
+        let results = cache.search_tasks_filtered("parser", &filter, 1).unwrap();
+        assert_eq!(results[0].title, "Parser tests");
+    }
+
+    #[test]
+    fn test_find_entities() {
+        let tmp = TempDir::new().unwrap();
+        let cache = SqliteCache::open(tmp.path()).unwrap();
+
+        let mut done = Task::new("Old parser".to_string(), 1);
+        done.status = crate::entity::TaskStatus::Done;
+        done.base.tags = vec!["active".to_string()];
+        let todo = Task::new("New parser".to_string(), 2);
+        let mut decision = Decision::new("Parser choice".to_string(), 3);
+        decision.base.tags = vec!["active".to_string()];
+        cache.index_task(&done).unwrap();
+        cache.index_task(&todo).unwrap();
+        cache.index_decision(&decision).unwrap();
+
+        let (_, filter) = crate::search::parse_query("type:task status:done");
+        let found = cache.find_entities("", &filter).unwrap();
+        assert_eq!(found, vec![(done.base.id.to_string(), "task".to_string())]);
+
+        let (_, filter) = crate::search::parse_query("tag:active");
+        let found = cache.find_entities("", &filter).unwrap();
+        assert_eq!(found.len(), 2);
+        assert_eq!(found[0].1, "decision");
+
+        let found = cache
+            .find_entities("new", &SearchFilter::default())
+            .unwrap();
+        assert_eq!(found, vec![(todo.base.id.to_string(), "task".to_string())]);
+    }
+
     #[test]
     fn test_search_semantic_basic() {
         let tmp = TempDir::new().unwrap();
@@ -2558,6 +5513,94 @@ mod tests {
         assert!((results[0].score - 1.0).abs() < f32::EPSILON);
     }
 
+    #[test]
+    fn test_search_semantic_within_candidates() {
+        let tmp = TempDir::new().unwrap();
+        let cache = SqliteCache::open(tmp.path()).unwrap();
+
+        let d1 = Decision::new("Use Postgres".to_string(), 1);
+        let d2 = Decision::new("Use Redis".to_string(), 2);
+        cache.index_decision(&d1).unwrap();
+        cache.index_decision(&d2).unwrap();
+        cache
+            .store_embedding(&d1.base.id.to_string(), "decision", &[1.0, 0.0, 0.0], "h1")
+            .unwrap();
+        cache
+            .store_embedding(&d2.base.id.to_string(), "decision", &[0.9, 0.1, 0.0], "h2")
+            .unwrap();
+
+        // Only the full-text hit is ranked, even though both are similar
+        let candidates: HashSet<String> = cache
+            .search_all("Redis", 10)
+            .unwrap()
+            .iter()
+            .map(|r| r.id().to_string())
+            .collect();
+        let results = cache
+            .search_semantic_within(&[1.0, 0.0, 0.0], None, Some(&candidates), 10, 0.0)
+            .unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].entity_id, d2.base.id.to_string());
+
+        let results = cache
+            .search_semantic_within(&[1.0, 0.0, 0.0], None, None, 10, 0.0)
+            .unwrap();
+        assert_eq!(results.len(), 2);
+    }
+
+    #[test]
+    fn test_suggest_related() {
+        let tmp = TempDir::new().unwrap();
+        let cache = SqliteCache::open(tmp.path()).unwrap();
+
+        let decisions: Vec<Decision> = ["Use Postgres", "Use Redis", "Use MySQL", "Hire"]
+            .iter()
+            .enumerate()
+            .map(|(i, title)| Decision::new(title.to_string(), i as u32 + 1))
+            .collect();
+        for (decision, vector) in decisions.iter().zip([
+            [1.0, 0.0, 0.0],
+            [0.9, 0.1, 0.0],
+            [0.8, 0.2, 0.0],
+            [0.0, 0.0, 1.0],
+        ]) {
+            cache.index_decision(decision).unwrap();
+            cache
+                .store_embedding(&decision.base.id.to_string(), "decision", &vector, "h")
+                .unwrap();
+        }
+        let id = |i: usize| decisions[i].base.id.to_string();
+
+        let results = cache
+            .suggest_related(&id(0), &HashSet::new(), 5, 0.5)
+            .unwrap();
+        let titles: Vec<&str> = results.iter().map(|r| r.title.as_str()).collect();
+        assert_eq!(titles, vec!["Use Redis", "Use MySQL"]);
+
+        // Already related entities are left out without shrinking the limit
+        let exclude = HashSet::from([id(1)]);
+        let results = cache.suggest_related(&id(0), &exclude, 1, 0.5).unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].title, "Use MySQL");
+
+        assert!(cache
+            .suggest_related(&uuid::Uuid::new_v4().to_string(), &HashSet::new(), 5, 0.0)
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_embedding_dimension() {
+        let tmp = TempDir::new().unwrap();
+        let cache = SqliteCache::open(tmp.path()).unwrap();
+        assert_eq!(cache.embedding_dimension().unwrap(), None);
+
+        cache
+            .store_embedding("some-id", "note", &[0.5, 0.5, 0.0, 1.0], "h")
+            .unwrap();
+        assert_eq!(cache.embedding_dimension().unwrap(), Some(4));
+    }
+
     #[test]
     fn test_search_semantic_threshold() {
         let tmp = TempDir::new().unwrap();
@@ -2674,4 +5717,144 @@ mod tests {
 
         assert_eq!(results.len(), 3);
     }
+
+    #[test]
+    fn test_find_duplicate_pairs() {
+        let tmp = TempDir::new().unwrap();
+        let cache = SqliteCache::open(tmp.path()).unwrap();
+
+        let vectors = [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.99, 0.05, 0.0]];
+        let mut ids = Vec::new();
+        for (i, vector) in vectors.iter().enumerate() {
+            let d = Decision::new(format!("Decision {}", i + 1), i as u32 + 1);
+            cache.index_decision(&d).unwrap();
+            cache
+                .store_embedding(&d.base.id.to_string(), "decision", vector, "h")
+                .unwrap();
+            ids.push(d.base.id.to_string());
+        }
+
+        let pairs = cache.find_duplicate_pairs(None, 0.9, 10).unwrap();
+        assert_eq!(pairs.len(), 1);
+        assert_eq!(pairs[0].entity_type, "decision");
+        assert_eq!(pairs[0].first.entity_id, ids[0]);
+        assert_eq!(pairs[0].second.entity_id, ids[2]);
+        assert_eq!(pairs[0].first.sequence_number, 1);
+
+        assert!(cache
+            .find_duplicate_pairs(Some("task"), 0.9, 10)
+            .unwrap()
+            .is_empty());
+        assert_eq!(cache.find_duplicate_pairs(None, -1.0, 2).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_find_duplicate_candidates() {
+        let tmp = TempDir::new().unwrap();
+        let cache = SqliteCache::open(tmp.path()).unwrap();
+
+        let postgres = Decision::new("Use PostgreSQL for the database".to_string(), 1);
+        let cache_layer = Decision::new("Add a Redis cache layer".to_string(), 2);
+        cache.index_decision(&postgres).unwrap();
+        cache.index_decision(&cache_layer).unwrap();
+        cache
+            .store_embedding(
+                &cache_layer.base.id.to_string(),
+                "decision",
+                &[1.0, 0.0],
+                "h",
+            )
+            .unwrap();
+
+        let found = cache
+            .find_duplicate_candidates("decision", "Use Postgresql for database", None, 5)
+            .unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].entity_id, postgres.base.id.to_string());
+        assert_eq!(found[0].matched_on, "title");
+
+        // Related but different titles are not duplicates
+        assert!(cache
+            .find_duplicate_candidates("decision", "Use PostgreSQL for analytics", None, 5)
+            .unwrap()
+            .is_empty());
+        assert!(cache
+            .find_duplicate_candidates("task", "Use PostgreSQL for the database", None, 5)
+            .unwrap()
+            .is_empty());
+
+        let found = cache
+            .find_duplicate_candidates("decision", "Cache with Redis", Some(&[0.99, 0.05]), 5)
+            .unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].sequence_number, 2);
+        assert_eq!(found[0].matched_on, "embedding");
+    }
+
+    #[test]
+    fn test_title_similarity() {
+        assert_eq!(title_similarity("Use Postgres", "use postgres!"), 1.0);
+        assert!(title_similarity("Use Postgres", "Use PostgreSQL") > 0.8);
+        assert!(title_similarity("Use Postgres", "Drop Redis") < 0.3);
+        assert_eq!(title_similarity("", "x"), 0.0);
+        assert_eq!(
+            title_similarity("Sprint 12 review", "Sprint 13 review"),
+            0.0
+        );
+    }
+
+    #[test]
+    fn test_search_semantic_vector_index_tracks_writes() {
+        let tmp = TempDir::new().unwrap();
+        let cache = SqliteCache::open(tmp.path()).unwrap();
+
+        let d1 = Decision::new("First".to_string(), 1);
+        let d2 = Decision::new("Second".to_string(), 2);
+        cache.index_decision(&d1).unwrap();
+        cache.index_decision(&d2).unwrap();
+        let (id1, id2) = (d1.base.id.to_string(), d2.base.id.to_string());
+        cache
+            .store_embedding(&id1, "decision", &[1.0, 0.0], "h1")
+            .unwrap();
+
+        // First search builds and persists the index
+        let results = cache.search_semantic(&[1.0, 0.0], None, 10, 0.5).unwrap();
+        assert_eq!(results.len(), 1);
+        assert!(tmp.path().join(VECTOR_INDEX_FILE).exists());
+
+        // Writes after that update the index in place, without a rebuild
+        cache
+            .store_embedding(&id2, "decision", &[1.0, 0.1], "h2")
+            .unwrap();
+        cache.delete_embedding(&id1).unwrap();
+        let results = cache.search_semantic(&[1.0, 0.0], None, 10, 0.5).unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].entity_id, id2);
+        cache.search_semantic(&[1.0, 0.0], None, 10, 0.5).unwrap();
+        assert_eq!(cache.vector_index_lookups(), (2, 1));
+
+        // Another connection reuses the persisted index, which has the writes
+        let reopened = SqliteCache::open(tmp.path()).unwrap();
+        let results = reopened
+            .search_semantic(&[1.0, 0.0], None, 10, 0.5)
+            .unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].entity_id, id2);
+        assert_eq!(
+            VectorIndex::load(&tmp.path().join(VECTOR_INDEX_FILE))
+                .unwrap()
+                .len(),
+            1
+        );
+
+        // Writes from another connection leave this one's index stale, so
+        // it is loaded again
+        reopened
+            .store_embedding(&id1, "decision", &[1.0, 0.0], "h1")
+            .unwrap();
+        let results = cache.search_semantic(&[1.0, 0.0], None, 10, 0.5).unwrap();
+        assert_eq!(results.len(), 2);
+        assert_eq!(cache.vector_index_lookups(), (2, 2));
+        assert_eq!(reopened.rebuild_vector_index().unwrap(), 1);
+    }
 }
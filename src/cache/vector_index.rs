@@ -0,0 +1,399 @@
+// src/cache/vector_index.rs
+//! Flat vector index for semantic search
+//!
+//! Embeddings are kept in SQLite as one BLOB per row, so scanning them means
+//! decoding every row and re-normalising every vector on each query. The
+//! index holds the same vectors pre-normalised in one contiguous buffer, so a
+//! query is a single pass of dot products. It is persisted next to cache.db
+//! and tagged with the cache's embedding generation, which changes on every
+//! embedding write. Writes made through the cache that holds the index update
+//! it in place; a file left stale by another connection is rebuilt rather
+//! than trusted.
+
+use std::collections::HashSet;
+use std::path::Path;
+
+use crate::error::{MedullaError, Result};
+
+/// File name of the persisted index, inside the .medulla directory
+pub const VECTOR_INDEX_FILE: &str = "vectors.idx";
+
+const MAGIC: &[u8; 8] = b"MEDVEC01";
+
+/// Pre-normalised embeddings of every entity, in one row-major buffer
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct VectorIndex {
+    generation: String,
+    dimension: usize,
+    /// (entity_id, entity_type) of each row
+    entries: Vec<(String, String)>,
+    vectors: Vec<f32>,
+}
+
+impl VectorIndex {
+    /// Build from `(entity_id, entity_type, embedding)` rows. Rows whose
+    /// dimension differs from the first one are skipped, as they can never
+    /// match a query.
+    pub fn build(generation: String, embeddings: Vec<(String, String, Vec<f32>)>) -> Self {
+        let dimension = embeddings.first().map_or(0, |(_, _, e)| e.len());
+        let mut index = Self {
+            generation,
+            dimension,
+            entries: Vec::with_capacity(embeddings.len()),
+            vectors: Vec::with_capacity(embeddings.len() * dimension),
+        };
+
+        for (entity_id, entity_type, embedding) in embeddings {
+            if embedding.len() != dimension {
+                continue;
+            }
+            index.vectors.extend(normalized(&embedding));
+            index.entries.push((entity_id, entity_type));
+        }
+
+        index
+    }
+
+    /// Embedding generation of the cache this index was built from
+    pub fn generation(&self) -> &str {
+        &self.generation
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Add or replace the vector of an entity, tagging the index with the
+    /// generation of that write. A vector whose dimension differs from the
+    /// indexed ones is left out, as in `build`.
+    pub fn upsert(
+        &mut self,
+        generation: String,
+        entity_id: &str,
+        entity_type: &str,
+        embedding: &[f32],
+    ) {
+        self.remove_row(entity_id);
+        if self.entries.is_empty() {
+            self.dimension = embedding.len();
+        }
+        if embedding.len() == self.dimension {
+            self.vectors.extend(normalized(embedding));
+            self.entries
+                .push((entity_id.to_string(), entity_type.to_string()));
+        }
+        self.generation = generation;
+    }
+
+    /// Drop the vector of an entity, tagging the index with the generation
+    /// of that delete
+    pub fn remove(&mut self, generation: String, entity_id: &str) {
+        self.remove_row(entity_id);
+        self.generation = generation;
+    }
+
+    /// Remove an entity's row by moving the last row into its place
+    fn remove_row(&mut self, entity_id: &str) {
+        let Some(row) = self.entries.iter().position(|(id, _)| id == entity_id) else {
+            return;
+        };
+        self.entries.swap_remove(row);
+        let last = self.entries.len();
+        if row != last {
+            self.vectors.copy_within(
+                last * self.dimension..(last + 1) * self.dimension,
+                row * self.dimension,
+            );
+        }
+        self.vectors.truncate(last * self.dimension);
+    }
+
+    /// Entities whose cosine similarity to `query` is at least `threshold`,
+    /// as `(entity_id, entity_type, score)` sorted by score descending.
+    pub fn search(
+        &self,
+        query: &[f32],
+        entity_type: Option<&str>,
+        candidates: Option<&HashSet<String>>,
+        threshold: f32,
+    ) -> Vec<(&str, &str, f32)> {
+        if query.len() != self.dimension || self.dimension == 0 {
+            return Vec::new();
+        }
+        let query = normalized(query);
+
+        let mut hits: Vec<(&str, &str, f32)> = self
+            .entries
+            .iter()
+            .zip(self.vectors.chunks_exact(self.dimension))
+            .filter(|((id, etype), _)| {
+                entity_type.map_or(true, |t| t == etype)
+                    && candidates.map_or(true, |c| c.contains(id))
+            })
+            .map(|((id, etype), vector)| {
+                let score: f32 = query.iter().zip(vector).map(|(a, b)| a * b).sum();
+                (id.as_str(), etype.as_str(), score)
+            })
+            .filter(|(_, _, score)| *score >= threshold)
+            .collect();
+
+        hits.sort_by(|a, b| b.2.partial_cmp(&a.2).unwrap_or(std::cmp::Ordering::Equal));
+        hits
+    }
+
+    /// Pairs of entities of the same type whose cosine similarity is at
+    /// least `threshold`, as `(entity_id, entity_id, entity_type, score)`
+    /// sorted by score descending. Compares every pair, so it is quadratic
+    /// in the number of indexed entities.
+    pub fn similar_pairs(
+        &self,
+        entity_type: Option<&str>,
+        threshold: f32,
+    ) -> Vec<(&str, &str, &str, f32)> {
+        if self.dimension == 0 {
+            return Vec::new();
+        }
+
+        let rows: Vec<(&(String, String), &[f32])> = self
+            .entries
+            .iter()
+            .zip(self.vectors.chunks_exact(self.dimension))
+            .filter(|((_, etype), _)| entity_type.map_or(true, |t| t == etype))
+            .collect();
+
+        let mut pairs = Vec::new();
+        for (i, ((id_a, type_a), vec_a)) in rows.iter().enumerate() {
+            for ((id_b, type_b), vec_b) in &rows[i + 1..] {
+                if type_a != type_b {
+                    continue;
+                }
+                let score: f32 = vec_a.iter().zip(*vec_b).map(|(a, b)| a * b).sum();
+                if score >= threshold {
+                    pairs.push((id_a.as_str(), id_b.as_str(), type_a.as_str(), score));
+                }
+            }
+        }
+
+        pairs.sort_by(|a, b| b.3.partial_cmp(&a.3).unwrap_or(std::cmp::Ordering::Equal));
+        pairs
+    }
+
+    /// Read a persisted index. Returns None if the file is missing or not a
+    /// readable index, in which case it should be rebuilt.
+    pub fn load(path: &Path) -> Option<Self> {
+        let bytes = std::fs::read(path).ok()?;
+        let mut reader = Reader { bytes: &bytes };
+
+        if reader.take(MAGIC.len())? != MAGIC {
+            return None;
+        }
+        let generation = reader.string()?;
+        let dimension = reader.u32()? as usize;
+        let count = reader.u32()? as usize;
+
+        let mut entries = Vec::with_capacity(count);
+        for _ in 0..count {
+            entries.push((reader.string()?, reader.string()?));
+        }
+        let vectors: Vec<f32> = reader
+            .take(count * dimension * 4)?
+            .chunks_exact(4)
+            .map(|chunk| f32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]))
+            .collect();
+        if !reader.bytes.is_empty() {
+            return None;
+        }
+
+        Some(Self {
+            generation,
+            dimension,
+            entries,
+            vectors,
+        })
+    }
+
+    /// Write the index to `path`, replacing any previous file
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let mut bytes = Vec::with_capacity(64 + self.entries.len() * 48 + self.vectors.len() * 4);
+        bytes.extend_from_slice(MAGIC);
+        write_string(&mut bytes, &self.generation)?;
+        bytes.extend_from_slice(&(self.dimension as u32).to_le_bytes());
+        bytes.extend_from_slice(&(self.entries.len() as u32).to_le_bytes());
+        for (entity_id, entity_type) in &self.entries {
+            write_string(&mut bytes, entity_id)?;
+            write_string(&mut bytes, entity_type)?;
+        }
+        for value in &self.vectors {
+            bytes.extend_from_slice(&value.to_le_bytes());
+        }
+
+        // Write then rename, so a concurrent reader never sees half a file
+        let tmp = path.with_extension("idx.tmp");
+        std::fs::write(&tmp, &bytes)?;
+        std::fs::rename(&tmp, path)?;
+        Ok(())
+    }
+}
+
+/// Unit-length copy of a vector; the zero vector stays zero
+fn normalized(vector: &[f32]) -> Vec<f32> {
+    let norm = vector.iter().map(|x| x * x).sum::<f32>().sqrt();
+    if norm == 0.0 {
+        vector.to_vec()
+    } else {
+        vector.iter().map(|x| x / norm).collect()
+    }
+}
+
+fn write_string(bytes: &mut Vec<u8>, value: &str) -> Result<()> {
+    let len = u16::try_from(value.len()).map_err(|_| {
+        MedullaError::Storage(format!("vector index key too long: {} bytes", value.len()))
+    })?;
+    bytes.extend_from_slice(&len.to_le_bytes());
+    bytes.extend_from_slice(value.as_bytes());
+    Ok(())
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, n: usize) -> Option<&'a [u8]> {
+        if self.bytes.len() < n {
+            return None;
+        }
+        let (head, rest) = self.bytes.split_at(n);
+        self.bytes = rest;
+        Some(head)
+    }
+
+    fn u32(&mut self) -> Option<u32> {
+        let b = self.take(4)?;
+        Some(u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+    }
+
+    fn string(&mut self) -> Option<String> {
+        let b = self.take(2)?;
+        let len = u16::from_le_bytes([b[0], b[1]]) as usize;
+        String::from_utf8(self.take(len)?.to_vec()).ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn sample() -> VectorIndex {
+        VectorIndex::build(
+            "gen-1".to_string(),
+            vec![
+                ("a".to_string(), "decision".to_string(), vec![2.0, 0.0, 0.0]),
+                ("b".to_string(), "note".to_string(), vec![1.0, 1.0, 0.0]),
+                ("c".to_string(), "note".to_string(), vec![0.0, 0.0, 3.0]),
+                ("bad".to_string(), "note".to_string(), vec![1.0, 0.0]),
+            ],
+        )
+    }
+
+    #[test]
+    fn test_search_ranks_by_cosine_similarity() {
+        let index = sample();
+        assert_eq!(index.len(), 3);
+
+        let hits = index.search(&[1.0, 0.0, 0.0], None, None, 0.5);
+        let ids: Vec<&str> = hits.iter().map(|(id, _, _)| *id).collect();
+        assert_eq!(ids, vec!["a", "b"]);
+        assert!((hits[0].2 - 1.0).abs() < 1e-6);
+        assert!((hits[1].2 - std::f32::consts::FRAC_1_SQRT_2).abs() < 1e-6);
+
+        let notes = index.search(&[1.0, 0.0, 0.0], Some("note"), None, 0.0);
+        assert_eq!(notes.len(), 2);
+        assert!(notes.iter().all(|(_, t, _)| *t == "note"));
+
+        let only_c: HashSet<String> = ["c".to_string()].into_iter().collect();
+        let hits = index.search(&[0.0, 0.0, 1.0], None, Some(&only_c), 0.0);
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].0, "c");
+
+        assert!(index.search(&[1.0, 0.0], None, None, 0.0).is_empty());
+    }
+
+    #[test]
+    fn test_upsert_and_remove_in_place() {
+        let mut index = sample();
+
+        // Replacing a vector keeps one row per entity
+        index.upsert("gen-2".to_string(), "a", "decision", &[0.0, 0.0, 1.0]);
+        assert_eq!(index.len(), 3);
+        assert_eq!(index.generation(), "gen-2");
+        let hits = index.search(&[0.0, 0.0, 1.0], None, None, 0.99);
+        let mut ids: Vec<&str> = hits.iter().map(|(id, _, _)| *id).collect();
+        ids.sort_unstable();
+        assert_eq!(ids, vec!["a", "c"]);
+
+        // A vector of another dimension is left out
+        index.upsert("gen-3".to_string(), "d", "note", &[1.0, 0.0]);
+        assert_eq!(index.len(), 3);
+
+        index.remove("gen-4".to_string(), "a");
+        index.remove("gen-5".to_string(), "missing");
+        assert_eq!(index.len(), 2);
+        assert_eq!(index.generation(), "gen-5");
+        let hits = index.search(&[1.0, 1.0, 0.0], None, None, 0.99);
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].0, "b");
+
+        // Matches an index built from the same vectors
+        let built = VectorIndex::build(
+            "gen-5".to_string(),
+            vec![
+                ("c".to_string(), "note".to_string(), vec![0.0, 0.0, 3.0]),
+                ("b".to_string(), "note".to_string(), vec![1.0, 1.0, 0.0]),
+            ],
+        );
+        assert_eq!(index, built);
+
+        // An emptied index takes the dimension of the next vector
+        index.remove("gen-6".to_string(), "b");
+        index.remove("gen-7".to_string(), "c");
+        index.upsert("gen-8".to_string(), "e", "task", &[0.0, 2.0]);
+        assert_eq!(index.search(&[0.0, 1.0], None, None, 0.99).len(), 1);
+    }
+
+    #[test]
+    fn test_similar_pairs_within_type() {
+        let index = VectorIndex::build(
+            "gen-1".to_string(),
+            vec![
+                ("a".to_string(), "note".to_string(), vec![1.0, 0.0]),
+                ("b".to_string(), "note".to_string(), vec![2.0, 0.1]),
+                ("c".to_string(), "task".to_string(), vec![1.0, 0.0]),
+                ("d".to_string(), "note".to_string(), vec![0.0, 1.0]),
+            ],
+        );
+
+        let pairs = index.similar_pairs(None, 0.9);
+        assert_eq!(pairs.len(), 1);
+        assert_eq!((pairs[0].0, pairs[0].1, pairs[0].2), ("a", "b", "note"));
+        assert!(pairs[0].3 > 0.99);
+
+        assert!(index.similar_pairs(Some("task"), 0.0).is_empty());
+        assert_eq!(index.similar_pairs(Some("note"), -1.0).len(), 3);
+    }
+
+    #[test]
+    fn test_save_and_load_roundtrip() {
+        let tmp = TempDir::new().unwrap();
+        let path = tmp.path().join(VECTOR_INDEX_FILE);
+
+        let index = sample();
+        index.save(&path).unwrap();
+        assert_eq!(VectorIndex::load(&path), Some(index));
+
+        std::fs::write(&path, b"not an index").unwrap();
+        assert_eq!(VectorIndex::load(&path), None);
+        assert_eq!(VectorIndex::load(&tmp.path().join("missing.idx")), None);
+    }
+}
@@ -1,10 +1,27 @@
+use std::path::PathBuf;
+
 use clap::{Args, Parser, Subcommand};
 
+use super::output::OutputFormat;
+
 #[derive(Parser, Debug)]
 #[command(name = "medulla")]
 #[command(version, about = "A git-native, AI-accessible knowledge engine")]
 #[command(propagate_version = true)]
 pub struct Cli {
+    /// Project directory (or its .medulla directory) to use instead of discovering from the current directory
+    #[arg(long, global = true, value_name = "PATH")]
+    pub data_dir: Option<PathBuf>,
+
+    /// Project to use: a name registered with `medulla workspace add`, or a path
+    #[arg(
+        long,
+        global = true,
+        value_name = "NAME|PATH",
+        conflicts_with = "data_dir"
+    )]
+    pub project: Option<String>,
+
     #[command(subcommand)]
     pub command: Commands,
 }
@@ -34,12 +51,64 @@ pub enum Commands {
         /// Output as JSON
         #[arg(long)]
         json: bool,
+
+        /// Output format
+        #[arg(long, value_enum, conflicts_with = "json")]
+        format: Option<OutputFormat>,
+
+        /// Include archived entities
+        #[arg(long)]
+        include_archived: bool,
+
+        /// Only entities in this collection
+        #[arg(long)]
+        collection: Option<String>,
     },
 
     /// Get a single entity by ID
     Get {
-        /// Entity ID (sequence number like "3" or UUID prefix like "a1b2c")
-        id: String,
+        /// Entity ID (sequence number like "3" or UUID prefix like "a1b2c");
+        /// picked interactively when omitted
+        id: Option<String>,
+
+        /// Also show incoming and outgoing relations
+        #[arg(long)]
+        relations: bool,
+
+        /// Also list the entities reachable through relations
+        #[arg(long)]
+        related: bool,
+
+        /// Relation hops to follow with --related (at most 3)
+        #[arg(long, default_value_t = 1, requires = "related")]
+        depth: usize,
+
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Show the change history of an entity
+    History {
+        /// Entity ID (sequence number, UUID prefix, or full UUID for deleted
+        /// entities); picked interactively when omitted
+        id: Option<String>,
+
+        /// Show the entity as it was at this time (RFC 3339 or YYYY-MM-DD)
+        #[arg(long)]
+        at: Option<String>,
+
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// List concurrent edits to the same entity fields found in the merged
+    /// history, with both values and the one the merge kept
+    MergeReport {
+        /// Only conflicts edited at or after this time (RFC 3339 or YYYY-MM-DD)
+        #[arg(long)]
+        since: Option<String>,
 
         /// Output as JSON
         #[arg(long)]
@@ -48,8 +117,9 @@ pub enum Commands {
 
     /// Update an existing entity
     Update {
-        /// Entity ID (sequence number like "3" or UUID prefix like "a1b2c")
-        id: String,
+        /// Entity ID (sequence number like "3" or UUID prefix like "a1b2c");
+        /// picked interactively when omitted
+        id: Option<String>,
 
         /// New title
         #[arg(long)]
@@ -79,21 +149,146 @@ pub enum Commands {
         #[arg(long, short = 'e')]
         edit: bool,
 
+        /// Change the status even if the configured workflow does not allow it
+        #[arg(long)]
+        force: bool,
+
         /// Output as JSON
         #[arg(long)]
         json: bool,
     },
 
-    /// Delete an entity
+    /// Move an entity and its relations to the trash
     Delete {
-        /// Entity ID (sequence number like "3" or UUID prefix like "a1b2c")
-        id: String,
+        /// Entity ID (sequence number like "3" or UUID prefix like "a1b2c");
+        /// picked interactively when omitted
+        id: Option<String>,
+
+        /// Skip confirmation prompt
+        #[arg(long, short = 'f')]
+        force: bool,
+    },
+
+    /// List, restore or permanently remove deleted entities
+    Trash(TrashCommand),
+
+    /// Fold one entity into another: content, tags and relations move to the
+    /// target and the source is moved to the trash
+    Merge {
+        /// Entity to merge away (sequence number or UUID prefix)
+        source: String,
+
+        /// Entity to keep (sequence number or UUID prefix)
+        target: String,
+
+        /// Archive the source instead of moving it to the trash
+        #[arg(long)]
+        archive: bool,
 
         /// Skip confirmation prompt
         #[arg(long, short = 'f')]
         force: bool,
     },
 
+    /// Find near-duplicate entities by embedding similarity
+    Dedupe {
+        /// Only compare entities of this type
+        #[arg(long = "type", short = 't')]
+        entity_type: Option<String>,
+
+        /// Minimum similarity for a pair to be reported (0.0-1.0)
+        #[arg(long, default_value = "0.9")]
+        threshold: f32,
+
+        /// Maximum number of pairs
+        #[arg(long, short = 'n', default_value = "20")]
+        limit: usize,
+
+        /// Review each pair and merge one entity into the other
+        #[arg(long, conflicts_with = "json")]
+        merge: bool,
+
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Archive an entity, hiding it from default list, search and ready results
+    Archive {
+        /// Entity ID (sequence number like "3" or UUID prefix like "a1b2c");
+        /// picked interactively when omitted
+        id: Option<String>,
+    },
+
+    /// Restore an archived entity
+    Unarchive {
+        /// Entity ID (sequence number like "3" or UUID prefix like "a1b2c");
+        /// picked interactively when omitted
+        id: Option<String>,
+    },
+
+    /// Attach a file (screenshot, diagram, ...) to an entity
+    ///
+    /// The file is copied to .medulla/attachments/<entity uuid>/ and listed
+    /// by `get`, entity_get and snapshots. Attaching a file with the same
+    /// name again replaces it. Attachments are removed when the entity is
+    /// purged from the trash.
+    Attach {
+        /// Entity ID (sequence number like "3" or UUID prefix like "a1b2c")
+        id: String,
+
+        /// File to attach
+        file: PathBuf,
+
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Give an entity a readable alias, accepted anywhere an ID is
+    ///
+    /// Aliases are unique, start with a letter and hold letters, digits,
+    /// '-' and '_'. Unlike sequence numbers they do not change when
+    /// replicas are merged. With only an ID, shows the entity's alias;
+    /// with no arguments, lists every alias.
+    Alias {
+        /// Entity ID (sequence number, alias or UUID prefix)
+        id: Option<String>,
+
+        /// Alias to assign, replacing the entity's current one
+        alias: Option<String>,
+
+        /// Remove the entity's alias
+        #[arg(long, requires = "id", conflicts_with = "alias")]
+        remove: bool,
+
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Put an entity in a collection, such as "backend" or "frontend"
+    ///
+    /// Entities in a collection are also numbered within it, so
+    /// "backend/3" is accepted anywhere an ID is. Moving an entity to
+    /// another collection numbers it afresh. With only an ID, shows the
+    /// entity's collection; with no arguments, lists every collection.
+    Collection {
+        /// Entity ID (sequence number, alias or UUID prefix)
+        id: Option<String>,
+
+        /// Collection to put the entity in, replacing its current one
+        name: Option<String>,
+
+        /// Take the entity out of its collection
+        #[arg(long, requires = "id", conflicts_with = "name")]
+        remove: bool,
+
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
     /// Search for entities
     Search {
         /// Search query
@@ -106,37 +301,288 @@ pub enum Commands {
         /// Output as JSON
         #[arg(long)]
         json: bool,
+
+        /// Output format
+        #[arg(long, value_enum, conflicts_with = "json")]
+        format: Option<OutputFormat>,
+
+        /// Include archived entities
+        #[arg(long)]
+        include_archived: bool,
+
+        /// Only entities in this collection
+        #[arg(long, conflicts_with = "all_projects")]
+        collection: Option<String>,
+
+        /// Search every project registered in the workspace
+        #[arg(long, conflicts_with_all = ["json", "format"])]
+        all_projects: bool,
     },
 
     /// Task queue commands (ready, blocked, next)
     Tasks(TasksCommand),
 
+    /// Question commands (open, answered, answer)
+    Questions(QuestionsCommand),
+
+    /// Milestone commands (status)
+    Milestones(MilestonesCommand),
+
+    /// Link commands (check)
+    Links(LinksCommand),
+
     /// Start the MCP server
     Serve {
         /// Run HTTP server on specified port instead of stdio
         #[arg(long)]
         http: Option<u16>,
+
+        /// Also serve every project registered in the workspace; tools then
+        /// take an optional `project` argument
+        #[arg(long)]
+        workspace: bool,
+
+        /// Expose Prometheus metrics (tool calls and latency, store size,
+        /// cache hit rate) at `/metrics` on the HTTP server
+        #[arg(long, requires = "http")]
+        metrics: bool,
+
+        /// Ask the user (through MCP elicitation, when the client supports
+        /// it) before deleting entities, superseding decisions or running
+        /// bulk changes; same as `require_confirmation: true` in the config
+        #[arg(long)]
+        require_confirmation: bool,
     },
 
     /// Manage relations between entities
+    #[command(alias = "relations")]
     Relation(RelationCommand),
 
+    /// Relation graph commands
+    Graph(GraphCommand),
+
+    /// Manage reusable entity templates
+    Template(TemplateCommand),
+
     /// Cache management commands
     Cache(CacheCommand),
 
-    /// Generate markdown snapshot
+    /// Compute embeddings for semantic search
+    Embeddings(EmbeddingsCommand),
+
+    /// Print a shell completion script
+    ///
+    /// For example `medulla completions bash > ~/.local/share/bash-completion/completions/medulla`
+    /// or `medulla completions zsh > "${fpath[1]}/_medulla"`.
+    Completions {
+        /// Shell to generate completions for
+        #[arg(value_enum)]
+        shell: clap_complete::Shell,
+    },
+
+    /// Check store size, cache staleness, orphaned embeddings, FTS consistency and duplicate sequence numbers
+    ///
+    /// Exits 0 when healthy, 2 on warnings and 3 on errors.
+    Doctor {
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Renumber entities that share a sequence number after replicas merge
+    ///
+    /// The earliest-created entity keeps the number; the others move to
+    /// fresh numbers above the current highest. Numbers within a collection
+    /// (backend/3) are renumbered the same way.
+    Resequence {
+        /// Report what would be renumbered without changing anything
+        #[arg(long)]
+        dry_run: bool,
+
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Shrink loro.db by dropping old edit history, and vacuum cache.db
+    ///
+    /// The current state is kept in full. Dropped history no longer shows
+    /// in `history` or `snapshot diff`, and copies of loro.db made before
+    /// the compaction can no longer be merged with `sync replica`.
+    Compact {
+        /// Keep the history of the last N days instead of dropping it all
+        #[arg(long, value_name = "N")]
+        keep_days: Option<u32>,
+
+        /// Skip confirmation prompt
+        #[arg(long, short = 'f')]
+        force: bool,
+
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Bulk-import entities
+    Import(ImportCommand),
+
+    /// Keep entities in step with another tool
+    Sync(SyncCommand),
+
+    /// Change many entities at once
+    Bulk(BulkCommand),
+
+    /// Use prompt entities
+    Prompt(PromptCommand),
+
+    /// Inspect the JSON Schema of tool parameters and responses
+    Schema(SchemaCommand),
+
+    /// List or try out the auto-tagging and validation rules in config.yaml
+    Rules(RulesCommand),
+
+    /// Export all entities, relations and embeddings as JSON Lines
+    ///
+    /// The output can be loaded into a fresh project with `medulla import jsonl`.
+    Export {
+        /// Output file (default: stdout)
+        #[arg(long, short = 'o')]
+        output: Option<PathBuf>,
+    },
+
+    /// Generate markdown snapshot, or a static HTML site
+    #[command(args_conflicts_with_subcommands = true)]
     Snapshot {
-        /// Output directory (default: .medulla/snapshot)
+        #[command(subcommand)]
+        action: Option<SnapshotAction>,
+
+        /// Output directory (default: .medulla/snapshot, or .medulla/site
+        /// for html)
         #[arg(long)]
         output: Option<String>,
 
+        /// Output format: markdown, or html for a browsable static site
+        /// with type, tag and entity pages and a relation graph
+        #[arg(long, default_value = "markdown", value_parser = ["markdown", "html"])]
+        format: String,
+
         /// Show verbose output with list of generated files
         #[arg(long, short = 'v')]
         verbose: bool,
+
+        /// Write one `NNN-slug.md` file per entity, tasks included, so each
+        /// has a stable link. Later snapshots keep this layout.
+        #[arg(long, conflicts_with = "grouped")]
+        per_entity: bool,
+
+        /// Switch back to the default layout, with tasks grouped into
+        /// active.md and completed.md
+        #[arg(long)]
+        grouped: bool,
+
+        /// Write a named snapshot (e.g. a release version) to
+        /// versions/<tag> under the output directory, kept in the
+        /// snapshot history
+        #[arg(long)]
+        tag: Option<String>,
+
+        /// Add a "Related" section to each entity's page suggesting
+        /// entities with similar embeddings that it has no relation with
+        /// (markdown only; needs embeddings)
+        #[arg(long)]
+        related: bool,
+
+        /// Snapshot only the entities in this collection, to
+        /// collections/<name> under the output directory
+        #[arg(long)]
+        collection: Option<String>,
+    },
+
+    /// Summarize what was created, updated and completed recently, as
+    /// markdown grouped by type and tag
+    Digest {
+        /// How far back to look (e.g. 7d, 2w, week, month)
+        #[arg(long, default_value = "7d")]
+        since: String,
+
+        /// Output file (default: stdout)
+        #[arg(long, short = 'o')]
+        output: Option<PathBuf>,
+
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Show a compact project overview: open decisions, task queue, overdue
+    /// work, recent changes, orphans and store health
+    Status {
+        /// Output file (default: stdout)
+        #[arg(long, short = 'o')]
+        output: Option<PathBuf>,
+
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Watch the store and regenerate the cache and snapshot when it changes
+    ///
+    /// Useful after `git pull` brings in a new `.medulla/loro.db`. Runs until
+    /// interrupted.
+    Watch {
+        /// Milliseconds to wait for further changes before regenerating
+        #[arg(long, default_value = "500")]
+        debounce_ms: u64,
+
+        /// Only sync the cache, skip snapshot generation
+        #[arg(long)]
+        no_snapshot: bool,
+    },
+
+    /// Sync the cache, regenerate the snapshot, backfill embeddings and
+    /// run the health check in the background on an interval
+    #[command(args_conflicts_with_subcommands = true)]
+    Daemon {
+        #[command(subcommand)]
+        action: Option<DaemonAction>,
+
+        /// Time between passes (e.g. 30s, 15m, 2h)
+        #[arg(long, default_value = "15m")]
+        interval: String,
+
+        /// Run in this terminal instead of in the background
+        #[arg(long)]
+        foreground: bool,
+
+        /// Skip snapshot generation
+        #[arg(long)]
+        no_snapshot: bool,
+
+        /// Skip the embedding backfill
+        #[arg(long)]
+        no_embeddings: bool,
     },
 
     /// Manage git hooks
     Hook(HookCommand),
+
+    /// Link entities to git commits
+    Git(GitCommand),
+
+    /// Register projects for --project, cross-project search and serving
+    Workspace(WorkspaceCommand),
+
+    /// Time core operations against a throwaway store of synthetic entities
+    Selftest {
+        /// Number of synthetic entities to generate
+        #[arg(long, default_value = "500")]
+        entities: usize,
+
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
 }
 
 #[derive(Args, Debug)]
@@ -156,10 +602,26 @@ pub enum TasksAction {
         /// Output as JSON
         #[arg(long)]
         json: bool,
+
+        /// Output format
+        #[arg(long, value_enum, conflicts_with = "json")]
+        format: Option<OutputFormat>,
+
+        /// Include archived entities
+        #[arg(long)]
+        include_archived: bool,
+
+        /// Skip tasks whose assignee is already at their WIP limit
+        #[arg(long)]
+        respect_wip: bool,
     },
 
     /// Show the single highest-priority ready task
     Next {
+        /// Skip tasks whose assignee is already at their WIP limit
+        #[arg(long)]
+        respect_wip: bool,
+
         /// Output as JSON
         #[arg(long)]
         json: bool,
@@ -175,54 +637,64 @@ pub enum TasksAction {
         #[arg(long)]
         json: bool,
     },
-}
 
-#[derive(Args, Debug)]
-pub struct RelationCommand {
-    #[command(subcommand)]
-    pub action: RelationAction,
-}
+    /// List open tasks whose due date has passed
+    Overdue {
+        /// Maximum number of tasks to show
+        #[arg(long, short = 'n', default_value = "50")]
+        limit: u32,
 
-#[derive(Subcommand, Debug)]
-pub enum RelationAction {
-    /// Add a relation between two entities
-    Add {
-        /// Source entity ID (sequence number like "3" or UUID prefix like "a1b2c")
-        source_id: String,
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
 
-        /// Target entity ID (sequence number like "3" or UUID prefix like "a1b2c")
-        target_id: String,
+    /// List open tasks due soon
+    Due {
+        /// How far ahead to look (e.g. 7d, 2w)
+        #[arg(long, default_value = "7d")]
+        within: String,
 
-        /// Relation type (implements, blocks, supersedes, references, belongs_to, documents)
-        #[arg(long = "type", short = 't')]
-        relation_type: String,
+        /// Maximum number of tasks to show
+        #[arg(long, short = 'n', default_value = "50")]
+        limit: u32,
 
         /// Output as JSON
         #[arg(long)]
         json: bool,
     },
 
-    /// Delete a relation between two entities
-    Delete {
-        /// Source entity ID (sequence number like "3" or UUID prefix like "a1b2c")
-        source_id: String,
+    /// List open tasks assigned to one person
+    Mine {
+        /// Assignee to show (defaults to your git user.name)
+        #[arg(long)]
+        assignee: Option<String>,
 
-        /// Target entity ID (sequence number like "3" or UUID prefix like "a1b2c")
-        target_id: String,
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
 
-        /// Relation type (implements, blocks, supersedes, references, belongs_to, documents)
-        #[arg(long = "type", short = 't')]
-        relation_type: String,
+    /// Show open task counts and queues for every assignee
+    Workload {
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
 
+    /// Show in-progress tasks per assignee against the `wip_limits` in the
+    /// project config; exits non-zero when someone is over their limit
+    Wip {
         /// Output as JSON
         #[arg(long)]
         json: bool,
     },
 
-    /// List relations for an entity
-    List {
-        /// Entity ID (sequence number like "3" or UUID prefix like "a1b2c")
-        entity_id: String,
+    /// Show a task's subtask hierarchy with rollup progress
+    Tree {
+        /// Root task ID (sequence number or UUID prefix); picked
+        /// interactively when omitted
+        id: Option<String>,
 
         /// Output as JSON
         #[arg(long)]
@@ -231,21 +703,258 @@ pub enum RelationAction {
 }
 
 #[derive(Args, Debug)]
-pub struct AddCommand {
+pub struct QuestionsCommand {
     #[command(subcommand)]
-    pub entity: AddEntity,
+    pub action: QuestionsAction,
 }
 
 #[derive(Subcommand, Debug)]
-pub enum AddEntity {
-    /// Add a new decision
-    Decision {
-        /// Decision title
-        title: String,
+pub enum QuestionsAction {
+    /// List questions that still need an answer
+    Open {
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
 
-        /// Decision status (proposed, accepted, deprecated, superseded)
-        #[arg(long, default_value = "proposed")]
-        status: String,
+        /// Output format
+        #[arg(long, value_enum, conflicts_with = "json")]
+        format: Option<OutputFormat>,
+
+        /// Include archived entities
+        #[arg(long)]
+        include_archived: bool,
+    },
+
+    /// List answered questions and what answered them
+    Answered {
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+
+        /// Output format
+        #[arg(long, value_enum, conflicts_with = "json")]
+        format: Option<OutputFormat>,
+
+        /// Include archived entities
+        #[arg(long)]
+        include_archived: bool,
+    },
+
+    /// Mark a question answered by another entity
+    Answer {
+        /// Question ID (sequence number like "3" or UUID prefix like "a1b2c");
+        /// picked interactively when omitted
+        id: Option<String>,
+
+        /// ID of the entity that answers it (usually a decision or note)
+        #[arg(long, value_name = "ID")]
+        by: String,
+
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+}
+
+#[derive(Args, Debug)]
+pub struct MilestonesCommand {
+    #[command(subcommand)]
+    pub action: MilestonesAction,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum MilestonesAction {
+    /// Show task completion per milestone, or the tasks of one milestone
+    Status {
+        /// Milestone ID (sequence number like "3" or UUID prefix like "a1b2c");
+        /// all milestones when omitted
+        id: Option<String>,
+
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+
+        /// Output format
+        #[arg(long, value_enum, conflicts_with = "json")]
+        format: Option<OutputFormat>,
+
+        /// Include archived milestones
+        #[arg(long)]
+        include_archived: bool,
+    },
+}
+
+#[derive(Args, Debug)]
+pub struct LinksCommand {
+    #[command(subcommand)]
+    pub action: LinksAction,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum LinksAction {
+    /// Fetch every link's URL and report dead links and pages that changed
+    /// since they were last fetched; exits with status 1 if any link is dead
+    Check {
+        /// Record the fetched page hash of reachable links, so changed
+        /// pages are only reported once
+        #[arg(long)]
+        update: bool,
+
+        /// Include archived links
+        #[arg(long)]
+        include_archived: bool,
+
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+}
+
+#[derive(Args, Debug)]
+pub struct RelationCommand {
+    #[command(subcommand)]
+    pub action: RelationAction,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum RelationAction {
+    /// Add a relation between two entities
+    Add {
+        /// Source entity ID (sequence number like "3" or UUID prefix like "a1b2c")
+        source_id: String,
+
+        /// Target entity ID (sequence number like "3" or UUID prefix like "a1b2c")
+        target_id: String,
+
+        /// Relation type (implements, blocks, supersedes, references, belongs_to, documents, or a registered type)
+        #[arg(long = "type", short = 't')]
+        relation_type: String,
+
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Delete a relation between two entities
+    Delete {
+        /// Source entity ID (sequence number like "3" or UUID prefix like "a1b2c")
+        source_id: String,
+
+        /// Target entity ID (sequence number like "3" or UUID prefix like "a1b2c")
+        target_id: String,
+
+        /// Relation type (implements, blocks, supersedes, references, belongs_to, documents, or a registered type)
+        #[arg(long = "type", short = 't')]
+        relation_type: String,
+
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// List relations for an entity
+    List {
+        /// Entity ID (sequence number like "3" or UUID prefix like "a1b2c")
+        entity_id: String,
+
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Create relations from a CSV file of source, relation_type, target
+    /// rows
+    ///
+    /// Entities are given by sequence number, UUID prefix or exact title.
+    /// A header row naming the columns is optional. Every row is validated
+    /// before any relation is written; if one is invalid, none are created.
+    Import {
+        /// Path to the CSV file
+        path: PathBuf,
+
+        /// Validate the rows without creating any relations
+        #[arg(long)]
+        dry_run: bool,
+
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Add `references` relations for every `#<seq>` or `med-<seq>` mention
+    /// in existing entity content
+    Infer {
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Register, list or remove project-defined relation types
+    Type(RelationTypeCommand),
+}
+
+#[derive(Args, Debug)]
+pub struct RelationTypeCommand {
+    #[command(subcommand)]
+    pub action: RelationTypeAction,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum RelationTypeAction {
+    /// Register a relation type in the project (shared through git like
+    /// entities, unlike `relation_types` in config.yaml)
+    Add {
+        /// Type name: lowercase letters, digits and underscores (e.g. depends_on)
+        name: String,
+
+        /// Label for the relation seen from the target's end (e.g. required_by)
+        #[arg(long)]
+        inverse: Option<String>,
+
+        /// The relation reads the same from both ends
+        #[arg(long, conflicts_with = "inverse")]
+        symmetric: bool,
+    },
+
+    /// List built-in and project-defined relation types
+    List {
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Unregister a relation type no relation uses
+    Remove {
+        /// Type name
+        name: String,
+    },
+}
+
+#[derive(Args, Debug)]
+pub struct AddCommand {
+    #[command(subcommand)]
+    pub entity: AddEntity,
+
+    /// Create the entity even if one with a very similar title exists
+    #[arg(long, global = true)]
+    pub no_dup_check: bool,
+
+    /// Put the entity in a collection (e.g. "backend"), numbering it
+    /// within the collection so it can be referenced as backend/3
+    #[arg(long, global = true)]
+    pub collection: Option<String>,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum AddEntity {
+    /// Add a new decision
+    Decision {
+        /// Decision title
+        title: String,
+
+        /// Decision status (proposed, accepted, deprecated, superseded)
+        #[arg(long, default_value = "proposed")]
+        status: String,
 
         /// Tags (can be specified multiple times)
         #[arg(long = "tag", short = 't')]
@@ -289,6 +998,10 @@ pub enum AddEntity {
         #[arg(long)]
         assignee: Option<String>,
 
+        /// Recurrence: daily, weekly, monthly, "every N days|weeks|months", or a cron expression
+        #[arg(long)]
+        recur: Option<String>,
+
         /// Tags (can be specified multiple times)
         #[arg(long = "tag", short = 't')]
         tags: Vec<String>,
@@ -409,6 +1122,12 @@ pub enum AddEntity {
         #[arg(long = "type")]
         link_type: Option<String>,
 
+        /// Fetch the URL and take the title, description and favicon from
+        /// the page (on by default with `fetch_link_metadata: true` in the
+        /// config)
+        #[arg(long)]
+        fetch: bool,
+
         /// Tags (can be specified multiple times)
         #[arg(long = "tag", short = 't')]
         tags: Vec<String>,
@@ -421,6 +1140,58 @@ pub enum AddEntity {
         #[arg(long)]
         json: bool,
     },
+
+    /// Add a new open question
+    Question {
+        /// The question
+        title: String,
+
+        /// Entity that answers it (marks the question answered)
+        #[arg(long, value_name = "ID")]
+        answered_by: Option<String>,
+
+        /// Tags (can be specified multiple times)
+        #[arg(long = "tag", short = 't')]
+        tags: Vec<String>,
+
+        /// Relations in format "type:target_id"
+        #[arg(long = "relation", short = 'r')]
+        relations: Vec<String>,
+
+        /// Read content from stdin
+        #[arg(long)]
+        stdin: bool,
+
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Add a new milestone; tasks join it with a belongs_to relation
+    Milestone {
+        /// Milestone title
+        title: String,
+
+        /// Target date (YYYY-MM-DD)
+        #[arg(long, value_name = "DATE")]
+        target: Option<String>,
+
+        /// Tags (can be specified multiple times)
+        #[arg(long = "tag", short = 't')]
+        tags: Vec<String>,
+
+        /// Relations in format "type:target_id"
+        #[arg(long = "relation", short = 'r')]
+        relations: Vec<String>,
+
+        /// Read content from stdin
+        #[arg(long)]
+        stdin: bool,
+
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
 }
 
 #[derive(Args, Debug)]
@@ -444,6 +1215,412 @@ pub enum CacheAction {
         #[arg(long)]
         json: bool,
     },
+
+    /// Rebuild only the full-text search indexes (keeps embeddings and relations)
+    RebuildFts {
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+}
+
+#[derive(Args, Debug)]
+pub struct GraphCommand {
+    #[command(subcommand)]
+    pub action: GraphAction,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum GraphAction {
+    /// Render the relation graph as GraphViz DOT or Mermaid text
+    Export {
+        /// Output format: dot or mermaid
+        #[arg(long, default_value = "mermaid")]
+        format: String,
+
+        /// Only export the subgraph around this entity
+        #[arg(long, value_name = "ID")]
+        root: Option<String>,
+
+        /// Maximum hops from the root (default: everything reachable)
+        #[arg(long)]
+        depth: Option<usize>,
+
+        /// Output file (default: stdout)
+        #[arg(long, short = 'o')]
+        output: Option<PathBuf>,
+    },
+
+    /// List cycles of `blocks` relations, which keep their tasks from ever
+    /// becoming ready
+    Cycles {
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Show the most connected entities (degree and betweenness
+    /// centrality), connected groups and communities
+    Stats {
+        /// Number of hubs to show
+        #[arg(long, default_value_t = 10)]
+        limit: usize,
+
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+}
+
+#[derive(Args, Debug)]
+pub struct TemplateCommand {
+    #[command(subcommand)]
+    pub action: TemplateAction,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum TemplateAction {
+    /// Save a template
+    ///
+    /// The content skeleton (read from stdin) may use {{title}} and {{date}}
+    /// placeholders.
+    Add {
+        /// Template name
+        name: String,
+
+        /// Entity type the template creates
+        #[arg(long = "type")]
+        entity_type: String,
+
+        /// What the template is for
+        #[arg(long)]
+        description: Option<String>,
+
+        /// Default status
+        #[arg(long)]
+        status: Option<String>,
+
+        /// Default property in format "key=value" (can be specified multiple times)
+        #[arg(long = "property", short = 'p')]
+        properties: Vec<String>,
+
+        /// Default tags (can be specified multiple times)
+        #[arg(long = "tag", short = 't')]
+        tags: Vec<String>,
+
+        /// Read the content skeleton from stdin
+        #[arg(long)]
+        stdin: bool,
+
+        /// Replace an existing template with the same name
+        #[arg(long, short = 'f')]
+        force: bool,
+    },
+
+    /// List templates
+    List {
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Create an entity from a template
+    Apply {
+        /// Template name
+        name: String,
+
+        /// Title for the new entity
+        title: String,
+
+        /// Extra tags (can be specified multiple times)
+        #[arg(long = "tag", short = 't')]
+        tags: Vec<String>,
+
+        /// Property override in format "key=value" (can be specified multiple times)
+        #[arg(long = "property", short = 'p')]
+        properties: Vec<String>,
+
+        /// Read content from stdin instead of using the template's skeleton
+        #[arg(long)]
+        stdin: bool,
+
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Delete a template
+    Delete {
+        /// Template name
+        name: String,
+    },
+}
+
+#[derive(Args, Debug)]
+pub struct ImportCommand {
+    #[command(subcommand)]
+    pub action: ImportAction,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum ImportAction {
+    /// Import entities from a CSV file with a header row
+    ///
+    /// Columns name the entity fields (type, title, content, tags, and
+    /// type-specific properties like status or priority).
+    Csv {
+        /// Path to the CSV file
+        path: PathBuf,
+
+        /// Map a CSV column to an entity field, e.g. --map "Name=title" (can be specified multiple times)
+        #[arg(long = "map", value_name = "COLUMN=FIELD")]
+        mappings: Vec<String>,
+
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Import a JSON Lines file written by `medulla export`
+    ///
+    /// The project must not contain any entities yet; IDs and sequence
+    /// numbers are kept as exported.
+    Jsonl {
+        /// Path to the export file
+        path: PathBuf,
+
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Import Architecture Decision Records (MADR or Nygard Markdown)
+    ///
+    /// Each `*.md` file in the directory becomes a decision. "Supersedes"
+    /// links between the files become supersedes relations. ADRs whose
+    /// title matches an existing decision are skipped.
+    Adr {
+        /// Directory containing the ADR files, e.g. docs/adr
+        dir: PathBuf,
+
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Import open GitHub issues as tasks
+    ///
+    /// Each issue becomes a task (labels as tags, assignee kept) that
+    /// references a link to the issue. Issues imported before are skipped.
+    /// Set GITHUB_TOKEN for private repositories or a higher rate limit.
+    Github {
+        /// Repository as owner/name
+        #[arg(long)]
+        repo: String,
+
+        /// Also mark tasks done whose issue was closed, and reopen tasks
+        /// whose issue was reopened
+        #[arg(long)]
+        sync: bool,
+
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+}
+
+#[derive(Args, Debug)]
+pub struct SyncCommand {
+    #[command(subcommand)]
+    pub action: SyncAction,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum SyncAction {
+    /// Two-way sync with an Obsidian vault
+    ///
+    /// Writes every entity to <vault>/medulla/ as markdown with front matter
+    /// and relations as wikilinks. Title, content, tag and status edits made
+    /// in Obsidian are imported first, matched by the medulla_id in the front
+    /// matter. Files edited on both sides are reported and left untouched.
+    Obsidian {
+        /// Path to the Obsidian vault
+        vault: PathBuf,
+
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Merge another copy of loro.db into this project
+    ///
+    /// Entities both copies created with the same sequence number are
+    /// renumbered, as by `medulla resequence`.
+    Replica {
+        /// Path to the other loro.db
+        path: PathBuf,
+
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+}
+
+#[derive(Args, Debug)]
+pub struct BulkCommand {
+    #[command(subcommand)]
+    pub action: BulkAction,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum BulkAction {
+    /// Add and remove tags on every entity matching a filter expression
+    ///
+    /// The query uses the search filter syntax (type:, status:, tag:,
+    /// created:>, created_by:, assignee:); other words are matched with
+    /// full-text search. Archived entities are left out.
+    Tag {
+        /// Filter expression, e.g. "type:task status:done"
+        #[arg(long, short = 'q')]
+        query: String,
+
+        /// Tags to add (can be specified multiple times)
+        #[arg(long)]
+        add: Vec<String>,
+
+        /// Tags to remove (can be specified multiple times)
+        #[arg(long)]
+        remove: Vec<String>,
+
+        /// List the matching entities without changing them
+        #[arg(long)]
+        dry_run: bool,
+
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+}
+
+#[derive(Args, Debug)]
+pub struct PromptCommand {
+    #[command(subcommand)]
+    pub action: PromptAction,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum PromptAction {
+    /// Fill a prompt's template with variables and print it
+    ///
+    /// Every declared variable and {{name}} placeholder needs a value.
+    Render {
+        /// Prompt ID (sequence number or UUID prefix); picked interactively
+        /// when omitted
+        id: Option<String>,
+
+        /// Variable value as name=value (can be specified multiple times)
+        #[arg(long = "var")]
+        vars: Vec<String>,
+
+        /// Model output to check against the prompt's output schema
+        /// ("-" reads it from stdin)
+        #[arg(long, value_name = "OUTPUT")]
+        check: Option<String>,
+
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+}
+
+#[derive(Args, Debug)]
+pub struct SchemaCommand {
+    #[command(subcommand)]
+    pub action: SchemaAction,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum SchemaAction {
+    /// Print the JSON Schema of entity metadata, tool responses and tool
+    /// parameters (the same document as the medulla://schema resource)
+    Dump {
+        /// Output file (default: stdout)
+        #[arg(long, short = 'o')]
+        output: Option<PathBuf>,
+    },
+}
+
+#[derive(Args, Debug)]
+pub struct RulesCommand {
+    #[command(subcommand)]
+    pub action: RulesAction,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum RulesAction {
+    /// List default tags, tagging rules and validation rules
+    List {
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Show the tags a new entity would get and any validation rules it
+    /// would fail, without creating it
+    Test {
+        /// Entity type (decision, task, note, prompt, component, link, question, milestone)
+        #[arg(long = "type", short = 't')]
+        entity_type: String,
+
+        /// Entity title
+        title: String,
+
+        /// Entity content
+        #[arg(long)]
+        content: Option<String>,
+
+        /// URL, for links
+        #[arg(long)]
+        url: Option<String>,
+
+        /// Tags the entity is created with (can be specified multiple times)
+        #[arg(long = "tag")]
+        tags: Vec<String>,
+
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+}
+
+#[derive(Args, Debug)]
+pub struct EmbeddingsCommand {
+    #[command(subcommand)]
+    pub action: EmbeddingsAction,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum EmbeddingsAction {
+    /// Compute missing and stale embeddings, most recently updated first
+    ///
+    /// With --daemon, keeps running and backfills again whenever the store
+    /// changes, so the model download and large stores never block other
+    /// commands. Unlike `medulla daemon`, which backfills on a fixed interval
+    /// along with its other upkeep, this reacts to each change, so new
+    /// entities are searchable right away.
+    Backfill {
+        /// Keep running and backfill after every store change
+        #[arg(long)]
+        daemon: bool,
+
+        /// Milliseconds to wait for further changes before backfilling
+        #[arg(long, default_value = "500")]
+        debounce_ms: u64,
+
+        /// Output as JSON (one line per pass with --daemon)
+        #[arg(long)]
+        json: bool,
+    },
 }
 
 #[derive(Args, Debug)]
@@ -467,3 +1644,152 @@ pub enum HookAction {
     /// Check if hook is installed
     Status,
 }
+
+#[derive(Args, Debug)]
+pub struct GitCommand {
+    #[command(subcommand)]
+    pub action: GitAction,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum GitAction {
+    /// Link a commit to an entity
+    Link {
+        /// Entity ID (sequence number like "3" or UUID prefix like "a1b2c")
+        entity_id: String,
+
+        /// Commit SHA (or any revision git can resolve)
+        sha: String,
+    },
+
+    /// Link a commit to the entities its message mentions as med-<seq>
+    Scan {
+        /// Commit to scan
+        #[arg(default_value = "HEAD")]
+        rev: String,
+    },
+
+    /// List the commits linked to an entity
+    Commits {
+        /// Entity ID (sequence number like "3" or UUID prefix like "a1b2c")
+        entity_id: String,
+
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+}
+
+#[derive(Args, Debug)]
+pub struct WorkspaceCommand {
+    #[command(subcommand)]
+    pub action: WorkspaceAction,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum WorkspaceAction {
+    /// Register a project under a name
+    Add {
+        /// Name to refer to the project by
+        name: String,
+
+        /// Project directory (default: the current project)
+        path: Option<PathBuf>,
+    },
+
+    /// Unregister a project (its data is left alone)
+    Remove {
+        /// Registered project name
+        name: String,
+    },
+
+    /// List registered projects
+    List {
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum DaemonAction {
+    /// Show whether the daemon is running and how its last pass went
+    Status {
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Stop the running daemon
+    Stop,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum SnapshotAction {
+    /// List recorded snapshot runs, newest first
+    List {
+        /// Only list tagged snapshots
+        #[arg(long)]
+        tagged: bool,
+
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Print a markdown changelog of the entities added, changed and
+    /// removed since an older version of the store
+    Diff {
+        /// What to compare against: a git revision (e.g. HEAD) whose
+        /// committed `.medulla/loro.db` is the old store, a date or window
+        /// (2024-06-01, 7d, week) to use the store's own history, a copy
+        /// of a `loro.db` file, or a previously generated snapshot
+        /// directory
+        old_ref: String,
+
+        /// Output file (default: stdout)
+        #[arg(long, short = 'o')]
+        output: Option<PathBuf>,
+
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+}
+
+#[derive(Args, Debug)]
+pub struct TrashCommand {
+    #[command(subcommand)]
+    pub action: TrashAction,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum TrashAction {
+    /// List deleted entities
+    List {
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Restore a deleted entity and its relations
+    Restore {
+        /// Entity ID (sequence number like "3" or UUID prefix like "a1b2c")
+        id: String,
+    },
+
+    /// Permanently remove entities from the trash
+    Purge {
+        /// Entity ID to purge (omit with --all to empty the trash)
+        #[arg(required_unless_present = "all", conflicts_with = "all")]
+        id: Option<String>,
+
+        /// Purge every entity in the trash
+        #[arg(long)]
+        all: bool,
+
+        /// Skip confirmation prompt
+        #[arg(long, short = 'f')]
+        force: bool,
+    },
+}
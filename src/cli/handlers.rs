@@ -1,16 +1,20 @@
 use std::env;
 use std::io::{self, Read};
-use std::path::PathBuf;
-use std::time::Duration;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
 
 use crate::cache::SqliteCache;
 use crate::embeddings::Embedder;
 use crate::entity::{
-    Component, ComponentStatus, Decision, DecisionStatus, Link, Note, Prompt, Relation,
-    RelationType, Task, TaskStatus,
+    CommitLink, Component, ComponentStatus, Decision, DecisionStatus, Link, Milestone,
+    MilestoneStatus, Note, Prompt, Question, QuestionStatus, Relation, RelationType, Task,
+    TaskStatus,
 };
 use crate::error::{MedullaError, Result};
 use crate::mcp::MedullaServer;
+
+use super::interactive::{self, PickItem};
+use super::output::{OutputFormat, Table};
 use std::sync::OnceLock;
 
 /// Lazy-initialized embedding model for CLI.
@@ -22,7 +26,8 @@ fn get_embedder() -> Option<&'static Embedder> {
     result.as_ref().ok()
 }
 use crate::storage::{
-    ComponentUpdate, DecisionUpdate, LinkUpdate, LoroStore, NoteUpdate, PromptUpdate, TaskUpdate,
+    ComponentUpdate, DecisionUpdate, LinkUpdate, LoroStore, MilestoneUpdate, NoteUpdate,
+    PromptUpdate, QuestionUpdate, TaskUpdate,
 };
 
 /// Reference to any entity type in the system
@@ -34,82 +39,111 @@ enum EntityRef {
     Prompt(Prompt),
     Component(Component),
     Link(Link),
+    Question(Question),
+    Milestone(Milestone),
 }
 
-/// Find an entity by ID (sequence number or UUID prefix) across all entity types
-fn find_entity_by_id(store: &LoroStore, id: &str) -> Result<EntityRef> {
-    // Try to parse as sequence number first
-    if let Ok(seq) = id.parse::<u32>() {
-        // Search through all entity types by sequence number
-        for decision in store.list_decisions()? {
-            if decision.base.sequence_number == seq {
-                return Ok(EntityRef::Decision(decision));
-            }
-        }
-        for task in store.list_tasks()? {
-            if task.base.sequence_number == seq {
-                return Ok(EntityRef::Task(task));
-            }
-        }
-        for note in store.list_notes()? {
-            if note.base.sequence_number == seq {
-                return Ok(EntityRef::Note(note));
-            }
-        }
-        for prompt in store.list_prompts()? {
-            if prompt.base.sequence_number == seq {
-                return Ok(EntityRef::Prompt(prompt));
-            }
-        }
-        for component in store.list_components()? {
-            if component.base.sequence_number == seq {
-                return Ok(EntityRef::Component(component));
-            }
-        }
-        for link in store.list_links()? {
-            if link.base.sequence_number == seq {
-                return Ok(EntityRef::Link(link));
-            }
-        }
-    } else {
-        // Search by UUID prefix
-        for decision in store.list_decisions()? {
-            if decision.base.id.to_string().starts_with(id) {
-                return Ok(EntityRef::Decision(decision));
-            }
-        }
-        for task in store.list_tasks()? {
-            if task.base.id.to_string().starts_with(id) {
-                return Ok(EntityRef::Task(task));
-            }
-        }
-        for note in store.list_notes()? {
-            if note.base.id.to_string().starts_with(id) {
-                return Ok(EntityRef::Note(note));
-            }
-        }
-        for prompt in store.list_prompts()? {
-            if prompt.base.id.to_string().starts_with(id) {
-                return Ok(EntityRef::Prompt(prompt));
-            }
-        }
-        for component in store.list_components()? {
-            if component.base.id.to_string().starts_with(id) {
-                return Ok(EntityRef::Component(component));
-            }
-        }
-        for link in store.list_links()? {
-            if link.base.id.to_string().starts_with(id) {
-                return Ok(EntityRef::Link(link));
-            }
+impl EntityRef {
+    fn base(&self) -> &crate::entity::EntityBase {
+        match self {
+            EntityRef::Decision(d) => &d.base,
+            EntityRef::Task(t) => &t.base,
+            EntityRef::Note(n) => &n.base,
+            EntityRef::Prompt(p) => &p.base,
+            EntityRef::Component(c) => &c.base,
+            EntityRef::Link(l) => &l.base,
+            EntityRef::Question(q) => &q.base,
+            EntityRef::Milestone(m) => &m.base,
         }
     }
+}
+
+/// Find an entity by ID (sequence number, alias or UUID prefix) across all
+/// entity types
+fn find_entity_by_id(store: &LoroStore, id: &str) -> Result<EntityRef> {
+    let not_found = || MedullaError::EntityNotFound(id.to_string());
+    let (uuid, entity_type) = store.lookup_id(id, None).ok_or_else(not_found)?;
+    let entity = match entity_type {
+        "decision" => store.get_decision(&uuid)?.map(EntityRef::Decision),
+        "task" => store.get_task(&uuid)?.map(EntityRef::Task),
+        "note" => store.get_note(&uuid)?.map(EntityRef::Note),
+        "prompt" => store.get_prompt(&uuid)?.map(EntityRef::Prompt),
+        "component" => store.get_component(&uuid)?.map(EntityRef::Component),
+        "link" => store.get_link(&uuid)?.map(EntityRef::Link),
+        "question" => store.get_question(&uuid)?.map(EntityRef::Question),
+        "milestone" => store.get_milestone(&uuid)?.map(EntityRef::Milestone),
+        _ => None,
+    };
+    entity.ok_or_else(not_found)
+}
+
+/// The ID given on the command line, or one picked interactively from the
+/// entities (of `entity_type`, if given) when it was left out.
+pub fn resolve_id_arg(
+    data_dir: Option<&Path>,
+    id: Option<String>,
+    entity_type: Option<&str>,
+) -> Result<String> {
+    if let Some(id) = id {
+        return Ok(id);
+    }
+    let root = find_project_root(data_dir);
+    let store = LoroStore::open(&root)?;
+
+    let items: Vec<PickItem> = store
+        .entity_values()?
+        .into_iter()
+        .filter(|(t, value)| {
+            entity_type.map_or(true, |wanted| *t == wanted)
+                && value.get("archived").and_then(|v| v.as_bool()) != Some(true)
+        })
+        .map(|(t, value)| PickItem {
+            sequence_number: value
+                .get("sequence_number")
+                .and_then(|v| v.as_u64())
+                .unwrap_or_default() as u32,
+            entity_type: t.to_string(),
+            title: value
+                .get("title")
+                .and_then(|v| v.as_str())
+                .unwrap_or_default()
+                .to_string(),
+            detail: value
+                .get("status")
+                .and_then(|v| v.as_str())
+                .map(str::to_string),
+        })
+        .collect();
+
+    let what = entity_type.unwrap_or("entity");
+    match interactive::pick(&items, what)? {
+        Some(item) => Ok(item.sequence_number.to_string()),
+        None => Err(MedullaError::Storage("Cancelled".to_string())),
+    }
+}
 
-    Err(MedullaError::EntityNotFound(id.to_string()))
+/// Print a shell completion script for `medulla` to stdout.
+pub fn handle_completions(shell: clap_complete::Shell) -> Result<()> {
+    use clap::CommandFactory;
+
+    let mut command = super::Cli::command();
+    clap_complete::generate(shell, &mut command, "medulla", &mut io::stdout());
+    Ok(())
 }
 
 /// Find the project root by looking for .medulla/ or .git/
-fn find_project_root() -> PathBuf {
+fn find_project_root(data_dir: Option<&Path>) -> PathBuf {
+    // An explicit --data-dir overrides discovery. Accept either the project
+    // directory or the .medulla directory itself.
+    if let Some(dir) = data_dir {
+        if dir.file_name().is_some_and(|name| name == ".medulla") {
+            if let Some(parent) = dir.parent() {
+                return parent.to_path_buf();
+            }
+        }
+        return dir.to_path_buf();
+    }
+
     let cwd = env::current_dir().unwrap_or_else(|_| PathBuf::from("."));
 
     let mut current = cwd.as_path();
@@ -124,8 +158,11 @@ fn find_project_root() -> PathBuf {
     }
 }
 
-pub fn handle_init(yes: bool, _no: bool) -> Result<()> {
-    let root = env::current_dir()?;
+pub fn handle_init(data_dir: Option<&Path>, yes: bool, _no: bool) -> Result<()> {
+    let root = match data_dir {
+        Some(dir) => dir.to_path_buf(),
+        None => env::current_dir()?,
+    };
 
     let _store = LoroStore::init(&root)?;
 
@@ -139,16 +176,19 @@ pub fn handle_init(yes: bool, _no: bool) -> Result<()> {
     Ok(())
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn handle_add_decision(
+    data_dir: Option<&Path>,
     title: String,
     status: String,
     tags: Vec<String>,
     relations: Vec<String>,
     stdin: bool,
     edit: bool,
+    collection: Option<String>,
     json: bool,
 ) -> Result<()> {
-    let root = find_project_root();
+    let root = find_project_root(data_dir);
     let store = LoroStore::open(&root)?;
 
     let seq = store.next_sequence_number();
@@ -178,7 +218,21 @@ pub fn handle_add_decision(
     let git_author = get_git_author();
     decision.base.created_by = git_author.clone();
 
+    decision
+        .base
+        .tags
+        .extend(auto_tags(&store, "decision", &decision)?);
     store.add_decision(&decision)?;
+    if let Some(ref collection) = collection {
+        store.set_collection(&decision.base.id, collection)?;
+    }
+    add_inferred_references(
+        &store,
+        decision.base.id,
+        "decision",
+        decision.base.content.as_deref(),
+        &git_author,
+    )?;
 
     // Handle relations after decision is added
     for rel_str in &relations {
@@ -220,17 +274,25 @@ pub fn handle_add_decision(
 
 #[allow(clippy::too_many_arguments)]
 pub fn handle_add_task(
+    data_dir: Option<&Path>,
     title: String,
     status: String,
     priority: String,
     due: Option<String>,
     assignee: Option<String>,
+    recur: Option<String>,
     tags: Vec<String>,
     relations: Vec<String>,
     stdin: bool,
+    collection: Option<String>,
     json: bool,
 ) -> Result<()> {
-    let root = find_project_root();
+    let recurrence = match recur {
+        Some(r) => Some(r.parse().map_err(MedullaError::Storage)?),
+        None => None,
+    };
+
+    let root = find_project_root(data_dir);
     let store = LoroStore::open(&root)?;
 
     let seq = store.next_sequence_number();
@@ -240,6 +302,7 @@ pub fn handle_add_task(
     task.priority = priority.parse().unwrap_or_default();
     task.due_date = due.and_then(|d| chrono::NaiveDate::parse_from_str(&d, "%Y-%m-%d").ok());
     task.assignee = assignee;
+    task.recurrence = recurrence;
     task.base.tags = tags;
 
     if stdin {
@@ -253,8 +316,19 @@ pub fn handle_add_task(
     let git_author = get_git_author();
     task.base.created_by = git_author.clone();
 
+    task.base.tags.extend(auto_tags(&store, "task", &task)?);
     store.add_task(&task)?;
+    if let Some(ref collection) = collection {
+        store.set_collection(&task.base.id, collection)?;
+    }
     add_relations_for_entity(&store, task.base.id, "task", &relations, &git_author)?;
+    add_inferred_references(
+        &store,
+        task.base.id,
+        "task",
+        task.base.content.as_deref(),
+        &git_author,
+    )?;
     store.save()?;
 
     if json {
@@ -271,15 +345,18 @@ pub fn handle_add_task(
     Ok(())
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn handle_add_note(
+    data_dir: Option<&Path>,
     title: String,
     note_type: Option<String>,
     tags: Vec<String>,
     relations: Vec<String>,
     stdin: bool,
+    collection: Option<String>,
     json: bool,
 ) -> Result<()> {
-    let root = find_project_root();
+    let root = find_project_root(data_dir);
     let store = LoroStore::open(&root)?;
 
     let seq = store.next_sequence_number();
@@ -299,8 +376,19 @@ pub fn handle_add_note(
     let git_author = get_git_author();
     note.base.created_by = git_author.clone();
 
+    note.base.tags.extend(auto_tags(&store, "note", &note)?);
     store.add_note(&note)?;
+    if let Some(ref collection) = collection {
+        store.set_collection(&note.base.id, collection)?;
+    }
     add_relations_for_entity(&store, note.base.id, "note", &relations, &git_author)?;
+    add_inferred_references(
+        &store,
+        note.base.id,
+        "note",
+        note.base.content.as_deref(),
+        &git_author,
+    )?;
     store.save()?;
 
     if json {
@@ -317,16 +405,19 @@ pub fn handle_add_note(
     Ok(())
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn handle_add_prompt(
+    data_dir: Option<&Path>,
     title: String,
     template: Option<String>,
     variables: Vec<String>,
     output_schema: Option<String>,
     tags: Vec<String>,
     stdin: bool,
+    collection: Option<String>,
     json: bool,
 ) -> Result<()> {
-    let root = find_project_root();
+    let root = find_project_root(data_dir);
     let store = LoroStore::open(&root)?;
 
     let seq = store.next_sequence_number();
@@ -348,9 +439,23 @@ pub fn handle_add_prompt(
     }
 
     let git_author = get_git_author();
-    prompt.base.created_by = git_author;
+    prompt.base.created_by = git_author.clone();
 
+    prompt
+        .base
+        .tags
+        .extend(auto_tags(&store, "prompt", &prompt)?);
     store.add_prompt(&prompt)?;
+    if let Some(ref collection) = collection {
+        store.set_collection(&prompt.base.id, collection)?;
+    }
+    add_inferred_references(
+        &store,
+        prompt.base.id,
+        "prompt",
+        prompt.template.as_deref(),
+        &git_author,
+    )?;
     store.save()?;
 
     if json {
@@ -369,6 +474,7 @@ pub fn handle_add_prompt(
 
 #[allow(clippy::too_many_arguments)]
 pub fn handle_add_component(
+    data_dir: Option<&Path>,
     title: String,
     component_type: Option<String>,
     status: String,
@@ -376,9 +482,10 @@ pub fn handle_add_component(
     tags: Vec<String>,
     relations: Vec<String>,
     stdin: bool,
+    collection: Option<String>,
     json: bool,
 ) -> Result<()> {
-    let root = find_project_root();
+    let root = find_project_root(data_dir);
     let store = LoroStore::open(&root)?;
 
     let seq = store.next_sequence_number();
@@ -400,7 +507,14 @@ pub fn handle_add_component(
     let git_author = get_git_author();
     component.base.created_by = git_author.clone();
 
+    component
+        .base
+        .tags
+        .extend(auto_tags(&store, "component", &component)?);
     store.add_component(&component)?;
+    if let Some(ref collection) = collection {
+        store.set_collection(&component.base.id, collection)?;
+    }
     add_relations_for_entity(
         &store,
         component.base.id,
@@ -408,6 +522,13 @@ pub fn handle_add_component(
         &relations,
         &git_author,
     )?;
+    add_inferred_references(
+        &store,
+        component.base.id,
+        "component",
+        component.base.content.as_deref(),
+        &git_author,
+    )?;
     store.save()?;
 
     if json {
@@ -424,16 +545,21 @@ pub fn handle_add_component(
     Ok(())
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn handle_add_link(
+    data_dir: Option<&Path>,
     title: String,
     url: String,
     link_type: Option<String>,
+    fetch: bool,
     tags: Vec<String>,
     relations: Vec<String>,
+    collection: Option<String>,
     json: bool,
 ) -> Result<()> {
-    let root = find_project_root();
+    let root = find_project_root(data_dir);
     let store = LoroStore::open(&root)?;
+    let config = crate::config::ProjectConfig::load(store.medulla_dir())?;
 
     let seq = store.next_sequence_number();
     let mut link = Link::new(title, url, seq);
@@ -441,11 +567,30 @@ pub fn handle_add_link(
     link.link_type = link_type;
     link.base.tags = tags;
 
+    // An unreachable page leaves the link as given
+    if fetch || config.fetch_link_metadata {
+        match crate::fetch::fetch_page(&link.url) {
+            Ok(page) => page.apply_to(&mut link),
+            Err(e) => eprintln!("Warning: could not fetch {}: {}", link.url, e),
+        }
+    }
+
     let git_author = get_git_author();
     link.base.created_by = git_author.clone();
 
+    link.base.tags.extend(auto_tags(&store, "link", &link)?);
     store.add_link(&link)?;
+    if let Some(ref collection) = collection {
+        store.set_collection(&link.base.id, collection)?;
+    }
     add_relations_for_entity(&store, link.base.id, "link", &relations, &git_author)?;
+    add_inferred_references(
+        &store,
+        link.base.id,
+        "link",
+        link.base.content.as_deref(),
+        &git_author,
+    )?;
     store.save()?;
 
     if json {
@@ -462,6 +607,157 @@ pub fn handle_add_link(
     Ok(())
 }
 
+#[allow(clippy::too_many_arguments)]
+pub fn handle_add_question(
+    data_dir: Option<&Path>,
+    title: String,
+    answered_by: Option<String>,
+    tags: Vec<String>,
+    relations: Vec<String>,
+    stdin: bool,
+    collection: Option<String>,
+    json: bool,
+) -> Result<()> {
+    let root = find_project_root(data_dir);
+    let store = LoroStore::open(&root)?;
+
+    let seq = store.next_sequence_number();
+    let mut question = Question::new(title, seq);
+
+    question.base.tags = tags;
+    if let Some(reference) = answered_by {
+        question.answered_by = Some(find_entity_by_id(&store, &reference)?.base().id.to_string());
+        question.status = QuestionStatus::Answered;
+    }
+
+    if stdin {
+        let mut content = String::new();
+        io::stdin().read_to_string(&mut content)?;
+        if !content.is_empty() {
+            question.base.content = Some(content);
+        }
+    }
+
+    let git_author = get_git_author();
+    question.base.created_by = git_author.clone();
+
+    question
+        .base
+        .tags
+        .extend(auto_tags(&store, "question", &question)?);
+    store.add_question(&question)?;
+    if let Some(ref collection) = collection {
+        store.set_collection(&question.base.id, collection)?;
+    }
+    add_relations_for_entity(
+        &store,
+        question.base.id,
+        "question",
+        &relations,
+        &git_author,
+    )?;
+    add_inferred_references(
+        &store,
+        question.base.id,
+        "question",
+        question.base.content.as_deref(),
+        &git_author,
+    )?;
+    store.save()?;
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&question)?);
+    } else {
+        println!(
+            "Created question {:03} ({}) - {}",
+            question.base.sequence_number,
+            &question.base.id.to_string()[..7],
+            question.base.title
+        );
+    }
+
+    Ok(())
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn handle_add_milestone(
+    data_dir: Option<&Path>,
+    title: String,
+    target: Option<String>,
+    tags: Vec<String>,
+    relations: Vec<String>,
+    stdin: bool,
+    collection: Option<String>,
+    json: bool,
+) -> Result<()> {
+    let root = find_project_root(data_dir);
+    let store = LoroStore::open(&root)?;
+
+    let seq = store.next_sequence_number();
+    let mut milestone = Milestone::new(title, seq);
+
+    milestone.base.tags = tags;
+    milestone.target_date = target.as_deref().map(parse_target_date).transpose()?;
+
+    if stdin {
+        let mut content = String::new();
+        io::stdin().read_to_string(&mut content)?;
+        if !content.is_empty() {
+            milestone.base.content = Some(content);
+        }
+    }
+
+    let git_author = get_git_author();
+    milestone.base.created_by = git_author.clone();
+
+    milestone
+        .base
+        .tags
+        .extend(auto_tags(&store, "milestone", &milestone)?);
+    store.add_milestone(&milestone)?;
+    if let Some(ref collection) = collection {
+        store.set_collection(&milestone.base.id, collection)?;
+    }
+    add_relations_for_entity(
+        &store,
+        milestone.base.id,
+        "milestone",
+        &relations,
+        &git_author,
+    )?;
+    add_inferred_references(
+        &store,
+        milestone.base.id,
+        "milestone",
+        milestone.base.content.as_deref(),
+        &git_author,
+    )?;
+    store.save()?;
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&milestone)?);
+    } else {
+        println!(
+            "Created milestone {:03} ({}) - {}",
+            milestone.base.sequence_number,
+            &milestone.base.id.to_string()[..7],
+            milestone.base.title
+        );
+    }
+
+    Ok(())
+}
+
+/// Parse a milestone target date given as YYYY-MM-DD.
+fn parse_target_date(date: &str) -> Result<chrono::NaiveDate> {
+    chrono::NaiveDate::parse_from_str(date, "%Y-%m-%d").map_err(|_| {
+        MedullaError::Storage(format!(
+            "Invalid target date '{}', expected YYYY-MM-DD",
+            date
+        ))
+    })
+}
+
 /// Helper to add relations for any entity type
 fn add_relations_for_entity(
     store: &LoroStore,
@@ -493,17 +789,59 @@ fn add_relations_for_entity(
     Ok(())
 }
 
-pub fn handle_list(entity_type: Option<String>, json: bool) -> Result<()> {
-    let root = find_project_root();
+/// Helper to add `references` relations for the `#<seq>` and `med-<seq>`
+/// mentions in newly written content
+fn add_inferred_references(
+    store: &LoroStore,
+    source_id: uuid::Uuid,
+    source_type: &str,
+    content: Option<&str>,
+    git_author: &Option<String>,
+) -> Result<()> {
+    if let Some(content) = content {
+        store.add_inferred_references(source_id, source_type, content, git_author.clone())?;
+    }
+    Ok(())
+}
+
+pub fn handle_list(
+    data_dir: Option<&Path>,
+    entity_type: Option<String>,
+    format: OutputFormat,
+    include_archived: bool,
+    collection: Option<String>,
+) -> Result<()> {
+    let root = find_project_root(data_dir);
     let store = LoroStore::open(&root)?;
 
+    let members = collection.map(|c| store.collection_members(&c));
+    let keep = |base: &crate::entity::EntityBase| {
+        (include_archived || !base.archived)
+            && members
+                .as_ref()
+                .map_or(true, |m| m.contains(&base.id.to_string()))
+    };
+
     let entity_type = entity_type.as_deref().unwrap_or("decision");
 
     match entity_type {
         "decision" | "decisions" => {
-            let decisions = store.list_decisions()?;
-            if json {
+            let mut decisions = store.list_decisions()?;
+            decisions.retain(|e| keep(&e.base));
+            if format == OutputFormat::Json {
                 println!("{}", serde_json::to_string_pretty(&decisions)?);
+            } else if format.is_table() {
+                let mut table = Table::new(&["seq", "id", "status", "title", "tags"]);
+                for d in &decisions {
+                    table.push(vec![
+                        d.base.sequence_number.to_string(),
+                        d.base.id.to_string(),
+                        d.status.to_string(),
+                        d.base.title.clone(),
+                        d.base.tags.join(", "),
+                    ]);
+                }
+                table.print(format)?;
             } else if decisions.is_empty() {
                 println!("No decisions found.");
             } else {
@@ -523,9 +861,26 @@ pub fn handle_list(entity_type: Option<String>, json: bool) -> Result<()> {
             }
         }
         "task" | "tasks" => {
-            let tasks = store.list_tasks()?;
-            if json {
+            let mut tasks = store.list_tasks()?;
+            tasks.retain(|e| keep(&e.base));
+            if format == OutputFormat::Json {
                 println!("{}", serde_json::to_string_pretty(&tasks)?);
+            } else if format.is_table() {
+                let mut table = Table::new(&[
+                    "seq", "id", "status", "priority", "due", "assignee", "title",
+                ]);
+                for t in &tasks {
+                    table.push(vec![
+                        t.base.sequence_number.to_string(),
+                        t.base.id.to_string(),
+                        t.status.to_string(),
+                        t.priority.to_string(),
+                        t.due_date.map(|d| d.to_string()).unwrap_or_default(),
+                        t.assignee.clone().unwrap_or_default(),
+                        t.base.title.clone(),
+                    ]);
+                }
+                table.print(format)?;
             } else if tasks.is_empty() {
                 println!("No tasks found.");
             } else {
@@ -548,9 +903,22 @@ pub fn handle_list(entity_type: Option<String>, json: bool) -> Result<()> {
             }
         }
         "note" | "notes" => {
-            let notes = store.list_notes()?;
-            if json {
+            let mut notes = store.list_notes()?;
+            notes.retain(|e| keep(&e.base));
+            if format == OutputFormat::Json {
                 println!("{}", serde_json::to_string_pretty(&notes)?);
+            } else if format.is_table() {
+                let mut table = Table::new(&["seq", "id", "type", "title", "tags"]);
+                for n in &notes {
+                    table.push(vec![
+                        n.base.sequence_number.to_string(),
+                        n.base.id.to_string(),
+                        n.note_type.clone().unwrap_or_default(),
+                        n.base.title.clone(),
+                        n.base.tags.join(", "),
+                    ]);
+                }
+                table.print(format)?;
             } else if notes.is_empty() {
                 println!("No notes found.");
             } else {
@@ -568,9 +936,21 @@ pub fn handle_list(entity_type: Option<String>, json: bool) -> Result<()> {
             }
         }
         "prompt" | "prompts" => {
-            let prompts = store.list_prompts()?;
-            if json {
+            let mut prompts = store.list_prompts()?;
+            prompts.retain(|e| keep(&e.base));
+            if format == OutputFormat::Json {
                 println!("{}", serde_json::to_string_pretty(&prompts)?);
+            } else if format.is_table() {
+                let mut table = Table::new(&["seq", "id", "title", "variables"]);
+                for p in &prompts {
+                    table.push(vec![
+                        p.base.sequence_number.to_string(),
+                        p.base.id.to_string(),
+                        p.base.title.clone(),
+                        p.variables.join(", "),
+                    ]);
+                }
+                table.print(format)?;
             } else if prompts.is_empty() {
                 println!("No prompts found.");
             } else {
@@ -592,9 +972,23 @@ pub fn handle_list(entity_type: Option<String>, json: bool) -> Result<()> {
             }
         }
         "component" | "components" => {
-            let components = store.list_components()?;
-            if json {
+            let mut components = store.list_components()?;
+            components.retain(|e| keep(&e.base));
+            if format == OutputFormat::Json {
                 println!("{}", serde_json::to_string_pretty(&components)?);
+            } else if format.is_table() {
+                let mut table = Table::new(&["seq", "id", "type", "status", "owner", "title"]);
+                for c in &components {
+                    table.push(vec![
+                        c.base.sequence_number.to_string(),
+                        c.base.id.to_string(),
+                        c.component_type.clone().unwrap_or_default(),
+                        c.status.to_string(),
+                        c.owner.clone().unwrap_or_default(),
+                        c.base.title.clone(),
+                    ]);
+                }
+                table.print(format)?;
             } else if components.is_empty() {
                 println!("No components found.");
             } else {
@@ -613,9 +1007,22 @@ pub fn handle_list(entity_type: Option<String>, json: bool) -> Result<()> {
             }
         }
         "link" | "links" => {
-            let links = store.list_links()?;
-            if json {
+            let mut links = store.list_links()?;
+            links.retain(|e| keep(&e.base));
+            if format == OutputFormat::Json {
                 println!("{}", serde_json::to_string_pretty(&links)?);
+            } else if format.is_table() {
+                let mut table = Table::new(&["seq", "id", "type", "title", "url"]);
+                for l in &links {
+                    table.push(vec![
+                        l.base.sequence_number.to_string(),
+                        l.base.id.to_string(),
+                        l.link_type.clone().unwrap_or_default(),
+                        l.base.title.clone(),
+                        l.url.clone(),
+                    ]);
+                }
+                table.print(format)?;
             } else if links.is_empty() {
                 println!("No links found.");
             } else {
@@ -631,9 +1038,19 @@ pub fn handle_list(entity_type: Option<String>, json: bool) -> Result<()> {
                 }
             }
         }
+        "question" | "questions" => {
+            let mut questions = store.list_questions()?;
+            questions.retain(|e| keep(&e.base));
+            print_questions(&store, &questions, format)?;
+        }
+        "milestone" | "milestones" => {
+            let mut milestones = store.list_milestones()?;
+            milestones.retain(|e| keep(&e.base));
+            print_milestones(&milestones, format)?;
+        }
         _ => {
             eprintln!(
-                "Unknown entity type '{}'. Valid types: decision, task, note, prompt, component, link",
+                "Unknown entity type '{}'. Valid types: decision, task, note, prompt, component, link, question, milestone",
                 entity_type
             );
         }
@@ -642,25 +1059,149 @@ pub fn handle_list(entity_type: Option<String>, json: bool) -> Result<()> {
     Ok(())
 }
 
-pub fn handle_get(id: String, json: bool) -> Result<()> {
-    let root = find_project_root();
+/// Print milestones as JSON, a table or a plain list.
+fn print_milestones(milestones: &[Milestone], format: OutputFormat) -> Result<()> {
+    if format == OutputFormat::Json {
+        println!("{}", serde_json::to_string_pretty(milestones)?);
+    } else if format.is_table() {
+        let mut table = Table::new(&["seq", "id", "status", "target", "title"]);
+        for m in milestones {
+            table.push(vec![
+                m.base.sequence_number.to_string(),
+                m.base.id.to_string(),
+                m.status.to_string(),
+                m.target_date.map(|d| d.to_string()).unwrap_or_default(),
+                m.base.title.clone(),
+            ]);
+        }
+        table.print(format)?;
+    } else if milestones.is_empty() {
+        println!("No milestones found.");
+    } else {
+        println!("Milestones:\n");
+        for m in milestones {
+            let target_str = m
+                .target_date
+                .map(|d| format!(" target:{}", d))
+                .unwrap_or_default();
+            println!(
+                "  {:03} ({}) [{}] {}{}",
+                m.base.sequence_number,
+                &m.base.id.to_string()[..7],
+                m.status,
+                m.base.title,
+                target_str
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Print questions as JSON, a table or a plain list. The answering entity is
+/// shown by sequence number when it still exists.
+fn print_questions(store: &LoroStore, questions: &[Question], format: OutputFormat) -> Result<()> {
+    let answer_label = |q: &Question| -> String {
+        q.answered_by
+            .as_deref()
+            .map(|id| match find_entity_by_id(store, id) {
+                Ok(entity) => format!("{:03}", entity.base().sequence_number),
+                Err(_) => id[..id.len().min(7)].to_string(),
+            })
+            .unwrap_or_default()
+    };
+
+    if format == OutputFormat::Json {
+        println!("{}", serde_json::to_string_pretty(questions)?);
+    } else if format.is_table() {
+        let mut table = Table::new(&["seq", "id", "status", "answered_by", "title"]);
+        for q in questions {
+            table.push(vec![
+                q.base.sequence_number.to_string(),
+                q.base.id.to_string(),
+                q.status.to_string(),
+                answer_label(q),
+                q.base.title.clone(),
+            ]);
+        }
+        table.print(format)?;
+    } else if questions.is_empty() {
+        println!("No questions found.");
+    } else {
+        println!("Questions:\n");
+        for q in questions {
+            let answer = answer_label(q);
+            let answer_str = if answer.is_empty() {
+                String::new()
+            } else {
+                format!(" -> {}", answer)
+            };
+            println!(
+                "  {:03} ({}) [{}] {}{}",
+                q.base.sequence_number,
+                &q.base.id.to_string()[..7],
+                q.status,
+                q.base.title,
+                answer_str
+            );
+        }
+    }
+    Ok(())
+}
+
+pub fn handle_get(
+    data_dir: Option<&Path>,
+    id: String,
+    relations: bool,
+    related_depth: Option<usize>,
+    json: bool,
+) -> Result<()> {
+    // Capped as for `entity_get`, since each hop can fan out widely
+    let related_depth = related_depth.map(|depth| depth.clamp(1, crate::graph::MAX_RELATED_DEPTH));
+    let root = find_project_root(data_dir);
     let store = LoroStore::open(&root)?;
 
     let decisions = store.list_decisions()?;
 
-    // Try to find by sequence number first, then by UUID prefix
-    let decision = if let Ok(seq) = id.parse::<u32>() {
-        decisions.iter().find(|d| d.base.sequence_number == seq)
-    } else {
-        decisions
-            .iter()
-            .find(|d| d.base.id.to_string().starts_with(&id))
-    };
+    let decision = store
+        .lookup_id(&id, Some("decision"))
+        .and_then(|(uuid, _)| decisions.iter().find(|d| d.base.id == uuid));
 
     match decision {
         Some(d) => {
+            let (outgoing, incoming) = if relations {
+                let id = d.base.id.to_string();
+                (store.get_relations_from(&id)?, store.get_relations_to(&id)?)
+            } else {
+                (Vec::new(), Vec::new())
+            };
+            let related = match related_depth {
+                Some(depth) => crate::graph::related_entities(&store, d.base.id, depth)?,
+                None => Vec::new(),
+            };
+
             if json {
-                println!("{}", serde_json::to_string_pretty(&d)?);
+                let mut value = serde_json::to_value(d)?;
+                if relations {
+                    value["relations"] = serde_json::json!({
+                        "outgoing": outgoing,
+                        "incoming": incoming,
+                    });
+                }
+                if related_depth.is_some() {
+                    value["related_entities"] = related
+                        .iter()
+                        .map(|(n, distance)| {
+                            serde_json::json!({
+                                "id": n.id.to_string(),
+                                "type": n.entity_type,
+                                "sequence_number": n.sequence_number,
+                                "title": n.title,
+                                "distance": distance,
+                            })
+                        })
+                        .collect();
+                }
+                println!("{}", serde_json::to_string_pretty(&value)?);
             } else {
                 println!("Decision {:03} ({})", d.base.sequence_number, d.base.id);
                 println!("Title: {}", d.base.title);
@@ -672,6 +1213,48 @@ pub fn handle_get(id: String, json: bool) -> Result<()> {
                 if !d.base.tags.is_empty() {
                     println!("Tags: {}", d.base.tags.join(", "));
                 }
+                let attachments = store.list_attachments(Some(&d.base.id))?;
+                if !attachments.is_empty() {
+                    println!("Attachments:");
+                    for a in &attachments {
+                        println!(
+                            "  {} ({}, {} bytes) .medulla/attachments/{}",
+                            a.filename,
+                            a.mime,
+                            a.size,
+                            a.relative_path()
+                        );
+                    }
+                }
+                if relations {
+                    println!("Relations:");
+                    if outgoing.is_empty() && incoming.is_empty() {
+                        println!("  (none)");
+                    }
+                    for r in &outgoing {
+                        println!(
+                            "  -> {} {}",
+                            r.relation_type,
+                            get_entity_title(&store, &r.target_id)
+                        );
+                    }
+                    for r in &incoming {
+                        println!(
+                            "  <- {} {}",
+                            r.relation_type,
+                            get_entity_title(&store, &r.source_id)
+                        );
+                    }
+                }
+                if let Some(depth) = related_depth {
+                    println!("Related (within {} hops):", depth);
+                    if related.is_empty() {
+                        println!("  (none)");
+                    }
+                    for (n, distance) in &related {
+                        println!("  {}  {}", distance, n.label());
+                    }
+                }
                 if let Some(ref content) = d.base.content {
                     println!("\n{}", content);
                 }
@@ -685,30 +1268,245 @@ pub fn handle_get(id: String, json: bool) -> Result<()> {
     Ok(())
 }
 
-fn get_git_author() -> Option<String> {
-    std::process::Command::new("git")
-        .args(["config", "user.name"])
-        .output()
-        .ok()
-        .and_then(|output| {
-            if output.status.success() {
-                String::from_utf8(output.stdout)
-                    .ok()
-                    .map(|s| s.trim().to_string())
-                    .filter(|s| !s.is_empty())
-            } else {
-                None
-            }
-        })
-}
+pub fn handle_history(
+    data_dir: Option<&Path>,
+    id: String,
+    at: Option<String>,
+    json: bool,
+) -> Result<()> {
+    let at = match at {
+        Some(at) => Some(parse_history_time(&at).ok_or_else(|| {
+            MedullaError::Storage(format!(
+                "Invalid time '{}', expected RFC 3339 or YYYY-MM-DD",
+                at
+            ))
+        })?),
+        None => None,
+    };
 
-/// Parse a relation string in format "type:target_id"
-fn parse_relation_string(s: &str) -> Result<(RelationType, uuid::Uuid)> {
-    let parts: Vec<&str> = s.splitn(2, ':').collect();
-    if parts.len() != 2 {
-        return Err(MedullaError::Storage(format!(
-            "Invalid relation format '{}'. Expected 'type:target_id'",
-            s
+    let root = find_project_root(data_dir);
+    let store = LoroStore::open(&root)?;
+
+    // Deleted entities can no longer be looked up, so accept a full UUID as is
+    let uuid = match uuid::Uuid::parse_str(&id) {
+        Ok(uuid) => uuid,
+        Err(_) => find_entity_id_with_type(&store, &id)?.0,
+    };
+    let versions = store.entity_history(&uuid)?;
+    if versions.is_empty() {
+        return Err(MedullaError::EntityNotFound(id));
+    }
+
+    if let Some(at) = at {
+        let version = versions
+            .iter()
+            .rev()
+            .find(|v| v.timestamp.is_some_and(|t| t <= at))
+            .ok_or_else(|| {
+                MedullaError::Storage(format!("Entity {} did not exist at {}", id, at))
+            })?;
+        match version.state {
+            Some(ref state) => println!("{}", serde_json::to_string_pretty(state)?),
+            None => println!("Entity {} was deleted at {}", uuid, at),
+        }
+        return Ok(());
+    }
+
+    if json {
+        let list: Vec<serde_json::Value> = versions
+            .iter()
+            .enumerate()
+            .map(|(index, v)| {
+                serde_json::json!({
+                    "version": index + 1,
+                    "peer": v.peer.to_string(),
+                    "timestamp": v.timestamp.map(|t| t.to_rfc3339()),
+                    "changed_fields": v.changed_fields,
+                    "deleted": v.state.is_none(),
+                })
+            })
+            .collect();
+        println!("{}", serde_json::to_string_pretty(&list)?);
+        return Ok(());
+    }
+
+    println!("History of {} ({} versions):", uuid, versions.len());
+    for (index, v) in versions.iter().enumerate() {
+        let when = v
+            .timestamp
+            .map(|t| t.format("%Y-%m-%d %H:%M:%S").to_string())
+            .unwrap_or_else(|| "unknown time".to_string());
+        let change = if v.state.is_none() {
+            "deleted".to_string()
+        } else if index == 0 {
+            "created".to_string()
+        } else {
+            v.changed_fields.join(", ")
+        };
+        println!("  {:>3}  {}  peer {}  {}", index + 1, when, v.peer, change);
+    }
+
+    Ok(())
+}
+
+/// List fields that were edited concurrently on different replicas, so
+/// someone can check what the automatic merge kept.
+pub fn handle_merge_report(
+    data_dir: Option<&Path>,
+    since: Option<String>,
+    json: bool,
+) -> Result<()> {
+    let since = match since {
+        Some(since) => Some(parse_history_time(&since).ok_or_else(|| {
+            MedullaError::Storage(format!(
+                "Invalid time '{}', expected RFC 3339 or YYYY-MM-DD",
+                since
+            ))
+        })?),
+        None => None,
+    };
+
+    let root = find_project_root(data_dir);
+    let store = LoroStore::open(&root)?;
+    let conflicts = store.merge_conflicts(since)?;
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&conflicts)?);
+        return Ok(());
+    }
+
+    if conflicts.is_empty() {
+        println!("No concurrent field edits found.");
+        return Ok(());
+    }
+
+    println!("{} concurrent field edit(s):", conflicts.len());
+    for conflict in &conflicts {
+        let entity = match conflict.sequence_number {
+            Some(seq) => format!("{} #{}", conflict.entity_type, seq),
+            None => format!("{} {}", conflict.entity_type, conflict.entity_id),
+        };
+        println!();
+        println!(
+            "{} \"{}\": {}",
+            entity,
+            conflict.title.as_deref().unwrap_or(""),
+            conflict.field
+        );
+        for edit in &conflict.edits {
+            let when = edit
+                .timestamp
+                .map(|t| t.format("%Y-%m-%d %H:%M:%S").to_string())
+                .unwrap_or_else(|| "unknown time".to_string());
+            let who = edit.author.as_deref().unwrap_or("unknown");
+            println!(
+                "  {}  {} (peer {}): {}",
+                when,
+                who,
+                edit.peer,
+                format_conflict_value(&edit.value)
+            );
+        }
+        println!("  kept: {}", format_conflict_value(&conflict.resolved));
+    }
+
+    Ok(())
+}
+
+/// A field value on one line: strings without quotes, long ones cut short
+fn format_conflict_value(value: &serde_json::Value) -> String {
+    let text = match value {
+        serde_json::Value::Null => return "(unset)".to_string(),
+        serde_json::Value::String(s) => s.replace('\n', " "),
+        other => other.to_string(),
+    };
+    if text.chars().count() > 80 {
+        format!("{}...", text.chars().take(77).collect::<String>())
+    } else {
+        text
+    }
+}
+
+/// Parse an RFC 3339 datetime or a YYYY-MM-DD date (midnight UTC).
+fn parse_history_time(s: &str) -> Option<chrono::DateTime<chrono::Utc>> {
+    if let Ok(dt) = chrono::DateTime::parse_from_rfc3339(s) {
+        return Some(dt.with_timezone(&chrono::Utc));
+    }
+    let date = chrono::NaiveDate::parse_from_str(s, "%Y-%m-%d").ok()?;
+    Some(date.and_hms_opt(0, 0, 0)?.and_utc())
+}
+
+/// Refuse to create an entity when one of the same type has a very similar
+/// title, or embedding when embeddings have been computed, listing the
+/// likely duplicates. `--no-dup-check` skips this.
+pub fn check_duplicates(data_dir: Option<&Path>, entity_type: &str, title: &str) -> Result<()> {
+    let root = find_project_root(data_dir);
+    let store = LoroStore::open(&root)?;
+    let cache = SqliteCache::open(store.medulla_dir())?;
+    store.sync_cache(&cache)?;
+
+    // Loading the model is only worth it once there are embeddings to compare
+    let embedding = match cache.embedding_dimension()? {
+        Some(_) => get_embedder().and_then(|e| e.embed(title).ok()),
+        None => None,
+    };
+    let candidates =
+        cache.find_duplicate_candidates(entity_type, title, embedding.as_deref(), 5)?;
+    if candidates.is_empty() {
+        return Ok(());
+    }
+
+    eprintln!("Similar {}s already exist:", entity_type);
+    for c in &candidates {
+        eprintln!(
+            "  {:03} ({}) - {}  [{} {:.2}]",
+            c.sequence_number,
+            &c.entity_id[..7.min(c.entity_id.len())],
+            c.title,
+            c.matched_on,
+            c.score
+        );
+    }
+    Err(MedullaError::Storage(
+        "Not creating a likely duplicate; update the existing entity or pass --no-dup-check"
+            .to_string(),
+    ))
+}
+
+/// Tags the `default_tags` and `tagging_rules` in config.yaml give a new
+/// entity, beyond those it already has
+fn auto_tags<T: serde::Serialize>(
+    store: &LoroStore,
+    entity_type: &str,
+    entity: &T,
+) -> Result<Vec<String>> {
+    let config = crate::config::ProjectConfig::load(store.medulla_dir())?;
+    let value = serde_json::to_value(entity)?;
+    let tags: Vec<String> = value
+        .get("tags")
+        .and_then(|t| serde_json::from_value(t.clone()).ok())
+        .unwrap_or_default();
+    let text = |key: &str| value.get(key).and_then(|v| v.as_str());
+    Ok(config.auto_tags(&crate::config::RuleSubject {
+        entity_type,
+        title: text("title").unwrap_or_default(),
+        content: text("content"),
+        tags: &tags,
+        properties: &value,
+    }))
+}
+
+fn get_git_author() -> Option<String> {
+    crate::author::detect()
+}
+
+/// Parse a relation string in format "type:target_id"
+fn parse_relation_string(s: &str) -> Result<(RelationType, uuid::Uuid)> {
+    let parts: Vec<&str> = s.splitn(2, ':').collect();
+    if parts.len() != 2 {
+        return Err(MedullaError::Storage(format!(
+            "Invalid relation format '{}'. Expected 'type:target_id'",
+            s
         )));
     }
 
@@ -724,6 +1522,7 @@ fn parse_relation_string(s: &str) -> Result<(RelationType, uuid::Uuid)> {
 
 #[allow(clippy::too_many_arguments)]
 pub fn handle_update(
+    data_dir: Option<&Path>,
     id: String,
     title: Option<String>,
     status: Option<String>,
@@ -732,14 +1531,33 @@ pub fn handle_update(
     relations: Vec<String>,
     stdin: bool,
     edit: bool,
+    force: bool,
     json: bool,
 ) -> Result<()> {
-    let root = find_project_root();
-    let store = LoroStore::open(&root)?;
+    let root = find_project_root(data_dir);
+    let store = LoroStore::open(&root)?.with_author(get_git_author());
 
     // Find the entity by ID across all types
     let entity = find_entity_by_id(&store, &id)?;
 
+    // Status changes must follow the configured workflow unless forced
+    if let (Some(to), false) = (status.as_deref(), force) {
+        let current = match &entity {
+            EntityRef::Decision(d) => Some(("decision", d.status.to_string())),
+            EntityRef::Task(t) => Some(("task", t.status.to_string())),
+            EntityRef::Component(c) => Some(("component", c.status.to_string())),
+            EntityRef::Question(q) => Some(("question", q.status.to_string())),
+            EntityRef::Milestone(m) => Some(("milestone", m.status.to_string())),
+            _ => None,
+        };
+        if let Some((entity_type, from)) = current {
+            let config = crate::config::ProjectConfig::load(store.medulla_dir())?;
+            config
+                .check_transition(entity_type, &from, to)
+                .map_err(|e| MedullaError::Storage(format!("{}; use --force to override", e)))?;
+        }
+    }
+
     // TODO: Handle --edit flag (Phase 4)
     if edit {
         eprintln!("Warning: --edit flag not yet implemented, skipping");
@@ -765,7 +1583,15 @@ pub fn handle_update(
                 }
             }
 
+            let content = updates.content.clone();
             store.update_decision(&decision.base.id, updates)?;
+            add_inferred_references(
+                &store,
+                decision.base.id,
+                "decision",
+                content.as_deref(),
+                &git_author,
+            )?;
             add_relations_for_entity(
                 &store,
                 decision.base.id,
@@ -805,14 +1631,29 @@ pub fn handle_update(
                 }
             }
 
+            let content = updates.content.clone();
             store.update_task(&task.base.id, updates)?;
+            add_inferred_references(
+                &store,
+                task.base.id,
+                "task",
+                content.as_deref(),
+                &git_author,
+            )?;
             add_relations_for_entity(&store, task.base.id, "task", &relations, &git_author)?;
-            store.save()?;
 
             let updated = store.get_task(&task.base.id)?.ok_or_else(|| {
                 MedullaError::Storage("Failed to retrieve updated task".to_string())
             })?;
 
+            // Completing a recurring task schedules its next occurrence
+            let next = if task.status != TaskStatus::Done && updated.status == TaskStatus::Done {
+                store.add_next_occurrence(&updated, chrono::Utc::now().date_naive())?
+            } else {
+                None
+            };
+            store.save()?;
+
             if json {
                 println!("{}", serde_json::to_string_pretty(&updated)?);
             } else {
@@ -822,6 +1663,14 @@ pub fn handle_update(
                     &updated.base.id.to_string()[..7],
                     updated.base.title
                 );
+                if let Some(next) = next {
+                    println!(
+                        "Created next occurrence {:03} ({}) due {}",
+                        next.base.sequence_number,
+                        &next.base.id.to_string()[..7],
+                        next.due_date.map(|d| d.to_string()).unwrap_or_default()
+                    );
+                }
             }
         }
         EntityRef::Note(note) => {
@@ -838,7 +1687,15 @@ pub fn handle_update(
                 }
             }
 
+            let content = updates.content.clone();
             store.update_note(&note.base.id, updates)?;
+            add_inferred_references(
+                &store,
+                note.base.id,
+                "note",
+                content.as_deref(),
+                &git_author,
+            )?;
             add_relations_for_entity(&store, note.base.id, "note", &relations, &git_author)?;
             store.save()?;
 
@@ -871,7 +1728,15 @@ pub fn handle_update(
                 }
             }
 
+            let content = updates.content.clone();
             store.update_prompt(&prompt.base.id, updates)?;
+            add_inferred_references(
+                &store,
+                prompt.base.id,
+                "prompt",
+                content.as_deref(),
+                &git_author,
+            )?;
             add_relations_for_entity(&store, prompt.base.id, "prompt", &relations, &git_author)?;
             store.save()?;
 
@@ -905,7 +1770,15 @@ pub fn handle_update(
                 }
             }
 
+            let content = updates.content.clone();
             store.update_component(&component.base.id, updates)?;
+            add_inferred_references(
+                &store,
+                component.base.id,
+                "component",
+                content.as_deref(),
+                &git_author,
+            )?;
             add_relations_for_entity(
                 &store,
                 component.base.id,
@@ -944,7 +1817,15 @@ pub fn handle_update(
                 }
             }
 
+            let content = updates.content.clone();
             store.update_link(&link.base.id, updates)?;
+            add_inferred_references(
+                &store,
+                link.base.id,
+                "link",
+                content.as_deref(),
+                &git_author,
+            )?;
             add_relations_for_entity(&store, link.base.id, "link", &relations, &git_author)?;
             store.save()?;
 
@@ -963,770 +1844,3807 @@ pub fn handle_update(
                 );
             }
         }
-    }
-
-    Ok(())
-}
-
-pub fn handle_delete(id: String, force: bool) -> Result<()> {
-    let root = find_project_root();
-    let store = LoroStore::open(&root)?;
+        EntityRef::Question(question) => {
+            let mut updates = QuestionUpdate::default();
+            updates.title = title;
+            updates.status = status.and_then(|s| s.parse::<QuestionStatus>().ok());
+            updates.add_tags = tags;
+            updates.remove_tags = remove_tags;
 
-    // Find the entity by ID across all types
-    let entity = find_entity_by_id(&store, &id)?;
+            if stdin {
+                let mut content = String::new();
+                io::stdin().read_to_string(&mut content)?;
+                if !content.is_empty() {
+                    updates.content = Some(content);
+                }
+            }
 
-    // Extract entity info for confirmation message
-    let (entity_id, entity_type, title, sequence_number) = match &entity {
-        EntityRef::Decision(d) => (
-            d.base.id,
-            "decision",
-            d.base.title.clone(),
-            d.base.sequence_number,
-        ),
-        EntityRef::Task(t) => (
-            t.base.id,
-            "task",
-            t.base.title.clone(),
-            t.base.sequence_number,
-        ),
-        EntityRef::Note(n) => (
-            n.base.id,
-            "note",
-            n.base.title.clone(),
-            n.base.sequence_number,
-        ),
-        EntityRef::Prompt(p) => (
-            p.base.id,
-            "prompt",
-            p.base.title.clone(),
-            p.base.sequence_number,
-        ),
-        EntityRef::Component(c) => (
-            c.base.id,
-            "component",
-            c.base.title.clone(),
-            c.base.sequence_number,
-        ),
-        EntityRef::Link(l) => (
-            l.base.id,
-            "link",
-            l.base.title.clone(),
-            l.base.sequence_number,
-        ),
-    };
+            let content = updates.content.clone();
+            store.update_question(&question.base.id, updates)?;
+            add_inferred_references(
+                &store,
+                question.base.id,
+                "question",
+                content.as_deref(),
+                &git_author,
+            )?;
+            add_relations_for_entity(
+                &store,
+                question.base.id,
+                "question",
+                &relations,
+                &git_author,
+            )?;
+            store.save()?;
 
-    // Confirm deletion unless --force is used
-    if !force {
-        eprintln!(
-            "Delete {} {:03} ({}) - {}? [y/N] ",
-            entity_type,
-            sequence_number,
-            &entity_id.to_string()[..7],
-            title
-        );
+            let updated = store.get_question(&question.base.id)?.ok_or_else(|| {
+                MedullaError::Storage("Failed to retrieve updated question".to_string())
+            })?;
 
-        // Check if stdin is a tty for interactive confirmation
-        if atty::is(atty::Stream::Stdin) {
-            let mut input = String::new();
-            io::stdin().read_line(&mut input)?;
-            if !input.trim().eq_ignore_ascii_case("y") {
-                println!("Cancelled.");
-                return Ok(());
+            if json {
+                println!("{}", serde_json::to_string_pretty(&updated)?);
+            } else {
+                println!(
+                    "Updated question {:03} ({}) - {}",
+                    updated.base.sequence_number,
+                    &updated.base.id.to_string()[..7],
+                    updated.base.title
+                );
             }
-        } else {
-            // Non-interactive mode without --force, abort
-            return Err(MedullaError::Storage(
-                "Use --force to delete in non-interactive mode".to_string(),
-            ));
         }
-    }
+        EntityRef::Milestone(milestone) => {
+            let mut updates = MilestoneUpdate::default();
+            updates.title = title;
+            updates.status = status.and_then(|s| s.parse::<MilestoneStatus>().ok());
+            updates.add_tags = tags;
+            updates.remove_tags = remove_tags;
 
-    // Delete any relations involving this entity
-    let relations = store.list_relations()?;
-    for rel in relations {
-        if rel.source_id == entity_id || rel.target_id == entity_id {
-            let _ = store.delete_relation(
-                &rel.source_id.to_string(),
-                &rel.relation_type.to_string(),
-                &rel.target_id.to_string(),
-            );
-        }
-    }
+            if stdin {
+                let mut content = String::new();
+                io::stdin().read_to_string(&mut content)?;
+                if !content.is_empty() {
+                    updates.content = Some(content);
+                }
+            }
 
-    // Delete the entity based on its type
-    match entity {
-        EntityRef::Decision(_) => store.delete_decision(&entity_id)?,
-        EntityRef::Task(_) => store.delete_task(&entity_id)?,
-        EntityRef::Note(_) => store.delete_note(&entity_id)?,
-        EntityRef::Prompt(_) => store.delete_prompt(&entity_id)?,
-        EntityRef::Component(_) => store.delete_component(&entity_id)?,
-        EntityRef::Link(_) => store.delete_link(&entity_id)?,
-    }
+            let content = updates.content.clone();
+            store.update_milestone(&milestone.base.id, updates)?;
+            add_inferred_references(
+                &store,
+                milestone.base.id,
+                "milestone",
+                content.as_deref(),
+                &git_author,
+            )?;
+            add_relations_for_entity(
+                &store,
+                milestone.base.id,
+                "milestone",
+                &relations,
+                &git_author,
+            )?;
+            store.save()?;
 
-    store.save()?;
+            let updated = store.get_milestone(&milestone.base.id)?.ok_or_else(|| {
+                MedullaError::Storage("Failed to retrieve updated milestone".to_string())
+            })?;
 
-    println!(
-        "Deleted {} {:03} ({}) - {}",
-        entity_type,
-        sequence_number,
-        &entity_id.to_string()[..7],
-        title
-    );
+            if json {
+                println!("{}", serde_json::to_string_pretty(&updated)?);
+            } else {
+                println!(
+                    "Updated milestone {:03} ({}) - {}",
+                    updated.base.sequence_number,
+                    &updated.base.id.to_string()[..7],
+                    updated.base.title
+                );
+            }
+        }
+    }
 
     Ok(())
 }
 
-pub fn handle_tasks_ready(limit: u32, json: bool) -> Result<()> {
-    let root = find_project_root();
-    let store = LoroStore::open(&root)?;
-    let cache = SqliteCache::open(store.medulla_dir())?;
+pub fn handle_archive(data_dir: Option<&Path>, id: String) -> Result<()> {
+    set_archived(data_dir, &id, true)
+}
 
-    // Sync cache with store
-    store.sync_cache(&cache)?;
-
-    let ready_tasks = cache.get_ready_tasks(Some(limit))?;
-
-    if json {
-        println!("{}", serde_json::to_string_pretty(&ready_tasks)?);
-    } else if ready_tasks.is_empty() {
-        println!("No ready tasks found.");
-    } else {
-        println!("Ready tasks ({}): \n", ready_tasks.len());
-        for task in ready_tasks {
-            let due_str = task
-                .due_date
-                .as_ref()
-                .map(|d| format!(" due:{}", d))
-                .unwrap_or_default();
-            let assignee_str = task
-                .assignee
-                .as_ref()
-                .map(|a| format!(" @{}", a))
-                .unwrap_or_default();
-            println!(
-                "  {:03} ({}) [{}|{}]{}{} {}",
-                task.sequence_number,
-                &task.id[..7.min(task.id.len())],
-                task.status,
-                task.priority,
-                due_str,
-                assignee_str,
-                task.title
-            );
-        }
-    }
-
-    Ok(())
+pub fn handle_unarchive(data_dir: Option<&Path>, id: String) -> Result<()> {
+    set_archived(data_dir, &id, false)
 }
 
-pub fn handle_tasks_next(json: bool) -> Result<()> {
-    let root = find_project_root();
+pub fn handle_attach(data_dir: Option<&Path>, id: String, file: &Path, json: bool) -> Result<()> {
+    let root = find_project_root(data_dir);
     let store = LoroStore::open(&root)?;
-    let cache = SqliteCache::open(store.medulla_dir())?;
 
-    // Sync cache with store
-    store.sync_cache(&cache)?;
-
-    let next_task = cache.get_next_task()?;
+    let entity = find_entity_by_id(&store, &id)?;
+    let base = entity.base();
+    let author = get_git_author();
+    let attachment = store.attach_file(&base.id, file, author.as_deref())?;
+    store.save()?;
 
     if json {
-        println!("{}", serde_json::to_string_pretty(&next_task)?);
-    } else if let Some(task) = next_task {
-        let due_str = task
-            .due_date
-            .as_ref()
-            .map(|d| format!(" due:{}", d))
-            .unwrap_or_default();
-        let assignee_str = task
-            .assignee
-            .as_ref()
-            .map(|a| format!(" @{}", a))
-            .unwrap_or_default();
-        println!("Next task:\n");
+        println!("{}", serde_json::to_string_pretty(&attachment)?);
+    } else {
         println!(
-            "  {:03} ({}) [{}|{}]{}{} {}",
-            task.sequence_number,
-            &task.id[..7.min(task.id.len())],
-            task.status,
-            task.priority,
-            due_str,
-            assignee_str,
-            task.title
+            "Attached {} ({}, {} bytes) to {:03} - {}",
+            attachment.filename, attachment.mime, attachment.size, base.sequence_number, base.title
         );
-    } else {
-        println!("No ready tasks found.");
     }
-
     Ok(())
 }
 
-pub fn handle_tasks_blocked(id: Option<String>, json: bool) -> Result<()> {
-    let root = find_project_root();
+/// Set, show or remove an entity's alias, or list every alias when no ID
+/// is given
+pub fn handle_alias(
+    data_dir: Option<&Path>,
+    id: Option<String>,
+    alias: Option<String>,
+    remove: bool,
+    json: bool,
+) -> Result<()> {
+    let root = find_project_root(data_dir);
     let store = LoroStore::open(&root)?;
-    let cache = SqliteCache::open(store.medulla_dir())?;
-
-    // Sync cache with store
-    store.sync_cache(&cache)?;
 
-    if let Some(task_id) = id {
-        // Show blockers for a specific task
-        // First, resolve the task ID (could be sequence number or UUID prefix)
-        let resolved_id = resolve_task_id(&store, &task_id)?;
-        let blockers = cache.get_task_blockers(&resolved_id)?;
-
-        if json {
-            println!("{}", serde_json::to_string_pretty(&blockers)?);
-        } else if blockers.is_empty() {
-            println!("Task {} has no blockers.", task_id);
-        } else {
-            println!("Blockers for task {}:\n", task_id);
-            for blocker in blockers {
-                println!(
-                    "  {:03} ({}) [{}] {}",
-                    blocker.sequence_number,
-                    &blocker.id[..7.min(blocker.id.len())],
-                    blocker.status,
-                    blocker.title
-                );
+    let Some(id) = id else {
+        let mut rows = Vec::new();
+        for (alias, uuid) in store.list_aliases()? {
+            if let Ok(entity) = find_entity_by_id(&store, &uuid.to_string()) {
+                let base = entity.base();
+                rows.push((alias, base.sequence_number, base.title.clone(), uuid));
             }
         }
-    } else {
-        // Show all blocked tasks
-        let blocked_tasks = cache.get_blocked_tasks(None)?;
-
         if json {
-            println!("{}", serde_json::to_string_pretty(&blocked_tasks)?);
-        } else if blocked_tasks.is_empty() {
-            println!("No blocked tasks found.");
+            let value: Vec<_> = rows
+                .iter()
+                .map(|(alias, seq, title, uuid)| {
+                    serde_json::json!({
+                        "alias": alias,
+                        "id": uuid.to_string(),
+                        "sequence_number": seq,
+                        "title": title,
+                    })
+                })
+                .collect();
+            println!("{}", serde_json::to_string_pretty(&value)?);
+        } else if rows.is_empty() {
+            println!("No aliases. Add one with: medulla alias <id> <alias>");
         } else {
-            println!("Blocked tasks ({}):\n", blocked_tasks.len());
-            for task in blocked_tasks {
-                let due_str = task
-                    .due_date
-                    .as_ref()
-                    .map(|d| format!(" due:{}", d))
-                    .unwrap_or_default();
-                println!(
-                    "  {:03} ({}) [{}|{}]{} {}",
-                    task.sequence_number,
-                    &task.id[..7.min(task.id.len())],
-                    task.status,
-                    task.priority,
-                    due_str,
-                    task.title
-                );
-                println!("      blocked by:");
-                for blocker in &task.blockers {
-                    println!(
-                        "        - {:03} ({}) [{}] {}",
-                        blocker.sequence_number,
-                        &blocker.id[..7.min(blocker.id.len())],
-                        blocker.status,
-                        blocker.title
-                    );
-                }
+            for (alias, seq, title, _) in rows {
+                println!("{:<24} {:03} - {}", alias, seq, title);
             }
         }
-    }
+        return Ok(());
+    };
 
-    Ok(())
-}
+    let entity = find_entity_by_id(&store, &id)?;
+    let base = entity.base();
+    let current = store.alias_of(&base.id);
 
-/// Resolve a task ID from sequence number or UUID prefix to full UUID
-fn resolve_task_id(store: &LoroStore, id: &str) -> Result<String> {
-    // Try to parse as sequence number first
-    if let Ok(seq) = id.parse::<u32>() {
-        for task in store.list_tasks()? {
-            if task.base.sequence_number == seq {
-                return Ok(task.base.id.to_string());
-            }
+    let alias = match (alias, remove) {
+        (Some(alias), _) => {
+            let alias = store.set_alias(&base.id, &alias)?;
+            store.save()?;
+            Some(alias)
         }
+        (None, true) => {
+            let Some(ref current) = current else {
+                return Err(MedullaError::Storage(format!(
+                    "{:03} - {} has no alias",
+                    base.sequence_number, base.title
+                )));
+            };
+            store.remove_alias(current)?;
+            store.save()?;
+            None
+        }
+        (None, false) => current,
+    };
+
+    if json {
+        let value = serde_json::json!({
+            "id": base.id.to_string(),
+            "sequence_number": base.sequence_number,
+            "title": base.title,
+            "alias": alias,
+        });
+        println!("{}", serde_json::to_string_pretty(&value)?);
     } else {
-        // Search by UUID prefix
-        for task in store.list_tasks()? {
-            if task.base.id.to_string().starts_with(id) {
-                return Ok(task.base.id.to_string());
-            }
+        match alias {
+            Some(alias) => println!(
+                "{:03} - {} is '{}'",
+                base.sequence_number, base.title, alias
+            ),
+            None => println!("{:03} - {} has no alias", base.sequence_number, base.title),
         }
     }
-
-    Err(MedullaError::EntityNotFound(id.to_string()))
+    Ok(())
 }
 
-/// Find an entity by ID and return its UUID and type
-fn find_entity_id_with_type(store: &LoroStore, id: &str) -> Result<(uuid::Uuid, String)> {
-    let entity = find_entity_by_id(store, id)?;
-    match entity {
-        EntityRef::Decision(d) => Ok((d.base.id, "decision".to_string())),
-        EntityRef::Task(t) => Ok((t.base.id, "task".to_string())),
-        EntityRef::Note(n) => Ok((n.base.id, "note".to_string())),
-        EntityRef::Prompt(p) => Ok((p.base.id, "prompt".to_string())),
-        EntityRef::Component(c) => Ok((c.base.id, "component".to_string())),
-        EntityRef::Link(l) => Ok((l.base.id, "link".to_string())),
-    }
-}
+pub fn handle_collection(
+    data_dir: Option<&Path>,
+    id: Option<String>,
+    name: Option<String>,
+    remove: bool,
+    json: bool,
+) -> Result<()> {
+    let root = find_project_root(data_dir);
+    let store = LoroStore::open(&root)?;
 
-/// Get entity title by ID for display purposes
-fn get_entity_title(store: &LoroStore, id: &uuid::Uuid) -> String {
-    // Search through all entity types
-    if let Ok(decisions) = store.list_decisions() {
-        for d in decisions {
-            if d.base.id == *id {
-                return d.base.title;
-            }
-        }
-    }
-    if let Ok(tasks) = store.list_tasks() {
-        for t in tasks {
-            if t.base.id == *id {
-                return t.base.title;
-            }
-        }
-    }
-    if let Ok(notes) = store.list_notes() {
-        for n in notes {
-            if n.base.id == *id {
-                return n.base.title;
+    let Some(id) = id else {
+        let collections = store.list_collections()?;
+        if json {
+            let value: Vec<_> = collections
+                .iter()
+                .map(|(name, count)| serde_json::json!({ "collection": name, "count": count }))
+                .collect();
+            println!("{}", serde_json::to_string_pretty(&value)?);
+        } else if collections.is_empty() {
+            println!("No collections. Add an entity to one with: medulla collection <id> <name>");
+        } else {
+            for (name, count) in collections {
+                println!("{:<24} {}", name, count);
             }
         }
-    }
-    if let Ok(prompts) = store.list_prompts() {
-        for p in prompts {
-            if p.base.id == *id {
-                return p.base.title;
-            }
+        return Ok(());
+    };
+
+    let entity = find_entity_by_id(&store, &id)?;
+    let base = entity.base();
+    let current = store.collection_of(&base.id);
+
+    let membership = match (name, remove) {
+        (Some(name), _) => {
+            let membership = store.set_collection(&base.id, &name)?;
+            store.save()?;
+            Some(membership)
         }
-    }
-    if let Ok(components) = store.list_components() {
-        for c in components {
-            if c.base.id == *id {
-                return c.base.title;
+        (None, true) => {
+            if current.is_none() {
+                return Err(MedullaError::Storage(format!(
+                    "{:03} - {} is not in a collection",
+                    base.sequence_number, base.title
+                )));
             }
+            store.remove_collection(&base.id)?;
+            store.save()?;
+            None
         }
-    }
-    if let Ok(links) = store.list_links() {
-        for l in links {
-            if l.base.id == *id {
-                return l.base.title;
-            }
+        (None, false) => current,
+    };
+
+    if json {
+        let value = serde_json::json!({
+            "id": base.id.to_string(),
+            "sequence_number": base.sequence_number,
+            "title": base.title,
+            "collection": membership.as_ref().map(|(name, _)| name),
+            "collection_id": membership
+                .as_ref()
+                .map(|(name, seq)| format!("{}/{}", name, seq)),
+        });
+        println!("{}", serde_json::to_string_pretty(&value)?);
+    } else {
+        match membership {
+            Some((name, seq)) => println!(
+                "{:03} - {} is {}/{}",
+                base.sequence_number, base.title, name, seq
+            ),
+            None => println!(
+                "{:03} - {} is not in a collection",
+                base.sequence_number, base.title
+            ),
         }
     }
-    id.to_string()[..7].to_string()
+    Ok(())
 }
 
-pub fn handle_relation_add(
-    source_id: String,
-    target_id: String,
-    relation_type: String,
-    json: bool,
-) -> Result<()> {
-    let root = find_project_root();
+/// Archive or restore an entity, reporting if it was already in that state
+fn set_archived(data_dir: Option<&Path>, id: &str, archived: bool) -> Result<()> {
+    let root = find_project_root(data_dir);
     let store = LoroStore::open(&root)?;
 
-    // Resolve source and target IDs to UUIDs with types
-    let (source_uuid, source_type) = find_entity_id_with_type(&store, &source_id)?;
-    let (target_uuid, target_type) = find_entity_id_with_type(&store, &target_id)?;
+    let entity = find_entity_by_id(&store, id)?;
+    let (base, entity_type) = match &entity {
+        EntityRef::Decision(d) => (&d.base, "decision"),
+        EntityRef::Task(t) => (&t.base, "task"),
+        EntityRef::Note(n) => (&n.base, "note"),
+        EntityRef::Prompt(p) => (&p.base, "prompt"),
+        EntityRef::Component(c) => (&c.base, "component"),
+        EntityRef::Link(l) => (&l.base, "link"),
+        EntityRef::Question(q) => (&q.base, "question"),
+        EntityRef::Milestone(m) => (&m.base, "milestone"),
+    };
 
-    // Parse and validate relation type
-    let rel_type: RelationType = relation_type
-        .parse()
-        .map_err(|e: String| MedullaError::Storage(e))?;
+    if base.archived == archived {
+        let state = if archived { "archived" } else { "not archived" };
+        println!(
+            "{} {:03} is already {} - {}",
+            entity_type, base.sequence_number, state, base.title
+        );
+        return Ok(());
+    }
 
-    // Create the relation
-    let mut relation = Relation::new(
-        source_uuid,
-        source_type.clone(),
-        target_uuid,
-        target_type.clone(),
-        rel_type,
+    store.set_archived(&base.id, archived)?;
+    store.save()?;
+
+    println!(
+        "{} {} {:03} ({}) - {}",
+        if archived { "Archived" } else { "Restored" },
+        entity_type,
+        base.sequence_number,
+        &base.id.to_string()[..7],
+        base.title
     );
+    Ok(())
+}
 
-    // Try to get git author
-    let git_author = get_git_author();
-    relation.created_by = git_author;
+pub fn handle_delete(data_dir: Option<&Path>, id: String, force: bool) -> Result<()> {
+    let root = find_project_root(data_dir);
+    let store = LoroStore::open(&root)?;
 
-    // Store the relation
-    store.add_relation(&relation)?;
-    store.save()?;
+    // Find the entity by ID across all types
+    let entity = find_entity_by_id(&store, &id)?;
 
-    if json {
-        let response = serde_json::json!({
-            "source_id": source_uuid.to_string(),
-            "source_type": source_type,
-            "target_id": target_uuid.to_string(),
-            "target_type": target_type,
-            "relation_type": rel_type.to_string(),
-            "created_at": relation.created_at.to_rfc3339(),
-            "message": format!(
-                "Created '{}' relation from {} to {}",
-                rel_type, source_id, target_id
-            ),
-        });
-        println!("{}", serde_json::to_string_pretty(&response)?);
-    } else {
-        println!(
-            "Created '{}' relation: {} ({}) -> {} ({})",
-            rel_type, source_id, source_type, target_id, target_type
-        );
+    // Extract entity info for confirmation message
+    let (entity_id, entity_type, title, sequence_number) = match &entity {
+        EntityRef::Decision(d) => (
+            d.base.id,
+            "decision",
+            d.base.title.clone(),
+            d.base.sequence_number,
+        ),
+        EntityRef::Task(t) => (
+            t.base.id,
+            "task",
+            t.base.title.clone(),
+            t.base.sequence_number,
+        ),
+        EntityRef::Note(n) => (
+            n.base.id,
+            "note",
+            n.base.title.clone(),
+            n.base.sequence_number,
+        ),
+        EntityRef::Prompt(p) => (
+            p.base.id,
+            "prompt",
+            p.base.title.clone(),
+            p.base.sequence_number,
+        ),
+        EntityRef::Component(c) => (
+            c.base.id,
+            "component",
+            c.base.title.clone(),
+            c.base.sequence_number,
+        ),
+        EntityRef::Link(l) => (
+            l.base.id,
+            "link",
+            l.base.title.clone(),
+            l.base.sequence_number,
+        ),
+        EntityRef::Question(q) => (
+            q.base.id,
+            "question",
+            q.base.title.clone(),
+            q.base.sequence_number,
+        ),
+        EntityRef::Milestone(m) => (
+            m.base.id,
+            "milestone",
+            m.base.title.clone(),
+            m.base.sequence_number,
+        ),
+    };
+
+    // Confirm deletion unless --force is used
+    if !force
+        && !confirm(&format!(
+            "Delete {} {:03} ({}) - {}?",
+            entity_type,
+            sequence_number,
+            &entity_id.to_string()[..7],
+            title
+        ))?
+    {
+        println!("Cancelled.");
+        return Ok(());
     }
 
+    // Move the entity and its relations to the trash
+    store.trash_entity(&entity_id, get_git_author().as_deref())?;
+
+    store.save()?;
+
+    println!(
+        "Moved {} {:03} ({}) - {} to the trash. Undo with `medulla trash restore {}`.",
+        entity_type,
+        sequence_number,
+        &entity_id.to_string()[..7],
+        title,
+        sequence_number
+    );
+
     Ok(())
 }
 
-pub fn handle_relation_delete(
-    source_id: String,
-    target_id: String,
-    relation_type: String,
-    json: bool,
-) -> Result<()> {
-    let root = find_project_root();
-    let store = LoroStore::open(&root)?;
+/// Ask a yes/no question on stderr. Without a terminal there is no one to
+/// answer, so this fails and asks for --force instead.
+fn confirm(question: &str) -> Result<bool> {
+    eprintln!("{} [y/N] ", question);
 
-    // Resolve source and target IDs to UUIDs
-    let (source_uuid, _) = find_entity_id_with_type(&store, &source_id)?;
-    let (target_uuid, _) = find_entity_id_with_type(&store, &target_id)?;
+    if !atty::is(atty::Stream::Stdin) {
+        return Err(MedullaError::Storage(
+            "Use --force to delete in non-interactive mode".to_string(),
+        ));
+    }
+    let mut input = String::new();
+    io::stdin().read_line(&mut input)?;
+    Ok(input.trim().eq_ignore_ascii_case("y"))
+}
 
-    // Parse and validate relation type
-    let rel_type: RelationType = relation_type
-        .parse()
-        .map_err(|e: String| MedullaError::Storage(e))?;
+/// Find a trashed entity by sequence number or UUID prefix
+fn find_trashed(store: &LoroStore, id: &str) -> Result<crate::storage::TrashedEntity> {
+    let id = id.trim().to_lowercase();
+    let mut matches: Vec<_> = store
+        .list_trash()?
+        .into_iter()
+        .filter(|t| {
+            t.sequence_number.to_string() == id
+                || (id.parse::<u32>().is_err() && t.id.to_string().starts_with(&id))
+        })
+        .collect();
 
-    // Delete from store
-    store.delete_relation(
-        &source_uuid.to_string(),
-        &rel_type.to_string(),
-        &target_uuid.to_string(),
-    )?;
-    store.save()?;
+    match matches.len() {
+        0 => Err(MedullaError::EntityNotFound(format!("{} (in trash)", id))),
+        1 => Ok(matches.remove(0)),
+        _ => Err(MedullaError::Storage(format!(
+            "ID prefix '{}' matches {} trashed entities; use a longer prefix",
+            id,
+            matches.len()
+        ))),
+    }
+}
+
+pub fn handle_trash_list(data_dir: Option<&Path>, json: bool) -> Result<()> {
+    let root = find_project_root(data_dir);
+    let store = LoroStore::open(&root)?;
+    let trash = store.list_trash()?;
 
     if json {
-        let response = serde_json::json!({
-            "deleted": true,
-            "source_id": source_uuid.to_string(),
-            "target_id": target_uuid.to_string(),
-            "relation_type": rel_type.to_string(),
-            "message": format!(
-                "Deleted '{}' relation from {} to {}",
-                rel_type, source_id, target_id
-            ),
-        });
-        println!("{}", serde_json::to_string_pretty(&response)?);
+        println!("{}", serde_json::to_string_pretty(&trash)?);
+    } else if trash.is_empty() {
+        println!("Trash is empty.");
     } else {
-        println!(
-            "Deleted '{}' relation: {} -> {}",
-            rel_type, source_id, target_id
-        );
+        println!("Trash ({}): \n", trash.len());
+        for entity in &trash {
+            let deleted = entity
+                .deleted_at
+                .map(|d| d.format("%Y-%m-%d %H:%M").to_string())
+                .unwrap_or_else(|| "-".to_string());
+            let by = entity
+                .deleted_by
+                .as_ref()
+                .map(|b| format!(" by {}", b))
+                .unwrap_or_default();
+            println!(
+                "  {:03} ({}) [{}] {} (deleted {}{})",
+                entity.sequence_number,
+                &entity.id.to_string()[..7],
+                entity.entity_type,
+                entity.title,
+                deleted,
+                by
+            );
+        }
     }
 
     Ok(())
 }
 
-pub fn handle_relation_list(entity_id: String, json: bool) -> Result<()> {
-    let root = find_project_root();
+pub fn handle_trash_restore(data_dir: Option<&Path>, id: String) -> Result<()> {
+    let root = find_project_root(data_dir);
     let store = LoroStore::open(&root)?;
 
-    // Resolve entity ID to UUID
-    let (entity_uuid, entity_type) = find_entity_id_with_type(&store, &entity_id)?;
-
-    // Get all relations involving this entity (both as source and target)
-    let relations_from = store.get_relations_from(&entity_uuid.to_string())?;
-    let relations_to = store.get_relations_to(&entity_uuid.to_string())?;
-
-    if json {
-        #[derive(serde::Serialize)]
-        struct RelationJson {
-            direction: String,
-            relation_type: String,
-            other_id: String,
-            other_type: String,
-            other_title: String,
-            created_at: String,
-        }
-
-        let mut all_relations: Vec<RelationJson> = Vec::new();
-
-        for r in &relations_from {
-            all_relations.push(RelationJson {
-                direction: "outgoing".to_string(),
-                relation_type: r.relation_type.to_string(),
-                other_id: r.target_id.to_string(),
-                other_type: r.target_type.clone(),
-                other_title: get_entity_title(&store, &r.target_id),
-                created_at: r.created_at.to_rfc3339(),
-            });
-        }
+    let trashed = find_trashed(&store, &id)?;
+    let restored = store.restore_entity(&trashed.id)?;
+    store.save()?;
 
-        for r in &relations_to {
-            all_relations.push(RelationJson {
-                direction: "incoming".to_string(),
-                relation_type: r.relation_type.to_string(),
-                other_id: r.source_id.to_string(),
-                other_type: r.source_type.clone(),
-                other_title: get_entity_title(&store, &r.source_id),
-                created_at: r.created_at.to_rfc3339(),
-            });
+    println!(
+        "Restored {} {:03} ({}) - {}{}",
+        trashed.entity_type,
+        trashed.sequence_number,
+        &trashed.id.to_string()[..7],
+        trashed.title,
+        match restored.relations {
+            0 => String::new(),
+            1 => " with 1 relation".to_string(),
+            n => format!(" with {} relations", n),
         }
-
-        let response = serde_json::json!({
-            "entity_id": entity_uuid.to_string(),
-            "entity_type": entity_type,
-            "relations": all_relations,
-        });
-        println!("{}", serde_json::to_string_pretty(&response)?);
-    } else {
-        let entity_title = get_entity_title(&store, &entity_uuid);
+    );
+    if restored.skipped_relations > 0 {
         println!(
-            "Relations for {} ({}) - {}:\n",
-            entity_id, entity_type, entity_title
+            "Left out {} relation(s) to entities that are trashed or gone; those in the trash return when their entity is restored.",
+            restored.skipped_relations
         );
+    }
 
-        if relations_from.is_empty() && relations_to.is_empty() {
-            println!("  No relations found.");
-        } else {
-            if !relations_from.is_empty() {
-                println!("  Outgoing relations:");
-                for r in &relations_from {
-                    let target_title = get_entity_title(&store, &r.target_id);
-                    println!(
-                        "    --[{}]--> {} ({}) - {}",
-                        r.relation_type,
-                        &r.target_id.to_string()[..7],
-                        r.target_type,
-                        target_title
-                    );
-                }
-            }
+    Ok(())
+}
 
-            if !relations_to.is_empty() {
-                println!("  Incoming relations:");
-                for r in &relations_to {
-                    let source_title = get_entity_title(&store, &r.source_id);
-                    println!(
-                        "    <--[{}]-- {} ({}) - {}",
-                        r.relation_type,
-                        &r.source_id.to_string()[..7],
-                        r.source_type,
-                        source_title
-                    );
-                }
+/// Purge one trashed entity, or the whole trash when `id` is None
+pub fn handle_trash_purge(data_dir: Option<&Path>, id: Option<String>, force: bool) -> Result<()> {
+    let root = find_project_root(data_dir);
+    let store = LoroStore::open(&root)?;
+
+    let trashed = match id {
+        Some(ref id) => Some(find_trashed(&store, id)?),
+        None => None,
+    };
+    let question = match &trashed {
+        Some(t) => format!(
+            "Permanently delete {} {:03} ({}) - {}?",
+            t.entity_type,
+            t.sequence_number,
+            &t.id.to_string()[..7],
+            t.title
+        ),
+        None => {
+            let count = store.list_trash()?.len();
+            if count == 0 {
+                println!("Trash is empty.");
+                return Ok(());
             }
+            format!("Permanently delete all {} entities in the trash?", count)
         }
+    };
+    if !force && !confirm(&question)? {
+        println!("Cancelled.");
+        return Ok(());
     }
 
+    let purged = store.purge_trash(trashed.as_ref().map(|t| &t.id))?;
+    store.save()?;
+    println!(
+        "Purged {} {} from the trash",
+        purged,
+        if purged == 1 { "entity" } else { "entities" }
+    );
+
     Ok(())
 }
 
-pub fn handle_search(query: String, semantic: bool, json: bool) -> Result<()> {
-    let root = find_project_root();
+pub fn handle_dedupe(
+    data_dir: Option<&Path>,
+    entity_type: Option<String>,
+    threshold: f32,
+    limit: usize,
+    merge: bool,
+    json: bool,
+) -> Result<()> {
+    if !(0.0..=1.0).contains(&threshold) {
+        return Err(MedullaError::Storage(
+            "Threshold must be between 0.0 and 1.0".to_string(),
+        ));
+    }
+    if merge && (json || !atty::is(atty::Stream::Stdin)) {
+        return Err(MedullaError::Storage(
+            "--merge needs an interactive terminal".to_string(),
+        ));
+    }
+
+    let root = find_project_root(data_dir);
     let store = LoroStore::open(&root)?;
     let cache = SqliteCache::open(store.medulla_dir())?;
-
-    // Sync cache with store
     store.sync_cache(&cache)?;
 
-    // Parse query for filters (type:, status:, tag:, created:)
-    let (search_text, filter) = crate::search::parse_query(&query);
+    let pairs = cache.find_duplicate_pairs(entity_type.as_deref(), threshold, limit)?;
 
-    if semantic {
-        return handle_search_semantic(&cache, &search_text, &filter, json);
+    if json {
+        println!("{}", serde_json::to_string_pretty(&pairs)?);
+        return Ok(());
+    }
+    if pairs.is_empty() {
+        println!(
+            "No duplicates found (similarity threshold: {:.2}).",
+            threshold
+        );
+        if cache.embedding_dimension()?.is_none() {
+            println!("\nHint: Duplicate detection compares embeddings.");
+            println!(
+                "Try running 'medulla cache rebuild' to generate embeddings for existing entities."
+            );
+        }
+        return Ok(());
     }
 
-    // Determine search text (if empty after parsing, search all)
-    let search_query = if search_text.is_empty() {
-        "*"
-    } else {
-        &search_text
+    let describe = |e: &crate::cache::DuplicateEntity| {
+        format!(
+            "{:03} ({}) - {}",
+            e.sequence_number,
+            &e.entity_id[..7.min(e.entity_id.len())],
+            e.title
+        )
     };
 
-    // Perform full-text search across all entity types (or filtered type)
-    let results = if let Some(ref entity_type) = filter.entity_type {
-        // Search only the specified entity type
-        cache.search_by_type(entity_type, search_query, 100)?
-    } else {
-        cache.search_all(search_query, 100)?
-    };
+    let author = get_git_author();
+    let mut merged = std::collections::HashSet::new();
+    for pair in &pairs {
+        if merged.contains(&pair.first.entity_id) || merged.contains(&pair.second.entity_id) {
+            continue;
+        }
+        println!("{:.4}  {}", pair.score, pair.entity_type);
+        println!("  [1] {}", describe(&pair.first));
+        println!("  [2] {}", describe(&pair.second));
+        if !merge {
+            continue;
+        }
 
-    // Apply additional filters
-    let results: Vec<_> = results
+        eprintln!("Keep [1] or [2] and merge the other into it, [s]kip or [q]uit? ");
+        let mut input = String::new();
+        io::stdin().read_line(&mut input)?;
+        let (keep, duplicate) = match input.trim().to_lowercase().as_str() {
+            "1" => (&pair.first, &pair.second),
+            "2" => (&pair.second, &pair.first),
+            "q" => break,
+            _ => continue,
+        };
+
+        let (Ok(keep_id), Ok(duplicate_id)) = (
+            uuid::Uuid::parse_str(&keep.entity_id),
+            uuid::Uuid::parse_str(&duplicate.entity_id),
+        ) else {
+            continue;
+        };
+        store.merge_entities(&keep_id, &duplicate_id, author.as_deref(), false)?;
+        store.save()?;
+        merged.insert(duplicate.entity_id.clone());
+        println!(
+            "Merged {} into {:03}. Undo with `medulla trash restore {}`.",
+            describe(duplicate),
+            keep.sequence_number,
+            duplicate.sequence_number
+        );
+    }
+
+    if !merged.is_empty() {
+        store.sync_cache(&cache)?;
+        println!(
+            "\nMerged {} {}",
+            merged.len(),
+            if merged.len() == 1 {
+                "entity"
+            } else {
+                "entities"
+            }
+        );
+    } else if !merge {
+        println!("\nRun with --merge to review and merge these pairs.");
+    }
+
+    Ok(())
+}
+
+pub fn handle_merge(
+    data_dir: Option<&Path>,
+    source: String,
+    target: String,
+    archive: bool,
+    force: bool,
+) -> Result<()> {
+    let root = find_project_root(data_dir);
+    let store = LoroStore::open(&root)?;
+
+    let source = find_entity_by_id(&store, &source)?;
+    let target = find_entity_by_id(&store, &target)?;
+    let describe = |base: &crate::entity::EntityBase| {
+        format!(
+            "{:03} ({}) - {}",
+            base.sequence_number,
+            &base.id.to_string()[..7],
+            base.title
+        )
+    };
+    let (source, target) = (source.base(), target.base());
+
+    if !force
+        && !confirm(&format!(
+            "Merge {} into {}?",
+            describe(source),
+            describe(target)
+        ))?
+    {
+        println!("Cancelled.");
+        return Ok(());
+    }
+
+    let entity_type =
+        store.merge_entities(&target.id, &source.id, get_git_author().as_deref(), archive)?;
+    store.save()?;
+
+    let cache = SqliteCache::open(store.medulla_dir())?;
+    store.sync_cache(&cache)?;
+
+    println!(
+        "Merged {} {} into {}.",
+        entity_type,
+        describe(source),
+        describe(target)
+    );
+    if archive {
+        println!(
+            "{:03} is archived; unarchive it with `medulla unarchive {}`.",
+            source.sequence_number, source.sequence_number
+        );
+    } else {
+        println!(
+            "{:03} is in the trash; restore it with `medulla trash restore {}`.",
+            source.sequence_number, source.sequence_number
+        );
+    }
+
+    Ok(())
+}
+
+pub fn handle_tasks_ready(
+    data_dir: Option<&Path>,
+    limit: u32,
+    format: OutputFormat,
+    include_archived: bool,
+    respect_wip: bool,
+) -> Result<()> {
+    let root = find_project_root(data_dir);
+    let store = LoroStore::open(&root)?;
+    let cache = SqliteCache::open(store.medulla_dir())?;
+
+    // Sync cache with store
+    store.sync_cache(&cache)?;
+    let config = crate::config::ProjectConfig::load(store.medulla_dir())?;
+    cache.set_task_scoring(&config.task_scoring)?;
+
+    let ready_tasks = cache.get_ready_tasks_with(
+        Some(limit),
+        include_archived,
+        respect_wip.then_some(&config.wip_limits),
+    )?;
+
+    if format == OutputFormat::Json {
+        println!("{}", serde_json::to_string_pretty(&ready_tasks)?);
+    } else if format.is_table() {
+        let mut table = Table::new(&[
+            "seq", "id", "status", "priority", "due", "score", "assignee", "title",
+        ]);
+        for task in &ready_tasks {
+            table.push(vec![
+                task.sequence_number.to_string(),
+                task.id.clone(),
+                task.status.clone(),
+                task.priority.clone(),
+                task.due_date.clone().unwrap_or_default(),
+                format!("{:.2}", task.score.total),
+                task.assignee.clone().unwrap_or_default(),
+                task.title.clone(),
+            ]);
+        }
+        table.print(format)?;
+    } else if ready_tasks.is_empty() {
+        println!("No ready tasks found.");
+    } else {
+        println!("Ready tasks ({}): \n", ready_tasks.len());
+        for task in ready_tasks {
+            let due_str = task
+                .due_date
+                .as_ref()
+                .map(|d| format!(" due:{}", d))
+                .unwrap_or_default();
+            let assignee_str = task
+                .assignee
+                .as_ref()
+                .map(|a| format!(" @{}", a))
+                .unwrap_or_default();
+            println!(
+                "  {:03} ({}) [{}|{}]{}{} {}",
+                task.sequence_number,
+                &task.id[..7.min(task.id.len())],
+                task.status,
+                task.priority,
+                due_str,
+                assignee_str,
+                task.title
+            );
+        }
+    }
+
+    Ok(())
+}
+
+/// Print tasks from a due-date report, one per line.
+fn print_due_tasks(tasks: &[crate::cache::DueTask]) {
+    for task in tasks {
+        let when = match task.days_until_due {
+            0 => "today".to_string(),
+            1 => "tomorrow".to_string(),
+            -1 => "1 day overdue".to_string(),
+            d if d < 0 => format!("{} days overdue", -d),
+            d => format!("in {} days", d),
+        };
+        let assignee_str = task
+            .assignee
+            .as_ref()
+            .map(|a| format!(" @{}", a))
+            .unwrap_or_default();
+        println!(
+            "  {:03} ({}) [{}|{}] due:{} ({}){} {}",
+            task.sequence_number,
+            &task.id[..7.min(task.id.len())],
+            task.status,
+            task.priority,
+            task.due_date,
+            when,
+            assignee_str,
+            task.title
+        );
+    }
+}
+
+pub fn handle_tasks_overdue(data_dir: Option<&Path>, limit: u32, json: bool) -> Result<()> {
+    let root = find_project_root(data_dir);
+    let store = LoroStore::open(&root)?;
+    let cache = SqliteCache::open(store.medulla_dir())?;
+    store.sync_cache(&cache)?;
+
+    let today = chrono::Utc::now().date_naive();
+    let tasks = cache.get_overdue_tasks(today, Some(limit))?;
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&tasks)?);
+    } else if tasks.is_empty() {
+        println!("No overdue tasks.");
+    } else {
+        println!("Overdue tasks ({}): \n", tasks.len());
+        print_due_tasks(&tasks);
+    }
+
+    Ok(())
+}
+
+pub fn handle_tasks_due(
+    data_dir: Option<&Path>,
+    within: String,
+    limit: u32,
+    json: bool,
+) -> Result<()> {
+    let days = crate::entity::parse_due_window(&within).map_err(MedullaError::Storage)?;
+
+    let root = find_project_root(data_dir);
+    let store = LoroStore::open(&root)?;
+    let cache = SqliteCache::open(store.medulla_dir())?;
+    store.sync_cache(&cache)?;
+
+    let today = chrono::Utc::now().date_naive();
+    let tasks = cache.get_tasks_due_within(today, days, Some(limit))?;
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&tasks)?);
+    } else if tasks.is_empty() {
+        println!("No tasks due in the next {} days.", days);
+    } else {
+        println!("Tasks due in the next {} days ({}): \n", days, tasks.len());
+        print_due_tasks(&tasks);
+    }
+
+    Ok(())
+}
+
+/// Print one assignee's queue, one task per line.
+fn print_assigned_tasks(tasks: &[crate::cache::AssignedTask]) {
+    for task in tasks {
+        let due_str = task
+            .due_date
+            .as_ref()
+            .map(|d| format!(" due:{}", d))
+            .unwrap_or_default();
+        let blocked_str = if task.blocked { " (blocked)" } else { "" };
+        println!(
+            "  {:03} ({}) [{}|{}]{}{} {}",
+            task.sequence_number,
+            &task.id[..7.min(task.id.len())],
+            task.status,
+            task.priority,
+            due_str,
+            blocked_str,
+            task.title
+        );
+    }
+}
+
+pub fn handle_tasks_mine(
+    data_dir: Option<&Path>,
+    assignee: Option<String>,
+    json: bool,
+) -> Result<()> {
+    let assignee = assignee.or_else(get_git_author).ok_or_else(|| {
+        MedullaError::Config(
+            "No assignee given and git user.name is not set. Pass --assignee.".to_string(),
+        )
+    })?;
+
+    let root = find_project_root(data_dir);
+    let store = LoroStore::open(&root)?;
+    let cache = SqliteCache::open(store.medulla_dir())?;
+    store.sync_cache(&cache)?;
+
+    let tasks = cache
+        .get_assignee_workload(Some(&assignee))?
         .into_iter()
-        .filter(|r| matches_cli_filter(&cache, r, &filter))
-        .take(50)
-        .collect();
+        .next()
+        .map(|group| group.tasks)
+        .unwrap_or_default();
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&tasks)?);
+    } else if tasks.is_empty() {
+        println!("No open tasks assigned to {}.", assignee);
+    } else {
+        println!("Open tasks for {} ({}): \n", assignee, tasks.len());
+        print_assigned_tasks(&tasks);
+    }
+
+    Ok(())
+}
+
+pub fn handle_tasks_workload(data_dir: Option<&Path>, json: bool) -> Result<()> {
+    let root = find_project_root(data_dir);
+    let store = LoroStore::open(&root)?;
+    let cache = SqliteCache::open(store.medulla_dir())?;
+    store.sync_cache(&cache)?;
+
+    let groups = cache.get_assignee_workload(None)?;
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&groups)?);
+    } else if groups.is_empty() {
+        println!("No open tasks.");
+    } else {
+        for (i, group) in groups.iter().enumerate() {
+            if i > 0 {
+                println!();
+            }
+            let priorities: Vec<String> = ["urgent", "high", "normal", "low"]
+                .iter()
+                .filter_map(|p| group.by_priority.get(*p).map(|n| format!("{} {}", n, p)))
+                .collect();
+            println!(
+                "{}: {} open, {} blocked ({})",
+                group.assignee.as_deref().unwrap_or("(unassigned)"),
+                group.open,
+                group.blocked,
+                priorities.join(", ")
+            );
+            print_assigned_tasks(&group.tasks);
+        }
+    }
+
+    Ok(())
+}
+
+/// Report in-progress tasks per assignee against the configured WIP
+/// limits. Returns whether anyone is over their limit.
+pub fn handle_tasks_wip(data_dir: Option<&Path>, json: bool) -> Result<bool> {
+    let root = find_project_root(data_dir);
+    let store = LoroStore::open(&root)?;
+    let cache = SqliteCache::open(store.medulla_dir())?;
+    store.sync_cache(&cache)?;
+    let config = crate::config::ProjectConfig::load(store.medulla_dir())?;
+
+    let report = cache.get_wip_report(&config.wip_limits)?;
+    let over = report.iter().filter(|w| w.over_limit).count();
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&report)?);
+        return Ok(over > 0);
+    }
+
+    if config.wip_limits.is_empty() {
+        println!("No WIP limits configured (set wip_limits in .medulla/config.yaml).\n");
+    }
+    if report.is_empty() {
+        println!("No tasks in progress.");
+        return Ok(false);
+    }
+    for (i, wip) in report.iter().enumerate() {
+        if i > 0 {
+            println!();
+        }
+        let limit = wip
+            .limit
+            .map_or("no limit".to_string(), |l| format!("limit {}", l));
+        let flag = if wip.over_limit { "  OVER LIMIT" } else { "" };
+        println!(
+            "{}: {} in progress ({}){}",
+            wip.assignee, wip.in_progress, limit, flag
+        );
+        print_assigned_tasks(&wip.tasks);
+    }
+    if over > 0 {
+        println!("\n{} assignee(s) over their WIP limit", over);
+    }
+
+    Ok(over > 0)
+}
+
+/// Handle `links check`. Returns whether any link is dead.
+pub fn handle_links_check(
+    data_dir: Option<&Path>,
+    update: bool,
+    include_archived: bool,
+    json: bool,
+) -> Result<bool> {
+    let root = find_project_root(data_dir);
+    let store = LoroStore::open(&root)?.with_author(get_git_author());
+
+    let mut links = store.list_links()?;
+    links.retain(|l| include_archived || !l.base.archived);
+
+    let mut checks = Vec::new();
+    for link in &links {
+        let check = crate::fetch::check_link(link);
+        if let (true, Some(page)) = (update, &check.page) {
+            let update = LinkUpdate {
+                fetched: Some(page.to_link_fetch()),
+                ..Default::default()
+            };
+            store.update_link(&link.base.id, update)?;
+        }
+        checks.push(check);
+    }
+    if update {
+        store.save()?;
+    }
+    let dead = checks
+        .iter()
+        .filter(|c| c.state == crate::fetch::LinkState::Dead)
+        .count();
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&checks)?);
+        return Ok(dead > 0);
+    }
+
+    if checks.is_empty() {
+        println!("No links.");
+        return Ok(false);
+    }
+    for check in &checks {
+        let detail = match (&check.error, &check.redirected_to) {
+            (Some(error), _) => format!(" ({})", error),
+            (None, Some(to)) => format!(" (redirects to {})", to),
+            (None, None) => String::new(),
+        };
+        println!(
+            "{:<7} {:03} {} - {}{}",
+            check.state.to_string(),
+            check.sequence_number,
+            check.title,
+            check.url,
+            detail
+        );
+    }
+    let changed = checks
+        .iter()
+        .filter(|c| c.state == crate::fetch::LinkState::Changed)
+        .count();
+    println!(
+        "\n{} link(s) checked: {} dead, {} changed",
+        checks.len(),
+        dead,
+        changed
+    );
+
+    Ok(dead > 0)
+}
+
+pub fn handle_questions_list(
+    data_dir: Option<&Path>,
+    status: QuestionStatus,
+    format: OutputFormat,
+    include_archived: bool,
+) -> Result<()> {
+    let root = find_project_root(data_dir);
+    let store = LoroStore::open(&root)?;
+
+    let mut questions = store.list_questions()?;
+    questions.retain(|q| q.status == status && (include_archived || !q.base.archived));
+    questions.sort_by_key(|q| q.base.sequence_number);
+
+    if questions.is_empty() && format == OutputFormat::Plain {
+        println!("No {} questions.", status);
+        return Ok(());
+    }
+    print_questions(&store, &questions, format)
+}
+
+pub fn handle_questions_answer(
+    data_dir: Option<&Path>,
+    id: String,
+    by: String,
+    json: bool,
+) -> Result<()> {
+    let root = find_project_root(data_dir);
+    let store = LoroStore::open(&root)?.with_author(get_git_author());
+
+    let question = match find_entity_by_id(&store, &id)? {
+        EntityRef::Question(q) => q,
+        other => {
+            return Err(MedullaError::Storage(format!(
+                "Entity {} is not a question - {}",
+                id,
+                other.base().title
+            )))
+        }
+    };
+    let answer = find_entity_by_id(&store, &by)?;
+    let answer = answer.base();
+
+    let updates = QuestionUpdate {
+        status: Some(QuestionStatus::Answered),
+        answered_by: Some(Some(answer.id.to_string())),
+        ..Default::default()
+    };
+    store.update_question(&question.base.id, updates)?;
+    store.save()?;
+
+    let updated = store
+        .get_question(&question.base.id)?
+        .ok_or_else(|| MedullaError::Storage("Failed to retrieve updated question".to_string()))?;
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&updated)?);
+    } else {
+        println!(
+            "Answered question {:03} ({}) - {} by {:03} - {}",
+            updated.base.sequence_number,
+            &updated.base.id.to_string()[..7],
+            updated.base.title,
+            answer.sequence_number,
+            answer.title
+        );
+    }
+
+    Ok(())
+}
+
+/// Show completion of one milestone (with its tasks) or of every milestone.
+pub fn handle_milestones_status(
+    data_dir: Option<&Path>,
+    id: Option<String>,
+    format: OutputFormat,
+    include_archived: bool,
+) -> Result<()> {
+    let root = find_project_root(data_dir);
+    let store = LoroStore::open(&root)?;
+    let today = chrono::Local::now().date_naive();
+    let single = id.is_some();
+
+    let progress = match id {
+        Some(id) => {
+            let (uuid, entity_type) = find_entity_id_with_type(&store, &id)?;
+            if entity_type != "milestone" {
+                return Err(MedullaError::Storage(format!(
+                    "Entity {} is a {}, not a milestone",
+                    id, entity_type
+                )));
+            }
+            vec![crate::graph::MilestoneProgress::build(&store, uuid)?]
+        }
+        None => crate::graph::MilestoneProgress::all(&store, include_archived)?,
+    };
+
+    if format == OutputFormat::Json {
+        let json: Vec<serde_json::Value> = progress.iter().map(|p| p.to_json(today)).collect();
+        println!("{}", serde_json::to_string_pretty(&json)?);
+    } else if format.is_table() {
+        let mut table = Table::new(&[
+            "seq", "id", "status", "target", "done", "total", "percent", "title",
+        ]);
+        for p in &progress {
+            let (done, total) = p.counts();
+            table.push(vec![
+                p.milestone.base.sequence_number.to_string(),
+                p.milestone.base.id.to_string(),
+                p.milestone.status.to_string(),
+                p.milestone
+                    .target_date
+                    .map(|d| d.to_string())
+                    .unwrap_or_default(),
+                done.to_string(),
+                total.to_string(),
+                p.percent().to_string(),
+                p.milestone.base.title.clone(),
+            ]);
+        }
+        table.print(format)?;
+    } else if progress.is_empty() {
+        println!("No milestones found.");
+    } else {
+        for p in &progress {
+            let target = p
+                .milestone
+                .target_date
+                .map(|d| format!(" target:{}", d))
+                .unwrap_or_default();
+            let overdue = if p.is_overdue(today) { " OVERDUE" } else { "" };
+            println!(
+                "  {:03} ({}) [{}] {}{}{} - {}",
+                p.milestone.base.sequence_number,
+                &p.milestone.base.id.to_string()[..7],
+                p.milestone.status,
+                p.milestone.base.title,
+                target,
+                overdue,
+                p.summary()
+            );
+            // A single milestone also lists the tasks behind its progress
+            if single {
+                for t in &p.tasks {
+                    println!(
+                        "      {:03} ({}) [{}] {}",
+                        t.base.sequence_number,
+                        &t.base.id.to_string()[..7],
+                        t.status,
+                        t.base.title
+                    );
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+pub fn handle_tasks_tree(data_dir: Option<&Path>, id: String, json: bool) -> Result<()> {
+    let root = find_project_root(data_dir);
+    let store = LoroStore::open(&root)?;
+
+    let (uuid, entity_type) = find_entity_id_with_type(&store, &id)?;
+    if entity_type != "task" {
+        return Err(MedullaError::Storage(format!(
+            "Entity {} is a {}, not a task",
+            id, entity_type
+        )));
+    }
+
+    let tree = crate::graph::TaskTree::build(&store, uuid)?;
+    if json {
+        println!("{}", serde_json::to_string_pretty(&tree.to_json())?);
+    } else {
+        print!("{}", tree.render());
+    }
+
+    Ok(())
+}
+
+pub fn handle_tasks_next(data_dir: Option<&Path>, respect_wip: bool, json: bool) -> Result<()> {
+    let root = find_project_root(data_dir);
+    let store = LoroStore::open(&root)?;
+    let cache = SqliteCache::open(store.medulla_dir())?;
+
+    // Sync cache with store
+    store.sync_cache(&cache)?;
+    let config = crate::config::ProjectConfig::load(store.medulla_dir())?;
+    cache.set_task_scoring(&config.task_scoring)?;
+
+    let next_task = cache.get_next_task_with(respect_wip.then_some(&config.wip_limits))?;
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&next_task)?);
+    } else if let Some(task) = next_task {
+        let due_str = task
+            .due_date
+            .as_ref()
+            .map(|d| format!(" due:{}", d))
+            .unwrap_or_default();
+        let assignee_str = task
+            .assignee
+            .as_ref()
+            .map(|a| format!(" @{}", a))
+            .unwrap_or_default();
+        println!("Next task:\n");
+        println!(
+            "  {:03} ({}) [{}|{}]{}{} {}",
+            task.sequence_number,
+            &task.id[..7.min(task.id.len())],
+            task.status,
+            task.priority,
+            due_str,
+            assignee_str,
+            task.title
+        );
+        println!(
+            "\n  score {:.2} = priority {:.2} + due {:.2} + age {:.2} + unblocks {:.2} ({} tasks)",
+            task.score.total,
+            task.score.priority,
+            task.score.due,
+            task.score.age,
+            task.score.unblocks,
+            task.unblocks
+        );
+    } else {
+        println!("No ready tasks found.");
+    }
+
+    Ok(())
+}
+
+pub fn handle_tasks_blocked(data_dir: Option<&Path>, id: Option<String>, json: bool) -> Result<()> {
+    let root = find_project_root(data_dir);
+    let store = LoroStore::open(&root)?;
+    let cache = SqliteCache::open(store.medulla_dir())?;
+
+    // Sync cache with store
+    store.sync_cache(&cache)?;
+
+    if let Some(task_id) = id {
+        // Show blockers for a specific task
+        // First, resolve the task ID (could be sequence number or UUID prefix)
+        let resolved_id = resolve_task_id(&store, &task_id)?;
+        let blockers = cache.get_task_blockers(&resolved_id)?;
+
+        if json {
+            println!("{}", serde_json::to_string_pretty(&blockers)?);
+        } else if blockers.is_empty() {
+            println!("Task {} has no blockers.", task_id);
+        } else {
+            println!("Blockers for task {}:\n", task_id);
+            for blocker in blockers {
+                println!(
+                    "  {:03} ({}) [{}] {}",
+                    blocker.sequence_number,
+                    &blocker.id[..7.min(blocker.id.len())],
+                    blocker.status,
+                    blocker.title
+                );
+            }
+        }
+    } else {
+        // Show all blocked tasks
+        let blocked_tasks = cache.get_blocked_tasks(None)?;
+
+        if json {
+            println!("{}", serde_json::to_string_pretty(&blocked_tasks)?);
+        } else if blocked_tasks.is_empty() {
+            println!("No blocked tasks found.");
+        } else {
+            println!("Blocked tasks ({}):\n", blocked_tasks.len());
+            for task in blocked_tasks {
+                let due_str = task
+                    .due_date
+                    .as_ref()
+                    .map(|d| format!(" due:{}", d))
+                    .unwrap_or_default();
+                println!(
+                    "  {:03} ({}) [{}|{}]{} {}",
+                    task.sequence_number,
+                    &task.id[..7.min(task.id.len())],
+                    task.status,
+                    task.priority,
+                    due_str,
+                    task.title
+                );
+                println!("      blocked by:");
+                for blocker in &task.blockers {
+                    println!(
+                        "        - {:03} ({}) [{}] {}",
+                        blocker.sequence_number,
+                        &blocker.id[..7.min(blocker.id.len())],
+                        blocker.status,
+                        blocker.title
+                    );
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Resolve a task ID from sequence number, alias or UUID prefix to full UUID
+fn resolve_task_id(store: &LoroStore, id: &str) -> Result<String> {
+    store
+        .lookup_id(id, Some("task"))
+        .map(|(uuid, _)| uuid.to_string())
+        .ok_or_else(|| MedullaError::EntityNotFound(id.to_string()))
+}
+
+/// Find an entity by ID and return its UUID and type
+fn find_entity_id_with_type(store: &LoroStore, id: &str) -> Result<(uuid::Uuid, String)> {
+    store
+        .lookup_id(id, None)
+        .map(|(uuid, entity_type)| (uuid, entity_type.to_string()))
+        .ok_or_else(|| MedullaError::EntityNotFound(id.to_string()))
+}
+
+/// Get entity title by ID for display purposes
+fn get_entity_title(store: &LoroStore, id: &uuid::Uuid) -> String {
+    // Search through all entity types
+    if let Ok(decisions) = store.list_decisions() {
+        for d in decisions {
+            if d.base.id == *id {
+                return d.base.title;
+            }
+        }
+    }
+    if let Ok(tasks) = store.list_tasks() {
+        for t in tasks {
+            if t.base.id == *id {
+                return t.base.title;
+            }
+        }
+    }
+    if let Ok(notes) = store.list_notes() {
+        for n in notes {
+            if n.base.id == *id {
+                return n.base.title;
+            }
+        }
+    }
+    if let Ok(prompts) = store.list_prompts() {
+        for p in prompts {
+            if p.base.id == *id {
+                return p.base.title;
+            }
+        }
+    }
+    if let Ok(components) = store.list_components() {
+        for c in components {
+            if c.base.id == *id {
+                return c.base.title;
+            }
+        }
+    }
+    if let Ok(links) = store.list_links() {
+        for l in links {
+            if l.base.id == *id {
+                return l.base.title;
+            }
+        }
+    }
+    if let Ok(questions) = store.list_questions() {
+        for q in questions {
+            if q.base.id == *id {
+                return q.base.title;
+            }
+        }
+    }
+    if let Ok(milestones) = store.list_milestones() {
+        for m in milestones {
+            if m.base.id == *id {
+                return m.base.title;
+            }
+        }
+    }
+    id.to_string()[..7].to_string()
+}
+
+pub fn handle_relation_add(
+    data_dir: Option<&Path>,
+    source_id: String,
+    target_id: String,
+    relation_type: String,
+    json: bool,
+) -> Result<()> {
+    let root = find_project_root(data_dir);
+    let store = LoroStore::open(&root)?;
+
+    // Resolve source and target IDs to UUIDs with types
+    let (source_uuid, source_type) = find_entity_id_with_type(&store, &source_id)?;
+    let (target_uuid, target_type) = find_entity_id_with_type(&store, &target_id)?;
+
+    // Parse and validate relation type, including project-defined ones
+    let config = relation_config(&store)?;
+    let rel_type = config
+        .parse_relation_type(&relation_type)
+        .map_err(MedullaError::Storage)?;
+
+    // A blocks cycle would keep every task on it out of `tasks ready`
+    if rel_type == RelationType::Blocks {
+        if let Some(cycle) = crate::graph::blocks_cycle_with(&store, source_uuid, target_uuid)? {
+            let mut labels: Vec<String> = cycle.iter().map(|n| n.label()).collect();
+            labels.push(labels[0].clone());
+            return Err(MedullaError::Storage(format!(
+                "Adding this 'blocks' relation would create a cycle: {}",
+                labels.join(" -> ")
+            )));
+        }
+    }
+
+    // Create the relation
+    let mut relation = Relation::new(
+        source_uuid,
+        source_type.clone(),
+        target_uuid,
+        target_type.clone(),
+        rel_type.clone(),
+    );
+
+    // Try to get git author
+    let git_author = get_git_author();
+    relation.created_by = git_author;
+
+    // Store the relation
+    store.add_relation(&relation)?;
+    store.save()?;
+
+    if json {
+        let response = serde_json::json!({
+            "source_id": source_uuid.to_string(),
+            "source_type": source_type,
+            "target_id": target_uuid.to_string(),
+            "target_type": target_type,
+            "relation_type": rel_type.to_string(),
+            "created_at": relation.created_at.to_rfc3339(),
+            "message": format!(
+                "Created '{}' relation from {} to {}",
+                rel_type, source_id, target_id
+            ),
+        });
+        println!("{}", serde_json::to_string_pretty(&response)?);
+    } else {
+        println!(
+            "Created '{}' relation: {} ({}) -> {} ({})",
+            rel_type, source_id, source_type, target_id, target_type
+        );
+    }
+
+    Ok(())
+}
+
+pub fn handle_relation_delete(
+    data_dir: Option<&Path>,
+    source_id: String,
+    target_id: String,
+    relation_type: String,
+    json: bool,
+) -> Result<()> {
+    let root = find_project_root(data_dir);
+    let store = LoroStore::open(&root)?;
+
+    // Resolve source and target IDs to UUIDs
+    let (source_uuid, _) = find_entity_id_with_type(&store, &source_id)?;
+    let (target_uuid, _) = find_entity_id_with_type(&store, &target_id)?;
+
+    // Parse and validate relation type, including project-defined ones
+    let config = relation_config(&store)?;
+    let rel_type = config
+        .parse_relation_type(&relation_type)
+        .map_err(MedullaError::Storage)?;
+
+    // Delete from store
+    store.delete_relation(
+        &source_uuid.to_string(),
+        &rel_type.to_string(),
+        &target_uuid.to_string(),
+    )?;
+    store.save()?;
+
+    if json {
+        let response = serde_json::json!({
+            "deleted": true,
+            "source_id": source_uuid.to_string(),
+            "target_id": target_uuid.to_string(),
+            "relation_type": rel_type.to_string(),
+            "message": format!(
+                "Deleted '{}' relation from {} to {}",
+                rel_type, source_id, target_id
+            ),
+        });
+        println!("{}", serde_json::to_string_pretty(&response)?);
+    } else {
+        println!(
+            "Deleted '{}' relation: {} -> {}",
+            rel_type, source_id, target_id
+        );
+    }
+
+    Ok(())
+}
+
+pub fn handle_relation_list(data_dir: Option<&Path>, entity_id: String, json: bool) -> Result<()> {
+    let root = find_project_root(data_dir);
+    let store = LoroStore::open(&root)?;
+
+    // Resolve entity ID to UUID
+    let (entity_uuid, entity_type) = find_entity_id_with_type(&store, &entity_id)?;
+
+    // Get all relations involving this entity (both as source and target)
+    let relations_from = store.get_relations_from(&entity_uuid.to_string())?;
+    let relations_to = store.get_relations_to(&entity_uuid.to_string())?;
+
+    if json {
+        #[derive(serde::Serialize)]
+        struct RelationJson {
+            direction: String,
+            relation_type: String,
+            other_id: String,
+            other_type: String,
+            other_title: String,
+            created_at: String,
+        }
+
+        let mut all_relations: Vec<RelationJson> = Vec::new();
+
+        for r in &relations_from {
+            all_relations.push(RelationJson {
+                direction: "outgoing".to_string(),
+                relation_type: r.relation_type.to_string(),
+                other_id: r.target_id.to_string(),
+                other_type: r.target_type.clone(),
+                other_title: get_entity_title(&store, &r.target_id),
+                created_at: r.created_at.to_rfc3339(),
+            });
+        }
+
+        for r in &relations_to {
+            all_relations.push(RelationJson {
+                direction: "incoming".to_string(),
+                relation_type: r.relation_type.to_string(),
+                other_id: r.source_id.to_string(),
+                other_type: r.source_type.clone(),
+                other_title: get_entity_title(&store, &r.source_id),
+                created_at: r.created_at.to_rfc3339(),
+            });
+        }
+
+        let response = serde_json::json!({
+            "entity_id": entity_uuid.to_string(),
+            "entity_type": entity_type,
+            "relations": all_relations,
+        });
+        println!("{}", serde_json::to_string_pretty(&response)?);
+    } else {
+        let entity_title = get_entity_title(&store, &entity_uuid);
+        println!(
+            "Relations for {} ({}) - {}:\n",
+            entity_id, entity_type, entity_title
+        );
+
+        if relations_from.is_empty() && relations_to.is_empty() {
+            println!("  No relations found.");
+        } else {
+            if !relations_from.is_empty() {
+                println!("  Outgoing relations:");
+                for r in &relations_from {
+                    let target_title = get_entity_title(&store, &r.target_id);
+                    println!(
+                        "    --[{}]--> {} ({}) - {}",
+                        r.relation_type,
+                        &r.target_id.to_string()[..7],
+                        r.target_type,
+                        target_title
+                    );
+                }
+            }
+
+            if !relations_to.is_empty() {
+                println!("  Incoming relations:");
+                for r in &relations_to {
+                    let source_title = get_entity_title(&store, &r.source_id);
+                    println!(
+                        "    <--[{}]-- {} ({}) - {}",
+                        r.relation_type,
+                        &r.source_id.to_string()[..7],
+                        r.source_type,
+                        source_title
+                    );
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Handle relation infer command: backfill `references` relations from the
+/// `#<seq>` and `med-<seq>` mentions in every entity's content.
+pub fn handle_relation_infer(data_dir: Option<&Path>, json: bool) -> Result<()> {
+    let root = find_project_root(data_dir);
+    let store = LoroStore::open(&root)?;
+    let git_author = get_git_author();
+
+    let mut texts: Vec<(uuid::Uuid, &str, Option<String>)> = Vec::new();
+    texts.extend(
+        store
+            .list_decisions()?
+            .into_iter()
+            .map(|e| (e.base.id, "decision", e.base.content)),
+    );
+    texts.extend(
+        store
+            .list_tasks()?
+            .into_iter()
+            .map(|e| (e.base.id, "task", e.base.content)),
+    );
+    texts.extend(
+        store
+            .list_notes()?
+            .into_iter()
+            .map(|e| (e.base.id, "note", e.base.content)),
+    );
+    texts.extend(store.list_prompts()?.into_iter().map(|e| {
+        let text = [e.base.content, e.template]
+            .into_iter()
+            .flatten()
+            .collect::<Vec<_>>()
+            .join("\n\n");
+        (e.base.id, "prompt", Some(text))
+    }));
+    texts.extend(
+        store
+            .list_components()?
+            .into_iter()
+            .map(|e| (e.base.id, "component", e.base.content)),
+    );
+    texts.extend(
+        store
+            .list_links()?
+            .into_iter()
+            .map(|e| (e.base.id, "link", e.base.content)),
+    );
+    texts.extend(
+        store
+            .list_questions()?
+            .into_iter()
+            .map(|e| (e.base.id, "question", e.base.content)),
+    );
+    texts.extend(
+        store
+            .list_milestones()?
+            .into_iter()
+            .map(|e| (e.base.id, "milestone", e.base.content)),
+    );
+
+    let mut added = Vec::new();
+    for (id, entity_type, content) in &texts {
+        if let Some(content) = content {
+            added.extend(store.add_inferred_references(
+                *id,
+                entity_type,
+                content,
+                git_author.clone(),
+            )?);
+        }
+    }
+    if !added.is_empty() {
+        store.save()?;
+    }
+
+    if json {
+        let relations: Vec<serde_json::Value> = added
+            .iter()
+            .map(|r| {
+                serde_json::json!({
+                    "source_id": r.source_id.to_string(),
+                    "source_type": r.source_type,
+                    "target_id": r.target_id.to_string(),
+                    "target_type": r.target_type,
+                    "relation_type": r.relation_type.to_string(),
+                })
+            })
+            .collect();
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&serde_json::json!({
+                "entities_scanned": texts.len(),
+                "relations_added": relations,
+            }))?
+        );
+    } else if added.is_empty() {
+        println!("No new references found in {} entities.", texts.len());
+    } else {
+        for r in &added {
+            println!(
+                "  {} -> {} (references)",
+                get_entity_title(&store, &r.source_id),
+                get_entity_title(&store, &r.target_id)
+            );
+        }
+        println!(
+            "Added {} references relation(s) from {} entities.",
+            added.len(),
+            texts.len()
+        );
+    }
+
+    Ok(())
+}
+
+/// Project config with the relation types registered in the store merged in
+fn relation_config(store: &LoroStore) -> Result<crate::config::ProjectConfig> {
+    let mut config = crate::config::ProjectConfig::load(store.medulla_dir())?;
+    config.merge_relation_types(store.list_relation_types()?);
+    Ok(config)
+}
+
+pub fn handle_relation_type_add(
+    data_dir: Option<&Path>,
+    name: String,
+    inverse: Option<String>,
+    symmetric: bool,
+) -> Result<()> {
+    let root = find_project_root(data_dir);
+    let store = LoroStore::open(&root)?;
+
+    let def = crate::config::RelationTypeDef {
+        name: name.trim().to_lowercase(),
+        inverse: inverse.map(|i| i.trim().to_lowercase()),
+        symmetric,
+    };
+    store.put_relation_type(&def)?;
+    store.save()?;
+
+    match def.inverse_label() {
+        Some(inverse) => println!(
+            "Registered relation type {} (inverse: {})",
+            def.name, inverse
+        ),
+        None => println!("Registered relation type {}", def.name),
+    }
+
+    Ok(())
+}
+
+pub fn handle_relation_type_remove(data_dir: Option<&Path>, name: String) -> Result<()> {
+    let root = find_project_root(data_dir);
+    let store = LoroStore::open(&root)?;
+
+    store.delete_relation_type(name.trim())?;
+    store.save()?;
+    println!("Removed relation type {}", name.trim());
+
+    Ok(())
+}
+
+/// List built-in relation types, then those from config.yaml and the store
+pub fn handle_relation_type_list(data_dir: Option<&Path>, json: bool) -> Result<()> {
+    let root = find_project_root(data_dir);
+    let store = LoroStore::open(&root)?;
+    let config = crate::config::ProjectConfig::load(store.medulla_dir())?;
+
+    let mut types: Vec<serde_json::Value> = RelationType::builtin()
+        .iter()
+        .map(|t| serde_json::json!({ "name": t.to_string(), "source": "builtin" }))
+        .collect();
+    let mut add = |def: &crate::config::RelationTypeDef, source: &str| {
+        types.push(serde_json::json!({
+            "name": def.name,
+            "inverse": def.inverse_label(),
+            "source": source,
+        }));
+    };
+    for def in &config.relation_types {
+        add(def, "config");
+    }
+    for def in store.list_relation_types()? {
+        if !config.relation_types.iter().any(|d| d.name == def.name) {
+            add(&def, "store");
+        }
+    }
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&types)?);
+    } else {
+        for t in &types {
+            let inverse = t["inverse"]
+                .as_str()
+                .map(|i| format!(" (inverse: {})", i))
+                .unwrap_or_default();
+            println!(
+                "  {:<16} {}{}",
+                t["name"].as_str().unwrap_or_default(),
+                t["source"].as_str().unwrap_or_default(),
+                inverse
+            );
+        }
+    }
+
+    Ok(())
+}
+
+pub fn handle_search(
+    data_dir: Option<&Path>,
+    query: String,
+    semantic: bool,
+    format: OutputFormat,
+    include_archived: bool,
+    collection: Option<String>,
+) -> Result<()> {
+    let root = find_project_root(data_dir);
+    let store = LoroStore::open(&root)?;
+    let cache = SqliteCache::open(store.medulla_dir())?;
+
+    // Sync cache with store
+    store.sync_cache(&cache)?;
+
+    // Parse query for filters (type:, status:, tag:, created:)
+    let (search_text, filter) = crate::search::parse_query(&query);
+
+    let mut excluded = if include_archived {
+        std::collections::HashSet::new()
+    } else {
+        cache.get_archived_ids()?
+    };
+    if let Some(collection) = collection {
+        let members = store.collection_members(&collection);
+        excluded.extend(
+            store
+                .entity_ids()
+                .into_iter()
+                .filter(|id| !members.contains(id)),
+        );
+    }
+
+    if semantic {
+        return handle_search_semantic(&cache, &search_text, &filter, &excluded, format);
+    }
+
+    // Determine search text (if empty after parsing, search all)
+    let search_query = if search_text.is_empty() {
+        "*"
+    } else {
+        &search_text
+    };
+
+    // Perform full-text search across all entity types (or filtered type)
+    let results = if let Some(ref entity_type) = filter.entity_type {
+        // Search only the specified entity type
+        cache.search_by_type(entity_type, search_query, 100)?
+    } else {
+        cache.search_all(search_query, 100)?
+    };
+
+    // Apply additional filters
+    let results: Vec<_> = results
+        .into_iter()
+        .filter(|r| !excluded.contains(r.id()) && matches_cli_filter(&cache, r, &filter))
+        .take(50)
+        .collect();
+
+    if format == OutputFormat::Json || format.is_table() {
+        #[derive(serde::Serialize)]
+        struct SearchResultJson {
+            entity_type: String,
+            id: String,
+            sequence_number: u32,
+            title: String,
+            status: Option<String>,
+            snippet: Option<String>,
+        }
+
+        let json_results: Vec<SearchResultJson> = results
+            .into_iter()
+            .map(|r| match r {
+                crate::cache::SearchResult::Decision(d) => SearchResultJson {
+                    entity_type: "decision".to_string(),
+                    id: d.id,
+                    sequence_number: d.sequence_number,
+                    title: d.title,
+                    status: Some(d.status),
+                    snippet: d.content_snippet,
+                },
+                crate::cache::SearchResult::Task(t) => SearchResultJson {
+                    entity_type: "task".to_string(),
+                    id: t.id,
+                    sequence_number: t.sequence_number,
+                    title: t.title,
+                    status: Some(t.status),
+                    snippet: t.content_snippet,
+                },
+                crate::cache::SearchResult::Note(n) => SearchResultJson {
+                    entity_type: "note".to_string(),
+                    id: n.id,
+                    sequence_number: n.sequence_number,
+                    title: n.title,
+                    status: n.note_type,
+                    snippet: n.content_snippet,
+                },
+                crate::cache::SearchResult::Prompt(p) => SearchResultJson {
+                    entity_type: "prompt".to_string(),
+                    id: p.id,
+                    sequence_number: p.sequence_number,
+                    title: p.title,
+                    status: None,
+                    snippet: p.content_snippet,
+                },
+                crate::cache::SearchResult::Component(c) => SearchResultJson {
+                    entity_type: "component".to_string(),
+                    id: c.id,
+                    sequence_number: c.sequence_number,
+                    title: c.title,
+                    status: Some(c.status),
+                    snippet: c.content_snippet,
+                },
+                crate::cache::SearchResult::Link(l) => SearchResultJson {
+                    entity_type: "link".to_string(),
+                    id: l.id,
+                    sequence_number: l.sequence_number,
+                    title: l.title,
+                    status: l.link_type,
+                    snippet: l.content_snippet,
+                },
+                crate::cache::SearchResult::Question(q) => SearchResultJson {
+                    entity_type: "question".to_string(),
+                    id: q.id,
+                    sequence_number: q.sequence_number,
+                    title: q.title,
+                    status: Some(q.status),
+                    snippet: q.content_snippet,
+                },
+                crate::cache::SearchResult::Milestone(m) => SearchResultJson {
+                    entity_type: "milestone".to_string(),
+                    id: m.id,
+                    sequence_number: m.sequence_number,
+                    title: m.title,
+                    status: Some(m.status),
+                    snippet: m.content_snippet,
+                },
+            })
+            .collect();
+
+        if format == OutputFormat::Json {
+            println!("{}", serde_json::to_string_pretty(&json_results)?);
+        } else {
+            let mut table = Table::new(&["type", "seq", "id", "status", "title", "snippet"]);
+            for r in json_results {
+                let snippet = r
+                    .snippet
+                    .map(|s| s.replace("<mark>", "").replace("</mark>", ""))
+                    .unwrap_or_default();
+                table.push(vec![
+                    r.entity_type,
+                    r.sequence_number.to_string(),
+                    r.id,
+                    r.status.unwrap_or_default(),
+                    r.title,
+                    snippet,
+                ]);
+            }
+            table.print(format)?;
+        }
+    } else if results.is_empty() {
+        println!("No results found for '{}'.", query);
+    } else {
+        println!("Search results for '{}':\n", query);
+        for r in results {
+            match r {
+                crate::cache::SearchResult::Decision(d) => {
+                    println!(
+                        "  [DECISION] {:03} ({}) [{}] {}",
+                        d.sequence_number,
+                        &d.id[..7.min(d.id.len())],
+                        d.status,
+                        d.title
+                    );
+                    if let Some(snippet) = d.content_snippet {
+                        let clean_snippet = snippet
+                            .replace("<mark>", "\x1b[1m")
+                            .replace("</mark>", "\x1b[0m");
+                        println!("      {}", clean_snippet);
+                    }
+                }
+                crate::cache::SearchResult::Task(t) => {
+                    println!(
+                        "  [TASK] {:03} ({}) [{}|{}] {}",
+                        t.sequence_number,
+                        &t.id[..7.min(t.id.len())],
+                        t.status,
+                        t.priority,
+                        t.title
+                    );
+                    if let Some(assignee) = t.assignee {
+                        println!("      assignee: {}", assignee);
+                    }
+                    if let Some(snippet) = t.content_snippet {
+                        let clean_snippet = snippet
+                            .replace("<mark>", "\x1b[1m")
+                            .replace("</mark>", "\x1b[0m");
+                        println!("      {}", clean_snippet);
+                    }
+                }
+                crate::cache::SearchResult::Note(n) => {
+                    let type_str = n.note_type.as_deref().unwrap_or("note");
+                    println!(
+                        "  [NOTE] {:03} ({}) [{}] {}",
+                        n.sequence_number,
+                        &n.id[..7.min(n.id.len())],
+                        type_str,
+                        n.title
+                    );
+                    if let Some(snippet) = n.content_snippet {
+                        let clean_snippet = snippet
+                            .replace("<mark>", "\x1b[1m")
+                            .replace("</mark>", "\x1b[0m");
+                        println!("      {}", clean_snippet);
+                    }
+                }
+                crate::cache::SearchResult::Prompt(p) => {
+                    println!(
+                        "  [PROMPT] {:03} ({}) {}",
+                        p.sequence_number,
+                        &p.id[..7.min(p.id.len())],
+                        p.title
+                    );
+                    if !p.variables.is_empty() {
+                        println!("      vars: {}", p.variables.join(", "));
+                    }
+                    if let Some(snippet) = p.content_snippet {
+                        let clean_snippet = snippet
+                            .replace("<mark>", "\x1b[1m")
+                            .replace("</mark>", "\x1b[0m");
+                        println!("      {}", clean_snippet);
+                    }
+                }
+                crate::cache::SearchResult::Component(c) => {
+                    let type_str = c.component_type.as_deref().unwrap_or("component");
+                    println!(
+                        "  [COMPONENT] {:03} ({}) [{}|{}] {}",
+                        c.sequence_number,
+                        &c.id[..7.min(c.id.len())],
+                        type_str,
+                        c.status,
+                        c.title
+                    );
+                    if let Some(owner) = c.owner {
+                        println!("      owner: {}", owner);
+                    }
+                    if let Some(snippet) = c.content_snippet {
+                        let clean_snippet = snippet
+                            .replace("<mark>", "\x1b[1m")
+                            .replace("</mark>", "\x1b[0m");
+                        println!("      {}", clean_snippet);
+                    }
+                }
+                crate::cache::SearchResult::Link(l) => {
+                    println!(
+                        "  [LINK] {:03} ({}) {} -> {}",
+                        l.sequence_number,
+                        &l.id[..7.min(l.id.len())],
+                        l.title,
+                        l.url
+                    );
+                    if let Some(snippet) = l.content_snippet {
+                        let clean_snippet = snippet
+                            .replace("<mark>", "\x1b[1m")
+                            .replace("</mark>", "\x1b[0m");
+                        println!("      {}", clean_snippet);
+                    }
+                }
+                crate::cache::SearchResult::Question(q) => {
+                    println!(
+                        "  [QUESTION] {:03} ({}) [{}] {}",
+                        q.sequence_number,
+                        &q.id[..7.min(q.id.len())],
+                        q.status,
+                        q.title
+                    );
+                    if let Some(snippet) = q.content_snippet {
+                        let clean_snippet = snippet
+                            .replace("<mark>", "\x1b[1m")
+                            .replace("</mark>", "\x1b[0m");
+                        println!("      {}", clean_snippet);
+                    }
+                }
+                crate::cache::SearchResult::Milestone(m) => {
+                    println!(
+                        "  [MILESTONE] {:03} ({}) [{}] {}",
+                        m.sequence_number,
+                        &m.id[..7.min(m.id.len())],
+                        m.status,
+                        m.title
+                    );
+                    if let Some(snippet) = m.content_snippet {
+                        let clean_snippet = snippet
+                            .replace("<mark>", "\x1b[1m")
+                            .replace("</mark>", "\x1b[0m");
+                        println!("      {}", clean_snippet);
+                    }
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Check if a fulltext search result matches the CLI filter.
+fn matches_cli_filter(
+    cache: &SqliteCache,
+    result: &crate::cache::SearchResult,
+    filter: &crate::search::SearchFilter,
+) -> bool {
+    // Get entity ID and type from result
+    let (entity_id, entity_type, result_status) = match result {
+        crate::cache::SearchResult::Decision(d) => (&d.id, "decision", Some(&d.status)),
+        crate::cache::SearchResult::Task(t) => (&t.id, "task", Some(&t.status)),
+        crate::cache::SearchResult::Component(c) => (&c.id, "component", Some(&c.status)),
+        crate::cache::SearchResult::Note(n) => (&n.id, "note", None),
+        crate::cache::SearchResult::Prompt(p) => (&p.id, "prompt", None),
+        crate::cache::SearchResult::Link(l) => (&l.id, "link", None),
+        crate::cache::SearchResult::Question(q) => (&q.id, "question", Some(&q.status)),
+        crate::cache::SearchResult::Milestone(m) => (&m.id, "milestone", Some(&m.status)),
+    };
+
+    // Check status filter (use status from search result for efficiency)
+    if let Some(ref required_status) = filter.status {
+        match result_status {
+            Some(actual) if actual == required_status => {}
+            _ => return false,
+        }
+    }
+
+    // If no tag, date or people filters, we're done
+    if filter.tags.is_empty()
+        && filter.created_after.is_none()
+        && filter.created_before.is_none()
+        && filter.created_by.is_none()
+        && filter.assignee.is_none()
+    {
+        return true;
+    }
+
+    // Load full metadata for tag, date and people checks
+    let metadata = match cache.get_filter_metadata(entity_id, entity_type) {
+        Ok(Some(m)) => m,
+        _ => return false,
+    };
+
+    if !filter.matches_people(metadata.created_by.as_deref(), metadata.assignee.as_deref()) {
+        return false;
+    }
+
+    // Check tags
+    for required_tag in &filter.tags {
+        if !metadata
+            .tags
+            .iter()
+            .any(|t| t.eq_ignore_ascii_case(required_tag))
+        {
+            return false;
+        }
+    }
+
+    // Check dates
+    if let Some(ref after) = filter.created_after {
+        match &metadata.created_at {
+            Some(created) if created >= after => {}
+            _ => return false,
+        }
+    }
+
+    if let Some(ref before) = filter.created_before {
+        match &metadata.created_at {
+            Some(created) if created <= before => {}
+            _ => return false,
+        }
+    }
+
+    true
+}
+
+/// Handle semantic search using vector embeddings.
+fn handle_search_semantic(
+    cache: &SqliteCache,
+    query: &str,
+    filter: &crate::search::SearchFilter,
+    excluded: &std::collections::HashSet<String>,
+    format: OutputFormat,
+) -> Result<()> {
+    let embedder = get_embedder().ok_or_else(|| {
+        MedullaError::Embedding("Embedding model not available. Try again later.".to_string())
+    })?;
+
+    // Compute query embedding
+    let query_embedding = embedder.embed(query)?;
+
+    // Perform semantic search with entity type filter
+    let results =
+        cache.search_semantic(&query_embedding, filter.entity_type.as_deref(), 50, 0.3)?;
+
+    // Apply additional filters (status, tags, dates)
+    let results: Vec<_> = results
+        .into_iter()
+        .filter(|r| !excluded.contains(&r.entity_id) && matches_semantic_filter(cache, r, filter))
+        .take(20)
+        .collect();
+
+    if format == OutputFormat::Json {
+        println!("{}", serde_json::to_string_pretty(&results)?);
+    } else if format.is_table() {
+        let mut table = Table::new(&["type", "seq", "id", "score", "title"]);
+        for r in &results {
+            table.push(vec![
+                r.entity_type.clone(),
+                r.sequence_number.to_string(),
+                r.entity_id.clone(),
+                format!("{:.4}", r.score),
+                r.title.clone(),
+            ]);
+        }
+        table.print(format)?;
+    } else if results.is_empty() {
+        println!("No semantically similar results found for '{}'.", query);
+        println!("\nHint: Semantic search requires entities to have embeddings.");
+        println!(
+            "Try running 'medulla cache rebuild' to generate embeddings for existing entities."
+        );
+    } else {
+        println!(
+            "Semantic search results for '{}' (similarity threshold: 0.3):\n",
+            query
+        );
+        for r in results {
+            let type_upper = r.entity_type.to_uppercase();
+            println!(
+                "  [{type_upper}] {:03} ({}) {:.2}% - {}",
+                r.sequence_number,
+                &r.entity_id[..7.min(r.entity_id.len())],
+                r.score * 100.0,
+                r.title
+            );
+        }
+    }
+
+    Ok(())
+}
+
+/// Check if a semantic search result matches the CLI filter.
+fn matches_semantic_filter(
+    cache: &SqliteCache,
+    result: &crate::cache::SemanticSearchResult,
+    filter: &crate::search::SearchFilter,
+) -> bool {
+    // If no advanced filters, always match
+    if filter.status.is_none()
+        && filter.tags.is_empty()
+        && filter.created_after.is_none()
+        && filter.created_before.is_none()
+        && filter.created_by.is_none()
+        && filter.assignee.is_none()
+    {
+        return true;
+    }
+
+    // Load filter metadata
+    let metadata = match cache.get_filter_metadata(&result.entity_id, &result.entity_type) {
+        Ok(Some(m)) => m,
+        _ => return false,
+    };
+
+    if !filter.matches_people(metadata.created_by.as_deref(), metadata.assignee.as_deref()) {
+        return false;
+    }
+
+    // Check status
+    if let Some(ref required_status) = filter.status {
+        match &metadata.status {
+            Some(actual) if actual == required_status => {}
+            _ => return false,
+        }
+    }
+
+    // Check tags
+    for required_tag in &filter.tags {
+        if !metadata
+            .tags
+            .iter()
+            .any(|t| t.eq_ignore_ascii_case(required_tag))
+        {
+            return false;
+        }
+    }
+
+    // Check dates
+    if let Some(ref after) = filter.created_after {
+        match &metadata.created_at {
+            Some(created) if created >= after => {}
+            _ => return false,
+        }
+    }
+
+    if let Some(ref before) = filter.created_before {
+        match &metadata.created_at {
+            Some(created) if created <= before => {}
+            _ => return false,
+        }
+    }
+
+    true
+}
+
+/// Open a project's store, cache and config as an MCP server.
+fn open_project_server(root: &Path) -> Result<MedullaServer> {
+    // Open the store, cache and config, syncing the cache with the store
+    let client = crate::client::Client::open(root)?.with_author(get_git_author());
+
+    // Check performance thresholds
+    if let Ok(stats) = client.cache().get_stats() {
+        let loro_size = std::fs::metadata(root.join(".medulla/loro.db"))
+            .map(|m| m.len())
+            .unwrap_or(0);
+        for warning in crate::warnings::check_thresholds(&stats, loro_size) {
+            tracing::warn!("{}", crate::warnings::format_warning(&warning));
+        }
+    }
+
+    let (store, cache, config) = client.into_parts();
+    Ok(MedullaServer::new(store, cache).with_config(config))
+}
+
+/// Start the MCP server with graceful shutdown support.
+///
+/// Server startup flow:
+/// 1. Open existing `LoroStore` (fail if not initialized)
+/// 2. Open/create `SqliteCache`, sync from Loro
+/// 3. Create `MedullaServer` with store + cache
+/// 4. Install signal handlers for graceful shutdown
+/// 5. Call `server.serve(rmcp::transport::io::stdio()).await`
+/// 6. Wait for shutdown signal
+pub fn handle_serve(
+    data_dir: Option<&Path>,
+    http_port: Option<u16>,
+    workspace: bool,
+    metrics: bool,
+    require_confirmation: bool,
+) -> Result<()> {
+    let root = find_project_root(data_dir);
+    let registry = if workspace {
+        crate::workspace::Workspace::load()?
+    } else {
+        crate::workspace::Workspace::default()
+    };
+
+    // Check if this is an initialized medulla project. Serving a workspace
+    // from outside any project defaults to its first registered project.
+    let root = if root.join(".medulla").exists() {
+        root
+    } else if let Some(first) = registry.projects.values().next() {
+        first.clone()
+    } else {
+        return Err(MedullaError::Storage(
+            "Not a medulla project. Run `medulla init` first.".to_string(),
+        ));
+    };
+
+    // Set up tracing to stderr (stdout is reserved for MCP protocol in stdio mode)
+    let filter = tracing_subscriber::EnvFilter::try_from_env("MEDULLA_LOG_LEVEL")
+        .or_else(|_| tracing_subscriber::EnvFilter::try_from_env("RUST_LOG"))
+        .unwrap_or_else(|_| tracing_subscriber::EnvFilter::new("info"));
+
+    tracing_subscriber::fmt()
+        .with_env_filter(filter)
+        .with_writer(std::io::stderr)
+        .init();
+
+    let open = |dir: &Path| {
+        open_project_server(dir).map(|server| {
+            if require_confirmation {
+                server.with_required_confirmation()
+            } else {
+                server
+            }
+        })
+    };
+    let mut server = open(&root)?;
+
+    if workspace {
+        let mut projects = std::collections::BTreeMap::new();
+        for (name, dir) in &registry.projects {
+            // The default project shares its store rather than opening it twice
+            let project = if registry.name_of(&root) == Some(name.as_str()) {
+                server.clone()
+            } else {
+                match open(dir) {
+                    Ok(project) => project,
+                    Err(e) => {
+                        tracing::warn!("Skipping project '{}' ({}): {}", name, dir.display(), e);
+                        continue;
+                    }
+                }
+            };
+            projects.insert(name.clone(), project);
+        }
+        tracing::info!(
+            "Serving {} workspace project(s): {}",
+            projects.len(),
+            projects.keys().cloned().collect::<Vec<_>>().join(", ")
+        );
+        server = server.with_projects(projects);
+    }
+
+    // Run the async server with tokio runtime
+    let rt = tokio::runtime::Runtime::new()
+        .map_err(|e| MedullaError::Storage(format!("Failed to create tokio runtime: {}", e)))?;
+
+    rt.block_on(async move {
+        match http_port {
+            Some(port) => {
+                tracing::info!("Starting Medulla MCP HTTP server on port {}", port);
+                run_http_server(server, port, metrics).await
+            }
+            None => {
+                tracing::info!("Starting Medulla MCP server (stdio)");
+                run_server(server).await
+            }
+        }
+    })
+}
+
+/// Run the MCP server with graceful shutdown on SIGINT/SIGTERM (stdio transport).
+async fn run_server(server: MedullaServer) -> Result<()> {
+    use rmcp::transport::io::stdio;
+
+    let transport = stdio();
+
+    // Spawn the server task
+    let server_task = tokio::spawn(async move {
+        match server.serve(transport).await {
+            Ok(_) => {
+                tracing::info!("Server stopped");
+                Ok(())
+            }
+            Err(e) => {
+                tracing::error!("Server error: {}", e);
+                Err(MedullaError::Storage(format!("MCP server error: {}", e)))
+            }
+        }
+    });
+
+    // Wait for shutdown signal or server completion
+    tokio::select! {
+        result = server_task => {
+            match result {
+                Ok(inner_result) => inner_result,
+                Err(e) => Err(MedullaError::Storage(format!("Server task panicked: {}", e))),
+            }
+        }
+        _ = shutdown_signal() => {
+            tracing::info!("Shutdown signal received, stopping server...");
+            // Give a brief moment for cleanup
+            tokio::time::sleep(Duration::from_millis(100)).await;
+            Ok(())
+        }
+    }
+}
+
+/// Run the MCP server over HTTP with graceful shutdown.
+async fn run_http_server(server: MedullaServer, port: u16, metrics: bool) -> Result<()> {
+    use axum::{routing::get, Router};
+    use rmcp::transport::streamable_http_server::{
+        session::local::LocalSessionManager, StreamableHttpServerConfig, StreamableHttpService,
+    };
+    use std::sync::Arc;
+    use tokio_util::sync::CancellationToken;
+
+    let cancel_token = CancellationToken::new();
+    let config = StreamableHttpServerConfig {
+        sse_keep_alive: Some(Duration::from_secs(30)),
+        sse_retry: Some(Duration::from_secs(5)),
+        stateful_mode: true,
+        cancellation_token: cancel_token.clone(),
+    };
+
+    let session_manager = Arc::new(LocalSessionManager::default());
+
+    // Create the MCP HTTP service
+    let server_clone = server.clone();
+    let mcp_service =
+        StreamableHttpService::new(move || Ok(server_clone.clone()), session_manager, config);
+
+    // Build the router with MCP, REST and utility routes
+    let mut router = Router::new()
+        .nest_service("/mcp", mcp_service)
+        .layer(axum::middleware::from_fn_with_state(
+            server.clone(),
+            crate::http::limit_payload,
+        ))
+        .merge(crate::http::router(server.clone()))
+        .route("/health", get(|| async { "OK" }))
+        .route(
+            "/openapi.yaml",
+            get(|| async {
+                (
+                    [(axum::http::header::CONTENT_TYPE, "application/yaml")],
+                    include_str!("../../openapi.yaml"),
+                )
+            }),
+        );
+
+    if metrics {
+        router = router.merge(crate::http::metrics_router(server));
+    }
+
+    let addr = std::net::SocketAddr::from(([0, 0, 0, 0], port));
+    tracing::info!("MCP HTTP server listening on http://{}", addr);
+    tracing::info!("  - MCP endpoint: http://{}/mcp", addr);
+    tracing::info!("  - REST API: http://{}/api", addr);
+    tracing::info!("  - Health check: http://{}/health", addr);
+    tracing::info!("  - OpenAPI spec: http://{}/openapi.yaml", addr);
+    if metrics {
+        tracing::info!("  - Metrics: http://{}/metrics", addr);
+    }
+
+    let listener = tokio::net::TcpListener::bind(addr)
+        .await
+        .map_err(|e| MedullaError::Storage(format!("Failed to bind to port {}: {}", port, e)))?;
+
+    // Run server with graceful shutdown
+    let cancel_token_clone = cancel_token.clone();
+    // Peer addresses let the REST API rate limit each client separately
+    axum::serve(
+        listener,
+        router.into_make_service_with_connect_info::<std::net::SocketAddr>(),
+    )
+    .with_graceful_shutdown(async move {
+        shutdown_signal().await;
+        tracing::info!("Shutdown signal received, stopping HTTP server...");
+        cancel_token_clone.cancel();
+    })
+    .await
+    .map_err(|e| MedullaError::Storage(format!("HTTP server error: {}", e)))?;
+
+    Ok(())
+}
+
+/// Wait for shutdown signal (Ctrl+C or SIGTERM).
+async fn shutdown_signal() {
+    let ctrl_c = async {
+        tokio::signal::ctrl_c()
+            .await
+            .expect("Failed to install Ctrl+C handler");
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())
+            .expect("Failed to install SIGTERM handler")
+            .recv()
+            .await;
+    };
+
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {},
+        _ = terminate => {},
+    }
+}
+
+/// Handle cache stats command.
+pub fn handle_cache_stats(data_dir: Option<&Path>, json: bool) -> Result<()> {
+    let root = find_project_root(data_dir);
+    let store = LoroStore::open(&root)?;
+    let cache = SqliteCache::open(store.medulla_dir())?;
+
+    // Sync cache with store
+    store.sync_cache(&cache)?;
+
+    let stats = cache.get_stats()?;
+
+    // Get loro.db size
+    let loro_path = root.join(".medulla/loro.db");
+    let loro_size = std::fs::metadata(&loro_path).map(|m| m.len()).unwrap_or(0);
+
+    // Check for warnings
+    let warnings = crate::warnings::check_thresholds(&stats, loro_size);
+
+    if json {
+        #[derive(serde::Serialize)]
+        struct StatsJson {
+            entity_count: usize,
+            embedding_count: usize,
+            decisions: usize,
+            tasks: usize,
+            notes: usize,
+            prompts: usize,
+            components: usize,
+            links: usize,
+            relations: usize,
+            loro_db_size_bytes: u64,
+            warnings: Vec<String>,
+        }
+
+        let json_out = StatsJson {
+            entity_count: stats.entity_count,
+            embedding_count: stats.embedding_count,
+            decisions: stats.decisions,
+            tasks: stats.tasks,
+            notes: stats.notes,
+            prompts: stats.prompts,
+            components: stats.components,
+            links: stats.links,
+            relations: stats.relations,
+            loro_db_size_bytes: loro_size,
+            warnings: warnings
+                .iter()
+                .map(crate::warnings::format_warning)
+                .collect(),
+        };
+
+        println!("{}", serde_json::to_string_pretty(&json_out)?);
+    } else {
+        println!("Cache Statistics:");
+        println!("  Entities: {}", stats.entity_count);
+        println!("    Decisions:  {}", stats.decisions);
+        println!("    Tasks:      {}", stats.tasks);
+        println!("    Notes:      {}", stats.notes);
+        println!("    Prompts:    {}", stats.prompts);
+        println!("    Components: {}", stats.components);
+        println!("    Links:      {}", stats.links);
+        println!("  Relations: {}", stats.relations);
+        println!("  Embeddings: {}", stats.embedding_count);
+        println!(
+            "  loro.db size: {:.2} MB",
+            loro_size as f64 / (1024.0 * 1024.0)
+        );
+
+        if !warnings.is_empty() {
+            println!();
+            for warning in &warnings {
+                eprintln!("{}", crate::warnings::format_warning(warning));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Handle cache rebuild command.
+pub fn handle_cache_rebuild(data_dir: Option<&Path>, json: bool) -> Result<()> {
+    let root = find_project_root(data_dir);
+    let store = LoroStore::open(&root)?;
+    let cache = SqliteCache::open(store.medulla_dir())?;
+
+    // Clear the cache
+    cache.clear()?;
+
+    // Sync from store (rebuilds all index data)
+    store.sync_cache(&cache)?;
+
+    // Recompute all embeddings
+    let embedder = get_embedder();
+    if embedder.is_none() && !json {
+        eprintln!("Warning: Embedding model not available, skipping embedding regeneration");
+    }
+
+    let mut embedding_count = 0;
+    let mut errors = 0;
+
+    if let Some(embedder) = embedder {
+        use crate::embeddings::backfill::{backfill, pending_embeddings};
+
+        // The cache was just cleared, so every entity is pending
+        let pending = pending_embeddings(&store, &cache)?;
+        let bar = if json {
+            indicatif::ProgressBar::hidden()
+        } else {
+            indicatif::ProgressBar::new(pending.len() as u64)
+        };
+        bar.set_style(
+            indicatif::ProgressStyle::with_template("Embedding {bar:40} {pos}/{len} ({eta})")
+                .expect("valid progress template"),
+        );
+        let stats = backfill(&cache, embedder, &pending, |done, _| {
+            bar.set_position(done as u64)
+        });
+        bar.finish_and_clear();
+
+        embedding_count = stats.computed;
+        errors = stats.errors;
+    }
+
+    // Write a fresh vector index; this also upgrades caches created before
+    // semantic search used one
+    let vectors_indexed = cache.rebuild_vector_index()?;
+
+    let stats = cache.get_stats()?;
+
+    if json {
+        #[derive(serde::Serialize)]
+        struct RebuildResult {
+            success: bool,
+            entity_count: usize,
+            embeddings_computed: usize,
+            embedding_errors: usize,
+            vectors_indexed: usize,
+        }
+
+        let result = RebuildResult {
+            success: true,
+            entity_count: stats.entity_count,
+            embeddings_computed: embedding_count,
+            embedding_errors: errors,
+            vectors_indexed,
+        };
+
+        println!("{}", serde_json::to_string_pretty(&result)?);
+    } else {
+        println!("Cache rebuilt successfully.");
+        println!("  Entities indexed: {}", stats.entity_count);
+        println!("  Embeddings computed: {}", embedding_count);
+        println!("  Vectors indexed: {}", vectors_indexed);
+        if errors > 0 {
+            eprintln!("  Embedding errors: {}", errors);
+        }
+    }
+
+    Ok(())
+}
+
+/// Handle cache rebuild-fts command.
+pub fn handle_cache_rebuild_fts(data_dir: Option<&Path>, json: bool) -> Result<()> {
+    let root = find_project_root(data_dir);
+    let store = LoroStore::open(&root)?;
+    let cache = SqliteCache::open(store.medulla_dir())?;
+
+    let tables_rebuilt = cache.rebuild_fts()?;
+
+    if json {
+        #[derive(serde::Serialize)]
+        struct RebuildFtsResult {
+            success: bool,
+            tables_rebuilt: usize,
+        }
+
+        let result = RebuildFtsResult {
+            success: true,
+            tables_rebuilt,
+        };
+
+        println!("{}", serde_json::to_string_pretty(&result)?);
+    } else {
+        println!("Full-text indexes rebuilt successfully.");
+        println!("  Tables rebuilt: {}", tables_rebuilt);
+    }
+
+    Ok(())
+}
+
+/// Handle doctor command. Returns the process exit code for the worst
+/// issue found (see `Severity::exit_code`).
+pub fn handle_doctor(data_dir: Option<&Path>, json: bool) -> Result<i32> {
+    let root = find_project_root(data_dir);
+    let store = LoroStore::open(&root)?;
+    let cache = SqliteCache::open(store.medulla_dir())?;
+
+    // No sync here: a stale cache is one of the things being checked
+    let report = crate::warnings::health_check(&store, &cache)?;
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&report)?);
+    } else {
+        println!("Health: {}", report.status.as_str());
+        println!(
+            "  Entities: {} in store, {} in cache",
+            report.entity_count, report.cached_entity_count
+        );
+        println!(
+            "  Embeddings: {} ({} orphaned)",
+            report.embedding_count, report.orphaned_embeddings
+        );
+        println!(
+            "  loro.db size: {:.2} MB",
+            report.loro_db_size_bytes as f64 / (1024.0 * 1024.0)
+        );
+
+        if !report.issues.is_empty() {
+            println!();
+            for issue in &report.issues {
+                println!("[{}] {}", issue.severity.as_str(), issue.message);
+                if let Some(fix) = issue.fix {
+                    println!("  fix: {}", fix);
+                }
+            }
+        }
+    }
+
+    Ok(report.exit_code())
+}
+
+// =============================================================================
+// Snapshot handlers
+// =============================================================================
+
+/// Handle snapshot generation command.
+#[allow(clippy::too_many_arguments)]
+pub fn handle_snapshot(
+    data_dir: Option<&Path>,
+    output: Option<String>,
+    format: &str,
+    verbose: bool,
+    layout: Option<crate::snapshot::SnapshotLayout>,
+    tag: Option<String>,
+    related: bool,
+    collection: Option<String>,
+) -> Result<()> {
+    let root = find_project_root(data_dir);
+    let full_store = LoroStore::open(&root)?;
+    // A collection snapshot is generated from a copy holding only its entities
+    let view = collection
+        .as_deref()
+        .map(|name| full_store.collection_view(name))
+        .transpose()?;
+    let store = view.as_ref().unwrap_or(&full_store);
+    let html = format == "html";
+    if html && related {
+        return Err(MedullaError::Config(
+            "--related is only supported for markdown snapshots".to_string(),
+        ));
+    }
+
+    let default_dir = if html {
+        crate::snapshot::DEFAULT_SITE_DIR
+    } else {
+        crate::snapshot::DEFAULT_SNAPSHOT_DIR
+    };
+    let base_dir = output
+        .map(PathBuf::from)
+        .unwrap_or_else(|| root.join(default_dir));
+    let base_dir = match collection.as_deref() {
+        Some(name) => crate::snapshot::collection_snapshot_dir(&base_dir, &name.to_lowercase()),
+        None => base_dir,
+    };
+
+    // A tagged snapshot is written once, in the layout of the main one
+    let (snapshot_dir, layout) = match tag.as_deref() {
+        Some(tag) => {
+            crate::snapshot::validate_snapshot_tag(tag)?;
+            let dir = crate::snapshot::tagged_snapshot_dir(&base_dir, tag);
+            if dir.exists() {
+                return Err(MedullaError::Storage(format!(
+                    "Snapshot '{}' already exists at {}; remove it to regenerate",
+                    tag,
+                    dir.display()
+                )));
+            }
+            let layout =
+                layout.or(crate::snapshot::read_snapshot_meta(&base_dir)?.map(|meta| meta.layout));
+            (dir, layout)
+        }
+        None => (base_dir, layout),
+    };
+    let generated_at = chrono::Utc::now();
+
+    // Without an explicit layout, keep the one the last snapshot used
+    let stats = match layout {
+        _ if html => crate::snapshot::generate_html_site(store, &snapshot_dir)?,
+        _ if related => {
+            let cache = SqliteCache::open(store.medulla_dir())?;
+            full_store.sync_cache(&cache)?;
+            let suggestions = crate::snapshot::RelatedSuggestions::collect(
+                store,
+                &cache,
+                crate::snapshot::DEFAULT_RELATED_LIMIT,
+                crate::snapshot::DEFAULT_RELATED_MIN_SCORE,
+            )?;
+            let layout = match layout {
+                Some(layout) => layout,
+                None => crate::snapshot::read_snapshot_meta(&snapshot_dir)?
+                    .map(|meta| meta.layout)
+                    .unwrap_or_default(),
+            };
+            crate::snapshot::generate_snapshot_with_related(
+                store,
+                &snapshot_dir,
+                layout,
+                suggestions,
+            )?
+        }
+        Some(layout) => {
+            crate::snapshot::generate_snapshot_with_layout(store, &snapshot_dir, layout)?
+        }
+        None => crate::snapshot::generate_snapshot(store, &snapshot_dir)?,
+    };
+
+    if verbose {
+        println!("Generated {} files:", stats.files_generated.len());
+        for file in &stats.files_generated {
+            let marker = if stats.files_written.contains(file) {
+                "*"
+            } else {
+                " "
+            };
+            println!("  {} {}", marker, file);
+        }
+        for file in &stats.files_removed {
+            println!("  - {}", file);
+        }
+        println!();
+    }
+
+    println!(
+        "Snapshot generated: {} decisions, {} tasks ({} active), {} notes, {} prompts, {} components, {} links",
+        stats.decisions,
+        stats.tasks_total,
+        stats.tasks_active,
+        stats.notes,
+        stats.prompts,
+        stats.components,
+        stats.links,
+    );
+
+    println!(
+        "Files: {} written, {} unchanged, {} removed",
+        stats.files_written.len(),
+        stats.files_generated.len() - stats.files_written.len(),
+        stats.files_removed.len(),
+    );
+    println!("Output: {}", snapshot_dir.display());
+
+    crate::snapshot::record_snapshot_run(
+        store.medulla_dir(),
+        crate::snapshot::SnapshotRun {
+            generated_at,
+            tag,
+            format: if html { "html" } else { "markdown" }.to_string(),
+            path: snapshot_dir
+                .strip_prefix(&root)
+                .unwrap_or(&snapshot_dir)
+                .display()
+                .to_string(),
+            collection: collection.as_ref().map(|name| name.to_lowercase()),
+            store_version: full_store.version_hash(),
+            entity_counts: stats.entity_counts(),
+        },
+    )?;
+
+    Ok(())
+}
+
+pub fn handle_snapshot_list(data_dir: Option<&Path>, tagged: bool, json: bool) -> Result<()> {
+    let root = find_project_root(data_dir);
+    let store = LoroStore::open(&root)?;
+
+    let mut runs = crate::snapshot::read_snapshot_history(store.medulla_dir())?;
+    runs.retain(|r| !tagged || r.tag.is_some());
+    runs.reverse();
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&runs)?);
+        return Ok(());
+    }
+    if runs.is_empty() {
+        println!("No snapshots recorded.");
+        return Ok(());
+    }
+    for run in &runs {
+        println!(
+            "  {}  {:<12} {:<8} {:>5} entities  {}",
+            run.generated_at.format("%Y-%m-%d %H:%M"),
+            run.tag.as_deref().unwrap_or("-"),
+            run.format,
+            run.total_entities(),
+            run.path
+        );
+    }
+
+    Ok(())
+}
+
+/// Handle snapshot diff: compare the current store against `old_ref`.
+///
+/// `old_ref` is tried as a snapshot directory or `loro.db` file on disk,
+/// then as a date or window into the store's own history, then as a git
+/// revision holding a committed `.medulla/loro.db`.
+pub fn handle_snapshot_diff(
+    data_dir: Option<&Path>,
+    old_ref: &str,
+    output: Option<PathBuf>,
+    json: bool,
+) -> Result<()> {
+    let root = find_project_root(data_dir);
+    let store = LoroStore::open(&root)?;
+
+    let path = Path::new(old_ref);
+    let diff = if path.is_dir() {
+        crate::snapshot::diff_snapshot_dir(path, &store)?
+    } else if path.is_file() {
+        let old = LoroStore::from_bytes(&std::fs::read(path)?, path.to_path_buf())?;
+        crate::snapshot::diff_stores(old_ref, &old, &store)?
+    } else if let Some(at) = parse_history_time(old_ref).or_else(|| {
+        let days = crate::snapshot::parse_period(old_ref).ok()?;
+        Some(chrono::Utc::now() - chrono::Duration::days(days as i64))
+    }) {
+        let old = store.at_time(at)?;
+        crate::snapshot::diff_stores(&crate::snapshot::format_timestamp(&at), &old, &store)?
+    } else {
+        let bytes = git_show_store(&root, old_ref).ok_or_else(|| {
+            MedullaError::Storage(format!(
+                "'{}' is not a snapshot directory, store file, date or git revision with a committed .medulla/loro.db",
+                old_ref
+            ))
+        })?;
+        let old = LoroStore::from_bytes(&bytes, store.path().to_path_buf())?;
+        crate::snapshot::diff_stores(old_ref, &old, &store)?
+    };
+
+    let text = if json {
+        serde_json::to_string_pretty(&diff)?
+    } else {
+        diff.to_markdown()
+    };
+
+    match output {
+        Some(path) => {
+            std::fs::write(&path, text)?;
+            eprintln!("Wrote snapshot diff to {}", path.display());
+        }
+        None => println!("{}", text),
+    }
+
+    Ok(())
+}
+
+/// The store as committed at a git revision, read with `git show`
+fn git_show_store(root: &Path, rev: &str) -> Option<Vec<u8>> {
+    if rev.starts_with('-') {
+        return None;
+    }
+
+    let output = std::process::Command::new("git")
+        .arg("-C")
+        .arg(root)
+        .arg("show")
+        .arg(format!("{}:./.medulla/loro.db", rev))
+        .output()
+        .ok()?;
+    output.status.success().then_some(output.stdout)
+}
+
+// =============================================================================
+// Watch handler
+// =============================================================================
+
+/// Whether a file system event rewrote the Loro document at `db_path`.
+fn is_store_change(event: &notify::Event, db_path: &Path) -> bool {
+    (event.kind.is_create() || event.kind.is_modify())
+        && event
+            .paths
+            .iter()
+            .any(|p| p.file_name() == db_path.file_name())
+}
+
+/// Reload the store, sync the cache and (optionally) regenerate the snapshot.
+fn refresh_derived(root: &Path, snapshot: bool) -> Result<String> {
+    let store = LoroStore::open(root)?;
+    let cache = SqliteCache::open(store.medulla_dir())?;
+    store.sync_cache(&cache)?;
+
+    if !snapshot {
+        return Ok("cache synced".to_string());
+    }
+    let stats = crate::snapshot::generate_snapshot(&store, &root.join(".medulla/snapshot"))?;
+    Ok(format!(
+        "cache synced, snapshot regenerated ({} entities)",
+        stats.total_entities()
+    ))
+}
+
+pub fn handle_watch(data_dir: Option<&Path>, debounce_ms: u64, no_snapshot: bool) -> Result<()> {
+    use notify::Watcher;
+
+    let root = find_project_root(data_dir);
+    let store = LoroStore::open(&root)?;
+    let db_path = store.path().to_path_buf();
+    let medulla_dir = store.medulla_dir().to_path_buf();
+    drop(store);
+
+    let (tx, rx) = std::sync::mpsc::channel();
+    let mut watcher = notify::recommended_watcher(tx)?;
+    // Watch the directory rather than the file, since git replaces the file
+    // on checkout instead of writing to it
+    watcher.watch(&medulla_dir, notify::RecursiveMode::NonRecursive)?;
+
+    println!("{}", refresh_derived(&root, !no_snapshot)?);
+    println!("Watching {} (Ctrl+C to stop)", db_path.display());
+
+    let debounce = Duration::from_millis(debounce_ms);
+    for event in &rx {
+        match event {
+            Ok(event) if is_store_change(&event, &db_path) => {}
+            Ok(_) => continue,
+            Err(e) => {
+                eprintln!("Warning: watch error: {}", e);
+                continue;
+            }
+        }
+
+        // A save or checkout can arrive as several events; wait for quiet
+        while rx.recv_timeout(debounce).is_ok() {}
+
+        match refresh_derived(&root, !no_snapshot) {
+            Ok(summary) => println!("{}: {}", chrono::Local::now().format("%H:%M:%S"), summary),
+            Err(e) => eprintln!("Warning: refresh failed: {}", e),
+        }
+    }
+
+    Ok(())
+}
+
+/// One backfill pass: sync the cache, then embed whatever is missing or stale.
+fn backfill_pass(
+    root: &Path,
+    embedder: &Embedder,
+    progress: bool,
+) -> Result<crate::embeddings::backfill::BackfillStats> {
+    use crate::embeddings::backfill::{backfill, pending_embeddings};
+
+    let store = LoroStore::open(root)?;
+    let cache = SqliteCache::open(store.medulla_dir())?;
+    store.sync_cache(&cache)?;
+    let pending = pending_embeddings(&store, &cache)?;
+    drop(store);
+
+    let stats = backfill(&cache, embedder, &pending, |done, total| {
+        if progress {
+            eprint!("\rEmbedding {}/{}", done, total);
+        }
+    });
+    if progress && !pending.is_empty() {
+        eprintln!();
+    }
+    Ok(stats)
+}
+
+fn backfill_summary(stats: &crate::embeddings::backfill::BackfillStats) -> String {
+    if stats.missing + stats.stale == 0 {
+        return "embeddings up to date".to_string();
+    }
+    let mut summary = format!(
+        "computed {} embeddings ({} missing, {} stale)",
+        stats.computed, stats.missing, stats.stale
+    );
+    if stats.errors > 0 {
+        summary.push_str(&format!(", {} errors", stats.errors));
+    }
+    summary
+}
+
+pub fn handle_embeddings_backfill(
+    data_dir: Option<&Path>,
+    daemon: bool,
+    debounce_ms: u64,
+    json: bool,
+) -> Result<()> {
+    use notify::Watcher;
+
+    let root = find_project_root(data_dir);
+    let store = LoroStore::open(&root)?;
+    let db_path = store.path().to_path_buf();
+    let medulla_dir = store.medulla_dir().to_path_buf();
+    drop(store);
+
+    // The model is downloaded on first use, which can take a while
+    let embedder = get_embedder().ok_or_else(|| {
+        MedullaError::Embedding("Embedding model not available. Try again later.".to_string())
+    })?;
+
+    if !daemon {
+        let stats = backfill_pass(&root, embedder, !json)?;
+        if json {
+            println!("{}", serde_json::to_string_pretty(&stats)?);
+        } else {
+            println!("{}", backfill_summary(&stats));
+        }
+        return Ok(());
+    }
+
+    // One compact line per pass, so the output can be tailed or piped
+    let report = |stats: &crate::embeddings::backfill::BackfillStats| -> Result<()> {
+        if json {
+            println!("{}", serde_json::to_string(stats)?);
+        } else {
+            println!(
+                "{}: {}",
+                chrono::Local::now().format("%H:%M:%S"),
+                backfill_summary(stats)
+            );
+        }
+        Ok(())
+    };
+
+    let (tx, rx) = std::sync::mpsc::channel();
+    let mut watcher = notify::recommended_watcher(tx)?;
+    watcher.watch(&medulla_dir, notify::RecursiveMode::NonRecursive)?;
+
+    report(&backfill_pass(&root, embedder, !json)?)?;
+    if !json {
+        println!("Watching {} (Ctrl+C to stop)", db_path.display());
+    }
+
+    let debounce = Duration::from_millis(debounce_ms);
+    for event in &rx {
+        match event {
+            Ok(event) if is_store_change(&event, &db_path) => {}
+            Ok(_) => continue,
+            Err(e) => {
+                eprintln!("Warning: watch error: {}", e);
+                continue;
+            }
+        }
+
+        while rx.recv_timeout(debounce).is_ok() {}
+
+        match backfill_pass(&root, embedder, !json) {
+            Ok(stats) => report(&stats)?,
+            Err(e) => eprintln!("Warning: backfill failed: {}", e),
+        }
+    }
+
+    Ok(())
+}
+
+/// Time `medulla daemon stop` waits for the daemon to exit, which it only
+/// does once its current pass is over
+const DAEMON_STOP_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// One daemon pass: sync the cache and regenerate the snapshot, backfill
+/// embeddings, then check health. A step that fails is reported in the
+/// summary instead of stopping the daemon.
+fn daemon_pass(
+    root: &Path,
+    snapshot: bool,
+    embeddings: bool,
+) -> (String, Option<crate::warnings::Severity>) {
+    let mut parts = Vec::new();
+    match refresh_derived(root, snapshot) {
+        Ok(summary) => parts.push(summary),
+        Err(e) => parts.push(format!("refresh failed: {}", e)),
+    }
+
+    if embeddings {
+        match get_embedder() {
+            Some(embedder) => match backfill_pass(root, embedder, false) {
+                Ok(stats) => parts.push(backfill_summary(&stats)),
+                Err(e) => parts.push(format!("backfill failed: {}", e)),
+            },
+            None => parts.push("embedding model not available".to_string()),
+        }
+    }
+
+    let report = LoroStore::open(root).and_then(|store| {
+        let cache = SqliteCache::open(store.medulla_dir())?;
+        crate::warnings::health_check(&store, &cache)
+    });
+    let health = match report {
+        Ok(report) => {
+            parts.push(format!(
+                "health {} ({} issues)",
+                report.status.as_str(),
+                report.issues.len()
+            ));
+            Some(report.status)
+        }
+        Err(e) => {
+            parts.push(format!("health check failed: {}", e));
+            None
+        }
+    };
+
+    (parts.join(", "), health)
+}
+
+/// Start the maintenance daemon: detached, re-running this executable with
+/// `--foreground` and its output going to `.medulla/daemon.log`, or in this
+/// terminal with `foreground`.
+pub fn handle_daemon_start(
+    data_dir: Option<&Path>,
+    interval: &str,
+    foreground: bool,
+    no_snapshot: bool,
+    no_embeddings: bool,
+) -> Result<()> {
+    use crate::daemon::{DaemonState, DAEMON_LOG_FILE};
+
+    let interval = crate::daemon::parse_interval(interval).map_err(MedullaError::Config)?;
+    let root = find_project_root(data_dir);
+    let store = LoroStore::open(&root)?;
+    let medulla_dir = store.medulla_dir().to_path_buf();
+    drop(store);
+
+    if let Some(state) = DaemonState::load(&medulla_dir)? {
+        if state.pid != std::process::id() && state.is_running() {
+            return Err(MedullaError::Config(format!(
+                "The daemon is already running (PID {}); stop it with `medulla daemon stop`",
+                state.pid
+            )));
+        }
+    }
+
+    if !foreground {
+        let log_path = medulla_dir.join(DAEMON_LOG_FILE);
+        let log = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&log_path)?;
+        let mut command = std::process::Command::new(env::current_exe()?);
+        command
+            .arg("--data-dir")
+            .arg(&root)
+            .args(["daemon", "--foreground", "--interval"])
+            .arg(crate::daemon::format_interval(interval));
+        if no_snapshot {
+            command.arg("--no-snapshot");
+        }
+        if no_embeddings {
+            command.arg("--no-embeddings");
+        }
+        command
+            .stdin(std::process::Stdio::null())
+            .stdout(log.try_clone()?)
+            .stderr(log);
+        // Its own process group, so Ctrl+C in this terminal does not reach it
+        #[cfg(unix)]
+        {
+            use std::os::unix::process::CommandExt;
+            command.process_group(0);
+        }
+        let child = command.spawn()?;
+        println!(
+            "Daemon started (PID {}), running every {}; output goes to {}",
+            child.id(),
+            crate::daemon::format_interval(interval),
+            log_path.display()
+        );
+        return Ok(());
+    }
+
+    run_daemon(&root, &medulla_dir, interval, !no_snapshot, !no_embeddings)
+}
+
+fn run_daemon(
+    root: &Path,
+    medulla_dir: &Path,
+    interval: Duration,
+    snapshot: bool,
+    embeddings: bool,
+) -> Result<()> {
+    use crate::daemon::{clear_stop_request, wait_for_stop, DaemonState};
+
+    let step = chrono::Duration::from_std(interval).map_err(|_| {
+        MedullaError::Config(format!(
+            "Interval {} is too long",
+            crate::daemon::format_interval(interval)
+        ))
+    })?;
+    clear_stop_request(medulla_dir)?;
+    let mut state = DaemonState {
+        pid: std::process::id(),
+        started_at: chrono::Utc::now(),
+        interval_secs: interval.as_secs(),
+        snapshot,
+        embeddings,
+        runs: 0,
+        last_run: None,
+        last_summary: None,
+        last_health: None,
+        next_run: None,
+    };
+    state.save(medulla_dir)?;
+    println!(
+        "Daemon running every {} (PID {}); stop with `medulla daemon stop`",
+        crate::daemon::format_interval(interval),
+        state.pid
+    );
+
+    loop {
+        let (summary, health) = daemon_pass(root, snapshot, embeddings);
+        let now = chrono::Utc::now();
+        println!(
+            "{}: {}",
+            chrono::Local::now().format("%Y-%m-%d %H:%M:%S"),
+            summary
+        );
+
+        state.runs += 1;
+        state.last_run = Some(now);
+        state.last_summary = Some(summary);
+        state.last_health = health;
+        state.next_run = now.checked_add_signed(step);
+        state.save(medulla_dir)?;
+
+        if wait_for_stop(medulla_dir, interval) {
+            break;
+        }
+    }
+
+    clear_stop_request(medulla_dir)?;
+    DaemonState::remove(medulla_dir)?;
+    println!("Daemon stopped");
+    Ok(())
+}
+
+pub fn handle_daemon_status(data_dir: Option<&Path>, json: bool) -> Result<()> {
+    use crate::daemon::DaemonState;
+
+    let root = find_project_root(data_dir);
+    let store = LoroStore::open(&root)?;
+    let state = DaemonState::load(store.medulla_dir())?;
+    let running = state.as_ref().is_some_and(|s| s.is_running());
 
     if json {
-        #[derive(serde::Serialize)]
-        struct SearchResultJson {
-            entity_type: String,
-            id: String,
-            sequence_number: u32,
-            title: String,
-            status: Option<String>,
-            snippet: Option<String>,
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&serde_json::json!({
+                "running": running,
+                "state": state,
+            }))?
+        );
+        return Ok(());
+    }
+
+    let Some(state) = state else {
+        println!("Daemon is not running");
+        return Ok(());
+    };
+    if !running {
+        println!(
+            "Daemon is not running (PID {} exited without cleaning up)",
+            state.pid
+        );
+        return Ok(());
+    }
+
+    let format_time = |t: chrono::DateTime<chrono::Utc>| {
+        t.with_timezone(&chrono::Local)
+            .format("%Y-%m-%d %H:%M:%S")
+            .to_string()
+    };
+    println!(
+        "Daemon running (PID {}) since {}, every {}",
+        state.pid,
+        format_time(state.started_at),
+        crate::daemon::format_interval(Duration::from_secs(state.interval_secs))
+    );
+    match (state.last_run, &state.last_summary) {
+        (Some(at), Some(summary)) => {
+            println!("Last pass:  {} ({} so far)", format_time(at), state.runs);
+            println!("  {}", summary);
         }
+        _ => println!("Last pass:  none yet"),
+    }
+    if let Some(next) = state.next_run {
+        println!("Next pass:  {}", format_time(next));
+    }
+    Ok(())
+}
 
-        let json_results: Vec<SearchResultJson> = results
-            .into_iter()
-            .map(|r| match r {
-                crate::cache::SearchResult::Decision(d) => SearchResultJson {
-                    entity_type: "decision".to_string(),
-                    id: d.id,
-                    sequence_number: d.sequence_number,
-                    title: d.title,
-                    status: Some(d.status),
-                    snippet: d.content_snippet,
-                },
-                crate::cache::SearchResult::Task(t) => SearchResultJson {
-                    entity_type: "task".to_string(),
-                    id: t.id,
-                    sequence_number: t.sequence_number,
-                    title: t.title,
-                    status: Some(t.status),
-                    snippet: t.content_snippet,
-                },
-                crate::cache::SearchResult::Note(n) => SearchResultJson {
-                    entity_type: "note".to_string(),
-                    id: n.id,
-                    sequence_number: n.sequence_number,
-                    title: n.title,
-                    status: n.note_type,
-                    snippet: n.content_snippet,
-                },
-                crate::cache::SearchResult::Prompt(p) => SearchResultJson {
-                    entity_type: "prompt".to_string(),
-                    id: p.id,
-                    sequence_number: p.sequence_number,
-                    title: p.title,
-                    status: None,
-                    snippet: p.content_snippet,
-                },
-                crate::cache::SearchResult::Component(c) => SearchResultJson {
-                    entity_type: "component".to_string(),
-                    id: c.id,
-                    sequence_number: c.sequence_number,
-                    title: c.title,
-                    status: Some(c.status),
-                    snippet: c.content_snippet,
-                },
-                crate::cache::SearchResult::Link(l) => SearchResultJson {
-                    entity_type: "link".to_string(),
-                    id: l.id,
-                    sequence_number: l.sequence_number,
-                    title: l.title,
-                    status: l.link_type,
-                    snippet: l.content_snippet,
-                },
-            })
+pub fn handle_daemon_stop(data_dir: Option<&Path>) -> Result<()> {
+    use crate::daemon::{clear_stop_request, request_stop, DaemonState};
+
+    let root = find_project_root(data_dir);
+    let store = LoroStore::open(&root)?;
+    let medulla_dir = store.medulla_dir().to_path_buf();
+    drop(store);
+
+    let Some(state) = DaemonState::load(&medulla_dir)? else {
+        println!("Daemon is not running");
+        return Ok(());
+    };
+    if !state.is_running() {
+        // Left behind by a daemon that was killed
+        DaemonState::remove(&medulla_dir)?;
+        clear_stop_request(&medulla_dir)?;
+        println!("Daemon is not running (removed its stale status file)");
+        return Ok(());
+    }
+
+    request_stop(&medulla_dir)?;
+    let deadline = Instant::now() + DAEMON_STOP_TIMEOUT;
+    while DaemonState::path(&medulla_dir).exists() && Instant::now() < deadline {
+        std::thread::sleep(Duration::from_millis(200));
+    }
+    if DaemonState::path(&medulla_dir).exists() {
+        println!(
+            "Asked the daemon (PID {}) to stop; it will exit when its current pass ends",
+            state.pid
+        );
+    } else {
+        println!("Daemon stopped (PID {})", state.pid);
+    }
+    Ok(())
+}
+
+// =============================================================================
+// Import handlers
+// =============================================================================
+
+/// Read CSV records into import rows.
+///
+/// Header names are used as field names unless remapped with `COLUMN=FIELD`.
+/// Empty cells are left out so they don't override defaults.
+fn read_csv_rows<R: io::Read>(reader: R, mappings: &[String]) -> Result<Vec<serde_json::Value>> {
+    let mut column_map = std::collections::HashMap::new();
+    for mapping in mappings {
+        let (column, field) = mapping.split_once('=').ok_or_else(|| {
+            MedullaError::Storage(format!(
+                "Invalid mapping '{}'. Expected 'COLUMN=FIELD'",
+                mapping
+            ))
+        })?;
+        column_map.insert(column.trim().to_string(), field.trim().to_string());
+    }
+
+    let mut csv_reader = csv::ReaderBuilder::new()
+        .trim(csv::Trim::All)
+        .from_reader(reader);
+
+    let fields: Vec<String> = csv_reader
+        .headers()?
+        .iter()
+        .map(|h| column_map.get(h).cloned().unwrap_or_else(|| h.to_string()))
+        .collect();
+
+    let mut rows = Vec::new();
+    for record in csv_reader.records() {
+        let record = record?;
+        let row: serde_json::Map<String, serde_json::Value> = fields
+            .iter()
+            .zip(record.iter())
+            .filter(|(_, value)| !value.is_empty())
+            .map(|(field, value)| (field.clone(), serde_json::Value::String(value.to_string())))
             .collect();
+        rows.push(serde_json::Value::Object(row));
+    }
 
-        println!("{}", serde_json::to_string_pretty(&json_results)?);
-    } else if results.is_empty() {
-        println!("No results found for '{}'.", query);
+    Ok(rows)
+}
+
+/// Rows of a relation CSV as source, relation type and target, with the
+/// line each came from, skipping a header row that names the columns
+fn read_relation_rows<R: io::Read>(
+    reader: R,
+) -> Result<(Vec<crate::mcp::tools::RelationBatchItem>, Vec<u64>)> {
+    let mut csv_reader = csv::ReaderBuilder::new()
+        .has_headers(false)
+        .trim(csv::Trim::All)
+        .flexible(true)
+        .from_reader(reader);
+
+    let mut rows = Vec::new();
+    let mut lines = Vec::new();
+    for (index, record) in csv_reader.records().enumerate() {
+        let record = record?;
+        let is_header = index == 0
+            && record
+                .get(0)
+                .is_some_and(|c| c.eq_ignore_ascii_case("source"));
+        if is_header {
+            continue;
+        }
+        let [source, relation_type, target] = [0, 1, 2].map(|i| record.get(i).unwrap_or_default());
+        rows.push(crate::mcp::tools::RelationBatchItem {
+            source: source.to_string(),
+            relation_type: relation_type.to_string(),
+            target: target.to_string(),
+        });
+        lines.push(record.position().map_or(0, |p| p.line()));
+    }
+
+    Ok((rows, lines))
+}
+
+/// Handle relation import command.
+///
+/// Rows go through the same validation as the MCP `relation_batch` tool,
+/// without its per-call cap, so a file is created all at once or not at all.
+pub fn handle_relation_import(
+    data_dir: Option<&Path>,
+    path: &Path,
+    dry_run: bool,
+    json: bool,
+) -> Result<()> {
+    let root = find_project_root(data_dir);
+    let store = LoroStore::open(&root)?.with_author(get_git_author());
+    let cache = SqliteCache::open(store.medulla_dir())?;
+    store.sync_cache(&cache)?;
+    let config = crate::config::ProjectConfig::load(store.medulla_dir())?;
+    cache.set_inverse_relations(config.materialize_inverse_relations)?;
+    let server = MedullaServer::new(store, cache).with_config(config);
+
+    let (rows, lines) = read_relation_rows(std::fs::File::open(path)?)?;
+
+    let rt = tokio::runtime::Runtime::new()
+        .map_err(|e| MedullaError::Storage(format!("Failed to create tokio runtime: {}", e)))?;
+    let result = rt
+        .block_on(server.create_relations(&rows, dry_run))
+        .map_err(|e| MedullaError::Storage(e.to_string()))?;
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&result)?);
     } else {
-        println!("Search results for '{}':\n", query);
-        for r in results {
-            match r {
-                crate::cache::SearchResult::Decision(d) => {
-                    println!(
-                        "  [DECISION] {:03} ({}) [{}] {}",
-                        d.sequence_number,
-                        &d.id[..7.min(d.id.len())],
-                        d.status,
-                        d.title
-                    );
-                    if let Some(snippet) = d.content_snippet {
-                        let clean_snippet = snippet
-                            .replace("<mark>", "\x1b[1m")
-                            .replace("</mark>", "\x1b[0m");
-                        println!("      {}", clean_snippet);
-                    }
-                }
-                crate::cache::SearchResult::Task(t) => {
-                    println!(
-                        "  [TASK] {:03} ({}) [{}|{}] {}",
-                        t.sequence_number,
-                        &t.id[..7.min(t.id.len())],
-                        t.status,
-                        t.priority,
-                        t.title
-                    );
-                    if let Some(assignee) = t.assignee {
-                        println!("      assignee: {}", assignee);
-                    }
-                    if let Some(snippet) = t.content_snippet {
-                        let clean_snippet = snippet
-                            .replace("<mark>", "\x1b[1m")
-                            .replace("</mark>", "\x1b[0m");
-                        println!("      {}", clean_snippet);
-                    }
-                }
-                crate::cache::SearchResult::Note(n) => {
-                    let type_str = n.note_type.as_deref().unwrap_or("note");
-                    println!(
-                        "  [NOTE] {:03} ({}) [{}] {}",
-                        n.sequence_number,
-                        &n.id[..7.min(n.id.len())],
-                        type_str,
-                        n.title
-                    );
-                    if let Some(snippet) = n.content_snippet {
-                        let clean_snippet = snippet
-                            .replace("<mark>", "\x1b[1m")
-                            .replace("</mark>", "\x1b[0m");
-                        println!("      {}", clean_snippet);
-                    }
-                }
-                crate::cache::SearchResult::Prompt(p) => {
-                    println!(
-                        "  [PROMPT] {:03} ({}) {}",
-                        p.sequence_number,
-                        &p.id[..7.min(p.id.len())],
-                        p.title
-                    );
-                    if !p.variables.is_empty() {
-                        println!("      vars: {}", p.variables.join(", "));
-                    }
-                    if let Some(snippet) = p.content_snippet {
-                        let clean_snippet = snippet
-                            .replace("<mark>", "\x1b[1m")
-                            .replace("</mark>", "\x1b[0m");
-                        println!("      {}", clean_snippet);
-                    }
-                }
-                crate::cache::SearchResult::Component(c) => {
-                    let type_str = c.component_type.as_deref().unwrap_or("component");
-                    println!(
-                        "  [COMPONENT] {:03} ({}) [{}|{}] {}",
-                        c.sequence_number,
-                        &c.id[..7.min(c.id.len())],
-                        type_str,
-                        c.status,
-                        c.title
-                    );
-                    if let Some(owner) = c.owner {
-                        println!("      owner: {}", owner);
-                    }
-                    if let Some(snippet) = c.content_snippet {
-                        let clean_snippet = snippet
-                            .replace("<mark>", "\x1b[1m")
-                            .replace("</mark>", "\x1b[0m");
-                        println!("      {}", clean_snippet);
-                    }
-                }
-                crate::cache::SearchResult::Link(l) => {
-                    println!(
-                        "  [LINK] {:03} ({}) {} -> {}",
-                        l.sequence_number,
-                        &l.id[..7.min(l.id.len())],
-                        l.title,
-                        l.url
-                    );
-                    if let Some(snippet) = l.content_snippet {
-                        let clean_snippet = snippet
-                            .replace("<mark>", "\x1b[1m")
-                            .replace("</mark>", "\x1b[0m");
-                        println!("      {}", clean_snippet);
-                    }
-                }
+        match result.abort_reason {
+            Some(_) => println!(
+                "No relations created: {} of {} rows are invalid",
+                result.failed,
+                rows.len()
+            ),
+            None if dry_run => println!("All {} rows are valid", result.succeeded),
+            None => println!("Created {} relations", result.succeeded),
+        }
+        for r in result.results.iter().filter(|r| !r.success) {
+            if let Some(ref error) = r.error {
+                println!("  line {}: {}", lines[r.index], error.message);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Handle import csv command.
+///
+/// Rows go through the MCP `entity_import_rows` tool so they get the same
+/// validation, rules and indexing as entities created by assistants.
+pub fn handle_import_csv(
+    data_dir: Option<&Path>,
+    path: &Path,
+    mappings: &[String],
+    json: bool,
+) -> Result<()> {
+    let root = find_project_root(data_dir);
+    let store = LoroStore::open(&root)?.with_author(get_git_author());
+    let cache = SqliteCache::open(store.medulla_dir())?;
+    store.sync_cache(&cache)?;
+    let config = crate::config::ProjectConfig::load(store.medulla_dir())?;
+    cache.set_inverse_relations(config.materialize_inverse_relations)?;
+    cache.set_task_scoring(&config.task_scoring)?;
+    let chunk_size = config.limits.max_batch_size;
+    let server = MedullaServer::new(store, cache).with_config(config);
+
+    let rows = read_csv_rows(std::fs::File::open(path)?, mappings)?;
+
+    let rt = tokio::runtime::Runtime::new()
+        .map_err(|e| MedullaError::Storage(format!("Failed to create tokio runtime: {}", e)))?;
+
+    // The tool caps rows per call, so import in chunks and renumber results
+    let mut results = Vec::new();
+    for (chunk_index, chunk) in rows.chunks(chunk_size).enumerate() {
+        let params = crate::mcp::tools::EntityImportRowsParams {
+            rows: chunk.to_vec(),
+        };
+        let tool_result = rt
+            .block_on(server.entity_import_rows(rmcp::handler::server::wrapper::Parameters(params)))
+            .map_err(|e| MedullaError::Storage(e.message.to_string()))?;
+
+        let text = match tool_result.content.first().map(|c| &c.raw) {
+            Some(rmcp::model::RawContent::Text(t)) => t.text.clone(),
+            _ => String::new(),
+        };
+        let batch: crate::mcp::tools::BatchResult = serde_json::from_str(&text)?;
+        results.extend(batch.results.into_iter().map(|mut r| {
+            r.index += chunk_index * chunk_size;
+            r
+        }));
+    }
+
+    let succeeded = results.iter().filter(|r| r.success).count();
+    let failed = results.len() - succeeded;
+
+    if json {
+        let out = crate::mcp::tools::BatchResult {
+            results,
+            succeeded,
+            failed,
+            abort_reason: None,
+        };
+        println!("{}", serde_json::to_string_pretty(&out)?);
+    } else {
+        println!("Imported {} of {} rows", succeeded, succeeded + failed);
+        for r in results.iter().filter(|r| !r.success) {
+            if let Some(ref error) = r.error {
+                // Row numbers are 1-based and skip the header line
+                println!("  row {}: {}", r.index + 2, error.message);
             }
         }
     }
@@ -1734,653 +5652,1094 @@ pub fn handle_search(query: String, semantic: bool, json: bool) -> Result<()> {
     Ok(())
 }
 
-/// Check if a fulltext search result matches the CLI filter.
-fn matches_cli_filter(
-    cache: &SqliteCache,
-    result: &crate::cache::SearchResult,
-    filter: &crate::search::SearchFilter,
-) -> bool {
-    // Get entity ID and type from result
-    let (entity_id, entity_type, result_status) = match result {
-        crate::cache::SearchResult::Decision(d) => (&d.id, "decision", Some(&d.status)),
-        crate::cache::SearchResult::Task(t) => (&t.id, "task", Some(&t.status)),
-        crate::cache::SearchResult::Component(c) => (&c.id, "component", Some(&c.status)),
-        crate::cache::SearchResult::Note(n) => (&n.id, "note", None),
-        crate::cache::SearchResult::Prompt(p) => (&p.id, "prompt", None),
-        crate::cache::SearchResult::Link(l) => (&l.id, "link", None),
+/// Handle bulk tag command.
+///
+/// Goes through the MCP `entity_bulk_update` tool, so each change is
+/// validated and indexed like a single `update`.
+pub fn handle_bulk_tag(
+    data_dir: Option<&Path>,
+    query: &str,
+    add: Vec<String>,
+    remove: Vec<String>,
+    dry_run: bool,
+    json: bool,
+) -> Result<()> {
+    let root = find_project_root(data_dir);
+    let server = open_project_server(&root)?;
+
+    let params = crate::mcp::tools::EntityBulkUpdateParams {
+        query: query.to_string(),
+        add_tags: Some(add),
+        remove_tags: Some(remove),
+        dry_run: Some(dry_run),
+    };
+    let rt = tokio::runtime::Runtime::new()
+        .map_err(|e| MedullaError::Storage(format!("Failed to create tokio runtime: {}", e)))?;
+    let tool_result = rt
+        .block_on(server.entity_bulk_update(rmcp::handler::server::wrapper::Parameters(params)))
+        .map_err(|e| MedullaError::Storage(e.message.to_string()))?;
+
+    let text = match tool_result.content.first().map(|c| &c.raw) {
+        Some(rmcp::model::RawContent::Text(t)) => t.text.clone(),
+        _ => String::new(),
     };
+    let result: serde_json::Value = serde_json::from_str(&text)?;
 
-    // Check status filter (use status from search result for efficiency)
-    if let Some(ref required_status) = filter.status {
-        match result_status {
-            Some(actual) if actual == required_status => {}
-            _ => return false,
-        }
+    if json {
+        println!("{}", serde_json::to_string_pretty(&result)?);
+        return Ok(());
     }
 
-    // If no tag or date filters, we're done
-    if filter.tags.is_empty() && filter.created_after.is_none() && filter.created_before.is_none() {
-        return true;
+    let entities = result["entities"].as_array().cloned().unwrap_or_default();
+    if entities.is_empty() {
+        println!("No entities match '{}'", query);
+        return Ok(());
+    }
+    if dry_run {
+        println!("Would update {} entities:", entities.len());
+        for entity in &entities {
+            let id = entity["id"].as_str().unwrap_or_default();
+            println!(
+                "  [{}] {:03} ({}) - {}",
+                entity["type"].as_str().unwrap_or_default().to_uppercase(),
+                entity["sequence_number"].as_u64().unwrap_or_default(),
+                &id[..7.min(id.len())],
+                entity["title"].as_str().unwrap_or_default()
+            );
+        }
+    } else {
+        println!(
+            "Updated {} of {} entities",
+            result["succeeded"],
+            entities.len()
+        );
+        for r in result["results"].as_array().into_iter().flatten() {
+            if let Some(message) = r["error"]["message"].as_str() {
+                let id = r["id"].as_str().unwrap_or_default();
+                println!("  {}: {}", &id[..7.min(id.len())], message);
+            }
+        }
     }
 
-    // Load full metadata for tag and date checks
-    let metadata = match cache.get_filter_metadata(entity_id, entity_type) {
-        Ok(Some(m)) => m,
-        _ => return false,
-    };
+    Ok(())
+}
 
-    // Check tags
-    for required_tag in &filter.tags {
-        if !metadata
-            .tags
-            .iter()
-            .any(|t| t.eq_ignore_ascii_case(required_tag))
-        {
-            return false;
+// =============================================================================
+// Prompt handlers
+// =============================================================================
+
+pub fn handle_prompt_render(
+    data_dir: Option<&Path>,
+    id: &str,
+    vars: &[String],
+    check: Option<String>,
+    json: bool,
+) -> Result<()> {
+    let root = find_project_root(data_dir);
+    let server = open_project_server(&root)?;
+
+    let output = match check.as_deref() {
+        Some("-") => {
+            let mut text = String::new();
+            io::stdin().read_to_string(&mut text)?;
+            Some(serde_json::Value::String(text))
         }
-    }
+        Some(text) => Some(serde_json::Value::String(text.to_string())),
+        None => None,
+    };
+    let params = crate::mcp::tools::PromptRenderParams {
+        id: id.to_string(),
+        variables: Some(parse_property_args(vars)?.into_iter().collect()),
+        output,
+    };
+    let rt = tokio::runtime::Runtime::new()
+        .map_err(|e| MedullaError::Storage(format!("Failed to create tokio runtime: {}", e)))?;
+    let tool_result = rt
+        .block_on(server.prompt_render(rmcp::handler::server::wrapper::Parameters(params)))
+        .map_err(|e| MedullaError::Storage(e.message.to_string()))?;
 
-    // Check dates
-    if let Some(ref after) = filter.created_after {
-        match &metadata.created_at {
-            Some(created) if created >= after => {}
-            _ => return false,
+    let text = match tool_result.content.first().map(|c| &c.raw) {
+        Some(rmcp::model::RawContent::Text(t)) => t.text.clone(),
+        _ => String::new(),
+    };
+    let result: serde_json::Value = serde_json::from_str(&text)?;
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&result)?);
+    } else {
+        println!("{}", result["rendered"].as_str().unwrap_or_default());
+        for name in result["unused_variables"].as_array().into_iter().flatten() {
+            eprintln!(
+                "Warning: variable '{}' is not used by the prompt",
+                name.as_str().unwrap_or_default()
+            );
         }
     }
 
-    if let Some(ref before) = filter.created_before {
-        match &metadata.created_at {
-            Some(created) if created <= before => {}
-            _ => return false,
+    // A failed check exits non-zero, so scripts can gate on it
+    if result["output_valid"] == false {
+        if !json {
+            eprintln!("Output does not match the prompt's output schema:");
+            for error in result["output_errors"].as_array().into_iter().flatten() {
+                eprintln!("  {}", error.as_str().unwrap_or_default());
+            }
         }
+        return Err(MedullaError::Storage(
+            "Output failed schema validation".to_string(),
+        ));
+    }
+    if check.is_some() && !json {
+        eprintln!("Output matches the prompt's output schema");
     }
 
-    true
+    Ok(())
 }
 
-/// Handle semantic search using vector embeddings.
-fn handle_search_semantic(
-    cache: &SqliteCache,
-    query: &str,
-    filter: &crate::search::SearchFilter,
-    json: bool,
+// =============================================================================
+// Graph handlers
+// =============================================================================
+
+pub fn handle_graph_export(
+    data_dir: Option<&Path>,
+    format: String,
+    root_id: Option<String>,
+    depth: Option<usize>,
+    output: Option<PathBuf>,
 ) -> Result<()> {
-    let embedder = get_embedder().ok_or_else(|| {
-        MedullaError::Embedding("Embedding model not available. Try again later.".to_string())
-    })?;
+    let format: crate::graph::GraphFormat = format.parse().map_err(MedullaError::Storage)?;
 
-    // Compute query embedding
-    let query_embedding = embedder.embed(query)?;
+    let root = find_project_root(data_dir);
+    let store = LoroStore::open(&root)?;
+    let root_uuid = match root_id {
+        Some(ref id) => Some(find_entity_id_with_type(&store, id)?.0),
+        None => None,
+    };
 
-    // Perform semantic search with entity type filter
-    let results =
-        cache.search_semantic(&query_embedding, filter.entity_type.as_deref(), 50, 0.3)?;
+    let graph = crate::graph::Graph::collect(&store, root_uuid, depth)?;
+    let rendered = graph.render(format);
 
-    // Apply additional filters (status, tags, dates)
-    let results: Vec<_> = results
-        .into_iter()
-        .filter(|r| matches_semantic_filter(cache, r, filter))
-        .take(20)
-        .collect();
+    match output {
+        Some(path) => {
+            std::fs::write(&path, rendered)?;
+            eprintln!(
+                "Exported {} entities and {} relations to {}",
+                graph.nodes.len(),
+                graph.edges.len(),
+                path.display()
+            );
+        }
+        None => print!("{}", rendered),
+    }
+
+    Ok(())
+}
+
+pub fn handle_graph_cycles(data_dir: Option<&Path>, json: bool) -> Result<()> {
+    let root = find_project_root(data_dir);
+    let store = LoroStore::open(&root)?;
+    let cycles = crate::graph::blocks_cycles(&store)?;
 
     if json {
-        println!("{}", serde_json::to_string_pretty(&results)?);
-    } else if results.is_empty() {
-        println!("No semantically similar results found for '{}'.", query);
-        println!("\nHint: Semantic search requires entities to have embeddings.");
+        let groups: Vec<Vec<serde_json::Value>> = cycles
+            .iter()
+            .map(|cycle| {
+                cycle
+                    .iter()
+                    .map(|n| {
+                        serde_json::json!({
+                            "id": n.id.to_string(),
+                            "type": n.entity_type,
+                            "sequence_number": n.sequence_number,
+                            "title": n.title,
+                        })
+                    })
+                    .collect()
+            })
+            .collect();
         println!(
-            "Try running 'medulla cache rebuild' to generate embeddings for existing entities."
+            "{}",
+            serde_json::to_string_pretty(&serde_json::json!({ "cycles": groups }))?
         );
+    } else if cycles.is_empty() {
+        println!("No blocks cycles found.");
     } else {
+        println!("Found {} blocks cycle(s):", cycles.len());
+        for (i, cycle) in cycles.iter().enumerate() {
+            println!();
+            println!("Cycle {} ({} entities):", i + 1, cycle.len());
+            for node in cycle {
+                println!("  {}", node.label());
+            }
+        }
+        println!();
+        println!("Remove one 'blocks' relation in each cycle with 'medulla relation delete'.");
+    }
+
+    Ok(())
+}
+
+pub fn handle_graph_stats(data_dir: Option<&Path>, limit: usize, json: bool) -> Result<()> {
+    let root = find_project_root(data_dir);
+    let store = LoroStore::open(&root)?;
+    let analytics = crate::graph::GraphAnalytics::build(&store)?;
+
+    if json {
         println!(
-            "Semantic search results for '{}' (similarity threshold: 0.3):\n",
-            query
+            "{}",
+            serde_json::to_string_pretty(&analytics.to_json(limit))?
         );
-        for r in results {
-            let type_upper = r.entity_type.to_uppercase();
-            println!(
-                "  [{type_upper}] {:03} ({}) {:.2}% - {}",
-                r.sequence_number,
-                &r.entity_id[..7.min(r.entity_id.len())],
-                r.score * 100.0,
-                r.title
-            );
+        return Ok(());
+    }
+
+    println!(
+        "{} entities, {} relations, {} connected group(s), {} unconnected entities",
+        analytics.nodes,
+        analytics.edges,
+        analytics.components.len(),
+        analytics.isolated
+    );
+
+    let hubs = analytics.hubs(limit);
+    if hubs.is_empty() {
+        println!("\nNo relations yet.");
+        return Ok(());
+    }
+    println!("\nTop hubs:");
+    for c in hubs {
+        println!(
+            "  {:>3} relations ({} in, {} out)  betweenness {:.3}  {}",
+            c.degree(),
+            c.in_degree,
+            c.out_degree,
+            c.betweenness,
+            c.node.label()
+        );
+    }
+
+    println!("\nCommunities:");
+    for (i, community) in analytics.communities.iter().enumerate() {
+        let members: Vec<String> = community
+            .iter()
+            .map(|n| format!("#{}", n.sequence_number))
+            .collect();
+        println!(
+            "  {}. {} entities: {}",
+            i + 1,
+            community.len(),
+            members.join(" ")
+        );
+    }
+
+    Ok(())
+}
+
+// =============================================================================
+// Template handlers
+// =============================================================================
+
+/// Parse "key=value" property arguments into a JSON object.
+fn parse_property_args(
+    properties: &[String],
+) -> Result<serde_json::Map<String, serde_json::Value>> {
+    properties
+        .iter()
+        .map(|p| {
+            let (key, value) = p.split_once('=').ok_or_else(|| {
+                MedullaError::Storage(format!(
+                    "Invalid property '{}', expected format key=value",
+                    p
+                ))
+            })?;
+            Ok((
+                key.trim().to_string(),
+                serde_json::Value::String(value.trim().to_string()),
+            ))
+        })
+        .collect()
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn handle_template_add(
+    data_dir: Option<&Path>,
+    name: String,
+    entity_type: String,
+    description: Option<String>,
+    status: Option<String>,
+    properties: Vec<String>,
+    tags: Vec<String>,
+    stdin: bool,
+    force: bool,
+) -> Result<()> {
+    let entity_type = entity_type.trim().to_lowercase();
+    if !crate::mcp::error::VALID_ENTITY_TYPES.contains(&entity_type.as_str()) {
+        return Err(MedullaError::InvalidEntityType(entity_type));
+    }
+
+    let root = find_project_root(data_dir);
+    let store = LoroStore::open(&root)?;
+    if !force && store.get_template(&name)?.is_some() {
+        return Err(MedullaError::Storage(format!(
+            "Template '{}' already exists (use --force to replace it)",
+            name
+        )));
+    }
+
+    let mut template = crate::entity::EntityTemplate::new(name, entity_type);
+    template.description = description;
+    template.tags = tags;
+    template.properties = parse_property_args(&properties)?;
+    if let Some(status) = status {
+        template
+            .properties
+            .insert("status".to_string(), serde_json::Value::String(status));
+    }
+    template.created_by = get_git_author();
+
+    if stdin {
+        let mut content = String::new();
+        io::stdin().read_to_string(&mut content)?;
+        if !content.is_empty() {
+            template.content = Some(content);
         }
     }
 
+    store.put_template(&template)?;
+    store.save()?;
+
+    println!(
+        "Saved template '{}' ({})",
+        template.name, template.entity_type
+    );
     Ok(())
 }
 
-/// Check if a semantic search result matches the CLI filter.
-fn matches_semantic_filter(
-    cache: &SqliteCache,
-    result: &crate::cache::SemanticSearchResult,
-    filter: &crate::search::SearchFilter,
-) -> bool {
-    // If no advanced filters, always match
-    if filter.status.is_none()
-        && filter.tags.is_empty()
-        && filter.created_after.is_none()
-        && filter.created_before.is_none()
-    {
-        return true;
+pub fn handle_template_list(data_dir: Option<&Path>, json: bool) -> Result<()> {
+    let root = find_project_root(data_dir);
+    let store = LoroStore::open(&root)?;
+    let templates = store.list_templates()?;
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&templates)?);
+    } else if templates.is_empty() {
+        println!("No templates found.");
+    } else {
+        for t in &templates {
+            let description = t
+                .description
+                .as_ref()
+                .map(|d| format!(" - {}", d))
+                .unwrap_or_default();
+            let tags = if t.tags.is_empty() {
+                String::new()
+            } else {
+                format!(" [{}]", t.tags.join(", "))
+            };
+            println!("{} ({}){}{}", t.name, t.entity_type, tags, description);
+        }
     }
 
-    // Load filter metadata
-    let metadata = match cache.get_filter_metadata(&result.entity_id, &result.entity_type) {
-        Ok(Some(m)) => m,
-        _ => return false,
+    Ok(())
+}
+
+pub fn handle_template_apply(
+    data_dir: Option<&Path>,
+    name: String,
+    title: String,
+    tags: Vec<String>,
+    properties: Vec<String>,
+    stdin: bool,
+    json: bool,
+) -> Result<()> {
+    let properties = parse_property_args(&properties)?;
+    let content = if stdin {
+        let mut content = String::new();
+        io::stdin().read_to_string(&mut content)?;
+        (!content.is_empty()).then_some(content)
+    } else {
+        None
+    };
+
+    let root = find_project_root(data_dir);
+    let store = LoroStore::open(&root)?.with_author(get_git_author());
+    let cache = SqliteCache::open(store.medulla_dir())?;
+    store.sync_cache(&cache)?;
+    let config = crate::config::ProjectConfig::load(store.medulla_dir())?;
+    let server = MedullaServer::new(store, cache).with_config(config);
+
+    let params = crate::mcp::tools::EntityCreateFromTemplateParams {
+        template: name,
+        title,
+        content,
+        tags: (!tags.is_empty()).then_some(tags),
+        properties: (!properties.is_empty()).then_some(serde_json::Value::Object(properties)),
+    };
+
+    let rt = tokio::runtime::Runtime::new()
+        .map_err(|e| MedullaError::Storage(format!("Failed to create tokio runtime: {}", e)))?;
+    let tool_result = rt
+        .block_on(
+            server.entity_create_from_template(rmcp::handler::server::wrapper::Parameters(params)),
+        )
+        .map_err(|e| MedullaError::Storage(e.message.to_string()))?;
+
+    let text = match tool_result.content.first().map(|c| &c.raw) {
+        Some(rmcp::model::RawContent::Text(t)) => t.text.clone(),
+        _ => String::new(),
     };
+    let created: crate::mcp::tools::EntityResponse = serde_json::from_str(&text)?;
 
-    // Check status
-    if let Some(ref required_status) = filter.status {
-        match &metadata.status {
-            Some(actual) if actual == required_status => {}
-            _ => return false,
-        }
+    if json {
+        println!("{}", serde_json::to_string_pretty(&created)?);
+    } else {
+        println!(
+            "Created {} {:03} ({}) - {}",
+            created.entity_type,
+            created.sequence_number,
+            &created.id[..7.min(created.id.len())],
+            created.title
+        );
     }
 
-    // Check tags
-    for required_tag in &filter.tags {
-        if !metadata
-            .tags
-            .iter()
-            .any(|t| t.eq_ignore_ascii_case(required_tag))
-        {
-            return false;
-        }
-    }
+    Ok(())
+}
 
-    // Check dates
-    if let Some(ref after) = filter.created_after {
-        match &metadata.created_at {
-            Some(created) if created >= after => {}
-            _ => return false,
-        }
-    }
+pub fn handle_template_delete(data_dir: Option<&Path>, name: String) -> Result<()> {
+    let root = find_project_root(data_dir);
+    let store = LoroStore::open(&root)?;
+    store.delete_template(&name)?;
+    store.save()?;
+    println!("Deleted template '{}'", name);
+    Ok(())
+}
 
-    if let Some(ref before) = filter.created_before {
-        match &metadata.created_at {
-            Some(created) if created <= before => {}
-            _ => return false,
+// =============================================================================
+// Export / JSONL import handlers
+// =============================================================================
+
+pub fn handle_export(data_dir: Option<&Path>, output: Option<PathBuf>) -> Result<()> {
+    let root = find_project_root(data_dir);
+    let store = LoroStore::open(&root)?;
+    let cache = SqliteCache::open(store.medulla_dir())?;
+
+    match output {
+        Some(path) => {
+            let file = std::fs::File::create(&path)?;
+            let stats = crate::transfer::export(&store, Some(&cache), io::BufWriter::new(file))?;
+            eprintln!(
+                "Exported {} entities, {} relations and {} embeddings to {}",
+                stats.entities,
+                stats.relations,
+                stats.embeddings,
+                path.display()
+            );
+        }
+        None => {
+            crate::transfer::export(&store, Some(&cache), io::stdout().lock())?;
         }
     }
 
-    true
+    Ok(())
 }
 
-/// Start the MCP server with graceful shutdown support.
-///
-/// Server startup flow:
-/// 1. Open existing `LoroStore` (fail if not initialized)
-/// 2. Open/create `SqliteCache`, sync from Loro
-/// 3. Create `MedullaServer` with store + cache
-/// 4. Install signal handlers for graceful shutdown
-/// 5. Call `server.serve(rmcp::transport::io::stdio()).await`
-/// 6. Wait for shutdown signal
-pub fn handle_serve(http_port: Option<u16>) -> Result<()> {
-    let root = find_project_root();
+pub fn handle_digest(
+    data_dir: Option<&Path>,
+    since: &str,
+    output: Option<PathBuf>,
+    json: bool,
+) -> Result<()> {
+    let days = crate::snapshot::parse_period(since).map_err(MedullaError::Storage)?;
+    let root = find_project_root(data_dir);
+    let store = LoroStore::open(&root)?;
 
-    // Check if this is an initialized medulla project
-    if !root.join(".medulla").exists() {
-        return Err(MedullaError::Storage(
-            "Not a medulla project. Run `medulla init` first.".to_string(),
-        ));
-    }
+    let until = chrono::Utc::now();
+    let digest = crate::snapshot::generate_digest(
+        &store,
+        until - chrono::Duration::days(days as i64),
+        until,
+    )?;
+    let text = if json {
+        serde_json::to_string_pretty(&digest)?
+    } else {
+        digest.to_markdown()
+    };
 
-    // Set up tracing to stderr (stdout is reserved for MCP protocol in stdio mode)
-    let filter = tracing_subscriber::EnvFilter::try_from_env("MEDULLA_LOG_LEVEL")
-        .or_else(|_| tracing_subscriber::EnvFilter::try_from_env("RUST_LOG"))
-        .unwrap_or_else(|_| tracing_subscriber::EnvFilter::new("info"));
+    match output {
+        Some(path) => {
+            std::fs::write(&path, text)?;
+            eprintln!("Wrote digest to {}", path.display());
+        }
+        None => println!("{}", text),
+    }
 
-    tracing_subscriber::fmt()
-        .with_env_filter(filter)
-        .with_writer(std::io::stderr)
-        .init();
+    Ok(())
+}
 
-    // Open the store and cache
+pub fn handle_status(data_dir: Option<&Path>, output: Option<PathBuf>, json: bool) -> Result<()> {
+    let root = find_project_root(data_dir);
     let store = LoroStore::open(&root)?;
     let cache = SqliteCache::open(store.medulla_dir())?;
 
-    // Sync cache with store
-    store.sync_cache(&cache)?;
+    // No sync here: a stale cache shows up as a health warning
+    let dashboard = crate::snapshot::generate_dashboard(&store, &cache, chrono::Utc::now())?;
+    let text = if json {
+        serde_json::to_string_pretty(&dashboard)?
+    } else {
+        dashboard.to_markdown()
+    };
 
-    // Check performance thresholds
-    if let Ok(stats) = cache.get_stats() {
-        let loro_size = std::fs::metadata(root.join(".medulla/loro.db"))
-            .map(|m| m.len())
-            .unwrap_or(0);
-        for warning in crate::warnings::check_thresholds(&stats, loro_size) {
-            tracing::warn!("{}", crate::warnings::format_warning(&warning));
+    match output {
+        Some(path) => {
+            std::fs::write(&path, text)?;
+            eprintln!("Wrote status to {}", path.display());
         }
+        None => println!("{}", text),
     }
 
-    // Create the server
-    let server = MedullaServer::new(store, cache);
+    Ok(())
+}
 
-    // Run the async server with tokio runtime
-    let rt = tokio::runtime::Runtime::new()
-        .map_err(|e| MedullaError::Storage(format!("Failed to create tokio runtime: {}", e)))?;
+pub fn handle_schema_dump(output: Option<PathBuf>) -> Result<()> {
+    let schema = serde_json::to_string_pretty(&crate::mcp::schema::schema_document())?;
 
-    rt.block_on(async move {
-        match http_port {
-            Some(port) => {
-                tracing::info!("Starting Medulla MCP HTTP server on port {}", port);
-                run_http_server(server, port).await
-            }
-            None => {
-                tracing::info!("Starting Medulla MCP server (stdio)");
-                run_server(server).await
-            }
+    match output {
+        Some(path) => {
+            std::fs::write(&path, schema + "\n")?;
+            eprintln!("Wrote schema to {}", path.display());
         }
-    })
+        None => println!("{}", schema),
+    }
+
+    Ok(())
 }
 
-/// Run the MCP server with graceful shutdown on SIGINT/SIGTERM (stdio transport).
-async fn run_server(server: MedullaServer) -> Result<()> {
-    use rmcp::transport::io::stdio;
+pub fn handle_rules_list(data_dir: Option<&Path>, json: bool) -> Result<()> {
+    let root = find_project_root(data_dir);
+    let store = LoroStore::open(&root)?;
+    let config = crate::config::ProjectConfig::load(store.medulla_dir())?;
 
-    let transport = stdio();
+    if json {
+        let output = serde_json::json!({
+            "default_tags": config.default_tags,
+            "tagging_rules": config.tagging_rules,
+            "validation_rules": config.validation_rules,
+        });
+        println!("{}", serde_json::to_string_pretty(&output)?);
+        return Ok(());
+    }
 
-    // Spawn the server task
-    let server_task = tokio::spawn(async move {
-        match server.serve(transport).await {
-            Ok(_) => {
-                tracing::info!("Server stopped");
-                Ok(())
-            }
-            Err(e) => {
-                tracing::error!("Server error: {}", e);
-                Err(MedullaError::Storage(format!("MCP server error: {}", e)))
-            }
-        }
-    });
+    if config.default_tags.is_empty()
+        && config.tagging_rules.is_empty()
+        && config.validation_rules.is_empty()
+    {
+        println!("No rules configured in .medulla/config.yaml");
+        return Ok(());
+    }
 
-    // Wait for shutdown signal or server completion
-    tokio::select! {
-        result = server_task => {
-            match result {
-                Ok(inner_result) => inner_result,
-                Err(e) => Err(MedullaError::Storage(format!("Server task panicked: {}", e))),
-            }
+    if !config.default_tags.is_empty() {
+        println!("Default tags:");
+        for (entity_type, tags) in &config.default_tags {
+            println!("  {:<10} {}", entity_type, tags.join(", "));
         }
-        _ = shutdown_signal() => {
-            tracing::info!("Shutdown signal received, stopping server...");
-            // Give a brief moment for cleanup
-            tokio::time::sleep(Duration::from_millis(100)).await;
-            Ok(())
+    }
+    if !config.tagging_rules.is_empty() {
+        println!("Tagging rules:");
+        for rule in &config.tagging_rules {
+            println!("  {} -> {}", rule.label(), rule.tags.join(", "));
+        }
+    }
+    if !config.validation_rules.is_empty() {
+        println!("Validation rules:");
+        for rule in &config.validation_rules {
+            let check = match &rule.kind {
+                crate::config::RuleKind::FieldRequired { field } => {
+                    format!("field '{}' required", field)
+                }
+                crate::config::RuleKind::TagRequired { min } => {
+                    format!("at least {} tag(s)", min)
+                }
+                crate::config::RuleKind::TitlePattern { pattern } => {
+                    format!("title matches '{}'", pattern)
+                }
+            };
+            let types = if rule.types.is_empty() {
+                "all types".to_string()
+            } else {
+                rule.types.join(", ")
+            };
+            println!("  {} ({})", check, types);
         }
     }
+
+    Ok(())
 }
 
-/// Run the MCP server over HTTP with graceful shutdown.
-async fn run_http_server(server: MedullaServer, port: u16) -> Result<()> {
-    use axum::{routing::get, Router};
-    use rmcp::transport::streamable_http_server::{
-        session::local::LocalSessionManager, StreamableHttpServerConfig, StreamableHttpService,
-    };
-    use std::sync::Arc;
-    use tokio_util::sync::CancellationToken;
+pub fn handle_rules_test(
+    data_dir: Option<&Path>,
+    entity_type: &str,
+    title: &str,
+    content: Option<String>,
+    url: Option<String>,
+    tags: Vec<String>,
+    json: bool,
+) -> Result<()> {
+    let root = find_project_root(data_dir);
+    let store = LoroStore::open(&root)?;
+    let config = crate::config::ProjectConfig::load(store.medulla_dir())?;
 
-    let cancel_token = CancellationToken::new();
-    let config = StreamableHttpServerConfig {
-        sse_keep_alive: Some(Duration::from_secs(30)),
-        sse_retry: Some(Duration::from_secs(5)),
-        stateful_mode: true,
-        cancellation_token: cancel_token.clone(),
+    let properties = match &url {
+        Some(url) => serde_json::json!({ "url": url }),
+        None => serde_json::Value::Null,
     };
+    let subject = crate::config::RuleSubject {
+        entity_type,
+        title,
+        content: content.as_deref(),
+        tags: &tags,
+        properties: &properties,
+    };
+    let matched: Vec<String> = config
+        .tagging_rules
+        .iter()
+        .filter(|rule| rule.matches(&subject))
+        .map(|rule| rule.label())
+        .collect();
+    let added = config.auto_tags(&subject);
 
-    let session_manager = Arc::new(LocalSessionManager::default());
-
-    // Create the MCP HTTP service
-    let server_clone = server.clone();
-    let mcp_service =
-        StreamableHttpService::new(move || Ok(server_clone.clone()), session_manager, config);
-
-    // Build the router with MCP and utility routes
-    let router = Router::new()
-        .nest_service("/mcp", mcp_service)
-        .route("/health", get(|| async { "OK" }))
-        .route(
-            "/openapi.yaml",
-            get(|| async {
-                (
-                    [(axum::http::header::CONTENT_TYPE, "application/yaml")],
-                    include_str!("../../openapi.yaml"),
-                )
-            }),
-        );
-
-    let addr = std::net::SocketAddr::from(([0, 0, 0, 0], port));
-    tracing::info!("MCP HTTP server listening on http://{}", addr);
-    tracing::info!("  - MCP endpoint: http://{}/mcp", addr);
-    tracing::info!("  - Health check: http://{}/health", addr);
-    tracing::info!("  - OpenAPI spec: http://{}/openapi.yaml", addr);
+    let tags: Vec<String> = tags.iter().chain(&added).cloned().collect();
+    let violations = config.check_rules(&crate::config::RuleSubject {
+        entity_type,
+        title,
+        content: content.as_deref(),
+        tags: &tags,
+        properties: &properties,
+    });
 
-    let listener = tokio::net::TcpListener::bind(addr)
-        .await
-        .map_err(|e| MedullaError::Storage(format!("Failed to bind to port {}: {}", port, e)))?;
+    if json {
+        let output = serde_json::json!({
+            "matched_rules": matched,
+            "added_tags": added,
+            "tags": tags,
+            "violations": violations,
+        });
+        println!("{}", serde_json::to_string_pretty(&output)?);
+        return Ok(());
+    }
 
-    // Run server with graceful shutdown
-    let cancel_token_clone = cancel_token.clone();
-    axum::serve(listener, router)
-        .with_graceful_shutdown(async move {
-            shutdown_signal().await;
-            tracing::info!("Shutdown signal received, stopping HTTP server...");
-            cancel_token_clone.cancel();
-        })
-        .await
-        .map_err(|e| MedullaError::Storage(format!("HTTP server error: {}", e)))?;
+    if matched.is_empty() {
+        println!("No tagging rules match");
+    } else {
+        println!("Matching tagging rules:");
+        for label in &matched {
+            println!("  {}", label);
+        }
+    }
+    if added.is_empty() {
+        println!("No tags added");
+    } else {
+        println!("Tags added: {}", added.join(", "));
+    }
+    if violations.is_empty() {
+        println!("Passes all validation rules");
+    } else {
+        println!("Fails validation:");
+        for violation in &violations {
+            println!("  {}: {}", violation.field, violation.message);
+        }
+    }
 
     Ok(())
 }
 
-/// Wait for shutdown signal (Ctrl+C or SIGTERM).
-async fn shutdown_signal() {
-    let ctrl_c = async {
-        tokio::signal::ctrl_c()
-            .await
-            .expect("Failed to install Ctrl+C handler");
-    };
-
-    #[cfg(unix)]
-    let terminate = async {
-        tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())
-            .expect("Failed to install SIGTERM handler")
-            .recv()
-            .await;
-    };
+pub fn handle_import_jsonl(data_dir: Option<&Path>, path: &Path, json: bool) -> Result<()> {
+    let root = find_project_root(data_dir);
+    let store = LoroStore::open(&root)?;
+    let cache = SqliteCache::open(store.medulla_dir())?;
 
-    #[cfg(not(unix))]
-    let terminate = std::future::pending::<()>();
+    let file = std::fs::File::open(path)?;
+    let stats = crate::transfer::import(&store, &cache, io::BufReader::new(file))?;
 
-    tokio::select! {
-        _ = ctrl_c => {},
-        _ = terminate => {},
+    if json {
+        println!("{}", serde_json::to_string_pretty(&stats)?);
+    } else {
+        println!(
+            "Imported {} entities, {} relations and {} embeddings",
+            stats.entities, stats.relations, stats.embeddings
+        );
     }
+
+    Ok(())
 }
 
-/// Handle cache stats command.
-pub fn handle_cache_stats(json: bool) -> Result<()> {
-    let root = find_project_root();
+pub fn handle_import_adr(data_dir: Option<&Path>, dir: &Path, json: bool) -> Result<()> {
+    let root = find_project_root(data_dir);
     let store = LoroStore::open(&root)?;
-    let cache = SqliteCache::open(store.medulla_dir())?;
 
-    // Sync cache with store
-    store.sync_cache(&cache)?;
+    let stats = crate::transfer::import_adr_dir(&store, dir)?;
+    if stats.decisions > 0 {
+        let cache = SqliteCache::open(store.medulla_dir())?;
+        store.sync_cache(&cache)?;
+    }
 
-    let stats = cache.get_stats()?;
+    if json {
+        println!("{}", serde_json::to_string_pretty(&stats)?);
+    } else {
+        println!(
+            "Imported {} decisions and {} supersedes relations",
+            stats.decisions, stats.supersedes
+        );
+        for (file, reason) in &stats.skipped {
+            println!("  skipped {}: {}", file, reason);
+        }
+    }
 
-    // Get loro.db size
-    let loro_path = root.join(".medulla/loro.db");
-    let loro_size = std::fs::metadata(&loro_path).map(|m| m.len()).unwrap_or(0);
+    Ok(())
+}
 
-    // Check for warnings
-    let warnings = crate::warnings::check_thresholds(&stats, loro_size);
+/// Handle `import github` command.
+pub fn handle_import_github(
+    data_dir: Option<&Path>,
+    repo: &str,
+    sync: bool,
+    json: bool,
+) -> Result<()> {
+    crate::transfer::validate_repo(repo)?;
+    let root = find_project_root(data_dir);
+    let store = LoroStore::open(&root)?;
+
+    let token = std::env::var("GITHUB_TOKEN")
+        .or_else(|_| std::env::var("GH_TOKEN"))
+        .ok()
+        .filter(|t| !t.is_empty());
+    // Closed issues only matter when syncing statuses
+    let issues = crate::transfer::fetch_github_issues(repo, sync, token.as_deref())?;
+    let stats = crate::transfer::import_github_issues(&store, repo, &issues, sync)?;
+    if stats.created + stats.closed + stats.reopened > 0 {
+        let cache = SqliteCache::open(store.medulla_dir())?;
+        store.sync_cache(&cache)?;
+    }
 
     if json {
-        #[derive(serde::Serialize)]
-        struct StatsJson {
-            entity_count: usize,
-            embedding_count: usize,
-            decisions: usize,
-            tasks: usize,
-            notes: usize,
-            prompts: usize,
-            components: usize,
-            links: usize,
-            relations: usize,
-            loro_db_size_bytes: u64,
-            warnings: Vec<String>,
+        println!("{}", serde_json::to_string_pretty(&stats)?);
+    } else {
+        println!(
+            "Imported {} issues from {} ({} already imported)",
+            stats.created, repo, stats.unchanged
+        );
+        if sync {
+            println!(
+                "  {} tasks closed, {} reopened",
+                stats.closed, stats.reopened
+            );
         }
+    }
 
-        let json_out = StatsJson {
-            entity_count: stats.entity_count,
-            embedding_count: stats.embedding_count,
-            decisions: stats.decisions,
-            tasks: stats.tasks,
-            notes: stats.notes,
-            prompts: stats.prompts,
-            components: stats.components,
-            links: stats.links,
-            relations: stats.relations,
-            loro_db_size_bytes: loro_size,
-            warnings: warnings
-                .iter()
-                .map(crate::warnings::format_warning)
-                .collect(),
-        };
+    Ok(())
+}
 
-        println!("{}", serde_json::to_string_pretty(&json_out)?);
+/// Handle `sync obsidian` command.
+pub fn handle_sync_obsidian(data_dir: Option<&Path>, vault: &Path, json: bool) -> Result<()> {
+    let root = find_project_root(data_dir);
+    let store = LoroStore::open(&root)?;
+
+    let stats = crate::transfer::sync_obsidian(&store, vault)?;
+    if stats.imported > 0 {
+        let cache = SqliteCache::open(store.medulla_dir())?;
+        store.sync_cache(&cache)?;
+    }
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&stats)?);
     } else {
-        println!("Cache Statistics:");
-        println!("  Entities: {}", stats.entity_count);
-        println!("    Decisions:  {}", stats.decisions);
-        println!("    Tasks:      {}", stats.tasks);
-        println!("    Notes:      {}", stats.notes);
-        println!("    Prompts:    {}", stats.prompts);
-        println!("    Components: {}", stats.components);
-        println!("    Links:      {}", stats.links);
-        println!("  Relations: {}", stats.relations);
-        println!("  Embeddings: {}", stats.embedding_count);
         println!(
-            "  loro.db size: {:.2} MB",
-            loro_size as f64 / (1024.0 * 1024.0)
+            "Imported {} edits, wrote {} files ({} unchanged, {} removed)",
+            stats.imported, stats.exported, stats.unchanged, stats.removed
         );
-
-        if !warnings.is_empty() {
-            println!();
-            for warning in &warnings {
-                eprintln!("{}", crate::warnings::format_warning(warning));
-            }
+        for file in &stats.conflicts {
+            println!("  conflict {}: edited in both medulla and Obsidian", file);
+        }
+        for (file, reason) in &stats.skipped {
+            println!("  skipped {}: {}", file, reason);
         }
     }
 
     Ok(())
 }
 
-/// Handle cache rebuild command.
-pub fn handle_cache_rebuild(json: bool) -> Result<()> {
-    let root = find_project_root();
+pub fn handle_sync_replica(data_dir: Option<&Path>, path: &Path, json: bool) -> Result<()> {
+    let root = find_project_root(data_dir);
     let store = LoroStore::open(&root)?;
-    let cache = SqliteCache::open(store.medulla_dir())?;
-
-    // Clear the cache
-    cache.clear()?;
 
-    // Sync from store (rebuilds all index data)
+    let bytes = std::fs::read(path)?;
+    let reassigned = store.import_replica(&bytes)?;
+    store.save()?;
+    let cache = SqliteCache::open(store.medulla_dir())?;
     store.sync_cache(&cache)?;
 
-    // Recompute all embeddings
-    let embedder = get_embedder();
-    if embedder.is_none() && !json {
-        eprintln!("Warning: Embedding model not available, skipping embedding regeneration");
+    if json {
+        println!("{}", serde_json::to_string_pretty(&reassigned)?);
+    } else {
+        println!("Merged {}", path.display());
+        print_reassignments(&reassigned, false);
     }
 
-    let mut embedding_count = 0;
-    let mut errors = 0;
+    Ok(())
+}
 
-    if let Some(embedder) = embedder {
-        // Process all entity types
-        // Each entity type is processed with (id, title, content, tags)
-        // Content comes from base.content for all types
-        for (entity_type, entities) in [
-            (
-                "decision",
-                store
-                    .list_decisions()?
-                    .into_iter()
-                    .map(|e| {
-                        (
-                            e.base.id.to_string(),
-                            e.base.title.clone(),
-                            e.base.content.clone(),
-                            e.base.tags.clone(),
-                        )
-                    })
-                    .collect::<Vec<_>>(),
-            ),
-            (
-                "task",
-                store
-                    .list_tasks()?
-                    .into_iter()
-                    .map(|e| {
-                        (
-                            e.base.id.to_string(),
-                            e.base.title.clone(),
-                            e.base.content.clone(),
-                            e.base.tags.clone(),
-                        )
-                    })
-                    .collect::<Vec<_>>(),
-            ),
-            (
-                "note",
-                store
-                    .list_notes()?
-                    .into_iter()
-                    .map(|e| {
-                        (
-                            e.base.id.to_string(),
-                            e.base.title.clone(),
-                            e.base.content.clone(),
-                            e.base.tags.clone(),
-                        )
-                    })
-                    .collect::<Vec<_>>(),
-            ),
-            (
-                "prompt",
-                store
-                    .list_prompts()?
-                    .into_iter()
-                    .map(|e| {
-                        (
-                            e.base.id.to_string(),
-                            e.base.title.clone(),
-                            e.template.clone(),
-                            e.base.tags.clone(),
-                        )
-                    })
-                    .collect::<Vec<_>>(),
-            ),
-            (
-                "component",
-                store
-                    .list_components()?
-                    .into_iter()
-                    .map(|e| {
-                        (
-                            e.base.id.to_string(),
-                            e.base.title.clone(),
-                            e.base.content.clone(),
-                            e.base.tags.clone(),
-                        )
-                    })
-                    .collect::<Vec<_>>(),
-            ),
-            (
-                "link",
-                store
-                    .list_links()?
-                    .into_iter()
-                    .map(|e| {
-                        (
-                            e.base.id.to_string(),
-                            e.base.title.clone(),
-                            e.base.content.clone(),
-                            e.base.tags.clone(),
-                        )
-                    })
-                    .collect::<Vec<_>>(),
-            ),
-        ] {
-            for (id, title, content, tags) in entities {
-                match cache.compute_and_store_embedding_if_changed(
-                    &id,
-                    entity_type,
-                    &title,
-                    content.as_deref(),
-                    &tags,
-                    embedder,
-                ) {
-                    Ok(true) => embedding_count += 1,
-                    Ok(false) => {} // Skipped (unchanged)
-                    Err(_) => errors += 1,
-                }
-            }
-        }
-    }
+pub fn handle_resequence(data_dir: Option<&Path>, dry_run: bool, json: bool) -> Result<()> {
+    let root = find_project_root(data_dir);
+    let store = LoroStore::open(&root)?;
 
-    let stats = cache.get_stats()?;
+    // A dry run renumbers a staging copy, which never saves
+    let reassigned = if dry_run {
+        store.staging().resolve_sequence_collisions()?
+    } else {
+        let reassigned = store.resolve_sequence_collisions()?;
+        if !reassigned.is_empty() {
+            store.save()?;
+            let cache = SqliteCache::open(store.medulla_dir())?;
+            store.sync_cache(&cache)?;
+        }
+        reassigned
+    };
 
     if json {
-        #[derive(serde::Serialize)]
-        struct RebuildResult {
-            success: bool,
-            entity_count: usize,
-            embeddings_computed: usize,
-            embedding_errors: usize,
-        }
+        println!("{}", serde_json::to_string_pretty(&reassigned)?);
+    } else {
+        print_reassignments(&reassigned, dry_run);
+    }
 
-        let result = RebuildResult {
-            success: true,
-            entity_count: stats.entity_count,
-            embeddings_computed: embedding_count,
-            embedding_errors: errors,
+    Ok(())
+}
+
+/// Report renumbered entities. Mentions such as `#12` written before the
+/// merge may now point at the entity that kept the number.
+fn print_reassignments(reassigned: &[crate::storage::SequenceReassignment], dry_run: bool) {
+    if reassigned.is_empty() {
+        println!("No duplicate sequence numbers.");
+        return;
+    }
+    let verb = if dry_run {
+        "Would renumber"
+    } else {
+        "Renumbered"
+    };
+    println!("{} {} entities:", verb, reassigned.len());
+    for r in reassigned {
+        let number = |sequence: u32| match &r.collection {
+            Some(collection) => format!("{}/{}", collection, sequence),
+            None => format!("{:03}", sequence),
         };
+        println!(
+            "  {} {} -> {} ({}) - {}",
+            r.entity_type,
+            number(r.old_sequence),
+            number(r.new_sequence),
+            &r.id.to_string()[..7],
+            r.title
+        );
+    }
+    println!("Check mentions like #<seq> in content written before the merge.");
+}
 
-        println!("{}", serde_json::to_string_pretty(&result)?);
+pub fn handle_compact(
+    data_dir: Option<&Path>,
+    keep_days: Option<u32>,
+    force: bool,
+    json: bool,
+) -> Result<()> {
+    let root = find_project_root(data_dir);
+    let store = LoroStore::open(&root)?;
+    let medulla_dir = store.medulla_dir().to_path_buf();
+
+    let history = match keep_days {
+        Some(days) => format!("history older than {} days", days),
+        None => "all history".to_string(),
+    };
+    if !force
+        && !confirm(&format!(
+            "Compact loro.db, dropping {}? Replicas copied before now can no longer be merged.",
+            history
+        ))?
+    {
+        println!("Cancelled.");
+        return Ok(());
+    }
+
+    let keep_since = keep_days.map(|days| chrono::Utc::now() - chrono::Duration::days(days.into()));
+    let loro = store.compact(keep_since)?;
+
+    let cache = SqliteCache::open(&medulla_dir)?;
+    let cache_path = medulla_dir.join("cache.db");
+    let cache_before = std::fs::metadata(&cache_path).map(|m| m.len()).unwrap_or(0);
+    cache.vacuum()?;
+    let cache_after = std::fs::metadata(&cache_path).map(|m| m.len()).unwrap_or(0);
+
+    if json {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&serde_json::json!({
+                "loro_db": loro,
+                "cache_db": {
+                    "before_bytes": cache_before,
+                    "after_bytes": cache_after,
+                },
+            }))?
+        );
     } else {
-        println!("Cache rebuilt successfully.");
-        println!("  Entities indexed: {}", stats.entity_count);
-        println!("  Embeddings computed: {}", embedding_count);
-        if errors > 0 {
-            eprintln!("  Embedding errors: {}", errors);
-        }
+        let mb = |bytes: u64| bytes as f64 / (1024.0 * 1024.0);
+        println!(
+            "loro.db:  {:.2} MB -> {:.2} MB",
+            mb(loro.before_bytes),
+            mb(loro.after_bytes)
+        );
+        println!(
+            "cache.db: {:.2} MB -> {:.2} MB",
+            mb(cache_before),
+            mb(cache_after)
+        );
     }
 
     Ok(())
 }
 
 // =============================================================================
-// Snapshot handlers
+// Self-test handler
 // =============================================================================
 
-/// Handle snapshot generation command.
-pub fn handle_snapshot(output: Option<String>, verbose: bool) -> Result<()> {
-    let root = find_project_root();
-    let store = LoroStore::open(&root)?;
+/// Dimension of the synthetic vectors used by the self-test.
+const SELFTEST_EMBEDDING_DIM: usize = 384;
 
-    let snapshot_dir = output
-        .map(PathBuf::from)
-        .unwrap_or_else(|| root.join(".medulla/snapshot"));
+/// Timing for one self-test phase.
+#[derive(Debug, serde::Serialize)]
+struct SelftestPhase {
+    name: &'static str,
+    millis: f64,
+}
 
-    let stats = crate::snapshot::generate_snapshot(&store, &snapshot_dir)?;
+/// Build a deterministic synthetic embedding for the self-test.
+/// The real model is not loaded so that timings don't depend on a download.
+fn synthetic_embedding(seed: usize) -> Vec<f32> {
+    (0..SELFTEST_EMBEDDING_DIM)
+        .map(|d| ((seed * 31 + d * 7) % 97) as f32 / 97.0)
+        .collect()
+}
 
-    if verbose {
-        println!("Generated {} files:", stats.files_generated.len());
-        for file in &stats.files_generated {
-            println!("  {}", file);
+/// Handle selftest command.
+///
+/// Generates synthetic entities and relations in a temporary store, then times
+/// create, list, search, semantic search and snapshot generation. The user's
+/// project is never opened.
+pub fn handle_selftest(entities: usize, json: bool) -> Result<()> {
+    let root = env::temp_dir().join(format!("medulla-selftest-{}", uuid::Uuid::new_v4()));
+
+    let result = run_selftest(&root, entities);
+    let _ = std::fs::remove_dir_all(&root);
+    let (relations, phases) = result?;
+
+    if json {
+        let out = serde_json::json!({
+            "entities": entities,
+            "relations": relations,
+            "phases": phases,
+        });
+        println!("{}", serde_json::to_string_pretty(&out)?);
+    } else {
+        println!(
+            "Self-test with {} entities and {} relations:",
+            entities, relations
+        );
+        for phase in &phases {
+            println!("  {:<16} {:>10.2} ms", phase.name, phase.millis);
         }
-        println!();
     }
 
-    println!(
-        "Snapshot generated: {} decisions, {} tasks ({} active), {} notes, {} prompts, {} components, {} links",
-        stats.decisions,
-        stats.tasks_total,
-        stats.tasks_active,
-        stats.notes,
-        stats.prompts,
-        stats.components,
-        stats.links,
-    );
+    Ok(())
+}
 
-    println!("Output: {}", snapshot_dir.display());
+/// Run each self-test phase against a fresh store in `root`.
+fn run_selftest(root: &Path, entities: usize) -> Result<(usize, Vec<SelftestPhase>)> {
+    let mut phases = Vec::new();
+    let mut timed = |name: &'static str, start: Instant| {
+        phases.push(SelftestPhase {
+            name,
+            millis: start.elapsed().as_secs_f64() * 1000.0,
+        });
+    };
 
-    Ok(())
+    // Create: entities cycle through decision, task and note, each task
+    // referencing the previous entity
+    let start = Instant::now();
+    let store = LoroStore::init(root)?;
+    let mut ids: Vec<(uuid::Uuid, &'static str)> = Vec::with_capacity(entities);
+    let mut relations = 0;
+    for i in 0..entities {
+        let seq = store.next_sequence_number();
+        let title = format!("Synthetic entity {}", i);
+        let content = Some(format!("Benchmark content for entity {} about caching", i));
+        let (id, entity_type) = match i % 3 {
+            0 => {
+                let mut decision = Decision::new(title, seq);
+                decision.base.content = content;
+                store.add_decision(&decision)?;
+                (decision.base.id, "decision")
+            }
+            1 => {
+                let mut task = Task::new(title, seq);
+                task.base.content = content;
+                store.add_task(&task)?;
+                (task.base.id, "task")
+            }
+            _ => {
+                let mut note = Note::new(title, seq);
+                note.base.content = content;
+                store.add_note(&note)?;
+                (note.base.id, "note")
+            }
+        };
+
+        if entity_type == "task" {
+            if let Some(&(prev_id, prev_type)) = ids.last() {
+                let relation = Relation::new(
+                    id,
+                    entity_type.to_string(),
+                    prev_id,
+                    prev_type.to_string(),
+                    RelationType::References,
+                );
+                store.add_relation(&relation)?;
+                relations += 1;
+            }
+        }
+        ids.push((id, entity_type));
+    }
+    store.save()?;
+    let cache = SqliteCache::open(store.medulla_dir())?;
+    store.sync_cache(&cache)?;
+    timed("create", start);
+
+    let start = Instant::now();
+    store.list_decisions()?;
+    store.list_tasks()?;
+    store.list_notes()?;
+    timed("list", start);
+
+    let start = Instant::now();
+    cache.search_all("caching", 50)?;
+    timed("search", start);
+
+    // Embeddings are stored outside the timed section
+    for (i, (id, entity_type)) in ids.iter().enumerate() {
+        cache.store_embedding(&id.to_string(), entity_type, &synthetic_embedding(i), "")?;
+    }
+    let start = Instant::now();
+    cache.search_semantic(&synthetic_embedding(0), None, 20, 0.3)?;
+    timed("semantic_search", start);
+
+    let start = Instant::now();
+    crate::snapshot::generate_snapshot(&store, &root.join("snapshot"))?;
+    timed("snapshot", start);
+
+    Ok((relations, phases))
 }
 
 // =============================================================================
@@ -2408,8 +6767,8 @@ if ! medulla snapshot; then
     exit 1
 fi
 
-# Stage generated snapshot files
-git add .medulla/snapshot/
+# Stage generated snapshot files and the snapshot history
+git add .medulla/snapshot/ .medulla/snapshot-history.json
 
 exit 0
 "#;
@@ -2449,9 +6808,33 @@ fn is_medulla_hook(hook_path: &std::path::Path) -> bool {
     }
 }
 
+/// Post-commit hook script template
+const POSTCOMMIT_HOOK: &str = r#"#!/bin/sh
+# MEDULLA_HOOK - Auto-generated by medulla. Do not edit.
+# This hook links the new commit to entities mentioned as med-<seq> in its message.
+
+# Fast-path: skip if the message mentions no entity
+if ! git log -1 --format=%B | grep -qi 'med-[0-9]'; then
+    exit 0
+fi
+
+# Never fail: the commit has already been made
+if ! medulla git scan HEAD; then
+    echo "Warning: Medulla could not link this commit to its entities."
+fi
+
+exit 0
+"#;
+
+/// Hooks installed by `medulla hook install`, by file name
+const HOOKS: [(&str, &str); 2] = [
+    ("pre-commit", PRECOMMIT_HOOK),
+    ("post-commit", POSTCOMMIT_HOOK),
+];
+
 /// Handle hook install command.
-pub fn handle_hook_install(force: bool) -> Result<()> {
-    let root = find_project_root();
+pub fn handle_hook_install(data_dir: Option<&Path>, force: bool) -> Result<()> {
+    let root = find_project_root(data_dir);
 
     // Verify we're in a medulla project
     if !root.join(".medulla").exists() {
@@ -2465,78 +6848,111 @@ pub fn handle_hook_install(force: bool) -> Result<()> {
     let hooks_dir = git_dir.join("hooks");
     std::fs::create_dir_all(&hooks_dir)?;
 
-    let hook_path = hooks_dir.join("pre-commit");
+    // Check for existing hooks before writing any
+    if !force {
+        for (name, _) in HOOKS {
+            let hook_path = hooks_dir.join(name);
+            if hook_path.exists() && !is_medulla_hook(&hook_path) {
+                return Err(MedullaError::Storage(format!(
+                    "A {} hook already exists. Use --force to overwrite, or manually integrate medulla.",
+                    name
+                )));
+            }
+        }
+    }
+
+    let mut installed = Vec::new();
+    for (name, script) in HOOKS {
+        let hook_path = hooks_dir.join(name);
+        if hook_path.exists() {
+            if is_medulla_hook(&hook_path) {
+                if !force {
+                    continue;
+                }
+            } else {
+                // Backup existing hook
+                let backup_path = hooks_dir.join(format!("{}.backup", name));
+                std::fs::rename(&hook_path, &backup_path)?;
+                println!("Backed up existing hook to {}.backup", name);
+            }
+        }
+
+        // Write hook
+        std::fs::write(&hook_path, script)?;
 
-    // Check for existing hook
-    if hook_path.exists() {
-        if is_medulla_hook(&hook_path) {
-            println!("Medulla hook already installed. Use --force to reinstall.");
-            return Ok(());
-        } else if !force {
-            return Err(MedullaError::Storage(
-                "A pre-commit hook already exists. Use --force to overwrite, or manually integrate medulla.".to_string()
-            ));
-        } else {
-            // Backup existing hook
-            let backup_path = hooks_dir.join("pre-commit.backup");
-            std::fs::rename(&hook_path, &backup_path)?;
-            println!("Backed up existing hook to pre-commit.backup");
+        // Make executable (Unix only)
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mut perms = std::fs::metadata(&hook_path)?.permissions();
+            perms.set_mode(0o755);
+            std::fs::set_permissions(&hook_path, perms)?;
         }
-    }
 
-    // Write hook
-    std::fs::write(&hook_path, PRECOMMIT_HOOK)?;
+        installed.push(name);
+    }
 
-    // Make executable (Unix only)
-    #[cfg(unix)]
-    {
-        use std::os::unix::fs::PermissionsExt;
-        let mut perms = std::fs::metadata(&hook_path)?.permissions();
-        perms.set_mode(0o755);
-        std::fs::set_permissions(&hook_path, perms)?;
+    if installed.is_empty() {
+        println!("Medulla hooks already installed. Use --force to reinstall.");
+        return Ok(());
     }
 
-    println!("Installed pre-commit hook.");
+    println!("Installed {} hook(s).", installed.join(" and "));
     println!("Snapshots will be auto-generated when .medulla/loro.db is committed.");
+    println!("Commits mentioning med-<seq> will be linked to that entity.");
 
     Ok(())
 }
 
 /// Handle hook uninstall command.
-pub fn handle_hook_uninstall() -> Result<()> {
-    let root = find_project_root();
+pub fn handle_hook_uninstall(data_dir: Option<&Path>) -> Result<()> {
+    let root = find_project_root(data_dir);
 
     let git_dir = find_git_dir(&root)
         .ok_or_else(|| MedullaError::Storage("Not a git repository.".to_string()))?;
 
-    let hook_path = git_dir.join("hooks/pre-commit");
+    let mut removed = 0;
+    for (name, _) in HOOKS {
+        let hook_path = git_dir.join("hooks").join(name);
+        if !hook_path.exists() {
+            continue;
+        }
 
-    if !hook_path.exists() {
-        println!("No pre-commit hook installed.");
-        return Ok(());
-    }
+        if !is_medulla_hook(&hook_path) {
+            println!(
+                "The {} hook is not a Medulla hook; remove it manually if needed.",
+                name
+            );
+            continue;
+        }
 
-    if !is_medulla_hook(&hook_path) {
-        return Err(MedullaError::Storage(
-            "Pre-commit hook is not a Medulla hook. Remove it manually if needed.".to_string(),
-        ));
-    }
+        std::fs::remove_file(&hook_path)?;
+        println!("Uninstalled Medulla {} hook.", name);
+        removed += 1;
 
-    std::fs::remove_file(&hook_path)?;
-    println!("Uninstalled Medulla pre-commit hook.");
+        // Check for backup
+        if git_dir
+            .join("hooks")
+            .join(format!("{}.backup", name))
+            .exists()
+        {
+            println!(
+                "Note: A backup of your previous hook exists at {}.backup",
+                name
+            );
+        }
+    }
 
-    // Check for backup
-    let backup_path = git_dir.join("hooks/pre-commit.backup");
-    if backup_path.exists() {
-        println!("Note: A backup of your previous hook exists at pre-commit.backup");
+    if removed == 0 {
+        println!("No Medulla hooks installed.");
     }
 
     Ok(())
 }
 
 /// Handle hook status command.
-pub fn handle_hook_status() -> Result<()> {
-    let root = find_project_root();
+pub fn handle_hook_status(data_dir: Option<&Path>) -> Result<()> {
+    let root = find_project_root(data_dir);
 
     let git_dir = match find_git_dir(&root) {
         Some(dir) => dir,
@@ -2546,18 +6962,262 @@ pub fn handle_hook_status() -> Result<()> {
         }
     };
 
-    let hook_path = git_dir.join("hooks/pre-commit");
+    let mut missing = false;
+    for (name, _) in HOOKS {
+        let hook_path = git_dir.join("hooks").join(name);
+        let status = if !hook_path.exists() {
+            missing = true;
+            "Not installed"
+        } else if is_medulla_hook(&hook_path) {
+            "Installed"
+        } else {
+            "Custom hook exists"
+        };
+        println!("{}: {}", name, status);
+    }
+
+    if missing {
+        println!("Run 'medulla hook install' to enable automatic snapshots and commit links.");
+    }
+
+    Ok(())
+}
+
+// =============================================================================
+// Git commit link handlers
+// =============================================================================
+
+/// Full SHA and message of a commit, read with `git log`
+fn git_commit(root: &Path, rev: &str) -> Option<(String, String)> {
+    if rev.starts_with('-') {
+        return None;
+    }
+
+    let output = std::process::Command::new("git")
+        .arg("-C")
+        .arg(root)
+        .args(["log", "-1", "--format=%H%n%B", rev, "--"])
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+
+    let text = String::from_utf8_lossy(&output.stdout);
+    let (sha, message) = text.split_once('\n')?;
+    Some((sha.trim().to_string(), message.trim_end().to_string()))
+}
+
+/// Handle git link command: attach a commit to an entity.
+pub fn handle_git_link(data_dir: Option<&Path>, entity_id: String, sha: String) -> Result<()> {
+    let root = find_project_root(data_dir);
+    let store = LoroStore::open(&root)?;
+    let (uuid, entity_type) = find_entity_id_with_type(&store, &entity_id)?;
+
+    // Prefer git's view of the commit; fall back to the SHA as given when
+    // it can't be read (e.g. not fetched yet)
+    let link = match git_commit(&root, &sha) {
+        Some((full_sha, message)) => {
+            let mut link = CommitLink::new(uuid, entity_type, full_sha);
+            link.summary = message.lines().next().map(str::to_string);
+            link
+        }
+        None if CommitLink::is_valid_sha(&sha) => CommitLink::new(uuid, entity_type, sha),
+        None => {
+            return Err(MedullaError::Storage(format!(
+                "Not a commit: {}. Use a commit SHA or a revision git can resolve.",
+                sha
+            )))
+        }
+    };
+
+    if store.add_commit_link(&link)? {
+        store.save()?;
+        println!(
+            "Linked commit {} to {} {}",
+            &link.sha[..7.min(link.sha.len())],
+            link.entity_type,
+            entity_id
+        );
+    } else {
+        println!(
+            "Commit {} is already linked to {} {}",
+            &link.sha[..7.min(link.sha.len())],
+            link.entity_type,
+            entity_id
+        );
+    }
+
+    Ok(())
+}
+
+/// Handle git scan command: link a commit to every entity its message
+/// mentions as `med-<seq>`. Run by the post-commit hook.
+pub fn handle_git_scan(data_dir: Option<&Path>, rev: String) -> Result<()> {
+    let root = find_project_root(data_dir);
+    let store = LoroStore::open(&root)?;
+
+    let (sha, message) = git_commit(&root, &rev)
+        .ok_or_else(|| MedullaError::Storage(format!("Could not read commit {}", rev)))?;
+    let summary = message.lines().next().map(str::to_string);
+
+    let mut linked = 0;
+    for seq in CommitLink::mentions(&message) {
+        let Some((uuid, entity_type)) = store.lookup_id(&seq.to_string(), None) else {
+            eprintln!("Warning: med-{} does not match any entity", seq);
+            continue;
+        };
+
+        let mut link = CommitLink::new(uuid, entity_type.to_string(), sha.clone());
+        link.summary = summary.clone();
+        if store.add_commit_link(&link)? {
+            println!(
+                "Linked commit {} to {} {:03}",
+                &sha[..7.min(sha.len())],
+                entity_type,
+                seq
+            );
+            linked += 1;
+        }
+    }
+
+    if linked > 0 {
+        store.save()?;
+    }
+
+    Ok(())
+}
+
+/// Handle git commits command: list the commits linked to an entity.
+pub fn handle_git_commits(data_dir: Option<&Path>, entity_id: String, json: bool) -> Result<()> {
+    let root = find_project_root(data_dir);
+    let store = LoroStore::open(&root)?;
+    let (uuid, entity_type) = find_entity_id_with_type(&store, &entity_id)?;
+
+    let links = store.get_commit_links(&uuid)?;
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&links)?);
+    } else if links.is_empty() {
+        println!("No commits linked to {} {}.", entity_type, entity_id);
+    } else {
+        println!("Commits linked to {} {}:\n", entity_type, entity_id);
+        for link in links {
+            println!(
+                "  {} {} {}",
+                &link.sha[..7.min(link.sha.len())],
+                link.linked_at.format("%Y-%m-%d"),
+                link.summary.as_deref().unwrap_or("")
+            );
+        }
+    }
+
+    Ok(())
+}
+
+// =============================================================================
+// Workspace handlers
+// =============================================================================
+
+/// Handle workspace add command: register a project under a name.
+pub fn handle_workspace_add(
+    data_dir: Option<&Path>,
+    name: String,
+    path: Option<&Path>,
+) -> Result<()> {
+    let dir = match path {
+        Some(path) => path.to_path_buf(),
+        None => find_project_root(data_dir),
+    };
+    let mut workspace = crate::workspace::Workspace::load()?;
+    workspace.add(&name, &dir)?;
+    workspace.save()?;
+
+    println!(
+        "Registered project '{}' at {}",
+        name.trim(),
+        workspace.projects[name.trim()].display()
+    );
+    Ok(())
+}
+
+/// Handle workspace remove command.
+pub fn handle_workspace_remove(name: String) -> Result<()> {
+    let mut workspace = crate::workspace::Workspace::load()?;
+    if !workspace.remove(&name) {
+        return Err(MedullaError::Config(format!(
+            "No project named '{}' in the workspace",
+            name
+        )));
+    }
+    workspace.save()?;
+
+    println!("Removed project '{}' from the workspace", name);
+    Ok(())
+}
 
-    if !hook_path.exists() {
-        println!("Status: Not installed");
-        println!("Run 'medulla hook install' to enable automatic snapshots.");
-    } else if is_medulla_hook(&hook_path) {
-        println!("Status: Installed");
-        println!("Snapshots will be auto-generated when .medulla/loro.db is committed.");
+/// Handle workspace list command.
+pub fn handle_workspace_list(json: bool) -> Result<()> {
+    let workspace = crate::workspace::Workspace::load()?;
+
+    if json {
+        let projects: Vec<serde_json::Value> = workspace
+            .projects
+            .iter()
+            .map(|(name, dir)| {
+                serde_json::json!({
+                    "name": name,
+                    "path": dir.display().to_string(),
+                    "initialized": dir.join(".medulla").is_dir(),
+                })
+            })
+            .collect();
+        println!("{}", serde_json::to_string_pretty(&projects)?);
+    } else if workspace.projects.is_empty() {
+        println!("No projects registered. Add one with `medulla workspace add <name> [path]`.");
     } else {
-        println!("Status: Custom hook exists");
-        println!("A non-Medulla pre-commit hook is installed.");
-        println!("Use 'medulla hook install --force' to replace it.");
+        for (name, dir) in &workspace.projects {
+            let missing = if dir.join(".medulla").is_dir() {
+                ""
+            } else {
+                " (missing)"
+            };
+            println!("  {:<20} {}{}", name, dir.display(), missing);
+        }
+    }
+
+    Ok(())
+}
+
+/// Handle search --all-projects: run the search in every registered project.
+pub fn handle_search_all_projects(
+    query: String,
+    semantic: bool,
+    include_archived: bool,
+) -> Result<()> {
+    let workspace = crate::workspace::Workspace::load()?;
+    if workspace.projects.is_empty() {
+        return Err(MedullaError::Config(
+            "No projects registered. Add one with `medulla workspace add <name> [path]`."
+                .to_string(),
+        ));
+    }
+
+    for (i, (name, dir)) in workspace.projects.iter().enumerate() {
+        if i > 0 {
+            println!();
+        }
+        println!("== {} ({}) ==", name, dir.display());
+        if let Err(e) = handle_search(
+            Some(dir),
+            query.clone(),
+            semantic,
+            OutputFormat::Plain,
+            include_archived,
+            None,
+        ) {
+            eprintln!("  Skipped: {}", e);
+        }
     }
 
     Ok(())
@@ -2569,6 +7229,128 @@ mod tests {
     use std::fs;
     use tempfile::TempDir;
 
+    #[test]
+    fn test_is_store_change() {
+        use notify::event::{AccessKind, CreateKind, EventKind, ModifyKind};
+
+        let db_path = PathBuf::from("/project/.medulla/loro.db");
+        let event = |kind, path: &str| notify::Event::new(kind).add_path(PathBuf::from(path));
+
+        assert!(is_store_change(
+            &event(
+                EventKind::Modify(ModifyKind::Any),
+                "/project/.medulla/loro.db"
+            ),
+            &db_path
+        ));
+        assert!(is_store_change(
+            &event(
+                EventKind::Create(CreateKind::File),
+                "/project/.medulla/loro.db"
+            ),
+            &db_path
+        ));
+        // Cache writes and reads of the store itself are ignored
+        assert!(!is_store_change(
+            &event(
+                EventKind::Modify(ModifyKind::Any),
+                "/project/.medulla/cache.db"
+            ),
+            &db_path
+        ));
+        assert!(!is_store_change(
+            &event(
+                EventKind::Access(AccessKind::Any),
+                "/project/.medulla/loro.db"
+            ),
+            &db_path
+        ));
+    }
+
+    #[test]
+    fn test_refresh_derived_syncs_cache_and_snapshot() {
+        let tmp = TempDir::new().unwrap();
+        let store = LoroStore::init(tmp.path()).unwrap();
+        store
+            .add_note(&crate::entity::Note::new("Pulled note".to_string(), 1))
+            .unwrap();
+        store.save().unwrap();
+
+        let summary = refresh_derived(tmp.path(), true).unwrap();
+        assert!(summary.contains("1 entities"));
+        assert!(tmp.path().join(".medulla/snapshot").exists());
+
+        let cache = SqliteCache::open(store.medulla_dir()).unwrap();
+        assert_eq!(cache.get_stats().unwrap().notes, 1);
+    }
+
+    #[test]
+    fn test_read_csv_rows_with_mapping() {
+        let csv = "Kind,Name,tags,priority\ntask, Write docs ,\"a, b\",high\nnote,Scratch,,\n";
+        let rows = read_csv_rows(
+            csv.as_bytes(),
+            &["Kind=type".to_string(), "Name=title".to_string()],
+        )
+        .unwrap();
+
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[0]["type"], "task");
+        assert_eq!(rows[0]["title"], "Write docs");
+        assert_eq!(rows[0]["tags"], "a, b");
+        assert_eq!(rows[0]["priority"], "high");
+        // Empty cells are omitted
+        assert!(rows[1].get("tags").is_none());
+        assert!(rows[1].get("priority").is_none());
+
+        assert!(read_csv_rows(csv.as_bytes(), &["bad".to_string()]).is_err());
+    }
+
+    #[test]
+    fn test_read_relation_rows() {
+        let csv = "source,relation_type,target\n1, blocks ,2\n\nEvent store,implements,\"Use CQRS, maybe\"\n";
+        let (rows, lines) = read_relation_rows(csv.as_bytes()).unwrap();
+
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[0].source, "1");
+        assert_eq!(rows[0].relation_type, "blocks");
+        assert_eq!(rows[1].source, "Event store");
+        assert_eq!(rows[1].target, "Use CQRS, maybe");
+        // Blank lines are skipped but still counted
+        assert_eq!(lines, vec![2, 4]);
+
+        // The header is optional
+        let (rows, _) = read_relation_rows("1,blocks,2\n".as_bytes()).unwrap();
+        assert_eq!(rows.len(), 1);
+    }
+
+    #[test]
+    fn test_find_project_root_with_data_dir() {
+        let tmp = TempDir::new().unwrap();
+        assert_eq!(find_project_root(Some(tmp.path())), tmp.path());
+        assert_eq!(
+            find_project_root(Some(&tmp.path().join(".medulla"))),
+            tmp.path()
+        );
+    }
+
+    #[test]
+    fn test_handle_list_with_data_dir() {
+        let tmp = TempDir::new().unwrap();
+        let store = LoroStore::init(tmp.path()).unwrap();
+        store
+            .add_decision(&Decision::new("Elsewhere".to_string(), 1))
+            .unwrap();
+        store.save().unwrap();
+
+        assert!(handle_list(Some(tmp.path()), None, OutputFormat::Json, false, None).is_ok());
+
+        let empty = TempDir::new().unwrap();
+        assert!(matches!(
+            handle_list(Some(empty.path()), None, OutputFormat::Json, false, None),
+            Err(MedullaError::NotInitialized)
+        ));
+    }
+
     // =========================================================================
     // Hook-related tests
     // =========================================================================
@@ -2778,10 +7560,458 @@ mod tests {
         assert_eq!(content, custom_content);
     }
 
+    #[test]
+    fn test_handle_update_workflow() {
+        let tmp = TempDir::new().unwrap();
+        let store = LoroStore::init(tmp.path()).unwrap();
+        store
+            .add_task(&Task::new("Ship it".to_string(), 1))
+            .unwrap();
+        store.save().unwrap();
+        fs::write(
+            store.medulla_dir().join(crate::config::CONFIG_FILE),
+            "workflows:\n  - type: task\n    transitions:\n      todo: [in_progress]\n",
+        )
+        .unwrap();
+
+        let update = |status: &str, force: bool| {
+            handle_update(
+                Some(tmp.path()),
+                "1".to_string(),
+                None,
+                Some(status.to_string()),
+                vec![],
+                vec![],
+                vec![],
+                false,
+                false,
+                force,
+                false,
+            )
+        };
+        assert!(update("done", false).is_err());
+        update("done", true).unwrap();
+        update("done", false).unwrap();
+    }
+
+    #[test]
+    fn test_handle_archive_roundtrip() {
+        let tmp = TempDir::new().unwrap();
+        let store = LoroStore::init(tmp.path()).unwrap();
+        let task = Task::new("Old chore".to_string(), 1);
+        store.add_task(&task).unwrap();
+        store.save().unwrap();
+
+        handle_archive(Some(tmp.path()), "1".to_string()).unwrap();
+        let store = LoroStore::open(tmp.path()).unwrap();
+        assert!(
+            store
+                .get_task(&task.base.id)
+                .unwrap()
+                .unwrap()
+                .base
+                .archived
+        );
+
+        // Archived again is a no-op; unarchive restores it
+        handle_archive(Some(tmp.path()), "1".to_string()).unwrap();
+        handle_unarchive(Some(tmp.path()), "1".to_string()).unwrap();
+        let store = LoroStore::open(tmp.path()).unwrap();
+        assert!(
+            !store
+                .get_task(&task.base.id)
+                .unwrap()
+                .unwrap()
+                .base
+                .archived
+        );
+
+        assert!(handle_archive(Some(tmp.path()), "99".to_string()).is_err());
+    }
+
+    #[test]
+    fn test_handle_delete_restore_and_purge() {
+        let tmp = TempDir::new().unwrap();
+        let store = LoroStore::init(tmp.path()).unwrap();
+        let task = Task::new("Ship release".to_string(), 1);
+        let note = Note::new("Release notes".to_string(), 2);
+        store.add_task(&task).unwrap();
+        store.add_note(&note).unwrap();
+        store
+            .add_relation(&Relation::new(
+                note.base.id,
+                "note".to_string(),
+                task.base.id,
+                "task".to_string(),
+                RelationType::References,
+            ))
+            .unwrap();
+        store.save().unwrap();
+
+        handle_delete(Some(tmp.path()), "1".to_string(), true).unwrap();
+        let store = LoroStore::open(tmp.path()).unwrap();
+        assert!(store.get_task(&task.base.id).unwrap().is_none());
+        assert!(store.list_relations().unwrap().is_empty());
+        assert_eq!(store.list_trash().unwrap().len(), 1);
+
+        handle_trash_restore(Some(tmp.path()), "1".to_string()).unwrap();
+        let store = LoroStore::open(tmp.path()).unwrap();
+        assert!(store.get_task(&task.base.id).unwrap().is_some());
+        assert_eq!(store.list_relations().unwrap().len(), 1);
+        assert!(handle_trash_restore(Some(tmp.path()), "1".to_string()).is_err());
+
+        let prefix = note.base.id.to_string()[..8].to_string();
+        handle_delete(Some(tmp.path()), "2".to_string(), true).unwrap();
+        handle_trash_purge(Some(tmp.path()), Some(prefix), true).unwrap();
+        let store = LoroStore::open(tmp.path()).unwrap();
+        assert!(store.list_trash().unwrap().is_empty());
+        assert!(store.get_note(&note.base.id).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_handle_dedupe() {
+        let tmp = TempDir::new().unwrap();
+        let store = LoroStore::init(tmp.path()).unwrap();
+        let first = Note::new("Retry policy".to_string(), 1);
+        let second = Note::new("Retry policy draft".to_string(), 2);
+        store.add_note(&first).unwrap();
+        store.add_note(&second).unwrap();
+        store.save().unwrap();
+
+        let cache = SqliteCache::open(store.medulla_dir()).unwrap();
+        store.sync_cache(&cache).unwrap();
+        for note in [&first, &second] {
+            cache
+                .store_embedding(&note.base.id.to_string(), "note", &[1.0, 0.0], "h")
+                .unwrap();
+        }
+
+        handle_dedupe(Some(tmp.path()), None, 0.9, 20, false, false).unwrap();
+        handle_dedupe(
+            Some(tmp.path()),
+            Some("note".to_string()),
+            0.9,
+            20,
+            false,
+            true,
+        )
+        .unwrap();
+        assert!(handle_dedupe(Some(tmp.path()), None, 1.5, 20, false, false).is_err());
+        assert!(handle_dedupe(Some(tmp.path()), None, 0.9, 20, true, true).is_err());
+    }
+
+    #[test]
+    fn test_handle_attach() {
+        let tmp = TempDir::new().unwrap();
+        let store = LoroStore::init(tmp.path()).unwrap();
+        let note = Note::new("Architecture".to_string(), 1);
+        store.add_note(&note).unwrap();
+        store.save().unwrap();
+        let diagram = tmp.path().join("diagram.svg");
+        fs::write(&diagram, "<svg/>").unwrap();
+
+        handle_attach(Some(tmp.path()), "1".to_string(), &diagram, false).unwrap();
+        assert!(handle_attach(Some(tmp.path()), "9".to_string(), &diagram, false).is_err());
+
+        let store = LoroStore::open(tmp.path()).unwrap();
+        let attachments = store.list_attachments(Some(&note.base.id)).unwrap();
+        assert_eq!(attachments.len(), 1);
+        assert_eq!(attachments[0].mime, "image/svg+xml");
+        let stored = store.attachments_dir().join(attachments[0].relative_path());
+        assert_eq!(fs::read_to_string(&stored).unwrap(), "<svg/>");
+
+        // Kept while trashed, removed on purge
+        store.trash_entity(&note.base.id, None).unwrap();
+        assert!(stored.exists());
+        store.purge_trash(Some(&note.base.id)).unwrap();
+        assert!(!stored.exists());
+        assert!(store.list_attachments(None).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_handle_bulk_tag() {
+        let tmp = TempDir::new().unwrap();
+        let store = LoroStore::init(tmp.path()).unwrap();
+        let mut draft = Note::new("Draft".to_string(), 1);
+        draft.base.tags = vec!["wip".to_string()];
+        store.add_note(&draft).unwrap();
+        store.add_note(&Note::new("Other".to_string(), 2)).unwrap();
+        store.save().unwrap();
+
+        let add = vec!["reviewed".to_string()];
+        let remove = vec!["wip".to_string()];
+        handle_bulk_tag(
+            Some(tmp.path()),
+            "tag:wip",
+            add.clone(),
+            remove.clone(),
+            true,
+            false,
+        )
+        .unwrap();
+        let store = LoroStore::open(tmp.path()).unwrap();
+        assert_eq!(
+            store.get_note(&draft.base.id).unwrap().unwrap().base.tags,
+            vec!["wip"]
+        );
+
+        handle_bulk_tag(Some(tmp.path()), "tag:wip", add, remove, false, false).unwrap();
+        let store = LoroStore::open(tmp.path()).unwrap();
+        let tagged: Vec<_> = store
+            .list_notes()
+            .unwrap()
+            .into_iter()
+            .filter(|n| n.base.tags == vec!["reviewed"])
+            .map(|n| n.base.title)
+            .collect();
+        assert_eq!(tagged, vec!["Draft"]);
+    }
+
+    #[test]
+    fn test_handle_prompt_render() {
+        let tmp = TempDir::new().unwrap();
+        let store = LoroStore::init(tmp.path()).unwrap();
+        let mut prompt = Prompt::new("Summarize".to_string(), 1);
+        prompt.template = Some("Summarize {{topic}}".to_string());
+        prompt.output_schema = Some(r#"{"type": "object", "required": ["summary"]}"#.to_string());
+        store.add_prompt(&prompt).unwrap();
+        store.save().unwrap();
+
+        let vars = vec!["topic=the cache".to_string()];
+        handle_prompt_render(Some(tmp.path()), "1", &vars, None, false).unwrap();
+        assert!(handle_prompt_render(Some(tmp.path()), "1", &[], None, false).is_err());
+
+        let valid = Some(r#"{"summary": "ok"}"#.to_string());
+        handle_prompt_render(Some(tmp.path()), "1", &vars, valid, true).unwrap();
+        let invalid = Some("{}".to_string());
+        assert!(handle_prompt_render(Some(tmp.path()), "1", &vars, invalid, false).is_err());
+    }
+
+    #[test]
+    fn test_handle_digest() {
+        let tmp = TempDir::new().unwrap();
+        let store = LoroStore::init(tmp.path()).unwrap();
+        store
+            .add_task(&Task::new("Write docs".to_string(), 1))
+            .unwrap();
+        store.save().unwrap();
+
+        let path = tmp.path().join("digest.md");
+        handle_digest(Some(tmp.path()), "week", Some(path.clone()), false).unwrap();
+        let digest = fs::read_to_string(&path).unwrap();
+        assert!(digest.contains("### Tasks\n\n- 001 Write docs (todo)"));
+
+        assert!(handle_digest(Some(tmp.path()), "soon", None, false).is_err());
+    }
+
+    #[test]
+    fn test_handle_schema_dump() {
+        let tmp = TempDir::new().unwrap();
+        let path = tmp.path().join("schema.json");
+        handle_schema_dump(Some(path.clone())).unwrap();
+
+        let schema: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        assert!(schema["responses"]["BatchResult"].is_object());
+        assert!(schema["tools"]["entity_create"]["parameters"].is_object());
+    }
+
+    #[test]
+    fn test_handle_doctor() {
+        let tmp = TempDir::new().unwrap();
+        let store = LoroStore::init(tmp.path()).unwrap();
+        store.save().unwrap();
+        assert_eq!(handle_doctor(Some(tmp.path()), true).unwrap(), 0);
+
+        // Written to the store only, so the cache is stale until synced
+        store
+            .add_note(&Note::new("Not cached".to_string(), 1))
+            .unwrap();
+        store.save().unwrap();
+        assert_eq!(handle_doctor(Some(tmp.path()), false).unwrap(), 2);
+
+        let cache = SqliteCache::open(store.medulla_dir()).unwrap();
+        store.sync_cache(&cache).unwrap();
+        assert_eq!(handle_doctor(Some(tmp.path()), true).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_handle_merge() {
+        let tmp = TempDir::new().unwrap();
+        let store = LoroStore::init(tmp.path()).unwrap();
+        let mut target = Note::new("Retry policy".to_string(), 1);
+        target.base.content = Some("Back off exponentially.".to_string());
+        let mut source = Note::new("Retry policy draft".to_string(), 2);
+        source.base.content = Some("Cap retries at five.".to_string());
+        source.base.tags = vec!["network".to_string()];
+        let other = Note::new("Alerting".to_string(), 3);
+        let task = Task::new("Tune retries".to_string(), 4);
+        store.add_note(&target).unwrap();
+        store.add_note(&source).unwrap();
+        store.add_note(&other).unwrap();
+        store.add_task(&task).unwrap();
+        store
+            .add_relation(&Relation::new(
+                source.base.id,
+                "note".to_string(),
+                other.base.id,
+                "note".to_string(),
+                RelationType::References,
+            ))
+            .unwrap();
+        store.save().unwrap();
+
+        assert!(handle_merge(
+            Some(tmp.path()),
+            "4".to_string(),
+            "1".to_string(),
+            false,
+            true
+        )
+        .is_err());
+
+        handle_merge(
+            Some(tmp.path()),
+            "2".to_string(),
+            "1".to_string(),
+            false,
+            true,
+        )
+        .unwrap();
+        let store = LoroStore::open(tmp.path()).unwrap();
+        let merged = store.get_note(&target.base.id).unwrap().unwrap();
+        assert_eq!(
+            merged.base.content.as_deref(),
+            Some("Back off exponentially.\n\nCap retries at five.")
+        );
+        assert_eq!(merged.base.tags, vec!["network".to_string()]);
+        assert!(store.get_note(&source.base.id).unwrap().is_none());
+        let relations = store.list_relations().unwrap();
+        assert_eq!(relations.len(), 1);
+        assert_eq!(relations[0].source_id, target.base.id);
+
+        handle_merge(
+            Some(tmp.path()),
+            "3".to_string(),
+            "1".to_string(),
+            true,
+            true,
+        )
+        .unwrap();
+        let store = LoroStore::open(tmp.path()).unwrap();
+        assert!(
+            store
+                .get_note(&other.base.id)
+                .unwrap()
+                .unwrap()
+                .base
+                .archived
+        );
+        assert!(store.list_relations().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_handle_relation_type_add_and_use() {
+        let tmp = TempDir::new().unwrap();
+        let store = LoroStore::init(tmp.path()).unwrap();
+        store
+            .add_component(&Component::new("Gateway".to_string(), 1))
+            .unwrap();
+        store
+            .add_component(&Component::new("Auth".to_string(), 2))
+            .unwrap();
+        store.save().unwrap();
+
+        let add = || {
+            handle_relation_add(
+                Some(tmp.path()),
+                "1".to_string(),
+                "2".to_string(),
+                "depends_on".to_string(),
+                false,
+            )
+        };
+        assert!(add().is_err());
+
+        handle_relation_type_add(
+            Some(tmp.path()),
+            "Depends_On".to_string(),
+            Some("required_by".to_string()),
+            false,
+        )
+        .unwrap();
+        add().unwrap();
+        assert!(handle_relation_type_remove(Some(tmp.path()), "depends_on".to_string()).is_err());
+
+        let store = LoroStore::open(tmp.path()).unwrap();
+        let relations = store.list_relations().unwrap();
+        assert_eq!(
+            relations[0].relation_type,
+            RelationType::Custom("depends_on".to_string())
+        );
+    }
+
+    #[test]
+    fn test_postcommit_hook_content() {
+        assert!(POSTCOMMIT_HOOK.contains(HOOK_MARKER));
+        assert!(POSTCOMMIT_HOOK.contains("medulla git scan HEAD"));
+        assert!(HOOKS
+            .iter()
+            .all(|(_, script)| script.starts_with("#!/bin/sh")));
+    }
+
+    #[test]
+    fn test_handle_git_link_without_git() {
+        let tmp = TempDir::new().unwrap();
+        let store = LoroStore::init(tmp.path()).unwrap();
+        let task = Task::new("Add login".to_string(), 1);
+        store.add_task(&task).unwrap();
+        store.save().unwrap();
+
+        // Outside a git repository the SHA is taken as given
+        handle_git_link(Some(tmp.path()), "1".to_string(), "ABCDEF1".to_string()).unwrap();
+        assert!(handle_git_link(Some(tmp.path()), "1".to_string(), "HEAD".to_string()).is_err());
+
+        let store = LoroStore::open(tmp.path()).unwrap();
+        let links = store.get_commit_links(&task.base.id).unwrap();
+        assert_eq!(links.len(), 1);
+        assert_eq!(links[0].sha, "abcdef1");
+        assert_eq!(links[0].entity_type, "task");
+    }
+
     // =========================================================================
     // Relation parsing tests
     // =========================================================================
 
+    #[test]
+    fn test_handle_relation_add_rejects_blocks_cycle() {
+        let tmp = TempDir::new().unwrap();
+        let store = LoroStore::init(tmp.path()).unwrap();
+        for (seq, title) in [(1, "Schema"), (2, "Migration")] {
+            store.add_task(&Task::new(title.to_string(), seq)).unwrap();
+        }
+        store.save().unwrap();
+
+        let add = |source: &str, target: &str| {
+            handle_relation_add(
+                Some(tmp.path()),
+                source.to_string(),
+                target.to_string(),
+                "blocks".to_string(),
+                true,
+            )
+        };
+        add("1", "2").unwrap();
+        let err = add("2", "1").unwrap_err().to_string();
+        assert!(err.contains("#2 Migration (task) -> #1 Schema (task) -> #2 Migration (task)"));
+        assert!(add("1", "1").is_err());
+
+        let store = LoroStore::open(tmp.path()).unwrap();
+        assert_eq!(store.list_relations().unwrap().len(), 1);
+        assert!(crate::graph::blocks_cycles(&store).unwrap().is_empty());
+        handle_graph_cycles(Some(tmp.path()), true).unwrap();
+    }
+
     #[test]
     fn test_parse_relation_string_valid() {
         let valid_uuid = "550e8400-e29b-41d4-a716-446655440000";
@@ -2811,7 +8041,8 @@ mod tests {
     fn test_parse_relation_string_all_types() {
         let uuid = "550e8400-e29b-41d4-a716-446655440000";
 
-        // Valid relation types: implements, blocks, supersedes, references, belongs_to, documents
+        // Valid relation types: implements, blocks, supersedes, references, belongs_to,
+        // documents, parent_of, child_of
         for rel_type in [
             "blocks",
             "implements",
@@ -2819,6 +8050,8 @@ mod tests {
             "references",
             "belongs_to",
             "documents",
+            "parent_of",
+            "child_of",
         ] {
             let result = parse_relation_string(&format!("{}:{}", rel_type, uuid));
             assert!(
@@ -0,0 +1,212 @@
+//! Interactive ID picker for commands run without an entity ID.
+//!
+//! Instead of running `medulla list` to find a sequence number, the user
+//! types a few letters of a title and picks from the best fuzzy matches.
+//! The picker is line-based: each line typed narrows the candidates, a
+//! number picks one of the listed matches and an empty line picks the top
+//! match. It reads stdin and writes to stderr, so stdout stays clean for
+//! the command's own output.
+
+use std::io::{self, BufRead, Write};
+
+use crate::error::{MedullaError, Result};
+
+/// How many matches are shown at a time
+const SHOWN_MATCHES: usize = 10;
+
+/// An entity the picker can offer.
+#[derive(Debug, Clone)]
+pub struct PickItem {
+    pub sequence_number: u32,
+    pub entity_type: String,
+    pub title: String,
+    /// Status or similar, shown in brackets after the type
+    pub detail: Option<String>,
+}
+
+impl PickItem {
+    fn label(&self) -> String {
+        match &self.detail {
+            Some(detail) => format!(
+                "{:03} {} [{}] {}",
+                self.sequence_number, self.entity_type, detail, self.title
+            ),
+            None => format!(
+                "{:03} {} {}",
+                self.sequence_number, self.entity_type, self.title
+            ),
+        }
+    }
+}
+
+/// Score how well `query` matches `text` as a case-insensitive
+/// subsequence, or None if it does not. Consecutive characters and matches
+/// at the start of a word score higher, so "authm" prefers
+/// "Auth middleware" over "Add authentication to the admin".
+pub fn fuzzy_score(query: &str, text: &str) -> Option<i64> {
+    let query: Vec<char> = query
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .flat_map(char::to_lowercase)
+        .collect();
+    if query.is_empty() {
+        return Some(0);
+    }
+    let text: Vec<char> = text.chars().flat_map(char::to_lowercase).collect();
+
+    let mut score = 0;
+    let mut next = 0;
+    let mut previous: Option<usize> = None;
+    for (i, c) in text.iter().enumerate() {
+        if next == query.len() {
+            break;
+        }
+        if *c != query[next] {
+            continue;
+        }
+        score += 1;
+        if previous.is_some_and(|p| p + 1 == i) {
+            score += 5;
+        }
+        if i == 0 || !text[i - 1].is_alphanumeric() {
+            score += 3;
+        }
+        previous = Some(i);
+        next += 1;
+    }
+    if next < query.len() {
+        return None;
+    }
+    // Prefer shorter titles among equally good matches
+    Some(score * 100 - text.len() as i64)
+}
+
+/// Items matching `query`, best first. A query that is a sequence number
+/// also matches that entity exactly.
+pub fn filter<'a>(items: &'a [PickItem], query: &str) -> Vec<&'a PickItem> {
+    let query = query.trim();
+    let mut scored: Vec<(i64, &PickItem)> = items
+        .iter()
+        .filter_map(|item| {
+            if query.parse::<u32>().ok() == Some(item.sequence_number) {
+                return Some((i64::MAX, item));
+            }
+            fuzzy_score(query, &item.title).map(|score| (score, item))
+        })
+        .collect();
+    scored.sort_by(|a, b| {
+        b.0.cmp(&a.0)
+            .then(b.1.sequence_number.cmp(&a.1.sequence_number))
+    });
+    scored.into_iter().map(|(_, item)| item).collect()
+}
+
+/// Let the user pick one of `items` in the terminal. Returns None when
+/// they cancel, and an error when there is no terminal to ask.
+pub fn pick<'a>(items: &'a [PickItem], what: &str) -> Result<Option<&'a PickItem>> {
+    if !atty::is(atty::Stream::Stdin) || !atty::is(atty::Stream::Stderr) {
+        return Err(MedullaError::Storage(format!(
+            "No {} given. Pass an ID, or run in a terminal to pick one.",
+            what
+        )));
+    }
+    if items.is_empty() {
+        return Err(MedullaError::Storage(format!("No {} to pick from", what)));
+    }
+
+    let stdin = io::stdin();
+    let mut stderr = io::stderr();
+    let mut query = String::new();
+    loop {
+        let matches = filter(items, &query);
+        writeln!(stderr)?;
+        if matches.is_empty() {
+            writeln!(stderr, "  No matches for '{}'", query)?;
+        }
+        for (i, item) in matches.iter().take(SHOWN_MATCHES).enumerate() {
+            let marker = if i == 0 { '>' } else { ' ' };
+            writeln!(stderr, "{} {:>2}. {}", marker, i + 1, item.label())?;
+        }
+        if matches.len() > SHOWN_MATCHES {
+            writeln!(stderr, "     ... {} more", matches.len() - SHOWN_MATCHES)?;
+        }
+        write!(
+            stderr,
+            "Pick {} (type to filter, 1-{} to choose, Enter for top, q to cancel){}: ",
+            what,
+            matches.len().clamp(1, SHOWN_MATCHES),
+            if query.is_empty() {
+                String::new()
+            } else {
+                format!(" [{}]", query)
+            }
+        )?;
+        stderr.flush()?;
+
+        let mut line = String::new();
+        if stdin.lock().read_line(&mut line)? == 0 {
+            return Ok(None);
+        }
+        let line = line.trim();
+        match line {
+            "q" | "Q" => return Ok(None),
+            "" if !matches.is_empty() => return Ok(Some(matches[0])),
+            "" => query.clear(),
+            _ => match line.parse::<usize>() {
+                Ok(n) if (1..=matches.len().min(SHOWN_MATCHES)).contains(&n) => {
+                    return Ok(Some(matches[n - 1]));
+                }
+                _ => query = line.to_string(),
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item(seq: u32, title: &str) -> PickItem {
+        PickItem {
+            sequence_number: seq,
+            entity_type: "task".to_string(),
+            title: title.to_string(),
+            detail: None,
+        }
+    }
+
+    #[test]
+    fn test_fuzzy_score() {
+        assert!(fuzzy_score("pg", "Use PostgreSQL").is_some());
+        assert!(fuzzy_score("gp", "Use PostgreSQL").is_none());
+        assert_eq!(fuzzy_score("", "anything"), Some(0));
+        assert!(
+            fuzzy_score("authm", "Auth middleware").unwrap()
+                > fuzzy_score("authm", "Add authentication to the admin").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_filter_orders_best_first() {
+        let items = vec![
+            item(1, "Add authentication to the admin"),
+            item(2, "Auth middleware"),
+            item(3, "Write docs"),
+        ];
+        let found: Vec<u32> = filter(&items, "authm")
+            .iter()
+            .map(|i| i.sequence_number)
+            .collect();
+        assert_eq!(found, vec![2, 1]);
+
+        // A sequence number picks that entity first
+        assert_eq!(filter(&items, "3")[0].sequence_number, 3);
+
+        // No query lists everything, newest first
+        let all: Vec<u32> = filter(&items, "")
+            .iter()
+            .map(|i| i.sequence_number)
+            .collect();
+        assert_eq!(all, vec![3, 2, 1]);
+    }
+}
@@ -1,14 +1,41 @@
 mod commands;
 mod handlers;
+mod interactive;
+mod output;
 
 pub use commands::{
-    AddCommand, AddEntity, CacheAction, CacheCommand, Cli, Commands, HookAction, HookCommand,
-    RelationAction, RelationCommand, TasksAction, TasksCommand,
+    AddCommand, AddEntity, BulkAction, BulkCommand, CacheAction, CacheCommand, Cli, Commands,
+    DaemonAction, EmbeddingsAction, EmbeddingsCommand, GitAction, GitCommand, GraphAction,
+    GraphCommand, HookAction, HookCommand, ImportAction, ImportCommand, LinksAction, LinksCommand,
+    MilestonesAction, MilestonesCommand, PromptAction, PromptCommand, QuestionsAction,
+    QuestionsCommand, RelationAction, RelationCommand, RelationTypeAction, RelationTypeCommand,
+    RulesAction, RulesCommand, SchemaAction, SchemaCommand, SnapshotAction, SyncAction,
+    SyncCommand, TasksAction, TasksCommand, TemplateAction, TemplateCommand, TrashAction,
+    TrashCommand, WorkspaceAction, WorkspaceCommand,
 };
 pub use handlers::{
-    handle_add_component, handle_add_decision, handle_add_link, handle_add_note, handle_add_prompt,
-    handle_add_task, handle_cache_rebuild, handle_cache_stats, handle_delete, handle_get,
-    handle_hook_install, handle_hook_status, handle_hook_uninstall, handle_init, handle_list,
-    handle_relation_add, handle_relation_delete, handle_relation_list, handle_search, handle_serve,
-    handle_snapshot, handle_tasks_blocked, handle_tasks_next, handle_tasks_ready, handle_update,
+    check_duplicates, handle_add_component, handle_add_decision, handle_add_link,
+    handle_add_milestone, handle_add_note, handle_add_prompt, handle_add_question, handle_add_task,
+    handle_alias, handle_archive, handle_attach, handle_bulk_tag, handle_cache_rebuild,
+    handle_cache_rebuild_fts, handle_cache_stats, handle_collection, handle_compact,
+    handle_completions, handle_daemon_start, handle_daemon_status, handle_daemon_stop,
+    handle_dedupe, handle_delete, handle_digest, handle_doctor, handle_embeddings_backfill,
+    handle_export, handle_get, handle_git_commits, handle_git_link, handle_git_scan,
+    handle_graph_cycles, handle_graph_export, handle_graph_stats, handle_history,
+    handle_hook_install, handle_hook_status, handle_hook_uninstall, handle_import_adr,
+    handle_import_csv, handle_import_github, handle_import_jsonl, handle_init, handle_links_check,
+    handle_list, handle_merge, handle_merge_report, handle_milestones_status, handle_prompt_render,
+    handle_questions_answer, handle_questions_list, handle_relation_add, handle_relation_delete,
+    handle_relation_import, handle_relation_infer, handle_relation_list, handle_relation_type_add,
+    handle_relation_type_list, handle_relation_type_remove, handle_resequence, handle_rules_list,
+    handle_rules_test, handle_schema_dump, handle_search, handle_search_all_projects,
+    handle_selftest, handle_serve, handle_snapshot, handle_snapshot_diff, handle_snapshot_list,
+    handle_status, handle_sync_obsidian, handle_sync_replica, handle_tasks_blocked,
+    handle_tasks_due, handle_tasks_mine, handle_tasks_next, handle_tasks_overdue,
+    handle_tasks_ready, handle_tasks_tree, handle_tasks_wip, handle_tasks_workload,
+    handle_template_add, handle_template_apply, handle_template_delete, handle_template_list,
+    handle_trash_list, handle_trash_purge, handle_trash_restore, handle_unarchive, handle_update,
+    handle_watch, handle_workspace_add, handle_workspace_list, handle_workspace_remove,
+    resolve_id_arg,
 };
+pub use output::OutputFormat;
@@ -0,0 +1,151 @@
+//! Output formats for commands that print lists of entities.
+//!
+//! Besides the human-readable listing and JSON, list-style commands can print
+//! their results as a table: CSV for spreadsheets, or a Markdown table for
+//! pasting into issues and pull request comments.
+
+use clap::ValueEnum;
+
+use crate::error::Result;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum OutputFormat {
+    /// Human-readable listing
+    #[default]
+    Plain,
+    /// Pretty-printed JSON
+    Json,
+    /// Comma-separated values with a header row
+    Csv,
+    /// Markdown table
+    Md,
+}
+
+impl OutputFormat {
+    /// Combine `--format` with the `--json` flag, which takes precedence.
+    pub fn resolve(format: Option<OutputFormat>, json: bool) -> Self {
+        if json {
+            OutputFormat::Json
+        } else {
+            format.unwrap_or_default()
+        }
+    }
+
+    /// Whether results are printed through a `Table`.
+    pub fn is_table(self) -> bool {
+        matches!(self, OutputFormat::Csv | OutputFormat::Md)
+    }
+}
+
+/// Rows of plain-text cells under a fixed set of column headers.
+#[derive(Debug, Clone)]
+pub struct Table {
+    headers: Vec<&'static str>,
+    rows: Vec<Vec<String>>,
+}
+
+impl Table {
+    pub fn new(headers: &[&'static str]) -> Self {
+        Self {
+            headers: headers.to_vec(),
+            rows: Vec::new(),
+        }
+    }
+
+    /// Append a row, padding or truncating it to the number of headers.
+    pub fn push(&mut self, mut row: Vec<String>) {
+        row.resize(self.headers.len(), String::new());
+        self.rows.push(row);
+    }
+
+    pub fn to_csv(&self) -> Result<String> {
+        let mut writer = csv::Writer::from_writer(Vec::new());
+        writer.write_record(&self.headers)?;
+        for row in &self.rows {
+            writer.write_record(row)?;
+        }
+        let bytes = writer
+            .into_inner()
+            .map_err(|e| std::io::Error::other(e.to_string()))?;
+        Ok(String::from_utf8_lossy(&bytes).into_owned())
+    }
+
+    pub fn to_markdown(&self) -> String {
+        let mut out = format!("| {} |\n", self.headers.join(" | "));
+        out.push_str(&format!(
+            "|{}\n",
+            self.headers.iter().map(|_| " --- |").collect::<String>()
+        ));
+        for row in &self.rows {
+            let cells: Vec<String> = row.iter().map(|c| markdown_cell(c)).collect();
+            out.push_str(&format!("| {} |\n", cells.join(" | ")));
+        }
+        out
+    }
+
+    /// Print as CSV, or as a Markdown table for any other format.
+    pub fn print(&self, format: OutputFormat) -> Result<()> {
+        match format {
+            OutputFormat::Csv => print!("{}", self.to_csv()?),
+            _ => print!("{}", self.to_markdown()),
+        }
+        Ok(())
+    }
+}
+
+/// Escape a cell so it stays on one line and inside its column.
+fn markdown_cell(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('|', "\\|")
+        .replace("\r\n", " ")
+        .replace(['\n', '\r'], " ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample() -> Table {
+        let mut table = Table::new(&["seq", "title", "tags"]);
+        table.push(vec![
+            "1".to_string(),
+            "Use \"Postgres\", not MySQL".to_string(),
+            "db".to_string(),
+        ]);
+        table.push(vec!["2".to_string(), "a | b\nc".to_string()]);
+        table
+    }
+
+    #[test]
+    fn test_resolve_format() {
+        assert_eq!(OutputFormat::resolve(None, false), OutputFormat::Plain);
+        assert_eq!(
+            OutputFormat::resolve(Some(OutputFormat::Csv), false),
+            OutputFormat::Csv
+        );
+        assert_eq!(
+            OutputFormat::resolve(Some(OutputFormat::Md), true),
+            OutputFormat::Json
+        );
+    }
+
+    #[test]
+    fn test_table_to_csv_quotes_cells() {
+        assert_eq!(
+            sample().to_csv().unwrap(),
+            "seq,title,tags\n1,\"Use \"\"Postgres\"\", not MySQL\",db\n2,\"a | b\nc\",\n"
+        );
+    }
+
+    #[test]
+    fn test_table_to_markdown_escapes_cells() {
+        assert_eq!(
+            sample().to_markdown(),
+            "| seq | title | tags |\n\
+             | --- | --- | --- |\n\
+             | 1 | Use \"Postgres\", not MySQL | db |\n\
+             | 2 | a \\| b c |  |\n"
+        );
+    }
+}
@@ -0,0 +1,748 @@
+//! Typed library API for embedding medulla in other Rust programs.
+//!
+//! [`Client`] opens a project's Loro store, SQLite cache and config together
+//! and keeps the cache indexed as entities are written, so callers get the
+//! same behaviour as the CLI and MCP server without repeating their glue:
+//!
+//! ```no_run
+//! use medulla::entity::{Task, TaskPriority};
+//! use medulla::Client;
+//!
+//! let client = Client::open(".")?;
+//! let mut task = Task::new("Document the client".to_string(), 0);
+//! task.priority = TaskPriority::High;
+//! let task = client.create(task)?;
+//!
+//! let open: Vec<Task> = client.list::<Task>().status("todo").limit(20).fetch()?;
+//! let hits = client.search("client").entity_type("task").fetch()?;
+//! let next = client.ready_tasks().limit(3).fetch()?;
+//! # Ok::<(), medulla::MedullaError>(())
+//! ```
+
+use std::path::Path;
+
+use uuid::Uuid;
+
+use crate::cache::{BlockedTask, EntityPageQuery, ReadyTask, SemanticSearchResult, SqliteCache};
+use crate::config::{ProjectConfig, RuleSubject};
+use crate::embeddings::Embedder;
+use crate::entity::{
+    Component, Decision, EntityBase, Link, Milestone, Note, Prompt, Question, Task,
+};
+use crate::error::{MedullaError, Result};
+use crate::search::SearchFilter;
+use crate::storage::{
+    ComponentUpdate, DecisionUpdate, LinkUpdate, LoroStore, MilestoneUpdate, NoteUpdate,
+    PromptUpdate, QuestionUpdate, TaskUpdate,
+};
+
+/// An entity of any type
+#[derive(Debug, Clone)]
+pub enum Entity {
+    Decision(Decision),
+    Task(Task),
+    Note(Note),
+    Prompt(Prompt),
+    Component(Component),
+    Link(Link),
+    Question(Question),
+    Milestone(Milestone),
+}
+
+impl Entity {
+    /// Fields shared by every entity type
+    pub fn base(&self) -> &EntityBase {
+        match self {
+            Entity::Decision(e) => &e.base,
+            Entity::Task(e) => &e.base,
+            Entity::Note(e) => &e.base,
+            Entity::Prompt(e) => &e.base,
+            Entity::Component(e) => &e.base,
+            Entity::Link(e) => &e.base,
+            Entity::Question(e) => &e.base,
+            Entity::Milestone(e) => &e.base,
+        }
+    }
+
+    /// Type name, as used by the CLI and MCP tools
+    pub fn entity_type(&self) -> &'static str {
+        match self {
+            Entity::Decision(_) => Decision::TYPE,
+            Entity::Task(_) => Task::TYPE,
+            Entity::Note(_) => Note::TYPE,
+            Entity::Prompt(_) => Prompt::TYPE,
+            Entity::Component(_) => Component::TYPE,
+            Entity::Link(_) => Link::TYPE,
+            Entity::Question(_) => Question::TYPE,
+            Entity::Milestone(_) => Milestone::TYPE,
+        }
+    }
+
+    /// Load an entity of a known type by UUID
+    fn load(store: &LoroStore, entity_type: &str, id: &Uuid) -> Result<Option<Self>> {
+        Ok(match entity_type {
+            "decision" => store.get_decision(id)?.map(Entity::Decision),
+            "task" => store.get_task(id)?.map(Entity::Task),
+            "note" => store.get_note(id)?.map(Entity::Note),
+            "prompt" => store.get_prompt(id)?.map(Entity::Prompt),
+            "component" => store.get_component(id)?.map(Entity::Component),
+            "link" => store.get_link(id)?.map(Entity::Link),
+            "question" => store.get_question(id)?.map(Entity::Question),
+            "milestone" => store.get_milestone(id)?.map(Entity::Milestone),
+            _ => None,
+        })
+    }
+}
+
+/// An entity type the client can create, fetch, update and list.
+pub trait TypedEntity: Clone + Sized + serde::Serialize {
+    /// Type name, as used by the CLI and MCP tools
+    const TYPE: &'static str;
+    /// Partial update accepted by [`Client::update`]
+    type Update: Default;
+
+    fn base(&self) -> &EntityBase;
+    fn base_mut(&mut self) -> &mut EntityBase;
+    fn get(store: &LoroStore, id: &Uuid) -> Result<Option<Self>>;
+    fn add(store: &LoroStore, entity: &Self) -> Result<()>;
+    fn apply(store: &LoroStore, id: &Uuid, update: Self::Update) -> Result<()>;
+    fn index(cache: &SqliteCache, entity: &Self) -> Result<()>;
+    fn unindex(cache: &SqliteCache, id: &str) -> Result<()>;
+}
+
+macro_rules! typed_entity {
+    ($ty:ty, $name:literal, $update:ty, $get:ident, $add:ident, $apply:ident, $index:ident, $remove:ident) => {
+        impl TypedEntity for $ty {
+            const TYPE: &'static str = $name;
+            type Update = $update;
+
+            fn base(&self) -> &EntityBase {
+                &self.base
+            }
+
+            fn base_mut(&mut self) -> &mut EntityBase {
+                &mut self.base
+            }
+
+            fn get(store: &LoroStore, id: &Uuid) -> Result<Option<Self>> {
+                store.$get(id)
+            }
+
+            fn add(store: &LoroStore, entity: &Self) -> Result<()> {
+                store.$add(entity)
+            }
+
+            fn apply(store: &LoroStore, id: &Uuid, update: Self::Update) -> Result<()> {
+                store.$apply(id, update)
+            }
+
+            fn index(cache: &SqliteCache, entity: &Self) -> Result<()> {
+                cache.$index(entity)
+            }
+
+            fn unindex(cache: &SqliteCache, id: &str) -> Result<()> {
+                cache.$remove(id)
+            }
+        }
+    };
+}
+
+typed_entity!(
+    Decision,
+    "decision",
+    DecisionUpdate,
+    get_decision,
+    add_decision,
+    update_decision,
+    index_decision,
+    remove_decision
+);
+typed_entity!(
+    Task,
+    "task",
+    TaskUpdate,
+    get_task,
+    add_task,
+    update_task,
+    index_task,
+    remove_task
+);
+typed_entity!(
+    Note,
+    "note",
+    NoteUpdate,
+    get_note,
+    add_note,
+    update_note,
+    index_note,
+    remove_note
+);
+typed_entity!(
+    Prompt,
+    "prompt",
+    PromptUpdate,
+    get_prompt,
+    add_prompt,
+    update_prompt,
+    index_prompt,
+    remove_prompt
+);
+typed_entity!(
+    Component,
+    "component",
+    ComponentUpdate,
+    get_component,
+    add_component,
+    update_component,
+    index_component,
+    remove_component
+);
+typed_entity!(
+    Link,
+    "link",
+    LinkUpdate,
+    get_link,
+    add_link,
+    update_link,
+    index_link,
+    remove_link
+);
+typed_entity!(
+    Question,
+    "question",
+    QuestionUpdate,
+    get_question,
+    add_question,
+    update_question,
+    index_question,
+    remove_question
+);
+typed_entity!(
+    Milestone,
+    "milestone",
+    MilestoneUpdate,
+    get_milestone,
+    add_milestone,
+    update_milestone,
+    index_milestone,
+    remove_milestone
+);
+
+/// A medulla project opened for reading and writing.
+pub struct Client {
+    store: LoroStore,
+    cache: SqliteCache,
+    config: ProjectConfig,
+    embed_on_write: bool,
+}
+
+impl Client {
+    /// Open the project at `root` (the directory holding `.medulla/`),
+    /// bringing its cache up to date with the store.
+    pub fn open(root: impl AsRef<Path>) -> Result<Self> {
+        Self::from_store(LoroStore::open(root.as_ref())?)
+    }
+
+    /// Create a new project at `root`.
+    pub fn init(root: impl AsRef<Path>) -> Result<Self> {
+        Self::from_store(LoroStore::init(root.as_ref())?)
+    }
+
+    fn from_store(store: LoroStore) -> Result<Self> {
+        let cache = SqliteCache::open(store.medulla_dir())?;
+        store.sync_cache(&cache)?;
+        let config = ProjectConfig::load(store.medulla_dir())?;
+        cache.set_inverse_relations(config.materialize_inverse_relations)?;
+        cache.set_task_scoring(&config.task_scoring)?;
+        Ok(Self {
+            store,
+            cache,
+            config,
+            embed_on_write: false,
+        })
+    }
+
+    /// Attribute writes to `author` (recorded as `created_by`/`updated_by`).
+    pub fn with_author(mut self, author: Option<String>) -> Self {
+        self.store = self.store.with_author(author);
+        self
+    }
+
+    /// Compute embeddings for entities as they are written, so they can be
+    /// found by [`Client::search_semantic`] straight away. Loads the
+    /// embedding model on the first write.
+    pub fn with_embeddings(mut self) -> Self {
+        self.embed_on_write = true;
+        self
+    }
+
+    pub fn store(&self) -> &LoroStore {
+        &self.store
+    }
+
+    pub fn cache(&self) -> &SqliteCache {
+        &self.cache
+    }
+
+    pub fn config(&self) -> &ProjectConfig {
+        &self.config
+    }
+
+    /// The store, cache and config, for handing to a `MedullaServer`
+    pub fn into_parts(self) -> (LoroStore, SqliteCache, ProjectConfig) {
+        (self.store, self.cache, self.config)
+    }
+
+    /// Add an entity to the store. It is given the next sequence number
+    /// (whatever it was built with), unless already set the client's
+    /// author, and the tags of the config's auto-tagging. Returns the
+    /// entity as stored.
+    pub fn create<E: TypedEntity>(&self, mut entity: E) -> Result<E> {
+        let sequence_number = self.store.next_sequence_number();
+        let author = self.store.author().map(str::to_string);
+        let value = serde_json::to_value(&entity)?;
+        let auto_tags = self.config.auto_tags(&RuleSubject {
+            entity_type: E::TYPE,
+            title: &entity.base().title,
+            content: entity.base().content.as_deref(),
+            tags: &entity.base().tags,
+            properties: &value,
+        });
+
+        let base = entity.base_mut();
+        base.sequence_number = sequence_number;
+        if base.created_by.is_none() {
+            base.created_by = author;
+        }
+        base.tags.extend(auto_tags);
+
+        E::add(&self.store, &entity)?;
+        self.store.save()?;
+        E::index(&self.cache, &entity)?;
+        self.embed(E::TYPE, entity.base());
+        Ok(entity)
+    }
+
+    /// Fetch an entity of type `E` by sequence number or UUID prefix.
+    pub fn get<E: TypedEntity>(&self, id: &str) -> Result<E> {
+        let not_found = || MedullaError::EntityNotFound(id.to_string());
+        let (uuid, _) = self
+            .store
+            .lookup_id(id, Some(E::TYPE))
+            .ok_or_else(not_found)?;
+        E::get(&self.store, &uuid)?.ok_or_else(not_found)
+    }
+
+    /// Fetch an entity of any type by sequence number or UUID prefix.
+    pub fn get_any(&self, id: &str) -> Result<Entity> {
+        let not_found = || MedullaError::EntityNotFound(id.to_string());
+        let (uuid, entity_type) = self.store.lookup_id(id, None).ok_or_else(not_found)?;
+        Entity::load(&self.store, entity_type, &uuid)?.ok_or_else(not_found)
+    }
+
+    /// Apply a partial update to an entity of type `E`. Returns the entity
+    /// as updated.
+    pub fn update<E: TypedEntity>(&self, id: &str, update: E::Update) -> Result<E> {
+        let uuid = self.get::<E>(id)?.base().id;
+        E::apply(&self.store, &uuid, update)?;
+        self.store.save()?;
+
+        let updated = E::get(&self.store, &uuid)?
+            .ok_or_else(|| MedullaError::EntityNotFound(id.to_string()))?;
+        E::index(&self.cache, &updated)?;
+        self.embed(E::TYPE, updated.base());
+        Ok(updated)
+    }
+
+    /// Move an entity, and its relations, to the trash. Returns the entity
+    /// as it was.
+    pub fn delete(&self, id: &str) -> Result<Entity> {
+        let entity = self.get_any(id)?;
+        let uuid = entity.base().id;
+        let relation_keys: Vec<String> = self
+            .store
+            .list_relations()?
+            .into_iter()
+            .filter(|r| r.source_id == uuid || r.target_id == uuid)
+            .map(|r| r.composite_key())
+            .collect();
+
+        self.store.trash_entity(&uuid, self.store.author())?;
+        self.store.save()?;
+
+        let id_str = uuid.to_string();
+        match &entity {
+            Entity::Decision(_) => Decision::unindex(&self.cache, &id_str)?,
+            Entity::Task(_) => Task::unindex(&self.cache, &id_str)?,
+            Entity::Note(_) => Note::unindex(&self.cache, &id_str)?,
+            Entity::Prompt(_) => Prompt::unindex(&self.cache, &id_str)?,
+            Entity::Component(_) => Component::unindex(&self.cache, &id_str)?,
+            Entity::Link(_) => Link::unindex(&self.cache, &id_str)?,
+            Entity::Question(_) => Question::unindex(&self.cache, &id_str)?,
+            Entity::Milestone(_) => Milestone::unindex(&self.cache, &id_str)?,
+        }
+        for key in relation_keys {
+            self.cache.remove_relation(&key)?;
+        }
+        self.cache.delete_embedding(&id_str)?;
+        Ok(entity)
+    }
+
+    /// Entities of type `E`, in sequence order
+    pub fn list<E: TypedEntity>(&self) -> ListQuery<'_, E> {
+        ListQuery {
+            client: self,
+            status: None,
+            tag: None,
+            offset: 0,
+            limit: usize::MAX,
+            _entity: std::marker::PhantomData,
+        }
+    }
+
+    /// Full-text search over every entity type. An empty query matches
+    /// everything the filters allow.
+    pub fn search(&self, query: &str) -> SearchQuery<'_> {
+        SearchQuery {
+            client: self,
+            query: query.to_string(),
+            filter: SearchFilter::default(),
+            limit: None,
+        }
+    }
+
+    /// Search by embedding similarity to `query`. Only entities with an
+    /// embedding are found (see [`Client::with_embeddings`] and
+    /// `medulla embeddings backfill`).
+    pub fn search_semantic(&self, query: &str) -> SemanticQuery<'_> {
+        SemanticQuery {
+            client: self,
+            query: query.to_string(),
+            entity_type: None,
+            limit: 10,
+            threshold: 0.0,
+        }
+    }
+
+    /// Tasks with no open blockers, highest score first
+    pub fn ready_tasks(&self) -> ReadyQuery<'_> {
+        ReadyQuery {
+            client: self,
+            limit: None,
+            include_archived: false,
+            respect_wip_limits: false,
+        }
+    }
+
+    /// Tasks waiting on open blockers, with the tasks blocking them
+    pub fn blocked_tasks(&self, limit: Option<u32>) -> Result<Vec<BlockedTask>> {
+        self.cache.get_blocked_tasks(limit)
+    }
+
+    /// The ready task with the highest score
+    pub fn next_task(&self) -> Result<Option<ReadyTask>> {
+        self.cache.get_next_task()
+    }
+
+    /// Store an embedding for an entity just written, when enabled. Failures
+    /// are logged; a write never fails because its embedding could not be
+    /// computed.
+    fn embed(&self, entity_type: &str, base: &EntityBase) {
+        if !self.embed_on_write {
+            return;
+        }
+        let result = Embedder::new().and_then(|embedder| {
+            self.cache.compute_and_store_embedding_if_changed(
+                &base.id.to_string(),
+                entity_type,
+                &base.title,
+                base.content.as_deref(),
+                &base.tags,
+                &embedder,
+            )
+        });
+        if let Err(e) = result {
+            tracing::warn!(
+                "Failed to compute embedding for {} {}: {}",
+                entity_type,
+                base.id,
+                e
+            );
+        }
+    }
+}
+
+/// Builder for [`Client::list`]
+pub struct ListQuery<'a, E> {
+    client: &'a Client,
+    status: Option<String>,
+    tag: Option<String>,
+    offset: usize,
+    limit: usize,
+    _entity: std::marker::PhantomData<E>,
+}
+
+impl<E: TypedEntity> ListQuery<'_, E> {
+    /// Only entities with this status (decisions, tasks and components)
+    pub fn status(mut self, status: impl Into<String>) -> Self {
+        self.status = Some(status.into());
+        self
+    }
+
+    /// Only entities with this tag
+    pub fn tag(mut self, tag: impl Into<String>) -> Self {
+        self.tag = Some(tag.into());
+        self
+    }
+
+    pub fn offset(mut self, offset: usize) -> Self {
+        self.offset = offset;
+        self
+    }
+
+    pub fn limit(mut self, limit: usize) -> Self {
+        self.limit = limit;
+        self
+    }
+
+    /// Number of matching entities, ignoring offset and limit
+    pub fn count(&self) -> Result<usize> {
+        let (_, total) = self.client.cache.list_entity_page(&self.page(0))?;
+        Ok(total)
+    }
+
+    pub fn fetch(self) -> Result<Vec<E>> {
+        let (rows, _) = self.client.cache.list_entity_page(&self.page(self.limit))?;
+        let mut entities = Vec::with_capacity(rows.len());
+        for (id, _, _) in rows {
+            let Ok(uuid) = Uuid::parse_str(&id) else {
+                continue;
+            };
+            if let Some(entity) = E::get(&self.client.store, &uuid)? {
+                entities.push(entity);
+            }
+        }
+        Ok(entities)
+    }
+
+    fn page(&self, limit: usize) -> EntityPageQuery<'_> {
+        EntityPageQuery {
+            entity_type: Some(E::TYPE),
+            status: self.status.as_deref(),
+            tag: self.tag.as_deref(),
+            offset: self.offset,
+            // SQLite takes a signed limit
+            limit: limit.min(i64::MAX as usize),
+            ..Default::default()
+        }
+    }
+}
+
+/// Builder for [`Client::search`]
+pub struct SearchQuery<'a> {
+    client: &'a Client,
+    query: String,
+    filter: SearchFilter,
+    limit: Option<usize>,
+}
+
+impl SearchQuery<'_> {
+    pub fn entity_type(mut self, entity_type: impl Into<String>) -> Self {
+        self.filter.entity_type = Some(entity_type.into());
+        self
+    }
+
+    pub fn status(mut self, status: impl Into<String>) -> Self {
+        self.filter.status = Some(status.into());
+        self
+    }
+
+    /// Require a tag; may be given more than once
+    pub fn tag(mut self, tag: impl Into<String>) -> Self {
+        self.filter.tags.push(tag.into());
+        self
+    }
+
+    /// Only entities created by this author
+    pub fn created_by(mut self, author: impl Into<String>) -> Self {
+        self.filter.created_by = Some(author.into());
+        self
+    }
+
+    /// Only tasks assigned to this person
+    pub fn assignee(mut self, assignee: impl Into<String>) -> Self {
+        self.filter.assignee = Some(assignee.into());
+        self
+    }
+
+    pub fn limit(mut self, limit: usize) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+
+    /// Matching unarchived entities, in type then sequence order
+    pub fn fetch(self) -> Result<Vec<Entity>> {
+        let store = &self.client.store;
+        let mut entities = Vec::new();
+        for (id, entity_type) in self.client.cache.find_entities(&self.query, &self.filter)? {
+            if self.limit.is_some_and(|limit| entities.len() >= limit) {
+                break;
+            }
+            let Ok(uuid) = Uuid::parse_str(&id) else {
+                continue;
+            };
+            if let Some(entity) = Entity::load(store, &entity_type, &uuid)? {
+                entities.push(entity);
+            }
+        }
+        Ok(entities)
+    }
+}
+
+/// Builder for [`Client::search_semantic`]
+pub struct SemanticQuery<'a> {
+    client: &'a Client,
+    query: String,
+    entity_type: Option<String>,
+    limit: usize,
+    threshold: f32,
+}
+
+impl SemanticQuery<'_> {
+    pub fn entity_type(mut self, entity_type: impl Into<String>) -> Self {
+        self.entity_type = Some(entity_type.into());
+        self
+    }
+
+    pub fn limit(mut self, limit: usize) -> Self {
+        self.limit = limit;
+        self
+    }
+
+    /// Minimum similarity score, from 0 to 1
+    pub fn threshold(mut self, threshold: f32) -> Self {
+        self.threshold = threshold;
+        self
+    }
+
+    /// Most similar entities first. Loads the embedding model.
+    pub fn fetch(self) -> Result<Vec<SemanticSearchResult>> {
+        let embedding = Embedder::new()?.embed(&self.query)?;
+        self.client.cache.search_semantic(
+            &embedding,
+            self.entity_type.as_deref(),
+            self.limit,
+            self.threshold,
+        )
+    }
+}
+
+/// Builder for [`Client::ready_tasks`]
+pub struct ReadyQuery<'a> {
+    client: &'a Client,
+    limit: Option<u32>,
+    include_archived: bool,
+    respect_wip_limits: bool,
+}
+
+impl ReadyQuery<'_> {
+    pub fn limit(mut self, limit: u32) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+
+    pub fn include_archived(mut self, include_archived: bool) -> Self {
+        self.include_archived = include_archived;
+        self
+    }
+
+    /// Skip tasks whose assignee already holds as many in-progress tasks as
+    /// `wip_limits` in the project config allows
+    pub fn respect_wip_limits(mut self, respect: bool) -> Self {
+        self.respect_wip_limits = respect;
+        self
+    }
+
+    pub fn fetch(self) -> Result<Vec<ReadyTask>> {
+        let wip_limits = self
+            .respect_wip_limits
+            .then_some(&self.client.config.wip_limits);
+        self.client
+            .cache
+            .get_ready_tasks_with(self.limit, self.include_archived, wip_limits)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::entity::{RelationType, TaskPriority, TaskStatus};
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_crud_and_queries() {
+        let tmp = TempDir::new().unwrap();
+        let client = Client::init(tmp.path())
+            .unwrap()
+            .with_author(Some("alice".to_string()));
+
+        let mut task = Task::new("Write the client".to_string(), 0);
+        task.priority = TaskPriority::High;
+        let task = client.create(task).unwrap();
+        assert_eq!(task.base.sequence_number, 1);
+        assert_eq!(task.base.created_by.as_deref(), Some("alice"));
+
+        let blocker = client
+            .create(Task::new("Design the client".to_string(), 0))
+            .unwrap();
+        client
+            .store()
+            .add_relation(&crate::entity::Relation::new(
+                blocker.base.id,
+                "task".to_string(),
+                task.base.id,
+                "task".to_string(),
+                RelationType::Blocks,
+            ))
+            .unwrap();
+        client.store().sync_cache(client.cache()).unwrap();
+
+        let note = client
+            .create(Note::new("Client API notes".to_string(), 0))
+            .unwrap();
+        assert_eq!(client.get::<Note>("3").unwrap().base.id, note.base.id);
+        assert!(client.get::<Task>("3").is_err());
+        assert_eq!(client.get_any("3").unwrap().entity_type(), "note");
+
+        let updated: Task = client
+            .update::<Task>(
+                "1",
+                TaskUpdate {
+                    status: Some(TaskStatus::InProgress),
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        assert_eq!(updated.status, TaskStatus::InProgress);
+
+        let query = client.list::<Task>();
+        assert_eq!(query.count().unwrap(), 2);
+        let in_progress = client.list::<Task>().status("in_progress").fetch().unwrap();
+        assert_eq!(in_progress.len(), 1);
+        assert_eq!(in_progress[0].base.id, task.base.id);
+
+        let hits = client.search("client").entity_type("task").fetch().unwrap();
+        assert_eq!(hits.len(), 2);
+        assert_eq!(client.search("client").limit(1).fetch().unwrap().len(), 1);
+
+        let ready = client.ready_tasks().fetch().unwrap();
+        assert_eq!(ready.len(), 1);
+        assert_eq!(ready[0].id, blocker.base.id.to_string());
+        assert_eq!(client.blocked_tasks(None).unwrap().len(), 1);
+
+        client.delete("2").unwrap();
+        assert!(client.get_any("2").is_err());
+        assert!(client.blocked_tasks(None).unwrap().is_empty());
+        assert_eq!(client.store().list_trash().unwrap().len(), 1);
+    }
+}
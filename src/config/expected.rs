@@ -0,0 +1,159 @@
+//! Expected relation rules checked by graph linting.
+//!
+//! Unlike validation rules these never block a write; entities missing an
+//! expected relation are reported as warnings.
+
+use serde::{Deserialize, Serialize};
+
+use super::RelationTypeDef;
+use crate::entity::{Relation, RelationType};
+use crate::error::{MedullaError, Result};
+
+/// Entity types a rule may name.
+const ENTITY_TYPES: [&str; 8] = [
+    "decision",
+    "task",
+    "note",
+    "prompt",
+    "component",
+    "link",
+    "question",
+    "milestone",
+];
+
+/// Which end of the relation the entity must be on.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RelationDirection {
+    /// The entity is the source (`component implements decision`)
+    #[default]
+    Outgoing,
+    /// The entity is the target (`decision` implemented by something)
+    Incoming,
+}
+
+/// An entity type that is expected to have at least one relation of a kind.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExpectedRelation {
+    /// Entity type the rule applies to
+    #[serde(rename = "type")]
+    pub entity_type: String,
+    /// Relation type the entity should have
+    pub relation: String,
+    #[serde(default)]
+    pub direction: RelationDirection,
+    /// Entity type expected on the other end (any type if omitted)
+    #[serde(default)]
+    pub other_type: Option<String>,
+    /// Custom message reported for entities missing the relation
+    #[serde(default)]
+    pub message: Option<String>,
+}
+
+impl ExpectedRelation {
+    /// Check that the rule names known entity and relation types, including
+    /// the project's custom relation types.
+    pub fn check(&self, relation_types: &[RelationTypeDef]) -> Result<()> {
+        for entity_type in std::iter::once(&self.entity_type).chain(self.other_type.as_ref()) {
+            if !ENTITY_TYPES.contains(&entity_type.as_str()) {
+                return Err(MedullaError::Config(format!(
+                    "Unknown entity type '{}' in expected_relations",
+                    entity_type
+                )));
+            }
+        }
+        let relation_type = self.relation_type();
+        if !relation_type.is_builtin()
+            && !relation_types
+                .iter()
+                .any(|def| relation_type == RelationType::Custom(def.name.clone()))
+        {
+            return Err(MedullaError::Config(format!(
+                "Unknown relation type: {} in expected_relations",
+                self.relation
+            )));
+        }
+        Ok(())
+    }
+
+    fn relation_type(&self) -> RelationType {
+        self.relation
+            .parse()
+            .unwrap_or_else(|_| RelationType::Custom(self.relation.trim().to_lowercase()))
+    }
+
+    /// Whether a relation satisfies the rule for the given entity.
+    pub fn is_satisfied_by(&self, entity_id: &uuid::Uuid, relation: &Relation) -> bool {
+        if relation.relation_type != self.relation_type() {
+            return false;
+        }
+
+        let (own_id, other_type) = match self.direction {
+            RelationDirection::Outgoing => (&relation.source_id, &relation.target_type),
+            RelationDirection::Incoming => (&relation.target_id, &relation.source_type),
+        };
+        own_id == entity_id
+            && self
+                .other_type
+                .as_ref()
+                .map_or(true, |expected| expected == other_type)
+    }
+
+    /// The warning reported for an entity missing the relation.
+    pub fn warning(&self) -> String {
+        if let Some(ref message) = self.message {
+            return message.clone();
+        }
+        let other = self.other_type.as_deref().unwrap_or("entity");
+        match self.direction {
+            RelationDirection::Outgoing => format!(
+                "{} has no '{}' relation to a {}",
+                self.entity_type, self.relation, other
+            ),
+            RelationDirection::Incoming => format!(
+                "{} has no incoming '{}' relation from a {}",
+                self.entity_type, self.relation, other
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_expected_relation_matching() {
+        let rule: ExpectedRelation =
+            serde_yaml::from_str("type: component\nrelation: implements\nother_type: decision\n")
+                .unwrap();
+        assert!(rule.check(&[]).is_ok());
+        assert_eq!(rule.direction, RelationDirection::Outgoing);
+
+        let component = uuid::Uuid::new_v4();
+        let relation = Relation::new(
+            component,
+            "component".to_string(),
+            uuid::Uuid::new_v4(),
+            "decision".to_string(),
+            RelationType::Implements,
+        );
+        assert!(rule.is_satisfied_by(&component, &relation));
+
+        // Wrong end of the relation
+        assert!(!rule.is_satisfied_by(&relation.target_id, &relation));
+    }
+
+    #[test]
+    fn test_expected_relation_rejects_unknown_types() {
+        let rule: ExpectedRelation =
+            serde_yaml::from_str("type: component\nrelation: powers\n").unwrap();
+        assert!(rule.check(&[]).is_err());
+        let powers: RelationTypeDef = serde_yaml::from_str("name: powers\n").unwrap();
+        assert!(rule.check(&[powers]).is_ok());
+
+        let rule: ExpectedRelation =
+            serde_yaml::from_str("type: widget\nrelation: implements\n").unwrap();
+        assert!(rule.check(&[]).is_err());
+    }
+}
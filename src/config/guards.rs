@@ -0,0 +1,46 @@
+//! Request guards for `medulla serve`.
+//!
+//! These protect the store from runaway clients: a per-client rate limit on
+//! tool calls, a cap on request size, and a cap on how many store-mutating
+//! tool calls may run at once.
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::{MedullaError, Result};
+
+/// Limits applied to requests reaching the server.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Guards {
+    /// Tool calls each client may make per minute, with bursts up to the
+    /// same number (0 disables the limit)
+    pub requests_per_minute: u32,
+    /// Maximum size in bytes of a tool call's arguments or an HTTP request
+    /// body
+    pub max_payload_bytes: usize,
+    /// Store-mutating tool calls that may run at once; further calls fail
+    /// until one finishes (0 disables the cap)
+    pub max_concurrent_writes: usize,
+}
+
+impl Default for Guards {
+    fn default() -> Self {
+        Self {
+            requests_per_minute: 600,
+            max_payload_bytes: 4 * 1024 * 1024,
+            max_concurrent_writes: 4,
+        }
+    }
+}
+
+impl Guards {
+    /// Check that requests of some size are accepted.
+    pub fn check(&self) -> Result<()> {
+        if self.max_payload_bytes == 0 {
+            return Err(MedullaError::Config(
+                "guards.max_payload_bytes must be greater than 0".to_string(),
+            ));
+        }
+        Ok(())
+    }
+}
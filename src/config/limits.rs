@@ -0,0 +1,55 @@
+//! Per-project overrides of the MCP validation limits.
+//!
+//! Defaults are the constants in `mcp::error::validation`; a project can
+//! raise or lower them under `limits:` in its config.
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::{MedullaError, Result};
+use crate::mcp::error::validation;
+
+/// Size and count limits applied to tool input.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Limits {
+    /// Maximum size in bytes of a decision's context
+    pub max_context_size: usize,
+    /// Maximum size in bytes of a prompt template
+    pub max_template_size: usize,
+    /// Maximum operations per `entity_batch` call, or rows per
+    /// `entity_import_rows` call
+    pub max_batch_size: usize,
+    /// Maximum results a list or search returns per call
+    pub max_limit: usize,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Self {
+            max_context_size: validation::MAX_CONTEXT_SIZE,
+            max_template_size: validation::MAX_TEMPLATE_SIZE,
+            max_batch_size: validation::MAX_BATCH_SIZE,
+            max_limit: validation::MAX_LIMIT,
+        }
+    }
+}
+
+impl Limits {
+    /// Check that every limit allows at least one item or byte.
+    pub fn check(&self) -> Result<()> {
+        for (name, value) in [
+            ("max_context_size", self.max_context_size),
+            ("max_template_size", self.max_template_size),
+            ("max_batch_size", self.max_batch_size),
+            ("max_limit", self.max_limit),
+        ] {
+            if value == 0 {
+                return Err(MedullaError::Config(format!(
+                    "limits.{} must be greater than 0",
+                    name
+                )));
+            }
+        }
+        Ok(())
+    }
+}
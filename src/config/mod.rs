@@ -0,0 +1,502 @@
+//! Project configuration for Medulla.
+//!
+//! Configuration is read from `.medulla/config.yaml`. The file is optional;
+//! a missing file yields the default configuration.
+
+mod expected;
+mod guards;
+mod limits;
+mod relation_types;
+mod rules;
+mod scoring;
+mod tagging;
+mod wip;
+mod workflow;
+
+pub use expected::{ExpectedRelation, RelationDirection};
+pub use guards::Guards;
+pub use limits::Limits;
+pub use relation_types::RelationTypeDef;
+pub use rules::{RuleKind, RuleSubject, RuleViolation, ValidationRule};
+pub use scoring::{TaskScore, TaskScoring};
+pub use tagging::TaggingRule;
+pub use wip::WipLimits;
+pub use workflow::{canonical_status, Workflow};
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::entity::RelationType;
+use crate::error::{MedullaError, Result};
+
+/// Name of the config file inside the `.medulla` directory.
+pub const CONFIG_FILE: &str = "config.yaml";
+
+/// Value substituted for redacted fields.
+pub const REDACTED: &str = "[redacted]";
+
+/// Project-level configuration.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ProjectConfig {
+    /// Declarative validation rules applied when entities are created or updated.
+    #[serde(default)]
+    pub validation_rules: Vec<ValidationRule>,
+    /// Require enum values (status, priority, relation type) to be given in
+    /// their exact lowercase form instead of matching case-insensitively.
+    #[serde(default)]
+    pub strict_enums: bool,
+    /// Materialize the inverse of every relation in the cache, so cache
+    /// relation lookups see it from both ends. Loro still stores one relation.
+    #[serde(default)]
+    pub materialize_inverse_relations: bool,
+    /// Relations entities of a type are expected to have, reported as
+    /// warnings by `graph_lint`.
+    #[serde(default)]
+    pub expected_relations: Vec<ExpectedRelation>,
+    /// Project-defined relation types accepted alongside the built-in ones.
+    #[serde(default)]
+    pub relation_types: Vec<RelationTypeDef>,
+    /// Field names whose values are replaced with a placeholder in tool
+    /// responses, e.g. `assignee` or `created_by`.
+    #[serde(default)]
+    pub redact_fields: Vec<String>,
+    /// Overrides of the tool input limits (context and template size,
+    /// batch size, results per call).
+    #[serde(default)]
+    pub limits: Limits,
+    /// Weights of the score ordering `task_ready` and `task_next` (priority,
+    /// due-date proximity, age, and how many tasks each one unblocks).
+    #[serde(default)]
+    pub task_scoring: TaskScoring,
+    /// In-progress tasks each assignee may hold, which `task_ready` and
+    /// `task_next` can respect when picking work.
+    #[serde(default)]
+    pub wip_limits: WipLimits,
+    /// Allowed status transitions, one workflow per entity type. Types
+    /// without a workflow may move between any statuses.
+    #[serde(default)]
+    pub workflows: Vec<Workflow>,
+    /// Rate, payload size and write concurrency limits for `medulla serve`.
+    #[serde(default)]
+    pub guards: Guards,
+    /// Tags every new entity of a type gets, by type.
+    #[serde(default)]
+    pub default_tags: BTreeMap<String, Vec<String>>,
+    /// Tags added to new entities matching a rule, e.g. tasks with "bug"
+    /// in the title or links to github.com.
+    #[serde(default)]
+    pub tagging_rules: Vec<TaggingRule>,
+    /// Fetch the URL of every new link for its page title, description
+    /// and favicon, as `medulla add link --fetch` does.
+    #[serde(default)]
+    pub fetch_link_metadata: bool,
+    /// Ask the user, through MCP elicitation, before `entity_delete`,
+    /// `decision_supersede` and bulk changes run.
+    #[serde(default)]
+    pub require_confirmation: bool,
+}
+
+impl ProjectConfig {
+    /// Load the config from a `.medulla` directory.
+    /// Returns the default config if no config file exists.
+    pub fn load(medulla_dir: &Path) -> Result<Self> {
+        let path = medulla_dir.join(CONFIG_FILE);
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+
+        let text = fs::read_to_string(&path)?;
+        Self::from_yaml(&text)
+    }
+
+    /// Parse a config from YAML text and check that it is well-formed.
+    pub fn from_yaml(text: &str) -> Result<Self> {
+        let config: Self = serde_yaml::from_str(text)
+            .map_err(|e| MedullaError::Config(format!("Failed to parse {}: {}", CONFIG_FILE, e)))?;
+
+        for rule in &config.validation_rules {
+            rule.check()?;
+        }
+        for rule in &config.tagging_rules {
+            rule.check()?;
+        }
+        config.limits.check()?;
+        config.guards.check()?;
+        config.task_scoring.check()?;
+        config.wip_limits.check()?;
+        for (i, def) in config.relation_types.iter().enumerate() {
+            def.check()?;
+            if config.relation_types[..i]
+                .iter()
+                .any(|d| d.name == def.name)
+            {
+                return Err(MedullaError::Config(format!(
+                    "Relation type '{}' is defined more than once",
+                    def.name
+                )));
+            }
+        }
+        for rule in &config.expected_relations {
+            rule.check(&config.relation_types)?;
+        }
+        for (i, workflow) in config.workflows.iter().enumerate() {
+            workflow.check()?;
+            if config.workflows[..i]
+                .iter()
+                .any(|w| w.entity_type == workflow.entity_type)
+            {
+                return Err(MedullaError::Config(format!(
+                    "Workflow for '{}' is defined more than once",
+                    workflow.entity_type
+                )));
+            }
+        }
+
+        Ok(config)
+    }
+
+    /// Add relation types registered in the store (`medulla relation type
+    /// add`) that the config file does not define itself.
+    pub fn merge_relation_types(&mut self, registered: Vec<RelationTypeDef>) {
+        for def in registered {
+            if !self.relation_types.iter().any(|d| d.name == def.name) {
+                self.relation_types.push(def);
+            }
+        }
+    }
+
+    /// Parse a relation type name, accepting built-in types and those
+    /// registered in `relation_types`.
+    pub fn parse_relation_type(&self, s: &str) -> std::result::Result<RelationType, String> {
+        if let Ok(relation_type) = s.parse() {
+            return Ok(relation_type);
+        }
+        let name = s.trim().to_lowercase();
+        self.relation_types
+            .iter()
+            .find(|def| def.name == name)
+            .map(|def| RelationType::Custom(def.name.clone()))
+            .ok_or_else(|| format!("Unknown relation type: {}", s))
+    }
+
+    /// Label for a relation seen from its target's end: the opposite of a
+    /// parent/child relation, or the inverse a custom type defines.
+    pub fn inverse_label(&self, relation_type: &RelationType) -> Option<&str> {
+        match relation_type {
+            RelationType::ParentOf => Some("child_of"),
+            RelationType::ChildOf => Some("parent_of"),
+            RelationType::Custom(name) => self
+                .relation_types
+                .iter()
+                .find(|def| &def.name == name)
+                .and_then(RelationTypeDef::inverse_label),
+            _ => None,
+        }
+    }
+
+    /// Replace the values of `redact_fields` anywhere in a JSON value.
+    /// Null values are left alone, so absent fields still read as absent.
+    pub fn redact(&self, value: &mut serde_json::Value) {
+        if self.redact_fields.is_empty() {
+            return;
+        }
+        match value {
+            serde_json::Value::Object(map) => {
+                for (key, field) in map.iter_mut() {
+                    if self.redact_fields.contains(key) && !field.is_null() {
+                        *field = serde_json::Value::String(REDACTED.to_string());
+                    } else {
+                        self.redact(field);
+                    }
+                }
+            }
+            serde_json::Value::Array(items) => items.iter_mut().for_each(|item| self.redact(item)),
+            _ => {}
+        }
+    }
+
+    /// Evaluate all validation rules against an entity.
+    /// Returns every violation rather than stopping at the first.
+    pub fn check_rules(&self, subject: &RuleSubject) -> Vec<RuleViolation> {
+        self.validation_rules
+            .iter()
+            .filter_map(|rule| rule.evaluate(subject))
+            .collect()
+    }
+
+    /// Tags a new entity gets from `default_tags` and every matching
+    /// tagging rule, in config order, leaving out tags it already has.
+    pub fn auto_tags(&self, subject: &RuleSubject) -> Vec<String> {
+        let defaults = self
+            .default_tags
+            .get(subject.entity_type)
+            .into_iter()
+            .flatten();
+        let from_rules = self
+            .tagging_rules
+            .iter()
+            .filter(|rule| rule.matches(subject))
+            .flat_map(|rule| &rule.tags);
+
+        let mut tags: Vec<String> = Vec::new();
+        for tag in defaults.chain(from_rules) {
+            let tag = tag.trim();
+            if !tag.is_empty()
+                && !subject.tags.iter().any(|t| t == tag)
+                && !tags.iter().any(|t| t == tag)
+            {
+                tags.push(tag.to_string());
+            }
+        }
+        tags
+    }
+
+    /// Check a status change against the workflow for `entity_type`.
+    /// Fails with a message naming the allowed next statuses.
+    pub fn check_transition(
+        &self,
+        entity_type: &str,
+        from: &str,
+        to: &str,
+    ) -> std::result::Result<(), String> {
+        let Some(workflow) = self.workflows.iter().find(|w| w.entity_type == entity_type) else {
+            return Ok(());
+        };
+        if workflow.allows(from, to) {
+            return Ok(());
+        }
+        let next = workflow.next_statuses(from);
+        Err(format!(
+            "The {} workflow does not allow '{}' -> '{}' (allowed from '{}': {})",
+            entity_type,
+            from,
+            to,
+            from,
+            if next.is_empty() {
+                "none".to_string()
+            } else {
+                next.join(", ")
+            }
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_load_missing_config_is_default() {
+        let tmp = TempDir::new().unwrap();
+        let config = ProjectConfig::load(tmp.path()).unwrap();
+        assert!(config.validation_rules.is_empty());
+        assert!(!config.strict_enums);
+    }
+
+    #[test]
+    fn test_load_config_file() {
+        let tmp = TempDir::new().unwrap();
+        fs::write(
+            tmp.path().join(CONFIG_FILE),
+            "validation_rules:\n  - rule: tag_required\n    types: [task]\n",
+        )
+        .unwrap();
+
+        let config = ProjectConfig::load(tmp.path()).unwrap();
+        assert_eq!(config.validation_rules.len(), 1);
+    }
+
+    #[test]
+    fn test_strict_enums_flag() {
+        let config = ProjectConfig::from_yaml("strict_enums: true\n").unwrap();
+        assert!(config.strict_enums);
+        assert!(config.validation_rules.is_empty());
+        assert!(!config.materialize_inverse_relations);
+    }
+
+    #[test]
+    fn test_custom_relation_types() {
+        let config = ProjectConfig::from_yaml(
+            "relation_types:\n  - name: tested_by\n    inverse: tests\nexpected_relations:\n  - type: component\n    relation: tested_by\n",
+        )
+        .unwrap();
+        let tested_by = config.parse_relation_type("Tested_By").unwrap();
+        assert_eq!(tested_by, RelationType::Custom("tested_by".to_string()));
+        assert_eq!(config.inverse_label(&tested_by), Some("tests"));
+        assert_eq!(
+            config.parse_relation_type("blocks").unwrap(),
+            RelationType::Blocks
+        );
+        assert!(config.parse_relation_type("verifies").is_err());
+
+        assert!(ProjectConfig::from_yaml(
+            "relation_types:\n  - name: tested_by\n  - name: tested_by\n"
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_merge_relation_types() {
+        let mut config = ProjectConfig::from_yaml(
+            "relation_types:\n  - name: depends_on\n    inverse: needed_by\n",
+        )
+        .unwrap();
+        config.merge_relation_types(vec![
+            RelationTypeDef {
+                name: "depends_on".to_string(),
+                inverse: None,
+                symmetric: false,
+            },
+            RelationTypeDef {
+                name: "replaces".to_string(),
+                inverse: None,
+                symmetric: false,
+            },
+        ]);
+        assert_eq!(config.relation_types.len(), 2);
+        assert_eq!(
+            config.inverse_label(&RelationType::Custom("depends_on".to_string())),
+            Some("needed_by")
+        );
+        assert!(config.parse_relation_type("replaces").is_ok());
+    }
+
+    #[test]
+    fn test_redact_fields() {
+        let config = ProjectConfig::from_yaml("redact_fields: [assignee]\n").unwrap();
+        let mut value = serde_json::json!({
+            "title": "Ship it",
+            "properties": { "assignee": "alice", "status": "todo" },
+            "tasks": [{ "assignee": null }],
+        });
+        config.redact(&mut value);
+        assert_eq!(value["properties"]["assignee"], REDACTED);
+        assert_eq!(value["properties"]["status"], "todo");
+        assert_eq!(value["title"], "Ship it");
+        assert!(value["tasks"][0]["assignee"].is_null());
+    }
+
+    #[test]
+    fn test_limits() {
+        let config = ProjectConfig::from_yaml("strict_enums: true\n").unwrap();
+        assert_eq!(config.limits, Limits::default());
+
+        let config =
+            ProjectConfig::from_yaml("limits:\n  max_batch_size: 500\n  max_limit: 20\n").unwrap();
+        assert_eq!(config.limits.max_batch_size, 500);
+        assert_eq!(config.limits.max_limit, 20);
+        assert_eq!(
+            config.limits.max_context_size,
+            Limits::default().max_context_size
+        );
+
+        assert!(ProjectConfig::from_yaml("limits:\n  max_limit: 0\n").is_err());
+    }
+
+    #[test]
+    fn test_workflows() {
+        let config = ProjectConfig::from_yaml(
+            "workflows:\n  - type: task\n    transitions:\n      todo: [in_progress]\n      in-progress: [done, blocked]\n      blocked: [in_progress]\n",
+        )
+        .unwrap();
+        assert!(config
+            .check_transition("task", "todo", "In Progress")
+            .is_ok());
+        assert!(config
+            .check_transition("task", "in_progress", "done")
+            .is_ok());
+        assert!(config.check_transition("task", "done", "done").is_ok());
+        assert!(config
+            .check_transition("decision", "accepted", "proposed")
+            .is_ok());
+
+        let err = config.check_transition("task", "todo", "done").unwrap_err();
+        assert!(err.contains("allowed from 'todo': in_progress"), "{}", err);
+        let err = config.check_transition("task", "done", "todo").unwrap_err();
+        assert!(err.contains("none"), "{}", err);
+
+        assert!(ProjectConfig::from_yaml(
+            "workflows:\n  - type: decision\n    transitions:\n      proposed: [rejected]\n"
+        )
+        .is_err());
+        assert!(ProjectConfig::from_yaml(
+            "workflows:\n  - type: note\n    transitions:\n      draft: [final]\n"
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_guards() {
+        let config = ProjectConfig::from_yaml("strict_enums: true\n").unwrap();
+        assert_eq!(config.guards, Guards::default());
+
+        let config = ProjectConfig::from_yaml(
+            "guards:\n  requests_per_minute: 0\n  max_concurrent_writes: 1\n",
+        )
+        .unwrap();
+        assert_eq!(config.guards.requests_per_minute, 0);
+        assert_eq!(config.guards.max_concurrent_writes, 1);
+        assert_eq!(
+            config.guards.max_payload_bytes,
+            Guards::default().max_payload_bytes
+        );
+
+        assert!(ProjectConfig::from_yaml("guards:\n  max_payload_bytes: 0\n").is_err());
+    }
+
+    #[test]
+    fn test_auto_tags() {
+        let config = ProjectConfig::from_yaml(
+            "default_tags:\n  task: [triage]\ntagging_rules:\n  - types: [task]\n    title_contains: bug\n    tags: [bug, triage]\n  - url_host: github.com\n    tags: [github]\n",
+        )
+        .unwrap();
+        let props = serde_json::Value::Null;
+        let existing = vec!["bug".to_string()];
+        let subject = |title, tags| RuleSubject {
+            entity_type: "task",
+            title,
+            content: None,
+            tags,
+            properties: &props,
+        };
+
+        assert_eq!(
+            config.auto_tags(&subject("Fix login bug", &[])),
+            vec!["triage", "bug"]
+        );
+        // Tags the entity already has are not repeated
+        assert_eq!(
+            config.auto_tags(&subject("Fix login bug", &existing)),
+            vec!["triage"]
+        );
+        assert_eq!(config.auto_tags(&subject("Add login", &[])), vec!["triage"]);
+
+        let link = serde_json::json!({ "url": "https://github.com/a/b" });
+        let subject = RuleSubject {
+            entity_type: "link",
+            title: "Repo",
+            content: None,
+            tags: &[],
+            properties: &link,
+        };
+        assert_eq!(config.auto_tags(&subject), vec!["github"]);
+
+        assert!(
+            ProjectConfig::from_yaml("tagging_rules:\n  - title_contains: x\n    tags: []\n")
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_invalid_config_is_rejected() {
+        assert!(ProjectConfig::from_yaml("validation_rules: 3").is_err());
+        assert!(ProjectConfig::from_yaml(
+            "validation_rules:\n  - rule: title_pattern\n    pattern: \"[unclosed\"\n"
+        )
+        .is_err());
+    }
+}
@@ -0,0 +1,95 @@
+//! Project-defined relation types.
+//!
+//! These extend the built-in `RelationType` variants. Relations of a custom
+//! type are stored by name like any other relation.
+
+use serde::{Deserialize, Serialize};
+
+use crate::entity::RelationType;
+use crate::error::{MedullaError, Result};
+
+/// A relation type registered in the project config.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RelationTypeDef {
+    /// Name used when creating relations (`tested_by`)
+    pub name: String,
+    /// Label for the relation seen from the target's end (`tests`)
+    #[serde(default)]
+    pub inverse: Option<String>,
+    /// Whether the relation reads the same from both ends
+    #[serde(default)]
+    pub symmetric: bool,
+}
+
+impl RelationTypeDef {
+    /// Check that the name is usable and does not shadow a built-in type.
+    pub fn check(&self) -> Result<()> {
+        let valid = |s: &str| {
+            !s.is_empty()
+                && s.chars()
+                    .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_')
+        };
+        if !valid(&self.name) {
+            return Err(MedullaError::Config(format!(
+                "Invalid relation type name '{}': use lowercase letters, digits and underscores",
+                self.name
+            )));
+        }
+        if self.name.parse::<RelationType>().is_ok() {
+            return Err(MedullaError::Config(format!(
+                "Relation type '{}' is built in and cannot be redefined",
+                self.name
+            )));
+        }
+        if let Some(ref inverse) = self.inverse {
+            if !valid(inverse) {
+                return Err(MedullaError::Config(format!(
+                    "Invalid inverse label '{}' for relation type '{}'",
+                    inverse, self.name
+                )));
+            }
+            if self.symmetric && inverse != &self.name {
+                return Err(MedullaError::Config(format!(
+                    "Symmetric relation type '{}' cannot have a different inverse label",
+                    self.name
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// Label for the relation seen from the target's end, if any.
+    pub fn inverse_label(&self) -> Option<&str> {
+        match self.inverse {
+            Some(ref inverse) => Some(inverse),
+            None if self.symmetric => Some(&self.name),
+            None => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn def(yaml: &str) -> RelationTypeDef {
+        serde_yaml::from_str(yaml).unwrap()
+    }
+
+    #[test]
+    fn test_relation_type_def_check() {
+        let tested_by = def("name: tested_by\ninverse: tests\n");
+        assert!(tested_by.check().is_ok());
+        assert_eq!(tested_by.inverse_label(), Some("tests"));
+
+        let related = def("name: related_to\nsymmetric: true\n");
+        assert!(related.check().is_ok());
+        assert_eq!(related.inverse_label(), Some("related_to"));
+
+        assert!(def("name: blocks\n").check().is_err());
+        assert!(def("name: Tested-By\n").check().is_err());
+        assert!(def("name: pairs_with\nsymmetric: true\ninverse: other\n")
+            .check()
+            .is_err());
+    }
+}
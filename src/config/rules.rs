@@ -0,0 +1,192 @@
+//! Declarative validation rules evaluated on entity create and update.
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::error::{MedullaError, Result};
+
+/// A validation rule loaded from the project config.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ValidationRule {
+    /// Entity types the rule applies to (empty means all types)
+    #[serde(default)]
+    pub types: Vec<String>,
+    /// Custom message reported when the rule fails
+    #[serde(default)]
+    pub message: Option<String>,
+    #[serde(flatten)]
+    pub kind: RuleKind,
+}
+
+/// The check performed by a validation rule.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "rule", rename_all = "snake_case")]
+pub enum RuleKind {
+    /// A field must be present and non-empty.
+    /// Checks `title`, `content` and `tags` directly, anything else in properties.
+    FieldRequired { field: String },
+    /// The entity must have at least `min` tags.
+    TagRequired {
+        #[serde(default = "default_min_tags")]
+        min: usize,
+    },
+    /// The title must match a regular expression.
+    TitlePattern { pattern: String },
+}
+
+fn default_min_tags() -> usize {
+    1
+}
+
+/// The entity state a rule is evaluated against.
+#[derive(Debug, Clone)]
+pub struct RuleSubject<'a> {
+    pub entity_type: &'a str,
+    pub title: &'a str,
+    pub content: Option<&'a str>,
+    pub tags: &'a [String],
+    /// Type-specific properties as a JSON object
+    pub properties: &'a Value,
+}
+
+/// A failed validation rule.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RuleViolation {
+    pub field: String,
+    pub message: String,
+}
+
+impl ValidationRule {
+    /// Check that the rule itself is well-formed.
+    pub fn check(&self) -> Result<()> {
+        if let RuleKind::TitlePattern { pattern } = &self.kind {
+            Regex::new(pattern).map_err(|e| {
+                MedullaError::Config(format!("Invalid title_pattern '{}': {}", pattern, e))
+            })?;
+        }
+        Ok(())
+    }
+
+    /// Whether the rule applies to the given entity type.
+    pub fn applies_to(&self, entity_type: &str) -> bool {
+        self.types.is_empty() || self.types.iter().any(|t| t == entity_type)
+    }
+
+    /// Evaluate the rule, returning a violation if it fails.
+    pub fn evaluate(&self, subject: &RuleSubject) -> Option<RuleViolation> {
+        if !self.applies_to(subject.entity_type) {
+            return None;
+        }
+
+        let (field, default_message) = match &self.kind {
+            RuleKind::FieldRequired { field } => {
+                if field_present(subject, field) {
+                    return None;
+                }
+                (
+                    field.clone(),
+                    format!("{} requires field '{}'", subject.entity_type, field),
+                )
+            }
+            RuleKind::TagRequired { min } => {
+                if subject.tags.len() >= *min {
+                    return None;
+                }
+                (
+                    "tags".to_string(),
+                    format!("{} requires at least {} tag(s)", subject.entity_type, min),
+                )
+            }
+            RuleKind::TitlePattern { pattern } => {
+                // Patterns are checked at load time; treat a bad one as non-matching
+                if Regex::new(pattern)
+                    .map(|re| re.is_match(subject.title))
+                    .unwrap_or(false)
+                {
+                    return None;
+                }
+                (
+                    "title".to_string(),
+                    format!("Title must match pattern '{}'", pattern),
+                )
+            }
+        };
+
+        Some(RuleViolation {
+            field,
+            message: self.message.clone().unwrap_or(default_message),
+        })
+    }
+}
+
+fn field_present(subject: &RuleSubject, field: &str) -> bool {
+    match field {
+        "title" => !subject.title.trim().is_empty(),
+        "content" => subject.content.is_some_and(|c| !c.trim().is_empty()),
+        "tags" => !subject.tags.is_empty(),
+        _ => match subject.properties.get(field) {
+            None | Some(Value::Null) => false,
+            Some(Value::String(s)) => !s.trim().is_empty(),
+            Some(Value::Array(a)) => !a.is_empty(),
+            Some(_) => true,
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn subject<'a>(entity_type: &'a str, tags: &'a [String], props: &'a Value) -> RuleSubject<'a> {
+        RuleSubject {
+            entity_type,
+            title: "Some title",
+            content: None,
+            tags,
+            properties: props,
+        }
+    }
+
+    #[test]
+    fn test_tag_required() {
+        let rule: ValidationRule =
+            serde_yaml::from_str("rule: tag_required\ntypes: [task]\n").unwrap();
+        let props = Value::Null;
+
+        let violation = rule.evaluate(&subject("task", &[], &props)).unwrap();
+        assert_eq!(violation.field, "tags");
+        assert!(rule
+            .evaluate(&subject("task", &["backend".to_string()], &props))
+            .is_none());
+        // Other types are unaffected
+        assert!(rule.evaluate(&subject("note", &[], &props)).is_none());
+    }
+
+    #[test]
+    fn test_field_required() {
+        let rule: ValidationRule =
+            serde_yaml::from_str("rule: field_required\nfield: owner\nmessage: needs owner\n")
+                .unwrap();
+
+        let props = serde_json::json!({ "owner": "" });
+        let violation = rule.evaluate(&subject("component", &[], &props)).unwrap();
+        assert_eq!(violation.message, "needs owner");
+
+        let props = serde_json::json!({ "owner": "alice" });
+        assert!(rule.evaluate(&subject("component", &[], &props)).is_none());
+    }
+
+    #[test]
+    fn test_title_pattern() {
+        let rule: ValidationRule =
+            serde_yaml::from_str("rule: title_pattern\npattern: \"^Some\"\n").unwrap();
+        assert!(rule.check().is_ok());
+        let props = Value::Null;
+        assert!(rule.evaluate(&subject("task", &[], &props)).is_none());
+
+        let rule: ValidationRule =
+            serde_yaml::from_str("rule: title_pattern\npattern: \"^ADR-\"\n").unwrap();
+        assert!(rule.evaluate(&subject("task", &[], &props)).is_some());
+    }
+}
@@ -0,0 +1,158 @@
+//! Weights of the score that orders ready tasks.
+//!
+//! Each factor is normalised to 0..1 and multiplied by its weight: priority
+//! (low 0 to urgent 1), due-date proximity (1 when due today or overdue,
+//! falling to 0 at `due_horizon_days`), age (reaching 1 at `age_cap_days`)
+//! and blocker fan-out (n / (n + 1) for n tasks this one unblocks). The
+//! defaults keep priority dominant while letting the other factors lift a
+//! task by up to two priority levels.
+
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+
+use crate::error::{MedullaError, Result};
+
+/// Per-project weights of the ready-task score, under `task_scoring:`.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TaskScoring {
+    pub priority: f64,
+    pub due: f64,
+    pub age: f64,
+    pub unblocks: f64,
+    /// Days before the due date at which the due factor starts to rise
+    pub due_horizon_days: u32,
+    /// Age in days at which the age factor stops growing
+    pub age_cap_days: u32,
+}
+
+impl Default for TaskScoring {
+    fn default() -> Self {
+        Self {
+            priority: 3.0,
+            due: 0.9,
+            age: 0.5,
+            unblocks: 0.6,
+            due_horizon_days: 14,
+            age_cap_days: 30,
+        }
+    }
+}
+
+/// A ready task's score and the weighted contribution of each factor.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize)]
+pub struct TaskScore {
+    pub total: f64,
+    pub priority: f64,
+    pub due: f64,
+    pub age: f64,
+    pub unblocks: f64,
+}
+
+impl TaskScoring {
+    /// Check that weights are finite and non-negative and the day spans
+    /// are at least one day.
+    pub fn check(&self) -> Result<()> {
+        for (name, value) in [
+            ("priority", self.priority),
+            ("due", self.due),
+            ("age", self.age),
+            ("unblocks", self.unblocks),
+        ] {
+            if !value.is_finite() || value < 0.0 {
+                return Err(MedullaError::Config(format!(
+                    "task_scoring.{} must be a non-negative number",
+                    name
+                )));
+            }
+        }
+        if self.due_horizon_days == 0 || self.age_cap_days == 0 {
+            return Err(MedullaError::Config(
+                "task_scoring.due_horizon_days and age_cap_days must be greater than 0".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Score a task as of `today`. `age_days` is the time since the task
+    /// was created and `unblocks` the number of open tasks it blocks.
+    pub fn score(
+        &self,
+        priority: &str,
+        due_date: Option<NaiveDate>,
+        age_days: i64,
+        unblocks: u32,
+        today: NaiveDate,
+    ) -> TaskScore {
+        let priority_factor = match priority {
+            "urgent" => 1.0,
+            "high" => 2.0 / 3.0,
+            "low" => 0.0,
+            _ => 1.0 / 3.0,
+        };
+        let due_factor = due_date.map_or(0.0, |due| {
+            let days_left = (due - today).num_days() as f64;
+            (1.0 - days_left / self.due_horizon_days as f64).clamp(0.0, 1.0)
+        });
+        let age_factor = (age_days.max(0) as f64 / self.age_cap_days as f64).min(1.0);
+        let unblocks_factor = unblocks as f64 / (unblocks as f64 + 1.0);
+
+        let mut score = TaskScore {
+            total: 0.0,
+            priority: round(self.priority * priority_factor),
+            due: round(self.due * due_factor),
+            age: round(self.age * age_factor),
+            unblocks: round(self.unblocks * unblocks_factor),
+        };
+        score.total = round(score.priority + score.due + score.age + score.unblocks);
+        score
+    }
+}
+
+fn round(value: f64) -> f64 {
+    (value * 1000.0).round() / 1000.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_score_factors() {
+        let scoring = TaskScoring::default();
+        let today = NaiveDate::from_ymd_opt(2026, 3, 1).unwrap();
+
+        let fresh = scoring.score("normal", None, 0, 0, today);
+        assert_eq!(fresh.total, 1.0);
+
+        let overdue = scoring.score("normal", Some(today.pred_opt().unwrap()), 0, 0, today);
+        assert_eq!(overdue.due, 0.9);
+        let week_out = scoring.score("normal", Some(today + chrono::Days::new(7)), 0, 0, today);
+        assert_eq!(week_out.due, 0.45);
+
+        let old = scoring.score("low", None, 90, 3, today);
+        assert_eq!(old.age, 0.5);
+        assert_eq!(old.unblocks, 0.45);
+
+        // Other factors lift a task by at most two priority levels
+        let high = scoring.score("high", None, 0, 0, today);
+        let boosted = scoring.score("normal", Some(today), 60, 1, today);
+        assert!(boosted.total > high.total);
+        assert!(scoring.score("urgent", None, 0, 0, today).total > old.total);
+    }
+
+    #[test]
+    fn test_check_rejects_bad_weights() {
+        assert!(TaskScoring::default().check().is_ok());
+        let negative = TaskScoring {
+            age: -1.0,
+            ..Default::default()
+        };
+        assert!(negative.check().is_err());
+        let no_horizon = TaskScoring {
+            due_horizon_days: 0,
+            ..Default::default()
+        };
+        assert!(no_horizon.check().is_err());
+    }
+}
@@ -0,0 +1,186 @@
+//! Auto-tagging rules applied when entities are created.
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use super::RuleSubject;
+use crate::error::{MedullaError, Result};
+
+/// Tags added to new entities that match every condition given. A rule
+/// with no conditions tags every new entity of its types.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TaggingRule {
+    /// Name shown by `medulla rules list` and `rules test`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// Entity types the rule applies to (empty means all types)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub types: Vec<String>,
+    /// Tags to add
+    pub tags: Vec<String>,
+    /// The title contains this text, ignoring case
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title_contains: Option<String>,
+    /// The title matches this regular expression
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title_pattern: Option<String>,
+    /// The content contains this text, ignoring case
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content_contains: Option<String>,
+    /// The `url` property points at this host or one of its subdomains
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub url_host: Option<String>,
+}
+
+impl TaggingRule {
+    /// Check that the rule itself is well-formed.
+    pub fn check(&self) -> Result<()> {
+        if self.tags.iter().all(|t| t.trim().is_empty()) {
+            return Err(MedullaError::Config(format!(
+                "Tagging rule '{}' has no tags",
+                self.label()
+            )));
+        }
+        if let Some(pattern) = &self.title_pattern {
+            Regex::new(pattern).map_err(|e| {
+                MedullaError::Config(format!("Invalid title_pattern '{}': {}", pattern, e))
+            })?;
+        }
+        Ok(())
+    }
+
+    /// The rule's name, or a description of its conditions.
+    pub fn label(&self) -> String {
+        if let Some(name) = &self.name {
+            return name.clone();
+        }
+        let mut conditions = Vec::new();
+        if !self.types.is_empty() {
+            conditions.push(format!("type in [{}]", self.types.join(", ")));
+        }
+        if let Some(text) = &self.title_contains {
+            conditions.push(format!("title contains '{}'", text));
+        }
+        if let Some(pattern) = &self.title_pattern {
+            conditions.push(format!("title matches '{}'", pattern));
+        }
+        if let Some(text) = &self.content_contains {
+            conditions.push(format!("content contains '{}'", text));
+        }
+        if let Some(host) = &self.url_host {
+            conditions.push(format!("url host is {}", host));
+        }
+        if conditions.is_empty() {
+            "every entity".to_string()
+        } else {
+            conditions.join(" and ")
+        }
+    }
+
+    /// Whether a new entity matches every condition of the rule.
+    pub fn matches(&self, subject: &RuleSubject) -> bool {
+        if !self.types.is_empty() && !self.types.iter().any(|t| t == subject.entity_type) {
+            return false;
+        }
+        if let Some(text) = &self.title_contains {
+            if !contains_ignore_case(subject.title, text) {
+                return false;
+            }
+        }
+        if let Some(pattern) = &self.title_pattern {
+            // Patterns are checked at load time; treat a bad one as non-matching
+            if !Regex::new(pattern).is_ok_and(|re| re.is_match(subject.title)) {
+                return false;
+            }
+        }
+        if let Some(text) = &self.content_contains {
+            if !subject
+                .content
+                .is_some_and(|c| contains_ignore_case(c, text))
+            {
+                return false;
+            }
+        }
+        if let Some(host) = &self.url_host {
+            let url_host = subject
+                .properties
+                .get("url")
+                .and_then(|v| v.as_str())
+                .and_then(host_of);
+            if !url_host.is_some_and(|h| host_matches(&h, host)) {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+fn contains_ignore_case(haystack: &str, needle: &str) -> bool {
+    haystack.to_lowercase().contains(&needle.to_lowercase())
+}
+
+/// Lowercase host of an absolute URL, without port or credentials
+fn host_of(url: &str) -> Option<String> {
+    let (_, rest) = url.split_once("://")?;
+    let authority = rest.split(['/', '?', '#']).next()?;
+    let host = authority.rsplit('@').next()?;
+    let host = host.split(':').next()?;
+    (!host.is_empty()).then(|| host.to_lowercase())
+}
+
+fn host_matches(host: &str, expected: &str) -> bool {
+    let expected = expected.trim().to_lowercase();
+    host == expected || host.ends_with(&format!(".{}", expected))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::Value;
+
+    fn subject<'a>(entity_type: &'a str, title: &'a str, props: &'a Value) -> RuleSubject<'a> {
+        RuleSubject {
+            entity_type,
+            title,
+            content: None,
+            tags: &[],
+            properties: props,
+        }
+    }
+
+    #[test]
+    fn test_title_contains() {
+        let rule: TaggingRule =
+            serde_yaml::from_str("types: [task]\ntitle_contains: bug\ntags: [bug]\n").unwrap();
+        assert!(rule.check().is_ok());
+        let props = Value::Null;
+        assert!(rule.matches(&subject("task", "Fix login BUG", &props)));
+        assert!(!rule.matches(&subject("task", "Add login", &props)));
+        assert!(!rule.matches(&subject("note", "Bug triage notes", &props)));
+        assert_eq!(rule.label(), "type in [task] and title contains 'bug'");
+    }
+
+    #[test]
+    fn test_url_host() {
+        let rule: TaggingRule =
+            serde_yaml::from_str("name: github\nurl_host: github.com\ntags: [github]\n").unwrap();
+        let on = |url: &str| {
+            let props = serde_json::json!({ "url": url });
+            rule.matches(&subject("link", "Repo", &props))
+        };
+        assert!(on("https://github.com/skeletor-js/medulla"));
+        assert!(on("https://gist.GitHub.com/x"));
+        assert!(on("https://user@github.com:443/"));
+        assert!(!on("https://notgithub.com/"));
+        assert!(!on("https://example.com/?u=github.com"));
+        assert!(!rule.matches(&subject("link", "Repo", &Value::Null)));
+    }
+
+    #[test]
+    fn test_check() {
+        let rule: TaggingRule = serde_yaml::from_str("title_pattern: \"(\"\ntags: [x]\n").unwrap();
+        assert!(rule.check().is_err());
+        let rule: TaggingRule = serde_yaml::from_str("title_contains: x\ntags: []\n").unwrap();
+        assert!(rule.check().is_err());
+    }
+}
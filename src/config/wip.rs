@@ -0,0 +1,80 @@
+//! Work-in-progress limits per assignee.
+//!
+//! A limit caps how many `in_progress` tasks one assignee should hold.
+//! `task_ready` and `task_next` can skip tasks for assignees already at
+//! their limit, so a swarm of agents spreads work out instead of queueing
+//! it on one worker; `medulla tasks wip` reports who is over.
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::{MedullaError, Result};
+
+/// WIP limits, under `wip_limits:`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WipLimits {
+    /// Limit for every assignee without an override; unset means none
+    pub default: Option<u32>,
+    /// Limits by assignee name (matched case-insensitively)
+    pub assignees: BTreeMap<String, u32>,
+}
+
+impl WipLimits {
+    /// Check that every limit allows at least one task.
+    pub fn check(&self) -> Result<()> {
+        if self.default == Some(0) || self.assignees.values().any(|&n| n == 0) {
+            return Err(MedullaError::Config(
+                "wip_limits must be greater than 0".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Whether no limit is configured at all.
+    pub fn is_empty(&self) -> bool {
+        self.default.is_none() && self.assignees.is_empty()
+    }
+
+    /// Limit for an assignee: their override, else the default.
+    pub fn limit_for(&self, assignee: &str) -> Option<u32> {
+        let assignee = assignee.trim();
+        self.assignees
+            .iter()
+            .find(|(name, _)| name.trim().eq_ignore_ascii_case(assignee))
+            .map(|(_, &n)| n)
+            .or(self.default)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_limit_for() {
+        let limits = WipLimits {
+            default: Some(2),
+            assignees: BTreeMap::from([("Agent-1".to_string(), 1)]),
+        };
+        assert_eq!(limits.limit_for("agent-1"), Some(1));
+        assert_eq!(limits.limit_for("alice"), Some(2));
+        assert!(limits.check().is_ok());
+
+        assert_eq!(WipLimits::default().limit_for("alice"), None);
+        assert!(WipLimits::default().is_empty());
+        let zero = WipLimits {
+            default: Some(0),
+            ..Default::default()
+        };
+        assert!(zero.check().is_err());
+
+        let config = crate::config::ProjectConfig::from_yaml(
+            "wip_limits:\n  default: 3\n  assignees:\n    bot: 1\n",
+        )
+        .unwrap();
+        assert_eq!(config.wip_limits.limit_for("Bot"), Some(1));
+        assert!(crate::config::ProjectConfig::from_yaml("wip_limits:\n  default: 0\n").is_err());
+    }
+}
@@ -0,0 +1,68 @@
+//! Status workflows: the transitions allowed between an entity type's statuses.
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::entity::{ComponentStatus, DecisionStatus, MilestoneStatus, QuestionStatus, TaskStatus};
+use crate::error::{MedullaError, Result};
+
+/// The status transitions allowed for one entity type.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Workflow {
+    /// Entity type the workflow applies to (decision, task, component, question
+    /// or milestone)
+    #[serde(rename = "type")]
+    pub entity_type: String,
+    /// Statuses reachable from each status. A status without an entry can
+    /// not be left.
+    pub transitions: BTreeMap<String, Vec<String>>,
+}
+
+/// Canonical name of a status of `entity_type`, e.g. `in_progress` for
+/// "In-Progress".
+pub fn canonical_status(entity_type: &str, status: &str) -> std::result::Result<String, String> {
+    match entity_type {
+        "decision" => status.parse::<DecisionStatus>().map(|s| s.to_string()),
+        "task" => status.parse::<TaskStatus>().map(|s| s.to_string()),
+        "component" => status.parse::<ComponentStatus>().map(|s| s.to_string()),
+        "question" => status.parse::<QuestionStatus>().map(|s| s.to_string()),
+        "milestone" => status.parse::<MilestoneStatus>().map(|s| s.to_string()),
+        other => Err(format!("Entity type '{}' has no status", other)),
+    }
+}
+
+impl Workflow {
+    /// Check that the type has statuses and every status named is valid.
+    pub fn check(&self) -> Result<()> {
+        let statuses = self
+            .transitions
+            .iter()
+            .flat_map(|(from, to)| std::iter::once(from).chain(to));
+        for status in statuses {
+            canonical_status(&self.entity_type, status).map_err(|e| {
+                MedullaError::Config(format!("Invalid workflow for {}: {}", self.entity_type, e))
+            })?;
+        }
+        Ok(())
+    }
+
+    /// Statuses an entity in `from` may move to
+    pub fn next_statuses(&self, from: &str) -> Vec<String> {
+        let canonical = |s: &str| canonical_status(&self.entity_type, s).ok();
+        let from = canonical(from);
+        self.transitions
+            .iter()
+            .find(|(status, _)| canonical(status) == from)
+            .map(|(_, to)| to.iter().filter_map(|s| canonical(s)).collect())
+            .unwrap_or_default()
+    }
+
+    /// Whether moving from `from` to `to` is allowed. Staying in the same
+    /// status always is.
+    pub fn allows(&self, from: &str, to: &str) -> bool {
+        let to = canonical_status(&self.entity_type, to).unwrap_or_else(|_| to.to_string());
+        canonical_status(&self.entity_type, from).ok().as_ref() == Some(&to)
+            || self.next_statuses(from).contains(&to)
+    }
+}
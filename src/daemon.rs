@@ -0,0 +1,259 @@
+//! Background maintenance daemon.
+//!
+//! `medulla daemon` re-runs itself detached and, on a fixed interval, syncs
+//! the cache, regenerates the snapshot, backfills embeddings and runs the
+//! health check. While it runs, `.medulla/daemon.json` records its PID and
+//! the outcome of the last pass, which `medulla daemon status` reads.
+//! `medulla daemon stop` asks it to exit by creating `.medulla/daemon.stop`,
+//! which the daemon checks every second, so stopping works the same on every
+//! platform without signals.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::error::{MedullaError, Result};
+use crate::warnings::Severity;
+
+/// PID and status file, under `.medulla/`
+pub const DAEMON_STATE_FILE: &str = "daemon.json";
+
+/// Created to ask a running daemon to exit, under `.medulla/`
+pub const DAEMON_STOP_FILE: &str = "daemon.stop";
+
+/// Output of a detached daemon, under `.medulla/`
+pub const DAEMON_LOG_FILE: &str = "daemon.log";
+
+/// How often a waiting daemon looks for a stop request
+const STOP_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+/// What a daemon is doing, as written to `daemon.json`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DaemonState {
+    pub pid: u32,
+    pub started_at: DateTime<Utc>,
+    pub interval_secs: u64,
+    /// Whether passes regenerate the snapshot
+    pub snapshot: bool,
+    /// Whether passes backfill embeddings
+    pub embeddings: bool,
+    /// Passes completed so far
+    #[serde(default)]
+    pub runs: u64,
+    #[serde(default)]
+    pub last_run: Option<DateTime<Utc>>,
+    /// One-line outcome of the last pass
+    #[serde(default)]
+    pub last_summary: Option<String>,
+    /// Health status found by the last pass
+    #[serde(default)]
+    pub last_health: Option<Severity>,
+    #[serde(default)]
+    pub next_run: Option<DateTime<Utc>>,
+}
+
+impl DaemonState {
+    pub fn path(medulla_dir: &Path) -> PathBuf {
+        medulla_dir.join(DAEMON_STATE_FILE)
+    }
+
+    /// The recorded state, or None when no daemon has left one
+    pub fn load(medulla_dir: &Path) -> Result<Option<Self>> {
+        let path = Self::path(medulla_dir);
+        if !path.exists() {
+            return Ok(None);
+        }
+        let content = fs::read_to_string(&path)?;
+        serde_json::from_str(&content).map(Some).map_err(|e| {
+            MedullaError::Config(format!("Invalid daemon file {}: {}", path.display(), e))
+        })
+    }
+
+    /// Write the state, through a temporary file so `status` never reads a
+    /// half-written one
+    pub fn save(&self, medulla_dir: &Path) -> Result<()> {
+        let path = Self::path(medulla_dir);
+        let tmp = path.with_extension("json.tmp");
+        fs::write(&tmp, serde_json::to_string_pretty(self)?)?;
+        fs::rename(&tmp, &path)?;
+        Ok(())
+    }
+
+    pub fn remove(medulla_dir: &Path) -> Result<()> {
+        match fs::remove_file(Self::path(medulla_dir)) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+            _ => Ok(()),
+        }
+    }
+
+    /// Whether the recorded process is still running
+    pub fn is_running(&self) -> bool {
+        process_alive(self.pid)
+    }
+}
+
+/// Parse an interval such as `30s`, `15m`, `2h` or `1d`; a bare number is
+/// seconds
+pub fn parse_interval(s: &str) -> std::result::Result<Duration, String> {
+    let s = s.trim().to_lowercase();
+    let split = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+    let (number, unit) = s.split_at(split);
+    let n: u64 = number
+        .parse()
+        .map_err(|_| format!("Invalid interval '{}', expected e.g. 30s, 15m or 2h", s))?;
+    let unit_secs: u64 = match unit {
+        "" | "s" => 1,
+        "m" => 60,
+        "h" => 3600,
+        "d" => 86400,
+        _ => {
+            return Err(format!(
+                "Invalid interval '{}', expected e.g. 30s, 15m or 2h",
+                s
+            ))
+        }
+    };
+    let too_long = || format!("Interval '{}' is too long", s);
+    let secs = n.checked_mul(unit_secs).ok_or_else(too_long)?;
+    if secs == 0 {
+        return Err("Interval must be greater than zero".to_string());
+    }
+    // The daemon dates its next pass with it, so chrono must fit it too
+    let interval = Duration::from_secs(secs);
+    chrono::Duration::from_std(interval).map_err(|_| too_long())?;
+    Ok(interval)
+}
+
+/// Ask the daemon of this project to exit at its next check
+pub fn request_stop(medulla_dir: &Path) -> Result<()> {
+    fs::write(medulla_dir.join(DAEMON_STOP_FILE), "")?;
+    Ok(())
+}
+
+/// Whether a stop has been requested
+pub fn stop_requested(medulla_dir: &Path) -> bool {
+    medulla_dir.join(DAEMON_STOP_FILE).exists()
+}
+
+/// Forget a stop request, once handled or left behind by an old daemon
+pub fn clear_stop_request(medulla_dir: &Path) -> Result<()> {
+    match fs::remove_file(medulla_dir.join(DAEMON_STOP_FILE)) {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+        _ => Ok(()),
+    }
+}
+
+/// Sleep for `duration`, checking for a stop request every second.
+/// Returns whether one came.
+pub fn wait_for_stop(medulla_dir: &Path, duration: Duration) -> bool {
+    let deadline = std::time::Instant::now().checked_add(duration);
+    loop {
+        if stop_requested(medulla_dir) {
+            return true;
+        }
+        // A deadline past what `Instant` can hold is never reached
+        let left = deadline.map_or(STOP_CHECK_INTERVAL, |deadline| {
+            deadline.saturating_duration_since(std::time::Instant::now())
+        });
+        if left.is_zero() {
+            return false;
+        }
+        std::thread::sleep(left.min(STOP_CHECK_INTERVAL));
+    }
+}
+
+/// An interval in the largest unit that divides it, as `parse_interval`
+/// reads it
+pub fn format_interval(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    match secs {
+        s if s > 0 && s % 86400 == 0 => format!("{}d", s / 86400),
+        s if s > 0 && s % 3600 == 0 => format!("{}h", s / 3600),
+        s if s > 0 && s % 60 == 0 => format!("{}m", s / 60),
+        s => format!("{}s", s),
+    }
+}
+
+#[cfg(unix)]
+fn process_alive(pid: u32) -> bool {
+    // Signal 0 checks that the process exists without touching it
+    std::process::Command::new("kill")
+        .args(["-0", &pid.to_string()])
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null())
+        .status()
+        .is_ok_and(|s| s.success())
+}
+
+#[cfg(not(unix))]
+fn process_alive(pid: u32) -> bool {
+    std::process::Command::new("tasklist")
+        .args(["/FI", &format!("PID eq {}", pid), "/NH"])
+        .output()
+        .is_ok_and(|o| String::from_utf8_lossy(&o.stdout).contains(&pid.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_parse_interval() {
+        assert_eq!(parse_interval("90"), Ok(Duration::from_secs(90)));
+        assert_eq!(parse_interval("30s"), Ok(Duration::from_secs(30)));
+        assert_eq!(parse_interval("15m"), Ok(Duration::from_secs(900)));
+        assert_eq!(parse_interval("2H"), Ok(Duration::from_secs(7200)));
+        assert_eq!(parse_interval("1d"), Ok(Duration::from_secs(86400)));
+        assert!(parse_interval("0m").is_err());
+        assert!(parse_interval("m").is_err());
+        assert!(parse_interval("5 weeks").is_err());
+        assert_eq!(
+            parse_interval("999999999999999999d"),
+            Err("Interval '999999999999999999d' is too long".to_string())
+        );
+        assert!(parse_interval(&format!("{}h", u64::MAX / 3600 + 1)).is_err());
+        assert!(parse_interval(&format!("{}s", u64::MAX)).is_err());
+
+        for interval in ["45s", "15m", "2h", "1d", "90s"] {
+            assert_eq!(format_interval(parse_interval(interval).unwrap()), interval);
+        }
+    }
+
+    #[test]
+    fn test_state_and_stop_files() {
+        let tmp = TempDir::new().unwrap();
+        assert_eq!(DaemonState::load(tmp.path()).unwrap(), None);
+
+        let state = DaemonState {
+            pid: std::process::id(),
+            started_at: Utc::now(),
+            interval_secs: 900,
+            snapshot: true,
+            embeddings: false,
+            runs: 1,
+            last_run: Some(Utc::now()),
+            last_summary: Some("cache synced".to_string()),
+            last_health: Some(Severity::Ok),
+            next_run: None,
+        };
+        state.save(tmp.path()).unwrap();
+        assert_eq!(DaemonState::load(tmp.path()).unwrap(), Some(state.clone()));
+        #[cfg(unix)]
+        assert!(state.is_running());
+
+        assert!(!stop_requested(tmp.path()));
+        request_stop(tmp.path()).unwrap();
+        assert!(stop_requested(tmp.path()));
+        assert!(wait_for_stop(tmp.path(), Duration::from_secs(60)));
+        clear_stop_request(tmp.path()).unwrap();
+        assert!(!stop_requested(tmp.path()));
+
+        DaemonState::remove(tmp.path()).unwrap();
+        DaemonState::remove(tmp.path()).unwrap();
+        assert_eq!(DaemonState::load(tmp.path()).unwrap(), None);
+    }
+}
@@ -0,0 +1,308 @@
+//! Incremental embedding backfill.
+//!
+//! Finds entities whose embedding is missing or was computed from text that
+//! has since changed, and computes them most recently updated first, so the
+//! entities someone is working on become searchable before the long tail.
+
+use rayon::prelude::*;
+use serde::Serialize;
+
+use super::Embedder;
+use crate::cache::{compute_text_hash, embeddable_text, SqliteCache};
+use crate::entity::EntityBase;
+use crate::error::Result;
+use crate::storage::LoroStore;
+
+/// Entities embedded per model call
+pub const BACKFILL_BATCH_SIZE: usize = 16;
+
+/// An entity whose embedding is missing or stale
+#[derive(Debug, Clone)]
+pub struct PendingEmbedding {
+    pub entity_type: &'static str,
+    pub base: EntityBase,
+    /// Body embedded with the title and tags: the template of a prompt, the
+    /// content of anything else
+    pub body: Option<String>,
+    /// Whether an outdated embedding exists (as opposed to none)
+    pub stale: bool,
+}
+
+impl PendingEmbedding {
+    fn text(&self) -> String {
+        embeddable_text(&self.base.title, self.body.as_deref(), &self.base.tags)
+    }
+}
+
+/// Outcome of one backfill pass
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct BackfillStats {
+    /// Entities that had no embedding
+    pub missing: usize,
+    /// Entities whose embedding no longer matched their text
+    pub stale: usize,
+    pub computed: usize,
+    pub errors: usize,
+}
+
+/// Entities needing an embedding, most recently updated first
+pub fn pending_embeddings(store: &LoroStore, cache: &SqliteCache) -> Result<Vec<PendingEmbedding>> {
+    let mut entities: Vec<(&'static str, EntityBase)> = Vec::new();
+    entities.extend(
+        store
+            .list_decisions()?
+            .into_iter()
+            .map(|e| ("decision", e.base)),
+    );
+    entities.extend(store.list_tasks()?.into_iter().map(|e| ("task", e.base)));
+    entities.extend(store.list_notes()?.into_iter().map(|e| ("note", e.base)));
+    entities.extend(
+        store
+            .list_components()?
+            .into_iter()
+            .map(|e| ("component", e.base)),
+    );
+    entities.extend(store.list_links()?.into_iter().map(|e| ("link", e.base)));
+    entities.extend(
+        store
+            .list_questions()?
+            .into_iter()
+            .map(|e| ("question", e.base)),
+    );
+    entities.extend(
+        store
+            .list_milestones()?
+            .into_iter()
+            .map(|e| ("milestone", e.base)),
+    );
+    let mut bodies: Vec<(&'static str, EntityBase, Option<String>)> = entities
+        .into_iter()
+        .map(|(entity_type, base)| {
+            let body = base.content.clone();
+            (entity_type, base, body)
+        })
+        .collect();
+    // Prompts are searched by what they ask, so their template is embedded
+    bodies.extend(
+        store
+            .list_prompts()?
+            .into_iter()
+            .map(|e| ("prompt", e.base, e.template)),
+    );
+
+    let mut pending = Vec::new();
+    for (entity_type, base, body) in bodies {
+        let text = embeddable_text(&base.title, body.as_deref(), &base.tags);
+        let stored = cache.get_embedding_text_hash(&base.id.to_string())?;
+        if stored.as_deref() == Some(compute_text_hash(&text).as_str()) {
+            continue;
+        }
+        pending.push(PendingEmbedding {
+            entity_type,
+            base,
+            body,
+            stale: stored.is_some(),
+        });
+    }
+    pending.sort_by_key(|p| std::cmp::Reverse(p.base.updated_at));
+    Ok(pending)
+}
+
+/// Compute and store embeddings for `pending` in batches, calling
+/// `progress(done, total)` after each round. Batches in a round are embedded
+/// in parallel across the rayon pool; storing stays on the calling thread
+/// since the cache connection is not shared. A failed batch is counted in
+/// `errors` and the rest still run.
+pub fn backfill(
+    cache: &SqliteCache,
+    embedder: &Embedder,
+    pending: &[PendingEmbedding],
+    progress: impl FnMut(usize, usize),
+) -> BackfillStats {
+    // One batch per thread in each round
+    let round_size = BACKFILL_BATCH_SIZE * rayon::current_num_threads().max(1);
+    backfill_with(
+        cache,
+        |texts| embedder.embed_batch(texts),
+        pending,
+        round_size,
+        progress,
+    )
+}
+
+/// `backfill` with the model call and round size passed in
+fn backfill_with(
+    cache: &SqliteCache,
+    embed_batch: impl Fn(&[String]) -> Result<Vec<Vec<f32>>> + Sync,
+    pending: &[PendingEmbedding],
+    round_size: usize,
+    mut progress: impl FnMut(usize, usize),
+) -> BackfillStats {
+    let mut stats = BackfillStats {
+        stale: pending.iter().filter(|p| p.stale).count(),
+        ..Default::default()
+    };
+    stats.missing = pending.len() - stats.stale;
+
+    let mut done = 0;
+    for round in pending.chunks(round_size) {
+        let batches: Vec<(&[PendingEmbedding], Vec<String>)> = round
+            .chunks(BACKFILL_BATCH_SIZE)
+            .map(|batch| (batch, batch.iter().map(PendingEmbedding::text).collect()))
+            .collect();
+        let embedded: Vec<_> = batches
+            .par_iter()
+            .map(|(_, texts)| embed_batch(texts))
+            .collect();
+
+        for ((batch, texts), result) in batches.iter().zip(embedded) {
+            let Ok(embeddings) = result else {
+                stats.errors += batch.len();
+                continue;
+            };
+            for ((entry, text), embedding) in batch.iter().zip(texts).zip(embeddings) {
+                match cache.store_embedding(
+                    &entry.base.id.to_string(),
+                    entry.entity_type,
+                    &embedding,
+                    &compute_text_hash(text),
+                ) {
+                    Ok(()) => stats.computed += 1,
+                    Err(_) => stats.errors += 1,
+                }
+            }
+        }
+        done += round.len();
+        progress(done, pending.len());
+    }
+    stats
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::entity::{Note, Prompt, Task};
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_pending_embeddings() {
+        let tmp = TempDir::new().unwrap();
+        let store = LoroStore::init(tmp.path()).unwrap();
+        let cache = SqliteCache::open(store.medulla_dir()).unwrap();
+
+        let mut old = Task::new("Old task".to_string(), 1);
+        old.base.updated_at -= chrono::Duration::days(2);
+        store.add_task(&old).unwrap();
+        let note = Note::new("Fresh note".to_string(), 2);
+        store.add_note(&note).unwrap();
+        let mut embedded = Task::new("Embedded".to_string(), 3);
+        embedded.base.tags = vec!["done".to_string()];
+        store.add_task(&embedded).unwrap();
+
+        let text = embeddable_text("Embedded", None, &embedded.base.tags);
+        cache
+            .store_embedding(
+                &embedded.base.id.to_string(),
+                "task",
+                &[1.0, 0.0],
+                &compute_text_hash(&text),
+            )
+            .unwrap();
+        cache
+            .store_embedding(&old.base.id.to_string(), "task", &[0.0, 1.0], "outdated")
+            .unwrap();
+
+        let pending = pending_embeddings(&store, &cache).unwrap();
+        let ids: Vec<_> = pending.iter().map(|p| p.base.id).collect();
+        assert_eq!(ids, vec![note.base.id, old.base.id]);
+        assert!(!pending[0].stale);
+        assert!(pending[1].stale);
+    }
+
+    /// Stand-in for the model: a vector derived from the text alone
+    fn fake_embedding(text: &str) -> Vec<f32> {
+        let hash = compute_text_hash(text);
+        hash.bytes().take(8).map(|b| b as f32).collect()
+    }
+
+    #[test]
+    fn test_parallel_backfill_matches_sequential() {
+        let tmp = TempDir::new().unwrap();
+        let store = LoroStore::init(tmp.path()).unwrap();
+
+        let mut seq = 1;
+        for i in 0..40 {
+            store
+                .add_note(&Note::new(format!("Note {}", i), seq))
+                .unwrap();
+            seq += 1;
+        }
+        for i in 0..30 {
+            let mut prompt = Prompt::new(format!("Prompt {}", i), seq);
+            prompt.base.content = Some("About the prompt".to_string());
+            prompt.template = Some(format!("Summarize {{{{topic}}}} in {} words", i));
+            store.add_prompt(&prompt).unwrap();
+            seq += 1;
+        }
+
+        // Rounds of two batches, so 70 entities take three
+        let parallel = SqliteCache::open_in_memory().unwrap();
+        let pending = pending_embeddings(&store, &parallel).unwrap();
+        assert_eq!(pending.len(), 70);
+        let mut rounds = 0;
+        let stats = backfill_with(
+            &parallel,
+            |texts| Ok(texts.iter().map(|t| fake_embedding(t)).collect()),
+            &pending,
+            2 * BACKFILL_BATCH_SIZE,
+            |_, _| rounds += 1,
+        );
+        assert_eq!(rounds, 3);
+        assert_eq!((stats.computed, stats.errors), (70, 0));
+
+        // The same texts, embedded one at a time
+        let sequential = SqliteCache::open_in_memory().unwrap();
+        let mut expected: Vec<(String, Option<String>, Vec<String>)> = store
+            .list_notes()
+            .unwrap()
+            .into_iter()
+            .map(|n| (n.base.id.to_string(), n.base.content, n.base.tags))
+            .collect();
+        let prompts = store.list_prompts().unwrap();
+        expected.extend(prompts.iter().map(|p| {
+            (
+                p.base.id.to_string(),
+                p.template.clone(),
+                p.base.tags.clone(),
+            )
+        }));
+        for (id, body, tags) in &expected {
+            let base = pending
+                .iter()
+                .find(|p| p.base.id.to_string() == *id)
+                .unwrap();
+            let text = embeddable_text(&base.base.title, body.as_deref(), tags);
+            sequential
+                .store_embedding(
+                    id,
+                    base.entity_type,
+                    &fake_embedding(&text),
+                    &compute_text_hash(&text),
+                )
+                .unwrap();
+        }
+
+        let mut got = parallel.list_all_embeddings(None).unwrap();
+        let mut want = sequential.list_all_embeddings(None).unwrap();
+        got.sort_by(|a, b| a.0.cmp(&b.0));
+        want.sort_by(|a, b| a.0.cmp(&b.0));
+        assert_eq!(got, want);
+        for (id, _, _) in &want {
+            assert_eq!(
+                parallel.get_embedding_text_hash(id).unwrap(),
+                sequential.get_embedding_text_hash(id).unwrap()
+            );
+        }
+        assert!(pending_embeddings(&store, &parallel).unwrap().is_empty());
+    }
+}
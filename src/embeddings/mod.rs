@@ -1,3 +1,4 @@
+pub mod backfill;
 pub mod config;
 
 use fastembed::{EmbeddingModel, InitOptions, TextEmbedding};
@@ -5,6 +6,9 @@ use std::sync::OnceLock;
 
 use crate::error::{MedullaError, Result};
 
+/// Length of the vectors the embedding model (all-MiniLM-L6-v2) produces
+pub const EMBEDDING_DIMENSION: usize = 384;
+
 static MODEL: OnceLock<std::result::Result<TextEmbedding, String>> = OnceLock::new();
 
 /// Wrapper around the embedding model for computing text embeddings.
@@ -46,7 +50,7 @@ impl Embedder {
 
     /// Get the embedding dimension (384 for all-MiniLM-L6-v2).
     pub fn dimension(&self) -> usize {
-        384
+        EMBEDDING_DIMENSION
     }
 }
 
@@ -0,0 +1,75 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// A git commit attached to an entity, either with `medulla git link` or
+/// by the post-commit hook picking up a `med-<seq>` mention in the commit
+/// message.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CommitLink {
+    pub entity_id: Uuid,
+    pub entity_type: String,
+    /// Commit SHA, lowercase hex (full length when git could resolve it)
+    pub sha: String,
+    /// First line of the commit message, when known
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub summary: Option<String>,
+    pub linked_at: DateTime<Utc>,
+}
+
+impl CommitLink {
+    pub fn new(entity_id: Uuid, entity_type: String, sha: String) -> Self {
+        Self {
+            entity_id,
+            entity_type,
+            sha: sha.to_lowercase(),
+            summary: None,
+            linked_at: Utc::now(),
+        }
+    }
+
+    /// Key in the store; one link per entity and commit
+    pub fn composite_key(&self) -> String {
+        format!("{}:{}", self.entity_id, self.sha)
+    }
+
+    /// Whether `sha` looks like an abbreviated or full commit SHA.
+    pub fn is_valid_sha(sha: &str) -> bool {
+        (7..=40).contains(&sha.len()) && sha.chars().all(|c| c.is_ascii_hexdigit())
+    }
+
+    /// Sequence numbers mentioned as `med-<seq>` (case-insensitive) in a
+    /// commit message, in order of first mention.
+    pub fn mentions(message: &str) -> Vec<u32> {
+        let mut found = Vec::new();
+        for (_, seq) in super::mention::prefixed_numbers(message, "med-") {
+            if !found.contains(&seq) {
+                found.push(seq);
+            }
+        }
+        found
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mentions() {
+        assert_eq!(
+            CommitLink::mentions("Fix login (MED-12, med-3)\n\nAlso med-12 again"),
+            vec![12, 3]
+        );
+        assert!(CommitLink::mentions("armed-3 med- med-x med-4b").is_empty());
+        assert_eq!(CommitLink::mentions("refs med-7."), vec![7]);
+    }
+
+    #[test]
+    fn test_is_valid_sha() {
+        assert!(CommitLink::is_valid_sha("a1b2c3d"));
+        assert!(CommitLink::is_valid_sha(&"f".repeat(40)));
+        assert!(!CommitLink::is_valid_sha("a1b2c3"));
+        assert!(!CommitLink::is_valid_sha("HEAD~1"));
+    }
+}
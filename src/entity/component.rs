@@ -1,7 +1,7 @@
 // src/entity/component.rs
 use serde::{Deserialize, Serialize};
 
-use super::EntityBase;
+use super::{EntityBase, TaskPriority};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
 #[serde(rename_all = "lowercase")]
@@ -26,7 +26,7 @@ impl std::str::FromStr for ComponentStatus {
     type Err = String;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        match s.to_lowercase().as_str() {
+        match s.trim().to_lowercase().as_str() {
             "active" => Ok(ComponentStatus::Active),
             "deprecated" => Ok(ComponentStatus::Deprecated),
             "planned" => Ok(ComponentStatus::Planned),
@@ -42,6 +42,8 @@ pub struct Component {
     pub component_type: Option<String>,
     pub status: ComponentStatus,
     pub owner: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub priority: Option<TaskPriority>,
 }
 
 impl Component {
@@ -51,6 +53,7 @@ impl Component {
             component_type: None,
             status: ComponentStatus::default(),
             owner: None,
+            priority: None,
         }
     }
 }
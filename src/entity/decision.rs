@@ -1,6 +1,6 @@
 use serde::{Deserialize, Serialize};
 
-use super::EntityBase;
+use super::{EntityBase, TaskPriority};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
 #[serde(rename_all = "lowercase")]
@@ -27,7 +27,7 @@ impl std::str::FromStr for DecisionStatus {
     type Err = String;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        match s.to_lowercase().as_str() {
+        match s.trim().to_lowercase().as_str() {
             "proposed" => Ok(DecisionStatus::Proposed),
             "accepted" => Ok(DecisionStatus::Accepted),
             "deprecated" => Ok(DecisionStatus::Deprecated),
@@ -45,6 +45,8 @@ pub struct Decision {
     pub context: Option<String>,
     pub consequences: Vec<String>,
     pub superseded_by: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub priority: Option<TaskPriority>,
 }
 
 impl Decision {
@@ -55,6 +57,7 @@ impl Decision {
             context: None,
             consequences: Vec::new(),
             superseded_by: None,
+            priority: None,
         }
     }
 }
@@ -1,4 +1,5 @@
 // src/entity/link.rs
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 
 use super::EntityBase;
@@ -11,6 +12,18 @@ pub struct Link {
     pub url: String,
     /// Type of link (e.g., "documentation", "issue", "pr", "reference")
     pub link_type: Option<String>,
+    /// Description from the page's metadata, when the URL was fetched
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// Absolute URL of the page's favicon, when the URL was fetched
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub favicon: Option<String>,
+    /// Hash of the page body when last fetched, to notice it changing
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content_hash: Option<String>,
+    /// When the URL was last fetched
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fetched_at: Option<DateTime<Utc>>,
 }
 
 impl Link {
@@ -19,6 +32,10 @@ impl Link {
             base: EntityBase::new(title, sequence_number),
             url,
             link_type: None,
+            description: None,
+            favicon: None,
+            content_hash: None,
+            fetched_at: None,
         }
     }
 }
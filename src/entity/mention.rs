@@ -0,0 +1,61 @@
+/// Sequence numbers an entity's text refers to as `#<seq>` or `med-<seq>`
+/// (case-insensitive), in order of first mention.
+pub fn entity_mentions(text: &str) -> Vec<u32> {
+    let mut found = Vec::new();
+    let mut all: Vec<(usize, u32)> = prefixed_numbers(text, "#");
+    all.extend(prefixed_numbers(text, "med-"));
+    all.sort_by_key(|(start, _)| *start);
+    for (_, seq) in all {
+        if !found.contains(&seq) {
+            found.push(seq);
+        }
+    }
+    found
+}
+
+/// Numbers written as `<prefix><digits>` (prefix matched case-insensitively)
+/// with their byte offset. The prefix must start a word, so `armed-3`,
+/// `page/#3` and `&#38;` are skipped, and the digits must end one, so
+/// `#12ab` is not a mention either.
+pub(crate) fn prefixed_numbers(text: &str, prefix: &str) -> Vec<(usize, u32)> {
+    let lower = text.to_ascii_lowercase();
+    let bytes = lower.as_bytes();
+    let mut found = Vec::new();
+
+    for (start, _) in lower.match_indices(prefix) {
+        if start > 0 {
+            let before = bytes[start - 1];
+            if before.is_ascii_alphanumeric() || matches!(before, b'_' | b'&' | b'/' | b'#') {
+                continue;
+            }
+        }
+        let digits_start = start + prefix.len();
+        let digits: String = lower[digits_start..]
+            .chars()
+            .take_while(|c| c.is_ascii_digit())
+            .collect();
+        let end = digits_start + digits.len();
+        if end < bytes.len() && (bytes[end].is_ascii_alphabetic() || bytes[end] == b'_') {
+            continue;
+        }
+        if let Ok(seq) = digits.parse::<u32>() {
+            found.push((start, seq));
+        }
+    }
+
+    found
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_entity_mentions() {
+        assert_eq!(
+            entity_mentions("Follows #12 and MED-3; see also #12.\n\n#4, (med-7)"),
+            vec![12, 3, 4, 7]
+        );
+        assert!(entity_mentions("# Heading, #fff, #12ab, &#38;, docs/#5, armed-2, ##9").is_empty());
+    }
+}
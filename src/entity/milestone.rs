@@ -0,0 +1,53 @@
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+
+use super::EntityBase;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum MilestoneStatus {
+    #[default]
+    Open,
+    Completed,
+}
+
+impl std::fmt::Display for MilestoneStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MilestoneStatus::Open => write!(f, "open"),
+            MilestoneStatus::Completed => write!(f, "completed"),
+        }
+    }
+}
+
+impl std::str::FromStr for MilestoneStatus {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "open" => Ok(MilestoneStatus::Open),
+            "completed" | "complete" | "done" => Ok(MilestoneStatus::Completed),
+            _ => Err(format!("Invalid milestone status: {}", s)),
+        }
+    }
+}
+
+/// A roadmap milestone. Tasks join a milestone through a `belongs_to`
+/// relation to it, and its progress is computed from their statuses.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Milestone {
+    #[serde(flatten)]
+    pub base: EntityBase,
+    pub status: MilestoneStatus,
+    pub target_date: Option<NaiveDate>,
+}
+
+impl Milestone {
+    pub fn new(title: String, sequence_number: u32) -> Self {
+        Self {
+            base: EntityBase::new(title, sequence_number),
+            status: MilestoneStatus::default(),
+            target_date: None,
+        }
+    }
+}
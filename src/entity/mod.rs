@@ -1,18 +1,35 @@
+mod commit_link;
 mod component;
 mod decision;
 mod link;
+mod mention;
+mod milestone;
 mod note;
 mod prompt;
+mod question;
+mod recurrence;
 mod relation;
+mod sections;
 mod task;
+mod template;
 
+pub use commit_link::CommitLink;
 pub use component::{Component, ComponentStatus};
 pub use decision::{Decision, DecisionStatus};
 pub use link::Link;
+pub use mention::entity_mentions;
+pub use milestone::{Milestone, MilestoneStatus};
 pub use note::Note;
-pub use prompt::Prompt;
+pub use prompt::{placeholders, validate_json, Prompt};
+pub use question::{Question, QuestionStatus};
+pub use recurrence::{CronRule, Recurrence};
 pub use relation::{Relation, RelationType};
-pub use task::{Task, TaskPriority, TaskStatus};
+pub(crate) use sections::markdown_headings;
+pub use sections::{
+    find_section, patch_section, sections, Section, SectionEdit, DEFAULT_SECTION_LEVEL,
+};
+pub use task::{parse_due_window, Task, TaskPriority, TaskStatus};
+pub use template::EntityTemplate;
 
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
@@ -28,7 +45,13 @@ pub struct EntityBase {
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
     pub created_by: Option<String>,
+    /// Author of the most recent update
+    #[serde(default)]
+    pub updated_by: Option<String>,
     pub sequence_number: u32,
+    /// Hidden from default list, search and ready-task results
+    #[serde(default)]
+    pub archived: bool,
 }
 
 impl EntityBase {
@@ -42,7 +65,9 @@ impl EntityBase {
             created_at: now,
             updated_at: now,
             created_by: None,
+            updated_by: None,
             sequence_number,
+            archived: false,
         }
     }
 }
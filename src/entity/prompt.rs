@@ -1,5 +1,8 @@
 // src/entity/prompt.rs
+use std::collections::BTreeMap;
+
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 
 use super::EntityBase;
 
@@ -24,4 +27,351 @@ impl Prompt {
             output_schema: None,
         }
     }
+
+    /// The text to render: the template, or the content for prompts saved
+    /// without one.
+    pub fn template_text(&self) -> &str {
+        self.template
+            .as_deref()
+            .or(self.base.content.as_deref())
+            .unwrap_or("")
+    }
+
+    /// Variables a render needs: the declared ones, then any other
+    /// placeholders used in the template.
+    pub fn required_variables(&self) -> Vec<String> {
+        let mut required = self.variables.clone();
+        for name in placeholders(self.template_text()) {
+            if !required.contains(&name) {
+                required.push(name);
+            }
+        }
+        required
+    }
+
+    /// Fill the template's `{{name}}` placeholders from `values`. Fails with
+    /// the names of required variables that have no value.
+    pub fn render(&self, values: &BTreeMap<String, String>) -> Result<String, Vec<String>> {
+        let missing: Vec<String> = self
+            .required_variables()
+            .into_iter()
+            .filter(|name| !values.contains_key(name))
+            .collect();
+        if !missing.is_empty() {
+            return Err(missing);
+        }
+
+        let template = self.template_text();
+        let mut rendered = String::with_capacity(template.len());
+        let mut rest = template;
+        while let Some((before, name, after)) = next_placeholder(rest) {
+            rendered.push_str(before);
+            rendered.push_str(&values[name]);
+            rest = after;
+        }
+        rendered.push_str(rest);
+        Ok(rendered)
+    }
+
+    /// Check `output` against the prompt's `output_schema`. Returns the
+    /// validation errors (empty if valid), `None` without a schema, or an
+    /// error if the stored schema is not JSON.
+    pub fn validate_output(&self, output: &Value) -> Option<Result<Vec<String>, String>> {
+        let schema = self.output_schema.as_deref()?;
+        Some(
+            serde_json::from_str::<Value>(schema)
+                .map(|schema| validate_json(&schema, output))
+                .map_err(|e| format!("output_schema is not valid JSON: {}", e)),
+        )
+    }
+}
+
+/// Names of the `{{name}}` placeholders in `template`, in order of first use
+pub fn placeholders(template: &str) -> Vec<String> {
+    let mut names: Vec<String> = Vec::new();
+    let mut rest = template;
+    while let Some((_, name, after)) = next_placeholder(rest) {
+        if !names.iter().any(|n| n == name) {
+            names.push(name.to_string());
+        }
+        rest = after;
+    }
+    names
+}
+
+/// The text before the next placeholder, its (trimmed) name, and the text
+/// after it. Braces around anything but a plain name are left as text.
+fn next_placeholder(text: &str) -> Option<(&str, &str, &str)> {
+    let mut offset = 0;
+    while let Some(start) = text[offset..].find("{{").map(|i| offset + i) {
+        let end = text[start + 2..].find("}}").map(|i| start + 2 + i)?;
+        let name = text[start + 2..end].trim();
+        let valid = !name.is_empty()
+            && name
+                .chars()
+                .all(|c| c.is_alphanumeric() || matches!(c, '_' | '-' | '.'));
+        if valid {
+            return Some((&text[..start], name, &text[end + 2..]));
+        }
+        offset = start + 2;
+    }
+    None
+}
+
+/// Validate `value` against a JSON Schema, returning one message per
+/// violation prefixed with its path (`$`, `$.field`, `$.list[0]`).
+///
+/// Supports the keywords prompt output schemas use in practice: `type`,
+/// `enum`, `const`, `properties`, `required`, `additionalProperties`,
+/// `items`, `minItems`/`maxItems`, `minLength`/`maxLength`, `pattern`,
+/// `minimum`/`maximum` and `anyOf`. `$ref`, `allOf`, `oneOf`, `not` and
+/// `format` restrict values in ways not checked here, so a schema using them
+/// is reported as a violation rather than passing everything; other
+/// keywords are ignored. So is an invalid `pattern`.
+pub fn validate_json(schema: &Value, value: &Value) -> Vec<String> {
+    let mut errors = Vec::new();
+    validate_at(schema, value, "$", &mut errors);
+    errors
+}
+
+/// Keywords that narrow what a value may be but aren't checked
+const UNSUPPORTED_KEYWORDS: [&str; 5] = ["$ref", "allOf", "oneOf", "not", "format"];
+
+fn json_type(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(n) if n.is_i64() || n.is_u64() => "integer",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+fn type_matches(expected: &str, value: &Value) -> bool {
+    let actual = json_type(value);
+    actual == expected
+        || (expected == "number" && actual == "integer")
+        || (expected == "integer" && value.as_f64().is_some_and(|n| n.fract() == 0.0))
+}
+
+fn validate_at(schema: &Value, value: &Value, path: &str, errors: &mut Vec<String>) {
+    let Some(schema) = schema.as_object() else {
+        // `true` accepts anything, `false` nothing
+        if schema == &Value::Bool(false) {
+            errors.push(format!("{}: not allowed", path));
+        }
+        return;
+    };
+
+    for keyword in UNSUPPORTED_KEYWORDS {
+        if schema.contains_key(keyword) {
+            errors.push(format!(
+                "{}: unsupported schema keyword '{}'",
+                path, keyword
+            ));
+        }
+    }
+    let pattern = match schema.get("pattern").and_then(Value::as_str) {
+        Some(pattern) => match regex::Regex::new(pattern) {
+            Ok(re) => Some((pattern, re)),
+            Err(e) => {
+                errors.push(format!("{}: invalid pattern {}: {}", path, pattern, e));
+                None
+            }
+        },
+        None => None,
+    };
+
+    if let Some(expected) = schema.get("type") {
+        let types: Vec<&str> = match expected {
+            Value::String(t) => vec![t.as_str()],
+            Value::Array(ts) => ts.iter().filter_map(Value::as_str).collect(),
+            _ => Vec::new(),
+        };
+        if !types.is_empty() && !types.iter().any(|t| type_matches(t, value)) {
+            errors.push(format!(
+                "{}: expected {}, got {}",
+                path,
+                types.join(" or "),
+                json_type(value)
+            ));
+            return;
+        }
+    }
+
+    if let Some(allowed) = schema.get("enum").and_then(Value::as_array) {
+        if !allowed.contains(value) {
+            errors.push(format!(
+                "{}: must be one of {}",
+                path,
+                Value::Array(allowed.clone())
+            ));
+        }
+    }
+    if let Some(expected) = schema.get("const") {
+        if expected != value {
+            errors.push(format!("{}: must equal {}", path, expected));
+        }
+    }
+
+    if let Some(options) = schema.get("anyOf").and_then(Value::as_array) {
+        let matched = options.iter().any(|option| {
+            let mut option_errors = Vec::new();
+            validate_at(option, value, path, &mut option_errors);
+            option_errors.is_empty()
+        });
+        if !matched {
+            errors.push(format!("{}: does not match any allowed schema", path));
+        }
+    }
+
+    let limit = |key: &str| schema.get(key).and_then(Value::as_f64);
+    match value {
+        Value::Object(object) => {
+            for name in schema
+                .get("required")
+                .and_then(Value::as_array)
+                .into_iter()
+                .flatten()
+                .filter_map(Value::as_str)
+            {
+                if !object.contains_key(name) {
+                    errors.push(format!("{}: missing required property '{}'", path, name));
+                }
+            }
+            let properties = schema.get("properties").and_then(Value::as_object);
+            for (name, field) in object {
+                let field_path = format!("{}.{}", path, name);
+                match properties.and_then(|p| p.get(name)) {
+                    Some(field_schema) => validate_at(field_schema, field, &field_path, errors),
+                    None => {
+                        if let Some(additional) = schema.get("additionalProperties") {
+                            validate_at(additional, field, &field_path, errors);
+                        }
+                    }
+                }
+            }
+        }
+        Value::Array(items) => {
+            if let Some(min) = limit("minItems").filter(|min| (items.len() as f64) < *min) {
+                errors.push(format!("{}: expected at least {} items", path, min));
+            }
+            if let Some(max) = limit("maxItems").filter(|max| (items.len() as f64) > *max) {
+                errors.push(format!("{}: expected at most {} items", path, max));
+            }
+            if let Some(item_schema) = schema.get("items") {
+                for (i, item) in items.iter().enumerate() {
+                    validate_at(item_schema, item, &format!("{}[{}]", path, i), errors);
+                }
+            }
+        }
+        Value::String(text) => {
+            let length = text.chars().count() as f64;
+            if let Some(min) = limit("minLength").filter(|min| length < *min) {
+                errors.push(format!("{}: shorter than {} characters", path, min));
+            }
+            if let Some(max) = limit("maxLength").filter(|max| length > *max) {
+                errors.push(format!("{}: longer than {} characters", path, max));
+            }
+            if let Some((pattern, re)) = &pattern {
+                if !re.is_match(text) {
+                    errors.push(format!("{}: does not match pattern {}", path, pattern));
+                }
+            }
+        }
+        Value::Number(n) => {
+            let n = n.as_f64().unwrap_or_default();
+            if let Some(min) = limit("minimum").filter(|min| n < *min) {
+                errors.push(format!("{}: less than minimum {}", path, min));
+            }
+            if let Some(max) = limit("maximum").filter(|max| n > *max) {
+                errors.push(format!("{}: greater than maximum {}", path, max));
+            }
+        }
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_fills_placeholders() {
+        let mut prompt = Prompt::new("Review".to_string(), 1);
+        prompt.template =
+            Some("Review {{ file }} for {{focus}}; {{file}} again. {{ not a var }}".to_string());
+        prompt.variables = vec!["focus".to_string(), "tone".to_string()];
+        assert_eq!(prompt.required_variables(), vec!["focus", "tone", "file"]);
+
+        let mut values = BTreeMap::from([
+            ("file".to_string(), "main.rs".to_string()),
+            ("focus".to_string(), "safety".to_string()),
+        ]);
+        assert_eq!(prompt.render(&values), Err(vec!["tone".to_string()]));
+
+        values.insert("tone".to_string(), "terse".to_string());
+        assert_eq!(
+            prompt.render(&values).unwrap(),
+            "Review main.rs for safety; main.rs again. {{ not a var }}"
+        );
+    }
+
+    #[test]
+    fn test_validate_json() {
+        let schema = serde_json::json!({
+            "type": "object",
+            "required": ["verdict", "issues"],
+            "properties": {
+                "verdict": { "enum": ["approve", "reject"] },
+                "issues": {
+                    "type": "array",
+                    "items": { "type": "object", "required": ["line"], "properties": { "line": { "type": "integer", "minimum": 1 } } }
+                }
+            },
+            "additionalProperties": false
+        });
+
+        let valid = serde_json::json!({ "verdict": "approve", "issues": [{ "line": 3 }] });
+        assert!(validate_json(&schema, &valid).is_empty());
+
+        let invalid =
+            serde_json::json!({ "verdict": "maybe", "issues": [{ "line": 0 }, {}], "extra": 1 });
+        let mut errors = validate_json(&schema, &invalid);
+        errors.sort();
+        assert_eq!(
+            errors,
+            vec![
+                "$.extra: not allowed",
+                "$.issues[0].line: less than minimum 1",
+                "$.issues[1]: missing required property 'line'",
+                "$.verdict: must be one of [\"approve\",\"reject\"]",
+            ]
+        );
+        assert_eq!(
+            validate_json(&schema, &serde_json::json!("text")),
+            vec!["$: expected object, got string"]
+        );
+    }
+
+    #[test]
+    fn test_validate_json_reports_unsupported_schema() {
+        let schema = serde_json::json!({
+            "type": "object",
+            "properties": {
+                "email": { "type": "string", "format": "email" },
+                "kind": { "oneOf": [{ "const": "a" }, { "const": "b" }] },
+                "code": { "type": "string", "pattern": "([a-z" }
+            }
+        });
+        let value = serde_json::json!({ "email": "x", "kind": "a", "code": "abc" });
+        let mut errors = validate_json(&schema, &value);
+        errors.sort();
+        assert_eq!(errors.len(), 3);
+        assert!(errors[0].starts_with("$.code: invalid pattern ([a-z"));
+        assert_eq!(errors[1], "$.email: unsupported schema keyword 'format'");
+        assert_eq!(errors[2], "$.kind: unsupported schema keyword 'oneOf'");
+    }
 }
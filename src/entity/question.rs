@@ -0,0 +1,51 @@
+use serde::{Deserialize, Serialize};
+
+use super::EntityBase;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum QuestionStatus {
+    #[default]
+    Open,
+    Answered,
+}
+
+impl std::fmt::Display for QuestionStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            QuestionStatus::Open => write!(f, "open"),
+            QuestionStatus::Answered => write!(f, "answered"),
+        }
+    }
+}
+
+impl std::str::FromStr for QuestionStatus {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "open" => Ok(QuestionStatus::Open),
+            "answered" => Ok(QuestionStatus::Answered),
+            _ => Err(format!("Invalid question status: {}", s)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Question {
+    #[serde(flatten)]
+    pub base: EntityBase,
+    pub status: QuestionStatus,
+    /// ID of the entity (usually a decision or note) that answers the question
+    pub answered_by: Option<String>,
+}
+
+impl Question {
+    pub fn new(title: String, sequence_number: u32) -> Self {
+        Self {
+            base: EntityBase::new(title, sequence_number),
+            status: QuestionStatus::default(),
+            answered_by: None,
+        }
+    }
+}
@@ -0,0 +1,237 @@
+use chrono::{Datelike, Days, Months, NaiveDate};
+use serde::{Deserialize, Serialize};
+
+/// How often a recurring task comes back once completed.
+///
+/// Written as `daily`, `weekly`, `monthly`, `every N days|weeks|months`, or a
+/// five-field cron expression (`minute hour day-of-month month day-of-week`).
+/// Tasks are due on dates, so the minute and hour fields of a cron expression
+/// are checked but otherwise ignored.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Recurrence {
+    /// Every N days
+    Daily(u32),
+    /// Every N weeks
+    Weekly(u32),
+    /// Every N months, on the same day of the month where possible
+    Monthly(u32),
+    /// Days matching a cron expression
+    Cron(CronRule),
+}
+
+/// The date fields of a cron expression.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CronRule {
+    expression: String,
+    days_of_month: Vec<u32>,
+    months: Vec<u32>,
+    days_of_week: Vec<u32>,
+    any_day_of_month: bool,
+    any_day_of_week: bool,
+}
+
+/// How far ahead to look for a matching cron day before giving up.
+const CRON_SEARCH_DAYS: u64 = 366 * 8;
+
+impl Recurrence {
+    /// The first occurrence after `today`, counting on from the current due
+    /// date if there is one (so an overdue chore keeps its rhythm).
+    pub fn next_due(&self, due: Option<NaiveDate>, today: NaiveDate) -> Option<NaiveDate> {
+        if let Recurrence::Cron(rule) = self {
+            return rule.next_after(due.map_or(today, |d| d.max(today)));
+        }
+
+        let start = due.unwrap_or(today);
+        (1..)
+            .map_while(|count| self.step(start, count))
+            .find(|d| *d > today)
+    }
+
+    /// The date `count` intervals after `from`.
+    fn step(&self, from: NaiveDate, count: u32) -> Option<NaiveDate> {
+        match self {
+            Recurrence::Daily(n) => {
+                from.checked_add_days(Days::new(u64::from(n.checked_mul(count)?)))
+            }
+            Recurrence::Weekly(n) => {
+                from.checked_add_days(Days::new(u64::from(n.checked_mul(count)?) * 7))
+            }
+            // Always step from the original date so the 31st stays the 31st
+            // (or the month's last day) instead of drifting earlier
+            Recurrence::Monthly(n) => from.checked_add_months(Months::new(n.checked_mul(count)?)),
+            Recurrence::Cron(rule) => rule.next_after(from),
+        }
+    }
+}
+
+impl CronRule {
+    /// Whether `date` matches. As in cron, when both day fields are
+    /// restricted a date matching either one counts.
+    fn matches(&self, date: NaiveDate) -> bool {
+        if !self.months.contains(&date.month()) {
+            return false;
+        }
+        let dom = self.days_of_month.contains(&date.day());
+        let dow = self
+            .days_of_week
+            .contains(&date.weekday().num_days_from_sunday());
+        match (self.any_day_of_month, self.any_day_of_week) {
+            (true, true) => true,
+            (true, false) => dow,
+            (false, true) => dom,
+            (false, false) => dom || dow,
+        }
+    }
+
+    /// The first matching date after `date`.
+    fn next_after(&self, date: NaiveDate) -> Option<NaiveDate> {
+        (1..=CRON_SEARCH_DAYS)
+            .filter_map(|n| date.checked_add_days(Days::new(n)))
+            .find(|d| self.matches(*d))
+    }
+}
+
+/// Parse one cron field into the values it allows, within `min..=max`.
+/// Supports `*`, numbers, ranges (`1-5`), lists (`1,15`) and steps (`*/2`).
+fn parse_cron_field(field: &str, min: u32, max: u32) -> Result<Vec<u32>, String> {
+    let mut values = Vec::new();
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => {
+                let step: u32 = step
+                    .parse()
+                    .map_err(|_| format!("Invalid step in cron field: {}", field))?;
+                if step == 0 {
+                    return Err(format!("Invalid step in cron field: {}", field));
+                }
+                (range, step)
+            }
+            None => (part, 1),
+        };
+        let (start, end) = if range == "*" {
+            (min, max)
+        } else if let Some((start, end)) = range.split_once('-') {
+            let start = start.parse::<u32>();
+            let end = end.parse::<u32>();
+            match (start, end) {
+                (Ok(start), Ok(end)) => (start, end),
+                _ => return Err(format!("Invalid range in cron field: {}", field)),
+            }
+        } else {
+            let value: u32 = range
+                .parse()
+                .map_err(|_| format!("Invalid value in cron field: {}", field))?;
+            // A lone number with a step runs to the end of the range
+            (value, if step > 1 { max } else { value })
+        };
+        if start < min || end > max || start > end {
+            return Err(format!(
+                "Cron field '{}' out of range {}-{}",
+                field, min, max
+            ));
+        }
+        values.extend((start..=end).step_by(step as usize));
+    }
+    values.sort_unstable();
+    values.dedup();
+    Ok(values)
+}
+
+fn parse_cron(expression: &str) -> Result<CronRule, String> {
+    let fields: Vec<&str> = expression.split_whitespace().collect();
+    let [minute, hour, dom, month, dow] = fields[..] else {
+        return Err(format!(
+            "Cron expression must have 5 fields, got {}: {}",
+            fields.len(),
+            expression
+        ));
+    };
+    parse_cron_field(minute, 0, 59)?;
+    parse_cron_field(hour, 0, 23)?;
+    let days_of_month = parse_cron_field(dom, 1, 31)?;
+    let months = parse_cron_field(month, 1, 12)?;
+    // Both 0 and 7 mean Sunday
+    let mut days_of_week: Vec<u32> = parse_cron_field(dow, 0, 7)?
+        .into_iter()
+        .map(|d| d % 7)
+        .collect();
+    days_of_week.sort_unstable();
+    days_of_week.dedup();
+
+    Ok(CronRule {
+        expression: fields.join(" "),
+        days_of_month,
+        months,
+        days_of_week,
+        any_day_of_month: dom == "*",
+        any_day_of_week: dow == "*",
+    })
+}
+
+impl std::fmt::Display for Recurrence {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Recurrence::Daily(1) => write!(f, "daily"),
+            Recurrence::Weekly(1) => write!(f, "weekly"),
+            Recurrence::Monthly(1) => write!(f, "monthly"),
+            Recurrence::Daily(n) => write!(f, "every {} days", n),
+            Recurrence::Weekly(n) => write!(f, "every {} weeks", n),
+            Recurrence::Monthly(n) => write!(f, "every {} months", n),
+            Recurrence::Cron(rule) => write!(f, "{}", rule.expression),
+        }
+    }
+}
+
+impl std::str::FromStr for Recurrence {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let normalized = s.trim().to_lowercase();
+        match normalized.as_str() {
+            "daily" | "every day" => return Ok(Recurrence::Daily(1)),
+            "weekly" | "every week" => return Ok(Recurrence::Weekly(1)),
+            "monthly" | "every month" => return Ok(Recurrence::Monthly(1)),
+            _ => {}
+        }
+
+        if let Some(rest) = normalized.strip_prefix("every ") {
+            let (count, unit) = rest
+                .split_once(' ')
+                .ok_or_else(|| format!("Invalid recurrence: {}", s))?;
+            let count: u32 = count
+                .parse()
+                .map_err(|_| format!("Invalid recurrence interval: {}", s))?;
+            if count == 0 {
+                return Err(format!("Recurrence interval must be at least 1: {}", s));
+            }
+            return match unit.trim_end_matches('s') {
+                "day" => Ok(Recurrence::Daily(count)),
+                "week" => Ok(Recurrence::Weekly(count)),
+                "month" => Ok(Recurrence::Monthly(count)),
+                _ => Err(format!("Invalid recurrence unit: {}", s)),
+            };
+        }
+
+        if normalized.split_whitespace().count() == 5 {
+            return parse_cron(&normalized).map(Recurrence::Cron);
+        }
+
+        Err(format!(
+            "Invalid recurrence: {} (expected daily, weekly, monthly, 'every N days|weeks|months', or a cron expression)",
+            s
+        ))
+    }
+}
+
+impl Serialize for Recurrence {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Recurrence {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(serde::de::Error::custom)
+    }
+}
@@ -3,9 +3,12 @@ use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use uuid::Uuid;
 
-/// Relation types supported by the system
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
-#[serde(rename_all = "snake_case")]
+/// Relation types supported by the system.
+///
+/// Besides the built-in types, projects can register their own in
+/// `relation_types` in the config or in the store (`medulla relation type
+/// add`); those are carried as `Custom` and stored by name.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum RelationType {
     /// Task implements this decision
     Implements,
@@ -19,6 +22,52 @@ pub enum RelationType {
     BelongsTo,
     /// Note documents a component
     Documents,
+    /// Source task is the parent of the target subtask
+    ParentOf,
+    /// Source subtask is a child of the target task
+    ChildOf,
+    /// Project-defined relation type, by name
+    Custom(String),
+}
+
+impl RelationType {
+    /// Parse a stored relation type, keeping unknown names as `Custom`.
+    pub fn from_stored(s: &str) -> Self {
+        s.parse()
+            .unwrap_or_else(|_| RelationType::Custom(s.to_string()))
+    }
+
+    /// Every built-in relation type.
+    pub fn builtin() -> [RelationType; 8] {
+        [
+            RelationType::Implements,
+            RelationType::Blocks,
+            RelationType::Supersedes,
+            RelationType::References,
+            RelationType::BelongsTo,
+            RelationType::Documents,
+            RelationType::ParentOf,
+            RelationType::ChildOf,
+        ]
+    }
+
+    /// Whether this is one of the built-in relation types.
+    pub fn is_builtin(&self) -> bool {
+        !matches!(self, RelationType::Custom(_))
+    }
+}
+
+impl Serialize for RelationType {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for RelationType {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        Ok(RelationType::from_stored(&s))
+    }
 }
 
 impl std::fmt::Display for RelationType {
@@ -30,6 +79,9 @@ impl std::fmt::Display for RelationType {
             RelationType::References => write!(f, "references"),
             RelationType::BelongsTo => write!(f, "belongs_to"),
             RelationType::Documents => write!(f, "documents"),
+            RelationType::ParentOf => write!(f, "parent_of"),
+            RelationType::ChildOf => write!(f, "child_of"),
+            RelationType::Custom(name) => write!(f, "{}", name),
         }
     }
 }
@@ -38,13 +90,15 @@ impl std::str::FromStr for RelationType {
     type Err = String;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        match s.to_lowercase().as_str() {
+        match s.trim().to_lowercase().as_str() {
             "implements" => Ok(RelationType::Implements),
             "blocks" => Ok(RelationType::Blocks),
             "supersedes" => Ok(RelationType::Supersedes),
             "references" => Ok(RelationType::References),
             "belongs_to" | "belongsto" => Ok(RelationType::BelongsTo),
             "documents" => Ok(RelationType::Documents),
+            "parent_of" | "parentof" => Ok(RelationType::ParentOf),
+            "child_of" | "childof" => Ok(RelationType::ChildOf),
             _ => Err(format!("Unknown relation type: {}", s)),
         }
     }
@@ -70,6 +124,9 @@ pub struct Relation {
     /// Additional properties (optional metadata)
     #[serde(default)]
     pub properties: HashMap<String, String>,
+    /// When the relation was marked resolved (kept for history, no longer in effect)
+    #[serde(default)]
+    pub resolved_at: Option<DateTime<Utc>>,
 }
 
 impl Relation {
@@ -90,9 +147,15 @@ impl Relation {
             created_at: Utc::now(),
             created_by: None,
             properties: HashMap::new(),
+            resolved_at: None,
         }
     }
 
+    /// Whether the relation is still in effect (not resolved)
+    pub fn is_active(&self) -> bool {
+        self.resolved_at.is_none()
+    }
+
     /// Generate the composite key for this relation
     pub fn composite_key(&self) -> String {
         format!(
@@ -0,0 +1,311 @@
+//! Sections of an entity's markdown content.
+//!
+//! Content is split at ATX headings such as `## Context` or `## Outcome`.
+//! A section runs from its heading to the next heading of the same or a
+//! higher level, so it includes its subsections; headings inside fenced
+//! code blocks are ignored. Sections are addressed by heading text
+//! (case-insensitive) or anchor slug, so one section can be read or
+//! rewritten without touching the rest of the content.
+
+use std::str::FromStr;
+
+use serde::Serialize;
+
+use crate::snapshot::utils::slugify;
+
+/// Level of a heading added for a section the content does not have yet
+pub const DEFAULT_SECTION_LEVEL: u8 = 2;
+
+/// One heading and the text under it
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Section {
+    pub level: u8,
+    pub heading: String,
+    /// Text under the heading, subsections included, without surrounding
+    /// blank lines
+    pub body: String,
+}
+
+/// How `patch_section` changes a section
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SectionEdit {
+    /// Replace the section's text, keeping its heading
+    Replace,
+    /// Add text at the end of the section
+    Append,
+    /// Remove the heading and its text
+    Delete,
+}
+
+impl FromStr for SectionEdit {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "replace" => Ok(Self::Replace),
+            "append" => Ok(Self::Append),
+            "delete" => Ok(Self::Delete),
+            _ => Err(format!(
+                "Invalid section edit '{}': use replace, append or delete",
+                s
+            )),
+        }
+    }
+}
+
+/// Parse an ATX heading line (`# Title`, `## Title ##`), returning level and text
+pub(crate) fn parse_heading(line: &str) -> Option<(u8, String)> {
+    // Up to three spaces of indentation are allowed
+    let indent = line.len() - line.trim_start_matches(' ').len();
+    if indent > 3 {
+        return None;
+    }
+    let line = &line[indent..];
+
+    let level = line.chars().take_while(|&c| c == '#').count();
+    if level == 0 || level > 6 {
+        return None;
+    }
+
+    let rest = &line[level..];
+    if !rest.is_empty() && !rest.starts_with(char::is_whitespace) {
+        return None;
+    }
+
+    // Strip an optional closing sequence of #s
+    let text = rest.trim();
+    let text = match text.trim_end_matches('#') {
+        stripped if stripped.is_empty() || stripped.ends_with(char::is_whitespace) => {
+            stripped.trim_end()
+        }
+        _ => text,
+    };
+
+    if text.is_empty() {
+        return None;
+    }
+
+    Some((level as u8, text.to_string()))
+}
+
+/// Headings of markdown content as (line index, level, text), skipping
+/// fenced code blocks
+pub(crate) fn markdown_headings(content: &str) -> Vec<(usize, u8, String)> {
+    let mut headings = Vec::new();
+    let mut fence: Option<&str> = None;
+
+    for (index, line) in content.lines().enumerate() {
+        let trimmed = line.trim_start();
+        if let Some(marker) = ["```", "~~~"].into_iter().find(|m| trimmed.starts_with(m)) {
+            fence = match fence {
+                None => Some(marker),
+                Some(open) if open == marker => None,
+                open => open,
+            };
+            continue;
+        }
+        if fence.is_some() {
+            continue;
+        }
+        if let Some((level, text)) = parse_heading(line) {
+            headings.push((index, level, text));
+        }
+    }
+
+    headings
+}
+
+/// Line range of each section: (level, heading, heading line, end line)
+fn section_spans(content: &str) -> Vec<(u8, String, usize, usize)> {
+    let headings = markdown_headings(content);
+    let line_count = content.lines().count();
+
+    headings
+        .iter()
+        .enumerate()
+        .map(|(i, (start, level, text))| {
+            let end = headings[i + 1..]
+                .iter()
+                .find(|(_, l, _)| l <= level)
+                .map_or(line_count, |(line, _, _)| *line);
+            (*level, text.clone(), *start, end)
+        })
+        .collect()
+}
+
+/// Whether a heading is the one asked for: same text ignoring case and
+/// leading `#`s, or same anchor slug
+fn heading_matches(heading: &str, wanted: &str) -> bool {
+    let wanted = wanted.trim().trim_start_matches('#').trim();
+    heading.eq_ignore_ascii_case(wanted) || slugify(heading) == wanted.to_lowercase()
+}
+
+/// Lines joined without leading or trailing blank lines
+fn trim_blank_lines(lines: &[&str]) -> String {
+    let start = lines.iter().position(|l| !l.trim().is_empty());
+    let end = lines.iter().rposition(|l| !l.trim().is_empty());
+    match (start, end) {
+        (Some(start), Some(end)) => lines[start..=end].join("\n"),
+        _ => String::new(),
+    }
+}
+
+/// Every section of the content, in document order. Text before the first
+/// heading belongs to no section.
+pub fn sections(content: &str) -> Vec<Section> {
+    let lines: Vec<&str> = content.lines().collect();
+    section_spans(content)
+        .into_iter()
+        .map(|(level, heading, start, end)| Section {
+            level,
+            heading,
+            body: trim_blank_lines(&lines[start + 1..end]),
+        })
+        .collect()
+}
+
+/// The first section with the given heading
+pub fn find_section(content: &str, heading: &str) -> Option<Section> {
+    sections(content)
+        .into_iter()
+        .find(|s| heading_matches(&s.heading, heading))
+}
+
+/// Content with one section replaced, appended to or deleted.
+///
+/// Replacing or appending to a section the content does not have adds it
+/// at the end as a heading of `level`. Returns `None` when deleting a
+/// section that does not exist.
+pub fn patch_section(
+    content: &str,
+    heading: &str,
+    edit: SectionEdit,
+    text: &str,
+    level: u8,
+) -> Option<String> {
+    let lines: Vec<&str> = content.lines().collect();
+    let text = text.trim_matches('\n');
+
+    let Some((_, _, start, end)) = section_spans(content)
+        .into_iter()
+        .find(|(_, h, _, _)| heading_matches(h, heading))
+    else {
+        if edit == SectionEdit::Delete {
+            return None;
+        }
+        let mut patched = content.trim_end().to_string();
+        if !patched.is_empty() {
+            patched.push_str("\n\n");
+        }
+        let heading = heading.trim().trim_start_matches('#').trim();
+        patched.push_str(&format!(
+            "{} {}\n",
+            "#".repeat(level.clamp(1, 6) as usize),
+            heading
+        ));
+        if !text.is_empty() {
+            patched.push_str(&format!("\n{}\n", text));
+        }
+        return Some(patched);
+    };
+
+    let mut patched: Vec<String> = lines[..start].iter().map(|l| l.to_string()).collect();
+    if edit != SectionEdit::Delete {
+        patched.push(lines[start].to_string());
+        let body = match edit {
+            SectionEdit::Append => {
+                let existing = trim_blank_lines(&lines[start + 1..end]);
+                match (existing.is_empty(), text.is_empty()) {
+                    (true, _) => text.to_string(),
+                    (false, true) => existing,
+                    (false, false) => format!("{}\n\n{}", existing, text),
+                }
+            }
+            _ => text.to_string(),
+        };
+        if !body.is_empty() {
+            patched.push(String::new());
+            patched.push(body);
+        }
+        if end < lines.len() {
+            patched.push(String::new());
+        }
+    } else if end == lines.len() {
+        // Don't leave the blank lines that separated the removed section
+        while patched.last().is_some_and(|l| l.trim().is_empty()) {
+            patched.pop();
+        }
+    }
+    patched.extend(lines[end..].iter().map(|l| l.to_string()));
+
+    let mut patched = patched.join("\n");
+    if !patched.is_empty() {
+        patched.push('\n');
+    }
+    Some(patched)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ADR: &str = "Intro line\n\n## Context\n\nWe need a store.\n\n### Constraints\n\nOffline first.\n\n## Options\n\n```md\n## Not a heading\n```\n\n## Outcome\n\nUse Loro.\n";
+
+    #[test]
+    fn test_parse_heading() {
+        assert_eq!(parse_heading("# Title"), Some((1, "Title".to_string())));
+        assert_eq!(parse_heading("### Deep ###"), Some((3, "Deep".to_string())));
+        assert_eq!(
+            parse_heading("   ## Indented"),
+            Some((2, "Indented".to_string()))
+        );
+        assert_eq!(parse_heading("#NoSpace"), None);
+        assert_eq!(parse_heading("####### Too deep"), None);
+        assert_eq!(parse_heading("Plain text"), None);
+    }
+
+    #[test]
+    fn test_sections() {
+        let all = sections(ADR);
+        let headings: Vec<&str> = all.iter().map(|s| s.heading.as_str()).collect();
+        assert_eq!(
+            headings,
+            vec!["Context", "Constraints", "Options", "Outcome"]
+        );
+        assert_eq!(
+            all[0].body,
+            "We need a store.\n\n### Constraints\n\nOffline first."
+        );
+        assert_eq!(all[2].body, "```md\n## Not a heading\n```");
+
+        let outcome = find_section(ADR, "## outcome").unwrap();
+        assert_eq!(outcome.level, 2);
+        assert_eq!(outcome.body, "Use Loro.");
+        assert!(find_section(ADR, "constraints").is_some());
+        assert!(find_section(ADR, "Not a heading").is_none());
+    }
+
+    #[test]
+    fn test_patch_section() {
+        let replaced =
+            patch_section(ADR, "Context", SectionEdit::Replace, "Rewritten.", 2).unwrap();
+        assert!(replaced.starts_with("Intro line\n\n## Context\n\nRewritten.\n\n## Options\n"));
+        assert!(replaced.ends_with("## Outcome\n\nUse Loro.\n"));
+
+        let appended = patch_section(ADR, "outcome", SectionEdit::Append, "Ship it.", 2).unwrap();
+        assert!(appended.ends_with("## Outcome\n\nUse Loro.\n\nShip it.\n"));
+
+        let deleted = patch_section(ADR, "Outcome", SectionEdit::Delete, "", 2).unwrap();
+        assert!(deleted.ends_with("## Not a heading\n```\n"));
+        let deleted = patch_section(ADR, "Context", SectionEdit::Delete, "", 2).unwrap();
+        assert!(deleted.starts_with("Intro line\n\n## Options\n"));
+        assert!(patch_section(ADR, "Risks", SectionEdit::Delete, "", 2).is_none());
+
+        let added = patch_section(ADR, "Risks", SectionEdit::Replace, "Lock-in.", 3).unwrap();
+        assert!(added.ends_with("Use Loro.\n\n### Risks\n\nLock-in.\n"));
+        assert_eq!(
+            patch_section("", "Context", SectionEdit::Append, "Why.", 2).unwrap(),
+            "## Context\n\nWhy.\n"
+        );
+    }
+}
@@ -2,7 +2,7 @@
 use chrono::NaiveDate;
 use serde::{Deserialize, Serialize};
 
-use super::EntityBase;
+use super::{EntityBase, Recurrence};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
 #[serde(rename_all = "lowercase")]
@@ -29,7 +29,7 @@ impl std::str::FromStr for TaskStatus {
     type Err = String;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        match s.to_lowercase().replace('-', "_").as_str() {
+        match s.trim().to_lowercase().replace('-', "_").as_str() {
             "todo" => Ok(TaskStatus::Todo),
             "in_progress" | "inprogress" => Ok(TaskStatus::InProgress),
             "done" => Ok(TaskStatus::Done),
@@ -39,7 +39,7 @@ impl std::str::FromStr for TaskStatus {
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, Default)]
 #[serde(rename_all = "lowercase")]
 pub enum TaskPriority {
     Low,
@@ -64,7 +64,7 @@ impl std::str::FromStr for TaskPriority {
     type Err = String;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        match s.to_lowercase().as_str() {
+        match s.trim().to_lowercase().as_str() {
             "low" => Ok(TaskPriority::Low),
             "normal" => Ok(TaskPriority::Normal),
             "high" => Ok(TaskPriority::High),
@@ -82,6 +82,12 @@ pub struct Task {
     pub priority: TaskPriority,
     pub due_date: Option<NaiveDate>,
     pub assignee: Option<String>,
+    /// Estimated effort in minutes
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub estimate_minutes: Option<u32>,
+    /// When set, completing the task creates its next occurrence
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub recurrence: Option<Recurrence>,
 }
 
 impl Task {
@@ -92,6 +98,43 @@ impl Task {
             priority: TaskPriority::default(),
             due_date: None,
             assignee: None,
+            estimate_minutes: None,
+            recurrence: None,
         }
     }
+
+    /// The next occurrence of a recurring task: a fresh `todo` copy due on
+    /// the next date of its recurrence. None if the task does not recur.
+    pub fn next_occurrence(&self, sequence_number: u32, today: NaiveDate) -> Option<Task> {
+        let recurrence = self.recurrence.as_ref()?;
+        let due_date = recurrence.next_due(self.due_date, today)?;
+
+        let mut next = Task::new(self.base.title.clone(), sequence_number);
+        next.base.content = self.base.content.clone();
+        next.base.tags = self.base.tags.clone();
+        next.base.created_by = self.base.created_by.clone();
+        next.priority = self.priority;
+        next.due_date = Some(due_date);
+        next.assignee = self.assignee.clone();
+        next.estimate_minutes = self.estimate_minutes;
+        next.recurrence = Some(recurrence.clone());
+        Some(next)
+    }
+}
+
+/// Parse a look-ahead window for due-date reports into a number of days:
+/// `7`, `7d`, `2w`.
+pub fn parse_due_window(s: &str) -> Result<u32, String> {
+    let s = s.trim().to_lowercase();
+    let (count, multiplier) = if let Some(weeks) = s.strip_suffix('w') {
+        (weeks, 7)
+    } else {
+        (s.strip_suffix('d').unwrap_or(&s), 1)
+    };
+    count
+        .trim()
+        .parse::<u32>()
+        .ok()
+        .and_then(|n| n.checked_mul(multiplier))
+        .ok_or_else(|| format!("Invalid window: {} (expected e.g. 7d or 2w)", s))
 }
@@ -0,0 +1,54 @@
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+
+/// A reusable starting point for new entities: default tags, properties
+/// (status, priority, ...) and a content skeleton.
+///
+/// The content may contain `{{title}}` and `{{date}}` placeholders, filled
+/// in with the new entity's title and today's date when the template is
+/// applied.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EntityTemplate {
+    /// Unique template name
+    pub name: String,
+    /// Type of entity the template creates
+    pub entity_type: String,
+    /// What the template is for
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// Content skeleton
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content: Option<String>,
+    /// Tags added to every entity created from the template
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Default type-specific properties, as accepted by `entity_create`
+    #[serde(default)]
+    pub properties: serde_json::Map<String, serde_json::Value>,
+    pub created_at: DateTime<Utc>,
+    pub created_by: Option<String>,
+}
+
+impl EntityTemplate {
+    pub fn new(name: String, entity_type: String) -> Self {
+        Self {
+            name,
+            entity_type,
+            description: None,
+            content: None,
+            tags: Vec::new(),
+            properties: serde_json::Map::new(),
+            created_at: Utc::now(),
+            created_by: None,
+        }
+    }
+
+    /// The content skeleton with its placeholders filled in.
+    pub fn render_content(&self, title: &str, date: NaiveDate) -> Option<String> {
+        self.content.as_ref().map(|content| {
+            content
+                .replace("{{title}}", title)
+                .replace("{{date}}", &date.to_string())
+        })
+    }
+}
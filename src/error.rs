@@ -31,6 +31,21 @@ pub enum MedullaError {
 
     #[error("Embedding error: {0}")]
     Embedding(String),
+
+    #[error("Config error: {0}")]
+    Config(String),
+
+    #[error("CSV error: {0}")]
+    Csv(#[from] csv::Error),
+
+    #[error("Watch error: {0}")]
+    Watch(#[from] notify::Error),
+
+    #[error("Fetch error: {0}")]
+    Fetch(String),
+
+    #[error("Template error: {0}")]
+    Template(String),
 }
 
 pub type Result<T> = std::result::Result<T, MedullaError>;
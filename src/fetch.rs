@@ -0,0 +1,465 @@
+//! Fetching the pages links point to.
+//!
+//! Adding a link with `--fetch` (or with `fetch_link_metadata: true` in the
+//! config) reads its page for a title, description and favicon and records
+//! a hash of the body, since titles given by hand or by agents are often
+//! just the URL or a placeholder. `medulla links check` fetches every link
+//! again to report those that are dead or whose page has changed.
+
+use std::io::{self, Read};
+use std::net::{IpAddr, SocketAddr, ToSocketAddrs};
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use chrono::Utc;
+use regex::Regex;
+use serde::Serialize;
+use url::Url;
+
+use crate::entity::Link;
+use crate::error::{MedullaError, Result};
+use crate::snapshot::utils::bytes_hash;
+use crate::storage::LinkFetch;
+
+/// How long a fetch may take before the link counts as unreachable
+const FETCH_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Most of a page body read; metadata sits in the head well before this
+const MAX_BODY_BYTES: u64 = 2 * 1024 * 1024;
+
+/// Redirects followed before giving up
+const MAX_REDIRECTS: u32 = 5;
+
+/// Title, description and favicon read from a page's HTML
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PageMetadata {
+    pub title: Option<String>,
+    pub description: Option<String>,
+    /// Absolute favicon URL
+    pub favicon: Option<String>,
+}
+
+/// A successfully fetched page
+#[derive(Debug, Clone)]
+pub struct FetchedPage {
+    /// URL the page was served from, after redirects
+    pub final_url: String,
+    pub metadata: PageMetadata,
+    pub content_hash: String,
+}
+
+impl FetchedPage {
+    /// Metadata to record on the link
+    pub fn to_link_fetch(&self) -> LinkFetch {
+        LinkFetch {
+            description: self.metadata.description.clone(),
+            favicon: self.metadata.favicon.clone(),
+            content_hash: Some(self.content_hash.clone()),
+            fetched_at: Utc::now(),
+        }
+    }
+
+    /// Fill a link not yet stored with the page's metadata; the page title
+    /// replaces the link's title when there is one
+    pub fn apply_to(&self, link: &mut Link) {
+        if let Some(title) = &self.metadata.title {
+            link.base.title = title.clone();
+        }
+        let fetch = self.to_link_fetch();
+        link.description = fetch.description;
+        link.favicon = fetch.favicon;
+        link.content_hash = fetch.content_hash;
+        link.fetched_at = Some(fetch.fetched_at);
+    }
+}
+
+/// Fetch a URL, failing on transport errors and non-success statuses.
+/// Metadata is only read from HTML pages; any body is hashed.
+pub fn fetch_page(url: &str) -> Result<FetchedPage> {
+    fetch(url, false)
+}
+
+/// Fetch a URL like `fetch_page`, refusing hosts (including redirect
+/// targets) that resolve to loopback, private or link-local addresses.
+/// Used for URLs given by agents, which shouldn't reach internal services.
+pub fn fetch_public_page(url: &str) -> Result<FetchedPage> {
+    fetch(url, true)
+}
+
+fn fetch(url: &str, public_only: bool) -> Result<FetchedPage> {
+    let mut builder = ureq::AgentBuilder::new()
+        .timeout(FETCH_TIMEOUT)
+        .redirects(MAX_REDIRECTS)
+        .user_agent(concat!("medulla/", env!("CARGO_PKG_VERSION")));
+    if public_only {
+        builder = builder.resolver(resolve_public);
+    }
+    let agent = builder.build();
+
+    let response = agent.get(url).call().map_err(|e| match e {
+        ureq::Error::Status(code, response) => {
+            MedullaError::Fetch(format!("HTTP {} {}", code, response.status_text()))
+        }
+        ureq::Error::Transport(t) => MedullaError::Fetch(t.to_string()),
+    })?;
+
+    let final_url = response.get_url().to_string();
+    let is_html = response.content_type().contains("html");
+    let mut body = Vec::new();
+    response
+        .into_reader()
+        .take(MAX_BODY_BYTES)
+        .read_to_end(&mut body)?;
+
+    let metadata = match (is_html, Url::parse(&final_url)) {
+        (true, Ok(base)) => parse_page_metadata(&String::from_utf8_lossy(&body), &base),
+        _ => PageMetadata::default(),
+    };
+
+    Ok(FetchedPage {
+        final_url,
+        metadata,
+        content_hash: bytes_hash(&body),
+    })
+}
+
+/// Resolve a `host:port`, failing if any address isn't public. Checking at
+/// resolution covers every redirect and the address actually connected to.
+fn resolve_public(netloc: &str) -> io::Result<Vec<SocketAddr>> {
+    let addrs: Vec<SocketAddr> = netloc.to_socket_addrs()?.collect();
+    if let Some(addr) = addrs.iter().find(|addr| !is_public_ip(addr.ip())) {
+        return Err(io::Error::new(
+            io::ErrorKind::PermissionDenied,
+            format!("{} is not a public address", addr.ip()),
+        ));
+    }
+    Ok(addrs)
+}
+
+/// Whether an address is reachable on the public internet, as opposed to
+/// loopback, private, link-local, shared or unspecified
+fn is_public_ip(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => {
+            let [a, b, ..] = ip.octets();
+            !(ip.is_loopback()
+                || ip.is_private()
+                || ip.is_link_local()
+                || ip.is_unspecified()
+                || ip.is_broadcast()
+                || a == 0
+                || (a == 100 && (64..128).contains(&b)))
+        }
+        IpAddr::V6(ip) => {
+            if let Some(v4) = ip.to_ipv4_mapped() {
+                return is_public_ip(IpAddr::V4(v4));
+            }
+            let first = ip.segments()[0];
+            !(ip.is_loopback()
+                || ip.is_unspecified()
+                || (first & 0xfe00) == 0xfc00
+                || (first & 0xffc0) == 0xfe80)
+        }
+    }
+}
+
+/// Read the title, description and favicon of an HTML page served from
+/// `base`. Open Graph tags fill in for a missing `<title>` or description,
+/// and the favicon falls back to `/favicon.ico`.
+pub fn parse_page_metadata(html: &str, base: &Url) -> PageMetadata {
+    static TITLE: OnceLock<Regex> = OnceLock::new();
+    static TAG: OnceLock<Regex> = OnceLock::new();
+    let title_re =
+        TITLE.get_or_init(|| Regex::new(r"(?is)<title[^>]*>(.*?)</title>").expect("valid regex"));
+    let tag_re =
+        TAG.get_or_init(|| Regex::new(r"(?is)<(meta|link)\s([^>]*)>").expect("valid regex"));
+
+    let mut meta = std::collections::HashMap::new();
+    let mut icon = None;
+    for tag in tag_re.captures_iter(html) {
+        let attrs = parse_attributes(&tag[2]);
+        let attr = |name: &str| {
+            attrs
+                .iter()
+                .find(|(k, _)| k == name)
+                .map(|(_, v)| v.as_str())
+        };
+        if tag[1].eq_ignore_ascii_case("meta") {
+            if let (Some(key), Some(content)) = (attr("name").or(attr("property")), attr("content"))
+            {
+                meta.entry(key.to_lowercase())
+                    .or_insert_with(|| content.to_string());
+            }
+        } else if icon.is_none()
+            && attr("rel").is_some_and(|rel| {
+                rel.split_whitespace()
+                    .any(|r| r.eq_ignore_ascii_case("icon"))
+            })
+        {
+            icon = attr("href").map(str::to_string);
+        }
+    }
+
+    let clean = |text: &str| {
+        let text = decode_entities(text);
+        let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+        (!text.is_empty()).then_some(text)
+    };
+    let title = title_re
+        .captures(html)
+        .and_then(|c| clean(&c[1]))
+        .or_else(|| meta.get("og:title").and_then(|t| clean(t)));
+    let description = meta
+        .get("description")
+        .or_else(|| meta.get("og:description"))
+        .and_then(|d| clean(d));
+    let favicon = base
+        .join(icon.as_deref().unwrap_or("/favicon.ico"))
+        .ok()
+        .map(|u| u.to_string());
+
+    PageMetadata {
+        title,
+        description,
+        favicon,
+    }
+}
+
+/// Attributes of an HTML tag as lowercase name and value pairs
+fn parse_attributes(attrs: &str) -> Vec<(String, String)> {
+    static ATTR: OnceLock<Regex> = OnceLock::new();
+    let attr_re = ATTR.get_or_init(|| {
+        Regex::new(r#"([A-Za-z_:][-A-Za-z0-9_:.]*)\s*=\s*(?:"([^"]*)"|'([^']*)'|([^\s"'>]+))"#)
+            .expect("valid regex")
+    });
+    attr_re
+        .captures_iter(attrs)
+        .map(|c| {
+            let value = c
+                .get(2)
+                .or(c.get(3))
+                .or(c.get(4))
+                .map_or("", |m| m.as_str());
+            (c[1].to_lowercase(), decode_entities(value))
+        })
+        .collect()
+}
+
+/// Decode the character references common in titles and descriptions
+fn decode_entities(text: &str) -> String {
+    static ENTITY: OnceLock<Regex> = OnceLock::new();
+    let entity_re = ENTITY.get_or_init(|| {
+        Regex::new(r"&(#[0-9]+|#[xX][0-9a-fA-F]+|[a-zA-Z]+);").expect("valid regex")
+    });
+    entity_re
+        .replace_all(text, |c: &regex::Captures| {
+            let name = &c[1];
+            let decoded = match name {
+                "amp" => Some('&'),
+                "lt" => Some('<'),
+                "gt" => Some('>'),
+                "quot" => Some('"'),
+                "apos" => Some('\''),
+                "nbsp" => Some(' '),
+                _ => name
+                    .strip_prefix("#x")
+                    .or_else(|| name.strip_prefix("#X"))
+                    .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+                    .or_else(|| name.strip_prefix('#').and_then(|dec| dec.parse().ok()))
+                    .and_then(char::from_u32),
+            };
+            decoded.map_or_else(|| c[0].to_string(), String::from)
+        })
+        .into_owned()
+}
+
+/// State of a link's URL found by `check_link`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LinkState {
+    /// Reachable, and unchanged since last fetched (or never fetched)
+    Ok,
+    /// Reachable, but the body differs from the recorded hash
+    Changed,
+    /// Unreachable or answering with an error status
+    Dead,
+}
+
+impl std::fmt::Display for LinkState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LinkState::Ok => write!(f, "ok"),
+            LinkState::Changed => write!(f, "changed"),
+            LinkState::Dead => write!(f, "dead"),
+        }
+    }
+}
+
+/// Result of checking one link
+#[derive(Debug, Clone, Serialize)]
+pub struct LinkCheck {
+    pub id: String,
+    pub sequence_number: u32,
+    pub title: String,
+    pub url: String,
+    pub state: LinkState,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// Where the URL now redirects, if elsewhere
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub redirected_to: Option<String>,
+    /// The fetched page, unless the link is dead
+    #[serde(skip)]
+    pub page: Option<FetchedPage>,
+}
+
+/// Fetch a link's URL and compare the page with what was last recorded
+pub fn check_link(link: &Link) -> LinkCheck {
+    let mut check = LinkCheck {
+        id: link.base.id.to_string(),
+        sequence_number: link.base.sequence_number,
+        title: link.base.title.clone(),
+        url: link.url.clone(),
+        state: LinkState::Ok,
+        error: None,
+        redirected_to: None,
+        page: None,
+    };
+    match fetch_page(&link.url) {
+        Ok(page) => {
+            if link
+                .content_hash
+                .as_ref()
+                .is_some_and(|hash| *hash != page.content_hash)
+            {
+                check.state = LinkState::Changed;
+            }
+            if page.final_url.trim_end_matches('/') != link.url.trim_end_matches('/') {
+                check.redirected_to = Some(page.final_url.clone());
+            }
+            check.page = Some(page);
+        }
+        Err(e) => {
+            check.state = LinkState::Dead;
+            check.error = Some(match e {
+                MedullaError::Fetch(message) => message,
+                other => other.to_string(),
+            });
+        }
+    }
+    check
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use std::net::TcpListener;
+
+    #[test]
+    fn test_parse_page_metadata() {
+        let html = r#"<html><head>
+            <title>
+              Loro &amp; CRDTs &#8211; Guide
+            </title>
+            <meta property="og:title" content="Ignored">
+            <meta name="Description" content='Sync without a server'>
+            <link rel="shortcut icon" href="/static/icon.png">
+            </head><body></body></html>"#;
+        let base = Url::parse("https://example.com/docs/guide").unwrap();
+        let metadata = parse_page_metadata(html, &base);
+        assert_eq!(
+            metadata.title.as_deref(),
+            Some("Loro & CRDTs \u{2013} Guide")
+        );
+        assert_eq!(
+            metadata.description.as_deref(),
+            Some("Sync without a server")
+        );
+        assert_eq!(
+            metadata.favicon.as_deref(),
+            Some("https://example.com/static/icon.png")
+        );
+
+        // Open Graph fills in, and the favicon defaults to the site root
+        let html = r#"<meta property="og:title" content="From OG"><meta property="og:description" content="OG text">"#;
+        let metadata = parse_page_metadata(html, &base);
+        assert_eq!(metadata.title.as_deref(), Some("From OG"));
+        assert_eq!(metadata.description.as_deref(), Some("OG text"));
+        assert_eq!(
+            metadata.favicon.as_deref(),
+            Some("https://example.com/favicon.ico")
+        );
+    }
+
+    /// Serve one canned HTTP response on a local port
+    fn serve_once(status: &str, body: &'static str) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let status = status.to_string();
+        std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut request = [0u8; 1024];
+            let _ = stream.read(&mut request);
+            let _ = write!(
+                stream,
+                "HTTP/1.1 {}\r\nContent-Type: text/html\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                status,
+                body.len(),
+                body
+            );
+        });
+        format!("http://{}/page", addr)
+    }
+
+    #[test]
+    fn test_fetch_and_check_link() {
+        let url = serve_once("200 OK", "<title>Runbook</title>");
+        let page = fetch_page(&url).unwrap();
+        let mut link = Link::new(url.clone(), url, 1);
+        page.apply_to(&mut link);
+        assert_eq!(link.base.title, "Runbook");
+        assert_eq!(
+            link.content_hash.as_deref(),
+            Some(page.content_hash.as_str())
+        );
+        assert!(link.fetched_at.is_some());
+
+        link.url = serve_once("200 OK", "<title>Runbook v2</title>");
+        assert_eq!(check_link(&link).state, LinkState::Changed);
+
+        link.url = serve_once("404 Not Found", "gone");
+        let check = check_link(&link);
+        assert_eq!(check.state, LinkState::Dead);
+        assert_eq!(check.error.as_deref(), Some("HTTP 404 Not Found"));
+    }
+
+    #[test]
+    fn test_fetch_public_page_refuses_internal_hosts() {
+        let url = serve_once("200 OK", "<title>Admin</title>");
+        assert!(matches!(
+            fetch_public_page(&url),
+            Err(MedullaError::Fetch(_))
+        ));
+
+        for ip in [
+            "127.0.0.1",
+            "10.1.2.3",
+            "192.168.0.1",
+            "169.254.169.254",
+            "::1",
+            "fe80::1",
+            "fd00::1",
+            "::ffff:127.0.0.1",
+        ] {
+            assert!(
+                !is_public_ip(ip.parse().unwrap()),
+                "{} should not be public",
+                ip
+            );
+        }
+        for ip in ["93.184.216.34", "2606:4700::1111"] {
+            assert!(is_public_ip(ip.parse().unwrap()), "{} should be public", ip);
+        }
+    }
+}
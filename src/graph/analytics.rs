@@ -0,0 +1,363 @@
+//! Centrality and cluster analysis of the relation graph.
+//!
+//! Relations are treated as undirected links here: a decision that many
+//! tasks implement and a component that many notes document are both hubs,
+//! whichever way the relations point. Parallel relations between the same
+//! two entities count once towards betweenness but each towards degree.
+
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use uuid::Uuid;
+
+use crate::error::Result;
+use crate::storage::LoroStore;
+
+use super::{Graph, GraphNode};
+
+/// Rounds of label propagation before giving up on convergence
+const MAX_PROPAGATION_ROUNDS: usize = 20;
+
+/// Centrality scores of one entity.
+#[derive(Debug, Clone)]
+pub struct Centrality {
+    pub node: GraphNode,
+    /// Relations pointing at the entity
+    pub in_degree: usize,
+    /// Relations starting at the entity
+    pub out_degree: usize,
+    /// Share of shortest paths between other entities that pass through
+    /// this one, from 0 to 1
+    pub betweenness: f64,
+}
+
+impl Centrality {
+    pub fn degree(&self) -> usize {
+        self.in_degree + self.out_degree
+    }
+}
+
+/// Centrality, connected components and communities of the relation graph.
+#[derive(Debug, Clone)]
+pub struct GraphAnalytics {
+    pub nodes: usize,
+    pub edges: usize,
+    /// Every entity with at least one relation, most connected first
+    pub centrality: Vec<Centrality>,
+    /// Connected groups of two or more entities, largest first
+    pub components: Vec<Vec<GraphNode>>,
+    /// Entities with no relations at all
+    pub isolated: usize,
+    /// Densely linked groups found by label propagation, largest first;
+    /// each lies within one component
+    pub communities: Vec<Vec<GraphNode>>,
+}
+
+impl GraphAnalytics {
+    /// Analyse every entity and active relation in the store.
+    pub fn build(store: &LoroStore) -> Result<Self> {
+        Ok(Self::from_graph(&Graph::collect(store, None, None)?))
+    }
+
+    /// Analyse an already collected graph.
+    pub fn from_graph(graph: &Graph) -> Self {
+        let mut nodes = graph.nodes.clone();
+        nodes.sort_by_key(|n| n.sequence_number);
+        let index: HashMap<Uuid, usize> =
+            nodes.iter().enumerate().map(|(i, n)| (n.id, i)).collect();
+
+        let mut in_degree = vec![0; nodes.len()];
+        let mut out_degree = vec![0; nodes.len()];
+        let mut neighbours: Vec<HashSet<usize>> = vec![HashSet::new(); nodes.len()];
+        for edge in &graph.edges {
+            let (Some(&s), Some(&t)) = (index.get(&edge.source_id), index.get(&edge.target_id))
+            else {
+                continue;
+            };
+            out_degree[s] += 1;
+            in_degree[t] += 1;
+            if s != t {
+                neighbours[s].insert(t);
+                neighbours[t].insert(s);
+            }
+        }
+        // Sorted adjacency keeps traversal order, and so the results,
+        // independent of hashing
+        let adjacency: Vec<Vec<usize>> = neighbours
+            .into_iter()
+            .map(|n| {
+                let mut n: Vec<usize> = n.into_iter().collect();
+                n.sort_unstable();
+                n
+            })
+            .collect();
+
+        let betweenness = betweenness(&adjacency);
+        let mut centrality: Vec<Centrality> = nodes
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| in_degree[*i] + out_degree[*i] > 0)
+            .map(|(i, node)| Centrality {
+                node: node.clone(),
+                in_degree: in_degree[i],
+                out_degree: out_degree[i],
+                betweenness: betweenness[i],
+            })
+            .collect();
+        centrality.sort_by(|a, b| {
+            b.degree()
+                .cmp(&a.degree())
+                .then(b.betweenness.total_cmp(&a.betweenness))
+                .then(a.node.sequence_number.cmp(&b.node.sequence_number))
+        });
+
+        let isolated = adjacency
+            .iter()
+            .enumerate()
+            .filter(|(i, n)| n.is_empty() && in_degree[*i] + out_degree[*i] == 0)
+            .count();
+        let components = groups(&nodes, &connected_components(&adjacency));
+        let communities = groups(&nodes, &label_propagation(&adjacency));
+
+        Self {
+            nodes: nodes.len(),
+            edges: graph.edges.len(),
+            centrality,
+            components,
+            isolated,
+            communities,
+        }
+    }
+
+    /// The `limit` most connected entities.
+    pub fn hubs(&self, limit: usize) -> &[Centrality] {
+        &self.centrality[..self.centrality.len().min(limit)]
+    }
+
+    /// JSON form with the top `limit` hubs and every component and
+    /// community.
+    pub fn to_json(&self, limit: usize) -> serde_json::Value {
+        let node_json = |n: &GraphNode| {
+            serde_json::json!({
+                "id": n.id.to_string(),
+                "sequence_number": n.sequence_number,
+                "type": n.entity_type,
+                "title": n.title,
+            })
+        };
+        let group_json = |group: &Vec<GraphNode>| {
+            serde_json::json!({
+                "size": group.len(),
+                "entities": group.iter().map(node_json).collect::<Vec<_>>(),
+            })
+        };
+        serde_json::json!({
+            "nodes": self.nodes,
+            "edges": self.edges,
+            "isolated": self.isolated,
+            "hubs": self.hubs(limit).iter().map(|c| {
+                let mut value = node_json(&c.node);
+                value["degree"] = c.degree().into();
+                value["in_degree"] = c.in_degree.into();
+                value["out_degree"] = c.out_degree.into();
+                value["betweenness"] = ((c.betweenness * 1000.0).round() / 1000.0).into();
+                value
+            }).collect::<Vec<_>>(),
+            "components": self.components.iter().map(group_json).collect::<Vec<_>>(),
+            "communities": self.communities.iter().map(group_json).collect::<Vec<_>>(),
+        })
+    }
+}
+
+/// Normalised betweenness centrality of every node (Brandes' algorithm on
+/// the unweighted, undirected graph).
+fn betweenness(adjacency: &[Vec<usize>]) -> Vec<f64> {
+    let n = adjacency.len();
+    let mut scores = vec![0.0; n];
+    for source in 0..n {
+        let mut order = Vec::new();
+        let mut predecessors: Vec<Vec<usize>> = vec![Vec::new(); n];
+        let mut paths = vec![0.0; n];
+        let mut distance: Vec<Option<usize>> = vec![None; n];
+        paths[source] = 1.0;
+        distance[source] = Some(0);
+
+        let mut queue = VecDeque::from([source]);
+        while let Some(v) = queue.pop_front() {
+            order.push(v);
+            let next = distance[v].unwrap_or_default() + 1;
+            for &w in &adjacency[v] {
+                if distance[w].is_none() {
+                    distance[w] = Some(next);
+                    queue.push_back(w);
+                }
+                if distance[w] == Some(next) {
+                    paths[w] += paths[v];
+                    predecessors[w].push(v);
+                }
+            }
+        }
+
+        let mut dependency = vec![0.0; n];
+        for &w in order.iter().rev() {
+            for &v in &predecessors[w] {
+                dependency[v] += paths[v] / paths[w] * (1.0 + dependency[w]);
+            }
+            if w != source {
+                scores[w] += dependency[w];
+            }
+        }
+    }
+
+    // Every pair was counted from both ends
+    let pairs = if n > 2 {
+        ((n - 1) * (n - 2)) as f64
+    } else {
+        1.0
+    };
+    scores.iter().map(|s| s / pairs).collect()
+}
+
+/// Component label of every node, the lowest node index in its component.
+fn connected_components(adjacency: &[Vec<usize>]) -> Vec<usize> {
+    let mut label: Vec<Option<usize>> = vec![None; adjacency.len()];
+    for start in 0..adjacency.len() {
+        if label[start].is_some() {
+            continue;
+        }
+        label[start] = Some(start);
+        let mut queue = VecDeque::from([start]);
+        while let Some(v) = queue.pop_front() {
+            for &w in &adjacency[v] {
+                if label[w].is_none() {
+                    label[w] = Some(start);
+                    queue.push_back(w);
+                }
+            }
+        }
+    }
+    label.into_iter().map(|l| l.unwrap_or_default()).collect()
+}
+
+/// Community label of every node by label propagation: each node in turn
+/// takes the label most common among its neighbours (the lowest on a tie)
+/// until no label changes.
+fn label_propagation(adjacency: &[Vec<usize>]) -> Vec<usize> {
+    let mut label: Vec<usize> = (0..adjacency.len()).collect();
+    for _ in 0..MAX_PROPAGATION_ROUNDS {
+        let mut changed = false;
+        for v in 0..adjacency.len() {
+            if adjacency[v].is_empty() {
+                continue;
+            }
+            let mut counts: HashMap<usize, usize> = HashMap::new();
+            for &w in &adjacency[v] {
+                *counts.entry(label[w]).or_default() += 1;
+            }
+            let best = counts
+                .into_iter()
+                .max_by(|a, b| a.1.cmp(&b.1).then(b.0.cmp(&a.0)))
+                .map(|(l, _)| l)
+                .unwrap_or(label[v]);
+            if best != label[v] {
+                label[v] = best;
+                changed = true;
+            }
+        }
+        if !changed {
+            break;
+        }
+    }
+    label
+}
+
+/// Groups of two or more nodes sharing a label, largest first.
+fn groups(nodes: &[GraphNode], labels: &[usize]) -> Vec<Vec<GraphNode>> {
+    let mut by_label: HashMap<usize, Vec<GraphNode>> = HashMap::new();
+    for (node, &l) in nodes.iter().zip(labels) {
+        by_label.entry(l).or_default().push(node.clone());
+    }
+    let mut groups: Vec<Vec<GraphNode>> = by_label.into_values().filter(|g| g.len() > 1).collect();
+    groups.sort_by(|a, b| {
+        b.len()
+            .cmp(&a.len())
+            .then(a[0].sequence_number.cmp(&b[0].sequence_number))
+    });
+    groups
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::entity::{Decision, Relation, RelationType, Task};
+    use tempfile::TempDir;
+
+    fn relate(store: &LoroStore, source: Uuid, target: Uuid, relation_type: RelationType) {
+        store
+            .add_relation(&Relation::new(
+                source,
+                "task".to_string(),
+                target,
+                "decision".to_string(),
+                relation_type,
+            ))
+            .unwrap();
+    }
+
+    #[test]
+    fn test_graph_analytics() {
+        let tmp = TempDir::new().unwrap();
+        let store = LoroStore::init(tmp.path()).unwrap();
+
+        // Two stars joined through a bridge task, plus one loose task
+        let hub = Decision::new("Use Postgres".to_string(), 1);
+        store.add_decision(&hub).unwrap();
+        let other = Decision::new("Use Redis".to_string(), 2);
+        store.add_decision(&other).unwrap();
+        let mut tasks = Vec::new();
+        for seq in 3..=8 {
+            let task = Task::new(format!("Task {}", seq), seq);
+            store.add_task(&task).unwrap();
+            tasks.push(task.base.id);
+        }
+        for task in &tasks[..3] {
+            relate(&store, *task, hub.base.id, RelationType::Implements);
+        }
+        relate(&store, tasks[3], other.base.id, RelationType::Implements);
+        relate(&store, tasks[4], other.base.id, RelationType::Implements);
+        relate(&store, tasks[2], other.base.id, RelationType::References);
+
+        let analytics = GraphAnalytics::build(&store).unwrap();
+        assert_eq!(analytics.nodes, 8);
+        assert_eq!(analytics.edges, 6);
+        assert_eq!(analytics.isolated, 1);
+
+        let top = &analytics.hubs(1)[0];
+        assert_eq!(top.node.title, "Use Postgres");
+        assert_eq!((top.in_degree, top.out_degree), (3, 0));
+        // Every path between the two stars runs through the bridge task:
+        // 9 of the 21 pairs of other entities
+        let score = |title: &str| {
+            analytics
+                .centrality
+                .iter()
+                .find(|c| c.node.title == title)
+                .unwrap()
+                .betweenness
+        };
+        assert!((score("Task 5") - 9.0 / 21.0).abs() < 1e-9);
+        assert_eq!(score("Task 3"), 0.0);
+
+        assert_eq!(analytics.components.len(), 1);
+        assert_eq!(analytics.components[0].len(), 7);
+        let communities: Vec<Vec<u32>> = analytics
+            .communities
+            .iter()
+            .map(|c| c.iter().map(|n| n.sequence_number).collect())
+            .collect();
+        assert_eq!(communities, vec![vec![1, 3, 4, 5], vec![2, 6, 7]]);
+
+        let json = analytics.to_json(3);
+        assert_eq!(json["hubs"].as_array().unwrap().len(), 3);
+        assert_eq!(json["components"][0]["size"], 7);
+    }
+}
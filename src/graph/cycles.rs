@@ -0,0 +1,260 @@
+//! Cycles among `blocks` relations.
+//!
+//! An entity on a `blocks` cycle waits on itself, so none of the tasks
+//! involved ever become ready. New relations that would close a cycle are
+//! rejected, and existing cycles (e.g. merged in from another branch) can be
+//! listed with `medulla graph cycles`.
+
+use std::collections::{HashMap, VecDeque};
+
+use uuid::Uuid;
+
+use crate::entity::RelationType;
+use crate::error::Result;
+use crate::storage::LoroStore;
+
+use super::{list_bases, GraphNode};
+
+/// Entities by ID, and the `blocks` relations between them as
+/// (blocker, blocked)
+type BlocksGraph = (HashMap<Uuid, GraphNode>, Vec<(Uuid, Uuid)>);
+
+/// Active `blocks` relations as (blocker, blocked), plus the entities they
+/// connect. Relations to deleted entities are ignored.
+fn blocks_edges(store: &LoroStore) -> Result<BlocksGraph> {
+    let nodes: HashMap<Uuid, GraphNode> = list_bases(store)?
+        .into_iter()
+        .map(|(entity_type, base)| {
+            (
+                base.id,
+                GraphNode {
+                    id: base.id,
+                    sequence_number: base.sequence_number,
+                    entity_type,
+                    title: base.title,
+                },
+            )
+        })
+        .collect();
+
+    let edges = store
+        .list_relations()?
+        .into_iter()
+        .filter(|r| {
+            r.is_active()
+                && r.relation_type == RelationType::Blocks
+                && nodes.contains_key(&r.source_id)
+                && nodes.contains_key(&r.target_id)
+        })
+        .map(|r| (r.source_id, r.target_id))
+        .collect();
+
+    Ok((nodes, edges))
+}
+
+/// The cycle a new `source blocks target` relation would close, starting at
+/// `source`, or None if it is safe to add.
+pub fn blocks_cycle_with(
+    store: &LoroStore,
+    source: Uuid,
+    target: Uuid,
+) -> Result<Option<Vec<GraphNode>>> {
+    let (nodes, edges) = blocks_edges(store)?;
+
+    // The new relation closes a cycle if `target` already blocks `source`,
+    // directly or through other entities
+    let mut parent: HashMap<Uuid, Uuid> = HashMap::new();
+    let mut queue = VecDeque::from([target]);
+    let mut found = source == target;
+    while let Some(id) = queue.pop_front() {
+        if found {
+            break;
+        }
+        for &(blocker, blocked) in &edges {
+            if blocker != id || blocked == target || parent.contains_key(&blocked) {
+                continue;
+            }
+            parent.insert(blocked, id);
+            if blocked == source {
+                found = true;
+                break;
+            }
+            queue.push_back(blocked);
+        }
+    }
+    if !found {
+        return Ok(None);
+    }
+
+    // Walk back from `source` to `target`, then put `source` first
+    let mut path = vec![source];
+    let mut id = source;
+    while id != target {
+        id = parent[&id];
+        path.push(id);
+    }
+    path.reverse();
+    path.rotate_right(1);
+
+    Ok(Some(
+        path.iter()
+            .filter_map(|id| nodes.get(id).cloned())
+            .collect(),
+    ))
+}
+
+/// Groups of entities that block each other in a cycle, each ordered by
+/// sequence number, largest group first.
+pub fn blocks_cycles(store: &LoroStore) -> Result<Vec<Vec<GraphNode>>> {
+    let (nodes, edges) = blocks_edges(store)?;
+
+    let mut ids: Vec<Uuid> = edges.iter().flat_map(|&(a, b)| [a, b]).collect();
+    ids.sort();
+    ids.dedup();
+    let index: HashMap<Uuid, usize> = ids.iter().enumerate().map(|(i, id)| (*id, i)).collect();
+    let mut out: Vec<Vec<usize>> = vec![Vec::new(); ids.len()];
+    let mut inn: Vec<Vec<usize>> = vec![Vec::new(); ids.len()];
+    for (blocker, blocked) in &edges {
+        out[index[blocker]].push(index[blocked]);
+        inn[index[blocked]].push(index[blocker]);
+    }
+
+    let mut cycles: Vec<Vec<GraphNode>> = strongly_connected(&out, &inn)
+        .into_iter()
+        .filter(|group| group.len() > 1 || out[group[0]].contains(&group[0]))
+        .map(|group| {
+            let mut members: Vec<GraphNode> =
+                group.iter().map(|&i| nodes[&ids[i]].clone()).collect();
+            members.sort_by_key(|n| n.sequence_number);
+            members
+        })
+        .collect();
+    cycles.sort_by(|a, b| {
+        b.len()
+            .cmp(&a.len())
+            .then(a[0].sequence_number.cmp(&b[0].sequence_number))
+    });
+
+    Ok(cycles)
+}
+
+/// Strongly connected components (Kosaraju), given successor and
+/// predecessor lists.
+fn strongly_connected(out: &[Vec<usize>], inn: &[Vec<usize>]) -> Vec<Vec<usize>> {
+    let n = out.len();
+
+    // Order nodes by DFS finish time
+    let mut visited = vec![false; n];
+    let mut finished = Vec::with_capacity(n);
+    for start in 0..n {
+        if visited[start] {
+            continue;
+        }
+        visited[start] = true;
+        let mut stack = vec![(start, 0)];
+        while let Some((node, next)) = stack.pop() {
+            if let Some(&succ) = out[node].get(next) {
+                stack.push((node, next + 1));
+                if !visited[succ] {
+                    visited[succ] = true;
+                    stack.push((succ, 0));
+                }
+            } else {
+                finished.push(node);
+            }
+        }
+    }
+
+    // Each DFS over reversed edges, latest finish first, is one component
+    let mut assigned = vec![false; n];
+    let mut components = Vec::new();
+    for &start in finished.iter().rev() {
+        if assigned[start] {
+            continue;
+        }
+        assigned[start] = true;
+        let mut component = vec![start];
+        let mut stack = vec![start];
+        while let Some(node) = stack.pop() {
+            for &pred in &inn[node] {
+                if !assigned[pred] {
+                    assigned[pred] = true;
+                    component.push(pred);
+                    stack.push(pred);
+                }
+            }
+        }
+        components.push(component);
+    }
+    components
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::entity::{Relation, Task};
+    use tempfile::TempDir;
+
+    fn setup(count: u32) -> (LoroStore, TempDir, Vec<Uuid>) {
+        let tmp = TempDir::new().unwrap();
+        let store = LoroStore::init(tmp.path()).unwrap();
+        let ids = (1..=count)
+            .map(|seq| {
+                let task = Task::new(format!("Task {}", seq), seq);
+                store.add_task(&task).unwrap();
+                task.base.id
+            })
+            .collect();
+        (store, tmp, ids)
+    }
+
+    fn block(store: &LoroStore, blocker: Uuid, blocked: Uuid) {
+        store
+            .add_relation(&Relation::new(
+                blocker,
+                "task".to_string(),
+                blocked,
+                "task".to_string(),
+                RelationType::Blocks,
+            ))
+            .unwrap();
+    }
+
+    fn seqs(nodes: &[GraphNode]) -> Vec<u32> {
+        nodes.iter().map(|n| n.sequence_number).collect()
+    }
+
+    #[test]
+    fn test_blocks_cycle_with() {
+        let (store, _tmp, ids) = setup(4);
+        block(&store, ids[0], ids[1]);
+        block(&store, ids[1], ids[2]);
+
+        // 3 blocks 1 would close 1 -> 2 -> 3 -> 1
+        let cycle = blocks_cycle_with(&store, ids[2], ids[0]).unwrap().unwrap();
+        assert_eq!(seqs(&cycle), vec![3, 1, 2]);
+
+        let itself = blocks_cycle_with(&store, ids[3], ids[3]).unwrap().unwrap();
+        assert_eq!(seqs(&itself), vec![4]);
+
+        assert!(blocks_cycle_with(&store, ids[0], ids[2]).unwrap().is_none());
+        assert!(blocks_cycle_with(&store, ids[3], ids[0]).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_blocks_cycles() {
+        let (store, _tmp, ids) = setup(6);
+        assert!(blocks_cycles(&store).unwrap().is_empty());
+
+        block(&store, ids[0], ids[1]);
+        block(&store, ids[1], ids[2]);
+        block(&store, ids[2], ids[0]);
+        block(&store, ids[2], ids[3]); // downstream of the cycle only
+        block(&store, ids[4], ids[5]);
+        block(&store, ids[5], ids[4]);
+
+        let cycles = blocks_cycles(&store).unwrap();
+        let groups: Vec<Vec<u32>> = cycles.iter().map(|c| seqs(c)).collect();
+        assert_eq!(groups, vec![vec![1, 2, 3], vec![5, 6]]);
+    }
+}
@@ -0,0 +1,223 @@
+//! Milestone progress, computed from the tasks that belong to each
+//! milestone through active `belongs_to` relations.
+
+use std::collections::HashMap;
+
+use chrono::NaiveDate;
+use uuid::Uuid;
+
+use crate::entity::{Milestone, MilestoneStatus, RelationType, Task, TaskStatus};
+use crate::error::{MedullaError, Result};
+use crate::storage::LoroStore;
+
+/// A milestone and the unarchived tasks that belong to it.
+#[derive(Debug, Clone)]
+pub struct MilestoneProgress {
+    pub milestone: Milestone,
+    pub tasks: Vec<Task>,
+}
+
+impl MilestoneProgress {
+    /// Progress of every milestone, archived ones included only when asked,
+    /// ordered by target date (undated last) and then sequence number.
+    pub fn all(store: &LoroStore, include_archived: bool) -> Result<Vec<Self>> {
+        let milestones: Vec<Milestone> = store
+            .list_milestones()?
+            .into_iter()
+            .filter(|m| include_archived || !m.base.archived)
+            .collect();
+        let mut tasks_by_milestone = Self::tasks_by_milestone(store)?;
+
+        let mut progress: Vec<Self> = milestones
+            .into_iter()
+            .map(|milestone| {
+                let tasks = tasks_by_milestone
+                    .remove(&milestone.base.id)
+                    .unwrap_or_default();
+                Self { milestone, tasks }
+            })
+            .collect();
+        progress.sort_by_key(|p| {
+            (
+                p.milestone.target_date.is_none(),
+                p.milestone.target_date,
+                p.milestone.base.sequence_number,
+            )
+        });
+        Ok(progress)
+    }
+
+    /// Progress of one milestone.
+    pub fn build(store: &LoroStore, id: Uuid) -> Result<Self> {
+        let milestone = store
+            .get_milestone(&id)?
+            .ok_or_else(|| MedullaError::EntityNotFound(id.to_string()))?;
+        let tasks = Self::tasks_by_milestone(store)?
+            .remove(&id)
+            .unwrap_or_default();
+        Ok(Self { milestone, tasks })
+    }
+
+    /// Unarchived tasks grouped by the milestone they belong to, in
+    /// sequence order.
+    fn tasks_by_milestone(store: &LoroStore) -> Result<HashMap<Uuid, Vec<Task>>> {
+        let mut tasks: HashMap<Uuid, Task> = store
+            .list_tasks()?
+            .into_iter()
+            .filter(|t| !t.base.archived)
+            .map(|t| (t.base.id, t))
+            .collect();
+
+        let mut grouped: HashMap<Uuid, Vec<Task>> = HashMap::new();
+        for r in store.list_relations()? {
+            if !r.is_active()
+                || r.relation_type != RelationType::BelongsTo
+                || r.target_type != "milestone"
+            {
+                continue;
+            }
+            // A task counts towards one milestone only once
+            if let Some(task) = tasks.remove(&r.source_id) {
+                grouped.entry(r.target_id).or_default().push(task);
+            }
+        }
+        for tasks in grouped.values_mut() {
+            tasks.sort_by_key(|t| t.base.sequence_number);
+        }
+        Ok(grouped)
+    }
+
+    /// Tasks that are done, and in total.
+    pub fn counts(&self) -> (usize, usize) {
+        let done = self
+            .tasks
+            .iter()
+            .filter(|t| t.status == TaskStatus::Done)
+            .count();
+        (done, self.tasks.len())
+    }
+
+    /// Share of tasks done, rounded down to a whole percent. A completed
+    /// milestone is 100% even without tasks; an open one without tasks is 0%.
+    pub fn percent(&self) -> u32 {
+        let (done, total) = self.counts();
+        if total == 0 {
+            return if self.milestone.status == MilestoneStatus::Completed {
+                100
+            } else {
+                0
+            };
+        }
+        (done * 100 / total) as u32
+    }
+
+    /// Whether the milestone is still open after its target date.
+    pub fn is_overdue(&self, today: NaiveDate) -> bool {
+        self.milestone.status == MilestoneStatus::Open
+            && self.milestone.target_date.is_some_and(|d| d < today)
+    }
+
+    /// One-line summary such as `3/5 tasks done (60%)`.
+    pub fn summary(&self) -> String {
+        let (done, total) = self.counts();
+        format!("{}/{} tasks done ({}%)", done, total, self.percent())
+    }
+
+    /// JSON form: the milestone summary, its counts and its tasks.
+    pub fn to_json(&self, today: NaiveDate) -> serde_json::Value {
+        let (done, total) = self.counts();
+        serde_json::json!({
+            "id": self.milestone.base.id.to_string(),
+            "sequence_number": self.milestone.base.sequence_number,
+            "title": self.milestone.base.title,
+            "status": self.milestone.status.to_string(),
+            "target_date": self.milestone.target_date.map(|d| d.to_string()),
+            "tasks_done": done,
+            "tasks_total": total,
+            "percent_complete": self.percent(),
+            "overdue": self.is_overdue(today),
+            "tasks": self
+                .tasks
+                .iter()
+                .map(|t| serde_json::json!({
+                    "id": t.base.id.to_string(),
+                    "sequence_number": t.base.sequence_number,
+                    "title": t.base.title,
+                    "status": t.status.to_string(),
+                }))
+                .collect::<Vec<_>>(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::entity::Relation;
+    use tempfile::TempDir;
+
+    fn add_task(store: &LoroStore, title: &str, seq: u32, status: TaskStatus) -> Uuid {
+        let mut task = Task::new(title.to_string(), seq);
+        task.status = status;
+        store.add_task(&task).unwrap();
+        task.base.id
+    }
+
+    fn belongs_to(store: &LoroStore, task: Uuid, milestone: Uuid) {
+        store
+            .add_relation(&Relation::new(
+                task,
+                "task".to_string(),
+                milestone,
+                "milestone".to_string(),
+                RelationType::BelongsTo,
+            ))
+            .unwrap();
+    }
+
+    #[test]
+    fn test_milestone_progress() {
+        let tmp = TempDir::new().unwrap();
+        let store = LoroStore::init(tmp.path()).unwrap();
+
+        let mut beta = Milestone::new("Beta".to_string(), 1);
+        beta.target_date = NaiveDate::from_ymd_opt(2026, 3, 1);
+        store.add_milestone(&beta).unwrap();
+        let mut alpha = Milestone::new("Alpha".to_string(), 2);
+        alpha.target_date = NaiveDate::from_ymd_opt(2026, 1, 15);
+        store.add_milestone(&alpha).unwrap();
+        let launch = Milestone::new("Launch".to_string(), 3);
+        store.add_milestone(&launch).unwrap();
+
+        let done = add_task(&store, "Auth", 4, TaskStatus::Done);
+        let todo = add_task(&store, "Billing", 5, TaskStatus::Todo);
+        let other = add_task(&store, "Docs", 6, TaskStatus::Done);
+        belongs_to(&store, done, beta.base.id);
+        belongs_to(&store, todo, beta.base.id);
+        belongs_to(&store, other, alpha.base.id);
+
+        let all = MilestoneProgress::all(&store, false).unwrap();
+        let titles: Vec<&str> = all
+            .iter()
+            .map(|p| p.milestone.base.title.as_str())
+            .collect();
+        assert_eq!(titles, vec!["Alpha", "Beta", "Launch"]);
+
+        let beta_progress = MilestoneProgress::build(&store, beta.base.id).unwrap();
+        assert_eq!(beta_progress.counts(), (1, 2));
+        assert_eq!(beta_progress.percent(), 50);
+        assert_eq!(beta_progress.summary(), "1/2 tasks done (50%)");
+        assert_eq!(all[0].percent(), 100);
+        assert_eq!(all[2].percent(), 0);
+
+        let today = NaiveDate::from_ymd_opt(2026, 2, 1).unwrap();
+        assert!(all[0].is_overdue(today));
+        assert!(!beta_progress.is_overdue(today));
+        let json = beta_progress.to_json(today);
+        assert_eq!(json["tasks_total"], 2);
+        assert_eq!(json["percent_complete"], 50);
+        assert_eq!(json["tasks"][0]["title"], "Auth");
+
+        assert!(MilestoneProgress::build(&store, Uuid::new_v4()).is_err());
+    }
+}
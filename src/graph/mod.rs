@@ -0,0 +1,329 @@
+//! Rendering of the relation graph as GraphViz DOT or Mermaid text.
+//!
+//! The graph covers either every entity and active relation, or the
+//! neighbourhood of a root entity up to a given depth (following relations
+//! in both directions). Task hierarchies live in [`tree`], milestone
+//! progress in [`milestone`], cycles of `blocks` relations are found in
+//! [`cycles`], and centrality and clusters are computed in [`analytics`].
+
+mod analytics;
+mod cycles;
+mod milestone;
+mod tree;
+
+pub use analytics::{Centrality, GraphAnalytics};
+pub use cycles::{blocks_cycle_with, blocks_cycles};
+pub use milestone::MilestoneProgress;
+pub use tree::TaskTree;
+
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use uuid::Uuid;
+
+use crate::entity::{EntityBase, Relation};
+use crate::error::Result;
+use crate::storage::LoroStore;
+
+/// Output format for graph export.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GraphFormat {
+    Dot,
+    Mermaid,
+}
+
+impl std::str::FromStr for GraphFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "dot" | "graphviz" => Ok(GraphFormat::Dot),
+            "mermaid" => Ok(GraphFormat::Mermaid),
+            _ => Err(format!(
+                "Unknown graph format: {} (expected 'dot' or 'mermaid')",
+                s
+            )),
+        }
+    }
+}
+
+impl std::fmt::Display for GraphFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GraphFormat::Dot => write!(f, "dot"),
+            GraphFormat::Mermaid => write!(f, "mermaid"),
+        }
+    }
+}
+
+/// An entity in the exported graph.
+#[derive(Debug, Clone)]
+pub struct GraphNode {
+    pub id: Uuid,
+    pub sequence_number: u32,
+    pub entity_type: &'static str,
+    pub title: String,
+}
+
+impl GraphNode {
+    /// Short label such as `#3 Write migration (task)`
+    pub fn label(&self) -> String {
+        format!(
+            "#{} {} ({})",
+            self.sequence_number, self.title, self.entity_type
+        )
+    }
+}
+
+/// Entities and the active relations between them.
+#[derive(Debug, Clone, Default)]
+pub struct Graph {
+    pub nodes: Vec<GraphNode>,
+    pub edges: Vec<Relation>,
+}
+
+fn list_bases(store: &LoroStore) -> Result<Vec<(&'static str, EntityBase)>> {
+    let mut bases = Vec::new();
+    bases.extend(
+        store
+            .list_decisions()?
+            .into_iter()
+            .map(|e| ("decision", e.base)),
+    );
+    bases.extend(store.list_tasks()?.into_iter().map(|e| ("task", e.base)));
+    bases.extend(store.list_notes()?.into_iter().map(|e| ("note", e.base)));
+    bases.extend(
+        store
+            .list_prompts()?
+            .into_iter()
+            .map(|e| ("prompt", e.base)),
+    );
+    bases.extend(
+        store
+            .list_components()?
+            .into_iter()
+            .map(|e| ("component", e.base)),
+    );
+    bases.extend(store.list_links()?.into_iter().map(|e| ("link", e.base)));
+    bases.extend(
+        store
+            .list_questions()?
+            .into_iter()
+            .map(|e| ("question", e.base)),
+    );
+    bases.extend(
+        store
+            .list_milestones()?
+            .into_iter()
+            .map(|e| ("milestone", e.base)),
+    );
+    Ok(bases)
+}
+
+impl Graph {
+    /// Collect the graph from the store. With a root, only entities within
+    /// `depth` hops of it are included (all reachable ones if `depth` is None).
+    pub fn collect(store: &LoroStore, root: Option<Uuid>, depth: Option<usize>) -> Result<Self> {
+        let mut nodes: Vec<GraphNode> = list_bases(store)?
+            .into_iter()
+            .map(|(entity_type, base)| GraphNode {
+                id: base.id,
+                sequence_number: base.sequence_number,
+                entity_type,
+                title: base.title,
+            })
+            .collect();
+        nodes.sort_by_key(|n| n.sequence_number);
+
+        let known: HashSet<Uuid> = nodes.iter().map(|n| n.id).collect();
+        let mut edges: Vec<Relation> = store
+            .list_relations()?
+            .into_iter()
+            .filter(|r| {
+                r.is_active() && known.contains(&r.source_id) && known.contains(&r.target_id)
+            })
+            .collect();
+
+        if let Some(root) = root {
+            let included = neighbourhood(root, &edges, depth);
+            nodes.retain(|n| included.contains(&n.id));
+            edges.retain(|r| included.contains(&r.source_id) && included.contains(&r.target_id));
+        }
+
+        Ok(Self { nodes, edges })
+    }
+
+    /// Render the graph in the given format.
+    pub fn render(&self, format: GraphFormat) -> String {
+        match format {
+            GraphFormat::Dot => self.to_dot(),
+            GraphFormat::Mermaid => self.to_mermaid(),
+        }
+    }
+
+    /// Render as a GraphViz digraph.
+    pub fn to_dot(&self) -> String {
+        let escape = |s: &str| s.replace('\\', "\\\\").replace('"', "\\\"");
+        let mut out = String::from("digraph medulla {\n    rankdir=LR;\n    node [shape=box];\n");
+        for node in &self.nodes {
+            out.push_str(&format!(
+                "    \"{}\" [label=\"{}\"];\n",
+                node.id,
+                escape(&node.label())
+            ));
+        }
+        for edge in &self.edges {
+            out.push_str(&format!(
+                "    \"{}\" -> \"{}\" [label=\"{}\"];\n",
+                edge.source_id,
+                edge.target_id,
+                escape(&edge.relation_type.to_string())
+            ));
+        }
+        out.push_str("}\n");
+        out
+    }
+
+    /// Render as a Mermaid flowchart, suitable for GitHub markdown.
+    pub fn to_mermaid(&self) -> String {
+        let escape = |s: &str| s.replace('"', "#quot;");
+        let ids: HashMap<Uuid, u32> = self
+            .nodes
+            .iter()
+            .map(|n| (n.id, n.sequence_number))
+            .collect();
+
+        let mut out = String::from("graph LR\n");
+        for node in &self.nodes {
+            out.push_str(&format!(
+                "    e{}[\"{}\"]\n",
+                node.sequence_number,
+                escape(&node.label())
+            ));
+        }
+        for edge in &self.edges {
+            out.push_str(&format!(
+                "    e{} -->|{}| e{}\n",
+                ids[&edge.source_id], edge.relation_type, ids[&edge.target_id]
+            ));
+        }
+        out
+    }
+}
+
+/// Entities within `depth` hops of `root`, following relations both ways.
+fn neighbourhood(root: Uuid, edges: &[Relation], depth: Option<usize>) -> HashSet<Uuid> {
+    distances(root, edges, depth).into_keys().collect()
+}
+
+/// Hops from `root` to every entity within `depth` of it (the root itself
+/// at 0), following relations both ways.
+fn distances(root: Uuid, edges: &[Relation], depth: Option<usize>) -> HashMap<Uuid, usize> {
+    let mut seen = HashMap::from([(root, 0)]);
+    let mut queue = VecDeque::from([(root, 0)]);
+    while let Some((id, hops)) = queue.pop_front() {
+        if depth.is_some_and(|d| hops >= d) {
+            continue;
+        }
+        for edge in edges {
+            let next = if edge.source_id == id {
+                edge.target_id
+            } else if edge.target_id == id {
+                edge.source_id
+            } else {
+                continue;
+            };
+            if let std::collections::hash_map::Entry::Vacant(entry) = seen.entry(next) {
+                entry.insert(hops + 1);
+                queue.push_back((next, hops + 1));
+            }
+        }
+    }
+    seen
+}
+
+/// Most relation hops followed when listing related entities
+pub const MAX_RELATED_DEPTH: usize = 3;
+
+/// Entities within `depth` hops of `root` through active relations (either
+/// direction) with their distance from it, nearest first and then by
+/// sequence number. The root itself is left out.
+pub fn related_entities(
+    store: &LoroStore,
+    root: Uuid,
+    depth: usize,
+) -> Result<Vec<(GraphNode, usize)>> {
+    let graph = Graph::collect(store, Some(root), Some(depth))?;
+    let distance = distances(root, &graph.edges, Some(depth));
+    let mut related: Vec<(GraphNode, usize)> = graph
+        .nodes
+        .into_iter()
+        .filter(|n| n.id != root)
+        .filter_map(|n| distance.get(&n.id).map(|&d| (n, d)))
+        .collect();
+    related.sort_by_key(|(n, d)| (*d, n.sequence_number));
+    Ok(related)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::entity::{Decision, RelationType, Task};
+    use tempfile::TempDir;
+
+    fn setup() -> (LoroStore, TempDir, Vec<Uuid>) {
+        let tmp = TempDir::new().unwrap();
+        let store = LoroStore::init(tmp.path()).unwrap();
+        let decision = Decision::new("Use \"Postgres\"".to_string(), 1);
+        let task = Task::new("Write migration".to_string(), 2);
+        let other = Task::new("Unrelated".to_string(), 3);
+        store.add_decision(&decision).unwrap();
+        store.add_task(&task).unwrap();
+        store.add_task(&other).unwrap();
+        store
+            .add_relation(&Relation::new(
+                task.base.id,
+                "task".to_string(),
+                decision.base.id,
+                "decision".to_string(),
+                RelationType::Implements,
+            ))
+            .unwrap();
+        let ids = vec![decision.base.id, task.base.id, other.base.id];
+        (store, tmp, ids)
+    }
+
+    #[test]
+    fn test_render_dot_and_mermaid() {
+        let (store, _tmp, ids) = setup();
+        let graph = Graph::collect(&store, None, None).unwrap();
+        assert_eq!(graph.nodes.len(), 3);
+
+        let dot = graph.render(GraphFormat::Dot);
+        assert!(dot.starts_with("digraph medulla {"));
+        assert!(dot.contains(&format!(
+            "\"{}\" -> \"{}\" [label=\"implements\"]",
+            ids[1], ids[0]
+        )));
+        assert!(dot.contains("Use \\\"Postgres\\\""));
+
+        let mermaid = graph.render(GraphFormat::Mermaid);
+        assert!(mermaid.starts_with("graph LR"));
+        assert!(mermaid.contains("e2 -->|implements| e1"));
+        assert!(mermaid.contains("#quot;Postgres#quot;"));
+    }
+
+    #[test]
+    fn test_subgraph_from_root() {
+        let (store, _tmp, ids) = setup();
+        let graph = Graph::collect(&store, Some(ids[0]), Some(1)).unwrap();
+        let included: Vec<Uuid> = graph.nodes.iter().map(|n| n.id).collect();
+        assert_eq!(included, vec![ids[0], ids[1]]);
+        assert_eq!(graph.edges.len(), 1);
+
+        let related = related_entities(&store, ids[0], 2).unwrap();
+        assert_eq!(related.len(), 1);
+        assert_eq!((related[0].0.id, related[0].1), (ids[1], 1));
+
+        assert!("svg".parse::<GraphFormat>().is_err());
+    }
+}
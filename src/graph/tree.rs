@@ -0,0 +1,200 @@
+//! Parent/child task hierarchies built from `parent_of` and `child_of`
+//! relations, with rollup progress over each subtree.
+
+use std::collections::{HashMap, HashSet};
+
+use uuid::Uuid;
+
+use crate::entity::{Relation, RelationType, Task, TaskStatus};
+use crate::error::{MedullaError, Result};
+use crate::storage::LoroStore;
+
+/// A task and its subtasks.
+#[derive(Debug, Clone)]
+pub struct TaskTree {
+    pub task: Task,
+    pub children: Vec<TaskTree>,
+}
+
+/// Map each task to its direct subtasks, from active parent/child relations
+/// in either direction.
+fn child_ids(relations: &[Relation]) -> HashMap<Uuid, Vec<Uuid>> {
+    let mut children: HashMap<Uuid, Vec<Uuid>> = HashMap::new();
+    for r in relations.iter().filter(|r| r.is_active()) {
+        let (parent, child) = match r.relation_type {
+            RelationType::ParentOf => (r.source_id, r.target_id),
+            RelationType::ChildOf => (r.target_id, r.source_id),
+            _ => continue,
+        };
+        let entry = children.entry(parent).or_default();
+        if !entry.contains(&child) {
+            entry.push(child);
+        }
+    }
+    children
+}
+
+impl TaskTree {
+    /// Build the subtask tree rooted at a task. Children that are not tasks
+    /// are skipped, and a task already on the tree is not expanded again, so
+    /// a cycle of relations cannot recurse forever.
+    pub fn build(store: &LoroStore, root: Uuid) -> Result<Self> {
+        let tasks: HashMap<Uuid, Task> = store
+            .list_tasks()?
+            .into_iter()
+            .map(|t| (t.base.id, t))
+            .collect();
+        let children = child_ids(&store.list_relations()?);
+
+        let mut seen = HashSet::new();
+        Self::expand(root, &tasks, &children, &mut seen)
+            .ok_or_else(|| MedullaError::EntityNotFound(root.to_string()))
+    }
+
+    fn expand(
+        id: Uuid,
+        tasks: &HashMap<Uuid, Task>,
+        children: &HashMap<Uuid, Vec<Uuid>>,
+        seen: &mut HashSet<Uuid>,
+    ) -> Option<Self> {
+        let task = tasks.get(&id)?.clone();
+        if !seen.insert(id) {
+            return None;
+        }
+
+        let mut subtasks: Vec<TaskTree> = children
+            .get(&id)
+            .into_iter()
+            .flatten()
+            .filter_map(|child| Self::expand(*child, tasks, children, seen))
+            .collect();
+        subtasks.sort_by_key(|t| t.task.base.sequence_number);
+
+        Some(Self {
+            task,
+            children: subtasks,
+        })
+    }
+
+    /// Subtasks at any depth below this task that are done, and in total.
+    pub fn progress(&self) -> (usize, usize) {
+        self.children.iter().fold((0, 0), |(done, total), child| {
+            let (child_done, child_total) = child.progress();
+            let own = usize::from(child.task.status == TaskStatus::Done);
+            (done + own + child_done, total + 1 + child_total)
+        })
+    }
+
+    /// JSON form: the task summary, its rollup and its subtrees.
+    pub fn to_json(&self) -> serde_json::Value {
+        let (done, total) = self.progress();
+        serde_json::json!({
+            "id": self.task.base.id.to_string(),
+            "sequence_number": self.task.base.sequence_number,
+            "title": self.task.base.title,
+            "status": self.task.status.to_string(),
+            "priority": self.task.priority.to_string(),
+            "subtasks_done": done,
+            "subtasks_total": total,
+            "children": self.children.iter().map(TaskTree::to_json).collect::<Vec<_>>(),
+        })
+    }
+
+    /// Render as an indented text tree, with rollup progress on tasks that
+    /// have subtasks.
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+        self.render_into(&mut out, "", "");
+        out
+    }
+
+    fn render_into(&self, out: &mut String, first_prefix: &str, rest_prefix: &str) {
+        let (done, total) = self.progress();
+        let rollup = if total > 0 {
+            format!(" ({}/{} done)", done, total)
+        } else {
+            String::new()
+        };
+        out.push_str(&format!(
+            "{}{:03} [{}] {}{}\n",
+            first_prefix,
+            self.task.base.sequence_number,
+            self.task.status,
+            self.task.base.title,
+            rollup
+        ));
+
+        for (index, child) in self.children.iter().enumerate() {
+            let last = index + 1 == self.children.len();
+            let (branch, indent) = if last {
+                ("└── ", "    ")
+            } else {
+                ("├── ", "│   ")
+            };
+            child.render_into(
+                out,
+                &format!("{}{}", rest_prefix, branch),
+                &format!("{}{}", rest_prefix, indent),
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn add_task(store: &LoroStore, title: &str, seq: u32, status: TaskStatus) -> Uuid {
+        let mut task = Task::new(title.to_string(), seq);
+        task.status = status;
+        store.add_task(&task).unwrap();
+        task.base.id
+    }
+
+    fn relate(store: &LoroStore, source: Uuid, target: Uuid, relation_type: RelationType) {
+        store
+            .add_relation(&Relation::new(
+                source,
+                "task".to_string(),
+                target,
+                "task".to_string(),
+                relation_type,
+            ))
+            .unwrap();
+    }
+
+    #[test]
+    fn test_task_tree_rollup() {
+        let tmp = TempDir::new().unwrap();
+        let store = LoroStore::init(tmp.path()).unwrap();
+        let epic = add_task(&store, "Launch", 1, TaskStatus::InProgress);
+        let docs = add_task(&store, "Docs", 2, TaskStatus::Done);
+        let build = add_task(&store, "Installer", 3, TaskStatus::Todo);
+        let mac = add_task(&store, "macOS", 4, TaskStatus::Done);
+        let win = add_task(&store, "Windows", 5, TaskStatus::Todo);
+
+        relate(&store, epic, docs, RelationType::ParentOf);
+        // Either direction works
+        relate(&store, build, epic, RelationType::ChildOf);
+        relate(&store, build, mac, RelationType::ParentOf);
+        relate(&store, build, win, RelationType::ParentOf);
+        // A cycle back to the root is ignored
+        relate(&store, win, epic, RelationType::ParentOf);
+
+        let tree = TaskTree::build(&store, epic).unwrap();
+        assert_eq!(tree.progress(), (2, 4));
+        assert_eq!(tree.children[1].progress(), (1, 2));
+        let expected = [
+            "001 [in_progress] Launch (2/4 done)",
+            "├── 002 [done] Docs",
+            "└── 003 [todo] Installer (1/2 done)",
+            "    ├── 004 [done] macOS",
+            "    └── 005 [todo] Windows",
+        ];
+        assert_eq!(tree.render(), format!("{}\n", expected.join("\n")));
+        assert_eq!(tree.to_json()["subtasks_total"], 4);
+
+        assert!(TaskTree::build(&store, Uuid::new_v4()).is_err());
+    }
+}
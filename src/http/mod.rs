@@ -0,0 +1,489 @@
+//! REST API served next to the MCP HTTP transport by `medulla serve --http`.
+//!
+//! Every endpoint calls the matching MCP tool and returns the JSON that tool
+//! produces, so dashboards and scripts see exactly what MCP clients see
+//! (including redaction). Tool errors are returned as
+//! `{"error": {"code", "message", "data"}}` with an HTTP status derived from
+//! the MCP error code. Requests pass through the server's request guard, so
+//! rate limits, payload limits and the write cap apply here as they do to
+//! MCP tool calls. [`metrics_router`] serves the server's metrics for
+//! Prometheus.
+
+use std::net::SocketAddr;
+
+use axum::extract::{ConnectInfo, DefaultBodyLimit, Path, Query, Request, State};
+use axum::http::{header, Method, StatusCode};
+use axum::middleware::{self, Next};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use rmcp::handler::server::wrapper::Parameters;
+use rmcp::model::{CallToolResult, RawContent};
+use rmcp::ErrorData as McpErrorData;
+use serde::Deserialize;
+
+use crate::mcp::error::error_codes;
+use crate::mcp::tools::{
+    EntityCreateParams, EntityDeleteParams, EntityGetParams, EntityListParams, EntityUpdateParams,
+    SearchFulltextParams, SearchSemanticParams, TaskBlockedParams, TaskCompleteParams,
+    TaskNextParams, TaskReadyParams,
+};
+use crate::mcp::MedullaServer;
+
+/// Query parameters for `GET /api/entities/{id}`.
+#[derive(Debug, Default, Deserialize)]
+pub struct EntityGetQuery {
+    #[serde(rename = "type")]
+    pub entity_type: Option<String>,
+    pub include_relations: Option<bool>,
+    pub include_related_entities: Option<bool>,
+    pub depth: Option<u32>,
+}
+
+/// Build the REST routes, all under `/api`.
+pub fn router(server: MedullaServer) -> Router {
+    let api = Router::new()
+        .route("/entities", get(list_entities).post(create_entity))
+        .route(
+            "/entities/{id}",
+            get(get_entity).patch(update_entity).delete(delete_entity),
+        )
+        .route("/search", get(search_fulltext))
+        .route("/search/semantic", get(search_semantic))
+        .route("/tasks/ready", get(tasks_ready))
+        .route("/tasks/blocked", get(tasks_blocked))
+        .route("/tasks/next", get(task_next))
+        .route("/tasks/plan", get(task_plan))
+        .route("/tasks/{id}/complete", post(task_complete))
+        .layer(DefaultBodyLimit::max(
+            server.guard.guards().max_payload_bytes,
+        ))
+        .layer(middleware::from_fn_with_state(
+            server.clone(),
+            guard_requests,
+        ))
+        .with_state(server);
+    Router::new().nest("/api", api)
+}
+
+/// Apply the request guard to a REST call: rate limit the caller by address,
+/// refuse bodies declared over the payload limit, and take a write slot for
+/// methods that change the store.
+async fn guard_requests(
+    State(server): State<MedullaServer>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let guard = &server.guard;
+    let client = request
+        .extensions()
+        .get::<ConnectInfo<SocketAddr>>()
+        .map_or_else(|| "http".to_string(), |info| format!("ip:{}", info.0.ip()));
+    if let Err(e) = guard.check_rate(&client) {
+        return error_response(e.into());
+    }
+    if let Err(e) = check_content_length(&server, &request) {
+        return error_response(e);
+    }
+    let _permit = if request.method() == Method::GET {
+        None
+    } else {
+        match guard.acquire_write() {
+            Ok(permit) => permit,
+            Err(e) => return error_response(e.into()),
+        }
+    };
+    next.run(request).await
+}
+
+/// Refuse requests whose declared body is over the payload limit. Applied
+/// to the MCP endpoint, whose tool calls are otherwise guarded one by one.
+pub async fn limit_payload(
+    State(server): State<MedullaServer>,
+    request: Request,
+    next: Next,
+) -> Response {
+    match check_content_length(&server, &request) {
+        Ok(()) => next.run(request).await,
+        Err(e) => error_response(e),
+    }
+}
+
+fn check_content_length(server: &MedullaServer, request: &Request) -> Result<(), McpErrorData> {
+    let length = request
+        .headers()
+        .get(header::CONTENT_LENGTH)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.parse::<usize>().ok());
+    match length {
+        Some(length) => server.guard.check_payload(length).map_err(Into::into),
+        None => Ok(()),
+    }
+}
+
+/// Build the `/metrics` route, serving the server's metrics in the
+/// Prometheus text exposition format.
+pub fn metrics_router(server: MedullaServer) -> Router {
+    Router::new()
+        .route("/metrics", get(metrics))
+        .with_state(server)
+}
+
+async fn metrics(State(server): State<MedullaServer>) -> Response {
+    (
+        [(
+            header::CONTENT_TYPE,
+            "text/plain; version=0.0.4; charset=utf-8",
+        )],
+        server.render_metrics().await,
+    )
+        .into_response()
+}
+
+/// HTTP status for an MCP error code.
+fn error_status(code: i32) -> StatusCode {
+    match code {
+        error_codes::ENTITY_NOT_FOUND | error_codes::RESOURCE_NOT_FOUND => StatusCode::NOT_FOUND,
+        error_codes::ENTITY_TYPE_INVALID
+        | error_codes::VALIDATION_FAILED
+        | error_codes::RELATION_TARGET_NOT_FOUND
+        | error_codes::INVALID_RESOURCE_URI => StatusCode::BAD_REQUEST,
+        error_codes::RATE_LIMITED => StatusCode::TOO_MANY_REQUESTS,
+        error_codes::PAYLOAD_TOO_LARGE => StatusCode::PAYLOAD_TOO_LARGE,
+        // JSON-RPC invalid params, e.g. a body that doesn't match the tool's schema
+        -32602 => StatusCode::BAD_REQUEST,
+        _ => StatusCode::INTERNAL_SERVER_ERROR,
+    }
+}
+
+fn error_response(err: McpErrorData) -> Response {
+    let body = serde_json::json!({
+        "error": {
+            "code": err.code.0,
+            "message": err.message,
+            "data": err.data,
+        }
+    });
+    (error_status(err.code.0), Json(body)).into_response()
+}
+
+/// Turn a tool result into a response carrying the tool's JSON output.
+fn respond(
+    server: &MedullaServer,
+    result: Result<CallToolResult, McpErrorData>,
+    status: StatusCode,
+) -> Response {
+    let result = match result {
+        Ok(result) => server.redact_result(result),
+        Err(err) => return error_response(err),
+    };
+
+    let text = result
+        .content
+        .iter()
+        .find_map(|c| match &c.raw {
+            RawContent::Text(t) => Some(t.text.as_str()),
+            _ => None,
+        })
+        .unwrap_or("null");
+    let body =
+        serde_json::from_str(text).unwrap_or_else(|_| serde_json::Value::String(text.to_string()));
+
+    let status = if result.is_error == Some(true) {
+        StatusCode::BAD_REQUEST
+    } else {
+        status
+    };
+    (status, Json(body)).into_response()
+}
+
+async fn list_entities(
+    State(server): State<MedullaServer>,
+    Query(params): Query<EntityListParams>,
+) -> Response {
+    let result = server.entity_list(Parameters(params)).await;
+    respond(&server, result, StatusCode::OK)
+}
+
+async fn create_entity(
+    State(server): State<MedullaServer>,
+    Json(params): Json<EntityCreateParams>,
+) -> Response {
+    let result = server.entity_create(Parameters(params)).await;
+    respond(&server, result, StatusCode::CREATED)
+}
+
+async fn get_entity(
+    State(server): State<MedullaServer>,
+    Path(id): Path<String>,
+    Query(query): Query<EntityGetQuery>,
+) -> Response {
+    let params = EntityGetParams {
+        id,
+        entity_type: query.entity_type,
+        include_relations: query.include_relations,
+        include_related_entities: query.include_related_entities,
+        depth: query.depth,
+    };
+    let result = server.entity_get(Parameters(params)).await;
+    respond(&server, result, StatusCode::OK)
+}
+
+/// The body is an `entity_update` argument object without the `id`, which
+/// comes from the path.
+async fn update_entity(
+    State(server): State<MedullaServer>,
+    Path(id): Path<String>,
+    Json(mut body): Json<serde_json::Value>,
+) -> Response {
+    let Some(fields) = body.as_object_mut() else {
+        return error_response(McpErrorData::invalid_params(
+            "Request body must be a JSON object",
+            None,
+        ));
+    };
+    fields.insert("id".to_string(), serde_json::Value::String(id));
+    let params: EntityUpdateParams = match serde_json::from_value(body) {
+        Ok(params) => params,
+        Err(e) => return error_response(McpErrorData::invalid_params(e.to_string(), None)),
+    };
+    let result = server.entity_update(Parameters(params)).await;
+    respond(&server, result, StatusCode::OK)
+}
+
+async fn delete_entity(State(server): State<MedullaServer>, Path(id): Path<String>) -> Response {
+    let result = server
+        .entity_delete(Parameters(EntityDeleteParams { id }))
+        .await;
+    respond(&server, result, StatusCode::OK)
+}
+
+async fn search_fulltext(
+    State(server): State<MedullaServer>,
+    Query(params): Query<SearchFulltextParams>,
+) -> Response {
+    let result = server.search_fulltext(Parameters(params)).await;
+    respond(&server, result, StatusCode::OK)
+}
+
+async fn search_semantic(
+    State(server): State<MedullaServer>,
+    Query(params): Query<SearchSemanticParams>,
+) -> Response {
+    let result = server.search_semantic(Parameters(params)).await;
+    respond(&server, result, StatusCode::OK)
+}
+
+async fn tasks_ready(
+    State(server): State<MedullaServer>,
+    Query(params): Query<TaskReadyParams>,
+) -> Response {
+    let result = server.task_ready(Parameters(params)).await;
+    respond(&server, result, StatusCode::OK)
+}
+
+async fn tasks_blocked(
+    State(server): State<MedullaServer>,
+    Query(params): Query<TaskBlockedParams>,
+) -> Response {
+    let result = server.task_blocked(Parameters(params)).await;
+    respond(&server, result, StatusCode::OK)
+}
+
+async fn task_next(
+    State(server): State<MedullaServer>,
+    Query(params): Query<TaskNextParams>,
+) -> Response {
+    let result = server.task_next(Parameters(params)).await;
+    respond(&server, result, StatusCode::OK)
+}
+
+async fn task_plan(State(server): State<MedullaServer>) -> Response {
+    let result = server.task_plan().await;
+    respond(&server, result, StatusCode::OK)
+}
+
+async fn task_complete(State(server): State<MedullaServer>, Path(id): Path<String>) -> Response {
+    let result = server
+        .task_complete(Parameters(TaskCompleteParams { id }))
+        .await;
+    respond(&server, result, StatusCode::OK)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cache::SqliteCache;
+    use crate::storage::LoroStore;
+    use axum::body::Body;
+    use axum::http::Request;
+    use tempfile::TempDir;
+    use tower::ServiceExt;
+
+    fn setup() -> (Router, TempDir) {
+        let tmp = TempDir::new().unwrap();
+        let store = LoroStore::init(tmp.path()).unwrap();
+        let cache = SqliteCache::open(store.medulla_dir()).unwrap();
+        (router(MedullaServer::new(store, cache)), tmp)
+    }
+
+    async fn call(
+        app: &Router,
+        method: &str,
+        uri: &str,
+        body: Option<serde_json::Value>,
+    ) -> (StatusCode, serde_json::Value) {
+        let request = Request::builder()
+            .method(method)
+            .uri(uri)
+            .header("content-type", "application/json");
+        let body = body.map_or_else(Body::empty, |b| Body::from(b.to_string()));
+        let response = app
+            .clone()
+            .oneshot(request.body(body).unwrap())
+            .await
+            .unwrap();
+        let status = response.status();
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        (status, serde_json::from_slice(&bytes).unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_entity_crud() {
+        let (app, _tmp) = setup();
+
+        let (status, created) = call(
+            &app,
+            "POST",
+            "/api/entities",
+            Some(serde_json::json!({
+                "type": "task",
+                "title": "Ship the REST API",
+                "properties": { "priority": "high" }
+            })),
+        )
+        .await;
+        assert_eq!(status, StatusCode::CREATED);
+        assert_eq!(created["sequence_number"], 1);
+
+        let (status, updated) = call(
+            &app,
+            "PATCH",
+            "/api/entities/1",
+            Some(serde_json::json!({ "title": "Ship it" })),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(updated["title"], "Ship it");
+
+        let (status, fetched) = call(&app, "GET", "/api/entities/1", None).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(fetched["properties"]["priority"], "high");
+
+        let (status, listed) = call(&app, "GET", "/api/entities?type=task", None).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(listed["total"], 1);
+
+        let (status, _) = call(&app, "DELETE", "/api/entities/1", None).await;
+        assert_eq!(status, StatusCode::OK);
+
+        let (status, error) = call(&app, "GET", "/api/entities/1", None).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert_eq!(error["error"]["code"], error_codes::ENTITY_NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_tasks_and_search() {
+        let (app, _tmp) = setup();
+        call(
+            &app,
+            "POST",
+            "/api/entities",
+            Some(serde_json::json!({ "type": "task", "title": "Write migration" })),
+        )
+        .await;
+
+        let (status, ready) = call(&app, "GET", "/api/tasks/ready", None).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(ready["total"], 1);
+
+        let (status, done) = call(&app, "POST", "/api/tasks/1/complete", None).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(done["properties"]["status"], "done");
+
+        let (status, results) = call(&app, "GET", "/api/search?query=migration", None).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(results["total"], 1);
+
+        let (status, _) = call(
+            &app,
+            "POST",
+            "/api/entities",
+            Some(serde_json::json!({ "type": "widget", "title": "Nope" })),
+        )
+        .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_guards() {
+        let tmp = TempDir::new().unwrap();
+        let store = LoroStore::init(tmp.path()).unwrap();
+        let cache = SqliteCache::open(store.medulla_dir()).unwrap();
+        let config = crate::config::ProjectConfig::from_yaml(
+            "guards:\n  requests_per_minute: 2\n  max_payload_bytes: 64\n",
+        )
+        .unwrap();
+        let app = router(MedullaServer::new(store, cache).with_config(config));
+
+        let (status, error) = call(
+            &app,
+            "POST",
+            "/api/entities",
+            Some(serde_json::json!({ "type": "note", "title": "x".repeat(100) })),
+        )
+        .await;
+        assert_eq!(status, StatusCode::PAYLOAD_TOO_LARGE);
+        assert_eq!(error["error"]["code"], error_codes::PAYLOAD_TOO_LARGE);
+
+        let (status, _) = call(&app, "GET", "/api/entities", None).await;
+        assert_eq!(status, StatusCode::OK);
+        let (status, error) = call(&app, "GET", "/api/entities", None).await;
+        assert_eq!(status, StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(error["error"]["code"], error_codes::RATE_LIMITED);
+    }
+
+    #[tokio::test]
+    async fn test_metrics() {
+        let tmp = TempDir::new().unwrap();
+        let store = LoroStore::init(tmp.path()).unwrap();
+        let cache = SqliteCache::open(store.medulla_dir()).unwrap();
+        let server = MedullaServer::new(store, cache);
+        server
+            .metrics
+            .record_tool("entity_list", std::time::Duration::from_millis(2), false);
+        let app = metrics_router(server.clone()).merge(router(server));
+        call(
+            &app,
+            "POST",
+            "/api/entities",
+            Some(serde_json::json!({ "type": "task", "title": "Count me" })),
+        )
+        .await;
+
+        let response = app
+            .oneshot(Request::get("/metrics").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let text = String::from_utf8(bytes.to_vec()).unwrap();
+        assert!(text.contains("medulla_tool_calls_total{tool=\"entity_list\"} 1\n"));
+        assert!(text.contains("medulla_entities{type=\"task\"} 1\n"));
+        assert!(!text.contains("medulla_store_size_bytes 0\n"));
+    }
+}
@@ -1,14 +1,24 @@
+pub mod author;
 pub mod cache;
 pub mod cli;
+pub mod client;
+pub mod config;
+pub mod daemon;
 pub mod embeddings;
 pub mod entity;
 pub mod error;
+pub mod fetch;
+pub mod graph;
+pub mod http;
 pub mod mcp;
 pub mod search;
 pub mod snapshot;
 pub mod storage;
+pub mod transfer;
 pub mod warnings;
+pub mod workspace;
 
 pub use cache::SqliteCache;
+pub use client::Client;
 pub use error::{MedullaError, Result};
 pub use mcp::MedullaServer;
@@ -1,99 +1,222 @@
 use clap::Parser;
 use medulla::cli::{
-    handle_add_component, handle_add_decision, handle_add_link, handle_add_note, handle_add_prompt,
-    handle_add_task, handle_cache_rebuild, handle_cache_stats, handle_delete, handle_get,
-    handle_hook_install, handle_hook_status, handle_hook_uninstall, handle_init, handle_list,
-    handle_relation_add, handle_relation_delete, handle_relation_list, handle_search, handle_serve,
-    handle_snapshot, handle_tasks_blocked, handle_tasks_next, handle_tasks_ready, handle_update,
-    AddEntity, CacheAction, Cli, Commands, HookAction, RelationAction, TasksAction,
+    check_duplicates, handle_add_component, handle_add_decision, handle_add_link,
+    handle_add_milestone, handle_add_note, handle_add_prompt, handle_add_question, handle_add_task,
+    handle_alias, handle_archive, handle_attach, handle_bulk_tag, handle_cache_rebuild,
+    handle_cache_rebuild_fts, handle_cache_stats, handle_collection, handle_compact,
+    handle_completions, handle_daemon_start, handle_daemon_status, handle_daemon_stop,
+    handle_dedupe, handle_delete, handle_digest, handle_doctor, handle_embeddings_backfill,
+    handle_export, handle_get, handle_git_commits, handle_git_link, handle_git_scan,
+    handle_graph_cycles, handle_graph_export, handle_graph_stats, handle_history,
+    handle_hook_install, handle_hook_status, handle_hook_uninstall, handle_import_adr,
+    handle_import_csv, handle_import_github, handle_import_jsonl, handle_init, handle_links_check,
+    handle_list, handle_merge, handle_merge_report, handle_milestones_status, handle_prompt_render,
+    handle_questions_answer, handle_questions_list, handle_relation_add, handle_relation_delete,
+    handle_relation_import, handle_relation_infer, handle_relation_list, handle_relation_type_add,
+    handle_relation_type_list, handle_relation_type_remove, handle_resequence, handle_rules_list,
+    handle_rules_test, handle_schema_dump, handle_search, handle_search_all_projects,
+    handle_selftest, handle_serve, handle_snapshot, handle_snapshot_diff, handle_snapshot_list,
+    handle_status, handle_sync_obsidian, handle_sync_replica, handle_tasks_blocked,
+    handle_tasks_due, handle_tasks_mine, handle_tasks_next, handle_tasks_overdue,
+    handle_tasks_ready, handle_tasks_tree, handle_tasks_wip, handle_tasks_workload,
+    handle_template_add, handle_template_apply, handle_template_delete, handle_template_list,
+    handle_trash_list, handle_trash_purge, handle_trash_restore, handle_unarchive, handle_update,
+    handle_watch, handle_workspace_add, handle_workspace_list, handle_workspace_remove,
+    resolve_id_arg, AddEntity, BulkAction, CacheAction, Cli, Commands, DaemonAction,
+    EmbeddingsAction, GitAction, GraphAction, HookAction, ImportAction, LinksAction,
+    MilestonesAction, OutputFormat, PromptAction, QuestionsAction, RelationAction,
+    RelationTypeAction, RulesAction, SchemaAction, SnapshotAction, SyncAction, TasksAction,
+    TemplateAction, TrashAction, WorkspaceAction,
 };
+use medulla::entity::QuestionStatus;
+use medulla::snapshot::SnapshotLayout;
+use medulla::storage::validate_collection;
 
 fn main() {
     let cli = Cli::parse();
 
+    // --project resolves a registered name (or a path) to a project directory
+    let project_dir = match cli.project.as_deref() {
+        Some(project) => {
+            match medulla::workspace::Workspace::load().and_then(|w| w.resolve(project)) {
+                Ok(dir) => Some(dir),
+                Err(e) => {
+                    eprintln!("Error: {}", e);
+                    std::process::exit(1);
+                }
+            }
+        }
+        None => cli.data_dir,
+    };
+    let data_dir = project_dir.as_deref();
+
     let result = match cli.command {
-        Commands::Init { yes, no } => handle_init(yes, no),
-        Commands::Add(add) => match add.entity {
-            AddEntity::Decision {
-                title,
-                status,
-                tags,
-                relations,
-                stdin,
-                edit,
-                json,
-            } => handle_add_decision(title, status, tags, relations, stdin, edit, json),
-            AddEntity::Task {
-                title,
-                status,
-                priority,
-                due,
-                assignee,
-                tags,
-                relations,
-                stdin,
-                json,
-            } => handle_add_task(
-                title, status, priority, due, assignee, tags, relations, stdin, json,
-            ),
-            AddEntity::Note {
-                title,
-                note_type,
-                tags,
-                relations,
-                stdin,
-                json,
-            } => handle_add_note(title, note_type, tags, relations, stdin, json),
-            AddEntity::Prompt {
-                title,
-                template,
-                variables,
-                output_schema,
-                tags,
-                stdin,
-                json,
-            } => handle_add_prompt(title, template, variables, output_schema, tags, stdin, json),
-            AddEntity::Component {
-                title,
-                component_type,
-                status,
-                owner,
-                tags,
-                relations,
-                stdin,
-                json,
-            } => handle_add_component(
-                title,
-                component_type,
-                status,
-                owner,
-                tags,
-                relations,
-                stdin,
-                json,
-            ),
-            AddEntity::Link {
-                title,
-                url,
-                link_type,
-                tags,
-                relations,
-                json,
-            } => handle_add_link(title, url, link_type, tags, relations, json),
-        },
-        Commands::List { entity_type, json } => handle_list(entity_type, json),
-        Commands::Get { id, json } => handle_get(id, json),
-        Commands::Update {
+        Commands::Init { yes, no } => handle_init(data_dir, yes, no),
+        Commands::Add(add) => {
+            let (entity_type, title) = match &add.entity {
+                AddEntity::Decision { title, .. } => ("decision", title),
+                AddEntity::Task { title, .. } => ("task", title),
+                AddEntity::Note { title, .. } => ("note", title),
+                AddEntity::Prompt { title, .. } => ("prompt", title),
+                AddEntity::Component { title, .. } => ("component", title),
+                AddEntity::Link { title, .. } => ("link", title),
+                AddEntity::Question { title, .. } => ("question", title),
+                AddEntity::Milestone { title, .. } => ("milestone", title),
+            };
+            let checked = if add.no_dup_check {
+                Ok(())
+            } else {
+                check_duplicates(data_dir, entity_type, title)
+            };
+            let checked = checked.and_then(|()| {
+                add.collection
+                    .as_deref()
+                    .map(validate_collection)
+                    .transpose()
+            });
+            checked.and_then(|collection| match add.entity {
+                AddEntity::Decision {
+                    title,
+                    status,
+                    tags,
+                    relations,
+                    stdin,
+                    edit,
+                    json,
+                } => handle_add_decision(
+                    data_dir, title, status, tags, relations, stdin, edit, collection, json,
+                ),
+                AddEntity::Task {
+                    title,
+                    status,
+                    priority,
+                    due,
+                    assignee,
+                    recur,
+                    tags,
+                    relations,
+                    stdin,
+                    json,
+                } => handle_add_task(
+                    data_dir, title, status, priority, due, assignee, recur, tags, relations,
+                    stdin, collection, json,
+                ),
+                AddEntity::Note {
+                    title,
+                    note_type,
+                    tags,
+                    relations,
+                    stdin,
+                    json,
+                } => handle_add_note(
+                    data_dir, title, note_type, tags, relations, stdin, collection, json,
+                ),
+                AddEntity::Prompt {
+                    title,
+                    template,
+                    variables,
+                    output_schema,
+                    tags,
+                    stdin,
+                    json,
+                } => handle_add_prompt(
+                    data_dir,
+                    title,
+                    template,
+                    variables,
+                    output_schema,
+                    tags,
+                    stdin,
+                    collection,
+                    json,
+                ),
+                AddEntity::Component {
+                    title,
+                    component_type,
+                    status,
+                    owner,
+                    tags,
+                    relations,
+                    stdin,
+                    json,
+                } => handle_add_component(
+                    data_dir,
+                    title,
+                    component_type,
+                    status,
+                    owner,
+                    tags,
+                    relations,
+                    stdin,
+                    collection,
+                    json,
+                ),
+                AddEntity::Link {
+                    title,
+                    url,
+                    link_type,
+                    fetch,
+                    tags,
+                    relations,
+                    json,
+                } => handle_add_link(
+                    data_dir, title, url, link_type, fetch, tags, relations, collection, json,
+                ),
+                AddEntity::Question {
+                    title,
+                    answered_by,
+                    tags,
+                    relations,
+                    stdin,
+                    json,
+                } => handle_add_question(
+                    data_dir,
+                    title,
+                    answered_by,
+                    tags,
+                    relations,
+                    stdin,
+                    collection,
+                    json,
+                ),
+                AddEntity::Milestone {
+                    title,
+                    target,
+                    tags,
+                    relations,
+                    stdin,
+                    json,
+                } => handle_add_milestone(
+                    data_dir, title, target, tags, relations, stdin, collection, json,
+                ),
+            })
+        }
+        Commands::List {
+            entity_type,
+            json,
+            format,
+            include_archived,
+            collection,
+        } => handle_list(
+            data_dir,
+            entity_type,
+            OutputFormat::resolve(format, json),
+            include_archived,
+            collection,
+        ),
+        Commands::Get {
             id,
-            title,
-            status,
-            tags,
-            remove_tags,
             relations,
-            stdin,
-            edit,
+            related,
+            depth,
             json,
-        } => handle_update(
+        } => resolve_id_arg(data_dir, id, None)
+            .and_then(|id| handle_get(data_dir, id, relations, related.then_some(depth), json)),
+        Commands::History { id, at, json } => {
+            resolve_id_arg(data_dir, id, None).and_then(|id| handle_history(data_dir, id, at, json))
+        }
+        Commands::MergeReport { since, json } => handle_merge_report(data_dir, since, json),
+        Commands::Update {
             id,
             title,
             status,
@@ -102,45 +225,400 @@ fn main() {
             relations,
             stdin,
             edit,
+            force,
             json,
-        ),
-        Commands::Delete { id, force } => handle_delete(id, force),
+        } => resolve_id_arg(data_dir, id, None).and_then(|id| {
+            handle_update(
+                data_dir,
+                id,
+                title,
+                status,
+                tags,
+                remove_tags,
+                relations,
+                stdin,
+                edit,
+                force,
+                json,
+            )
+        }),
+        Commands::Delete { id, force } => {
+            resolve_id_arg(data_dir, id, None).and_then(|id| handle_delete(data_dir, id, force))
+        }
+        Commands::Trash(trash_cmd) => match trash_cmd.action {
+            TrashAction::List { json } => handle_trash_list(data_dir, json),
+            TrashAction::Restore { id } => handle_trash_restore(data_dir, id),
+            TrashAction::Purge { id, all: _, force } => handle_trash_purge(data_dir, id, force),
+        },
+        Commands::Merge {
+            source,
+            target,
+            archive,
+            force,
+        } => handle_merge(data_dir, source, target, archive, force),
+        Commands::Dedupe {
+            entity_type,
+            threshold,
+            limit,
+            merge,
+            json,
+        } => handle_dedupe(data_dir, entity_type, threshold, limit, merge, json),
+        Commands::Archive { id } => {
+            resolve_id_arg(data_dir, id, None).and_then(|id| handle_archive(data_dir, id))
+        }
+        Commands::Unarchive { id } => {
+            resolve_id_arg(data_dir, id, None).and_then(|id| handle_unarchive(data_dir, id))
+        }
+        Commands::Attach { id, file, json } => handle_attach(data_dir, id, &file, json),
+        Commands::Alias {
+            id,
+            alias,
+            remove,
+            json,
+        } => handle_alias(data_dir, id, alias, remove, json),
+        Commands::Collection {
+            id,
+            name,
+            remove,
+            json,
+        } => handle_collection(data_dir, id, name, remove, json),
         Commands::Search {
             query,
             semantic,
             json,
-        } => handle_search(query, semantic, json),
+            format,
+            include_archived,
+            collection,
+            all_projects,
+        } => {
+            if all_projects {
+                handle_search_all_projects(query, semantic, include_archived)
+            } else {
+                handle_search(
+                    data_dir,
+                    query,
+                    semantic,
+                    OutputFormat::resolve(format, json),
+                    include_archived,
+                    collection,
+                )
+            }
+        }
+        Commands::Questions(questions_cmd) => match questions_cmd.action {
+            QuestionsAction::Open {
+                json,
+                format,
+                include_archived,
+            } => handle_questions_list(
+                data_dir,
+                QuestionStatus::Open,
+                OutputFormat::resolve(format, json),
+                include_archived,
+            ),
+            QuestionsAction::Answered {
+                json,
+                format,
+                include_archived,
+            } => handle_questions_list(
+                data_dir,
+                QuestionStatus::Answered,
+                OutputFormat::resolve(format, json),
+                include_archived,
+            ),
+            QuestionsAction::Answer { id, by, json } => {
+                resolve_id_arg(data_dir, id, Some("question"))
+                    .and_then(|id| handle_questions_answer(data_dir, id, by, json))
+            }
+        },
+        Commands::Links(links_cmd) => match links_cmd.action {
+            LinksAction::Check {
+                update,
+                include_archived,
+                json,
+            } => match handle_links_check(data_dir, update, include_archived, json) {
+                Ok(false) => Ok(()),
+                Ok(true) => std::process::exit(1),
+                Err(e) => Err(e),
+            },
+        },
+        Commands::Milestones(milestones_cmd) => match milestones_cmd.action {
+            MilestonesAction::Status {
+                id,
+                json,
+                format,
+                include_archived,
+            } => handle_milestones_status(
+                data_dir,
+                id,
+                OutputFormat::resolve(format, json),
+                include_archived,
+            ),
+        },
         Commands::Tasks(tasks_cmd) => match tasks_cmd.action {
-            TasksAction::Ready { limit, json } => handle_tasks_ready(limit, json),
-            TasksAction::Next { json } => handle_tasks_next(json),
-            TasksAction::Blocked { id, json } => handle_tasks_blocked(id, json),
+            TasksAction::Ready {
+                limit,
+                json,
+                format,
+                include_archived,
+                respect_wip,
+            } => handle_tasks_ready(
+                data_dir,
+                limit,
+                OutputFormat::resolve(format, json),
+                include_archived,
+                respect_wip,
+            ),
+            TasksAction::Next { respect_wip, json } => {
+                handle_tasks_next(data_dir, respect_wip, json)
+            }
+            TasksAction::Blocked { id, json } => handle_tasks_blocked(data_dir, id, json),
+            TasksAction::Tree { id, json } => resolve_id_arg(data_dir, id, Some("task"))
+                .and_then(|id| handle_tasks_tree(data_dir, id, json)),
+            TasksAction::Mine { assignee, json } => handle_tasks_mine(data_dir, assignee, json),
+            TasksAction::Workload { json } => handle_tasks_workload(data_dir, json),
+            TasksAction::Wip { json } => match handle_tasks_wip(data_dir, json) {
+                Ok(false) => Ok(()),
+                Ok(true) => std::process::exit(1),
+                Err(e) => Err(e),
+            },
+            TasksAction::Overdue { limit, json } => handle_tasks_overdue(data_dir, limit, json),
+            TasksAction::Due {
+                within,
+                limit,
+                json,
+            } => handle_tasks_due(data_dir, within, limit, json),
         },
-        Commands::Serve { http } => handle_serve(http),
+        Commands::Serve {
+            http,
+            workspace,
+            metrics,
+            require_confirmation,
+        } => handle_serve(data_dir, http, workspace, metrics, require_confirmation),
         Commands::Relation(rel_cmd) => match rel_cmd.action {
             RelationAction::Add {
                 source_id,
                 target_id,
                 relation_type,
                 json,
-            } => handle_relation_add(source_id, target_id, relation_type, json),
+            } => handle_relation_add(data_dir, source_id, target_id, relation_type, json),
             RelationAction::Delete {
                 source_id,
                 target_id,
                 relation_type,
                 json,
-            } => handle_relation_delete(source_id, target_id, relation_type, json),
-            RelationAction::List { entity_id, json } => handle_relation_list(entity_id, json),
+            } => handle_relation_delete(data_dir, source_id, target_id, relation_type, json),
+            RelationAction::List { entity_id, json } => {
+                handle_relation_list(data_dir, entity_id, json)
+            }
+            RelationAction::Import {
+                path,
+                dry_run,
+                json,
+            } => handle_relation_import(data_dir, &path, dry_run, json),
+            RelationAction::Infer { json } => handle_relation_infer(data_dir, json),
+            RelationAction::Type(type_cmd) => match type_cmd.action {
+                RelationTypeAction::Add {
+                    name,
+                    inverse,
+                    symmetric,
+                } => handle_relation_type_add(data_dir, name, inverse, symmetric),
+                RelationTypeAction::List { json } => handle_relation_type_list(data_dir, json),
+                RelationTypeAction::Remove { name } => handle_relation_type_remove(data_dir, name),
+            },
+        },
+        Commands::Graph(graph_cmd) => match graph_cmd.action {
+            GraphAction::Export {
+                format,
+                root,
+                depth,
+                output,
+            } => handle_graph_export(data_dir, format, root, depth, output),
+            GraphAction::Cycles { json } => handle_graph_cycles(data_dir, json),
+            GraphAction::Stats { limit, json } => handle_graph_stats(data_dir, limit, json),
+        },
+        Commands::Template(template_cmd) => match template_cmd.action {
+            TemplateAction::Add {
+                name,
+                entity_type,
+                description,
+                status,
+                properties,
+                tags,
+                stdin,
+                force,
+            } => handle_template_add(
+                data_dir,
+                name,
+                entity_type,
+                description,
+                status,
+                properties,
+                tags,
+                stdin,
+                force,
+            ),
+            TemplateAction::List { json } => handle_template_list(data_dir, json),
+            TemplateAction::Apply {
+                name,
+                title,
+                tags,
+                properties,
+                stdin,
+                json,
+            } => handle_template_apply(data_dir, name, title, tags, properties, stdin, json),
+            TemplateAction::Delete { name } => handle_template_delete(data_dir, name),
         },
         Commands::Cache(cache_cmd) => match cache_cmd.action {
-            CacheAction::Stats { json } => handle_cache_stats(json),
-            CacheAction::Rebuild { json } => handle_cache_rebuild(json),
+            CacheAction::Stats { json } => handle_cache_stats(data_dir, json),
+            CacheAction::Rebuild { json } => handle_cache_rebuild(data_dir, json),
+            CacheAction::RebuildFts { json } => handle_cache_rebuild_fts(data_dir, json),
+        },
+        Commands::Embeddings(embeddings_cmd) => match embeddings_cmd.action {
+            EmbeddingsAction::Backfill {
+                daemon,
+                debounce_ms,
+                json,
+            } => handle_embeddings_backfill(data_dir, daemon, debounce_ms, json),
+        },
+        Commands::Completions { shell } => handle_completions(shell),
+        Commands::Doctor { json } => match handle_doctor(data_dir, json) {
+            Ok(0) => Ok(()),
+            Ok(code) => std::process::exit(code),
+            Err(e) => Err(e),
+        },
+        Commands::Resequence { dry_run, json } => handle_resequence(data_dir, dry_run, json),
+        Commands::Compact {
+            keep_days,
+            force,
+            json,
+        } => handle_compact(data_dir, keep_days, force, json),
+        Commands::Import(import_cmd) => match import_cmd.action {
+            ImportAction::Csv {
+                path,
+                mappings,
+                json,
+            } => handle_import_csv(data_dir, &path, &mappings, json),
+            ImportAction::Jsonl { path, json } => handle_import_jsonl(data_dir, &path, json),
+            ImportAction::Adr { dir, json } => handle_import_adr(data_dir, &dir, json),
+            ImportAction::Github { repo, sync, json } => {
+                handle_import_github(data_dir, &repo, sync, json)
+            }
         },
-        Commands::Snapshot { output, verbose } => handle_snapshot(output, verbose),
+        Commands::Sync(sync_cmd) => match sync_cmd.action {
+            SyncAction::Obsidian { vault, json } => handle_sync_obsidian(data_dir, &vault, json),
+            SyncAction::Replica { path, json } => handle_sync_replica(data_dir, &path, json),
+        },
+        Commands::Bulk(bulk_cmd) => match bulk_cmd.action {
+            BulkAction::Tag {
+                query,
+                add,
+                remove,
+                dry_run,
+                json,
+            } => handle_bulk_tag(data_dir, &query, add, remove, dry_run, json),
+        },
+        Commands::Prompt(prompt_cmd) => match prompt_cmd.action {
+            PromptAction::Render {
+                id,
+                vars,
+                check,
+                json,
+            } => resolve_id_arg(data_dir, id, Some("prompt"))
+                .and_then(|id| handle_prompt_render(data_dir, &id, &vars, check, json)),
+        },
+        Commands::Schema(schema_cmd) => match schema_cmd.action {
+            SchemaAction::Dump { output } => handle_schema_dump(output),
+        },
+        Commands::Rules(rules_cmd) => match rules_cmd.action {
+            RulesAction::List { json } => handle_rules_list(data_dir, json),
+            RulesAction::Test {
+                entity_type,
+                title,
+                content,
+                url,
+                tags,
+                json,
+            } => handle_rules_test(data_dir, &entity_type, &title, content, url, tags, json),
+        },
+        Commands::Export { output } => handle_export(data_dir, output),
+        Commands::Snapshot {
+            action:
+                Some(SnapshotAction::Diff {
+                    old_ref,
+                    output,
+                    json,
+                }),
+            ..
+        } => handle_snapshot_diff(data_dir, &old_ref, output, json),
+        Commands::Snapshot {
+            action: Some(SnapshotAction::List { tagged, json }),
+            ..
+        } => handle_snapshot_list(data_dir, tagged, json),
+        Commands::Snapshot {
+            action: None,
+            output,
+            format,
+            verbose,
+            per_entity,
+            grouped,
+            tag,
+            related,
+            collection,
+        } => {
+            let layout = if per_entity {
+                Some(SnapshotLayout::PerEntity)
+            } else if grouped {
+                Some(SnapshotLayout::Grouped)
+            } else {
+                None
+            };
+            handle_snapshot(
+                data_dir, output, &format, verbose, layout, tag, related, collection,
+            )
+        }
+        Commands::Digest {
+            since,
+            output,
+            json,
+        } => handle_digest(data_dir, &since, output, json),
+        Commands::Status { output, json } => handle_status(data_dir, output, json),
+        Commands::Watch {
+            debounce_ms,
+            no_snapshot,
+        } => handle_watch(data_dir, debounce_ms, no_snapshot),
+        Commands::Daemon {
+            action: Some(DaemonAction::Status { json }),
+            ..
+        } => handle_daemon_status(data_dir, json),
+        Commands::Daemon {
+            action: Some(DaemonAction::Stop),
+            ..
+        } => handle_daemon_stop(data_dir),
+        Commands::Daemon {
+            action: None,
+            interval,
+            foreground,
+            no_snapshot,
+            no_embeddings,
+        } => handle_daemon_start(data_dir, &interval, foreground, no_snapshot, no_embeddings),
         Commands::Hook(hook_cmd) => match hook_cmd.action {
-            HookAction::Install { force } => handle_hook_install(force),
-            HookAction::Uninstall => handle_hook_uninstall(),
-            HookAction::Status => handle_hook_status(),
+            HookAction::Install { force } => handle_hook_install(data_dir, force),
+            HookAction::Uninstall => handle_hook_uninstall(data_dir),
+            HookAction::Status => handle_hook_status(data_dir),
+        },
+        Commands::Git(git_cmd) => match git_cmd.action {
+            GitAction::Link { entity_id, sha } => handle_git_link(data_dir, entity_id, sha),
+            GitAction::Scan { rev } => handle_git_scan(data_dir, rev),
+            GitAction::Commits { entity_id, json } => handle_git_commits(data_dir, entity_id, json),
+        },
+        Commands::Workspace(workspace_cmd) => match workspace_cmd.action {
+            WorkspaceAction::Add { name, path } => {
+                handle_workspace_add(data_dir, name, path.as_deref())
+            }
+            WorkspaceAction::Remove { name } => handle_workspace_remove(name),
+            WorkspaceAction::List { json } => handle_workspace_list(json),
         },
+        Commands::Selftest { entities, json } => handle_selftest(entities, json),
     };
 
     if let Err(e) = result {
@@ -0,0 +1,223 @@
+//! Confirmation of destructive tool calls through MCP elicitation.
+//!
+//! With `require_confirmation` set (in the project config, or with
+//! `medulla serve --require-confirmation`), deleting an entity,
+//! superseding a decision and bulk changes ask the user before they run,
+//! so an agent cannot quietly remove knowledge. The question goes to the
+//! client as an elicitation request; clients that do not support
+//! elicitation are not asked and the call runs as before.
+
+use std::time::Duration;
+
+use rmcp::model::JsonObject;
+use rmcp::service::{ElicitationError, Peer, RoleServer};
+use schemars::JsonSchema;
+use serde::Deserialize;
+
+use super::error::McpError;
+use super::tools::{BatchOperation, DecisionSupersedeParams, EntityBatchParams};
+use crate::storage::LoroStore;
+
+/// How long the user has to answer before the call is refused
+const CONFIRMATION_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// The user's answer to a confirmation request
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct Confirmation {
+    /// Go ahead with the operation
+    pub confirm: bool,
+}
+
+rmcp::elicit_safe!(Confirmation);
+
+/// `type seq "title"` for an entity ID, or `None` if it does not resolve
+fn describe(store: &LoroStore, id: &str) -> Option<String> {
+    let (uuid, entity_type) = store.lookup_id(id, None)?;
+    let entity = super::entity_response(store, entity_type, &uuid).ok()??;
+    Some(format!(
+        "{} {} \"{}\"",
+        entity_type, entity.sequence_number, entity.title
+    ))
+}
+
+/// What the user is asked before a tool call runs, or `None` when the call
+/// needs no confirmation. Calls whose arguments do not parse or name no
+/// entity are let through, so the tool reports the problem itself.
+pub fn confirmation_message(
+    tool: &str,
+    arguments: Option<&JsonObject>,
+    store: &LoroStore,
+) -> Option<String> {
+    let arguments = serde_json::Value::Object(arguments.cloned().unwrap_or_default());
+    match tool {
+        "entity_delete" => {
+            let entity = describe(store, arguments.get("id")?.as_str()?)?;
+            Some(format!(
+                "Delete {}? It moves to the trash along with its relations.",
+                entity
+            ))
+        }
+        "decision_supersede" => {
+            let params: DecisionSupersedeParams = serde_json::from_value(arguments).ok()?;
+            let old = describe(store, &params.old_id)?;
+            let new = describe(store, &params.new_id)?;
+            let migrate = if params.migrate_relations.unwrap_or(false) {
+                ", moving its incoming relations to the new decision"
+            } else {
+                ""
+            };
+            Some(format!("Mark {} as superseded by {}{}?", old, new, migrate))
+        }
+        "entity_batch" => {
+            let params: EntityBatchParams = serde_json::from_value(arguments).ok()?;
+            let count = |wanted: fn(&BatchOperation) -> bool| {
+                params.operations.iter().filter(|op| wanted(op)).count()
+            };
+            let deletes = count(|op| matches!(op, BatchOperation::Delete(_)));
+            let updates = count(|op| matches!(op, BatchOperation::Update(_)));
+            // A batch that only creates entities changes nothing existing
+            if deletes == 0 && updates == 0 {
+                return None;
+            }
+            Some(format!(
+                "Run a batch of {} operations, deleting {} and updating {} entities?",
+                params.operations.len(),
+                deletes,
+                updates
+            ))
+        }
+        "entity_bulk_update" => {
+            if arguments.get("dry_run").and_then(|v| v.as_bool()) == Some(true) {
+                return None;
+            }
+            let tags = |field: &str| -> Vec<String> {
+                arguments
+                    .get(field)
+                    .and_then(|v| v.as_array())
+                    .map(|tags| {
+                        tags.iter()
+                            .filter_map(|t| t.as_str().map(str::to_string))
+                            .collect()
+                    })
+                    .unwrap_or_default()
+            };
+            let mut changes = Vec::new();
+            let (add, remove) = (tags("add_tags"), tags("remove_tags"));
+            if !add.is_empty() {
+                changes.push(format!("adding {}", add.join(", ")));
+            }
+            if !remove.is_empty() {
+                changes.push(format!("removing {}", remove.join(", ")));
+            }
+            Some(format!(
+                "Change the tags of every entity matching \"{}\" ({})?",
+                arguments.get("query")?.as_str()?,
+                changes.join("; ")
+            ))
+        }
+        _ => None,
+    }
+}
+
+/// Ask the user to confirm `message`. Succeeds without asking when the
+/// client does not support elicitation; anything but an explicit yes
+/// refuses the call.
+pub async fn confirm(peer: &Peer<RoleServer>, tool: &str, message: String) -> Result<(), McpError> {
+    if !peer.supports_elicitation() {
+        return Ok(());
+    }
+    let reason = match peer
+        .elicit_with_timeout::<Confirmation>(message, Some(CONFIRMATION_TIMEOUT))
+        .await
+    {
+        Ok(Some(Confirmation { confirm: true })) => return Ok(()),
+        Ok(_) | Err(ElicitationError::UserDeclined) => "the user declined".to_string(),
+        Err(ElicitationError::UserCancelled) => "the user cancelled".to_string(),
+        Err(e) => format!("confirmation failed: {}", e),
+    };
+    Err(McpError::ConfirmationDeclined {
+        tool: tool.to_string(),
+        reason,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::entity::{Decision, Task};
+    use tempfile::TempDir;
+
+    fn args(value: serde_json::Value) -> JsonObject {
+        value.as_object().unwrap().clone()
+    }
+
+    #[test]
+    fn test_confirmation_message() {
+        let tmp = TempDir::new().unwrap();
+        let store = LoroStore::init(tmp.path()).unwrap();
+        store
+            .add_decision(&Decision::new("Use MySQL".to_string(), 1))
+            .unwrap();
+        store
+            .add_decision(&Decision::new("Use Postgres".to_string(), 2))
+            .unwrap();
+        store
+            .add_task(&Task::new("Add login".to_string(), 3))
+            .unwrap();
+
+        let message = |tool: &str, value: serde_json::Value| {
+            confirmation_message(tool, Some(&args(value)), &store)
+        };
+
+        assert_eq!(
+            message("entity_delete", serde_json::json!({ "id": "3" })).unwrap(),
+            "Delete task 3 \"Add login\"? It moves to the trash along with its relations."
+        );
+        // Unknown IDs are left for the tool to report
+        assert_eq!(
+            message("entity_delete", serde_json::json!({ "id": "9" })),
+            None
+        );
+        assert_eq!(
+            message(
+                "decision_supersede",
+                serde_json::json!({ "old_id": "1", "new_id": "2", "migrate_relations": true })
+            )
+            .unwrap(),
+            "Mark decision 1 \"Use MySQL\" as superseded by decision 2 \"Use Postgres\", moving its incoming relations to the new decision?"
+        );
+
+        let batch = serde_json::json!({ "operations": [
+            { "op": "create", "type": "note", "title": "New" },
+            { "op": "delete", "id": "3" },
+        ]});
+        assert_eq!(
+            message("entity_batch", batch).unwrap(),
+            "Run a batch of 2 operations, deleting 1 and updating 0 entities?"
+        );
+        let creates_only = serde_json::json!({ "operations": [
+            { "op": "create", "type": "note", "title": "New" },
+        ]});
+        assert_eq!(message("entity_batch", creates_only), None);
+
+        assert_eq!(
+            message(
+                "entity_bulk_update",
+                serde_json::json!({ "query": "type:task", "add_tags": ["q3"] })
+            )
+            .unwrap(),
+            "Change the tags of every entity matching \"type:task\" (adding q3)?"
+        );
+        assert_eq!(
+            message(
+                "entity_bulk_update",
+                serde_json::json!({ "query": "type:task", "add_tags": ["q3"], "dry_run": true })
+            ),
+            None
+        );
+        assert_eq!(
+            message("entity_get", serde_json::json!({ "id": "3" })),
+            None
+        );
+    }
+}
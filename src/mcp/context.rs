@@ -0,0 +1,302 @@
+//! Context packing for the `context_pack` tool.
+//!
+//! Seed entities (search hits or a requested entity) are ranked together
+//! with their graph neighbourhood, then rendered as one markdown document
+//! that stays within a token budget.
+
+use std::collections::HashMap;
+
+use serde::Serialize;
+
+use crate::mcp::tools::EntityResponse;
+
+/// Share of a score passed on to each related entity, per hop
+pub const GRAPH_DECAY: f32 = 0.5;
+
+/// Smallest remainder of the budget worth filling with a truncated section
+const MIN_SECTION_TOKENS: usize = 48;
+
+/// Rough token count: about four characters per token for English text
+/// and code, erring on the high side.
+pub fn estimate_tokens(text: &str) -> usize {
+    text.chars().count().div_ceil(4)
+}
+
+/// Spread seed scores over related entities, decaying by [`GRAPH_DECAY`]
+/// per hop up to `depth` hops, and rank every entity reached by score.
+/// Each seed is `(id, score, reason)`; entities reached only through the
+/// graph get the reason "related".
+pub fn rank_with_graph<F>(
+    seeds: &[(String, f32, &'static str)],
+    depth: usize,
+    mut neighbors: F,
+) -> Vec<(String, f32, &'static str)>
+where
+    F: FnMut(&str) -> Vec<String>,
+{
+    let mut best: HashMap<String, (f32, &'static str)> = HashMap::new();
+    for (id, score, reason) in seeds {
+        let entry = best.entry(id.clone()).or_insert((*score, *reason));
+        if *score > entry.0 {
+            *entry = (*score, *reason);
+        }
+    }
+
+    let mut frontier: Vec<(String, f32)> =
+        best.iter().map(|(id, (s, _))| (id.clone(), *s)).collect();
+    for _ in 0..depth {
+        let mut next = Vec::new();
+        for (id, score) in &frontier {
+            let passed = score * GRAPH_DECAY;
+            for neighbor in neighbors(id) {
+                let improved = best.get(&neighbor).map_or(true, |(s, _)| passed > *s);
+                if improved {
+                    best.insert(neighbor.clone(), (passed, "related"));
+                    next.push((neighbor, passed));
+                }
+            }
+        }
+        if next.is_empty() {
+            break;
+        }
+        frontier = next;
+    }
+
+    let mut ranked: Vec<_> = best
+        .into_iter()
+        .map(|(id, (score, reason))| (id, score, reason))
+        .collect();
+    ranked.sort_by(|a, b| b.1.total_cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    ranked
+}
+
+/// An entity considered for the pack, in rank order.
+pub struct ContextCandidate {
+    pub entity: EntityResponse,
+    pub score: f32,
+    pub reason: &'static str,
+    /// `(relation_type, other_entity_id, outgoing)` for each relation of
+    /// the entity
+    pub relations: Vec<(String, String, bool)>,
+}
+
+/// An entity included in the pack.
+#[derive(Debug, Serialize)]
+pub struct PackedEntity {
+    pub id: String,
+    #[serde(rename = "type")]
+    pub entity_type: String,
+    pub sequence_number: u32,
+    pub title: String,
+    pub score: f32,
+    pub reason: &'static str,
+    /// Content was cut short to fit the budget
+    pub truncated: bool,
+}
+
+/// The packed document and what went into it.
+#[derive(Debug, Serialize)]
+pub struct ContextPack {
+    pub markdown: String,
+    pub tokens: usize,
+    pub entities: Vec<PackedEntity>,
+    /// Candidates left out because the budget ran out
+    pub omitted: usize,
+}
+
+/// Render candidates, best first, into a markdown document of at most
+/// `max_tokens` estimated tokens. The first section that does not fit is
+/// truncated when enough budget remains; later ones are omitted.
+pub fn pack(heading: &str, candidates: &[ContextCandidate], max_tokens: usize) -> ContextPack {
+    let mut markdown = format!("# Context: {}\n", heading);
+    let mut tokens = estimate_tokens(&markdown);
+    let mut entities: Vec<PackedEntity> = Vec::new();
+    let mut omitted = 0;
+
+    for candidate in candidates {
+        let remaining = max_tokens.saturating_sub(tokens);
+        let titles: HashMap<&str, String> = entities
+            .iter()
+            .map(|e| {
+                (
+                    e.id.as_str(),
+                    entity_label(&e.entity_type, e.sequence_number, &e.title),
+                )
+            })
+            .collect();
+        let head = section_head(candidate, &titles);
+        let body = candidate.entity.content.as_deref().unwrap_or("").trim();
+
+        let full = format!("{}{}", head, section_body(body));
+        let (section, truncated) = if estimate_tokens(&full) <= remaining {
+            (full, false)
+        } else if remaining >= MIN_SECTION_TOKENS
+            && estimate_tokens(&head) + MIN_SECTION_TOKENS / 2 <= remaining
+        {
+            let marker = "\n\n[truncated]";
+            let room = (remaining - estimate_tokens(&head)) * 4;
+            let keep = room.saturating_sub(marker.len() + 4);
+            let cut: String = body.chars().take(keep).collect();
+            (format!("{}\n{}{}\n", head, cut.trim_end(), marker), true)
+        } else {
+            omitted += 1;
+            continue;
+        };
+
+        tokens += estimate_tokens(&section);
+        markdown.push_str(&section);
+        entities.push(PackedEntity {
+            id: candidate.entity.id.clone(),
+            entity_type: candidate.entity.entity_type.clone(),
+            sequence_number: candidate.entity.sequence_number,
+            title: candidate.entity.title.clone(),
+            score: (candidate.score * 1000.0).round() / 1000.0,
+            reason: candidate.reason,
+            truncated,
+        });
+    }
+
+    ContextPack {
+        tokens: estimate_tokens(&markdown),
+        markdown,
+        entities,
+        omitted,
+    }
+}
+
+fn entity_label(entity_type: &str, sequence_number: u32, title: &str) -> String {
+    format!(
+        "[{}] {:03} {}",
+        entity_type.to_uppercase(),
+        sequence_number,
+        title
+    )
+}
+
+/// Heading, metadata line and relations to entities already in the pack.
+fn section_head(candidate: &ContextCandidate, packed: &HashMap<&str, String>) -> String {
+    let entity = &candidate.entity;
+    let mut head = format!(
+        "\n## {}\n\n",
+        entity_label(&entity.entity_type, entity.sequence_number, &entity.title)
+    );
+
+    let mut meta = vec![format!("id: {}", entity.id)];
+    for key in ["status", "priority", "due_date", "assignee", "url"] {
+        if let Some(value) = entity.properties.get(key).and_then(|v| v.as_str()) {
+            meta.push(format!("{}: {}", key, value));
+        }
+    }
+    if !entity.tags.is_empty() {
+        meta.push(format!("tags: {}", entity.tags.join(", ")));
+    }
+    head.push_str(&meta.join(" · "));
+    head.push('\n');
+
+    let related: Vec<String> = candidate
+        .relations
+        .iter()
+        .filter_map(|(relation_type, other, outgoing)| {
+            let label = packed.get(other.as_str())?;
+            Some(if *outgoing {
+                format!("{} {}", relation_type, label)
+            } else {
+                format!("{} {} this", label, relation_type)
+            })
+        })
+        .collect();
+    if !related.is_empty() {
+        head.push_str(&format!("Related: {}\n", related.join("; ")));
+    }
+    head
+}
+
+fn section_body(body: &str) -> String {
+    if body.is_empty() {
+        String::new()
+    } else {
+        format!("\n{}\n", body)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn candidate(
+        id: &str,
+        seq: u32,
+        content: &str,
+        relations: Vec<(&str, &str, bool)>,
+    ) -> ContextCandidate {
+        ContextCandidate {
+            entity: EntityResponse {
+                id: id.to_string(),
+                sequence_number: seq,
+                entity_type: "note".to_string(),
+                title: format!("Note {}", seq),
+                content: Some(content.to_string()),
+                tags: vec![],
+                created_at: String::new(),
+                updated_at: String::new(),
+                created_by: None,
+                properties: serde_json::json!({}),
+            },
+            score: 1.0,
+            reason: "requested",
+            relations: relations
+                .into_iter()
+                .map(|(r, o, out)| (r.to_string(), o.to_string(), out))
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn test_rank_with_graph_decays_by_hop() {
+        let edges: HashMap<&str, Vec<&str>> =
+            HashMap::from([("a", vec!["b"]), ("b", vec!["a", "c"]), ("c", vec!["b"])]);
+        let seeds = vec![
+            ("a".to_string(), 0.8, "semantic"),
+            ("c".to_string(), 0.1, "fulltext"),
+        ];
+        let ranked = rank_with_graph(&seeds, 2, |id| {
+            edges[id].iter().map(|s| s.to_string()).collect()
+        });
+
+        assert_eq!(ranked[0], ("a".to_string(), 0.8, "semantic"));
+        assert_eq!(ranked[1], ("b".to_string(), 0.4, "related"));
+        // Two hops from a beats the weaker direct hit
+        assert_eq!(ranked[2], ("c".to_string(), 0.2, "related"));
+
+        let ranked = rank_with_graph(&seeds, 0, |_| unreachable!());
+        assert_eq!(ranked.len(), 2);
+    }
+
+    #[test]
+    fn test_pack_fits_budget() {
+        let long = "word ".repeat(400);
+        let candidates = vec![
+            candidate("id-1", 1, "Short body", vec![]),
+            candidate("id-2", 2, &long, vec![("relates_to", "id-1", true)]),
+            candidate("id-3", 3, "Never reached", vec![("blocks", "id-1", false)]),
+        ];
+
+        let packed = pack("parser", &candidates, 200);
+        assert!(packed.tokens <= 200);
+        assert_eq!(packed.entities.len(), 2);
+        assert!(!packed.entities[0].truncated);
+        assert!(packed.entities[1].truncated);
+        assert_eq!(packed.omitted, 1);
+        assert!(packed
+            .markdown
+            .contains("Related: relates_to [NOTE] 001 Note 1"));
+        assert!(packed.markdown.contains("[truncated]"));
+
+        let roomy = pack("parser", &candidates, 10_000);
+        assert_eq!(roomy.entities.len(), 3);
+        assert_eq!(roomy.omitted, 0);
+        assert!(roomy
+            .markdown
+            .contains("Related: [NOTE] 001 Note 1 blocks this"));
+    }
+}
@@ -1,5 +1,6 @@
 //! MCP-specific error types and mapping to JSON-RPC error codes.
 
+use crate::config::RuleViolation;
 use crate::error::MedullaError;
 use rmcp::model::ErrorCode;
 use rmcp::ErrorData as RmcpError;
@@ -15,6 +16,9 @@ pub mod error_codes {
     pub const RELATION_TARGET_NOT_FOUND: i32 = -32004;
     pub const RESOURCE_NOT_FOUND: i32 = -32005;
     pub const INVALID_RESOURCE_URI: i32 = -32006;
+    pub const RATE_LIMITED: i32 = -32007;
+    pub const PAYLOAD_TOO_LARGE: i32 = -32008;
+    pub const CONFIRMATION_DECLINED: i32 = -32009;
     pub const STORAGE_ERROR: i32 = -32010;
     pub const INTERNAL_ERROR: i32 = -32011;
 }
@@ -58,6 +62,9 @@ pub enum McpError {
     #[error("Invalid URL: {value}")]
     InvalidUrl { value: String },
 
+    #[error("Validation rules failed: {}", format_violations(violations))]
+    RuleViolations { violations: Vec<RuleViolation> },
+
     // Relation errors
     #[error("Relation target not found: {target_id}")]
     RelationTargetNotFound { target_id: String },
@@ -65,6 +72,9 @@ pub enum McpError {
     #[error("Self-referential relation not allowed for entity: {id}")]
     SelfReferentialRelation { id: String },
 
+    #[error("Relation would create a blocks cycle: {}", cycle.join(" -> "))]
+    RelationCycle { cycle: Vec<String> },
+
     // Graph errors
     #[error("No path found from '{from}' to '{to}'")]
     PathNotFound { from: String, to: String },
@@ -79,6 +89,19 @@ pub enum McpError {
     #[error("Invalid resource URI: {uri}")]
     InvalidResourceUri { uri: String },
 
+    // Request guards
+    #[error("Rate limit of {limit} tool calls per minute exceeded; retry in {retry_after_secs}s")]
+    RateLimited { limit: u32, retry_after_secs: u64 },
+
+    #[error("Too many concurrent writes (max {max}); retry when a running write finishes")]
+    TooManyConcurrentWrites { max: usize },
+
+    #[error("Request too large: {actual} bytes (max {max})")]
+    PayloadTooLarge { max: usize, actual: usize },
+
+    #[error("{tool} was not run: {reason}")]
+    ConfirmationDeclined { tool: String, reason: String },
+
     // Server errors
     #[error("Storage error: {message}")]
     StorageError { message: String },
@@ -87,6 +110,14 @@ pub enum McpError {
     InternalError { message: String },
 }
 
+fn format_violations(violations: &[RuleViolation]) -> String {
+    violations
+        .iter()
+        .map(|v| v.message.as_str())
+        .collect::<Vec<_>>()
+        .join("; ")
+}
+
 impl McpError {
     /// Get the JSON-RPC error code for this error type.
     pub fn error_code(&self) -> i32 {
@@ -99,7 +130,9 @@ impl McpError {
             | McpError::ContentTooLarge { .. }
             | McpError::InvalidEnumValue { .. }
             | McpError::InvalidDateFormat { .. }
-            | McpError::InvalidUrl { .. } => error_codes::VALIDATION_FAILED,
+            | McpError::InvalidUrl { .. }
+            | McpError::RuleViolations { .. }
+            | McpError::RelationCycle { .. } => error_codes::VALIDATION_FAILED,
             McpError::RelationTargetNotFound { .. } | McpError::SelfReferentialRelation { .. } => {
                 error_codes::RELATION_TARGET_NOT_FOUND
             }
@@ -108,6 +141,11 @@ impl McpError {
             }
             McpError::ResourceNotFound { .. } => error_codes::RESOURCE_NOT_FOUND,
             McpError::InvalidResourceUri { .. } => error_codes::INVALID_RESOURCE_URI,
+            McpError::RateLimited { .. } | McpError::TooManyConcurrentWrites { .. } => {
+                error_codes::RATE_LIMITED
+            }
+            McpError::PayloadTooLarge { .. } => error_codes::PAYLOAD_TOO_LARGE,
+            McpError::ConfirmationDeclined { .. } => error_codes::CONFIRMATION_DECLINED,
             McpError::StorageError { .. } => error_codes::STORAGE_ERROR,
             McpError::InternalError { .. } => error_codes::INTERNAL_ERROR,
         }
@@ -125,12 +163,18 @@ impl McpError {
             McpError::InvalidEnumValue { .. } => "InvalidEnumValue",
             McpError::InvalidDateFormat { .. } => "InvalidDateFormat",
             McpError::InvalidUrl { .. } => "InvalidUrl",
+            McpError::RuleViolations { .. } => "RuleViolations",
             McpError::RelationTargetNotFound { .. } => "RelationTargetNotFound",
             McpError::SelfReferentialRelation { .. } => "SelfReferentialRelation",
+            McpError::RelationCycle { .. } => "RelationCycle",
             McpError::PathNotFound { .. } => "PathNotFound",
             McpError::MaxDepthExceeded { .. } => "MaxDepthExceeded",
             McpError::ResourceNotFound { .. } => "ResourceNotFound",
             McpError::InvalidResourceUri { .. } => "InvalidResourceUri",
+            McpError::RateLimited { .. } => "RateLimited",
+            McpError::TooManyConcurrentWrites { .. } => "TooManyConcurrentWrites",
+            McpError::PayloadTooLarge { .. } => "PayloadTooLarge",
+            McpError::ConfirmationDeclined { .. } => "ConfirmationDeclined",
             McpError::StorageError { .. } => "StorageError",
             McpError::InternalError { .. } => "InternalError",
         }
@@ -174,6 +218,8 @@ impl From<MedullaError> for McpError {
                     "prompt".to_string(),
                     "component".to_string(),
                     "link".to_string(),
+                    "question".to_string(),
+                    "milestone".to_string(),
                 ],
             },
             MedullaError::Storage(msg) => McpError::StorageError { message: msg },
@@ -192,15 +238,39 @@ impl From<MedullaError> for McpError {
             MedullaError::Embedding(e) => McpError::InternalError {
                 message: format!("Embedding error: {}", e),
             },
+            MedullaError::Config(e) => McpError::InternalError {
+                message: format!("Config error: {}", e),
+            },
+            MedullaError::Csv(e) => McpError::InternalError {
+                message: format!("CSV error: {}", e),
+            },
+            MedullaError::Watch(e) => McpError::InternalError {
+                message: format!("Watch error: {}", e),
+            },
+            MedullaError::Fetch(e) => McpError::InternalError {
+                message: format!("Fetch error: {}", e),
+            },
+            MedullaError::Template(e) => McpError::InternalError {
+                message: format!("Template error: {}", e),
+            },
         }
     }
 }
 
 /// Valid entity types for validation.
-pub const VALID_ENTITY_TYPES: &[&str] =
-    &["decision", "task", "note", "prompt", "component", "link"];
+pub const VALID_ENTITY_TYPES: &[&str] = &[
+    "decision",
+    "task",
+    "note",
+    "prompt",
+    "component",
+    "link",
+    "question",
+    "milestone",
+];
 
-/// Validation constants.
+/// Validation constants. The context, template, batch and result limits are
+/// defaults for `config::Limits`, which a project can override.
 pub mod validation {
     pub const MAX_TITLE_LENGTH: usize = 500;
     pub const MAX_CONTENT_SIZE: usize = 102_400; // 100KB
@@ -0,0 +1,313 @@
+//! Request guards for `medulla serve`.
+//!
+//! [`RequestGuard`] enforces the project's [`Guards`]: a token bucket per
+//! client limits how fast tool calls arrive, oversized arguments and request
+//! bodies are refused, and a semaphore caps how many store-mutating tools
+//! run at once. MCP tool calls pass through it in `call_tool`; the REST API
+//! passes through it in a middleware layer.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+use super::error::McpError;
+use crate::config::Guards;
+
+/// Tools that write to the store, and so count against
+/// `max_concurrent_writes`.
+pub const MUTATING_TOOLS: &[&str] = &[
+    "entity_create",
+    "entity_create_from_template",
+    "entity_update",
+    "entity_patch_section",
+    "entity_delete",
+    "entity_batch",
+    "entity_import_rows",
+    "entity_bulk_update",
+    "task_complete",
+    "task_reschedule",
+    "decision_supersede",
+    "relation_create",
+    "relation_batch",
+    "relation_delete",
+    "relation_resolve",
+    "focus_set",
+];
+
+/// Clients tracked before idle buckets are dropped
+const MAX_TRACKED_CLIENTS: usize = 1024;
+
+/// Tool calls a client may still make, refilled continuously
+#[derive(Debug, Clone, Copy)]
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+/// Rate, payload and write limits shared by every clone of a server.
+#[derive(Debug)]
+pub struct RequestGuard {
+    guards: Guards,
+    buckets: Mutex<HashMap<String, Bucket>>,
+    writes: Arc<Semaphore>,
+}
+
+impl Default for RequestGuard {
+    fn default() -> Self {
+        Self::new(Guards::default())
+    }
+}
+
+impl RequestGuard {
+    pub fn new(guards: Guards) -> Self {
+        Self {
+            guards,
+            buckets: Mutex::new(HashMap::new()),
+            writes: Arc::new(Semaphore::new(
+                guards.max_concurrent_writes.min(Semaphore::MAX_PERMITS),
+            )),
+        }
+    }
+
+    /// The limits being enforced
+    pub fn guards(&self) -> &Guards {
+        &self.guards
+    }
+
+    /// Admit one tool call from `client`: check its rate limit, the size of
+    /// its arguments and, for store-mutating tools, take a write slot. The
+    /// returned permit holds the slot until it is dropped.
+    pub fn admit(
+        &self,
+        client: &str,
+        tool: &str,
+        arguments: Option<&serde_json::Map<String, serde_json::Value>>,
+    ) -> Result<Option<OwnedSemaphorePermit>, McpError> {
+        self.check_request(client, arguments)?;
+        self.acquire_for(tool)
+    }
+
+    /// Check a call from `client` against the rate limit and the size of
+    /// its arguments against the payload limit.
+    pub fn check_request(
+        &self,
+        client: &str,
+        arguments: Option<&serde_json::Map<String, serde_json::Value>>,
+    ) -> Result<(), McpError> {
+        self.check_rate(client)?;
+        if let Some(arguments) = arguments {
+            let size = serde_json::to_string(arguments).map_or(0, |s| s.len());
+            self.check_payload(size)?;
+        }
+        Ok(())
+    }
+
+    /// Take a write slot if `tool` mutates the store.
+    pub fn acquire_for(&self, tool: &str) -> Result<Option<OwnedSemaphorePermit>, McpError> {
+        if MUTATING_TOOLS.contains(&tool) {
+            self.acquire_write()
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Take one call from `client`'s bucket, failing if it is empty.
+    pub fn check_rate(&self, client: &str) -> Result<(), McpError> {
+        self.check_rate_at(client, Instant::now())
+    }
+
+    fn check_rate_at(&self, client: &str, now: Instant) -> Result<(), McpError> {
+        let limit = self.guards.requests_per_minute;
+        if limit == 0 {
+            return Ok(());
+        }
+        let capacity = f64::from(limit);
+        let per_second = capacity / 60.0;
+
+        let mut buckets = match self.buckets.lock() {
+            Ok(buckets) => buckets,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+        if buckets.len() >= MAX_TRACKED_CLIENTS && !buckets.contains_key(client) {
+            // A bucket idle for a minute has refilled, so forgetting it
+            // changes nothing for that client
+            buckets.retain(|_, b| now.duration_since(b.updated).as_secs() < 60);
+        }
+        let bucket = buckets.entry(client.to_string()).or_insert(Bucket {
+            tokens: capacity,
+            updated: now,
+        });
+
+        let elapsed = now.duration_since(bucket.updated).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * per_second).min(capacity);
+        bucket.updated = now;
+        if bucket.tokens < 1.0 {
+            return Err(McpError::RateLimited {
+                limit,
+                retry_after_secs: ((1.0 - bucket.tokens) / per_second).ceil() as u64,
+            });
+        }
+        bucket.tokens -= 1.0;
+        Ok(())
+    }
+
+    /// Fail if a request of `size` bytes is over the payload limit.
+    pub fn check_payload(&self, size: usize) -> Result<(), McpError> {
+        if size > self.guards.max_payload_bytes {
+            return Err(McpError::PayloadTooLarge {
+                max: self.guards.max_payload_bytes,
+                actual: size,
+            });
+        }
+        Ok(())
+    }
+
+    /// Take a write slot, failing rather than waiting if none is free.
+    /// Returns `None` when writes are not capped.
+    pub fn acquire_write(&self) -> Result<Option<OwnedSemaphorePermit>, McpError> {
+        let max = self.guards.max_concurrent_writes;
+        if max == 0 {
+            return Ok(None);
+        }
+        self.writes
+            .clone()
+            .try_acquire_owned()
+            .map(Some)
+            .map_err(|_| McpError::TooManyConcurrentWrites { max })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_rate_limit_refills() {
+        let guard = RequestGuard::new(Guards {
+            requests_per_minute: 2,
+            ..Guards::default()
+        });
+        let start = Instant::now();
+        assert!(guard.check_rate_at("a", start).is_ok());
+        assert!(guard.check_rate_at("a", start).is_ok());
+        let err = guard.check_rate_at("a", start).unwrap_err();
+        assert!(matches!(
+            err,
+            McpError::RateLimited {
+                limit: 2,
+                retry_after_secs: 30
+            }
+        ));
+
+        // Other clients have their own bucket
+        assert!(guard.check_rate_at("b", start).is_ok());
+
+        // One call comes back every 30 seconds
+        assert!(guard
+            .check_rate_at("a", start + Duration::from_secs(30))
+            .is_ok());
+        assert!(guard
+            .check_rate_at("a", start + Duration::from_secs(30))
+            .is_err());
+    }
+
+    #[test]
+    fn test_unlimited_rate() {
+        let guard = RequestGuard::new(Guards {
+            requests_per_minute: 0,
+            ..Guards::default()
+        });
+        for _ in 0..1000 {
+            assert!(guard.check_rate("a").is_ok());
+        }
+    }
+
+    #[test]
+    fn test_payload_and_write_slots() {
+        let guard = RequestGuard::new(Guards {
+            max_payload_bytes: 16,
+            max_concurrent_writes: 1,
+            ..Guards::default()
+        });
+        let big = serde_json::json!({ "title": "far more than sixteen bytes" });
+        let err = guard.admit("a", "entity_get", big.as_object()).unwrap_err();
+        assert!(matches!(err, McpError::PayloadTooLarge { max: 16, .. }));
+
+        let permit = guard.admit("a", "entity_create", None).unwrap();
+        assert!(permit.is_some());
+        assert!(matches!(
+            guard.admit("a", "entity_update", None),
+            Err(McpError::TooManyConcurrentWrites { max: 1 })
+        ));
+        // Reads don't need a slot
+        assert!(guard.admit("a", "entity_list", None).unwrap().is_none());
+
+        drop(permit);
+        assert!(guard.admit("a", "entity_update", None).unwrap().is_some());
+    }
+
+    /// Tools that only read the store. Every tool must be listed either
+    /// here or in `MUTATING_TOOLS`.
+    const READ_ONLY_TOOLS: &[&str] = &[
+        "ping",
+        "entity_get",
+        "entity_outline",
+        "entity_get_section",
+        "entity_list",
+        "entity_index",
+        "entity_history",
+        "search_fulltext",
+        "search_semantic",
+        "search_by_vector",
+        "entity_duplicates",
+        "entity_related",
+        "search_query",
+        "context_pack",
+        "prompt_render",
+        "graph_relations",
+        "graph_path",
+        "graph_export",
+        "graph_analytics",
+        "graph_orphans",
+        "graph_lint",
+        "health_check",
+        "task_ready",
+        "task_blocked",
+        "task_overdue",
+        "task_due",
+        "task_by_assignee",
+        "task_plan",
+        "task_tree",
+        "milestone_progress",
+        "task_next",
+        "project_burndown",
+        "snapshot_preview",
+        "snapshot_pending",
+        "focus_get",
+    ];
+
+    #[test]
+    fn test_every_tool_is_classified() {
+        let tools = crate::mcp::MedullaServer::tool_router().list_all();
+        for tool in &tools {
+            let name = tool.name.as_ref();
+            let mutating = MUTATING_TOOLS.contains(&name);
+            let read_only = READ_ONLY_TOOLS.contains(&name);
+            assert!(
+                mutating != read_only,
+                "tool '{}' must be listed in exactly one of MUTATING_TOOLS and READ_ONLY_TOOLS",
+                name
+            );
+        }
+        for name in MUTATING_TOOLS.iter().chain(READ_ONLY_TOOLS) {
+            assert!(
+                tools.iter().any(|t| t.name == *name),
+                "no tool named '{}'",
+                name
+            );
+        }
+    }
+}
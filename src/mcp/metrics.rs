@@ -0,0 +1,221 @@
+//! Prometheus-style metrics for the MCP server.
+//!
+//! Tool calls are counted and timed as they pass through `call_tool`;
+//! gauges such as the store size are read when the metrics are rendered.
+//! `medulla serve --http <port> --metrics` exposes them at `/metrics` in
+//! the Prometheus text exposition format.
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// Upper bounds, in seconds, of the tool latency histogram buckets
+pub const LATENCY_BUCKETS: [f64; 11] = [
+    0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
+];
+
+/// Calls and latencies of one tool
+#[derive(Debug, Clone, Default)]
+struct ToolStats {
+    calls: u64,
+    errors: u64,
+    /// Calls at or under each of [`LATENCY_BUCKETS`] (not cumulative)
+    buckets: [u64; LATENCY_BUCKETS.len()],
+    latency_sum: f64,
+}
+
+/// Values read from the store and cache when the metrics are rendered
+#[derive(Debug, Clone, Default)]
+pub struct Gauges {
+    /// Size of `.medulla/loro.db` in bytes
+    pub store_size_bytes: u64,
+    /// Size of `.medulla/cache.db` in bytes
+    pub cache_size_bytes: u64,
+    /// Semantic searches that found the vector index already in memory
+    pub cache_hits: u64,
+    /// Semantic searches that had to load or rebuild the vector index
+    pub cache_misses: u64,
+    /// Entities in the store, by type
+    pub entities: BTreeMap<String, usize>,
+}
+
+/// Counters shared by every clone of a server.
+#[derive(Debug, Default)]
+pub struct Metrics {
+    tools: Mutex<BTreeMap<String, ToolStats>>,
+}
+
+impl Metrics {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record one call of `tool` taking `elapsed`. Errors include both
+    /// protocol errors and results flagged `is_error`.
+    pub fn record_tool(&self, tool: &str, elapsed: Duration, is_error: bool) {
+        let Ok(mut tools) = self.tools.lock() else {
+            return;
+        };
+        let stats = tools.entry(tool.to_string()).or_default();
+        let seconds = elapsed.as_secs_f64();
+        stats.calls += 1;
+        if is_error {
+            stats.errors += 1;
+        }
+        stats.latency_sum += seconds;
+        if let Some(bucket) = LATENCY_BUCKETS.iter().position(|b| seconds <= *b) {
+            stats.buckets[bucket] += 1;
+        }
+    }
+
+    /// Calls recorded for `tool` so far
+    pub fn tool_calls(&self, tool: &str) -> u64 {
+        self.tools
+            .lock()
+            .ok()
+            .and_then(|tools| tools.get(tool).map(|s| s.calls))
+            .unwrap_or(0)
+    }
+
+    /// Render every metric in the Prometheus text exposition format
+    pub fn render(&self, gauges: &Gauges) -> String {
+        let tools = match self.tools.lock() {
+            Ok(tools) => tools,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+        let mut out = String::new();
+
+        out.push_str("# HELP medulla_tool_calls_total MCP tool invocations.\n");
+        out.push_str("# TYPE medulla_tool_calls_total counter\n");
+        for (tool, stats) in tools.iter() {
+            let _ = writeln!(
+                out,
+                "medulla_tool_calls_total{{tool=\"{}\"}} {}",
+                tool, stats.calls
+            );
+        }
+
+        out.push_str("# HELP medulla_tool_errors_total MCP tool invocations that failed.\n");
+        out.push_str("# TYPE medulla_tool_errors_total counter\n");
+        for (tool, stats) in tools.iter() {
+            let _ = writeln!(
+                out,
+                "medulla_tool_errors_total{{tool=\"{}\"}} {}",
+                tool, stats.errors
+            );
+        }
+
+        out.push_str("# HELP medulla_tool_duration_seconds MCP tool latency.\n");
+        out.push_str("# TYPE medulla_tool_duration_seconds histogram\n");
+        for (tool, stats) in tools.iter() {
+            let mut cumulative = 0;
+            for (bound, count) in LATENCY_BUCKETS.iter().zip(stats.buckets) {
+                cumulative += count;
+                let _ = writeln!(
+                    out,
+                    "medulla_tool_duration_seconds_bucket{{tool=\"{}\",le=\"{}\"}} {}",
+                    tool, bound, cumulative
+                );
+            }
+            let _ = writeln!(
+                out,
+                "medulla_tool_duration_seconds_bucket{{tool=\"{}\",le=\"+Inf\"}} {}",
+                tool, stats.calls
+            );
+            let _ = writeln!(
+                out,
+                "medulla_tool_duration_seconds_sum{{tool=\"{}\"}} {}",
+                tool, stats.latency_sum
+            );
+            let _ = writeln!(
+                out,
+                "medulla_tool_duration_seconds_count{{tool=\"{}\"}} {}",
+                tool, stats.calls
+            );
+        }
+
+        out.push_str(
+            "# HELP medulla_cache_requests_total Vector index lookups by semantic search, by result.\n",
+        );
+        out.push_str("# TYPE medulla_cache_requests_total counter\n");
+        let _ = writeln!(
+            out,
+            "medulla_cache_requests_total{{result=\"hit\"}} {}",
+            gauges.cache_hits
+        );
+        let _ = writeln!(
+            out,
+            "medulla_cache_requests_total{{result=\"miss\"}} {}",
+            gauges.cache_misses
+        );
+
+        let lookups = gauges.cache_hits + gauges.cache_misses;
+        let hit_ratio = if lookups == 0 {
+            0.0
+        } else {
+            gauges.cache_hits as f64 / lookups as f64
+        };
+        out.push_str(
+            "# HELP medulla_cache_hit_ratio Share of vector index lookups served from memory.\n",
+        );
+        out.push_str("# TYPE medulla_cache_hit_ratio gauge\n");
+        let _ = writeln!(out, "medulla_cache_hit_ratio {}", hit_ratio);
+
+        out.push_str("# HELP medulla_store_size_bytes Size of the Loro document.\n");
+        out.push_str("# TYPE medulla_store_size_bytes gauge\n");
+        let _ = writeln!(out, "medulla_store_size_bytes {}", gauges.store_size_bytes);
+
+        out.push_str("# HELP medulla_cache_size_bytes Size of the SQLite cache.\n");
+        out.push_str("# TYPE medulla_cache_size_bytes gauge\n");
+        let _ = writeln!(out, "medulla_cache_size_bytes {}", gauges.cache_size_bytes);
+
+        out.push_str("# HELP medulla_entities Entities in the store, by type.\n");
+        out.push_str("# TYPE medulla_entities gauge\n");
+        for (entity_type, count) in &gauges.entities {
+            let _ = writeln!(
+                out,
+                "medulla_entities{{type=\"{}\"}} {}",
+                entity_type, count
+            );
+        }
+
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_tool_histogram() {
+        let metrics = Metrics::new();
+        metrics.record_tool("entity_get", Duration::from_millis(3), false);
+        metrics.record_tool("entity_get", Duration::from_millis(40), true);
+        metrics.record_tool("entity_get", Duration::from_secs(30), false);
+        assert_eq!(metrics.tool_calls("entity_get"), 3);
+        assert_eq!(metrics.tool_calls("search_fulltext"), 0);
+
+        let gauges = Gauges {
+            store_size_bytes: 2048,
+            cache_hits: 3,
+            cache_misses: 1,
+            entities: BTreeMap::from([("task".to_string(), 4)]),
+            ..Default::default()
+        };
+        let text = metrics.render(&gauges);
+        assert!(text.contains("medulla_tool_calls_total{tool=\"entity_get\"} 3\n"));
+        assert!(text.contains("medulla_tool_errors_total{tool=\"entity_get\"} 1\n"));
+        assert!(text.contains(
+            "medulla_tool_duration_seconds_bucket{tool=\"entity_get\",le=\"0.005\"} 1\n"
+        ));
+        assert!(text
+            .contains("medulla_tool_duration_seconds_bucket{tool=\"entity_get\",le=\"0.05\"} 2\n"));
+        assert!(text
+            .contains("medulla_tool_duration_seconds_bucket{tool=\"entity_get\",le=\"+Inf\"} 3\n"));
+        assert!(text.contains("medulla_cache_hit_ratio 0.75\n"));
+        assert!(text.contains("medulla_store_size_bytes 2048\n"));
+        assert!(text.contains("medulla_entities{type=\"task\"} 4\n"));
+    }
+}
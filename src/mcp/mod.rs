@@ -8,24 +8,36 @@
 #![allow(clippy::clone_on_copy)]
 #![allow(clippy::manual_async_fn)]
 
+pub mod confirm;
+pub mod context;
 pub mod error;
+pub mod guard;
+pub mod metrics;
 pub mod resources;
+pub mod schema;
 pub mod tools;
 
-use crate::cache::SqliteCache;
+use crate::cache::{EntityPageQuery, EntitySort, SearchResult, SqliteCache};
+use crate::config::{ProjectConfig, RuleSubject};
 use crate::embeddings::Embedder;
-use crate::entity::{Component, Decision, EntityBase, Link, Note, Prompt, Task};
+use crate::entity::{
+    find_section, patch_section, sections, Component, Decision, EntityBase, Link, Milestone, Note,
+    Prompt, Question, QuestionStatus, SectionEdit, Task, DEFAULT_SECTION_LEVEL,
+};
 use crate::storage::{
-    ComponentUpdate, DecisionUpdate, LinkUpdate, LoroStore, NoteUpdate, PromptUpdate, TaskUpdate,
+    validate_collection, ComponentUpdate, DecisionUpdate, LinkUpdate, LoroStore, MilestoneUpdate,
+    NoteUpdate, PromptUpdate, QuestionUpdate, TaskUpdate,
 };
 use error::{validation, McpError, VALID_ENTITY_TYPES};
+use guard::RequestGuard;
+use metrics::{Gauges, Metrics};
 use rmcp::{
     handler::server::wrapper::Parameters,
     model::*,
     service::{RequestContext, RoleServer},
-    tool, tool_handler, tool_router, ErrorData as McpErrorData, ServerHandler,
+    tool, tool_router, ErrorData as McpErrorData, ServerHandler,
 };
-use std::collections::{HashMap, HashSet, VecDeque};
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
 use std::sync::{Arc, OnceLock};
 use tokio::sync::Mutex;
 use tools::*;
@@ -36,51 +48,142 @@ static EMBEDDER: OnceLock<std::result::Result<Embedder, String>> = OnceLock::new
 /// Subscription identifier type.
 pub type SubscriptionId = String;
 
-/// Manages active resource subscriptions.
-#[derive(Debug, Default)]
-pub struct SubscriptionState {
-    /// Map of resource URI to list of subscription IDs.
-    pub by_resource: HashMap<String, Vec<SubscriptionId>>,
+/// Identifies a connected client session in `SubscriptionState`.
+pub type PeerKey = usize;
+
+/// The key of a client session: the address of its peer info, which every
+/// clone of the session's `Peer` shares and no other session does.
+pub fn peer_key(peer: &rmcp::service::Peer<RoleServer>) -> Option<PeerKey> {
+    peer.peer_info()
+        .map(|info| info as *const InitializeRequestParams as PeerKey)
+}
+
+/// Manages active resource subscriptions, per client session, so that each
+/// change is only sent to the sessions subscribed to it.
+#[derive(Debug)]
+pub struct SubscriptionState<P = rmcp::service::Peer<RoleServer>> {
+    /// Map of resource URI to its subscriptions and the session of each.
+    pub by_resource: HashMap<String, Vec<(SubscriptionId, PeerKey)>>,
+    /// Handle used to notify each subscribed session.
+    peers: HashMap<PeerKey, P>,
     /// Counter for generating unique subscription IDs.
     next_id: u64,
 }
 
-impl SubscriptionState {
+impl<P> Default for SubscriptionState<P> {
+    fn default() -> Self {
+        Self {
+            by_resource: HashMap::new(),
+            peers: HashMap::new(),
+            next_id: 0,
+        }
+    }
+}
+
+impl<P: Clone> SubscriptionState<P> {
     /// Create a new empty subscription state.
     pub fn new() -> Self {
         Self::default()
     }
 
-    /// Add a subscription for a resource URI.
-    pub fn subscribe(&mut self, uri: &str) -> SubscriptionId {
+    /// Subscribe the session `key` to a resource URI. Subscribing again
+    /// returns the existing subscription.
+    pub fn subscribe(&mut self, uri: &str, key: PeerKey, peer: P) -> SubscriptionId {
+        self.peers.insert(key, peer);
+        let subs = self.by_resource.entry(uri.to_string()).or_default();
+        if let Some((id, _)) = subs.iter().find(|(_, k)| *k == key) {
+            return id.clone();
+        }
         let id = format!("sub_{}", self.next_id);
         self.next_id += 1;
-        self.by_resource
-            .entry(uri.to_string())
-            .or_default()
-            .push(id.clone());
+        subs.push((id.clone(), key));
         id
     }
 
     /// Remove a subscription by ID.
     pub fn unsubscribe(&mut self, id: &str) -> bool {
         for subs in self.by_resource.values_mut() {
-            if let Some(pos) = subs.iter().position(|s| s == id) {
+            if let Some(pos) = subs.iter().position(|(s, _)| s == id) {
                 subs.remove(pos);
+                self.prune();
                 return true;
             }
         }
         false
     }
 
+    /// Remove the subscription of session `key` to a resource URI.
+    pub fn unsubscribe_uri(&mut self, uri: &str, key: PeerKey) -> bool {
+        let Some(subs) = self.by_resource.get_mut(uri) else {
+            return false;
+        };
+        let before = subs.len();
+        subs.retain(|(_, k)| *k != key);
+        let removed = subs.len() < before;
+        self.prune();
+        removed
+    }
+
+    /// Drop every subscription of a session, once it has disconnected.
+    pub fn remove_peer(&mut self, key: PeerKey) {
+        for subs in self.by_resource.values_mut() {
+            subs.retain(|(_, k)| *k != key);
+        }
+        self.prune();
+    }
+
+    /// The given URIs that have at least one subscriber.
+    pub fn subscribed(&self, uris: &[String]) -> Vec<String> {
+        uris.iter()
+            .filter(|uri| self.by_resource.get(*uri).is_some_and(|s| !s.is_empty()))
+            .cloned()
+            .collect()
+    }
+
+    /// The sessions subscribed to any of the given URIs, each with the URIs
+    /// it is subscribed to.
+    pub fn subscribers(&self, uris: &[String]) -> Vec<(PeerKey, P, Vec<String>)> {
+        let mut by_peer: BTreeMap<PeerKey, Vec<String>> = BTreeMap::new();
+        for uri in uris {
+            for (_, key) in self.by_resource.get(uri).into_iter().flatten() {
+                by_peer.entry(*key).or_default().push(uri.clone());
+            }
+        }
+        by_peer
+            .into_iter()
+            .filter_map(|(key, uris)| Some((key, self.peers.get(&key)?.clone(), uris)))
+            .collect()
+    }
+
+    /// The handle of a subscribed session.
+    pub fn peer(&self, key: PeerKey) -> Option<&P> {
+        self.peers.get(&key)
+    }
+
     /// Get all subscription IDs for a resource URI.
     pub fn get_subscribers(&self, uri: &str) -> Vec<SubscriptionId> {
-        self.by_resource.get(uri).cloned().unwrap_or_default()
+        self.by_resource
+            .get(uri)
+            .map(|subs| subs.iter().map(|(id, _)| id.clone()).collect())
+            .unwrap_or_default()
     }
 
     /// Clear all subscriptions (for disconnect cleanup).
     pub fn clear(&mut self) {
         self.by_resource.clear();
+        self.peers.clear();
+    }
+
+    /// Forget URIs without subscribers and sessions without subscriptions.
+    fn prune(&mut self) {
+        self.by_resource.retain(|_, subs| !subs.is_empty());
+        let active: HashSet<PeerKey> = self
+            .by_resource
+            .values()
+            .flatten()
+            .map(|(_, k)| *k)
+            .collect();
+        self.peers.retain(|key, _| active.contains(key));
     }
 }
 
@@ -94,8 +197,21 @@ pub struct MedullaServer {
     pub store: Arc<Mutex<LoroStore>>,
     /// The SQLite cache for full-text search (wrapped in Mutex for thread safety).
     pub cache: Arc<Mutex<SqliteCache>>,
-    /// Active resource subscriptions.
+    /// Active resource subscriptions of every connected session.
     pub subscriptions: Arc<Mutex<SubscriptionState>>,
+    /// While set, resource notifications are collected here instead of
+    /// sent, so a tool made of several writes sends each URI once.
+    pending_notifications: Option<Arc<std::sync::Mutex<Vec<String>>>>,
+    /// Project configuration (validation rules, etc.).
+    pub config: Arc<ProjectConfig>,
+    /// Other workspace projects served alongside this one, by name. Tool
+    /// calls with a `project` argument are routed to them.
+    pub projects: Arc<BTreeMap<String, MedullaServer>>,
+    /// Tool call counts and latencies, shared by every clone.
+    pub metrics: Arc<Metrics>,
+    /// Rate, payload and write limits from the project config, shared by
+    /// every clone.
+    pub guard: Arc<RequestGuard>,
     /// Tool router for MCP tool handling.
     pub tool_router: rmcp::handler::server::tool::ToolRouter<Self>,
 }
@@ -106,6 +222,16 @@ const SERVER_NAME: &str = "medulla";
 #[allow(dead_code)]
 const SERVER_VERSION: &str = env!("CARGO_PKG_VERSION");
 
+/// Most full-text hits considered as candidates by `search_semantic`'s
+/// prefilter (per entity type when searching all types).
+const PREFILTER_LIMIT: i64 = 500;
+
+/// Search hits used to seed a context pack, per search method
+const CONTEXT_SEED_LIMIT: usize = 20;
+
+/// Ranked entities considered for a context pack
+const CONTEXT_CANDIDATE_LIMIT: usize = 50;
+
 /// Parse a datetime string into DateTime<Utc>.
 /// Supports ISO 8601 datetime (RFC 3339) or date-only (YYYY-MM-DD).
 fn parse_datetime(s: &str) -> Option<chrono::DateTime<chrono::Utc>> {
@@ -125,6 +251,118 @@ fn parse_datetime(s: &str) -> Option<chrono::DateTime<chrono::Utc>> {
     None
 }
 
+/// Find an entity of one type by sequence number or UUID prefix, through
+/// the store's ID index.
+fn lookup<T>(
+    store: &LoroStore,
+    entity_type: &str,
+    id: &str,
+    get: impl Fn(&LoroStore, &uuid::Uuid) -> crate::error::Result<Option<T>>,
+) -> Result<Option<T>, McpError> {
+    match store.lookup_id(id, Some(entity_type)) {
+        Some((uuid, _)) => get(store, &uuid).map_err(McpError::from),
+        None => Ok(None),
+    }
+}
+
+/// Load an entity of a known type by UUID as a tool response
+fn entity_response(
+    store: &LoroStore,
+    entity_type: &str,
+    id: &uuid::Uuid,
+) -> Result<Option<EntityResponse>, McpError> {
+    let response = match entity_type {
+        "decision" => store.get_decision(id)?.map(|e| decision_to_response(&e)),
+        "task" => store.get_task(id)?.map(|e| task_to_response(&e)),
+        "note" => store.get_note(id)?.map(|e| note_to_response(&e)),
+        "prompt" => store.get_prompt(id)?.map(|e| prompt_to_response(&e)),
+        "component" => store.get_component(id)?.map(|e| component_to_response(&e)),
+        "link" => store.get_link(id)?.map(|e| link_to_response(&e)),
+        "question" => store.get_question(id)?.map(|e| question_to_response(&e)),
+        "milestone" => store.get_milestone(id)?.map(|e| milestone_to_response(&e)),
+        _ => None,
+    };
+    Ok(response)
+}
+
+/// Resolve the `answered_by` property of a question to the full UUID of
+/// the answering entity
+fn resolve_answered_by(store: &LoroStore, reference: &str) -> Result<String, McpError> {
+    store
+        .lookup_id(reference, None)
+        .map(|(id, _)| id.to_string())
+        .ok_or_else(|| McpError::EntityNotFound {
+            id: reference.to_string(),
+        })
+}
+
+/// The `collection` property of a create or update: `None` when absent,
+/// `Some(None)` to take the entity out of its collection (null or empty)
+fn collection_property(
+    properties: Option<&serde_json::Value>,
+) -> Result<Option<Option<String>>, McpError> {
+    match properties.and_then(|p| p.get("collection")) {
+        None => Ok(None),
+        Some(serde_json::Value::Null) => Ok(Some(None)),
+        Some(serde_json::Value::String(name)) if name.trim().is_empty() => Ok(Some(None)),
+        Some(serde_json::Value::String(name)) => validate_collection(name)
+            .map(|name| Some(Some(name)))
+            .map_err(|e| McpError::ValidationFailed {
+                field: "collection".to_string(),
+                message: e.to_string(),
+            }),
+        Some(_) => Err(McpError::ValidationFailed {
+            field: "collection".to_string(),
+            message: "collection must be a string or null".to_string(),
+        }),
+    }
+}
+
+/// Put an entity in a collection, or take it out of its own, in the store
+/// and the cache
+fn apply_collection(
+    store: &LoroStore,
+    cache: &SqliteCache,
+    entity_id: &str,
+    collection: Option<&str>,
+) -> Result<(), McpError> {
+    let id = uuid::Uuid::parse_str(entity_id).map_err(|_| McpError::EntityNotFound {
+        id: entity_id.to_string(),
+    })?;
+    match collection {
+        Some(name) => {
+            let (name, sequence) = store.set_collection(&id, name).map_err(McpError::from)?;
+            cache
+                .index_collection(entity_id, &name, sequence)
+                .map_err(McpError::from)?;
+        }
+        None if store.collection_of(&id).is_some() => {
+            store.remove_collection(&id).map_err(McpError::from)?;
+            cache
+                .unindex_collection(entity_id)
+                .map_err(McpError::from)?;
+        }
+        None => return Ok(()),
+    }
+    store.save().map_err(McpError::from)?;
+    Ok(())
+}
+
+/// Error for a section heading the content does not have, listing the
+/// headings it does
+fn missing_section_error(content: &str, section: &str) -> McpError {
+    let headings: Vec<String> = sections(content).into_iter().map(|s| s.heading).collect();
+    let available = if headings.is_empty() {
+        "the content has no headings".to_string()
+    } else {
+        format!("available sections: {}", headings.join(", "))
+    };
+    McpError::ValidationFailed {
+        field: "section".to_string(),
+        message: format!("No section '{}'; {}", section, available),
+    }
+}
+
 // All tool implementations in the tool_router impl block
 #[tool_router]
 impl MedullaServer {
@@ -134,10 +372,196 @@ impl MedullaServer {
             store: Arc::new(Mutex::new(store)),
             cache: Arc::new(Mutex::new(cache)),
             subscriptions: Arc::new(Mutex::new(SubscriptionState::new())),
+            pending_notifications: None,
+            config: Arc::new(ProjectConfig::default()),
+            projects: Arc::new(BTreeMap::new()),
+            metrics: Arc::new(Metrics::new()),
+            guard: Arc::new(RequestGuard::default()),
             tool_router: Self::tool_router(),
         }
     }
 
+    /// Use the given project configuration.
+    pub fn with_config(mut self, config: ProjectConfig) -> Self {
+        self.guard = Arc::new(RequestGuard::new(config.guards));
+        self.config = Arc::new(config);
+        self
+    }
+
+    /// Ask for confirmation before destructive tool calls, whatever the
+    /// project config says.
+    pub fn with_required_confirmation(mut self) -> Self {
+        Arc::make_mut(&mut self.config).require_confirmation = true;
+        self
+    }
+
+    /// Also serve the given workspace projects, selected per tool call with
+    /// a `project` argument.
+    pub fn with_projects(mut self, projects: BTreeMap<String, MedullaServer>) -> Self {
+        self.projects = Arc::new(projects);
+        self
+    }
+
+    /// The server a tool call runs against: the project named by its
+    /// `project` argument (which is removed before the tool sees the
+    /// arguments), or this one if there is none.
+    fn route_project(&self, arguments: Option<&mut JsonObject>) -> Result<&Self, McpError> {
+        let name = match arguments.and_then(|args| args.remove("project")) {
+            None | Some(serde_json::Value::Null) => return Ok(self),
+            Some(serde_json::Value::String(name)) => name,
+            Some(_) => {
+                return Err(McpError::ValidationFailed {
+                    field: "project".to_string(),
+                    message: "must be a project name".to_string(),
+                })
+            }
+        };
+        self.projects
+            .get(&name)
+            .ok_or_else(|| McpError::ValidationFailed {
+                field: "project".to_string(),
+                message: if self.projects.is_empty() {
+                    format!(
+                        "Unknown project '{}'. Start the server with `medulla serve --workspace` to serve several projects.",
+                        name
+                    )
+                } else {
+                    format!(
+                        "Unknown project '{}'. Served projects: {}",
+                        name,
+                        self.projects
+                            .keys()
+                            .map(String::as_str)
+                            .collect::<Vec<_>>()
+                            .join(", ")
+                    )
+                },
+            })
+    }
+
+    /// Send `notifications/resources/updated` to each session subscribed to
+    /// any of the URIs, for the URIs it subscribed to. Failures are logged;
+    /// a mutation never fails because a client could not be notified.
+    /// Sessions whose connection has closed are dropped.
+    pub async fn notify_resources_updated(&self, uris: Vec<String>) {
+        if let Some(pending) = &self.pending_notifications {
+            pending.lock().unwrap().extend(uris);
+            return;
+        }
+        let subscribers = self.subscriptions.lock().await.subscribers(&uris);
+        for (key, peer, uris) in subscribers {
+            if peer.is_transport_closed() {
+                self.subscriptions.lock().await.remove_peer(key);
+                continue;
+            }
+            for uri in uris {
+                let param = ResourceUpdatedNotificationParam { uri: uri.clone() };
+                if let Err(e) = peer.notify_resource_updated(param).await {
+                    tracing::warn!("Failed to notify subscribers of {}: {}", uri, e);
+                    if peer.is_transport_closed() {
+                        self.subscriptions.lock().await.remove_peer(key);
+                        break;
+                    }
+                }
+            }
+        }
+    }
+
+    /// Run `run` against a clone of the server that collects resource
+    /// notifications, then send each collected URI once.
+    async fn coalescing_notifications<T, F, Fut>(&self, run: F) -> T
+    where
+        F: FnOnce(MedullaServer) -> Fut,
+        Fut: std::future::Future<Output = T>,
+    {
+        let pending = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let mut collector = self.clone();
+        collector.pending_notifications = Some(pending.clone());
+        let result = run(collector).await;
+
+        let mut uris = std::mem::take(&mut *pending.lock().unwrap());
+        uris.sort();
+        uris.dedup();
+        self.notify_resources_updated(uris).await;
+        result
+    }
+
+    /// Render the metrics in the Prometheus text format, reading the store
+    /// and cache gauges as they are now.
+    pub async fn render_metrics(&self) -> String {
+        let mut gauges = Gauges::default();
+        {
+            let store = self.store.lock().await;
+            gauges.store_size_bytes = std::fs::metadata(store.path())
+                .map(|m| m.len())
+                .unwrap_or(0);
+        }
+        {
+            let cache = self.cache.lock().await;
+            gauges.cache_size_bytes = cache.size_bytes();
+            (gauges.cache_hits, gauges.cache_misses) = cache.vector_index_lookups();
+            if let Ok(stats) = cache.get_stats() {
+                gauges.entities = BTreeMap::from([
+                    ("decision".to_string(), stats.decisions),
+                    ("task".to_string(), stats.tasks),
+                    ("note".to_string(), stats.notes),
+                    ("prompt".to_string(), stats.prompts),
+                    ("component".to_string(), stats.components),
+                    ("link".to_string(), stats.links),
+                    ("question".to_string(), stats.questions),
+                    ("milestone".to_string(), stats.milestones),
+                ]);
+            }
+        }
+        self.metrics.render(&gauges)
+    }
+
+    /// Apply the configured `redact_fields` to the JSON text of a tool result.
+    pub fn redact_result(&self, mut result: CallToolResult) -> CallToolResult {
+        if self.config.redact_fields.is_empty() {
+            return result;
+        }
+        for content in result.content.iter_mut() {
+            if let RawContent::Text(ref mut text) = content.raw {
+                if let Ok(mut value) = serde_json::from_str::<serde_json::Value>(&text.text) {
+                    self.config.redact(&mut value);
+                    if let Ok(json) = serde_json::to_string_pretty(&value) {
+                        text.text = json;
+                    }
+                }
+            }
+        }
+        result
+    }
+
+    /// Under `strict_enums`, reject non-canonical status and priority values.
+    fn check_enum_properties(
+        &self,
+        properties: Option<&serde_json::Value>,
+    ) -> Result<(), McpError> {
+        if !self.config.strict_enums {
+            return Ok(());
+        }
+        if let Some(props) = properties {
+            for field in ["status", "priority"] {
+                if let Some(value) = props.get(field).and_then(|v| v.as_str()) {
+                    check_strict_enum(field, value)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Evaluate the configured validation rules, aggregating all violations.
+    fn check_rules(&self, subject: &RuleSubject) -> Result<(), McpError> {
+        let violations = self.config.check_rules(subject);
+        if violations.is_empty() {
+            Ok(())
+        } else {
+            Err(McpError::RuleViolations { violations })
+        }
+    }
+
     /// Get the embedder for computing text embeddings.
     /// Lazily initializes the embedding model on first use.
     /// Returns None if the embedder failed to initialize.
@@ -175,6 +599,64 @@ impl MedullaServer {
         }
     }
 
+    /// Fetch the page of a link about to be created, if its `fetch` property
+    /// (or the config) asks for it. The fetch blocks, so it runs on the
+    /// blocking pool, and only public hosts are fetched. An unreachable page
+    /// leaves the link as given.
+    async fn fetch_link_page(
+        &self,
+        properties: Option<&serde_json::Value>,
+    ) -> Option<crate::fetch::FetchedPage> {
+        let url = properties?.get("url")?.as_str()?.to_string();
+        let fetch = properties
+            .and_then(|p| p.get("fetch"))
+            .and_then(|v| v.as_bool())
+            .unwrap_or(self.config.fetch_link_metadata);
+        if !fetch || validate_url(&url).is_err() {
+            return None;
+        }
+        let result = {
+            let url = url.clone();
+            tokio::task::spawn_blocking(move || crate::fetch::fetch_public_page(&url)).await
+        };
+        match result {
+            Ok(Ok(page)) => Some(page),
+            Ok(Err(e)) => {
+                tracing::warn!("Failed to fetch {}: {}", url, e);
+                None
+            }
+            Err(e) => {
+                tracing::warn!("Fetch of {} did not complete: {}", url, e);
+                None
+            }
+        }
+    }
+
+    /// Add and index `references` relations for the `#<seq>` and `med-<seq>`
+    /// mentions in content an entity was just written with.
+    fn add_inferred_references(
+        store: &LoroStore,
+        cache: &SqliteCache,
+        entity_id: &str,
+        entity_type: &str,
+        content: Option<&str>,
+    ) -> Result<(), McpError> {
+        let (Some(content), Ok(uuid)) = (content, entity_id.parse::<uuid::Uuid>()) else {
+            return Ok(());
+        };
+        let added = store
+            .add_inferred_references(uuid, entity_type, content, None)
+            .map_err(McpError::from)?;
+        if added.is_empty() {
+            return Ok(());
+        }
+        store.save().map_err(McpError::from)?;
+        for relation in &added {
+            cache.index_relation(relation).map_err(McpError::from)?;
+        }
+        Ok(())
+    }
+
     /// Start the MCP server on the given transport.
     ///
     /// This method runs the server until the transport is closed or an error occurs.
@@ -208,26 +690,73 @@ impl MedullaServer {
     // ========================================================================
 
     /// Create a new entity of any type.
-    #[tool(description = "Create a new entity (decision, task, note, prompt, component, or link)")]
+    #[tool(
+        description = "Create a new entity (decision, task, note, prompt, component, link, question, or milestone). Tasks join a milestone through a belongs_to relation to it. Links with the `fetch` property (or when the project config sets fetch_link_metadata) take their title, description and favicon from the fetched page. A `collection` property (e.g. \"backend\") puts the entity in a collection with its own numbering, so it can also be referenced as backend/3. Existing entities of the same type with a near-identical title or embedding are listed in `duplicate_candidates`; consider updating one of them instead."
+    )]
     pub async fn entity_create(
         &self,
-        Parameters(params): Parameters<EntityCreateParams>,
+        Parameters(mut params): Parameters<EntityCreateParams>,
     ) -> Result<CallToolResult, McpErrorData> {
         // Validate common fields
         validate_entity_type(&params.entity_type)?;
         validate_title(&params.title)?;
         validate_content(&params.content)?;
         validate_tags(&params.tags)?;
+        self.check_enum_properties(params.properties.as_ref())?;
+        let collection = collection_property(params.properties.as_ref())?.flatten();
+
+        // Auto-tag before the rules run, so tag rules see the added tags
+        let properties = params.properties.clone().unwrap_or(serde_json::Value::Null);
+        let auto_tags = self.config.auto_tags(&RuleSubject {
+            entity_type: &params.entity_type,
+            title: params.title.trim(),
+            content: params.content.as_deref(),
+            tags: params.tags.as_deref().unwrap_or_default(),
+            properties: &properties,
+        });
+        if !auto_tags.is_empty() {
+            params.tags.get_or_insert_with(Vec::new).extend(auto_tags);
+        }
+        self.check_rules(&RuleSubject {
+            entity_type: &params.entity_type,
+            title: params.title.trim(),
+            content: params.content.as_deref(),
+            tags: params.tags.as_deref().unwrap_or_default(),
+            properties: &properties,
+        })?;
+
+        // Fetch a link's page before taking the locks, so a slow site
+        // doesn't hold up every other tool call
+        let mut fetched = match params.entity_type.as_str() {
+            "link" => self.fetch_link_page(params.properties.as_ref()).await,
+            _ => None,
+        };
 
+        let content = params.content.clone();
         let store = self.store.lock().await;
         let cache = self.cache.lock().await;
 
+        // Look for likely duplicates before the new entity can match itself
+        let embedding = match cache.embedding_dimension().map_err(McpError::from)? {
+            Some(_) => Self::get_embedder().and_then(|e| e.embed(params.title.trim()).ok()),
+            None => None,
+        };
+        let duplicate_candidates = cache
+            .find_duplicate_candidates(
+                &params.entity_type,
+                params.title.trim(),
+                embedding.as_deref(),
+                5,
+            )
+            .map_err(McpError::from)?;
+
         let response = match params.entity_type.as_str() {
             "decision" => {
                 let seq = store.next_sequence_number();
                 let mut decision = Decision::new(params.title.trim().to_string(), seq);
                 decision.base.content = params.content;
                 decision.base.tags = params.tags.unwrap_or_default();
+                decision.base.created_by = store.author().map(str::to_string);
 
                 // Parse decision-specific properties
                 if let Some(props) = params.properties {
@@ -235,12 +764,12 @@ impl MedullaServer {
                         decision.status = parse_decision_status(status)?;
                     }
                     if let Some(context) = props.get("context").and_then(|v| v.as_str()) {
-                        if context.len() > validation::MAX_CONTEXT_SIZE {
+                        if context.len() > self.config.limits.max_context_size {
                             return Err(McpError::ValidationFailed {
                                 field: "context".to_string(),
                                 message: format!(
                                     "Context exceeds maximum size of {}",
-                                    validation::MAX_CONTEXT_SIZE
+                                    self.config.limits.max_context_size
                                 ),
                             }
                             .into());
@@ -258,6 +787,9 @@ impl MedullaServer {
                     {
                         decision.superseded_by = Some(superseded_by.to_string());
                     }
+                    if let Some(priority) = props.get("priority").and_then(|v| v.as_str()) {
+                        decision.priority = Some(parse_task_priority(priority)?);
+                    }
                 }
 
                 store
@@ -285,6 +817,7 @@ impl MedullaServer {
                 let mut task = Task::new(params.title.trim().to_string(), seq);
                 task.base.content = params.content;
                 task.base.tags = params.tags.unwrap_or_default();
+                task.base.created_by = store.author().map(str::to_string);
 
                 if let Some(props) = params.properties {
                     if let Some(status) = props.get("status").and_then(|v| v.as_str()) {
@@ -299,6 +832,12 @@ impl MedullaServer {
                     if let Some(assignee) = props.get("assignee").and_then(|v| v.as_str()) {
                         task.assignee = Some(assignee.to_string());
                     }
+                    if let Some(estimate) = props.get("estimate_minutes") {
+                        task.estimate_minutes = parse_estimate(estimate)?;
+                    }
+                    if let Some(recurrence) = props.get("recurrence") {
+                        task.recurrence = parse_recurrence(recurrence)?;
+                    }
                 }
 
                 store.add_task(&task).map_err(|e| McpError::from(e))?;
@@ -322,6 +861,7 @@ impl MedullaServer {
                 let mut note = Note::new(params.title.trim().to_string(), seq);
                 note.base.content = params.content;
                 note.base.tags = params.tags.unwrap_or_default();
+                note.base.created_by = store.author().map(str::to_string);
 
                 if let Some(props) = params.properties {
                     if let Some(note_type) = props.get("note_type").and_then(|v| v.as_str()) {
@@ -350,15 +890,16 @@ impl MedullaServer {
                 let mut prompt = Prompt::new(params.title.trim().to_string(), seq);
                 prompt.base.content = params.content;
                 prompt.base.tags = params.tags.unwrap_or_default();
+                prompt.base.created_by = store.author().map(str::to_string);
 
                 if let Some(props) = params.properties {
                     if let Some(template) = props.get("template").and_then(|v| v.as_str()) {
-                        if template.len() > validation::MAX_TEMPLATE_SIZE {
+                        if template.len() > self.config.limits.max_template_size {
                             return Err(McpError::ValidationFailed {
                                 field: "template".to_string(),
                                 message: format!(
                                     "Template exceeds maximum size of {}",
-                                    validation::MAX_TEMPLATE_SIZE
+                                    self.config.limits.max_template_size
                                 ),
                             }
                             .into());
@@ -408,6 +949,7 @@ impl MedullaServer {
                 let mut component = Component::new(params.title.trim().to_string(), seq);
                 component.base.content = params.content;
                 component.base.tags = params.tags.unwrap_or_default();
+                component.base.created_by = store.author().map(str::to_string);
 
                 if let Some(props) = params.properties {
                     if let Some(component_type) =
@@ -421,6 +963,9 @@ impl MedullaServer {
                     if let Some(owner) = props.get("owner").and_then(|v| v.as_str()) {
                         component.owner = Some(owner.to_string());
                     }
+                    if let Some(priority) = props.get("priority").and_then(|v| v.as_str()) {
+                        component.priority = Some(parse_task_priority(priority)?);
+                    }
                 }
 
                 store
@@ -461,12 +1006,16 @@ impl MedullaServer {
                 let mut link = Link::new(params.title.trim().to_string(), url.to_string(), seq);
                 link.base.content = params.content;
                 link.base.tags = params.tags.unwrap_or_default();
+                link.base.created_by = store.author().map(str::to_string);
 
                 if let Some(props) = params.properties {
                     if let Some(link_type) = props.get("link_type").and_then(|v| v.as_str()) {
                         link.link_type = Some(link_type.to_string());
                     }
                 }
+                if let Some(page) = fetched.take() {
+                    page.apply_to(&mut link);
+                }
 
                 store.add_link(&link).map_err(|e| McpError::from(e))?;
                 store.save().map_err(|e| McpError::from(e))?;
@@ -484,24 +1033,168 @@ impl MedullaServer {
 
                 link_to_response(&link)
             }
+            "question" => {
+                let seq = store.next_sequence_number();
+                let mut question = Question::new(params.title.trim().to_string(), seq);
+                question.base.content = params.content;
+                question.base.tags = params.tags.unwrap_or_default();
+                question.base.created_by = store.author().map(str::to_string);
+
+                if let Some(props) = params.properties {
+                    if let Some(answered_by) = props.get("answered_by").and_then(|v| v.as_str()) {
+                        question.answered_by = Some(resolve_answered_by(&store, answered_by)?);
+                        question.status = QuestionStatus::Answered;
+                    }
+                    if let Some(status) = props.get("status").and_then(|v| v.as_str()) {
+                        question.status = parse_question_status(status)?;
+                    }
+                }
+
+                store
+                    .add_question(&question)
+                    .map_err(|e| McpError::from(e))?;
+                store.save().map_err(|e| McpError::from(e))?;
+                cache
+                    .index_question(&question)
+                    .map_err(|e| McpError::from(e))?;
+
+                // Compute embedding
+                Self::try_compute_embedding(
+                    &cache,
+                    &question.base.id.to_string(),
+                    "question",
+                    &question.base.title,
+                    question.base.content.as_deref(),
+                    &question.base.tags,
+                );
+
+                question_to_response(&question)
+            }
+            "milestone" => {
+                let seq = store.next_sequence_number();
+                let mut milestone = Milestone::new(params.title.trim().to_string(), seq);
+                milestone.base.content = params.content;
+                milestone.base.tags = params.tags.unwrap_or_default();
+                milestone.base.created_by = store.author().map(str::to_string);
+
+                if let Some(props) = params.properties {
+                    if let Some(status) = props.get("status").and_then(|v| v.as_str()) {
+                        milestone.status = parse_milestone_status(status)?;
+                    }
+                    if let Some(target_date) = props.get("target_date").and_then(|v| v.as_str()) {
+                        milestone.target_date = Some(parse_date("target_date", target_date)?);
+                    }
+                }
+
+                store
+                    .add_milestone(&milestone)
+                    .map_err(|e| McpError::from(e))?;
+                store.save().map_err(|e| McpError::from(e))?;
+                cache
+                    .index_milestone(&milestone)
+                    .map_err(|e| McpError::from(e))?;
+
+                // Compute embedding
+                Self::try_compute_embedding(
+                    &cache,
+                    &milestone.base.id.to_string(),
+                    "milestone",
+                    &milestone.base.title,
+                    milestone.base.content.as_deref(),
+                    &milestone.base.tags,
+                );
+
+                milestone_to_response(&milestone)
+            }
             _ => unreachable!(), // Already validated
         };
-
+        if let Some(ref collection) = collection {
+            apply_collection(&store, &cache, &response.id, Some(collection))?;
+        }
+        Self::add_inferred_references(
+            &store,
+            &cache,
+            &response.id,
+            &response.entity_type,
+            content.as_deref(),
+        )?;
+
+        let response = EntityCreateResponse {
+            entity: response,
+            duplicate_candidates,
+        };
         let json =
             serde_json::to_string_pretty(&response).map_err(|e| McpError::InternalError {
                 message: format!("Failed to serialize response: {}", e),
             })?;
 
+        drop(cache);
+        drop(store);
+        self.notify_resources_updated(resources::entity_change_uris(
+            &response.entity.entity_type,
+            &response.entity.id,
+            response.entity.sequence_number,
+        ))
+        .await;
+
         Ok(CallToolResult::success(vec![Content::text(json)]))
     }
 
+    // ========================================================================
+    // entity_create_from_template
+    // ========================================================================
+
+    /// Create an entity from a stored template.
+    #[tool(
+        description = "Create an entity from a stored template (see `medulla template list`). The template supplies the type, default tags, properties and a content skeleton; the given content replaces the skeleton, tags are added, and properties override the defaults."
+    )]
+    pub async fn entity_create_from_template(
+        &self,
+        Parameters(params): Parameters<EntityCreateFromTemplateParams>,
+    ) -> Result<CallToolResult, McpErrorData> {
+        // Look the template up first: entity_create takes the store lock itself
+        let template = {
+            let store = self.store.lock().await;
+            match store
+                .get_template(&params.template)
+                .map_err(McpError::from)?
+            {
+                Some(template) => template,
+                None => {
+                    let available: Vec<String> = store
+                        .list_templates()
+                        .map_err(McpError::from)?
+                        .into_iter()
+                        .map(|t| t.name)
+                        .collect();
+                    return Err(McpError::ValidationFailed {
+                        field: "template".to_string(),
+                        message: format!(
+                            "Unknown template '{}'. Available: {}",
+                            params.template,
+                            if available.is_empty() {
+                                "none".to_string()
+                            } else {
+                                available.join(", ")
+                            }
+                        ),
+                    }
+                    .into());
+                }
+            }
+        };
+
+        let create = template_create_params(&template, params, chrono::Utc::now().date_naive())?;
+        self.entity_create(Parameters(create)).await
+    }
+
     // ========================================================================
     // entity_get
     // ========================================================================
 
     /// Get an entity by ID (sequence number or UUID prefix).
     #[tool(
-        description = "Get an entity by ID (sequence number like '1' or UUID prefix like 'abc123')"
+        description = "Get an entity by ID (sequence number like '1' or UUID prefix like 'abc123'), optionally with its relations inline and summaries of related entities up to `depth` hops away, so one call gathers an entity's context"
     )]
     pub async fn entity_get(
         &self,
@@ -509,144 +1202,366 @@ impl MedullaServer {
     ) -> Result<CallToolResult, McpErrorData> {
         let store = self.store.lock().await;
 
-        // Determine if ID is a sequence number or UUID prefix
-        let is_sequence = params.id.chars().all(|c| c.is_ascii_digit());
-
         // If we have a type hint, search only that type
+        let mut found = None;
         if let Some(ref entity_type) = params.entity_type {
             validate_entity_type(entity_type)?;
-
-            let response = self.find_entity_by_id(&store, entity_type, &params.id, is_sequence)?;
-
-            if let Some(resp) = response {
-                let json =
-                    serde_json::to_string_pretty(&resp).map_err(|e| McpError::InternalError {
-                        message: format!("Failed to serialize response: {}", e),
-                    })?;
-                return Ok(CallToolResult::success(vec![Content::text(json)]));
+            found = self.find_entity_by_id(&store, entity_type, &params.id)?;
+        } else {
+            // Search all entity types
+            for entity_type in VALID_ENTITY_TYPES {
+                found = self.find_entity_by_id(&store, entity_type, &params.id)?;
+                if found.is_some() {
+                    break;
+                }
             }
+        }
 
+        let Some(resp) = found else {
             return Err(McpError::EntityNotFound {
                 id: params.id.clone(),
             }
             .into());
+        };
+
+        let mut response = serde_json::to_value(&resp).map_err(|e| McpError::InternalError {
+            message: format!("Failed to serialize response: {}", e),
+        })?;
+
+        if params.include_relations.unwrap_or(false) {
+            let (outgoing, incoming) = self.inline_relations(&store, &resp.id)?;
+            response["relations"] = serde_json::json!({
+                "outgoing": outgoing,
+                "incoming": incoming,
+            });
         }
 
-        // Search all entity types
-        for entity_type in VALID_ENTITY_TYPES {
-            if let Some(response) =
-                self.find_entity_by_id(&store, entity_type, &params.id, is_sequence)?
-            {
-                let json = serde_json::to_string_pretty(&response).map_err(|e| {
-                    McpError::InternalError {
-                        message: format!("Failed to serialize response: {}", e),
-                    }
+        if params.include_related_entities.unwrap_or(false) {
+            let depth = params
+                .depth
+                .unwrap_or(1)
+                .clamp(1, crate::graph::MAX_RELATED_DEPTH as u32);
+            let related = self.related_summaries(&store, &resp.id, depth as usize)?;
+            response["related_entities"] =
+                serde_json::to_value(&related).map_err(|e| McpError::InternalError {
+                    message: format!("Failed to serialize response: {}", e),
                 })?;
-                return Ok(CallToolResult::success(vec![Content::text(json)]));
-            }
         }
 
-        Err(McpError::EntityNotFound {
-            id: params.id.clone(),
+        let uuid = uuid::Uuid::parse_str(&resp.id).ok();
+        if let Some(alias) = uuid.and_then(|id| store.alias_of(&id)) {
+            response["alias"] = alias.into();
         }
-        .into())
+        if let Some((collection, sequence)) = uuid.and_then(|id| store.collection_of(&id)) {
+            response["collection_id"] = format!("{}/{}", collection, sequence).into();
+            response["collection"] = collection.into();
+        }
+
+        let attachments = match uuid {
+            Some(id) => store.list_attachments(Some(&id)).map_err(McpError::from)?,
+            None => Vec::new(),
+        };
+        if !attachments.is_empty() {
+            response["attachments"] =
+                serde_json::to_value(&attachments).map_err(|e| McpError::InternalError {
+                    message: format!("Failed to serialize response: {}", e),
+                })?;
+        }
+
+        let json =
+            serde_json::to_string_pretty(&response).map_err(|e| McpError::InternalError {
+                message: format!("Failed to serialize response: {}", e),
+            })?;
+        Ok(CallToolResult::success(vec![Content::text(json)]))
     }
 
     // ========================================================================
-    // entity_list
+    // entity_outline
     // ========================================================================
 
-    /// List entities with optional filters.
+    /// Get the heading outline of an entity's markdown content.
     #[tool(
-        description = "List entities with optional filters by type, status, tag, with pagination"
+        description = "Get a table of contents for an entity's markdown content: nested headings with their levels and anchor slugs."
     )]
-    pub async fn entity_list(
+    pub async fn entity_outline(
         &self,
-        Parameters(params): Parameters<EntityListParams>,
+        Parameters(params): Parameters<EntityOutlineParams>,
     ) -> Result<CallToolResult, McpErrorData> {
         let store = self.store.lock().await;
 
-        let limit = params
-            .limit
-            .unwrap_or(validation::DEFAULT_LIMIT as u32)
-            .min(validation::MAX_LIMIT as u32) as usize;
-        let offset = params.offset.unwrap_or(0) as usize;
+        let (uuid, entity_type) = self.resolve_entity_id_with_type(&store, &params.id)?;
+        let entity = self
+            .find_entity_by_id(&store, &entity_type, &uuid.to_string())?
+            .ok_or_else(|| McpError::EntityNotFound {
+                id: params.id.clone(),
+            })?;
 
-        let mut all_entities: Vec<EntityResponse> = Vec::new();
+        let outline = crate::snapshot::outline(entity.content.as_deref().unwrap_or(""));
 
-        // Determine which types to fetch
-        let types_to_fetch: Vec<&str> = if let Some(ref t) = params.entity_type {
-            validate_entity_type(t)?;
-            vec![t.as_str()]
-        } else {
-            VALID_ENTITY_TYPES.to_vec()
+        let response = serde_json::json!({
+            "id": entity.id,
+            "type": entity_type,
+            "title": entity.title,
+            "outline": outline,
+        });
+
+        let json =
+            serde_json::to_string_pretty(&response).map_err(|e| McpError::InternalError {
+                message: format!("Failed to serialize response: {}", e),
+            })?;
+
+        Ok(CallToolResult::success(vec![Content::text(json)]))
+    }
+
+    // ========================================================================
+    // entity_get_section / entity_patch_section
+    // ========================================================================
+
+    /// Get one section of an entity's markdown content.
+    #[tool(
+        description = "Get one section of an entity's markdown content by heading (e.g. \"Context\", \"Outcome\"). The section includes its subsections. If no section matches, the error lists the available headings."
+    )]
+    pub async fn entity_get_section(
+        &self,
+        Parameters(params): Parameters<EntityGetSectionParams>,
+    ) -> Result<CallToolResult, McpErrorData> {
+        let store = self.store.lock().await;
+
+        let (uuid, entity_type) = self.resolve_entity_id_with_type(&store, &params.id)?;
+        let entity = self
+            .find_entity_by_id(&store, &entity_type, &uuid.to_string())?
+            .ok_or_else(|| McpError::EntityNotFound {
+                id: params.id.clone(),
+            })?;
+        let content = entity.content.as_deref().unwrap_or("");
+
+        let section = find_section(content, &params.section)
+            .ok_or_else(|| missing_section_error(content, &params.section))?;
+
+        let response = serde_json::json!({
+            "id": entity.id,
+            "type": entity_type,
+            "title": entity.title,
+            "section": section.heading,
+            "level": section.level,
+            "content": section.body,
+        });
+
+        let json =
+            serde_json::to_string_pretty(&response).map_err(|e| McpError::InternalError {
+                message: format!("Failed to serialize response: {}", e),
+            })?;
+
+        Ok(CallToolResult::success(vec![Content::text(json)]))
+    }
+
+    /// Replace, append to or delete one section of an entity's content.
+    #[tool(
+        description = "Update one section of an entity's markdown content without rewriting the rest. mode: replace (default) swaps the section body, append adds to its end, delete removes the heading and body. Replacing or appending to a missing section adds it at the end. Returns the updated entity."
+    )]
+    pub async fn entity_patch_section(
+        &self,
+        Parameters(params): Parameters<EntityPatchSectionParams>,
+    ) -> Result<CallToolResult, McpErrorData> {
+        let edit: SectionEdit = params
+            .mode
+            .as_deref()
+            .unwrap_or("replace")
+            .parse()
+            .map_err(|message| McpError::ValidationFailed {
+                field: "mode".to_string(),
+                message,
+            })?;
+        let level = params.level.unwrap_or(DEFAULT_SECTION_LEVEL);
+        if !(1..=6).contains(&level) {
+            return Err(McpError::ValidationFailed {
+                field: "level".to_string(),
+                message: "Heading level must be between 1 and 6".to_string(),
+            }
+            .into());
+        }
+        if params
+            .section
+            .trim()
+            .trim_start_matches('#')
+            .trim()
+            .is_empty()
+        {
+            return Err(McpError::ValidationFailed {
+                field: "section".to_string(),
+                message: "Section heading cannot be empty".to_string(),
+            }
+            .into());
+        }
+
+        let (id, content) = {
+            let store = self.store.lock().await;
+            let (uuid, entity_type) = self.resolve_entity_id_with_type(&store, &params.id)?;
+            let entity = self
+                .find_entity_by_id(&store, &entity_type, &uuid.to_string())?
+                .ok_or_else(|| McpError::EntityNotFound {
+                    id: params.id.clone(),
+                })?;
+            (entity.id, entity.content.unwrap_or_default())
         };
 
-        for entity_type in types_to_fetch {
-            match entity_type {
-                "decision" => {
-                    let decisions = store.list_decisions().map_err(McpError::from)?;
-                    for d in decisions {
-                        if self.matches_filters(&d.base, &params, Some(&d.status.to_string())) {
-                            all_entities.push(decision_to_response(&d));
-                        }
-                    }
-                }
-                "task" => {
-                    let tasks = store.list_tasks().map_err(McpError::from)?;
-                    for t in tasks {
-                        if self.matches_filters(&t.base, &params, Some(&t.status.to_string())) {
-                            all_entities.push(task_to_response(&t));
-                        }
-                    }
-                }
-                "note" => {
-                    let notes = store.list_notes().map_err(McpError::from)?;
-                    for n in notes {
-                        if self.matches_filters(&n.base, &params, None) {
-                            all_entities.push(note_to_response(&n));
-                        }
-                    }
-                }
-                "prompt" => {
-                    let prompts = store.list_prompts().map_err(McpError::from)?;
-                    for p in prompts {
-                        if self.matches_filters(&p.base, &params, None) {
-                            all_entities.push(prompt_to_response(&p));
-                        }
-                    }
-                }
-                "component" => {
-                    let components = store.list_components().map_err(McpError::from)?;
-                    for c in components {
-                        if self.matches_filters(&c.base, &params, Some(&c.status.to_string())) {
-                            all_entities.push(component_to_response(&c));
-                        }
-                    }
+        let patched = patch_section(
+            &content,
+            &params.section,
+            edit,
+            params.content.as_deref().unwrap_or(""),
+            level,
+        )
+        .ok_or_else(|| missing_section_error(&content, &params.section))?;
+
+        // Go through entity_update so the cache, embeddings and inferred
+        // references stay in step with the new content
+        self.entity_update(Parameters(EntityUpdateParams {
+            id,
+            title: None,
+            content: Some(patched),
+            add_tags: None,
+            remove_tags: None,
+            properties: None,
+            force: None,
+        }))
+        .await
+    }
+
+    // ========================================================================
+    // entity_list
+    // ========================================================================
+
+    /// List entities with optional filters.
+    #[tool(
+        description = "List entities with optional filters by type, status, tag, collection and created/updated date, sorted by sequence, created, updated, title or priority, with pagination"
+    )]
+    pub async fn entity_list(
+        &self,
+        Parameters(params): Parameters<EntityListParams>,
+    ) -> Result<CallToolResult, McpErrorData> {
+        let store = self.store.lock().await;
+
+        let limit = params
+            .limit
+            .unwrap_or(validation::DEFAULT_LIMIT as u32)
+            .min(self.config.limits.max_limit as u32) as usize;
+        let offset = params.offset.unwrap_or(0) as usize;
+
+        let after_sequence = match params.cursor.as_deref() {
+            Some(_)
+                if params.offset.is_some()
+                    || params.sort_by.is_some()
+                    || params.order.is_some() =>
+            {
+                return Err(McpError::ValidationFailed {
+                    field: "cursor".to_string(),
+                    message: "cursor cannot be combined with offset, sort_by or order".to_string(),
                 }
-                "link" => {
-                    let links = store.list_links().map_err(McpError::from)?;
-                    for l in links {
-                        if self.matches_filters(&l.base, &params, None) {
-                            all_entities.push(link_to_response(&l));
-                        }
-                    }
+                .into());
+            }
+            Some(cursor) => {
+                Some(
+                    decode_list_cursor(cursor).ok_or_else(|| McpError::ValidationFailed {
+                        field: "cursor".to_string(),
+                        message: format!("Invalid cursor '{}'", cursor),
+                    })?,
+                )
+            }
+            None => None,
+        };
+        let sort: EntitySort = match params.sort_by.as_deref() {
+            None => EntitySort::Sequence,
+            Some(s) => s.parse().map_err(|_| McpError::InvalidEnumValue {
+                field: "sort_by".to_string(),
+                value: s.to_string(),
+                valid: ["sequence", "created", "updated", "title", "priority"]
+                    .iter()
+                    .map(|v| v.to_string())
+                    .collect(),
+            })?,
+        };
+        let descending = match params.order.as_deref() {
+            None => sort == EntitySort::Priority,
+            Some(o) if o.eq_ignore_ascii_case("asc") => false,
+            Some(o) if o.eq_ignore_ascii_case("desc") => true,
+            Some(o) => {
+                return Err(McpError::InvalidEnumValue {
+                    field: "order".to_string(),
+                    value: o.to_string(),
+                    valid: vec!["asc".to_string(), "desc".to_string()],
                 }
-                _ => {}
+                .into());
             }
+        };
+        let date_param = |field: &str, value: &Option<String>| match value {
+            None => Ok(None),
+            Some(v) => parse_datetime(v)
+                .map(Some)
+                .ok_or_else(|| McpError::ValidationFailed {
+                    field: field.to_string(),
+                    message: format!("Invalid date '{}'. Use RFC 3339 or YYYY-MM-DD", v),
+                }),
+        };
+        let created_after = date_param("created_after", &params.created_after)?;
+        let updated_after = date_param("updated_after", &params.updated_after)?;
+        if let Some(ref t) = params.entity_type {
+            validate_entity_type(t)?;
         }
+        let collection = params
+            .collection
+            .as_deref()
+            .map(|c| c.trim().to_lowercase());
+
+        // Filters, order and paging all run in SQL against the cache; only
+        // the page itself is loaded from the store.
+        // One extra row tells whether there is a page after this one
+        let (mut rows, total) = {
+            let cache = self.cache.lock().await;
+            cache
+                .list_entity_page(&EntityPageQuery {
+                    entity_type: params.entity_type.as_deref(),
+                    status: params.status.as_deref(),
+                    tag: params.tag.as_deref(),
+                    created_after,
+                    updated_after,
+                    collection: collection.as_deref(),
+                    sort,
+                    descending,
+                    after_sequence,
+                    offset,
+                    limit: limit + 1,
+                })
+                .map_err(McpError::from)?
+        };
+        let has_more = rows.len() > limit;
+        rows.truncate(limit);
 
-        let total = all_entities.len();
+        let mut entities = Vec::with_capacity(rows.len());
+        for (id, entity_type, _) in &rows {
+            let Ok(uuid) = uuid::Uuid::parse_str(id) else {
+                continue;
+            };
+            if let Some(entity) = entity_response(&store, entity_type, &uuid)? {
+                entities.push(entity);
+            }
+        }
 
-        // Apply pagination
-        let paginated: Vec<EntityResponse> =
-            all_entities.into_iter().skip(offset).take(limit).collect();
+        // Cursors are sequence positions, so only sequence order gets one
+        let next_cursor = rows
+            .last()
+            .filter(|_| has_more && sort == EntitySort::Sequence && !descending)
+            .map(|(_, _, seq)| encode_list_cursor(*seq));
+        let next_offset = (has_more && after_sequence.is_none()).then(|| offset + rows.len());
 
         let response = serde_json::json!({
-            "entities": paginated,
+            "entities": entities,
             "total": total,
             "limit": limit,
             "offset": offset,
+            "has_more": has_more,
+            "next_offset": next_offset,
+            "next_cursor": next_cursor,
         });
 
         let json =
@@ -657,12 +1572,47 @@ impl MedullaServer {
         Ok(CallToolResult::success(vec![Content::text(json)]))
     }
 
+    // ========================================================================
+    // entity_index
+    // ========================================================================
+
+    /// List every entity of a type with only minimal fields.
+    #[tool(
+        description = "Compact, unpaginated index of all entities of a type (sequence_number, title, status and alias only)"
+    )]
+    pub async fn entity_index(
+        &self,
+        Parameters(params): Parameters<EntityIndexParams>,
+    ) -> Result<CallToolResult, McpErrorData> {
+        validate_entity_type(&params.entity_type)?;
+
+        let cache = self.cache.lock().await;
+        let entries = cache
+            .get_entity_index(&params.entity_type)
+            .map_err(McpError::from)?;
+
+        let response = serde_json::json!({
+            "type": params.entity_type,
+            "entries": entries,
+            "total": entries.len(),
+        });
+
+        let json =
+            serde_json::to_string_pretty(&response).map_err(|e| McpError::InternalError {
+                message: format!("Failed to serialize index: {}", e),
+            })?;
+
+        Ok(CallToolResult::success(vec![Content::text(json)]))
+    }
+
     // ========================================================================
     // entity_update
     // ========================================================================
 
     /// Update an existing entity.
-    #[tool(description = "Update an existing entity's title, content, tags, or properties")]
+    #[tool(
+        description = "Update an existing entity's title, content, tags, or properties. The `collection` property moves the entity to a collection, or out of its collection when null. Status changes must follow the workflow in the project config unless force is true."
+    )]
     pub async fn entity_update(
         &self,
         Parameters(params): Parameters<EntityUpdateParams>,
@@ -673,21 +1623,56 @@ impl MedullaServer {
         }
         validate_content(&params.content)?;
         validate_tags(&params.add_tags)?;
+        self.check_enum_properties(params.properties.as_ref())?;
+        let collection = collection_property(params.properties.as_ref())?;
 
         let store = self.store.lock().await;
         let cache = self.cache.lock().await;
 
         // Find the entity by ID
-        let is_sequence = params.id.chars().all(|c| c.is_ascii_digit());
+
+        // Check validation rules and the status workflow against the
+        // post-update state before writing
+        let check_workflow = !self.config.workflows.is_empty() && !params.force.unwrap_or(false);
+        if !self.config.validation_rules.is_empty() || check_workflow {
+            for entity_type in VALID_ENTITY_TYPES {
+                if let Some(current) = self.find_entity_by_id(&store, entity_type, &params.id)? {
+                    if check_workflow {
+                        self.check_status_transition(&current, &params)?;
+                    }
+                    if !self.config.validation_rules.is_empty() {
+                        self.check_update_rules(&current, &params)?;
+                    }
+                    break;
+                }
+            }
+        }
 
         for entity_type in VALID_ENTITY_TYPES {
-            let response =
-                self.try_update_entity(&store, &cache, entity_type, &params, is_sequence)?;
+            let response = self.try_update_entity(&store, &cache, entity_type, &params)?;
             if let Some(resp) = response {
+                if let Some(ref collection) = collection {
+                    apply_collection(&store, &cache, &resp.id, collection.as_deref())?;
+                }
+                Self::add_inferred_references(
+                    &store,
+                    &cache,
+                    &resp.id,
+                    &resp.entity_type,
+                    params.content.as_deref(),
+                )?;
                 let json =
                     serde_json::to_string_pretty(&resp).map_err(|e| McpError::InternalError {
                         message: format!("Failed to serialize response: {}", e),
                     })?;
+                drop(cache);
+                drop(store);
+                self.notify_resources_updated(resources::entity_change_uris(
+                    &resp.entity_type,
+                    &resp.id,
+                    resp.sequence_number,
+                ))
+                .await;
                 return Ok(CallToolResult::success(vec![Content::text(json)]));
             }
         }
@@ -702,8 +1687,10 @@ impl MedullaServer {
     // entity_delete
     // ========================================================================
 
-    /// Delete an entity by ID.
-    #[tool(description = "Delete an entity by ID")]
+    /// Move an entity to the trash by ID.
+    #[tool(
+        description = "Delete an entity by ID. The entity and its relations move to the trash and can be restored with `medulla trash restore`."
+    )]
     pub async fn entity_delete(
         &self,
         Parameters(params): Parameters<EntityDeleteParams>,
@@ -711,14 +1698,22 @@ impl MedullaServer {
         let store = self.store.lock().await;
         let cache = self.cache.lock().await;
 
-        let is_sequence = params.id.chars().all(|c| c.is_ascii_digit());
-
         for entity_type in VALID_ENTITY_TYPES {
-            let deleted =
-                self.try_delete_entity(&store, &cache, entity_type, &params.id, is_sequence)?;
+            let existing = self.find_entity_by_id(&store, entity_type, &params.id)?;
+            let deleted = self.try_delete_entity(&store, &cache, entity_type, &params.id)?;
             if deleted {
+                drop(cache);
+                drop(store);
+                if let Some(resp) = existing {
+                    self.notify_resources_updated(resources::entity_change_uris(
+                        &resp.entity_type,
+                        &resp.id,
+                        resp.sequence_number,
+                    ))
+                    .await;
+                }
                 return Ok(CallToolResult::success(vec![Content::text(format!(
-                    "Entity {} deleted successfully",
+                    "Entity {} moved to the trash (restore with `medulla trash restore`)",
                     params.id
                 ))]));
             }
@@ -730,135 +1725,303 @@ impl MedullaServer {
         .into())
     }
 
+    // ========================================================================
+    // entity_history
+    // ========================================================================
+
+    /// List past versions of an entity, or its state at a point in time.
+    #[tool(
+        description = "List past versions of an entity from the CRDT history (which peer changed which fields, and when). With 'at', return the entity's state as of that time."
+    )]
+    pub async fn entity_history(
+        &self,
+        Parameters(params): Parameters<EntityHistoryParams>,
+    ) -> Result<CallToolResult, McpErrorData> {
+        let at = match params.at {
+            Some(ref at) => Some(
+                parse_datetime(at).ok_or_else(|| McpError::ValidationFailed {
+                    field: "at".to_string(),
+                    message: format!("Invalid datetime '{}', expected ISO 8601 or YYYY-MM-DD", at),
+                })?,
+            ),
+            None => None,
+        };
+
+        let store = self.store.lock().await;
+        // Deleted entities can no longer be resolved, so accept a full UUID as is
+        let uuid = match uuid::Uuid::parse_str(&params.id) {
+            Ok(uuid) => uuid,
+            Err(_) => self.resolve_entity_id(&store, &params.id)?,
+        };
+        let versions = store.entity_history(&uuid).map_err(McpError::from)?;
+        if versions.is_empty() {
+            return Err(McpError::EntityNotFound {
+                id: params.id.clone(),
+            }
+            .into());
+        }
+
+        let response = match at {
+            Some(at) => {
+                let found = versions
+                    .iter()
+                    .enumerate()
+                    .rev()
+                    .find(|(_, v)| v.timestamp.is_some_and(|t| t <= at));
+                let Some((index, version)) = found else {
+                    return Err(McpError::ValidationFailed {
+                        field: "at".to_string(),
+                        message: format!("Entity {} did not exist at {}", params.id, at),
+                    }
+                    .into());
+                };
+                serde_json::json!({
+                    "id": uuid.to_string(),
+                    "at": at.to_rfc3339(),
+                    "version": index + 1,
+                    "timestamp": version.timestamp.map(|t| t.to_rfc3339()),
+                    "deleted": version.state.is_none(),
+                    "state": version.state,
+                })
+            }
+            None => {
+                let list: Vec<serde_json::Value> = versions
+                    .iter()
+                    .enumerate()
+                    .map(|(index, v)| {
+                        serde_json::json!({
+                            "version": index + 1,
+                            "peer": v.peer.to_string(),
+                            "timestamp": v.timestamp.map(|t| t.to_rfc3339()),
+                            "changed_fields": v.changed_fields,
+                            "deleted": v.state.is_none(),
+                        })
+                    })
+                    .collect();
+                serde_json::json!({
+                    "id": uuid.to_string(),
+                    "versions": list,
+                    "total": list.len(),
+                })
+            }
+        };
+
+        let json =
+            serde_json::to_string_pretty(&response).map_err(|e| McpError::InternalError {
+                message: format!("Failed to serialize history: {}", e),
+            })?;
+
+        Ok(CallToolResult::success(vec![Content::text(json)]))
+    }
+
     // ========================================================================
     // entity_batch
     // ========================================================================
 
-    /// Execute multiple entity operations in a batch (best-effort semantics).
+    /// Execute multiple entity operations in a batch (best-effort semantics,
+    /// or all-or-nothing with `atomic`).
     #[tool(
-        description = "Execute multiple entity operations in a batch. Operations run sequentially with best-effort semantics."
+        description = "Execute multiple entity operations in a batch. Operations run sequentially with best-effort semantics; with atomic=true they are all applied or, if any fails, none are (the result's abort_reason says why)."
     )]
     pub async fn entity_batch(
         &self,
         Parameters(params): Parameters<EntityBatchParams>,
     ) -> Result<CallToolResult, McpErrorData> {
-        if params.operations.len() > validation::MAX_BATCH_SIZE {
+        if params.operations.len() > self.config.limits.max_batch_size {
             return Err(McpError::ValidationFailed {
                 field: "operations".to_string(),
                 message: format!(
                     "Maximum {} operations allowed per batch",
-                    validation::MAX_BATCH_SIZE
+                    self.config.limits.max_batch_size
                 ),
             }
             .into());
         }
 
-        let mut results = Vec::new();
-        let mut succeeded = 0;
-        let mut failed = 0;
+        let batch_result = if params.atomic.unwrap_or(false) {
+            self.run_atomic_batch(params.operations).await?
+        } else {
+            self.coalescing_notifications(|server| async move {
+                server.run_batch(params.operations, false).await
+            })
+            .await
+        };
 
-        for (index, op) in params.operations.into_iter().enumerate() {
-            let result = match op {
-                BatchOperation::Create(create_params) => {
-                    match self.entity_create(Parameters(create_params)).await {
-                        Ok(tool_result) => {
-                            // Extract ID from the result
-                            let id = tool_result.content.first().and_then(|c| {
-                                if let RawContent::Text(ref t) = c.raw {
-                                    serde_json::from_str::<EntityResponse>(&t.text)
-                                        .ok()
-                                        .map(|r| r.id)
-                                } else {
-                                    None
-                                }
-                            });
-                            succeeded += 1;
-                            BatchOperationResult {
-                                index,
-                                success: true,
-                                id,
-                                error: None,
-                            }
-                        }
-                        Err(e) => {
-                            failed += 1;
-                            BatchOperationResult {
-                                index,
-                                success: false,
-                                id: None,
-                                error: Some(BatchError {
-                                    code: "CREATE_FAILED".to_string(),
-                                    message: e.message.to_string(),
-                                }),
-                            }
-                        }
-                    }
-                }
-                BatchOperation::Update(update_params) => {
-                    let id = update_params.id.clone();
-                    match self.entity_update(Parameters(update_params)).await {
-                        Ok(_) => {
-                            succeeded += 1;
-                            BatchOperationResult {
-                                index,
-                                success: true,
-                                id: Some(id),
-                                error: None,
-                            }
-                        }
-                        Err(e) => {
-                            failed += 1;
-                            BatchOperationResult {
-                                index,
-                                success: false,
-                                id: None,
-                                error: Some(BatchError {
-                                    code: "UPDATE_FAILED".to_string(),
-                                    message: e.message.to_string(),
-                                }),
-                            }
-                        }
-                    }
+        let json =
+            serde_json::to_string_pretty(&batch_result).map_err(|e| McpError::InternalError {
+                message: format!("Failed to serialize batch result: {}", e),
+            })?;
+
+        Ok(CallToolResult::success(vec![Content::text(json)]))
+    }
+
+    // ========================================================================
+    // entity_import_rows
+    // ========================================================================
+
+    /// Create entities from a list of row objects (best-effort semantics).
+    #[tool(
+        description = "Bulk-create entities from row objects (e.g. spreadsheet rows). Each row needs 'type' and 'title'; other fields become properties. Rows are validated independently and reported like entity_batch."
+    )]
+    pub async fn entity_import_rows(
+        &self,
+        Parameters(params): Parameters<EntityImportRowsParams>,
+    ) -> Result<CallToolResult, McpErrorData> {
+        if params.rows.len() > self.config.limits.max_batch_size {
+            return Err(McpError::ValidationFailed {
+                field: "rows".to_string(),
+                message: format!(
+                    "Maximum {} rows allowed per import",
+                    self.config.limits.max_batch_size
+                ),
+            }
+            .into());
+        }
+
+        let rows = params.rows;
+        let batch_result = self
+            .coalescing_notifications(|server| async move { server.import_rows(&rows).await })
+            .await;
+
+        let json =
+            serde_json::to_string_pretty(&batch_result).map_err(|e| McpError::InternalError {
+                message: format!("Failed to serialize import result: {}", e),
+            })?;
+
+        Ok(CallToolResult::success(vec![Content::text(json)]))
+    }
+
+    // ========================================================================
+    // entity_bulk_update
+    // ========================================================================
+
+    /// Add and remove tags on every entity matching a filter expression.
+    #[tool(
+        description = "Add and remove tags on every entity matching a filter expression such as \"type:task status:done\" (the search_fulltext syntax; leftover words are matched with full-text search). dry_run lists the matching entities without changing them. Updates are reported like entity_batch."
+    )]
+    pub async fn entity_bulk_update(
+        &self,
+        Parameters(params): Parameters<EntityBulkUpdateParams>,
+    ) -> Result<CallToolResult, McpErrorData> {
+        let add_tags = params.add_tags.clone().unwrap_or_default();
+        let remove_tags = params.remove_tags.clone().unwrap_or_default();
+        if add_tags.is_empty() && remove_tags.is_empty() {
+            return Err(McpError::ValidationFailed {
+                field: "add_tags".to_string(),
+                message: "Give at least one tag to add or remove".to_string(),
+            }
+            .into());
+        }
+        validate_tags(&params.add_tags)?;
+
+        let (query, filter) = crate::search::parse_query(&params.query);
+        if query.is_empty() && filter.is_empty() {
+            return Err(McpError::ValidationFailed {
+                field: "query".to_string(),
+                message:
+                    "A filter expression is required so the update does not touch every entity"
+                        .to_string(),
+            }
+            .into());
+        }
+        if let Some(ref entity_type) = filter.entity_type {
+            validate_entity_type(entity_type)?;
+        }
+
+        let matches = {
+            let cache = self.cache.lock().await;
+            cache
+                .find_entities(&query, &filter)
+                .map_err(McpError::from)?
+        };
+        let dry_run = params.dry_run.unwrap_or(false);
+        if !dry_run && matches.len() > self.config.limits.max_batch_size {
+            return Err(McpError::ValidationFailed {
+                field: "query".to_string(),
+                message: format!(
+                    "Query matches {} entities; at most {} can be updated at once",
+                    matches.len(),
+                    self.config.limits.max_batch_size
+                ),
+            }
+            .into());
+        }
+
+        let mut entities = Vec::with_capacity(matches.len());
+        {
+            let store = self.store.lock().await;
+            for (id, entity_type) in &matches {
+                let Ok(uuid) = uuid::Uuid::parse_str(id) else {
+                    continue;
+                };
+                if let Some(entity) = entity_response(&store, entity_type, &uuid)? {
+                    entities.push(serde_json::json!({
+                        "id": entity.id,
+                        "type": entity.entity_type,
+                        "sequence_number": entity.sequence_number,
+                        "title": entity.title,
+                        "tags": entity.tags,
+                    }));
                 }
-                BatchOperation::Delete(delete_params) => {
-                    let id = delete_params.id.clone();
-                    match self.entity_delete(Parameters(delete_params)).await {
-                        Ok(_) => {
-                            succeeded += 1;
-                            BatchOperationResult {
-                                index,
-                                success: true,
-                                id: Some(id),
-                                error: None,
-                            }
+            }
+        }
+
+        let mut results = Vec::new();
+        let mut succeeded = 0;
+        let mut failed = 0;
+        if !dry_run {
+            for (index, (id, _)) in matches.iter().enumerate() {
+                let update = EntityUpdateParams {
+                    id: id.clone(),
+                    title: None,
+                    content: None,
+                    add_tags: params.add_tags.clone(),
+                    remove_tags: params.remove_tags.clone(),
+                    properties: None,
+                    force: None,
+                };
+                let result = match self.entity_update(Parameters(update)).await {
+                    Ok(_) => {
+                        succeeded += 1;
+                        BatchOperationResult {
+                            index,
+                            success: true,
+                            id: Some(id.clone()),
+                            error: None,
                         }
-                        Err(e) => {
-                            failed += 1;
-                            BatchOperationResult {
-                                index,
-                                success: false,
-                                id: None,
-                                error: Some(BatchError {
-                                    code: "DELETE_FAILED".to_string(),
-                                    message: e.message.to_string(),
-                                }),
-                            }
+                    }
+                    Err(e) => {
+                        failed += 1;
+                        BatchOperationResult {
+                            index,
+                            success: false,
+                            id: Some(id.clone()),
+                            error: Some(BatchError {
+                                code: "UPDATE_FAILED".to_string(),
+                                message: e.message.to_string(),
+                            }),
                         }
                     }
-                }
-            };
-            results.push(result);
+                };
+                results.push(result);
+            }
         }
 
-        let batch_result = BatchResult {
-            results,
-            succeeded,
-            failed,
-        };
+        let response = serde_json::json!({
+            "query": params.query,
+            "dry_run": dry_run,
+            "matched": entities.len(),
+            "entities": entities,
+            "add_tags": add_tags,
+            "remove_tags": remove_tags,
+            "results": results,
+            "succeeded": succeeded,
+            "failed": failed,
+        });
 
         let json =
-            serde_json::to_string_pretty(&batch_result).map_err(|e| McpError::InternalError {
-                message: format!("Failed to serialize batch result: {}", e),
+            serde_json::to_string_pretty(&response).map_err(|e| McpError::InternalError {
+                message: format!("Failed to serialize bulk update result: {}", e),
             })?;
 
         Ok(CallToolResult::success(vec![Content::text(json)]))
@@ -869,7 +2032,14 @@ impl MedullaServer {
     // ========================================================================
 
     /// Full-text search across entities.
-    #[tool(description = "Full-text search across entities via SQLite FTS5")]
+    ///
+    /// `type:`, `status:`, `tag:`, `created:>` / `created:<`, `created_by:`
+    /// and `assignee:` tokens in the query act as filters, applied in the
+    /// cache query so the limit counts matching results. A query made only
+    /// of filters lists the matching entities instead.
+    #[tool(
+        description = "Full-text search across entities via SQLite FTS5. The query may include type:, status:, tag: (repeatable), created:>DATE, created:<DATE, created_by: and assignee: filters; a filters-only query lists matching entities."
+    )]
     pub async fn search_fulltext(
         &self,
         Parameters(params): Parameters<SearchFulltextParams>,
@@ -882,132 +2052,137 @@ impl MedullaServer {
             .into());
         }
 
-        if let Some(ref entity_type) = params.entity_type {
+        let (query, filter) = crate::search::parse_query(&params.query);
+        let entity_type = params.entity_type.clone().or(filter.entity_type.clone());
+        if let Some(ref entity_type) = entity_type {
             validate_entity_type(entity_type)?;
         }
 
+        // Nothing left to match on: fall through to a filtered listing,
+        // refusing filters the listing can't apply rather than ignoring them
+        if query.is_empty() {
+            if filter.tags.len() > 1
+                || filter.created_before.is_some()
+                || filter.created_by.is_some()
+                || filter.assignee.is_some()
+            {
+                return Err(McpError::ValidationFailed {
+                    field: "query".to_string(),
+                    message: "A query of only filters supports type:, status:, created:> and a single tag:"
+                        .to_string(),
+                }
+                .into());
+            }
+            if params.within_focus.unwrap_or(false) {
+                return Err(McpError::ValidationFailed {
+                    field: "within_focus".to_string(),
+                    message:
+                        "within_focus needs search terms; a query of only filters lists every match"
+                            .to_string(),
+                }
+                .into());
+            }
+            return self
+                .entity_list(Parameters(EntityListParams {
+                    entity_type,
+                    status: filter.status,
+                    tag: filter.tags.into_iter().next(),
+                    collection: params.collection,
+                    created_after: filter.created_after.map(|t| t.to_rfc3339()),
+                    updated_after: None,
+                    sort_by: None,
+                    order: None,
+                    limit: params.limit,
+                    offset: params.offset,
+                    cursor: None,
+                }))
+                .await;
+        }
+
+        // Resolve the focus set and collection before taking the cache lock;
+        // results must be in both when both are asked for
+        let scope: Option<HashSet<String>> = {
+            let store = self.store.lock().await;
+            let focus: Option<HashSet<String>> = if params.within_focus.unwrap_or(false) {
+                let ids = store.get_focus().map_err(McpError::from)?;
+                Some(ids.iter().map(|id| id.to_string()).collect())
+            } else {
+                None
+            };
+            let members = params
+                .collection
+                .as_deref()
+                .map(|c| store.collection_members(c));
+            match (focus, members) {
+                (Some(focus), Some(members)) => Some(&focus & &members),
+                (focus, members) => focus.or(members),
+            }
+        };
+
         let cache = self.cache.lock().await;
         let limit = params
             .limit
             .unwrap_or(validation::DEFAULT_LIMIT as u32)
-            .min(validation::MAX_LIMIT as u32) as i64;
-
-        let mut results: Vec<serde_json::Value> = Vec::new();
+            .min(self.config.limits.max_limit as u32) as usize;
+        let offset = params.offset.unwrap_or(0) as usize;
 
         // Determine which types to search
-        let types_to_search: Vec<&str> = if let Some(ref t) = params.entity_type {
+        let types_to_search: Vec<&str> = if let Some(ref t) = entity_type {
             vec![t.as_str()]
         } else {
             VALID_ENTITY_TYPES.to_vec()
         };
 
+        // A page of the merged ranking can only draw on the top
+        // `offset + limit` hits of each type. The focus set and collection
+        // are applied after the query, so they need every hit to count them.
+        let fetch = if scope.is_some() {
+            -1
+        } else {
+            (offset + limit) as i64
+        };
+
+        let mut hits: Vec<SearchResult> = Vec::new();
+        let mut counts: BTreeMap<String, usize> = BTreeMap::new();
         for entity_type in types_to_search {
-            match entity_type {
-                "decision" => {
-                    if let Ok(search_results) = cache.search_decisions(&params.query, limit) {
-                        for r in search_results {
-                            results.push(serde_json::json!({
-                                "type": "decision",
-                                "id": r.id,
-                                "sequence_number": r.sequence_number,
-                                "title": r.title,
-                                "status": r.status,
-                                "title_highlight": r.title_highlight,
-                                "content_snippet": r.content_snippet,
-                            }));
-                        }
-                    }
-                }
-                "task" => {
-                    if let Ok(search_results) = cache.search_tasks(&params.query, limit) {
-                        for r in search_results {
-                            results.push(serde_json::json!({
-                                "type": "task",
-                                "id": r.id,
-                                "sequence_number": r.sequence_number,
-                                "title": r.title,
-                                "status": r.status,
-                                "priority": r.priority,
-                                "title_highlight": r.title_highlight,
-                                "content_snippet": r.content_snippet,
-                            }));
-                        }
-                    }
-                }
-                "note" => {
-                    if let Ok(search_results) = cache.search_notes(&params.query, limit) {
-                        for r in search_results {
-                            results.push(serde_json::json!({
-                                "type": "note",
-                                "id": r.id,
-                                "sequence_number": r.sequence_number,
-                                "title": r.title,
-                                "note_type": r.note_type,
-                                "title_highlight": r.title_highlight,
-                                "content_snippet": r.content_snippet,
-                            }));
-                        }
-                    }
-                }
-                "prompt" => {
-                    if let Ok(search_results) = cache.search_prompts(&params.query, limit) {
-                        for r in search_results {
-                            results.push(serde_json::json!({
-                                "type": "prompt",
-                                "id": r.id,
-                                "sequence_number": r.sequence_number,
-                                "title": r.title,
-                                "variables": r.variables,
-                                "title_highlight": r.title_highlight,
-                                "content_snippet": r.content_snippet,
-                            }));
-                        }
-                    }
-                }
-                "component" => {
-                    if let Ok(search_results) = cache.search_components(&params.query, limit) {
-                        for r in search_results {
-                            results.push(serde_json::json!({
-                                "type": "component",
-                                "id": r.id,
-                                "sequence_number": r.sequence_number,
-                                "title": r.title,
-                                "status": r.status,
-                                "component_type": r.component_type,
-                                "title_highlight": r.title_highlight,
-                                "content_snippet": r.content_snippet,
-                            }));
-                        }
-                    }
-                }
-                "link" => {
-                    if let Ok(search_results) = cache.search_links(&params.query, limit) {
-                        for r in search_results {
-                            results.push(serde_json::json!({
-                                "type": "link",
-                                "id": r.id,
-                                "sequence_number": r.sequence_number,
-                                "title": r.title,
-                                "url": r.url,
-                                "link_type": r.link_type,
-                                "title_highlight": r.title_highlight,
-                                "content_snippet": r.content_snippet,
-                            }));
-                        }
-                    }
-                }
-                _ => {}
-            }
+            let mut type_hits = cache
+                .search_by_type_filtered(entity_type, &query, &filter, fetch)
+                .unwrap_or_default();
+            let count = if let Some(ref scope) = scope {
+                type_hits.retain(|r| scope.contains(r.id()));
+                type_hits.len()
+            } else {
+                cache
+                    .count_search_filtered(entity_type, &query, &filter)
+                    .unwrap_or(type_hits.len())
+            };
+            counts.insert(entity_type.to_string(), count);
+            hits.extend(type_hits);
         }
 
-        // Truncate to limit
-        results.truncate(limit as usize);
+        // Merge the types by relevance; the sort is stable, so ties keep
+        // the fixed type order
+        hits.sort_by(|a, b| a.rank().total_cmp(&b.rank()));
 
-        let response = serde_json::json!({
-            "results": results,
-            "total": results.len(),
-            "query": params.query,
-        });
+        let total = counts.values().sum();
+        let results: Vec<FulltextHit> = hits
+            .into_iter()
+            .skip(offset)
+            .take(limit)
+            .map(FulltextHit::from)
+            .collect();
+        let next_offset = next_page_offset(offset, results.len(), total);
+
+        let response = FulltextSearchResponse {
+            total,
+            counts,
+            limit,
+            offset,
+            has_more: next_offset.is_some(),
+            next_offset,
+            results,
+            query: params.query,
+        };
 
         let json =
             serde_json::to_string_pretty(&response).map_err(|e| McpError::InternalError {
@@ -1022,7 +2197,12 @@ impl MedullaServer {
     // ========================================================================
 
     /// Semantic similarity search across entities using vector embeddings.
-    #[tool(description = "Search entities by semantic similarity to a natural language query")]
+    ///
+    /// With `prefilter`, a full-text query narrows the candidates first and
+    /// only its hits are re-ranked by similarity.
+    #[tool(
+        description = "Search entities by semantic similarity to a natural language query. min_score sets the similarity cutoff (default 0.3); prefilter is a full-text query that candidates must also match before being ranked by similarity."
+    )]
     pub async fn search_semantic(
         &self,
         Parameters(params): Parameters<SearchSemanticParams>,
@@ -1039,6 +2219,15 @@ impl MedullaServer {
             validate_entity_type(entity_type)?;
         }
 
+        let min_score = params.min_score.or(params.threshold).unwrap_or(0.3);
+        if !(0.0..=1.0).contains(&min_score) {
+            return Err(McpError::ValidationFailed {
+                field: "min_score".to_string(),
+                message: "min_score must be between 0.0 and 1.0".to_string(),
+            }
+            .into());
+        }
+
         // Get the embedder
         let embedder = Self::get_embedder().ok_or_else(|| McpError::InternalError {
             message: "Embedding model not available".to_string(),
@@ -1053,27 +2242,56 @@ impl MedullaServer {
                 })?;
 
         let cache = self.cache.lock().await;
-        let limit = params.limit.unwrap_or(10).min(100) as usize;
-        let threshold = params.threshold.unwrap_or(0.3);
+        let limit = params
+            .limit
+            .unwrap_or(10)
+            .min(self.config.limits.max_limit as u32) as usize;
+
+        // Rank only the full-text hits of the prefilter query, if given
+        let prefilter = params
+            .prefilter
+            .as_deref()
+            .map(str::trim)
+            .filter(|q| !q.is_empty());
+        let candidates: Option<HashSet<String>> = match prefilter {
+            Some(fts_query) => {
+                let hits = match params.entity_type.as_deref() {
+                    Some(entity_type) => {
+                        cache.search_by_type(entity_type, fts_query, PREFILTER_LIMIT)
+                    }
+                    None => cache.search_all(fts_query, PREFILTER_LIMIT),
+                }
+                .map_err(|e| McpError::ValidationFailed {
+                    field: "prefilter".to_string(),
+                    message: format!("Invalid full-text query: {}", e),
+                })?;
+                Some(hits.iter().map(|r| r.id().to_string()).collect())
+            }
+            None => None,
+        };
 
         // Perform semantic search
         let results = cache
-            .search_semantic(
+            .search_semantic_within(
                 &query_embedding,
                 params.entity_type.as_deref(),
+                candidates.as_ref(),
                 limit,
-                threshold,
+                min_score,
             )
             .map_err(|e| McpError::InternalError {
                 message: format!("Semantic search failed: {}", e),
             })?;
 
-        let response = serde_json::json!({
-            "results": results,
-            "total": results.len(),
-            "query": params.query,
-            "threshold": threshold,
-        });
+        let response = SemanticSearchResponse {
+            total: results.len(),
+            results,
+            query: params.query.clone(),
+            min_score,
+            threshold: min_score,
+            prefilter: prefilter.map(str::to_string),
+            candidates: candidates.as_ref().map(|c| c.len()),
+        };
 
         let json =
             serde_json::to_string_pretty(&response).map_err(|e| McpError::InternalError {
@@ -1084,21 +2302,21 @@ impl MedullaServer {
     }
 
     // ========================================================================
-    // search_query
+    // search_by_vector
     // ========================================================================
 
-    /// Combined search with filters, supporting both fulltext and semantic search.
-    #[tool(description = "Advanced search combining fulltext, semantic, and structured filters")]
-    pub async fn search_query(
+    /// Semantic similarity search using a caller-supplied embedding.
+    #[tool(
+        description = "Search entities by similarity to a raw embedding vector, bypassing the text embedder. The vector must match the dimension of stored embeddings."
+    )]
+    pub async fn search_by_vector(
         &self,
-        Parameters(params): Parameters<SearchQueryParams>,
+        Parameters(params): Parameters<SearchByVectorParams>,
     ) -> Result<CallToolResult, McpErrorData> {
-        // At least one search method should be provided
-        if params.query.is_none() && params.semantic_query.is_none() {
+        if params.vector.is_empty() || params.vector.iter().any(|v| !v.is_finite()) {
             return Err(McpError::ValidationFailed {
-                field: "query".to_string(),
-                message: "Either 'query' (fulltext) or 'semantic_query' must be provided"
-                    .to_string(),
+                field: "vector".to_string(),
+                message: "Vector must be non-empty and contain only finite numbers".to_string(),
             }
             .into());
         }
@@ -1108,26 +2326,226 @@ impl MedullaServer {
         }
 
         let cache = self.cache.lock().await;
-        let limit = params.limit.unwrap_or(20).min(100) as usize;
 
-        // Build filter from params
-        let filter = crate::search::SearchFilter {
-            entity_type: params.entity_type.clone(),
-            status: params.status.clone(),
-            tags: params.tags.clone().unwrap_or_default(),
-            created_after: params
-                .created_after
-                .as_ref()
-                .and_then(|s| parse_datetime(s)),
-            created_before: params
-                .created_before
-                .as_ref()
-                .and_then(|s| parse_datetime(s)),
-        };
+        // With nothing stored yet, vectors must still fit the embedder's
+        // output, or they could never match anything stored later
+        let dimension = cache
+            .embedding_dimension()
+            .map_err(McpError::from)?
+            .unwrap_or(crate::embeddings::EMBEDDING_DIMENSION);
+        if params.vector.len() != dimension {
+            return Err(McpError::ValidationFailed {
+                field: "vector".to_string(),
+                message: format!(
+                    "Vector has {} dimensions, embeddings have {}",
+                    params.vector.len(),
+                    dimension
+                ),
+            }
+            .into());
+        }
 
-        let mut results: Vec<serde_json::Value> = Vec::new();
+        let limit = params
+            .limit
+            .unwrap_or(10)
+            .min(self.config.limits.max_limit as u32) as usize;
+        let threshold = params.threshold.unwrap_or(0.3);
 
-        // Perform semantic search if semantic_query provided
+        let results = cache
+            .search_semantic(
+                &params.vector,
+                params.entity_type.as_deref(),
+                limit,
+                threshold,
+            )
+            .map_err(|e| McpError::InternalError {
+                message: format!("Semantic search failed: {}", e),
+            })?;
+
+        let response = serde_json::json!({
+            "results": results,
+            "total": results.len(),
+            "dimension": params.vector.len(),
+            "threshold": threshold,
+        });
+
+        let json =
+            serde_json::to_string_pretty(&response).map_err(|e| McpError::InternalError {
+                message: format!("Failed to serialize search results: {}", e),
+            })?;
+
+        Ok(CallToolResult::success(vec![Content::text(json)]))
+    }
+
+    // ========================================================================
+    // entity_duplicates
+    // ========================================================================
+
+    /// Find pairs of near-duplicate entities by embedding similarity.
+    #[tool(
+        description = "Find pairs of entities of the same type whose embeddings are nearly identical (cosine similarity at or above the threshold, default 0.9). Useful before creating an entity, or to clean up duplicates; the older entity of each pair is listed first."
+    )]
+    pub async fn entity_duplicates(
+        &self,
+        Parameters(params): Parameters<EntityDuplicatesParams>,
+    ) -> Result<CallToolResult, McpErrorData> {
+        if let Some(ref entity_type) = params.entity_type {
+            validate_entity_type(entity_type)?;
+        }
+
+        let threshold = params.threshold.unwrap_or(0.9);
+        if !(0.0..=1.0).contains(&threshold) {
+            return Err(McpError::ValidationFailed {
+                field: "threshold".to_string(),
+                message: "Threshold must be between 0.0 and 1.0".to_string(),
+            }
+            .into());
+        }
+        let limit = params
+            .limit
+            .unwrap_or(20)
+            .min(self.config.limits.max_limit as u32) as usize;
+
+        let cache = self.cache.lock().await;
+        let pairs = cache
+            .find_duplicate_pairs(params.entity_type.as_deref(), threshold, limit)
+            .map_err(|e| McpError::InternalError {
+                message: format!("Duplicate detection failed: {}", e),
+            })?;
+
+        let response = serde_json::json!({
+            "pairs": pairs,
+            "total": pairs.len(),
+            "threshold": threshold,
+        });
+
+        let json =
+            serde_json::to_string_pretty(&response).map_err(|e| McpError::InternalError {
+                message: format!("Failed to serialize duplicates: {}", e),
+            })?;
+
+        Ok(CallToolResult::success(vec![Content::text(json)]))
+    }
+
+    // ========================================================================
+    // entity_related
+    // ========================================================================
+
+    /// Suggest entities related to one by embedding similarity.
+    #[tool(
+        description = "Suggest entities related to an entity: its nearest neighbours by embedding similarity (at or above min_score, default 0.5) that it has no active relation with yet, in either direction. Surfaces connections the relation graph misses; add a relation to accept a suggestion. Empty if the entity has no embedding."
+    )]
+    pub async fn entity_related(
+        &self,
+        Parameters(params): Parameters<EntityRelatedParams>,
+    ) -> Result<CallToolResult, McpErrorData> {
+        let min_score = params.min_score.unwrap_or(0.5);
+        if !(0.0..=1.0).contains(&min_score) {
+            return Err(McpError::ValidationFailed {
+                field: "min_score".to_string(),
+                message: "min_score must be between 0.0 and 1.0".to_string(),
+            }
+            .into());
+        }
+        let limit = params
+            .limit
+            .unwrap_or(5)
+            .min(self.config.limits.max_limit as u32) as usize;
+
+        let (uuid, entity_type, related_ids) = {
+            let store = self.store.lock().await;
+            let (uuid, entity_type) = self.resolve_entity_id_with_type(&store, &params.id)?;
+            let id = uuid.to_string();
+            let relations = store
+                .get_relations_from(&id)
+                .and_then(|mut from| {
+                    from.extend(store.get_relations_to(&id)?);
+                    Ok(from)
+                })
+                .map_err(|e| McpError::InternalError {
+                    message: format!("Failed to get relations: {}", e),
+                })?;
+            let related_ids: HashSet<String> = relations
+                .iter()
+                .filter(|r| r.is_active())
+                .flat_map(|r| [r.source_id.to_string(), r.target_id.to_string()])
+                .collect();
+            (uuid, entity_type, related_ids)
+        };
+
+        let cache = self.cache.lock().await;
+        let suggestions = cache
+            .suggest_related(&uuid.to_string(), &related_ids, limit, min_score)
+            .map_err(|e| McpError::InternalError {
+                message: format!("Related entity search failed: {}", e),
+            })?;
+
+        let response = serde_json::json!({
+            "id": uuid.to_string(),
+            "type": entity_type,
+            "related": suggestions,
+            "total": suggestions.len(),
+            "min_score": min_score,
+        });
+
+        let json =
+            serde_json::to_string_pretty(&response).map_err(|e| McpError::InternalError {
+                message: format!("Failed to serialize related entities: {}", e),
+            })?;
+
+        Ok(CallToolResult::success(vec![Content::text(json)]))
+    }
+
+    // ========================================================================
+    // search_query
+    // ========================================================================
+
+    /// Combined search with filters, supporting both fulltext and semantic search.
+    #[tool(description = "Advanced search combining fulltext, semantic, and structured filters")]
+    pub async fn search_query(
+        &self,
+        Parameters(params): Parameters<SearchQueryParams>,
+    ) -> Result<CallToolResult, McpErrorData> {
+        // At least one search method should be provided
+        if params.query.is_none() && params.semantic_query.is_none() {
+            return Err(McpError::ValidationFailed {
+                field: "query".to_string(),
+                message: "Either 'query' (fulltext) or 'semantic_query' must be provided"
+                    .to_string(),
+            }
+            .into());
+        }
+
+        if let Some(ref entity_type) = params.entity_type {
+            validate_entity_type(entity_type)?;
+        }
+
+        let cache = self.cache.lock().await;
+        let limit = params
+            .limit
+            .unwrap_or(20)
+            .min(self.config.limits.max_limit as u32) as usize;
+
+        // Build filter from params
+        let filter = crate::search::SearchFilter {
+            entity_type: params.entity_type.clone(),
+            status: params.status.clone(),
+            tags: params.tags.clone().unwrap_or_default(),
+            created_after: params
+                .created_after
+                .as_ref()
+                .and_then(|s| parse_datetime(s)),
+            created_before: params
+                .created_before
+                .as_ref()
+                .and_then(|s| parse_datetime(s)),
+            created_by: params.created_by.clone(),
+            assignee: params.assignee.clone(),
+        };
+
+        let mut results: Vec<serde_json::Value> = Vec::new();
+
+        // Perform semantic search if semantic_query provided
         if let Some(ref semantic_query) = params.semantic_query {
             let embedder = Self::get_embedder().ok_or_else(|| McpError::InternalError {
                 message: "Embedding model not available".to_string(),
@@ -1211,6 +2629,8 @@ impl MedullaServer {
             && filter.tags.is_empty()
             && filter.created_after.is_none()
             && filter.created_before.is_none()
+            && filter.created_by.is_none()
+            && filter.assignee.is_none()
         {
             return true;
         }
@@ -1221,6 +2641,10 @@ impl MedullaServer {
             _ => return false, // Can't verify filters without metadata
         };
 
+        if !filter.matches_people(metadata.created_by.as_deref(), metadata.assignee.as_deref()) {
+            return false;
+        }
+
         // Check status filter
         if let Some(ref required_status) = filter.status {
             match &metadata.status {
@@ -1231,10 +2655,9 @@ impl MedullaServer {
 
         // Check tag filters (entity must have ALL specified tags)
         for required_tag in &filter.tags {
-            if !metadata
-                .tags
-                .iter()
-                .any(|t| t.eq_ignore_ascii_case(required_tag))
+            if !cache
+                .entity_has_tag(entity_id, required_tag)
+                .unwrap_or(false)
             {
                 return false;
             }
@@ -1272,40 +2695,36 @@ impl MedullaServer {
 
         match entity_type {
             "decision" => {
-                if let Ok(search_results) = cache.search_decisions(query, limit) {
+                if let Ok(search_results) = cache.search_decisions_filtered(query, filter, limit) {
                     for r in search_results {
-                        if filter.status.is_none() || filter.status.as_deref() == Some(&r.status) {
-                            results.push(serde_json::json!({
-                                "type": "decision",
-                                "id": r.id,
-                                "sequence_number": r.sequence_number,
-                                "title": r.title,
-                                "status": r.status,
-                                "match_type": "fulltext",
-                            }));
-                        }
+                        results.push(serde_json::json!({
+                            "type": "decision",
+                            "id": r.id,
+                            "sequence_number": r.sequence_number,
+                            "title": r.title,
+                            "status": r.status,
+                            "match_type": "fulltext",
+                        }));
                     }
                 }
             }
             "task" => {
-                if let Ok(search_results) = cache.search_tasks(query, limit) {
+                if let Ok(search_results) = cache.search_tasks_filtered(query, filter, limit) {
                     for r in search_results {
-                        if filter.status.is_none() || filter.status.as_deref() == Some(&r.status) {
-                            results.push(serde_json::json!({
-                                "type": "task",
-                                "id": r.id,
-                                "sequence_number": r.sequence_number,
-                                "title": r.title,
-                                "status": r.status,
-                                "priority": r.priority,
-                                "match_type": "fulltext",
-                            }));
-                        }
+                        results.push(serde_json::json!({
+                            "type": "task",
+                            "id": r.id,
+                            "sequence_number": r.sequence_number,
+                            "title": r.title,
+                            "status": r.status,
+                            "priority": r.priority,
+                            "match_type": "fulltext",
+                        }));
                     }
                 }
             }
             "note" => {
-                if let Ok(search_results) = cache.search_notes(query, limit) {
+                if let Ok(search_results) = cache.search_notes_filtered(query, filter, limit) {
                     for r in search_results {
                         results.push(serde_json::json!({
                             "type": "note",
@@ -1319,7 +2738,7 @@ impl MedullaServer {
                 }
             }
             "prompt" => {
-                if let Ok(search_results) = cache.search_prompts(query, limit) {
+                if let Ok(search_results) = cache.search_prompts_filtered(query, filter, limit) {
                     for r in search_results {
                         results.push(serde_json::json!({
                             "type": "prompt",
@@ -1332,23 +2751,21 @@ impl MedullaServer {
                 }
             }
             "component" => {
-                if let Ok(search_results) = cache.search_components(query, limit) {
+                if let Ok(search_results) = cache.search_components_filtered(query, filter, limit) {
                     for r in search_results {
-                        if filter.status.is_none() || filter.status.as_deref() == Some(&r.status) {
-                            results.push(serde_json::json!({
-                                "type": "component",
-                                "id": r.id,
-                                "sequence_number": r.sequence_number,
-                                "title": r.title,
-                                "status": r.status,
-                                "match_type": "fulltext",
-                            }));
-                        }
+                        results.push(serde_json::json!({
+                            "type": "component",
+                            "id": r.id,
+                            "sequence_number": r.sequence_number,
+                            "title": r.title,
+                            "status": r.status,
+                            "match_type": "fulltext",
+                        }));
                     }
                 }
             }
             "link" => {
-                if let Ok(search_results) = cache.search_links(query, limit) {
+                if let Ok(search_results) = cache.search_links_filtered(query, filter, limit) {
                     for r in search_results {
                         results.push(serde_json::json!({
                             "type": "link",
@@ -1361,6 +2778,35 @@ impl MedullaServer {
                     }
                 }
             }
+            "question" => {
+                if let Ok(search_results) = cache.search_questions_filtered(query, filter, limit) {
+                    for r in search_results {
+                        results.push(serde_json::json!({
+                            "type": "question",
+                            "id": r.id,
+                            "sequence_number": r.sequence_number,
+                            "title": r.title,
+                            "status": r.status,
+                            "match_type": "fulltext",
+                        }));
+                    }
+                }
+            }
+            "milestone" => {
+                if let Ok(search_results) = cache.search_milestones_filtered(query, filter, limit) {
+                    for r in search_results {
+                        results.push(serde_json::json!({
+                            "type": "milestone",
+                            "id": r.id,
+                            "sequence_number": r.sequence_number,
+                            "title": r.title,
+                            "status": r.status,
+                            "target_date": r.target_date,
+                            "match_type": "fulltext",
+                        }));
+                    }
+                }
+            }
             _ => {}
         }
 
@@ -1368,83 +2814,301 @@ impl MedullaServer {
     }
 
     // ========================================================================
-    // graph_relations
+    // context_pack
     // ========================================================================
 
-    /// Get relations for an entity.
-    #[tool(description = "Get relations for an entity (outgoing, incoming, or both)")]
-    pub async fn graph_relations(
+    /// Pack the entities most relevant to a query or entity into one
+    /// markdown document sized to a token budget.
+    ///
+    /// Seeds are semantic matches (when embeddings are available), full-text
+    /// matches and the requested entity; their scores spread to related
+    /// entities, halving per hop, and the best-ranked entities are rendered
+    /// until the budget is spent.
+    #[tool(
+        description = "Build a markdown context document for a task: give a query and/or an entity id plus max_tokens (default 4000). Picks the most relevant entities by semantic similarity, full-text match and graph proximity (depth relation hops, default 1) and packs them, best first, within the budget."
+    )]
+    pub async fn context_pack(
         &self,
-        Parameters(params): Parameters<GraphRelationsParams>,
+        Parameters(params): Parameters<ContextPackParams>,
     ) -> Result<CallToolResult, McpErrorData> {
-        let direction = params.direction.as_deref().unwrap_or("both");
-        if !["from", "to", "both"].contains(&direction) {
+        let query = params
+            .query
+            .as_deref()
+            .map(str::trim)
+            .filter(|q| !q.is_empty());
+        if query.is_none() && params.id.is_none() {
             return Err(McpError::ValidationFailed {
-                field: "direction".to_string(),
-                message: "Direction must be 'from', 'to', or 'both'".to_string(),
+                field: "query".to_string(),
+                message: "Either 'query' or 'id' must be provided".to_string(),
             }
             .into());
         }
+        let max_tokens = params.max_tokens.unwrap_or(4000).clamp(100, 32_000) as usize;
+        let depth = params.depth.unwrap_or(1).min(3) as usize;
 
         let store = self.store.lock().await;
+        let cache = self.cache.lock().await;
 
-        // Resolve the ID to a UUID
-        let uuid = self.resolve_entity_id(&store, &params.id)?;
-        let uuid_str = uuid.to_string();
-
-        let mut outgoing: Vec<RelationResponse> = Vec::new();
-        let mut incoming: Vec<RelationResponse> = Vec::new();
-
-        if direction == "from" || direction == "both" {
-            let relations = store
-                .get_relations_from(&uuid_str)
-                .map_err(McpError::from)?;
-            outgoing = relations.iter().map(relation_to_response).collect();
+        let mut seeds: Vec<(String, f32, &'static str)> = Vec::new();
+        let mut heading = String::new();
+        if let Some(ref id) = params.id {
+            let (uuid, entity_type) = self.resolve_entity_id_with_type(&store, id)?;
+            if let Some(entity) = self.find_entity_by_id(&store, &entity_type, &uuid.to_string())? {
+                heading = entity.title.clone();
+            }
+            seeds.push((uuid.to_string(), 1.0, "requested"));
+        }
+        if let Some(query) = query {
+            heading = query.to_string();
+            // Full-text hits rank below close semantic matches, in FTS order
+            if let Ok(hits) = cache.search_all(query, CONTEXT_SEED_LIMIT as i64) {
+                for (rank, hit) in hits.iter().enumerate() {
+                    seeds.push((hit.id().to_string(), 0.5 - 0.01 * rank as f32, "fulltext"));
+                }
+            }
+            if let Some(embedding) = Self::get_embedder().and_then(|e| e.embed(query).ok()) {
+                let hits = cache
+                    .search_semantic(&embedding, None, CONTEXT_SEED_LIMIT, 0.3)
+                    .map_err(McpError::from)?;
+                for hit in hits {
+                    seeds.push((hit.entity_id, hit.score, "semantic"));
+                }
+            }
         }
 
-        if direction == "to" || direction == "both" {
-            let relations = store.get_relations_to(&uuid_str).map_err(McpError::from)?;
-            incoming = relations.iter().map(relation_to_response).collect();
+        let archived = cache.get_archived_ids().map_err(McpError::from)?;
+        seeds.retain(|(id, _, reason)| *reason == "requested" || !archived.contains(id.as_str()));
+
+        // Canonical rows only: both directions are read explicitly
+        let relations_of = |id: &str| -> Vec<(String, String, bool)> {
+            let from = cache.get_relations_from(id).unwrap_or_default();
+            let to = cache.get_relations_to(id).unwrap_or_default();
+            from.into_iter()
+                .filter(|r| !r.is_inverse)
+                .map(|r| (r.relation_type, r.target_id, true))
+                .chain(
+                    to.into_iter()
+                        .filter(|r| !r.is_inverse)
+                        .map(|r| (r.relation_type, r.source_id, false)),
+                )
+                .filter(|(_, other, _)| !archived.contains(other))
+                .collect()
+        };
+        let ranked = context::rank_with_graph(&seeds, depth, |id| {
+            relations_of(id)
+                .into_iter()
+                .map(|(_, other, _)| other)
+                .collect()
+        });
+
+        let mut candidates = Vec::new();
+        for (id, score, reason) in ranked.into_iter().take(CONTEXT_CANDIDATE_LIMIT) {
+            let Some((uuid, entity_type)) = store.lookup_id(&id, None) else {
+                continue;
+            };
+            if let Some(entity) = self.find_entity_by_id(&store, entity_type, &uuid.to_string())? {
+                candidates.push(context::ContextCandidate {
+                    entity,
+                    score,
+                    reason,
+                    relations: relations_of(&id),
+                });
+            }
         }
 
+        let packed = context::pack(&heading, &candidates, max_tokens);
         let response = serde_json::json!({
-            "entity_id": uuid_str,
-            "outgoing": outgoing,
-            "incoming": incoming,
+            "query": query,
+            "id": params.id,
+            "max_tokens": max_tokens,
+            "tokens": packed.tokens,
+            "entities": packed.entities,
+            "omitted": packed.omitted,
+            "markdown": packed.markdown,
         });
 
         let json =
             serde_json::to_string_pretty(&response).map_err(|e| McpError::InternalError {
-                message: format!("Failed to serialize relations: {}", e),
+                message: format!("Failed to serialize context pack: {}", e),
             })?;
 
         Ok(CallToolResult::success(vec![Content::text(json)]))
     }
 
     // ========================================================================
-    // graph_path
+    // prompt_render
     // ========================================================================
 
-    /// Find shortest path between two entities.
-    #[tool(description = "Find the shortest path between two entities using BFS traversal")]
-    pub async fn graph_path(
+    /// Fill a prompt's template with variables and optionally check an
+    /// output against its output_schema.
+    #[tool(
+        description = "Render a prompt entity: substitute variables into its {{name}} placeholders and return the text. Fails listing any required variables (declared or used in the template) that are missing. Pass 'output' to validate a model's response against the prompt's output_schema; the result reports output_valid and output_errors."
+    )]
+    pub async fn prompt_render(
         &self,
-        Parameters(params): Parameters<GraphPathParams>,
+        Parameters(params): Parameters<PromptRenderParams>,
     ) -> Result<CallToolResult, McpErrorData> {
-        let max_depth = params
-            .max_depth
-            .unwrap_or(validation::DEFAULT_MAX_DEPTH as u32) as usize;
+        let prompt = {
+            let store = self.store.lock().await;
+            lookup(&store, "prompt", &params.id, LoroStore::get_prompt)?
+        }
+        .ok_or_else(|| McpError::EntityNotFound {
+            id: params.id.clone(),
+        })?;
 
-        let store = self.store.lock().await;
+        let provided = params.variables.unwrap_or_default();
+        let values: BTreeMap<String, String> = provided
+            .iter()
+            .map(|(name, value)| {
+                let text = match value {
+                    serde_json::Value::String(s) => s.clone(),
+                    other => other.to_string(),
+                };
+                (name.clone(), text)
+            })
+            .collect();
+        let rendered = prompt
+            .render(&values)
+            .map_err(|missing| McpError::ValidationFailed {
+                field: "variables".to_string(),
+                message: format!("Missing required variables: {}", missing.join(", ")),
+            })?;
 
-        // Resolve both IDs
-        let from_uuid = self.resolve_entity_id(&store, &params.from_id)?;
-        let to_uuid = self.resolve_entity_id(&store, &params.to_id)?;
+        let required = prompt.required_variables();
+        let mut unused: Vec<&String> = values.keys().filter(|n| !required.contains(n)).collect();
+        unused.sort();
+
+        let mut response = serde_json::json!({
+            "id": prompt.base.id.to_string(),
+            "sequence_number": prompt.base.sequence_number,
+            "title": prompt.base.title,
+            "rendered": rendered,
+            "variables": required,
+            "unused_variables": unused,
+        });
 
-        // Same entity
-        if from_uuid == to_uuid {
-            let response = serde_json::json!({
-                "path": [from_uuid.to_string()],
+        if let Some(output) = params.output {
+            // Model responses usually arrive as text holding JSON
+            let output = match output {
+                serde_json::Value::String(text) => {
+                    serde_json::from_str(&text).unwrap_or(serde_json::Value::String(text))
+                }
+                other => other,
+            };
+            let errors = match prompt.validate_output(&output) {
+                Some(Ok(errors)) => errors,
+                Some(Err(message)) => {
+                    return Err(McpError::ValidationFailed {
+                        field: "output_schema".to_string(),
+                        message,
+                    }
+                    .into())
+                }
+                None => {
+                    return Err(McpError::ValidationFailed {
+                        field: "output".to_string(),
+                        message: "Prompt has no output_schema to validate against".to_string(),
+                    }
+                    .into())
+                }
+            };
+            response["output_valid"] = serde_json::json!(errors.is_empty());
+            response["output_errors"] = serde_json::json!(errors);
+        }
+
+        let json =
+            serde_json::to_string_pretty(&response).map_err(|e| McpError::InternalError {
+                message: format!("Failed to serialize rendered prompt: {}", e),
+            })?;
+
+        Ok(CallToolResult::success(vec![Content::text(json)]))
+    }
+
+    // ========================================================================
+    // graph_relations
+    // ========================================================================
+
+    /// Get relations for an entity.
+    #[tool(description = "Get relations for an entity (outgoing, incoming, or both)")]
+    pub async fn graph_relations(
+        &self,
+        Parameters(params): Parameters<GraphRelationsParams>,
+    ) -> Result<CallToolResult, McpErrorData> {
+        let direction = params.direction.as_deref().unwrap_or("both");
+        if !["from", "to", "both"].contains(&direction) {
+            return Err(McpError::ValidationFailed {
+                field: "direction".to_string(),
+                message: "Direction must be 'from', 'to', or 'both'".to_string(),
+            }
+            .into());
+        }
+
+        let store = self.store.lock().await;
+
+        // Resolve the ID to a UUID
+        let uuid = self.resolve_entity_id(&store, &params.id)?;
+        let uuid_str = uuid.to_string();
+
+        let mut outgoing: Vec<RelationResponse> = Vec::new();
+        let mut incoming: Vec<RelationResponse> = Vec::new();
+
+        if direction == "from" || direction == "both" {
+            let relations = store
+                .get_relations_from(&uuid_str)
+                .map_err(McpError::from)?;
+            outgoing = relations.iter().map(relation_to_response).collect();
+        }
+
+        if direction == "to" || direction == "both" {
+            let relations = store.get_relations_to(&uuid_str).map_err(McpError::from)?;
+            let config = self.relation_config(&store)?;
+            incoming = relations
+                .iter()
+                .map(|r| RelationResponse {
+                    inverse_label: config.inverse_label(&r.relation_type).map(str::to_string),
+                    ..relation_to_response(r)
+                })
+                .collect();
+        }
+
+        let response = serde_json::json!({
+            "entity_id": uuid_str,
+            "outgoing": outgoing,
+            "incoming": incoming,
+        });
+
+        let json =
+            serde_json::to_string_pretty(&response).map_err(|e| McpError::InternalError {
+                message: format!("Failed to serialize relations: {}", e),
+            })?;
+
+        Ok(CallToolResult::success(vec![Content::text(json)]))
+    }
+
+    // ========================================================================
+    // graph_path
+    // ========================================================================
+
+    /// Find shortest path between two entities.
+    #[tool(description = "Find the shortest path between two entities using BFS traversal")]
+    pub async fn graph_path(
+        &self,
+        Parameters(params): Parameters<GraphPathParams>,
+    ) -> Result<CallToolResult, McpErrorData> {
+        let max_depth = params
+            .max_depth
+            .unwrap_or(validation::DEFAULT_MAX_DEPTH as u32) as usize;
+
+        let store = self.store.lock().await;
+
+        // Resolve both IDs
+        let from_uuid = self.resolve_entity_id(&store, &params.from_id)?;
+        let to_uuid = self.resolve_entity_id(&store, &params.to_id)?;
+
+        // Same entity
+        if from_uuid == to_uuid {
+            let response = serde_json::json!({
+                "path": [from_uuid.to_string()],
                 "length": 0,
             });
             let json =
@@ -1523,6 +3187,80 @@ impl MedullaServer {
         Ok(CallToolResult::success(vec![Content::text(json)]))
     }
 
+    // ========================================================================
+    // graph_export
+    // ========================================================================
+
+    /// Render the relation graph as GraphViz DOT or Mermaid text.
+    #[tool(
+        description = "Render the relation graph as GraphViz DOT or Mermaid text for embedding in docs. Exports the whole graph, or the subgraph around a root entity up to a depth."
+    )]
+    pub async fn graph_export(
+        &self,
+        Parameters(params): Parameters<GraphExportParams>,
+    ) -> Result<CallToolResult, McpErrorData> {
+        let format: crate::graph::GraphFormat = params
+            .format
+            .as_deref()
+            .unwrap_or("mermaid")
+            .parse()
+            .map_err(|e: String| McpError::ValidationFailed {
+                field: "format".to_string(),
+                message: e,
+            })?;
+
+        let store = self.store.lock().await;
+        let root = match params.root {
+            Some(ref id) => Some(self.resolve_entity_id(&store, id)?),
+            None => None,
+        };
+        let graph = crate::graph::Graph::collect(&store, root, params.depth.map(|d| d as usize))
+            .map_err(McpError::from)?;
+
+        let response = serde_json::json!({
+            "format": format.to_string(),
+            "nodes": graph.nodes.len(),
+            "edges": graph.edges.len(),
+            "content": graph.render(format),
+        });
+
+        let json =
+            serde_json::to_string_pretty(&response).map_err(|e| McpError::InternalError {
+                message: format!("Failed to serialize graph: {}", e),
+            })?;
+
+        Ok(CallToolResult::success(vec![Content::text(json)]))
+    }
+
+    // ========================================================================
+    // graph_analytics
+    // ========================================================================
+
+    /// Centrality, connected components and communities of the relation graph.
+    #[tool(
+        description = "Analyse the relation graph: the top hub entities by degree and betweenness centrality (the decisions and components everything depends on), connected components, and communities of densely linked entities. Relations count in both directions."
+    )]
+    pub async fn graph_analytics(
+        &self,
+        Parameters(params): Parameters<GraphAnalyticsParams>,
+    ) -> Result<CallToolResult, McpErrorData> {
+        let limit = params
+            .limit
+            .unwrap_or(10)
+            .min(self.config.limits.max_limit as u32) as usize;
+
+        let store = self.store.lock().await;
+        let analytics = crate::graph::GraphAnalytics::build(&store).map_err(McpError::from)?;
+
+        let json = serde_json::to_string_pretty(&analytics.to_json(limit)).map_err(|e| {
+            McpError::InternalError {
+                message: format!("Failed to serialize graph analytics: {}", e),
+            }
+        })?;
+
+        Ok(CallToolResult::success(vec![Content::text(json)]))
+    }
+
     // ========================================================================
     // graph_orphans
     // ========================================================================
@@ -1541,7 +3279,8 @@ impl MedullaServer {
         let limit = params
             .limit
             .unwrap_or(validation::DEFAULT_LIMIT as u32)
-            .min(validation::MAX_LIMIT as u32) as usize;
+            .min(self.config.limits.max_limit as u32) as usize;
+        let offset = params.offset.unwrap_or(0) as usize;
 
         // Get all entity IDs that have relations
         let relations = store.list_relations().map_err(McpError::from)?;
@@ -1561,19 +3300,12 @@ impl MedullaServer {
         };
 
         for entity_type in types_to_check {
-            if orphans.len() >= limit {
-                break;
-            }
-
             match entity_type {
                 "decision" => {
                     let decisions = store.list_decisions().map_err(McpError::from)?;
                     for d in decisions {
                         if !connected_ids.contains(&d.base.id.to_string()) {
                             orphans.push(decision_to_response(&d));
-                            if orphans.len() >= limit {
-                                break;
-                            }
                         }
                     }
                 }
@@ -1582,9 +3314,6 @@ impl MedullaServer {
                     for t in tasks {
                         if !connected_ids.contains(&t.base.id.to_string()) {
                             orphans.push(task_to_response(&t));
-                            if orphans.len() >= limit {
-                                break;
-                            }
                         }
                     }
                 }
@@ -1593,9 +3322,6 @@ impl MedullaServer {
                     for n in notes {
                         if !connected_ids.contains(&n.base.id.to_string()) {
                             orphans.push(note_to_response(&n));
-                            if orphans.len() >= limit {
-                                break;
-                            }
                         }
                     }
                 }
@@ -1604,9 +3330,6 @@ impl MedullaServer {
                     for p in prompts {
                         if !connected_ids.contains(&p.base.id.to_string()) {
                             orphans.push(prompt_to_response(&p));
-                            if orphans.len() >= limit {
-                                break;
-                            }
                         }
                     }
                 }
@@ -1615,9 +3338,6 @@ impl MedullaServer {
                     for c in components {
                         if !connected_ids.contains(&c.base.id.to_string()) {
                             orphans.push(component_to_response(&c));
-                            if orphans.len() >= limit {
-                                break;
-                            }
                         }
                     }
                 }
@@ -1626,9 +3346,22 @@ impl MedullaServer {
                     for l in links {
                         if !connected_ids.contains(&l.base.id.to_string()) {
                             orphans.push(link_to_response(&l));
-                            if orphans.len() >= limit {
-                                break;
-                            }
+                        }
+                    }
+                }
+                "question" => {
+                    let questions = store.list_questions().map_err(McpError::from)?;
+                    for q in questions {
+                        if !connected_ids.contains(&q.base.id.to_string()) {
+                            orphans.push(question_to_response(&q));
+                        }
+                    }
+                }
+                "milestone" => {
+                    let milestones = store.list_milestones().map_err(McpError::from)?;
+                    for m in milestones {
+                        if !connected_ids.contains(&m.base.id.to_string()) {
+                            orphans.push(milestone_to_response(&m));
                         }
                     }
                 }
@@ -1636,9 +3369,19 @@ impl MedullaServer {
             }
         }
 
+        let total = orphans.len();
+
+        // Apply pagination
+        let paginated: Vec<EntityResponse> = orphans.into_iter().skip(offset).take(limit).collect();
+        let next_offset = next_page_offset(offset, paginated.len(), total);
+
         let response = serde_json::json!({
-            "orphans": orphans,
-            "total": orphans.len(),
+            "orphans": paginated,
+            "total": total,
+            "limit": limit,
+            "offset": offset,
+            "has_more": next_offset.is_some(),
+            "next_offset": next_offset,
         });
 
         let json =
@@ -1649,21 +3392,94 @@ impl MedullaServer {
         Ok(CallToolResult::success(vec![Content::text(json)]))
     }
 
+    // ========================================================================
+    // graph_lint
+    // ========================================================================
+
+    /// Report entities missing the relations the project config expects.
+    #[tool(
+        description = "Check graph completeness against the configured expected_relations rules (e.g. every component implements a decision). Reports warnings; nothing is blocked."
+    )]
+    pub async fn graph_lint(&self) -> Result<CallToolResult, McpErrorData> {
+        let store = self.store.lock().await;
+        let bases = self.list_entity_bases(&store)?;
+        let relations = store.list_relations().map_err(McpError::from)?;
+
+        let mut warnings = Vec::new();
+        for rule in &self.config.expected_relations {
+            for (entity_type, base) in &bases {
+                if *entity_type != rule.entity_type {
+                    continue;
+                }
+                if relations.iter().any(|r| rule.is_satisfied_by(&base.id, r)) {
+                    continue;
+                }
+                warnings.push(serde_json::json!({
+                    "id": base.id.to_string(),
+                    "sequence_number": base.sequence_number,
+                    "type": entity_type,
+                    "title": base.title,
+                    "relation": rule.relation,
+                    "direction": rule.direction,
+                    "message": rule.warning(),
+                }));
+            }
+        }
+
+        let response = serde_json::json!({
+            "warnings": warnings,
+            "total": warnings.len(),
+            "rules_checked": self.config.expected_relations.len(),
+        });
+
+        let json =
+            serde_json::to_string_pretty(&response).map_err(|e| McpError::InternalError {
+                message: format!("Failed to serialize response: {}", e),
+            })?;
+
+        Ok(CallToolResult::success(vec![Content::text(json)]))
+    }
+
+    // ========================================================================
+    // health_check
+    // ========================================================================
+
+    /// Report store size, cache staleness, orphaned embeddings and FTS
+    /// consistency, each with a severity.
+    #[tool(
+        description = "Check the health of the knowledge base: store size, cache staleness, orphaned embeddings and full-text index consistency. Returns an overall status (ok, info, warning, error) and a list of issues with severities and suggested fix commands."
+    )]
+    pub async fn health_check(&self) -> Result<CallToolResult, McpErrorData> {
+        let store = self.store.lock().await;
+        let cache = self.cache.lock().await;
+        let report = crate::warnings::health_check(&store, &cache).map_err(McpError::from)?;
+
+        let json = serde_json::to_string_pretty(&report).map_err(|e| McpError::InternalError {
+            message: format!("Failed to serialize response: {}", e),
+        })?;
+
+        Ok(CallToolResult::success(vec![Content::text(json)]))
+    }
+
     // ========================================================================
     // task_ready (Beads Parity)
     // ========================================================================
 
-    /// List tasks with no unresolved blockers, sorted by priority and due date.
+    /// List tasks with no unresolved blockers, highest score first.
     #[tool(
-        description = "List tasks that are ready to work on (no unresolved blockers). Returns tasks sorted by priority (urgent > high > normal > low), then by due date."
+        description = "List tasks that are ready to work on (no unresolved blockers). Returns tasks sorted by score, which weighs priority (urgent > high > normal > low), due-date proximity, task age and how many tasks each one unblocks; each task includes its score breakdown. With respect_wip_limits, tasks whose assignee is already at their WIP limit are left out."
     )]
     pub async fn task_ready(
         &self,
         Parameters(params): Parameters<TaskReadyParams>,
     ) -> Result<CallToolResult, McpErrorData> {
+        let wip_limits = params
+            .respect_wip_limits
+            .unwrap_or(false)
+            .then_some(&self.config.wip_limits);
         let cache = self.cache.lock().await;
         let ready_tasks = cache
-            .get_ready_tasks(params.limit)
+            .get_ready_tasks_with(params.limit, false, wip_limits)
             .map_err(McpError::from)?;
 
         let tasks: Vec<serde_json::Value> = ready_tasks
@@ -1677,6 +3493,8 @@ impl MedullaServer {
                     "priority": t.priority,
                     "due_date": t.due_date,
                     "assignee": t.assignee,
+                    "unblocks": t.unblocks,
+                    "score": t.score,
                 })
             })
             .collect();
@@ -1792,98 +3610,412 @@ impl MedullaServer {
     }
 
     // ========================================================================
-    // task_next (Beads Parity)
+    // task_overdue / task_due
     // ========================================================================
 
-    /// Get the highest-priority ready task.
-    #[tool(description = "Get the single highest-priority task that is ready to work on")]
-    pub async fn task_next(&self) -> Result<CallToolResult, McpErrorData> {
+    /// List open tasks whose due date has passed.
+    #[tool(
+        description = "List tasks that are not done and whose due date is before today, most overdue first. Each task includes days_until_due (negative)."
+    )]
+    pub async fn task_overdue(
+        &self,
+        Parameters(params): Parameters<TaskOverdueParams>,
+    ) -> Result<CallToolResult, McpErrorData> {
+        let today = chrono::Utc::now().date_naive();
         let cache = self.cache.lock().await;
-        let next_task = cache.get_next_task().map_err(McpError::from)?;
-
-        match next_task {
-            Some(t) => {
-                let response = serde_json::json!({
-                    "id": t.id,
-                    "sequence_number": t.sequence_number,
-                    "title": t.title,
-                    "status": t.status,
-                    "priority": t.priority,
-                    "due_date": t.due_date,
-                    "assignee": t.assignee,
-                });
-
-                let json = serde_json::to_string_pretty(&response).map_err(|e| {
-                    McpError::InternalError {
-                        message: format!("Failed to serialize next task: {}", e),
-                    }
-                })?;
+        let tasks = cache
+            .get_overdue_tasks(today, params.limit)
+            .map_err(McpError::from)?;
 
-                Ok(CallToolResult::success(vec![Content::text(json)]))
-            }
-            None => {
-                let response = serde_json::json!({
-                    "message": "No ready tasks available",
-                    "task": null,
-                });
+        let response = serde_json::json!({
+            "today": today.to_string(),
+            "tasks": tasks,
+            "total": tasks.len(),
+        });
 
-                let json = serde_json::to_string_pretty(&response).map_err(|e| {
-                    McpError::InternalError {
-                        message: format!("Failed to serialize response: {}", e),
-                    }
-                })?;
+        let json =
+            serde_json::to_string_pretty(&response).map_err(|e| McpError::InternalError {
+                message: format!("Failed to serialize overdue tasks: {}", e),
+            })?;
 
-                Ok(CallToolResult::success(vec![Content::text(json)]))
-            }
-        }
+        Ok(CallToolResult::success(vec![Content::text(json)]))
     }
 
-    // ========================================================================
-    // task_complete (Convenience)
-    // ========================================================================
-
-    /// Mark a task as done.
+    /// List open tasks due within a window from today.
     #[tool(
-        description = "Mark a task as done (convenience wrapper for entity_update with status=done)"
+        description = "List tasks that are not done and are due between today and a window ahead (within, e.g. \"7d\" or \"2w\"; default 7d), soonest first. Use task_overdue for tasks already past due."
     )]
-    pub async fn task_complete(
+    pub async fn task_due(
         &self,
-        Parameters(params): Parameters<TaskCompleteParams>,
+        Parameters(params): Parameters<TaskDueParams>,
     ) -> Result<CallToolResult, McpErrorData> {
-        let store = self.store.lock().await;
-        let cache = self.cache.lock().await;
+        let within = params.within.as_deref().unwrap_or("7d");
+        let days = crate::entity::parse_due_window(within).map_err(|message| {
+            McpError::ValidationFailed {
+                field: "within".to_string(),
+                message,
+            }
+        })?;
 
-        let is_sequence = params.id.chars().all(|c| c.is_ascii_digit());
+        let today = chrono::Utc::now().date_naive();
+        let cache = self.cache.lock().await;
+        let tasks = cache
+            .get_tasks_due_within(today, days, params.limit)
+            .map_err(McpError::from)?;
 
-        // Find the task
-        let tasks = store.list_tasks().map_err(McpError::from)?;
-        for t in tasks {
-            if self.matches_id(&t.base, &params.id, is_sequence) {
-                let mut update = TaskUpdate::default();
-                update.status = Some(crate::entity::TaskStatus::Done);
+        let response = serde_json::json!({
+            "today": today.to_string(),
+            "within_days": days,
+            "tasks": tasks,
+            "total": tasks.len(),
+        });
 
-                store
-                    .update_task(&t.base.id, update)
-                    .map_err(McpError::from)?;
-                store.save().map_err(McpError::from)?;
+        let json =
+            serde_json::to_string_pretty(&response).map_err(|e| McpError::InternalError {
+                message: format!("Failed to serialize due tasks: {}", e),
+            })?;
+
+        Ok(CallToolResult::success(vec![Content::text(json)]))
+    }
+
+    // ========================================================================
+    // task_by_assignee
+    // ========================================================================
+
+    /// Group open tasks by assignee.
+    #[tool(
+        description = "Open tasks grouped by assignee, with open, blocked and per-priority counts, highest priority first within each group. Tasks without an assignee are grouped last with assignee null. Pass assignee to get one person's queue."
+    )]
+    pub async fn task_by_assignee(
+        &self,
+        Parameters(params): Parameters<TaskByAssigneeParams>,
+    ) -> Result<CallToolResult, McpErrorData> {
+        let cache = self.cache.lock().await;
+        let groups = cache
+            .get_assignee_workload(params.assignee.as_deref())
+            .map_err(McpError::from)?;
+
+        let response = serde_json::json!({
+            "total_open": groups.iter().map(|g| g.open).sum::<usize>(),
+            "assignees": groups,
+        });
+
+        let json =
+            serde_json::to_string_pretty(&response).map_err(|e| McpError::InternalError {
+                message: format!("Failed to serialize workload: {}", e),
+            })?;
+
+        Ok(CallToolResult::success(vec![Content::text(json)]))
+    }
+
+    // ========================================================================
+    // task_plan
+    // ========================================================================
+
+    /// List open tasks in dependency order.
+    #[tool(
+        description = "List all non-done tasks in an order that respects 'blocks' relations (blockers before the tasks they block), breaking ties by priority then due date. Tasks caught in a dependency cycle are reported separately."
+    )]
+    pub async fn task_plan(&self) -> Result<CallToolResult, McpErrorData> {
+        let store = self.store.lock().await;
+        let tasks: Vec<Task> = store
+            .list_tasks()
+            .map_err(McpError::from)?
+            .into_iter()
+            .filter(|t| t.status != crate::entity::TaskStatus::Done)
+            .collect();
+        let index: HashMap<uuid::Uuid, usize> = tasks
+            .iter()
+            .enumerate()
+            .map(|(i, t)| (t.base.id, i))
+            .collect();
+
+        // Done blockers no longer hold anything up, so only edges between
+        // open tasks count
+        let blocks: Vec<(usize, usize)> = store
+            .list_relations()
+            .map_err(McpError::from)?
+            .iter()
+            .filter(|r| r.is_active() && r.relation_type == crate::entity::RelationType::Blocks)
+            .filter_map(|r| Some((*index.get(&r.source_id)?, *index.get(&r.target_id)?)))
+            .collect();
+
+        let ranks: Vec<_> = tasks
+            .iter()
+            .map(|t| {
+                (
+                    std::cmp::Reverse(t.priority),
+                    t.due_date.is_none(),
+                    t.due_date,
+                    t.base.sequence_number,
+                )
+            })
+            .collect();
+        let (order, leftover) = topological_order(&ranks, &blocks);
+        let cycles = dependency_cycles(&leftover, &blocks);
+
+        let task_json = |i: &usize| {
+            let t = &tasks[*i];
+            serde_json::json!({
+                "id": t.base.id.to_string(),
+                "sequence_number": t.base.sequence_number,
+                "title": t.base.title,
+                "status": t.status.to_string(),
+                "priority": t.priority.to_string(),
+                "due_date": t.due_date.map(|d| d.to_string()),
+                "assignee": t.assignee,
+            })
+        };
+        let cycles: Vec<Vec<serde_json::Value>> = cycles
+            .iter()
+            .map(|cycle| cycle.iter().map(task_json).collect())
+            .collect();
+
+        let response = serde_json::json!({
+            "tasks": order.iter().map(task_json).collect::<Vec<_>>(),
+            "total": order.len(),
+            "cycles": cycles,
+            "unordered": leftover.iter().map(task_json).collect::<Vec<_>>(),
+        });
+
+        let json =
+            serde_json::to_string_pretty(&response).map_err(|e| McpError::InternalError {
+                message: format!("Failed to serialize task plan: {}", e),
+            })?;
 
-                let updated = store
-                    .get_task(&t.base.id)
+        Ok(CallToolResult::success(vec![Content::text(json)]))
+    }
+
+    // ========================================================================
+    // task_tree
+    // ========================================================================
+
+    /// Show a task's subtask hierarchy with rollup progress.
+    #[tool(
+        description = "Show a task's subtasks (from parent_of/child_of relations) as a tree, with rollup progress such as 3/7 subtasks done at each level"
+    )]
+    pub async fn task_tree(
+        &self,
+        Parameters(params): Parameters<TaskTreeParams>,
+    ) -> Result<CallToolResult, McpErrorData> {
+        let store = self.store.lock().await;
+        let (uuid, entity_type) = self.resolve_entity_id_with_type(&store, &params.id)?;
+        if entity_type != "task" {
+            return Err(McpError::ValidationFailed {
+                field: "id".to_string(),
+                message: format!("Entity {} is a {}, not a task", params.id, entity_type),
+            }
+            .into());
+        }
+
+        let tree = crate::graph::TaskTree::build(&store, uuid).map_err(McpError::from)?;
+        let mut response = tree.to_json();
+        response["rendered"] = serde_json::Value::String(tree.render());
+
+        let json =
+            serde_json::to_string_pretty(&response).map_err(|e| McpError::InternalError {
+                message: format!("Failed to serialize task tree: {}", e),
+            })?;
+
+        Ok(CallToolResult::success(vec![Content::text(json)]))
+    }
+
+    // ========================================================================
+    // milestone_progress
+    // ========================================================================
+
+    /// Show milestone completion computed from the tasks that belong to it.
+    #[tool(
+        description = "Show roadmap progress: each milestone's target date, status and completion percentage, computed from the tasks linked to it with belongs_to relations. Pass an id for one milestone, or omit it for all unarchived milestones ordered by target date."
+    )]
+    pub async fn milestone_progress(
+        &self,
+        Parameters(params): Parameters<MilestoneProgressParams>,
+    ) -> Result<CallToolResult, McpErrorData> {
+        let store = self.store.lock().await;
+        let today = chrono::Utc::now().date_naive();
+
+        let response = match params.id {
+            Some(ref id) => {
+                let (uuid, entity_type) = self.resolve_entity_id_with_type(&store, id)?;
+                if entity_type != "milestone" {
+                    return Err(McpError::ValidationFailed {
+                        field: "id".to_string(),
+                        message: format!("Entity {} is a {}, not a milestone", id, entity_type),
+                    }
+                    .into());
+                }
+                crate::graph::MilestoneProgress::build(&store, uuid)
                     .map_err(McpError::from)?
-                    .ok_or_else(|| McpError::EntityNotFound {
-                        id: params.id.clone(),
-                    })?;
-                cache.index_task(&updated).map_err(McpError::from)?;
+                    .to_json(today)
+            }
+            None => {
+                let milestones =
+                    crate::graph::MilestoneProgress::all(&store, false).map_err(McpError::from)?;
+                serde_json::json!({
+                    "milestones": milestones
+                        .iter()
+                        .map(|p| p.to_json(today))
+                        .collect::<Vec<_>>(),
+                })
+            }
+        };
+
+        let json =
+            serde_json::to_string_pretty(&response).map_err(|e| McpError::InternalError {
+                message: format!("Failed to serialize milestone progress: {}", e),
+            })?;
+
+        Ok(CallToolResult::success(vec![Content::text(json)]))
+    }
+
+    // ========================================================================
+    // task_next (Beads Parity)
+    // ========================================================================
+
+    /// Get the highest-scoring ready task.
+    #[tool(
+        description = "Get the single task that is ready to work on with the highest score. The score breakdown (priority, due, age, unblocks, total) explains the choice; weights come from task_scoring in the project config. With respect_wip_limits, tasks whose assignee is already at their WIP limit are skipped."
+    )]
+    pub async fn task_next(
+        &self,
+        Parameters(params): Parameters<TaskNextParams>,
+    ) -> Result<CallToolResult, McpErrorData> {
+        let wip_limits = params
+            .respect_wip_limits
+            .unwrap_or(false)
+            .then_some(&self.config.wip_limits);
+        let cache = self.cache.lock().await;
+        let next_task = cache
+            .get_next_task_with(wip_limits)
+            .map_err(McpError::from)?;
+
+        match next_task {
+            Some(t) => {
+                let response = serde_json::json!({
+                    "id": t.id,
+                    "sequence_number": t.sequence_number,
+                    "title": t.title,
+                    "status": t.status,
+                    "priority": t.priority,
+                    "due_date": t.due_date,
+                    "assignee": t.assignee,
+                    "unblocks": t.unblocks,
+                    "score": t.score,
+                });
 
-                let response = task_to_response(&updated);
                 let json = serde_json::to_string_pretty(&response).map_err(|e| {
                     McpError::InternalError {
-                        message: format!("Failed to serialize task: {}", e),
+                        message: format!("Failed to serialize next task: {}", e),
                     }
                 })?;
 
-                return Ok(CallToolResult::success(vec![Content::text(json)]));
+                Ok(CallToolResult::success(vec![Content::text(json)]))
+            }
+            None => {
+                let response = serde_json::json!({
+                    "message": "No ready tasks available",
+                    "task": null,
+                });
+
+                let json = serde_json::to_string_pretty(&response).map_err(|e| {
+                    McpError::InternalError {
+                        message: format!("Failed to serialize response: {}", e),
+                    }
+                })?;
+
+                Ok(CallToolResult::success(vec![Content::text(json)]))
+            }
+        }
+    }
+
+    // ========================================================================
+    // task_complete (Convenience)
+    // ========================================================================
+
+    /// Mark a task as done.
+    #[tool(
+        description = "Mark a task as done (convenience wrapper for entity_update with status=done). If the task has a recurrence rule, its next occurrence is created and returned as next_occurrence."
+    )]
+    pub async fn task_complete(
+        &self,
+        Parameters(params): Parameters<TaskCompleteParams>,
+    ) -> Result<CallToolResult, McpErrorData> {
+        let store = self.store.lock().await;
+        let cache = self.cache.lock().await;
+
+        // Find the task
+        if let Some(t) = lookup(&store, "task", &params.id, LoroStore::get_task)? {
+            let mut update = TaskUpdate::default();
+            update.status = Some(crate::entity::TaskStatus::Done);
+
+            store
+                .update_task(&t.base.id, update)
+                .map_err(McpError::from)?;
+            store.save().map_err(McpError::from)?;
+
+            let updated = store
+                .get_task(&t.base.id)
+                .map_err(McpError::from)?
+                .ok_or_else(|| McpError::EntityNotFound {
+                    id: params.id.clone(),
+                })?;
+            cache.index_task(&updated).map_err(McpError::from)?;
+
+            let mut response = serde_json::to_value(task_to_response(&updated)).map_err(|e| {
+                McpError::InternalError {
+                    message: format!("Failed to serialize task: {}", e),
+                }
+            })?;
+
+            let mut uris = resources::entity_change_uris(
+                "task",
+                &updated.base.id.to_string(),
+                updated.base.sequence_number,
+            );
+
+            // Completing a recurring task schedules its next occurrence
+            // (only once, not on repeat completions)
+            if t.status != crate::entity::TaskStatus::Done {
+                let today = chrono::Utc::now().date_naive();
+                if let Some(next) = store
+                    .add_next_occurrence(&updated, today)
+                    .map_err(McpError::from)?
+                {
+                    store.save().map_err(McpError::from)?;
+                    cache.index_task(&next).map_err(McpError::from)?;
+                    Self::try_compute_embedding(
+                        &cache,
+                        &next.base.id.to_string(),
+                        "task",
+                        &next.base.title,
+                        next.base.content.as_deref(),
+                        &next.base.tags,
+                    );
+                    response["next_occurrence"] = serde_json::to_value(task_to_response(&next))
+                        .map_err(|e| McpError::InternalError {
+                            message: format!("Failed to serialize task: {}", e),
+                        })?;
+                    uris.extend(resources::entity_change_uris(
+                        "task",
+                        &next.base.id.to_string(),
+                        next.base.sequence_number,
+                    ));
+                    uris.sort();
+                    uris.dedup();
+                }
             }
+
+            let json =
+                serde_json::to_string_pretty(&response).map_err(|e| McpError::InternalError {
+                    message: format!("Failed to serialize task: {}", e),
+                })?;
+
+            drop(cache);
+            drop(store);
+            self.notify_resources_updated(uris).await;
+
+            return Ok(CallToolResult::success(vec![Content::text(json)]));
         }
 
         Err(McpError::EntityNotFound {
@@ -1908,37 +4040,31 @@ impl MedullaServer {
         let store = self.store.lock().await;
         let cache = self.cache.lock().await;
 
-        let is_sequence = params.id.chars().all(|c| c.is_ascii_digit());
-
         // Find the task
-        let tasks = store.list_tasks().map_err(McpError::from)?;
-        for t in tasks {
-            if self.matches_id(&t.base, &params.id, is_sequence) {
-                let mut update = TaskUpdate::default();
-                update.due_date = Some(Some(due_date));
+        if let Some(t) = lookup(&store, "task", &params.id, LoroStore::get_task)? {
+            let mut update = TaskUpdate::default();
+            update.due_date = Some(Some(due_date));
 
-                store
-                    .update_task(&t.base.id, update)
-                    .map_err(McpError::from)?;
-                store.save().map_err(McpError::from)?;
+            store
+                .update_task(&t.base.id, update)
+                .map_err(McpError::from)?;
+            store.save().map_err(McpError::from)?;
 
-                let updated = store
-                    .get_task(&t.base.id)
-                    .map_err(McpError::from)?
-                    .ok_or_else(|| McpError::EntityNotFound {
-                        id: params.id.clone(),
-                    })?;
-                cache.index_task(&updated).map_err(McpError::from)?;
+            let updated = store
+                .get_task(&t.base.id)
+                .map_err(McpError::from)?
+                .ok_or_else(|| McpError::EntityNotFound {
+                    id: params.id.clone(),
+                })?;
+            cache.index_task(&updated).map_err(McpError::from)?;
 
-                let response = task_to_response(&updated);
-                let json = serde_json::to_string_pretty(&response).map_err(|e| {
-                    McpError::InternalError {
-                        message: format!("Failed to serialize task: {}", e),
-                    }
+            let response = task_to_response(&updated);
+            let json =
+                serde_json::to_string_pretty(&response).map_err(|e| McpError::InternalError {
+                    message: format!("Failed to serialize task: {}", e),
                 })?;
 
-                return Ok(CallToolResult::success(vec![Content::text(json)]));
-            }
+            return Ok(CallToolResult::success(vec![Content::text(json)]));
         }
 
         Err(McpError::EntityNotFound {
@@ -1947,6 +4073,70 @@ impl MedullaServer {
         .into())
     }
 
+    // ========================================================================
+    // project_burndown
+    // ========================================================================
+
+    /// Summarize estimated effort for a set of tasks.
+    #[tool(
+        description = "Estimated effort for tasks (optionally those with a project tag): total, completed and remaining minutes, plus task counts by status"
+    )]
+    pub async fn project_burndown(
+        &self,
+        Parameters(params): Parameters<ProjectBurndownParams>,
+    ) -> Result<CallToolResult, McpErrorData> {
+        let store = self.store.lock().await;
+        let tasks: Vec<Task> = store
+            .list_tasks()
+            .map_err(McpError::from)?
+            .into_iter()
+            .filter(|t| {
+                params
+                    .tag
+                    .as_ref()
+                    .map_or(true, |tag| t.base.tags.contains(tag))
+            })
+            .collect();
+
+        let mut counts: HashMap<String, usize> = ["todo", "in_progress", "done", "blocked"]
+            .iter()
+            .map(|s| (s.to_string(), 0))
+            .collect();
+        let mut total_minutes: u64 = 0;
+        let mut completed_minutes: u64 = 0;
+        let mut unestimated = 0;
+
+        for task in &tasks {
+            *counts.entry(task.status.to_string()).or_insert(0) += 1;
+            match task.estimate_minutes {
+                Some(minutes) => {
+                    total_minutes += minutes as u64;
+                    if task.status == crate::entity::TaskStatus::Done {
+                        completed_minutes += minutes as u64;
+                    }
+                }
+                None => unestimated += 1,
+            }
+        }
+
+        let response = serde_json::json!({
+            "tag": params.tag,
+            "total_tasks": tasks.len(),
+            "counts": counts,
+            "unestimated_tasks": unestimated,
+            "total_minutes": total_minutes,
+            "completed_minutes": completed_minutes,
+            "remaining_minutes": total_minutes - completed_minutes,
+        });
+
+        let json =
+            serde_json::to_string_pretty(&response).map_err(|e| McpError::InternalError {
+                message: format!("Failed to serialize response: {}", e),
+            })?;
+
+        Ok(CallToolResult::success(vec![Content::text(json)]))
+    }
+
     // ========================================================================
     // decision_supersede (Convenience)
     // ========================================================================
@@ -1974,7 +4164,7 @@ impl MedullaServer {
                 id: params.old_id.clone(),
             })?;
 
-        let _new_decision = store
+        let new_decision = store
             .get_decision(&new_uuid)
             .map_err(McpError::from)?
             .ok_or_else(|| McpError::EntityNotFound {
@@ -2000,6 +4190,65 @@ impl MedullaServer {
         );
 
         store.add_relation(&relation).map_err(McpError::from)?;
+
+        // Optionally repoint incoming relations (e.g. components implementing
+        // the old decision) at the new decision
+        let mut migrated = Vec::new();
+        let mut uris = resources::entity_change_uris(
+            "decision",
+            &old_uuid.to_string(),
+            old_decision.base.sequence_number,
+        );
+        uris.extend(resources::entity_change_uris(
+            "decision",
+            &new_uuid.to_string(),
+            new_decision.base.sequence_number,
+        ));
+        uris.extend(resources::relation_change_uris(
+            &new_uuid.to_string(),
+            &old_uuid.to_string(),
+        ));
+        if params.migrate_relations.unwrap_or(false) {
+            let incoming = store
+                .get_relations_to(&old_uuid.to_string())
+                .map_err(McpError::from)?;
+
+            for old_relation in incoming {
+                if old_relation.source_id == new_uuid
+                    || old_relation.relation_type == crate::entity::RelationType::Supersedes
+                {
+                    continue;
+                }
+
+                let mut moved = old_relation.clone();
+                moved.target_id = new_uuid;
+
+                store
+                    .delete_relation(
+                        &old_relation.source_id.to_string(),
+                        &old_relation.relation_type.to_string(),
+                        &old_relation.target_id.to_string(),
+                    )
+                    .map_err(McpError::from)?;
+                store.add_relation(&moved).map_err(McpError::from)?;
+
+                cache
+                    .remove_relation(&old_relation.composite_key())
+                    .map_err(McpError::from)?;
+                cache.index_relation(&moved).map_err(McpError::from)?;
+
+                uris.extend(resources::relation_change_uris(
+                    &moved.source_id.to_string(),
+                    &old_uuid.to_string(),
+                ));
+                uris.extend(resources::relation_change_uris(
+                    &moved.source_id.to_string(),
+                    &new_uuid.to_string(),
+                ));
+                migrated.push(relation_to_response(&moved));
+            }
+        }
+
         store.save().map_err(McpError::from)?;
 
         // Reindex both decisions
@@ -2020,6 +4269,7 @@ impl MedullaServer {
                 "from": new_uuid.to_string(),
                 "to": old_uuid.to_string(),
             },
+            "migrated_relations": migrated,
             "message": format!(
                 "Decision '{}' has been superseded by decision '{}'",
                 old_decision.base.title,
@@ -2032,6 +4282,12 @@ impl MedullaServer {
                 message: format!("Failed to serialize response: {}", e),
             })?;
 
+        drop(cache);
+        drop(store);
+        uris.sort();
+        uris.dedup();
+        self.notify_resources_updated(uris).await;
+
         Ok(CallToolResult::success(vec![Content::text(json)]))
     }
 
@@ -2057,14 +4313,27 @@ impl MedullaServer {
             self.resolve_entity_id_with_type(&store, &params.target_id)?;
 
         // Parse and validate relation type
-        let relation_type: crate::entity::RelationType =
-            params
-                .relation_type
-                .parse()
-                .map_err(|e: String| McpError::ValidationFailed {
-                    field: "relation_type".to_string(),
-                    message: e,
-                })?;
+        if self.config.strict_enums {
+            check_strict_enum("relation_type", &params.relation_type)?;
+        }
+        let relation_type = self
+            .relation_config(&store)?
+            .parse_relation_type(&params.relation_type)
+            .map_err(|e| McpError::ValidationFailed {
+                field: "relation_type".to_string(),
+                message: e,
+            })?;
+
+        // A blocks cycle would keep every task on it out of tasks_ready
+        if relation_type == crate::entity::RelationType::Blocks {
+            if let Some(cycle) = crate::graph::blocks_cycle_with(&store, source_uuid, target_uuid)
+                .map_err(McpError::from)?
+            {
+                let mut cycle: Vec<String> = cycle.iter().map(|n| n.label()).collect();
+                cycle.push(cycle[0].clone());
+                return Err(McpError::RelationCycle { cycle }.into());
+            }
+        }
 
         // Create the relation
         let relation = crate::entity::Relation::new(
@@ -2098,9 +4367,176 @@ impl MedullaServer {
                 message: format!("Failed to serialize response: {}", e),
             })?;
 
+        drop(cache);
+        drop(store);
+        self.notify_resources_updated(resources::relation_change_uris(
+            &source_uuid.to_string(),
+            &target_uuid.to_string(),
+        ))
+        .await;
+
+        Ok(CallToolResult::success(vec![Content::text(json)]))
+    }
+
+    // ========================================================================
+    // relation_batch
+    // ========================================================================
+
+    /// Create many relations at once, all or none.
+    #[tool(
+        description = "Create many relations at once. Source and target may be a sequence number, UUID prefix or exact title. Every relation is validated (entities, relation type, blocks cycles) before any is written; if one fails, none are created and abort_reason says why. dry_run validates only."
+    )]
+    pub async fn relation_batch(
+        &self,
+        Parameters(params): Parameters<RelationBatchParams>,
+    ) -> Result<CallToolResult, McpErrorData> {
+        if params.relations.len() > self.config.limits.max_batch_size {
+            return Err(McpError::ValidationFailed {
+                field: "relations".to_string(),
+                message: format!(
+                    "Maximum {} relations allowed per batch",
+                    self.config.limits.max_batch_size
+                ),
+            }
+            .into());
+        }
+
+        let batch_result = self
+            .create_relations(&params.relations, params.dry_run.unwrap_or(false))
+            .await?;
+
+        let json =
+            serde_json::to_string_pretty(&batch_result).map_err(|e| McpError::InternalError {
+                message: format!("Failed to serialize batch result: {}", e),
+            })?;
+
         Ok(CallToolResult::success(vec![Content::text(json)]))
     }
 
+    /// Validate every relation against a staging copy of the store, then
+    /// merge them into the store in one save, or create none if any is
+    /// invalid. Validation sees the relations before it, so a `blocks`
+    /// cycle closed within the batch is caught too. Not capped by
+    /// `max_batch_size`, for `medulla relation import`.
+    pub async fn create_relations(
+        &self,
+        items: &[RelationBatchItem],
+        dry_run: bool,
+    ) -> Result<BatchResult, McpError> {
+        let store = self.store.lock().await;
+        let relation_config = self.relation_config(&store)?;
+        let staged = store.staging();
+        let mut titles = None;
+
+        let mut results = Vec::new();
+        let mut relations = Vec::new();
+        for (index, item) in items.iter().enumerate() {
+            let relation = (|| {
+                let (source_uuid, source_type) =
+                    self.resolve_entity_ref(&store, &item.source, &mut titles)?;
+                let (target_uuid, target_type) =
+                    self.resolve_entity_ref(&store, &item.target, &mut titles)?;
+                if self.config.strict_enums {
+                    check_strict_enum("relation_type", &item.relation_type)?;
+                }
+                let relation_type = relation_config
+                    .parse_relation_type(&item.relation_type)
+                    .map_err(|e| McpError::ValidationFailed {
+                        field: "relation_type".to_string(),
+                        message: e,
+                    })?;
+                if relation_type == crate::entity::RelationType::Blocks {
+                    if let Some(cycle) =
+                        crate::graph::blocks_cycle_with(&staged, source_uuid, target_uuid)?
+                    {
+                        let mut cycle: Vec<String> = cycle.iter().map(|n| n.label()).collect();
+                        cycle.push(cycle[0].clone());
+                        return Err(McpError::RelationCycle { cycle });
+                    }
+                }
+
+                let mut relation = crate::entity::Relation::new(
+                    source_uuid,
+                    source_type,
+                    target_uuid,
+                    target_type,
+                    relation_type,
+                );
+                relation.created_by = store.author().map(str::to_string);
+                staged.add_relation(&relation)?;
+                Ok::<_, McpError>(relation)
+            })();
+
+            results.push(match relation {
+                Ok(relation) => {
+                    let result = BatchOperationResult {
+                        index,
+                        success: true,
+                        id: Some(relation.composite_key()),
+                        error: None,
+                    };
+                    relations.push(relation);
+                    result
+                }
+                Err(e) => BatchOperationResult {
+                    index,
+                    success: false,
+                    id: None,
+                    error: Some(BatchError {
+                        code: "INVALID_RELATION".to_string(),
+                        message: e.to_string(),
+                    }),
+                },
+            });
+        }
+
+        let succeeded = relations.len();
+        let failed = results.len() - succeeded;
+        let mut batch_result = BatchResult {
+            results,
+            succeeded,
+            failed,
+            abort_reason: None,
+        };
+        if let Some(failure) = batch_result.results.iter().find(|r| !r.success) {
+            let message = failure
+                .error
+                .as_ref()
+                .map_or("unknown error", |e| e.message.as_str());
+            batch_result.abort_reason = Some(format!(
+                "{} of {} relations are invalid, none were created; relation {}: {}",
+                failed,
+                items.len(),
+                failure.index,
+                message
+            ));
+            return Ok(batch_result);
+        }
+        if dry_run || relations.is_empty() {
+            return Ok(batch_result);
+        }
+
+        let cache = self.cache.lock().await;
+        store.apply_staged(&staged)?;
+        store.save()?;
+        let mut uris = Vec::new();
+        for relation in &relations {
+            cache.index_relation(relation)?;
+            uris.extend(resources::relation_change_uris(
+                &relation.source_id.to_string(),
+                &relation.target_id.to_string(),
+            ));
+        }
+        drop(cache);
+        drop(store);
+
+        uris.sort();
+        uris.dedup();
+        self.notify_resources_updated(uris).await;
+
+        Ok(batch_result)
+    }
+
     // ========================================================================
     // relation_delete
     // ========================================================================
@@ -2121,14 +4557,16 @@ impl MedullaServer {
         let (target_uuid, _) = self.resolve_entity_id_with_type(&store, &params.target_id)?;
 
         // Parse and validate relation type
-        let relation_type: crate::entity::RelationType =
-            params
-                .relation_type
-                .parse()
-                .map_err(|e: String| McpError::ValidationFailed {
-                    field: "relation_type".to_string(),
-                    message: e,
-                })?;
+        if self.config.strict_enums {
+            check_strict_enum("relation_type", &params.relation_type)?;
+        }
+        let relation_type = self
+            .relation_config(&store)?
+            .parse_relation_type(&params.relation_type)
+            .map_err(|e| McpError::ValidationFailed {
+                field: "relation_type".to_string(),
+                message: e,
+            })?;
 
         // Build the composite key for cache deletion
         let composite_key = format!("{}:{}:{}", source_uuid, relation_type, target_uuid);
@@ -2164,625 +4602,697 @@ impl MedullaServer {
                 message: format!("Failed to serialize response: {}", e),
             })?;
 
+        drop(cache);
+        drop(store);
+        self.notify_resources_updated(resources::relation_change_uris(
+            &source_uuid.to_string(),
+            &target_uuid.to_string(),
+        ))
+        .await;
+
         Ok(CallToolResult::success(vec![Content::text(json)]))
     }
-}
 
-// Helper methods that don't need #[tool] attribute - separate impl block
-impl MedullaServer {
-    fn find_entity_by_id(
+    // ========================================================================
+    // relation_resolve
+    // ========================================================================
+
+    /// Mark a relation as resolved without deleting it.
+    #[tool(
+        description = "Mark a relation as resolved (e.g., a finished blocker). The relation is kept for history but no longer blocks tasks."
+    )]
+    pub async fn relation_resolve(
         &self,
-        store: &LoroStore,
-        entity_type: &str,
-        id: &str,
-        is_sequence: bool,
-    ) -> Result<Option<EntityResponse>, McpError> {
-        match entity_type {
-            "decision" => {
-                let decisions = store.list_decisions().map_err(McpError::from)?;
-                for d in decisions {
-                    if self.matches_id(&d.base, id, is_sequence) {
-                        return Ok(Some(decision_to_response(&d)));
-                    }
-                }
-            }
-            "task" => {
-                let tasks = store.list_tasks().map_err(McpError::from)?;
-                for t in tasks {
-                    if self.matches_id(&t.base, id, is_sequence) {
-                        return Ok(Some(task_to_response(&t)));
-                    }
-                }
-            }
-            "note" => {
-                let notes = store.list_notes().map_err(McpError::from)?;
-                for n in notes {
-                    if self.matches_id(&n.base, id, is_sequence) {
-                        return Ok(Some(note_to_response(&n)));
-                    }
-                }
-            }
-            "prompt" => {
-                let prompts = store.list_prompts().map_err(McpError::from)?;
-                for p in prompts {
-                    if self.matches_id(&p.base, id, is_sequence) {
-                        return Ok(Some(prompt_to_response(&p)));
-                    }
-                }
-            }
-            "component" => {
-                let components = store.list_components().map_err(McpError::from)?;
-                for c in components {
-                    if self.matches_id(&c.base, id, is_sequence) {
-                        return Ok(Some(component_to_response(&c)));
-                    }
-                }
-            }
-            "link" => {
-                let links = store.list_links().map_err(McpError::from)?;
-                for l in links {
-                    if self.matches_id(&l.base, id, is_sequence) {
-                        return Ok(Some(link_to_response(&l)));
-                    }
-                }
-            }
-            _ => {}
-        }
-        Ok(None)
-    }
+        Parameters(params): Parameters<RelationResolveParams>,
+    ) -> Result<CallToolResult, McpErrorData> {
+        let store = self.store.lock().await;
+        let cache = self.cache.lock().await;
 
-    fn matches_id(&self, base: &EntityBase, id: &str, is_sequence: bool) -> bool {
-        if is_sequence {
-            base.sequence_number.to_string() == id
-        } else {
-            // UUID prefix match (case-insensitive)
-            let uuid_str = base.id.to_string().replace('-', "");
-            let search_id = id.replace('-', "").to_lowercase();
-            uuid_str.to_lowercase().starts_with(&search_id)
-        }
-    }
+        // Resolve source and target IDs to UUIDs
+        let (source_uuid, _) = self.resolve_entity_id_with_type(&store, &params.source_id)?;
+        let (target_uuid, _) = self.resolve_entity_id_with_type(&store, &params.target_id)?;
 
-    fn matches_filters(
-        &self,
-        base: &EntityBase,
-        params: &EntityListParams,
-        status: Option<&str>,
-    ) -> bool {
-        // Filter by status
-        if let Some(ref filter_status) = params.status {
-            if let Some(entity_status) = status {
-                if entity_status != filter_status {
-                    return false;
-                }
-            } else {
-                return false;
-            }
+        // Parse and validate relation type
+        if self.config.strict_enums {
+            check_strict_enum("relation_type", &params.relation_type)?;
         }
+        let relation_type = self
+            .relation_config(&store)?
+            .parse_relation_type(&params.relation_type)
+            .map_err(|e| McpError::ValidationFailed {
+                field: "relation_type".to_string(),
+                message: e,
+            })?;
 
-        // Filter by tag
-        if let Some(ref filter_tag) = params.tag {
-            if !base.tags.iter().any(|t| t == filter_tag) {
-                return false;
-            }
-        }
+        let relation = store
+            .resolve_relation(
+                &source_uuid.to_string(),
+                &relation_type.to_string(),
+                &target_uuid.to_string(),
+            )
+            .map_err(McpError::from)?;
+        store.save().map_err(McpError::from)?;
 
-        true
+        cache.index_relation(&relation).map_err(McpError::from)?;
+
+        let response = relation_to_response(&relation);
+
+        let json =
+            serde_json::to_string_pretty(&response).map_err(|e| McpError::InternalError {
+                message: format!("Failed to serialize response: {}", e),
+            })?;
+
+        drop(cache);
+        drop(store);
+        self.notify_resources_updated(resources::relation_change_uris(
+            &source_uuid.to_string(),
+            &target_uuid.to_string(),
+        ))
+        .await;
+
+        Ok(CallToolResult::success(vec![Content::text(json)]))
     }
 
-    fn try_update_entity(
+    // ========================================================================
+    // snapshot_preview
+    // ========================================================================
+
+    /// Render a single entity as snapshot markdown without writing files.
+    #[tool(
+        description = "Preview how an entity will render in the markdown snapshot, including YAML frontmatter. Does not write any files."
+    )]
+    pub async fn snapshot_preview(
         &self,
-        store: &LoroStore,
-        cache: &SqliteCache,
-        entity_type: &str,
-        params: &EntityUpdateParams,
-        is_sequence: bool,
-    ) -> Result<Option<EntityResponse>, McpError> {
-        match entity_type {
-            "decision" => {
-                let decisions = store.list_decisions().map_err(McpError::from)?;
-                for d in decisions {
-                    if self.matches_id(&d.base, &params.id, is_sequence) {
-                        let mut update = DecisionUpdate::default();
-                        update.title = params.title.clone();
-                        update.content = params.content.clone();
-                        update.add_tags = params.add_tags.clone().unwrap_or_default();
-                        update.remove_tags = params.remove_tags.clone().unwrap_or_default();
-
-                        if let Some(ref props) = params.properties {
-                            if let Some(status) = props.get("status").and_then(|v| v.as_str()) {
-                                update.status = Some(parse_decision_status(status)?);
-                            }
-                            if let Some(context) = props.get("context").and_then(|v| v.as_str()) {
-                                update.context = Some(context.to_string());
-                            }
-                        }
+        Parameters(params): Parameters<SnapshotPreviewParams>,
+    ) -> Result<CallToolResult, McpErrorData> {
+        let store = self.store.lock().await;
 
-                        store
-                            .update_decision(&d.base.id, update)
-                            .map_err(McpError::from)?;
-                        store.save().map_err(McpError::from)?;
-
-                        // Refetch and reindex
-                        let updated = store
-                            .get_decision(&d.base.id)
-                            .map_err(McpError::from)?
-                            .ok_or_else(|| McpError::EntityNotFound {
-                                id: params.id.clone(),
-                            })?;
-                        cache.index_decision(&updated).map_err(McpError::from)?;
-
-                        // Recompute embedding if embeddable content changed
-                        if params.title.is_some()
-                            || params.content.is_some()
-                            || params.add_tags.is_some()
-                            || params.remove_tags.is_some()
-                        {
-                            Self::try_compute_embedding(
-                                cache,
-                                &updated.base.id.to_string(),
-                                "decision",
-                                &updated.base.title,
-                                updated.base.content.as_deref(),
-                                &updated.base.tags,
-                            );
-                        }
+        let (uuid, entity_type) = self.resolve_entity_id_with_type(&store, &params.id)?;
+        let markdown = crate::snapshot::render_entity(&store, &entity_type, &uuid)
+            .map_err(McpError::from)?
+            .ok_or_else(|| McpError::EntityNotFound {
+                id: params.id.clone(),
+            })?;
 
-                        return Ok(Some(decision_to_response(&updated)));
-                    }
-                }
-            }
-            "task" => {
-                let tasks = store.list_tasks().map_err(McpError::from)?;
-                for t in tasks {
-                    if self.matches_id(&t.base, &params.id, is_sequence) {
-                        let mut update = TaskUpdate::default();
-                        update.title = params.title.clone();
-                        update.content = params.content.clone();
-                        update.add_tags = params.add_tags.clone().unwrap_or_default();
-                        update.remove_tags = params.remove_tags.clone().unwrap_or_default();
-
-                        if let Some(ref props) = params.properties {
-                            if let Some(status) = props.get("status").and_then(|v| v.as_str()) {
-                                update.status = Some(parse_task_status(status)?);
-                            }
-                            if let Some(priority) = props.get("priority").and_then(|v| v.as_str()) {
-                                update.priority = Some(parse_task_priority(priority)?);
-                            }
-                            if let Some(due_date) = props.get("due_date").and_then(|v| v.as_str()) {
-                                update.due_date = Some(Some(parse_date("due_date", due_date)?));
-                            }
-                            if let Some(assignee) = props.get("assignee").and_then(|v| v.as_str()) {
-                                update.assignee = Some(Some(assignee.to_string()));
-                            }
-                        }
+        let response = serde_json::json!({
+            "id": uuid.to_string(),
+            "type": entity_type,
+            "markdown": markdown,
+        });
 
-                        store
-                            .update_task(&t.base.id, update)
-                            .map_err(McpError::from)?;
-                        store.save().map_err(McpError::from)?;
-
-                        let updated = store
-                            .get_task(&t.base.id)
-                            .map_err(McpError::from)?
-                            .ok_or_else(|| McpError::EntityNotFound {
-                                id: params.id.clone(),
-                            })?;
-                        cache.index_task(&updated).map_err(McpError::from)?;
-
-                        // Recompute embedding if embeddable content changed
-                        if params.title.is_some()
-                            || params.content.is_some()
-                            || params.add_tags.is_some()
-                            || params.remove_tags.is_some()
-                        {
-                            Self::try_compute_embedding(
-                                cache,
-                                &updated.base.id.to_string(),
-                                "task",
-                                &updated.base.title,
-                                updated.base.content.as_deref(),
-                                &updated.base.tags,
-                            );
-                        }
+        let json =
+            serde_json::to_string_pretty(&response).map_err(|e| McpError::InternalError {
+                message: format!("Failed to serialize response: {}", e),
+            })?;
 
-                        return Ok(Some(task_to_response(&updated)));
-                    }
-                }
-            }
-            "note" => {
-                let notes = store.list_notes().map_err(McpError::from)?;
-                for n in notes {
-                    if self.matches_id(&n.base, &params.id, is_sequence) {
-                        let mut update = NoteUpdate::default();
-                        update.title = params.title.clone();
-                        update.content = params.content.clone();
-                        update.add_tags = params.add_tags.clone().unwrap_or_default();
-                        update.remove_tags = params.remove_tags.clone().unwrap_or_default();
-
-                        if let Some(ref props) = params.properties {
-                            if let Some(note_type) = props.get("note_type").and_then(|v| v.as_str())
-                            {
-                                update.note_type = Some(Some(note_type.to_string()));
-                            }
-                        }
+        Ok(CallToolResult::success(vec![Content::text(json)]))
+    }
 
-                        store
-                            .update_note(&n.base.id, update)
-                            .map_err(McpError::from)?;
-                        store.save().map_err(McpError::from)?;
-
-                        let updated = store
-                            .get_note(&n.base.id)
-                            .map_err(McpError::from)?
-                            .ok_or_else(|| McpError::EntityNotFound {
-                                id: params.id.clone(),
-                            })?;
-                        cache.index_note(&updated).map_err(McpError::from)?;
-
-                        // Recompute embedding if embeddable content changed
-                        if params.title.is_some()
-                            || params.content.is_some()
-                            || params.add_tags.is_some()
-                            || params.remove_tags.is_some()
-                        {
-                            Self::try_compute_embedding(
-                                cache,
-                                &updated.base.id.to_string(),
-                                "note",
-                                &updated.base.title,
-                                updated.base.content.as_deref(),
-                                &updated.base.tags,
-                            );
-                        }
+    // ========================================================================
+    // snapshot_pending
+    // ========================================================================
 
-                        return Ok(Some(note_to_response(&updated)));
-                    }
-                }
-            }
-            "prompt" => {
-                let prompts = store.list_prompts().map_err(McpError::from)?;
-                for p in prompts {
-                    if self.matches_id(&p.base, &params.id, is_sequence) {
-                        let mut update = PromptUpdate::default();
-                        update.title = params.title.clone();
-                        update.content = params.content.clone();
-                        update.add_tags = params.add_tags.clone().unwrap_or_default();
-                        update.remove_tags = params.remove_tags.clone().unwrap_or_default();
-
-                        if let Some(ref props) = params.properties {
-                            if let Some(template) = props.get("template").and_then(|v| v.as_str()) {
-                                update.template = Some(Some(template.to_string()));
-                            }
-                            if let Some(output_schema) =
-                                props.get("output_schema").and_then(|v| v.as_str())
-                            {
-                                update.output_schema = Some(Some(output_schema.to_string()));
-                            }
-                            if let Some(add_vars) =
-                                props.get("add_variables").and_then(|v| v.as_array())
-                            {
-                                update.add_variables = add_vars
-                                    .iter()
-                                    .filter_map(|v| v.as_str().map(String::from))
-                                    .collect();
-                            }
-                            if let Some(remove_vars) =
-                                props.get("remove_variables").and_then(|v| v.as_array())
-                            {
-                                update.remove_variables = remove_vars
-                                    .iter()
-                                    .filter_map(|v| v.as_str().map(String::from))
-                                    .collect();
-                            }
-                        }
+    /// List entities changed since the last snapshot was generated.
+    #[tool(
+        description = "List entities modified since the snapshot was last generated (in the output directory of the latest `medulla snapshot` run), with counts by type. An empty list means the snapshot is up to date."
+    )]
+    pub async fn snapshot_pending(&self) -> Result<CallToolResult, McpErrorData> {
+        let store = self.store.lock().await;
 
-                        store
-                            .update_prompt(&p.base.id, update)
-                            .map_err(McpError::from)?;
-                        store.save().map_err(McpError::from)?;
-
-                        let updated = store
-                            .get_prompt(&p.base.id)
-                            .map_err(McpError::from)?
-                            .ok_or_else(|| McpError::EntityNotFound {
-                                id: params.id.clone(),
-                            })?;
-                        cache.index_prompt(&updated).map_err(McpError::from)?;
-
-                        // Recompute embedding if embeddable content changed
-                        if params.title.is_some()
-                            || params.content.is_some()
-                            || params.add_tags.is_some()
-                            || params.remove_tags.is_some()
-                        {
-                            Self::try_compute_embedding(
-                                cache,
-                                &updated.base.id.to_string(),
-                                "prompt",
-                                &updated.base.title,
-                                updated.base.content.as_deref(),
-                                &updated.base.tags,
-                            );
-                        }
+        let snapshot_dir =
+            crate::snapshot::current_snapshot_dir(store.medulla_dir()).map_err(McpError::from)?;
+        let meta = crate::snapshot::read_snapshot_meta(&snapshot_dir).map_err(McpError::from)?;
+
+        // Without a recorded snapshot everything is pending; an unchanged
+        // store version means nothing is
+        let unchanged = meta
+            .as_ref()
+            .is_some_and(|m| m.store_version == store.version_hash());
+        let since = meta.as_ref().map(|m| m.generated_at);
+        let bases = if unchanged {
+            Vec::new()
+        } else {
+            self.list_entity_bases(&store)?
+        };
 
-                        return Ok(Some(prompt_to_response(&updated)));
-                    }
-                }
+        let mut pending = Vec::new();
+        let mut counts: HashMap<&str, usize> = HashMap::new();
+        for (entity_type, base) in bases {
+            if since.is_some_and(|t| base.updated_at <= t) {
+                continue;
             }
-            "component" => {
-                let components = store.list_components().map_err(McpError::from)?;
-                for c in components {
-                    if self.matches_id(&c.base, &params.id, is_sequence) {
-                        let mut update = ComponentUpdate::default();
-                        update.title = params.title.clone();
-                        update.content = params.content.clone();
-                        update.add_tags = params.add_tags.clone().unwrap_or_default();
-                        update.remove_tags = params.remove_tags.clone().unwrap_or_default();
-
-                        if let Some(ref props) = params.properties {
-                            if let Some(status) = props.get("status").and_then(|v| v.as_str()) {
-                                update.status = Some(parse_component_status(status)?);
-                            }
-                            if let Some(component_type) =
-                                props.get("component_type").and_then(|v| v.as_str())
-                            {
-                                update.component_type = Some(Some(component_type.to_string()));
-                            }
-                            if let Some(owner) = props.get("owner").and_then(|v| v.as_str()) {
-                                update.owner = Some(Some(owner.to_string()));
-                            }
-                        }
+            *counts.entry(entity_type).or_insert(0) += 1;
+            pending.push(serde_json::json!({
+                "id": base.id.to_string(),
+                "sequence_number": base.sequence_number,
+                "type": entity_type,
+                "title": base.title,
+                "updated_at": base.updated_at.to_rfc3339(),
+            }));
+        }
 
-                        store
-                            .update_component(&c.base.id, update)
-                            .map_err(McpError::from)?;
-                        store.save().map_err(McpError::from)?;
-
-                        let updated = store
-                            .get_component(&c.base.id)
-                            .map_err(McpError::from)?
-                            .ok_or_else(|| McpError::EntityNotFound {
-                                id: params.id.clone(),
-                            })?;
-                        cache.index_component(&updated).map_err(McpError::from)?;
-
-                        // Recompute embedding if embeddable content changed
-                        if params.title.is_some()
-                            || params.content.is_some()
-                            || params.add_tags.is_some()
-                            || params.remove_tags.is_some()
-                        {
-                            Self::try_compute_embedding(
-                                cache,
-                                &updated.base.id.to_string(),
-                                "component",
-                                &updated.base.title,
-                                updated.base.content.as_deref(),
-                                &updated.base.tags,
-                            );
-                        }
+        let response = serde_json::json!({
+            "last_snapshot_at": meta.map(|m| m.generated_at.to_rfc3339()),
+            "up_to_date": pending.is_empty(),
+            "pending": pending,
+            "counts": counts,
+            "total": pending.len(),
+        });
 
-                        return Ok(Some(component_to_response(&updated)));
-                    }
-                }
-            }
-            "link" => {
-                let links = store.list_links().map_err(McpError::from)?;
-                for l in links {
-                    if self.matches_id(&l.base, &params.id, is_sequence) {
-                        let mut update = LinkUpdate::default();
-                        update.title = params.title.clone();
-                        update.content = params.content.clone();
-                        update.add_tags = params.add_tags.clone().unwrap_or_default();
-                        update.remove_tags = params.remove_tags.clone().unwrap_or_default();
-
-                        if let Some(ref props) = params.properties {
-                            if let Some(url) = props.get("url").and_then(|v| v.as_str()) {
-                                validate_url(url)?;
-                                update.url = Some(url.to_string());
-                            }
-                            if let Some(link_type) = props.get("link_type").and_then(|v| v.as_str())
-                            {
-                                update.link_type = Some(Some(link_type.to_string()));
-                            }
-                        }
+        let json =
+            serde_json::to_string_pretty(&response).map_err(|e| McpError::InternalError {
+                message: format!("Failed to serialize response: {}", e),
+            })?;
 
-                        store
-                            .update_link(&l.base.id, update)
-                            .map_err(McpError::from)?;
-                        store.save().map_err(McpError::from)?;
-
-                        let updated = store
-                            .get_link(&l.base.id)
-                            .map_err(McpError::from)?
-                            .ok_or_else(|| McpError::EntityNotFound {
-                                id: params.id.clone(),
-                            })?;
-                        cache.index_link(&updated).map_err(McpError::from)?;
-
-                        // Recompute embedding if embeddable content changed
-                        if params.title.is_some()
-                            || params.content.is_some()
-                            || params.add_tags.is_some()
-                            || params.remove_tags.is_some()
-                        {
-                            Self::try_compute_embedding(
-                                cache,
-                                &updated.base.id.to_string(),
-                                "link",
-                                &updated.base.title,
-                                updated.base.content.as_deref(),
-                                &updated.base.tags,
-                            );
-                        }
+        Ok(CallToolResult::success(vec![Content::text(json)]))
+    }
 
-                        return Ok(Some(link_to_response(&updated)));
-                    }
-                }
+    // ========================================================================
+    // focus_set / focus_get
+    // ========================================================================
+
+    /// Replace the focus set with the given entities.
+    #[tool(
+        description = "Set the focus: the working set of entities relevant to the current session. Replaces any previous focus; an empty list clears it. Pair with search_fulltext within_focus to scope searches."
+    )]
+    pub async fn focus_set(
+        &self,
+        Parameters(params): Parameters<FocusSetParams>,
+    ) -> Result<CallToolResult, McpErrorData> {
+        let store = self.store.lock().await;
+
+        let mut ids: Vec<uuid::Uuid> = Vec::new();
+        for id in &params.ids {
+            let (uuid, _) = self.resolve_entity_id_with_type(&store, id)?;
+            if !ids.contains(&uuid) {
+                ids.push(uuid);
             }
-            _ => {}
         }
-        Ok(None)
-    }
 
-    fn try_delete_entity(
+        store.set_focus(&ids).map_err(McpError::from)?;
+        store.save().map_err(McpError::from)?;
+
+        let response = resources::focus_summary(&store)?;
+        let json =
+            serde_json::to_string_pretty(&response).map_err(|e| McpError::InternalError {
+                message: format!("Failed to serialize response: {}", e),
+            })?;
+
+        drop(store);
+        self.notify_resources_updated(vec![resources::static_resources::FOCUS.to_string()])
+            .await;
+
+        Ok(CallToolResult::success(vec![Content::text(json)]))
+    }
+
+    /// Get summaries of the entities in the focus set.
+    #[tool(description = "Get the current focus set with a short summary of each entity")]
+    pub async fn focus_get(&self) -> Result<CallToolResult, McpErrorData> {
+        let store = self.store.lock().await;
+
+        let response = resources::focus_summary(&store)?;
+        let json =
+            serde_json::to_string_pretty(&response).map_err(|e| McpError::InternalError {
+                message: format!("Failed to serialize response: {}", e),
+            })?;
+
+        Ok(CallToolResult::success(vec![Content::text(json)]))
+    }
+}
+
+// Helper methods that don't need #[tool] attribute - separate impl block
+impl MedullaServer {
+    /// Collect the base fields of every entity, paired with its type.
+    fn list_entity_bases(
         &self,
         store: &LoroStore,
-        cache: &SqliteCache,
-        entity_type: &str,
-        id: &str,
-        is_sequence: bool,
-    ) -> Result<bool, McpError> {
-        match entity_type {
-            "decision" => {
-                let decisions = store.list_decisions().map_err(McpError::from)?;
-                for d in decisions {
-                    if self.matches_id(&d.base, id, is_sequence) {
-                        store.delete_decision(&d.base.id).map_err(McpError::from)?;
-                        store.save().map_err(McpError::from)?;
-                        cache
-                            .remove_decision(&d.base.id.to_string())
-                            .map_err(McpError::from)?;
-                        return Ok(true);
-                    }
-                }
-            }
-            "task" => {
-                let tasks = store.list_tasks().map_err(McpError::from)?;
-                for t in tasks {
-                    if self.matches_id(&t.base, id, is_sequence) {
-                        store.delete_task(&t.base.id).map_err(McpError::from)?;
-                        store.save().map_err(McpError::from)?;
-                        cache
-                            .remove_task(&t.base.id.to_string())
-                            .map_err(McpError::from)?;
-                        return Ok(true);
+    ) -> Result<Vec<(&'static str, EntityBase)>, McpError> {
+        let mut bases = Vec::new();
+        bases.extend(
+            store
+                .list_decisions()
+                .map_err(McpError::from)?
+                .into_iter()
+                .map(|d| ("decision", d.base)),
+        );
+        bases.extend(
+            store
+                .list_tasks()
+                .map_err(McpError::from)?
+                .into_iter()
+                .map(|t| ("task", t.base)),
+        );
+        bases.extend(
+            store
+                .list_notes()
+                .map_err(McpError::from)?
+                .into_iter()
+                .map(|n| ("note", n.base)),
+        );
+        bases.extend(
+            store
+                .list_prompts()
+                .map_err(McpError::from)?
+                .into_iter()
+                .map(|p| ("prompt", p.base)),
+        );
+        bases.extend(
+            store
+                .list_components()
+                .map_err(McpError::from)?
+                .into_iter()
+                .map(|c| ("component", c.base)),
+        );
+        bases.extend(
+            store
+                .list_links()
+                .map_err(McpError::from)?
+                .into_iter()
+                .map(|l| ("link", l.base)),
+        );
+        bases.extend(
+            store
+                .list_questions()
+                .map_err(McpError::from)?
+                .into_iter()
+                .map(|q| ("question", q.base)),
+        );
+        bases.extend(
+            store
+                .list_milestones()
+                .map_err(McpError::from)?
+                .into_iter()
+                .map(|m| ("milestone", m.base)),
+        );
+        Ok(bases)
+    }
+
+    /// Create an entity for each row, reporting each like a batch operation.
+    async fn import_rows(&self, rows: &[serde_json::Value]) -> BatchResult {
+        let mut results = Vec::new();
+        let mut succeeded = 0;
+        let mut failed = 0;
+
+        for (index, row) in rows.iter().enumerate() {
+            let created = match row_to_create_params(row) {
+                Ok(create_params) => self
+                    .entity_create(Parameters(create_params))
+                    .await
+                    .map_err(|e| ("CREATE_FAILED", e.message.to_string())),
+                Err(e) => Err(("INVALID_ROW", e.to_string())),
+            };
+
+            let result = match created {
+                Ok(tool_result) => {
+                    let id = tool_result.content.first().and_then(|c| {
+                        if let RawContent::Text(ref t) = c.raw {
+                            serde_json::from_str::<EntityResponse>(&t.text)
+                                .ok()
+                                .map(|r| r.id)
+                        } else {
+                            None
+                        }
+                    });
+                    succeeded += 1;
+                    BatchOperationResult {
+                        index,
+                        success: true,
+                        id,
+                        error: None,
                     }
                 }
-            }
-            "note" => {
-                let notes = store.list_notes().map_err(McpError::from)?;
-                for n in notes {
-                    if self.matches_id(&n.base, id, is_sequence) {
-                        store.delete_note(&n.base.id).map_err(McpError::from)?;
-                        store.save().map_err(McpError::from)?;
-                        cache
-                            .remove_note(&n.base.id.to_string())
-                            .map_err(McpError::from)?;
-                        return Ok(true);
+                Err((code, message)) => {
+                    failed += 1;
+                    BatchOperationResult {
+                        index,
+                        success: false,
+                        id: None,
+                        error: Some(BatchError {
+                            code: code.to_string(),
+                            message,
+                        }),
                     }
                 }
-            }
-            "prompt" => {
-                let prompts = store.list_prompts().map_err(McpError::from)?;
-                for p in prompts {
-                    if self.matches_id(&p.base, id, is_sequence) {
-                        store.delete_prompt(&p.base.id).map_err(McpError::from)?;
-                        store.save().map_err(McpError::from)?;
-                        cache
-                            .remove_prompt(&p.base.id.to_string())
-                            .map_err(McpError::from)?;
-                        return Ok(true);
+            };
+            results.push(result);
+        }
+
+        BatchResult {
+            results,
+            succeeded,
+            failed,
+            abort_reason: None,
+        }
+    }
+
+    /// Run batch operations in order, optionally stopping at the first
+    /// failure.
+    async fn run_batch(
+        &self,
+        operations: Vec<BatchOperation>,
+        stop_on_failure: bool,
+    ) -> BatchResult {
+        let mut results = Vec::new();
+        let mut succeeded = 0;
+        let mut failed = 0;
+
+        for (index, op) in operations.into_iter().enumerate() {
+            let result = match op {
+                BatchOperation::Create(create_params) => {
+                    match self.entity_create(Parameters(create_params)).await {
+                        Ok(tool_result) => {
+                            // Extract ID from the result
+                            let id = tool_result.content.first().and_then(|c| {
+                                if let RawContent::Text(ref t) = c.raw {
+                                    serde_json::from_str::<EntityResponse>(&t.text)
+                                        .ok()
+                                        .map(|r| r.id)
+                                } else {
+                                    None
+                                }
+                            });
+                            succeeded += 1;
+                            BatchOperationResult {
+                                index,
+                                success: true,
+                                id,
+                                error: None,
+                            }
+                        }
+                        Err(e) => {
+                            failed += 1;
+                            BatchOperationResult {
+                                index,
+                                success: false,
+                                id: None,
+                                error: Some(BatchError {
+                                    code: "CREATE_FAILED".to_string(),
+                                    message: e.message.to_string(),
+                                }),
+                            }
+                        }
                     }
                 }
-            }
-            "component" => {
-                let components = store.list_components().map_err(McpError::from)?;
-                for c in components {
-                    if self.matches_id(&c.base, id, is_sequence) {
-                        store.delete_component(&c.base.id).map_err(McpError::from)?;
-                        store.save().map_err(McpError::from)?;
-                        cache
-                            .remove_component(&c.base.id.to_string())
-                            .map_err(McpError::from)?;
-                        return Ok(true);
+                BatchOperation::Update(update_params) => {
+                    let id = update_params.id.clone();
+                    match self.entity_update(Parameters(update_params)).await {
+                        Ok(_) => {
+                            succeeded += 1;
+                            BatchOperationResult {
+                                index,
+                                success: true,
+                                id: Some(id),
+                                error: None,
+                            }
+                        }
+                        Err(e) => {
+                            failed += 1;
+                            BatchOperationResult {
+                                index,
+                                success: false,
+                                id: None,
+                                error: Some(BatchError {
+                                    code: "UPDATE_FAILED".to_string(),
+                                    message: e.message.to_string(),
+                                }),
+                            }
+                        }
                     }
                 }
-            }
-            "link" => {
-                let links = store.list_links().map_err(McpError::from)?;
-                for l in links {
-                    if self.matches_id(&l.base, id, is_sequence) {
-                        store.delete_link(&l.base.id).map_err(McpError::from)?;
-                        store.save().map_err(McpError::from)?;
-                        cache
-                            .remove_link(&l.base.id.to_string())
-                            .map_err(McpError::from)?;
-                        return Ok(true);
+                BatchOperation::Delete(delete_params) => {
+                    let id = delete_params.id.clone();
+                    match self.entity_delete(Parameters(delete_params)).await {
+                        Ok(_) => {
+                            succeeded += 1;
+                            BatchOperationResult {
+                                index,
+                                success: true,
+                                id: Some(id),
+                                error: None,
+                            }
+                        }
+                        Err(e) => {
+                            failed += 1;
+                            BatchOperationResult {
+                                index,
+                                success: false,
+                                id: None,
+                                error: Some(BatchError {
+                                    code: "DELETE_FAILED".to_string(),
+                                    message: e.message.to_string(),
+                                }),
+                            }
+                        }
                     }
                 }
+            };
+            let stop = stop_on_failure && !result.success;
+            results.push(result);
+            if stop {
+                break;
             }
-            _ => {}
         }
-        Ok(false)
+
+        BatchResult {
+            results,
+            succeeded,
+            failed,
+            abort_reason: None,
+        }
     }
 
-    fn resolve_entity_id(&self, store: &LoroStore, id: &str) -> Result<uuid::Uuid, McpError> {
-        let is_sequence = id.chars().all(|c| c.is_ascii_digit());
+    /// Run batch operations against a staging copy of the store and cache,
+    /// then merge the staged changes into the real store in one save, or
+    /// discard them all if any operation fails.
+    async fn run_atomic_batch(
+        &self,
+        operations: Vec<BatchOperation>,
+    ) -> Result<BatchResult, McpError> {
+        // The store stays locked until the staged changes are applied, so no
+        // other write can land between staging and applying
+        let store = self.store.lock().await;
+        let mut staged = {
+            let staged_store = store.staging();
+            let staged_cache = SqliteCache::open_in_memory().map_err(McpError::from)?;
+            staged_store
+                .sync_cache(&staged_cache)
+                .map_err(McpError::from)?;
+            MedullaServer::new(staged_store, staged_cache)
+        };
+        staged.config = self.config.clone();
 
-        for entity_type in VALID_ENTITY_TYPES {
-            if let Some(uuid) = self.find_uuid_by_id(store, entity_type, id, is_sequence)? {
-                return Ok(uuid);
+        let mut batch_result = staged.run_batch(operations, true).await;
+        if let Some(failure) = batch_result.results.iter().find(|r| !r.success) {
+            let message = failure
+                .error
+                .as_ref()
+                .map_or("unknown error", |e| e.message.as_str());
+            batch_result.abort_reason = Some(format!(
+                "Operation {} failed, no operations were applied: {}",
+                failure.index, message
+            ));
+            return Ok(batch_result);
+        }
+
+        let staged_store = staged.store.lock().await;
+        let cache = self.cache.lock().await;
+
+        // Entities the batch touched, from the operation IDs resolved both
+        // before the batch (deleted entities) and after it (created ones)
+        let mut touched: Vec<(uuid::Uuid, &'static str)> = Vec::new();
+        for id in batch_result.results.iter().filter_map(|r| r.id.as_ref()) {
+            for entity in [store.lookup_id(id, None), staged_store.lookup_id(id, None)]
+                .into_iter()
+                .flatten()
+            {
+                if !touched.contains(&entity) {
+                    touched.push(entity);
+                }
+            }
+        }
+        let before: Vec<Option<EntityResponse>> = touched
+            .iter()
+            .map(|(uuid, entity_type)| {
+                self.find_entity_by_id(&store, entity_type, &uuid.to_string())
+            })
+            .collect::<Result<_, _>>()?;
+
+        store.apply_staged(&staged_store).map_err(McpError::from)?;
+        store.save().map_err(McpError::from)?;
+        drop(staged_store);
+
+        let mut uris = Vec::new();
+        for ((uuid, entity_type), before) in touched.iter().zip(before) {
+            let after = Self::reindex_entity(&store, &cache, entity_type, uuid)?;
+            if let Some(resp) = after.or(before) {
+                uris.extend(resources::entity_change_uris(
+                    &resp.entity_type,
+                    &resp.id,
+                    resp.sequence_number,
+                ));
             }
         }
+        drop(cache);
+        drop(store);
+
+        uris.sort();
+        uris.dedup();
+        self.notify_resources_updated(uris).await;
+
+        Ok(batch_result)
+    }
+
+    /// Bring an entity's cache row and embedding in line with the store,
+    /// removing the row if the entity no longer exists.
+    fn reindex_entity(
+        store: &LoroStore,
+        cache: &SqliteCache,
+        entity_type: &str,
+        id: &uuid::Uuid,
+    ) -> Result<Option<EntityResponse>, McpError> {
+        let id_str = id.to_string();
+        let response = match entity_type {
+            "decision" => match store.get_decision(id).map_err(McpError::from)? {
+                Some(e) => {
+                    cache.index_decision(&e).map_err(McpError::from)?;
+                    Some(decision_to_response(&e))
+                }
+                None => {
+                    cache.remove_decision(&id_str).map_err(McpError::from)?;
+                    None
+                }
+            },
+            "task" => match store.get_task(id).map_err(McpError::from)? {
+                Some(e) => {
+                    cache.index_task(&e).map_err(McpError::from)?;
+                    Some(task_to_response(&e))
+                }
+                None => {
+                    cache.remove_task(&id_str).map_err(McpError::from)?;
+                    None
+                }
+            },
+            "note" => match store.get_note(id).map_err(McpError::from)? {
+                Some(e) => {
+                    cache.index_note(&e).map_err(McpError::from)?;
+                    Some(note_to_response(&e))
+                }
+                None => {
+                    cache.remove_note(&id_str).map_err(McpError::from)?;
+                    None
+                }
+            },
+            "prompt" => match store.get_prompt(id).map_err(McpError::from)? {
+                Some(e) => {
+                    cache.index_prompt(&e).map_err(McpError::from)?;
+                    Some(prompt_to_response(&e))
+                }
+                None => {
+                    cache.remove_prompt(&id_str).map_err(McpError::from)?;
+                    None
+                }
+            },
+            "component" => match store.get_component(id).map_err(McpError::from)? {
+                Some(e) => {
+                    cache.index_component(&e).map_err(McpError::from)?;
+                    Some(component_to_response(&e))
+                }
+                None => {
+                    cache.remove_component(&id_str).map_err(McpError::from)?;
+                    None
+                }
+            },
+            "link" => match store.get_link(id).map_err(McpError::from)? {
+                Some(e) => {
+                    cache.index_link(&e).map_err(McpError::from)?;
+                    Some(link_to_response(&e))
+                }
+                None => {
+                    cache.remove_link(&id_str).map_err(McpError::from)?;
+                    None
+                }
+            },
+            "question" => match store.get_question(id).map_err(McpError::from)? {
+                Some(e) => {
+                    cache.index_question(&e).map_err(McpError::from)?;
+                    Some(question_to_response(&e))
+                }
+                None => {
+                    cache.remove_question(&id_str).map_err(McpError::from)?;
+                    None
+                }
+            },
+            "milestone" => match store.get_milestone(id).map_err(McpError::from)? {
+                Some(e) => {
+                    cache.index_milestone(&e).map_err(McpError::from)?;
+                    Some(milestone_to_response(&e))
+                }
+                None => {
+                    cache.remove_milestone(&id_str).map_err(McpError::from)?;
+                    None
+                }
+            },
+            _ => None,
+        };
 
-        Err(McpError::EntityNotFound { id: id.to_string() })
+        if let Some(ref resp) = response {
+            Self::try_compute_embedding(
+                cache,
+                &resp.id,
+                &resp.entity_type,
+                &resp.title,
+                resp.content.as_deref(),
+                &resp.tags,
+            );
+        }
+        Ok(response)
     }
 
-    fn find_uuid_by_id(
+    fn find_entity_by_id(
         &self,
         store: &LoroStore,
         entity_type: &str,
         id: &str,
-        is_sequence: bool,
-    ) -> Result<Option<uuid::Uuid>, McpError> {
+    ) -> Result<Option<EntityResponse>, McpError> {
         match entity_type {
             "decision" => {
-                let decisions = store.list_decisions().map_err(McpError::from)?;
-                for d in decisions {
-                    if self.matches_id(&d.base, id, is_sequence) {
-                        return Ok(Some(d.base.id));
-                    }
+                if let Some(d) = lookup(store, "decision", id, LoroStore::get_decision)? {
+                    return Ok(Some(decision_to_response(&d)));
                 }
             }
             "task" => {
-                let tasks = store.list_tasks().map_err(McpError::from)?;
-                for t in tasks {
-                    if self.matches_id(&t.base, id, is_sequence) {
-                        return Ok(Some(t.base.id));
-                    }
+                if let Some(t) = lookup(store, "task", id, LoroStore::get_task)? {
+                    return Ok(Some(task_to_response(&t)));
                 }
             }
             "note" => {
-                let notes = store.list_notes().map_err(McpError::from)?;
-                for n in notes {
-                    if self.matches_id(&n.base, id, is_sequence) {
-                        return Ok(Some(n.base.id));
-                    }
+                if let Some(n) = lookup(store, "note", id, LoroStore::get_note)? {
+                    return Ok(Some(note_to_response(&n)));
                 }
             }
             "prompt" => {
-                let prompts = store.list_prompts().map_err(McpError::from)?;
-                for p in prompts {
-                    if self.matches_id(&p.base, id, is_sequence) {
-                        return Ok(Some(p.base.id));
-                    }
+                if let Some(p) = lookup(store, "prompt", id, LoroStore::get_prompt)? {
+                    return Ok(Some(prompt_to_response(&p)));
                 }
             }
             "component" => {
-                let components = store.list_components().map_err(McpError::from)?;
-                for c in components {
-                    if self.matches_id(&c.base, id, is_sequence) {
-                        return Ok(Some(c.base.id));
-                    }
+                if let Some(c) = lookup(store, "component", id, LoroStore::get_component)? {
+                    return Ok(Some(component_to_response(&c)));
                 }
             }
             "link" => {
-                let links = store.list_links().map_err(McpError::from)?;
-                for l in links {
-                    if self.matches_id(&l.base, id, is_sequence) {
-                        return Ok(Some(l.base.id));
-                    }
+                if let Some(l) = lookup(store, "link", id, LoroStore::get_link)? {
+                    return Ok(Some(link_to_response(&l)));
+                }
+            }
+            "question" => {
+                if let Some(q) = lookup(store, "question", id, LoroStore::get_question)? {
+                    return Ok(Some(question_to_response(&q)));
+                }
+            }
+            "milestone" => {
+                if let Some(m) = lookup(store, "milestone", id, LoroStore::get_milestone)? {
+                    return Ok(Some(milestone_to_response(&m)));
                 }
             }
             _ => {}
@@ -2790,34 +5300,837 @@ impl MedullaServer {
         Ok(None)
     }
 
-    /// Resolve an entity ID to UUID and entity type.
-    fn resolve_entity_id_with_type(
+    /// Evaluate validation rules against an entity as it would look after an update.
+    fn check_update_rules(
         &self,
-        store: &LoroStore,
-        id: &str,
-    ) -> Result<(uuid::Uuid, String), McpError> {
-        let is_sequence = id.chars().all(|c| c.is_ascii_digit());
+        current: &EntityResponse,
+        params: &EntityUpdateParams,
+    ) -> Result<(), McpError> {
+        let title = params.title.as_deref().unwrap_or(&current.title).trim();
+        let content = params.content.as_deref().or(current.content.as_deref());
+
+        let mut tags = current.tags.clone();
+        if let Some(ref add) = params.add_tags {
+            for tag in add {
+                if !tags.contains(tag) {
+                    tags.push(tag.clone());
+                }
+            }
+        }
+        if let Some(ref remove) = params.remove_tags {
+            tags.retain(|t| !remove.contains(t));
+        }
 
-        for entity_type in VALID_ENTITY_TYPES {
-            if let Some(uuid) = self.find_uuid_by_id(store, entity_type, id, is_sequence)? {
-                return Ok((uuid, entity_type.to_string()));
+        let mut properties = current.properties.clone();
+        if let (Some(merged), Some(serde_json::Value::Object(updates))) =
+            (properties.as_object_mut(), params.properties.as_ref())
+        {
+            for (key, value) in updates {
+                merged.insert(key.clone(), value.clone());
             }
         }
 
-        Err(McpError::EntityNotFound { id: id.to_string() })
+        self.check_rules(&RuleSubject {
+            entity_type: &current.entity_type,
+            title,
+            content,
+            tags: &tags,
+            properties: &properties,
+        })
     }
-}
 
-#[tool_handler]
-impl ServerHandler for MedullaServer {
-    fn get_info(&self) -> ServerInfo {
-        ServerInfo {
-            protocol_version: ProtocolVersion::LATEST,
-            capabilities: ServerCapabilities::builder()
-                .enable_tools()
-                .enable_resources()
-                .build(),
-            server_info: Implementation::from_build_env(),
+    /// Reject a status change the configured workflow does not allow.
+    fn check_status_transition(
+        &self,
+        current: &EntityResponse,
+        params: &EntityUpdateParams,
+    ) -> Result<(), McpError> {
+        let to = params
+            .properties
+            .as_ref()
+            .and_then(|p| p.get("status"))
+            .and_then(|v| v.as_str());
+        let from = current.properties.get("status").and_then(|v| v.as_str());
+        let (Some(from), Some(to)) = (from, to) else {
+            return Ok(());
+        };
+        self.config
+            .check_transition(&current.entity_type, from, to)
+            .map_err(|message| McpError::ValidationFailed {
+                field: "status".to_string(),
+                message: format!("{}; pass force to override", message),
+            })
+    }
+
+    /// Collect an entity's outgoing and incoming relations, annotated with the
+    /// title of the entity on the other end of each relation.
+    fn inline_relations(
+        &self,
+        store: &LoroStore,
+        uuid_str: &str,
+    ) -> Result<(Vec<InlineRelationResponse>, Vec<InlineRelationResponse>), McpError> {
+        let outgoing = store
+            .get_relations_from(uuid_str)
+            .map_err(McpError::from)?
+            .iter()
+            .map(|r| {
+                let related_title = self
+                    .find_entity_by_id(store, &r.target_type, &r.target_id.to_string())?
+                    .map(|e| e.title);
+                Ok(InlineRelationResponse {
+                    relation: relation_to_response(r),
+                    related_title,
+                })
+            })
+            .collect::<Result<Vec<_>, McpError>>()?;
+
+        let incoming = store
+            .get_relations_to(uuid_str)
+            .map_err(McpError::from)?
+            .iter()
+            .map(|r| {
+                let related_title = self
+                    .find_entity_by_id(store, &r.source_type, &r.source_id.to_string())?
+                    .map(|e| e.title);
+                Ok(InlineRelationResponse {
+                    relation: relation_to_response(r),
+                    related_title,
+                })
+            })
+            .collect::<Result<Vec<_>, McpError>>()?;
+
+        Ok((outgoing, incoming))
+    }
+
+    /// Summaries of the entities within `depth` relation hops of an entity,
+    /// nearest first, up to the configured result limit.
+    fn related_summaries(
+        &self,
+        store: &LoroStore,
+        uuid_str: &str,
+        depth: usize,
+    ) -> Result<Vec<RelatedEntitySummary>, McpError> {
+        let Ok(root) = uuid::Uuid::parse_str(uuid_str) else {
+            return Ok(Vec::new());
+        };
+        crate::graph::related_entities(store, root, depth)
+            .map_err(McpError::from)?
+            .into_iter()
+            .take(self.config.limits.max_limit)
+            .map(|(node, distance)| {
+                let status = self
+                    .find_entity_by_id(store, node.entity_type, &node.id.to_string())?
+                    .and_then(|e| e.properties["status"].as_str().map(str::to_string));
+                Ok(RelatedEntitySummary {
+                    id: node.id.to_string(),
+                    sequence_number: node.sequence_number,
+                    entity_type: node.entity_type.to_string(),
+                    title: node.title,
+                    status,
+                    distance: distance as u32,
+                })
+            })
+            .collect()
+    }
+
+    fn try_update_entity(
+        &self,
+        store: &LoroStore,
+        cache: &SqliteCache,
+        entity_type: &str,
+        params: &EntityUpdateParams,
+    ) -> Result<Option<EntityResponse>, McpError> {
+        match entity_type {
+            "decision" => {
+                if let Some(d) = lookup(store, "decision", &params.id, LoroStore::get_decision)? {
+                    let mut update = DecisionUpdate::default();
+                    update.title = params.title.clone();
+                    update.content = params.content.clone();
+                    update.add_tags = params.add_tags.clone().unwrap_or_default();
+                    update.remove_tags = params.remove_tags.clone().unwrap_or_default();
+
+                    if let Some(ref props) = params.properties {
+                        if let Some(status) = props.get("status").and_then(|v| v.as_str()) {
+                            update.status = Some(parse_decision_status(status)?);
+                        }
+                        if let Some(context) = props.get("context").and_then(|v| v.as_str()) {
+                            update.context = Some(context.to_string());
+                        }
+                        if let Some(priority) = props.get("priority") {
+                            update.priority = Some(parse_optional_priority(priority)?);
+                        }
+                    }
+
+                    store
+                        .update_decision(&d.base.id, update)
+                        .map_err(McpError::from)?;
+                    store.save().map_err(McpError::from)?;
+
+                    // Refetch and reindex
+                    let updated = store
+                        .get_decision(&d.base.id)
+                        .map_err(McpError::from)?
+                        .ok_or_else(|| McpError::EntityNotFound {
+                            id: params.id.clone(),
+                        })?;
+                    cache.index_decision(&updated).map_err(McpError::from)?;
+
+                    // Recompute embedding if embeddable content changed
+                    if params.title.is_some()
+                        || params.content.is_some()
+                        || params.add_tags.is_some()
+                        || params.remove_tags.is_some()
+                    {
+                        Self::try_compute_embedding(
+                            cache,
+                            &updated.base.id.to_string(),
+                            "decision",
+                            &updated.base.title,
+                            updated.base.content.as_deref(),
+                            &updated.base.tags,
+                        );
+                    }
+
+                    return Ok(Some(decision_to_response(&updated)));
+                }
+            }
+            "task" => {
+                if let Some(t) = lookup(store, "task", &params.id, LoroStore::get_task)? {
+                    let mut update = TaskUpdate::default();
+                    update.title = params.title.clone();
+                    update.content = params.content.clone();
+                    update.add_tags = params.add_tags.clone().unwrap_or_default();
+                    update.remove_tags = params.remove_tags.clone().unwrap_or_default();
+
+                    if let Some(ref props) = params.properties {
+                        if let Some(status) = props.get("status").and_then(|v| v.as_str()) {
+                            update.status = Some(parse_task_status(status)?);
+                        }
+                        if let Some(priority) = props.get("priority").and_then(|v| v.as_str()) {
+                            update.priority = Some(parse_task_priority(priority)?);
+                        }
+                        if let Some(due_date) = props.get("due_date").and_then(|v| v.as_str()) {
+                            update.due_date = Some(Some(parse_date("due_date", due_date)?));
+                        }
+                        if let Some(assignee) = props.get("assignee").and_then(|v| v.as_str()) {
+                            update.assignee = Some(Some(assignee.to_string()));
+                        }
+                        if let Some(estimate) = props.get("estimate_minutes") {
+                            update.estimate_minutes = Some(parse_estimate(estimate)?);
+                        }
+                        if let Some(recurrence) = props.get("recurrence") {
+                            update.recurrence = Some(parse_recurrence(recurrence)?);
+                        }
+                    }
+
+                    store
+                        .update_task(&t.base.id, update)
+                        .map_err(McpError::from)?;
+                    store.save().map_err(McpError::from)?;
+
+                    let updated = store
+                        .get_task(&t.base.id)
+                        .map_err(McpError::from)?
+                        .ok_or_else(|| McpError::EntityNotFound {
+                            id: params.id.clone(),
+                        })?;
+                    cache.index_task(&updated).map_err(McpError::from)?;
+
+                    // Recompute embedding if embeddable content changed
+                    if params.title.is_some()
+                        || params.content.is_some()
+                        || params.add_tags.is_some()
+                        || params.remove_tags.is_some()
+                    {
+                        Self::try_compute_embedding(
+                            cache,
+                            &updated.base.id.to_string(),
+                            "task",
+                            &updated.base.title,
+                            updated.base.content.as_deref(),
+                            &updated.base.tags,
+                        );
+                    }
+
+                    return Ok(Some(task_to_response(&updated)));
+                }
+            }
+            "note" => {
+                if let Some(n) = lookup(store, "note", &params.id, LoroStore::get_note)? {
+                    let mut update = NoteUpdate::default();
+                    update.title = params.title.clone();
+                    update.content = params.content.clone();
+                    update.add_tags = params.add_tags.clone().unwrap_or_default();
+                    update.remove_tags = params.remove_tags.clone().unwrap_or_default();
+
+                    if let Some(ref props) = params.properties {
+                        if let Some(note_type) = props.get("note_type").and_then(|v| v.as_str()) {
+                            update.note_type = Some(Some(note_type.to_string()));
+                        }
+                    }
+
+                    store
+                        .update_note(&n.base.id, update)
+                        .map_err(McpError::from)?;
+                    store.save().map_err(McpError::from)?;
+
+                    let updated = store
+                        .get_note(&n.base.id)
+                        .map_err(McpError::from)?
+                        .ok_or_else(|| McpError::EntityNotFound {
+                            id: params.id.clone(),
+                        })?;
+                    cache.index_note(&updated).map_err(McpError::from)?;
+
+                    // Recompute embedding if embeddable content changed
+                    if params.title.is_some()
+                        || params.content.is_some()
+                        || params.add_tags.is_some()
+                        || params.remove_tags.is_some()
+                    {
+                        Self::try_compute_embedding(
+                            cache,
+                            &updated.base.id.to_string(),
+                            "note",
+                            &updated.base.title,
+                            updated.base.content.as_deref(),
+                            &updated.base.tags,
+                        );
+                    }
+
+                    return Ok(Some(note_to_response(&updated)));
+                }
+            }
+            "prompt" => {
+                if let Some(p) = lookup(store, "prompt", &params.id, LoroStore::get_prompt)? {
+                    let mut update = PromptUpdate::default();
+                    update.title = params.title.clone();
+                    update.content = params.content.clone();
+                    update.add_tags = params.add_tags.clone().unwrap_or_default();
+                    update.remove_tags = params.remove_tags.clone().unwrap_or_default();
+
+                    if let Some(ref props) = params.properties {
+                        if let Some(template) = props.get("template").and_then(|v| v.as_str()) {
+                            update.template = Some(Some(template.to_string()));
+                        }
+                        if let Some(output_schema) =
+                            props.get("output_schema").and_then(|v| v.as_str())
+                        {
+                            update.output_schema = Some(Some(output_schema.to_string()));
+                        }
+                        if let Some(add_vars) =
+                            props.get("add_variables").and_then(|v| v.as_array())
+                        {
+                            update.add_variables = add_vars
+                                .iter()
+                                .filter_map(|v| v.as_str().map(String::from))
+                                .collect();
+                        }
+                        if let Some(remove_vars) =
+                            props.get("remove_variables").and_then(|v| v.as_array())
+                        {
+                            update.remove_variables = remove_vars
+                                .iter()
+                                .filter_map(|v| v.as_str().map(String::from))
+                                .collect();
+                        }
+                    }
+
+                    store
+                        .update_prompt(&p.base.id, update)
+                        .map_err(McpError::from)?;
+                    store.save().map_err(McpError::from)?;
+
+                    let updated = store
+                        .get_prompt(&p.base.id)
+                        .map_err(McpError::from)?
+                        .ok_or_else(|| McpError::EntityNotFound {
+                            id: params.id.clone(),
+                        })?;
+                    cache.index_prompt(&updated).map_err(McpError::from)?;
+
+                    // Recompute embedding if embeddable content changed
+                    if params.title.is_some()
+                        || params.content.is_some()
+                        || params.add_tags.is_some()
+                        || params.remove_tags.is_some()
+                    {
+                        Self::try_compute_embedding(
+                            cache,
+                            &updated.base.id.to_string(),
+                            "prompt",
+                            &updated.base.title,
+                            updated.base.content.as_deref(),
+                            &updated.base.tags,
+                        );
+                    }
+
+                    return Ok(Some(prompt_to_response(&updated)));
+                }
+            }
+            "component" => {
+                if let Some(c) = lookup(store, "component", &params.id, LoroStore::get_component)? {
+                    let mut update = ComponentUpdate::default();
+                    update.title = params.title.clone();
+                    update.content = params.content.clone();
+                    update.add_tags = params.add_tags.clone().unwrap_or_default();
+                    update.remove_tags = params.remove_tags.clone().unwrap_or_default();
+
+                    if let Some(ref props) = params.properties {
+                        if let Some(status) = props.get("status").and_then(|v| v.as_str()) {
+                            update.status = Some(parse_component_status(status)?);
+                        }
+                        if let Some(component_type) =
+                            props.get("component_type").and_then(|v| v.as_str())
+                        {
+                            update.component_type = Some(Some(component_type.to_string()));
+                        }
+                        if let Some(owner) = props.get("owner").and_then(|v| v.as_str()) {
+                            update.owner = Some(Some(owner.to_string()));
+                        }
+                        if let Some(priority) = props.get("priority") {
+                            update.priority = Some(parse_optional_priority(priority)?);
+                        }
+                    }
+
+                    store
+                        .update_component(&c.base.id, update)
+                        .map_err(McpError::from)?;
+                    store.save().map_err(McpError::from)?;
+
+                    let updated = store
+                        .get_component(&c.base.id)
+                        .map_err(McpError::from)?
+                        .ok_or_else(|| McpError::EntityNotFound {
+                            id: params.id.clone(),
+                        })?;
+                    cache.index_component(&updated).map_err(McpError::from)?;
+
+                    // Recompute embedding if embeddable content changed
+                    if params.title.is_some()
+                        || params.content.is_some()
+                        || params.add_tags.is_some()
+                        || params.remove_tags.is_some()
+                    {
+                        Self::try_compute_embedding(
+                            cache,
+                            &updated.base.id.to_string(),
+                            "component",
+                            &updated.base.title,
+                            updated.base.content.as_deref(),
+                            &updated.base.tags,
+                        );
+                    }
+
+                    return Ok(Some(component_to_response(&updated)));
+                }
+            }
+            "link" => {
+                if let Some(l) = lookup(store, "link", &params.id, LoroStore::get_link)? {
+                    let mut update = LinkUpdate::default();
+                    update.title = params.title.clone();
+                    update.content = params.content.clone();
+                    update.add_tags = params.add_tags.clone().unwrap_or_default();
+                    update.remove_tags = params.remove_tags.clone().unwrap_or_default();
+
+                    if let Some(ref props) = params.properties {
+                        if let Some(url) = props.get("url").and_then(|v| v.as_str()) {
+                            validate_url(url)?;
+                            update.url = Some(url.to_string());
+                        }
+                        if let Some(link_type) = props.get("link_type").and_then(|v| v.as_str()) {
+                            update.link_type = Some(Some(link_type.to_string()));
+                        }
+                    }
+
+                    store
+                        .update_link(&l.base.id, update)
+                        .map_err(McpError::from)?;
+                    store.save().map_err(McpError::from)?;
+
+                    let updated = store
+                        .get_link(&l.base.id)
+                        .map_err(McpError::from)?
+                        .ok_or_else(|| McpError::EntityNotFound {
+                            id: params.id.clone(),
+                        })?;
+                    cache.index_link(&updated).map_err(McpError::from)?;
+
+                    // Recompute embedding if embeddable content changed
+                    if params.title.is_some()
+                        || params.content.is_some()
+                        || params.add_tags.is_some()
+                        || params.remove_tags.is_some()
+                    {
+                        Self::try_compute_embedding(
+                            cache,
+                            &updated.base.id.to_string(),
+                            "link",
+                            &updated.base.title,
+                            updated.base.content.as_deref(),
+                            &updated.base.tags,
+                        );
+                    }
+
+                    return Ok(Some(link_to_response(&updated)));
+                }
+            }
+            "question" => {
+                if let Some(q) = lookup(store, "question", &params.id, LoroStore::get_question)? {
+                    let mut update = QuestionUpdate::default();
+                    update.title = params.title.clone();
+                    update.content = params.content.clone();
+                    update.add_tags = params.add_tags.clone().unwrap_or_default();
+                    update.remove_tags = params.remove_tags.clone().unwrap_or_default();
+
+                    if let Some(ref props) = params.properties {
+                        match props.get("answered_by") {
+                            Some(serde_json::Value::String(answered_by)) => {
+                                update.answered_by =
+                                    Some(Some(resolve_answered_by(store, answered_by)?));
+                                update.status = Some(QuestionStatus::Answered);
+                            }
+                            Some(serde_json::Value::Null) => update.answered_by = Some(None),
+                            _ => {}
+                        }
+                        if let Some(status) = props.get("status").and_then(|v| v.as_str()) {
+                            update.status = Some(parse_question_status(status)?);
+                        }
+                    }
+
+                    store
+                        .update_question(&q.base.id, update)
+                        .map_err(McpError::from)?;
+                    store.save().map_err(McpError::from)?;
+
+                    let updated = store
+                        .get_question(&q.base.id)
+                        .map_err(McpError::from)?
+                        .ok_or_else(|| McpError::EntityNotFound {
+                            id: params.id.clone(),
+                        })?;
+                    cache.index_question(&updated).map_err(McpError::from)?;
+
+                    // Recompute embedding if embeddable content changed
+                    if params.title.is_some()
+                        || params.content.is_some()
+                        || params.add_tags.is_some()
+                        || params.remove_tags.is_some()
+                    {
+                        Self::try_compute_embedding(
+                            cache,
+                            &updated.base.id.to_string(),
+                            "question",
+                            &updated.base.title,
+                            updated.base.content.as_deref(),
+                            &updated.base.tags,
+                        );
+                    }
+
+                    return Ok(Some(question_to_response(&updated)));
+                }
+            }
+            "milestone" => {
+                if let Some(m) = lookup(store, "milestone", &params.id, LoroStore::get_milestone)? {
+                    let mut update = MilestoneUpdate::default();
+                    update.title = params.title.clone();
+                    update.content = params.content.clone();
+                    update.add_tags = params.add_tags.clone().unwrap_or_default();
+                    update.remove_tags = params.remove_tags.clone().unwrap_or_default();
+
+                    if let Some(ref props) = params.properties {
+                        if let Some(status) = props.get("status").and_then(|v| v.as_str()) {
+                            update.status = Some(parse_milestone_status(status)?);
+                        }
+                        match props.get("target_date") {
+                            Some(serde_json::Value::String(target_date)) => {
+                                update.target_date =
+                                    Some(Some(parse_date("target_date", target_date)?));
+                            }
+                            Some(serde_json::Value::Null) => update.target_date = Some(None),
+                            _ => {}
+                        }
+                    }
+
+                    store
+                        .update_milestone(&m.base.id, update)
+                        .map_err(McpError::from)?;
+                    store.save().map_err(McpError::from)?;
+
+                    let updated = store
+                        .get_milestone(&m.base.id)
+                        .map_err(McpError::from)?
+                        .ok_or_else(|| McpError::EntityNotFound {
+                            id: params.id.clone(),
+                        })?;
+                    cache.index_milestone(&updated).map_err(McpError::from)?;
+
+                    // Recompute embedding if embeddable content changed
+                    if params.title.is_some()
+                        || params.content.is_some()
+                        || params.add_tags.is_some()
+                        || params.remove_tags.is_some()
+                    {
+                        Self::try_compute_embedding(
+                            cache,
+                            &updated.base.id.to_string(),
+                            "milestone",
+                            &updated.base.title,
+                            updated.base.content.as_deref(),
+                            &updated.base.tags,
+                        );
+                    }
+
+                    return Ok(Some(milestone_to_response(&updated)));
+                }
+            }
+            _ => {}
+        }
+        Ok(None)
+    }
+
+    fn try_delete_entity(
+        &self,
+        store: &LoroStore,
+        cache: &SqliteCache,
+        entity_type: &str,
+        id: &str,
+    ) -> Result<bool, McpError> {
+        match entity_type {
+            "decision" => {
+                if let Some(d) = lookup(store, "decision", id, LoroStore::get_decision)? {
+                    self.trash_entity(store, cache, &d.base.id)?;
+                    store.save().map_err(McpError::from)?;
+                    cache
+                        .remove_decision(&d.base.id.to_string())
+                        .map_err(McpError::from)?;
+                    return Ok(true);
+                }
+            }
+            "task" => {
+                if let Some(t) = lookup(store, "task", id, LoroStore::get_task)? {
+                    self.trash_entity(store, cache, &t.base.id)?;
+                    store.save().map_err(McpError::from)?;
+                    cache
+                        .remove_task(&t.base.id.to_string())
+                        .map_err(McpError::from)?;
+                    return Ok(true);
+                }
+            }
+            "note" => {
+                if let Some(n) = lookup(store, "note", id, LoroStore::get_note)? {
+                    self.trash_entity(store, cache, &n.base.id)?;
+                    store.save().map_err(McpError::from)?;
+                    cache
+                        .remove_note(&n.base.id.to_string())
+                        .map_err(McpError::from)?;
+                    return Ok(true);
+                }
+            }
+            "prompt" => {
+                if let Some(p) = lookup(store, "prompt", id, LoroStore::get_prompt)? {
+                    self.trash_entity(store, cache, &p.base.id)?;
+                    store.save().map_err(McpError::from)?;
+                    cache
+                        .remove_prompt(&p.base.id.to_string())
+                        .map_err(McpError::from)?;
+                    return Ok(true);
+                }
+            }
+            "component" => {
+                if let Some(c) = lookup(store, "component", id, LoroStore::get_component)? {
+                    self.trash_entity(store, cache, &c.base.id)?;
+                    store.save().map_err(McpError::from)?;
+                    cache
+                        .remove_component(&c.base.id.to_string())
+                        .map_err(McpError::from)?;
+                    return Ok(true);
+                }
+            }
+            "link" => {
+                if let Some(l) = lookup(store, "link", id, LoroStore::get_link)? {
+                    self.trash_entity(store, cache, &l.base.id)?;
+                    store.save().map_err(McpError::from)?;
+                    cache
+                        .remove_link(&l.base.id.to_string())
+                        .map_err(McpError::from)?;
+                    return Ok(true);
+                }
+            }
+            "question" => {
+                if let Some(q) = lookup(store, "question", id, LoroStore::get_question)? {
+                    self.trash_entity(store, cache, &q.base.id)?;
+                    store.save().map_err(McpError::from)?;
+                    cache
+                        .remove_question(&q.base.id.to_string())
+                        .map_err(McpError::from)?;
+                    return Ok(true);
+                }
+            }
+            "milestone" => {
+                if let Some(m) = lookup(store, "milestone", id, LoroStore::get_milestone)? {
+                    self.trash_entity(store, cache, &m.base.id)?;
+                    store.save().map_err(McpError::from)?;
+                    cache
+                        .remove_milestone(&m.base.id.to_string())
+                        .map_err(McpError::from)?;
+                    return Ok(true);
+                }
+            }
+            _ => {}
+        }
+        Ok(false)
+    }
+
+    /// Move an entity to the trash and drop its relations from the cache,
+    /// since the trash holds them until the entity is restored.
+    fn trash_entity(
+        &self,
+        store: &LoroStore,
+        cache: &SqliteCache,
+        id: &uuid::Uuid,
+    ) -> Result<(), McpError> {
+        let relation_keys: Vec<String> = store
+            .list_relations()
+            .map_err(McpError::from)?
+            .into_iter()
+            .filter(|r| r.source_id == *id || r.target_id == *id)
+            .map(|r| r.composite_key())
+            .collect();
+
+        store.trash_entity(id, None).map_err(McpError::from)?;
+        for key in relation_keys {
+            cache.remove_relation(&key).map_err(McpError::from)?;
+        }
+        Ok(())
+    }
+
+    /// The project config with relation types registered in the store
+    /// merged in. Read on each use, so types registered after the server
+    /// started are accepted.
+    fn relation_config(
+        &self,
+        store: &LoroStore,
+    ) -> Result<std::borrow::Cow<'_, ProjectConfig>, McpError> {
+        let registered = store.list_relation_types().map_err(McpError::from)?;
+        if registered.iter().all(|def| {
+            self.config
+                .relation_types
+                .iter()
+                .any(|d| d.name == def.name)
+        }) {
+            return Ok(std::borrow::Cow::Borrowed(&*self.config));
+        }
+        let mut config = (*self.config).clone();
+        config.merge_relation_types(registered);
+        Ok(std::borrow::Cow::Owned(config))
+    }
+
+    fn resolve_entity_id(&self, store: &LoroStore, id: &str) -> Result<uuid::Uuid, McpError> {
+        self.resolve_entity_id_with_type(store, id)
+            .map(|(uuid, _)| uuid)
+    }
+
+    /// Resolve an entity ID to UUID and entity type.
+    fn resolve_entity_id_with_type(
+        &self,
+        store: &LoroStore,
+        id: &str,
+    ) -> Result<(uuid::Uuid, String), McpError> {
+        store
+            .lookup_id(id, None)
+            .map(|(uuid, entity_type)| (uuid, entity_type.to_string()))
+            .ok_or_else(|| McpError::EntityNotFound { id: id.to_string() })
+    }
+
+    /// Resolve a sequence number, UUID prefix or exact entity title.
+    /// `titles` caches the title lookup table across calls.
+    fn resolve_entity_ref(
+        &self,
+        store: &LoroStore,
+        reference: &str,
+        titles: &mut Option<HashMap<String, Vec<(uuid::Uuid, &'static str)>>>,
+    ) -> Result<(uuid::Uuid, String), McpError> {
+        let reference = reference.trim();
+        if let Some((uuid, entity_type)) = store.lookup_id(reference, None) {
+            return Ok((uuid, entity_type.to_string()));
+        }
+
+        if titles.is_none() {
+            let mut map: HashMap<String, Vec<(uuid::Uuid, &'static str)>> = HashMap::new();
+            for (entity_type, value) in store.entity_values().map_err(McpError::from)? {
+                let id = value.get("id").and_then(|v| v.as_str());
+                let title = value.get("title").and_then(|v| v.as_str());
+                if let (Some(Ok(id)), Some(title)) = (id.map(uuid::Uuid::parse_str), title) {
+                    map.entry(title.to_string())
+                        .or_default()
+                        .push((id, entity_type));
+                }
+            }
+            *titles = Some(map);
+        }
+        match titles
+            .as_ref()
+            .and_then(|t| t.get(reference))
+            .map(Vec::as_slice)
+        {
+            Some([(uuid, entity_type)]) => Ok((*uuid, entity_type.to_string())),
+            Some(matches) if matches.len() > 1 => Err(McpError::ValidationFailed {
+                field: "id".to_string(),
+                message: format!(
+                    "Title '{}' matches {} entities; use a sequence number or ID",
+                    reference,
+                    matches.len()
+                ),
+            }),
+            _ => Err(McpError::EntityNotFound {
+                id: reference.to_string(),
+            }),
+        }
+    }
+}
+
+impl MedullaServer {
+    /// What to ask the user before running a tool call, when the config
+    /// requires confirmation and the call is destructive
+    async fn confirmation_message(&self, request: &CallToolRequestParams) -> Option<String> {
+        if !self.config.require_confirmation {
+            return None;
+        }
+        let store = self.store.lock().await;
+        confirm::confirmation_message(&request.name, request.arguments.as_ref(), &store)
+    }
+}
+
+/// The client a tool call is rate limited as: its MCP session over HTTP,
+/// otherwise the name it gave on initialization.
+fn client_id(context: &RequestContext<RoleServer>) -> String {
+    if let Some(session) = context
+        .extensions
+        .get::<axum::http::request::Parts>()
+        .and_then(|parts| parts.headers.get("mcp-session-id"))
+        .and_then(|value| value.to_str().ok())
+    {
+        return format!("session:{}", session);
+    }
+    context
+        .peer
+        .peer_info()
+        .map(|info| format!("client:{}", info.client_info.name))
+        .unwrap_or_else(|| "stdio".to_string())
+}
+
+// Tool calls are routed by hand rather than with #[tool_handler] so every
+// response passes through `redact_result`.
+impl ServerHandler for MedullaServer {
+    fn get_info(&self) -> ServerInfo {
+        ServerInfo {
+            protocol_version: ProtocolVersion::LATEST,
+            capabilities: ServerCapabilities::builder()
+                .enable_tools()
+                .enable_resources()
+                .build(),
+            server_info: Implementation::from_build_env(),
             instructions: Some(
                 "Medulla is a knowledge engine for software projects. \
                  Use entity tools to manage decisions, tasks, notes, prompts, \
@@ -2828,6 +6141,88 @@ impl ServerHandler for MedullaServer {
         }
     }
 
+    async fn call_tool(
+        &self,
+        request: CallToolRequestParams,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, rmcp::ErrorData> {
+        let mut request = request;
+        let server = self.route_project(request.arguments.as_mut())?;
+        // Only known tools are recorded, so clients can't add metric labels
+        let tool = server
+            .tool_router
+            .has_route(&request.name)
+            .then(|| request.name.to_string());
+        let started = std::time::Instant::now();
+        // Rate and payload limits come first, so rejected calls never prompt
+        // the user; then ask before taking a write permit, so waiting on the
+        // user holds up no other writes
+        let admitted = match self
+            .guard
+            .check_request(&client_id(&context), request.arguments.as_ref())
+        {
+            Ok(()) => match server.confirmation_message(&request).await {
+                Some(message) => confirm::confirm(&context.peer, &request.name, message).await,
+                None => Ok(()),
+            }
+            .and_then(|()| self.guard.acquire_for(&request.name)),
+            Err(e) => Err(e),
+        };
+        let result = match admitted {
+            Ok(_permit) => {
+                let tcc =
+                    rmcp::handler::server::tool::ToolCallContext::new(server, request, context);
+                server
+                    .tool_router
+                    .call(tcc)
+                    .await
+                    .map(|result| server.redact_result(result))
+            }
+            Err(e) => Err(e.into()),
+        };
+        if let Some(tool) = tool {
+            let failed = result.as_ref().map_or(true, |r| r.is_error == Some(true));
+            self.metrics.record_tool(&tool, started.elapsed(), failed);
+        }
+        result
+    }
+
+    async fn list_tools(
+        &self,
+        _request: Option<PaginatedRequestParams>,
+        _context: RequestContext<RoleServer>,
+    ) -> Result<ListToolsResult, rmcp::ErrorData> {
+        let mut tools = self.tool_router.list_all();
+
+        // Advertise the `project` argument understood by `call_tool`
+        if !self.projects.is_empty() {
+            let names: Vec<&String> = self.projects.keys().collect();
+            for tool in &mut tools {
+                let mut schema = (*tool.input_schema).clone();
+                let properties = schema
+                    .entry("properties")
+                    .or_insert_with(|| serde_json::json!({}));
+                if let Some(properties) = properties.as_object_mut() {
+                    properties.insert(
+                        "project".to_string(),
+                        serde_json::json!({
+                            "type": "string",
+                            "enum": names,
+                            "description": "Workspace project to run against (default: the project the server was started in)",
+                        }),
+                    );
+                }
+                tool.input_schema = Arc::new(schema);
+            }
+        }
+
+        Ok(ListToolsResult {
+            tools,
+            next_cursor: None,
+            meta: None,
+        })
+    }
+
     fn list_resources(
         &self,
         _request: Option<PaginatedRequestParams>,
@@ -2882,7 +6277,7 @@ impl ServerHandler for MedullaServer {
     fn subscribe(
         &self,
         request: SubscribeRequestParams,
-        _context: RequestContext<RoleServer>,
+        context: RequestContext<RoleServer>,
     ) -> impl std::future::Future<Output = Result<(), rmcp::ErrorData>> + Send + '_ {
         async move {
             // Validate the URI starts with medulla://
@@ -2893,8 +6288,22 @@ impl ServerHandler for MedullaServer {
                 ));
             }
 
+            let Some(key) = peer_key(&context.peer) else {
+                return Err(rmcp::ErrorData::invalid_request(
+                    "Subscribe before the session is initialized".to_string(),
+                    None,
+                ));
+            };
             let mut subscriptions = self.subscriptions.lock().await;
-            subscriptions.subscribe(&request.uri);
+            // A closed session's key may be reused by a new one; its
+            // subscriptions must not carry over
+            if subscriptions
+                .peer(key)
+                .is_some_and(|peer| peer.is_transport_closed())
+            {
+                subscriptions.remove_peer(key);
+            }
+            subscriptions.subscribe(&request.uri, key, context.peer);
             Ok(())
         }
     }
@@ -2902,14 +6311,16 @@ impl ServerHandler for MedullaServer {
     fn unsubscribe(
         &self,
         request: UnsubscribeRequestParams,
-        _context: RequestContext<RoleServer>,
+        context: RequestContext<RoleServer>,
     ) -> impl std::future::Future<Output = Result<(), rmcp::ErrorData>> + Send + '_ {
         async move {
-            let mut subscriptions = self.subscriptions.lock().await;
-            // Note: The unsubscribe method takes an ID, but we're receiving a URI.
-            // For now, we'll remove all subscriptions for this URI.
-            // In a full implementation, we'd track subscription IDs per client.
-            subscriptions.by_resource.remove(&request.uri);
+            // Only this session's subscription goes; others to the same URI stay
+            if let Some(key) = peer_key(&context.peer) {
+                self.subscriptions
+                    .lock()
+                    .await
+                    .unsubscribe_uri(&request.uri, key);
+            }
             Ok(())
         }
     }
@@ -2933,12 +6344,13 @@ mod tests {
 
     #[test]
     fn test_subscription_state() {
-        let mut state = SubscriptionState::new();
+        let mut state: SubscriptionState<&str> = SubscriptionState::new();
 
-        // Subscribe
-        let id1 = state.subscribe("medulla://entities/task");
-        let _id2 = state.subscribe("medulla://entities/task");
-        let _id3 = state.subscribe("medulla://entities/decision");
+        // Subscribe two sessions
+        let id1 = state.subscribe("medulla://entities/task", 1, "first");
+        let _id2 = state.subscribe("medulla://entities/task", 2, "second");
+        let _id3 = state.subscribe("medulla://entities/decision", 2, "second");
+        assert_eq!(state.subscribe("medulla://entities/task", 1, "first"), id1);
 
         assert_eq!(state.get_subscribers("medulla://entities/task").len(), 2);
         assert_eq!(
@@ -2947,17 +6359,53 @@ mod tests {
         );
         assert_eq!(state.get_subscribers("medulla://entities/note").len(), 0);
 
+        // Each session is notified of the URIs it subscribed to
+        let changed = vec![
+            "medulla://entities/task".to_string(),
+            "medulla://entities/decision".to_string(),
+            "medulla://entities/note".to_string(),
+        ];
+        assert_eq!(
+            state.subscribers(&changed),
+            vec![
+                (1, "first", vec!["medulla://entities/task".to_string()]),
+                (
+                    2,
+                    "second",
+                    vec![
+                        "medulla://entities/task".to_string(),
+                        "medulla://entities/decision".to_string()
+                    ]
+                ),
+            ]
+        );
+
         // Unsubscribe
         assert!(state.unsubscribe(&id1));
         assert_eq!(state.get_subscribers("medulla://entities/task").len(), 1);
+        assert_eq!(state.subscribers(&changed).len(), 1);
+
+        // Only URIs with subscribers are selected for notification
+        assert_eq!(
+            state.subscribed(&changed),
+            vec!["medulla://entities/task", "medulla://entities/decision"]
+        );
+
+        // Unsubscribing a URI only affects that session
+        state.subscribe("medulla://entities/task", 1, "first");
+        assert!(state.unsubscribe_uri("medulla://entities/task", 2));
+        assert_eq!(state.get_subscribers("medulla://entities/task").len(), 1);
+
+        // A disconnected session loses all its subscriptions
+        state.remove_peer(2);
+        assert!(state
+            .get_subscribers("medulla://entities/decision")
+            .is_empty());
 
         // Clear
         state.clear();
         assert_eq!(state.get_subscribers("medulla://entities/task").len(), 0);
-        assert_eq!(
-            state.get_subscribers("medulla://entities/decision").len(),
-            0
-        );
+        assert!(state.subscribers(&changed).is_empty());
     }
 
     #[test]
@@ -2972,7 +6420,7 @@ mod tests {
     #[test]
     fn test_build_static_resources() {
         let resources = resources::build_static_resources();
-        assert_eq!(resources.len(), 9);
+        assert_eq!(resources.len(), 11);
         assert!(resources.iter().any(|r| r.uri == "medulla://schema"));
         assert!(resources.iter().any(|r| r.uri == "medulla://stats"));
         assert!(resources.iter().any(|r| r.uri == "medulla://entities"));
@@ -2983,7 +6431,7 @@ mod tests {
     #[test]
     fn test_build_resource_templates() {
         let templates = resources::build_resource_templates();
-        assert_eq!(templates.len(), 5);
+        assert_eq!(templates.len(), 6);
         assert!(templates
             .iter()
             .any(|t| t.uri_template == "medulla://entities/{type}"));
@@ -3006,219 +6454,3374 @@ mod tests {
         assert_eq!(read_result.contents.len(), 1);
     }
 
-    #[tokio::test]
-    async fn test_read_resource_stats() {
-        let (server, _tmp) = setup_test_server();
-        let result =
-            resources::read_resource("medulla://stats", &server.store, &server.cache).await;
+    #[tokio::test]
+    async fn test_read_resource_stats() {
+        let (server, _tmp) = setup_test_server();
+        let result =
+            resources::read_resource("medulla://stats", &server.store, &server.cache).await;
+
+        assert!(result.is_ok());
+        let read_result = result.unwrap();
+        assert_eq!(read_result.contents.len(), 1);
+
+        if let ResourceContents::TextResourceContents { text, .. } = &read_result.contents[0] {
+            let parsed: serde_json::Value = serde_json::from_str(text).unwrap();
+            assert!(parsed["entity_counts"].is_object());
+            assert!(parsed["medulla_version"].is_string());
+        } else {
+            panic!("Expected TextResourceContents");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_read_resource_invalid_uri() {
+        let (server, _tmp) = setup_test_server();
+        let result =
+            resources::read_resource("invalid://schema", &server.store, &server.cache).await;
+
+        assert!(result.is_err());
+    }
+
+    /// A client session talking raw JSON-RPC to the server over an
+    /// in-memory transport.
+    struct TestSession {
+        lines: tokio::io::Lines<tokio::io::BufReader<tokio::io::ReadHalf<tokio::io::DuplexStream>>>,
+        writer: tokio::io::WriteHalf<tokio::io::DuplexStream>,
+        served: tokio::task::JoinHandle<()>,
+    }
+
+    impl TestSession {
+        async fn connect(server: &MedullaServer) -> Self {
+            use tokio::io::AsyncBufReadExt;
+
+            let (client, server_side) = tokio::io::duplex(64 * 1024);
+            let served = tokio::spawn({
+                let server = server.clone();
+                async move {
+                    let _ = server.serve(tokio::io::split(server_side)).await;
+                }
+            });
+            let (reader, writer) = tokio::io::split(client);
+            let mut session = Self {
+                lines: tokio::io::BufReader::new(reader).lines(),
+                writer,
+                served,
+            };
+
+            session
+                .request(
+                    1,
+                    "initialize",
+                    serde_json::json!({
+                        "protocolVersion": "2025-06-18",
+                        "capabilities": {},
+                        "clientInfo": {"name": "test", "version": "0"},
+                    }),
+                )
+                .await;
+            session
+                .send(serde_json::json!({"jsonrpc": "2.0", "method": "notifications/initialized"}))
+                .await;
+            session
+        }
+
+        async fn send(&mut self, message: serde_json::Value) {
+            use tokio::io::AsyncWriteExt;
+            let line = format!("{}\n", message);
+            self.writer.write_all(line.as_bytes()).await.unwrap();
+        }
+
+        async fn recv(&mut self) -> serde_json::Value {
+            let line =
+                tokio::time::timeout(std::time::Duration::from_secs(5), self.lines.next_line())
+                    .await
+                    .expect("timed out waiting for a message")
+                    .unwrap()
+                    .expect("transport closed");
+            serde_json::from_str(&line).unwrap()
+        }
+
+        /// Send a request and return its result.
+        async fn request(
+            &mut self,
+            id: u64,
+            method: &str,
+            params: serde_json::Value,
+        ) -> serde_json::Value {
+            self.send(
+                serde_json::json!({"jsonrpc": "2.0", "id": id, "method": method, "params": params}),
+            )
+            .await;
+            let response = self.recv().await;
+            assert_eq!(response["id"], id, "unexpected message {}", response);
+            response["result"].clone()
+        }
+
+        async fn subscribe(&mut self, id: u64, uri: &str) {
+            self.request(id, "resources/subscribe", serde_json::json!({"uri": uri}))
+                .await;
+        }
+
+        /// The URI of the next `resources/updated` notification.
+        async fn next_updated(&mut self) -> String {
+            let message = self.recv().await;
+            assert_eq!(message["method"], "notifications/resources/updated");
+            message["params"]["uri"].as_str().unwrap().to_string()
+        }
+    }
+
+    async fn create_entity(server: &MedullaServer, entity_type: &str, title: &str) {
+        let params = EntityCreateParams {
+            entity_type: entity_type.to_string(),
+            title: title.to_string(),
+            content: None,
+            tags: None,
+            properties: None,
+        };
+        server
+            .entity_create(rmcp::handler::server::wrapper::Parameters(params))
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_subscriptions_notify_each_session() {
+        let (server, _tmp) = setup_test_server();
+
+        let mut tasks = TestSession::connect(&server).await;
+        tasks.subscribe(2, "medulla://entities/task").await;
+        let mut notes = TestSession::connect(&server).await;
+        notes.subscribe(2, "medulla://entities/note").await;
+
+        // Only the session subscribed to tasks hears about the new task
+        create_entity(&server, "task", "Write the parser").await;
+        assert_eq!(tasks.next_updated().await, "medulla://entities/task");
+
+        server
+            .task_complete(rmcp::handler::server::wrapper::Parameters(
+                TaskCompleteParams {
+                    id: "1".to_string(),
+                },
+            ))
+            .await
+            .unwrap();
+        assert_eq!(tasks.next_updated().await, "medulla://entities/task");
+
+        notes.subscribe(3, "medulla://focus").await;
+        server
+            .focus_set(rmcp::handler::server::wrapper::Parameters(FocusSetParams {
+                ids: vec!["1".to_string()],
+            }))
+            .await
+            .unwrap();
+        create_entity(&server, "note", "Parser notes").await;
+        // Nothing about the task was queued for the notes session
+        assert_eq!(notes.next_updated().await, "medulla://focus");
+        let mut updated = vec![notes.next_updated().await, notes.next_updated().await];
+        updated.sort();
+        assert_eq!(updated, ["medulla://entities/note", "medulla://focus"]);
+
+        // A disconnected session loses its subscriptions
+        let TestSession {
+            lines,
+            writer,
+            served,
+        } = notes;
+        drop((lines, writer));
+        served.await.unwrap();
+        create_entity(&server, "note", "More parser notes").await;
+        let subs = server.subscriptions.lock().await;
+        assert!(subs
+            .subscribed(&["medulla://entities/note".to_string()])
+            .is_empty());
+        assert!(!subs
+            .subscribed(&["medulla://entities/task".to_string()])
+            .is_empty());
+    }
+
+    // ========================================================================
+    // MCP Tool Unit Tests
+    // ========================================================================
+
+    #[tokio::test]
+    async fn test_entity_create_decision() {
+        let (server, _tmp) = setup_test_server();
+
+        let params = EntityCreateParams {
+            entity_type: "decision".to_string(),
+            title: "Use Rust for CLI".to_string(),
+            content: Some("We decided to use Rust for the CLI implementation.".to_string()),
+            tags: Some(vec!["lang".to_string(), "tooling".to_string()]),
+            properties: Some(serde_json::json!({
+                "status": "accepted",
+                "context": "Need a fast, reliable CLI"
+            })),
+        };
+
+        let result = server
+            .entity_create(rmcp::handler::server::wrapper::Parameters(params))
+            .await;
+
+        assert!(result.is_ok());
+        let tool_result = result.unwrap();
+        assert!(!tool_result.is_error.unwrap_or(false));
+
+        // Verify the entity was created
+        let store = server.store.lock().await;
+        let decisions = store.list_decisions().unwrap();
+        assert_eq!(decisions.len(), 1);
+        assert_eq!(decisions[0].base.title, "Use Rust for CLI");
+        assert_eq!(decisions[0].status, crate::entity::DecisionStatus::Accepted);
+    }
+
+    #[tokio::test]
+    async fn test_entity_create_reports_duplicate_candidates() {
+        let (server, _tmp) = setup_test_server();
+
+        let mut candidates = Vec::new();
+        for title in [
+            "Use PostgreSQL for the database",
+            "Use Postgresql for database",
+        ] {
+            let params = EntityCreateParams {
+                entity_type: "decision".to_string(),
+                title: title.to_string(),
+                content: None,
+                tags: None,
+                properties: None,
+            };
+            let result = server
+                .entity_create(rmcp::handler::server::wrapper::Parameters(params))
+                .await
+                .unwrap();
+            if let rmcp::model::RawContent::Text(t) = &result.content[0].raw {
+                let parsed: serde_json::Value = serde_json::from_str(&t.text).unwrap();
+                candidates.push(parsed.get("duplicate_candidates").cloned());
+            } else {
+                panic!("Expected text content");
+            }
+        }
+
+        // The first had nothing to duplicate; the second is still created
+        assert_eq!(candidates[0], None);
+        let second = candidates[1].as_ref().unwrap();
+        assert_eq!(second[0]["sequence_number"], 1);
+        assert_eq!(second[0]["matched_on"], "title");
+        assert_eq!(server.store.lock().await.list_decisions().unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_entity_create_task() {
+        let (server, _tmp) = setup_test_server();
+
+        let params = EntityCreateParams {
+            entity_type: "task".to_string(),
+            title: "Implement auth".to_string(),
+            content: None,
+            tags: Some(vec!["backend".to_string()]),
+            properties: Some(serde_json::json!({
+                "status": "todo",
+                "priority": "high",
+                "due_date": "2025-03-01",
+                "assignee": "alice"
+            })),
+        };
+
+        let result = server
+            .entity_create(rmcp::handler::server::wrapper::Parameters(params))
+            .await;
+
+        assert!(result.is_ok());
+
+        let store = server.store.lock().await;
+        let tasks = store.list_tasks().unwrap();
+        assert_eq!(tasks.len(), 1);
+        assert_eq!(tasks[0].base.title, "Implement auth");
+        assert_eq!(tasks[0].priority, crate::entity::TaskPriority::High);
+        assert_eq!(tasks[0].assignee, Some("alice".to_string()));
+    }
+
+    #[tokio::test]
+    async fn test_entity_create_validation_error_empty_title() {
+        let (server, _tmp) = setup_test_server();
+
+        let params = EntityCreateParams {
+            entity_type: "decision".to_string(),
+            title: "   ".to_string(), // whitespace only
+            content: None,
+            tags: None,
+            properties: None,
+        };
+
+        let result = server
+            .entity_create(rmcp::handler::server::wrapper::Parameters(params))
+            .await;
+
+        assert!(result.is_err());
+        let err = result.unwrap_err();
+        assert!(err.message.contains("Title"));
+    }
+
+    #[tokio::test]
+    async fn test_entity_create_validation_error_invalid_type() {
+        let (server, _tmp) = setup_test_server();
+
+        let params = EntityCreateParams {
+            entity_type: "invalid_type".to_string(),
+            title: "Test".to_string(),
+            content: None,
+            tags: None,
+            properties: None,
+        };
+
+        let result = server
+            .entity_create(rmcp::handler::server::wrapper::Parameters(params))
+            .await;
+
+        assert!(result.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_validation_rule_requires_task_tag() {
+        let (server, _tmp) = setup_test_server();
+        let config = crate::config::ProjectConfig::from_yaml(
+            "validation_rules:\n  - rule: tag_required\n    types: [task]\n",
+        )
+        .unwrap();
+        let server = server.with_config(config);
+
+        let params = EntityCreateParams {
+            entity_type: "task".to_string(),
+            title: "Untagged task".to_string(),
+            content: None,
+            tags: None,
+            properties: None,
+        };
+        let err = server
+            .entity_create(rmcp::handler::server::wrapper::Parameters(params))
+            .await
+            .unwrap_err();
+        assert_eq!(err.code.0, error::error_codes::VALIDATION_FAILED);
+        assert!(err.message.contains("at least 1 tag"));
+
+        let params = EntityCreateParams {
+            entity_type: "task".to_string(),
+            title: "Tagged task".to_string(),
+            content: None,
+            tags: Some(vec!["backend".to_string()]),
+            properties: None,
+        };
+        assert!(server
+            .entity_create(rmcp::handler::server::wrapper::Parameters(params))
+            .await
+            .is_ok());
+
+        // Removing the last tag violates the rule
+        let params = EntityUpdateParams {
+            id: "1".to_string(),
+            title: None,
+            content: None,
+            add_tags: None,
+            remove_tags: Some(vec!["backend".to_string()]),
+            properties: None,
+            force: None,
+        };
+        assert!(server
+            .entity_update(rmcp::handler::server::wrapper::Parameters(params))
+            .await
+            .is_err());
+
+        // Rules only apply to the configured types
+        let params = EntityCreateParams {
+            entity_type: "note".to_string(),
+            title: "Untagged note".to_string(),
+            content: None,
+            tags: None,
+            properties: None,
+        };
+        assert!(server
+            .entity_create(rmcp::handler::server::wrapper::Parameters(params))
+            .await
+            .is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_entity_get_by_sequence_number() {
+        let (server, _tmp) = setup_test_server();
+
+        // Create an entity first
+        let create_params = EntityCreateParams {
+            entity_type: "decision".to_string(),
+            title: "Test Decision".to_string(),
+            content: None,
+            tags: None,
+            properties: None,
+        };
+        server
+            .entity_create(rmcp::handler::server::wrapper::Parameters(create_params))
+            .await
+            .unwrap();
+
+        // Get by sequence number
+        let get_params = EntityGetParams {
+            id: "1".to_string(),
+            entity_type: None,
+            include_relations: None,
+            include_related_entities: None,
+            depth: None,
+        };
+
+        let result = server
+            .entity_get(rmcp::handler::server::wrapper::Parameters(get_params))
+            .await;
+
+        assert!(result.is_ok());
+        let tool_result = result.unwrap();
+        if let rmcp::model::RawContent::Text(t) = &tool_result.content[0].raw {
+            assert!(t.text.contains("Test Decision"));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_entity_get_attachments() {
+        let (server, tmp) = setup_test_server();
+        let create_params = EntityCreateParams {
+            entity_type: "decision".to_string(),
+            title: "Use Postgres".to_string(),
+            content: None,
+            tags: None,
+            properties: None,
+        };
+        server
+            .entity_create(rmcp::handler::server::wrapper::Parameters(create_params))
+            .await
+            .unwrap();
+        let screenshot = tmp.path().join("benchmark.png");
+        std::fs::write(&screenshot, b"png").unwrap();
+        {
+            let store = server.store.lock().await;
+            let (id, _) = store.lookup_id("1", None).unwrap();
+            store.attach_file(&id, &screenshot, None).unwrap();
+        }
+
+        let get_params = EntityGetParams {
+            id: "1".to_string(),
+            entity_type: None,
+            include_relations: None,
+            include_related_entities: None,
+            depth: None,
+        };
+        let result = server
+            .entity_get(rmcp::handler::server::wrapper::Parameters(get_params))
+            .await
+            .unwrap();
+        let text = match &result.content[0].raw {
+            RawContent::Text(t) => &t.text,
+            _ => panic!("Expected text content"),
+        };
+        let parsed: serde_json::Value = serde_json::from_str(text).unwrap();
+        assert_eq!(parsed["attachments"][0]["filename"], "benchmark.png");
+        assert_eq!(parsed["attachments"][0]["mime"], "image/png");
+        assert_eq!(parsed["attachments"][0]["size"], 3);
+    }
+
+    #[tokio::test]
+    async fn test_entity_get_not_found() {
+        let (server, _tmp) = setup_test_server();
+
+        let get_params = EntityGetParams {
+            id: "999".to_string(),
+            entity_type: None,
+            include_relations: None,
+            include_related_entities: None,
+            depth: None,
+        };
+
+        let result = server
+            .entity_get(rmcp::handler::server::wrapper::Parameters(get_params))
+            .await;
+
+        assert!(result.is_err());
+        let err = result.unwrap_err();
+        assert!(err.message.contains("not found") || err.code.0 == -32001);
+    }
+
+    #[tokio::test]
+    async fn test_entity_get_include_relations() {
+        let (server, _tmp) = setup_test_server();
+
+        for title in ["Decision A", "Decision B"] {
+            let params = EntityCreateParams {
+                entity_type: "decision".to_string(),
+                title: title.to_string(),
+                content: None,
+                tags: None,
+                properties: None,
+            };
+            server
+                .entity_create(rmcp::handler::server::wrapper::Parameters(params))
+                .await
+                .unwrap();
+        }
+
+        {
+            let store = server.store.lock().await;
+            let decisions = store.list_decisions().unwrap();
+            let a = decisions
+                .iter()
+                .find(|d| d.base.title == "Decision A")
+                .unwrap();
+            let b = decisions
+                .iter()
+                .find(|d| d.base.title == "Decision B")
+                .unwrap();
+            let relation = crate::entity::Relation::new(
+                a.base.id,
+                "decision".to_string(),
+                b.base.id,
+                "decision".to_string(),
+                crate::entity::RelationType::References,
+            );
+            store.add_relation(&relation).unwrap();
+            store.save().unwrap();
+        }
+
+        // Relations are omitted by default
+        let get_params = EntityGetParams {
+            id: "1".to_string(),
+            entity_type: None,
+            include_relations: None,
+            include_related_entities: None,
+            depth: None,
+        };
+        let result = server
+            .entity_get(rmcp::handler::server::wrapper::Parameters(get_params))
+            .await
+            .unwrap();
+        if let rmcp::model::RawContent::Text(t) = &result.content[0].raw {
+            let parsed: serde_json::Value = serde_json::from_str(&t.text).unwrap();
+            assert!(parsed.get("relations").is_none());
+        }
+
+        // Outgoing relation resolves the target's title
+        let get_params = EntityGetParams {
+            id: "1".to_string(),
+            entity_type: None,
+            include_relations: Some(true),
+            include_related_entities: None,
+            depth: None,
+        };
+        let result = server
+            .entity_get(rmcp::handler::server::wrapper::Parameters(get_params))
+            .await
+            .unwrap();
+        if let rmcp::model::RawContent::Text(t) = &result.content[0].raw {
+            let parsed: serde_json::Value = serde_json::from_str(&t.text).unwrap();
+            let outgoing = parsed["relations"]["outgoing"].as_array().unwrap();
+            assert_eq!(outgoing.len(), 1);
+            assert_eq!(outgoing[0]["relation_type"], "references");
+            assert_eq!(outgoing[0]["related_title"], "Decision B");
+            assert!(parsed["relations"]["incoming"]
+                .as_array()
+                .unwrap()
+                .is_empty());
+        }
+
+        // Incoming relation resolves the source's title
+        let get_params = EntityGetParams {
+            id: "2".to_string(),
+            entity_type: Some("decision".to_string()),
+            include_relations: Some(true),
+            include_related_entities: None,
+            depth: None,
+        };
+        let result = server
+            .entity_get(rmcp::handler::server::wrapper::Parameters(get_params))
+            .await
+            .unwrap();
+        if let rmcp::model::RawContent::Text(t) = &result.content[0].raw {
+            let parsed: serde_json::Value = serde_json::from_str(&t.text).unwrap();
+            let incoming = parsed["relations"]["incoming"].as_array().unwrap();
+            assert_eq!(incoming.len(), 1);
+            assert_eq!(incoming[0]["related_title"], "Decision A");
+            assert!(parsed.get("related_entities").is_none());
+        }
+
+        // Related entity summaries follow relations in either direction
+        let get_params = EntityGetParams {
+            id: "2".to_string(),
+            entity_type: None,
+            include_relations: None,
+            include_related_entities: Some(true),
+            depth: Some(5),
+        };
+        let result = server
+            .entity_get(rmcp::handler::server::wrapper::Parameters(get_params))
+            .await
+            .unwrap();
+        if let rmcp::model::RawContent::Text(t) = &result.content[0].raw {
+            let parsed: serde_json::Value = serde_json::from_str(&t.text).unwrap();
+            assert!(parsed.get("relations").is_none());
+            let related = parsed["related_entities"].as_array().unwrap();
+            assert_eq!(related.len(), 1);
+            assert_eq!(related[0]["title"], "Decision A");
+            assert_eq!(related[0]["type"], "decision");
+            assert_eq!(related[0]["status"], "proposed");
+            assert_eq!(related[0]["distance"], 1);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_entity_list_all() {
+        let (server, _tmp) = setup_test_server();
+
+        // Create multiple entities
+        for title in ["Decision A", "Decision B"] {
+            let params = EntityCreateParams {
+                entity_type: "decision".to_string(),
+                title: title.to_string(),
+                content: None,
+                tags: None,
+                properties: None,
+            };
+            server
+                .entity_create(rmcp::handler::server::wrapper::Parameters(params))
+                .await
+                .unwrap();
+        }
+
+        let list_params = EntityListParams {
+            entity_type: Some("decision".to_string()),
+            status: None,
+            tag: None,
+            collection: None,
+            created_after: None,
+            updated_after: None,
+            sort_by: None,
+            order: None,
+            limit: None,
+            offset: None,
+            cursor: None,
+        };
+
+        let result = server
+            .entity_list(rmcp::handler::server::wrapper::Parameters(list_params))
+            .await;
+
+        assert!(result.is_ok());
+        let tool_result = result.unwrap();
+        if let rmcp::model::RawContent::Text(t) = &tool_result.content[0].raw {
+            let parsed: serde_json::Value = serde_json::from_str(&t.text).unwrap();
+            assert_eq!(parsed["total"], 2);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_entity_index() {
+        let (server, _tmp) = setup_test_server();
+
+        for (entity_type, title) in [
+            ("task", "Task A"),
+            ("task", "Task B"),
+            ("note", "Unrelated note"),
+        ] {
+            let params = EntityCreateParams {
+                entity_type: entity_type.to_string(),
+                title: title.to_string(),
+                content: Some("Long content that should not appear".to_string()),
+                tags: Some(vec!["tagged".to_string()]),
+                properties: None,
+            };
+            server
+                .entity_create(rmcp::handler::server::wrapper::Parameters(params))
+                .await
+                .unwrap();
+        }
+
+        let params = EntityIndexParams {
+            entity_type: "task".to_string(),
+        };
+        let result = server
+            .entity_index(rmcp::handler::server::wrapper::Parameters(params))
+            .await
+            .unwrap();
+
+        if let rmcp::model::RawContent::Text(t) = &result.content[0].raw {
+            let parsed: serde_json::Value = serde_json::from_str(&t.text).unwrap();
+            assert_eq!(parsed["total"], 2);
+            let entries = parsed["entries"].as_array().unwrap();
+            assert_eq!(entries[0]["title"], "Task A");
+            assert_eq!(entries[0]["status"], "todo");
+            for entry in entries {
+                let keys: Vec<&String> = entry.as_object().unwrap().keys().collect();
+                assert_eq!(keys.len(), 3);
+                assert!(entry.get("content").is_none());
+                assert!(entry.get("tags").is_none());
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_entity_get_by_alias() {
+        let (server, _tmp) = setup_test_server();
+
+        let params = EntityCreateParams {
+            entity_type: "decision".to_string(),
+            title: "Use OAuth".to_string(),
+            content: None,
+            tags: None,
+            properties: None,
+        };
+        server
+            .entity_create(rmcp::handler::server::wrapper::Parameters(params))
+            .await
+            .unwrap();
+        {
+            let store = server.store.lock().await;
+            let (id, _) = store.lookup_id("1", None).unwrap();
+            store.set_alias(&id, "auth-decision").unwrap();
+        }
+
+        let get_params = EntityGetParams {
+            id: "auth-decision".to_string(),
+            entity_type: None,
+            include_relations: None,
+            include_related_entities: None,
+            depth: None,
+        };
+        let result = server
+            .entity_get(rmcp::handler::server::wrapper::Parameters(get_params))
+            .await
+            .unwrap();
+        if let rmcp::model::RawContent::Text(t) = &result.content[0].raw {
+            let parsed: serde_json::Value = serde_json::from_str(&t.text).unwrap();
+            assert_eq!(parsed["title"], "Use OAuth");
+            assert_eq!(parsed["alias"], "auth-decision");
+        }
+
+        let params = EntityUpdateParams {
+            id: "AUTH-DECISION".to_string(),
+            title: Some("Use OAuth 2".to_string()),
+            content: None,
+            add_tags: None,
+            remove_tags: None,
+            properties: None,
+            force: None,
+        };
+        server
+            .entity_update(rmcp::handler::server::wrapper::Parameters(params))
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_entity_collections() {
+        let (server, _tmp) = setup_test_server();
+
+        for (title, collection) in [
+            ("Schema", Some("Backend")),
+            ("Login page", None),
+            ("Queue", Some("backend")),
+        ] {
+            let params = EntityCreateParams {
+                entity_type: "note".to_string(),
+                title: title.to_string(),
+                content: None,
+                tags: None,
+                properties: collection.map(|c| serde_json::json!({ "collection": c })),
+            };
+            server
+                .entity_create(rmcp::handler::server::wrapper::Parameters(params))
+                .await
+                .unwrap();
+        }
+
+        let get = |id: &str| EntityGetParams {
+            id: id.to_string(),
+            entity_type: None,
+            include_relations: None,
+            include_related_entities: None,
+            depth: None,
+        };
+        let result = server
+            .entity_get(rmcp::handler::server::wrapper::Parameters(get("backend/2")))
+            .await
+            .unwrap();
+        if let rmcp::model::RawContent::Text(t) = &result.content[0].raw {
+            let parsed: serde_json::Value = serde_json::from_str(&t.text).unwrap();
+            assert_eq!(parsed["title"], "Queue");
+            assert_eq!(parsed["collection"], "backend");
+            assert_eq!(parsed["collection_id"], "backend/2");
+        }
+
+        // Move the first note out and the second in
+        for (id, collection) in [("1", serde_json::Value::Null), ("2", "backend".into())] {
+            let params = EntityUpdateParams {
+                id: id.to_string(),
+                title: None,
+                content: None,
+                add_tags: None,
+                remove_tags: None,
+                properties: Some(serde_json::json!({ "collection": collection })),
+                force: None,
+            };
+            server
+                .entity_update(rmcp::handler::server::wrapper::Parameters(params))
+                .await
+                .unwrap();
+        }
+        assert!(server
+            .entity_get(rmcp::handler::server::wrapper::Parameters(get("backend/1")))
+            .await
+            .is_err());
+
+        let list_params = EntityListParams {
+            entity_type: None,
+            status: None,
+            tag: None,
+            collection: Some("BACKEND".to_string()),
+            created_after: None,
+            updated_after: None,
+            sort_by: None,
+            order: None,
+            limit: None,
+            offset: None,
+            cursor: None,
+        };
+        let result = server
+            .entity_list(rmcp::handler::server::wrapper::Parameters(list_params))
+            .await
+            .unwrap();
+        if let rmcp::model::RawContent::Text(t) = &result.content[0].raw {
+            let parsed: serde_json::Value = serde_json::from_str(&t.text).unwrap();
+            assert_eq!(parsed["total"], 2);
+        }
+
+        let params = EntityCreateParams {
+            entity_type: "note".to_string(),
+            title: "Bad".to_string(),
+            content: None,
+            tags: None,
+            properties: Some(serde_json::json!({ "collection": "has space" })),
+        };
+        assert!(server
+            .entity_create(rmcp::handler::server::wrapper::Parameters(params))
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_entity_list_pagination_metadata() {
+        let (server, _tmp) = setup_test_server();
+
+        for i in 1..=5 {
+            let params = EntityCreateParams {
+                entity_type: "note".to_string(),
+                title: format!("Note {}", i),
+                content: None,
+                tags: None,
+                properties: None,
+            };
+            server
+                .entity_create(rmcp::handler::server::wrapper::Parameters(params))
+                .await
+                .unwrap();
+        }
+
+        let list = |offset: u32| EntityListParams {
+            entity_type: Some("note".to_string()),
+            status: None,
+            tag: None,
+            collection: None,
+            created_after: None,
+            updated_after: None,
+            sort_by: None,
+            order: None,
+            limit: Some(2),
+            offset: Some(offset),
+            cursor: None,
+        };
+
+        let result = server
+            .entity_list(rmcp::handler::server::wrapper::Parameters(list(0)))
+            .await
+            .unwrap();
+        if let rmcp::model::RawContent::Text(t) = &result.content[0].raw {
+            let parsed: serde_json::Value = serde_json::from_str(&t.text).unwrap();
+            assert_eq!(parsed["total"], 5);
+            assert_eq!(parsed["has_more"], true);
+            assert_eq!(parsed["next_offset"], 2);
+        } else {
+            panic!("Expected text content");
+        }
+
+        // Last page: one entity left, nothing after it
+        let result = server
+            .entity_list(rmcp::handler::server::wrapper::Parameters(list(4)))
+            .await
+            .unwrap();
+        if let rmcp::model::RawContent::Text(t) = &result.content[0].raw {
+            let parsed: serde_json::Value = serde_json::from_str(&t.text).unwrap();
+            assert_eq!(parsed["entities"].as_array().unwrap().len(), 1);
+            assert_eq!(parsed["has_more"], false);
+            assert!(parsed["next_offset"].is_null());
+        }
+
+        // Orphans paginate the same way
+        let params = GraphOrphansParams {
+            entity_type: None,
+            limit: Some(3),
+            offset: None,
+        };
+        let result = server
+            .graph_orphans(rmcp::handler::server::wrapper::Parameters(params))
+            .await
+            .unwrap();
+        if let rmcp::model::RawContent::Text(t) = &result.content[0].raw {
+            let parsed: serde_json::Value = serde_json::from_str(&t.text).unwrap();
+            assert_eq!(parsed["total"], 5);
+            assert_eq!(parsed["has_more"], true);
+            assert_eq!(parsed["next_offset"], 3);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_entity_list_cursor() {
+        let (server, _tmp) = setup_test_server();
+
+        for i in 1..=5 {
+            let params = EntityCreateParams {
+                entity_type: "note".to_string(),
+                title: format!("Note {}", i),
+                content: None,
+                tags: None,
+                properties: None,
+            };
+            server
+                .entity_create(rmcp::handler::server::wrapper::Parameters(params))
+                .await
+                .unwrap();
+        }
+
+        let page = |cursor: Option<String>| {
+            let server = &server;
+            async move {
+                let params = EntityListParams {
+                    entity_type: Some("note".to_string()),
+                    status: None,
+                    tag: None,
+                    collection: None,
+                    created_after: None,
+                    updated_after: None,
+                    sort_by: None,
+                    order: None,
+                    limit: Some(2),
+                    offset: None,
+                    cursor,
+                };
+                let result = server
+                    .entity_list(rmcp::handler::server::wrapper::Parameters(params))
+                    .await
+                    .unwrap();
+                match &result.content[0].raw {
+                    rmcp::model::RawContent::Text(t) => {
+                        serde_json::from_str::<serde_json::Value>(&t.text).unwrap()
+                    }
+                    _ => panic!("Expected text content"),
+                }
+            }
+        };
+        let titles = |parsed: &serde_json::Value| -> Vec<String> {
+            parsed["entities"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|e| e["title"].as_str().unwrap().to_string())
+                .collect()
+        };
+
+        let first = page(None).await;
+        assert_eq!(titles(&first), vec!["Note 1", "Note 2"]);
+        let cursor = first["next_cursor"].as_str().unwrap().to_string();
+
+        // Deleting an entity already seen does not shift the next page
+        server
+            .entity_delete(rmcp::handler::server::wrapper::Parameters(
+                EntityDeleteParams {
+                    id: "1".to_string(),
+                },
+            ))
+            .await
+            .unwrap();
+
+        let second = page(Some(cursor)).await;
+        assert_eq!(titles(&second), vec!["Note 3", "Note 4"]);
+        assert!(second["next_offset"].is_null());
+        let third = page(second["next_cursor"].as_str().map(String::from)).await;
+        assert_eq!(titles(&third), vec!["Note 5"]);
+        assert_eq!(third["has_more"], false);
+        assert!(third["next_cursor"].is_null());
+
+        let params = EntityListParams {
+            entity_type: None,
+            status: None,
+            tag: None,
+            collection: None,
+            created_after: None,
+            updated_after: None,
+            sort_by: None,
+            order: None,
+            limit: None,
+            offset: None,
+            cursor: Some("not-a-cursor".to_string()),
+        };
+        assert!(server
+            .entity_list(rmcp::handler::server::wrapper::Parameters(params))
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_entity_list_with_tag_filter() {
+        let (server, _tmp) = setup_test_server();
+
+        for (entity_type, title, tags) in [
+            ("decision", "Tagged decision", vec!["infra"]),
+            ("task", "Tagged task", vec!["infra", "urgent"]),
+            ("note", "Untagged note", vec![]),
+        ] {
+            let params = EntityCreateParams {
+                entity_type: entity_type.to_string(),
+                title: title.to_string(),
+                content: None,
+                tags: Some(tags.into_iter().map(String::from).collect()),
+                properties: None,
+            };
+            server
+                .entity_create(rmcp::handler::server::wrapper::Parameters(params))
+                .await
+                .unwrap();
+        }
+
+        let list_params = EntityListParams {
+            entity_type: None,
+            status: None,
+            tag: Some("infra".to_string()),
+            collection: None,
+            created_after: None,
+            updated_after: None,
+            sort_by: None,
+            order: None,
+            limit: None,
+            offset: None,
+            cursor: None,
+        };
+        let result = server
+            .entity_list(rmcp::handler::server::wrapper::Parameters(list_params))
+            .await
+            .unwrap();
+        if let rmcp::model::RawContent::Text(t) = &result.content[0].raw {
+            let parsed: serde_json::Value = serde_json::from_str(&t.text).unwrap();
+            assert_eq!(parsed["total"], 2);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_entity_list_with_status_filter() {
+        let (server, _tmp) = setup_test_server();
+
+        // Create decisions with different statuses
+        for (title, status) in [("Accepted", "accepted"), ("Proposed", "proposed")] {
+            let params = EntityCreateParams {
+                entity_type: "decision".to_string(),
+                title: title.to_string(),
+                content: None,
+                tags: None,
+                properties: Some(serde_json::json!({ "status": status })),
+            };
+            server
+                .entity_create(rmcp::handler::server::wrapper::Parameters(params))
+                .await
+                .unwrap();
+        }
+
+        let list_params = EntityListParams {
+            entity_type: Some("decision".to_string()),
+            status: Some("accepted".to_string()),
+            tag: None,
+            collection: None,
+            created_after: None,
+            updated_after: None,
+            sort_by: None,
+            order: None,
+            limit: None,
+            offset: None,
+            cursor: None,
+        };
+
+        let result = server
+            .entity_list(rmcp::handler::server::wrapper::Parameters(list_params))
+            .await;
+
+        assert!(result.is_ok());
+        let tool_result = result.unwrap();
+        if let rmcp::model::RawContent::Text(t) = &tool_result.content[0].raw {
+            let parsed: serde_json::Value = serde_json::from_str(&t.text).unwrap();
+            assert_eq!(parsed["total"], 1);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_entity_update() {
+        let (server, _tmp) = setup_test_server();
+
+        // Create an entity
+        let create_params = EntityCreateParams {
+            entity_type: "decision".to_string(),
+            title: "Original Title".to_string(),
+            content: None,
+            tags: Some(vec!["old-tag".to_string()]),
+            properties: Some(serde_json::json!({ "status": "proposed" })),
+        };
+        server
+            .entity_create(rmcp::handler::server::wrapper::Parameters(create_params))
+            .await
+            .unwrap();
+
+        // Update it
+        let update_params = EntityUpdateParams {
+            id: "1".to_string(),
+            title: Some("Updated Title".to_string()),
+            content: Some("New content".to_string()),
+            add_tags: Some(vec!["new-tag".to_string()]),
+            remove_tags: Some(vec!["old-tag".to_string()]),
+            properties: Some(serde_json::json!({ "status": "accepted" })),
+            force: None,
+        };
+
+        let result = server
+            .entity_update(rmcp::handler::server::wrapper::Parameters(update_params))
+            .await;
+
+        assert!(result.is_ok());
+
+        // Verify
+        let store = server.store.lock().await;
+        let decisions = store.list_decisions().unwrap();
+        assert_eq!(decisions[0].base.title, "Updated Title");
+        assert_eq!(decisions[0].status, crate::entity::DecisionStatus::Accepted);
+        assert!(decisions[0].base.tags.contains(&"new-tag".to_string()));
+        assert!(!decisions[0].base.tags.contains(&"old-tag".to_string()));
+    }
+
+    #[tokio::test]
+    async fn test_entity_delete() {
+        let (server, _tmp) = setup_test_server();
+
+        // Create an entity
+        let create_params = EntityCreateParams {
+            entity_type: "decision".to_string(),
+            title: "To Be Deleted".to_string(),
+            content: None,
+            tags: None,
+            properties: None,
+        };
+        server
+            .entity_create(rmcp::handler::server::wrapper::Parameters(create_params))
+            .await
+            .unwrap();
+
+        // Delete it
+        let delete_params = EntityDeleteParams {
+            id: "1".to_string(),
+        };
+
+        let result = server
+            .entity_delete(rmcp::handler::server::wrapper::Parameters(delete_params))
+            .await;
+
+        assert!(result.is_ok());
+
+        // Verify it's gone, but kept in the trash
+        let store = server.store.lock().await;
+        let decisions = store.list_decisions().unwrap();
+        assert!(decisions.is_empty());
+        assert_eq!(store.list_trash().unwrap().len(), 1);
+        drop(store);
+        let cache = server.cache.lock().await;
+        assert!(cache.search_decisions("Deleted", 10).unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_search_fulltext() {
+        let (server, _tmp) = setup_test_server();
+
+        // Create entities with searchable content
+        for (title, content) in [
+            ("PostgreSQL Decision", "Use PostgreSQL for database storage"),
+            ("Redis Decision", "Use Redis for caching"),
+        ] {
+            let params = EntityCreateParams {
+                entity_type: "decision".to_string(),
+                title: title.to_string(),
+                content: Some(content.to_string()),
+                tags: None,
+                properties: None,
+            };
+            server
+                .entity_create(rmcp::handler::server::wrapper::Parameters(params))
+                .await
+                .unwrap();
+        }
+
+        // Search for PostgreSQL
+        let search_params = SearchFulltextParams {
+            query: "PostgreSQL".to_string(),
+            entity_type: None,
+            limit: None,
+            offset: None,
+            within_focus: None,
+            collection: None,
+        };
+
+        let result = server
+            .search_fulltext(rmcp::handler::server::wrapper::Parameters(search_params))
+            .await;
+
+        assert!(result.is_ok());
+        let tool_result = result.unwrap();
+        if let rmcp::model::RawContent::Text(t) = &tool_result.content[0].raw {
+            let parsed: serde_json::Value = serde_json::from_str(&t.text).unwrap();
+            assert_eq!(parsed["total"], 1);
+            assert!(parsed["results"][0]["title"]
+                .as_str()
+                .unwrap()
+                .contains("PostgreSQL"));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_search_by_vector() {
+        let (server, _tmp) = setup_test_server();
+
+        // With no embeddings stored, vectors must fit the embedder
+        let params = SearchByVectorParams {
+            vector: vec![1.0, 0.0, 0.0],
+            entity_type: None,
+            limit: None,
+            threshold: None,
+        };
+        assert!(server
+            .search_by_vector(rmcp::handler::server::wrapper::Parameters(params))
+            .await
+            .is_err());
+
+        let params = EntityCreateParams {
+            entity_type: "note".to_string(),
+            title: "Diagram of the sync pipeline".to_string(),
+            content: None,
+            tags: None,
+            properties: None,
+        };
+        server
+            .entity_create(rmcp::handler::server::wrapper::Parameters(params))
+            .await
+            .unwrap();
+
+        // Store an externally computed embedding for the note
+        let vector: Vec<f32> = (0..384).map(|i| ((i % 7) as f32 - 3.0) / 3.0).collect();
+        let note_id = {
+            let store = server.store.lock().await;
+            store.list_notes().unwrap()[0].base.id.to_string()
+        };
+        {
+            let cache = server.cache.lock().await;
+            cache
+                .store_embedding(&note_id, "note", &vector, "external")
+                .unwrap();
+        }
+
+        let params = SearchByVectorParams {
+            vector: vector.clone(),
+            entity_type: None,
+            limit: None,
+            threshold: None,
+        };
+        let result = server
+            .search_by_vector(rmcp::handler::server::wrapper::Parameters(params))
+            .await
+            .unwrap();
+        if let rmcp::model::RawContent::Text(t) = &result.content[0].raw {
+            let parsed: serde_json::Value = serde_json::from_str(&t.text).unwrap();
+            assert_eq!(parsed["results"][0]["entity_id"], note_id);
+            let score = parsed["results"][0]["score"].as_f64().unwrap();
+            assert!((score - 1.0).abs() < 1e-4);
+        } else {
+            panic!("Expected text content");
+        }
+
+        // A vector of the wrong dimension is rejected
+        let params = SearchByVectorParams {
+            vector: vec![1.0, 0.0, 0.0],
+            entity_type: None,
+            limit: None,
+            threshold: None,
+        };
+        assert!(server
+            .search_by_vector(rmcp::handler::server::wrapper::Parameters(params))
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_entity_duplicates() {
+        let (server, _tmp) = setup_test_server();
+
+        for title in ["Retry policy", "Retry policy draft", "Release checklist"] {
+            let params = EntityCreateParams {
+                entity_type: "note".to_string(),
+                title: title.to_string(),
+                content: None,
+                tags: None,
+                properties: None,
+            };
+            server
+                .entity_create(rmcp::handler::server::wrapper::Parameters(params))
+                .await
+                .unwrap();
+        }
+
+        let notes = {
+            let store = server.store.lock().await;
+            store.list_notes().unwrap()
+        };
+        {
+            let cache = server.cache.lock().await;
+            for (note, vector) in
+                notes
+                    .iter()
+                    .zip([[1.0, 0.0, 0.0], [0.98, 0.1, 0.0], [0.0, 1.0, 0.0]])
+            {
+                cache
+                    .store_embedding(&note.base.id.to_string(), "note", &vector, "h")
+                    .unwrap();
+            }
+        }
+
+        let params = EntityDuplicatesParams {
+            entity_type: Some("note".to_string()),
+            threshold: None,
+            limit: None,
+        };
+        let result = server
+            .entity_duplicates(rmcp::handler::server::wrapper::Parameters(params))
+            .await
+            .unwrap();
+        if let rmcp::model::RawContent::Text(t) = &result.content[0].raw {
+            let parsed: serde_json::Value = serde_json::from_str(&t.text).unwrap();
+            assert_eq!(parsed["total"], 1);
+            assert_eq!(parsed["pairs"][0]["first"]["title"], "Retry policy");
+            assert_eq!(parsed["pairs"][0]["second"]["title"], "Retry policy draft");
+        } else {
+            panic!("Expected text content");
+        }
+
+        let params = EntityDuplicatesParams {
+            entity_type: None,
+            threshold: Some(1.5),
+            limit: None,
+        };
+        assert!(server
+            .entity_duplicates(rmcp::handler::server::wrapper::Parameters(params))
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_entity_related() {
+        let (server, _tmp) = setup_test_server();
+
+        for title in ["Retry policy", "Backoff tuning", "Timeouts", "Team lunch"] {
+            let params = EntityCreateParams {
+                entity_type: "note".to_string(),
+                title: title.to_string(),
+                content: None,
+                tags: None,
+                properties: None,
+            };
+            server
+                .entity_create(rmcp::handler::server::wrapper::Parameters(params))
+                .await
+                .unwrap();
+        }
+        let params = RelationCreateParams {
+            source_id: "3".to_string(),
+            target_id: "1".to_string(),
+            relation_type: "references".to_string(),
+        };
+        server
+            .relation_create(rmcp::handler::server::wrapper::Parameters(params))
+            .await
+            .unwrap();
+
+        let notes = {
+            let store = server.store.lock().await;
+            store.list_notes().unwrap()
+        };
+        {
+            let cache = server.cache.lock().await;
+            for note in &notes {
+                let vector = match note.base.sequence_number {
+                    1 => [1.0, 0.0, 0.0],
+                    2 => [0.9, 0.1, 0.0],
+                    3 => [0.95, 0.05, 0.0],
+                    _ => [0.0, 0.0, 1.0],
+                };
+                cache
+                    .store_embedding(&note.base.id.to_string(), "note", &vector, "h")
+                    .unwrap();
+            }
+        }
+
+        // Timeouts already references the retry policy, so isn't suggested
+        let params = EntityRelatedParams {
+            id: "1".to_string(),
+            limit: None,
+            min_score: None,
+        };
+        let result = server
+            .entity_related(rmcp::handler::server::wrapper::Parameters(params))
+            .await
+            .unwrap();
+        if let rmcp::model::RawContent::Text(t) = &result.content[0].raw {
+            let parsed: serde_json::Value = serde_json::from_str(&t.text).unwrap();
+            assert_eq!(parsed["total"], 1);
+            assert_eq!(parsed["related"][0]["title"], "Backoff tuning");
+        } else {
+            panic!("Expected text content");
+        }
+
+        let params = EntityRelatedParams {
+            id: "1".to_string(),
+            limit: None,
+            min_score: Some(-0.1),
+        };
+        assert!(server
+            .entity_related(rmcp::handler::server::wrapper::Parameters(params))
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_search_fulltext_filters_only_lists_entities() {
+        let (server, _tmp) = setup_test_server();
+
+        for (entity_type, title, status) in [
+            ("task", "Write parser", "todo"),
+            ("task", "Ship parser", "done"),
+            ("decision", "Use a parser generator", "accepted"),
+        ] {
+            let params = EntityCreateParams {
+                entity_type: entity_type.to_string(),
+                title: title.to_string(),
+                content: None,
+                tags: None,
+                properties: Some(serde_json::json!({ "status": status })),
+            };
+            server
+                .entity_create(rmcp::handler::server::wrapper::Parameters(params))
+                .await
+                .unwrap();
+        }
+
+        // Only filters: no text left to match, so list with the filters
+        let params = SearchFulltextParams {
+            query: "type:task status:todo".to_string(),
+            entity_type: None,
+            limit: None,
+            offset: None,
+            within_focus: None,
+            collection: None,
+        };
+        let result = server
+            .search_fulltext(rmcp::handler::server::wrapper::Parameters(params))
+            .await
+            .unwrap();
+        if let rmcp::model::RawContent::Text(t) = &result.content[0].raw {
+            let parsed: serde_json::Value = serde_json::from_str(&t.text).unwrap();
+            assert_eq!(parsed["total"], 1);
+            assert_eq!(parsed["entities"][0]["title"], "Write parser");
+        } else {
+            panic!("Expected text content");
+        }
+
+        // Filters combined with text narrow the search results
+        let params = SearchFulltextParams {
+            query: "parser status:done".to_string(),
+            entity_type: None,
+            limit: None,
+            offset: None,
+            within_focus: None,
+            collection: None,
+        };
+        let result = server
+            .search_fulltext(rmcp::handler::server::wrapper::Parameters(params))
+            .await
+            .unwrap();
+        if let rmcp::model::RawContent::Text(t) = &result.content[0].raw {
+            let parsed: serde_json::Value = serde_json::from_str(&t.text).unwrap();
+            assert_eq!(parsed["total"], 1);
+            assert_eq!(parsed["results"][0]["title"], "Ship parser");
+        }
+
+        // created:> is passed to the listing
+        for (query, total) in [
+            ("type:task created:>2000-01-01", 2),
+            ("type:task created:>2999-01-01", 0),
+        ] {
+            let params = SearchFulltextParams {
+                query: query.to_string(),
+                entity_type: None,
+                limit: None,
+                offset: None,
+                within_focus: None,
+                collection: None,
+            };
+            let result = server
+                .search_fulltext(rmcp::handler::server::wrapper::Parameters(params))
+                .await
+                .unwrap();
+            if let rmcp::model::RawContent::Text(t) = &result.content[0].raw {
+                let parsed: serde_json::Value = serde_json::from_str(&t.text).unwrap();
+                assert_eq!(parsed["total"], total, "{}", query);
+            }
+        }
+
+        // Filters the listing can't apply are refused, not ignored
+        for (query, within_focus) in [
+            ("tag:a tag:b", None),
+            ("created:<2999-01-01", None),
+            ("type:task", Some(true)),
+        ] {
+            let params = SearchFulltextParams {
+                query: query.to_string(),
+                entity_type: None,
+                limit: None,
+                offset: None,
+                within_focus,
+                collection: None,
+            };
+            assert!(
+                server
+                    .search_fulltext(rmcp::handler::server::wrapper::Parameters(params))
+                    .await
+                    .is_err(),
+                "{}",
+                query
+            );
+        }
+
+        // A genuinely empty query is still an error
+        let params = SearchFulltextParams {
+            query: "   ".to_string(),
+            entity_type: None,
+            limit: None,
+            offset: None,
+            within_focus: None,
+            collection: None,
+        };
+        assert!(server
+            .search_fulltext(rmcp::handler::server::wrapper::Parameters(params))
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_search_fulltext_pages_merged_results() {
+        let (server, _tmp) = setup_test_server();
+
+        for (entity_type, title) in [
+            ("decision", "Cache eviction"),
+            ("task", "Cache warmup"),
+            ("task", "Cache metrics"),
+            ("note", "Cache cache cache"),
+            ("note", "Unrelated"),
+        ] {
+            let params = EntityCreateParams {
+                entity_type: entity_type.to_string(),
+                title: title.to_string(),
+                content: None,
+                tags: None,
+                properties: None,
+            };
+            server
+                .entity_create(rmcp::handler::server::wrapper::Parameters(params))
+                .await
+                .unwrap();
+        }
+
+        let page = |offset: u32| SearchFulltextParams {
+            query: "cache".to_string(),
+            entity_type: None,
+            limit: Some(3),
+            offset: Some(offset),
+            within_focus: None,
+            collection: None,
+        };
+        let parse = |result: CallToolResult| -> serde_json::Value {
+            let rmcp::model::RawContent::Text(t) = &result.content[0].raw else {
+                panic!("Expected text content");
+            };
+            serde_json::from_str(&t.text).unwrap()
+        };
+
+        let first = parse(
+            server
+                .search_fulltext(rmcp::handler::server::wrapper::Parameters(page(0)))
+                .await
+                .unwrap(),
+        );
+        assert_eq!(first["total"], 4);
+        assert_eq!(first["counts"]["decision"], 1);
+        assert_eq!(first["counts"]["task"], 2);
+        assert_eq!(first["counts"]["note"], 1);
+        assert_eq!(first["results"].as_array().unwrap().len(), 3);
+        assert_eq!(first["has_more"], true);
+        assert_eq!(first["next_offset"], 3);
+        // The note matching the term most often outranks the decision,
+        // even though decisions are searched first
+        assert_eq!(first["results"][0]["title"], "Cache cache cache");
+
+        let second = parse(
+            server
+                .search_fulltext(rmcp::handler::server::wrapper::Parameters(page(3)))
+                .await
+                .unwrap(),
+        );
+        assert_eq!(second["total"], 4);
+        assert_eq!(second["results"].as_array().unwrap().len(), 1);
+        assert_eq!(second["has_more"], false);
+        assert!(second["next_offset"].is_null());
+
+        let mut seen: Vec<String> = first["results"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .chain(second["results"].as_array().unwrap())
+            .map(|r| r["id"].as_str().unwrap().to_string())
+            .collect();
+        seen.sort();
+        seen.dedup();
+        assert_eq!(seen.len(), 4);
+    }
+
+    #[tokio::test]
+    async fn test_search_by_author() {
+        let tmp = TempDir::new().unwrap();
+        let store = LoroStore::init(tmp.path())
+            .unwrap()
+            .with_author(Some("Ada".to_string()));
+        let cache = SqliteCache::open(&tmp.path().join(".medulla")).unwrap();
+        let server = MedullaServer::new(store, cache);
+
+        for (title, assignee) in [("Write parser", "grace"), ("Ship parser", "linus")] {
+            let params = EntityCreateParams {
+                entity_type: "task".to_string(),
+                title: title.to_string(),
+                content: None,
+                tags: None,
+                properties: Some(serde_json::json!({ "assignee": assignee })),
+            };
+            server
+                .entity_create(rmcp::handler::server::wrapper::Parameters(params))
+                .await
+                .unwrap();
+        }
+
+        let search = |query: &str| SearchFulltextParams {
+            query: query.to_string(),
+            entity_type: None,
+            limit: None,
+            offset: None,
+            within_focus: None,
+            collection: None,
+        };
+        let result = server
+            .search_fulltext(rmcp::handler::server::wrapper::Parameters(search(
+                "parser created_by:ada assignee:Grace",
+            )))
+            .await
+            .unwrap();
+        if let rmcp::model::RawContent::Text(t) = &result.content[0].raw {
+            let parsed: serde_json::Value = serde_json::from_str(&t.text).unwrap();
+            assert_eq!(parsed["total"], 1);
+            assert_eq!(parsed["results"][0]["title"], "Write parser");
+        } else {
+            panic!("Expected text content");
+        }
+
+        let result = server
+            .search_fulltext(rmcp::handler::server::wrapper::Parameters(search(
+                "parser created_by:someone-else",
+            )))
+            .await
+            .unwrap();
+        if let rmcp::model::RawContent::Text(t) = &result.content[0].raw {
+            let parsed: serde_json::Value = serde_json::from_str(&t.text).unwrap();
+            assert_eq!(parsed["total"], 0);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_health_check() {
+        let (server, _tmp) = setup_test_server();
+        let params = EntityCreateParams {
+            entity_type: "note".to_string(),
+            title: "Checked".to_string(),
+            content: None,
+            tags: None,
+            properties: None,
+        };
+        server
+            .entity_create(rmcp::handler::server::wrapper::Parameters(params))
+            .await
+            .unwrap();
+
+        let result = server.health_check().await.unwrap();
+        let text = match &result.content[0].raw {
+            RawContent::Text(t) => &t.text,
+            _ => panic!("Expected text content"),
+        };
+        let report: serde_json::Value = serde_json::from_str(text).unwrap();
+        assert_eq!(report["status"], "ok");
+        assert_eq!(report["entity_count"], 1);
+        assert_eq!(report["cached_entity_count"], 1);
+        assert_eq!(report["issues"].as_array().unwrap().len(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_graph_lint_flags_missing_relations() {
+        let (server, _tmp) = setup_test_server();
+        let config = ProjectConfig::from_yaml(
+            "expected_relations:\n  - type: component\n    relation: implements\n    other_type: decision\n",
+        )
+        .unwrap();
+        let server = server.with_config(config);
+
+        for (entity_type, title) in [
+            ("decision", "Use event sourcing"),
+            ("component", "Event store"),
+            ("component", "Legacy importer"),
+        ] {
+            let params = EntityCreateParams {
+                entity_type: entity_type.to_string(),
+                title: title.to_string(),
+                content: None,
+                tags: None,
+                properties: None,
+            };
+            server
+                .entity_create(rmcp::handler::server::wrapper::Parameters(params))
+                .await
+                .unwrap();
+        }
+
+        let params = RelationCreateParams {
+            source_id: "2".to_string(),
+            target_id: "1".to_string(),
+            relation_type: "implements".to_string(),
+        };
+        server
+            .relation_create(rmcp::handler::server::wrapper::Parameters(params))
+            .await
+            .unwrap();
+
+        let result = server.graph_lint().await.unwrap();
+        if let rmcp::model::RawContent::Text(t) = &result.content[0].raw {
+            let parsed: serde_json::Value = serde_json::from_str(&t.text).unwrap();
+            assert_eq!(parsed["total"], 1);
+            assert_eq!(parsed["warnings"][0]["title"], "Legacy importer");
+            assert_eq!(parsed["warnings"][0]["relation"], "implements");
+        } else {
+            panic!("Expected text content");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_custom_relation_type() {
+        let (server, _tmp) = setup_test_server();
+        let config =
+            ProjectConfig::from_yaml("relation_types:\n  - name: tested_by\n    inverse: tests\n")
+                .unwrap();
+        let server = server.with_config(config);
+
+        for (entity_type, title) in [("component", "Parser"), ("note", "Parser test plan")] {
+            let params = EntityCreateParams {
+                entity_type: entity_type.to_string(),
+                title: title.to_string(),
+                content: None,
+                tags: None,
+                properties: None,
+            };
+            server
+                .entity_create(rmcp::handler::server::wrapper::Parameters(params))
+                .await
+                .unwrap();
+        }
+
+        let params = RelationCreateParams {
+            source_id: "1".to_string(),
+            target_id: "2".to_string(),
+            relation_type: "tested_by".to_string(),
+        };
+        server
+            .relation_create(rmcp::handler::server::wrapper::Parameters(params))
+            .await
+            .unwrap();
+
+        // Unregistered types are still rejected
+        let params = RelationCreateParams {
+            source_id: "1".to_string(),
+            target_id: "2".to_string(),
+            relation_type: "verified_by".to_string(),
+        };
+        assert!(server
+            .relation_create(rmcp::handler::server::wrapper::Parameters(params))
+            .await
+            .is_err());
+
+        let params = GraphRelationsParams {
+            id: "2".to_string(),
+            direction: None,
+        };
+        let result = server
+            .graph_relations(rmcp::handler::server::wrapper::Parameters(params))
+            .await
+            .unwrap();
+        if let rmcp::model::RawContent::Text(t) = &result.content[0].raw {
+            let parsed: serde_json::Value = serde_json::from_str(&t.text).unwrap();
+            assert_eq!(parsed["incoming"][0]["relation_type"], "tested_by");
+            assert_eq!(parsed["incoming"][0]["inverse_label"], "tests");
+        } else {
+            panic!("Expected text content");
+        }
+
+        // The relation round-trips through storage as the custom type
+        let store = server.store.lock().await;
+        let relations = store.list_relations().unwrap();
+        assert_eq!(
+            relations[0].relation_type,
+            crate::entity::RelationType::Custom("tested_by".to_string())
+        );
+    }
+
+    #[tokio::test]
+    async fn test_store_registered_relation_type() {
+        let (server, _tmp) = setup_test_server();
+
+        for title in ["Auth service", "Session store"] {
+            let params = EntityCreateParams {
+                entity_type: "component".to_string(),
+                title: title.to_string(),
+                content: None,
+                tags: None,
+                properties: None,
+            };
+            server
+                .entity_create(rmcp::handler::server::wrapper::Parameters(params))
+                .await
+                .unwrap();
+        }
+
+        let params = || RelationCreateParams {
+            source_id: "1".to_string(),
+            target_id: "2".to_string(),
+            relation_type: "depends_on".to_string(),
+        };
+        assert!(server
+            .relation_create(rmcp::handler::server::wrapper::Parameters(params()))
+            .await
+            .is_err());
+
+        // Registered while the server is running
+        server
+            .store
+            .lock()
+            .await
+            .put_relation_type(&crate::config::RelationTypeDef {
+                name: "depends_on".to_string(),
+                inverse: Some("required_by".to_string()),
+                symmetric: false,
+            })
+            .unwrap();
+        server
+            .relation_create(rmcp::handler::server::wrapper::Parameters(params()))
+            .await
+            .unwrap();
+
+        let result = server
+            .graph_relations(rmcp::handler::server::wrapper::Parameters(
+                GraphRelationsParams {
+                    id: "2".to_string(),
+                    direction: None,
+                },
+            ))
+            .await
+            .unwrap();
+        if let rmcp::model::RawContent::Text(t) = &result.content[0].raw {
+            let parsed: serde_json::Value = serde_json::from_str(&t.text).unwrap();
+            assert_eq!(parsed["incoming"][0]["inverse_label"], "required_by");
+        } else {
+            panic!("Expected text content");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_relation_create_rejects_blocks_cycle() {
+        let (server, _tmp) = setup_test_server();
+
+        for title in ["Schema", "Migration", "Backfill"] {
+            let params = EntityCreateParams {
+                entity_type: "task".to_string(),
+                title: title.to_string(),
+                content: None,
+                tags: None,
+                properties: None,
+            };
+            server
+                .entity_create(rmcp::handler::server::wrapper::Parameters(params))
+                .await
+                .unwrap();
+        }
+
+        let blocks = |source: &str, target: &str| RelationCreateParams {
+            source_id: source.to_string(),
+            target_id: target.to_string(),
+            relation_type: "blocks".to_string(),
+        };
+        for (source, target) in [("1", "2"), ("2", "3")] {
+            server
+                .relation_create(rmcp::handler::server::wrapper::Parameters(blocks(
+                    source, target,
+                )))
+                .await
+                .unwrap();
+        }
+
+        let err = server
+            .relation_create(rmcp::handler::server::wrapper::Parameters(blocks("3", "1")))
+            .await
+            .unwrap_err();
+        assert_eq!(err.code.0, error::error_codes::VALIDATION_FAILED);
+        assert!(err
+            .message
+            .contains("#3 Backfill (task) -> #1 Schema (task) -> #2 Migration (task) -> #3"));
+
+        // Other relation types may still point back
+        let params = RelationCreateParams {
+            source_id: "3".to_string(),
+            target_id: "1".to_string(),
+            relation_type: "references".to_string(),
+        };
+        server
+            .relation_create(rmcp::handler::server::wrapper::Parameters(params))
+            .await
+            .unwrap();
+        assert_eq!(server.store.lock().await.list_relations().unwrap().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_relation_batch_is_all_or_nothing() {
+        let (server, _tmp) = setup_test_server();
+
+        for (entity_type, title) in [
+            ("task", "Schema"),
+            ("task", "Migration"),
+            ("component", "Event store"),
+        ] {
+            let params = EntityCreateParams {
+                entity_type: entity_type.to_string(),
+                title: title.to_string(),
+                content: None,
+                tags: None,
+                properties: None,
+            };
+            server
+                .entity_create(rmcp::handler::server::wrapper::Parameters(params))
+                .await
+                .unwrap();
+        }
+
+        let item = |source: &str, relation_type: &str, target: &str| RelationBatchItem {
+            source: source.to_string(),
+            relation_type: relation_type.to_string(),
+            target: target.to_string(),
+        };
+        let run = |relations: Vec<RelationBatchItem>| {
+            let server = server.clone();
+            async move {
+                let result = server
+                    .relation_batch(rmcp::handler::server::wrapper::Parameters(
+                        RelationBatchParams {
+                            relations,
+                            dry_run: None,
+                        },
+                    ))
+                    .await
+                    .unwrap();
+                let RawContent::Text(t) = &result.content[0].raw else {
+                    panic!("Expected text content");
+                };
+                serde_json::from_str::<BatchResult>(&t.text).unwrap()
+            }
+        };
+
+        // A cycle closed within the batch and an unknown title fail, so the
+        // valid first row is not written either
+        let result = run(vec![
+            item("Schema", "blocks", "2"),
+            item("Migration", "blocks", "Schema"),
+            item("Nowhere", "references", "3"),
+        ])
+        .await;
+        assert_eq!(result.succeeded, 1);
+        assert_eq!(result.failed, 2);
+        assert!(result.abort_reason.is_some());
+        assert!(!result.results[1].success);
+        assert!(!result.results[2].success);
+        assert!(server
+            .store
+            .lock()
+            .await
+            .list_relations()
+            .unwrap()
+            .is_empty());
+
+        // Titles, sequence numbers and UUID prefixes all resolve
+        let component_id = {
+            let store = server.store.lock().await;
+            store.lookup_id("3", None).unwrap().0.to_string()
+        };
+        let result = run(vec![
+            item("Schema", "blocks", "2"),
+            item("Migration", "references", &component_id[..8]),
+        ])
+        .await;
+        assert_eq!(result.succeeded, 2);
+        assert!(result.abort_reason.is_none());
+        assert_eq!(server.store.lock().await.list_relations().unwrap().len(), 2);
+        let cache = server.cache.lock().await;
+        assert_eq!(cache.get_stats().unwrap().relations, 2);
+    }
+
+    #[tokio::test]
+    async fn test_route_project() {
+        let (server, _tmp) = setup_test_server();
+        let (other, _other_tmp) = setup_test_server();
+
+        let mut args = serde_json::Map::new();
+        args.insert("project".to_string(), serde_json::json!("other"));
+        assert!(server.route_project(Some(&mut args)).is_err());
+
+        let server = server.with_projects(BTreeMap::from([("other".to_string(), other)]));
+        let routed = server.route_project(Some(&mut args)).unwrap();
+        assert!(!args.contains_key("project"));
+        assert!(server.route_project(None).is_ok());
+
+        // Tools on the routed server use that project's store
+        let params = EntityCreateParams {
+            entity_type: "note".to_string(),
+            title: "Only in other".to_string(),
+            content: None,
+            tags: None,
+            properties: None,
+        };
+        routed
+            .entity_create(rmcp::handler::server::wrapper::Parameters(params))
+            .await
+            .unwrap();
+        assert_eq!(routed.store.lock().await.list_notes().unwrap().len(), 1);
+        assert!(server.store.lock().await.list_notes().unwrap().is_empty());
+
+        args.insert("project".to_string(), serde_json::json!("missing"));
+        let Err(err) = server.route_project(Some(&mut args)) else {
+            panic!("routed to an unknown project");
+        };
+        assert!(err.to_string().contains("Served projects: other"));
+    }
+
+    #[tokio::test]
+    async fn test_entity_content_mentions_become_references() {
+        let (server, _tmp) = setup_test_server();
+
+        for (entity_type, title, content) in [
+            ("decision", "Use Postgres", None),
+            ("note", "Benchmarks", Some("Numbers behind #1")),
+        ] {
+            let params = EntityCreateParams {
+                entity_type: entity_type.to_string(),
+                title: title.to_string(),
+                content: content.map(String::from),
+                tags: None,
+                properties: None,
+            };
+            server
+                .entity_create(rmcp::handler::server::wrapper::Parameters(params))
+                .await
+                .unwrap();
+        }
+
+        let params = EntityUpdateParams {
+            id: "1".to_string(),
+            title: None,
+            content: Some("Chosen after the benchmarks in med-2".to_string()),
+            add_tags: None,
+            remove_tags: None,
+            properties: None,
+            force: None,
+        };
+        server
+            .entity_update(rmcp::handler::server::wrapper::Parameters(params))
+            .await
+            .unwrap();
+
+        let store = server.store.lock().await;
+        let relations = store.list_relations().unwrap();
+        assert_eq!(relations.len(), 2);
+        assert!(relations
+            .iter()
+            .all(|r| r.relation_type == crate::entity::RelationType::References));
+        let cache = server.cache.lock().await;
+        let note_id = store.lookup_id("2", None).unwrap().0.to_string();
+        assert_eq!(cache.get_relations_from(&note_id).unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_task_tree() {
+        let (server, _tmp) = setup_test_server();
+
+        for (title, status) in [
+            ("Release 2.0", "in_progress"),
+            ("Changelog", "done"),
+            ("Packaging", "todo"),
+        ] {
+            let params = EntityCreateParams {
+                entity_type: "task".to_string(),
+                title: title.to_string(),
+                content: None,
+                tags: None,
+                properties: Some(serde_json::json!({ "status": status })),
+            };
+            server
+                .entity_create(rmcp::handler::server::wrapper::Parameters(params))
+                .await
+                .unwrap();
+        }
+        for (source, target, relation_type) in [("1", "2", "parent_of"), ("3", "1", "child_of")] {
+            let params = RelationCreateParams {
+                source_id: source.to_string(),
+                target_id: target.to_string(),
+                relation_type: relation_type.to_string(),
+            };
+            server
+                .relation_create(rmcp::handler::server::wrapper::Parameters(params))
+                .await
+                .unwrap();
+        }
+
+        let params = TaskTreeParams {
+            id: "1".to_string(),
+        };
+        let result = server
+            .task_tree(rmcp::handler::server::wrapper::Parameters(params))
+            .await
+            .unwrap();
+        if let rmcp::model::RawContent::Text(t) = &result.content[0].raw {
+            let parsed: serde_json::Value = serde_json::from_str(&t.text).unwrap();
+            assert_eq!(parsed["subtasks_done"], 1);
+            assert_eq!(parsed["subtasks_total"], 2);
+            assert_eq!(parsed["children"][1]["title"], "Packaging");
+            assert!(parsed["rendered"]
+                .as_str()
+                .unwrap()
+                .starts_with("001 [in_progress] Release 2.0 (1/2 done)"));
+        } else {
+            panic!("Expected text content");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_milestone_progress() {
+        let (server, _tmp) = setup_test_server();
+
+        let params = EntityCreateParams {
+            entity_type: "milestone".to_string(),
+            title: "Public beta".to_string(),
+            content: None,
+            tags: None,
+            properties: Some(serde_json::json!({ "target_date": "2026-06-30" })),
+        };
+        server
+            .entity_create(rmcp::handler::server::wrapper::Parameters(params))
+            .await
+            .unwrap();
+        for (title, status) in [("Invite flow", "done"), ("Usage limits", "todo")] {
+            let params = EntityCreateParams {
+                entity_type: "task".to_string(),
+                title: title.to_string(),
+                content: None,
+                tags: None,
+                properties: Some(serde_json::json!({ "status": status })),
+            };
+            server
+                .entity_create(rmcp::handler::server::wrapper::Parameters(params))
+                .await
+                .unwrap();
+        }
+        for source in ["2", "3"] {
+            let params = RelationCreateParams {
+                source_id: source.to_string(),
+                target_id: "1".to_string(),
+                relation_type: "belongs_to".to_string(),
+            };
+            server
+                .relation_create(rmcp::handler::server::wrapper::Parameters(params))
+                .await
+                .unwrap();
+        }
+
+        let result = server
+            .milestone_progress(rmcp::handler::server::wrapper::Parameters(
+                MilestoneProgressParams {
+                    id: Some("1".to_string()),
+                },
+            ))
+            .await
+            .unwrap();
+        if let rmcp::model::RawContent::Text(t) = &result.content[0].raw {
+            let parsed: serde_json::Value = serde_json::from_str(&t.text).unwrap();
+            assert_eq!(parsed["target_date"], "2026-06-30");
+            assert_eq!(parsed["tasks_done"], 1);
+            assert_eq!(parsed["tasks_total"], 2);
+            assert_eq!(parsed["percent_complete"], 50);
+        } else {
+            panic!("Expected text content");
+        }
+
+        let result = server
+            .milestone_progress(rmcp::handler::server::wrapper::Parameters(
+                MilestoneProgressParams { id: None },
+            ))
+            .await
+            .unwrap();
+        if let rmcp::model::RawContent::Text(t) = &result.content[0].raw {
+            let parsed: serde_json::Value = serde_json::from_str(&t.text).unwrap();
+            assert_eq!(parsed["milestones"].as_array().unwrap().len(), 1);
+        }
+
+        // A task is not a milestone
+        assert!(server
+            .milestone_progress(rmcp::handler::server::wrapper::Parameters(
+                MilestoneProgressParams {
+                    id: Some("2".to_string()),
+                },
+            ))
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_task_plan_orders_blockers_first() {
+        let (server, _tmp) = setup_test_server();
+
+        // 1 (low) blocks 2 (urgent), which blocks 3; 4 (high) is independent
+        for (title, priority) in [
+            ("Design schema", "low"),
+            ("Write migration", "urgent"),
+            ("Backfill data", "normal"),
+            ("Update docs", "high"),
+        ] {
+            let params = EntityCreateParams {
+                entity_type: "task".to_string(),
+                title: title.to_string(),
+                content: None,
+                tags: None,
+                properties: Some(serde_json::json!({ "priority": priority })),
+            };
+            server
+                .entity_create(rmcp::handler::server::wrapper::Parameters(params))
+                .await
+                .unwrap();
+        }
+        for (source, target) in [("1", "2"), ("2", "3")] {
+            let params = RelationCreateParams {
+                source_id: source.to_string(),
+                target_id: target.to_string(),
+                relation_type: "blocks".to_string(),
+            };
+            server
+                .relation_create(rmcp::handler::server::wrapper::Parameters(params))
+                .await
+                .unwrap();
+        }
+
+        let result = server.task_plan().await.unwrap();
+        if let rmcp::model::RawContent::Text(t) = &result.content[0].raw {
+            let parsed: serde_json::Value = serde_json::from_str(&t.text).unwrap();
+            let order: Vec<u64> = parsed["tasks"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|t| t["sequence_number"].as_u64().unwrap())
+                .collect();
+            assert_eq!(order, vec![4, 1, 2, 3]);
+            assert!(parsed["cycles"].as_array().unwrap().is_empty());
+        } else {
+            panic!("Expected text content");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_redact_fields_in_tool_results() {
+        let (server, _tmp) = setup_test_server();
+        let config = ProjectConfig::from_yaml("redact_fields: [assignee]\n").unwrap();
+        let server = server.with_config(config);
+
+        let params = EntityCreateParams {
+            entity_type: "task".to_string(),
+            title: "Rotate credentials".to_string(),
+            content: None,
+            tags: None,
+            properties: Some(serde_json::json!({
+                "assignee": "alice",
+                "priority": "high"
+            })),
+        };
+        server
+            .entity_create(rmcp::handler::server::wrapper::Parameters(params))
+            .await
+            .unwrap();
+
+        let params = EntityGetParams {
+            id: "1".to_string(),
+            entity_type: None,
+            include_relations: None,
+            include_related_entities: None,
+            depth: None,
+        };
+        let result = server
+            .entity_get(rmcp::handler::server::wrapper::Parameters(params))
+            .await
+            .unwrap();
+        let result = server.redact_result(result);
+        if let rmcp::model::RawContent::Text(t) = &result.content[0].raw {
+            assert!(!t.text.contains("alice"));
+            let parsed: serde_json::Value = serde_json::from_str(&t.text).unwrap();
+            assert_eq!(parsed["properties"]["assignee"], crate::config::REDACTED);
+            assert_eq!(parsed["properties"]["priority"], "high");
+            assert_eq!(parsed["title"], "Rotate credentials");
+        } else {
+            panic!("Expected text content");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_graph_export_mermaid() {
+        let (server, _tmp) = setup_test_server();
+
+        for (entity_type, title) in [("decision", "Use SQLite"), ("task", "Add cache")] {
+            let params = EntityCreateParams {
+                entity_type: entity_type.to_string(),
+                title: title.to_string(),
+                content: None,
+                tags: None,
+                properties: None,
+            };
+            server
+                .entity_create(rmcp::handler::server::wrapper::Parameters(params))
+                .await
+                .unwrap();
+        }
+        let params = RelationCreateParams {
+            source_id: "2".to_string(),
+            target_id: "1".to_string(),
+            relation_type: "implements".to_string(),
+        };
+        server
+            .relation_create(rmcp::handler::server::wrapper::Parameters(params))
+            .await
+            .unwrap();
+
+        let params = GraphExportParams {
+            format: Some("mermaid".to_string()),
+            root: Some("1".to_string()),
+            depth: Some(1),
+        };
+        let result = server
+            .graph_export(rmcp::handler::server::wrapper::Parameters(params))
+            .await
+            .unwrap();
+        if let rmcp::model::RawContent::Text(t) = &result.content[0].raw {
+            let parsed: serde_json::Value = serde_json::from_str(&t.text).unwrap();
+            assert_eq!(parsed["nodes"], 2);
+            assert_eq!(parsed["edges"], 1);
+            let content = parsed["content"].as_str().unwrap();
+            assert!(content.contains("e2 -->|implements| e1"));
+        } else {
+            panic!("Expected text content");
+        }
+
+        let params = GraphExportParams {
+            format: Some("svg".to_string()),
+            root: None,
+            depth: None,
+        };
+        assert!(server
+            .graph_export(rmcp::handler::server::wrapper::Parameters(params))
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_graph_analytics() {
+        let (server, _tmp) = setup_test_server();
+
+        for (entity_type, title) in [
+            ("decision", "Use SQLite"),
+            ("task", "Add cache"),
+            ("task", "Add migrations"),
+            ("note", "Unlinked"),
+        ] {
+            let params = EntityCreateParams {
+                entity_type: entity_type.to_string(),
+                title: title.to_string(),
+                content: None,
+                tags: None,
+                properties: None,
+            };
+            server
+                .entity_create(rmcp::handler::server::wrapper::Parameters(params))
+                .await
+                .unwrap();
+        }
+        for source in ["2", "3"] {
+            let params = RelationCreateParams {
+                source_id: source.to_string(),
+                target_id: "1".to_string(),
+                relation_type: "implements".to_string(),
+            };
+            server
+                .relation_create(rmcp::handler::server::wrapper::Parameters(params))
+                .await
+                .unwrap();
+        }
+
+        let result = server
+            .graph_analytics(rmcp::handler::server::wrapper::Parameters(
+                GraphAnalyticsParams { limit: Some(1) },
+            ))
+            .await
+            .unwrap();
+        if let rmcp::model::RawContent::Text(t) = &result.content[0].raw {
+            let parsed: serde_json::Value = serde_json::from_str(&t.text).unwrap();
+            assert_eq!(parsed["nodes"], 4);
+            assert_eq!(parsed["edges"], 2);
+            assert_eq!(parsed["isolated"], 1);
+            assert_eq!(parsed["hubs"].as_array().unwrap().len(), 1);
+            assert_eq!(parsed["hubs"][0]["title"], "Use SQLite");
+            assert_eq!(parsed["hubs"][0]["degree"], 2);
+            assert_eq!(parsed["components"][0]["size"], 3);
+        } else {
+            panic!("Expected text content");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_entity_create_from_template() {
+        let (server, _tmp) = setup_test_server();
+        {
+            let store = server.store.lock().await;
+            let mut template =
+                crate::entity::EntityTemplate::new("adr".to_string(), "decision".to_string());
+            template.content = Some("# {{title}}\n\n## Context\n".to_string());
+            template.tags = vec!["adr".to_string()];
+            template
+                .properties
+                .insert("status".to_string(), serde_json::json!("proposed"));
+            store.put_template(&template).unwrap();
+        }
+
+        let params = EntityCreateFromTemplateParams {
+            template: "adr".to_string(),
+            title: "Use Postgres".to_string(),
+            content: None,
+            tags: Some(vec!["db".to_string()]),
+            properties: None,
+        };
+        let result = server
+            .entity_create_from_template(rmcp::handler::server::wrapper::Parameters(params))
+            .await
+            .unwrap();
+        if let rmcp::model::RawContent::Text(t) = &result.content[0].raw {
+            let parsed: serde_json::Value = serde_json::from_str(&t.text).unwrap();
+            assert_eq!(parsed["type"], "decision");
+            assert_eq!(parsed["content"], "# Use Postgres\n\n## Context\n");
+            assert_eq!(parsed["tags"], serde_json::json!(["adr", "db"]));
+            assert_eq!(parsed["properties"]["status"], "proposed");
+        } else {
+            panic!("Expected text content");
+        }
+
+        let params = EntityCreateFromTemplateParams {
+            template: "rfc".to_string(),
+            title: "Anything".to_string(),
+            content: None,
+            tags: None,
+            properties: None,
+        };
+        let err = server
+            .entity_create_from_template(rmcp::handler::server::wrapper::Parameters(params))
+            .await
+            .unwrap_err();
+        assert!(err.message.contains("Available: adr"));
+    }
+
+    #[tokio::test]
+    async fn test_entity_history() {
+        let (server, _tmp) = setup_test_server();
+
+        let params = EntityCreateParams {
+            entity_type: "note".to_string(),
+            title: "First draft".to_string(),
+            content: None,
+            tags: None,
+            properties: None,
+        };
+        server
+            .entity_create(rmcp::handler::server::wrapper::Parameters(params))
+            .await
+            .unwrap();
+        let params = EntityUpdateParams {
+            id: "1".to_string(),
+            title: Some("Second draft".to_string()),
+            content: None,
+            add_tags: None,
+            remove_tags: None,
+            properties: None,
+            force: None,
+        };
+        server
+            .entity_update(rmcp::handler::server::wrapper::Parameters(params))
+            .await
+            .unwrap();
+
+        let params = EntityHistoryParams {
+            id: "1".to_string(),
+            at: None,
+        };
+        let result = server
+            .entity_history(rmcp::handler::server::wrapper::Parameters(params))
+            .await
+            .unwrap();
+        if let rmcp::model::RawContent::Text(t) = &result.content[0].raw {
+            let parsed: serde_json::Value = serde_json::from_str(&t.text).unwrap();
+            assert_eq!(parsed["total"], 2);
+            let changed = parsed["versions"][1]["changed_fields"].as_array().unwrap();
+            assert!(changed.iter().any(|f| f == "title"));
+        } else {
+            panic!("Expected text content");
+        }
+
+        // Nothing existed before the entity was created
+        let params = EntityHistoryParams {
+            id: "1".to_string(),
+            at: Some("2000-01-01".to_string()),
+        };
+        assert!(server
+            .entity_history(rmcp::handler::server::wrapper::Parameters(params))
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_task_overdue_and_due() {
+        let (server, _tmp) = setup_test_server();
+        let today = chrono::Utc::now().date_naive();
+
+        for (title, offset) in [("Late", -3i64), ("Soon", 2), ("Far", 30)] {
+            let due = today + chrono::Duration::days(offset);
+            let params = EntityCreateParams {
+                entity_type: "task".to_string(),
+                title: title.to_string(),
+                content: None,
+                tags: None,
+                properties: Some(serde_json::json!({ "due_date": due.to_string() })),
+            };
+            server
+                .entity_create(rmcp::handler::server::wrapper::Parameters(params))
+                .await
+                .unwrap();
+        }
+
+        let result = server
+            .task_overdue(rmcp::handler::server::wrapper::Parameters(
+                TaskOverdueParams { limit: None },
+            ))
+            .await
+            .unwrap();
+        if let rmcp::model::RawContent::Text(t) = &result.content[0].raw {
+            let parsed: serde_json::Value = serde_json::from_str(&t.text).unwrap();
+            assert_eq!(parsed["total"], 1);
+            assert_eq!(parsed["tasks"][0]["title"], "Late");
+            assert_eq!(parsed["tasks"][0]["days_until_due"], -3);
+        } else {
+            panic!("Expected text content");
+        }
+
+        let result = server
+            .task_due(rmcp::handler::server::wrapper::Parameters(TaskDueParams {
+                within: None,
+                limit: None,
+            }))
+            .await
+            .unwrap();
+        if let rmcp::model::RawContent::Text(t) = &result.content[0].raw {
+            let parsed: serde_json::Value = serde_json::from_str(&t.text).unwrap();
+            assert_eq!(parsed["within_days"], 7);
+            assert_eq!(parsed["total"], 1);
+            assert_eq!(parsed["tasks"][0]["title"], "Soon");
+        } else {
+            panic!("Expected text content");
+        }
+
+        let result = server
+            .task_due(rmcp::handler::server::wrapper::Parameters(TaskDueParams {
+                within: Some("5w".to_string()),
+                limit: None,
+            }))
+            .await
+            .unwrap();
+        if let rmcp::model::RawContent::Text(t) = &result.content[0].raw {
+            let parsed: serde_json::Value = serde_json::from_str(&t.text).unwrap();
+            assert_eq!(parsed["total"], 2);
+        } else {
+            panic!("Expected text content");
+        }
+
+        assert!(server
+            .task_due(rmcp::handler::server::wrapper::Parameters(TaskDueParams {
+                within: Some("soon".to_string()),
+                limit: None,
+            }))
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_task_by_assignee() {
+        let (server, _tmp) = setup_test_server();
+
+        for (title, assignee) in [("Fix login", Some("alice")), ("Triage", None)] {
+            let params = EntityCreateParams {
+                entity_type: "task".to_string(),
+                title: title.to_string(),
+                content: None,
+                tags: None,
+                properties: assignee.map(|a| serde_json::json!({ "assignee": a })),
+            };
+            server
+                .entity_create(rmcp::handler::server::wrapper::Parameters(params))
+                .await
+                .unwrap();
+        }
+
+        let result = server
+            .task_by_assignee(rmcp::handler::server::wrapper::Parameters(
+                TaskByAssigneeParams { assignee: None },
+            ))
+            .await
+            .unwrap();
+        if let rmcp::model::RawContent::Text(t) = &result.content[0].raw {
+            let parsed: serde_json::Value = serde_json::from_str(&t.text).unwrap();
+            assert_eq!(parsed["total_open"], 2);
+            assert_eq!(parsed["assignees"][0]["assignee"], "alice");
+            assert_eq!(parsed["assignees"][0]["tasks"][0]["title"], "Fix login");
+            assert!(parsed["assignees"][1]["assignee"].is_null());
+        } else {
+            panic!("Expected text content");
+        }
+
+        let result = server
+            .task_by_assignee(rmcp::handler::server::wrapper::Parameters(
+                TaskByAssigneeParams {
+                    assignee: Some("Alice".to_string()),
+                },
+            ))
+            .await
+            .unwrap();
+        if let rmcp::model::RawContent::Text(t) = &result.content[0].raw {
+            let parsed: serde_json::Value = serde_json::from_str(&t.text).unwrap();
+            assert_eq!(parsed["total_open"], 1);
+        } else {
+            panic!("Expected text content");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_task_ready() {
+        let (server, _tmp) = setup_test_server();
+
+        // Create tasks
+        for (title, priority) in [("Task High", "high"), ("Task Low", "low")] {
+            let params = EntityCreateParams {
+                entity_type: "task".to_string(),
+                title: title.to_string(),
+                content: None,
+                tags: None,
+                properties: Some(serde_json::json!({
+                    "status": "todo",
+                    "priority": priority
+                })),
+            };
+            server
+                .entity_create(rmcp::handler::server::wrapper::Parameters(params))
+                .await
+                .unwrap();
+        }
+
+        let ready_params = TaskReadyParams {
+            limit: None,
+            respect_wip_limits: None,
+        };
+
+        let result = server
+            .task_ready(rmcp::handler::server::wrapper::Parameters(ready_params))
+            .await;
+
+        assert!(result.is_ok());
+        let tool_result = result.unwrap();
+        if let rmcp::model::RawContent::Text(t) = &tool_result.content[0].raw {
+            let parsed: serde_json::Value = serde_json::from_str(&t.text).unwrap();
+            assert_eq!(parsed["total"], 2);
+            // High priority should come first
+            assert!(parsed["tasks"][0]["title"]
+                .as_str()
+                .unwrap()
+                .contains("High"));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_relation_resolve_unblocks_task() {
+        let (server, _tmp) = setup_test_server();
+
+        for title in ["Blocker", "Blocked"] {
+            let params = EntityCreateParams {
+                entity_type: "task".to_string(),
+                title: title.to_string(),
+                content: None,
+                tags: None,
+                properties: None,
+            };
+            server
+                .entity_create(rmcp::handler::server::wrapper::Parameters(params))
+                .await
+                .unwrap();
+        }
+
+        let params = RelationCreateParams {
+            source_id: "1".to_string(),
+            target_id: "2".to_string(),
+            relation_type: "blocks".to_string(),
+        };
+        server
+            .relation_create(rmcp::handler::server::wrapper::Parameters(params))
+            .await
+            .unwrap();
+
+        let ready_total = |result: CallToolResult| {
+            if let rmcp::model::RawContent::Text(t) = &result.content[0].raw {
+                let parsed: serde_json::Value = serde_json::from_str(&t.text).unwrap();
+                parsed["total"].as_u64().unwrap()
+            } else {
+                panic!("expected text content")
+            }
+        };
+
+        let result = server
+            .task_ready(rmcp::handler::server::wrapper::Parameters(
+                TaskReadyParams {
+                    limit: None,
+                    respect_wip_limits: None,
+                },
+            ))
+            .await
+            .unwrap();
+        assert_eq!(ready_total(result), 1);
+
+        let params = RelationResolveParams {
+            source_id: "1".to_string(),
+            target_id: "2".to_string(),
+            relation_type: "blocks".to_string(),
+        };
+        let result = server
+            .relation_resolve(rmcp::handler::server::wrapper::Parameters(params))
+            .await
+            .unwrap();
+        if let rmcp::model::RawContent::Text(t) = &result.content[0].raw {
+            let parsed: serde_json::Value = serde_json::from_str(&t.text).unwrap();
+            assert!(parsed["resolved_at"].is_string());
+        }
+
+        // Both tasks are now ready
+        let result = server
+            .task_ready(rmcp::handler::server::wrapper::Parameters(
+                TaskReadyParams {
+                    limit: None,
+                    respect_wip_limits: None,
+                },
+            ))
+            .await
+            .unwrap();
+        assert_eq!(ready_total(result), 2);
+
+        // The relation is retained for history
+        let store = server.store.lock().await;
+        let relations = store.list_relations().unwrap();
+        assert_eq!(relations.len(), 1);
+        assert!(!relations[0].is_active());
+    }
+
+    #[tokio::test]
+    async fn test_task_next() {
+        let (server, _tmp) = setup_test_server();
+
+        // Create tasks with different priorities
+        for (title, priority) in [("Normal Task", "normal"), ("Urgent Task", "urgent")] {
+            let params = EntityCreateParams {
+                entity_type: "task".to_string(),
+                title: title.to_string(),
+                content: None,
+                tags: None,
+                properties: Some(serde_json::json!({
+                    "status": "todo",
+                    "priority": priority
+                })),
+            };
+            server
+                .entity_create(rmcp::handler::server::wrapper::Parameters(params))
+                .await
+                .unwrap();
+        }
+
+        let result = server
+            .task_next(Parameters(TaskNextParams::default()))
+            .await;
+
+        assert!(result.is_ok());
+        let tool_result = result.unwrap();
+        if let rmcp::model::RawContent::Text(t) = &tool_result.content[0].raw {
+            let parsed: serde_json::Value = serde_json::from_str(&t.text).unwrap();
+            // Urgent should be first
+            assert!(parsed["title"].as_str().unwrap().contains("Urgent"));
+            // With the score that put it there
+            assert_eq!(parsed["score"]["priority"], 3.0);
+            assert_eq!(parsed["score"]["total"], 3.0);
+            assert_eq!(parsed["unblocks"], 0);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_task_complete() {
+        let (server, _tmp) = setup_test_server();
+
+        // Create a task
+        let params = EntityCreateParams {
+            entity_type: "task".to_string(),
+            title: "Task to Complete".to_string(),
+            content: None,
+            tags: None,
+            properties: Some(serde_json::json!({ "status": "todo" })),
+        };
+        server
+            .entity_create(rmcp::handler::server::wrapper::Parameters(params))
+            .await
+            .unwrap();
+
+        // Complete it
+        let complete_params = TaskCompleteParams {
+            id: "1".to_string(),
+        };
+
+        let result = server
+            .task_complete(rmcp::handler::server::wrapper::Parameters(complete_params))
+            .await;
+
+        assert!(result.is_ok());
+
+        // Verify it's done
+        let store = server.store.lock().await;
+        let tasks = store.list_tasks().unwrap();
+        assert_eq!(tasks[0].status, crate::entity::TaskStatus::Done);
+    }
+
+    #[tokio::test]
+    async fn test_task_complete_recurring() {
+        let (server, _tmp) = setup_test_server();
+
+        let params = EntityCreateParams {
+            entity_type: "task".to_string(),
+            title: "Renew certificates".to_string(),
+            content: None,
+            tags: Some(vec!["ops".to_string()]),
+            properties: Some(serde_json::json!({
+                "due_date": "2020-01-06",
+                "recurrence": "every 2 weeks"
+            })),
+        };
+        server
+            .entity_create(rmcp::handler::server::wrapper::Parameters(params))
+            .await
+            .unwrap();
+
+        let complete = || TaskCompleteParams {
+            id: "1".to_string(),
+        };
+        let result = server
+            .task_complete(rmcp::handler::server::wrapper::Parameters(complete()))
+            .await
+            .unwrap();
+        if let rmcp::model::RawContent::Text(t) = &result.content[0].raw {
+            let parsed: serde_json::Value = serde_json::from_str(&t.text).unwrap();
+            assert_eq!(parsed["properties"]["status"], "done");
+            let next = &parsed["next_occurrence"];
+            assert_eq!(next["sequence_number"], 2);
+            assert_eq!(next["properties"]["status"], "todo");
+            assert_eq!(next["properties"]["recurrence"], "every 2 weeks");
+            // Counted on from the old due date, landing after today
+            let due: chrono::NaiveDate = next["properties"]["due_date"]
+                .as_str()
+                .unwrap()
+                .parse()
+                .unwrap();
+            assert!(due > chrono::Utc::now().date_naive());
+            assert_eq!(
+                (due - chrono::NaiveDate::from_ymd_opt(2020, 1, 6).unwrap()).num_days() % 14,
+                0
+            );
+        } else {
+            panic!("Expected text content");
+        }
+
+        // Completing it again does not spawn another occurrence
+        server
+            .task_complete(rmcp::handler::server::wrapper::Parameters(complete()))
+            .await
+            .unwrap();
+        let store = server.store.lock().await;
+        assert_eq!(store.list_tasks().unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_graph_relations() {
+        let (server, _tmp) = setup_test_server();
+
+        // Create two decisions
+        for title in ["Decision A", "Decision B"] {
+            let params = EntityCreateParams {
+                entity_type: "decision".to_string(),
+                title: title.to_string(),
+                content: None,
+                tags: None,
+                properties: None,
+            };
+            server
+                .entity_create(rmcp::handler::server::wrapper::Parameters(params))
+                .await
+                .unwrap();
+        }
+
+        // Add a relation
+        {
+            let store = server.store.lock().await;
+            let decisions = store.list_decisions().unwrap();
+            let relation = crate::entity::Relation::new(
+                decisions[0].base.id,
+                "decision".to_string(),
+                decisions[1].base.id,
+                "decision".to_string(),
+                crate::entity::RelationType::Supersedes,
+            );
+            store.add_relation(&relation).unwrap();
+            store.save().unwrap();
+        }
+
+        // Query relations
+        let params = GraphRelationsParams {
+            id: "1".to_string(),
+            direction: Some("both".to_string()),
+        };
+
+        let result = server
+            .graph_relations(rmcp::handler::server::wrapper::Parameters(params))
+            .await;
 
         assert!(result.is_ok());
-        let read_result = result.unwrap();
-        assert_eq!(read_result.contents.len(), 1);
-
-        if let ResourceContents::TextResourceContents { text, .. } = &read_result.contents[0] {
-            let parsed: serde_json::Value = serde_json::from_str(text).unwrap();
-            assert!(parsed["entity_counts"].is_object());
-            assert!(parsed["medulla_version"].is_string());
-        } else {
-            panic!("Expected TextResourceContents");
+        let tool_result = result.unwrap();
+        if let rmcp::model::RawContent::Text(t) = &tool_result.content[0].raw {
+            let parsed: serde_json::Value = serde_json::from_str(&t.text).unwrap();
+            assert!(!parsed["outgoing"].as_array().unwrap().is_empty());
         }
     }
 
     #[tokio::test]
-    async fn test_read_resource_invalid_uri() {
+    async fn test_graph_orphans() {
         let (server, _tmp) = setup_test_server();
-        let result =
-            resources::read_resource("invalid://schema", &server.store, &server.cache).await;
 
-        assert!(result.is_err());
-    }
+        // Create an orphan entity (no relations)
+        let params = EntityCreateParams {
+            entity_type: "decision".to_string(),
+            title: "Orphan Decision".to_string(),
+            content: None,
+            tags: None,
+            properties: None,
+        };
+        server
+            .entity_create(rmcp::handler::server::wrapper::Parameters(params))
+            .await
+            .unwrap();
 
-    #[tokio::test]
-    async fn test_subscription_via_server() {
-        let (server, _tmp) = setup_test_server();
+        let orphan_params = GraphOrphansParams {
+            entity_type: None,
+            limit: None,
+            offset: None,
+        };
 
-        // Subscribe directly via subscription state
-        {
-            let mut subs = server.subscriptions.lock().await;
-            let _id = subs.subscribe("medulla://entities/task");
-            assert!(!subs.get_subscribers("medulla://entities/task").is_empty());
-        }
+        let result = server
+            .graph_orphans(rmcp::handler::server::wrapper::Parameters(orphan_params))
+            .await;
 
-        // Unsubscribe via removing by URI
-        {
-            let mut subs = server.subscriptions.lock().await;
-            subs.by_resource.remove("medulla://entities/task");
-            assert!(subs.get_subscribers("medulla://entities/task").is_empty());
+        assert!(result.is_ok());
+        let tool_result = result.unwrap();
+        if let rmcp::model::RawContent::Text(t) = &tool_result.content[0].raw {
+            let parsed: serde_json::Value = serde_json::from_str(&t.text).unwrap();
+            assert_eq!(parsed["total"], 1);
         }
     }
 
-    // ========================================================================
-    // MCP Tool Unit Tests
-    // ========================================================================
-
     #[tokio::test]
-    async fn test_entity_create_decision() {
+    async fn test_entity_batch_mixed_operations() {
         let (server, _tmp) = setup_test_server();
 
-        let params = EntityCreateParams {
+        // First, create an entity to update/delete later
+        let create_params = EntityCreateParams {
             entity_type: "decision".to_string(),
-            title: "Use Rust for CLI".to_string(),
-            content: Some("We decided to use Rust for the CLI implementation.".to_string()),
-            tags: Some(vec!["lang".to_string(), "tooling".to_string()]),
-            properties: Some(serde_json::json!({
-                "status": "accepted",
-                "context": "Need a fast, reliable CLI"
-            })),
+            title: "Existing Decision".to_string(),
+            content: None,
+            tags: None,
+            properties: None,
+        };
+        server
+            .entity_create(rmcp::handler::server::wrapper::Parameters(create_params))
+            .await
+            .unwrap();
+
+        // Batch with create, update, delete (and one invalid delete)
+        let batch_params = EntityBatchParams {
+            operations: vec![
+                BatchOperation::Create(EntityCreateParams {
+                    entity_type: "decision".to_string(),
+                    title: "New via Batch".to_string(),
+                    content: None,
+                    tags: None,
+                    properties: None,
+                }),
+                BatchOperation::Update(EntityUpdateParams {
+                    id: "1".to_string(),
+                    title: Some("Updated via Batch".to_string()),
+                    content: None,
+                    add_tags: None,
+                    remove_tags: None,
+                    properties: None,
+                    force: None,
+                }),
+                BatchOperation::Delete(EntityDeleteParams {
+                    id: "999".to_string(), // doesn't exist
+                }),
+            ],
+            atomic: None,
         };
 
         let result = server
-            .entity_create(rmcp::handler::server::wrapper::Parameters(params))
+            .entity_batch(rmcp::handler::server::wrapper::Parameters(batch_params))
             .await;
 
         assert!(result.is_ok());
         let tool_result = result.unwrap();
-        assert!(!tool_result.is_error.unwrap_or(false));
-
-        // Verify the entity was created
-        let store = server.store.lock().await;
-        let decisions = store.list_decisions().unwrap();
-        assert_eq!(decisions.len(), 1);
-        assert_eq!(decisions[0].base.title, "Use Rust for CLI");
-        assert_eq!(decisions[0].status, crate::entity::DecisionStatus::Accepted);
+        if let rmcp::model::RawContent::Text(t) = &tool_result.content[0].raw {
+            let parsed: serde_json::Value = serde_json::from_str(&t.text).unwrap();
+            assert_eq!(parsed["succeeded"], 2); // create and update succeed
+            assert_eq!(parsed["failed"], 1); // delete fails
+        }
     }
 
     #[tokio::test]
-    async fn test_entity_create_task() {
+    async fn test_entity_update_workflow() {
         let (server, _tmp) = setup_test_server();
+        let config = crate::config::ProjectConfig::from_yaml(
+            "workflows:\n  - type: task\n    transitions:\n      todo: [in_progress]\n      in_progress: [done]\n",
+        )
+        .unwrap();
+        let server = server.with_config(config);
 
-        let params = EntityCreateParams {
-            entity_type: "task".to_string(),
-            title: "Implement auth".to_string(),
+        server
+            .entity_create(Parameters(EntityCreateParams {
+                entity_type: "task".to_string(),
+                title: "Ship it".to_string(),
+                content: None,
+                tags: None,
+                properties: None,
+            }))
+            .await
+            .unwrap();
+
+        let set_status = |status: &str, force: Option<bool>| EntityUpdateParams {
+            id: "1".to_string(),
+            title: None,
             content: None,
-            tags: Some(vec!["backend".to_string()]),
-            properties: Some(serde_json::json!({
-                "status": "todo",
-                "priority": "high",
-                "due_date": "2025-03-01",
-                "assignee": "alice"
-            })),
+            add_tags: None,
+            remove_tags: None,
+            properties: Some(serde_json::json!({ "status": status })),
+            force,
         };
 
-        let result = server
-            .entity_create(rmcp::handler::server::wrapper::Parameters(params))
-            .await;
-
-        assert!(result.is_ok());
+        let err = server
+            .entity_update(Parameters(set_status("done", None)))
+            .await
+            .unwrap_err();
+        assert!(err.message.contains("allowed from 'todo': in_progress"));
 
-        let store = server.store.lock().await;
-        let tasks = store.list_tasks().unwrap();
-        assert_eq!(tasks.len(), 1);
-        assert_eq!(tasks[0].base.title, "Implement auth");
-        assert_eq!(tasks[0].priority, crate::entity::TaskPriority::High);
-        assert_eq!(tasks[0].assignee, Some("alice".to_string()));
+        server
+            .entity_update(Parameters(set_status("in_progress", None)))
+            .await
+            .unwrap();
+        server
+            .entity_update(Parameters(set_status("done", None)))
+            .await
+            .unwrap();
+        assert!(server
+            .entity_update(Parameters(set_status("todo", None)))
+            .await
+            .is_err());
+        server
+            .entity_update(Parameters(set_status("todo", Some(true))))
+            .await
+            .unwrap();
     }
 
     #[tokio::test]
-    async fn test_entity_create_validation_error_empty_title() {
+    async fn test_configured_limits() {
         let (server, _tmp) = setup_test_server();
+        let config = crate::config::ProjectConfig::from_yaml(
+            "limits:\n  max_batch_size: 1\n  max_context_size: 10\n",
+        )
+        .unwrap();
+        let server = server.with_config(config);
+
+        let create = |title: &str| {
+            BatchOperation::Create(EntityCreateParams {
+                entity_type: "task".to_string(),
+                title: title.to_string(),
+                content: None,
+                tags: None,
+                properties: None,
+            })
+        };
+        let err = server
+            .entity_batch(rmcp::handler::server::wrapper::Parameters(
+                EntityBatchParams {
+                    operations: vec![create("One"), create("Two")],
+                    atomic: None,
+                },
+            ))
+            .await
+            .unwrap_err();
+        assert!(err.message.contains("Maximum 1 operations"));
 
         let params = EntityCreateParams {
             entity_type: "decision".to_string(),
-            title: "   ".to_string(), // whitespace only
+            title: "Long context".to_string(),
             content: None,
             tags: None,
-            properties: None,
+            properties: Some(serde_json::json!({ "context": "more than ten bytes" })),
         };
-
-        let result = server
+        let err = server
             .entity_create(rmcp::handler::server::wrapper::Parameters(params))
-            .await;
-
-        assert!(result.is_err());
-        let err = result.unwrap_err();
-        assert!(err.message.contains("Title"));
+            .await
+            .unwrap_err();
+        assert_eq!(err.code.0, error::error_codes::VALIDATION_FAILED);
     }
 
     #[tokio::test]
-    async fn test_entity_create_validation_error_invalid_type() {
+    async fn test_entity_batch_atomic() {
         let (server, _tmp) = setup_test_server();
 
-        let params = EntityCreateParams {
-            entity_type: "invalid_type".to_string(),
-            title: "Test".to_string(),
-            content: None,
-            tags: None,
-            properties: None,
+        let create = |title: &str| {
+            BatchOperation::Create(EntityCreateParams {
+                entity_type: "decision".to_string(),
+                title: title.to_string(),
+                content: None,
+                tags: None,
+                properties: None,
+            })
         };
 
+        // A failing operation rolls back the create before it
+        let batch_params = EntityBatchParams {
+            operations: vec![
+                create("Rolled back"),
+                BatchOperation::Delete(EntityDeleteParams {
+                    id: "999".to_string(),
+                }),
+                create("Never attempted"),
+            ],
+            atomic: Some(true),
+        };
         let result = server
-            .entity_create(rmcp::handler::server::wrapper::Parameters(params))
-            .await;
+            .entity_batch(rmcp::handler::server::wrapper::Parameters(batch_params))
+            .await
+            .unwrap();
+        if let rmcp::model::RawContent::Text(t) = &result.content[0].raw {
+            let parsed: serde_json::Value = serde_json::from_str(&t.text).unwrap();
+            assert_eq!(parsed["failed"], 1);
+            assert_eq!(parsed["results"].as_array().unwrap().len(), 2);
+            assert!(parsed["abort_reason"]
+                .as_str()
+                .unwrap()
+                .starts_with("Operation 1 failed"));
+        } else {
+            panic!("Expected text content");
+        }
+        assert!(server
+            .store
+            .lock()
+            .await
+            .list_decisions()
+            .unwrap()
+            .is_empty());
 
-        assert!(result.is_err());
+        // A fully valid batch is applied to the store and the cache
+        let batch_params = EntityBatchParams {
+            operations: vec![create("Use SQLite"), create("Use Loro")],
+            atomic: Some(true),
+        };
+        let result = server
+            .entity_batch(rmcp::handler::server::wrapper::Parameters(batch_params))
+            .await
+            .unwrap();
+        if let rmcp::model::RawContent::Text(t) = &result.content[0].raw {
+            let parsed: serde_json::Value = serde_json::from_str(&t.text).unwrap();
+            assert_eq!(parsed["succeeded"], 2);
+            assert!(parsed.get("abort_reason").is_none());
+        } else {
+            panic!("Expected text content");
+        }
+        assert_eq!(server.store.lock().await.list_decisions().unwrap().len(), 2);
+        let hits = server
+            .cache
+            .lock()
+            .await
+            .search_decisions("SQLite", 10)
+            .unwrap();
+        assert_eq!(hits.len(), 1);
     }
 
     #[tokio::test]
-    async fn test_entity_get_by_sequence_number() {
+    async fn test_entity_batch_atomic_with_concurrent_write() {
         let (server, _tmp) = setup_test_server();
 
-        // Create an entity first
-        let create_params = EntityCreateParams {
+        let create = |title: &str| EntityCreateParams {
             entity_type: "decision".to_string(),
-            title: "Test Decision".to_string(),
+            title: title.to_string(),
             content: None,
             tags: None,
             properties: None,
         };
-        server
-            .entity_create(rmcp::handler::server::wrapper::Parameters(create_params))
-            .await
-            .unwrap();
-
-        // Get by sequence number
-        let get_params = EntityGetParams {
-            id: "1".to_string(),
-            entity_type: None,
+        let batch_params = EntityBatchParams {
+            operations: (0..5)
+                .map(|i| BatchOperation::Create(create(&format!("Batched {}", i))))
+                .collect(),
+            atomic: Some(true),
         };
 
-        let result = server
-            .entity_get(rmcp::handler::server::wrapper::Parameters(get_params))
-            .await;
-
-        assert!(result.is_ok());
-        let tool_result = result.unwrap();
-        if let rmcp::model::RawContent::Text(t) = &tool_result.content[0].raw {
-            assert!(t.text.contains("Test Decision"));
-        }
+        // A write racing the batch lands before or after it, never between
+        // staging and applying
+        let (batch, single) = tokio::join!(
+            server.entity_batch(rmcp::handler::server::wrapper::Parameters(batch_params)),
+            server.entity_create(rmcp::handler::server::wrapper::Parameters(create(
+                "Written alongside"
+            ))),
+        );
+        batch.unwrap();
+        single.unwrap();
+
+        let decisions = server.store.lock().await.list_decisions().unwrap();
+        assert_eq!(decisions.len(), 6);
+        let mut numbers: Vec<u32> = decisions.iter().map(|d| d.base.sequence_number).collect();
+        numbers.sort_unstable();
+        assert_eq!(numbers, [1, 2, 3, 4, 5, 6]);
     }
 
     #[tokio::test]
-    async fn test_entity_get_not_found() {
-        let (server, _tmp) = setup_test_server();
+    async fn test_snapshot_preview_decision() {
+        let (server, tmp) = setup_test_server();
 
-        let get_params = EntityGetParams {
-            id: "999".to_string(),
-            entity_type: None,
+        let params = EntityCreateParams {
+            entity_type: "decision".to_string(),
+            title: "Use Postgres".to_string(),
+            content: Some("We will use Postgres for persistence.".to_string()),
+            tags: None,
+            properties: None,
         };
+        server
+            .entity_create(rmcp::handler::server::wrapper::Parameters(params))
+            .await
+            .unwrap();
 
+        let params = SnapshotPreviewParams {
+            id: "1".to_string(),
+        };
         let result = server
-            .entity_get(rmcp::handler::server::wrapper::Parameters(get_params))
-            .await;
+            .snapshot_preview(rmcp::handler::server::wrapper::Parameters(params))
+            .await
+            .unwrap();
 
-        assert!(result.is_err());
-        let err = result.unwrap_err();
-        assert!(err.message.contains("not found") || err.code.0 == -32001);
+        if let rmcp::model::RawContent::Text(t) = &result.content[0].raw {
+            let parsed: serde_json::Value = serde_json::from_str(&t.text).unwrap();
+            assert_eq!(parsed["type"], "decision");
+            let markdown = parsed["markdown"].as_str().unwrap();
+            assert!(markdown.starts_with("---\n"));
+            assert!(markdown.contains("title: Use Postgres"));
+            assert!(markdown.contains("We will use Postgres for persistence."));
+        } else {
+            panic!("Expected text content");
+        }
+
+        // Nothing is written to disk
+        assert!(!tmp.path().join(".medulla").join("snapshot").exists());
     }
 
     #[tokio::test]
-    async fn test_entity_list_all() {
+    async fn test_decision_supersede_migrates_relations() {
         let (server, _tmp) = setup_test_server();
 
-        // Create multiple entities
-        for title in ["Decision A", "Decision B"] {
+        for (entity_type, title) in [
+            ("decision", "Use REST"),
+            ("decision", "Use gRPC"),
+            ("component", "API gateway"),
+        ] {
             let params = EntityCreateParams {
-                entity_type: "decision".to_string(),
+                entity_type: entity_type.to_string(),
                 title: title.to_string(),
                 content: None,
                 tags: None,
@@ -3230,38 +9833,83 @@ mod tests {
                 .unwrap();
         }
 
-        let list_params = EntityListParams {
-            entity_type: Some("decision".to_string()),
-            status: None,
-            tag: None,
-            limit: None,
-            offset: None,
+        let params = RelationCreateParams {
+            source_id: "3".to_string(),
+            target_id: "1".to_string(),
+            relation_type: "implements".to_string(),
         };
+        server
+            .relation_create(rmcp::handler::server::wrapper::Parameters(params))
+            .await
+            .unwrap();
 
+        let params = DecisionSupersedeParams {
+            old_id: "1".to_string(),
+            new_id: "2".to_string(),
+            migrate_relations: Some(true),
+        };
         let result = server
-            .entity_list(rmcp::handler::server::wrapper::Parameters(list_params))
-            .await;
+            .decision_supersede(rmcp::handler::server::wrapper::Parameters(params))
+            .await
+            .unwrap();
 
-        assert!(result.is_ok());
-        let tool_result = result.unwrap();
-        if let rmcp::model::RawContent::Text(t) = &tool_result.content[0].raw {
+        if let rmcp::model::RawContent::Text(t) = &result.content[0].raw {
             let parsed: serde_json::Value = serde_json::from_str(&t.text).unwrap();
-            assert_eq!(parsed["total"], 2);
+            assert_eq!(parsed["migrated_relations"].as_array().unwrap().len(), 1);
         }
+
+        let store = server.store.lock().await;
+        let decisions = store.list_decisions().unwrap();
+        let old = decisions
+            .iter()
+            .find(|d| d.base.sequence_number == 1)
+            .unwrap();
+        let new = decisions
+            .iter()
+            .find(|d| d.base.sequence_number == 2)
+            .unwrap();
+        let component = &store.list_components().unwrap()[0];
+
+        let outgoing = store
+            .get_relations_from(&component.base.id.to_string())
+            .unwrap();
+        assert_eq!(outgoing.len(), 1);
+        assert_eq!(
+            outgoing[0].relation_type,
+            crate::entity::RelationType::Implements
+        );
+        assert_eq!(outgoing[0].target_id, new.base.id);
+
+        // Only the supersedes relation still points at the old decision
+        let incoming = store.get_relations_to(&old.base.id.to_string()).unwrap();
+        assert_eq!(incoming.len(), 1);
+        assert_eq!(
+            incoming[0].relation_type,
+            crate::entity::RelationType::Supersedes
+        );
     }
 
     #[tokio::test]
-    async fn test_entity_list_with_status_filter() {
+    async fn test_project_burndown() {
         let (server, _tmp) = setup_test_server();
 
-        // Create decisions with different statuses
-        for (title, status) in [("Accepted", "accepted"), ("Proposed", "proposed")] {
+        let seeds = [
+            ("Design schema", "done", Some(120), "apollo"),
+            ("Write migrations", "in_progress", Some(90), "apollo"),
+            ("Load test", "todo", Some(60), "apollo"),
+            ("Fix flaky CI", "blocked", None, "apollo"),
+            ("Unrelated chore", "done", Some(500), "other"),
+        ];
+        for (title, status, estimate, tag) in seeds {
             let params = EntityCreateParams {
-                entity_type: "decision".to_string(),
+                entity_type: "task".to_string(),
                 title: title.to_string(),
                 content: None,
-                tags: None,
-                properties: Some(serde_json::json!({ "status": status })),
+                tags: Some(vec![tag.to_string()]),
+                properties: Some(serde_json::json!({
+                    "status": status,
+                    "estimate_minutes": estimate,
+                })),
             };
             server
                 .entity_create(rmcp::handler::server::wrapper::Parameters(params))
@@ -3269,115 +9917,130 @@ mod tests {
                 .unwrap();
         }
 
-        let list_params = EntityListParams {
-            entity_type: Some("decision".to_string()),
-            status: Some("accepted".to_string()),
-            tag: None,
-            limit: None,
-            offset: None,
+        let params = ProjectBurndownParams {
+            tag: Some("apollo".to_string()),
         };
-
         let result = server
-            .entity_list(rmcp::handler::server::wrapper::Parameters(list_params))
-            .await;
-
-        assert!(result.is_ok());
-        let tool_result = result.unwrap();
-        if let rmcp::model::RawContent::Text(t) = &tool_result.content[0].raw {
+            .project_burndown(rmcp::handler::server::wrapper::Parameters(params))
+            .await
+            .unwrap();
+        if let rmcp::model::RawContent::Text(t) = &result.content[0].raw {
             let parsed: serde_json::Value = serde_json::from_str(&t.text).unwrap();
-            assert_eq!(parsed["total"], 1);
+            assert_eq!(parsed["total_tasks"], 4);
+            assert_eq!(parsed["total_minutes"], 270);
+            assert_eq!(parsed["completed_minutes"], 120);
+            assert_eq!(parsed["remaining_minutes"], 150);
+            assert_eq!(parsed["unestimated_tasks"], 1);
+            assert_eq!(parsed["counts"]["done"], 1);
+            assert_eq!(parsed["counts"]["blocked"], 1);
+        } else {
+            panic!("Expected text content");
         }
-    }
-
-    #[tokio::test]
-    async fn test_entity_update() {
-        let (server, _tmp) = setup_test_server();
 
-        // Create an entity
-        let create_params = EntityCreateParams {
-            entity_type: "decision".to_string(),
-            title: "Original Title".to_string(),
-            content: None,
-            tags: Some(vec!["old-tag".to_string()]),
-            properties: Some(serde_json::json!({ "status": "proposed" })),
-        };
-        server
-            .entity_create(rmcp::handler::server::wrapper::Parameters(create_params))
+        // Without a tag every task counts
+        let params = ProjectBurndownParams { tag: None };
+        let result = server
+            .project_burndown(rmcp::handler::server::wrapper::Parameters(params))
             .await
             .unwrap();
-
-        // Update it
-        let update_params = EntityUpdateParams {
-            id: "1".to_string(),
-            title: Some("Updated Title".to_string()),
-            content: Some("New content".to_string()),
-            add_tags: Some(vec!["new-tag".to_string()]),
-            remove_tags: Some(vec!["old-tag".to_string()]),
-            properties: Some(serde_json::json!({ "status": "accepted" })),
-        };
-
-        let result = server
-            .entity_update(rmcp::handler::server::wrapper::Parameters(update_params))
-            .await;
-
-        assert!(result.is_ok());
-
-        // Verify
-        let store = server.store.lock().await;
-        let decisions = store.list_decisions().unwrap();
-        assert_eq!(decisions[0].base.title, "Updated Title");
-        assert_eq!(decisions[0].status, crate::entity::DecisionStatus::Accepted);
-        assert!(decisions[0].base.tags.contains(&"new-tag".to_string()));
-        assert!(!decisions[0].base.tags.contains(&"old-tag".to_string()));
+        if let rmcp::model::RawContent::Text(t) = &result.content[0].raw {
+            let parsed: serde_json::Value = serde_json::from_str(&t.text).unwrap();
+            assert_eq!(parsed["total_minutes"], 770);
+            assert_eq!(parsed["remaining_minutes"], 150);
+        }
     }
 
     #[tokio::test]
-    async fn test_entity_delete() {
+    async fn test_decision_priority_and_sort() {
         let (server, _tmp) = setup_test_server();
 
-        // Create an entity
-        let create_params = EntityCreateParams {
-            entity_type: "decision".to_string(),
-            title: "To Be Deleted".to_string(),
+        for (title, priority) in [
+            ("Raised later", None),
+            ("Low priority", Some("low")),
+            ("Urgent priority", Some("urgent")),
+        ] {
+            let params = EntityCreateParams {
+                entity_type: "decision".to_string(),
+                title: title.to_string(),
+                content: None,
+                tags: None,
+                properties: priority.map(|p| serde_json::json!({ "priority": p })),
+            };
+            server
+                .entity_create(rmcp::handler::server::wrapper::Parameters(params))
+                .await
+                .unwrap();
+        }
+
+        // Priority round-trips through the store
+        {
+            let store = server.store.lock().await;
+            let decisions = store.list_decisions().unwrap();
+            assert_eq!(decisions[0].priority, None);
+            assert_eq!(
+                decisions[2].priority,
+                Some(crate::entity::TaskPriority::Urgent)
+            );
+        }
+
+        // Give the unprioritized decision a high priority
+        let params = EntityUpdateParams {
+            id: "1".to_string(),
+            title: None,
             content: None,
-            tags: None,
-            properties: None,
+            add_tags: None,
+            remove_tags: None,
+            properties: Some(serde_json::json!({ "priority": "high" })),
+            force: None,
         };
         server
-            .entity_create(rmcp::handler::server::wrapper::Parameters(create_params))
+            .entity_update(rmcp::handler::server::wrapper::Parameters(params))
             .await
             .unwrap();
 
-        // Delete it
-        let delete_params = EntityDeleteParams {
-            id: "1".to_string(),
+        let list_params = EntityListParams {
+            entity_type: Some("decision".to_string()),
+            status: None,
+            tag: None,
+            collection: None,
+            created_after: None,
+            updated_after: None,
+            sort_by: Some("priority".to_string()),
+            order: None,
+            limit: None,
+            offset: None,
+            cursor: None,
         };
-
         let result = server
-            .entity_delete(rmcp::handler::server::wrapper::Parameters(delete_params))
-            .await;
-
-        assert!(result.is_ok());
+            .entity_list(rmcp::handler::server::wrapper::Parameters(list_params))
+            .await
+            .unwrap();
 
-        // Verify it's gone
-        let store = server.store.lock().await;
-        let decisions = store.list_decisions().unwrap();
-        assert!(decisions.is_empty());
+        if let rmcp::model::RawContent::Text(t) = &result.content[0].raw {
+            let parsed: serde_json::Value = serde_json::from_str(&t.text).unwrap();
+            let titles: Vec<&str> = parsed["entities"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|e| e["title"].as_str().unwrap())
+                .collect();
+            assert_eq!(
+                titles,
+                vec!["Urgent priority", "Raised later", "Low priority"]
+            );
+            assert_eq!(parsed["entities"][0]["properties"]["priority"], "urgent");
+        }
     }
 
     #[tokio::test]
-    async fn test_search_fulltext() {
+    async fn test_snapshot_pending_lists_modified_entities() {
         let (server, _tmp) = setup_test_server();
 
-        // Create entities with searchable content
-        for (title, content) in [
-            ("PostgreSQL Decision", "Use PostgreSQL for database storage"),
-            ("Redis Decision", "Use Redis for caching"),
-        ] {
+        for title in ["First", "Second", "Third"] {
             let params = EntityCreateParams {
-                entity_type: "decision".to_string(),
+                entity_type: "note".to_string(),
                 title: title.to_string(),
-                content: Some(content.to_string()),
+                content: None,
                 tags: None,
                 properties: None,
             };
@@ -3387,44 +10050,61 @@ mod tests {
                 .unwrap();
         }
 
-        // Search for PostgreSQL
-        let search_params = SearchFulltextParams {
-            query: "PostgreSQL".to_string(),
-            entity_type: None,
-            limit: None,
-        };
+        {
+            let store = server.store.lock().await;
+            let snapshot_dir = store.medulla_dir().join("snapshot");
+            crate::snapshot::generate_snapshot(&store, &snapshot_dir).unwrap();
+        }
 
-        let result = server
-            .search_fulltext(rmcp::handler::server::wrapper::Parameters(search_params))
-            .await;
+        let result = server.snapshot_pending().await.unwrap();
+        if let rmcp::model::RawContent::Text(t) = &result.content[0].raw {
+            let parsed: serde_json::Value = serde_json::from_str(&t.text).unwrap();
+            assert_eq!(parsed["up_to_date"], true);
+            assert_eq!(parsed["total"], 0);
+        }
 
-        assert!(result.is_ok());
-        let tool_result = result.unwrap();
-        if let rmcp::model::RawContent::Text(t) = &tool_result.content[0].raw {
+        for id in ["1", "3"] {
+            let params = EntityUpdateParams {
+                id: id.to_string(),
+                title: None,
+                content: Some("Edited after snapshot".to_string()),
+                add_tags: None,
+                remove_tags: None,
+                properties: None,
+                force: None,
+            };
+            server
+                .entity_update(rmcp::handler::server::wrapper::Parameters(params))
+                .await
+                .unwrap();
+        }
+
+        let result = server.snapshot_pending().await.unwrap();
+        if let rmcp::model::RawContent::Text(t) = &result.content[0].raw {
             let parsed: serde_json::Value = serde_json::from_str(&t.text).unwrap();
-            assert_eq!(parsed["total"], 1);
-            assert!(parsed["results"][0]["title"]
-                .as_str()
+            assert_eq!(parsed["total"], 2);
+            assert_eq!(parsed["counts"]["note"], 2);
+            let titles: Vec<&str> = parsed["pending"]
+                .as_array()
                 .unwrap()
-                .contains("PostgreSQL"));
+                .iter()
+                .map(|e| e["title"].as_str().unwrap())
+                .collect();
+            assert_eq!(titles, vec!["First", "Third"]);
         }
     }
 
     #[tokio::test]
-    async fn test_task_ready() {
+    async fn test_focus_set_and_resource() {
         let (server, _tmp) = setup_test_server();
 
-        // Create tasks
-        for (title, priority) in [("Task High", "high"), ("Task Low", "low")] {
+        for title in ["Cache layout", "Cache eviction", "Cache warmup"] {
             let params = EntityCreateParams {
-                entity_type: "task".to_string(),
+                entity_type: "note".to_string(),
                 title: title.to_string(),
                 content: None,
                 tags: None,
-                properties: Some(serde_json::json!({
-                    "status": "todo",
-                    "priority": priority
-                })),
+                properties: None,
             };
             server
                 .entity_create(rmcp::handler::server::wrapper::Parameters(params))
@@ -3432,153 +10112,228 @@ mod tests {
                 .unwrap();
         }
 
-        let ready_params = TaskReadyParams { limit: None };
+        let params = FocusSetParams {
+            ids: vec!["1".to_string(), "3".to_string()],
+        };
+        server
+            .focus_set(rmcp::handler::server::wrapper::Parameters(params))
+            .await
+            .unwrap();
 
-        let result = server
-            .task_ready(rmcp::handler::server::wrapper::Parameters(ready_params))
-            .await;
+        let result = resources::read_resource("medulla://focus", &server.store, &server.cache)
+            .await
+            .unwrap();
+        if let rmcp::model::ResourceContents::TextResourceContents { text, .. } =
+            &result.contents[0]
+        {
+            let parsed: serde_json::Value = serde_json::from_str(text).unwrap();
+            assert_eq!(parsed["total"], 2);
+            let titles: Vec<&str> = parsed["entities"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|e| e["title"].as_str().unwrap())
+                .collect();
+            assert_eq!(titles, vec!["Cache layout", "Cache warmup"]);
+            assert_eq!(parsed["entities"][0]["type"], "note");
+        } else {
+            panic!("Expected TextResourceContents");
+        }
 
-        assert!(result.is_ok());
-        let tool_result = result.unwrap();
-        if let rmcp::model::RawContent::Text(t) = &tool_result.content[0].raw {
+        // Searches can be scoped to the focus set
+        let params = SearchFulltextParams {
+            query: "cache".to_string(),
+            entity_type: None,
+            limit: None,
+            offset: None,
+            within_focus: Some(true),
+            collection: None,
+        };
+        let result = server
+            .search_fulltext(rmcp::handler::server::wrapper::Parameters(params))
+            .await
+            .unwrap();
+        if let rmcp::model::RawContent::Text(t) = &result.content[0].raw {
             let parsed: serde_json::Value = serde_json::from_str(&t.text).unwrap();
             assert_eq!(parsed["total"], 2);
-            // High priority should come first
-            assert!(parsed["tasks"][0]["title"]
-                .as_str()
-                .unwrap()
-                .contains("High"));
         }
     }
 
     #[tokio::test]
-    async fn test_task_next() {
+    async fn test_context_pack() {
         let (server, _tmp) = setup_test_server();
-
-        // Create tasks with different priorities
-        for (title, priority) in [("Normal Task", "normal"), ("Urgent Task", "urgent")] {
+        for (title, content) in [
+            ("Storage design", "Entities live in a Loro document."),
+            ("Cache layer", "SQLite mirrors the document for search."),
+            ("Unrelated", "Nothing to do with storage."),
+        ] {
             let params = EntityCreateParams {
-                entity_type: "task".to_string(),
+                entity_type: "note".to_string(),
                 title: title.to_string(),
-                content: None,
+                content: Some(content.to_string()),
                 tags: None,
-                properties: Some(serde_json::json!({
-                    "status": "todo",
-                    "priority": priority
-                })),
+                properties: None,
             };
             server
                 .entity_create(rmcp::handler::server::wrapper::Parameters(params))
                 .await
                 .unwrap();
         }
+        let params = RelationCreateParams {
+            source_id: "2".to_string(),
+            target_id: "1".to_string(),
+            relation_type: "references".to_string(),
+        };
+        server
+            .relation_create(rmcp::handler::server::wrapper::Parameters(params))
+            .await
+            .unwrap();
 
-        let result = server.task_next().await;
-
-        assert!(result.is_ok());
-        let tool_result = result.unwrap();
-        if let rmcp::model::RawContent::Text(t) = &tool_result.content[0].raw {
-            let parsed: serde_json::Value = serde_json::from_str(&t.text).unwrap();
-            // Urgent should be first
-            assert!(parsed["title"].as_str().unwrap().contains("Urgent"));
-        }
+        let params = ContextPackParams {
+            query: None,
+            id: Some("1".to_string()),
+            max_tokens: None,
+            depth: None,
+        };
+        let result = server
+            .context_pack(rmcp::handler::server::wrapper::Parameters(params))
+            .await
+            .unwrap();
+        let RawContent::Text(t) = &result.content[0].raw else {
+            panic!("Expected text content");
+        };
+        let parsed: serde_json::Value = serde_json::from_str(&t.text).unwrap();
+        let entities = parsed["entities"].as_array().unwrap();
+        assert_eq!(entities.len(), 2);
+        assert_eq!(entities[0]["title"], "Storage design");
+        assert_eq!(entities[0]["reason"], "requested");
+        assert_eq!(entities[1]["title"], "Cache layer");
+        assert_eq!(entities[1]["reason"], "related");
+        let markdown = parsed["markdown"].as_str().unwrap();
+        assert!(markdown.starts_with("# Context: Storage design"));
+        assert!(markdown.contains("SQLite mirrors the document"));
+        assert!(markdown.contains("Related: references [NOTE] 001 Storage design"));
+
+        // Neither a query nor an id
+        let params = ContextPackParams {
+            query: Some("  ".to_string()),
+            id: None,
+            max_tokens: None,
+            depth: None,
+        };
+        assert!(server
+            .context_pack(rmcp::handler::server::wrapper::Parameters(params))
+            .await
+            .is_err());
     }
 
     #[tokio::test]
-    async fn test_task_complete() {
+    async fn test_prompt_render() {
         let (server, _tmp) = setup_test_server();
-
-        // Create a task
         let params = EntityCreateParams {
-            entity_type: "task".to_string(),
-            title: "Task to Complete".to_string(),
+            entity_type: "prompt".to_string(),
+            title: "Code review".to_string(),
             content: None,
             tags: None,
-            properties: Some(serde_json::json!({ "status": "todo" })),
+            properties: Some(serde_json::json!({
+                "template": "Review {{file}} focusing on {{ focus }}.",
+                "variables": ["file"],
+                "output_schema": "{\"type\": \"object\", \"required\": [\"verdict\"]}"
+            })),
         };
         server
             .entity_create(rmcp::handler::server::wrapper::Parameters(params))
             .await
             .unwrap();
 
-        // Complete it
-        let complete_params = TaskCompleteParams {
-            id: "1".to_string(),
+        let server = &server;
+        let render = move |variables: serde_json::Value, output: Option<serde_json::Value>| {
+            let params = PromptRenderParams {
+                id: "1".to_string(),
+                variables: serde_json::from_value(variables).unwrap(),
+                output,
+            };
+            server.prompt_render(rmcp::handler::server::wrapper::Parameters(params))
         };
 
-        let result = server
-            .task_complete(rmcp::handler::server::wrapper::Parameters(complete_params))
-            .await;
-
-        assert!(result.is_ok());
-
-        // Verify it's done
-        let store = server.store.lock().await;
-        let tasks = store.list_tasks().unwrap();
-        assert_eq!(tasks[0].status, crate::entity::TaskStatus::Done);
+        let err = render(serde_json::json!({ "file": "main.rs" }), None)
+            .await
+            .unwrap_err();
+        assert!(err.message.contains("Missing required variables: focus"));
+
+        let result = render(
+            serde_json::json!({ "file": "main.rs", "focus": 3, "extra": "x" }),
+            Some(serde_json::json!("{\"notes\": []}")),
+        )
+        .await
+        .unwrap();
+        let RawContent::Text(t) = &result.content[0].raw else {
+            panic!("Expected text content");
+        };
+        let parsed: serde_json::Value = serde_json::from_str(&t.text).unwrap();
+        assert_eq!(parsed["rendered"], "Review main.rs focusing on 3.");
+        assert_eq!(parsed["variables"], serde_json::json!(["file", "focus"]));
+        assert_eq!(parsed["unused_variables"], serde_json::json!(["extra"]));
+        assert_eq!(parsed["output_valid"], false);
+        assert_eq!(
+            parsed["output_errors"],
+            serde_json::json!(["$: missing required property 'verdict'"])
+        );
     }
-
     #[tokio::test]
-    async fn test_graph_relations() {
+    async fn test_entity_outline() {
         let (server, _tmp) = setup_test_server();
 
-        // Create two decisions
-        for title in ["Decision A", "Decision B"] {
-            let params = EntityCreateParams {
-                entity_type: "decision".to_string(),
-                title: title.to_string(),
-                content: None,
-                tags: None,
-                properties: None,
-            };
-            server
-                .entity_create(rmcp::handler::server::wrapper::Parameters(params))
-                .await
-                .unwrap();
-        }
-
-        // Add a relation
-        {
-            let store = server.store.lock().await;
-            let decisions = store.list_decisions().unwrap();
-            let relation = crate::entity::Relation::new(
-                decisions[0].base.id,
-                "decision".to_string(),
-                decisions[1].base.id,
-                "decision".to_string(),
-                crate::entity::RelationType::Supersedes,
-            );
-            store.add_relation(&relation).unwrap();
-            store.save().unwrap();
-        }
+        let params = EntityCreateParams {
+            entity_type: "note".to_string(),
+            title: "Design notes".to_string(),
+            content: Some(
+                "# Overview\nIntro text\n## Storage Layer\n### Loro CRDT\n## Search & Index\n# Open Questions\n"
+                    .to_string(),
+            ),
+            tags: None,
+            properties: None,
+        };
+        server
+            .entity_create(rmcp::handler::server::wrapper::Parameters(params))
+            .await
+            .unwrap();
 
-        // Query relations
-        let params = GraphRelationsParams {
+        let params = EntityOutlineParams {
             id: "1".to_string(),
-            direction: Some("both".to_string()),
         };
-
         let result = server
-            .graph_relations(rmcp::handler::server::wrapper::Parameters(params))
-            .await;
+            .entity_outline(rmcp::handler::server::wrapper::Parameters(params))
+            .await
+            .unwrap();
 
-        assert!(result.is_ok());
-        let tool_result = result.unwrap();
-        if let rmcp::model::RawContent::Text(t) = &tool_result.content[0].raw {
+        if let rmcp::model::RawContent::Text(t) = &result.content[0].raw {
             let parsed: serde_json::Value = serde_json::from_str(&t.text).unwrap();
-            assert!(!parsed["outgoing"].as_array().unwrap().is_empty());
+            let outline = parsed["outline"].as_array().unwrap();
+            assert_eq!(outline.len(), 2);
+            assert_eq!(outline[0]["text"], "Overview");
+            assert_eq!(outline[0]["level"], 1);
+
+            let sections = outline[0]["children"].as_array().unwrap();
+            assert_eq!(sections.len(), 2);
+            assert_eq!(sections[0]["slug"], "storage-layer");
+            assert_eq!(sections[0]["children"][0]["text"], "Loro CRDT");
+            assert_eq!(sections[0]["children"][0]["level"], 3);
+            assert_eq!(sections[1]["slug"], "search-index");
+
+            assert_eq!(outline[1]["slug"], "open-questions");
+            assert!(outline[1].get("children").is_none());
         }
     }
 
     #[tokio::test]
-    async fn test_graph_orphans() {
+    async fn test_entity_patch_section() {
         let (server, _tmp) = setup_test_server();
 
-        // Create an orphan entity (no relations)
         let params = EntityCreateParams {
             entity_type: "decision".to_string(),
-            title: "Orphan Decision".to_string(),
-            content: None,
+            title: "Use Loro".to_string(),
+            content: Some("## Context\n\nNeed sync.\n\n## Outcome\n\nTBD\n".to_string()),
             tags: None,
             properties: None,
         };
@@ -3587,74 +10342,232 @@ mod tests {
             .await
             .unwrap();
 
-        let orphan_params = GraphOrphansParams {
-            entity_type: None,
-            limit: None,
+        let params = EntityPatchSectionParams {
+            id: "1".to_string(),
+            section: "outcome".to_string(),
+            content: Some("Adopted for the store.".to_string()),
+            mode: None,
+            level: None,
         };
-
         let result = server
-            .graph_orphans(rmcp::handler::server::wrapper::Parameters(orphan_params))
-            .await;
+            .entity_patch_section(rmcp::handler::server::wrapper::Parameters(params))
+            .await
+            .unwrap();
+        if let rmcp::model::RawContent::Text(t) = &result.content[0].raw {
+            let parsed: serde_json::Value = serde_json::from_str(&t.text).unwrap();
+            assert_eq!(
+                parsed["content"],
+                "## Context\n\nNeed sync.\n\n## Outcome\n\nAdopted for the store.\n"
+            );
+        }
 
-        assert!(result.is_ok());
-        let tool_result = result.unwrap();
-        if let rmcp::model::RawContent::Text(t) = &tool_result.content[0].raw {
+        let params = EntityPatchSectionParams {
+            id: "1".to_string(),
+            section: "Risks".to_string(),
+            content: Some("Young library.".to_string()),
+            mode: Some("append".to_string()),
+            level: None,
+        };
+        server
+            .entity_patch_section(rmcp::handler::server::wrapper::Parameters(params))
+            .await
+            .unwrap();
+
+        let params = EntityGetSectionParams {
+            id: "1".to_string(),
+            section: "risks".to_string(),
+        };
+        let result = server
+            .entity_get_section(rmcp::handler::server::wrapper::Parameters(params))
+            .await
+            .unwrap();
+        if let rmcp::model::RawContent::Text(t) = &result.content[0].raw {
             let parsed: serde_json::Value = serde_json::from_str(&t.text).unwrap();
-            assert_eq!(parsed["total"], 1);
+            assert_eq!(parsed["section"], "Risks");
+            assert_eq!(parsed["level"], 2);
+            assert_eq!(parsed["content"], "Young library.");
         }
+
+        let params = EntityPatchSectionParams {
+            id: "1".to_string(),
+            section: "Alternatives".to_string(),
+            content: None,
+            mode: Some("delete".to_string()),
+            level: None,
+        };
+        let err = server
+            .entity_patch_section(rmcp::handler::server::wrapper::Parameters(params))
+            .await
+            .unwrap_err();
+        assert!(err.message.contains("Context, Outcome, Risks"));
+
+        let params = EntityPatchSectionParams {
+            id: "1".to_string(),
+            section: "Context".to_string(),
+            content: None,
+            mode: Some("rewrite".to_string()),
+            level: None,
+        };
+        assert!(server
+            .entity_patch_section(rmcp::handler::server::wrapper::Parameters(params))
+            .await
+            .is_err());
     }
 
     #[tokio::test]
-    async fn test_entity_batch_mixed_operations() {
+    async fn test_strict_enums_rejects_capitalized_status() {
         let (server, _tmp) = setup_test_server();
 
-        // First, create an entity to update/delete later
-        let create_params = EntityCreateParams {
+        // Lenient by default
+        let params = EntityCreateParams {
             entity_type: "decision".to_string(),
-            title: "Existing Decision".to_string(),
+            title: "Lenient".to_string(),
             content: None,
             tags: None,
-            properties: None,
+            properties: Some(serde_json::json!({ "status": " Accepted " })),
         };
         server
-            .entity_create(rmcp::handler::server::wrapper::Parameters(create_params))
+            .entity_create(rmcp::handler::server::wrapper::Parameters(params))
             .await
             .unwrap();
 
-        // Batch with create, update, delete (and one invalid delete)
-        let batch_params = EntityBatchParams {
-            operations: vec![
-                BatchOperation::Create(EntityCreateParams {
-                    entity_type: "decision".to_string(),
-                    title: "New via Batch".to_string(),
-                    content: None,
-                    tags: None,
-                    properties: None,
+        let config = crate::config::ProjectConfig::from_yaml("strict_enums: true\n").unwrap();
+        let server = server.with_config(config);
+
+        let params = EntityCreateParams {
+            entity_type: "decision".to_string(),
+            title: "Strict".to_string(),
+            content: None,
+            tags: None,
+            properties: Some(serde_json::json!({ "status": "Accepted" })),
+        };
+        let err = server
+            .entity_create(rmcp::handler::server::wrapper::Parameters(params))
+            .await
+            .unwrap_err();
+        assert_eq!(err.code.0, error::error_codes::VALIDATION_FAILED);
+        assert!(err.message.contains("strict_enums"));
+    }
+
+    #[tokio::test]
+    async fn test_entity_bulk_update() {
+        let (server, _tmp) = setup_test_server();
+        for (title, status) in [("Old", "done"), ("Older", "done"), ("Open", "todo")] {
+            let params = EntityCreateParams {
+                entity_type: "task".to_string(),
+                title: title.to_string(),
+                content: None,
+                tags: Some(vec!["active".to_string()]),
+                properties: Some(serde_json::json!({ "status": status })),
+            };
+            server
+                .entity_create(rmcp::handler::server::wrapper::Parameters(params))
+                .await
+                .unwrap();
+        }
+
+        let bulk = |dry_run: bool| EntityBulkUpdateParams {
+            query: "type:task status:done".to_string(),
+            add_tags: Some(vec!["legacy".to_string()]),
+            remove_tags: Some(vec!["active".to_string()]),
+            dry_run: Some(dry_run),
+        };
+        let parse = |result: CallToolResult| match &result.content[0].raw {
+            RawContent::Text(t) => serde_json::from_str::<serde_json::Value>(&t.text).unwrap(),
+            _ => panic!("Expected text content"),
+        };
+
+        // A dry run lists the matches and changes nothing
+        let preview = parse(
+            server
+                .entity_bulk_update(rmcp::handler::server::wrapper::Parameters(bulk(true)))
+                .await
+                .unwrap(),
+        );
+        assert_eq!(preview["matched"], 2);
+        assert_eq!(preview["entities"][0]["title"], "Old");
+        assert_eq!(
+            preview["entities"][0]["tags"],
+            serde_json::json!(["active"])
+        );
+
+        let applied = parse(
+            server
+                .entity_bulk_update(rmcp::handler::server::wrapper::Parameters(bulk(false)))
+                .await
+                .unwrap(),
+        );
+        assert_eq!(applied["succeeded"], 2);
+        assert_eq!(applied["failed"], 0);
+
+        let store = server.store.lock().await;
+        for task in store.list_tasks().unwrap() {
+            let expected = if task.base.title == "Open" {
+                vec!["active".to_string()]
+            } else {
+                vec!["legacy".to_string()]
+            };
+            assert_eq!(task.base.tags, expected, "{}", task.base.title);
+        }
+        drop(store);
+
+        // An empty filter would touch everything, so it is refused
+        let params = EntityBulkUpdateParams {
+            query: " ".to_string(),
+            ..bulk(false)
+        };
+        assert!(server
+            .entity_bulk_update(rmcp::handler::server::wrapper::Parameters(params))
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_entity_import_rows() {
+        let (server, _tmp) = setup_test_server();
+
+        let params = EntityImportRowsParams {
+            rows: vec![
+                serde_json::json!({
+                    "type": "task",
+                    "title": "Set up CI",
+                    "tags": "infra, ci",
+                    "priority": "high",
                 }),
-                BatchOperation::Update(EntityUpdateParams {
-                    id: "1".to_string(),
-                    title: Some("Updated via Batch".to_string()),
-                    content: None,
-                    add_tags: None,
-                    remove_tags: None,
-                    properties: None,
+                serde_json::json!({
+                    "type": "widget",
+                    "title": "Not a real type",
                 }),
-                BatchOperation::Delete(EntityDeleteParams {
-                    id: "999".to_string(), // doesn't exist
+                serde_json::json!({
+                    "type": "decision",
+                    "title": "Adopt trunk-based development",
+                    "content": "Short-lived branches only.",
+                    "status": "accepted",
                 }),
             ],
         };
-
         let result = server
-            .entity_batch(rmcp::handler::server::wrapper::Parameters(batch_params))
-            .await;
+            .entity_import_rows(rmcp::handler::server::wrapper::Parameters(params))
+            .await
+            .unwrap();
 
-        assert!(result.is_ok());
-        let tool_result = result.unwrap();
-        if let rmcp::model::RawContent::Text(t) = &tool_result.content[0].raw {
+        if let rmcp::model::RawContent::Text(t) = &result.content[0].raw {
             let parsed: serde_json::Value = serde_json::from_str(&t.text).unwrap();
-            assert_eq!(parsed["succeeded"], 2); // create and update succeed
-            assert_eq!(parsed["failed"], 1); // delete fails
+            assert_eq!(parsed["succeeded"], 2);
+            assert_eq!(parsed["failed"], 1);
+            assert_eq!(parsed["results"][1]["index"], 1);
+            assert_eq!(parsed["results"][1]["success"], false);
+            assert_eq!(parsed["results"][1]["error"]["code"], "CREATE_FAILED");
         }
+
+        let store = server.store.lock().await;
+        let tasks = store.list_tasks().unwrap();
+        assert_eq!(tasks.len(), 1);
+        assert_eq!(tasks[0].base.tags, vec!["infra", "ci"]);
+        assert_eq!(tasks[0].priority, crate::entity::TaskPriority::High);
+
+        let decisions = store.list_decisions().unwrap();
+        assert_eq!(decisions.len(), 1);
+        assert_eq!(decisions[0].status, crate::entity::DecisionStatus::Accepted);
     }
 }
@@ -7,10 +7,12 @@
 #![allow(clippy::manual_strip)]
 
 use crate::cache::SqliteCache;
+use crate::entity::EntityBase;
 use crate::mcp::error::{McpError, VALID_ENTITY_TYPES};
 use crate::mcp::tools::*;
 use crate::storage::LoroStore;
 use rmcp::model::{RawResource, RawResourceTemplate, ReadResourceResult, ResourceContents};
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
 use tokio::sync::Mutex;
 
@@ -31,6 +33,8 @@ pub mod static_resources {
     pub const TASKS_BLOCKED: &str = "medulla://tasks/blocked";
     pub const PROMPTS: &str = "medulla://prompts";
     pub const GRAPH: &str = "medulla://graph";
+    pub const FOCUS: &str = "medulla://focus";
+    pub const DASHBOARD: &str = "medulla://dashboard";
 }
 
 /// Resource template URI patterns (require parameter substitution).
@@ -40,6 +44,7 @@ pub mod resource_templates {
     pub const DECISIONS_ACTIVE: &str = "medulla://decisions/active";
     pub const TASKS_ACTIVE: &str = "medulla://tasks/active";
     pub const TASKS_DUE: &str = "medulla://tasks/due/{date}";
+    pub const DIGEST: &str = "medulla://digest/{period}";
 }
 
 /// Build the list of static resources.
@@ -135,6 +140,29 @@ pub fn build_static_resources() -> Vec<RawResource> {
             icons: None,
             meta: None,
         },
+        RawResource {
+            uri: static_resources::FOCUS.to_string(),
+            name: "Focus".to_string(),
+            title: Some("Focus Set".to_string()),
+            description: Some("Summaries of the entities in the current focus set".to_string()),
+            mime_type: Some(RESOURCE_MIME_TYPE.to_string()),
+            size: None,
+            icons: None,
+            meta: None,
+        },
+        RawResource {
+            uri: static_resources::DASHBOARD.to_string(),
+            name: "Dashboard".to_string(),
+            title: Some("Project Status".to_string()),
+            description: Some(
+                "Open decisions, task queue by priority, overdue work, recent changes, orphans and store health, as JSON and markdown; useful as session-start context"
+                    .to_string(),
+            ),
+            mime_type: Some(RESOURCE_MIME_TYPE.to_string()),
+            size: None,
+            icons: None,
+            meta: None,
+        },
     ]
 }
 
@@ -181,6 +209,60 @@ pub fn build_resource_templates() -> Vec<RawResourceTemplate> {
             mime_type: Some(RESOURCE_MIME_TYPE.to_string()),
             icons: None,
         },
+        RawResourceTemplate {
+            uri_template: resource_templates::DIGEST.to_string(),
+            name: "Digest".to_string(),
+            title: Some("Activity Digest".to_string()),
+            description: Some(
+                "Markdown summary of entities created, updated and completed in a period (day, week, month, or e.g. 7d, 2w)"
+                    .to_string(),
+            ),
+            mime_type: Some("text/markdown".to_string()),
+            icons: None,
+        },
+    ]
+}
+
+/// URIs whose contents change when an entity is created, updated or deleted.
+pub fn entity_change_uris(entity_type: &str, id: &str, sequence_number: u32) -> Vec<String> {
+    let mut uris = vec![
+        static_resources::STATS.to_string(),
+        static_resources::ENTITIES.to_string(),
+        static_resources::GRAPH.to_string(),
+        static_resources::FOCUS.to_string(),
+        static_resources::DASHBOARD.to_string(),
+        format!("{}entities/{}", MEDULLA_SCHEME, entity_type),
+        format!("{}entity/{}", MEDULLA_SCHEME, id),
+        format!("{}entity/{}", MEDULLA_SCHEME, sequence_number),
+    ];
+    let by_type: &[&str] = match entity_type {
+        "decision" => &[
+            static_resources::DECISIONS,
+            resource_templates::DECISIONS_ACTIVE,
+        ],
+        "task" => &[
+            static_resources::TASKS,
+            static_resources::TASKS_READY,
+            static_resources::TASKS_BLOCKED,
+            resource_templates::TASKS_ACTIVE,
+        ],
+        "prompt" => &[static_resources::PROMPTS],
+        _ => &[],
+    };
+    uris.extend(by_type.iter().map(|uri| uri.to_string()));
+    uris
+}
+
+/// URIs whose contents change when a relation is added, resolved or removed.
+pub fn relation_change_uris(source_id: &str, target_id: &str) -> Vec<String> {
+    vec![
+        static_resources::STATS.to_string(),
+        static_resources::GRAPH.to_string(),
+        static_resources::TASKS_READY.to_string(),
+        static_resources::TASKS_BLOCKED.to_string(),
+        static_resources::DASHBOARD.to_string(),
+        format!("{}entity/{}", MEDULLA_SCHEME, source_id),
+        format!("{}entity/{}", MEDULLA_SCHEME, target_id),
     ]
 }
 
@@ -210,6 +292,8 @@ pub async fn read_resource(
         "tasks/blocked" => read_blocked_tasks_resource(uri, cache).await,
         "prompts" => read_prompts_resource(uri, store).await,
         "graph" => read_graph_resource(uri, store).await,
+        "focus" => read_focus_resource(uri, store).await,
+        "dashboard" => read_dashboard_resource(uri, store, cache).await,
         _ => {
             // Try to match dynamic patterns
             if path.starts_with("entities/") {
@@ -224,6 +308,10 @@ pub async fn read_resource(
                 let date = &path["tasks/due/".len()..];
                 return read_tasks_due_resource(uri, store, date).await;
             }
+            if path.starts_with("digest/") {
+                let period = &path["digest/".len()..];
+                return read_digest_resource(uri, store, period).await;
+            }
 
             Err(McpError::ResourceNotFound {
                 uri: uri.to_string(),
@@ -234,32 +322,7 @@ pub async fn read_resource(
 
 /// Read the schema resource (static).
 fn read_schema_resource(uri: &str) -> Result<ReadResourceResult, McpError> {
-    let schema = serde_json::json!({
-        "entity_types": VALID_ENTITY_TYPES,
-        "decision": {
-            "status": ["proposed", "accepted", "deprecated", "superseded"],
-            "fields": ["context", "consequences", "superseded_by"]
-        },
-        "task": {
-            "status": ["todo", "in_progress", "done", "blocked"],
-            "priority": ["low", "normal", "high", "urgent"],
-            "fields": ["due_date", "assignee"]
-        },
-        "note": {
-            "fields": ["note_type"]
-        },
-        "prompt": {
-            "fields": ["template", "variables", "output_schema"]
-        },
-        "component": {
-            "status": ["active", "deprecated", "planned"],
-            "fields": ["component_type", "owner"]
-        },
-        "link": {
-            "fields": ["url", "link_type"]
-        },
-        "relation_types": ["blocks", "relates", "supersedes", "implements", "depends_on"]
-    });
+    let schema = super::schema::schema_document();
 
     let text = serde_json::to_string_pretty(&schema).map_err(|e| McpError::InternalError {
         message: format!("Failed to serialize schema: {}", e),
@@ -288,6 +351,8 @@ async fn read_stats_resource(
     let prompt_count = store.list_prompts().map_err(McpError::from)?.len();
     let component_count = store.list_components().map_err(McpError::from)?.len();
     let link_count = store.list_links().map_err(McpError::from)?.len();
+    let question_count = store.list_questions().map_err(McpError::from)?.len();
+    let milestone_count = store.list_milestones().map_err(McpError::from)?.len();
     let relation_count = store.list_relations().map_err(McpError::from)?.len();
 
     let stats = serde_json::json!({
@@ -298,6 +363,8 @@ async fn read_stats_resource(
             "prompt": prompt_count,
             "component": component_count,
             "link": link_count,
+            "question": question_count,
+            "milestone": milestone_count,
         },
         "relation_count": relation_count,
         "medulla_version": env!("CARGO_PKG_VERSION"),
@@ -344,6 +411,12 @@ async fn read_all_entities_resource(
     for l in store.list_links().map_err(McpError::from)? {
         entities.push(link_to_response(&l));
     }
+    for q in store.list_questions().map_err(McpError::from)? {
+        entities.push(question_to_response(&q));
+    }
+    for m in store.list_milestones().map_err(McpError::from)? {
+        entities.push(milestone_to_response(&m));
+    }
 
     let response = serde_json::json!({
         "entities": entities,
@@ -414,6 +487,18 @@ async fn read_entities_by_type_resource(
             .iter()
             .map(link_to_response)
             .collect(),
+        "question" => store
+            .list_questions()
+            .map_err(McpError::from)?
+            .iter()
+            .map(question_to_response)
+            .collect(),
+        "milestone" => store
+            .list_milestones()
+            .map_err(McpError::from)?
+            .iter()
+            .map(milestone_to_response)
+            .collect(),
         _ => {
             return Err(McpError::InvalidResourceUri {
                 uri: uri.to_string(),
@@ -564,6 +649,42 @@ async fn read_entity_by_id_resource(
         }
     }
 
+    for q in store.list_questions().map_err(McpError::from)? {
+        if matches_id(&q.base) {
+            let response = question_to_response(&q);
+            let text =
+                serde_json::to_string_pretty(&response).map_err(|e| McpError::InternalError {
+                    message: format!("Failed to serialize entity: {}", e),
+                })?;
+            return Ok(ReadResourceResult {
+                contents: vec![ResourceContents::TextResourceContents {
+                    uri: uri.to_string(),
+                    mime_type: Some(RESOURCE_MIME_TYPE.to_string()),
+                    text,
+                    meta: None,
+                }],
+            });
+        }
+    }
+
+    for m in store.list_milestones().map_err(McpError::from)? {
+        if matches_id(&m.base) {
+            let response = milestone_to_response(&m);
+            let text =
+                serde_json::to_string_pretty(&response).map_err(|e| McpError::InternalError {
+                    message: format!("Failed to serialize entity: {}", e),
+                })?;
+            return Ok(ReadResourceResult {
+                contents: vec![ResourceContents::TextResourceContents {
+                    uri: uri.to_string(),
+                    mime_type: Some(RESOURCE_MIME_TYPE.to_string()),
+                    text,
+                    meta: None,
+                }],
+            });
+        }
+    }
+
     Err(McpError::ResourceNotFound {
         uri: uri.to_string(),
     })
@@ -787,6 +908,69 @@ async fn read_tasks_due_resource(
     })
 }
 
+/// Read the activity digest for a period, as markdown.
+async fn read_digest_resource(
+    uri: &str,
+    store: &Arc<Mutex<LoroStore>>,
+    period: &str,
+) -> Result<ReadResourceResult, McpError> {
+    let days = crate::snapshot::parse_period(period).map_err(|_| McpError::InvalidResourceUri {
+        uri: uri.to_string(),
+    })?;
+
+    let store = store.lock().await;
+    let until = chrono::Utc::now();
+    let digest = crate::snapshot::generate_digest(
+        &store,
+        until - chrono::Duration::days(days as i64),
+        until,
+    )
+    .map_err(McpError::from)?;
+
+    Ok(ReadResourceResult {
+        contents: vec![ResourceContents::TextResourceContents {
+            uri: uri.to_string(),
+            mime_type: Some("text/markdown".to_string()),
+            text: digest.to_markdown(),
+            meta: None,
+        }],
+    })
+}
+
+/// Read the project dashboard, as JSON followed by the same overview in
+/// markdown.
+async fn read_dashboard_resource(
+    uri: &str,
+    store: &Arc<Mutex<LoroStore>>,
+    cache: &Arc<Mutex<SqliteCache>>,
+) -> Result<ReadResourceResult, McpError> {
+    let store = store.lock().await;
+    let cache = cache.lock().await;
+    let dashboard = crate::snapshot::generate_dashboard(&store, &cache, chrono::Utc::now())
+        .map_err(McpError::from)?;
+
+    let text = serde_json::to_string_pretty(&dashboard).map_err(|e| McpError::InternalError {
+        message: format!("Failed to serialize dashboard: {}", e),
+    })?;
+
+    Ok(ReadResourceResult {
+        contents: vec![
+            ResourceContents::TextResourceContents {
+                uri: uri.to_string(),
+                mime_type: Some(RESOURCE_MIME_TYPE.to_string()),
+                text,
+                meta: None,
+            },
+            ResourceContents::TextResourceContents {
+                uri: uri.to_string(),
+                mime_type: Some("text/markdown".to_string()),
+                text: dashboard.to_markdown(),
+                meta: None,
+            },
+        ],
+    })
+}
+
 /// Read prompts resource.
 async fn read_prompts_resource(
     uri: &str,
@@ -842,6 +1026,12 @@ async fn read_graph_resource(
     for l in store.list_links().map_err(McpError::from)? {
         entities.push(link_to_response(&l));
     }
+    for q in store.list_questions().map_err(McpError::from)? {
+        entities.push(question_to_response(&q));
+    }
+    for m in store.list_milestones().map_err(McpError::from)? {
+        entities.push(milestone_to_response(&m));
+    }
 
     // Collect all relations
     let relations = store.list_relations().map_err(McpError::from)?;
@@ -869,6 +1059,87 @@ async fn read_graph_resource(
     })
 }
 
+/// Build summaries of the entities in the focus set, in focus order.
+/// IDs whose entity has since been deleted are reported under `missing`.
+pub fn focus_summary(store: &LoroStore) -> Result<serde_json::Value, McpError> {
+    let focus = store.get_focus().map_err(McpError::from)?;
+    let wanted: HashSet<uuid::Uuid> = focus.iter().copied().collect();
+
+    let mut found: HashMap<uuid::Uuid, (&str, EntityBase)> = HashMap::new();
+    let mut collect = |entity_type: &'static str, base: EntityBase| {
+        if wanted.contains(&base.id) {
+            found.insert(base.id, (entity_type, base));
+        }
+    };
+    for d in store.list_decisions().map_err(McpError::from)? {
+        collect("decision", d.base);
+    }
+    for t in store.list_tasks().map_err(McpError::from)? {
+        collect("task", t.base);
+    }
+    for n in store.list_notes().map_err(McpError::from)? {
+        collect("note", n.base);
+    }
+    for p in store.list_prompts().map_err(McpError::from)? {
+        collect("prompt", p.base);
+    }
+    for c in store.list_components().map_err(McpError::from)? {
+        collect("component", c.base);
+    }
+    for l in store.list_links().map_err(McpError::from)? {
+        collect("link", l.base);
+    }
+    for q in store.list_questions().map_err(McpError::from)? {
+        collect("question", q.base);
+    }
+    for m in store.list_milestones().map_err(McpError::from)? {
+        collect("milestone", m.base);
+    }
+
+    let mut entities = Vec::new();
+    let mut missing = Vec::new();
+    for id in &focus {
+        match found.get(id) {
+            Some((entity_type, base)) => entities.push(serde_json::json!({
+                "id": base.id.to_string(),
+                "sequence_number": base.sequence_number,
+                "type": entity_type,
+                "title": base.title,
+                "tags": base.tags,
+            })),
+            None => missing.push(id.to_string()),
+        }
+    }
+
+    Ok(serde_json::json!({
+        "entities": entities,
+        "missing": missing,
+        "total": entities.len(),
+    }))
+}
+
+/// Read the focus set resource.
+async fn read_focus_resource(
+    uri: &str,
+    store: &Arc<Mutex<LoroStore>>,
+) -> Result<ReadResourceResult, McpError> {
+    let store = store.lock().await;
+    let response = focus_summary(&store)?;
+
+    let text = serde_json::to_string_pretty(&response).map_err(|e| McpError::InternalError {
+        message: format!("Failed to serialize focus: {}", e),
+    })?;
+
+    Ok(ReadResourceResult {
+        contents: vec![ResourceContents::TextResourceContents {
+            uri: uri.to_string(),
+            mime_type: Some(RESOURCE_MIME_TYPE.to_string()),
+            text,
+            meta: None,
+        }],
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -895,10 +1166,24 @@ mod tests {
         )
     }
 
+    #[test]
+    fn test_entity_change_uris() {
+        let uris = entity_change_uris("task", "abc", 7);
+        for expected in [
+            "medulla://tasks/ready",
+            "medulla://entities/task",
+            "medulla://entity/abc",
+            "medulla://entity/7",
+        ] {
+            assert!(uris.iter().any(|u| u == expected), "missing {}", expected);
+        }
+        assert!(!uris.iter().any(|u| u == static_resources::DECISIONS));
+    }
+
     #[test]
     fn test_build_static_resources() {
         let resources = build_static_resources();
-        assert_eq!(resources.len(), 9);
+        assert_eq!(resources.len(), 11);
         assert!(resources.iter().any(|r| r.uri == "medulla://schema"));
         assert!(resources.iter().any(|r| r.uri == "medulla://stats"));
         assert!(resources.iter().any(|r| r.uri == "medulla://entities"));
@@ -908,12 +1193,14 @@ mod tests {
         assert!(resources.iter().any(|r| r.uri == "medulla://tasks/blocked"));
         assert!(resources.iter().any(|r| r.uri == "medulla://prompts"));
         assert!(resources.iter().any(|r| r.uri == "medulla://graph"));
+        assert!(resources.iter().any(|r| r.uri == "medulla://focus"));
+        assert!(resources.iter().any(|r| r.uri == "medulla://dashboard"));
     }
 
     #[test]
     fn test_build_resource_templates() {
         let templates = build_resource_templates();
-        assert_eq!(templates.len(), 5);
+        assert_eq!(templates.len(), 6);
         assert!(templates
             .iter()
             .any(|t| t.uri_template == "medulla://entities/{type}"));
@@ -943,6 +1230,8 @@ mod tests {
             assert_eq!(uri, "medulla://schema");
             let parsed: serde_json::Value = serde_json::from_str(text).unwrap();
             assert!(parsed["entity_types"].is_array());
+            assert!(parsed["responses"]["EntityResponse"].is_object());
+            assert!(parsed["tools"]["search_fulltext"].is_object());
         } else {
             panic!("Expected TextResourceContents");
         }
@@ -974,6 +1263,41 @@ mod tests {
         }
     }
 
+    #[tokio::test]
+    async fn test_read_dashboard_resource() {
+        let (store, cache, _tmp) = setup_test_env().await;
+
+        {
+            let store = store.lock().await;
+            store
+                .add_decision(&Decision::new("Pick a queue".to_string(), 1))
+                .unwrap();
+            store.sync_cache(&*cache.lock().await).unwrap();
+        }
+
+        let result = read_resource("medulla://dashboard", &store, &cache)
+            .await
+            .unwrap();
+
+        assert_eq!(result.contents.len(), 2);
+        if let ResourceContents::TextResourceContents { text, .. } = &result.contents[0] {
+            let parsed: serde_json::Value = serde_json::from_str(text).unwrap();
+            assert_eq!(parsed["open_decision_count"], 1);
+            assert_eq!(parsed["health"]["status"], "ok");
+        } else {
+            panic!("Expected TextResourceContents");
+        }
+        if let ResourceContents::TextResourceContents {
+            mime_type, text, ..
+        } = &result.contents[1]
+        {
+            assert_eq!(mime_type.as_deref(), Some("text/markdown"));
+            assert!(text.contains("## Open decisions (1)"));
+        } else {
+            panic!("Expected TextResourceContents");
+        }
+    }
+
     #[tokio::test]
     async fn test_read_entities_resource() {
         let (store, cache, _tmp) = setup_test_env().await;
@@ -1137,6 +1461,36 @@ mod tests {
         }
     }
 
+    #[tokio::test]
+    async fn test_read_digest_resource() {
+        let (store, cache, _tmp) = setup_test_env().await;
+        {
+            let store = store.lock().await;
+            store
+                .add_task(&Task::new("Fresh task".to_string(), 1))
+                .unwrap();
+            store.save().unwrap();
+        }
+
+        let result = read_resource("medulla://digest/week", &store, &cache)
+            .await
+            .unwrap();
+        if let ResourceContents::TextResourceContents {
+            text, mime_type, ..
+        } = &result.contents[0]
+        {
+            assert_eq!(mime_type.as_deref(), Some("text/markdown"));
+            assert!(text.contains("## Created"));
+            assert!(text.contains("001 Fresh task"));
+        } else {
+            panic!("Expected TextResourceContents");
+        }
+
+        assert!(read_resource("medulla://digest/someday", &store, &cache)
+            .await
+            .is_err());
+    }
+
     #[tokio::test]
     async fn test_read_graph_resource() {
         let (store, cache, _tmp) = setup_test_env().await;
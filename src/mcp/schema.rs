@@ -0,0 +1,135 @@
+//! JSON Schema for Medulla's MCP surface.
+//!
+//! Response types are described with schemars; tool parameters come from
+//! the tool router, which already carries each tool's input schema. The
+//! combined document is served at `medulla://schema` and printed by
+//! `medulla schema dump`.
+
+use serde_json::{Map, Value};
+
+use super::error::VALID_ENTITY_TYPES;
+use super::tools::{
+    BatchResult, EntityResponse, FulltextSearchResponse, InlineRelationResponse, RelationResponse,
+    SemanticSearchResponse,
+};
+use super::MedullaServer;
+
+/// Statuses, priorities and type-specific fields of each entity type
+pub fn entity_metadata() -> Value {
+    serde_json::json!({
+        "entity_types": VALID_ENTITY_TYPES,
+        "decision": {
+            "status": ["proposed", "accepted", "deprecated", "superseded"],
+            "priority": ["low", "normal", "high", "urgent"],
+            "fields": ["context", "consequences", "superseded_by"]
+        },
+        "task": {
+            "status": ["todo", "in_progress", "done", "blocked"],
+            "priority": ["low", "normal", "high", "urgent"],
+            "fields": ["due_date", "assignee", "estimate_minutes", "recurrence"]
+        },
+        "note": {
+            "fields": ["note_type"]
+        },
+        "prompt": {
+            "fields": ["template", "variables", "output_schema"]
+        },
+        "component": {
+            "status": ["active", "deprecated", "planned"],
+            "priority": ["low", "normal", "high", "urgent"],
+            "fields": ["component_type", "owner"]
+        },
+        "link": {
+            "fields": ["url", "link_type"]
+        },
+        "question": {
+            "status": ["open", "answered"],
+            "fields": ["answered_by"]
+        },
+        "milestone": {
+            "status": ["open", "completed"],
+            "fields": ["target_date"]
+        },
+        "relation_types": ["blocks", "relates", "supersedes", "implements", "depends_on", "parent_of", "child_of"]
+    })
+}
+
+/// JSON Schema of each typed tool response, by type name
+pub fn response_schemas() -> Map<String, Value> {
+    fn schema<T: schemars::JsonSchema>() -> Value {
+        serde_json::to_value(schemars::schema_for!(T)).unwrap_or_default()
+    }
+
+    let mut schemas = Map::new();
+    schemas.insert("EntityResponse".to_string(), schema::<EntityResponse>());
+    schemas.insert("RelationResponse".to_string(), schema::<RelationResponse>());
+    schemas.insert(
+        "InlineRelationResponse".to_string(),
+        schema::<InlineRelationResponse>(),
+    );
+    schemas.insert("BatchResult".to_string(), schema::<BatchResult>());
+    schemas.insert(
+        "FulltextSearchResponse".to_string(),
+        schema::<FulltextSearchResponse>(),
+    );
+    schemas.insert(
+        "SemanticSearchResponse".to_string(),
+        schema::<SemanticSearchResponse>(),
+    );
+    schemas
+}
+
+/// Description and parameter schema of every MCP tool, by tool name
+pub fn tool_schemas() -> Map<String, Value> {
+    let mut tools: Vec<_> = MedullaServer::tool_router().list_all();
+    tools.sort_by(|a, b| a.name.cmp(&b.name));
+    tools
+        .into_iter()
+        .map(|tool| {
+            let schema = serde_json::json!({
+                "description": tool.description,
+                "parameters": Value::Object((*tool.input_schema).clone()),
+            });
+            (tool.name.to_string(), schema)
+        })
+        .collect()
+}
+
+/// The full schema document: entity metadata plus response and tool schemas
+pub fn schema_document() -> Value {
+    let mut document = entity_metadata();
+    if let Value::Object(ref mut map) = document {
+        map.insert(
+            "medulla_version".to_string(),
+            Value::from(env!("CARGO_PKG_VERSION")),
+        );
+        map.insert("responses".to_string(), Value::Object(response_schemas()));
+        map.insert("tools".to_string(), Value::Object(tool_schemas()));
+    }
+    document
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_schema_document() {
+        let document = schema_document();
+        assert!(document["entity_types"].is_array());
+
+        let entity = &document["responses"]["EntityResponse"];
+        assert_eq!(entity["type"], "object");
+        assert!(entity["properties"]["sequence_number"].is_object());
+        assert!(entity["properties"]["type"].is_object());
+        assert!(document["responses"]["BatchResult"]["properties"]["results"].is_object());
+
+        let hit = &document["responses"]["FulltextSearchResponse"];
+        assert!(hit["properties"]["results"].is_object());
+
+        let get = &document["tools"]["entity_get"];
+        assert!(get["description"].is_string());
+        assert!(get["parameters"]["properties"]["id"].is_object());
+        assert!(document["tools"]["prompt_render"].is_object());
+    }
+}
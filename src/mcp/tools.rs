@@ -4,13 +4,16 @@
 //! for MCP tools. The actual tool implementations are in mod.rs within the
 //! #[tool_router] impl block.
 
+use crate::cache::{DuplicateCandidate, SearchResult, SemanticSearchResult};
 use crate::entity::{
-    Component, ComponentStatus, Decision, DecisionStatus, Link, Note, Prompt, Relation, Task,
+    Component, ComponentStatus, Decision, DecisionStatus, EntityTemplate, Link, Milestone,
+    MilestoneStatus, Note, Prompt, Question, QuestionStatus, Recurrence, Relation, Task,
     TaskPriority, TaskStatus,
 };
 use crate::mcp::error::{validation, McpError, VALID_ENTITY_TYPES};
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
 
 // ============================================================================
 // Parameter and Result Types
@@ -19,7 +22,7 @@ use serde::{Deserialize, Serialize};
 /// Parameters for entity_create tool
 #[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct EntityCreateParams {
-    /// Entity type: decision, task, note, prompt, component, or link
+    /// Entity type: decision, task, note, prompt, component, link, question, or milestone
     #[serde(rename = "type")]
     pub entity_type: String,
     /// Title for the entity (required, 1-500 characters)
@@ -32,6 +35,21 @@ pub struct EntityCreateParams {
     pub properties: Option<serde_json::Value>,
 }
 
+/// Parameters for entity_create_from_template tool
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct EntityCreateFromTemplateParams {
+    /// Template name
+    pub template: String,
+    /// Title for the entity (required, 1-500 characters)
+    pub title: String,
+    /// Content, replacing the template's skeleton
+    pub content: Option<String>,
+    /// Tags added to the template's tags
+    pub tags: Option<Vec<String>>,
+    /// Properties overriding the template's defaults
+    pub properties: Option<serde_json::Value>,
+}
+
 /// Parameters for entity_get tool
 #[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct EntityGetParams {
@@ -40,6 +58,14 @@ pub struct EntityGetParams {
     /// Optional entity type hint for faster lookup
     #[serde(rename = "type")]
     pub entity_type: Option<String>,
+    /// Include outgoing/incoming relations in the response (default false)
+    pub include_relations: Option<bool>,
+    /// Include summaries of the entities reachable through relations, in
+    /// either direction (default false)
+    pub include_related_entities: Option<bool>,
+    /// How many relation hops to follow for related entities (default 1,
+    /// max 3)
+    pub depth: Option<u32>,
 }
 
 /// Parameters for entity_list tool
@@ -48,14 +74,40 @@ pub struct EntityListParams {
     /// Filter by entity type
     #[serde(rename = "type")]
     pub entity_type: Option<String>,
-    /// Filter by status (for decision/task/component)
+    /// Filter by status (for decision/task/component/question/milestone)
     pub status: Option<String>,
     /// Filter by tag
     pub tag: Option<String>,
+    /// Only entities in this collection
+    pub collection: Option<String>,
+    /// Only entities created at or after this date (RFC 3339 or YYYY-MM-DD)
+    pub created_after: Option<String>,
+    /// Only entities updated at or after this date (RFC 3339 or YYYY-MM-DD)
+    pub updated_after: Option<String>,
+    /// Sort field: "sequence" (default), "created", "updated", "title" or
+    /// "priority" (unprioritized entities sort lowest)
+    #[serde(alias = "sort")]
+    pub sort_by: Option<String>,
+    /// Sort direction: "asc" or "desc". Defaults to "desc" for priority
+    /// and "asc" otherwise.
+    pub order: Option<String>,
     /// Maximum results (default 50, max 100)
     pub limit: Option<u32>,
     /// Offset for pagination
     pub offset: Option<u32>,
+    /// Opaque cursor from a previous page's `next_cursor`. Continues after
+    /// the last entity returned, so entities created or deleted in between
+    /// do not shift the page. Not combinable with `offset`, `sort_by` or
+    /// `order`.
+    pub cursor: Option<String>,
+}
+
+/// Parameters for entity_index tool
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct EntityIndexParams {
+    /// Entity type to index
+    #[serde(rename = "type")]
+    pub entity_type: String,
 }
 
 /// Parameters for entity_update tool
@@ -73,6 +125,10 @@ pub struct EntityUpdateParams {
     pub remove_tags: Option<Vec<String>>,
     /// Type-specific properties to update
     pub properties: Option<serde_json::Value>,
+    /// Change the status even if the configured workflow does not allow
+    /// the transition (default false)
+    #[serde(default)]
+    pub force: Option<bool>,
 }
 
 /// Parameters for entity_delete tool
@@ -82,6 +138,15 @@ pub struct EntityDeleteParams {
     pub id: String,
 }
 
+/// Parameters for entity_history tool
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct EntityHistoryParams {
+    /// Entity ID (sequence number, UUID prefix, or full UUID for deleted entities)
+    pub id: String,
+    /// Return the entity's state as of this time (ISO 8601 datetime or YYYY-MM-DD)
+    pub at: Option<String>,
+}
+
 /// A single operation in a batch
 #[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 #[serde(tag = "op")]
@@ -99,10 +164,36 @@ pub enum BatchOperation {
 pub struct EntityBatchParams {
     /// Operations to perform (max 100)
     pub operations: Vec<BatchOperation>,
+    /// All or nothing: stop at the first failed operation and apply none of
+    /// them (default false: best-effort)
+    #[serde(default)]
+    pub atomic: Option<bool>,
+}
+
+/// Parameters for entity_import_rows tool
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct EntityImportRowsParams {
+    /// Row objects (max 100). Each row needs `type` and `title`, and may have
+    /// `content`, `tags` (array or comma-separated string), and type-specific fields.
+    pub rows: Vec<serde_json::Value>,
+}
+
+/// Parameters for entity_bulk_update tool
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct EntityBulkUpdateParams {
+    /// Filter expression selecting the entities, e.g. "type:task status:done";
+    /// any words left over are matched with full-text search
+    pub query: String,
+    /// Tags to add to every matching entity
+    pub add_tags: Option<Vec<String>>,
+    /// Tags to remove from every matching entity
+    pub remove_tags: Option<Vec<String>>,
+    /// List the matching entities without changing them
+    pub dry_run: Option<bool>,
 }
 
 /// Result of a single batch operation
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct BatchOperationResult {
     pub index: usize,
     pub success: bool,
@@ -113,18 +204,21 @@ pub struct BatchOperationResult {
 }
 
 /// Error in a batch operation
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct BatchError {
     pub code: String,
     pub message: String,
 }
 
 /// Result of entity_batch tool
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct BatchResult {
     pub results: Vec<BatchOperationResult>,
     pub succeeded: usize,
     pub failed: usize,
+    /// Why an atomic batch was rolled back; when set, no operation was applied
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub abort_reason: Option<String>,
 }
 
 /// Parameters for search_fulltext tool
@@ -137,6 +231,14 @@ pub struct SearchFulltextParams {
     pub entity_type: Option<String>,
     /// Maximum results (default 50, max 100)
     pub limit: Option<u32>,
+    /// Number of results to skip, for paging through the merged ranking
+    /// (default 0)
+    pub offset: Option<u32>,
+    /// Only return entities in the current focus set (default: false)
+    #[serde(default)]
+    pub within_focus: Option<bool>,
+    /// Only return entities in this collection
+    pub collection: Option<String>,
 }
 
 /// Parameters for search_semantic tool
@@ -149,10 +251,52 @@ pub struct SearchSemanticParams {
     pub entity_type: Option<String>,
     /// Maximum results (default 10)
     pub limit: Option<u32>,
+    /// Minimum similarity score (0.0-1.0, default 0.3)
+    pub min_score: Option<f32>,
+    /// Older name for `min_score`, used when `min_score` is not given
+    pub threshold: Option<f32>,
+    /// Full-text query candidates must also match; only entities it finds
+    /// are ranked by similarity
+    pub prefilter: Option<String>,
+}
+
+/// Parameters for search_by_vector tool
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct SearchByVectorParams {
+    /// Query embedding; must match the dimension of stored embeddings (384)
+    pub vector: Vec<f32>,
+    /// Optional entity type filter (decision, task, note, etc.)
+    #[serde(rename = "type")]
+    pub entity_type: Option<String>,
+    /// Maximum results (default 10)
+    pub limit: Option<u32>,
     /// Minimum similarity threshold (0.0-1.0, default 0.3)
     pub threshold: Option<f32>,
 }
 
+/// Parameters for entity_duplicates tool
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct EntityDuplicatesParams {
+    /// Optional entity type filter (decision, task, note, etc.)
+    #[serde(rename = "type")]
+    pub entity_type: Option<String>,
+    /// Minimum similarity for a pair to be reported (0.0-1.0, default 0.9)
+    pub threshold: Option<f32>,
+    /// Maximum pairs (default 20)
+    pub limit: Option<u32>,
+}
+
+/// Parameters for entity_related tool
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct EntityRelatedParams {
+    /// Entity ID (full UUID, prefix, sequence number or alias)
+    pub id: String,
+    /// Maximum suggestions (default 5)
+    pub limit: Option<u32>,
+    /// Minimum embedding similarity of a suggestion (0.0-1.0, default 0.5)
+    pub min_score: Option<f32>,
+}
+
 /// Parameters for search_query tool (combines fulltext, semantic, and filters)
 #[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct SearchQueryParams {
@@ -171,6 +315,10 @@ pub struct SearchQueryParams {
     pub created_after: Option<String>,
     /// Filter: created before this date (ISO 8601 format)
     pub created_before: Option<String>,
+    /// Filter by author (created_by, case-insensitive)
+    pub created_by: Option<String>,
+    /// Filter by task assignee (case-insensitive)
+    pub assignee: Option<String>,
     /// Maximum results (default 20)
     pub limit: Option<u32>,
 }
@@ -195,6 +343,24 @@ pub struct GraphPathParams {
     pub max_depth: Option<u32>,
 }
 
+/// Parameters for graph_export tool
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct GraphExportParams {
+    /// Output format: "dot" (GraphViz) or "mermaid" (default "mermaid")
+    pub format: Option<String>,
+    /// Only export the subgraph around this entity ID
+    pub root: Option<String>,
+    /// Maximum hops from the root (default: everything reachable)
+    pub depth: Option<u32>,
+}
+
+/// Parameters for graph_analytics tool
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct GraphAnalyticsParams {
+    /// Number of hub entities to return (default 10, max 100)
+    pub limit: Option<u32>,
+}
+
 /// Parameters for graph_orphans tool
 #[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct GraphOrphansParams {
@@ -203,6 +369,8 @@ pub struct GraphOrphansParams {
     pub entity_type: Option<String>,
     /// Maximum results (default 50, max 100)
     pub limit: Option<u32>,
+    /// Offset for pagination
+    pub offset: Option<u32>,
 }
 
 // ============================================================================
@@ -214,6 +382,43 @@ pub struct GraphOrphansParams {
 pub struct TaskReadyParams {
     /// Maximum results (default 50, max 100)
     pub limit: Option<u32>,
+    /// Skip tasks whose assignee already holds as many in-progress tasks as
+    /// the project's wip_limits allow (default false)
+    #[serde(default)]
+    pub respect_wip_limits: Option<bool>,
+}
+
+/// Parameters for task_next tool
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+pub struct TaskNextParams {
+    /// Skip tasks whose assignee already holds as many in-progress tasks as
+    /// the project's wip_limits allow (default false)
+    #[serde(default)]
+    pub respect_wip_limits: Option<bool>,
+}
+
+/// Parameters for task_overdue tool
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct TaskOverdueParams {
+    /// Maximum results (default 50, max 100)
+    pub limit: Option<u32>,
+}
+
+/// Parameters for task_due tool
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct TaskDueParams {
+    /// How far ahead to look, e.g. "7d" or "2w" (default "7d")
+    pub within: Option<String>,
+    /// Maximum results (default 50, max 100)
+    pub limit: Option<u32>,
+}
+
+/// Parameters for task_by_assignee tool
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct TaskByAssigneeParams {
+    /// Only show this person's queue (case-insensitive); every assignee
+    /// plus the unassigned bucket if omitted
+    pub assignee: Option<String>,
 }
 
 /// Parameters for task_blocked tool
@@ -225,6 +430,21 @@ pub struct TaskBlockedParams {
     pub limit: Option<u32>,
 }
 
+/// Parameters for task_tree tool
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct TaskTreeParams {
+    /// Root task ID (sequence number or UUID prefix)
+    pub id: String,
+}
+
+/// Parameters for milestone_progress tool
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct MilestoneProgressParams {
+    /// Milestone ID (sequence number or UUID prefix). Omit for every
+    /// unarchived milestone.
+    pub id: Option<String>,
+}
+
 /// Parameters for task_complete tool
 #[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct TaskCompleteParams {
@@ -241,6 +461,13 @@ pub struct TaskRescheduleParams {
     pub due_date: String,
 }
 
+/// Parameters for project_burndown tool
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct ProjectBurndownParams {
+    /// Only count tasks with this tag (the "project"); all tasks if omitted
+    pub tag: Option<String>,
+}
+
 /// Parameters for decision_supersede tool
 #[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct DecisionSupersedeParams {
@@ -248,6 +475,9 @@ pub struct DecisionSupersedeParams {
     pub old_id: String,
     /// ID of the new decision that supersedes it
     pub new_id: String,
+    /// Repoint incoming relations from the old decision to the new one (default: false)
+    #[serde(default)]
+    pub migrate_relations: Option<bool>,
 }
 
 /// Parameters for relation_create tool
@@ -261,6 +491,25 @@ pub struct RelationCreateParams {
     pub relation_type: String,
 }
 
+/// One relation of a relation_batch
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct RelationBatchItem {
+    /// Source entity: sequence number, UUID prefix or exact title
+    pub source: String,
+    /// Relation type: implements, blocks, supersedes, references, belongs_to, documents
+    pub relation_type: String,
+    /// Target entity: sequence number, UUID prefix or exact title
+    pub target: String,
+}
+
+/// Parameters for relation_batch tool
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct RelationBatchParams {
+    pub relations: Vec<RelationBatchItem>,
+    /// Validate every relation without creating any (default: false)
+    pub dry_run: Option<bool>,
+}
+
 /// Parameters for relation_delete tool
 #[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct RelationDeleteParams {
@@ -272,8 +521,93 @@ pub struct RelationDeleteParams {
     pub relation_type: String,
 }
 
+/// Parameters for relation_resolve tool
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct RelationResolveParams {
+    /// Source entity ID (sequence number or UUID prefix)
+    pub source_id: String,
+    /// Target entity ID (sequence number or UUID prefix)
+    pub target_id: String,
+    /// Relation type: implements, blocks, supersedes, references, belongs_to, documents
+    pub relation_type: String,
+}
+
+/// Parameters for snapshot_preview tool
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct SnapshotPreviewParams {
+    /// Entity ID (sequence number or UUID prefix)
+    pub id: String,
+}
+
+/// Parameters for entity_outline tool
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct EntityOutlineParams {
+    /// Entity ID (sequence number or UUID prefix)
+    pub id: String,
+}
+
+/// Parameters for entity_get_section tool
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct EntityGetSectionParams {
+    /// Entity ID (sequence number or UUID prefix)
+    pub id: String,
+    /// Section heading text (case-insensitive) or anchor slug, e.g. "Context"
+    pub section: String,
+}
+
+/// Parameters for entity_patch_section tool
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct EntityPatchSectionParams {
+    /// Entity ID (sequence number or UUID prefix)
+    pub id: String,
+    /// Section heading text (case-insensitive) or anchor slug, e.g. "Outcome"
+    pub section: String,
+    /// Markdown for the section body, without its heading
+    #[serde(default)]
+    pub content: Option<String>,
+    /// "replace" (default), "append" or "delete"
+    #[serde(default)]
+    pub mode: Option<String>,
+    /// Heading level when the section has to be added (1-6, default 2)
+    #[serde(default)]
+    pub level: Option<u8>,
+}
+
+/// Parameters for context_pack tool
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct ContextPackParams {
+    /// What the context is for; matched semantically and with full-text search
+    pub query: Option<String>,
+    /// Entity to build the context around (sequence number or UUID prefix)
+    pub id: Option<String>,
+    /// Token budget for the packed document (default 4000, max 32000)
+    pub max_tokens: Option<u32>,
+    /// Relation hops to follow from the matches (default 1, max 3)
+    pub depth: Option<u32>,
+}
+
+/// Parameters for prompt_render tool
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct PromptRenderParams {
+    /// Prompt ID (sequence number or UUID prefix)
+    pub id: String,
+    /// Values for the template's {{name}} placeholders; non-string values
+    /// are inserted as JSON
+    pub variables: Option<HashMap<String, serde_json::Value>>,
+    /// Output produced with the rendered prompt, to check against the
+    /// prompt's output_schema. A string holding JSON is parsed first.
+    pub output: Option<serde_json::Value>,
+}
+
+/// Parameters for focus_set tool
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct FocusSetParams {
+    /// Entity IDs (sequence numbers or UUID prefixes); an empty list clears the focus
+    pub ids: Vec<String>,
+}
+
 /// A serializable entity response
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct EntityResponse {
     pub id: String,
     pub sequence_number: u32,
@@ -290,8 +624,20 @@ pub struct EntityResponse {
     pub properties: serde_json::Value,
 }
 
+/// Response of entity_create: the new entity, plus existing entities of
+/// the same type it likely duplicates
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct EntityCreateResponse {
+    #[serde(flatten)]
+    pub entity: EntityResponse,
+    /// Entities with a near-identical title or embedding, most similar
+    /// first. Consider updating or merging instead of keeping both.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub duplicate_candidates: Vec<DuplicateCandidate>,
+}
+
 /// A relation in response format
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct RelationResponse {
     pub source_id: String,
     pub source_type: String,
@@ -299,6 +645,224 @@ pub struct RelationResponse {
     pub target_type: String,
     pub relation_type: String,
     pub created_at: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub resolved_at: Option<String>,
+    /// Label for a custom relation type seen from the target's end
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub inverse_label: Option<String>,
+}
+
+/// A relation annotated with the title of the entity on the other end
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct InlineRelationResponse {
+    #[serde(flatten)]
+    pub relation: RelationResponse,
+    pub related_title: Option<String>,
+}
+
+/// Summary of an entity reached from the one fetched by entity_get
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct RelatedEntitySummary {
+    pub id: String,
+    pub sequence_number: u32,
+    #[serde(rename = "type")]
+    pub entity_type: String,
+    pub title: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub status: Option<String>,
+    /// Relation hops from the fetched entity
+    pub distance: u32,
+}
+
+/// A full-text search hit; type-specific fields are only set for the
+/// entity types that have them
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct FulltextHit {
+    #[serde(rename = "type")]
+    pub entity_type: String,
+    pub id: String,
+    pub sequence_number: u32,
+    pub title: String,
+    /// Decisions, tasks and components
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub status: Option<String>,
+    /// Tasks
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub priority: Option<String>,
+    /// Notes
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub note_type: Option<String>,
+    /// Prompts
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub variables: Option<Vec<String>>,
+    /// Components
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub component_type: Option<String>,
+    /// Links
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    /// Links
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub link_type: Option<String>,
+    /// Title with the matched terms highlighted
+    pub title_highlight: Option<String>,
+    /// Excerpt of the content around the matched terms
+    pub content_snippet: Option<String>,
+}
+
+impl FulltextHit {
+    fn new(
+        entity_type: &str,
+        id: String,
+        sequence_number: u32,
+        title: String,
+        title_highlight: Option<String>,
+        content_snippet: Option<String>,
+    ) -> Self {
+        Self {
+            entity_type: entity_type.to_string(),
+            id,
+            sequence_number,
+            title,
+            status: None,
+            priority: None,
+            note_type: None,
+            variables: None,
+            component_type: None,
+            url: None,
+            link_type: None,
+            title_highlight,
+            content_snippet,
+        }
+    }
+}
+
+impl From<SearchResult> for FulltextHit {
+    fn from(result: SearchResult) -> Self {
+        match result {
+            SearchResult::Decision(r) => Self {
+                status: Some(r.status),
+                ..Self::new(
+                    "decision",
+                    r.id,
+                    r.sequence_number,
+                    r.title,
+                    r.title_highlight,
+                    r.content_snippet,
+                )
+            },
+            SearchResult::Task(r) => Self {
+                status: Some(r.status),
+                priority: Some(r.priority),
+                ..Self::new(
+                    "task",
+                    r.id,
+                    r.sequence_number,
+                    r.title,
+                    r.title_highlight,
+                    r.content_snippet,
+                )
+            },
+            SearchResult::Note(r) => Self {
+                note_type: r.note_type,
+                ..Self::new(
+                    "note",
+                    r.id,
+                    r.sequence_number,
+                    r.title,
+                    r.title_highlight,
+                    r.content_snippet,
+                )
+            },
+            SearchResult::Prompt(r) => Self {
+                variables: Some(r.variables),
+                ..Self::new(
+                    "prompt",
+                    r.id,
+                    r.sequence_number,
+                    r.title,
+                    r.title_highlight,
+                    r.content_snippet,
+                )
+            },
+            SearchResult::Component(r) => Self {
+                status: Some(r.status),
+                component_type: r.component_type,
+                ..Self::new(
+                    "component",
+                    r.id,
+                    r.sequence_number,
+                    r.title,
+                    r.title_highlight,
+                    r.content_snippet,
+                )
+            },
+            SearchResult::Link(r) => Self {
+                url: Some(r.url),
+                link_type: r.link_type,
+                ..Self::new(
+                    "link",
+                    r.id,
+                    r.sequence_number,
+                    r.title,
+                    r.title_highlight,
+                    r.content_snippet,
+                )
+            },
+            SearchResult::Question(r) => Self {
+                status: Some(r.status),
+                ..Self::new(
+                    "question",
+                    r.id,
+                    r.sequence_number,
+                    r.title,
+                    r.title_highlight,
+                    r.content_snippet,
+                )
+            },
+            SearchResult::Milestone(r) => Self {
+                status: Some(r.status),
+                ..Self::new(
+                    "milestone",
+                    r.id,
+                    r.sequence_number,
+                    r.title,
+                    r.title_highlight,
+                    r.content_snippet,
+                )
+            },
+        }
+    }
+}
+
+/// Result of search_fulltext
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct FulltextSearchResponse {
+    /// This page of hits, most relevant first across all types
+    pub results: Vec<FulltextHit>,
+    /// Hits across every searched type, not just this page
+    pub total: usize,
+    /// Hits per searched type
+    pub counts: BTreeMap<String, usize>,
+    pub limit: usize,
+    pub offset: usize,
+    pub has_more: bool,
+    pub next_offset: Option<usize>,
+    pub query: String,
+}
+
+/// Result of search_semantic
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct SemanticSearchResponse {
+    pub results: Vec<SemanticSearchResult>,
+    pub total: usize,
+    pub query: String,
+    pub min_score: f32,
+    /// Same as min_score, kept for older clients
+    pub threshold: f32,
+    pub prefilter: Option<String>,
+    /// Entities matching the prefilter, when one was given
+    pub candidates: Option<usize>,
 }
 
 // ============================================================================
@@ -365,6 +929,129 @@ pub fn validate_tags(tags: &Option<Vec<String>>) -> Result<(), McpError> {
     Ok(())
 }
 
+/// Reject enum values that aren't written exactly as they are stored, such
+/// as "Accepted" or "in-progress" for "in_progress". Values that aren't a
+/// known variant (custom relation types) must still be lowercase and
+/// trimmed. Only used when the project config enables `strict_enums`.
+pub fn check_strict_enum(field: &str, value: &str) -> Result<(), McpError> {
+    fn canonical<T: std::str::FromStr + std::fmt::Display>(value: &str) -> Option<String> {
+        value.parse::<T>().ok().map(|parsed| parsed.to_string())
+    }
+    // A status is canonical if any entity type stores it as given
+    let forms: Vec<String> = match field {
+        "status" => [
+            canonical::<DecisionStatus>(value),
+            canonical::<TaskStatus>(value),
+            canonical::<ComponentStatus>(value),
+            canonical::<QuestionStatus>(value),
+            canonical::<MilestoneStatus>(value),
+        ]
+        .into_iter()
+        .flatten()
+        .collect(),
+        "priority" => canonical::<TaskPriority>(value).into_iter().collect(),
+        "relation_type" => canonical::<crate::entity::RelationType>(value)
+            .into_iter()
+            .collect(),
+        _ => Vec::new(),
+    };
+    match forms.first() {
+        Some(_) if forms.iter().any(|form| form == value) => Ok(()),
+        Some(form) => Err(McpError::ValidationFailed {
+            field: field.to_string(),
+            message: format!(
+                "'{}' must be written '{}' (strict_enums is enabled)",
+                value, form
+            ),
+        }),
+        None if value.trim() != value || value.to_lowercase() != value => {
+            Err(McpError::ValidationFailed {
+                field: field.to_string(),
+                message: format!(
+                    "'{}' must be lowercase without surrounding whitespace (strict_enums is enabled)",
+                    value
+                ),
+            })
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Convert an import row into create parameters.
+/// Fields other than `type`, `title`, `content` and `tags` become properties;
+/// null values are skipped.
+pub fn row_to_create_params(row: &serde_json::Value) -> Result<EntityCreateParams, McpError> {
+    let Some(fields) = row.as_object() else {
+        return Err(McpError::ValidationFailed {
+            field: "row".to_string(),
+            message: "Row must be an object".to_string(),
+        });
+    };
+
+    let string_field = |name: &str| -> Result<Option<String>, McpError> {
+        match fields.get(name) {
+            None | Some(serde_json::Value::Null) => Ok(None),
+            Some(serde_json::Value::String(s)) => Ok(Some(s.clone())),
+            Some(_) => Err(McpError::ValidationFailed {
+                field: name.to_string(),
+                message: format!("'{}' must be a string", name),
+            }),
+        }
+    };
+
+    let entity_type = string_field("type")?.ok_or_else(|| McpError::ValidationFailed {
+        field: "type".to_string(),
+        message: "Row is missing 'type'".to_string(),
+    })?;
+    let title = string_field("title")?.ok_or_else(|| McpError::ValidationFailed {
+        field: "title".to_string(),
+        message: "Row is missing 'title'".to_string(),
+    })?;
+    let content = string_field("content")?;
+
+    let tags = match fields.get("tags") {
+        None | Some(serde_json::Value::Null) => None,
+        Some(serde_json::Value::String(s)) => Some(
+            s.split(',')
+                .map(|t| t.trim().to_string())
+                .filter(|t| !t.is_empty())
+                .collect(),
+        ),
+        Some(serde_json::Value::Array(items)) => Some(
+            items
+                .iter()
+                .filter_map(|v| v.as_str().map(String::from))
+                .collect(),
+        ),
+        Some(_) => {
+            return Err(McpError::ValidationFailed {
+                field: "tags".to_string(),
+                message: "'tags' must be an array or comma-separated string".to_string(),
+            })
+        }
+    };
+
+    let properties: serde_json::Map<String, serde_json::Value> = fields
+        .iter()
+        .filter(|(k, v)| {
+            !matches!(k.as_str(), "type" | "title" | "content" | "tags") && !v.is_null()
+        })
+        .map(|(k, v)| (k.clone(), v.clone()))
+        .collect();
+
+    Ok(EntityCreateParams {
+        entity_type,
+        title,
+        content,
+        tags,
+        properties: if properties.is_empty() {
+            None
+        } else {
+            Some(serde_json::Value::Object(properties))
+        },
+    })
+}
+
 pub fn parse_decision_status(s: &str) -> Result<DecisionStatus, McpError> {
     s.parse().map_err(|_| McpError::InvalidEnumValue {
         field: "status".to_string(),
@@ -404,6 +1091,17 @@ pub fn parse_task_priority(s: &str) -> Result<TaskPriority, McpError> {
     })
 }
 
+/// Parse an optional priority property, where null clears the priority
+pub fn parse_optional_priority(
+    value: &serde_json::Value,
+) -> Result<Option<TaskPriority>, McpError> {
+    match value {
+        serde_json::Value::Null => Ok(None),
+        serde_json::Value::String(s) => parse_task_priority(s).map(Some),
+        other => parse_task_priority(&other.to_string()).map(Some),
+    }
+}
+
 pub fn parse_component_status(s: &str) -> Result<ComponentStatus, McpError> {
     s.parse().map_err(|_| McpError::InvalidEnumValue {
         field: "status".to_string(),
@@ -416,6 +1114,190 @@ pub fn parse_component_status(s: &str) -> Result<ComponentStatus, McpError> {
     })
 }
 
+pub fn parse_question_status(s: &str) -> Result<QuestionStatus, McpError> {
+    s.parse().map_err(|_| McpError::InvalidEnumValue {
+        field: "status".to_string(),
+        value: s.to_string(),
+        valid: vec!["open".to_string(), "answered".to_string()],
+    })
+}
+
+pub fn parse_milestone_status(s: &str) -> Result<MilestoneStatus, McpError> {
+    s.parse().map_err(|_| McpError::InvalidEnumValue {
+        field: "status".to_string(),
+        value: s.to_string(),
+        valid: vec!["open".to_string(), "completed".to_string()],
+    })
+}
+
+/// Parse a task estimate in whole minutes; null clears it
+pub fn parse_estimate(value: &serde_json::Value) -> Result<Option<u32>, McpError> {
+    let minutes = match value {
+        serde_json::Value::Null => return Ok(None),
+        serde_json::Value::Number(n) => n.as_u64().and_then(|n| u32::try_from(n).ok()),
+        serde_json::Value::String(s) => s.trim().parse().ok(),
+        _ => None,
+    };
+    minutes.map(Some).ok_or_else(|| McpError::ValidationFailed {
+        field: "estimate_minutes".to_string(),
+        message: format!(
+            "Estimate must be a non-negative whole number of minutes, got {}",
+            value
+        ),
+    })
+}
+
+/// Combine a template with the caller's fields into entity_create arguments.
+/// The caller's content replaces the skeleton, tags are added to the
+/// template's, and properties override the template's defaults.
+pub fn template_create_params(
+    template: &EntityTemplate,
+    params: EntityCreateFromTemplateParams,
+    today: chrono::NaiveDate,
+) -> Result<EntityCreateParams, McpError> {
+    let mut properties = template.properties.clone();
+    match params.properties {
+        Some(serde_json::Value::Object(overrides)) => properties.extend(overrides),
+        Some(serde_json::Value::Null) | None => {}
+        Some(other) => {
+            return Err(McpError::ValidationFailed {
+                field: "properties".to_string(),
+                message: format!("Properties must be an object, got {}", other),
+            })
+        }
+    }
+
+    let mut tags = template.tags.clone();
+    for tag in params.tags.unwrap_or_default() {
+        if !tags.contains(&tag) {
+            tags.push(tag);
+        }
+    }
+
+    let content = params
+        .content
+        .or_else(|| template.render_content(params.title.trim(), today));
+
+    Ok(EntityCreateParams {
+        entity_type: template.entity_type.clone(),
+        title: params.title,
+        content,
+        tags: (!tags.is_empty()).then_some(tags),
+        properties: (!properties.is_empty()).then_some(serde_json::Value::Object(properties)),
+    })
+}
+
+/// Parse a task recurrence rule; null clears it
+pub fn parse_recurrence(value: &serde_json::Value) -> Result<Option<Recurrence>, McpError> {
+    match value {
+        serde_json::Value::Null => Ok(None),
+        serde_json::Value::String(s) => {
+            s.parse()
+                .map(Some)
+                .map_err(|message| McpError::ValidationFailed {
+                    field: "recurrence".to_string(),
+                    message,
+                })
+        }
+        _ => Err(McpError::ValidationFailed {
+            field: "recurrence".to_string(),
+            message: format!("Recurrence must be a string, got {}", value),
+        }),
+    }
+}
+
+/// Offset of the next page, or None when `page_len` items at `offset` reach the end
+pub fn next_page_offset(offset: usize, page_len: usize, total: usize) -> Option<usize> {
+    let next = offset + page_len;
+    (page_len > 0 && next < total).then_some(next)
+}
+
+/// Cursor pointing after the entity with this sequence number
+pub fn encode_list_cursor(sequence_number: u32) -> String {
+    format!("c{:08x}", sequence_number)
+}
+
+/// Sequence number a cursor from [`encode_list_cursor`] points after
+pub fn decode_list_cursor(cursor: &str) -> Option<u32> {
+    let hex = cursor.strip_prefix('c')?;
+    if hex.len() != 8 {
+        return None;
+    }
+    u32::from_str_radix(hex, 16).ok()
+}
+
+/// Order items so every blocker comes before the items it blocks.
+///
+/// `blocks` holds `(blocker, blocked)` index pairs. Among items that are free
+/// at the same time, the lowest rank goes first. Returns the order and the
+/// items left out because they are on or behind a cycle.
+pub fn topological_order<K: Ord>(
+    ranks: &[K],
+    blocks: &[(usize, usize)],
+) -> (Vec<usize>, Vec<usize>) {
+    use std::cmp::Reverse;
+    use std::collections::BinaryHeap;
+
+    let mut in_degree = vec![0usize; ranks.len()];
+    let mut blocked_by: Vec<Vec<usize>> = vec![Vec::new(); ranks.len()];
+    for &(blocker, blocked) in blocks {
+        in_degree[blocked] += 1;
+        blocked_by[blocker].push(blocked);
+    }
+
+    let mut free: BinaryHeap<Reverse<(&K, usize)>> = (0..ranks.len())
+        .filter(|&i| in_degree[i] == 0)
+        .map(|i| Reverse((&ranks[i], i)))
+        .collect();
+    let mut order = Vec::with_capacity(ranks.len());
+    while let Some(Reverse((_, i))) = free.pop() {
+        order.push(i);
+        for &next in &blocked_by[i] {
+            in_degree[next] -= 1;
+            if in_degree[next] == 0 {
+                free.push(Reverse((&ranks[next], next)));
+            }
+        }
+    }
+
+    let leftover = (0..ranks.len()).filter(|&i| in_degree[i] > 0).collect();
+    (order, leftover)
+}
+
+/// Group the given items into dependency cycles, using `(blocker, blocked)` pairs.
+/// Items that are only downstream of a cycle are not part of any group.
+pub fn dependency_cycles(items: &[usize], blocks: &[(usize, usize)]) -> Vec<Vec<usize>> {
+    let reachable = |start: usize| -> HashSet<usize> {
+        let mut seen = HashSet::new();
+        let mut stack = vec![start];
+        while let Some(i) = stack.pop() {
+            for &(blocker, blocked) in blocks {
+                if blocker == i && items.contains(&blocked) && seen.insert(blocked) {
+                    stack.push(blocked);
+                }
+            }
+        }
+        seen
+    };
+
+    let reach: HashMap<usize, HashSet<usize>> = items.iter().map(|&i| (i, reachable(i))).collect();
+    let mut assigned = HashSet::new();
+    let mut cycles = Vec::new();
+    for &i in items {
+        if assigned.contains(&i) || !reach[&i].contains(&i) {
+            continue;
+        }
+        let cycle: Vec<usize> = items
+            .iter()
+            .copied()
+            .filter(|j| reach[&i].contains(j) && reach[j].contains(&i))
+            .collect();
+        assigned.extend(cycle.iter().copied());
+        cycles.push(cycle);
+    }
+    cycles
+}
+
 pub fn parse_date(field: &str, value: &str) -> Result<chrono::NaiveDate, McpError> {
     chrono::NaiveDate::parse_from_str(value, "%Y-%m-%d").map_err(|_| McpError::InvalidDateFormat {
         field: field.to_string(),
@@ -449,6 +1331,7 @@ pub fn decision_to_response(d: &Decision) -> EntityResponse {
         "context": d.context,
         "consequences": d.consequences,
         "superseded_by": d.superseded_by,
+        "priority": d.priority.map(|p| p.to_string()),
     });
     EntityResponse {
         id: d.base.id.to_string(),
@@ -470,6 +1353,8 @@ pub fn task_to_response(t: &Task) -> EntityResponse {
         "priority": t.priority.to_string(),
         "due_date": t.due_date.map(|d| d.to_string()),
         "assignee": t.assignee,
+        "estimate_minutes": t.estimate_minutes,
+        "recurrence": t.recurrence.as_ref().map(|r| r.to_string()),
     });
     EntityResponse {
         id: t.base.id.to_string(),
@@ -528,6 +1413,7 @@ pub fn component_to_response(c: &Component) -> EntityResponse {
         "component_type": c.component_type,
         "status": c.status.to_string(),
         "owner": c.owner,
+        "priority": c.priority.map(|p| p.to_string()),
     });
     EntityResponse {
         id: c.base.id.to_string(),
@@ -547,6 +1433,10 @@ pub fn link_to_response(l: &Link) -> EntityResponse {
     let props = serde_json::json!({
         "url": l.url,
         "link_type": l.link_type,
+        "description": l.description,
+        "favicon": l.favicon,
+        "content_hash": l.content_hash,
+        "fetched_at": l.fetched_at.map(|t| t.to_rfc3339()),
     });
     EntityResponse {
         id: l.base.id.to_string(),
@@ -562,6 +1452,44 @@ pub fn link_to_response(l: &Link) -> EntityResponse {
     }
 }
 
+pub fn question_to_response(q: &Question) -> EntityResponse {
+    let props = serde_json::json!({
+        "status": q.status.to_string(),
+        "answered_by": q.answered_by,
+    });
+    EntityResponse {
+        id: q.base.id.to_string(),
+        sequence_number: q.base.sequence_number,
+        entity_type: "question".to_string(),
+        title: q.base.title.clone(),
+        content: q.base.content.clone(),
+        tags: q.base.tags.clone(),
+        created_at: q.base.created_at.to_rfc3339(),
+        updated_at: q.base.updated_at.to_rfc3339(),
+        created_by: q.base.created_by.clone(),
+        properties: props,
+    }
+}
+
+pub fn milestone_to_response(m: &Milestone) -> EntityResponse {
+    let props = serde_json::json!({
+        "status": m.status.to_string(),
+        "target_date": m.target_date.map(|d| d.to_string()),
+    });
+    EntityResponse {
+        id: m.base.id.to_string(),
+        sequence_number: m.base.sequence_number,
+        entity_type: "milestone".to_string(),
+        title: m.base.title.clone(),
+        content: m.base.content.clone(),
+        tags: m.base.tags.clone(),
+        created_at: m.base.created_at.to_rfc3339(),
+        updated_at: m.base.updated_at.to_rfc3339(),
+        created_by: m.base.created_by.clone(),
+        properties: props,
+    }
+}
+
 pub fn relation_to_response(r: &Relation) -> RelationResponse {
     RelationResponse {
         source_id: r.source_id.to_string(),
@@ -570,6 +1498,8 @@ pub fn relation_to_response(r: &Relation) -> RelationResponse {
         target_type: r.target_type.clone(),
         relation_type: r.relation_type.to_string(),
         created_at: r.created_at.to_rfc3339(),
+        resolved_at: r.resolved_at.map(|dt| dt.to_rfc3339()),
+        inverse_label: None,
     }
 }
 
@@ -607,6 +1537,183 @@ mod tests {
         assert!(parse_decision_status("invalid").is_err());
     }
 
+    #[test]
+    fn test_parse_enum_ignores_case_and_whitespace() {
+        for input in ["Accepted", "accepted", " ACCEPTED "] {
+            assert_eq!(
+                parse_decision_status(input).unwrap(),
+                DecisionStatus::Accepted
+            );
+        }
+        assert_eq!(
+            parse_task_status(" In_Progress").unwrap(),
+            TaskStatus::InProgress
+        );
+        assert_eq!(parse_task_priority("HIGH").unwrap(), TaskPriority::High);
+
+        match parse_decision_status("approved") {
+            Err(McpError::InvalidEnumValue { value, valid, .. }) => {
+                assert_eq!(value, "approved");
+                assert!(valid.contains(&"accepted".to_string()));
+            }
+            other => panic!("Expected InvalidEnumValue, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_list_cursor_roundtrip() {
+        assert_eq!(decode_list_cursor(&encode_list_cursor(42)), Some(42));
+        assert_eq!(
+            decode_list_cursor(&encode_list_cursor(u32::MAX)),
+            Some(u32::MAX)
+        );
+        assert_eq!(decode_list_cursor("42"), None);
+        assert_eq!(decode_list_cursor("czzzzzzzz"), None);
+    }
+
+    #[test]
+    fn test_next_page_offset() {
+        assert_eq!(next_page_offset(0, 2, 5), Some(2));
+        assert_eq!(next_page_offset(4, 1, 5), None);
+        assert_eq!(next_page_offset(0, 5, 5), None);
+        // Past the end there is nothing more to fetch
+        assert_eq!(next_page_offset(10, 0, 5), None);
+    }
+
+    #[test]
+    fn test_topological_order_and_cycles() {
+        // 0 blocks 1, and 2 outranks 0 but is independent
+        let (order, leftover) = topological_order(&[1, 0, 0], &[(0, 1)]);
+        assert_eq!(order, vec![2, 0, 1]);
+        assert!(leftover.is_empty());
+
+        // 0 <-> 1 form a cycle and 2 waits behind it
+        let blocks = [(0, 1), (1, 0), (1, 2)];
+        let (order, leftover) = topological_order(&[0, 0, 0, 0], &blocks);
+        assert_eq!(order, vec![3]);
+        assert_eq!(leftover, vec![0, 1, 2]);
+        assert_eq!(dependency_cycles(&leftover, &blocks), vec![vec![0, 1]]);
+    }
+
+    #[test]
+    fn test_parse_estimate() {
+        assert_eq!(parse_estimate(&serde_json::json!(90)).unwrap(), Some(90));
+        assert_eq!(
+            parse_estimate(&serde_json::json!(" 45 ")).unwrap(),
+            Some(45)
+        );
+        assert_eq!(parse_estimate(&serde_json::Value::Null).unwrap(), None);
+        assert!(parse_estimate(&serde_json::json!(-5)).is_err());
+        assert!(parse_estimate(&serde_json::json!(1.5)).is_err());
+        assert!(parse_estimate(&serde_json::json!("soon")).is_err());
+    }
+
+    #[test]
+    fn test_template_create_params() {
+        let mut template = EntityTemplate::new("adr".to_string(), "decision".to_string());
+        template.content = Some("# {{title}}\n\nDate: {{date}}\n".to_string());
+        template.tags = vec!["adr".to_string()];
+        template
+            .properties
+            .insert("status".to_string(), serde_json::json!("proposed"));
+        let today = chrono::NaiveDate::from_ymd_opt(2026, 5, 4).unwrap();
+
+        let params = EntityCreateFromTemplateParams {
+            template: "adr".to_string(),
+            title: "Use Postgres".to_string(),
+            content: None,
+            tags: Some(vec!["adr".to_string(), "db".to_string()]),
+            properties: Some(serde_json::json!({ "context": "Need durability" })),
+        };
+        let create = template_create_params(&template, params, today).unwrap();
+        assert_eq!(create.entity_type, "decision");
+        assert_eq!(
+            create.content.as_deref(),
+            Some("# Use Postgres\n\nDate: 2026-05-04\n")
+        );
+        assert_eq!(create.tags, Some(vec!["adr".to_string(), "db".to_string()]));
+        let properties = create.properties.unwrap();
+        assert_eq!(properties["status"], "proposed");
+        assert_eq!(properties["context"], "Need durability");
+
+        let params = EntityCreateFromTemplateParams {
+            template: "adr".to_string(),
+            title: "Use Redis".to_string(),
+            content: Some("Written by hand".to_string()),
+            tags: None,
+            properties: Some(serde_json::json!({ "status": "accepted" })),
+        };
+        let create = template_create_params(&template, params, today).unwrap();
+        assert_eq!(create.content.as_deref(), Some("Written by hand"));
+        assert_eq!(create.properties.unwrap()["status"], "accepted");
+
+        let params = EntityCreateFromTemplateParams {
+            template: "adr".to_string(),
+            title: "Bad".to_string(),
+            content: None,
+            tags: None,
+            properties: Some(serde_json::json!(["status"])),
+        };
+        assert!(template_create_params(&template, params, today).is_err());
+    }
+
+    #[test]
+    fn test_parse_recurrence() {
+        use chrono::NaiveDate;
+
+        let weekly = parse_recurrence(&serde_json::json!("Weekly"))
+            .unwrap()
+            .unwrap();
+        assert_eq!(weekly.to_string(), "weekly");
+        let every = parse_recurrence(&serde_json::json!("every 3 days"))
+            .unwrap()
+            .unwrap();
+        assert_eq!(every.to_string(), "every 3 days");
+        assert_eq!(parse_recurrence(&serde_json::Value::Null).unwrap(), None);
+        assert!(parse_recurrence(&serde_json::json!("every 0 weeks")).is_err());
+        assert!(parse_recurrence(&serde_json::json!("fortnightly")).is_err());
+        assert!(parse_recurrence(&serde_json::json!("0 9 32 * *")).is_err());
+        assert!(parse_recurrence(&serde_json::json!(7)).is_err());
+
+        // Overdue tasks keep their rhythm but land after today
+        let due = NaiveDate::from_ymd_opt(2026, 3, 2);
+        let today = NaiveDate::from_ymd_opt(2026, 3, 20).unwrap();
+        assert_eq!(
+            weekly.next_due(due, today),
+            NaiveDate::from_ymd_opt(2026, 3, 23)
+        );
+        assert_eq!(
+            every.next_due(None, today),
+            NaiveDate::from_ymd_opt(2026, 3, 23)
+        );
+
+        // First of the month, or any Friday, in June only
+        let cron = parse_recurrence(&serde_json::json!("0 0 1 6 5"))
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            cron.next_due(None, today),
+            NaiveDate::from_ymd_opt(2026, 6, 1)
+        );
+        assert_eq!(
+            cron.next_due(NaiveDate::from_ymd_opt(2026, 6, 1), today),
+            NaiveDate::from_ymd_opt(2026, 6, 5)
+        );
+    }
+
+    #[test]
+    fn test_check_strict_enum() {
+        assert!(check_strict_enum("status", "accepted").is_ok());
+        assert!(check_strict_enum("status", "Accepted").is_err());
+        assert!(check_strict_enum("status", " accepted").is_err());
+        assert!(check_strict_enum("status", "in_progress").is_ok());
+        assert!(check_strict_enum("status", "in-progress").is_err());
+        assert!(check_strict_enum("status", "inprogress").is_err());
+        assert!(check_strict_enum("relation_type", "belongsto").is_err());
+        assert!(check_strict_enum("relation_type", "depends_on").is_ok());
+        assert!(check_strict_enum("relation_type", "Depends_On").is_err());
+    }
+
     #[test]
     fn test_parse_task_status() {
         assert!(parse_task_status("todo").is_ok());
@@ -614,6 +1721,27 @@ mod tests {
         assert!(parse_task_status("invalid").is_err());
     }
 
+    #[test]
+    fn test_row_to_create_params() {
+        let row = serde_json::json!({
+            "type": "component",
+            "title": "Billing",
+            "tags": ["payments"],
+            "owner": "Team B",
+            "status": null,
+        });
+        let params = row_to_create_params(&row).unwrap();
+        assert_eq!(params.entity_type, "component");
+        assert_eq!(params.tags, Some(vec!["payments".to_string()]));
+        assert_eq!(
+            params.properties,
+            Some(serde_json::json!({ "owner": "Team B" }))
+        );
+
+        assert!(row_to_create_params(&serde_json::json!({ "title": "No type" })).is_err());
+        assert!(row_to_create_params(&serde_json::json!(["not", "an", "object"])).is_err());
+    }
+
     #[test]
     fn test_parse_date() {
         assert!(parse_date("due_date", "2025-01-31").is_ok());
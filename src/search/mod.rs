@@ -10,6 +10,8 @@ use chrono::{DateTime, Utc};
 /// - `tag:important` - Filter by tag (can specify multiple)
 /// - `created:>2025-01-01` - Created after date
 /// - `created:<2025-12-31` - Created before date
+/// - `created_by:alice` - Created by an author
+/// - `assignee:bob` - Task assigned to someone
 #[derive(Debug, Default, Clone)]
 pub struct SearchFilter {
     /// Entity type filter (decision, task, note, etc.)
@@ -22,6 +24,10 @@ pub struct SearchFilter {
     pub created_after: Option<DateTime<Utc>>,
     /// Created before this date/time
     pub created_before: Option<DateTime<Utc>>,
+    /// Author filter (matches `created_by`, case-insensitive)
+    pub created_by: Option<String>,
+    /// Assignee filter (tasks only, case-insensitive)
+    pub assignee: Option<String>,
 }
 
 impl SearchFilter {
@@ -37,6 +43,17 @@ impl SearchFilter {
             && self.tags.is_empty()
             && self.created_after.is_none()
             && self.created_before.is_none()
+            && self.created_by.is_none()
+            && self.assignee.is_none()
+    }
+
+    /// Check an entity's author and assignee against the filter.
+    pub fn matches_people(&self, created_by: Option<&str>, assignee: Option<&str>) -> bool {
+        let matches = |required: &Option<String>, actual: Option<&str>| match required {
+            Some(required) => actual.is_some_and(|actual| actual.eq_ignore_ascii_case(required)),
+            None => true,
+        };
+        matches(&self.created_by, created_by) && matches(&self.assignee, assignee)
     }
 }
 
@@ -65,6 +82,10 @@ pub fn parse_query(raw: &str) -> (String, SearchFilter) {
             filter.created_after = parse_date(value);
         } else if let Some(value) = token.strip_prefix("created:<") {
             filter.created_before = parse_date(value);
+        } else if let Some(value) = token.strip_prefix("created_by:") {
+            filter.created_by = Some(value.to_string());
+        } else if let Some(value) = token.strip_prefix("assignee:") {
+            filter.assignee = Some(value.to_string());
         } else {
             remaining.push(token);
         }
@@ -150,6 +171,17 @@ mod tests {
         assert_eq!(filter.status, Some("accepted".to_string()));
     }
 
+    #[test]
+    fn test_parse_query_people_filters() {
+        let (query, filter) = parse_query("created_by:Alice assignee:bob cache");
+        assert_eq!(query, "cache");
+        assert_eq!(filter.created_by, Some("Alice".to_string()));
+        assert_eq!(filter.assignee, Some("bob".to_string()));
+        assert!(filter.matches_people(Some("alice"), Some("Bob")));
+        assert!(!filter.matches_people(Some("alice"), None));
+        assert!(SearchFilter::new().matches_people(None, None));
+    }
+
     #[test]
     fn test_filter_is_empty() {
         let filter = SearchFilter::new();
@@ -0,0 +1,479 @@
+// src/snapshot/backlinks.rs
+//! "Referenced by" sections built from incoming relations, "Attachments"
+//! sections linking to an entity's attached files, and "Related" sections
+//! of suggested entities
+//!
+//! Relations are stored on their source entity, so without these sections a
+//! snapshot file gives no hint of what points at it.
+
+use std::collections::HashMap;
+use std::path::Path;
+
+use uuid::Uuid;
+
+use crate::storage::{Attachment, LoroStore, ATTACHMENTS_DIR};
+use crate::Result;
+
+use super::related::RelatedSuggestions;
+use super::templates::SnapshotTemplates;
+use super::utils::permalink;
+use super::{component, decision, link, milestone, note, prompt, question, task, SnapshotLayout};
+
+/// Snapshot file and title of an entity
+struct EntityFile {
+    entity_type: &'static str,
+    relative_path: String,
+    title: String,
+}
+
+/// Where every entity's snapshot is written, by entity ID
+#[derive(Default)]
+pub struct EntityFiles {
+    by_id: HashMap<Uuid, EntityFile>,
+    attachments: HashMap<Uuid, Vec<Attachment>>,
+    /// `.medulla/attachments` relative to the snapshot directory, when the
+    /// snapshot is written somewhere the files can be linked from
+    attachments_dir: Option<String>,
+    related: RelatedSuggestions,
+    templates: SnapshotTemplates,
+}
+
+impl EntityFiles {
+    /// Work out the snapshot file of every entity in the store
+    pub fn collect(store: &LoroStore) -> Result<Self> {
+        Self::collect_with(store, SnapshotLayout::Grouped)
+    }
+
+    /// Work out the snapshot file of every entity in the store for a layout
+    pub fn collect_with(store: &LoroStore, layout: SnapshotLayout) -> Result<Self> {
+        let mut files = if layout == SnapshotLayout::PerEntity {
+            Self::collect_per_entity(store)?
+        } else {
+            Self::collect_grouped(store)?
+        };
+        for attachment in store.list_attachments(None)? {
+            files
+                .attachments
+                .entry(attachment.entity_id)
+                .or_default()
+                .push(attachment);
+        }
+        Ok(files)
+    }
+
+    /// Link attachments relative to a snapshot written to `snapshot_dir`
+    pub fn with_attachments_dir(mut self, snapshot_dir: &Path, medulla_dir: &Path) -> Self {
+        self.attachments_dir = relative_dir(snapshot_dir, &medulla_dir.join(ATTACHMENTS_DIR));
+        self
+    }
+
+    /// List suggested related entities on each entity's page
+    pub fn with_related(mut self, related: RelatedSuggestions) -> Self {
+        self.related = related;
+        self
+    }
+
+    /// Render pages through the user's templates where there are some
+    pub fn with_templates(mut self, templates: SnapshotTemplates) -> Self {
+        self.templates = templates;
+        self
+    }
+
+    /// An entity's page: `default` (the built-in layout) put through the
+    /// template for `entity_type`, if there is one
+    pub fn render_page<T: serde::Serialize>(
+        &self,
+        entity_type: &str,
+        entity: &T,
+        default: String,
+    ) -> Result<String> {
+        self.templates.render(entity_type, entity, default)
+    }
+
+    fn collect_grouped(store: &LoroStore) -> Result<Self> {
+        let mut files = Self::default();
+
+        let decisions = store.list_decisions()?;
+        let titles: HashMap<Uuid, &str> = decisions
+            .iter()
+            .map(|d| (d.base.id, d.base.title.as_str()))
+            .collect();
+        files.extend("decision", decision::file_paths(&decisions), &titles);
+
+        for task in store.list_tasks()? {
+            files.by_id.insert(
+                task.base.id,
+                EntityFile {
+                    entity_type: "task",
+                    relative_path: task::file_path(&task).to_string(),
+                    title: task.base.title.clone(),
+                },
+            );
+        }
+
+        let notes = store.list_notes()?;
+        let titles: HashMap<Uuid, &str> = notes
+            .iter()
+            .map(|n| (n.base.id, n.base.title.as_str()))
+            .collect();
+        files.extend("note", note::file_paths(&notes), &titles);
+
+        let prompts = store.list_prompts()?;
+        let titles: HashMap<Uuid, &str> = prompts
+            .iter()
+            .map(|p| (p.base.id, p.base.title.as_str()))
+            .collect();
+        files.extend("prompt", prompt::file_paths(&prompts), &titles);
+
+        let components = store.list_components()?;
+        let titles: HashMap<Uuid, &str> = components
+            .iter()
+            .map(|c| (c.base.id, c.base.title.as_str()))
+            .collect();
+        files.extend("component", component::file_paths(&components), &titles);
+
+        let links = store.list_links()?;
+        let titles: HashMap<Uuid, &str> = links
+            .iter()
+            .map(|l| (l.base.id, l.base.title.as_str()))
+            .collect();
+        files.extend("link", link::file_paths(&links), &titles);
+
+        let questions = store.list_questions()?;
+        let titles: HashMap<Uuid, &str> = questions
+            .iter()
+            .map(|q| (q.base.id, q.base.title.as_str()))
+            .collect();
+        files.extend("question", question::file_paths(&questions), &titles);
+
+        let milestones = store.list_milestones()?;
+        let titles: HashMap<Uuid, &str> = milestones
+            .iter()
+            .map(|m| (m.base.id, m.base.title.as_str()))
+            .collect();
+        files.extend("milestone", milestone::file_paths(&milestones), &titles);
+
+        Ok(files)
+    }
+
+    /// Every entity, tasks included, gets its own `{sequence:03}-{slug}.md`
+    fn collect_per_entity(store: &LoroStore) -> Result<Self> {
+        let mut files = Self::default();
+        let mut add = |entity_type: &'static str, base: &crate::entity::EntityBase| {
+            files.by_id.insert(
+                base.id,
+                EntityFile {
+                    entity_type,
+                    relative_path: permalink(&format!("{}s", entity_type), base),
+                    title: base.title.clone(),
+                },
+            );
+        };
+
+        for d in store.list_decisions()? {
+            add("decision", &d.base);
+        }
+        for t in store.list_tasks()? {
+            add("task", &t.base);
+        }
+        for n in store.list_notes()? {
+            add("note", &n.base);
+        }
+        for p in store.list_prompts()? {
+            add("prompt", &p.base);
+        }
+        for c in store.list_components()? {
+            add("component", &c.base);
+        }
+        for l in store.list_links()? {
+            add("link", &l.base);
+        }
+        for q in store.list_questions()? {
+            add("question", &q.base);
+        }
+        for m in store.list_milestones()? {
+            add("milestone", &m.base);
+        }
+
+        Ok(files)
+    }
+
+    fn extend(
+        &mut self,
+        entity_type: &'static str,
+        paths: Vec<(Uuid, String)>,
+        titles: &HashMap<Uuid, &str>,
+    ) {
+        for (id, relative_path) in paths {
+            let title = titles.get(&id).copied().unwrap_or_default().to_string();
+            self.by_id.insert(
+                id,
+                EntityFile {
+                    entity_type,
+                    relative_path,
+                    title,
+                },
+            );
+        }
+    }
+
+    /// Snapshot file of an entity, relative to the snapshot directory
+    pub fn path(&self, id: &Uuid) -> Option<&str> {
+        self.by_id.get(id).map(|f| f.relative_path.as_str())
+    }
+
+    /// Markdown section listing the entities with a relation to `id`, linked
+    /// relative to its own snapshot file. Empty if nothing references it.
+    pub fn referenced_by(&self, store: &LoroStore, id: &Uuid) -> Result<String> {
+        let Some(target) = self.by_id.get(id) else {
+            return Ok(String::new());
+        };
+
+        let mut lines: Vec<String> = store
+            .get_relations_to(&id.to_string())?
+            .iter()
+            .filter_map(|relation| {
+                let source = self.by_id.get(&relation.source_id)?;
+                Some(format!(
+                    "- [{}]({}) ({}, {})",
+                    source.title,
+                    relative_link(&target.relative_path, &source.relative_path),
+                    source.entity_type,
+                    relation.relation_type
+                ))
+            })
+            .collect();
+        if lines.is_empty() {
+            return Ok(String::new());
+        }
+        lines.sort();
+        lines.dedup();
+
+        Ok(format!("\n## Referenced by\n\n{}\n", lines.join("\n")))
+    }
+
+    /// Markdown section listing the files attached to `id`. Files are
+    /// linked when the attachments directory is reachable from the snapshot,
+    /// and shown by path otherwise. Empty if it has no attachments.
+    pub fn attachments(&self, id: &Uuid) -> String {
+        let Some(attachments) = self.attachments.get(id) else {
+            return String::new();
+        };
+        let depth = self
+            .by_id
+            .get(id)
+            .map_or(0, |f| f.relative_path.matches('/').count());
+
+        let lines: Vec<String> = attachments
+            .iter()
+            .map(|a| {
+                let path = a.relative_path().replace(' ', "%20");
+                let target = match &self.attachments_dir {
+                    Some(dir) => {
+                        format!("[{}]({}{}/{})", a.filename, "../".repeat(depth), dir, path)
+                    }
+                    None => format!("`{}` (`.medulla/{}/{}`)", a.filename, ATTACHMENTS_DIR, path),
+                };
+                format!("- {} ({}, {} bytes)", target, a.mime, a.size)
+            })
+            .collect();
+
+        format!("\n## Attachments\n\n{}\n", lines.join("\n"))
+    }
+
+    /// Markdown section listing the entities suggested as related to `id`,
+    /// with their similarity. Empty if there are no suggestions.
+    pub fn related(&self, id: &Uuid) -> String {
+        let Some(target) = self.by_id.get(id) else {
+            return String::new();
+        };
+
+        let lines: Vec<String> = self
+            .related
+            .get(id)
+            .iter()
+            .filter_map(|(related_id, score)| {
+                let related = self.by_id.get(related_id)?;
+                Some(format!(
+                    "- [{}]({}) ({}, {:.2})",
+                    related.title,
+                    relative_link(&target.relative_path, &related.relative_path),
+                    related.entity_type,
+                    score
+                ))
+            })
+            .collect();
+        if lines.is_empty() {
+            return String::new();
+        }
+
+        format!("\n## Related\n\n{}\n", lines.join("\n"))
+    }
+
+    /// Append the "Attachments", "Referenced by" and "Related" sections for
+    /// `id` to rendered markdown
+    pub fn append_sections(
+        &self,
+        store: &LoroStore,
+        id: &Uuid,
+        content: &mut String,
+    ) -> Result<()> {
+        let section = self.attachments(id) + &self.referenced_by(store, id)? + &self.related(id);
+        if section.is_empty() {
+            return Ok(());
+        }
+        if !content.is_empty() && !content.ends_with('\n') {
+            content.push('\n');
+        }
+        content.push_str(&section);
+        Ok(())
+    }
+}
+
+/// Link from one snapshot file to another, both relative to the snapshot
+/// directory
+fn relative_link(from: &str, to: &str) -> String {
+    let depth = from.matches('/').count();
+    format!("{}{}", "../".repeat(depth), to)
+}
+
+/// Path of directory `to` relative to directory `from`, with `/`
+/// separators. None if either does not exist or they share no root.
+fn relative_dir(from: &Path, to: &Path) -> Option<String> {
+    let from = from.canonicalize().ok()?;
+    let to = to.canonicalize().ok()?;
+    let common = from
+        .components()
+        .zip(to.components())
+        .take_while(|(a, b)| a == b)
+        .count();
+    if common == 0 {
+        return None;
+    }
+    let ups = from.components().count() - common;
+    let downs: Vec<String> = to
+        .components()
+        .skip(common)
+        .map(|c| c.as_os_str().to_string_lossy().into_owned())
+        .collect();
+    Some(
+        std::iter::repeat("..".to_string())
+            .take(ups)
+            .chain(downs)
+            .collect::<Vec<_>>()
+            .join("/"),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::entity::{Decision, Note, Relation, RelationType, Task};
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_relative_link() {
+        assert_eq!(
+            relative_link("decisions/001-a.md", "notes/b.md"),
+            "../notes/b.md"
+        );
+        assert_eq!(relative_link("README.md", "notes/b.md"), "notes/b.md");
+    }
+
+    #[test]
+    fn test_referenced_by_links_sources() {
+        let tmp = TempDir::new().unwrap();
+        let store = LoroStore::init(tmp.path()).unwrap();
+
+        let decision = Decision::new("Use Postgres".to_string(), 1);
+        let note = Note::new("Benchmark results".to_string(), 2);
+        let task = Task::new("Migrate schema".to_string(), 3);
+        store.add_decision(&decision).unwrap();
+        store.add_note(&note).unwrap();
+        store.add_task(&task).unwrap();
+        for (source_id, source_type, relation_type) in [
+            (note.base.id, "note", RelationType::References),
+            (task.base.id, "task", RelationType::Implements),
+        ] {
+            store
+                .add_relation(&Relation::new(
+                    source_id,
+                    source_type.to_string(),
+                    decision.base.id,
+                    "decision".to_string(),
+                    relation_type,
+                ))
+                .unwrap();
+        }
+
+        let files = EntityFiles::collect(&store).unwrap();
+        assert_eq!(
+            files.path(&decision.base.id),
+            Some("decisions/001-use-postgres.md")
+        );
+
+        let section = files.referenced_by(&store, &decision.base.id).unwrap();
+        assert_eq!(
+            section,
+            "\n## Referenced by\n\n\
+             - [Benchmark results](../notes/benchmark-results.md) (note, references)\n\
+             - [Migrate schema](../tasks/active.md) (task, implements)\n"
+        );
+
+        // Nothing points at the note
+        assert_eq!(files.referenced_by(&store, &note.base.id).unwrap(), "");
+    }
+
+    #[test]
+    fn test_attachments_section() {
+        let tmp = TempDir::new().unwrap();
+        let store = LoroStore::init(tmp.path()).unwrap();
+        let decision = Decision::new("Use Postgres".to_string(), 1);
+        store.add_decision(&decision).unwrap();
+        let diagram = tmp.path().join("schema diagram.png");
+        std::fs::write(&diagram, b"png").unwrap();
+        store
+            .attach_file(&decision.base.id, &diagram, None)
+            .unwrap();
+
+        let snapshot_dir = store.medulla_dir().join("snapshot");
+        std::fs::create_dir_all(&snapshot_dir).unwrap();
+        let files = EntityFiles::collect(&store)
+            .unwrap()
+            .with_attachments_dir(&snapshot_dir, store.medulla_dir());
+        assert_eq!(
+            files.attachments(&decision.base.id),
+            format!(
+                "\n## Attachments\n\n\
+                 - [schema diagram.png](../../attachments/{}/schema%20diagram.png) (image/png, 3 bytes)\n",
+                decision.base.id
+            )
+        );
+
+        // Without a reachable snapshot directory the path is shown instead
+        let files = EntityFiles::collect(&store).unwrap();
+        assert!(files
+            .attachments(&decision.base.id)
+            .contains("`.medulla/attachments/"));
+    }
+
+    #[test]
+    fn test_per_entity_paths() {
+        let tmp = TempDir::new().unwrap();
+        let store = LoroStore::init(tmp.path()).unwrap();
+        let note = Note::new("Benchmark results".to_string(), 2);
+        let task = Task::new("Migrate schema".to_string(), 3);
+        store.add_note(&note).unwrap();
+        store.add_task(&task).unwrap();
+
+        let files = EntityFiles::collect_with(&store, SnapshotLayout::PerEntity).unwrap();
+        assert_eq!(
+            files.path(&note.base.id),
+            Some("notes/002-benchmark-results.md")
+        );
+        assert_eq!(
+            files.path(&task.base.id),
+            Some("tasks/003-migrate-schema.md")
+        );
+    }
+}
@@ -2,15 +2,16 @@
 //! Component snapshot generation
 
 use std::collections::HashSet;
-use std::path::Path;
 
 use serde::Serialize;
+use uuid::Uuid;
 
 use crate::entity::Component;
 use crate::storage::LoroStore;
 use crate::Result;
 
-use super::utils::{format_date, slugify, write_snapshot_file};
+use super::backlinks::EntityFiles;
+use super::utils::{format_date, slugify, SnapshotWriter};
 use super::{yaml_frontmatter, GeneratedFile};
 
 #[derive(Serialize)]
@@ -28,6 +29,8 @@ struct ComponentFrontmatter {
     updated: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     created_by: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    updated_by: Option<String>,
     #[serde(skip_serializing_if = "Vec::is_empty")]
     tags: Vec<String>,
 }
@@ -44,6 +47,7 @@ impl ComponentFrontmatter {
             created: format_date(&component.base.created_at),
             updated: format_date(&component.base.updated_at),
             created_by: component.base.created_by.clone(),
+            updated_by: component.base.updated_by.clone(),
             tags: component.base.tags.clone(),
         }
     }
@@ -63,8 +67,38 @@ fn unique_filename(base_slug: &str, sequence: u32, used_slugs: &mut HashSet<Stri
     }
 }
 
+/// Snapshot file of each component, relative to the snapshot directory
+pub(super) fn file_paths(components: &[Component]) -> Vec<(Uuid, String)> {
+    let mut sorted: Vec<&Component> = components.iter().collect();
+    sorted.sort_by_key(|c| c.base.sequence_number);
+
+    let mut used_slugs = HashSet::new();
+    sorted
+        .into_iter()
+        .map(|c| {
+            let slug = slugify(&c.base.title);
+            let filename = unique_filename(&slug, c.base.sequence_number, &mut used_slugs);
+            (c.base.id, format!("components/{}", filename))
+        })
+        .collect()
+}
+
+/// Render a component as snapshot markdown
+pub fn render(component: &Component) -> Result<String> {
+    let frontmatter = ComponentFrontmatter::from_component(component);
+    let yaml = yaml_frontmatter(&frontmatter)?;
+
+    // Content is just the body
+    let body = component.base.content.as_deref().unwrap_or("");
+    Ok(format!("{}\n{}", yaml, body))
+}
+
 /// Generate component snapshot files
-pub fn generate(store: &LoroStore, snapshot_dir: &Path) -> Result<Vec<GeneratedFile>> {
+pub fn generate(
+    store: &LoroStore,
+    writer: &mut SnapshotWriter,
+    files: &EntityFiles,
+) -> Result<Vec<GeneratedFile>> {
     let components = store.list_components()?;
     let mut generated = Vec::new();
 
@@ -76,25 +110,16 @@ pub fn generate(store: &LoroStore, snapshot_dir: &Path) -> Result<Vec<GeneratedF
     let mut sorted_components = components;
     sorted_components.sort_by_key(|c| c.base.sequence_number);
 
-    let components_dir = snapshot_dir.join("components");
-    let mut used_slugs = HashSet::new();
-
     for component in &sorted_components {
-        let frontmatter = ComponentFrontmatter::from_component(component);
-        let yaml = yaml_frontmatter(&frontmatter)?;
-
-        // Content is just the body
-        let body = component.base.content.as_deref().unwrap_or("");
-        let content = format!("{}\n{}", yaml, body);
-
-        let slug = slugify(&component.base.title);
-        let filename = unique_filename(&slug, component.base.sequence_number, &mut used_slugs);
-        let file_path = components_dir.join(&filename);
-
-        write_snapshot_file(&file_path, &content)?;
+        let Some(relative_path) = files.path(&component.base.id) else {
+            continue;
+        };
+        let mut content = files.render_page("component", &component, render(component)?)?;
+        files.append_sections(store, &component.base.id, &mut content)?;
+        writer.write(relative_path, &content)?;
 
         generated.push(GeneratedFile {
-            relative_path: format!("components/{}", filename),
+            relative_path: relative_path.to_string(),
             entity_count: 1,
         });
     }
@@ -178,7 +203,12 @@ mod tests {
         let snapshot_dir = medulla_dir.join("snapshot");
         super::super::utils::ensure_snapshot_dirs(&snapshot_dir).unwrap();
 
-        let files = generate(&store, &snapshot_dir).unwrap();
+        let files = generate(
+            &store,
+            &mut SnapshotWriter::new(&snapshot_dir),
+            &EntityFiles::collect(&store).unwrap(),
+        )
+        .unwrap();
 
         assert_eq!(files.len(), 1);
         assert!(files[0].relative_path.starts_with("components/"));
@@ -200,7 +230,12 @@ mod tests {
         let snapshot_dir = medulla_dir.join("snapshot");
         super::super::utils::ensure_snapshot_dirs(&snapshot_dir).unwrap();
 
-        generate(&store, &snapshot_dir).unwrap();
+        generate(
+            &store,
+            &mut SnapshotWriter::new(&snapshot_dir),
+            &EntityFiles::collect(&store).unwrap(),
+        )
+        .unwrap();
 
         let file_path = snapshot_dir.join("components/database.md");
         let content = std::fs::read_to_string(&file_path).unwrap();
@@ -222,7 +257,12 @@ mod tests {
         let snapshot_dir = medulla_dir.join("snapshot");
         super::super::utils::ensure_snapshot_dirs(&snapshot_dir).unwrap();
 
-        let files = generate(&store, &snapshot_dir).unwrap();
+        let files = generate(
+            &store,
+            &mut SnapshotWriter::new(&snapshot_dir),
+            &EntityFiles::collect(&store).unwrap(),
+        )
+        .unwrap();
 
         assert!(files.is_empty());
     }
@@ -0,0 +1,391 @@
+//! Project dashboard: a compact overview of where a project stands, meant
+//! as session-start context for an agent. It covers open decisions and
+//! questions, the task queue by priority, overdue work, recent changes,
+//! orphaned entities and store health.
+
+use std::collections::{BTreeMap, HashSet};
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+use crate::cache::SqliteCache;
+use crate::entity::{
+    DecisionStatus, EntityBase, MilestoneStatus, QuestionStatus, TaskPriority, TaskStatus,
+};
+use crate::storage::LoroStore;
+use crate::warnings::{health_check, HealthIssue, Severity};
+use crate::Result;
+
+use super::utils::format_date;
+
+/// Entities listed per section; counts always cover everything
+pub const DASHBOARD_LIST_LIMIT: usize = 10;
+
+/// An entity listed on the dashboard
+#[derive(Debug, Clone, Serialize)]
+pub struct DashboardEntry {
+    #[serde(rename = "type")]
+    pub entity_type: &'static str,
+    pub id: String,
+    pub sequence_number: u32,
+    pub title: String,
+    pub status: Option<String>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl DashboardEntry {
+    fn new(entity_type: &'static str, base: &EntityBase, status: Option<String>) -> Self {
+        Self {
+            entity_type,
+            id: base.id.to_string(),
+            sequence_number: base.sequence_number,
+            title: base.title.clone(),
+            status,
+            updated_at: base.updated_at,
+        }
+    }
+}
+
+/// Tasks that are not done, by priority and status
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct TaskQueue {
+    pub total: usize,
+    pub urgent: usize,
+    pub high: usize,
+    pub normal: usize,
+    pub low: usize,
+    pub in_progress: usize,
+    pub blocked: usize,
+}
+
+/// Work past its date: tasks not done by their due date and milestones
+/// still open after their target date
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct OverdueCounts {
+    pub tasks: usize,
+    pub milestones: usize,
+}
+
+/// Store health: the worst issue found and the issues themselves
+#[derive(Debug, Clone, Serialize)]
+pub struct DashboardHealth {
+    pub status: Severity,
+    pub issues: Vec<HealthIssue>,
+}
+
+/// Overview of a project at `generated_at`
+#[derive(Debug, Clone, Serialize)]
+pub struct Dashboard {
+    pub generated_at: DateTime<Utc>,
+    pub entity_counts: BTreeMap<&'static str, usize>,
+    /// Proposed decisions, most recently updated first
+    pub open_decisions: Vec<DashboardEntry>,
+    pub open_decision_count: usize,
+    pub open_question_count: usize,
+    pub task_queue: TaskQueue,
+    pub overdue: OverdueCounts,
+    /// Most recently updated entities of any type
+    pub recently_changed: Vec<DashboardEntry>,
+    /// Entities with no relations at all
+    pub orphan_count: usize,
+    pub health: DashboardHealth,
+}
+
+/// Build the dashboard. Health is read from the cache as it is, like
+/// `medulla doctor`, so a stale cache shows up as a warning.
+pub fn generate_dashboard(
+    store: &LoroStore,
+    cache: &SqliteCache,
+    now: DateTime<Utc>,
+) -> Result<Dashboard> {
+    let today = now.date_naive();
+    let mut entity_counts = BTreeMap::new();
+    let mut entries: Vec<DashboardEntry> = Vec::new();
+    let mut open_decisions = Vec::new();
+    let mut open_question_count = 0;
+    let mut task_queue = TaskQueue::default();
+    let mut overdue = OverdueCounts::default();
+
+    let decisions = store.list_decisions()?;
+    entity_counts.insert("decision", decisions.len());
+    for d in &decisions {
+        let entry = DashboardEntry::new("decision", &d.base, Some(d.status.to_string()));
+        if d.status == DecisionStatus::Proposed {
+            open_decisions.push(entry.clone());
+        }
+        entries.push(entry);
+    }
+
+    let tasks = store.list_tasks()?;
+    entity_counts.insert("task", tasks.len());
+    for t in &tasks {
+        if t.status != TaskStatus::Done {
+            task_queue.total += 1;
+            match t.priority {
+                TaskPriority::Urgent => task_queue.urgent += 1,
+                TaskPriority::High => task_queue.high += 1,
+                TaskPriority::Normal => task_queue.normal += 1,
+                TaskPriority::Low => task_queue.low += 1,
+            }
+            match t.status {
+                TaskStatus::InProgress => task_queue.in_progress += 1,
+                TaskStatus::Blocked => task_queue.blocked += 1,
+                _ => {}
+            }
+            if t.due_date.is_some_and(|due| due < today) {
+                overdue.tasks += 1;
+            }
+        }
+        entries.push(DashboardEntry::new(
+            "task",
+            &t.base,
+            Some(t.status.to_string()),
+        ));
+    }
+
+    let questions = store.list_questions()?;
+    entity_counts.insert("question", questions.len());
+    for q in &questions {
+        if q.status == QuestionStatus::Open {
+            open_question_count += 1;
+        }
+        entries.push(DashboardEntry::new(
+            "question",
+            &q.base,
+            Some(q.status.to_string()),
+        ));
+    }
+
+    let milestones = store.list_milestones()?;
+    entity_counts.insert("milestone", milestones.len());
+    for m in &milestones {
+        if m.status == MilestoneStatus::Open && m.target_date.is_some_and(|date| date < today) {
+            overdue.milestones += 1;
+        }
+        entries.push(DashboardEntry::new(
+            "milestone",
+            &m.base,
+            Some(m.status.to_string()),
+        ));
+    }
+
+    let notes = store.list_notes()?;
+    entity_counts.insert("note", notes.len());
+    entries.extend(
+        notes
+            .iter()
+            .map(|n| DashboardEntry::new("note", &n.base, None)),
+    );
+    let prompts = store.list_prompts()?;
+    entity_counts.insert("prompt", prompts.len());
+    entries.extend(
+        prompts
+            .iter()
+            .map(|p| DashboardEntry::new("prompt", &p.base, None)),
+    );
+    let components = store.list_components()?;
+    entity_counts.insert("component", components.len());
+    entries.extend(
+        components
+            .iter()
+            .map(|c| DashboardEntry::new("component", &c.base, Some(c.status.to_string()))),
+    );
+    let links = store.list_links()?;
+    entity_counts.insert("link", links.len());
+    entries.extend(
+        links
+            .iter()
+            .map(|l| DashboardEntry::new("link", &l.base, None)),
+    );
+
+    let mut connected: HashSet<String> = HashSet::new();
+    for r in store.list_relations()? {
+        connected.insert(r.source_id.to_string());
+        connected.insert(r.target_id.to_string());
+    }
+    let orphan_count = entries
+        .iter()
+        .filter(|entry| !connected.contains(&entry.id))
+        .count();
+
+    let open_decision_count = open_decisions.len();
+    open_decisions.sort_by_key(|entry| std::cmp::Reverse(entry.updated_at));
+    open_decisions.truncate(DASHBOARD_LIST_LIMIT);
+
+    let mut recently_changed = entries;
+    recently_changed.sort_by_key(|entry| std::cmp::Reverse(entry.updated_at));
+    recently_changed.truncate(DASHBOARD_LIST_LIMIT);
+
+    let report = health_check(store, cache)?;
+
+    Ok(Dashboard {
+        generated_at: now,
+        entity_counts,
+        open_decisions,
+        open_decision_count,
+        open_question_count,
+        task_queue,
+        overdue,
+        recently_changed,
+        orphan_count,
+        health: DashboardHealth {
+            status: report.status,
+            issues: report.issues,
+        },
+    })
+}
+
+impl Dashboard {
+    /// Render as markdown, leaving out sections with nothing to report
+    pub fn to_markdown(&self) -> String {
+        let total: usize = self.entity_counts.values().sum();
+        let mut md = format!(
+            "# Project status ({})\n\n{} entities: {}\n",
+            format_date(&self.generated_at),
+            total,
+            self.entity_counts
+                .iter()
+                .filter(|(_, &n)| n > 0)
+                .map(|(t, n)| format!("{} {}", n, t))
+                .collect::<Vec<_>>()
+                .join(", ")
+        );
+
+        md.push_str(&format!("\nHealth: {}\n", self.health.status.as_str()));
+        for issue in &self.health.issues {
+            md.push_str(&format!("- {}: {}", issue.severity.as_str(), issue.message));
+            if let Some(fix) = issue.fix {
+                md.push_str(&format!(" (run `{}`)", fix));
+            }
+            md.push('\n');
+        }
+
+        let queue = &self.task_queue;
+        md.push_str(&format!(
+            "\n## Task queue\n\n{} open: {} urgent, {} high, {} normal, {} low ({} in progress, {} blocked)\n",
+            queue.total,
+            queue.urgent,
+            queue.high,
+            queue.normal,
+            queue.low,
+            queue.in_progress,
+            queue.blocked
+        ));
+        if self.overdue.tasks + self.overdue.milestones > 0 {
+            md.push_str(&format!(
+                "Overdue: {} tasks, {} milestones\n",
+                self.overdue.tasks, self.overdue.milestones
+            ));
+        }
+
+        if self.open_decision_count > 0 {
+            md.push_str(&format!(
+                "\n## Open decisions ({})\n\n",
+                self.open_decision_count
+            ));
+            for entry in &self.open_decisions {
+                md.push_str(&format!("- {:03} {}\n", entry.sequence_number, entry.title));
+            }
+        }
+        if self.open_question_count > 0 {
+            md.push_str(&format!("\nOpen questions: {}\n", self.open_question_count));
+        }
+
+        if !self.recently_changed.is_empty() {
+            md.push_str("\n## Recently changed\n\n");
+            for entry in &self.recently_changed {
+                md.push_str(&format!(
+                    "- {} {:03} {}",
+                    entry.entity_type, entry.sequence_number, entry.title
+                ));
+                if let Some(ref status) = entry.status {
+                    md.push_str(&format!(" ({})", status));
+                }
+                md.push_str(&format!(" - {}\n", format_date(&entry.updated_at)));
+            }
+        }
+
+        if self.orphan_count > 0 {
+            md.push_str(&format!(
+                "\n{} entities have no relations\n",
+                self.orphan_count
+            ));
+        }
+        md
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::entity::{Decision, Milestone, Note, Relation, RelationType, Task};
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_generate_dashboard() {
+        let tmp = TempDir::new().unwrap();
+        let store = LoroStore::init(tmp.path()).unwrap();
+        let cache = SqliteCache::open(store.medulla_dir()).unwrap();
+        let now = Utc::now();
+        let yesterday = now.date_naive() - chrono::Duration::days(1);
+
+        let decision = Decision::new("Use SQLite".to_string(), 1);
+        store.add_decision(&decision).unwrap();
+        let mut accepted = Decision::new("Use Rust".to_string(), 2);
+        accepted.status = DecisionStatus::Accepted;
+        store.add_decision(&accepted).unwrap();
+
+        let mut urgent = Task::new("Fix crash".to_string(), 3);
+        urgent.priority = TaskPriority::Urgent;
+        urgent.status = TaskStatus::InProgress;
+        urgent.due_date = Some(yesterday);
+        store.add_task(&urgent).unwrap();
+        let mut done = Task::new("Write docs".to_string(), 4);
+        done.status = TaskStatus::Done;
+        done.due_date = Some(yesterday);
+        store.add_task(&done).unwrap();
+        store
+            .add_task(&Task::new("Add tests".to_string(), 5))
+            .unwrap();
+
+        let mut milestone = Milestone::new("Beta".to_string(), 6);
+        milestone.target_date = Some(yesterday);
+        store.add_milestone(&milestone).unwrap();
+        store
+            .add_note(&Note::new("Meeting notes".to_string(), 7))
+            .unwrap();
+
+        store
+            .add_relation(&Relation::new(
+                urgent.base.id,
+                "task".to_string(),
+                decision.base.id,
+                "decision".to_string(),
+                RelationType::Implements,
+            ))
+            .unwrap();
+        store.sync_cache(&cache).unwrap();
+
+        let dashboard = generate_dashboard(&store, &cache, now).unwrap();
+        assert_eq!(dashboard.entity_counts["task"], 3);
+        assert_eq!(dashboard.open_decision_count, 1);
+        assert_eq!(dashboard.open_decisions[0].title, "Use SQLite");
+        assert_eq!(dashboard.task_queue.total, 2);
+        assert_eq!(dashboard.task_queue.urgent, 1);
+        assert_eq!(dashboard.task_queue.normal, 1);
+        assert_eq!(dashboard.task_queue.in_progress, 1);
+        assert_eq!(dashboard.overdue.tasks, 1);
+        assert_eq!(dashboard.overdue.milestones, 1);
+        assert_eq!(dashboard.recently_changed.len(), 7);
+        // Everything but the task and the decision it implements
+        assert_eq!(dashboard.orphan_count, 5);
+        assert_eq!(dashboard.health.status, Severity::Ok);
+
+        let md = dashboard.to_markdown();
+        assert!(md.contains("7 entities: 2 decision, 1 milestone, 1 note, 3 task"));
+        assert!(md.contains("2 open: 1 urgent, 0 high, 1 normal, 0 low (1 in progress, 0 blocked)"));
+        assert!(md.contains("Overdue: 1 tasks, 1 milestones"));
+        assert!(md.contains("## Open decisions (1)\n\n- 001 Use SQLite\n"));
+        assert!(md.contains("5 entities have no relations"));
+    }
+}
@@ -1,15 +1,15 @@
 // src/snapshot/decision.rs
 //! Decision snapshot generation
 
-use std::path::Path;
-
 use serde::Serialize;
+use uuid::Uuid;
 
 use crate::entity::Decision;
 use crate::storage::LoroStore;
 use crate::Result;
 
-use super::utils::{format_date, slugify, write_snapshot_file};
+use super::backlinks::EntityFiles;
+use super::utils::{format_date, slugify, SnapshotWriter};
 use super::{yaml_frontmatter, GeneratedFile};
 
 #[derive(Serialize)]
@@ -22,6 +22,8 @@ struct DecisionFrontmatter {
     updated: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     created_by: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    updated_by: Option<String>,
     #[serde(skip_serializing_if = "Vec::is_empty")]
     tags: Vec<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -38,6 +40,7 @@ impl DecisionFrontmatter {
             created: format_date(&decision.base.created_at),
             updated: format_date(&decision.base.updated_at),
             created_by: decision.base.created_by.clone(),
+            updated_by: decision.base.updated_by.clone(),
             tags: decision.base.tags.clone(),
             superseded_by: decision.superseded_by.clone(),
         }
@@ -79,8 +82,34 @@ fn generate_body(decision: &Decision) -> String {
     body
 }
 
+/// Snapshot file of each decision, relative to the snapshot directory
+pub(super) fn file_paths(decisions: &[Decision]) -> Vec<(Uuid, String)> {
+    decisions
+        .iter()
+        .map(|d| {
+            // Filename: {sequence:03}-{slug}.md
+            let slug = slugify(&d.base.title);
+            let filename = format!("{:03}-{}.md", d.base.sequence_number, slug);
+            (d.base.id, format!("decisions/{}", filename))
+        })
+        .collect()
+}
+
+/// Render a decision as snapshot markdown
+pub fn render(decision: &Decision) -> Result<String> {
+    let frontmatter = DecisionFrontmatter::from_decision(decision);
+    let yaml = yaml_frontmatter(&frontmatter)?;
+    let body = generate_body(decision);
+
+    Ok(format!("{}{}", yaml, body))
+}
+
 /// Generate decision snapshot files
-pub fn generate(store: &LoroStore, snapshot_dir: &Path) -> Result<Vec<GeneratedFile>> {
+pub fn generate(
+    store: &LoroStore,
+    writer: &mut SnapshotWriter,
+    files: &EntityFiles,
+) -> Result<Vec<GeneratedFile>> {
     let decisions = store.list_decisions()?;
     let mut generated = Vec::new();
 
@@ -92,24 +121,16 @@ pub fn generate(store: &LoroStore, snapshot_dir: &Path) -> Result<Vec<GeneratedF
     let mut sorted_decisions = decisions;
     sorted_decisions.sort_by_key(|d| d.base.sequence_number);
 
-    let decisions_dir = snapshot_dir.join("decisions");
-
     for decision in &sorted_decisions {
-        let frontmatter = DecisionFrontmatter::from_decision(decision);
-        let yaml = yaml_frontmatter(&frontmatter)?;
-        let body = generate_body(decision);
-
-        let content = format!("{}{}", yaml, body);
-
-        // Filename: {sequence:03}-{slug}.md
-        let slug = slugify(&decision.base.title);
-        let filename = format!("{:03}-{}.md", decision.base.sequence_number, slug);
-        let file_path = decisions_dir.join(&filename);
-
-        write_snapshot_file(&file_path, &content)?;
+        let Some(relative_path) = files.path(&decision.base.id) else {
+            continue;
+        };
+        let mut content = files.render_page("decision", &decision, render(decision)?)?;
+        files.append_sections(store, &decision.base.id, &mut content)?;
+        writer.write(relative_path, &content)?;
 
         generated.push(GeneratedFile {
-            relative_path: format!("decisions/{}", filename),
+            relative_path: relative_path.to_string(),
             entity_count: 1,
         });
     }
@@ -220,7 +241,12 @@ mod tests {
         let snapshot_dir = medulla_dir.join("snapshot");
         super::super::utils::ensure_snapshot_dirs(&snapshot_dir).unwrap();
 
-        let files = generate(&store, &snapshot_dir).unwrap();
+        let files = generate(
+            &store,
+            &mut SnapshotWriter::new(&snapshot_dir),
+            &EntityFiles::collect(&store).unwrap(),
+        )
+        .unwrap();
 
         assert_eq!(files.len(), 1);
         // Format: 001-use-postgresql.md
@@ -243,7 +269,12 @@ mod tests {
         let snapshot_dir = medulla_dir.join("snapshot");
         super::super::utils::ensure_snapshot_dirs(&snapshot_dir).unwrap();
 
-        generate(&store, &snapshot_dir).unwrap();
+        generate(
+            &store,
+            &mut SnapshotWriter::new(&snapshot_dir),
+            &EntityFiles::collect(&store).unwrap(),
+        )
+        .unwrap();
 
         let slug = slugify(&decision.base.title);
         let file_path = snapshot_dir.join(format!(
@@ -270,7 +301,12 @@ mod tests {
         let snapshot_dir = medulla_dir.join("snapshot");
         super::super::utils::ensure_snapshot_dirs(&snapshot_dir).unwrap();
 
-        let files = generate(&store, &snapshot_dir).unwrap();
+        let files = generate(
+            &store,
+            &mut SnapshotWriter::new(&snapshot_dir),
+            &EntityFiles::collect(&store).unwrap(),
+        )
+        .unwrap();
 
         assert!(files.is_empty());
     }
@@ -298,7 +334,12 @@ mod tests {
         let snapshot_dir = medulla_dir.join("snapshot");
         super::super::utils::ensure_snapshot_dirs(&snapshot_dir).unwrap();
 
-        let files = generate(&store, &snapshot_dir).unwrap();
+        let files = generate(
+            &store,
+            &mut SnapshotWriter::new(&snapshot_dir),
+            &EntityFiles::collect(&store).unwrap(),
+        )
+        .unwrap();
 
         assert_eq!(files.len(), 3);
         // Files should be numbered in order
@@ -0,0 +1,409 @@
+// src/snapshot/diff.rs
+//! Snapshot diff: the entities added, changed and removed between an older
+//! version of the store (or a previously generated snapshot directory) and
+//! the current one, as a markdown changelog to review before committing
+//! `.medulla` changes.
+
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use serde::Serialize;
+
+use crate::storage::{changed_fields, LoroStore};
+use crate::Result;
+
+use super::{generate_snapshot_with_layout, read_snapshot_meta};
+
+/// Fields every update touches, left out of the changed field lists
+const BOOKKEEPING_FIELDS: &[&str] = &["updated_at", "updated_by", "updated"];
+
+/// What happened to an entity between the two versions
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChangeKind {
+    Added,
+    Changed,
+    Removed,
+}
+
+impl ChangeKind {
+    fn heading(self) -> &'static str {
+        match self {
+            ChangeKind::Added => "Added",
+            ChangeKind::Changed => "Changed",
+            ChangeKind::Removed => "Removed",
+        }
+    }
+}
+
+/// An entity that differs between the two versions
+#[derive(Debug, Clone, Serialize)]
+pub struct EntityChange {
+    pub kind: ChangeKind,
+    #[serde(rename = "type")]
+    pub entity_type: String,
+    pub id: String,
+    pub sequence_number: u32,
+    /// Title in the newer version, or the older one if it was removed
+    pub title: String,
+    /// Fields that differ, for changed entities
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub fields: Vec<String>,
+}
+
+/// Every entity that differs between two versions, by type then sequence
+/// number
+#[derive(Debug, Clone, Serialize)]
+pub struct SnapshotDiff {
+    /// What the current store was compared against
+    pub from: String,
+    pub changes: Vec<EntityChange>,
+}
+
+/// An entity as seen on one side of a diff
+struct Side {
+    entity_type: String,
+    id: String,
+    sequence_number: u32,
+    title: String,
+    value: serde_json::Value,
+}
+
+/// Compare two stores entity by entity
+pub fn diff_stores(from: &str, old: &LoroStore, new: &LoroStore) -> Result<SnapshotDiff> {
+    let sides = |store: &LoroStore| -> Result<BTreeMap<String, Side>> {
+        Ok(store
+            .entity_values()?
+            .into_iter()
+            .filter_map(|(entity_type, value)| {
+                let id = value.get("id")?.as_str()?.to_string();
+                let side = Side {
+                    entity_type: entity_type.to_string(),
+                    id: id.clone(),
+                    sequence_number: value.get("sequence_number")?.as_u64()? as u32,
+                    title: value.get("title")?.as_str()?.to_string(),
+                    value,
+                };
+                Some((id, side))
+            })
+            .collect())
+    };
+    Ok(diff_sides(from, sides(old)?, sides(new)?))
+}
+
+/// Compare a snapshot directory generated earlier with the snapshot the
+/// current store would generate in the same layout.
+///
+/// Entities are matched by the ID in their frontmatter, or for tasks in
+/// the grouped layout by the short ID on their list line. Changed fields
+/// come from the frontmatter, with `content` standing for the rest of the
+/// file.
+pub fn diff_snapshot_dir(old_dir: &Path, store: &LoroStore) -> Result<SnapshotDiff> {
+    let layout = read_snapshot_meta(old_dir)?
+        .map(|meta| meta.layout)
+        .unwrap_or_default();
+
+    let current_dir =
+        std::env::temp_dir().join(format!("medulla-snapshot-diff-{}", uuid::Uuid::new_v4()));
+    let current = generate_snapshot_with_layout(store, &current_dir, layout)
+        .and_then(|_| read_snapshot_dir(&current_dir));
+    let _ = std::fs::remove_dir_all(&current_dir);
+
+    Ok(diff_sides(
+        &old_dir.display().to_string(),
+        read_snapshot_dir(old_dir)?,
+        current?,
+    ))
+}
+
+fn diff_sides(
+    from: &str,
+    mut old: BTreeMap<String, Side>,
+    new: BTreeMap<String, Side>,
+) -> SnapshotDiff {
+    let mut changes = Vec::new();
+    for (key, after) in new {
+        let Some(before) = old.remove(&key) else {
+            changes.push(change(ChangeKind::Added, after, Vec::new()));
+            continue;
+        };
+        if before.value == after.value {
+            continue;
+        }
+        let fields: Vec<String> = changed_fields(Some(&before.value), Some(&after.value))
+            .into_iter()
+            .filter(|f| !BOOKKEEPING_FIELDS.contains(&f.as_str()))
+            .collect();
+        // Only touched, nothing visible changed
+        if fields.is_empty() && before.value.is_object() {
+            continue;
+        }
+        changes.push(change(ChangeKind::Changed, after, fields));
+    }
+    for (_, before) in old {
+        changes.push(change(ChangeKind::Removed, before, Vec::new()));
+    }
+    changes.sort_by(|a, b| {
+        (&a.entity_type, a.sequence_number, &a.id).cmp(&(&b.entity_type, b.sequence_number, &b.id))
+    });
+
+    SnapshotDiff {
+        from: from.to_string(),
+        changes,
+    }
+}
+
+fn change(kind: ChangeKind, side: Side, fields: Vec<String>) -> EntityChange {
+    EntityChange {
+        kind,
+        entity_type: side.entity_type,
+        id: side.id,
+        sequence_number: side.sequence_number,
+        title: side.title,
+        fields,
+    }
+}
+
+/// Entities in a generated snapshot directory, keyed by short ID
+fn read_snapshot_dir(dir: &Path) -> Result<BTreeMap<String, Side>> {
+    let mut sides = BTreeMap::new();
+    for (subdir, entity_type) in [
+        ("decisions", "decision"),
+        ("tasks", "task"),
+        ("notes", "note"),
+        ("prompts", "prompt"),
+        ("components", "component"),
+        ("links", "link"),
+        ("questions", "question"),
+        ("milestones", "milestone"),
+    ] {
+        let Ok(entries) = std::fs::read_dir(dir.join(subdir)) else {
+            continue;
+        };
+        for entry in entries {
+            let path = entry?.path();
+            if path.extension().and_then(|e| e.to_str()) != Some("md") {
+                continue;
+            }
+            let text = std::fs::read_to_string(&path)?;
+            let grouped_tasks = entity_type == "task"
+                && matches!(
+                    path.file_name().and_then(|n| n.to_str()),
+                    Some("active.md" | "completed.md")
+                );
+            if grouped_tasks {
+                for side in parse_task_lines(&text) {
+                    sides.insert(side.id.clone(), side);
+                }
+            } else if let Some(side) = parse_entity_file(entity_type, &text) {
+                sides.insert(side.id[..7.min(side.id.len())].to_string(), side);
+            }
+        }
+    }
+    Ok(sides)
+}
+
+/// An entity file: YAML frontmatter, then the rendered body
+fn parse_entity_file(entity_type: &str, text: &str) -> Option<Side> {
+    let rest = text.strip_prefix("---\n")?;
+    let (yaml, body) = rest.split_once("\n---\n")?;
+    let mut value: serde_json::Value = serde_yaml::from_str(yaml).ok()?;
+    let fields = value.as_object_mut()?;
+    fields.insert("content".to_string(), body.trim().into());
+
+    Some(Side {
+        entity_type: entity_type.to_string(),
+        id: fields.get("id")?.as_str()?.to_string(),
+        sequence_number: fields.get("sequence")?.as_u64()? as u32,
+        title: fields.get("title")?.as_str()?.to_string(),
+        value,
+    })
+}
+
+/// Tasks listed in active.md or completed.md: a
+/// ``- [ ] **Title** `#N` `(short-id)` `` line and its indented details
+fn parse_task_lines(text: &str) -> Vec<Side> {
+    let mut sides: Vec<Side> = Vec::new();
+    for line in text.lines() {
+        if let Some(item) = line.strip_prefix("- [") {
+            let title = item.split("**").nth(1).unwrap_or_default();
+            let mut codes = item.split('`').skip(1).step_by(2);
+            let sequence = codes.next().and_then(|c| c.strip_prefix('#'));
+            let short_id = codes
+                .next()
+                .and_then(|c| c.strip_prefix('('))
+                .and_then(|c| c.strip_suffix(')'));
+            if let (Some(sequence), Some(short_id)) =
+                (sequence.and_then(|s| s.parse().ok()), short_id)
+            {
+                sides.push(Side {
+                    entity_type: "task".to_string(),
+                    id: short_id.to_string(),
+                    sequence_number: sequence,
+                    title: title.to_string(),
+                    value: line.into(),
+                });
+                continue;
+            }
+        }
+        let continues = line.starts_with("  ") && !line.trim().is_empty();
+        if let Some(side) = sides.last_mut().filter(|_| continues) {
+            let entry = format!("{}\n{}", side.value.as_str().unwrap_or_default(), line);
+            side.value = entry.into();
+        }
+    }
+    sides
+}
+
+impl SnapshotDiff {
+    /// Number of changes of `kind`
+    pub fn count(&self, kind: ChangeKind) -> usize {
+        self.changes.iter().filter(|c| c.kind == kind).count()
+    }
+
+    /// Render as markdown: one section per kind of change with entities
+    /// grouped by type
+    pub fn to_markdown(&self) -> String {
+        let mut md = format!("# Changes since {}\n\n", self.from);
+        if self.changes.is_empty() {
+            md.push_str("No changes.\n");
+            return md;
+        }
+        md.push_str(&format!(
+            "{} added, {} changed, {} removed\n",
+            self.count(ChangeKind::Added),
+            self.count(ChangeKind::Changed),
+            self.count(ChangeKind::Removed)
+        ));
+
+        let mut by_kind: BTreeMap<ChangeKind, BTreeMap<&str, Vec<&EntityChange>>> = BTreeMap::new();
+        for change in &self.changes {
+            by_kind
+                .entry(change.kind)
+                .or_default()
+                .entry(change.entity_type.as_str())
+                .or_default()
+                .push(change);
+        }
+        for (kind, by_type) in &by_kind {
+            md.push_str(&format!("\n## {}\n", kind.heading()));
+            for (entity_type, changes) in by_type {
+                md.push_str(&format!("\n### {}s\n\n", capitalize(entity_type)));
+                for change in changes {
+                    md.push_str(&format!("- {:03} {}", change.sequence_number, change.title));
+                    if !change.fields.is_empty() {
+                        md.push_str(&format!(": {}", change.fields.join(", ")));
+                    }
+                    md.push('\n');
+                }
+            }
+        }
+        md
+    }
+}
+
+fn capitalize(s: &str) -> String {
+    let mut chars = s.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::entity::{Decision, Note, Task};
+    use crate::storage::DecisionUpdate;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_diff_stores() {
+        let tmp = TempDir::new().unwrap();
+        let store = LoroStore::init(tmp.path()).unwrap();
+
+        let decision = Decision::new("Use SQLite".to_string(), 1);
+        store.add_decision(&decision).unwrap();
+        let note = Note::new("Scratch".to_string(), 2);
+        store.add_note(&note).unwrap();
+        let old = store.staging();
+
+        store
+            .update_decision(
+                &decision.base.id,
+                DecisionUpdate {
+                    title: Some("Use Postgres".to_string()),
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        store.delete_note(&note.base.id).unwrap();
+        store
+            .add_task(&Task::new("Migrate".to_string(), 3))
+            .unwrap();
+
+        let diff = diff_stores("HEAD", &old, &store).unwrap();
+        assert_eq!(diff.count(ChangeKind::Added), 1);
+        assert_eq!(diff.count(ChangeKind::Changed), 1);
+        assert_eq!(diff.count(ChangeKind::Removed), 1);
+
+        let changed = diff
+            .changes
+            .iter()
+            .find(|c| c.kind == ChangeKind::Changed)
+            .unwrap();
+        assert_eq!(changed.title, "Use Postgres");
+        assert_eq!(changed.fields, vec!["title"]);
+
+        let md = diff.to_markdown();
+        assert!(md.starts_with("# Changes since HEAD"));
+        assert!(md.contains("1 added, 1 changed, 1 removed"));
+        assert!(md.contains("- 001 Use Postgres: title"));
+        assert!(md.contains("- 002 Scratch"));
+        assert!(md.contains("- 003 Migrate"));
+
+        assert!(diff_stores("HEAD", &store, &store)
+            .unwrap()
+            .to_markdown()
+            .contains("No changes."));
+    }
+
+    #[test]
+    fn test_diff_snapshot_dir() {
+        let tmp = TempDir::new().unwrap();
+        let store = LoroStore::init(tmp.path()).unwrap();
+        let snapshot_dir = tmp.path().join("snapshot");
+
+        let decision = Decision::new("Use SQLite".to_string(), 1);
+        store.add_decision(&decision).unwrap();
+        store
+            .add_task(&Task::new("Write parser".to_string(), 2))
+            .unwrap();
+        super::super::generate_snapshot(&store, &snapshot_dir).unwrap();
+
+        store
+            .update_decision(
+                &decision.base.id,
+                DecisionUpdate {
+                    content: Some("Because it is embedded".to_string()),
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        store
+            .add_task(&Task::new("Ship parser".to_string(), 3))
+            .unwrap();
+
+        let diff = diff_snapshot_dir(&snapshot_dir, &store).unwrap();
+        assert_eq!(diff.count(ChangeKind::Added), 1);
+        assert_eq!(diff.count(ChangeKind::Removed), 0);
+        let changed: Vec<_> = diff
+            .changes
+            .iter()
+            .filter(|c| c.kind == ChangeKind::Changed)
+            .collect();
+        assert_eq!(changed.len(), 1);
+        assert_eq!(changed[0].entity_type, "decision");
+        assert!(changed[0].fields.contains(&"content".to_string()));
+    }
+}
@@ -0,0 +1,287 @@
+// src/snapshot/digest.rs
+//! Activity digest: the entities created, updated and completed in a time
+//! window, as markdown that can be posted to a chat channel or pasted into
+//! a changelog.
+
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+use crate::entity::{EntityBase, MilestoneStatus, QuestionStatus, TaskStatus};
+use crate::storage::LoroStore;
+use crate::Result;
+
+use super::utils::format_date;
+
+/// What happened to an entity during the digest window
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DigestActivity {
+    /// A task that is done and was last changed in the window
+    Completed,
+    Created,
+    Updated,
+}
+
+impl DigestActivity {
+    fn heading(self) -> &'static str {
+        match self {
+            DigestActivity::Completed => "Completed",
+            DigestActivity::Created => "Created",
+            DigestActivity::Updated => "Updated",
+        }
+    }
+}
+
+/// An entity with activity in the window
+#[derive(Debug, Clone, Serialize)]
+pub struct DigestEntry {
+    pub activity: DigestActivity,
+    #[serde(rename = "type")]
+    pub entity_type: &'static str,
+    pub id: String,
+    pub sequence_number: u32,
+    pub title: String,
+    pub tags: Vec<String>,
+    pub status: Option<String>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Activity between `since` and `until`
+#[derive(Debug, Clone, Serialize)]
+pub struct Digest {
+    pub since: DateTime<Utc>,
+    pub until: DateTime<Utc>,
+    pub entries: Vec<DigestEntry>,
+}
+
+/// Days covered by a digest period: `day`, `week`, `month`, or a window
+/// such as `7d` or `2w`
+pub fn parse_period(period: &str) -> std::result::Result<u32, String> {
+    match period.trim().to_lowercase().as_str() {
+        "day" | "daily" => Ok(1),
+        "week" | "weekly" => Ok(7),
+        "month" | "monthly" => Ok(30),
+        other => crate::entity::parse_due_window(other),
+    }
+}
+
+/// Collect the entities created, updated or completed between `since` and
+/// `until`, most recently changed first
+pub fn generate_digest(
+    store: &LoroStore,
+    since: DateTime<Utc>,
+    until: DateTime<Utc>,
+) -> Result<Digest> {
+    let mut entities: Vec<(&'static str, EntityBase, Option<String>, bool)> = Vec::new();
+    for d in store.list_decisions()? {
+        entities.push(("decision", d.base, Some(d.status.to_string()), false));
+    }
+    for t in store.list_tasks()? {
+        let done = t.status == TaskStatus::Done;
+        entities.push(("task", t.base, Some(t.status.to_string()), done));
+    }
+    for n in store.list_notes()? {
+        entities.push(("note", n.base, None, false));
+    }
+    for p in store.list_prompts()? {
+        entities.push(("prompt", p.base, None, false));
+    }
+    for c in store.list_components()? {
+        entities.push(("component", c.base, Some(c.status.to_string()), false));
+    }
+    for l in store.list_links()? {
+        entities.push(("link", l.base, None, false));
+    }
+    for q in store.list_questions()? {
+        let answered = q.status == QuestionStatus::Answered;
+        entities.push(("question", q.base, Some(q.status.to_string()), answered));
+    }
+    for m in store.list_milestones()? {
+        let completed = m.status == MilestoneStatus::Completed;
+        entities.push(("milestone", m.base, Some(m.status.to_string()), completed));
+    }
+
+    let in_window = |at: &DateTime<Utc>| *at >= since && *at <= until;
+    let mut entries: Vec<DigestEntry> = entities
+        .into_iter()
+        .filter_map(|(entity_type, base, status, done)| {
+            let activity = if done && in_window(&base.updated_at) {
+                DigestActivity::Completed
+            } else if in_window(&base.created_at) {
+                DigestActivity::Created
+            } else if in_window(&base.updated_at) {
+                DigestActivity::Updated
+            } else {
+                return None;
+            };
+            Some(DigestEntry {
+                activity,
+                entity_type,
+                id: base.id.to_string(),
+                sequence_number: base.sequence_number,
+                title: base.title,
+                tags: base.tags,
+                status,
+                updated_at: base.updated_at,
+            })
+        })
+        .collect();
+    entries.sort_by_key(|e| std::cmp::Reverse(e.updated_at));
+
+    Ok(Digest {
+        since,
+        until,
+        entries,
+    })
+}
+
+impl Digest {
+    /// Number of entries with `activity`
+    pub fn count(&self, activity: DigestActivity) -> usize {
+        self.entries
+            .iter()
+            .filter(|e| e.activity == activity)
+            .count()
+    }
+
+    /// Render as markdown: one section per kind of activity with entities
+    /// grouped by type, then activity counts per tag
+    pub fn to_markdown(&self) -> String {
+        let mut md = format!(
+            "# Digest: {} to {}\n\n",
+            format_date(&self.since),
+            format_date(&self.until)
+        );
+        if self.entries.is_empty() {
+            md.push_str("No activity.\n");
+            return md;
+        }
+        md.push_str(&format!(
+            "{} completed, {} created, {} updated\n",
+            self.count(DigestActivity::Completed),
+            self.count(DigestActivity::Created),
+            self.count(DigestActivity::Updated)
+        ));
+
+        let mut by_activity: BTreeMap<DigestActivity, BTreeMap<&str, Vec<&DigestEntry>>> =
+            BTreeMap::new();
+        for entry in &self.entries {
+            by_activity
+                .entry(entry.activity)
+                .or_default()
+                .entry(entry.entity_type)
+                .or_default()
+                .push(entry);
+        }
+        for (activity, by_type) in &by_activity {
+            md.push_str(&format!("\n## {}\n", activity.heading()));
+            for (entity_type, entries) in by_type {
+                md.push_str(&format!("\n### {}s\n\n", capitalize(entity_type)));
+                for entry in entries {
+                    md.push_str(&format!("- {:03} {}", entry.sequence_number, entry.title));
+                    if let Some(ref status) = entry.status {
+                        if entry.activity != DigestActivity::Completed {
+                            md.push_str(&format!(" ({})", status));
+                        }
+                    }
+                    for tag in &entry.tags {
+                        md.push_str(&format!(" `{}`", tag));
+                    }
+                    md.push('\n');
+                }
+            }
+        }
+
+        // Counts per tag, in the order completed, created, updated
+        let mut by_tag: BTreeMap<&str, [usize; 3]> = BTreeMap::new();
+        for entry in &self.entries {
+            let slot = match entry.activity {
+                DigestActivity::Completed => 0,
+                DigestActivity::Created => 1,
+                DigestActivity::Updated => 2,
+            };
+            for tag in &entry.tags {
+                by_tag.entry(tag.as_str()).or_default()[slot] += 1;
+            }
+        }
+        if !by_tag.is_empty() {
+            md.push_str("\n## By tag\n\n");
+            for (tag, [completed, created, updated]) in by_tag {
+                md.push_str(&format!(
+                    "- `{}`: {} completed, {} created, {} updated\n",
+                    tag, completed, created, updated
+                ));
+            }
+        }
+        md
+    }
+}
+
+fn capitalize(s: &str) -> String {
+    let mut chars = s.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::entity::{Decision, Note, Task};
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_parse_period() {
+        assert_eq!(parse_period("week"), Ok(7));
+        assert_eq!(parse_period("7d"), Ok(7));
+        assert_eq!(parse_period("2w"), Ok(14));
+        assert!(parse_period("soon").is_err());
+    }
+
+    #[test]
+    fn test_generate_digest() {
+        let tmp = TempDir::new().unwrap();
+        let store = LoroStore::init(tmp.path()).unwrap();
+        let now = Utc::now();
+
+        let mut done = Task::new("Ship the parser".to_string(), 1);
+        done.status = TaskStatus::Done;
+        done.base.tags = vec!["backend".to_string()];
+        store.add_task(&done).unwrap();
+
+        let mut decision = Decision::new("Use SQLite".to_string(), 2);
+        decision.base.tags = vec!["backend".to_string()];
+        store.add_decision(&decision).unwrap();
+
+        let mut updated = Note::new("Meeting notes".to_string(), 3);
+        updated.base.created_at = now - chrono::Duration::days(30);
+        store.add_note(&updated).unwrap();
+
+        let mut old = Note::new("Ancient".to_string(), 4);
+        old.base.created_at = now - chrono::Duration::days(30);
+        old.base.updated_at = now - chrono::Duration::days(30);
+        store.add_note(&old).unwrap();
+
+        let digest = generate_digest(
+            &store,
+            now - chrono::Duration::days(7),
+            now + chrono::Duration::minutes(1),
+        )
+        .unwrap();
+        assert_eq!(digest.entries.len(), 3);
+        assert_eq!(digest.count(DigestActivity::Completed), 1);
+        assert_eq!(digest.count(DigestActivity::Created), 1);
+        assert_eq!(digest.count(DigestActivity::Updated), 1);
+
+        let md = digest.to_markdown();
+        assert!(md.contains("1 completed, 1 created, 1 updated"));
+        assert!(md.contains("## Completed\n\n### Tasks\n\n- 001 Ship the parser `backend`\n"));
+        assert!(md.contains("### Decisions\n\n- 002 Use SQLite (proposed) `backend`\n"));
+        assert!(md.contains("## Updated\n\n### Notes\n\n- 003 Meeting notes\n"));
+        assert!(md.contains("- `backend`: 1 completed, 1 created, 0 updated\n"));
+        assert!(!md.contains("Ancient"));
+    }
+}
@@ -0,0 +1,225 @@
+//! History of generated snapshots.
+//!
+//! Every `medulla snapshot` run is recorded in `.medulla/snapshot-history.json`
+//! with its time, entity counts and store version. Runs given a tag (such
+//! as a release version) are written to their own `versions/<tag>`
+//! directory and kept in the history for good; untagged runs only keep the
+//! most recent few, since the pre-commit hook adds one per commit.
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::error::MedullaError;
+use crate::Result;
+
+use super::{utils, SnapshotStats};
+
+/// Name of the snapshot history file in the `.medulla` directory
+pub const SNAPSHOT_HISTORY_FILE: &str = "snapshot-history.json";
+
+/// Where `medulla snapshot` writes markdown by default, from the project root
+pub const DEFAULT_SNAPSHOT_DIR: &str = ".medulla/snapshot";
+
+/// Where `medulla snapshot --format html` writes the site by default
+pub const DEFAULT_SITE_DIR: &str = ".medulla/site";
+
+/// Directory under the snapshot output holding tagged snapshots
+pub const SNAPSHOT_VERSIONS_DIR: &str = "versions";
+
+/// Directory under the snapshot output holding per-collection snapshots
+pub const SNAPSHOT_COLLECTIONS_DIR: &str = "collections";
+
+/// Untagged runs kept in the history
+const MAX_UNTAGGED_RUNS: usize = 20;
+
+/// One recorded snapshot generation
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SnapshotRun {
+    pub generated_at: DateTime<Utc>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tag: Option<String>,
+    /// `markdown` or `html`
+    pub format: String,
+    /// Output directory, relative to the project root when inside it
+    pub path: String,
+    /// Collection the snapshot was limited to
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub collection: Option<String>,
+    /// Store version hash at generation time
+    pub store_version: String,
+    /// Entities in the snapshot, by type
+    pub entity_counts: BTreeMap<String, usize>,
+}
+
+impl SnapshotRun {
+    pub fn total_entities(&self) -> usize {
+        self.entity_counts.values().sum()
+    }
+}
+
+impl SnapshotStats {
+    /// Entity counts by type, as recorded in the snapshot history
+    pub fn entity_counts(&self) -> BTreeMap<String, usize> {
+        [
+            ("decision", self.decisions),
+            ("task", self.tasks_total),
+            ("note", self.notes),
+            ("prompt", self.prompts),
+            ("component", self.components),
+            ("link", self.links),
+            ("question", self.questions),
+            ("milestone", self.milestones),
+        ]
+        .into_iter()
+        .map(|(t, n)| (t.to_string(), n))
+        .collect()
+    }
+}
+
+/// Check that a snapshot tag can name a directory: letters, digits, `.`,
+/// `-` and `_`, not starting with a dot.
+pub fn validate_snapshot_tag(tag: &str) -> Result<()> {
+    let valid = !tag.is_empty()
+        && !tag.starts_with('.')
+        && tag
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '_'));
+    if valid {
+        Ok(())
+    } else {
+        Err(MedullaError::Storage(format!(
+            "Invalid snapshot tag '{}': use letters, digits, '.', '-' and '_'",
+            tag
+        )))
+    }
+}
+
+/// Directory a snapshot tagged `tag` is written to
+pub fn tagged_snapshot_dir(snapshot_dir: &Path, tag: &str) -> PathBuf {
+    snapshot_dir.join(SNAPSHOT_VERSIONS_DIR).join(tag)
+}
+
+/// Directory a snapshot of just the collection `name` is written to
+pub fn collection_snapshot_dir(snapshot_dir: &Path, name: &str) -> PathBuf {
+    snapshot_dir.join(SNAPSHOT_COLLECTIONS_DIR).join(name)
+}
+
+/// Recorded snapshot runs, oldest first
+pub fn read_snapshot_history(medulla_dir: &Path) -> Result<Vec<SnapshotRun>> {
+    let path = medulla_dir.join(SNAPSHOT_HISTORY_FILE);
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let text = std::fs::read_to_string(&path)?;
+    serde_json::from_str(&text)
+        .map_err(|e| MedullaError::Storage(format!("Invalid {}: {}", SNAPSHOT_HISTORY_FILE, e)))
+}
+
+/// Output directory of the latest full snapshot: that of the last recorded
+/// run without a tag or collection, or `DEFAULT_SNAPSHOT_DIR` before any.
+pub fn current_snapshot_dir(medulla_dir: &Path) -> Result<PathBuf> {
+    let root = medulla_dir.parent().unwrap_or(medulla_dir);
+    let latest = read_snapshot_history(medulla_dir)?
+        .into_iter()
+        .rev()
+        .find(|run| run.tag.is_none() && run.collection.is_none());
+    Ok(root.join(latest.map_or_else(|| DEFAULT_SNAPSHOT_DIR.to_string(), |run| run.path)))
+}
+
+/// Append a run to the history, dropping the oldest untagged runs beyond
+/// the most recent few
+pub fn record_snapshot_run(medulla_dir: &Path, run: SnapshotRun) -> Result<()> {
+    let mut runs = read_snapshot_history(medulla_dir)?;
+    runs.push(run);
+
+    let untagged = runs.iter().filter(|r| r.tag.is_none()).count();
+    let mut excess = untagged.saturating_sub(MAX_UNTAGGED_RUNS);
+    runs.retain(|r| {
+        if r.tag.is_none() && excess > 0 {
+            excess -= 1;
+            return false;
+        }
+        true
+    });
+
+    utils::write_snapshot_file(
+        &medulla_dir.join(SNAPSHOT_HISTORY_FILE),
+        &serde_json::to_string_pretty(&runs)?,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn run(tag: Option<&str>, decisions: usize) -> SnapshotRun {
+        let stats = SnapshotStats {
+            decisions,
+            ..Default::default()
+        };
+        SnapshotRun {
+            generated_at: Utc::now(),
+            tag: tag.map(str::to_string),
+            format: "markdown".to_string(),
+            path: DEFAULT_SNAPSHOT_DIR.to_string(),
+            collection: None,
+            store_version: "v".to_string(),
+            entity_counts: stats.entity_counts(),
+        }
+    }
+
+    #[test]
+    fn test_record_snapshot_run_keeps_tagged() {
+        let tmp = TempDir::new().unwrap();
+        assert!(read_snapshot_history(tmp.path()).unwrap().is_empty());
+
+        record_snapshot_run(tmp.path(), run(Some("v1.0"), 1)).unwrap();
+        for i in 0..MAX_UNTAGGED_RUNS + 5 {
+            record_snapshot_run(tmp.path(), run(None, i)).unwrap();
+        }
+
+        let runs = read_snapshot_history(tmp.path()).unwrap();
+        assert_eq!(runs.len(), MAX_UNTAGGED_RUNS + 1);
+        assert_eq!(runs[0].tag.as_deref(), Some("v1.0"));
+        assert_eq!(runs[1].entity_counts["decision"], 5);
+        assert_eq!(runs.last().unwrap().total_entities(), MAX_UNTAGGED_RUNS + 4);
+    }
+
+    #[test]
+    fn test_current_snapshot_dir() {
+        let tmp = TempDir::new().unwrap();
+        let medulla_dir = tmp.path().join(".medulla");
+        std::fs::create_dir(&medulla_dir).unwrap();
+        assert_eq!(
+            current_snapshot_dir(&medulla_dir).unwrap(),
+            tmp.path().join(DEFAULT_SNAPSHOT_DIR)
+        );
+
+        let mut docs = run(None, 1);
+        docs.path = "docs/medulla".to_string();
+        record_snapshot_run(&medulla_dir, docs).unwrap();
+        let mut collection = run(None, 1);
+        collection.path = "docs/medulla/collections/backend".to_string();
+        collection.collection = Some("backend".to_string());
+        record_snapshot_run(&medulla_dir, collection).unwrap();
+        record_snapshot_run(&medulla_dir, run(Some("v1.0"), 1)).unwrap();
+
+        assert_eq!(
+            current_snapshot_dir(&medulla_dir).unwrap(),
+            tmp.path().join("docs/medulla")
+        );
+    }
+
+    #[test]
+    fn test_validate_snapshot_tag() {
+        assert!(validate_snapshot_tag("v1.2.0-rc_1").is_ok());
+        assert!(validate_snapshot_tag("").is_err());
+        assert!(validate_snapshot_tag("../v1").is_err());
+        assert!(validate_snapshot_tag(".hidden").is_err());
+        assert!(validate_snapshot_tag("release 1").is_err());
+    }
+}
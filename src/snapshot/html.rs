@@ -0,0 +1,814 @@
+// src/snapshot/html.rs
+//! Static HTML site generation
+//!
+//! Renders every entity into a browsable site: an index, one page per
+//! entity type, per tag and per entity, and a relation graph drawn in the
+//! browser from embedded JSON. Pages are filled from the small templates
+//! below, so the site needs no external assets.
+
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+
+use chrono::Utc;
+use serde::Serialize;
+use uuid::Uuid;
+
+use crate::entity::{EntityBase, TaskStatus};
+use crate::storage::LoroStore;
+use crate::Result;
+
+use super::utils::{self, format_date, slugify, SnapshotWriter};
+use super::{read_snapshot_meta, SnapshotMeta, SnapshotStats, SNAPSHOT_META_FILE};
+
+/// Entity types in the order they appear in the navigation
+const ENTITY_TYPES: [&str; 8] = [
+    "decision",
+    "task",
+    "note",
+    "prompt",
+    "component",
+    "link",
+    "question",
+    "milestone",
+];
+
+/// Layout shared by every page, filled by `fill_template`. Links are
+/// relative, so the site works when opened straight from disk.
+const PAGE_TEMPLATE: &str = r##"<!DOCTYPE html>
+<html lang="en">
+<head>
+<meta charset="utf-8">
+<meta name="viewport" content="width=device-width, initial-scale=1">
+<title>{{title}} · Medulla</title>
+<style>
+body { font-family: -apple-system, BlinkMacSystemFont, "Segoe UI", sans-serif; margin: 0; color: #1f2328; }
+header { background: #24292f; padding: 0.75rem 1.5rem; }
+header a { color: #f0f6fc; margin-right: 1rem; text-decoration: none; }
+main { max-width: 60rem; margin: 0 auto; padding: 1.5rem; }
+table { border-collapse: collapse; width: 100%; }
+th, td { text-align: left; padding: 0.35rem 0.6rem; border-bottom: 1px solid #d0d7de; }
+pre { background: #f6f8fa; padding: 0.75rem; overflow-x: auto; }
+.tag { display: inline-block; background: #ddf4ff; border-radius: 1rem; padding: 0 0.5rem; margin-right: 0.25rem; font-size: 0.85em; }
+.meta th { width: 10rem; color: #57606a; font-weight: normal; }
+footer { color: #57606a; font-size: 0.85em; margin-top: 2rem; }
+#graph { width: 100%; height: 70vh; border: 1px solid #d0d7de; }
+</style>
+</head>
+<body>
+<header>{{nav}}</header>
+<main>
+<h1>{{title}}</h1>
+{{body}}
+<footer>Generated by medulla</footer>
+</main>
+</body>
+</html>
+"##;
+
+/// Body of graph.html. The data is embedded as JSON and laid out with a
+/// small force simulation; clicking a node opens its page.
+const GRAPH_TEMPLATE: &str = r##"<svg id="graph"></svg>
+<script id="graph-data" type="application/json">{{data}}</script>
+<script>
+(function () {
+  var data = JSON.parse(document.getElementById("graph-data").textContent);
+  var svg = document.getElementById("graph");
+  var ns = "http://www.w3.org/2000/svg";
+  var colors = { decision: "#8250df", task: "#1a7f37", note: "#0969da", prompt: "#bf3989", component: "#bc4c00", link: "#57606a", question: "#9a6700", milestone: "#0550ae" };
+  var w = svg.clientWidth, h = svg.clientHeight;
+  var index = {};
+  data.nodes.forEach(function (n, i) {
+    n.x = w / 2 + Math.cos(i) * w / 3 * Math.random();
+    n.y = h / 2 + Math.sin(i) * h / 3 * Math.random();
+    n.vx = 0; n.vy = 0;
+    index[n.id] = n;
+  });
+  var edges = data.edges.filter(function (e) { return index[e.source] && index[e.target]; });
+  for (var step = 0; step < 300; step++) {
+    data.nodes.forEach(function (a) {
+      data.nodes.forEach(function (b) {
+        if (a === b) return;
+        var dx = a.x - b.x, dy = a.y - b.y, d2 = dx * dx + dy * dy + 0.01;
+        a.vx += dx / d2 * 400; a.vy += dy / d2 * 400;
+      });
+      a.vx += (w / 2 - a.x) * 0.002; a.vy += (h / 2 - a.y) * 0.002;
+    });
+    edges.forEach(function (e) {
+      var s = index[e.source], t = index[e.target];
+      var dx = t.x - s.x, dy = t.y - s.y;
+      s.vx += dx * 0.01; s.vy += dy * 0.01;
+      t.vx -= dx * 0.01; t.vy -= dy * 0.01;
+    });
+    data.nodes.forEach(function (n) {
+      n.vx *= 0.6; n.vy *= 0.6;
+      n.x = Math.max(20, Math.min(w - 20, n.x + n.vx));
+      n.y = Math.max(20, Math.min(h - 20, n.y + n.vy));
+    });
+  }
+  function el(name, attrs, parent) {
+    var e = document.createElementNS(ns, name);
+    for (var k in attrs) e.setAttribute(k, attrs[k]);
+    parent.appendChild(e);
+    return e;
+  }
+  edges.forEach(function (e) {
+    var s = index[e.source], t = index[e.target];
+    var line = el("line", { x1: s.x, y1: s.y, x2: t.x, y2: t.y, stroke: "#afb8c1" }, svg);
+    el("title", {}, line).textContent = e.type;
+  });
+  data.nodes.forEach(function (n) {
+    var a = el("a", { href: n.url }, svg);
+    el("circle", { cx: n.x, cy: n.y, r: 7, fill: colors[n.type] || "#57606a" }, a);
+    el("text", { x: n.x + 10, y: n.y + 4, "font-size": 11 }, a).textContent = n.label;
+  });
+})();
+</script>
+"##;
+
+/// An entity as shown on the site
+struct SiteEntity {
+    base: EntityBase,
+    entity_type: &'static str,
+    /// Type-specific fields, such as status or URL, in display order
+    fields: Vec<(&'static str, String)>,
+}
+
+impl SiteEntity {
+    fn path(&self) -> String {
+        format!(
+            "entities/{:03}-{}.html",
+            self.base.sequence_number,
+            slugify(&self.base.title)
+        )
+    }
+
+    fn label(&self) -> String {
+        format!("{:03} {}", self.base.sequence_number, self.base.title)
+    }
+
+    fn field(&self, name: &str) -> Option<&str> {
+        self.fields
+            .iter()
+            .find(|(key, _)| *key == name)
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+#[derive(Serialize)]
+struct GraphNodeData {
+    id: String,
+    label: String,
+    #[serde(rename = "type")]
+    entity_type: &'static str,
+    url: String,
+}
+
+#[derive(Serialize)]
+struct GraphEdgeData {
+    source: String,
+    target: String,
+    #[serde(rename = "type")]
+    relation_type: String,
+}
+
+fn collect_entities(store: &LoroStore) -> Result<Vec<SiteEntity>> {
+    let mut entities = Vec::new();
+    for d in store.list_decisions()? {
+        let mut fields = vec![("status", d.status.to_string())];
+        fields.extend(d.priority.map(|p| ("priority", p.to_string())));
+        entities.push(SiteEntity {
+            base: d.base,
+            entity_type: "decision",
+            fields,
+        });
+    }
+    for t in store.list_tasks()? {
+        let mut fields = vec![
+            ("status", t.status.to_string()),
+            ("priority", t.priority.to_string()),
+        ];
+        fields.extend(t.due_date.map(|d| ("due", d.to_string())));
+        fields.extend(t.assignee.map(|a| ("assignee", a)));
+        entities.push(SiteEntity {
+            base: t.base,
+            entity_type: "task",
+            fields,
+        });
+    }
+    for n in store.list_notes()? {
+        let fields = n.note_type.map(|t| ("note type", t)).into_iter().collect();
+        entities.push(SiteEntity {
+            base: n.base,
+            entity_type: "note",
+            fields,
+        });
+    }
+    for p in store.list_prompts()? {
+        entities.push(SiteEntity {
+            base: p.base,
+            entity_type: "prompt",
+            fields: Vec::new(),
+        });
+    }
+    for c in store.list_components()? {
+        let mut fields = vec![("status", c.status.to_string())];
+        fields.extend(c.component_type.map(|t| ("component type", t)));
+        fields.extend(c.owner.map(|o| ("owner", o)));
+        entities.push(SiteEntity {
+            base: c.base,
+            entity_type: "component",
+            fields,
+        });
+    }
+    for l in store.list_links()? {
+        let mut fields = vec![("url", l.url)];
+        fields.extend(l.link_type.map(|t| ("link type", t)));
+        entities.push(SiteEntity {
+            base: l.base,
+            entity_type: "link",
+            fields,
+        });
+    }
+    for q in store.list_questions()? {
+        let mut fields = vec![("status", q.status.to_string())];
+        fields.extend(q.answered_by.map(|a| ("answered by", a)));
+        entities.push(SiteEntity {
+            base: q.base,
+            entity_type: "question",
+            fields,
+        });
+    }
+    for progress in crate::graph::MilestoneProgress::all(store, true)? {
+        let mut fields = vec![("status", progress.milestone.status.to_string())];
+        fields.extend(
+            progress
+                .milestone
+                .target_date
+                .map(|d| ("target date", d.to_string())),
+        );
+        fields.push(("progress", progress.summary()));
+        entities.push(SiteEntity {
+            base: progress.milestone.base,
+            entity_type: "milestone",
+            fields,
+        });
+    }
+    entities.sort_by_key(|e| e.base.sequence_number);
+    Ok(entities)
+}
+
+/// Generate a static HTML site for all entities in `site_dir`
+///
+/// Like the markdown snapshot, generation is incremental: unchanged pages
+/// are not rewritten and pages of removed entities are deleted.
+pub fn generate_html_site(store: &LoroStore, site_dir: &Path) -> Result<SnapshotStats> {
+    let mut stats = SnapshotStats::default();
+    let previous = read_snapshot_meta(site_dir)?
+        .map(|meta| meta.file_hashes)
+        .unwrap_or_default();
+    let mut writer = SnapshotWriter::new(site_dir).with_previous(previous);
+    let generated_at = Utc::now();
+
+    let entities = collect_entities(store)?;
+    let by_id: HashMap<Uuid, &SiteEntity> = entities.iter().map(|e| (e.base.id, e)).collect();
+    let relations: Vec<_> = store
+        .list_relations()?
+        .into_iter()
+        .filter(|r| {
+            r.is_active() && by_id.contains_key(&r.source_id) && by_id.contains_key(&r.target_id)
+        })
+        .collect();
+
+    let mut tags: BTreeMap<String, Vec<&SiteEntity>> = BTreeMap::new();
+    for entity in &entities {
+        for tag in &entity.base.tags {
+            tags.entry(tag.clone()).or_default().push(entity);
+        }
+        match entity.entity_type {
+            "decision" => stats.decisions += 1,
+            "task" if entity.field("status") == Some(TaskStatus::Done.to_string().as_str()) => {
+                stats.tasks_completed += 1
+            }
+            "task" => stats.tasks_active += 1,
+            "note" => stats.notes += 1,
+            "prompt" => stats.prompts += 1,
+            "component" => stats.components += 1,
+            "question" => stats.questions += 1,
+            "milestone" => stats.milestones += 1,
+            _ => stats.links += 1,
+        }
+    }
+    stats.tasks_total = stats.tasks_active + stats.tasks_completed;
+    let slugs = tag_slugs(tags.keys());
+
+    let mut write_page = |path: &str, title: &str, body: &str| -> Result<()> {
+        writer.write(path, &render_page(path, title, body))?;
+        stats.files_generated.push(path.to_string());
+        Ok(())
+    };
+
+    // Entity pages
+    for entity in &entities {
+        let mut outgoing = Vec::new();
+        let mut incoming = Vec::new();
+        for relation in &relations {
+            if relation.source_id == entity.base.id {
+                outgoing.push((
+                    relation.relation_type.to_string(),
+                    by_id[&relation.target_id],
+                ));
+            } else if relation.target_id == entity.base.id {
+                incoming.push((
+                    relation.relation_type.to_string(),
+                    by_id[&relation.source_id],
+                ));
+            }
+        }
+        let body = entity_body(entity, &outgoing, &incoming, &slugs);
+        write_page(&entity.path(), &entity.label(), &body)?;
+    }
+
+    // Per-type pages
+    for entity_type in ENTITY_TYPES {
+        let of_type: Vec<&SiteEntity> = entities
+            .iter()
+            .filter(|e| e.entity_type == entity_type)
+            .collect();
+        let path = format!("types/{}.html", entity_type);
+        write_page(
+            &path,
+            &type_heading(entity_type),
+            &entity_table(&of_type, &slugs),
+        )?;
+    }
+
+    // Tag pages
+    for (tag, tagged) in &tags {
+        let path = format!("tags/{}.html", slugs[tag]);
+        write_page(
+            &path,
+            &format!("Tag: {}", tag),
+            &entity_table(tagged, &slugs),
+        )?;
+    }
+
+    // Relation graph
+    let data = GraphData {
+        nodes: entities
+            .iter()
+            .map(|e| GraphNodeData {
+                id: e.base.id.to_string(),
+                label: e.label(),
+                entity_type: e.entity_type,
+                url: e.path(),
+            })
+            .collect(),
+        edges: relations
+            .iter()
+            .map(|r| GraphEdgeData {
+                source: r.source_id.to_string(),
+                target: r.target_id.to_string(),
+                relation_type: r.relation_type.to_string(),
+            })
+            .collect(),
+    };
+    // `</` inside the embedded JSON would end the script element early
+    let json = serde_json::to_string(&data)?.replace("</", "<\\/");
+    write_page(
+        "graph.html",
+        "Relation graph",
+        &fill_template(GRAPH_TEMPLATE, &[("data", &json)]),
+    )?;
+
+    // Index (last, so the counts are complete)
+    let mut body = String::from("<h2>Entities</h2>\n<ul>\n");
+    for entity_type in ENTITY_TYPES {
+        let count = entities
+            .iter()
+            .filter(|e| e.entity_type == entity_type)
+            .count();
+        body.push_str(&format!(
+            "<li><a href=\"types/{}.html\">{}</a> ({})</li>\n",
+            entity_type,
+            type_heading(entity_type),
+            count
+        ));
+    }
+    body.push_str("</ul>\n<p><a href=\"graph.html\">Relation graph</a></p>\n");
+    if !tags.is_empty() {
+        body.push_str("<h2>Tags</h2>\n<p>\n");
+        for (tag, tagged) in &tags {
+            body.push_str(&format!(
+                "<a class=\"tag\" href=\"tags/{}.html\">{} ({})</a>\n",
+                slugs[tag],
+                escape_html(tag),
+                tagged.len()
+            ));
+        }
+        body.push_str("</p>\n");
+    }
+    let mut recent: Vec<&SiteEntity> = entities.iter().collect();
+    recent.sort_by_key(|e| std::cmp::Reverse(e.base.updated_at));
+    recent.truncate(20);
+    body.push_str("<h2>Recently updated</h2>\n");
+    body.push_str(&entity_table(&recent, &slugs));
+    write_page("index.html", "Medulla", &body)?;
+
+    stats.files_removed = utils::remove_stale_files(site_dir, writer.hashes())?;
+    stats.files_written = writer.written().to_vec();
+
+    let meta = SnapshotMeta {
+        store_version: store.version_hash(),
+        generated_at,
+        file_hashes: writer.hashes().clone(),
+        layout: Default::default(),
+    };
+    utils::write_snapshot_file(
+        &site_dir.join(SNAPSHOT_META_FILE),
+        &serde_json::to_string_pretty(&meta)?,
+    )?;
+
+    Ok(stats)
+}
+
+#[derive(Serialize)]
+struct GraphData {
+    nodes: Vec<GraphNodeData>,
+    edges: Vec<GraphEdgeData>,
+}
+
+/// Escape text for use in HTML content and attribute values
+pub fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Fill the page template for the page at `path` (relative to the site root)
+fn render_page(path: &str, title: &str, body: &str) -> String {
+    let root = "../".repeat(path.matches('/').count());
+    let mut nav = format!("<a href=\"{0}index.html\">Index</a>", root);
+    for entity_type in ENTITY_TYPES {
+        nav.push_str(&format!(
+            "<a href=\"{}types/{}.html\">{}</a>",
+            root,
+            entity_type,
+            type_heading(entity_type)
+        ));
+    }
+    nav.push_str(&format!("<a href=\"{}graph.html\">Graph</a>", root));
+
+    // Relative links in bodies are written from the site root
+    let body = body
+        .replace("href=\"types/", &format!("href=\"{}types/", root))
+        .replace("href=\"tags/", &format!("href=\"{}tags/", root))
+        .replace("href=\"entities/", &format!("href=\"{}entities/", root))
+        .replace("href=\"graph.html", &format!("href=\"{}graph.html", root));
+
+    fill_template(
+        PAGE_TEMPLATE,
+        &[
+            ("title", &escape_html(title)),
+            ("nav", &nav),
+            ("body", &body),
+        ],
+    )
+}
+
+/// Replace `{{name}}` placeholders in one pass over the template, so text
+/// filled in is never itself searched for placeholders. Unknown
+/// placeholders are left as they are.
+fn fill_template(template: &str, values: &[(&str, &str)]) -> String {
+    let mut filled = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find("{{") {
+        filled.push_str(&rest[..start]);
+        let after = &rest[start + 2..];
+        let value = after.find("}}").and_then(|end| {
+            values
+                .iter()
+                .find(|(name, _)| *name == &after[..end])
+                .map(|(_, value)| (end, value))
+        });
+        match value {
+            Some((end, value)) => {
+                filled.push_str(value);
+                rest = &after[end + 2..];
+            }
+            None => {
+                filled.push_str("{{");
+                rest = after;
+            }
+        }
+    }
+    filled.push_str(rest);
+    filled
+}
+
+/// File name (without extension) of each tag's page. Tags that slugify
+/// alike, such as "C++" and "c", get numbered suffixes in tag order, so
+/// every tag keeps a page of its own.
+fn tag_slugs<'a>(tags: impl IntoIterator<Item = &'a String>) -> HashMap<String, String> {
+    let mut used = std::collections::HashSet::new();
+    let mut slugs = HashMap::new();
+    for tag in tags {
+        let base = slugify(tag);
+        let mut slug = base.clone();
+        let mut n = 1;
+        while !used.insert(slug.clone()) {
+            n += 1;
+            slug = format!("{}-{}", base, n);
+        }
+        slugs.insert(tag.clone(), slug);
+    }
+    slugs
+}
+
+fn type_heading(entity_type: &str) -> String {
+    let mut heading = entity_type[..1].to_uppercase() + &entity_type[1..];
+    heading.push('s');
+    heading
+}
+
+fn tag_links(tags: &[String], slugs: &HashMap<String, String>) -> String {
+    tags.iter()
+        .map(|tag| {
+            format!(
+                "<a class=\"tag\" href=\"tags/{}.html\">{}</a>",
+                slugs.get(tag).cloned().unwrap_or_else(|| slugify(tag)),
+                escape_html(tag)
+            )
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn entity_link(entity: &SiteEntity) -> String {
+    format!(
+        "<a href=\"{}\">{}</a>",
+        entity.path(),
+        escape_html(&entity.label())
+    )
+}
+
+fn entity_table(entities: &[&SiteEntity], slugs: &HashMap<String, String>) -> String {
+    if entities.is_empty() {
+        return "<p><em>None.</em></p>\n".to_string();
+    }
+    let mut table = String::from(
+        "<table>\n<tr><th>Entity</th><th>Type</th><th>Status</th><th>Tags</th><th>Updated</th></tr>\n",
+    );
+    for entity in entities {
+        table.push_str(&format!(
+            "<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>\n",
+            entity_link(entity),
+            entity.entity_type,
+            escape_html(entity.field("status").unwrap_or("")),
+            tag_links(&entity.base.tags, slugs),
+            format_date(&entity.base.updated_at)
+        ));
+    }
+    table.push_str("</table>\n");
+    table
+}
+
+fn entity_body(
+    entity: &SiteEntity,
+    outgoing: &[(String, &SiteEntity)],
+    incoming: &[(String, &SiteEntity)],
+    slugs: &HashMap<String, String>,
+) -> String {
+    let base = &entity.base;
+    let mut rows = vec![
+        ("id", escape_html(&base.id.to_string())),
+        (
+            "type",
+            format!(
+                "<a href=\"types/{}.html\">{}</a>",
+                entity.entity_type, entity.entity_type
+            ),
+        ),
+    ];
+    for (key, value) in &entity.fields {
+        // Only web URLs become links, so stored text can't inject script
+        let value =
+            if *key == "url" && (value.starts_with("http://") || value.starts_with("https://")) {
+                format!("<a href=\"{0}\">{0}</a>", escape_html(value))
+            } else {
+                escape_html(value)
+            };
+        rows.push((*key, value));
+    }
+    if !base.tags.is_empty() {
+        rows.push(("tags", tag_links(&base.tags, slugs)));
+    }
+    rows.push(("created", format_date(&base.created_at)));
+    rows.push(("updated", format_date(&base.updated_at)));
+    if let Some(author) = &base.created_by {
+        rows.push(("created by", escape_html(author)));
+    }
+
+    let mut body = String::from("<table class=\"meta\">\n");
+    for (key, value) in rows {
+        body.push_str(&format!("<tr><th>{}</th><td>{}</td></tr>\n", key, value));
+    }
+    body.push_str("</table>\n");
+
+    if let Some(content) = base.content.as_deref().filter(|c| !c.trim().is_empty()) {
+        body.push_str(&markdown_to_html(content));
+    }
+
+    if !outgoing.is_empty() || !incoming.is_empty() {
+        body.push_str("<h2>Relations</h2>\n<ul>\n");
+        for (relation_type, other) in outgoing {
+            body.push_str(&format!(
+                "<li>{} {}</li>\n",
+                escape_html(relation_type),
+                entity_link(other)
+            ));
+        }
+        for (relation_type, other) in incoming {
+            body.push_str(&format!(
+                "<li>{} {} this</li>\n",
+                entity_link(other),
+                escape_html(relation_type)
+            ));
+        }
+        body.push_str("</ul>\n");
+    }
+    body
+}
+
+/// Render the common subset of markdown used in entity content: headings,
+/// fenced code blocks, bullet lists and paragraphs. Everything else is
+/// shown as escaped text.
+pub fn markdown_to_html(markdown: &str) -> String {
+    let mut html = String::new();
+    let mut paragraph: Vec<&str> = Vec::new();
+    let mut in_list = false;
+    let mut in_code = false;
+
+    let flush = |html: &mut String, paragraph: &mut Vec<&str>, in_list: &mut bool| {
+        if !paragraph.is_empty() {
+            html.push_str(&format!("<p>{}</p>\n", escape_html(&paragraph.join(" "))));
+            paragraph.clear();
+        }
+        if *in_list {
+            html.push_str("</ul>\n");
+            *in_list = false;
+        }
+    };
+
+    for line in markdown.lines() {
+        let trimmed = line.trim();
+        if trimmed.starts_with("```") {
+            if in_code {
+                html.push_str("</code></pre>\n");
+            } else {
+                flush(&mut html, &mut paragraph, &mut in_list);
+                html.push_str("<pre><code>");
+            }
+            in_code = !in_code;
+        } else if in_code {
+            html.push_str(&escape_html(line));
+            html.push('\n');
+        } else if trimmed.is_empty() {
+            flush(&mut html, &mut paragraph, &mut in_list);
+        } else if let Some(item) = trimmed
+            .strip_prefix("- ")
+            .or_else(|| trimmed.strip_prefix("* "))
+        {
+            if !in_list {
+                flush(&mut html, &mut paragraph, &mut in_list);
+                html.push_str("<ul>\n");
+                in_list = true;
+            }
+            html.push_str(&format!("<li>{}</li>\n", escape_html(item)));
+        } else if trimmed.starts_with('#') {
+            flush(&mut html, &mut paragraph, &mut in_list);
+            let level = trimmed.chars().take_while(|c| *c == '#').count().min(6);
+            // Entity titles are the page's h1
+            let level = (level + 1).min(6);
+            html.push_str(&format!(
+                "<h{0}>{1}</h{0}>\n",
+                level,
+                escape_html(trimmed.trim_start_matches('#').trim())
+            ));
+        } else {
+            if in_list {
+                flush(&mut html, &mut paragraph, &mut in_list);
+            }
+            paragraph.push(trimmed);
+        }
+    }
+    if in_code {
+        html.push_str("</code></pre>\n");
+    }
+    flush(&mut html, &mut paragraph, &mut in_list);
+    html
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::entity::{Decision, Relation, RelationType, Task};
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_markdown_to_html() {
+        let html = markdown_to_html(
+            "# Plan\n\nFirst <b>line</b>\nsecond\n\n- one\n- two\n\n```\nlet x = 1 < 2;\n```",
+        );
+        assert_eq!(
+            html,
+            "<h2>Plan</h2>\n<p>First &lt;b&gt;line&lt;/b&gt; second</p>\n<ul>\n<li>one</li>\n<li>two</li>\n</ul>\n<pre><code>let x = 1 &lt; 2;\n</code></pre>\n"
+        );
+    }
+
+    #[test]
+    fn test_fill_template_is_single_pass() {
+        let filled = fill_template(
+            "<h1>{{title}}</h1>{{body}}{{other}}",
+            &[("title", "{{body}}"), ("body", "<p>x</p>")],
+        );
+        assert_eq!(filled, "<h1>{{body}}</h1><p>x</p>{{other}}");
+    }
+
+    #[test]
+    fn test_tag_slugs_are_unique() {
+        let tags = ["C++", "c", "???", "untitled"].map(String::from);
+        let slugs = tag_slugs(&tags);
+        assert_eq!(slugs["C++"], "c");
+        assert_eq!(slugs["c"], "c-2");
+        assert_eq!(slugs["???"], "untitled");
+        assert_eq!(slugs["untitled"], "untitled-2");
+    }
+
+    #[test]
+    fn test_generate_html_site() {
+        let tmp = TempDir::new().unwrap();
+        let store = LoroStore::init(tmp.path()).unwrap();
+        let mut decision = Decision::new("Use <Postgres>".to_string(), 1);
+        decision.base.tags = vec!["database".to_string()];
+        decision.base.content = Some("We need </script> safety".to_string());
+        let task = Task::new("Write migration".to_string(), 2);
+        store.add_decision(&decision).unwrap();
+        store.add_task(&task).unwrap();
+        store
+            .add_relation(&Relation::new(
+                task.base.id,
+                "task".to_string(),
+                decision.base.id,
+                "decision".to_string(),
+                RelationType::Implements,
+            ))
+            .unwrap();
+
+        let site = tmp.path().join("site");
+        let stats = generate_html_site(&store, &site).unwrap();
+        assert_eq!(stats.decisions, 1);
+        assert_eq!(stats.tasks_active, 1);
+        for page in [
+            "index.html",
+            "graph.html",
+            "types/decision.html",
+            "tags/database.html",
+            "entities/001-use-postgres.html",
+        ] {
+            assert!(
+                stats.files_generated.contains(&page.to_string()),
+                "{}",
+                page
+            );
+        }
+
+        let decision_page =
+            std::fs::read_to_string(site.join("entities/001-use-postgres.html")).unwrap();
+        assert!(decision_page.contains("<h1>001 Use &lt;Postgres&gt;</h1>"));
+        assert!(decision_page.contains("href=\"../tags/database.html\""));
+        assert!(decision_page.contains("002 Write migration</a> implements this"));
+
+        assert!(decision_page.contains("<p>We need &lt;/script&gt; safety</p>"));
+
+        let graph = std::fs::read_to_string(site.join("graph.html")).unwrap();
+        assert!(graph.contains("\"type\":\"implements\""));
+
+        // A second run writes nothing, and removed entities lose their page
+        let again = generate_html_site(&store, &site).unwrap();
+        assert!(again.files_written.is_empty());
+        store.delete_task(&task.base.id).unwrap();
+        let after = generate_html_site(&store, &site).unwrap();
+        assert!(after
+            .files_removed
+            .contains(&"entities/002-write-migration.html".to_string()));
+    }
+}
@@ -2,15 +2,16 @@
 //! Link snapshot generation
 
 use std::collections::HashSet;
-use std::path::Path;
 
 use serde::Serialize;
+use uuid::Uuid;
 
 use crate::entity::Link;
 use crate::storage::LoroStore;
 use crate::Result;
 
-use super::utils::{format_date, slugify, write_snapshot_file};
+use super::backlinks::EntityFiles;
+use super::utils::{format_date, slugify, SnapshotWriter};
 use super::{yaml_frontmatter, GeneratedFile};
 
 #[derive(Serialize)]
@@ -22,10 +23,16 @@ struct LinkFrontmatter {
     #[serde(skip_serializing_if = "Option::is_none")]
     #[serde(rename = "type")]
     link_type: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    description: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    favicon: Option<String>,
     created: String,
     updated: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     created_by: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    updated_by: Option<String>,
     #[serde(skip_serializing_if = "Vec::is_empty")]
     tags: Vec<String>,
 }
@@ -38,9 +45,12 @@ impl LinkFrontmatter {
             title: link.base.title.clone(),
             url: link.url.clone(),
             link_type: link.link_type.clone(),
+            description: link.description.clone(),
+            favicon: link.favicon.clone(),
             created: format_date(&link.base.created_at),
             updated: format_date(&link.base.updated_at),
             created_by: link.base.created_by.clone(),
+            updated_by: link.base.updated_by.clone(),
             tags: link.base.tags.clone(),
         }
     }
@@ -60,8 +70,38 @@ fn unique_filename(base_slug: &str, sequence: u32, used_slugs: &mut HashSet<Stri
     }
 }
 
+/// Snapshot file of each link, relative to the snapshot directory
+pub(super) fn file_paths(links: &[Link]) -> Vec<(Uuid, String)> {
+    let mut sorted: Vec<&Link> = links.iter().collect();
+    sorted.sort_by_key(|l| l.base.sequence_number);
+
+    let mut used_slugs = HashSet::new();
+    sorted
+        .into_iter()
+        .map(|l| {
+            let slug = slugify(&l.base.title);
+            let filename = unique_filename(&slug, l.base.sequence_number, &mut used_slugs);
+            (l.base.id, format!("links/{}", filename))
+        })
+        .collect()
+}
+
+/// Render a link as snapshot markdown
+pub fn render(link: &Link) -> Result<String> {
+    let frontmatter = LinkFrontmatter::from_link(link);
+    let yaml = yaml_frontmatter(&frontmatter)?;
+
+    // Content is just the body
+    let body = link.base.content.as_deref().unwrap_or("");
+    Ok(format!("{}\n{}", yaml, body))
+}
+
 /// Generate link snapshot files
-pub fn generate(store: &LoroStore, snapshot_dir: &Path) -> Result<Vec<GeneratedFile>> {
+pub fn generate(
+    store: &LoroStore,
+    writer: &mut SnapshotWriter,
+    files: &EntityFiles,
+) -> Result<Vec<GeneratedFile>> {
     let links = store.list_links()?;
     let mut generated = Vec::new();
 
@@ -73,25 +113,16 @@ pub fn generate(store: &LoroStore, snapshot_dir: &Path) -> Result<Vec<GeneratedF
     let mut sorted_links = links;
     sorted_links.sort_by_key(|l| l.base.sequence_number);
 
-    let links_dir = snapshot_dir.join("links");
-    let mut used_slugs = HashSet::new();
-
     for link in &sorted_links {
-        let frontmatter = LinkFrontmatter::from_link(link);
-        let yaml = yaml_frontmatter(&frontmatter)?;
-
-        // Content is just the body
-        let body = link.base.content.as_deref().unwrap_or("");
-        let content = format!("{}\n{}", yaml, body);
-
-        let slug = slugify(&link.base.title);
-        let filename = unique_filename(&slug, link.base.sequence_number, &mut used_slugs);
-        let file_path = links_dir.join(&filename);
-
-        write_snapshot_file(&file_path, &content)?;
+        let Some(relative_path) = files.path(&link.base.id) else {
+            continue;
+        };
+        let mut content = files.render_page("link", &link, render(link)?)?;
+        files.append_sections(store, &link.base.id, &mut content)?;
+        writer.write(relative_path, &content)?;
 
         generated.push(GeneratedFile {
-            relative_path: format!("links/{}", filename),
+            relative_path: relative_path.to_string(),
             entity_count: 1,
         });
     }
@@ -172,7 +203,12 @@ mod tests {
         let snapshot_dir = medulla_dir.join("snapshot");
         super::super::utils::ensure_snapshot_dirs(&snapshot_dir).unwrap();
 
-        let files = generate(&store, &snapshot_dir).unwrap();
+        let files = generate(
+            &store,
+            &mut SnapshotWriter::new(&snapshot_dir),
+            &EntityFiles::collect(&store).unwrap(),
+        )
+        .unwrap();
 
         assert_eq!(files.len(), 1);
         assert!(files[0].relative_path.starts_with("links/"));
@@ -198,7 +234,12 @@ mod tests {
         let snapshot_dir = medulla_dir.join("snapshot");
         super::super::utils::ensure_snapshot_dirs(&snapshot_dir).unwrap();
 
-        generate(&store, &snapshot_dir).unwrap();
+        generate(
+            &store,
+            &mut SnapshotWriter::new(&snapshot_dir),
+            &EntityFiles::collect(&store).unwrap(),
+        )
+        .unwrap();
 
         let file_path = snapshot_dir.join("links/rust-homepage.md");
         let content = std::fs::read_to_string(&file_path).unwrap();
@@ -220,7 +261,12 @@ mod tests {
         let snapshot_dir = medulla_dir.join("snapshot");
         super::super::utils::ensure_snapshot_dirs(&snapshot_dir).unwrap();
 
-        let files = generate(&store, &snapshot_dir).unwrap();
+        let files = generate(
+            &store,
+            &mut SnapshotWriter::new(&snapshot_dir),
+            &EntityFiles::collect(&store).unwrap(),
+        )
+        .unwrap();
 
         assert!(files.is_empty());
     }
@@ -0,0 +1,166 @@
+// src/snapshot/milestone.rs
+//! Milestone snapshot generation
+
+use std::collections::HashSet;
+
+use serde::Serialize;
+use uuid::Uuid;
+
+use crate::entity::Milestone;
+use crate::graph::MilestoneProgress;
+use crate::storage::LoroStore;
+use crate::Result;
+
+use super::backlinks::EntityFiles;
+use super::utils::{format_date, slugify, SnapshotWriter};
+use super::{yaml_frontmatter, GeneratedFile};
+
+#[derive(Serialize)]
+struct MilestoneFrontmatter {
+    id: String,
+    sequence: u32,
+    title: String,
+    status: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    target_date: Option<String>,
+    tasks_done: usize,
+    tasks_total: usize,
+    percent_complete: u32,
+    created: String,
+    updated: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    created_by: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    updated_by: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    tags: Vec<String>,
+}
+
+impl MilestoneFrontmatter {
+    fn from_progress(progress: &MilestoneProgress) -> Self {
+        let milestone = &progress.milestone;
+        let (tasks_done, tasks_total) = progress.counts();
+        Self {
+            id: milestone.base.id.to_string(),
+            sequence: milestone.base.sequence_number,
+            title: milestone.base.title.clone(),
+            status: milestone.status.to_string(),
+            target_date: milestone.target_date.map(|d| d.to_string()),
+            tasks_done,
+            tasks_total,
+            percent_complete: progress.percent(),
+            created: format_date(&milestone.base.created_at),
+            updated: format_date(&milestone.base.updated_at),
+            created_by: milestone.base.created_by.clone(),
+            updated_by: milestone.base.updated_by.clone(),
+            tags: milestone.base.tags.clone(),
+        }
+    }
+}
+
+/// Snapshot file of each milestone, relative to the snapshot directory
+pub(super) fn file_paths(milestones: &[Milestone]) -> Vec<(Uuid, String)> {
+    let mut sorted: Vec<&Milestone> = milestones.iter().collect();
+    sorted.sort_by_key(|m| m.base.sequence_number);
+
+    let mut used_slugs = HashSet::new();
+    sorted
+        .into_iter()
+        .map(|m| {
+            let slug = slugify(&m.base.title);
+            let filename = if used_slugs.insert(slug.clone()) {
+                format!("{}.md", slug)
+            } else {
+                format!("{}-{}.md", slug, m.base.sequence_number)
+            };
+            (m.base.id, format!("milestones/{}", filename))
+        })
+        .collect()
+}
+
+/// Render a milestone, with its progress, as snapshot markdown
+pub fn render(progress: &MilestoneProgress) -> Result<String> {
+    let frontmatter = MilestoneFrontmatter::from_progress(progress);
+    let yaml = yaml_frontmatter(&frontmatter)?;
+
+    let body = progress.milestone.base.content.as_deref().unwrap_or("");
+    Ok(format!("{}\n{}", yaml, body))
+}
+
+/// Generate milestone snapshot files
+pub fn generate(
+    store: &LoroStore,
+    writer: &mut SnapshotWriter,
+    files: &EntityFiles,
+) -> Result<Vec<GeneratedFile>> {
+    let mut generated = Vec::new();
+
+    for progress in &MilestoneProgress::all(store, true)? {
+        let id = progress.milestone.base.id;
+        let Some(relative_path) = files.path(&id) else {
+            continue;
+        };
+        let mut content = files.render_page("milestone", &progress.milestone, render(progress)?)?;
+        files.append_sections(store, &id, &mut content)?;
+        writer.write(relative_path, &content)?;
+
+        generated.push(GeneratedFile {
+            relative_path: relative_path.to_string(),
+            entity_count: 1,
+        });
+    }
+
+    Ok(generated)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::entity::{Relation, RelationType, Task, TaskStatus};
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_generate_milestone_files() {
+        let tmp = TempDir::new().unwrap();
+        let medulla_dir = tmp.path().join(".medulla");
+        std::fs::create_dir_all(&medulla_dir).unwrap();
+
+        let store = crate::storage::LoroStore::init(&medulla_dir).unwrap();
+        let mut milestone = Milestone::new("Public beta".to_string(), 1);
+        milestone.target_date = chrono::NaiveDate::from_ymd_opt(2026, 6, 30);
+        milestone.base.content = Some("Invite-only beta for design partners".to_string());
+        store.add_milestone(&milestone).unwrap();
+        for (seq, status) in [(2, TaskStatus::Done), (3, TaskStatus::Todo)] {
+            let mut task = Task::new(format!("Task {}", seq), seq);
+            task.status = status;
+            store.add_task(&task).unwrap();
+            store
+                .add_relation(&Relation::new(
+                    task.base.id,
+                    "task".to_string(),
+                    milestone.base.id,
+                    "milestone".to_string(),
+                    RelationType::BelongsTo,
+                ))
+                .unwrap();
+        }
+
+        let snapshot_dir = medulla_dir.join("snapshot");
+        super::super::utils::ensure_snapshot_dirs(&snapshot_dir).unwrap();
+
+        let files = generate(
+            &store,
+            &mut SnapshotWriter::new(&snapshot_dir),
+            &EntityFiles::collect(&store).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(files.len(), 1);
+
+        let content =
+            std::fs::read_to_string(snapshot_dir.join("milestones/public-beta.md")).unwrap();
+        assert!(content.contains("target_date: 2026-06-30"));
+        assert!(content.contains("tasks_total: 2"));
+        assert!(content.contains("percent_complete: 50"));
+        assert!(content.contains("Invite-only beta"));
+    }
+}
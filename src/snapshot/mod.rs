@@ -2,25 +2,58 @@
 //! Snapshot generation module
 //!
 //! Generates human-readable markdown snapshots of all entities.
-//! These snapshots are derived views meant for browsing on GitHub.
+//! These snapshots are derived views meant for browsing on GitHub. The
+//! same entities can also be rendered as a static HTML site.
 
+mod backlinks;
 mod component;
+mod dashboard;
 mod decision;
+mod diff;
+mod digest;
+mod history;
+mod html;
 mod link;
+mod milestone;
 mod note;
+mod outline;
 mod prompt;
+mod question;
 mod readme;
+mod related;
 mod task;
+mod templates;
 pub mod utils;
 
+use std::collections::BTreeMap;
 use std::path::Path;
 
-use chrono::Utc;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
 
 use crate::storage::LoroStore;
 use crate::Result;
 
-pub use self::utils::{format_date, format_timestamp, short_uuid, slugify};
+use self::backlinks::EntityFiles;
+pub use self::dashboard::{
+    generate_dashboard, Dashboard, DashboardEntry, DashboardHealth, OverdueCounts, TaskQueue,
+    DASHBOARD_LIST_LIMIT,
+};
+pub use self::diff::{diff_snapshot_dir, diff_stores, ChangeKind, EntityChange, SnapshotDiff};
+pub use self::digest::{generate_digest, parse_period, Digest, DigestActivity, DigestEntry};
+pub use self::history::{
+    collection_snapshot_dir, current_snapshot_dir, read_snapshot_history, record_snapshot_run,
+    tagged_snapshot_dir, validate_snapshot_tag, SnapshotRun, DEFAULT_SITE_DIR,
+    DEFAULT_SNAPSHOT_DIR, SNAPSHOT_COLLECTIONS_DIR, SNAPSHOT_HISTORY_FILE, SNAPSHOT_VERSIONS_DIR,
+};
+pub use self::html::{escape_html, generate_html_site, markdown_to_html};
+pub use self::outline::{outline, OutlineEntry};
+pub use self::related::{RelatedSuggestions, DEFAULT_RELATED_LIMIT, DEFAULT_RELATED_MIN_SCORE};
+pub use self::templates::{SnapshotTemplates, SNAPSHOT_TEMPLATES_DIR};
+pub use self::utils::{
+    format_date, format_timestamp, permalink, short_uuid, slugify, SnapshotWriter,
+};
 
 /// Statistics about generated snapshot
 #[derive(Debug, Default)]
@@ -33,43 +66,131 @@ pub struct SnapshotStats {
     pub prompts: usize,
     pub components: usize,
     pub links: usize,
+    pub questions: usize,
+    pub milestones: usize,
     pub files_generated: Vec<String>,
+    /// Generated files that were new or changed and so actually written
+    pub files_written: Vec<String>,
+    /// Files deleted because their entity is gone (or was renamed)
+    pub files_removed: Vec<String>,
 }
 
 impl SnapshotStats {
     /// Total number of entities
     pub fn total_entities(&self) -> usize {
-        self.decisions + self.tasks_total + self.notes + self.prompts + self.components + self.links
+        self.decisions
+            + self.tasks_total
+            + self.notes
+            + self.prompts
+            + self.components
+            + self.links
+            + self.questions
+            + self.milestones
     }
 }
 
+/// How entities are laid out in snapshot files
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SnapshotLayout {
+    /// Tasks grouped into active.md and completed.md, other entities in
+    /// files named by slug
+    #[default]
+    Grouped,
+    /// One `{sequence:03}-{slug}.md` file per entity, tasks included, so
+    /// every entity has a stable permalink
+    PerEntity,
+}
+
+/// Name of the metadata file written alongside a generated snapshot
+pub const SNAPSHOT_META_FILE: &str = ".snapshot-meta.json";
+
+/// Store state recorded when a snapshot was generated
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SnapshotMeta {
+    /// Store version hash at generation time
+    pub store_version: String,
+    /// When generation started; entities updated after this are pending
+    pub generated_at: DateTime<Utc>,
+    /// Content hash of every generated file, by path relative to the
+    /// snapshot directory, so the next generation can skip unchanged files
+    #[serde(default)]
+    pub file_hashes: BTreeMap<String, String>,
+    /// Layout the snapshot was generated with, reused by later generations
+    #[serde(default)]
+    pub layout: SnapshotLayout,
+}
+
+/// Read the metadata of the last generated snapshot, if any
+pub fn read_snapshot_meta(snapshot_dir: &Path) -> Result<Option<SnapshotMeta>> {
+    let path = snapshot_dir.join(SNAPSHOT_META_FILE);
+    if !path.exists() {
+        return Ok(None);
+    }
+
+    let text = std::fs::read_to_string(&path)?;
+    Ok(serde_json::from_str(&text).ok())
+}
+
 /// Result of generating a single snapshot file
 pub struct GeneratedFile {
     pub relative_path: String,
     pub entity_count: usize,
 }
 
-/// Generate markdown snapshots for all entities
-///
-/// This will:
-/// 1. Clear the existing snapshot directory
-/// 2. Create fresh snapshots for all entity types
-/// 3. Generate an index README.md
+/// Generate markdown snapshots for all entities, in the layout of the last
+/// generation (grouped if there was none)
 pub fn generate_snapshot(store: &LoroStore, snapshot_dir: &Path) -> Result<SnapshotStats> {
+    let layout = read_snapshot_meta(snapshot_dir)?
+        .map(|meta| meta.layout)
+        .unwrap_or_default();
+    generate_snapshot_with_layout(store, snapshot_dir, layout)
+}
+
+/// Generate markdown snapshots for all entities in the given layout
+///
+/// Generation is incremental:
+/// 1. Render every entity type and the index README.md
+/// 2. Write only files whose content hash differs from the last generation
+/// 3. Delete files no longer generated (removed or renamed entities, or
+///    files of a previous layout)
+pub fn generate_snapshot_with_layout(
+    store: &LoroStore,
+    snapshot_dir: &Path,
+    layout: SnapshotLayout,
+) -> Result<SnapshotStats> {
+    generate_snapshot_with_related(store, snapshot_dir, layout, RelatedSuggestions::default())
+}
+
+/// Generate markdown snapshots in the given layout, listing suggested
+/// related entities in a "Related" section of each entity's page
+pub fn generate_snapshot_with_related(
+    store: &LoroStore,
+    snapshot_dir: &Path,
+    layout: SnapshotLayout,
+    related: RelatedSuggestions,
+) -> Result<SnapshotStats> {
     let mut stats = SnapshotStats::default();
+    let previous = read_snapshot_meta(snapshot_dir)?
+        .map(|meta| meta.file_hashes)
+        .unwrap_or_default();
+    let mut writer = SnapshotWriter::new(snapshot_dir).with_previous(previous);
+    let generated_at = Utc::now();
 
-    // Clear and recreate directory structure
-    utils::clear_snapshot_dir(snapshot_dir)?;
     utils::ensure_snapshot_dirs(snapshot_dir)?;
+    let files = EntityFiles::collect_with(store, layout)?
+        .with_attachments_dir(snapshot_dir, store.medulla_dir())
+        .with_related(related)
+        .with_templates(SnapshotTemplates::load(store.medulla_dir())?);
 
     // Generate snapshots for each entity type
-    let decision_files = decision::generate(store, snapshot_dir)?;
+    let decision_files = decision::generate(store, &mut writer, &files)?;
     stats.decisions = decision_files.iter().map(|f| f.entity_count).sum();
     stats
         .files_generated
         .extend(decision_files.into_iter().map(|f| f.relative_path));
 
-    let task_files = task::generate(store, snapshot_dir)?;
+    let task_files = task::generate(store, &mut writer)?;
     for file in &task_files {
         if file.relative_path.contains("active") {
             stats.tasks_active = file.entity_count;
@@ -81,38 +202,124 @@ pub fn generate_snapshot(store: &LoroStore, snapshot_dir: &Path) -> Result<Snaps
     stats
         .files_generated
         .extend(task_files.into_iter().map(|f| f.relative_path));
+    if layout == SnapshotLayout::PerEntity {
+        let task_files = task::generate_files(store, &mut writer, &files)?;
+        stats
+            .files_generated
+            .extend(task_files.into_iter().map(|f| f.relative_path));
+    }
 
-    let note_files = note::generate(store, snapshot_dir)?;
+    let note_files = note::generate(store, &mut writer, &files)?;
     stats.notes = note_files.iter().map(|f| f.entity_count).sum();
     stats
         .files_generated
         .extend(note_files.into_iter().map(|f| f.relative_path));
 
-    let prompt_files = prompt::generate(store, snapshot_dir)?;
+    let prompt_files = prompt::generate(store, &mut writer, &files)?;
     stats.prompts = prompt_files.iter().map(|f| f.entity_count).sum();
     stats
         .files_generated
         .extend(prompt_files.into_iter().map(|f| f.relative_path));
 
-    let component_files = component::generate(store, snapshot_dir)?;
+    let component_files = component::generate(store, &mut writer, &files)?;
     stats.components = component_files.iter().map(|f| f.entity_count).sum();
     stats
         .files_generated
         .extend(component_files.into_iter().map(|f| f.relative_path));
 
-    let link_files = link::generate(store, snapshot_dir)?;
+    let link_files = link::generate(store, &mut writer, &files)?;
     stats.links = link_files.iter().map(|f| f.entity_count).sum();
     stats
         .files_generated
         .extend(link_files.into_iter().map(|f| f.relative_path));
 
+    let question_files = question::generate(store, &mut writer, &files)?;
+    stats.questions = question_files.iter().map(|f| f.entity_count).sum();
+    stats
+        .files_generated
+        .extend(question_files.into_iter().map(|f| f.relative_path));
+
+    let milestone_files = milestone::generate(store, &mut writer, &files)?;
+    stats.milestones = milestone_files.iter().map(|f| f.entity_count).sum();
+    stats
+        .files_generated
+        .extend(milestone_files.into_iter().map(|f| f.relative_path));
+
     // Generate README index (must be last to have all stats)
-    readme::generate(store, snapshot_dir, &stats)?;
+    readme::generate(store, &mut writer, &files, &stats)?;
     stats.files_generated.push("README.md".to_string());
 
+    stats.files_removed = utils::remove_stale_files(snapshot_dir, writer.hashes())?;
+    stats.files_written = writer.written().to_vec();
+
+    // Record what was snapshotted so pending changes can be detected later
+    let meta = SnapshotMeta {
+        store_version: store.version_hash(),
+        generated_at,
+        file_hashes: writer.hashes().clone(),
+        layout,
+    };
+    utils::write_snapshot_file(
+        &snapshot_dir.join(SNAPSHOT_META_FILE),
+        &serde_json::to_string_pretty(&meta)?,
+    )?;
+
     Ok(stats)
 }
 
+/// Render a single entity as it would appear in a snapshot, without writing files.
+///
+/// Tasks have no file of their own, so they render as their list line;
+/// other entities go through the user's snapshot templates and include
+/// their "Attachments" and "Referenced by" sections.
+/// Returns `None` if no entity of the given type has this ID.
+pub fn render_entity(store: &LoroStore, entity_type: &str, id: &Uuid) -> Result<Option<String>> {
+    if entity_type == "task" {
+        return Ok(store.get_task(id)?.map(|t| task::render(&t)));
+    }
+
+    let files =
+        EntityFiles::collect(store)?.with_templates(SnapshotTemplates::load(store.medulla_dir())?);
+    let mut rendered = match entity_type {
+        "decision" => store
+            .get_decision(id)?
+            .map(|d| files.render_page("decision", &d, decision::render(&d)?))
+            .transpose()?,
+        "note" => store
+            .get_note(id)?
+            .map(|n| files.render_page("note", &n, note::render(&n)?))
+            .transpose()?,
+        "prompt" => store
+            .get_prompt(id)?
+            .map(|p| files.render_page("prompt", &p, prompt::render(&p)?))
+            .transpose()?,
+        "component" => store
+            .get_component(id)?
+            .map(|c| files.render_page("component", &c, component::render(&c)?))
+            .transpose()?,
+        "link" => store
+            .get_link(id)?
+            .map(|l| files.render_page("link", &l, link::render(&l)?))
+            .transpose()?,
+        "question" => store
+            .get_question(id)?
+            .map(|q| files.render_page("question", &q, question::render(&q)?))
+            .transpose()?,
+        "milestone" => match store.get_milestone(id)? {
+            Some(m) => {
+                let progress = crate::graph::MilestoneProgress::build(store, *id)?;
+                Some(files.render_page("milestone", &m, milestone::render(&progress)?)?)
+            }
+            None => None,
+        },
+        _ => None,
+    };
+    if let Some(content) = rendered.as_mut() {
+        files.append_sections(store, id, content)?;
+    }
+    Ok(rendered)
+}
+
 /// Generate YAML frontmatter block
 pub fn yaml_frontmatter<T: serde::Serialize>(data: &T) -> Result<String> {
     let yaml = serde_yaml::to_string(data).map_err(|e| {
@@ -170,7 +377,7 @@ mod tests {
             prompts: 2,
             components: 1,
             links: 4,
-            files_generated: vec![],
+            ..Default::default()
         };
 
         // Total should be decisions + tasks_total + notes + prompts + components + links
@@ -207,6 +414,20 @@ mod tests {
         assert!(snapshot_dir.join("README.md").exists());
     }
 
+    #[test]
+    fn test_generate_snapshot_records_meta() {
+        let tmp = TempDir::new().unwrap();
+        let store = LoroStore::init(tmp.path()).unwrap();
+        let snapshot_dir = tmp.path().join("snapshot");
+
+        assert!(read_snapshot_meta(&snapshot_dir).unwrap().is_none());
+
+        generate_snapshot(&store, &snapshot_dir).unwrap();
+
+        let meta = read_snapshot_meta(&snapshot_dir).unwrap().unwrap();
+        assert_eq!(meta.store_version, store.version_hash());
+    }
+
     #[test]
     fn test_generate_snapshot_empty_store() {
         let tmp = TempDir::new().unwrap();
@@ -248,4 +469,126 @@ mod tests {
         // Stale file should be gone
         assert!(!snapshot_dir.join("stale.md").exists());
     }
+
+    #[test]
+    fn test_generate_snapshot_keeps_tagged_versions() {
+        let tmp = TempDir::new().unwrap();
+        let store = LoroStore::init(tmp.path()).unwrap();
+        let snapshot_dir = tmp.path().join("snapshot");
+
+        let tagged = tagged_snapshot_dir(&snapshot_dir, "v1.0");
+        generate_snapshot(&store, &tagged).unwrap();
+        generate_snapshot(&store, &snapshot_dir).unwrap();
+
+        assert!(tagged.join("README.md").exists());
+        assert!(read_snapshot_meta(&tagged).unwrap().is_some());
+    }
+
+    #[test]
+    fn test_generate_snapshot_is_incremental() {
+        let tmp = TempDir::new().unwrap();
+        let store = LoroStore::init(tmp.path()).unwrap();
+        let snapshot_dir = tmp.path().join("snapshot");
+
+        let keep = crate::entity::Note::new("Keep me".to_string(), 1);
+        let drop = crate::entity::Note::new("Drop me".to_string(), 2);
+        store.add_note(&keep).unwrap();
+        store.add_note(&drop).unwrap();
+
+        let first = generate_snapshot(&store, &snapshot_dir).unwrap();
+        assert_eq!(first.files_written.len(), first.files_generated.len());
+
+        // Nothing changed: nothing is rewritten
+        let second = generate_snapshot(&store, &snapshot_dir).unwrap();
+        assert!(second.files_written.is_empty());
+        assert!(second.files_removed.is_empty());
+
+        // A removed entity's file is deleted; untouched files stay as they are
+        store.delete_note(&drop.base.id).unwrap();
+        let third = generate_snapshot(&store, &snapshot_dir).unwrap();
+        assert_eq!(third.files_removed, vec!["notes/drop-me.md".to_string()]);
+        assert!(!third
+            .files_written
+            .contains(&"notes/keep-me.md".to_string()));
+        assert!(third.files_written.contains(&"README.md".to_string()));
+        assert!(snapshot_dir.join("notes/keep-me.md").exists());
+        assert!(!snapshot_dir.join("notes/drop-me.md").exists());
+
+        // A file deleted by hand is restored even though its hash is recorded
+        std::fs::remove_file(snapshot_dir.join("notes/keep-me.md")).unwrap();
+        let fourth = generate_snapshot(&store, &snapshot_dir).unwrap();
+        assert_eq!(fourth.files_written, vec!["notes/keep-me.md".to_string()]);
+    }
+
+    #[test]
+    fn test_generate_snapshot_uses_templates() {
+        let tmp = TempDir::new().unwrap();
+        let store = LoroStore::init(tmp.path()).unwrap();
+        let snapshot_dir = tmp.path().join("snapshot");
+
+        let decision = crate::entity::Decision::new("Use Postgres".to_string(), 1);
+        store.add_decision(&decision).unwrap();
+        store
+            .add_note(&crate::entity::Note::new("Plain".to_string(), 2))
+            .unwrap();
+
+        let templates = store.medulla_dir().join(SNAPSHOT_TEMPLATES_DIR);
+        std::fs::create_dir(&templates).unwrap();
+        std::fs::write(
+            templates.join("decision.md"),
+            "# ADR {{ entity.sequence_number }}: {{ entity.title }}\n",
+        )
+        .unwrap();
+
+        generate_snapshot(&store, &snapshot_dir).unwrap();
+        let page =
+            std::fs::read_to_string(snapshot_dir.join("decisions/001-use-postgres.md")).unwrap();
+        assert!(page.starts_with("# ADR 1: Use Postgres\n"));
+        // Types without a template keep the built-in layout
+        let note = std::fs::read_to_string(snapshot_dir.join("notes/plain.md")).unwrap();
+        assert!(note.starts_with("---\n"));
+
+        let rendered = render_entity(&store, "decision", &decision.base.id)
+            .unwrap()
+            .unwrap();
+        assert!(rendered.starts_with("# ADR 1: Use Postgres\n"));
+    }
+
+    #[test]
+    fn test_generate_snapshot_per_entity_layout_sticks() {
+        let tmp = TempDir::new().unwrap();
+        let store = LoroStore::init(tmp.path()).unwrap();
+        let snapshot_dir = tmp.path().join("snapshot");
+
+        let mut task = crate::entity::Task::new("Ship it".to_string(), 1);
+        task.base.content = Some("Tag the release.".to_string());
+        store.add_task(&task).unwrap();
+        store
+            .add_note(&crate::entity::Note::new("Keep me".to_string(), 2))
+            .unwrap();
+
+        generate_snapshot(&store, &snapshot_dir).unwrap();
+        assert!(snapshot_dir.join("notes/keep-me.md").exists());
+
+        let stats = generate_snapshot_with_layout(&store, &snapshot_dir, SnapshotLayout::PerEntity)
+            .unwrap();
+        assert_eq!(stats.tasks_total, 1);
+        assert!(stats
+            .files_removed
+            .contains(&"notes/keep-me.md".to_string()));
+        let task_file = std::fs::read_to_string(snapshot_dir.join("tasks/001-ship-it.md")).unwrap();
+        assert!(task_file.contains("title: Ship it"));
+        assert!(task_file.contains("Tag the release."));
+        assert!(snapshot_dir.join("notes/002-keep-me.md").exists());
+        let readme = std::fs::read_to_string(snapshot_dir.join("README.md")).unwrap();
+        assert!(readme.contains("(notes/002-keep-me.md)"));
+
+        // Later generations keep the layout
+        let again = generate_snapshot(&store, &snapshot_dir).unwrap();
+        assert!(again.files_removed.is_empty());
+        assert_eq!(
+            read_snapshot_meta(&snapshot_dir).unwrap().unwrap().layout,
+            SnapshotLayout::PerEntity
+        );
+    }
 }
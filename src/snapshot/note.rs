@@ -2,15 +2,16 @@
 //! Note snapshot generation
 
 use std::collections::HashSet;
-use std::path::Path;
 
 use serde::Serialize;
+use uuid::Uuid;
 
 use crate::entity::Note;
 use crate::storage::LoroStore;
 use crate::Result;
 
-use super::utils::{format_date, slugify, write_snapshot_file};
+use super::backlinks::EntityFiles;
+use super::utils::{format_date, slugify, SnapshotWriter};
 use super::{yaml_frontmatter, GeneratedFile};
 
 #[derive(Serialize)]
@@ -25,6 +26,8 @@ struct NoteFrontmatter {
     updated: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     created_by: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    updated_by: Option<String>,
     #[serde(skip_serializing_if = "Vec::is_empty")]
     tags: Vec<String>,
 }
@@ -39,6 +42,7 @@ impl NoteFrontmatter {
             created: format_date(&note.base.created_at),
             updated: format_date(&note.base.updated_at),
             created_by: note.base.created_by.clone(),
+            updated_by: note.base.updated_by.clone(),
             tags: note.base.tags.clone(),
         }
     }
@@ -58,8 +62,38 @@ fn unique_filename(base_slug: &str, sequence: u32, used_slugs: &mut HashSet<Stri
     }
 }
 
+/// Snapshot file of each note, relative to the snapshot directory
+pub(super) fn file_paths(notes: &[Note]) -> Vec<(Uuid, String)> {
+    let mut sorted: Vec<&Note> = notes.iter().collect();
+    sorted.sort_by_key(|n| n.base.sequence_number);
+
+    let mut used_slugs = HashSet::new();
+    sorted
+        .into_iter()
+        .map(|n| {
+            let slug = slugify(&n.base.title);
+            let filename = unique_filename(&slug, n.base.sequence_number, &mut used_slugs);
+            (n.base.id, format!("notes/{}", filename))
+        })
+        .collect()
+}
+
+/// Render a note as snapshot markdown
+pub fn render(note: &Note) -> Result<String> {
+    let frontmatter = NoteFrontmatter::from_note(note);
+    let yaml = yaml_frontmatter(&frontmatter)?;
+
+    // Content is just the body
+    let body = note.base.content.as_deref().unwrap_or("");
+    Ok(format!("{}\n{}", yaml, body))
+}
+
 /// Generate note snapshot files
-pub fn generate(store: &LoroStore, snapshot_dir: &Path) -> Result<Vec<GeneratedFile>> {
+pub fn generate(
+    store: &LoroStore,
+    writer: &mut SnapshotWriter,
+    files: &EntityFiles,
+) -> Result<Vec<GeneratedFile>> {
     let notes = store.list_notes()?;
     let mut generated = Vec::new();
 
@@ -71,25 +105,16 @@ pub fn generate(store: &LoroStore, snapshot_dir: &Path) -> Result<Vec<GeneratedF
     let mut sorted_notes = notes;
     sorted_notes.sort_by_key(|n| n.base.sequence_number);
 
-    let notes_dir = snapshot_dir.join("notes");
-    let mut used_slugs = HashSet::new();
-
     for note in &sorted_notes {
-        let frontmatter = NoteFrontmatter::from_note(note);
-        let yaml = yaml_frontmatter(&frontmatter)?;
-
-        // Content is just the body
-        let body = note.base.content.as_deref().unwrap_or("");
-        let content = format!("{}\n{}", yaml, body);
-
-        let slug = slugify(&note.base.title);
-        let filename = unique_filename(&slug, note.base.sequence_number, &mut used_slugs);
-        let file_path = notes_dir.join(&filename);
-
-        write_snapshot_file(&file_path, &content)?;
+        let Some(relative_path) = files.path(&note.base.id) else {
+            continue;
+        };
+        let mut content = files.render_page("note", &note, render(note)?)?;
+        files.append_sections(store, &note.base.id, &mut content)?;
+        writer.write(relative_path, &content)?;
 
         generated.push(GeneratedFile {
-            relative_path: format!("notes/{}", filename),
+            relative_path: relative_path.to_string(),
             entity_count: 1,
         });
     }
@@ -168,7 +193,12 @@ mod tests {
         let snapshot_dir = medulla_dir.join("snapshot");
         super::super::utils::ensure_snapshot_dirs(&snapshot_dir).unwrap();
 
-        let files = generate(&store, &snapshot_dir).unwrap();
+        let files = generate(
+            &store,
+            &mut SnapshotWriter::new(&snapshot_dir),
+            &EntityFiles::collect(&store).unwrap(),
+        )
+        .unwrap();
 
         assert_eq!(files.len(), 1);
         assert!(files[0].relative_path.starts_with("notes/"));
@@ -189,7 +219,12 @@ mod tests {
         let snapshot_dir = medulla_dir.join("snapshot");
         super::super::utils::ensure_snapshot_dirs(&snapshot_dir).unwrap();
 
-        generate(&store, &snapshot_dir).unwrap();
+        generate(
+            &store,
+            &mut SnapshotWriter::new(&snapshot_dir),
+            &EntityFiles::collect(&store).unwrap(),
+        )
+        .unwrap();
 
         let file_path = snapshot_dir.join("notes/my-note.md");
         let content = std::fs::read_to_string(&file_path).unwrap();
@@ -209,7 +244,12 @@ mod tests {
         let snapshot_dir = medulla_dir.join("snapshot");
         super::super::utils::ensure_snapshot_dirs(&snapshot_dir).unwrap();
 
-        let files = generate(&store, &snapshot_dir).unwrap();
+        let files = generate(
+            &store,
+            &mut SnapshotWriter::new(&snapshot_dir),
+            &EntityFiles::collect(&store).unwrap(),
+        )
+        .unwrap();
 
         assert!(files.is_empty());
     }
@@ -230,7 +270,12 @@ mod tests {
         let snapshot_dir = medulla_dir.join("snapshot");
         super::super::utils::ensure_snapshot_dirs(&snapshot_dir).unwrap();
 
-        let files = generate(&store, &snapshot_dir).unwrap();
+        let files = generate(
+            &store,
+            &mut SnapshotWriter::new(&snapshot_dir),
+            &EntityFiles::collect(&store).unwrap(),
+        )
+        .unwrap();
 
         assert_eq!(files.len(), 2);
         // One should be "same-title.md", the other "same-title-2.md"
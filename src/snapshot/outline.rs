@@ -0,0 +1,86 @@
+// src/snapshot/outline.rs
+//! Heading outline extraction from markdown content
+
+use std::collections::HashMap;
+
+use serde::Serialize;
+
+use super::utils::slugify;
+use crate::entity::markdown_headings;
+
+/// A heading in a markdown outline, with its nested subheadings
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct OutlineEntry {
+    pub level: u8,
+    pub text: String,
+    /// Anchor slug, de-duplicated with a numeric suffix like GitHub does
+    pub slug: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub children: Vec<OutlineEntry>,
+}
+
+/// Insert an entry under the last sibling with a lower level, or as a new sibling
+fn insert(siblings: &mut Vec<OutlineEntry>, entry: OutlineEntry) {
+    match siblings.last_mut() {
+        Some(last) if last.level < entry.level => insert(&mut last.children, entry),
+        _ => siblings.push(entry),
+    }
+}
+
+/// Extract a nested heading outline from markdown content.
+///
+/// Headings inside fenced code blocks are ignored.
+pub fn outline(content: &str) -> Vec<OutlineEntry> {
+    let mut roots = Vec::new();
+    let mut slug_counts: HashMap<String, usize> = HashMap::new();
+
+    for (_, level, text) in markdown_headings(content) {
+        let base = slugify(&text);
+        let count = slug_counts.entry(base.clone()).or_insert(0);
+        let slug = if *count == 0 {
+            base
+        } else {
+            format!("{}-{}", base, count)
+        };
+        *count += 1;
+
+        insert(
+            &mut roots,
+            OutlineEntry {
+                level,
+                text,
+                slug,
+                children: Vec::new(),
+            },
+        );
+    }
+
+    roots
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_outline_nesting() {
+        let content = "# Intro\n\n## Goals\n\n### Short term\n\n## Risks\n\n# Appendix\n";
+        let entries = outline(content);
+
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].text, "Intro");
+        assert_eq!(entries[0].children.len(), 2);
+        assert_eq!(entries[0].children[0].children[0].text, "Short term");
+        assert_eq!(entries[1].slug, "appendix");
+    }
+
+    #[test]
+    fn test_outline_skips_code_blocks_and_dedupes_slugs() {
+        let content = "## Setup\n```sh\n# not a heading\n```\n## Setup\n";
+        let entries = outline(content);
+
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].slug, "setup");
+        assert_eq!(entries[1].slug, "setup-1");
+    }
+}
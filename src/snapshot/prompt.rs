@@ -2,15 +2,16 @@
 //! Prompt snapshot generation
 
 use std::collections::HashSet;
-use std::path::Path;
 
 use serde::Serialize;
+use uuid::Uuid;
 
 use crate::entity::Prompt;
 use crate::storage::LoroStore;
 use crate::Result;
 
-use super::utils::{format_date, slugify, write_snapshot_file};
+use super::backlinks::EntityFiles;
+use super::utils::{format_date, slugify, SnapshotWriter};
 use super::{yaml_frontmatter, GeneratedFile};
 
 #[derive(Serialize)]
@@ -22,6 +23,8 @@ struct PromptFrontmatter {
     updated: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     created_by: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    updated_by: Option<String>,
     #[serde(skip_serializing_if = "Vec::is_empty")]
     tags: Vec<String>,
     #[serde(skip_serializing_if = "Vec::is_empty")]
@@ -37,6 +40,7 @@ impl PromptFrontmatter {
             created: format_date(&prompt.base.created_at),
             updated: format_date(&prompt.base.updated_at),
             created_by: prompt.base.created_by.clone(),
+            updated_by: prompt.base.updated_by.clone(),
             tags: prompt.base.tags.clone(),
             variables: prompt.variables.clone(),
         }
@@ -94,8 +98,37 @@ fn unique_filename(base_slug: &str, sequence: u32, used_slugs: &mut HashSet<Stri
     }
 }
 
+/// Snapshot file of each prompt, relative to the snapshot directory
+pub(super) fn file_paths(prompts: &[Prompt]) -> Vec<(Uuid, String)> {
+    let mut sorted: Vec<&Prompt> = prompts.iter().collect();
+    sorted.sort_by_key(|p| p.base.sequence_number);
+
+    let mut used_slugs = HashSet::new();
+    sorted
+        .into_iter()
+        .map(|p| {
+            let slug = slugify(&p.base.title);
+            let filename = unique_filename(&slug, p.base.sequence_number, &mut used_slugs);
+            (p.base.id, format!("prompts/{}", filename))
+        })
+        .collect()
+}
+
+/// Render a prompt as snapshot markdown
+pub fn render(prompt: &Prompt) -> Result<String> {
+    let frontmatter = PromptFrontmatter::from_prompt(prompt);
+    let yaml = yaml_frontmatter(&frontmatter)?;
+    let body = generate_body(prompt);
+
+    Ok(format!("{}{}", yaml, body))
+}
+
 /// Generate prompt snapshot files
-pub fn generate(store: &LoroStore, snapshot_dir: &Path) -> Result<Vec<GeneratedFile>> {
+pub fn generate(
+    store: &LoroStore,
+    writer: &mut SnapshotWriter,
+    files: &EntityFiles,
+) -> Result<Vec<GeneratedFile>> {
     let prompts = store.list_prompts()?;
     let mut generated = Vec::new();
 
@@ -107,24 +140,16 @@ pub fn generate(store: &LoroStore, snapshot_dir: &Path) -> Result<Vec<GeneratedF
     let mut sorted_prompts = prompts;
     sorted_prompts.sort_by_key(|p| p.base.sequence_number);
 
-    let prompts_dir = snapshot_dir.join("prompts");
-    let mut used_slugs = HashSet::new();
-
     for prompt in &sorted_prompts {
-        let frontmatter = PromptFrontmatter::from_prompt(prompt);
-        let yaml = yaml_frontmatter(&frontmatter)?;
-        let body = generate_body(prompt);
-
-        let content = format!("{}{}", yaml, body);
-
-        let slug = slugify(&prompt.base.title);
-        let filename = unique_filename(&slug, prompt.base.sequence_number, &mut used_slugs);
-        let file_path = prompts_dir.join(&filename);
-
-        write_snapshot_file(&file_path, &content)?;
+        let Some(relative_path) = files.path(&prompt.base.id) else {
+            continue;
+        };
+        let mut content = files.render_page("prompt", &prompt, render(prompt)?)?;
+        files.append_sections(store, &prompt.base.id, &mut content)?;
+        writer.write(relative_path, &content)?;
 
         generated.push(GeneratedFile {
-            relative_path: format!("prompts/{}", filename),
+            relative_path: relative_path.to_string(),
             entity_count: 1,
         });
     }
@@ -218,7 +243,12 @@ mod tests {
         let snapshot_dir = medulla_dir.join("snapshot");
         super::super::utils::ensure_snapshot_dirs(&snapshot_dir).unwrap();
 
-        let files = generate(&store, &snapshot_dir).unwrap();
+        let files = generate(
+            &store,
+            &mut SnapshotWriter::new(&snapshot_dir),
+            &EntityFiles::collect(&store).unwrap(),
+        )
+        .unwrap();
 
         assert_eq!(files.len(), 1);
         assert!(files[0].relative_path.starts_with("prompts/"));
@@ -235,7 +265,12 @@ mod tests {
         let snapshot_dir = medulla_dir.join("snapshot");
         super::super::utils::ensure_snapshot_dirs(&snapshot_dir).unwrap();
 
-        let files = generate(&store, &snapshot_dir).unwrap();
+        let files = generate(
+            &store,
+            &mut SnapshotWriter::new(&snapshot_dir),
+            &EntityFiles::collect(&store).unwrap(),
+        )
+        .unwrap();
 
         assert!(files.is_empty());
     }
@@ -0,0 +1,154 @@
+// src/snapshot/question.rs
+//! Question snapshot generation
+
+use std::collections::HashSet;
+
+use serde::Serialize;
+use uuid::Uuid;
+
+use crate::entity::Question;
+use crate::storage::LoroStore;
+use crate::Result;
+
+use super::backlinks::EntityFiles;
+use super::utils::{format_date, slugify, SnapshotWriter};
+use super::{yaml_frontmatter, GeneratedFile};
+
+#[derive(Serialize)]
+struct QuestionFrontmatter {
+    id: String,
+    sequence: u32,
+    title: String,
+    status: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    answered_by: Option<String>,
+    created: String,
+    updated: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    created_by: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    updated_by: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    tags: Vec<String>,
+}
+
+impl QuestionFrontmatter {
+    fn from_question(question: &Question) -> Self {
+        Self {
+            id: question.base.id.to_string(),
+            sequence: question.base.sequence_number,
+            title: question.base.title.clone(),
+            status: question.status.to_string(),
+            answered_by: question.answered_by.clone(),
+            created: format_date(&question.base.created_at),
+            updated: format_date(&question.base.updated_at),
+            created_by: question.base.created_by.clone(),
+            updated_by: question.base.updated_by.clone(),
+            tags: question.base.tags.clone(),
+        }
+    }
+}
+
+/// Snapshot file of each question, relative to the snapshot directory
+pub(super) fn file_paths(questions: &[Question]) -> Vec<(Uuid, String)> {
+    let mut sorted: Vec<&Question> = questions.iter().collect();
+    sorted.sort_by_key(|q| q.base.sequence_number);
+
+    let mut used_slugs = HashSet::new();
+    sorted
+        .into_iter()
+        .map(|q| {
+            let slug = slugify(&q.base.title);
+            let filename = if used_slugs.insert(slug.clone()) {
+                format!("{}.md", slug)
+            } else {
+                format!("{}-{}.md", slug, q.base.sequence_number)
+            };
+            (q.base.id, format!("questions/{}", filename))
+        })
+        .collect()
+}
+
+/// Render a question as snapshot markdown
+pub fn render(question: &Question) -> Result<String> {
+    let frontmatter = QuestionFrontmatter::from_question(question);
+    let yaml = yaml_frontmatter(&frontmatter)?;
+
+    let body = question.base.content.as_deref().unwrap_or("");
+    Ok(format!("{}\n{}", yaml, body))
+}
+
+/// Generate question snapshot files
+pub fn generate(
+    store: &LoroStore,
+    writer: &mut SnapshotWriter,
+    files: &EntityFiles,
+) -> Result<Vec<GeneratedFile>> {
+    let mut generated = Vec::new();
+
+    for question in &store.list_questions()? {
+        let Some(relative_path) = files.path(&question.base.id) else {
+            continue;
+        };
+        let mut content = files.render_page("question", &question, render(question)?)?;
+        files.append_sections(store, &question.base.id, &mut content)?;
+        writer.write(relative_path, &content)?;
+
+        generated.push(GeneratedFile {
+            relative_path: relative_path.to_string(),
+            entity_count: 1,
+        });
+    }
+
+    Ok(generated)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::entity::QuestionStatus;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_question_frontmatter() {
+        let mut question = Question::new("Which queue?".to_string(), 3);
+        assert!(QuestionFrontmatter::from_question(&question)
+            .answered_by
+            .is_none());
+
+        question.status = QuestionStatus::Answered;
+        question.answered_by = Some("abc1234".to_string());
+        let fm = QuestionFrontmatter::from_question(&question);
+        assert_eq!(fm.status, "answered");
+        assert_eq!(fm.answered_by.as_deref(), Some("abc1234"));
+    }
+
+    #[test]
+    fn test_generate_question_files() {
+        let tmp = TempDir::new().unwrap();
+        let medulla_dir = tmp.path().join(".medulla");
+        std::fs::create_dir_all(&medulla_dir).unwrap();
+
+        let store = crate::storage::LoroStore::init(&medulla_dir).unwrap();
+        let mut question = Question::new("Do we shard by tenant?".to_string(), 1);
+        question.base.content = Some("Depends on the largest tenant".to_string());
+        store.add_question(&question).unwrap();
+
+        let snapshot_dir = medulla_dir.join("snapshot");
+        super::super::utils::ensure_snapshot_dirs(&snapshot_dir).unwrap();
+
+        let files = generate(
+            &store,
+            &mut SnapshotWriter::new(&snapshot_dir),
+            &EntityFiles::collect(&store).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(files.len(), 1);
+
+        let content =
+            std::fs::read_to_string(snapshot_dir.join("questions/do-we-shard-by-tenant.md"))
+                .unwrap();
+        assert!(content.contains("status: open"));
+        assert!(content.contains("Depends on the largest tenant"));
+    }
+}
@@ -1,14 +1,13 @@
 // src/snapshot/readme.rs
 //! README index generation for snapshot
 
-use std::path::Path;
-
-use crate::entity::{Component, Decision, TaskStatus};
+use crate::entity::{Component, Decision, QuestionStatus, TaskStatus};
+use crate::graph::MilestoneProgress;
 use crate::storage::LoroStore;
 use crate::Result;
 
-use super::current_timestamp;
-use super::utils::{format_date, slugify, write_snapshot_file};
+use super::backlinks::EntityFiles;
+use super::utils::{format_date, format_timestamp, SnapshotWriter};
 use super::SnapshotStats;
 
 /// A recent activity entry for display
@@ -20,18 +19,21 @@ struct RecentActivity {
     updated_at: chrono::DateTime<chrono::Utc>,
 }
 
+/// Snapshot file of an entity, as a link from README.md
+fn link_to(files: &EntityFiles, id: &uuid::Uuid) -> String {
+    files.path(id).unwrap_or_default().to_string()
+}
+
 /// Collect recent activity from all entity types
-fn collect_recent_activity(store: &LoroStore) -> Result<Vec<RecentActivity>> {
+fn collect_recent_activity(store: &LoroStore, files: &EntityFiles) -> Result<Vec<RecentActivity>> {
     let mut activities = Vec::new();
 
     // Decisions
     for decision in store.list_decisions()? {
-        let slug = slugify(&decision.base.title);
-        let filename = format!("{:03}-{}.md", decision.base.sequence_number, slug);
         activities.push(RecentActivity {
             entity_type: "Decision".to_string(),
             title: decision.base.title.clone(),
-            link: format!("decisions/{}", filename),
+            link: link_to(files, &decision.base.id),
             status: Some(decision.status.to_string()),
             updated_at: decision.base.updated_at,
         });
@@ -40,10 +42,15 @@ fn collect_recent_activity(store: &LoroStore) -> Result<Vec<RecentActivity>> {
     // Tasks (only show active tasks in recent activity)
     for task in store.list_tasks()? {
         if task.status != TaskStatus::Done {
+            // In the grouped layout the link points into active.md
+            let mut link = link_to(files, &task.base.id);
+            if link.ends_with("active.md") {
+                link.push_str(&format!("#{}", task.base.sequence_number));
+            }
             activities.push(RecentActivity {
                 entity_type: "Task".to_string(),
                 title: task.base.title.clone(),
-                link: format!("tasks/active.md#{}", task.base.sequence_number),
+                link,
                 status: Some(task.status.to_string()),
                 updated_at: task.base.updated_at,
             });
@@ -52,11 +59,10 @@ fn collect_recent_activity(store: &LoroStore) -> Result<Vec<RecentActivity>> {
 
     // Notes
     for note in store.list_notes()? {
-        let slug = slugify(&note.base.title);
         activities.push(RecentActivity {
             entity_type: "Note".to_string(),
             title: note.base.title.clone(),
-            link: format!("notes/{}.md", slug),
+            link: link_to(files, &note.base.id),
             status: note.note_type.clone(),
             updated_at: note.base.updated_at,
         });
@@ -64,11 +70,10 @@ fn collect_recent_activity(store: &LoroStore) -> Result<Vec<RecentActivity>> {
 
     // Prompts
     for prompt in store.list_prompts()? {
-        let slug = slugify(&prompt.base.title);
         activities.push(RecentActivity {
             entity_type: "Prompt".to_string(),
             title: prompt.base.title.clone(),
-            link: format!("prompts/{}.md", slug),
+            link: link_to(files, &prompt.base.id),
             status: None,
             updated_at: prompt.base.updated_at,
         });
@@ -76,11 +81,10 @@ fn collect_recent_activity(store: &LoroStore) -> Result<Vec<RecentActivity>> {
 
     // Components
     for component in store.list_components()? {
-        let slug = slugify(&component.base.title);
         activities.push(RecentActivity {
             entity_type: "Component".to_string(),
             title: component.base.title.clone(),
-            link: format!("components/{}.md", slug),
+            link: link_to(files, &component.base.id),
             status: Some(component.status.to_string()),
             updated_at: component.base.updated_at,
         });
@@ -88,24 +92,76 @@ fn collect_recent_activity(store: &LoroStore) -> Result<Vec<RecentActivity>> {
 
     // Links
     for link in store.list_links()? {
-        let slug = slugify(&link.base.title);
         activities.push(RecentActivity {
             entity_type: "Link".to_string(),
             title: link.base.title.clone(),
-            link: format!("links/{}.md", slug),
+            link: link_to(files, &link.base.id),
             status: link.link_type.clone(),
             updated_at: link.base.updated_at,
         });
     }
 
+    // Questions
+    for question in store.list_questions()? {
+        activities.push(RecentActivity {
+            entity_type: "Question".to_string(),
+            title: question.base.title.clone(),
+            link: link_to(files, &question.base.id),
+            status: Some(question.status.to_string()),
+            updated_at: question.base.updated_at,
+        });
+    }
+
+    // Milestones
+    for milestone in store.list_milestones()? {
+        activities.push(RecentActivity {
+            entity_type: "Milestone".to_string(),
+            title: milestone.base.title.clone(),
+            link: link_to(files, &milestone.base.id),
+            status: Some(milestone.status.to_string()),
+            updated_at: milestone.base.updated_at,
+        });
+    }
+
     // Sort by updated_at descending
     activities.sort_by(|a, b| b.updated_at.cmp(&a.updated_at));
 
     Ok(activities)
 }
 
+/// When any entity was last changed, if there are any
+fn latest_update(store: &LoroStore) -> Result<Option<chrono::DateTime<chrono::Utc>>> {
+    let updates = store
+        .list_decisions()?
+        .into_iter()
+        .map(|e| e.base.updated_at)
+        .chain(store.list_tasks()?.into_iter().map(|e| e.base.updated_at))
+        .chain(store.list_notes()?.into_iter().map(|e| e.base.updated_at))
+        .chain(store.list_prompts()?.into_iter().map(|e| e.base.updated_at))
+        .chain(
+            store
+                .list_components()?
+                .into_iter()
+                .map(|e| e.base.updated_at),
+        )
+        .chain(store.list_links()?.into_iter().map(|e| e.base.updated_at))
+        .chain(
+            store
+                .list_questions()?
+                .into_iter()
+                .map(|e| e.base.updated_at),
+        )
+        .chain(
+            store
+                .list_milestones()?
+                .into_iter()
+                .map(|e| e.base.updated_at),
+        );
+    Ok(updates.max())
+}
+
 /// Generate decisions quick links section
-fn generate_decisions_section(decisions: &[Decision]) -> String {
+fn generate_decisions_section(decisions: &[Decision], files: &EntityFiles) -> String {
     if decisions.is_empty() {
         return String::new();
     }
@@ -116,11 +172,12 @@ fn generate_decisions_section(decisions: &[Decision]) -> String {
     sorted.sort_by_key(|d| d.base.sequence_number);
 
     for decision in &sorted {
-        let slug = slugify(&decision.base.title);
-        let filename = format!("{:03}-{}.md", decision.base.sequence_number, slug);
         section.push_str(&format!(
-            "- [{:03} - {}](decisions/{}) `{}`\n",
-            decision.base.sequence_number, decision.base.title, filename, decision.status
+            "- [{:03} - {}]({}) `{}`\n",
+            decision.base.sequence_number,
+            decision.base.title,
+            link_to(files, &decision.base.id),
+            decision.status
         ));
     }
 
@@ -129,7 +186,7 @@ fn generate_decisions_section(decisions: &[Decision]) -> String {
 }
 
 /// Generate components quick links section
-fn generate_components_section(components: &[Component]) -> String {
+fn generate_components_section(components: &[Component], files: &EntityFiles) -> String {
     if components.is_empty() {
         return String::new();
     }
@@ -140,10 +197,55 @@ fn generate_components_section(components: &[Component]) -> String {
     sorted.sort_by_key(|c| c.base.sequence_number);
 
     for component in &sorted {
-        let slug = slugify(&component.base.title);
         section.push_str(&format!(
-            "- [{}](components/{}.md) `{}`\n",
-            component.base.title, slug, component.status
+            "- [{}]({}) `{}`\n",
+            component.base.title,
+            link_to(files, &component.base.id),
+            component.status
+        ));
+    }
+
+    section.push('\n');
+    section
+}
+
+/// Generate the milestones roadmap: target date, status and task progress
+/// of each unarchived milestone, soonest target first
+fn generate_milestones_section(milestones: &[MilestoneProgress], files: &EntityFiles) -> String {
+    if milestones.is_empty() {
+        return String::new();
+    }
+
+    let mut section = String::from(
+        "## Milestones
+
+",
+    );
+    section.push_str(
+        "| Milestone | Target | Status | Progress |
+",
+    );
+    section.push_str(
+        "|-----------|--------|--------|----------|
+",
+    );
+
+    for progress in milestones {
+        let milestone = &progress.milestone;
+        let (done, total) = progress.counts();
+        section.push_str(&format!(
+            "| [{}]({}) | {} | {} | {}% ({}/{} tasks) |
+",
+            milestone.base.title,
+            link_to(files, &milestone.base.id),
+            milestone
+                .target_date
+                .map(|d| d.to_string())
+                .unwrap_or_else(|| "-".to_string()),
+            milestone.status,
+            progress.percent(),
+            done,
+            total
         ));
     }
 
@@ -152,7 +254,12 @@ fn generate_components_section(components: &[Component]) -> String {
 }
 
 /// Generate README.md index
-pub fn generate(store: &LoroStore, snapshot_dir: &Path, stats: &SnapshotStats) -> Result<()> {
+pub fn generate(
+    store: &LoroStore,
+    writer: &mut SnapshotWriter,
+    files: &EntityFiles,
+    stats: &SnapshotStats,
+) -> Result<()> {
     let mut content = String::from("# Project Knowledge Base\n\n");
     content.push_str("> Auto-generated by [Medulla](https://github.com/jordanstella/medulla). Do not edit directly.\n\n");
 
@@ -169,6 +276,8 @@ pub fn generate(store: &LoroStore, snapshot_dir: &Path, stats: &SnapshotStats) -
     content.push_str(&format!("| Prompts | {} |\n", stats.prompts));
     content.push_str(&format!("| Components | {} |\n", stats.components));
     content.push_str(&format!("| Links | {} |\n", stats.links));
+    content.push_str(&format!("| Questions | {} |\n", stats.questions));
+    content.push_str(&format!("| Milestones | {} |\n", stats.milestones));
     content.push('\n');
 
     // Check if we have any entities
@@ -176,7 +285,7 @@ pub fn generate(store: &LoroStore, snapshot_dir: &Path, stats: &SnapshotStats) -
         content.push_str("*No entities yet. Use `medulla add` to create your first entity.*\n\n");
     } else {
         // Recent Activity (top 5)
-        let activities = collect_recent_activity(store)?;
+        let activities = collect_recent_activity(store, files)?;
         if !activities.is_empty() {
             content.push_str("## Recent Activity\n\n");
             for activity in activities.iter().take(5) {
@@ -197,12 +306,16 @@ pub fn generate(store: &LoroStore, snapshot_dir: &Path, stats: &SnapshotStats) -
             content.push('\n');
         }
 
+        // Roadmap from the task graph
+        let milestones = MilestoneProgress::all(store, false)?;
+        content.push_str(&generate_milestones_section(&milestones, files));
+
         // Quick Links
         content.push_str("## Quick Links\n\n");
 
         // Decisions
         let decisions = store.list_decisions()?;
-        content.push_str(&generate_decisions_section(&decisions));
+        content.push_str(&generate_decisions_section(&decisions, files));
 
         // Active Tasks
         if stats.tasks_active > 0 {
@@ -212,7 +325,25 @@ pub fn generate(store: &LoroStore, snapshot_dir: &Path, stats: &SnapshotStats) -
 
         // Components
         let components = store.list_components()?;
-        content.push_str(&generate_components_section(&components));
+        content.push_str(&generate_components_section(&components, files));
+
+        // Open Questions
+        let open_questions: Vec<_> = store
+            .list_questions()?
+            .into_iter()
+            .filter(|q| q.status == QuestionStatus::Open)
+            .collect();
+        if !open_questions.is_empty() {
+            content.push_str("### Open Questions\n\n");
+            for question in &open_questions {
+                content.push_str(&format!(
+                    "- [{}]({})\n",
+                    question.base.title,
+                    link_to(files, &question.base.id)
+                ));
+            }
+            content.push('\n');
+        }
 
         // Notes
         if stats.notes > 0 {
@@ -221,15 +352,16 @@ pub fn generate(store: &LoroStore, snapshot_dir: &Path, stats: &SnapshotStats) -
             let mut sorted = notes;
             sorted.sort_by(|a, b| b.base.updated_at.cmp(&a.base.updated_at));
             for note in sorted.iter().take(5) {
-                let slug = slugify(&note.base.title);
                 let type_str = note
                     .note_type
                     .as_ref()
                     .map(|t| format!(" `{}`", t))
                     .unwrap_or_default();
                 content.push_str(&format!(
-                    "- [{}](notes/{}.md){}\n",
-                    note.base.title, slug, type_str,
+                    "- [{}]({}){}\n",
+                    note.base.title,
+                    link_to(files, &note.base.id),
+                    type_str,
                 ));
             }
             if sorted.len() > 5 {
@@ -245,8 +377,11 @@ pub fn generate(store: &LoroStore, snapshot_dir: &Path, stats: &SnapshotStats) -
             let mut sorted = prompts;
             sorted.sort_by_key(|p| p.base.sequence_number);
             for prompt in &sorted {
-                let slug = slugify(&prompt.base.title);
-                content.push_str(&format!("- [{}](prompts/{}.md)\n", prompt.base.title, slug,));
+                content.push_str(&format!(
+                    "- [{}]({})\n",
+                    prompt.base.title,
+                    link_to(files, &prompt.base.id)
+                ));
             }
             content.push('\n');
         }
@@ -258,27 +393,33 @@ pub fn generate(store: &LoroStore, snapshot_dir: &Path, stats: &SnapshotStats) -
             let mut sorted = links;
             sorted.sort_by_key(|l| l.base.sequence_number);
             for link in &sorted {
-                let slug = slugify(&link.base.title);
                 let type_str = link
                     .link_type
                     .as_ref()
                     .map(|t| format!(" `{}`", t))
                     .unwrap_or_default();
                 content.push_str(&format!(
-                    "- [{}](links/{}.md){}\n",
-                    link.base.title, slug, type_str,
+                    "- [{}]({}){}\n",
+                    link.base.title,
+                    link_to(files, &link.base.id),
+                    type_str,
                 ));
             }
             content.push('\n');
         }
     }
 
-    // Footer
-    content.push_str("---\n\n");
-    content.push_str(&format!("*Generated: {}*\n", current_timestamp()));
+    // Footer, stamped with the latest entity change rather than the
+    // generation time so an unchanged store renders identically
+    if let Some(updated_at) = latest_update(store)? {
+        content.push_str("---\n\n");
+        content.push_str(&format!(
+            "*Last updated: {}*\n",
+            format_timestamp(&updated_at)
+        ));
+    }
 
-    let readme_path = snapshot_dir.join("README.md");
-    write_snapshot_file(&readme_path, &content)?;
+    writer.write("README.md", &content)?;
 
     Ok(())
 }
@@ -300,7 +441,13 @@ mod tests {
         std::fs::create_dir_all(&snapshot_dir).unwrap();
 
         let stats = SnapshotStats::default();
-        generate(&store, &snapshot_dir, &stats).unwrap();
+        generate(
+            &store,
+            &mut SnapshotWriter::new(&snapshot_dir),
+            &EntityFiles::collect(&store).unwrap(),
+            &stats,
+        )
+        .unwrap();
 
         let content = std::fs::read_to_string(snapshot_dir.join("README.md")).unwrap();
 
@@ -327,10 +474,20 @@ mod tests {
             prompts: 2,
             components: 1,
             links: 4,
+            questions: 0,
+            milestones: 0,
             files_generated: vec![],
+            files_written: vec![],
+            files_removed: vec![],
         };
 
-        generate(&store, &snapshot_dir, &stats).unwrap();
+        generate(
+            &store,
+            &mut SnapshotWriter::new(&snapshot_dir),
+            &EntityFiles::collect(&store).unwrap(),
+            &stats,
+        )
+        .unwrap();
 
         let content = std::fs::read_to_string(snapshot_dir.join("README.md")).unwrap();
 
@@ -342,6 +499,52 @@ mod tests {
         assert!(content.contains("| Links | 4 |"));
     }
 
+    #[test]
+    fn test_readme_milestones_section() {
+        let tmp = TempDir::new().unwrap();
+        let medulla_dir = tmp.path().join(".medulla");
+        std::fs::create_dir_all(&medulla_dir).unwrap();
+
+        let store = crate::storage::LoroStore::init(&medulla_dir).unwrap();
+        let snapshot_dir = medulla_dir.join("snapshot");
+        std::fs::create_dir_all(&snapshot_dir).unwrap();
+
+        let mut milestone = crate::entity::Milestone::new("v1.0".to_string(), 1);
+        milestone.target_date = chrono::NaiveDate::from_ymd_opt(2026, 9, 1);
+        store.add_milestone(&milestone).unwrap();
+        let mut task = crate::entity::Task::new("Ship installer".to_string(), 2);
+        task.status = TaskStatus::Done;
+        store.add_task(&task).unwrap();
+        store
+            .add_relation(&crate::entity::Relation::new(
+                task.base.id,
+                "task".to_string(),
+                milestone.base.id,
+                "milestone".to_string(),
+                crate::entity::RelationType::BelongsTo,
+            ))
+            .unwrap();
+
+        let stats = SnapshotStats {
+            tasks_total: 1,
+            milestones: 1,
+            ..Default::default()
+        };
+        generate(
+            &store,
+            &mut SnapshotWriter::new(&snapshot_dir),
+            &EntityFiles::collect(&store).unwrap(),
+            &stats,
+        )
+        .unwrap();
+
+        let content = std::fs::read_to_string(snapshot_dir.join("README.md")).unwrap();
+        assert!(content.contains("| Milestones | 1 |"));
+        assert!(content.contains("## Milestones"));
+        assert!(content
+            .contains("| [v1.0](milestones/v1-0.md) | 2026-09-01 | open | 100% (1/1 tasks) |"));
+    }
+
     #[test]
     fn test_readme_empty_message() {
         let tmp = TempDir::new().unwrap();
@@ -353,7 +556,13 @@ mod tests {
         std::fs::create_dir_all(&snapshot_dir).unwrap();
 
         let stats = SnapshotStats::default();
-        generate(&store, &snapshot_dir, &stats).unwrap();
+        generate(
+            &store,
+            &mut SnapshotWriter::new(&snapshot_dir),
+            &EntityFiles::collect(&store).unwrap(),
+            &stats,
+        )
+        .unwrap();
 
         let content = std::fs::read_to_string(snapshot_dir.join("README.md")).unwrap();
 
@@ -371,13 +580,28 @@ mod tests {
         let snapshot_dir = medulla_dir.join("snapshot");
         std::fs::create_dir_all(&snapshot_dir).unwrap();
 
-        let stats = SnapshotStats::default();
-        generate(&store, &snapshot_dir, &stats).unwrap();
+        let mut decision = Decision::new("Use PostgreSQL".to_string(), 1);
+        decision.base.updated_at = chrono::DateTime::parse_from_rfc3339("2025-03-01T12:30:00Z")
+            .unwrap()
+            .with_timezone(&chrono::Utc);
+        store.add_decision(&decision).unwrap();
+
+        let stats = SnapshotStats {
+            decisions: 1,
+            ..Default::default()
+        };
+        generate(
+            &store,
+            &mut SnapshotWriter::new(&snapshot_dir),
+            &EntityFiles::collect(&store).unwrap(),
+            &stats,
+        )
+        .unwrap();
 
         let content = std::fs::read_to_string(snapshot_dir.join("README.md")).unwrap();
 
-        assert!(content.contains("*Generated:"));
-        assert!(content.contains("UTC*"));
+        // Stamped with the latest entity change, not the generation time
+        assert!(content.contains("*Last updated: 2025-03-01 12:30:00 UTC*"));
     }
 
     #[test]
@@ -399,7 +623,13 @@ mod tests {
             ..Default::default()
         };
 
-        generate(&store, &snapshot_dir, &stats).unwrap();
+        generate(
+            &store,
+            &mut SnapshotWriter::new(&snapshot_dir),
+            &EntityFiles::collect(&store).unwrap(),
+            &stats,
+        )
+        .unwrap();
 
         let content = std::fs::read_to_string(snapshot_dir.join("README.md")).unwrap();
 
@@ -426,7 +656,13 @@ mod tests {
             ..Default::default()
         };
 
-        generate(&store, &snapshot_dir, &stats).unwrap();
+        generate(
+            &store,
+            &mut SnapshotWriter::new(&snapshot_dir),
+            &EntityFiles::collect(&store).unwrap(),
+            &stats,
+        )
+        .unwrap();
 
         let content = std::fs::read_to_string(snapshot_dir.join("README.md")).unwrap();
 
@@ -455,7 +691,13 @@ mod tests {
             ..Default::default()
         };
 
-        generate(&store, &snapshot_dir, &stats).unwrap();
+        generate(
+            &store,
+            &mut SnapshotWriter::new(&snapshot_dir),
+            &EntityFiles::collect(&store).unwrap(),
+            &stats,
+        )
+        .unwrap();
 
         let content = std::fs::read_to_string(snapshot_dir.join("README.md")).unwrap();
 
@@ -465,13 +707,13 @@ mod tests {
 
     #[test]
     fn test_generate_decisions_section_empty() {
-        let result = generate_decisions_section(&[]);
+        let result = generate_decisions_section(&[], &EntityFiles::default());
         assert!(result.is_empty());
     }
 
     #[test]
     fn test_generate_components_section_empty() {
-        let result = generate_components_section(&[]);
+        let result = generate_components_section(&[], &EntityFiles::default());
         assert!(result.is_empty());
     }
 
@@ -492,7 +734,8 @@ mod tests {
         d2.status = DecisionStatus::Accepted;
         store.add_decision(&d2).unwrap();
 
-        let activities = collect_recent_activity(&store).unwrap();
+        let files = EntityFiles::collect(&store).unwrap();
+        let activities = collect_recent_activity(&store, &files).unwrap();
 
         // Most recent should be first
         assert_eq!(activities.len(), 2);
@@ -0,0 +1,136 @@
+//! "Related" sections suggesting entities with similar embeddings
+//!
+//! The relation graph only holds links someone thought to add. Entities
+//! whose embeddings sit close together often belong together too, so a
+//! snapshot can list each entity's nearest neighbours that it has no
+//! relation with yet. Suggestions come from the cache's embeddings, so
+//! they are only generated on request.
+
+use std::collections::{HashMap, HashSet};
+
+use uuid::Uuid;
+
+use crate::cache::SqliteCache;
+use crate::storage::LoroStore;
+use crate::Result;
+
+/// Suggestions listed per entity by default
+pub const DEFAULT_RELATED_LIMIT: usize = 3;
+
+/// Minimum embedding similarity of a suggestion by default
+pub const DEFAULT_RELATED_MIN_SCORE: f32 = 0.5;
+
+/// Suggested related entities with their similarity, by entity ID
+#[derive(Debug, Clone, Default)]
+pub struct RelatedSuggestions {
+    by_id: HashMap<Uuid, Vec<(Uuid, f32)>>,
+}
+
+impl RelatedSuggestions {
+    /// Up to `limit` suggestions scoring at least `min_score` for every
+    /// entity with an embedding, skipping entities it already has an
+    /// active relation with in either direction
+    pub fn collect(
+        store: &LoroStore,
+        cache: &SqliteCache,
+        limit: usize,
+        min_score: f32,
+    ) -> Result<Self> {
+        let mut linked: HashMap<Uuid, HashSet<String>> = HashMap::new();
+        for relation in store.list_relations()?.iter().filter(|r| r.is_active()) {
+            linked
+                .entry(relation.source_id)
+                .or_default()
+                .insert(relation.target_id.to_string());
+            linked
+                .entry(relation.target_id)
+                .or_default()
+                .insert(relation.source_id.to_string());
+        }
+
+        let no_links = HashSet::new();
+        let mut by_id = HashMap::new();
+        for entity_id in store.entity_ids() {
+            let Ok(id) = Uuid::parse_str(&entity_id) else {
+                continue;
+            };
+            let exclude = linked.get(&id).unwrap_or(&no_links);
+            let suggestions: Vec<(Uuid, f32)> = cache
+                .suggest_related(&entity_id, exclude, limit, min_score)?
+                .into_iter()
+                .filter_map(|r| Some((Uuid::parse_str(&r.entity_id).ok()?, r.score)))
+                .collect();
+            if !suggestions.is_empty() {
+                by_id.insert(id, suggestions);
+            }
+        }
+
+        Ok(Self { by_id })
+    }
+
+    /// Suggestions for an entity, most similar first
+    pub fn get(&self, id: &Uuid) -> &[(Uuid, f32)] {
+        self.by_id.get(id).map_or(&[], Vec::as_slice)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::entity::{Note, Relation, RelationType};
+    use crate::snapshot::backlinks::EntityFiles;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_related_suggestions() {
+        let tmp = TempDir::new().unwrap();
+        let store = LoroStore::init(tmp.path()).unwrap();
+        let cache = SqliteCache::open(store.medulla_dir()).unwrap();
+
+        let notes: Vec<Note> = ["Retry policy", "Backoff tuning", "Timeouts", "Team lunch"]
+            .iter()
+            .enumerate()
+            .map(|(i, title)| Note::new(title.to_string(), i as u32 + 1))
+            .collect();
+        for note in &notes {
+            store.add_note(note).unwrap();
+        }
+        store
+            .add_relation(&Relation::new(
+                notes[0].base.id,
+                "note".to_string(),
+                notes[2].base.id,
+                "note".to_string(),
+                RelationType::References,
+            ))
+            .unwrap();
+        store.sync_cache(&cache).unwrap();
+        for (note, vector) in notes.iter().zip([
+            [1.0, 0.0, 0.0],
+            [0.9, 0.1, 0.0],
+            [0.95, 0.05, 0.0],
+            [0.0, 0.0, 1.0],
+        ]) {
+            cache
+                .store_embedding(&note.base.id.to_string(), "note", &vector, "h")
+                .unwrap();
+        }
+
+        let related = RelatedSuggestions::collect(&store, &cache, 3, 0.5).unwrap();
+        // Timeouts is already referenced, so only Backoff tuning is suggested
+        let suggested: Vec<Uuid> = related
+            .get(&notes[0].base.id)
+            .iter()
+            .map(|(id, _)| *id)
+            .collect();
+        assert_eq!(suggested, vec![notes[1].base.id]);
+        assert!(related.get(&notes[3].base.id).is_empty());
+
+        let files = EntityFiles::collect(&store).unwrap().with_related(related);
+        let section = files.related(&notes[0].base.id);
+        assert!(section.starts_with(
+            "\n## Related\n\n- [Backoff tuning](../notes/backoff-tuning.md) (note, 0.99)"
+        ));
+        assert_eq!(files.related(&notes[3].base.id), "");
+    }
+}
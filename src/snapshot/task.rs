@@ -1,15 +1,55 @@
 // src/snapshot/task.rs
 //! Task snapshot generation
 
-use std::path::Path;
-
 use crate::entity::{Task, TaskPriority, TaskStatus};
 use crate::storage::LoroStore;
 use crate::Result;
 
-use super::current_timestamp;
-use super::utils::{short_uuid, write_snapshot_file};
-use super::GeneratedFile;
+use serde::Serialize;
+
+use super::backlinks::EntityFiles;
+use super::utils::{format_date, format_timestamp, short_uuid, SnapshotWriter};
+use super::{yaml_frontmatter, GeneratedFile};
+
+#[derive(Serialize)]
+struct TaskFrontmatter {
+    id: String,
+    sequence: u32,
+    title: String,
+    status: String,
+    priority: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    due: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    assignee: Option<String>,
+    created: String,
+    updated: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    created_by: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    updated_by: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    tags: Vec<String>,
+}
+
+impl TaskFrontmatter {
+    fn from_task(task: &Task) -> Self {
+        Self {
+            id: task.base.id.to_string(),
+            sequence: task.base.sequence_number,
+            title: task.base.title.clone(),
+            status: task.status.to_string(),
+            priority: task.priority.to_string(),
+            due: task.due_date.map(|d| d.to_string()),
+            assignee: task.assignee.clone(),
+            created: format_date(&task.base.created_at),
+            updated: format_date(&task.base.updated_at),
+            created_by: task.base.created_by.clone(),
+            updated_by: task.base.updated_by.clone(),
+            tags: task.base.tags.clone(),
+        }
+    }
+}
 
 /// Format a single task line
 fn format_task_line(task: &Task) -> String {
@@ -72,8 +112,17 @@ fn format_completed_task_line(task: &Task) -> String {
     line
 }
 
+/// Footer stamped with the latest change to the listed tasks rather than the
+/// generation time, so an unchanged list renders identically
+fn last_updated_footer(tasks: &[&Task]) -> String {
+    match tasks.iter().map(|t| t.base.updated_at).max() {
+        Some(updated_at) => format!("---\n\n*Last updated: {}*\n", format_timestamp(&updated_at)),
+        None => String::new(),
+    }
+}
+
 /// Generate active.md with tasks grouped by priority
-fn generate_active(tasks: &[&Task], snapshot_dir: &Path) -> Result<GeneratedFile> {
+fn generate_active(tasks: &[&Task], writer: &mut SnapshotWriter) -> Result<GeneratedFile> {
     let mut content = String::from("# Active Tasks\n\n");
     content.push_str("> Generated from Medulla. Do not edit directly.\n\n");
 
@@ -111,11 +160,8 @@ fn generate_active(tasks: &[&Task], snapshot_dir: &Path) -> Result<GeneratedFile
         }
     }
 
-    content.push_str("---\n\n");
-    content.push_str(&format!("*Last updated: {}*\n", current_timestamp()));
-
-    let file_path = snapshot_dir.join("tasks/active.md");
-    write_snapshot_file(&file_path, &content)?;
+    content.push_str(&last_updated_footer(tasks));
+    writer.write("tasks/active.md", &content)?;
 
     Ok(GeneratedFile {
         relative_path: "tasks/active.md".to_string(),
@@ -124,7 +170,7 @@ fn generate_active(tasks: &[&Task], snapshot_dir: &Path) -> Result<GeneratedFile
 }
 
 /// Generate completed.md with done tasks
-fn generate_completed(tasks: &[&Task], snapshot_dir: &Path) -> Result<GeneratedFile> {
+fn generate_completed(tasks: &[&Task], writer: &mut SnapshotWriter) -> Result<GeneratedFile> {
     let mut content = String::from("# Completed Tasks\n\n");
     content.push_str("> Generated from Medulla. Do not edit directly.\n\n");
 
@@ -141,11 +187,8 @@ fn generate_completed(tasks: &[&Task], snapshot_dir: &Path) -> Result<GeneratedF
         }
     }
 
-    content.push_str("---\n\n");
-    content.push_str(&format!("*Last updated: {}*\n", current_timestamp()));
-
-    let file_path = snapshot_dir.join("tasks/completed.md");
-    write_snapshot_file(&file_path, &content)?;
+    content.push_str(&last_updated_footer(tasks));
+    writer.write("tasks/completed.md", &content)?;
 
     Ok(GeneratedFile {
         relative_path: "tasks/completed.md".to_string(),
@@ -153,8 +196,60 @@ fn generate_completed(tasks: &[&Task], snapshot_dir: &Path) -> Result<GeneratedF
     })
 }
 
+/// The snapshot file listing a task, relative to the snapshot directory
+pub(super) fn file_path(task: &Task) -> &'static str {
+    if task.status == TaskStatus::Done {
+        "tasks/completed.md"
+    } else {
+        "tasks/active.md"
+    }
+}
+
+/// Render a task as the line it occupies in active.md or completed.md
+pub fn render(task: &Task) -> String {
+    if task.status == TaskStatus::Done {
+        format_completed_task_line(task)
+    } else {
+        format_task_line(task)
+    }
+}
+
+/// Render a task as its own snapshot file, for the per-entity layout
+pub fn render_file(task: &Task) -> Result<String> {
+    let yaml = yaml_frontmatter(&TaskFrontmatter::from_task(task))?;
+    let body = task.base.content.as_deref().unwrap_or("");
+    Ok(format!("{}\n{}", yaml, body))
+}
+
+/// Generate one file per task, at the paths in `files`
+pub fn generate_files(
+    store: &LoroStore,
+    writer: &mut SnapshotWriter,
+    files: &EntityFiles,
+) -> Result<Vec<GeneratedFile>> {
+    let mut tasks = store.list_tasks()?;
+    tasks.sort_by_key(|t| t.base.sequence_number);
+
+    let mut generated = Vec::new();
+    for task in &tasks {
+        let Some(relative_path) = files.path(&task.base.id) else {
+            continue;
+        };
+        let mut content = files.render_page("task", &task, render_file(task)?)?;
+        files.append_sections(store, &task.base.id, &mut content)?;
+        writer.write(relative_path, &content)?;
+
+        generated.push(GeneratedFile {
+            relative_path: relative_path.to_string(),
+            entity_count: 1,
+        });
+    }
+
+    Ok(generated)
+}
+
 /// Generate task snapshot files
-pub fn generate(store: &LoroStore, snapshot_dir: &Path) -> Result<Vec<GeneratedFile>> {
+pub fn generate(store: &LoroStore, writer: &mut SnapshotWriter) -> Result<Vec<GeneratedFile>> {
     let tasks = store.list_tasks()?;
     let mut generated = Vec::new();
 
@@ -169,8 +264,8 @@ pub fn generate(store: &LoroStore, snapshot_dir: &Path) -> Result<Vec<GeneratedF
         .filter(|t| t.status == TaskStatus::Done)
         .collect();
 
-    generated.push(generate_active(&active, snapshot_dir)?);
-    generated.push(generate_completed(&completed, snapshot_dir)?);
+    generated.push(generate_active(&active, writer)?);
+    generated.push(generate_completed(&completed, writer)?);
 
     Ok(generated)
 }
@@ -189,7 +284,9 @@ mod tests {
             status,
             priority,
             due_date: None,
+            estimate_minutes: None,
             assignee: None,
+            recurrence: None,
         }
     }
 
@@ -263,7 +360,7 @@ mod tests {
         std::fs::create_dir_all(snapshot_dir.join("tasks")).unwrap();
 
         let tasks: Vec<&Task> = vec![];
-        let result = generate_active(&tasks, snapshot_dir).unwrap();
+        let result = generate_active(&tasks, &mut SnapshotWriter::new(snapshot_dir)).unwrap();
 
         assert_eq!(result.relative_path, "tasks/active.md");
         assert_eq!(result.entity_count, 0);
@@ -284,7 +381,7 @@ mod tests {
         let low = mock_task(4, "Low Task", TaskStatus::Todo, TaskPriority::Low);
 
         let tasks: Vec<&Task> = vec![&low, &normal, &urgent, &high]; // Intentionally out of order
-        generate_active(&tasks, snapshot_dir).unwrap();
+        generate_active(&tasks, &mut SnapshotWriter::new(snapshot_dir)).unwrap();
 
         let content = std::fs::read_to_string(snapshot_dir.join("tasks/active.md")).unwrap();
 
@@ -318,7 +415,7 @@ mod tests {
         task2.due_date = Some(NaiveDate::from_ymd_opt(2025, 1, 1).unwrap());
 
         let tasks: Vec<&Task> = vec![&task1, &task2]; // Later task first
-        generate_active(&tasks, snapshot_dir).unwrap();
+        generate_active(&tasks, &mut SnapshotWriter::new(snapshot_dir)).unwrap();
 
         let content = std::fs::read_to_string(snapshot_dir.join("tasks/active.md")).unwrap();
 
@@ -335,7 +432,7 @@ mod tests {
         std::fs::create_dir_all(snapshot_dir.join("tasks")).unwrap();
 
         let tasks: Vec<&Task> = vec![];
-        let result = generate_completed(&tasks, snapshot_dir).unwrap();
+        let result = generate_completed(&tasks, &mut SnapshotWriter::new(snapshot_dir)).unwrap();
 
         assert_eq!(result.relative_path, "tasks/completed.md");
         assert_eq!(result.entity_count, 0);
@@ -357,7 +454,7 @@ mod tests {
         task2.base.updated_at = Utc::now();
 
         let tasks: Vec<&Task> = vec![&task1, &task2]; // Old first
-        generate_completed(&tasks, snapshot_dir).unwrap();
+        generate_completed(&tasks, &mut SnapshotWriter::new(snapshot_dir)).unwrap();
 
         let content = std::fs::read_to_string(snapshot_dir.join("tasks/completed.md")).unwrap();
 
@@ -387,7 +484,7 @@ mod tests {
         let snapshot_dir = medulla_dir.join("snapshot");
         super::super::utils::ensure_snapshot_dirs(&snapshot_dir).unwrap();
 
-        let files = generate(&store, &snapshot_dir).unwrap();
+        let files = generate(&store, &mut SnapshotWriter::new(&snapshot_dir)).unwrap();
 
         assert_eq!(files.len(), 2);
 
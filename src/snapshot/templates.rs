@@ -0,0 +1,188 @@
+//! User-provided templates for snapshot pages
+//!
+//! Dropping `{type}.md` (e.g. `decision.md`) into `.medulla/templates/`
+//! replaces the built-in layout of that entity type's pages with a
+//! minijinja template, so a team can publish decisions in its own ADR
+//! format. Types without a template keep the built-in layout. Every file
+//! in the directory is loaded, so templates can `include` or `extend`
+//! shared partials.
+//!
+//! Templates see:
+//! - `entity`: the entity's fields, as in `medulla get --json`
+//! - `frontmatter`: the built-in YAML frontmatter block
+//! - `body`: the built-in page after the frontmatter
+//! - `default`: the whole built-in page
+//!
+//! and can format timestamps with the `date` filter. The "Attachments",
+//! "Referenced by" and "Related" sections are appended after rendering.
+
+use std::path::Path;
+
+use minijinja::{context, Environment};
+use serde::Serialize;
+
+use crate::error::MedullaError;
+use crate::Result;
+
+/// Directory under `.medulla/` holding snapshot templates
+pub const SNAPSHOT_TEMPLATES_DIR: &str = "templates";
+
+/// Templates loaded from `.medulla/templates/`, if any
+#[derive(Default)]
+pub struct SnapshotTemplates {
+    env: Option<Environment<'static>>,
+}
+
+impl SnapshotTemplates {
+    /// Load every template in `.medulla/templates/`. A missing directory
+    /// means no overrides; a template that does not parse is an error.
+    pub fn load(medulla_dir: &Path) -> Result<Self> {
+        let dir = medulla_dir.join(SNAPSHOT_TEMPLATES_DIR);
+        if !dir.is_dir() {
+            return Ok(Self::default());
+        }
+
+        let mut env = Environment::new();
+        env.set_keep_trailing_newline(true);
+        env.add_filter("date", date_filter);
+        for entry in std::fs::read_dir(&dir)? {
+            let path = entry?.path();
+            let Some(name) = path.file_name().and_then(|n| n.to_str()) else {
+                continue;
+            };
+            if !path.is_file() || name.starts_with('.') {
+                continue;
+            }
+            let source = std::fs::read_to_string(&path)?;
+            env.add_template_owned(name.to_string(), source)
+                .map_err(|e| template_error(name, e))?;
+        }
+        Ok(Self { env: Some(env) })
+    }
+
+    /// Whether there is a template for `entity_type`
+    pub fn has(&self, entity_type: &str) -> bool {
+        self.env
+            .as_ref()
+            .is_some_and(|env| env.get_template(&template_name(entity_type)).is_ok())
+    }
+
+    /// Render `entity` through the template for `entity_type`, or return
+    /// `default` (the built-in page) when there is none
+    pub fn render<T: Serialize>(
+        &self,
+        entity_type: &str,
+        entity: &T,
+        default: String,
+    ) -> Result<String> {
+        let name = template_name(entity_type);
+        let Some(template) = self
+            .env
+            .as_ref()
+            .and_then(|env| env.get_template(&name).ok())
+        else {
+            return Ok(default);
+        };
+
+        let (frontmatter, body) = split_frontmatter(&default);
+        template
+            .render(context! {
+                entity => entity,
+                frontmatter => frontmatter,
+                body => body,
+                default => &default,
+            })
+            .map_err(|e| template_error(&name, e))
+    }
+}
+
+fn template_name(entity_type: &str) -> String {
+    format!("{}.md", entity_type)
+}
+
+fn template_error(name: &str, error: minijinja::Error) -> MedullaError {
+    MedullaError::Template(format!("{}: {:#}", name, error))
+}
+
+/// `YYYY-MM-DD` of an RFC 3339 timestamp; other values pass through
+fn date_filter(value: String) -> String {
+    chrono::DateTime::parse_from_rfc3339(&value)
+        .map(|dt| dt.format("%Y-%m-%d").to_string())
+        .unwrap_or(value)
+}
+
+/// Split a built-in page into its frontmatter block and the rest
+fn split_frontmatter(page: &str) -> (&str, &str) {
+    if let Some(rest) = page.strip_prefix("---\n") {
+        if let Some(end) = rest.find("\n---\n") {
+            return page.split_at(4 + end + "\n---\n".len());
+        }
+    }
+    ("", page)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::entity::Decision;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_split_frontmatter() {
+        assert_eq!(
+            split_frontmatter("---\nid: 1\n---\n\nBody\n"),
+            ("---\nid: 1\n---\n", "\nBody\n")
+        );
+        assert_eq!(split_frontmatter("No frontmatter"), ("", "No frontmatter"));
+    }
+
+    #[test]
+    fn test_render_with_templates() {
+        let tmp = TempDir::new().unwrap();
+        let decision = Decision::new("Use Postgres".to_string(), 4);
+        let default = "---\nid: 4\n---\n\nBuilt in\n".to_string();
+
+        // No directory: the built-in page is kept
+        let templates = SnapshotTemplates::load(tmp.path()).unwrap();
+        assert!(!templates.has("decision"));
+        assert_eq!(
+            templates
+                .render("decision", &decision, default.clone())
+                .unwrap(),
+            default
+        );
+
+        let dir = tmp.path().join(SNAPSHOT_TEMPLATES_DIR);
+        std::fs::create_dir(&dir).unwrap();
+        std::fs::write(
+            dir.join("header.md"),
+            "# ADR-{{ entity.sequence_number }}\n",
+        )
+        .unwrap();
+        std::fs::write(
+            dir.join("decision.md"),
+            "{{ frontmatter }}{% include 'header.md' %}\nStatus: {{ entity.status }} ({{ entity.created_at | date }})\n{{ body }}",
+        )
+        .unwrap();
+        let templates = SnapshotTemplates::load(tmp.path()).unwrap();
+        assert!(templates.has("decision"));
+        assert!(!templates.has("note"));
+
+        let rendered = templates
+            .render("decision", &decision, default.clone())
+            .unwrap();
+        assert_eq!(
+            rendered,
+            format!(
+                "---\nid: 4\n---\n# ADR-4\n\nStatus: proposed ({})\n\nBuilt in\n",
+                decision.base.created_at.format("%Y-%m-%d")
+            )
+        );
+
+        std::fs::write(dir.join("note.md"), "{% if %}").unwrap();
+        let Err(err) = SnapshotTemplates::load(tmp.path()) else {
+            panic!("expected a syntax error");
+        };
+        assert!(err.to_string().contains("note.md"));
+    }
+}
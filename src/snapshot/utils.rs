@@ -1,8 +1,9 @@
 // src/snapshot/utils.rs
 //! Utility functions for snapshot generation
 
+use std::collections::BTreeMap;
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 use crate::Result;
 
@@ -39,6 +40,17 @@ pub fn slugify(title: &str) -> String {
     slug
 }
 
+/// Per-entity snapshot file `{dir}/{sequence:03}-{slug}.md`. Sequence
+/// numbers are never reused, so the prefix stays a stable link target.
+pub fn permalink(dir: &str, base: &crate::entity::EntityBase) -> String {
+    format!(
+        "{}/{:03}-{}.md",
+        dir,
+        base.sequence_number,
+        slugify(&base.title)
+    )
+}
+
 /// Ensure the snapshot directory structure exists
 pub fn ensure_snapshot_dirs(snapshot_dir: &Path) -> Result<()> {
     let subdirs = [
@@ -48,6 +60,8 @@ pub fn ensure_snapshot_dirs(snapshot_dir: &Path) -> Result<()> {
         "prompts",
         "components",
         "links",
+        "questions",
+        "milestones",
     ];
 
     for subdir in &subdirs {
@@ -57,17 +71,113 @@ pub fn ensure_snapshot_dirs(snapshot_dir: &Path) -> Result<()> {
     Ok(())
 }
 
-/// Remove all files from the snapshot directory (but keep the structure)
-pub fn clear_snapshot_dir(snapshot_dir: &Path) -> Result<()> {
-    if !snapshot_dir.exists() {
+/// Delete files under the snapshot directory that are not in `keep`
+/// (relative paths with `/` separators), returning the removed paths.
+/// Files starting with `.` (such as the snapshot metadata), tagged
+/// snapshots under `versions/` and collection snapshots under
+/// `collections/` are left alone.
+pub fn remove_stale_files(
+    snapshot_dir: &Path,
+    keep: &BTreeMap<String, String>,
+) -> Result<Vec<String>> {
+    let mut removed = Vec::new();
+    remove_stale_in(snapshot_dir, "", keep, &mut removed)?;
+    removed.sort();
+    Ok(removed)
+}
+
+fn remove_stale_in(
+    dir: &Path,
+    prefix: &str,
+    keep: &BTreeMap<String, String>,
+    removed: &mut Vec<String>,
+) -> Result<()> {
+    if !dir.exists() {
         return Ok(());
     }
-
-    // Remove and recreate to ensure clean state
-    fs::remove_dir_all(snapshot_dir)?;
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let name = entry.file_name().to_string_lossy().into_owned();
+        if name.starts_with('.')
+            || (prefix.is_empty()
+                && (name == super::SNAPSHOT_VERSIONS_DIR
+                    || name == super::SNAPSHOT_COLLECTIONS_DIR))
+        {
+            continue;
+        }
+        let relative = format!("{}{}", prefix, name);
+        if entry.file_type()?.is_dir() {
+            remove_stale_in(&entry.path(), &format!("{}/", relative), keep, removed)?;
+        } else if !keep.contains_key(&relative) {
+            fs::remove_file(entry.path())?;
+            removed.push(relative);
+        }
+    }
     Ok(())
 }
 
+/// Stable 64-bit FNV-1a hash of file content, as hex
+pub fn content_hash(content: &str) -> String {
+    bytes_hash(content.as_bytes())
+}
+
+/// `content_hash` of binary content
+pub fn bytes_hash(bytes: &[u8]) -> String {
+    let hash = bytes.iter().fold(0xcbf2_9ce4_8422_2325u64, |hash, byte| {
+        (hash ^ u64::from(*byte)).wrapping_mul(0x0100_0000_01b3)
+    });
+    format!("{:016x}", hash)
+}
+
+/// Writes snapshot files, skipping any whose content hash matches the one
+/// recorded by the previous generation (as long as the file is still there).
+pub struct SnapshotWriter {
+    dir: PathBuf,
+    previous: BTreeMap<String, String>,
+    hashes: BTreeMap<String, String>,
+    written: Vec<String>,
+}
+
+impl SnapshotWriter {
+    pub fn new(dir: &Path) -> Self {
+        Self {
+            dir: dir.to_path_buf(),
+            previous: BTreeMap::new(),
+            hashes: BTreeMap::new(),
+            written: Vec::new(),
+        }
+    }
+
+    /// Content hashes from the previous generation, by relative path
+    pub fn with_previous(mut self, previous: BTreeMap<String, String>) -> Self {
+        self.previous = previous;
+        self
+    }
+
+    /// Write `content` to `relative_path` (with `/` separators) unless the
+    /// file already holds it
+    pub fn write(&mut self, relative_path: &str, content: &str) -> Result<()> {
+        let hash = content_hash(content);
+        let path = self.dir.join(relative_path);
+        if self.previous.get(relative_path) != Some(&hash) || !path.exists() {
+            write_snapshot_file(&path, content)?;
+            self.written.push(relative_path.to_string());
+        }
+        self.hashes.insert(relative_path.to_string(), hash);
+        Ok(())
+    }
+
+    /// Content hashes of every file produced, by relative path
+    pub fn hashes(&self) -> &BTreeMap<String, String> {
+        &self.hashes
+    }
+
+    /// Files actually written (new or changed)
+    pub fn written(&self) -> &[String] {
+        &self.written
+    }
+}
+
 /// Write content to a file, creating parent directories if needed
 pub fn write_snapshot_file(path: &Path, content: &str) -> Result<()> {
     if let Some(parent) = path.parent() {
@@ -126,6 +236,13 @@ mod tests {
         assert_eq!(slugify("---"), "untitled");
     }
 
+    #[test]
+    fn test_content_hash_is_stable() {
+        assert_eq!(content_hash(""), "cbf29ce484222325");
+        assert_eq!(content_hash("a"), "af63dc4c8601ec8c");
+        assert_ne!(content_hash("# Note\n"), content_hash("# Note \n"));
+    }
+
     #[test]
     fn test_slugify_numbers() {
         assert_eq!(slugify("Task 123"), "task-123");
@@ -0,0 +1,243 @@
+//! Lookup table from sequence numbers, aliases and UUID prefixes to
+//! entities.
+//!
+//! IDs given by users and tools are a sequence number ("12"), an alias
+//! ("auth-decision"), a collection-scoped number ("backend/3") or a UUID
+//! prefix ("a1b2c3"). The index answers all of them without listing every
+//! entity: numbers and aliases through hash maps, prefixes through a binary
+//! search over the sorted UUIDs. `LoroStore` rebuilds it whenever the
+//! document has changed since it was last built.
+
+use std::collections::HashMap;
+
+use uuid::Uuid;
+
+/// Entity types in lookup order, with the Loro map each is stored in. When
+/// a UUID prefix matches several entities, earlier types win.
+pub(crate) const ENTITY_MAPS: [(&str, &str); 8] = [
+    ("decision", "decisions"),
+    ("task", "tasks"),
+    ("note", "notes"),
+    ("prompt", "prompts"),
+    ("component", "components"),
+    ("link", "links"),
+    ("question", "questions"),
+    ("milestone", "milestones"),
+];
+
+#[derive(Debug, Clone)]
+struct Entry {
+    /// Lowercase UUID without dashes, the prefix search key
+    key: String,
+    id: Uuid,
+    /// Index into `ENTITY_MAPS`
+    type_rank: usize,
+    sequence_number: u32,
+}
+
+#[derive(Debug, Clone, Default)]
+pub(crate) struct IdIndex {
+    /// Indexes into `entries`, lowest type rank first. Sequence numbers are
+    /// unique except after concurrent edits are merged.
+    by_sequence: HashMap<u32, Vec<usize>>,
+    /// Lowercase alias to index into `entries`
+    aliases: HashMap<String, usize>,
+    /// Lowercase collection and sequence within it to index into `entries`
+    collections: HashMap<(String, u32), usize>,
+    /// Sorted by key
+    entries: Vec<Entry>,
+}
+
+impl IdIndex {
+    /// Build from `(id, type_rank, sequence_number)` triples.
+    pub(crate) fn new(items: impl IntoIterator<Item = (Uuid, usize, u32)>) -> Self {
+        let mut entries: Vec<Entry> = items
+            .into_iter()
+            .map(|(id, type_rank, sequence_number)| Entry {
+                key: id.simple().to_string(),
+                id,
+                type_rank,
+                sequence_number,
+            })
+            .collect();
+        entries.sort_by(|a, b| a.key.cmp(&b.key));
+
+        let mut by_sequence: HashMap<u32, Vec<usize>> = HashMap::new();
+        for (index, entry) in entries.iter().enumerate() {
+            by_sequence
+                .entry(entry.sequence_number)
+                .or_default()
+                .push(index);
+        }
+        for indexes in by_sequence.values_mut() {
+            indexes.sort_by_key(|&index| entries[index].type_rank);
+        }
+
+        Self {
+            by_sequence,
+            aliases: HashMap::new(),
+            collections: HashMap::new(),
+            entries,
+        }
+    }
+
+    /// Add `(alias, id)` pairs. Aliases of entities not in the index are
+    /// ignored.
+    pub(crate) fn with_aliases(
+        mut self,
+        aliases: impl IntoIterator<Item = (String, Uuid)>,
+    ) -> Self {
+        let positions = self.positions();
+        for (alias, id) in aliases {
+            if let Some(&index) = positions.get(&id) {
+                self.aliases.insert(alias.to_lowercase(), index);
+            }
+        }
+        self
+    }
+
+    /// Add `(collection, sequence, id)` memberships. Memberships of entities
+    /// not in the index are ignored.
+    pub(crate) fn with_collections(
+        mut self,
+        memberships: impl IntoIterator<Item = (String, u32, Uuid)>,
+    ) -> Self {
+        let positions = self.positions();
+        for (collection, sequence, id) in memberships {
+            if let Some(&index) = positions.get(&id) {
+                self.collections
+                    .insert((collection.to_lowercase(), sequence), index);
+            }
+        }
+        self
+    }
+
+    fn positions(&self) -> HashMap<Uuid, usize> {
+        self.entries
+            .iter()
+            .enumerate()
+            .map(|(index, entry)| (entry.id, index))
+            .collect()
+    }
+
+    /// Highest sequence number in use, or 0 when there are no entities.
+    pub(crate) fn max_sequence(&self) -> u32 {
+        self.by_sequence.keys().copied().max().unwrap_or(0)
+    }
+
+    /// Resolve a sequence number, alias, `collection/sequence` pair or UUID
+    /// prefix (case- and dash-insensitive) to an entity's UUID and type,
+    /// optionally only among one type. An alias wins over a UUID prefix
+    /// spelled the same way.
+    pub(crate) fn lookup(
+        &self,
+        id: &str,
+        entity_type: Option<&str>,
+    ) -> Option<(Uuid, &'static str)> {
+        let wanted =
+            |entry: &Entry| entity_type.map_or(true, |t| ENTITY_MAPS[entry.type_rank].0 == t);
+
+        let found = if !id.is_empty() && id.chars().all(|c| c.is_ascii_digit()) {
+            let seq = id.parse::<u32>().ok()?;
+            self.by_sequence
+                .get(&seq)?
+                .iter()
+                .map(|&index| &self.entries[index])
+                .find(|entry| wanted(entry))
+        } else if let Some((collection, seq)) = id.split_once('/') {
+            let seq = seq.parse::<u32>().ok()?;
+            self.collections
+                .get(&(collection.to_lowercase(), seq))
+                .map(|&index| &self.entries[index])
+                .filter(|entry| wanted(entry))
+        } else if let Some(entry) = self
+            .aliases
+            .get(&id.to_lowercase())
+            .map(|&index| &self.entries[index])
+            .filter(|entry| wanted(entry))
+        {
+            Some(entry)
+        } else {
+            let key = id.replace('-', "").to_lowercase();
+            if key.is_empty() {
+                return None;
+            }
+            let start = self
+                .entries
+                .partition_point(|e| e.key.as_str() < key.as_str());
+            self.entries[start..]
+                .iter()
+                .take_while(|e| e.key.starts_with(&key))
+                .filter(|entry| wanted(entry))
+                .min_by_key(|e| (e.type_rank, e.sequence_number))
+        };
+
+        found.map(|entry| (entry.id, ENTITY_MAPS[entry.type_rank].0))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lookup_by_sequence_and_prefix() {
+        let decision = Uuid::parse_str("ab12cd34-0000-4000-8000-000000000001").unwrap();
+        let task = Uuid::parse_str("ab12ef56-0000-4000-8000-000000000002").unwrap();
+        let note = Uuid::parse_str("ff000000-0000-4000-8000-000000000003").unwrap();
+        let index = IdIndex::new([(task, 1, 2), (note, 2, 3), (decision, 0, 1)]);
+
+        assert_eq!(index.lookup("2", None), Some((task, "task")));
+        assert_eq!(index.lookup("2", Some("note")), None);
+        assert_eq!(index.lookup("9", None), None);
+
+        // Ambiguous prefixes resolve to the earlier type unless one is asked for
+        assert_eq!(index.lookup("AB12", None), Some((decision, "decision")));
+        assert_eq!(index.lookup("ab12", Some("task")), Some((task, "task")));
+        assert_eq!(index.lookup("ab12-ef", None), Some((task, "task")));
+        assert_eq!(index.lookup(&note.to_string(), None), Some((note, "note")));
+        assert_eq!(index.lookup("abc", None), None);
+        assert_eq!(index.lookup("", None), None);
+    }
+
+    #[test]
+    fn test_lookup_by_alias() {
+        let decision = Uuid::parse_str("ab12cd34-0000-4000-8000-000000000001").unwrap();
+        let task = Uuid::parse_str("ab12ef56-0000-4000-8000-000000000002").unwrap();
+        let index = IdIndex::new([(decision, 0, 1), (task, 1, 2)]).with_aliases([
+            ("auth-decision".to_string(), decision),
+            ("ab12ef".to_string(), decision),
+            ("gone".to_string(), Uuid::new_v4()),
+        ]);
+
+        assert_eq!(
+            index.lookup("Auth-Decision", None),
+            Some((decision, "decision"))
+        );
+        assert_eq!(index.lookup("auth-decision", Some("task")), None);
+        // The alias shadows the UUID prefix it spells
+        assert_eq!(index.lookup("ab12ef", None), Some((decision, "decision")));
+        assert_eq!(index.lookup("ab12ef", Some("task")), Some((task, "task")));
+        assert_eq!(index.lookup("gone", None), None);
+    }
+
+    #[test]
+    fn test_lookup_by_collection() {
+        let decision = Uuid::parse_str("ab12cd34-0000-4000-8000-000000000001").unwrap();
+        let task = Uuid::parse_str("ab12ef56-0000-4000-8000-000000000002").unwrap();
+        let index = IdIndex::new([(decision, 0, 1), (task, 1, 2)]).with_collections([
+            ("backend".to_string(), 1, task),
+            ("frontend".to_string(), 1, decision),
+            ("backend".to_string(), 2, Uuid::new_v4()),
+        ]);
+
+        assert_eq!(index.lookup("Backend/1", None), Some((task, "task")));
+        assert_eq!(
+            index.lookup("frontend/1", None),
+            Some((decision, "decision"))
+        );
+        assert_eq!(index.lookup("frontend/1", Some("task")), None);
+        assert_eq!(index.lookup("backend/2", None), None);
+        assert_eq!(index.lookup("backend/x", None), None);
+    }
+}
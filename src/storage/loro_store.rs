@@ -6,17 +6,22 @@
 #![allow(clippy::explicit_auto_deref)]
 #![allow(clippy::field_reassign_with_default)]
 
+use std::collections::{HashMap, HashSet};
 use std::fs;
 use std::path::{Path, PathBuf};
+use std::sync::Mutex;
 
 use loro::{LoroDoc, LoroMap, LoroValue, ValueOrContainer};
 
 use crate::cache::SqliteCache;
+use crate::config::RelationTypeDef;
 use crate::entity::{
-    Component, Decision, DecisionStatus, Link, Note, Prompt, Relation, RelationType, Task,
-    TaskPriority, TaskStatus,
+    CommitLink, Component, Decision, DecisionStatus, EntityTemplate, Link, Milestone,
+    MilestoneStatus, Note, Prompt, Question, QuestionStatus, Recurrence, Relation, RelationType,
+    Task, TaskPriority, TaskStatus,
 };
 use crate::error::{MedullaError, Result};
+use crate::storage::id_index::{IdIndex, ENTITY_MAPS};
 
 const MEDULLA_DIR: &str = ".medulla";
 const LORO_DB: &str = "loro.db";
@@ -29,6 +34,7 @@ pub struct DecisionUpdate {
     pub content: Option<String>,
     pub context: Option<String>,
     pub superseded_by: Option<Option<String>>,
+    pub priority: Option<Option<TaskPriority>>, // Some(None) to clear, Some(Some(p)) to set
     pub add_tags: Vec<String>,
     pub remove_tags: Vec<String>,
 }
@@ -42,6 +48,8 @@ pub struct TaskUpdate {
     pub content: Option<String>,
     pub due_date: Option<Option<chrono::NaiveDate>>, // Some(None) to clear, Some(Some(date)) to set
     pub assignee: Option<Option<String>>,            // Some(None) to clear, Some(Some(s)) to set
+    pub estimate_minutes: Option<Option<u32>>,       // Some(None) to clear, Some(Some(n)) to set
+    pub recurrence: Option<Option<Recurrence>>,      // Some(None) to clear, Some(Some(r)) to set
     pub add_tags: Vec<String>,
     pub remove_tags: Vec<String>,
 }
@@ -77,6 +85,7 @@ pub struct ComponentUpdate {
     pub status: Option<crate::entity::ComponentStatus>,
     pub component_type: Option<Option<String>>, // Some(None) to clear, Some(Some(s)) to set
     pub owner: Option<Option<String>>,          // Some(None) to clear, Some(Some(s)) to set
+    pub priority: Option<Option<TaskPriority>>, // Some(None) to clear, Some(Some(p)) to set
     pub add_tags: Vec<String>,
     pub remove_tags: Vec<String>,
 }
@@ -88,6 +97,39 @@ pub struct LinkUpdate {
     pub content: Option<String>,
     pub url: Option<String>,
     pub link_type: Option<Option<String>>, // Some(None) to clear, Some(Some(s)) to set
+    /// Metadata from fetching the URL, replacing what was fetched before
+    pub fetched: Option<LinkFetch>,
+    pub add_tags: Vec<String>,
+    pub remove_tags: Vec<String>,
+}
+
+/// Page metadata recorded on a link when its URL is fetched
+#[derive(Debug, Clone, Default)]
+pub struct LinkFetch {
+    pub description: Option<String>,
+    pub favicon: Option<String>,
+    pub content_hash: Option<String>,
+    pub fetched_at: chrono::DateTime<chrono::Utc>,
+}
+
+/// Update payload for a milestone
+#[derive(Default)]
+pub struct MilestoneUpdate {
+    pub title: Option<String>,
+    pub content: Option<String>,
+    pub status: Option<MilestoneStatus>,
+    pub target_date: Option<Option<chrono::NaiveDate>>, // Some(None) to clear, Some(Some(date)) to set
+    pub add_tags: Vec<String>,
+    pub remove_tags: Vec<String>,
+}
+
+/// Update payload for a question
+#[derive(Default)]
+pub struct QuestionUpdate {
+    pub title: Option<String>,
+    pub content: Option<String>,
+    pub status: Option<QuestionStatus>,
+    pub answered_by: Option<Option<String>>, // Some(None) to clear, Some(Some(s)) to set
     pub add_tags: Vec<String>,
     pub remove_tags: Vec<String>,
 }
@@ -95,6 +137,213 @@ pub struct LinkUpdate {
 pub struct LoroStore {
     doc: LoroDoc,
     path: PathBuf,
+    /// ID lookup index and the document version it was built at
+    id_index: Mutex<Option<(loro::Frontiers, IdIndex)>>,
+    /// In-memory staging copy (see `staging`); never written to disk
+    staging: bool,
+    /// Recorded as `created_by` / `updated_by` when a write names no one
+    author: Option<String>,
+}
+
+/// Record a timestamp on every change and keep each commit as its own
+/// change, so entity history has one version per write.
+fn configure_doc(doc: &LoroDoc) {
+    doc.set_record_timestamp(true);
+    doc.set_change_merge_interval(0);
+}
+
+/// Loro map holding deleted entities until they are restored or purged
+const TRASH_MAP: &str = "trash";
+
+/// Loro map holding attachment metadata, keyed by attachment ID
+const ATTACHMENTS_MAP: &str = "attachments";
+
+/// Loro map from alias to the UUID of the entity it names
+const ALIASES_MAP: &str = "aliases";
+
+/// Longest alias accepted
+const MAX_ALIAS_LEN: usize = 64;
+
+/// Loro map from entity UUID to the collection it belongs to and its
+/// sequence number there
+const COLLECTIONS_MAP: &str = "collections";
+
+/// Loro map from collection name to the last sequence number handed out in
+/// it, so numbers freed by removals and moves are not reused
+const COLLECTION_SEQUENCES_MAP: &str = "collection_sequences";
+
+/// Directory inside `.medulla` holding attached files, one subdirectory
+/// per entity
+pub const ATTACHMENTS_DIR: &str = "attachments";
+
+/// A file attached to an entity, stored at
+/// `.medulla/attachments/<entity_id>/<filename>`.
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+pub struct Attachment {
+    pub id: uuid::Uuid,
+    pub entity_id: uuid::Uuid,
+    pub filename: String,
+    pub mime: String,
+    /// FNV-1a hash of the file content
+    pub hash: String,
+    pub size: u64,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub created_by: Option<String>,
+}
+
+impl Attachment {
+    /// Path of the file relative to the attachments directory
+    pub fn relative_path(&self) -> String {
+        format!("{}/{}", self.entity_id, self.filename)
+    }
+}
+
+/// MIME type of a file, from its extension
+fn guess_mime(filename: &str) -> &'static str {
+    let extension = filename
+        .rsplit_once('.')
+        .map(|(_, ext)| ext.to_ascii_lowercase())
+        .unwrap_or_default();
+    match extension.as_str() {
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "svg" => "image/svg+xml",
+        "webp" => "image/webp",
+        "pdf" => "application/pdf",
+        "json" => "application/json",
+        "md" => "text/markdown",
+        "txt" | "log" => "text/plain",
+        "csv" => "text/csv",
+        "html" | "htm" => "text/html",
+        "mmd" => "text/vnd.mermaid",
+        "drawio" | "xml" => "application/xml",
+        _ => "application/octet-stream",
+    }
+}
+
+/// An entity moved to a new sequence number because another entity,
+/// created concurrently on a different replica, had the same one.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct SequenceReassignment {
+    pub id: uuid::Uuid,
+    pub entity_type: &'static str,
+    pub title: String,
+    pub old_sequence: u32,
+    pub new_sequence: u32,
+    /// The entity that kept the sequence number
+    pub kept_by: uuid::Uuid,
+    /// Collection the numbers are in, or None for the global sequence
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub collection: Option<String>,
+}
+
+/// An entity holding a sequence number, as seen by the collision check
+struct SequenceHolder {
+    id: uuid::Uuid,
+    entity_type: &'static str,
+    title: String,
+    /// RFC 3339, so it sorts chronologically as text
+    created_at: String,
+}
+
+/// Group holders by the number they hold, keeping only numbers held more
+/// than once, with the entity that keeps the number first
+fn group_collisions<K: Ord>(
+    holders: impl IntoIterator<Item = (K, SequenceHolder)>,
+) -> Vec<(K, Vec<SequenceHolder>)> {
+    let mut by_key: std::collections::BTreeMap<K, Vec<SequenceHolder>> = Default::default();
+    for (key, holder) in holders {
+        by_key.entry(key).or_default().push(holder);
+    }
+    by_key
+        .into_iter()
+        .filter(|(_, holders)| holders.len() > 1)
+        .map(|(key, mut holders)| {
+            // The earliest-created entity keeps the number; the UUID
+            // breaks ties so every replica picks the same one
+            holders.sort_by(|a, b| a.created_at.cmp(&b.created_at).then(a.id.cmp(&b.id)));
+            (key, holders)
+        })
+        .collect()
+}
+
+/// An entity in the trash.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct TrashedEntity {
+    pub id: uuid::Uuid,
+    pub entity_type: String,
+    pub sequence_number: u32,
+    pub title: String,
+    pub deleted_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub deleted_by: Option<String>,
+    /// Relations removed with the entity, put back on restore
+    pub relations: usize,
+}
+
+/// An entity put back from the trash by `LoroStore::restore_entity`.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct RestoredEntity {
+    pub entity_type: &'static str,
+    /// Relations put back with it
+    pub relations: usize,
+    /// Relations left out because the entity at the other end is trashed
+    /// (they return when it is restored) or gone
+    pub skipped_relations: usize,
+}
+
+/// Size of `loro.db` before and after `LoroStore::compact`.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct CompactStats {
+    pub before_bytes: u64,
+    pub after_bytes: u64,
+}
+
+/// One recorded version of an entity, from the Loro change history.
+#[derive(Debug, Clone)]
+pub struct EntityVersion {
+    /// Loro peer that made the change
+    pub peer: u64,
+    /// Lamport timestamp of the change, for ordering
+    pub lamport: u32,
+    /// When the change was made (the change timestamp, or the entity's
+    /// `updated_at` for changes recorded without one)
+    pub timestamp: Option<chrono::DateTime<chrono::Utc>>,
+    /// Fields that differ from the previous version
+    pub changed_fields: Vec<String>,
+    /// Entity state after the change, or None once deleted
+    pub state: Option<serde_json::Value>,
+}
+
+/// Fields every update rewrites, so concurrent updates always touch them
+/// without that being a conflict anyone needs to look at
+const BOOKKEEPING_FIELDS: [&str; 2] = ["updated_at", "updated_by"];
+
+/// Concurrent edits to one field of an entity, found in the merged history
+/// after a sync, and the value the merge kept.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct FieldConflict {
+    pub entity_id: uuid::Uuid,
+    pub entity_type: String,
+    pub sequence_number: Option<u32>,
+    pub title: Option<String>,
+    pub field: String,
+    /// The edits, none of which saw the others, oldest first
+    pub edits: Vec<ConflictingEdit>,
+    /// Value of the field once the edits were merged
+    pub resolved: serde_json::Value,
+}
+
+/// One side of a `FieldConflict`.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct ConflictingEdit {
+    /// Loro peer that made the edit
+    pub peer: String,
+    /// `updated_by` recorded with the edit, if any
+    pub author: Option<String>,
+    pub timestamp: Option<chrono::DateTime<chrono::Utc>>,
+    /// Value the edit wrote
+    pub value: serde_json::Value,
 }
 
 impl LoroStore {
@@ -109,9 +358,10 @@ impl LoroStore {
         fs::create_dir_all(&medulla_dir)?;
 
         let doc = LoroDoc::new();
+        configure_doc(&doc);
         let path = medulla_dir.join(LORO_DB);
 
-        let store = Self { doc, path };
+        let store = Self::from_doc(doc, path);
         store.save()?;
 
         Ok(store)
@@ -128,18 +378,90 @@ impl LoroStore {
 
         let bytes = fs::read(&path)?;
         let doc = LoroDoc::new();
+        configure_doc(&doc);
         doc.import(&bytes)?;
 
-        Ok(Self { doc, path })
+        Ok(Self::from_doc(doc, path))
+    }
+
+    /// A read-only store loaded from an exported document, such as an
+    /// older copy of `loro.db`. Saving it does nothing.
+    pub fn from_bytes(bytes: &[u8], path: PathBuf) -> Result<Self> {
+        let doc = LoroDoc::new();
+        configure_doc(&doc);
+        doc.import(bytes)?;
+
+        let mut store = Self::from_doc(doc, path);
+        store.staging = true;
+        Ok(store)
+    }
+
+    fn from_doc(doc: LoroDoc, path: PathBuf) -> Self {
+        Self {
+            doc,
+            path,
+            id_index: Mutex::new(None),
+            staging: false,
+            author: None,
+        }
+    }
+
+    /// Attribute writes that name no author to `author`.
+    pub fn with_author(mut self, author: Option<String>) -> Self {
+        self.author = author;
+        self
+    }
+
+    /// The author writes are attributed to by default.
+    pub fn author(&self) -> Option<&str> {
+        self.author.as_deref()
+    }
+
+    /// Record who made an update to an entity.
+    fn stamp_updated_by(&self, entity_map: &LoroMap) -> Result<()> {
+        if let Some(ref author) = self.author {
+            entity_map.insert("updated_by", author.clone())?;
+        }
+        Ok(())
+    }
+
+    /// An in-memory copy of the store for trying out a set of changes.
+    /// Saving the copy does nothing; `apply_staged` merges its changes back
+    /// into the store it was made from.
+    pub fn staging(&self) -> Self {
+        let doc = self.doc.fork();
+        configure_doc(&doc);
+        let mut store = Self::from_doc(doc, self.path.clone());
+        store.staging = true;
+        store.author = self.author.clone();
+        store
+    }
+
+    /// Merge the changes made to a staging copy into this store, as a
+    /// single import. The caller saves.
+    pub fn apply_staged(&self, staged: &LoroStore) -> Result<()> {
+        let updates = staged
+            .doc
+            .export(loro::ExportMode::updates(&self.doc.oplog_vv()))?;
+        self.doc.import(&updates)?;
+        Ok(())
     }
 
     /// Save the document to disk
     pub fn save(&self) -> Result<()> {
+        if self.staging {
+            return Ok(());
+        }
         let bytes = self.doc.export(loro::ExportMode::Snapshot)?;
         fs::write(&self.path, bytes)?;
         Ok(())
     }
 
+    /// Path of the Loro document file (`.medulla/loro.db`)
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
     /// Get the medulla directory path
     pub fn medulla_dir(&self) -> &Path {
         self.path.parent().unwrap()
@@ -161,7 +483,11 @@ impl LoroStore {
         let prompts = self.list_prompts()?;
         let components = self.list_components()?;
         let links = self.list_links()?;
+        let questions = self.list_questions()?;
+        let milestones = self.list_milestones()?;
         let relations = self.list_relations()?;
+        let aliases = self.list_aliases()?;
+        let collections = self.list_collection_members()?;
         let version = self.version_hash();
 
         cache.sync_from_loro_full(
@@ -171,7 +497,11 @@ impl LoroStore {
             &prompts,
             &components,
             &links,
+            &questions,
+            &milestones,
             &relations,
+            &aliases,
+            &collections,
             &version,
         )
     }
@@ -214,6 +544,582 @@ impl LoroStore {
         Ok(())
     }
 
+    /// Resolve a sequence number, alias or UUID prefix to an entity's UUID
+    /// and type, optionally only among entities of one type. When a prefix
+    /// matches several entities, the lowest type in decision, task, note,
+    /// prompt, component, link, question, milestone order wins, then the lowest
+    /// sequence number.
+    ///
+    /// Backed by an index rebuilt only when the document has changed since
+    /// the last lookup.
+    pub fn lookup_id(
+        &self,
+        id: &str,
+        entity_type: Option<&str>,
+    ) -> Option<(uuid::Uuid, &'static str)> {
+        let frontiers = self.doc.oplog_frontiers();
+        let mut guard = self.id_index.lock().unwrap_or_else(|e| e.into_inner());
+        if guard
+            .as_ref()
+            .map_or(true, |(built_at, _)| *built_at != frontiers)
+        {
+            *guard = Some((frontiers, self.build_id_index()));
+        }
+        guard
+            .as_ref()
+            .and_then(|(_, index)| index.lookup(id, entity_type))
+    }
+
+    /// IDs of every entity in the store, trashed ones excluded
+    pub fn entity_ids(&self) -> HashSet<String> {
+        let mut ids = HashSet::new();
+        for (_, map_name) in ENTITY_MAPS {
+            if let LoroValue::Map(map) = self.doc.get_map(map_name).get_deep_value() {
+                ids.extend(map.keys().cloned());
+            }
+        }
+        ids
+    }
+
+    fn build_id_index(&self) -> IdIndex {
+        let mut items = Vec::new();
+        for (rank, (_, map_name)) in ENTITY_MAPS.iter().enumerate() {
+            let LoroValue::Map(map) = self.doc.get_map(*map_name).get_deep_value() else {
+                continue;
+            };
+            for (key, value) in map.iter() {
+                let LoroValue::Map(entity_map) = value else {
+                    continue;
+                };
+                let (Ok(id), Some(LoroValue::I64(seq))) = (
+                    uuid::Uuid::parse_str(key),
+                    entity_map.get("sequence_number"),
+                ) else {
+                    continue;
+                };
+                items.push((id, rank, *seq as u32));
+            }
+        }
+        IdIndex::new(items)
+            .with_aliases(self.alias_entries())
+            .with_collections(self.collection_entries())
+    }
+
+    /// Replace the focus set (the entity IDs a session is currently working with)
+    pub fn set_focus(&self, ids: &[uuid::Uuid]) -> Result<()> {
+        let meta = self.doc.get_map("_meta");
+        let focus_list = meta.get_or_create_container("focus", loro::LoroList::new())?;
+        if focus_list.len() > 0 {
+            focus_list.delete(0, focus_list.len())?;
+        }
+        for id in ids {
+            focus_list.push(id.to_string())?;
+        }
+        Ok(())
+    }
+
+    /// Get the focus set, in the order it was set
+    pub fn get_focus(&self) -> Result<Vec<uuid::Uuid>> {
+        let meta = self.doc.get_map("_meta");
+        let ids = match meta.get("focus") {
+            Some(ValueOrContainer::Container(loro::Container::List(list))) => {
+                match list.get_deep_value() {
+                    LoroValue::List(items) => items
+                        .iter()
+                        .filter_map(|item| match item {
+                            LoroValue::String(s) => uuid::Uuid::parse_str(s).ok(),
+                            _ => None,
+                        })
+                        .collect(),
+                    _ => Vec::new(),
+                }
+            }
+            _ => Vec::new(),
+        };
+        Ok(ids)
+    }
+
+    /// Archive or unarchive an entity of any type. Returns the entity type.
+    pub fn set_archived(&self, id: &uuid::Uuid, archived: bool) -> Result<&'static str> {
+        let id_str = id.to_string();
+
+        for (entity_type, map_name) in ENTITY_MAPS {
+            if let Some(ValueOrContainer::Container(loro::Container::Map(entity_map))) =
+                self.doc.get_map(map_name).get(&id_str)
+            {
+                entity_map.insert("archived", archived)?;
+                entity_map.insert("updated_at", chrono::Utc::now().to_rfc3339())?;
+                self.doc.commit();
+                return Ok(entity_type);
+            }
+        }
+
+        Err(MedullaError::EntityNotFound(id_str))
+    }
+
+    /// Move an entity of any type, and every relation to or from it, into
+    /// the trash. It then no longer appears anywhere until restored.
+    /// Returns the entity type.
+    pub fn trash_entity(&self, id: &uuid::Uuid, deleted_by: Option<&str>) -> Result<&'static str> {
+        let entity_type = self.move_to_trash(id, deleted_by)?;
+        self.doc.commit();
+        Ok(entity_type)
+    }
+
+    /// Body of [`Self::trash_entity`], leaving the commit to the caller
+    fn move_to_trash(&self, id: &uuid::Uuid, deleted_by: Option<&str>) -> Result<&'static str> {
+        let id_str = id.to_string();
+
+        for (entity_type, map_name) in ENTITY_MAPS {
+            let entities = self.doc.get_map(map_name);
+            let Some(ValueOrContainer::Container(loro::Container::Map(entity_map))) =
+                entities.get(&id_str)
+            else {
+                continue;
+            };
+
+            let record = self
+                .doc
+                .get_map(TRASH_MAP)
+                .get_or_create_container(&id_str, LoroMap::new())?;
+            record.insert("entity_type", entity_type)?;
+            record.insert("deleted_at", chrono::Utc::now().to_rfc3339())?;
+            if let Some(deleted_by) = deleted_by {
+                record.insert("deleted_by", deleted_by)?;
+            }
+            write_value(&record, "entity", &entity_map.get_deep_value())?;
+
+            let relations = self.doc.get_map("relations");
+            let moved = record.get_or_create_container("relations", LoroMap::new())?;
+            if let LoroValue::Map(all) = relations.get_deep_value() {
+                for (key, value) in all.iter() {
+                    let LoroValue::Map(relation) = value else {
+                        continue;
+                    };
+                    if map_str(relation, "source_id") == Some(id_str.as_str())
+                        || map_str(relation, "target_id") == Some(id_str.as_str())
+                    {
+                        write_value(&moved, key, value)?;
+                        relations.delete(key)?;
+                    }
+                }
+            }
+
+            entities.delete(&id_str)?;
+            return Ok(entity_type);
+        }
+
+        Err(MedullaError::EntityNotFound(id_str))
+    }
+
+    /// Entities in the trash, most recently deleted first
+    pub fn list_trash(&self) -> Result<Vec<TrashedEntity>> {
+        let mut trashed = Vec::new();
+
+        if let LoroValue::Map(map) = self.doc.get_map(TRASH_MAP).get_deep_value() {
+            for (key, value) in map.iter() {
+                let (Ok(id), LoroValue::Map(record)) = (uuid::Uuid::parse_str(key), value) else {
+                    continue;
+                };
+                let Some(LoroValue::Map(entity)) = record.get("entity") else {
+                    continue;
+                };
+                trashed.push(TrashedEntity {
+                    id,
+                    entity_type: map_str(record, "entity_type")
+                        .unwrap_or_default()
+                        .to_string(),
+                    sequence_number: match entity.get("sequence_number") {
+                        Some(LoroValue::I64(n)) => *n as u32,
+                        _ => 0,
+                    },
+                    title: map_str(entity, "title").unwrap_or_default().to_string(),
+                    deleted_at: map_str(record, "deleted_at")
+                        .and_then(|s| chrono::DateTime::parse_from_rfc3339(s).ok())
+                        .map(|dt| dt.with_timezone(&chrono::Utc)),
+                    deleted_by: map_str(record, "deleted_by").map(String::from),
+                    relations: match record.get("relations") {
+                        Some(LoroValue::Map(relations)) => relations.len(),
+                        _ => 0,
+                    },
+                });
+            }
+        }
+
+        trashed.sort_by(|a, b| {
+            b.deleted_at
+                .cmp(&a.deleted_at)
+                .then(a.sequence_number.cmp(&b.sequence_number))
+        });
+        Ok(trashed)
+    }
+
+    /// Put a trashed entity back, with those of its relations whose other
+    /// end is live. A relation to another trashed entity moves to that
+    /// entity's trash record, to come back when it does; one to an entity
+    /// that no longer exists is dropped.
+    pub fn restore_entity(&self, id: &uuid::Uuid) -> Result<RestoredEntity> {
+        let id_str = id.to_string();
+        let trash = self.doc.get_map(TRASH_MAP);
+
+        let record = match trash.get_deep_value() {
+            LoroValue::Map(map) => match map.get(&id_str) {
+                Some(LoroValue::Map(record)) => record.clone(),
+                _ => return Err(MedullaError::EntityNotFound(id_str)),
+            },
+            _ => return Err(MedullaError::EntityNotFound(id_str)),
+        };
+        let entity_type = map_str(&record, "entity_type").unwrap_or_default();
+        let Some((entity_type, map_name)) =
+            ENTITY_MAPS.into_iter().find(|(t, _)| *t == entity_type)
+        else {
+            return Err(MedullaError::Storage(format!(
+                "Trashed entity {} has unknown type '{}'",
+                id_str, entity_type
+            )));
+        };
+        let Some(entity) = record.get("entity") else {
+            return Err(MedullaError::EntityNotFound(id_str));
+        };
+
+        let entities = self.doc.get_map(map_name);
+        if entities.get(&id_str).is_some() {
+            return Err(MedullaError::Storage(format!(
+                "{} {} already exists",
+                entity_type, id_str
+            )));
+        }
+        write_value(&entities, &id_str, entity)?;
+
+        let mut restored = RestoredEntity {
+            entity_type,
+            relations: 0,
+            skipped_relations: 0,
+        };
+        if let Some(LoroValue::Map(relations)) = record.get("relations") {
+            let live = self.doc.get_map("relations");
+            let live_ids = self.entity_ids();
+            for (key, value) in relations.iter() {
+                let LoroValue::Map(relation) = value else {
+                    continue;
+                };
+                let other = [
+                    map_str(relation, "source_id"),
+                    map_str(relation, "target_id"),
+                ]
+                .into_iter()
+                .flatten()
+                .find(|end| *end != id_str)
+                .unwrap_or(&id_str);
+                if live_ids.contains(other) {
+                    if live.get(key).is_none() {
+                        write_value(&live, key, value)?;
+                    }
+                    restored.relations += 1;
+                    continue;
+                }
+                restored.skipped_relations += 1;
+                if let Some(ValueOrContainer::Container(loro::Container::Map(other_record))) =
+                    trash.get(other)
+                {
+                    let moved =
+                        other_record.get_or_create_container("relations", LoroMap::new())?;
+                    write_value(&moved, key, value)?;
+                }
+            }
+        }
+
+        trash.delete(&id_str)?;
+        self.doc.commit();
+        Ok(restored)
+    }
+
+    /// Permanently delete one trashed entity, or with None the whole trash.
+    /// Returns how many entities were purged.
+    pub fn purge_trash(&self, id: Option<&uuid::Uuid>) -> Result<usize> {
+        let trash = self.doc.get_map(TRASH_MAP);
+        let keys: Vec<String> = match id {
+            Some(id) => {
+                let id_str = id.to_string();
+                if trash.get(&id_str).is_none() {
+                    return Err(MedullaError::EntityNotFound(id_str));
+                }
+                vec![id_str]
+            }
+            None => match trash.get_deep_value() {
+                LoroValue::Map(map) => map.keys().cloned().collect(),
+                _ => Vec::new(),
+            },
+        };
+
+        for key in &keys {
+            trash.delete(key)?;
+            self.remove_attachments(key)?;
+            self.remove_aliases_of(key)?;
+            self.doc.get_map(COLLECTIONS_MAP).delete(key)?;
+        }
+        self.doc.commit();
+        Ok(keys.len())
+    }
+
+    /// Directory holding attached files (`.medulla/attachments`)
+    pub fn attachments_dir(&self) -> PathBuf {
+        self.medulla_dir().join(ATTACHMENTS_DIR)
+    }
+
+    /// Copy a file into the attachments directory and record it on an
+    /// entity. A file of the same name already attached to the entity is
+    /// replaced.
+    pub fn attach_file(
+        &self,
+        entity_id: &uuid::Uuid,
+        source: &Path,
+        created_by: Option<&str>,
+    ) -> Result<Attachment> {
+        let entity_str = entity_id.to_string();
+        if !ENTITY_MAPS
+            .iter()
+            .any(|(_, map_name)| self.doc.get_map(*map_name).get(&entity_str).is_some())
+        {
+            return Err(MedullaError::EntityNotFound(entity_str));
+        }
+        let filename = source
+            .file_name()
+            .and_then(|n| n.to_str())
+            .filter(|n| !n.starts_with('.'))
+            .ok_or_else(|| {
+                MedullaError::Storage(format!("Not an attachable file: {}", source.display()))
+            })?
+            .to_string();
+        let bytes = fs::read(source)?;
+
+        let existing = self
+            .list_attachments(Some(entity_id))?
+            .into_iter()
+            .find(|a| a.filename == filename);
+        let attachment = Attachment {
+            id: existing.map_or_else(uuid::Uuid::new_v4, |a| a.id),
+            entity_id: *entity_id,
+            mime: guess_mime(&filename).to_string(),
+            hash: crate::snapshot::utils::bytes_hash(&bytes),
+            size: bytes.len() as u64,
+            created_at: chrono::Utc::now(),
+            created_by: created_by.map(str::to_string),
+            filename,
+        };
+
+        let path = self.attachments_dir().join(attachment.relative_path());
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&path, &bytes)?;
+
+        let record = self
+            .doc
+            .get_map(ATTACHMENTS_MAP)
+            .get_or_create_container(&attachment.id.to_string(), LoroMap::new())?;
+        record.insert("entity_id", entity_str)?;
+        record.insert("filename", attachment.filename.as_str())?;
+        record.insert("mime", attachment.mime.as_str())?;
+        record.insert("hash", attachment.hash.as_str())?;
+        record.insert("size", attachment.size as i64)?;
+        record.insert("created_at", attachment.created_at.to_rfc3339())?;
+        if let Some(created_by) = created_by {
+            record.insert("created_by", created_by)?;
+        }
+        self.doc.commit();
+
+        Ok(attachment)
+    }
+
+    /// Attachments of one entity, or of all entities, by filename
+    pub fn list_attachments(&self, entity_id: Option<&uuid::Uuid>) -> Result<Vec<Attachment>> {
+        let mut attachments = Vec::new();
+        let LoroValue::Map(map) = self.doc.get_map(ATTACHMENTS_MAP).get_deep_value() else {
+            return Ok(attachments);
+        };
+        for (key, value) in map.iter() {
+            let LoroValue::Map(record) = value else {
+                continue;
+            };
+            let (Ok(id), Some(Ok(owner))) = (
+                uuid::Uuid::parse_str(key),
+                map_str(record, "entity_id").map(uuid::Uuid::parse_str),
+            ) else {
+                continue;
+            };
+            if entity_id.is_some_and(|e| *e != owner) {
+                continue;
+            }
+            attachments.push(Attachment {
+                id,
+                entity_id: owner,
+                filename: map_str(record, "filename").unwrap_or_default().to_string(),
+                mime: map_str(record, "mime").unwrap_or_default().to_string(),
+                hash: map_str(record, "hash").unwrap_or_default().to_string(),
+                size: match record.get("size") {
+                    Some(LoroValue::I64(n)) => *n as u64,
+                    _ => 0,
+                },
+                created_at: map_str(record, "created_at")
+                    .and_then(|s| chrono::DateTime::parse_from_rfc3339(s).ok())
+                    .map(|dt| dt.with_timezone(&chrono::Utc))
+                    .unwrap_or_default(),
+                created_by: map_str(record, "created_by").map(str::to_string),
+            });
+        }
+        attachments.sort_by(|a, b| (a.entity_id, &a.filename).cmp(&(b.entity_id, &b.filename)));
+        Ok(attachments)
+    }
+
+    /// Drop the attachment records and files of an entity. The caller commits.
+    fn remove_attachments(&self, entity_id: &str) -> Result<()> {
+        let Ok(id) = uuid::Uuid::parse_str(entity_id) else {
+            return Ok(());
+        };
+        let map = self.doc.get_map(ATTACHMENTS_MAP);
+        for attachment in self.list_attachments(Some(&id))? {
+            map.delete(&attachment.id.to_string())?;
+        }
+        if !self.staging {
+            let dir = self.attachments_dir().join(entity_id);
+            if dir.exists() {
+                fs::remove_dir_all(dir)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Fold `source` into `target`: the source's content is appended to the
+    /// target's, its tags are added to the target's, and relations to or from
+    /// it are copied onto the target, keeping their history. The source is
+    /// then archived, or moved to the trash along with its original
+    /// relations. Both must be of the same type, and everything is one
+    /// commit. Returns the entity type.
+    pub fn merge_entities(
+        &self,
+        target: &uuid::Uuid,
+        source: &uuid::Uuid,
+        merged_by: Option<&str>,
+        archive_source: bool,
+    ) -> Result<&'static str> {
+        let (target_str, source_str) = (target.to_string(), source.to_string());
+        if target_str == source_str {
+            return Err(MedullaError::Storage(
+                "cannot merge an entity into itself".to_string(),
+            ));
+        }
+
+        let find = |id: &str| {
+            ENTITY_MAPS.into_iter().find_map(|(entity_type, map_name)| {
+                match self.doc.get_map(map_name).get(id) {
+                    Some(ValueOrContainer::Container(loro::Container::Map(map))) => {
+                        Some((entity_type, map))
+                    }
+                    _ => None,
+                }
+            })
+        };
+        let (target_type, target_map) =
+            find(&target_str).ok_or_else(|| MedullaError::EntityNotFound(target_str.clone()))?;
+        let (source_type, source_map) =
+            find(&source_str).ok_or_else(|| MedullaError::EntityNotFound(source_str.clone()))?;
+        if target_type != source_type {
+            return Err(MedullaError::InvalidEntityType(format!(
+                "cannot merge a {} into a {}",
+                source_type, target_type
+            )));
+        }
+
+        let content_of = |map: &LoroMap| match map.get("content") {
+            Some(ValueOrContainer::Value(LoroValue::String(s))) if !s.trim().is_empty() => {
+                Some(s.to_string())
+            }
+            _ => None,
+        };
+        if let Some(source_content) = content_of(&source_map) {
+            let merged = match content_of(&target_map) {
+                Some(existing) if existing.contains(source_content.trim()) => existing,
+                Some(existing) => format!("{}\n\n{}", existing.trim_end(), source_content),
+                None => source_content,
+            };
+            target_map.insert("content", merged)?;
+        }
+
+        let tags_of = |map: &LoroMap| -> Vec<String> {
+            match map.get("tags") {
+                Some(ValueOrContainer::Container(loro::Container::List(list))) => {
+                    match list.get_deep_value() {
+                        LoroValue::List(items) => items
+                            .iter()
+                            .filter_map(|item| match item {
+                                LoroValue::String(s) => Some(s.to_string()),
+                                _ => None,
+                            })
+                            .collect(),
+                        _ => Vec::new(),
+                    }
+                }
+                _ => Vec::new(),
+            }
+        };
+        let existing = tags_of(&target_map);
+        let new_tags: Vec<String> = tags_of(&source_map)
+            .into_iter()
+            .filter(|t| !existing.contains(t))
+            .collect();
+        if !new_tags.is_empty() {
+            let tags_list = target_map.get_or_create_container("tags", loro::LoroList::new())?;
+            for tag in new_tags {
+                tags_list.push(tag)?;
+            }
+        }
+        target_map.insert("updated_at", chrono::Utc::now().to_rfc3339())?;
+
+        let relations_map = self.doc.get_map("relations");
+        for relation in self.list_relations()? {
+            let mut moved = relation.clone();
+            if relation.source_id == *source {
+                moved.source_id = *target;
+            }
+            if relation.target_id == *source {
+                moved.target_id = *target;
+            }
+            if moved.source_id == relation.source_id && moved.target_id == relation.target_id {
+                continue;
+            }
+            if moved.source_id == moved.target_id
+                || relations_map.get(&moved.composite_key()).is_some()
+            {
+                continue;
+            }
+            self.insert_relation(&moved)?;
+        }
+
+        if archive_source {
+            source_map.insert("archived", true)?;
+            source_map.insert("updated_at", chrono::Utc::now().to_rfc3339())?;
+            // The source's relations now live on the target
+            if let LoroValue::Map(all) = relations_map.get_deep_value() {
+                for (key, value) in all.iter() {
+                    let LoroValue::Map(relation) = value else {
+                        continue;
+                    };
+                    if map_str(relation, "source_id") == Some(source_str.as_str())
+                        || map_str(relation, "target_id") == Some(source_str.as_str())
+                    {
+                        relations_map.delete(key)?;
+                    }
+                }
+            }
+        } else {
+            self.move_to_trash(source, merged_by)?;
+        }
+
+        self.doc.commit();
+        Ok(target_type)
+    }
+
     /// Get a decision by UUID
     pub fn get_decision(&self, id: &uuid::Uuid) -> Result<Option<Decision>> {
         let decisions_map = self.doc.get_map("decisions");
@@ -242,6 +1148,7 @@ impl LoroStore {
         // Apply updates
         let now = chrono::Utc::now();
         entity_map.insert("updated_at", now.to_rfc3339())?;
+        self.stamp_updated_by(&entity_map)?;
 
         if let Some(title) = updates.title {
             entity_map.insert("title", title)?;
@@ -265,6 +1172,13 @@ impl LoroStore {
             }
         }
 
+        if let Some(priority_opt) = updates.priority {
+            match priority_opt {
+                Some(priority) => entity_map.insert("priority", priority.to_string())?,
+                None => entity_map.delete("priority")?,
+            };
+        }
+
         // Handle tag additions and removals
         if !updates.add_tags.is_empty() || !updates.remove_tags.is_empty() {
             // Get existing tags
@@ -351,10 +1265,14 @@ impl LoroStore {
             entity_map.insert("content", content.clone())?;
         }
 
-        if let Some(ref created_by) = decision.base.created_by {
+        if let Some(created_by) = decision.base.created_by.as_ref().or(self.author.as_ref()) {
             entity_map.insert("created_by", created_by.clone())?;
         }
 
+        if decision.base.archived {
+            entity_map.insert("archived", true)?;
+        }
+
         // Store decision-specific fields
         entity_map.insert("status", decision.status.to_string())?;
 
@@ -366,6 +1284,10 @@ impl LoroStore {
             entity_map.insert("superseded_by", superseded_by.clone())?;
         }
 
+        if let Some(priority) = decision.priority {
+            entity_map.insert("priority", priority.to_string())?;
+        }
+
         // Store tags as LoroList
         let tags_list = entity_map.get_or_create_container("tags", loro::LoroList::new())?;
         for tag in &decision.base.tags {
@@ -410,6 +1332,13 @@ impl LoroStore {
 
     /// Add a relation to the store
     pub fn add_relation(&self, relation: &Relation) -> Result<()> {
+        self.insert_relation(relation)?;
+        self.doc.commit();
+        Ok(())
+    }
+
+    /// Body of [`Self::add_relation`], leaving the commit to the caller
+    fn insert_relation(&self, relation: &Relation) -> Result<()> {
         let relations_map = self.doc.get_map("relations");
         let key = relation.composite_key();
 
@@ -426,13 +1355,16 @@ impl LoroStore {
             relation_map.insert("created_by", created_by.clone())?;
         }
 
+        if let Some(resolved_at) = relation.resolved_at {
+            relation_map.insert("resolved_at", resolved_at.to_rfc3339())?;
+        }
+
         // Store properties as a nested LoroMap
         let props_map = relation_map.get_or_create_container("properties", LoroMap::new())?;
         for (k, v) in &relation.properties {
             props_map.insert(k, v.clone())?;
         }
 
-        self.doc.commit();
         Ok(())
     }
 
@@ -451,13 +1383,38 @@ impl LoroStore {
         Ok(())
     }
 
-    /// List all relations
-    pub fn list_relations(&self) -> Result<Vec<Relation>> {
+    /// Mark a relation as resolved, keeping it for history.
+    /// Returns the updated relation.
+    pub fn resolve_relation(
+        &self,
+        source_id: &str,
+        relation_type: &str,
+        target_id: &str,
+    ) -> Result<Relation> {
         let relations_map = self.doc.get_map("relations");
-        let mut relations = Vec::new();
+        let key = format!("{}:{}:{}", source_id, relation_type, target_id);
 
-        let json = relations_map.get_deep_value();
-        if let LoroValue::Map(map) = json {
+        let relation_map = match relations_map.get(&key) {
+            Some(ValueOrContainer::Container(loro::Container::Map(map))) => map,
+            _ => return Err(MedullaError::EntityNotFound(key)),
+        };
+
+        relation_map.insert("resolved_at", chrono::Utc::now().to_rfc3339())?;
+        self.doc.commit();
+
+        self.list_relations()?
+            .into_iter()
+            .find(|r| r.composite_key() == key)
+            .ok_or(MedullaError::EntityNotFound(key))
+    }
+
+    /// List all relations
+    pub fn list_relations(&self) -> Result<Vec<Relation>> {
+        let relations_map = self.doc.get_map("relations");
+        let mut relations = Vec::new();
+
+        let json = relations_map.get_deep_value();
+        if let LoroValue::Map(map) = json {
             for (_, relation_value) in map.iter() {
                 if let LoroValue::Map(relation_map) = relation_value {
                     if let Some(relation) = self.parse_relation_from_map(relation_map) {
@@ -490,6 +1447,55 @@ impl LoroStore {
             .collect())
     }
 
+    /// Add a `references` relation from an entity to each entity its text
+    /// mentions as `#<seq>` or `med-<seq>`. Mentions of itself, of unknown
+    /// sequence numbers and of entities it already references are skipped.
+    /// The new relations are marked with an `inferred` property and returned.
+    pub fn add_inferred_references(
+        &self,
+        source_id: uuid::Uuid,
+        source_type: &str,
+        text: &str,
+        created_by: Option<String>,
+    ) -> Result<Vec<Relation>> {
+        let mentions = crate::entity::entity_mentions(text);
+        if mentions.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let existing: std::collections::HashSet<uuid::Uuid> = self
+            .get_relations_from(&source_id.to_string())?
+            .into_iter()
+            .filter(|r| r.relation_type == RelationType::References)
+            .map(|r| r.target_id)
+            .collect();
+
+        let mut added = Vec::new();
+        for seq in mentions {
+            let Some((target_id, target_type)) = self.lookup_id(&seq.to_string(), None) else {
+                continue;
+            };
+            if target_id == source_id || existing.contains(&target_id) {
+                continue;
+            }
+            let mut relation = Relation::new(
+                source_id,
+                source_type.to_string(),
+                target_id,
+                target_type.to_string(),
+                RelationType::References,
+            );
+            relation.created_by = created_by.clone();
+            relation
+                .properties
+                .insert("inferred".to_string(), "true".to_string());
+            self.add_relation(&relation)?;
+            added.push(relation);
+        }
+
+        Ok(added)
+    }
+
     fn parse_relation_from_map(&self, map: &loro::LoroMapValue) -> Option<Relation> {
         let source_id = match map.get("source_id")? {
             LoroValue::String(s) => s.parse().ok()?,
@@ -512,7 +1518,7 @@ impl LoroStore {
         };
 
         let relation_type: RelationType = match map.get("relation_type")? {
-            LoroValue::String(s) => s.parse().ok()?,
+            LoroValue::String(s) => RelationType::from_stored(s),
             _ => return None,
         };
 
@@ -544,6 +1550,13 @@ impl LoroStore {
             })
             .unwrap_or_default();
 
+        let resolved_at = map.get("resolved_at").and_then(|v| match v {
+            LoroValue::String(s) => chrono::DateTime::parse_from_rfc3339(s)
+                .ok()
+                .map(|dt| dt.with_timezone(&chrono::Utc)),
+            _ => None,
+        });
+
         Some(Relation {
             source_id,
             source_type,
@@ -553,6 +1566,7 @@ impl LoroStore {
             created_at,
             created_by,
             properties,
+            resolved_at,
         })
     }
 
@@ -644,6 +1658,11 @@ impl LoroStore {
             _ => None,
         });
 
+        let priority = map.get("priority").and_then(|v| match v {
+            LoroValue::String(s) => s.parse().ok(),
+            _ => None,
+        });
+
         Some(Decision {
             base: crate::entity::EntityBase {
                 id,
@@ -653,12 +1672,15 @@ impl LoroStore {
                 created_at,
                 updated_at,
                 created_by,
+                updated_by: map_str(map, "updated_by").map(str::to_string),
                 sequence_number,
+                archived: matches!(map.get("archived"), Some(LoroValue::Bool(true))),
             },
             status,
             context,
             consequences,
             superseded_by,
+            priority,
         })
     }
 
@@ -681,10 +1703,14 @@ impl LoroStore {
             entity_map.insert("content", content.clone())?;
         }
 
-        if let Some(ref created_by) = task.base.created_by {
+        if let Some(created_by) = task.base.created_by.as_ref().or(self.author.as_ref()) {
             entity_map.insert("created_by", created_by.clone())?;
         }
 
+        if task.base.archived {
+            entity_map.insert("archived", true)?;
+        }
+
         // Store task-specific fields
         entity_map.insert("status", task.status.to_string())?;
         entity_map.insert("priority", task.priority.to_string())?;
@@ -697,6 +1723,14 @@ impl LoroStore {
             entity_map.insert("assignee", assignee.clone())?;
         }
 
+        if let Some(estimate) = task.estimate_minutes {
+            entity_map.insert("estimate_minutes", estimate as i64)?;
+        }
+
+        if let Some(ref recurrence) = task.recurrence {
+            entity_map.insert("recurrence", recurrence.to_string())?;
+        }
+
         // Store tags as LoroList
         let tags_list = entity_map.get_or_create_container("tags", loro::LoroList::new())?;
         for tag in &task.base.tags {
@@ -744,6 +1778,20 @@ impl LoroStore {
         Ok(tasks)
     }
 
+    /// Add the next occurrence of a recurring task that has just been
+    /// completed. Returns the new task, or None if the task does not recur.
+    pub fn add_next_occurrence(
+        &self,
+        task: &Task,
+        today: chrono::NaiveDate,
+    ) -> Result<Option<Task>> {
+        let Some(next) = task.next_occurrence(self.next_sequence_number(), today) else {
+            return Ok(None);
+        };
+        self.add_task(&next)?;
+        Ok(Some(next))
+    }
+
     /// Update an existing task
     pub fn update_task(&self, id: &uuid::Uuid, updates: TaskUpdate) -> Result<()> {
         let tasks_map = self.doc.get_map("tasks");
@@ -756,6 +1804,7 @@ impl LoroStore {
 
         let now = chrono::Utc::now();
         entity_map.insert("updated_at", now.to_rfc3339())?;
+        self.stamp_updated_by(&entity_map)?;
 
         if let Some(title) = updates.title {
             entity_map.insert("title", title)?;
@@ -787,6 +1836,20 @@ impl LoroStore {
             };
         }
 
+        if let Some(estimate_opt) = updates.estimate_minutes {
+            match estimate_opt {
+                Some(estimate) => entity_map.insert("estimate_minutes", estimate as i64)?,
+                None => entity_map.delete("estimate_minutes")?,
+            };
+        }
+
+        if let Some(recurrence_opt) = updates.recurrence {
+            match recurrence_opt {
+                Some(recurrence) => entity_map.insert("recurrence", recurrence.to_string())?,
+                None => entity_map.delete("recurrence")?,
+            };
+        }
+
         // Handle tag updates (same pattern as decisions)
         if !updates.add_tags.is_empty() || !updates.remove_tags.is_empty() {
             let existing_tags: Vec<String> = entity_map
@@ -914,6 +1977,16 @@ impl LoroStore {
             _ => None,
         });
 
+        let estimate_minutes = map.get("estimate_minutes").and_then(|v| match v {
+            LoroValue::I64(n) => u32::try_from(*n).ok(),
+            _ => None,
+        });
+
+        let recurrence = map.get("recurrence").and_then(|v| match v {
+            LoroValue::String(s) => s.parse::<Recurrence>().ok(),
+            _ => None,
+        });
+
         let tags = map
             .get("tags")
             .and_then(|v| match v {
@@ -938,12 +2011,16 @@ impl LoroStore {
                 created_at,
                 updated_at,
                 created_by,
+                updated_by: map_str(map, "updated_by").map(str::to_string),
                 sequence_number,
+                archived: matches!(map.get("archived"), Some(LoroValue::Bool(true))),
             },
             status,
             priority,
             due_date,
             assignee,
+            estimate_minutes,
+            recurrence,
         })
     }
 
@@ -966,9 +2043,13 @@ impl LoroStore {
         if let Some(ref content) = note.base.content {
             entity_map.insert("content", content.clone())?;
         }
-        if let Some(ref created_by) = note.base.created_by {
+        if let Some(created_by) = note.base.created_by.as_ref().or(self.author.as_ref()) {
             entity_map.insert("created_by", created_by.clone())?;
         }
+
+        if note.base.archived {
+            entity_map.insert("archived", true)?;
+        }
         if let Some(ref note_type) = note.note_type {
             entity_map.insert("note_type", note_type.clone())?;
         }
@@ -1047,6 +2128,7 @@ impl LoroStore {
         // Apply updates
         let now = chrono::Utc::now();
         entity_map.insert("updated_at", now.to_rfc3339())?;
+        self.stamp_updated_by(&entity_map)?;
 
         if let Some(title) = updates.title {
             entity_map.insert("title", title)?;
@@ -1176,7 +2258,9 @@ impl LoroStore {
                 created_at,
                 updated_at,
                 created_by,
+                updated_by: map_str(map, "updated_by").map(str::to_string),
                 sequence_number,
+                archived: matches!(map.get("archived"), Some(LoroValue::Bool(true))),
             },
             note_type,
         })
@@ -1201,9 +2285,13 @@ impl LoroStore {
         if let Some(ref content) = prompt.base.content {
             entity_map.insert("content", content.clone())?;
         }
-        if let Some(ref created_by) = prompt.base.created_by {
+        if let Some(created_by) = prompt.base.created_by.as_ref().or(self.author.as_ref()) {
             entity_map.insert("created_by", created_by.clone())?;
         }
+
+        if prompt.base.archived {
+            entity_map.insert("archived", true)?;
+        }
         if let Some(ref template) = prompt.template {
             entity_map.insert("template", template.clone())?;
         }
@@ -1291,6 +2379,7 @@ impl LoroStore {
         // Apply updates
         let now = chrono::Utc::now();
         entity_map.insert("updated_at", now.to_rfc3339())?;
+        self.stamp_updated_by(&entity_map)?;
 
         if let Some(title) = updates.title {
             entity_map.insert("title", title)?;
@@ -1494,7 +2583,9 @@ impl LoroStore {
                 created_at,
                 updated_at,
                 created_by,
+                updated_by: map_str(map, "updated_by").map(str::to_string),
                 sequence_number,
+                archived: matches!(map.get("archived"), Some(LoroValue::Bool(true))),
             },
             template,
             variables,
@@ -1522,9 +2613,13 @@ impl LoroStore {
         if let Some(ref content) = component.base.content {
             entity_map.insert("content", content.clone())?;
         }
-        if let Some(ref created_by) = component.base.created_by {
+        if let Some(created_by) = component.base.created_by.as_ref().or(self.author.as_ref()) {
             entity_map.insert("created_by", created_by.clone())?;
         }
+
+        if component.base.archived {
+            entity_map.insert("archived", true)?;
+        }
         if let Some(ref component_type) = component.component_type {
             entity_map.insert("component_type", component_type.clone())?;
         }
@@ -1532,6 +2627,10 @@ impl LoroStore {
             entity_map.insert("owner", owner.clone())?;
         }
 
+        if let Some(priority) = component.priority {
+            entity_map.insert("priority", priority.to_string())?;
+        }
+
         let tags_list = entity_map.get_or_create_container("tags", loro::LoroList::new())?;
         for tag in &component.base.tags {
             tags_list.push(tag.clone())?;
@@ -1606,6 +2705,7 @@ impl LoroStore {
         // Apply updates
         let now = chrono::Utc::now();
         entity_map.insert("updated_at", now.to_rfc3339())?;
+        self.stamp_updated_by(&entity_map)?;
 
         if let Some(title) = updates.title {
             entity_map.insert("title", title)?;
@@ -1633,6 +2733,13 @@ impl LoroStore {
             };
         }
 
+        if let Some(priority_opt) = updates.priority {
+            match priority_opt {
+                Some(priority) => entity_map.insert("priority", priority.to_string())?,
+                None => entity_map.delete("priority")?,
+            };
+        }
+
         // Handle tag additions and removals
         if !updates.add_tags.is_empty() || !updates.remove_tags.is_empty() {
             // Get existing tags
@@ -1748,6 +2855,11 @@ impl LoroStore {
             })
             .unwrap_or_default();
 
+        let priority = map.get("priority").and_then(|v| match v {
+            LoroValue::String(s) => s.parse().ok(),
+            _ => None,
+        });
+
         Some(Component {
             base: crate::entity::EntityBase {
                 id,
@@ -1757,11 +2869,14 @@ impl LoroStore {
                 created_at,
                 updated_at,
                 created_by,
+                updated_by: map_str(map, "updated_by").map(str::to_string),
                 sequence_number,
+                archived: matches!(map.get("archived"), Some(LoroValue::Bool(true))),
             },
             component_type,
             status,
             owner,
+            priority,
         })
     }
 
@@ -1785,12 +2900,28 @@ impl LoroStore {
         if let Some(ref content) = link.base.content {
             entity_map.insert("content", content.clone())?;
         }
-        if let Some(ref created_by) = link.base.created_by {
+        if let Some(created_by) = link.base.created_by.as_ref().or(self.author.as_ref()) {
             entity_map.insert("created_by", created_by.clone())?;
         }
+
+        if link.base.archived {
+            entity_map.insert("archived", true)?;
+        }
         if let Some(ref link_type) = link.link_type {
             entity_map.insert("link_type", link_type.clone())?;
         }
+        if let Some(ref description) = link.description {
+            entity_map.insert("description", description.clone())?;
+        }
+        if let Some(ref favicon) = link.favicon {
+            entity_map.insert("favicon", favicon.clone())?;
+        }
+        if let Some(ref content_hash) = link.content_hash {
+            entity_map.insert("content_hash", content_hash.clone())?;
+        }
+        if let Some(fetched_at) = link.fetched_at {
+            entity_map.insert("fetched_at", fetched_at.to_rfc3339())?;
+        }
 
         let tags_list = entity_map.get_or_create_container("tags", loro::LoroList::new())?;
         for tag in &link.base.tags {
@@ -1866,6 +2997,7 @@ impl LoroStore {
         // Apply updates
         let now = chrono::Utc::now();
         entity_map.insert("updated_at", now.to_rfc3339())?;
+        self.stamp_updated_by(&entity_map)?;
 
         if let Some(title) = updates.title {
             entity_map.insert("title", title)?;
@@ -1886,6 +3018,20 @@ impl LoroStore {
             };
         }
 
+        if let Some(fetched) = updates.fetched {
+            for (key, value) in [
+                ("description", fetched.description),
+                ("favicon", fetched.favicon),
+                ("content_hash", fetched.content_hash),
+            ] {
+                match value {
+                    Some(value) => entity_map.insert(key, value)?,
+                    None => entity_map.delete(key)?,
+                };
+            }
+            entity_map.insert("fetched_at", fetched.fetched_at.to_rfc3339())?;
+        }
+
         // Handle tag additions and removals
         if !updates.add_tags.is_empty() || !updates.remove_tags.is_empty() {
             // Get existing tags
@@ -2003,174 +3149,2429 @@ impl LoroStore {
                 created_at,
                 updated_at,
                 created_by,
+                updated_by: map_str(map, "updated_by").map(str::to_string),
                 sequence_number,
+                archived: matches!(map.get("archived"), Some(LoroValue::Bool(true))),
             },
             url,
             link_type,
+            description: map_str(map, "description").map(str::to_string),
+            favicon: map_str(map, "favicon").map(str::to_string),
+            content_hash: map_str(map, "content_hash").map(str::to_string),
+            fetched_at: map_str(map, "fetched_at")
+                .and_then(|s| chrono::DateTime::parse_from_rfc3339(s).ok())
+                .map(|d| d.with_timezone(&chrono::Utc)),
         })
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use tempfile::TempDir;
+    // ========== Template Methods ==========
 
-    #[test]
-    fn test_init_creates_medulla_directory() {
-        let tmp = TempDir::new().unwrap();
-        let _store = LoroStore::init(tmp.path()).unwrap();
+    /// Add or replace an entity template, keyed by name
+    pub fn put_template(&self, template: &EntityTemplate) -> Result<()> {
+        let templates_map = self.doc.get_map("templates");
+        // Replace wholesale so fields dropped from the template don't linger
+        if templates_map.get(&template.name).is_some() {
+            templates_map.delete(&template.name)?;
+        }
+        let template_map = templates_map.get_or_create_container(&template.name, LoroMap::new())?;
 
-        assert!(tmp.path().join(".medulla").exists());
-        assert!(tmp.path().join(".medulla/loro.db").exists());
-    }
+        template_map.insert("name", template.name.clone())?;
+        template_map.insert("entity_type", template.entity_type.clone())?;
+        template_map.insert("created_at", template.created_at.to_rfc3339())?;
 
-    #[test]
-    fn test_init_fails_if_already_initialized() {
-        let tmp = TempDir::new().unwrap();
-        LoroStore::init(tmp.path()).unwrap();
+        if let Some(ref description) = template.description {
+            template_map.insert("description", description.clone())?;
+        }
 
-        let result = LoroStore::init(tmp.path());
-        assert!(matches!(result, Err(MedullaError::AlreadyInitialized)));
-    }
+        if let Some(ref content) = template.content {
+            template_map.insert("content", content.clone())?;
+        }
 
-    #[test]
-    fn test_open_fails_if_not_initialized() {
-        let tmp = TempDir::new().unwrap();
+        if let Some(ref created_by) = template.created_by {
+            template_map.insert("created_by", created_by.clone())?;
+        }
 
-        let result = LoroStore::open(tmp.path());
-        assert!(matches!(result, Err(MedullaError::NotInitialized)));
-    }
+        if !template.properties.is_empty() {
+            template_map.insert("properties", serde_json::to_string(&template.properties)?)?;
+        }
 
-    #[test]
-    fn test_add_and_list_decision() {
-        let tmp = TempDir::new().unwrap();
-        let store = LoroStore::init(tmp.path()).unwrap();
+        let tags_list = template_map.get_or_create_container("tags", loro::LoroList::new())?;
+        for tag in &template.tags {
+            tags_list.push(tag.clone())?;
+        }
 
-        let mut decision = Decision::new("Use Rust".to_string(), 1);
-        decision.status = crate::entity::DecisionStatus::Accepted;
-        decision.base.tags = vec!["architecture".to_string()];
+        self.doc.commit();
+        Ok(())
+    }
 
-        store.add_decision(&decision).unwrap();
-        store.save().unwrap();
+    /// Get a template by name
+    pub fn get_template(&self, name: &str) -> Result<Option<EntityTemplate>> {
+        let templates_map = self.doc.get_map("templates");
+        let json = templates_map.get_deep_value();
+        if let LoroValue::Map(map) = json {
+            if let Some(LoroValue::Map(template_map)) = map.get(name) {
+                return Ok(self.parse_template_from_map(template_map));
+            }
+        }
+        Ok(None)
+    }
 
-        // Reopen and verify
-        let store2 = LoroStore::open(tmp.path()).unwrap();
-        let decisions = store2.list_decisions().unwrap();
+    /// List all templates, sorted by name
+    pub fn list_templates(&self) -> Result<Vec<EntityTemplate>> {
+        let templates_map = self.doc.get_map("templates");
+        let mut templates = Vec::new();
 
-        assert_eq!(decisions.len(), 1);
-        assert_eq!(decisions[0].base.title, "Use Rust");
-        assert_eq!(decisions[0].status, crate::entity::DecisionStatus::Accepted);
-    }
+        let json = templates_map.get_deep_value();
+        if let LoroValue::Map(map) = json {
+            for (_, template_value) in map.iter() {
+                if let LoroValue::Map(template_map) = template_value {
+                    if let Some(template) = self.parse_template_from_map(template_map) {
+                        templates.push(template);
+                    }
+                }
+            }
+        }
 
-    #[test]
-    fn test_add_and_list_relation() {
-        let tmp = TempDir::new().unwrap();
-        let store = LoroStore::init(tmp.path()).unwrap();
+        templates.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(templates)
+    }
 
-        // Create two decisions
-        let decision1 = Decision::new("Use Rust".to_string(), 1);
-        let decision2 = Decision::new("Use Loro".to_string(), 2);
+    /// Delete a template by name
+    pub fn delete_template(&self, name: &str) -> Result<()> {
+        let templates_map = self.doc.get_map("templates");
+        if templates_map.get(name).is_none() {
+            return Err(MedullaError::Storage(format!(
+                "Template not found: {}",
+                name
+            )));
+        }
+        templates_map.delete(name)?;
+        self.doc.commit();
+        Ok(())
+    }
 
-        store.add_decision(&decision1).unwrap();
-        store.add_decision(&decision2).unwrap();
+    // =========================================================================
+    // Relation types
+    // =========================================================================
+
+    /// Register a relation type in the document, so every copy of the
+    /// project accepts it without a config change. Replaces any previous
+    /// definition of the same name.
+    pub fn put_relation_type(&self, def: &RelationTypeDef) -> Result<()> {
+        def.check()?;
+        let types_map = self.doc.get_map("relation_types");
+        if types_map.get(&def.name).is_some() {
+            types_map.delete(&def.name)?;
+        }
+        let type_map = types_map.get_or_create_container(&def.name, LoroMap::new())?;
 
-        // Create a relation: decision2 implements decision1
-        let relation = crate::entity::Relation::new(
-            decision2.base.id,
-            "decision".to_string(),
-            decision1.base.id,
-            "decision".to_string(),
-            crate::entity::RelationType::Implements,
-        );
+        type_map.insert("name", def.name.clone())?;
+        if let Some(ref inverse) = def.inverse {
+            type_map.insert("inverse", inverse.clone())?;
+        }
+        type_map.insert("symmetric", def.symmetric)?;
 
-        store.add_relation(&relation).unwrap();
-        store.save().unwrap();
+        self.doc.commit();
+        Ok(())
+    }
 
-        // Reopen and verify
-        let store2 = LoroStore::open(tmp.path()).unwrap();
-        let relations = store2.list_relations().unwrap();
+    /// List relation types registered in the document, sorted by name
+    pub fn list_relation_types(&self) -> Result<Vec<RelationTypeDef>> {
+        let mut defs = Vec::new();
+
+        if let LoroValue::Map(map) = self.doc.get_map("relation_types").get_deep_value() {
+            for (_, value) in map.iter() {
+                let LoroValue::Map(type_map) = value else {
+                    continue;
+                };
+                let Some(name) = map_str(type_map, "name") else {
+                    continue;
+                };
+                defs.push(RelationTypeDef {
+                    name: name.to_string(),
+                    inverse: map_str(type_map, "inverse").map(String::from),
+                    symmetric: matches!(type_map.get("symmetric"), Some(LoroValue::Bool(true))),
+                });
+            }
+        }
 
-        assert_eq!(relations.len(), 1);
-        assert_eq!(relations[0].source_id, decision2.base.id);
-        assert_eq!(relations[0].target_id, decision1.base.id);
-        assert_eq!(
-            relations[0].relation_type,
-            crate::entity::RelationType::Implements
-        );
+        defs.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(defs)
     }
 
-    #[test]
-    fn test_get_relations_from_and_to() {
-        let tmp = TempDir::new().unwrap();
-        let store = LoroStore::init(tmp.path()).unwrap();
+    /// Unregister a relation type. Fails while relations of the type exist.
+    pub fn delete_relation_type(&self, name: &str) -> Result<()> {
+        let types_map = self.doc.get_map("relation_types");
+        if types_map.get(name).is_none() {
+            return Err(MedullaError::Storage(format!(
+                "Relation type not registered: {}",
+                name
+            )));
+        }
 
-        let decision1 = Decision::new("Decision A".to_string(), 1);
-        let decision2 = Decision::new("Decision B".to_string(), 2);
-        let decision3 = Decision::new("Decision C".to_string(), 3);
+        let in_use = self
+            .list_relations()?
+            .iter()
+            .filter(|r| r.relation_type.to_string() == name)
+            .count();
+        if in_use > 0 {
+            return Err(MedullaError::Storage(format!(
+                "Relation type '{}' is used by {} relation(s); delete them first",
+                name, in_use
+            )));
+        }
 
-        store.add_decision(&decision1).unwrap();
-        store.add_decision(&decision2).unwrap();
-        store.add_decision(&decision3).unwrap();
+        types_map.delete(name)?;
+        self.doc.commit();
+        Ok(())
+    }
 
-        // decision2 supersedes decision1
-        let rel1 = crate::entity::Relation::new(
-            decision2.base.id,
-            "decision".to_string(),
-            decision1.base.id,
-            "decision".to_string(),
-            crate::entity::RelationType::Supersedes,
-        );
+    fn parse_template_from_map(&self, map: &loro::LoroMapValue) -> Option<EntityTemplate> {
+        let string = |key: &str| match map.get(key) {
+            Some(LoroValue::String(s)) => Some(s.to_string()),
+            _ => None,
+        };
 
-        // decision3 references decision2
-        let rel2 = crate::entity::Relation::new(
-            decision3.base.id,
-            "decision".to_string(),
-            decision2.base.id,
-            "decision".to_string(),
-            crate::entity::RelationType::References,
-        );
+        let created_at = chrono::DateTime::parse_from_rfc3339(&string("created_at")?)
+            .ok()?
+            .with_timezone(&chrono::Utc);
 
-        store.add_relation(&rel1).unwrap();
-        store.add_relation(&rel2).unwrap();
-        store.save().unwrap();
+        let properties = string("properties")
+            .and_then(|s| serde_json::from_str(&s).ok())
+            .unwrap_or_default();
 
-        // Test get_relations_from
-        let from_d2 = store
-            .get_relations_from(&decision2.base.id.to_string())
-            .unwrap();
-        assert_eq!(from_d2.len(), 1);
-        assert_eq!(
-            from_d2[0].relation_type,
-            crate::entity::RelationType::Supersedes
-        );
+        let tags = match map.get("tags") {
+            Some(LoroValue::List(list)) => list
+                .iter()
+                .filter_map(|item| match item {
+                    LoroValue::String(s) => Some(s.to_string()),
+                    _ => None,
+                })
+                .collect(),
+            _ => Vec::new(),
+        };
 
-        // Test get_relations_to
-        let to_d2 = store
-            .get_relations_to(&decision2.base.id.to_string())
-            .unwrap();
-        assert_eq!(to_d2.len(), 1);
-        assert_eq!(
-            to_d2[0].relation_type,
-            crate::entity::RelationType::References
-        );
+        Some(EntityTemplate {
+            name: string("name")?,
+            entity_type: string("entity_type")?,
+            description: string("description"),
+            content: string("content"),
+            tags,
+            properties,
+            created_at,
+            created_by: string("created_by"),
+        })
     }
 
-    #[test]
-    fn test_delete_relation() {
-        let tmp = TempDir::new().unwrap();
-        let store = LoroStore::init(tmp.path()).unwrap();
+    // ========== Question Methods ==========
 
-        let decision1 = Decision::new("Decision A".to_string(), 1);
-        let decision2 = Decision::new("Decision B".to_string(), 2);
+    /// Add a question to the store
+    pub fn add_question(&self, question: &Question) -> Result<()> {
+        let questions = self.doc.get_map("questions");
+        let id_str = question.base.id.to_string();
 
-        store.add_decision(&decision1).unwrap();
-        store.add_decision(&decision2).unwrap();
+        let entity_map = questions.get_or_create_container(&id_str, LoroMap::new())?;
 
-        let relation = crate::entity::Relation::new(
-            decision2.base.id,
-            "decision".to_string(),
-            decision1.base.id,
+        entity_map.insert("id", id_str.clone())?;
+        entity_map.insert("type", "question")?;
+        entity_map.insert("sequence_number", question.base.sequence_number as i64)?;
+        entity_map.insert("title", question.base.title.clone())?;
+        entity_map.insert("created_at", question.base.created_at.to_rfc3339())?;
+        entity_map.insert("updated_at", question.base.updated_at.to_rfc3339())?;
+        entity_map.insert("status", question.status.to_string())?;
+
+        if let Some(ref content) = question.base.content {
+            entity_map.insert("content", content.clone())?;
+        }
+        if let Some(created_by) = question.base.created_by.as_ref().or(self.author.as_ref()) {
+            entity_map.insert("created_by", created_by.clone())?;
+        }
+
+        if question.base.archived {
+            entity_map.insert("archived", true)?;
+        }
+        if let Some(ref answered_by) = question.answered_by {
+            entity_map.insert("answered_by", answered_by.clone())?;
+        }
+
+        let tags_list = entity_map.get_or_create_container("tags", loro::LoroList::new())?;
+        for tag in &question.base.tags {
+            tags_list.push(tag.clone())?;
+        }
+
+        // Update global sequence counter
+        self.update_global_sequence(question.base.sequence_number)?;
+
+        self.doc.commit();
+        Ok(())
+    }
+
+    /// Get a question by UUID
+    pub fn get_question(&self, id: &uuid::Uuid) -> Result<Option<Question>> {
+        let questions_map = self.doc.get_map("questions");
+        let id_str = id.to_string();
+
+        let json = questions_map.get_deep_value();
+        if let LoroValue::Map(map) = json {
+            if let Some(LoroValue::Map(entity_map)) = map.get(&id_str) {
+                return Ok(self.parse_question_from_map(entity_map));
+            }
+        }
+        Ok(None)
+    }
+
+    /// List all questions
+    pub fn list_questions(&self) -> Result<Vec<Question>> {
+        let questions_map = self.doc.get_map("questions");
+        let mut questions = Vec::new();
+
+        let json = questions_map.get_deep_value();
+        if let LoroValue::Map(map) = json {
+            for (_, entity_value) in map.iter() {
+                if let LoroValue::Map(entity_map) = entity_value {
+                    if let Some(question) = self.parse_question_from_map(entity_map) {
+                        questions.push(question);
+                    }
+                }
+            }
+        }
+
+        questions.sort_by_key(|q| q.base.sequence_number);
+        Ok(questions)
+    }
+
+    /// Delete a question by UUID
+    pub fn delete_question(&self, id: &uuid::Uuid) -> Result<()> {
+        let questions_map = self.doc.get_map("questions");
+        let id_str = id.to_string();
+
+        if questions_map.get(&id_str).is_none() {
+            return Err(MedullaError::EntityNotFound(id_str));
+        }
+
+        questions_map.delete(&id_str)?;
+        self.doc.commit();
+        Ok(())
+    }
+
+    /// Update an existing question
+    pub fn update_question(&self, id: &uuid::Uuid, updates: QuestionUpdate) -> Result<()> {
+        let questions_map = self.doc.get_map("questions");
+        let id_str = id.to_string();
+
+        let entity_map = match questions_map.get(&id_str) {
+            Some(ValueOrContainer::Container(loro::Container::Map(map))) => map,
+            _ => return Err(MedullaError::EntityNotFound(id_str)),
+        };
+
+        let now = chrono::Utc::now();
+        entity_map.insert("updated_at", now.to_rfc3339())?;
+        self.stamp_updated_by(&entity_map)?;
+
+        if let Some(title) = updates.title {
+            entity_map.insert("title", title)?;
+        }
+
+        if let Some(content) = updates.content {
+            entity_map.insert("content", content)?;
+        }
+
+        if let Some(status) = updates.status {
+            entity_map.insert("status", status.to_string())?;
+        }
+
+        if let Some(answered_by_opt) = updates.answered_by {
+            match answered_by_opt {
+                Some(answered_by) => entity_map.insert("answered_by", answered_by)?,
+                None => entity_map.delete("answered_by")?,
+            };
+        }
+
+        // Handle tag additions and removals
+        if !updates.add_tags.is_empty() || !updates.remove_tags.is_empty() {
+            // Get existing tags
+            let existing_tags: Vec<String> = entity_map
+                .get("tags")
+                .and_then(|v| match v {
+                    ValueOrContainer::Container(loro::Container::List(list)) => {
+                        let deep = list.get_deep_value();
+                        match deep {
+                            LoroValue::List(items) => Some(
+                                items
+                                    .iter()
+                                    .filter_map(|item| match item {
+                                        LoroValue::String(s) => Some(s.to_string()),
+                                        _ => None,
+                                    })
+                                    .collect(),
+                            ),
+                            _ => None,
+                        }
+                    }
+                    _ => None,
+                })
+                .unwrap_or_default();
+
+            // Calculate new tags: existing + add - remove
+            let mut new_tags: Vec<String> = existing_tags
+                .into_iter()
+                .filter(|t| !updates.remove_tags.contains(t))
+                .collect();
+            for tag in updates.add_tags {
+                if !new_tags.contains(&tag) {
+                    new_tags.push(tag);
+                }
+            }
+
+            // Clear and repopulate tags list
+            let tags_list = entity_map.get_or_create_container("tags", loro::LoroList::new())?;
+            // Delete all existing entries
+            while tags_list.len() > 0 {
+                tags_list.delete(0, 1)?;
+            }
+            // Add new tags
+            for tag in new_tags {
+                tags_list.push(tag)?;
+            }
+        }
+
+        self.doc.commit();
+        Ok(())
+    }
+
+    fn parse_question_from_map(&self, map: &loro::LoroMapValue) -> Option<Question> {
+        let id = map_str(map, "id")?.parse().ok()?;
+        let parse_time = |key: &str| {
+            chrono::DateTime::parse_from_rfc3339(map_str(map, key)?)
+                .ok()
+                .map(|t| t.with_timezone(&chrono::Utc))
+        };
+        let sequence_number = match map.get("sequence_number")? {
+            LoroValue::I64(n) => *n as u32,
+            _ => return None,
+        };
+        let tags = match map.get("tags") {
+            Some(LoroValue::List(list)) => list
+                .iter()
+                .filter_map(|item| match item {
+                    LoroValue::String(s) => Some(s.to_string()),
+                    _ => None,
+                })
+                .collect(),
+            _ => Vec::new(),
+        };
+
+        Some(Question {
+            base: crate::entity::EntityBase {
+                id,
+                title: map_str(map, "title")?.to_string(),
+                content: map_str(map, "content").map(str::to_string),
+                tags,
+                created_at: parse_time("created_at")?,
+                updated_at: parse_time("updated_at")?,
+                created_by: map_str(map, "created_by").map(str::to_string),
+                updated_by: map_str(map, "updated_by").map(str::to_string),
+                sequence_number,
+                archived: matches!(map.get("archived"), Some(LoroValue::Bool(true))),
+            },
+            status: map_str(map, "status")
+                .and_then(|s| s.parse().ok())
+                .unwrap_or_default(),
+            answered_by: map_str(map, "answered_by").map(str::to_string),
+        })
+    }
+
+    // ========== Milestone Methods ==========
+
+    /// Add a milestone to the store
+    pub fn add_milestone(&self, milestone: &Milestone) -> Result<()> {
+        let milestones = self.doc.get_map("milestones");
+        let id_str = milestone.base.id.to_string();
+
+        let entity_map = milestones.get_or_create_container(&id_str, LoroMap::new())?;
+
+        entity_map.insert("id", id_str.clone())?;
+        entity_map.insert("type", "milestone")?;
+        entity_map.insert("sequence_number", milestone.base.sequence_number as i64)?;
+        entity_map.insert("title", milestone.base.title.clone())?;
+        entity_map.insert("created_at", milestone.base.created_at.to_rfc3339())?;
+        entity_map.insert("updated_at", milestone.base.updated_at.to_rfc3339())?;
+        entity_map.insert("status", milestone.status.to_string())?;
+
+        if let Some(ref content) = milestone.base.content {
+            entity_map.insert("content", content.clone())?;
+        }
+        if let Some(created_by) = milestone.base.created_by.as_ref().or(self.author.as_ref()) {
+            entity_map.insert("created_by", created_by.clone())?;
+        }
+
+        if milestone.base.archived {
+            entity_map.insert("archived", true)?;
+        }
+        if let Some(ref target_date) = milestone.target_date {
+            entity_map.insert("target_date", target_date.to_string())?;
+        }
+
+        let tags_list = entity_map.get_or_create_container("tags", loro::LoroList::new())?;
+        for tag in &milestone.base.tags {
+            tags_list.push(tag.clone())?;
+        }
+
+        // Update global sequence counter
+        self.update_global_sequence(milestone.base.sequence_number)?;
+
+        self.doc.commit();
+        Ok(())
+    }
+
+    /// Get a milestone by UUID
+    pub fn get_milestone(&self, id: &uuid::Uuid) -> Result<Option<Milestone>> {
+        let milestones_map = self.doc.get_map("milestones");
+        let id_str = id.to_string();
+
+        let json = milestones_map.get_deep_value();
+        if let LoroValue::Map(map) = json {
+            if let Some(LoroValue::Map(entity_map)) = map.get(&id_str) {
+                return Ok(self.parse_milestone_from_map(entity_map));
+            }
+        }
+        Ok(None)
+    }
+
+    /// List all milestones
+    pub fn list_milestones(&self) -> Result<Vec<Milestone>> {
+        let milestones_map = self.doc.get_map("milestones");
+        let mut milestones = Vec::new();
+
+        let json = milestones_map.get_deep_value();
+        if let LoroValue::Map(map) = json {
+            for (_, entity_value) in map.iter() {
+                if let LoroValue::Map(entity_map) = entity_value {
+                    if let Some(milestone) = self.parse_milestone_from_map(entity_map) {
+                        milestones.push(milestone);
+                    }
+                }
+            }
+        }
+
+        milestones.sort_by_key(|q| q.base.sequence_number);
+        Ok(milestones)
+    }
+
+    /// Delete a milestone by UUID
+    pub fn delete_milestone(&self, id: &uuid::Uuid) -> Result<()> {
+        let milestones_map = self.doc.get_map("milestones");
+        let id_str = id.to_string();
+
+        if milestones_map.get(&id_str).is_none() {
+            return Err(MedullaError::EntityNotFound(id_str));
+        }
+
+        milestones_map.delete(&id_str)?;
+        self.doc.commit();
+        Ok(())
+    }
+
+    /// Update an existing milestone
+    pub fn update_milestone(&self, id: &uuid::Uuid, updates: MilestoneUpdate) -> Result<()> {
+        let milestones_map = self.doc.get_map("milestones");
+        let id_str = id.to_string();
+
+        let entity_map = match milestones_map.get(&id_str) {
+            Some(ValueOrContainer::Container(loro::Container::Map(map))) => map,
+            _ => return Err(MedullaError::EntityNotFound(id_str)),
+        };
+
+        let now = chrono::Utc::now();
+        entity_map.insert("updated_at", now.to_rfc3339())?;
+        self.stamp_updated_by(&entity_map)?;
+
+        if let Some(title) = updates.title {
+            entity_map.insert("title", title)?;
+        }
+
+        if let Some(content) = updates.content {
+            entity_map.insert("content", content)?;
+        }
+
+        if let Some(status) = updates.status {
+            entity_map.insert("status", status.to_string())?;
+        }
+
+        if let Some(target_date_opt) = updates.target_date {
+            match target_date_opt {
+                Some(date) => entity_map.insert("target_date", date.to_string())?,
+                None => entity_map.delete("target_date")?,
+            };
+        }
+
+        // Handle tag additions and removals
+        if !updates.add_tags.is_empty() || !updates.remove_tags.is_empty() {
+            // Get existing tags
+            let existing_tags: Vec<String> = entity_map
+                .get("tags")
+                .and_then(|v| match v {
+                    ValueOrContainer::Container(loro::Container::List(list)) => {
+                        let deep = list.get_deep_value();
+                        match deep {
+                            LoroValue::List(items) => Some(
+                                items
+                                    .iter()
+                                    .filter_map(|item| match item {
+                                        LoroValue::String(s) => Some(s.to_string()),
+                                        _ => None,
+                                    })
+                                    .collect(),
+                            ),
+                            _ => None,
+                        }
+                    }
+                    _ => None,
+                })
+                .unwrap_or_default();
+
+            // Calculate new tags: existing + add - remove
+            let mut new_tags: Vec<String> = existing_tags
+                .into_iter()
+                .filter(|t| !updates.remove_tags.contains(t))
+                .collect();
+            for tag in updates.add_tags {
+                if !new_tags.contains(&tag) {
+                    new_tags.push(tag);
+                }
+            }
+
+            // Clear and repopulate tags list
+            let tags_list = entity_map.get_or_create_container("tags", loro::LoroList::new())?;
+            // Delete all existing entries
+            while tags_list.len() > 0 {
+                tags_list.delete(0, 1)?;
+            }
+            // Add new tags
+            for tag in new_tags {
+                tags_list.push(tag)?;
+            }
+        }
+
+        self.doc.commit();
+        Ok(())
+    }
+
+    fn parse_milestone_from_map(&self, map: &loro::LoroMapValue) -> Option<Milestone> {
+        let id = map_str(map, "id")?.parse().ok()?;
+        let parse_time = |key: &str| {
+            chrono::DateTime::parse_from_rfc3339(map_str(map, key)?)
+                .ok()
+                .map(|t| t.with_timezone(&chrono::Utc))
+        };
+        let sequence_number = match map.get("sequence_number")? {
+            LoroValue::I64(n) => *n as u32,
+            _ => return None,
+        };
+        let tags = match map.get("tags") {
+            Some(LoroValue::List(list)) => list
+                .iter()
+                .filter_map(|item| match item {
+                    LoroValue::String(s) => Some(s.to_string()),
+                    _ => None,
+                })
+                .collect(),
+            _ => Vec::new(),
+        };
+
+        Some(Milestone {
+            base: crate::entity::EntityBase {
+                id,
+                title: map_str(map, "title")?.to_string(),
+                content: map_str(map, "content").map(str::to_string),
+                tags,
+                created_at: parse_time("created_at")?,
+                updated_at: parse_time("updated_at")?,
+                created_by: map_str(map, "created_by").map(str::to_string),
+                updated_by: map_str(map, "updated_by").map(str::to_string),
+                sequence_number,
+                archived: matches!(map.get("archived"), Some(LoroValue::Bool(true))),
+            },
+            status: map_str(map, "status")
+                .and_then(|s| s.parse().ok())
+                .unwrap_or_default(),
+            target_date: map_str(map, "target_date").and_then(|s| s.parse().ok()),
+        })
+    }
+
+    // ========== Alias Methods ==========
+
+    /// Give an entity a unique alias, usable wherever an ID is accepted.
+    /// Replaces the entity's previous alias; fails if another entity
+    /// already has this one. Returns the alias as stored (lowercase).
+    pub fn set_alias(&self, entity_id: &uuid::Uuid, alias: &str) -> Result<String> {
+        let alias = validate_alias(alias)?;
+        let id_str = entity_id.to_string();
+        if self.lookup_id(&id_str, None).is_none() {
+            return Err(MedullaError::EntityNotFound(id_str));
+        }
+
+        if let Some((other, other_type)) = self
+            .alias_entries()
+            .into_iter()
+            .find(|(a, id)| *a == alias && id != entity_id)
+            .and_then(|(_, id)| self.lookup_id(&id.to_string(), None))
+        {
+            return Err(MedullaError::Storage(format!(
+                "Alias '{}' is already used by {} {}",
+                alias,
+                other_type,
+                &other.to_string()[..8]
+            )));
+        }
+
+        self.remove_aliases_of(&id_str)?;
+        self.doc.get_map(ALIASES_MAP).insert(&alias, id_str)?;
+        self.doc.commit();
+        Ok(alias)
+    }
+
+    /// Remove an alias. Fails if no entity has it.
+    pub fn remove_alias(&self, alias: &str) -> Result<()> {
+        let alias = alias.trim().to_lowercase();
+        let aliases = self.doc.get_map(ALIASES_MAP);
+        if aliases.get(&alias).is_none() {
+            return Err(MedullaError::Storage(format!("Alias not found: {}", alias)));
+        }
+        aliases.delete(&alias)?;
+        self.doc.commit();
+        Ok(())
+    }
+
+    /// Alias of an entity, if it has one
+    pub fn alias_of(&self, entity_id: &uuid::Uuid) -> Option<String> {
+        self.alias_entries()
+            .into_iter()
+            .find(|(_, id)| id == entity_id)
+            .map(|(alias, _)| alias)
+    }
+
+    /// Aliases of entities in the store (trashed ones excluded) with the
+    /// entity each names, sorted by alias
+    pub fn list_aliases(&self) -> Result<Vec<(String, uuid::Uuid)>> {
+        let live = self.entity_ids();
+        Ok(self
+            .alias_entries()
+            .into_iter()
+            .filter(|(_, id)| live.contains(&id.to_string()))
+            .collect())
+    }
+
+    /// Every recorded alias, including those of trashed entities, sorted
+    fn alias_entries(&self) -> Vec<(String, uuid::Uuid)> {
+        let mut entries = Vec::new();
+        if let LoroValue::Map(map) = self.doc.get_map(ALIASES_MAP).get_deep_value() {
+            for (alias, value) in map.iter() {
+                if let LoroValue::String(id) = value {
+                    if let Ok(id) = uuid::Uuid::parse_str(id) {
+                        entries.push((alias.clone(), id));
+                    }
+                }
+            }
+        }
+        entries.sort();
+        entries
+    }
+
+    /// Drop any alias naming the entity, without committing
+    fn remove_aliases_of(&self, id_str: &str) -> Result<()> {
+        let aliases = self.doc.get_map(ALIASES_MAP);
+        for (alias, id) in self.alias_entries() {
+            if id.to_string() == id_str {
+                aliases.delete(&alias)?;
+            }
+        }
+        Ok(())
+    }
+
+    // ========== Collection Methods ==========
+
+    /// Put an entity in a collection, giving it the next sequence number
+    /// there. Moving an entity to another collection numbers it afresh;
+    /// setting the collection it is already in keeps its number. Returns
+    /// the collection as stored (lowercase) and the entity's number in it.
+    pub fn set_collection(&self, entity_id: &uuid::Uuid, name: &str) -> Result<(String, u32)> {
+        let name = validate_collection(name)?;
+        let id_str = entity_id.to_string();
+        if self.lookup_id(&id_str, None).is_none() {
+            return Err(MedullaError::EntityNotFound(id_str));
+        }
+        if let Some((current, sequence)) = self.collection_of(entity_id) {
+            if current == name {
+                return Ok((current, sequence));
+            }
+        }
+
+        let sequence = self.last_collection_sequence(&name) + 1;
+        let entry = self
+            .doc
+            .get_map(COLLECTIONS_MAP)
+            .get_or_create_container(&id_str, LoroMap::new())?;
+        entry.insert("name", name.clone())?;
+        entry.insert("sequence", sequence as i64)?;
+        self.doc
+            .get_map(COLLECTION_SEQUENCES_MAP)
+            .insert(&name, sequence as i64)?;
+        self.doc.commit();
+        Ok((name, sequence))
+    }
+
+    /// Highest number handed out in a collection. Numbers are never reused,
+    /// even once their entity has left the collection; stores from before
+    /// the counter start from the members.
+    fn last_collection_sequence(&self, name: &str) -> u32 {
+        let issued = match self.doc.get_map(COLLECTION_SEQUENCES_MAP).get(name) {
+            Some(ValueOrContainer::Value(LoroValue::I64(n))) => n as u32,
+            _ => 0,
+        };
+        self.collection_entries()
+            .into_iter()
+            .filter(|(collection, _, _)| collection == name)
+            .map(|(_, sequence, _)| sequence)
+            .fold(issued, u32::max)
+    }
+
+    /// Take an entity out of its collection. Fails if it is in none.
+    pub fn remove_collection(&self, entity_id: &uuid::Uuid) -> Result<()> {
+        let id_str = entity_id.to_string();
+        let collections = self.doc.get_map(COLLECTIONS_MAP);
+        if collections.get(&id_str).is_none() {
+            return Err(MedullaError::Storage(format!(
+                "Entity {} is not in a collection",
+                &id_str[..8]
+            )));
+        }
+        collections.delete(&id_str)?;
+        self.doc.commit();
+        Ok(())
+    }
+
+    /// Collection of an entity and its sequence number there, if it is in one
+    pub fn collection_of(&self, entity_id: &uuid::Uuid) -> Option<(String, u32)> {
+        self.collection_entries()
+            .into_iter()
+            .find(|(_, _, id)| id == entity_id)
+            .map(|(name, sequence, _)| (name, sequence))
+    }
+
+    /// IDs of the entities in a collection, trashed ones excluded
+    pub fn collection_members(&self, name: &str) -> HashSet<String> {
+        let name = name.trim().to_lowercase();
+        let live = self.entity_ids();
+        self.collection_entries()
+            .into_iter()
+            .filter(|(collection, _, _)| *collection == name)
+            .map(|(_, _, id)| id.to_string())
+            .filter(|id| live.contains(id))
+            .collect()
+    }
+
+    /// A staging copy of the store holding only one collection's entities.
+    /// The others are moved to the copy's trash along with their relations,
+    /// so listings and snapshots of the copy cover just the collection.
+    pub fn collection_view(&self, name: &str) -> Result<Self> {
+        let members = self.collection_members(name);
+        if members.is_empty() {
+            return Err(MedullaError::Storage(format!(
+                "Collection not found: {}",
+                name.trim().to_lowercase()
+            )));
+        }
+        let view = self.staging();
+        for id in view.entity_ids() {
+            if !members.contains(&id) {
+                if let Ok(id) = uuid::Uuid::parse_str(&id) {
+                    view.move_to_trash(&id, None)?;
+                }
+            }
+        }
+        view.doc.commit();
+        Ok(view)
+    }
+
+    /// Memberships of entities in the store (trashed ones excluded) as
+    /// `(collection, sequence, id)`, sorted by collection and sequence
+    pub fn list_collection_members(&self) -> Result<Vec<(String, u32, uuid::Uuid)>> {
+        let live = self.entity_ids();
+        Ok(self
+            .collection_entries()
+            .into_iter()
+            .filter(|(_, _, id)| live.contains(&id.to_string()))
+            .collect())
+    }
+
+    /// Collections with at least one entity, with their entity counts
+    pub fn list_collections(&self) -> Result<Vec<(String, usize)>> {
+        let mut counts: Vec<(String, usize)> = Vec::new();
+        for (name, _, _) in self.list_collection_members()? {
+            match counts.last_mut() {
+                Some((last, count)) if *last == name => *count += 1,
+                _ => counts.push((name, 1)),
+            }
+        }
+        Ok(counts)
+    }
+
+    /// Every recorded membership, including those of trashed entities, sorted
+    fn collection_entries(&self) -> Vec<(String, u32, uuid::Uuid)> {
+        let mut entries = Vec::new();
+        if let LoroValue::Map(map) = self.doc.get_map(COLLECTIONS_MAP).get_deep_value() {
+            for (key, value) in map.iter() {
+                let LoroValue::Map(entry) = value else {
+                    continue;
+                };
+                let (Ok(id), Some(name), Some(LoroValue::I64(sequence))) = (
+                    uuid::Uuid::parse_str(key),
+                    map_str(entry, "name"),
+                    entry.get("sequence"),
+                ) else {
+                    continue;
+                };
+                entries.push((name.to_string(), *sequence as u32, id));
+            }
+        }
+        entries.sort();
+        entries
+    }
+
+    // ========== Commit Link Methods ==========
+
+    /// Attach a commit to an entity. Linking the same commit again keeps the
+    /// original link.
+    pub fn add_commit_link(&self, link: &CommitLink) -> Result<bool> {
+        let links_map = self.doc.get_map("commit_links");
+        let key = link.composite_key();
+        if links_map.get(&key).is_some() {
+            return Ok(false);
+        }
+
+        let link_map = links_map.get_or_create_container(&key, LoroMap::new())?;
+        link_map.insert("entity_id", link.entity_id.to_string())?;
+        link_map.insert("entity_type", link.entity_type.clone())?;
+        link_map.insert("sha", link.sha.clone())?;
+        link_map.insert("linked_at", link.linked_at.to_rfc3339())?;
+        if let Some(ref summary) = link.summary {
+            link_map.insert("summary", summary.clone())?;
+        }
+
+        self.doc.commit();
+        Ok(true)
+    }
+
+    /// Commits linked to an entity, oldest link first
+    pub fn get_commit_links(&self, entity_id: &uuid::Uuid) -> Result<Vec<CommitLink>> {
+        let links_map = self.doc.get_map("commit_links");
+        let mut links = Vec::new();
+
+        let json = links_map.get_deep_value();
+        if let LoroValue::Map(map) = json {
+            for (_, link_value) in map.iter() {
+                if let LoroValue::Map(link_map) = link_value {
+                    if let Some(link) = self.parse_commit_link_from_map(link_map) {
+                        if link.entity_id == *entity_id {
+                            links.push(link);
+                        }
+                    }
+                }
+            }
+        }
+
+        links.sort_by_key(|l| l.linked_at);
+        Ok(links)
+    }
+
+    fn parse_commit_link_from_map(&self, map: &loro::LoroMapValue) -> Option<CommitLink> {
+        let string = |key: &str| match map.get(key) {
+            Some(LoroValue::String(s)) => Some(s.to_string()),
+            _ => None,
+        };
+
+        let linked_at = chrono::DateTime::parse_from_rfc3339(&string("linked_at")?)
+            .ok()?
+            .with_timezone(&chrono::Utc);
+
+        Some(CommitLink {
+            entity_id: string("entity_id")?.parse().ok()?,
+            entity_type: string("entity_type")?,
+            sha: string("sha")?,
+            summary: string("summary"),
+            linked_at,
+        })
+    }
+
+    // =========================================================================
+    // Sequence Numbers
+    // =========================================================================
+
+    /// Merge another replica's document (such as a copy of `loro.db` from
+    /// another clone) into this one, then renumber any entities the two
+    /// replicas created with the same sequence number. The caller saves.
+    pub fn import_replica(&self, bytes: &[u8]) -> Result<Vec<SequenceReassignment>> {
+        self.doc.import(bytes)?;
+        self.resolve_sequence_collisions()
+    }
+
+    /// Groups of live entities sharing a sequence number, with the entity
+    /// that keeps the number first. Replicas that create entities offline
+    /// both take the next number, so these appear once their changes are
+    /// merged.
+    fn sequence_collisions(&self) -> Vec<(u32, Vec<SequenceHolder>)> {
+        group_collisions(self.sequence_holders())
+    }
+
+    /// Groups of live entities sharing a number within a collection, keyed
+    /// by collection and number, with the entity that keeps it first.
+    /// Replicas adding entities to a collection offline both take its next
+    /// number.
+    fn collection_sequence_collisions(&self) -> Vec<((String, u32), Vec<SequenceHolder>)> {
+        let mut holders: HashMap<uuid::Uuid, SequenceHolder> = self
+            .sequence_holders()
+            .into_iter()
+            .map(|(_, holder)| (holder.id, holder))
+            .collect();
+        group_collisions(
+            self.collection_entries()
+                .into_iter()
+                .filter_map(|(name, sequence, id)| Some(((name, sequence), holders.remove(&id)?))),
+        )
+    }
+
+    /// Every live entity with its sequence number
+    fn sequence_holders(&self) -> Vec<(u32, SequenceHolder)> {
+        let mut holders = Vec::new();
+        for (entity_type, map_name) in ENTITY_MAPS {
+            let LoroValue::Map(map) = self.doc.get_map(map_name).get_deep_value() else {
+                continue;
+            };
+            for (key, value) in map.iter() {
+                let LoroValue::Map(entity_map) = value else {
+                    continue;
+                };
+                let (Ok(id), Some(LoroValue::I64(seq))) = (
+                    uuid::Uuid::parse_str(key),
+                    entity_map.get("sequence_number"),
+                ) else {
+                    continue;
+                };
+                let text = |field: &str| match entity_map.get(field) {
+                    Some(LoroValue::String(s)) => s.to_string(),
+                    _ => String::new(),
+                };
+                holders.push((
+                    *seq as u32,
+                    SequenceHolder {
+                        id,
+                        entity_type,
+                        title: text("title"),
+                        created_at: text("created_at"),
+                    },
+                ));
+            }
+        }
+        holders
+    }
+
+    /// Number of live entities whose sequence number, or number in their
+    /// collection, is also used by an earlier-created entity, and so would
+    /// be renumbered.
+    pub fn count_sequence_collisions(&self) -> usize {
+        let global: usize = self
+            .sequence_collisions()
+            .iter()
+            .map(|(_, entities)| entities.len() - 1)
+            .sum();
+        let in_collections: usize = self
+            .collection_sequence_collisions()
+            .iter()
+            .map(|(_, entities)| entities.len() - 1)
+            .sum();
+        global + in_collections
+    }
+
+    /// Give every entity that shares a sequence number with an
+    /// earlier-created one a fresh number above the current highest, and
+    /// likewise for numbers within a collection. The result depends only on
+    /// the merged document, so replicas that each run this pass agree on the
+    /// new numbers. Returns what was moved; the caller saves and syncs the
+    /// cache.
+    pub fn resolve_sequence_collisions(&self) -> Result<Vec<SequenceReassignment>> {
+        let collisions = self.sequence_collisions();
+        let collection_collisions = self.collection_sequence_collisions();
+        if collisions.is_empty() && collection_collisions.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let mut next = self
+            .build_id_index()
+            .max_sequence()
+            .max(self.next_sequence_number().saturating_sub(1));
+        let mut reassigned = Vec::new();
+        for (old_sequence, entities) in collisions {
+            let kept_by = entities[0].id;
+            for SequenceHolder {
+                id,
+                entity_type,
+                title,
+                ..
+            } in entities.into_iter().skip(1)
+            {
+                next += 1;
+                let map_name = ENTITY_MAPS
+                    .iter()
+                    .find(|(t, _)| *t == entity_type)
+                    .map(|(_, m)| *m)
+                    .unwrap_or_default();
+                if let Some(ValueOrContainer::Container(loro::Container::Map(entity_map))) =
+                    self.doc.get_map(map_name).get(&id.to_string())
+                {
+                    entity_map.insert("sequence_number", next as i64)?;
+                }
+                reassigned.push(SequenceReassignment {
+                    id,
+                    entity_type,
+                    title,
+                    old_sequence,
+                    new_sequence: next,
+                    kept_by,
+                    collection: None,
+                });
+            }
+        }
+        if !reassigned.is_empty() {
+            self.update_global_sequence(next)?;
+        }
+
+        let collections = self.doc.get_map(COLLECTIONS_MAP);
+        let sequences = self.doc.get_map(COLLECTION_SEQUENCES_MAP);
+        for ((name, old_sequence), entities) in collection_collisions {
+            let kept_by = entities[0].id;
+            let mut next = self.last_collection_sequence(&name);
+            for SequenceHolder {
+                id,
+                entity_type,
+                title,
+                ..
+            } in entities.into_iter().skip(1)
+            {
+                next += 1;
+                if let Some(ValueOrContainer::Container(loro::Container::Map(entry))) =
+                    collections.get(&id.to_string())
+                {
+                    entry.insert("sequence", next as i64)?;
+                }
+                reassigned.push(SequenceReassignment {
+                    id,
+                    entity_type,
+                    title,
+                    old_sequence,
+                    new_sequence: next,
+                    kept_by,
+                    collection: Some(name.clone()),
+                });
+            }
+            sequences.insert(&name, next as i64)?;
+        }
+        self.doc.commit();
+
+        Ok(reassigned)
+    }
+
+    // =========================================================================
+    // History
+    // =========================================================================
+
+    /// Current state of any entity as JSON, with its type.
+    fn entity_value(&self, id: &uuid::Uuid) -> Result<Option<serde_json::Value>> {
+        let value = if let Some(e) = self.get_decision(id)? {
+            serde_json::to_value(e)?
+        } else if let Some(e) = self.get_task(id)? {
+            serde_json::to_value(e)?
+        } else if let Some(e) = self.get_note(id)? {
+            serde_json::to_value(e)?
+        } else if let Some(e) = self.get_prompt(id)? {
+            serde_json::to_value(e)?
+        } else if let Some(e) = self.get_component(id)? {
+            serde_json::to_value(e)?
+        } else if let Some(e) = self.get_link(id)? {
+            serde_json::to_value(e)?
+        } else if let Some(e) = self.get_question(id)? {
+            serde_json::to_value(e)?
+        } else if let Some(e) = self.get_milestone(id)? {
+            serde_json::to_value(e)?
+        } else {
+            return Ok(None);
+        };
+        Ok(Some(value))
+    }
+
+    /// Every entity in the store as JSON, with its type.
+    pub fn entity_values(&self) -> Result<Vec<(&'static str, serde_json::Value)>> {
+        let mut values = Vec::new();
+        for e in self.list_decisions()? {
+            values.push(("decision", serde_json::to_value(e)?));
+        }
+        for e in self.list_tasks()? {
+            values.push(("task", serde_json::to_value(e)?));
+        }
+        for e in self.list_notes()? {
+            values.push(("note", serde_json::to_value(e)?));
+        }
+        for e in self.list_prompts()? {
+            values.push(("prompt", serde_json::to_value(e)?));
+        }
+        for e in self.list_components()? {
+            values.push(("component", serde_json::to_value(e)?));
+        }
+        for e in self.list_links()? {
+            values.push(("link", serde_json::to_value(e)?));
+        }
+        for e in self.list_questions()? {
+            values.push(("question", serde_json::to_value(e)?));
+        }
+        for e in self.list_milestones()? {
+            values.push(("milestone", serde_json::to_value(e)?));
+        }
+        Ok(values)
+    }
+
+    /// A read-only copy of the store as it was at `at`, holding only the
+    /// changes made at or before then. Saving it does nothing.
+    pub fn at_time(&self, at: chrono::DateTime<chrono::Utc>) -> Result<Self> {
+        let doc = self.doc.fork_at(&self.frontiers_at(at)?)?;
+        let mut store = Self::from_doc(doc, self.path.clone());
+        store.staging = true;
+        Ok(store)
+    }
+
+    /// Frontiers of the changes made at or before `at`.
+    fn frontiers_at(&self, at: chrono::DateTime<chrono::Utc>) -> Result<loro::Frontiers> {
+        let cutoff = at.timestamp();
+        let mut vv = loro::VersionVector::new();
+        let heads: Vec<loro::ID> = self.doc.oplog_frontiers().iter().collect();
+        self.doc
+            .travel_change_ancestors(&heads, &mut |meta| {
+                if meta.timestamp <= cutoff {
+                    vv.extend_to_include_last_id(loro::ID::new(
+                        meta.id.peer,
+                        meta.id.counter + meta.len as i32 - 1,
+                    ));
+                }
+                std::ops::ControlFlow::Continue(())
+            })
+            .map_err(|e| MedullaError::Storage(format!("Failed to read history: {}", e)))?;
+        Ok(self.doc.vv_to_frontiers(&vv))
+    }
+
+    /// Rewrite `loro.db` as a shallow snapshot: the current state plus the
+    /// history made after `keep_since`, or no history when it is None.
+    ///
+    /// Dropped history is gone for good: `history` and time travel stop at
+    /// the new start, and a replica copied before it can no longer be
+    /// merged. The compacted document is checked against the current state
+    /// and written next to `loro.db` before being renamed over it, so a
+    /// failed compaction leaves the old file in place.
+    pub fn compact(
+        self,
+        keep_since: Option<chrono::DateTime<chrono::Utc>>,
+    ) -> Result<CompactStats> {
+        if self.staging {
+            return Err(MedullaError::Storage(
+                "Cannot compact a read-only copy of the store".to_string(),
+            ));
+        }
+        let before_bytes = fs::metadata(&self.path)?.len();
+
+        let start = match keep_since {
+            Some(at) => self.frontiers_at(at)?,
+            None => self.doc.oplog_frontiers(),
+        };
+        // Nothing is older than the cutoff, so there is no history to drop
+        let bytes = if start.is_empty() {
+            self.doc.export(loro::ExportMode::Snapshot)?
+        } else {
+            self.doc
+                .export(loro::ExportMode::shallow_snapshot(&start))?
+        };
+
+        let compacted = LoroDoc::new();
+        configure_doc(&compacted);
+        compacted.import(&bytes)?;
+        if compacted.get_deep_value() != self.doc.get_deep_value() {
+            return Err(MedullaError::Storage(
+                "Compacted document does not match the current state; loro.db was left unchanged"
+                    .to_string(),
+            ));
+        }
+
+        let tmp_path = self.path.with_extension("db.compact");
+        fs::write(&tmp_path, &bytes)?;
+        fs::rename(&tmp_path, &self.path)?;
+
+        Ok(CompactStats {
+            before_bytes,
+            after_bytes: bytes.len() as u64,
+        })
+    }
+
+    /// List the versions of an entity, oldest first.
+    ///
+    /// Replays the document at the end of every Loro change that writes to
+    /// the entity and records a version whenever its state differs from the
+    /// one before.
+    pub fn entity_history(&self, id: &uuid::Uuid) -> Result<Vec<EntityVersion>> {
+        let mut changes = Vec::new();
+        let heads: Vec<loro::ID> = self.doc.oplog_frontiers().iter().collect();
+        self.doc
+            .travel_change_ancestors(&heads, &mut |meta| {
+                changes.push(meta);
+                std::ops::ControlFlow::Continue(())
+            })
+            .map_err(|e| MedullaError::Storage(format!("Failed to read history: {}", e)))?;
+        changes.sort_by_key(|meta| meta.lamport);
+
+        let mut versions: Vec<EntityVersion> = Vec::new();
+        let mut previous: Option<serde_json::Value> = None;
+        for meta in changes {
+            let last_op = loro::ID::new(meta.id.peer, meta.id.counter + meta.len as i32 - 1);
+            let after = loro::Frontiers::from_id(last_op);
+            // Forking copies the whole document, so only changes that
+            // wrote to this entity are replayed
+            if !self.change_touches_entity(&meta.deps, &after, id)? {
+                continue;
+            }
+            let at = Self::from_doc(self.doc.fork_at(&after)?, self.path.clone());
+            let state = at.entity_value(id)?;
+            if state == previous {
+                continue;
+            }
+
+            let changed_fields = changed_fields(previous.as_ref(), state.as_ref());
+            let timestamp = chrono::DateTime::from_timestamp(meta.timestamp, 0)
+                .filter(|_| meta.timestamp > 0)
+                .or_else(|| {
+                    state
+                        .as_ref()
+                        .and_then(|s| s.get("updated_at"))
+                        .and_then(|v| v.as_str())
+                        .and_then(|s| chrono::DateTime::parse_from_rfc3339(s).ok())
+                        .map(|dt| dt.with_timezone(&chrono::Utc))
+                });
+            versions.push(EntityVersion {
+                peer: meta.id.peer,
+                lamport: meta.lamport,
+                timestamp,
+                changed_fields,
+                state: state.clone(),
+            });
+            previous = state;
+        }
+
+        Ok(versions)
+    }
+
+    /// Whether the change between `before` and `after` writes to an entity:
+    /// to its map or a container inside it, or to its key in its type's map,
+    /// as creating, trashing and restoring it do
+    fn change_touches_entity(
+        &self,
+        before: &loro::Frontiers,
+        after: &loro::Frontiers,
+        id: &uuid::Uuid,
+    ) -> Result<bool> {
+        let key = id.to_string();
+        let diff = self.doc.diff(before, after)?;
+        for (cid, change) in diff.iter() {
+            if let loro::ContainerID::Root { name, .. } = cid {
+                if let loro::event::Diff::Map(delta) = change {
+                    if ENTITY_MAPS.iter().any(|(_, map)| *map == name.as_str())
+                        && delta.updated.contains_key(key.as_str())
+                    {
+                        return Ok(true);
+                    }
+                }
+                continue;
+            }
+            let Some(path) = self.doc.get_path_to_container(cid) else {
+                continue;
+            };
+            if let [(_, loro::Index::Key(root)), (_, loro::Index::Key(entity)), ..] =
+                path.as_slice()
+            {
+                if entity.as_str() == key
+                    && ENTITY_MAPS.iter().any(|(_, map)| *map == root.as_str())
+                {
+                    return Ok(true);
+                }
+            }
+        }
+        Ok(false)
+    }
+
+    /// Edits to the same entity field made concurrently on different
+    /// replicas, i.e. without either having seen the other, which the CRDT
+    /// merge resolved on its own. With `since`, only conflicts whose latest
+    /// edit is at or after that time. Deleted entities are left out.
+    pub fn merge_conflicts(
+        &self,
+        since: Option<chrono::DateTime<chrono::Utc>>,
+    ) -> Result<Vec<FieldConflict>> {
+        let mut changes = Vec::new();
+        let heads: Vec<loro::ID> = self.doc.oplog_frontiers().iter().collect();
+        self.doc
+            .travel_change_ancestors(&heads, &mut |meta| {
+                changes.push(meta);
+                std::ops::ControlFlow::Continue(())
+            })
+            .map_err(|e| MedullaError::Storage(format!("Failed to read history: {}", e)))?;
+        changes.sort_by_key(|meta| meta.lamport);
+        if let Some(since) = since {
+            changes = self.changes_concurrent_since(changes, since.timestamp());
+        }
+
+        // Which entity fields each change wrote, from the change's own diff
+        let mut edits: HashMap<(uuid::Uuid, String, String), Vec<FieldEdit>> = HashMap::new();
+        for meta in &changes {
+            let last_op = loro::ID::new(meta.id.peer, meta.id.counter + meta.len as i32 - 1);
+            let after = loro::Frontiers::from_id(last_op);
+            let diff = self.doc.diff(&meta.deps, &after)?;
+            for (cid, change) in diff.iter() {
+                let loro::event::Diff::Map(delta) = change else {
+                    continue;
+                };
+                let Some((entity_type, id)) = self.entity_of_container(cid) else {
+                    continue;
+                };
+                for field in delta.updated.keys() {
+                    if BOOKKEEPING_FIELDS.contains(&field.as_ref()) {
+                        continue;
+                    }
+                    edits
+                        .entry((id, entity_type.to_string(), field.to_string()))
+                        .or_default()
+                        .push(FieldEdit {
+                            peer: meta.id.peer,
+                            lamport: meta.lamport,
+                            first_op: meta.id,
+                            last_op,
+                            timestamp: meta.timestamp,
+                            vv: self.doc.frontiers_to_vv(&after).unwrap_or_default(),
+                        });
+                }
+            }
+        }
+
+        let mut conflicts = Vec::new();
+        for ((id, entity_type, field), field_edits) in edits {
+            for group in concurrent_groups(field_edits) {
+                let latest = group.iter().map(|e| e.timestamp).max().unwrap_or(0);
+                if since.is_some_and(|since| latest < since.timestamp()) {
+                    continue;
+                }
+                conflicts.push(self.field_conflict(id, entity_type.clone(), &field, group)?);
+            }
+        }
+        conflicts
+            .sort_by_key(|c| std::cmp::Reverse(c.edits.iter().filter_map(|e| e.timestamp).max()));
+        Ok(conflicts)
+    }
+
+    /// The changes that can be part of a conflict with an edit made at or
+    /// after `since` (in seconds): those not seen by every such change, so
+    /// that diffing skips the history every recent change built on. Changes
+    /// concurrent with those, in turn, are kept as well.
+    fn changes_concurrent_since(
+        &self,
+        changes: Vec<loro::ChangeMeta>,
+        since: i64,
+    ) -> Vec<loro::ChangeMeta> {
+        let last_op = |meta: &loro::ChangeMeta| {
+            loro::ID::new(meta.id.peer, meta.id.counter + meta.len as i32 - 1)
+        };
+        let seen_by =
+            |meta: &loro::ChangeMeta| self.doc.frontiers_to_vv(&meta.deps).unwrap_or_default();
+
+        let Some(mut floor) = changes
+            .iter()
+            .filter(|meta| meta.timestamp >= since)
+            .map(seen_by)
+            .reduce(|floor, seen| floor.intersection(&seen))
+        else {
+            return Vec::new();
+        };
+        loop {
+            let lowered = changes
+                .iter()
+                .filter(|meta| !floor.includes_id(last_op(meta)))
+                .fold(floor.clone(), |floor, meta| {
+                    floor.intersection(&seen_by(meta))
+                });
+            if lowered == floor {
+                break;
+            }
+            floor = lowered;
+        }
+
+        changes
+            .into_iter()
+            .filter(|meta| !floor.includes_id(last_op(meta)))
+            .collect()
+    }
+
+    /// Entity type and ID of the map holding an entity's fields
+    fn entity_of_container(&self, cid: &loro::ContainerID) -> Option<(&'static str, uuid::Uuid)> {
+        let path = self.doc.get_path_to_container(cid)?;
+        let [(_, loro::Index::Key(root)), (_, loro::Index::Key(key))] = path.as_slice() else {
+            return None;
+        };
+        let (entity_type, _) = ENTITY_MAPS.iter().find(|(_, map)| *map == root.as_str())?;
+        Some((entity_type, uuid::Uuid::parse_str(key).ok()?))
+    }
+
+    /// Describe a group of concurrent edits to `field`, reading each side's
+    /// value from the entity as of that edit
+    fn field_conflict(
+        &self,
+        id: uuid::Uuid,
+        entity_type: String,
+        field: &str,
+        group: Vec<FieldEdit>,
+    ) -> Result<FieldConflict> {
+        let field_value = |state: &Option<serde_json::Value>| {
+            state
+                .as_ref()
+                .and_then(|s| s.get(field))
+                .cloned()
+                .unwrap_or(serde_json::Value::Null)
+        };
+
+        let mut merged = loro::VersionVector::default();
+        let mut edits = Vec::new();
+        for edit in &group {
+            merged.merge(&edit.vv);
+            let at = Self::from_doc(
+                self.doc.fork_at(&loro::Frontiers::from_id(edit.last_op))?,
+                self.path.clone(),
+            );
+            let state = at.entity_value(&id)?;
+            edits.push(ConflictingEdit {
+                peer: edit.peer.to_string(),
+                author: state
+                    .as_ref()
+                    .and_then(|s| s.get("updated_by"))
+                    .and_then(|v| v.as_str())
+                    .map(str::to_string),
+                timestamp: chrono::DateTime::from_timestamp(edit.timestamp, 0)
+                    .filter(|_| edit.timestamp > 0),
+                value: field_value(&state),
+            });
+        }
+
+        let resolved = Self::from_doc(
+            self.doc.fork_at(&self.doc.vv_to_frontiers(&merged))?,
+            self.path.clone(),
+        );
+        let current = self.entity_value(&id)?;
+        Ok(FieldConflict {
+            entity_id: id,
+            entity_type,
+            sequence_number: current
+                .as_ref()
+                .and_then(|s| s.get("sequence_number"))
+                .and_then(|v| v.as_u64())
+                .map(|n| n as u32),
+            title: current
+                .as_ref()
+                .and_then(|s| s.get("title"))
+                .and_then(|v| v.as_str())
+                .map(str::to_string),
+            field: field.to_string(),
+            edits,
+            resolved: field_value(&resolved.entity_value(&id)?),
+        })
+    }
+}
+
+/// A change that wrote an entity field
+struct FieldEdit {
+    peer: u64,
+    lamport: u32,
+    first_op: loro::ID,
+    last_op: loro::ID,
+    /// Change timestamp in seconds, 0 when not recorded
+    timestamp: i64,
+    /// Version as of the change, to tell whether another change saw it
+    vv: loro::VersionVector,
+}
+
+impl FieldEdit {
+    fn concurrent_with(&self, other: &FieldEdit) -> bool {
+        self.peer != other.peer
+            && !self.vv.includes_id(other.first_op)
+            && !other.vv.includes_id(self.first_op)
+    }
+}
+
+/// Split edits to one field (in lamport order) into groups linked by
+/// concurrency, keeping the groups with more than one edit
+fn concurrent_groups(edits: Vec<FieldEdit>) -> Vec<Vec<FieldEdit>> {
+    let mut groups: Vec<Vec<FieldEdit>> = Vec::new();
+    for edit in edits {
+        let (mut linked, rest): (Vec<_>, Vec<_>) = groups
+            .into_iter()
+            .partition(|g| g.iter().any(|e| e.concurrent_with(&edit)));
+        groups = rest;
+        let mut group: Vec<FieldEdit> = linked.drain(..).flatten().collect();
+        group.push(edit);
+        groups.push(group);
+    }
+    groups.retain(|g| g.len() > 1);
+    for group in &mut groups {
+        group.sort_by_key(|e| e.lamport);
+    }
+    groups
+}
+
+/// Check an alias and return it lowercased. Aliases start with a letter,
+/// so they never read as sequence numbers, and otherwise hold letters,
+/// digits, `-` and `_`.
+pub fn validate_alias(alias: &str) -> Result<String> {
+    let alias = alias.trim().to_lowercase();
+    let valid = alias.len() <= MAX_ALIAS_LEN
+        && alias.starts_with(|c: char| c.is_ascii_lowercase())
+        && alias
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || matches!(c, '-' | '_'));
+    if valid {
+        Ok(alias)
+    } else {
+        Err(MedullaError::Storage(format!(
+            "Invalid alias '{}': start with a letter and use letters, digits, '-' and '_' (at most {} characters)",
+            alias, MAX_ALIAS_LEN
+        )))
+    }
+}
+
+/// Check a collection name and return it lowercased. Names follow the
+/// alias rules, so `backend/3` always splits into a name and a number.
+pub fn validate_collection(name: &str) -> Result<String> {
+    validate_alias(name).map_err(|_| {
+        MedullaError::Storage(format!(
+            "Invalid collection '{}': start with a letter and use letters, digits, '-' and '_' (at most {} characters)",
+            name.trim().to_lowercase(),
+            MAX_ALIAS_LEN
+        ))
+    })
+}
+
+/// A string field of a map value
+fn map_str<'a>(map: &'a loro::LoroMapValue, key: &str) -> Option<&'a str> {
+    match map.get(key)? {
+        LoroValue::String(s) => {
+            let s: &str = s;
+            Some(s)
+        }
+        _ => None,
+    }
+}
+
+/// Write a value under `key`, recreating nested maps and lists as
+/// containers so the copy can be edited like the original.
+fn write_value(map: &LoroMap, key: &str, value: &LoroValue) -> Result<()> {
+    match value {
+        LoroValue::Map(fields) => {
+            let child = map.get_or_create_container(key, LoroMap::new())?;
+            for (k, v) in fields.iter() {
+                write_value(&child, k, v)?;
+            }
+        }
+        LoroValue::List(items) => {
+            let child = map.get_or_create_container(key, loro::LoroList::new())?;
+            for item in items.iter() {
+                child.push(item.clone())?;
+            }
+        }
+        _ => {
+            map.insert(key, value.clone())?;
+        }
+    }
+    Ok(())
+}
+
+/// Top-level fields that differ between two entity states.
+pub(crate) fn changed_fields(
+    before: Option<&serde_json::Value>,
+    after: Option<&serde_json::Value>,
+) -> Vec<String> {
+    let empty = serde_json::Map::new();
+    let before = before.and_then(|v| v.as_object()).unwrap_or(&empty);
+    let after = after.and_then(|v| v.as_object()).unwrap_or(&empty);
+
+    let mut changed: Vec<String> = before
+        .keys()
+        .chain(after.keys())
+        .filter(|k| before.get(*k) != after.get(*k))
+        .cloned()
+        .collect();
+    changed.sort();
+    changed.dedup();
+    changed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_init_creates_medulla_directory() {
+        let tmp = TempDir::new().unwrap();
+        let _store = LoroStore::init(tmp.path()).unwrap();
+
+        assert!(tmp.path().join(".medulla").exists());
+        assert!(tmp.path().join(".medulla/loro.db").exists());
+    }
+
+    #[test]
+    fn test_init_fails_if_already_initialized() {
+        let tmp = TempDir::new().unwrap();
+        LoroStore::init(tmp.path()).unwrap();
+
+        let result = LoroStore::init(tmp.path());
+        assert!(matches!(result, Err(MedullaError::AlreadyInitialized)));
+    }
+
+    #[test]
+    fn test_open_fails_if_not_initialized() {
+        let tmp = TempDir::new().unwrap();
+
+        let result = LoroStore::open(tmp.path());
+        assert!(matches!(result, Err(MedullaError::NotInitialized)));
+    }
+
+    #[test]
+    fn test_add_and_list_decision() {
+        let tmp = TempDir::new().unwrap();
+        let store = LoroStore::init(tmp.path()).unwrap();
+
+        let mut decision = Decision::new("Use Rust".to_string(), 1);
+        decision.status = crate::entity::DecisionStatus::Accepted;
+        decision.base.tags = vec!["architecture".to_string()];
+
+        store.add_decision(&decision).unwrap();
+        store.save().unwrap();
+
+        // Reopen and verify
+        let store2 = LoroStore::open(tmp.path()).unwrap();
+        let decisions = store2.list_decisions().unwrap();
+
+        assert_eq!(decisions.len(), 1);
+        assert_eq!(decisions[0].base.title, "Use Rust");
+        assert_eq!(decisions[0].status, crate::entity::DecisionStatus::Accepted);
+    }
+
+    #[test]
+    fn test_set_and_get_focus() {
+        let tmp = TempDir::new().unwrap();
+        let store = LoroStore::init(tmp.path()).unwrap();
+        assert!(store.get_focus().unwrap().is_empty());
+
+        let first = uuid::Uuid::new_v4();
+        let second = uuid::Uuid::new_v4();
+        store.set_focus(&[first, second]).unwrap();
+        store.set_focus(&[second, first]).unwrap();
+        store.save().unwrap();
+
+        // Replacing keeps only the latest set, and it survives a reopen
+        let store2 = LoroStore::open(tmp.path()).unwrap();
+        assert_eq!(store2.get_focus().unwrap(), vec![second, first]);
+    }
+
+    #[test]
+    fn test_templates() {
+        let tmp = TempDir::new().unwrap();
+        let store = LoroStore::init(tmp.path()).unwrap();
+
+        let mut adr = EntityTemplate::new("adr".to_string(), "decision".to_string());
+        adr.content = Some("# {{title}}\n\n## Context\n".to_string());
+        adr.tags = vec!["adr".to_string()];
+        adr.properties
+            .insert("status".to_string(), serde_json::json!("proposed"));
+        store.put_template(&adr).unwrap();
+        store
+            .put_template(&EntityTemplate::new(
+                "chore".to_string(),
+                "task".to_string(),
+            ))
+            .unwrap();
+
+        let loaded = store.get_template("adr").unwrap().unwrap();
+        assert_eq!(loaded.tags, vec!["adr".to_string()]);
+        assert_eq!(loaded.properties["status"], "proposed");
+        let names: Vec<String> = store
+            .list_templates()
+            .unwrap()
+            .into_iter()
+            .map(|t| t.name)
+            .collect();
+        assert_eq!(names, vec!["adr".to_string(), "chore".to_string()]);
+
+        // Replacing a template drops fields it no longer has
+        adr.tags.clear();
+        adr.content = None;
+        store.put_template(&adr).unwrap();
+        let loaded = store.get_template("adr").unwrap().unwrap();
+        assert!(loaded.tags.is_empty());
+        assert!(loaded.content.is_none());
+
+        store.delete_template("chore").unwrap();
+        assert!(store.get_template("chore").unwrap().is_none());
+        assert!(store.delete_template("chore").is_err());
+    }
+
+    #[test]
+    fn test_set_archived() {
+        let tmp = TempDir::new().unwrap();
+        let store = LoroStore::init(tmp.path()).unwrap();
+        let note = Note::new("Old meeting notes".to_string(), 1);
+        store.add_note(&note).unwrap();
+        assert!(
+            !store
+                .get_note(&note.base.id)
+                .unwrap()
+                .unwrap()
+                .base
+                .archived
+        );
+
+        assert_eq!(store.set_archived(&note.base.id, true).unwrap(), "note");
+        store.save().unwrap();
+        let store2 = LoroStore::open(tmp.path()).unwrap();
+        assert!(
+            store2
+                .get_note(&note.base.id)
+                .unwrap()
+                .unwrap()
+                .base
+                .archived
+        );
+
+        store2.set_archived(&note.base.id, false).unwrap();
+        assert!(
+            !store2
+                .get_note(&note.base.id)
+                .unwrap()
+                .unwrap()
+                .base
+                .archived
+        );
+        assert!(matches!(
+            store2.set_archived(&uuid::Uuid::new_v4(), true),
+            Err(MedullaError::EntityNotFound(_))
+        ));
+    }
+
+    #[test]
+    fn test_trash_restore_and_purge() {
+        let tmp = TempDir::new().unwrap();
+        let store = LoroStore::init(tmp.path()).unwrap();
+        let mut task = Task::new("Write docs".to_string(), 1);
+        task.base.tags = vec!["docs".to_string()];
+        let note = Note::new("Outline".to_string(), 2);
+        store.add_task(&task).unwrap();
+        store.add_note(&note).unwrap();
+        store
+            .add_relation(&Relation::new(
+                note.base.id,
+                "note".to_string(),
+                task.base.id,
+                "task".to_string(),
+                RelationType::References,
+            ))
+            .unwrap();
+
+        assert_eq!(
+            store.trash_entity(&task.base.id, Some("alice")).unwrap(),
+            "task"
+        );
+        assert!(store.get_task(&task.base.id).unwrap().is_none());
+        assert!(store.list_relations().unwrap().is_empty());
+        assert!(store.lookup_id("1", None).is_none());
+
+        store.save().unwrap();
+        let store = LoroStore::open(tmp.path()).unwrap();
+        let trash = store.list_trash().unwrap();
+        assert_eq!(trash.len(), 1);
+        assert_eq!(trash[0].id, task.base.id);
+        assert_eq!(trash[0].sequence_number, 1);
+        assert_eq!(trash[0].title, "Write docs");
+        assert_eq!(trash[0].deleted_by.as_deref(), Some("alice"));
+        assert_eq!(trash[0].relations, 1);
+
+        assert_eq!(
+            store.restore_entity(&task.base.id).unwrap().entity_type,
+            "task"
+        );
+        let restored = store.get_task(&task.base.id).unwrap().unwrap();
+        assert_eq!(restored.base.tags, vec!["docs".to_string()]);
+        assert_eq!(store.list_relations().unwrap().len(), 1);
+        assert!(store.list_trash().unwrap().is_empty());
+        assert!(store.restore_entity(&task.base.id).is_err());
+
+        store.trash_entity(&task.base.id, None).unwrap();
+        store.trash_entity(&note.base.id, None).unwrap();
+        assert_eq!(store.purge_trash(Some(&note.base.id)).unwrap(), 1);
+        assert_eq!(store.purge_trash(None).unwrap(), 1);
+        assert!(store.list_trash().unwrap().is_empty());
+        assert!(store.restore_entity(&task.base.id).is_err());
+    }
+
+    #[test]
+    fn test_restore_entity_skips_relations_to_trashed_entities() {
+        let tmp = TempDir::new().unwrap();
+        let store = LoroStore::init(tmp.path()).unwrap();
+        let task = Task::new("Write docs".to_string(), 1);
+        let note = Note::new("Outline".to_string(), 2);
+        store.add_task(&task).unwrap();
+        store.add_note(&note).unwrap();
+        store
+            .add_relation(&Relation::new(
+                note.base.id,
+                "note".to_string(),
+                task.base.id,
+                "task".to_string(),
+                RelationType::References,
+            ))
+            .unwrap();
+
+        // The relation leaves with the task; the note follows it to the trash
+        store.trash_entity(&task.base.id, None).unwrap();
+        store.trash_entity(&note.base.id, None).unwrap();
+
+        let restored = store.restore_entity(&task.base.id).unwrap();
+        assert_eq!((restored.relations, restored.skipped_relations), (0, 1));
+        assert!(store.list_relations().unwrap().is_empty());
+        let trash = store.list_trash().unwrap();
+        assert_eq!(trash.len(), 1);
+        assert_eq!(trash[0].relations, 1);
+
+        // Restoring the note brings the relation back
+        let restored = store.restore_entity(&note.base.id).unwrap();
+        assert_eq!((restored.relations, restored.skipped_relations), (1, 0));
+        assert_eq!(store.list_relations().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_merge_entities() {
+        let tmp = TempDir::new().unwrap();
+        let store = LoroStore::init(tmp.path()).unwrap();
+        let mut keep = Note::new("Caching plan".to_string(), 1);
+        keep.base.tags = vec!["cache".to_string()];
+        let mut dup = Note::new("Caching plan (again)".to_string(), 2);
+        dup.base.content = Some("Use an LRU.".to_string());
+        dup.base.tags = vec!["cache".to_string(), "perf".to_string()];
+        let task = Task::new("Add cache".to_string(), 3);
+        store.add_note(&keep).unwrap();
+        store.add_note(&dup).unwrap();
+        store.add_task(&task).unwrap();
+        for (source, target) in [(dup.base.id, task.base.id), (keep.base.id, dup.base.id)] {
+            store
+                .add_relation(&Relation::new(
+                    source,
+                    "note".to_string(),
+                    target,
+                    "note".to_string(),
+                    RelationType::References,
+                ))
+                .unwrap();
+        }
+
+        assert!(store
+            .merge_entities(&keep.base.id, &task.base.id, None, false)
+            .is_err());
+        assert!(store
+            .merge_entities(&keep.base.id, &keep.base.id, None, false)
+            .is_err());
+
+        assert_eq!(
+            store
+                .merge_entities(&keep.base.id, &dup.base.id, Some("alice"), false)
+                .unwrap(),
+            "note"
+        );
+        assert!(store.get_note(&dup.base.id).unwrap().is_none());
+        let merged = store.get_note(&keep.base.id).unwrap().unwrap();
+        assert_eq!(
+            merged.base.tags,
+            vec!["cache".to_string(), "perf".to_string()]
+        );
+
+        let relations = store.list_relations().unwrap();
+        assert_eq!(relations.len(), 1);
+        assert_eq!(relations[0].source_id, keep.base.id);
+        assert_eq!(relations[0].target_id, task.base.id);
+        assert_eq!(store.list_trash().unwrap()[0].id, dup.base.id);
+    }
+
+    #[test]
+    fn test_relation_types() {
+        let tmp = TempDir::new().unwrap();
+        let store = LoroStore::init(tmp.path()).unwrap();
+        let depends_on = RelationTypeDef {
+            name: "depends_on".to_string(),
+            inverse: Some("depended_on_by".to_string()),
+            symmetric: false,
+        };
+        store.put_relation_type(&depends_on).unwrap();
+        store
+            .put_relation_type(&RelationTypeDef {
+                name: "replaces".to_string(),
+                inverse: None,
+                symmetric: false,
+            })
+            .unwrap();
+        assert!(store
+            .put_relation_type(&RelationTypeDef {
+                name: "blocks".to_string(),
+                inverse: None,
+                symmetric: false,
+            })
+            .is_err());
+
+        store.save().unwrap();
+        let store = LoroStore::open(tmp.path()).unwrap();
+        let defs = store.list_relation_types().unwrap();
+        assert_eq!(defs.len(), 2);
+        assert_eq!(defs[0].name, "depends_on");
+        assert_eq!(defs[0].inverse.as_deref(), Some("depended_on_by"));
+
+        let a = Note::new("A".to_string(), 1);
+        let b = Note::new("B".to_string(), 2);
+        store.add_note(&a).unwrap();
+        store.add_note(&b).unwrap();
+        let relation = Relation::new(
+            a.base.id,
+            "note".to_string(),
+            b.base.id,
+            "note".to_string(),
+            RelationType::Custom("depends_on".to_string()),
+        );
+        store.add_relation(&relation).unwrap();
+        assert!(store.delete_relation_type("depends_on").is_err());
+        store.delete_relation_type("replaces").unwrap();
+        assert!(store.delete_relation_type("replaces").is_err());
+        assert_eq!(store.list_relation_types().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_commit_links() {
+        let tmp = TempDir::new().unwrap();
+        let store = LoroStore::init(tmp.path()).unwrap();
+        let task = Task::new("Add login".to_string(), 1);
+        let other = uuid::Uuid::new_v4();
+
+        let mut link = CommitLink::new(task.base.id, "task".to_string(), "ABC1234".to_string());
+        link.summary = Some("Add login form".to_string());
+        assert!(store.add_commit_link(&link).unwrap());
+        assert!(!store.add_commit_link(&link).unwrap());
+        store
+            .add_commit_link(&CommitLink::new(
+                other,
+                "task".to_string(),
+                "def5678".to_string(),
+            ))
+            .unwrap();
+        store.save().unwrap();
+
+        let store2 = LoroStore::open(tmp.path()).unwrap();
+        let links = store2.get_commit_links(&task.base.id).unwrap();
+        assert_eq!(links.len(), 1);
+        assert_eq!(links[0].sha, "abc1234");
+        assert_eq!(links[0].summary.as_deref(), Some("Add login form"));
+    }
+
+    #[test]
+    fn test_aliases() {
+        let tmp = TempDir::new().unwrap();
+        let store = LoroStore::init(tmp.path()).unwrap();
+        let decision = Decision::new("Use OAuth".to_string(), 1);
+        store.add_decision(&decision).unwrap();
+        let task = Task::new("Add login".to_string(), 2);
+        store.add_task(&task).unwrap();
+
+        let alias = store.set_alias(&decision.base.id, "Auth-Decision").unwrap();
+        assert_eq!(alias, "auth-decision");
+        assert_eq!(
+            store.lookup_id("auth-decision", None),
+            Some((decision.base.id, "decision"))
+        );
+        assert!(store.set_alias(&task.base.id, "auth-decision").is_err());
+        assert!(store.set_alias(&task.base.id, "14").is_err());
+        assert!(store.set_alias(&task.base.id, "has space").is_err());
+
+        // A new alias replaces the old one
+        store.set_alias(&decision.base.id, "oauth").unwrap();
+        assert_eq!(store.lookup_id("auth-decision", None), None);
+        assert_eq!(store.alias_of(&decision.base.id).as_deref(), Some("oauth"));
+        store.save().unwrap();
+
+        let store = LoroStore::open(tmp.path()).unwrap();
+        assert_eq!(
+            store.list_aliases().unwrap(),
+            vec![("oauth".to_string(), decision.base.id)]
+        );
+
+        // Trashed entities keep their alias until purged
+        store.trash_entity(&decision.base.id, None).unwrap();
+        assert!(store.list_aliases().unwrap().is_empty());
+        store.set_alias(&task.base.id, "oauth").unwrap();
+        store.remove_alias("OAUTH").unwrap();
+        assert!(store.remove_alias("oauth").is_err());
+        store.purge_trash(None).unwrap();
+        assert_eq!(store.alias_of(&decision.base.id), None);
+    }
+
+    #[test]
+    fn test_collections() {
+        let tmp = TempDir::new().unwrap();
+        let store = LoroStore::init(tmp.path()).unwrap();
+        let decision = Decision::new("Use Postgres".to_string(), 1);
+        store.add_decision(&decision).unwrap();
+        let task = Task::new("Add migrations".to_string(), 2);
+        store.add_task(&task).unwrap();
+        let note = Note::new("Login copy".to_string(), 3);
+        store.add_note(&note).unwrap();
+
+        assert_eq!(
+            store.set_collection(&decision.base.id, "Backend").unwrap(),
+            ("backend".to_string(), 1)
+        );
+        assert_eq!(
+            store.set_collection(&task.base.id, "backend").unwrap(),
+            ("backend".to_string(), 2)
+        );
+        assert_eq!(
+            store.set_collection(&note.base.id, "frontend").unwrap(),
+            ("frontend".to_string(), 1)
+        );
+        // Setting the same collection again keeps the number
+        assert_eq!(
+            store.set_collection(&decision.base.id, "backend").unwrap(),
+            ("backend".to_string(), 1)
+        );
+        assert!(store.set_collection(&task.base.id, "3").is_err());
+        assert_eq!(
+            store.lookup_id("backend/2", None),
+            Some((task.base.id, "task"))
+        );
+        store.save().unwrap();
+
+        let store = LoroStore::open(tmp.path()).unwrap();
+        assert_eq!(
+            store.list_collections().unwrap(),
+            vec![("backend".to_string(), 2), ("frontend".to_string(), 1)]
+        );
+        assert_eq!(
+            store.collection_members("backend"),
+            HashSet::from([decision.base.id.to_string(), task.base.id.to_string()])
+        );
+
+        // Trashed members keep their number, so it is not handed out again
+        store.trash_entity(&task.base.id, None).unwrap();
+        assert_eq!(store.lookup_id("backend/2", None), None);
+        assert_eq!(
+            store.set_collection(&note.base.id, "backend").unwrap(),
+            ("backend".to_string(), 3)
+        );
+        let view = store.collection_view("backend").unwrap();
+        assert_eq!(
+            view.entity_ids(),
+            HashSet::from([decision.base.id.to_string(), note.base.id.to_string()])
+        );
+        assert!(store.collection_view("frontend").is_err());
+        assert_eq!(store.entity_ids().len(), 2);
+
+        store.remove_collection(&note.base.id).unwrap();
+        assert!(store.remove_collection(&note.base.id).is_err());
+        assert_eq!(store.collection_of(&note.base.id), None);
+
+        // Nor is the number of an entity taken out of the collection
+        assert_eq!(
+            store.set_collection(&note.base.id, "backend").unwrap(),
+            ("backend".to_string(), 4)
+        );
+        store.set_collection(&note.base.id, "frontend").unwrap();
+        store.save().unwrap();
+        let store = LoroStore::open(tmp.path()).unwrap();
+        assert_eq!(
+            store.set_collection(&note.base.id, "backend").unwrap(),
+            ("backend".to_string(), 5)
+        );
+        store.remove_collection(&note.base.id).unwrap();
+        store.purge_trash(None).unwrap();
+        assert_eq!(store.collection_of(&task.base.id), None);
+        assert_eq!(
+            store.list_collections().unwrap(),
+            vec![("backend".to_string(), 1)]
+        );
+    }
+
+    #[test]
+    fn test_add_inferred_references() {
+        let tmp = TempDir::new().unwrap();
+        let store = LoroStore::init(tmp.path()).unwrap();
+        let decision = Decision::new("Use Postgres".to_string(), 1);
+        let note = Note::new("Benchmarks".to_string(), 2);
+        store.add_decision(&decision).unwrap();
+        store.add_note(&note).unwrap();
+
+        let text = "Numbers for #1, see med-2 (this note) and #99";
+        let added = store
+            .add_inferred_references(note.base.id, "note", text, None)
+            .unwrap();
+        assert_eq!(added.len(), 1);
+        assert_eq!(added[0].target_id, decision.base.id);
+        assert_eq!(added[0].target_type, "decision");
+        assert_eq!(added[0].relation_type, RelationType::References);
+        assert_eq!(added[0].properties["inferred"], "true");
+
+        // Existing references are not added again
+        assert!(store
+            .add_inferred_references(note.base.id, "note", text, None)
+            .unwrap()
+            .is_empty());
+        assert_eq!(store.list_relations().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_add_next_occurrence() {
+        let tmp = TempDir::new().unwrap();
+        let store = LoroStore::init(tmp.path()).unwrap();
+
+        let mut task = Task::new("Rotate keys".to_string(), 1);
+        task.due_date = chrono::NaiveDate::from_ymd_opt(2026, 1, 31);
+        task.recurrence = Some("monthly".parse().unwrap());
+        task.base.tags = vec!["ops".to_string()];
+        store.add_task(&task).unwrap();
+
+        let today = chrono::NaiveDate::from_ymd_opt(2026, 2, 10).unwrap();
+        let next = store.add_next_occurrence(&task, today).unwrap().unwrap();
+        assert_eq!(next.base.sequence_number, 2);
+        assert_eq!(next.status, TaskStatus::Todo);
+        assert_eq!(next.due_date, chrono::NaiveDate::from_ymd_opt(2026, 2, 28));
+
+        let stored = store.get_task(&next.base.id).unwrap().unwrap();
+        assert_eq!(stored.recurrence, task.recurrence);
+        assert_eq!(stored.base.tags, vec!["ops".to_string()]);
+
+        // Cron rules pick the next matching day: here the next Monday
+        let mut cron = Task::new("Weekly review".to_string(), 3);
+        cron.recurrence = Some("0 9 * * 1".parse().unwrap());
+        let next = cron.next_occurrence(4, today).unwrap();
+        assert_eq!(next.due_date, chrono::NaiveDate::from_ymd_opt(2026, 2, 16));
+
+        // Non-recurring tasks spawn nothing
+        let plain = Task::new("One-off".to_string(), 5);
+        assert!(store.add_next_occurrence(&plain, today).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_entity_history() {
+        let tmp = TempDir::new().unwrap();
+        let store = LoroStore::init(tmp.path()).unwrap();
+
+        let task = Task::new("Draft plan".to_string(), 1);
+        store.add_task(&task).unwrap();
+        store
+            .add_note(&Note::new("Unrelated".to_string(), 2))
+            .unwrap();
+        store
+            .update_task(
+                &task.base.id,
+                TaskUpdate {
+                    title: Some("Final plan".to_string()),
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        store.delete_task(&task.base.id).unwrap();
+
+        // Creation, the title change and the deletion; the note is not a version
+        let versions = store.entity_history(&task.base.id).unwrap();
+        assert_eq!(versions.len(), 3);
+        assert_eq!(versions[0].state.as_ref().unwrap()["title"], "Draft plan");
+        assert!(versions[1].changed_fields.contains(&"title".to_string()));
+        assert_eq!(versions[1].state.as_ref().unwrap()["title"], "Final plan");
+        assert!(versions[2].state.is_none());
+        assert!(versions.iter().all(|v| v.timestamp.is_some()));
+    }
+
+    #[test]
+    fn test_compact() {
+        let tmp = TempDir::new().unwrap();
+        let store = LoroStore::init(tmp.path()).unwrap();
+
+        let task = Task::new("Draft".to_string(), 1);
+        store.add_task(&task).unwrap();
+        for i in 0..50 {
+            store
+                .update_task(
+                    &task.base.id,
+                    TaskUpdate {
+                        title: Some(format!("Draft {}", i)),
+                        ..Default::default()
+                    },
+                )
+                .unwrap();
+        }
+        store.save().unwrap();
+
+        // A cutoff before every change leaves the history as it is
+        let before = chrono::Utc::now() - chrono::Duration::days(1);
+        store.compact(Some(before)).unwrap();
+        let store = LoroStore::open(tmp.path()).unwrap();
+        assert!(!store.doc.is_shallow());
+
+        let stats = store.compact(None).unwrap();
+        assert!(stats.after_bytes < stats.before_bytes);
+
+        let reopened = LoroStore::open(tmp.path()).unwrap();
+        assert!(reopened.doc.is_shallow());
+        let task = reopened.get_task(&task.base.id).unwrap().unwrap();
+        assert_eq!(task.base.title, "Draft 49");
+        assert!(!tmp.path().join(".medulla/loro.db.compact").exists());
+
+        // Changes made after compaction are saved as usual
+        reopened
+            .add_note(&Note::new("After".to_string(), 2))
+            .unwrap();
+        reopened.save().unwrap();
+        let reopened = LoroStore::open(tmp.path()).unwrap();
+        assert_eq!(reopened.list_notes().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_changed_fields() {
+        let before = serde_json::json!({"title": "a", "tags": []});
+        let after = serde_json::json!({"title": "b", "tags": [], "assignee": "x"});
+        assert_eq!(
+            changed_fields(Some(&before), Some(&after)),
+            vec!["assignee".to_string(), "title".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_add_and_list_relation() {
+        let tmp = TempDir::new().unwrap();
+        let store = LoroStore::init(tmp.path()).unwrap();
+
+        // Create two decisions
+        let decision1 = Decision::new("Use Rust".to_string(), 1);
+        let decision2 = Decision::new("Use Loro".to_string(), 2);
+
+        store.add_decision(&decision1).unwrap();
+        store.add_decision(&decision2).unwrap();
+
+        // Create a relation: decision2 implements decision1
+        let relation = crate::entity::Relation::new(
+            decision2.base.id,
+            "decision".to_string(),
+            decision1.base.id,
+            "decision".to_string(),
+            crate::entity::RelationType::Implements,
+        );
+
+        store.add_relation(&relation).unwrap();
+        store.save().unwrap();
+
+        // Reopen and verify
+        let store2 = LoroStore::open(tmp.path()).unwrap();
+        let relations = store2.list_relations().unwrap();
+
+        assert_eq!(relations.len(), 1);
+        assert_eq!(relations[0].source_id, decision2.base.id);
+        assert_eq!(relations[0].target_id, decision1.base.id);
+        assert_eq!(
+            relations[0].relation_type,
+            crate::entity::RelationType::Implements
+        );
+    }
+
+    #[test]
+    fn test_get_relations_from_and_to() {
+        let tmp = TempDir::new().unwrap();
+        let store = LoroStore::init(tmp.path()).unwrap();
+
+        let decision1 = Decision::new("Decision A".to_string(), 1);
+        let decision2 = Decision::new("Decision B".to_string(), 2);
+        let decision3 = Decision::new("Decision C".to_string(), 3);
+
+        store.add_decision(&decision1).unwrap();
+        store.add_decision(&decision2).unwrap();
+        store.add_decision(&decision3).unwrap();
+
+        // decision2 supersedes decision1
+        let rel1 = crate::entity::Relation::new(
+            decision2.base.id,
+            "decision".to_string(),
+            decision1.base.id,
+            "decision".to_string(),
+            crate::entity::RelationType::Supersedes,
+        );
+
+        // decision3 references decision2
+        let rel2 = crate::entity::Relation::new(
+            decision3.base.id,
+            "decision".to_string(),
+            decision2.base.id,
+            "decision".to_string(),
+            crate::entity::RelationType::References,
+        );
+
+        store.add_relation(&rel1).unwrap();
+        store.add_relation(&rel2).unwrap();
+        store.save().unwrap();
+
+        // Test get_relations_from
+        let from_d2 = store
+            .get_relations_from(&decision2.base.id.to_string())
+            .unwrap();
+        assert_eq!(from_d2.len(), 1);
+        assert_eq!(
+            from_d2[0].relation_type,
+            crate::entity::RelationType::Supersedes
+        );
+
+        // Test get_relations_to
+        let to_d2 = store
+            .get_relations_to(&decision2.base.id.to_string())
+            .unwrap();
+        assert_eq!(to_d2.len(), 1);
+        assert_eq!(
+            to_d2[0].relation_type,
+            crate::entity::RelationType::References
+        );
+    }
+
+    #[test]
+    fn test_delete_relation() {
+        let tmp = TempDir::new().unwrap();
+        let store = LoroStore::init(tmp.path()).unwrap();
+
+        let decision1 = Decision::new("Decision A".to_string(), 1);
+        let decision2 = Decision::new("Decision B".to_string(), 2);
+
+        store.add_decision(&decision1).unwrap();
+        store.add_decision(&decision2).unwrap();
+
+        let relation = crate::entity::Relation::new(
+            decision2.base.id,
+            "decision".to_string(),
+            decision1.base.id,
             "decision".to_string(),
             crate::entity::RelationType::Supersedes,
         );
@@ -2191,6 +5592,50 @@ mod tests {
         assert_eq!(store.list_relations().unwrap().len(), 0);
     }
 
+    #[test]
+    fn test_resolve_relation() {
+        let tmp = TempDir::new().unwrap();
+        let store = LoroStore::init(tmp.path()).unwrap();
+
+        let task1 = Task::new("Blocker".to_string(), 1);
+        let task2 = Task::new("Blocked".to_string(), 2);
+
+        let relation = crate::entity::Relation::new(
+            task1.base.id,
+            "task".to_string(),
+            task2.base.id,
+            "task".to_string(),
+            crate::entity::RelationType::Blocks,
+        );
+        store.add_relation(&relation).unwrap();
+        assert!(store.list_relations().unwrap()[0].is_active());
+
+        let resolved = store
+            .resolve_relation(
+                &task1.base.id.to_string(),
+                "blocks",
+                &task2.base.id.to_string(),
+            )
+            .unwrap();
+        assert!(resolved.resolved_at.is_some());
+        store.save().unwrap();
+
+        // Resolution survives a reload and the relation is kept
+        let reopened = LoroStore::open(tmp.path()).unwrap();
+        let relations = reopened.list_relations().unwrap();
+        assert_eq!(relations.len(), 1);
+        assert!(!relations[0].is_active());
+
+        // Resolving a missing relation fails
+        assert!(store
+            .resolve_relation(
+                &task2.base.id.to_string(),
+                "blocks",
+                &task1.base.id.to_string(),
+            )
+            .is_err());
+    }
+
     #[test]
     fn test_get_decision() {
         let tmp = TempDir::new().unwrap();
@@ -2380,5 +5825,260 @@ mod tests {
         assert_eq!(links.len(), 1);
         assert_eq!(links[0].base.title, "Project Docs");
         assert_eq!(links[0].url, "https://docs.example.com");
+        assert!(links[0].fetched_at.is_none());
+    }
+
+    #[test]
+    fn test_update_link_fetched_metadata() {
+        let tmp = TempDir::new().unwrap();
+        let store = LoroStore::init(tmp.path()).unwrap();
+        let mut link = Link::new(
+            "Docs".to_string(),
+            "https://docs.example.com".to_string(),
+            1,
+        );
+        link.description = Some("Old description".to_string());
+        store.add_link(&link).unwrap();
+
+        let fetched_at = chrono::Utc::now();
+        let update = LinkUpdate {
+            fetched: Some(LinkFetch {
+                description: None,
+                favicon: Some("https://docs.example.com/favicon.ico".to_string()),
+                content_hash: Some("abc".to_string()),
+                fetched_at,
+            }),
+            ..Default::default()
+        };
+        store.update_link(&link.base.id, update).unwrap();
+
+        let link = store.get_link(&link.base.id).unwrap().unwrap();
+        assert_eq!(link.description, None);
+        assert_eq!(
+            link.favicon.as_deref(),
+            Some("https://docs.example.com/favicon.ico")
+        );
+        assert_eq!(link.content_hash.as_deref(), Some("abc"));
+        assert_eq!(
+            link.fetched_at.map(|t| t.timestamp()),
+            Some(fetched_at.timestamp())
+        );
+    }
+
+    #[test]
+    fn test_lookup_id_follows_changes() {
+        let tmp = TempDir::new().unwrap();
+        let store = LoroStore::init(tmp.path()).unwrap();
+
+        let task = Task::new("Index me".to_string(), 1);
+        store.add_task(&task).unwrap();
+        assert_eq!(store.lookup_id("1", None), Some((task.base.id, "task")));
+
+        let prefix = &task.base.id.to_string()[..8].to_uppercase();
+        assert_eq!(
+            store.lookup_id(prefix, Some("task")),
+            Some((task.base.id, "task"))
+        );
+        assert_eq!(store.lookup_id(prefix, Some("note")), None);
+
+        // Entities added or removed after the index was built are seen
+        let note = Note::new("Later".to_string(), 2);
+        store.add_note(&note).unwrap();
+        assert_eq!(store.lookup_id("2", None), Some((note.base.id, "note")));
+
+        store.delete_task(&task.base.id).unwrap();
+        assert_eq!(store.lookup_id("1", None), None);
+    }
+
+    #[test]
+    fn test_author_stamps_writes() {
+        let tmp = TempDir::new().unwrap();
+        let store = LoroStore::init(tmp.path())
+            .unwrap()
+            .with_author(Some("Ada".to_string()));
+
+        let note = Note::new("Attributed".to_string(), 1);
+        store.add_note(&note).unwrap();
+        let mut named = Note::new("Named".to_string(), 2);
+        named.base.created_by = Some("Grace".to_string());
+        store.add_note(&named).unwrap();
+
+        let stored = store.get_note(&note.base.id).unwrap().unwrap();
+        assert_eq!(stored.base.created_by.as_deref(), Some("Ada"));
+        assert_eq!(stored.base.updated_by, None);
+        let stored = store.get_note(&named.base.id).unwrap().unwrap();
+        assert_eq!(stored.base.created_by.as_deref(), Some("Grace"));
+
+        let updates = NoteUpdate {
+            title: Some("Edited".to_string()),
+            ..Default::default()
+        };
+        store.update_note(&named.base.id, updates).unwrap();
+        let stored = store.get_note(&named.base.id).unwrap().unwrap();
+        assert_eq!(stored.base.created_by.as_deref(), Some("Grace"));
+        assert_eq!(stored.base.updated_by.as_deref(), Some("Ada"));
+    }
+
+    #[test]
+    fn test_resolve_sequence_collisions_after_merge() {
+        let tmp = TempDir::new().unwrap();
+        let store = LoroStore::init(tmp.path()).unwrap();
+        store
+            .add_decision(&Decision::new("Shared".to_string(), 1))
+            .unwrap();
+        store.save().unwrap();
+
+        // Another clone creates an entity offline with the same next number
+        let replica =
+            LoroStore::from_bytes(&fs::read(store.path()).unwrap(), PathBuf::new()).unwrap();
+        let mut theirs = Task::new("Theirs".to_string(), replica.next_sequence_number());
+        theirs.base.created_at = chrono::Utc::now() + chrono::Duration::seconds(60);
+        replica.add_task(&theirs).unwrap();
+
+        let ours = Note::new("Ours".to_string(), store.next_sequence_number());
+        store.add_note(&ours).unwrap();
+        assert_eq!(ours.base.sequence_number, theirs.base.sequence_number);
+
+        let bytes = replica.doc.export(loro::ExportMode::Snapshot).unwrap();
+        let reassigned = store.import_replica(&bytes).unwrap();
+
+        assert_eq!(reassigned.len(), 1);
+        assert_eq!(reassigned[0].id, theirs.base.id);
+        assert_eq!(reassigned[0].kept_by, ours.base.id);
+        assert_eq!(reassigned[0].new_sequence, 3);
+        assert_eq!(store.lookup_id("2", None), Some((ours.base.id, "note")));
+        assert_eq!(store.lookup_id("3", None), Some((theirs.base.id, "task")));
+        assert_eq!(store.next_sequence_number(), 4);
+        assert_eq!(store.count_sequence_collisions(), 0);
+        assert!(store.resolve_sequence_collisions().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_resolve_collection_sequence_collisions_after_merge() {
+        let tmp = TempDir::new().unwrap();
+        let store = LoroStore::init(tmp.path()).unwrap();
+        let ours = Task::new("Ours".to_string(), 1);
+        let mut theirs = Task::new("Theirs".to_string(), 2);
+        theirs.base.created_at = ours.base.created_at + chrono::Duration::seconds(60);
+        store.add_task(&ours).unwrap();
+        store.add_task(&theirs).unwrap();
+        store.save().unwrap();
+
+        // Each clone adds a different task to the collection offline
+        let replica =
+            LoroStore::from_bytes(&fs::read(store.path()).unwrap(), PathBuf::new()).unwrap();
+        assert_eq!(
+            replica.set_collection(&theirs.base.id, "backend").unwrap(),
+            ("backend".to_string(), 1)
+        );
+        assert_eq!(
+            store.set_collection(&ours.base.id, "backend").unwrap(),
+            ("backend".to_string(), 1)
+        );
+
+        let bytes = replica.doc.export(loro::ExportMode::Snapshot).unwrap();
+        store.doc.import(&bytes).unwrap();
+        assert_eq!(store.count_sequence_collisions(), 1);
+
+        let reassigned = store.resolve_sequence_collisions().unwrap();
+        assert_eq!(reassigned.len(), 1);
+        assert_eq!(reassigned[0].id, theirs.base.id);
+        assert_eq!(reassigned[0].kept_by, ours.base.id);
+        assert_eq!(reassigned[0].collection.as_deref(), Some("backend"));
+        assert_eq!(reassigned[0].old_sequence, 1);
+        assert_eq!(reassigned[0].new_sequence, 2);
+        assert_eq!(
+            store.lookup_id("backend/1", None),
+            Some((ours.base.id, "task"))
+        );
+        assert_eq!(
+            store.lookup_id("backend/2", None),
+            Some((theirs.base.id, "task"))
+        );
+        assert_eq!(store.count_sequence_collisions(), 0);
+
+        // The counter moved past the new number
+        let next = Task::new("Next".to_string(), 3);
+        store.add_task(&next).unwrap();
+        assert_eq!(store.set_collection(&next.base.id, "backend").unwrap().1, 3);
+    }
+
+    #[test]
+    fn test_merge_conflicts_after_concurrent_edits() {
+        let tmp = TempDir::new().unwrap();
+        let store = LoroStore::init(tmp.path())
+            .unwrap()
+            .with_author(Some("Ada".to_string()));
+        let task = Task::new("Ship it".to_string(), 1);
+        store.add_task(&task).unwrap();
+        store.save().unwrap();
+
+        let replica = LoroStore::from_bytes(&fs::read(store.path()).unwrap(), PathBuf::new())
+            .unwrap()
+            .with_author(Some("Grace".to_string()));
+        replica
+            .update_task(
+                &task.base.id,
+                TaskUpdate {
+                    title: Some("Ship it today".to_string()),
+                    priority: Some(TaskPriority::High),
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        store
+            .update_task(
+                &task.base.id,
+                TaskUpdate {
+                    title: Some("Ship it tomorrow".to_string()),
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        assert!(store.merge_conflicts(None).unwrap().is_empty());
+
+        let bytes = replica.doc.export(loro::ExportMode::Snapshot).unwrap();
+        store.import_replica(&bytes).unwrap();
+
+        // Only the title was edited on both sides
+        let conflicts = store.merge_conflicts(None).unwrap();
+        assert_eq!(conflicts.len(), 1);
+        let conflict = &conflicts[0];
+        assert_eq!(conflict.entity_id, task.base.id);
+        assert_eq!(conflict.entity_type, "task");
+        assert_eq!(conflict.field, "title");
+        assert_eq!(conflict.sequence_number, Some(1));
+
+        let mut values: Vec<(Option<&str>, &str)> = conflict
+            .edits
+            .iter()
+            .map(|e| (e.author.as_deref(), e.value.as_str().unwrap()))
+            .collect();
+        values.sort();
+        assert_eq!(
+            values,
+            vec![
+                (Some("Ada"), "Ship it tomorrow"),
+                (Some("Grace"), "Ship it today")
+            ]
+        );
+        let title = store.get_task(&task.base.id).unwrap().unwrap().base.title;
+        assert_eq!(conflict.resolved, serde_json::json!(title));
+
+        // Later edits made after seeing the merge are not concurrent
+        store
+            .update_task(
+                &task.base.id,
+                TaskUpdate {
+                    title: Some("Shipped".to_string()),
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        assert_eq!(store.merge_conflicts(None).unwrap().len(), 1);
+        let past = chrono::Utc::now() - chrono::Duration::days(1);
+        assert_eq!(store.merge_conflicts(Some(past)).unwrap().len(), 1);
+        let future = chrono::Utc::now() + chrono::Duration::days(1);
+        assert!(store.merge_conflicts(Some(future)).unwrap().is_empty());
     }
 }
@@ -1,5 +1,10 @@
+mod id_index;
 mod loro_store;
 
+pub(crate) use loro_store::changed_fields;
 pub use loro_store::{
-    ComponentUpdate, DecisionUpdate, LinkUpdate, LoroStore, NoteUpdate, PromptUpdate, TaskUpdate,
+    validate_alias, validate_collection, Attachment, CompactStats, ComponentUpdate,
+    ConflictingEdit, DecisionUpdate, EntityVersion, FieldConflict, LinkFetch, LinkUpdate,
+    LoroStore, MilestoneUpdate, NoteUpdate, PromptUpdate, QuestionUpdate, RestoredEntity,
+    SequenceReassignment, TaskUpdate, TrashedEntity, ATTACHMENTS_DIR,
 };
@@ -0,0 +1,537 @@
+//! Import of existing Architecture Decision Records.
+//!
+//! Reads a directory of Markdown ADRs in the Nygard format (`## Status`,
+//! `## Context`, `## Decision`, `## Consequences` sections) or MADR (YAML
+//! front matter or `* Status:` lines, `## Context and Problem Statement`,
+//! `### Consequences`). Each file becomes a decision; "Supersedes" and
+//! "Superseded by" links between the files become `supersedes` relations.
+
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::OnceLock;
+
+use chrono::{NaiveDate, TimeZone, Utc};
+use regex::Regex;
+use serde::Serialize;
+
+use crate::entity::{Decision, DecisionStatus, Relation, RelationType};
+use crate::error::Result;
+use crate::storage::LoroStore;
+
+/// Fields read from one ADR file.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct AdrDocument {
+    pub title: String,
+    pub status: DecisionStatus,
+    pub date: Option<NaiveDate>,
+    pub context: Option<String>,
+    pub consequences: Vec<String>,
+    /// Remaining sections (the decision itself, options, links, ...)
+    pub content: Option<String>,
+    /// File names of the ADRs this one supersedes
+    pub supersedes: Vec<String>,
+    /// File names of the ADRs that supersede this one
+    pub superseded_by: Vec<String>,
+}
+
+/// Result of an ADR import.
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct AdrImportStats {
+    pub decisions: usize,
+    pub supersedes: usize,
+    /// Files not imported, with the reason
+    pub skipped: Vec<(String, String)>,
+}
+
+struct Section<'a> {
+    heading: String,
+    level: usize,
+    lines: Vec<&'a str>,
+}
+
+/// Parse one ADR. Returns None if the file has no `# Title` heading.
+pub fn parse_adr(markdown: &str) -> Option<AdrDocument> {
+    let (front_matter, body) = split_front_matter(markdown);
+
+    let mut title = None;
+    let mut preamble = Vec::new();
+    let mut sections: Vec<Section> = Vec::new();
+    for line in body.lines() {
+        if title.is_none() && sections.is_empty() {
+            if let Some(heading) = line.strip_prefix("# ") {
+                title = Some(clean_title(heading));
+                continue;
+            }
+        }
+        if let Some((level, heading)) = subheading(line) {
+            sections.push(Section {
+                heading: heading.to_string(),
+                level,
+                lines: Vec::new(),
+            });
+            continue;
+        }
+        match sections.last_mut() {
+            Some(section) => section.lines.push(line),
+            None => preamble.push(line),
+        }
+    }
+    let title = title.filter(|t| !t.is_empty())?;
+
+    let mut adr = AdrDocument {
+        title,
+        ..Default::default()
+    };
+
+    // Status and date: front matter, then `Status:` lines, then sections
+    let mut status_lines: Vec<String> = Vec::new();
+    let mut date = front_matter.get("date").cloned();
+    if let Some(status) = front_matter.get("status") {
+        status_lines.push(status.clone());
+    }
+    for line in &preamble {
+        if let Some(value) = field_line(line, "status") {
+            status_lines.push(value.to_string());
+        } else if let Some(value) = field_line(line, "date") {
+            date.get_or_insert_with(|| value.to_string());
+        }
+    }
+    for section in &sections {
+        match section_kind(&section.heading) {
+            SectionKind::Status => status_lines.extend(
+                section
+                    .lines
+                    .iter()
+                    .map(|l| l.trim())
+                    .filter(|l| !l.is_empty())
+                    .map(String::from),
+            ),
+            SectionKind::Date => {
+                if let Some(line) = section.lines.iter().find(|l| !l.trim().is_empty()) {
+                    date.get_or_insert_with(|| line.trim().to_string());
+                }
+            }
+            _ => {}
+        }
+    }
+
+    if let Some(first) = status_lines.first() {
+        adr.status = parse_status(first);
+    }
+    adr.date = date.and_then(|d| NaiveDate::parse_from_str(d.get(..10)?, "%Y-%m-%d").ok());
+    for line in &status_lines {
+        let lower = line.to_lowercase();
+        if lower.contains("superseded by") {
+            adr.superseded_by.extend(link_targets(line));
+        } else if lower.contains("supersedes") {
+            adr.supersedes.extend(link_targets(line));
+        }
+    }
+
+    let mut content = Vec::new();
+    for section in &sections {
+        let text = section_text(&section.lines);
+        match section_kind(&section.heading) {
+            SectionKind::Status | SectionKind::Date => {}
+            SectionKind::Context => {
+                if !text.is_empty() {
+                    adr.context = Some(text);
+                }
+            }
+            SectionKind::Consequences => adr.consequences = list_items(&section.lines, &text),
+            SectionKind::Other => {
+                content.push(format!("{} {}", "#".repeat(section.level), section.heading));
+                if !text.is_empty() {
+                    content.push(text);
+                }
+            }
+        }
+    }
+    let content = content.join("\n\n");
+    if !content.is_empty() {
+        adr.content = Some(content);
+    }
+
+    Some(adr)
+}
+
+/// Import every `*.md` ADR in `dir` as a decision, in file name order.
+/// Files whose title matches an existing decision are skipped, so running
+/// the import again only adds new ADRs.
+pub fn import_adr_dir(store: &LoroStore, dir: &Path) -> Result<AdrImportStats> {
+    let mut paths: Vec<_> = std::fs::read_dir(dir)?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|p| {
+            p.is_file()
+                && p.extension()
+                    .is_some_and(|ext| ext.eq_ignore_ascii_case("md"))
+        })
+        .collect();
+    paths.sort();
+
+    let existing: Vec<String> = store
+        .list_decisions()?
+        .into_iter()
+        .map(|d| d.base.title.to_lowercase())
+        .collect();
+
+    let mut stats = AdrImportStats::default();
+    let mut imported: Vec<(String, AdrDocument, Decision)> = Vec::new();
+    for path in paths {
+        let file_name = path
+            .file_name()
+            .map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let stem = file_name.to_lowercase();
+        if stem.starts_with("readme") || stem.starts_with("index") || stem.contains("template") {
+            stats
+                .skipped
+                .push((file_name, "not an ADR (index or template)".to_string()));
+            continue;
+        }
+
+        let Some(adr) = parse_adr(&std::fs::read_to_string(&path)?) else {
+            stats
+                .skipped
+                .push((file_name, "no `# Title` heading".to_string()));
+            continue;
+        };
+        if existing.contains(&adr.title.to_lowercase()) {
+            stats
+                .skipped
+                .push((file_name, "a decision with this title exists".to_string()));
+            continue;
+        }
+
+        let seq = store.next_sequence_number() + imported.len() as u32;
+        let mut decision = Decision::new(adr.title.clone(), seq);
+        decision.status = adr.status;
+        decision.context = adr.context.clone();
+        decision.consequences = adr.consequences.clone();
+        decision.base.content = adr.content.clone();
+        if let Some(date) = adr.date.and_then(|d| d.and_hms_opt(0, 0, 0)) {
+            decision.base.created_at = Utc.from_utc_datetime(&date);
+            decision.base.updated_at = decision.base.created_at;
+        }
+        imported.push((file_name, adr, decision));
+    }
+
+    // Resolve supersede links between the imported files: (new, old)
+    let by_file: HashMap<String, usize> = imported
+        .iter()
+        .enumerate()
+        .map(|(i, (file, _, _))| (file.to_lowercase(), i))
+        .collect();
+    let mut pairs: Vec<(usize, usize)> = Vec::new();
+    for (i, (_, adr, _)) in imported.iter().enumerate() {
+        for target in &adr.supersedes {
+            if let Some(&old) = by_file.get(&target.to_lowercase()) {
+                pairs.push((i, old));
+            }
+        }
+        for target in &adr.superseded_by {
+            if let Some(&new) = by_file.get(&target.to_lowercase()) {
+                pairs.push((new, i));
+            }
+        }
+    }
+    pairs.sort();
+    pairs.dedup();
+    pairs.retain(|(new, old)| new != old);
+
+    for &(new, old) in &pairs {
+        let new_id = imported[new].2.base.id.to_string();
+        let old_decision = &mut imported[old].2;
+        old_decision.status = DecisionStatus::Superseded;
+        old_decision.superseded_by = Some(new_id);
+    }
+
+    for (_, _, decision) in &imported {
+        store.add_decision(decision)?;
+        stats.decisions += 1;
+    }
+    for &(new, old) in &pairs {
+        store.add_relation(&Relation::new(
+            imported[new].2.base.id,
+            "decision".to_string(),
+            imported[old].2.base.id,
+            "decision".to_string(),
+            RelationType::Supersedes,
+        ))?;
+        stats.supersedes += 1;
+    }
+    store.save()?;
+
+    Ok(stats)
+}
+
+enum SectionKind {
+    Status,
+    Date,
+    Context,
+    Consequences,
+    Other,
+}
+
+fn section_kind(heading: &str) -> SectionKind {
+    let heading = heading.trim().to_lowercase();
+    if heading == "status" {
+        SectionKind::Status
+    } else if heading == "date" {
+        SectionKind::Date
+    } else if heading.starts_with("context") {
+        SectionKind::Context
+    } else if heading.starts_with("consequences") {
+        SectionKind::Consequences
+    } else {
+        SectionKind::Other
+    }
+}
+
+/// `## Heading` or `### Heading`, with its level
+fn subheading(line: &str) -> Option<(usize, &str)> {
+    let level = line.chars().take_while(|&c| c == '#').count();
+    if !(2..=3).contains(&level) {
+        return None;
+    }
+    line[level..]
+        .strip_prefix(' ')
+        .map(|heading| (level, heading.trim()))
+}
+
+/// Drop ADR numbering such as "1. ", "0003 - " or "ADR-0003: " from a title
+fn clean_title(heading: &str) -> String {
+    static NUMBERING: OnceLock<Regex> = OnceLock::new();
+    let numbering = NUMBERING
+        .get_or_init(|| Regex::new(r"(?i)^\s*(adr[\s_-]*)?\d+\s*[.:)-]?\s+").expect("valid regex"));
+    numbering.replace(heading.trim(), "").trim().to_string()
+}
+
+/// Value of a `Status: x` line, optionally a list item or bold
+fn field_line<'a>(line: &'a str, field: &str) -> Option<&'a str> {
+    let line = line.trim().trim_start_matches(['*', '-']).trim();
+    let line = line.trim_start_matches("**");
+    let (name, value) = line.split_once(':')?;
+    if !name
+        .trim()
+        .trim_end_matches("**")
+        .eq_ignore_ascii_case(field)
+    {
+        return None;
+    }
+    Some(value.trim().trim_start_matches("**").trim())
+}
+
+fn parse_status(value: &str) -> DecisionStatus {
+    let word: String = value
+        .trim()
+        .chars()
+        .take_while(|c| c.is_alphabetic())
+        .collect::<String>()
+        .to_lowercase();
+    match word.as_str() {
+        "accepted" | "approved" | "adopted" => DecisionStatus::Accepted,
+        "deprecated" | "rejected" | "retired" => DecisionStatus::Deprecated,
+        "superseded" => DecisionStatus::Superseded,
+        _ => DecisionStatus::Proposed,
+    }
+}
+
+/// File names of the Markdown links on a line, e.g. `0002-foo.md` for
+/// `[ADR 2](./0002-foo.md#status)`
+fn link_targets(line: &str) -> Vec<String> {
+    let mut targets = Vec::new();
+    let mut rest = line;
+    while let Some(start) = rest.find("](") {
+        rest = &rest[start + 2..];
+        let Some(end) = rest.find(')') else {
+            break;
+        };
+        let target = rest[..end].split('#').next().unwrap_or_default();
+        if let Some(name) = Path::new(target.trim()).file_name() {
+            targets.push(name.to_string_lossy().into_owned());
+        }
+        rest = &rest[end..];
+    }
+    targets
+}
+
+/// Front matter fields (`key: value` lines between `---` markers) and the
+/// rest of the document
+fn split_front_matter(markdown: &str) -> (HashMap<String, String>, &str) {
+    let mut fields = HashMap::new();
+    let Some(rest) = markdown
+        .strip_prefix("---\n")
+        .or_else(|| markdown.strip_prefix("---\r\n"))
+    else {
+        return (fields, markdown);
+    };
+    let Some(end) = rest.find("\n---") else {
+        return (fields, markdown);
+    };
+    for line in rest[..end].lines() {
+        if let Some((key, value)) = line.split_once(':') {
+            let value = value.trim().trim_matches(|c| c == '"' || c == '\'');
+            fields.insert(key.trim().to_lowercase(), value.to_string());
+        }
+    }
+    let body = &rest[end + 4..];
+    (fields, body.strip_prefix('\n').unwrap_or(body))
+}
+
+/// Section lines joined, without leading and trailing blank lines
+fn section_text(lines: &[&str]) -> String {
+    lines.join("\n").trim().to_string()
+}
+
+/// Bullet items of a section, or its text as a single item if it has none
+fn list_items(lines: &[&str], text: &str) -> Vec<String> {
+    let items: Vec<String> = lines
+        .iter()
+        .filter_map(|l| {
+            let l = l.trim_start();
+            l.strip_prefix("* ").or_else(|| l.strip_prefix("- "))
+        })
+        .map(|item| item.trim().to_string())
+        .filter(|item| !item.is_empty())
+        .collect();
+    if items.is_empty() && !text.is_empty() {
+        vec![text.to_string()]
+    } else {
+        items
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    const NYGARD: &str = "# 2. Use PostgreSQL
+
+Date: 2021-03-04
+
+## Status
+
+Accepted
+
+Supersedes [1. Use MySQL](0001-use-mysql.md)
+
+## Context
+
+We need transactions.
+
+## Decision
+
+We will use PostgreSQL.
+
+## Consequences
+
+Operations must learn a new database.
+";
+
+    const MADR: &str = "---
+status: superseded by [ADR-0002](0002-use-postgresql.md)
+date: 2020-01-15
+---
+# Use MySQL
+
+## Context and Problem Statement
+
+Which database?
+
+## Decision Outcome
+
+Chosen option: MySQL.
+
+### Consequences
+
+* Good, because the team knows it
+* Bad, because of licensing
+";
+
+    #[test]
+    fn test_parse_nygard() {
+        let adr = parse_adr(NYGARD).unwrap();
+        assert_eq!(adr.title, "Use PostgreSQL");
+        assert_eq!(adr.status, DecisionStatus::Accepted);
+        assert_eq!(adr.date, NaiveDate::from_ymd_opt(2021, 3, 4));
+        assert_eq!(adr.context.as_deref(), Some("We need transactions."));
+        assert_eq!(
+            adr.consequences,
+            vec!["Operations must learn a new database.".to_string()]
+        );
+        assert_eq!(
+            adr.content.as_deref(),
+            Some("## Decision\n\nWe will use PostgreSQL.")
+        );
+        assert_eq!(adr.supersedes, vec!["0001-use-mysql.md".to_string()]);
+    }
+
+    #[test]
+    fn test_parse_madr() {
+        let adr = parse_adr(MADR).unwrap();
+        assert_eq!(adr.title, "Use MySQL");
+        assert_eq!(adr.status, DecisionStatus::Superseded);
+        assert_eq!(adr.date, NaiveDate::from_ymd_opt(2020, 1, 15));
+        assert_eq!(adr.context.as_deref(), Some("Which database?"));
+        assert_eq!(adr.consequences.len(), 2);
+        assert_eq!(
+            adr.superseded_by,
+            vec!["0002-use-postgresql.md".to_string()]
+        );
+
+        let old_madr = "# ADR-0007: Log as JSON\n\n* Status: proposed\n* Date: 2019-05-01\n";
+        let adr = parse_adr(old_madr).unwrap();
+        assert_eq!(adr.title, "Log as JSON");
+        assert_eq!(adr.status, DecisionStatus::Proposed);
+        assert_eq!(adr.date, NaiveDate::from_ymd_opt(2019, 5, 1));
+
+        assert!(parse_adr("No heading here").is_none());
+    }
+
+    #[test]
+    fn test_import_adr_dir() {
+        let tmp = TempDir::new().unwrap();
+        let store = LoroStore::init(tmp.path()).unwrap();
+        let adr_dir = tmp.path().join("docs/adr");
+        std::fs::create_dir_all(&adr_dir).unwrap();
+        std::fs::write(adr_dir.join("0001-use-mysql.md"), MADR).unwrap();
+        std::fs::write(adr_dir.join("0002-use-postgresql.md"), NYGARD).unwrap();
+        std::fs::write(adr_dir.join("README.md"), "# Decisions\n").unwrap();
+
+        let stats = import_adr_dir(&store, &adr_dir).unwrap();
+        assert_eq!(stats.decisions, 2);
+        assert_eq!(stats.supersedes, 1);
+        assert_eq!(stats.skipped.len(), 1);
+
+        let decisions = store.list_decisions().unwrap();
+        let mysql = decisions
+            .iter()
+            .find(|d| d.base.title == "Use MySQL")
+            .unwrap();
+        let postgres = decisions
+            .iter()
+            .find(|d| d.base.title == "Use PostgreSQL")
+            .unwrap();
+        assert_eq!(mysql.status, DecisionStatus::Superseded);
+        assert_eq!(
+            mysql.superseded_by.as_deref(),
+            Some(postgres.base.id.to_string().as_str())
+        );
+        assert_eq!(
+            mysql.base.sequence_number + 1,
+            postgres.base.sequence_number
+        );
+
+        let relations = store.list_relations().unwrap();
+        assert_eq!(relations.len(), 1);
+        assert_eq!(relations[0].source_id, postgres.base.id);
+        assert_eq!(relations[0].relation_type, RelationType::Supersedes);
+
+        // Importing again skips what is already there
+        let again = import_adr_dir(&store, &adr_dir).unwrap();
+        assert_eq!(again.decisions, 0);
+        assert_eq!(again.skipped.len(), 3);
+    }
+}
@@ -0,0 +1,344 @@
+//! Import of GitHub issues as tasks.
+//!
+//! Each open issue becomes a task (title, body, labels as tags, assignee)
+//! with a link entity for the issue URL that the task references, so the
+//! task graph can live next to an existing GitHub tracker. The link is
+//! also how later imports recognise issues they have already brought in:
+//! they are skipped, or with `sync` their task is marked done when the
+//! issue closes and reopened when it reopens.
+
+use std::collections::HashMap;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::entity::{Link, Relation, RelationType, Task, TaskStatus};
+use crate::error::{MedullaError, Result};
+use crate::storage::{LoroStore, TaskUpdate};
+
+/// GitHub REST API root
+pub const GITHUB_API_URL: &str = "https://api.github.com";
+
+/// `link_type` of the links recording issue URLs
+pub const ISSUE_LINK_TYPE: &str = "issue";
+
+/// Issues requested per page (the API maximum)
+const PER_PAGE: usize = 100;
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// An issue label
+#[derive(Debug, Clone, Deserialize)]
+pub struct GithubLabel {
+    pub name: String,
+}
+
+/// An issue's assignee
+#[derive(Debug, Clone, Deserialize)]
+pub struct GithubUser {
+    pub login: String,
+}
+
+/// The fields of an issue that the import uses
+#[derive(Debug, Clone, Deserialize)]
+pub struct GithubIssue {
+    pub number: u64,
+    pub title: String,
+    #[serde(default)]
+    pub body: Option<String>,
+    #[serde(default)]
+    pub labels: Vec<GithubLabel>,
+    #[serde(default)]
+    pub assignee: Option<GithubUser>,
+    /// `open` or `closed`
+    pub state: String,
+    pub html_url: String,
+    /// Present when the "issue" is a pull request
+    #[serde(default)]
+    pub pull_request: Option<serde_json::Value>,
+}
+
+impl GithubIssue {
+    pub fn is_open(&self) -> bool {
+        self.state == "open"
+    }
+}
+
+/// Result of a GitHub import.
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct GithubImportStats {
+    /// Tasks created for new open issues
+    pub created: usize,
+    /// Tasks marked done because their issue closed
+    pub closed: usize,
+    /// Done tasks reopened because their issue reopened
+    pub reopened: usize,
+    /// Issues already imported whose task needed no change
+    pub unchanged: usize,
+}
+
+/// Check that `repo` has the form `owner/name`
+pub fn validate_repo(repo: &str) -> Result<()> {
+    match repo.split_once('/') {
+        Some((owner, name)) if !owner.is_empty() && !name.is_empty() && !name.contains('/') => {
+            Ok(())
+        }
+        _ => Err(MedullaError::Config(format!(
+            "Invalid repository '{}': expected owner/name",
+            repo
+        ))),
+    }
+}
+
+/// Fetch the issues of `repo` from the GitHub API, pull requests left out.
+/// Only open issues unless `include_closed`; `token` raises the rate limit
+/// and gives access to private repositories.
+pub fn fetch_github_issues(
+    repo: &str,
+    include_closed: bool,
+    token: Option<&str>,
+) -> Result<Vec<GithubIssue>> {
+    validate_repo(repo)?;
+    let agent = ureq::AgentBuilder::new()
+        .timeout(REQUEST_TIMEOUT)
+        .user_agent(concat!("medulla/", env!("CARGO_PKG_VERSION")))
+        .build();
+    let state = if include_closed { "all" } else { "open" };
+
+    let mut issues = Vec::new();
+    for page in 1.. {
+        let url = format!(
+            "{}/repos/{}/issues?state={}&per_page={}&page={}",
+            GITHUB_API_URL, repo, state, PER_PAGE, page
+        );
+        let mut request = agent.get(&url).set("Accept", "application/vnd.github+json");
+        if let Some(token) = token {
+            request = request.set("Authorization", &format!("Bearer {}", token));
+        }
+        let body = request
+            .call()
+            .map_err(|e| match e {
+                ureq::Error::Status(code, response) => MedullaError::Fetch(format!(
+                    "GitHub API returned HTTP {} {} for {}",
+                    code,
+                    response.status_text(),
+                    repo
+                )),
+                ureq::Error::Transport(t) => MedullaError::Fetch(t.to_string()),
+            })?
+            .into_string()?;
+
+        let (page_issues, count) = parse_issues_page(&body)?;
+        issues.extend(page_issues);
+        if count < PER_PAGE {
+            break;
+        }
+    }
+    Ok(issues)
+}
+
+/// Issues in one page of the API's response, without pull requests, and
+/// the number of items the page held
+fn parse_issues_page(body: &str) -> Result<(Vec<GithubIssue>, usize)> {
+    let items: Vec<GithubIssue> = serde_json::from_str(body)?;
+    let count = items.len();
+    let issues = items
+        .into_iter()
+        .filter(|issue| issue.pull_request.is_none())
+        .collect();
+    Ok((issues, count))
+}
+
+/// Tasks already imported from issues, by issue URL: the tasks that
+/// reference an issue link
+fn imported_tasks(store: &LoroStore) -> Result<HashMap<String, Task>> {
+    let links: HashMap<Uuid, String> = store
+        .list_links()?
+        .into_iter()
+        .filter(|l| l.link_type.as_deref() == Some(ISSUE_LINK_TYPE))
+        .map(|l| (l.base.id, l.url))
+        .collect();
+    let tasks: HashMap<Uuid, Task> = store
+        .list_tasks()?
+        .into_iter()
+        .map(|t| (t.base.id, t))
+        .collect();
+
+    let mut by_url = HashMap::new();
+    for relation in store.list_relations()? {
+        if relation.relation_type != RelationType::References {
+            continue;
+        }
+        if let (Some(url), Some(task)) = (
+            links.get(&relation.target_id),
+            tasks.get(&relation.source_id),
+        ) {
+            by_url.insert(url.clone(), task.clone());
+        }
+    }
+    Ok(by_url)
+}
+
+/// Create tasks for the open issues not imported yet. With `sync`, also
+/// mark imported tasks done whose issue closed, and reopen done ones whose
+/// issue is open again.
+pub fn import_github_issues(
+    store: &LoroStore,
+    repo: &str,
+    issues: &[GithubIssue],
+    sync: bool,
+) -> Result<GithubImportStats> {
+    let imported = imported_tasks(store)?;
+    let mut stats = GithubImportStats::default();
+
+    for issue in issues {
+        let Some(task) = imported.get(&issue.html_url) else {
+            if issue.is_open() {
+                import_issue(store, repo, issue)?;
+                stats.created += 1;
+            }
+            continue;
+        };
+
+        let done = task.status == TaskStatus::Done;
+        let status = match (sync, issue.is_open(), done) {
+            (true, false, false) => Some(TaskStatus::Done),
+            (true, true, true) => Some(TaskStatus::Todo),
+            _ => None,
+        };
+        let Some(status) = status else {
+            stats.unchanged += 1;
+            continue;
+        };
+        store.update_task(
+            &task.base.id,
+            TaskUpdate {
+                status: Some(status),
+                ..Default::default()
+            },
+        )?;
+        if status == TaskStatus::Done {
+            stats.closed += 1;
+        } else {
+            stats.reopened += 1;
+        }
+    }
+
+    store.save()?;
+    Ok(stats)
+}
+
+/// Add a task for an issue, with a link to the issue that it references
+fn import_issue(store: &LoroStore, repo: &str, issue: &GithubIssue) -> Result<()> {
+    let mut task = Task::new(issue.title.clone(), store.next_sequence_number());
+    task.base.content = issue.body.clone().filter(|b| !b.trim().is_empty());
+    task.base.tags = issue.labels.iter().map(|l| l.name.clone()).collect();
+    task.assignee = issue.assignee.as_ref().map(|a| a.login.clone());
+    store.add_task(&task)?;
+
+    let mut link = Link::new(
+        format!("{}#{}", repo, issue.number),
+        issue.html_url.clone(),
+        store.next_sequence_number(),
+    );
+    link.link_type = Some(ISSUE_LINK_TYPE.to_string());
+    store.add_link(&link)?;
+
+    store.add_relation(&Relation::new(
+        task.base.id,
+        "task".to_string(),
+        link.base.id,
+        "link".to_string(),
+        RelationType::References,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn issue(number: u64, state: &str) -> GithubIssue {
+        GithubIssue {
+            number,
+            title: format!("Issue {}", number),
+            body: Some("Steps to reproduce".to_string()),
+            labels: vec![GithubLabel {
+                name: "bug".to_string(),
+            }],
+            assignee: Some(GithubUser {
+                login: "octocat".to_string(),
+            }),
+            state: state.to_string(),
+            html_url: format!("https://github.com/acme/app/issues/{}", number),
+            pull_request: None,
+        }
+    }
+
+    #[test]
+    fn test_validate_repo() {
+        assert!(validate_repo("acme/app").is_ok());
+        assert!(validate_repo("acme").is_err());
+        assert!(validate_repo("acme/app/issues").is_err());
+        assert!(validate_repo("/app").is_err());
+    }
+
+    #[test]
+    fn test_parse_issues_page() {
+        let body = r#"[
+            {"number": 1, "title": "Crash", "body": null, "labels": [{"name": "bug"}],
+             "assignee": null, "state": "open", "html_url": "https://github.com/acme/app/issues/1"},
+            {"number": 2, "title": "Fix crash", "state": "open",
+             "html_url": "https://github.com/acme/app/pull/2", "pull_request": {"url": "x"}}
+        ]"#;
+        let (issues, count) = parse_issues_page(body).unwrap();
+        assert_eq!(count, 2);
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].labels[0].name, "bug");
+    }
+
+    #[test]
+    fn test_import_and_sync_github_issues() {
+        let tmp = TempDir::new().unwrap();
+        let store = LoroStore::init(tmp.path()).unwrap();
+
+        let stats = import_github_issues(
+            &store,
+            "acme/app",
+            &[issue(1, "open"), issue(2, "closed")],
+            false,
+        )
+        .unwrap();
+        assert_eq!(stats.created, 1);
+        let tasks = store.list_tasks().unwrap();
+        assert_eq!(tasks.len(), 1);
+        assert_eq!(tasks[0].base.title, "Issue 1");
+        assert_eq!(tasks[0].base.tags, vec!["bug".to_string()]);
+        assert_eq!(tasks[0].assignee.as_deref(), Some("octocat"));
+        let links = store.list_links().unwrap();
+        assert_eq!(links[0].base.title, "acme/app#1");
+        assert_eq!(links[0].link_type.as_deref(), Some(ISSUE_LINK_TYPE));
+        assert_eq!(
+            store
+                .get_relations_from(&tasks[0].base.id.to_string())
+                .unwrap()
+                .len(),
+            1
+        );
+
+        // Importing again skips the issue; without sync nothing changes
+        let stats = import_github_issues(&store, "acme/app", &[issue(1, "closed")], false).unwrap();
+        assert_eq!((stats.created, stats.unchanged), (0, 1));
+        assert_eq!(store.list_tasks().unwrap()[0].status, TaskStatus::Todo);
+
+        let stats = import_github_issues(&store, "acme/app", &[issue(1, "closed")], true).unwrap();
+        assert_eq!(stats.closed, 1);
+        assert_eq!(store.list_tasks().unwrap()[0].status, TaskStatus::Done);
+
+        let stats = import_github_issues(&store, "acme/app", &[issue(1, "open")], true).unwrap();
+        assert_eq!(stats.reopened, 1);
+        assert_eq!(store.list_tasks().unwrap()[0].status, TaskStatus::Todo);
+        assert_eq!(store.list_tasks().unwrap().len(), 1);
+    }
+}
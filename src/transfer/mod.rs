@@ -0,0 +1,339 @@
+//! Bulk export and import of a whole project as JSON Lines.
+//!
+//! An export is one JSON record per line: a header naming the format
+//! version, then every entity, relation and embedding. Importing it into a
+//! fresh `.medulla` directory recreates the project with the same IDs and
+//! sequence numbers, without going through Loro snapshots.
+//!
+//! Markdown ADRs from other tools are imported by [`import_adr_dir`], GitHub
+//! issues by [`import_github_issues`], and an Obsidian vault is kept in step
+//! with the store by [`sync_obsidian`].
+
+mod adr;
+mod github;
+mod obsidian;
+
+pub use adr::{import_adr_dir, parse_adr, AdrDocument, AdrImportStats};
+pub use github::{
+    fetch_github_issues, import_github_issues, validate_repo, GithubImportStats, GithubIssue,
+    GithubLabel, GithubUser, GITHUB_API_URL, ISSUE_LINK_TYPE,
+};
+pub use obsidian::{sync_obsidian, ObsidianSyncStats, VAULT_DIR};
+
+use std::io::{BufRead, Write};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::cache::SqliteCache;
+use crate::entity::{Component, Decision, Link, Milestone, Note, Prompt, Question, Relation, Task};
+use crate::error::{MedullaError, Result};
+use crate::storage::LoroStore;
+
+/// Format name written in the export header.
+pub const FORMAT_NAME: &str = "medulla-export";
+
+/// Current export format version. Imports reject newer versions.
+pub const FORMAT_VERSION: u32 = 1;
+
+/// A stored embedding for one entity.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EmbeddingRecord {
+    pub entity_id: String,
+    pub entity_type: String,
+    /// Hash of the text the embedding was computed from, so an import does
+    /// not force recomputation
+    #[serde(default)]
+    pub text_hash: String,
+    pub vector: Vec<f32>,
+}
+
+/// One line of an export file.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Record {
+    Header {
+        format: String,
+        version: u32,
+        exported_at: DateTime<Utc>,
+    },
+    Decision(Decision),
+    Task(Task),
+    Note(Note),
+    Prompt(Prompt),
+    Component(Component),
+    Link(Link),
+    Question(Question),
+    Milestone(Milestone),
+    Relation(Relation),
+    Embedding(EmbeddingRecord),
+}
+
+impl Record {
+    /// Sequence number of an entity record.
+    fn sequence_number(&self) -> Option<u32> {
+        match self {
+            Record::Decision(e) => Some(e.base.sequence_number),
+            Record::Task(e) => Some(e.base.sequence_number),
+            Record::Note(e) => Some(e.base.sequence_number),
+            Record::Prompt(e) => Some(e.base.sequence_number),
+            Record::Component(e) => Some(e.base.sequence_number),
+            Record::Link(e) => Some(e.base.sequence_number),
+            Record::Question(e) => Some(e.base.sequence_number),
+            Record::Milestone(e) => Some(e.base.sequence_number),
+            _ => None,
+        }
+    }
+}
+
+/// Counts of records written or read.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct TransferStats {
+    pub entities: usize,
+    pub relations: usize,
+    pub embeddings: usize,
+}
+
+fn write_record<W: Write>(writer: &mut W, record: &Record) -> Result<()> {
+    serde_json::to_writer(&mut *writer, record)?;
+    writer.write_all(b"\n")?;
+    Ok(())
+}
+
+/// Write every entity, relation and (if a cache is given) embedding.
+/// Entities are written in sequence-number order.
+pub fn export<W: Write>(
+    store: &LoroStore,
+    cache: Option<&SqliteCache>,
+    mut writer: W,
+) -> Result<TransferStats> {
+    let mut stats = TransferStats::default();
+
+    write_record(
+        &mut writer,
+        &Record::Header {
+            format: FORMAT_NAME.to_string(),
+            version: FORMAT_VERSION,
+            exported_at: Utc::now(),
+        },
+    )?;
+
+    let mut entities: Vec<Record> = Vec::new();
+    entities.extend(store.list_decisions()?.into_iter().map(Record::Decision));
+    entities.extend(store.list_tasks()?.into_iter().map(Record::Task));
+    entities.extend(store.list_notes()?.into_iter().map(Record::Note));
+    entities.extend(store.list_prompts()?.into_iter().map(Record::Prompt));
+    entities.extend(store.list_components()?.into_iter().map(Record::Component));
+    entities.extend(store.list_links()?.into_iter().map(Record::Link));
+    entities.extend(store.list_questions()?.into_iter().map(Record::Question));
+    entities.extend(store.list_milestones()?.into_iter().map(Record::Milestone));
+    entities.sort_by_key(Record::sequence_number);
+    for record in &entities {
+        write_record(&mut writer, record)?;
+        stats.entities += 1;
+    }
+
+    for relation in store.list_relations()? {
+        write_record(&mut writer, &Record::Relation(relation))?;
+        stats.relations += 1;
+    }
+
+    if let Some(cache) = cache {
+        for (entity_id, entity_type, vector) in cache.list_all_embeddings(None)? {
+            let text_hash = cache
+                .get_embedding_text_hash(&entity_id)?
+                .unwrap_or_default();
+            let record = EmbeddingRecord {
+                entity_id,
+                entity_type,
+                text_hash,
+                vector,
+            };
+            write_record(&mut writer, &Record::Embedding(record))?;
+            stats.embeddings += 1;
+        }
+    }
+
+    writer.flush()?;
+    Ok(stats)
+}
+
+/// Read an export file into records, checking the header.
+pub fn read_records<R: BufRead>(reader: R) -> Result<Vec<Record>> {
+    let mut records = Vec::new();
+    for (index, line) in reader.lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let record: Record = serde_json::from_str(&line).map_err(|e| {
+            MedullaError::Storage(format!("Invalid record on line {}: {}", index + 1, e))
+        })?;
+        records.push(record);
+    }
+
+    match records.first() {
+        Some(Record::Header {
+            format, version, ..
+        }) => {
+            if format != FORMAT_NAME {
+                return Err(MedullaError::Storage(format!(
+                    "Unknown export format '{}'",
+                    format
+                )));
+            }
+            if *version > FORMAT_VERSION {
+                return Err(MedullaError::Storage(format!(
+                    "Export format version {} is newer than supported version {}",
+                    version, FORMAT_VERSION
+                )));
+            }
+        }
+        _ => {
+            return Err(MedullaError::Storage(
+                "Export file is missing its header line".to_string(),
+            ))
+        }
+    }
+
+    Ok(records)
+}
+
+/// Import an export file into an empty store, then rebuild the cache and
+/// restore embeddings. The store is saved.
+pub fn import<R: BufRead>(
+    store: &LoroStore,
+    cache: &SqliteCache,
+    reader: R,
+) -> Result<TransferStats> {
+    let has_entities = !store.list_decisions()?.is_empty()
+        || !store.list_tasks()?.is_empty()
+        || !store.list_notes()?.is_empty()
+        || !store.list_prompts()?.is_empty()
+        || !store.list_components()?.is_empty()
+        || !store.list_links()?.is_empty()
+        || !store.list_questions()?.is_empty()
+        || !store.list_milestones()?.is_empty();
+    if has_entities {
+        return Err(MedullaError::Storage(
+            "Import target already contains entities; import into a fresh project".to_string(),
+        ));
+    }
+
+    let mut records = read_records(reader)?;
+    // Entities go in sequence order so the global sequence counter ends at the highest
+    records.sort_by_key(|r| r.sequence_number().unwrap_or(u32::MAX));
+
+    let mut stats = TransferStats::default();
+    let mut embeddings = Vec::new();
+    for record in records {
+        match record {
+            Record::Header { .. } => continue,
+            Record::Decision(e) => store.add_decision(&e)?,
+            Record::Task(e) => store.add_task(&e)?,
+            Record::Note(e) => store.add_note(&e)?,
+            Record::Prompt(e) => store.add_prompt(&e)?,
+            Record::Component(e) => store.add_component(&e)?,
+            Record::Link(e) => store.add_link(&e)?,
+            Record::Question(e) => store.add_question(&e)?,
+            Record::Milestone(e) => store.add_milestone(&e)?,
+            Record::Relation(r) => {
+                store.add_relation(&r)?;
+                stats.relations += 1;
+                continue;
+            }
+            Record::Embedding(e) => {
+                embeddings.push(e);
+                continue;
+            }
+        }
+        stats.entities += 1;
+    }
+
+    store.save()?;
+    store.sync_cache(cache)?;
+
+    for e in embeddings {
+        cache.store_embedding(&e.entity_id, &e.entity_type, &e.vector, &e.text_hash)?;
+        stats.embeddings += 1;
+    }
+
+    Ok(stats)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::entity::RelationType;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_export_import_round_trip() {
+        let src_dir = TempDir::new().unwrap();
+        let store = LoroStore::init(src_dir.path()).unwrap();
+        let decision = Decision::new("Use Postgres".to_string(), 1);
+        let mut task = Task::new("Write migration".to_string(), 2);
+        task.base.tags = vec!["db".to_string()];
+        store.add_decision(&decision).unwrap();
+        store.add_task(&task).unwrap();
+        store
+            .add_relation(&Relation::new(
+                task.base.id,
+                "task".to_string(),
+                decision.base.id,
+                "decision".to_string(),
+                RelationType::Implements,
+            ))
+            .unwrap();
+        let cache = SqliteCache::open(store.medulla_dir()).unwrap();
+        store.sync_cache(&cache).unwrap();
+        cache
+            .store_embedding(&task.base.id.to_string(), "task", &[0.5, 0.25], "hash")
+            .unwrap();
+
+        let mut buf = Vec::new();
+        let exported = export(&store, Some(&cache), &mut buf).unwrap();
+        assert_eq!(
+            exported,
+            TransferStats {
+                entities: 2,
+                relations: 1,
+                embeddings: 1
+            }
+        );
+
+        let dst_dir = TempDir::new().unwrap();
+        let imported_store = LoroStore::init(dst_dir.path()).unwrap();
+        let imported_cache = SqliteCache::open(imported_store.medulla_dir()).unwrap();
+        let imported = import(&imported_store, &imported_cache, buf.as_slice()).unwrap();
+        assert_eq!(imported, exported);
+
+        let tasks = imported_store.list_tasks().unwrap();
+        assert_eq!(tasks[0].base.id, task.base.id);
+        assert_eq!(tasks[0].base.tags, vec!["db".to_string()]);
+        assert_eq!(imported_store.next_sequence_number(), 3);
+        assert_eq!(imported_store.list_relations().unwrap().len(), 1);
+        assert_eq!(
+            imported_cache
+                .get_embedding(&task.base.id.to_string())
+                .unwrap(),
+            Some(vec![0.5, 0.25])
+        );
+
+        // A second import into the now non-empty store is refused
+        assert!(import(&imported_store, &imported_cache, buf.as_slice()).is_err());
+    }
+
+    #[test]
+    fn test_read_records_requires_header() {
+        let line = serde_json::to_string(&Record::Note(Note::new("Loose".to_string(), 1))).unwrap();
+        assert!(read_records(line.as_bytes()).is_err());
+
+        let header = format!(
+            "{{\"kind\":\"header\",\"format\":\"{}\",\"version\":{},\"exported_at\":\"2026-01-01T00:00:00Z\"}}",
+            FORMAT_NAME,
+            FORMAT_VERSION + 1
+        );
+        assert!(read_records(header.as_bytes()).is_err());
+    }
+}
@@ -0,0 +1,684 @@
+//! Two-way sync with an Obsidian vault.
+//!
+//! Every entity is written to `<vault>/medulla/<type>/NNN-slug.md` with YAML
+//! front matter Obsidian understands (`tags`, `aliases`) plus the entity's
+//! UUID, and its outgoing relations as wikilinks. On the next sync, files
+//! whose title, content, tags or status were edited in Obsidian are matched
+//! back to their entity by that UUID and the edits applied to the store
+//! before the vault is rewritten.
+
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::entity::EntityBase;
+use crate::error::{MedullaError, Result};
+use crate::snapshot::utils::content_hash;
+use crate::snapshot::{permalink, yaml_frontmatter};
+use crate::storage::{
+    ComponentUpdate, DecisionUpdate, LinkUpdate, LoroStore, MilestoneUpdate, NoteUpdate,
+    PromptUpdate, QuestionUpdate, TaskUpdate,
+};
+
+/// Folder inside the vault that medulla owns
+pub const VAULT_DIR: &str = "medulla";
+
+/// Marks the start of the generated relations section; everything after
+/// it is rewritten on each sync and ignored on import
+const RELATIONS_MARKER: &str = "%% medulla:relations %%";
+
+/// Result of an Obsidian sync.
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct ObsidianSyncStats {
+    /// Entities updated from edits made in the vault
+    pub imported: usize,
+    /// Files written or rewritten
+    pub exported: usize,
+    /// Files already up to date
+    pub unchanged: usize,
+    /// Files removed because their entity was deleted, archived or renamed
+    pub removed: usize,
+    /// Files edited in the vault whose entity also changed since the last
+    /// sync; left untouched until resolved by hand
+    pub conflicts: Vec<String>,
+    /// Files not imported, with the reason
+    pub skipped: Vec<(String, String)>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct VaultFrontmatter {
+    medulla_id: Uuid,
+    #[serde(rename = "type")]
+    entity_type: String,
+    sequence_number: u32,
+    title: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    status: Option<String>,
+    #[serde(default)]
+    tags: Vec<String>,
+    #[serde(default)]
+    aliases: Vec<String>,
+    /// `updated_at` of the entity when the file was written
+    synced_at: DateTime<Utc>,
+    /// Hash of the editable fields as written, to tell vault edits apart
+    /// from changes made in medulla since
+    #[serde(default)]
+    fields_hash: String,
+}
+
+/// The fields of an entity that round-trip through the vault
+struct VaultEntity {
+    entity_type: &'static str,
+    base: EntityBase,
+    status: Option<String>,
+}
+
+impl VaultEntity {
+    fn relative_path(&self) -> String {
+        permalink(type_dir(self.entity_type), &self.base)
+    }
+}
+
+/// A medulla file found in the vault
+struct VaultFile {
+    relative_path: String,
+    frontmatter: VaultFrontmatter,
+    content: String,
+}
+
+impl VaultFile {
+    /// Whether the file was changed in the vault since it was written
+    fn is_edited(&self) -> bool {
+        let fm = &self.frontmatter;
+        fields_hash(&fm.title, fm.status.as_deref(), &fm.tags, &self.content) != fm.fields_hash
+    }
+}
+
+fn fields_hash(title: &str, status: Option<&str>, tags: &[String], content: &str) -> String {
+    content_hash(&format!(
+        "{}\0{}\0{}\0{}",
+        title,
+        status.unwrap_or(""),
+        tags.join("\0"),
+        content.trim()
+    ))
+}
+
+fn type_dir(entity_type: &str) -> &'static str {
+    match entity_type {
+        "decision" => "decisions",
+        "task" => "tasks",
+        "note" => "notes",
+        "prompt" => "prompts",
+        "component" => "components",
+        "link" => "links",
+        "question" => "questions",
+        "milestone" => "milestones",
+        other => unreachable!("no vault folder for entity type {}", other),
+    }
+}
+
+/// Import edits from the vault, then write every entity back to it. The
+/// store is saved if any edits were imported.
+pub fn sync_obsidian(store: &LoroStore, vault: &Path) -> Result<ObsidianSyncStats> {
+    if !vault.is_dir() {
+        return Err(MedullaError::Storage(format!(
+            "Obsidian vault not found: {}",
+            vault.display()
+        )));
+    }
+    let root = vault.join(VAULT_DIR);
+    let mut stats = ObsidianSyncStats::default();
+
+    let mut files = Vec::new();
+    if root.is_dir() {
+        read_vault_files(&root, "", &mut files, &mut stats)?;
+    }
+
+    let entities: HashMap<Uuid, VaultEntity> = collect_entities(store)?
+        .into_iter()
+        .map(|e| (e.base.id, e))
+        .collect();
+    // Entities whose vault edits were not imported; their files are kept
+    // as they are so the edits are not overwritten
+    let mut held = HashSet::new();
+    for file in &files {
+        let Some(entity) = entities.get(&file.frontmatter.medulla_id) else {
+            continue;
+        };
+        if !file.is_edited() || !differs(file, entity) {
+            continue;
+        }
+        if entity.base.updated_at > file.frontmatter.synced_at {
+            stats.conflicts.push(file.relative_path.clone());
+            held.insert(entity.base.id);
+            continue;
+        }
+        match apply_edits(store, file, entity) {
+            Ok(()) => stats.imported += 1,
+            Err(e) => {
+                stats
+                    .skipped
+                    .push((file.relative_path.clone(), e.to_string()));
+                held.insert(entity.base.id);
+            }
+        }
+    }
+
+    if stats.imported > 0 {
+        store.save()?;
+    }
+
+    // Re-read so imported edits are written back with their new timestamps
+    let entities = collect_entities(store)?;
+    let paths: HashMap<Uuid, String> = entities
+        .iter()
+        .map(|e| (e.base.id, e.relative_path()))
+        .collect();
+    let titles: HashMap<Uuid, &str> = entities
+        .iter()
+        .map(|e| (e.base.id, e.base.title.as_str()))
+        .collect();
+    let relations = store.list_relations()?;
+
+    let mut written = HashSet::new();
+    for entity in &entities {
+        if held.contains(&entity.base.id) {
+            continue;
+        }
+        let links: Vec<String> = relations
+            .iter()
+            .filter(|r| r.source_id == entity.base.id)
+            .filter_map(|r| {
+                let path = paths.get(&r.target_id)?;
+                let stem = path.rsplit('/').next()?.trim_end_matches(".md");
+                Some(format!(
+                    "- {} [[{}|{}]]",
+                    r.relation_type, stem, titles[&r.target_id]
+                ))
+            })
+            .collect();
+
+        let relative_path = entity.relative_path();
+        let rendered = render_file(entity, &links)?;
+        let path = root.join(&relative_path);
+        if fs::read_to_string(&path).ok().as_deref() == Some(rendered.as_str()) {
+            stats.unchanged += 1;
+        } else {
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            fs::write(&path, &rendered)?;
+            stats.exported += 1;
+        }
+        written.insert(relative_path);
+    }
+
+    // Only files carrying a medulla_id are ours to remove
+    for file in &files {
+        if written.contains(&file.relative_path) || held.contains(&file.frontmatter.medulla_id) {
+            continue;
+        }
+        fs::remove_file(root.join(&file.relative_path))?;
+        stats.removed += 1;
+    }
+
+    Ok(stats)
+}
+
+fn collect_entities(store: &LoroStore) -> Result<Vec<VaultEntity>> {
+    let mut entities = Vec::new();
+    for d in store.list_decisions()? {
+        entities.push(VaultEntity {
+            entity_type: "decision",
+            status: Some(d.status.to_string()),
+            base: d.base,
+        });
+    }
+    for t in store.list_tasks()? {
+        entities.push(VaultEntity {
+            entity_type: "task",
+            status: Some(t.status.to_string()),
+            base: t.base,
+        });
+    }
+    for n in store.list_notes()? {
+        entities.push(VaultEntity {
+            entity_type: "note",
+            status: None,
+            base: n.base,
+        });
+    }
+    for p in store.list_prompts()? {
+        entities.push(VaultEntity {
+            entity_type: "prompt",
+            status: None,
+            base: p.base,
+        });
+    }
+    for c in store.list_components()? {
+        entities.push(VaultEntity {
+            entity_type: "component",
+            status: Some(c.status.to_string()),
+            base: c.base,
+        });
+    }
+    for l in store.list_links()? {
+        entities.push(VaultEntity {
+            entity_type: "link",
+            status: None,
+            base: l.base,
+        });
+    }
+    for q in store.list_questions()? {
+        entities.push(VaultEntity {
+            entity_type: "question",
+            status: Some(q.status.to_string()),
+            base: q.base,
+        });
+    }
+    for m in store.list_milestones()? {
+        entities.push(VaultEntity {
+            entity_type: "milestone",
+            status: Some(m.status.to_string()),
+            base: m.base,
+        });
+    }
+    entities.retain(|e| !e.base.archived);
+    entities.sort_by_key(|e| e.base.sequence_number);
+    Ok(entities)
+}
+
+fn render_file(entity: &VaultEntity, links: &[String]) -> Result<String> {
+    let frontmatter = VaultFrontmatter {
+        medulla_id: entity.base.id,
+        entity_type: entity.entity_type.to_string(),
+        sequence_number: entity.base.sequence_number,
+        title: entity.base.title.clone(),
+        status: entity.status.clone(),
+        tags: entity.base.tags.clone(),
+        aliases: vec![entity.base.title.clone()],
+        synced_at: entity.base.updated_at,
+        fields_hash: fields_hash(
+            &entity.base.title,
+            entity.status.as_deref(),
+            &entity.base.tags,
+            entity.base.content.as_deref().unwrap_or(""),
+        ),
+    };
+
+    let mut out = yaml_frontmatter(&frontmatter)?;
+    if let Some(content) = entity.base.content.as_deref().map(str::trim) {
+        if !content.is_empty() {
+            out.push('\n');
+            out.push_str(content);
+            out.push('\n');
+        }
+    }
+    if !links.is_empty() {
+        out.push_str(&format!("\n{}\n## Relations\n\n", RELATIONS_MARKER));
+        for link in links {
+            out.push_str(link);
+            out.push('\n');
+        }
+    }
+    Ok(out)
+}
+
+/// Parse a vault file. Returns None for files without medulla front
+/// matter, which are the user's own notes.
+fn parse_file(relative_path: &str, markdown: &str) -> Option<Result<VaultFile>> {
+    let rest = markdown
+        .strip_prefix("---\n")
+        .or_else(|| markdown.strip_prefix("---\r\n"))?;
+    let end = rest.find("\n---")?;
+    let yaml = &rest[..end];
+    let body = rest[end + 4..].trim_start_matches(['\r', '\n']);
+
+    let mapping: serde_yaml::Mapping = serde_yaml::from_str(yaml).ok()?;
+    if !mapping.contains_key("medulla_id") {
+        return None;
+    }
+    let frontmatter = match serde_yaml::from_value(serde_yaml::Value::Mapping(mapping)) {
+        Ok(frontmatter) => frontmatter,
+        Err(e) => {
+            return Some(Err(MedullaError::Storage(format!(
+                "Invalid front matter: {}",
+                e
+            ))))
+        }
+    };
+    let content = match body.find(RELATIONS_MARKER) {
+        Some(i) => &body[..i],
+        None => body,
+    };
+
+    Some(Ok(VaultFile {
+        relative_path: relative_path.to_string(),
+        frontmatter,
+        content: content.trim().to_string(),
+    }))
+}
+
+fn read_vault_files(
+    dir: &Path,
+    prefix: &str,
+    files: &mut Vec<VaultFile>,
+    stats: &mut ObsidianSyncStats,
+) -> Result<()> {
+    let mut entries: Vec<PathBuf> = fs::read_dir(dir)?
+        .map(|e| e.map(|e| e.path()))
+        .collect::<std::io::Result<_>>()?;
+    entries.sort();
+
+    for path in entries {
+        let Some(name) = path.file_name().and_then(|n| n.to_str()) else {
+            continue;
+        };
+        let relative_path = format!("{}{}", prefix, name);
+        if path.is_dir() {
+            read_vault_files(&path, &format!("{}/", relative_path), files, stats)?;
+        } else if name.ends_with(".md") {
+            match parse_file(&relative_path, &fs::read_to_string(&path)?) {
+                Some(Ok(file)) => files.push(file),
+                Some(Err(e)) => stats.skipped.push((relative_path, e.to_string())),
+                None => {}
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Whether the file's editable fields differ from the entity's
+fn differs(file: &VaultFile, entity: &VaultEntity) -> bool {
+    let fm = &file.frontmatter;
+    fm.title != entity.base.title
+        || file.content != entity.base.content.as_deref().unwrap_or("").trim()
+        || fm.tags != entity.base.tags
+        || (entity.status.is_some() && fm.status != entity.status)
+}
+
+fn apply_edits(store: &LoroStore, file: &VaultFile, entity: &VaultEntity) -> Result<()> {
+    let fm = &file.frontmatter;
+    let id = &entity.base.id;
+    let title = (fm.title != entity.base.title).then(|| fm.title.clone());
+    let content = (file.content != entity.base.content.as_deref().unwrap_or("").trim())
+        .then(|| file.content.clone());
+    let add_tags: Vec<String> = fm
+        .tags
+        .iter()
+        .filter(|t| !entity.base.tags.contains(t))
+        .cloned()
+        .collect();
+    let remove_tags: Vec<String> = entity
+        .base
+        .tags
+        .iter()
+        .filter(|t| !fm.tags.contains(t))
+        .cloned()
+        .collect();
+    let status = fm
+        .status
+        .as_deref()
+        .filter(|s| entity.status.as_deref() != Some(*s));
+    let invalid = |e: String| MedullaError::Storage(e);
+
+    match entity.entity_type {
+        "decision" => store.update_decision(
+            id,
+            DecisionUpdate {
+                title,
+                content,
+                status: status.map(str::parse).transpose().map_err(invalid)?,
+                add_tags,
+                remove_tags,
+                ..Default::default()
+            },
+        ),
+        "task" => store.update_task(
+            id,
+            TaskUpdate {
+                title,
+                content,
+                status: status.map(str::parse).transpose().map_err(invalid)?,
+                add_tags,
+                remove_tags,
+                ..Default::default()
+            },
+        ),
+        "note" => store.update_note(
+            id,
+            NoteUpdate {
+                title,
+                content,
+                add_tags,
+                remove_tags,
+                ..Default::default()
+            },
+        ),
+        "prompt" => store.update_prompt(
+            id,
+            PromptUpdate {
+                title,
+                content,
+                add_tags,
+                remove_tags,
+                ..Default::default()
+            },
+        ),
+        "component" => store.update_component(
+            id,
+            ComponentUpdate {
+                title,
+                content,
+                status: status.map(str::parse).transpose().map_err(invalid)?,
+                add_tags,
+                remove_tags,
+                ..Default::default()
+            },
+        ),
+        "question" => store.update_question(
+            id,
+            QuestionUpdate {
+                title,
+                content,
+                status: status.map(str::parse).transpose().map_err(invalid)?,
+                add_tags,
+                remove_tags,
+                ..Default::default()
+            },
+        ),
+        "milestone" => store.update_milestone(
+            id,
+            MilestoneUpdate {
+                title,
+                content,
+                status: status.map(str::parse).transpose().map_err(invalid)?,
+                add_tags,
+                remove_tags,
+                ..Default::default()
+            },
+        ),
+        _ => store.update_link(
+            id,
+            LinkUpdate {
+                title,
+                content,
+                add_tags,
+                remove_tags,
+                ..Default::default()
+            },
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::entity::{
+        Decision, Milestone, MilestoneStatus, Note, Question, QuestionStatus, Relation,
+        RelationType,
+    };
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_sync_obsidian_round_trip() {
+        let tmp = TempDir::new().unwrap();
+        let vault = TempDir::new().unwrap();
+        let store = LoroStore::init(tmp.path()).unwrap();
+        let decision = Decision::new("Use Postgres".to_string(), 1);
+        let mut note = Note::new("Tuning notes".to_string(), 2);
+        note.base.content = Some("Raise work_mem.".to_string());
+        note.base.tags = vec!["db".to_string()];
+        store.add_decision(&decision).unwrap();
+        store.add_note(&note).unwrap();
+        store
+            .add_relation(&Relation::new(
+                note.base.id,
+                "note".to_string(),
+                decision.base.id,
+                "decision".to_string(),
+                RelationType::References,
+            ))
+            .unwrap();
+        fs::write(vault.path().join("Personal.md"), "# Mine\n").unwrap();
+
+        let stats = sync_obsidian(&store, vault.path()).unwrap();
+        assert_eq!(stats.exported, 2);
+        let note_path = vault.path().join("medulla/notes/002-tuning-notes.md");
+        let written = fs::read_to_string(&note_path).unwrap();
+        assert!(written.contains(&format!("medulla_id: {}", note.base.id)));
+        assert!(written.contains("Raise work_mem."));
+        assert!(written.contains("- references [[001-use-postgres|Use Postgres]]"));
+
+        let stats = sync_obsidian(&store, vault.path()).unwrap();
+        assert_eq!((stats.exported, stats.unchanged), (0, 2));
+
+        // Edit in Obsidian: new content, an extra tag, and a retitle
+        let edited = written
+            .replace("Raise work_mem.", "Raise work_mem to 64MB.")
+            .replace("- db", "- db\n- perf")
+            .replace("title: Tuning notes", "title: Postgres tuning");
+        fs::write(&note_path, edited).unwrap();
+
+        let stats = sync_obsidian(&store, vault.path()).unwrap();
+        assert_eq!(stats.imported, 1);
+        assert_eq!(stats.removed, 1);
+        let updated = store.get_note(&note.base.id).unwrap().unwrap();
+        assert_eq!(updated.base.title, "Postgres tuning");
+        assert_eq!(
+            updated.base.content.as_deref(),
+            Some("Raise work_mem to 64MB.")
+        );
+        assert_eq!(updated.base.tags, vec!["db", "perf"]);
+        assert!(!note_path.exists());
+        assert!(vault
+            .path()
+            .join("medulla/notes/002-postgres-tuning.md")
+            .exists());
+        assert!(vault.path().join("Personal.md").exists());
+
+        // A change made in medulla alone is exported, not a conflict
+        std::thread::sleep(std::time::Duration::from_millis(5));
+        store
+            .update_decision(
+                &decision.base.id,
+                DecisionUpdate {
+                    content: Some("Chosen for JSONB.".to_string()),
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        let stats = sync_obsidian(&store, vault.path()).unwrap();
+        assert!(stats.conflicts.is_empty());
+        assert_eq!((stats.imported, stats.exported), (0, 1));
+    }
+
+    #[test]
+    fn test_sync_obsidian_conflict() {
+        let tmp = TempDir::new().unwrap();
+        let vault = TempDir::new().unwrap();
+        let store = LoroStore::init(tmp.path()).unwrap();
+        let note = Note::new("Shared".to_string(), 1);
+        store.add_note(&note).unwrap();
+        sync_obsidian(&store, vault.path()).unwrap();
+
+        let path = vault.path().join("medulla/notes/001-shared.md");
+        let edited = format!("{}\nFrom Obsidian\n", fs::read_to_string(&path).unwrap());
+        fs::write(&path, &edited).unwrap();
+        std::thread::sleep(std::time::Duration::from_millis(5));
+        store
+            .update_note(
+                &note.base.id,
+                NoteUpdate {
+                    content: Some("From medulla".to_string()),
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+
+        let stats = sync_obsidian(&store, vault.path()).unwrap();
+        assert_eq!(stats.conflicts, vec!["notes/001-shared.md".to_string()]);
+        assert_eq!(stats.imported, 0);
+        assert_eq!(fs::read_to_string(&path).unwrap(), edited);
+        assert_eq!(
+            store.get_note(&note.base.id).unwrap().unwrap().base.content,
+            Some("From medulla".to_string())
+        );
+    }
+
+    #[test]
+    fn test_sync_obsidian_question_status() {
+        let tmp = TempDir::new().unwrap();
+        let vault = TempDir::new().unwrap();
+        let store = LoroStore::init(tmp.path()).unwrap();
+        let question = Question::new("Which queue?".to_string(), 1);
+        store.add_question(&question).unwrap();
+        sync_obsidian(&store, vault.path()).unwrap();
+
+        let path = vault.path().join("medulla/questions/001-which-queue.md");
+        let written = fs::read_to_string(&path).unwrap();
+        assert!(written.contains("status: open"));
+        fs::write(&path, written.replace("status: open", "status: answered")).unwrap();
+
+        let stats = sync_obsidian(&store, vault.path()).unwrap();
+        assert_eq!(stats.imported, 1);
+        assert_eq!(
+            store
+                .get_question(&question.base.id)
+                .unwrap()
+                .unwrap()
+                .status,
+            QuestionStatus::Answered
+        );
+    }
+
+    #[test]
+    fn test_sync_obsidian_milestone() {
+        let tmp = TempDir::new().unwrap();
+        let vault = TempDir::new().unwrap();
+        let store = LoroStore::init(tmp.path()).unwrap();
+        let milestone = Milestone::new("Beta".to_string(), 1);
+        store.add_milestone(&milestone).unwrap();
+        sync_obsidian(&store, vault.path()).unwrap();
+
+        let path = vault.path().join("medulla/milestones/001-beta.md");
+        let written = fs::read_to_string(&path).unwrap();
+        fs::write(&path, written.replace("status: open", "status: completed")).unwrap();
+
+        let stats = sync_obsidian(&store, vault.path()).unwrap();
+        assert_eq!(stats.imported, 1);
+        assert_eq!(
+            store
+                .get_milestone(&milestone.base.id)
+                .unwrap()
+                .unwrap()
+                .status,
+            MilestoneStatus::Completed
+        );
+    }
+}
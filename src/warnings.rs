@@ -1,17 +1,137 @@
-//! Performance threshold warnings for Medulla.
+//! Performance threshold warnings and health checks for Medulla.
 //!
 //! This module provides warning generation and formatting for when
-//! Medulla's data exceeds recommended thresholds.
+//! Medulla's data exceeds recommended thresholds, and the health report
+//! behind `medulla doctor` and the `health_check` MCP tool.
 
-use crate::cache::{CacheStats, ENTITY_WARNING_THRESHOLD, LORO_SIZE_WARNING_THRESHOLD};
+use serde::{Deserialize, Serialize};
 
-/// A warning about potential performance issues.
+use crate::cache::{
+    CacheStats, SqliteCache, ENTITY_WARNING_THRESHOLD, LORO_SIZE_WARNING_THRESHOLD,
+};
+use crate::error::Result;
+use crate::storage::LoroStore;
+
+/// How serious a health issue is, ordered from least to most severe.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Ok,
+    Info,
+    Warning,
+    Error,
+}
+
+impl Severity {
+    /// Process exit code for `medulla doctor`. 1 is left to ordinary
+    /// command failures, so scripts can tell "unhealthy" from "crashed".
+    pub fn exit_code(self) -> i32 {
+        match self {
+            Severity::Ok | Severity::Info => 0,
+            Severity::Warning => 2,
+            Severity::Error => 3,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Severity::Ok => "ok",
+            Severity::Info => "info",
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        }
+    }
+}
+
+/// A warning about potential performance or consistency issues.
 #[derive(Debug, Clone)]
 pub enum Warning {
     /// Entity count exceeds recommended threshold.
     HighEntityCount { count: usize, threshold: usize },
     /// loro.db file size exceeds recommended threshold.
     LargeLoroDb { size_mb: f64, threshold_mb: f64 },
+    /// Cache is out of sync with the store: `missing` entities are in the
+    /// store but not the cache, `extra` ones are in the cache only.
+    StaleCache { missing: usize, extra: usize },
+    /// Embeddings left behind for entities that no longer exist.
+    OrphanedEmbeddings { count: usize },
+    /// A full-text index no longer matches its content table.
+    FtsInconsistent { table: &'static str },
+    /// Entities share a sequence number with another, typically after
+    /// merging entities created offline on two replicas.
+    DuplicateSequenceNumbers { count: usize },
+}
+
+impl Warning {
+    pub fn severity(&self) -> Severity {
+        match self {
+            Warning::OrphanedEmbeddings { .. } => Severity::Info,
+            Warning::HighEntityCount { .. }
+            | Warning::LargeLoroDb { .. }
+            | Warning::StaleCache { .. }
+            | Warning::DuplicateSequenceNumbers { .. } => Severity::Warning,
+            Warning::FtsInconsistent { .. } => Severity::Error,
+        }
+    }
+
+    /// Stable machine-readable identifier
+    pub fn code(&self) -> &'static str {
+        match self {
+            Warning::HighEntityCount { .. } => "high_entity_count",
+            Warning::LargeLoroDb { .. } => "large_loro_db",
+            Warning::StaleCache { .. } => "stale_cache",
+            Warning::OrphanedEmbeddings { .. } => "orphaned_embeddings",
+            Warning::FtsInconsistent { .. } => "fts_inconsistent",
+            Warning::DuplicateSequenceNumbers { .. } => "duplicate_sequence_numbers",
+        }
+    }
+
+    /// Command that resolves the issue, if there is one
+    pub fn fix(&self) -> Option<&'static str> {
+        match self {
+            Warning::HighEntityCount { .. } => None,
+            Warning::LargeLoroDb { .. } => Some("medulla compact"),
+            Warning::StaleCache { .. } | Warning::OrphanedEmbeddings { .. } => {
+                Some("medulla cache rebuild")
+            }
+            Warning::FtsInconsistent { .. } => Some("medulla cache rebuild-fts"),
+            Warning::DuplicateSequenceNumbers { .. } => Some("medulla resequence"),
+        }
+    }
+
+    /// Description without the severity prefix
+    pub fn message(&self) -> String {
+        match self {
+            Warning::HighEntityCount { count, threshold } => format!(
+                "{} entities exceeds recommended {} - search may slow down",
+                count, threshold
+            ),
+            Warning::LargeLoroDb {
+                size_mb,
+                threshold_mb,
+            } => format!(
+                "loro.db size ({:.1}MB) exceeds recommended {:.0}MB",
+                size_mb, threshold_mb
+            ),
+            Warning::StaleCache { missing, extra } => format!(
+                "cache is out of sync with loro.db ({} missing, {} extra entities)",
+                missing, extra
+            ),
+            Warning::OrphanedEmbeddings { count } => {
+                format!(
+                    "{} embeddings belong to entities that no longer exist",
+                    count
+                )
+            }
+            Warning::FtsInconsistent { table } => {
+                format!("full-text index {} does not match its content", table)
+            }
+            Warning::DuplicateSequenceNumbers { count } => format!(
+                "{} entities share a sequence number, or a number in their collection, with an earlier one",
+                count
+            ),
+        }
+    }
 }
 
 /// Check thresholds and return any warnings.
@@ -46,25 +166,108 @@ pub fn check_thresholds(stats: &CacheStats, loro_size: u64) -> Vec<Warning> {
 
 /// Format a warning for display.
 pub fn format_warning(warning: &Warning) -> String {
-    match warning {
-        Warning::HighEntityCount { count, threshold } => {
-            format!(
-                "Warning: {} entities exceeds recommended {} - search may slow down",
-                count, threshold
-            )
-        }
-        Warning::LargeLoroDb {
-            size_mb,
-            threshold_mb,
-        } => {
-            format!(
-                "Warning: loro.db size ({:.1}MB) exceeds recommended {:.0}MB",
-                size_mb, threshold_mb
-            )
+    let label = match warning.severity() {
+        Severity::Error => "Error",
+        Severity::Info => "Info",
+        Severity::Ok | Severity::Warning => "Warning",
+    };
+    format!("{}: {}", label, warning.message())
+}
+
+/// One finding of a health check.
+#[derive(Debug, Clone, Serialize)]
+pub struct HealthIssue {
+    pub code: &'static str,
+    pub severity: Severity,
+    pub message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fix: Option<&'static str>,
+}
+
+impl From<&Warning> for HealthIssue {
+    fn from(warning: &Warning) -> Self {
+        Self {
+            code: warning.code(),
+            severity: warning.severity(),
+            message: warning.message(),
+            fix: warning.fix(),
         }
     }
 }
 
+/// Result of checking a store and its cache.
+#[derive(Debug, Clone, Serialize)]
+pub struct HealthReport {
+    /// Most severe issue found, or `ok`
+    pub status: Severity,
+    pub entity_count: usize,
+    pub cached_entity_count: usize,
+    pub embedding_count: usize,
+    pub orphaned_embeddings: usize,
+    pub loro_db_size_bytes: u64,
+    pub issues: Vec<HealthIssue>,
+}
+
+impl HealthReport {
+    pub fn exit_code(&self) -> i32 {
+        self.status.exit_code()
+    }
+}
+
+/// Check store size, cache staleness, orphaned embeddings, FTS
+/// consistency and duplicate sequence numbers. Reads the cache as it is,
+/// so callers must not sync it first or staleness will never be reported.
+pub fn health_check(store: &LoroStore, cache: &SqliteCache) -> Result<HealthReport> {
+    let stats = cache.get_stats()?;
+    let loro_size = std::fs::metadata(store.path())
+        .map(|m| m.len())
+        .unwrap_or(0);
+
+    let store_ids = store.entity_ids();
+    let cache_ids = cache.get_entity_ids()?;
+    let missing = store_ids.difference(&cache_ids).count();
+    let extra = cache_ids.difference(&store_ids).count();
+
+    let mut warnings = check_thresholds(
+        &CacheStats {
+            entity_count: store_ids.len(),
+            ..stats.clone()
+        },
+        loro_size,
+    );
+    if missing > 0 || extra > 0 {
+        warnings.push(Warning::StaleCache { missing, extra });
+    }
+    let orphaned_embeddings = cache.count_orphaned_embeddings()?;
+    if orphaned_embeddings > 0 {
+        warnings.push(Warning::OrphanedEmbeddings {
+            count: orphaned_embeddings,
+        });
+    }
+    for table in cache.check_fts_integrity()? {
+        warnings.push(Warning::FtsInconsistent { table });
+    }
+    let duplicates = store.count_sequence_collisions();
+    if duplicates > 0 {
+        warnings.push(Warning::DuplicateSequenceNumbers { count: duplicates });
+    }
+
+    let issues: Vec<HealthIssue> = warnings.iter().map(HealthIssue::from).collect();
+    Ok(HealthReport {
+        status: issues
+            .iter()
+            .map(|i| i.severity)
+            .max()
+            .unwrap_or(Severity::Ok),
+        entity_count: store_ids.len(),
+        cached_entity_count: cache_ids.len(),
+        embedding_count: stats.embedding_count,
+        orphaned_embeddings,
+        loro_db_size_bytes: loro_size,
+        issues,
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -79,6 +282,8 @@ mod tests {
             prompts: 0,
             components: 0,
             links: 0,
+            questions: 0,
+            milestones: 0,
             relations: 0,
         }
     }
@@ -145,4 +350,46 @@ mod tests {
         assert!(msg.contains("15.5"));
         assert!(msg.contains("10"));
     }
+
+    #[test]
+    fn test_severity_ordering_and_exit_codes() {
+        assert!(Severity::Error > Severity::Warning);
+        assert!(Severity::Warning > Severity::Info);
+        assert_eq!(Severity::Ok.exit_code(), 0);
+        assert_eq!(Severity::Info.exit_code(), 0);
+        assert_eq!(Severity::Warning.exit_code(), 2);
+        assert_eq!(Severity::Error.exit_code(), 3);
+
+        let fts = Warning::FtsInconsistent { table: "notes_fts" };
+        assert_eq!(fts.severity(), Severity::Error);
+        assert!(format_warning(&fts).starts_with("Error: "));
+    }
+
+    #[test]
+    fn test_health_check() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let store = LoroStore::init(tmp.path()).unwrap();
+        let cache = SqliteCache::open(store.medulla_dir()).unwrap();
+
+        let report = health_check(&store, &cache).unwrap();
+        assert_eq!(report.status, Severity::Ok);
+        assert!(report.issues.is_empty());
+
+        let note = crate::entity::Note::new("Unsynced".to_string(), 1);
+        store.add_note(&note).unwrap();
+        cache.store_embedding("gone", "note", &[1.0], "h").unwrap();
+
+        let report = health_check(&store, &cache).unwrap();
+        assert_eq!(report.status, Severity::Warning);
+        assert_eq!(report.exit_code(), 2);
+        assert_eq!(report.entity_count, 1);
+        assert_eq!(report.cached_entity_count, 0);
+        assert_eq!(report.orphaned_embeddings, 1);
+        let codes: Vec<&str> = report.issues.iter().map(|i| i.code).collect();
+        assert_eq!(codes, vec!["stale_cache", "orphaned_embeddings"]);
+
+        store.sync_cache(&cache).unwrap();
+        let report = health_check(&store, &cache).unwrap();
+        assert!(report.issues.iter().all(|i| i.code != "stale_cache"));
+    }
 }
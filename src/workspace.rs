@@ -0,0 +1,178 @@
+//! Registry of medulla projects for working across several repositories.
+//!
+//! The registry is a per-user JSON file mapping project names to project
+//! directories, at `$MEDULLA_WORKSPACE` if set, otherwise
+//! `$XDG_CONFIG_HOME/medulla/workspace.json` (falling back to
+//! `~/.config/medulla/workspace.json`). Names can then be passed to
+//! `--project`, searched together, or served from one MCP server.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::{MedullaError, Result};
+
+/// Environment variable overriding the registry location.
+pub const WORKSPACE_ENV: &str = "MEDULLA_WORKSPACE";
+
+/// Registered projects, by name.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Workspace {
+    #[serde(default)]
+    pub projects: BTreeMap<String, PathBuf>,
+}
+
+impl Workspace {
+    /// Location of the registry file.
+    pub fn default_path() -> Result<PathBuf> {
+        if let Some(path) = std::env::var_os(WORKSPACE_ENV) {
+            return Ok(PathBuf::from(path));
+        }
+        let config_dir = match std::env::var_os("XDG_CONFIG_HOME") {
+            Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+            _ => std::env::var_os("HOME")
+                .map(|home| PathBuf::from(home).join(".config"))
+                .ok_or_else(|| {
+                    MedullaError::Config(format!(
+                        "Cannot locate the workspace file: set {} or HOME",
+                        WORKSPACE_ENV
+                    ))
+                })?,
+        };
+        Ok(config_dir.join("medulla").join("workspace.json"))
+    }
+
+    /// Load the registry from its default location.
+    pub fn load() -> Result<Self> {
+        Self::load_from(&Self::default_path()?)
+    }
+
+    /// Load a registry file. A missing file is an empty workspace.
+    pub fn load_from(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let content = fs::read_to_string(path)?;
+        serde_json::from_str(&content).map_err(|e| {
+            MedullaError::Config(format!("Invalid workspace file {}: {}", path.display(), e))
+        })
+    }
+
+    /// Write the registry to its default location.
+    pub fn save(&self) -> Result<()> {
+        self.save_to(&Self::default_path()?)
+    }
+
+    pub fn save_to(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, serde_json::to_string_pretty(self)? + "\n")?;
+        Ok(())
+    }
+
+    /// Register a project directory under `name`, replacing any previous
+    /// entry. The directory must contain an initialized `.medulla`.
+    pub fn add(&mut self, name: &str, dir: &Path) -> Result<()> {
+        let name = name.trim();
+        if name.is_empty() || name.contains(['/', '\\']) {
+            return Err(MedullaError::Config(format!(
+                "Invalid project name '{}'",
+                name
+            )));
+        }
+        let dir = fs::canonicalize(dir)?;
+        if !dir.join(".medulla").is_dir() {
+            return Err(MedullaError::Config(format!(
+                "{} is not a medulla project. Run `medulla init` there first.",
+                dir.display()
+            )));
+        }
+        self.projects.insert(name.to_string(), dir);
+        Ok(())
+    }
+
+    /// Unregister a project. Returns false if it was not registered.
+    pub fn remove(&mut self, name: &str) -> bool {
+        self.projects.remove(name).is_some()
+    }
+
+    /// Directory of the project `name_or_path` refers to: a registered name,
+    /// or otherwise a path to a project directory.
+    pub fn resolve(&self, name_or_path: &str) -> Result<PathBuf> {
+        if let Some(dir) = self.projects.get(name_or_path) {
+            return Ok(dir.clone());
+        }
+        let path = PathBuf::from(name_or_path);
+        if path.exists() {
+            return Ok(path);
+        }
+        let known: Vec<&str> = self.projects.keys().map(String::as_str).collect();
+        Err(MedullaError::Config(format!(
+            "Unknown project '{}'. Registered projects: {}",
+            name_or_path,
+            if known.is_empty() {
+                "none (add one with `medulla workspace add`)".to_string()
+            } else {
+                known.join(", ")
+            }
+        )))
+    }
+
+    /// Name under which `dir` is registered, if any.
+    pub fn name_of(&self, dir: &Path) -> Option<&str> {
+        let dir = fs::canonicalize(dir).ok()?;
+        self.projects
+            .iter()
+            .find(|(_, path)| **path == dir)
+            .map(|(name, _)| name.as_str())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_add_resolve_remove() {
+        let tmp = TempDir::new().unwrap();
+        let project = tmp.path().join("api");
+        fs::create_dir_all(project.join(".medulla")).unwrap();
+
+        let mut workspace = Workspace::default();
+        workspace.add("api", &project).unwrap();
+        assert!(workspace.add("web", &tmp.path().join("missing")).is_err());
+        assert!(workspace.add("bad/name", &project).is_err());
+
+        let resolved = workspace.resolve("api").unwrap();
+        assert_eq!(resolved, fs::canonicalize(&project).unwrap());
+        assert_eq!(workspace.name_of(&project), Some("api"));
+        // Paths still work without registering them
+        assert_eq!(
+            workspace.resolve(project.to_str().unwrap()).unwrap(),
+            project
+        );
+        let err = workspace.resolve("web").unwrap_err().to_string();
+        assert!(err.contains("Registered projects: api"));
+
+        assert!(workspace.remove("api"));
+        assert!(!workspace.remove("api"));
+    }
+
+    #[test]
+    fn test_save_and_load() {
+        let tmp = TempDir::new().unwrap();
+        let path = tmp.path().join("config/medulla/workspace.json");
+        assert_eq!(Workspace::load_from(&path).unwrap(), Workspace::default());
+
+        let mut workspace = Workspace::default();
+        workspace
+            .projects
+            .insert("api".to_string(), PathBuf::from("/src/api"));
+        workspace.save_to(&path).unwrap();
+        assert_eq!(Workspace::load_from(&path).unwrap(), workspace);
+    }
+}
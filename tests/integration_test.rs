@@ -161,6 +161,56 @@ fn test_list_json_output() {
     assert_eq!(parsed.as_array().unwrap().len(), 1);
 }
 
+#[test]
+fn test_data_dir_flag_overrides_cwd() {
+    let store_dir = TempDir::new().unwrap();
+    let other_dir = TempDir::new().unwrap();
+
+    medulla_cmd()
+        .current_dir(store_dir.path())
+        .args(["init"])
+        .output()
+        .unwrap();
+
+    medulla_cmd()
+        .current_dir(store_dir.path())
+        .args(["add", "decision", "Remote Decision"])
+        .output()
+        .unwrap();
+
+    // Without --data-dir the unrelated cwd has no store
+    let output = medulla_cmd()
+        .current_dir(other_dir.path())
+        .args(["list", "--json"])
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+
+    // --data-dir points at the store regardless of cwd
+    let output = medulla_cmd()
+        .current_dir(other_dir.path())
+        .args(["list", "--json", "--data-dir"])
+        .arg(store_dir.path())
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let parsed: serde_json::Value =
+        serde_json::from_str(&String::from_utf8_lossy(&output.stdout)).unwrap();
+    assert_eq!(parsed.as_array().unwrap().len(), 1);
+    assert_eq!(parsed[0]["title"], "Remote Decision");
+
+    // The .medulla directory itself is accepted too
+    let output = medulla_cmd()
+        .current_dir(other_dir.path())
+        .arg("--data-dir")
+        .arg(store_dir.path().join(".medulla"))
+        .args(["get", "1"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).contains("Remote Decision"));
+}
+
 #[test]
 fn test_update_decision() {
     let tmp = TempDir::new().unwrap();
@@ -980,7 +1030,7 @@ fn test_snapshot_readme_content() {
     assert!(readme_content.contains("| Decisions | 1 |"));
     assert!(readme_content.contains("decisions/"));
     assert!(readme_content.contains("Use PostgreSQL"));
-    assert!(readme_content.contains("*Generated:"));
+    assert!(readme_content.contains("*Last updated:"));
 }
 
 #[test]
@@ -1521,3 +1571,115 @@ fn test_search_combined_filters() {
     assert_eq!(parsed.as_array().unwrap().len(), 1);
     assert_eq!(parsed[0]["title"], "API Design");
 }
+
+#[test]
+fn test_selftest_reports_all_phases() {
+    let tmp = TempDir::new().unwrap();
+
+    let output = medulla_cmd()
+        .current_dir(tmp.path())
+        .env("TMPDIR", tmp.path())
+        .args(["selftest", "--entities", "12", "--json"])
+        .output()
+        .unwrap();
+
+    assert!(output.status.success());
+    let parsed: serde_json::Value =
+        serde_json::from_str(&String::from_utf8_lossy(&output.stdout)).unwrap();
+    assert_eq!(parsed["entities"], 12);
+    assert_eq!(parsed["relations"], 4);
+
+    let names: Vec<&str> = parsed["phases"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|p| p["name"].as_str().unwrap())
+        .collect();
+    assert_eq!(
+        names,
+        vec!["create", "list", "search", "semantic_search", "snapshot"]
+    );
+    for phase in parsed["phases"].as_array().unwrap() {
+        assert!(phase["millis"].as_f64().unwrap() >= 0.0);
+    }
+
+    // The current directory is never initialized
+    assert!(!tmp.path().join(".medulla").exists());
+}
+
+#[test]
+fn test_get_with_relations_and_related() {
+    let tmp = TempDir::new().unwrap();
+
+    medulla_cmd()
+        .current_dir(tmp.path())
+        .args(["init"])
+        .output()
+        .unwrap();
+
+    // A chain of five decisions, each referencing the one before
+    let mut previous: Option<String> = None;
+    for title in ["First", "Second", "Third", "Fourth", "Fifth"] {
+        let mut args = vec![
+            "add".to_string(),
+            "decision".to_string(),
+            title.to_string(),
+            "--json".to_string(),
+        ];
+        if let Some(id) = &previous {
+            args.push(format!("--relation=references:{}", id));
+        }
+        let output = medulla_cmd()
+            .current_dir(tmp.path())
+            .args(&args)
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        let parsed: serde_json::Value =
+            serde_json::from_str(&String::from_utf8_lossy(&output.stdout)).unwrap();
+        previous = Some(parsed["id"].as_str().unwrap().to_string());
+    }
+
+    let output = medulla_cmd()
+        .current_dir(tmp.path())
+        .args(["get", "1", "--relations", "--json"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let parsed: serde_json::Value =
+        serde_json::from_str(&String::from_utf8_lossy(&output.stdout)).unwrap();
+    assert_eq!(parsed["relations"]["outgoing"].as_array().unwrap().len(), 0);
+    assert_eq!(parsed["relations"]["incoming"].as_array().unwrap().len(), 1);
+    assert!(parsed.get("related_entities").is_none());
+
+    // --depth is capped at 3 hops, so the fifth decision is out of reach
+    let output = medulla_cmd()
+        .current_dir(tmp.path())
+        .args(["get", "1", "--related", "--depth", "10", "--json"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let parsed: serde_json::Value =
+        serde_json::from_str(&String::from_utf8_lossy(&output.stdout)).unwrap();
+    let related: Vec<(u64, u64)> = parsed["related_entities"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|e| {
+            (
+                e["sequence_number"].as_u64().unwrap(),
+                e["distance"].as_u64().unwrap(),
+            )
+        })
+        .collect();
+    assert_eq!(related, vec![(2, 1), (3, 2), (4, 3)]);
+
+    let output = medulla_cmd()
+        .current_dir(tmp.path())
+        .args(["get", "1", "--related", "--depth", "10"])
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("Related (within 3 hops):"));
+    assert!(!stdout.contains("Fifth"));
+}